@@ -12,38 +12,42 @@ async fn main() -> Result<()> {
 
     let session: Session = SessionBuilder::new().known_node(uri).build().await?;
 
-    session.query("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
+    session.query_unpaged("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS ks.t (a int, b int, c text, primary key (a, b))",
             &[],
         )
         .await?;
 
     session
-        .query("INSERT INTO ks.t (a, b, c) VALUES (?, ?, ?)", (3, 4, "def"))
+        .query_unpaged("INSERT INTO ks.t (a, b, c) VALUES (?, ?, ?)", (3, 4, "def"))
         .await?;
 
     session
-        .query("INSERT INTO ks.t (a, b, c) VALUES (1, 2, 'abc')", &[])
+        .query_unpaged("INSERT INTO ks.t (a, b, c) VALUES (1, 2, 'abc')", &[])
         .await?;
 
     let prepared = session
         .prepare("INSERT INTO ks.t (a, b, c) VALUES (?, 7, ?)")
         .await?;
     session
-        .execute(&prepared, (42_i32, "I'm prepared!"))
+        .execute_unpaged(&prepared, (42_i32, "I'm prepared!"))
         .await?;
     session
-        .execute(&prepared, (43_i32, "I'm prepared 2!"))
+        .execute_unpaged(&prepared, (43_i32, "I'm prepared 2!"))
         .await?;
     session
-        .execute(&prepared, (44_i32, "I'm prepared 3!"))
+        .execute_unpaged(&prepared, (44_i32, "I'm prepared 3!"))
         .await?;
 
     // Rows can be parsed as tuples
-    if let Some(rows) = session.query("SELECT a, b, c FROM ks.t", &[]).await?.rows {
+    if let Some(rows) = session
+        .query_unpaged("SELECT a, b, c FROM ks.t", &[])
+        .await?
+        .rows
+    {
         for row in rows.into_typed::<(i32, i32, String)>() {
             let (a, b, c) = row?;
             println!("a, b, c: {}, {}, {}", a, b, c);
@@ -58,7 +62,11 @@ async fn main() -> Result<()> {
         _c: String,
     }
 
-    if let Some(rows) = session.query("SELECT a, b, c FROM ks.t", &[]).await?.rows {
+    if let Some(rows) = session
+        .query_unpaged("SELECT a, b, c FROM ks.t", &[])
+        .await?
+        .rows
+    {
         for row_data in rows.into_typed::<RowData>() {
             let row_data = row_data?;
             println!("row_data: {:?}", row_data);
@@ -66,7 +74,11 @@ async fn main() -> Result<()> {
     }
 
     // Or simply as untyped rows
-    if let Some(rows) = session.query("SELECT a, b, c FROM ks.t", &[]).await?.rows {
+    if let Some(rows) = session
+        .query_unpaged("SELECT a, b, c FROM ks.t", &[])
+        .await?
+        .rows
+    {
         for row in rows {
             let a = row.columns[0].as_ref().unwrap().as_int().unwrap();
             let b = row.columns[1].as_ref().unwrap().as_int().unwrap();
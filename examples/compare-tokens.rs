@@ -12,10 +12,10 @@ async fn main() -> Result<()> {
 
     let session: Session = SessionBuilder::new().known_node(uri).build().await?;
 
-    session.query("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
+    session.query_unpaged("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS ks.t (pk bigint primary key)",
             &[],
         )
@@ -25,7 +25,7 @@ async fn main() -> Result<()> {
 
     for pk in (0..100_i64).chain(99840..99936_i64) {
         session
-            .query("INSERT INTO ks.t (pk) VALUES (?)", (pk,))
+            .query_unpaged("INSERT INTO ks.t (pk) VALUES (?)", (pk,))
             .await?;
 
         let t = prepared.calculate_token(&(pk,))?.unwrap().value;
@@ -41,7 +41,7 @@ async fn main() -> Result<()> {
         );
 
         let qt = session
-            .query(format!("SELECT token(pk) FROM ks.t where pk = {}", pk), &[])
+            .query_unpaged(format!("SELECT token(pk) FROM ks.t where pk = {}", pk), &[])
             .await?
             .rows
             .unwrap()
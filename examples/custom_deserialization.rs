@@ -13,16 +13,16 @@ async fn main() -> Result<()> {
 
     let session: Session = SessionBuilder::new().known_node(uri).build().await?;
 
-    session.query("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
+    session.query_unpaged("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS ks.t (pk int PRIMARY KEY, v text)",
             &[],
         )
         .await?;
 
     session
-        .query("INSERT INTO ks.t (pk, v) VALUES (1, 'asdf')", ())
+        .query_unpaged("INSERT INTO ks.t (pk, v) VALUES (1, 'asdf')", ())
         .await?;
 
     // You can implement FromCqlVal for your own types
@@ -38,7 +38,7 @@ async fn main() -> Result<()> {
     }
 
     let (v,) = session
-        .query("SELECT v FROM ks.t WHERE pk = 1", ())
+        .query_unpaged("SELECT v FROM ks.t WHERE pk = 1", ())
         .await?
         .single_row_typed::<(MyType,)>()?;
     assert_eq!(v, MyType("asdf".to_owned()));
@@ -62,7 +62,7 @@ async fn main() -> Result<()> {
     impl_from_cql_value_from_method!(MyOtherType, into_my_other_type);
 
     let (v,) = session
-        .query("SELECT v FROM ks.t WHERE pk = 1", ())
+        .query_unpaged("SELECT v FROM ks.t WHERE pk = 1", ())
         .await?
         .single_row_typed::<(MyOtherType,)>()?;
     assert_eq!(v, MyOtherType("asdf".to_owned()));
@@ -59,10 +59,10 @@ async fn main() -> Result<()> {
     session_3_config.add_known_node(uri);
     let session3: Session = Session::connect(session_3_config).await?;
 
-    session1.query("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
+    session1.query_unpaged("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
 
     session2
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS ks.t (a int, b int, c text, primary key (a, b))",
             &[],
         )
@@ -73,18 +73,22 @@ async fn main() -> Result<()> {
     // As `query_insert` is set another handle than session1, the execution profile pointed by query's handle
     // will be preferred, so the query below will be executed with `profile2`, even though `session1` is set `profile1`.
     query_insert.set_execution_profile_handle(Some(handle2.clone()));
-    session1.query(query_insert.clone(), (3, 4, "def")).await?;
+    session1
+        .query_unpaged(query_insert.clone(), (3, 4, "def"))
+        .await?;
 
     // One can, however, change the execution profile referred by a handle:
     handle2.map_to_another_profile(profile1);
     // And now the following queries are executed with profile1:
-    session1.query(query_insert.clone(), (3, 4, "def")).await?;
-    session2.query("SELECT * FROM ks.t", ()).await?;
+    session1
+        .query_unpaged(query_insert.clone(), (3, 4, "def"))
+        .await?;
+    session2.query_unpaged("SELECT * FROM ks.t", ()).await?;
 
     // One can unset a profile handle from a statement and, since then, execute it with session's default profile.
     query_insert.set_execution_profile_handle(None);
-    session3.query("SELECT * FROM ks.t", ()).await?; // This executes with default session profile.
-    session2.query("SELECT * FROM ks.t", ()).await?; // This executes with profile1.
+    session3.query_unpaged("SELECT * FROM ks.t", ()).await?; // This executes with default session profile.
+    session2.query_unpaged("SELECT * FROM ks.t", ()).await?; // This executes with profile1.
 
     Ok(())
 }
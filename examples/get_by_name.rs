@@ -12,31 +12,31 @@ async fn main() -> Result<()> {
 
     let session: Session = SessionBuilder::new().known_node(uri).build().await?;
 
-    session.query("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
+    session.query_unpaged("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS ks.hello (pk int, ck int, value text, primary key (pk, ck))",
             &[],
         )
         .await?;
 
     session
-        .query(
+        .query_unpaged(
             "INSERT INTO ks.hello (pk, ck, value) VALUES (?, ?, ?)",
             (3, 4, "def"),
         )
         .await?;
 
     session
-        .query(
+        .query_unpaged(
             "INSERT INTO ks.hello (pk, ck, value) VALUES (1, 2, 'abc')",
             &[],
         )
         .await?;
 
     let query_result = session
-        .query("SELECT pk, ck, value FROM ks.hello", &[])
+        .query_unpaged("SELECT pk, ck, value FROM ks.hello", &[])
         .await?;
     let (ck_idx, _) = query_result
         .get_column_spec("ck")
@@ -14,10 +14,10 @@ async fn main() -> Result<()> {
     let session: Session = SessionBuilder::new().known_node(uri).build().await?;
     let session = Arc::new(session);
 
-    session.query("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
+    session.query_unpaged("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS ks.t2 (a int, b int, c text, primary key (a, b))",
             &[],
         )
@@ -34,7 +34,7 @@ async fn main() -> Result<()> {
         let permit = sem.clone().acquire_owned().await;
         tokio::task::spawn(async move {
             session
-                .query(
+                .query_unpaged(
                     format!(
                         "INSERT INTO ks.t2 (a, b, c) VALUES ({}, {}, 'abc')",
                         i,
@@ -17,10 +17,10 @@ async fn main() -> Result<()> {
 
     let session: Session = SessionBuilder::new().known_node(uri).build().await?;
 
-    session.query("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
+    session.query_unpaged("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS ks.t (a int, b int, c text, primary key (a, b))",
             &[],
         )
@@ -32,7 +32,7 @@ async fn main() -> Result<()> {
     query.set_history_listener(history_listener.clone());
 
     // Run the query, doesn't matter if it failed, the history will still be saved
-    let _ignore_error = session.query(query.clone(), ()).await;
+    let _ignore_error = session.query_unpaged(query.clone(), ()).await;
 
     // Access the collected history and print it
     let structured_history: StructuredHistory = history_listener.clone_structured_history();
@@ -40,14 +40,14 @@ async fn main() -> Result<()> {
 
     // A single history collector can contain histories of multiple queries.
     // To clear a collector create a new one and set it again.
-    let _second_execution = session.query(query, ()).await;
+    let _second_execution = session.query_unpaged(query, ()).await;
     let structured_history: StructuredHistory = history_listener.clone_structured_history();
     println!("Two queries history: {}", structured_history);
 
     // The same works for other types of queries, e.g iterators
     for i in 0..32 {
         session
-            .query("INSERT INTO ks.t (a, b, c) VALUES (?, ?, 't')", (i, i))
+            .query_unpaged("INSERT INTO ks.t (a, b, c) VALUES (?, ?, 't')", (i, i))
             .await?;
     }
 
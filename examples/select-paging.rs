@@ -11,10 +11,10 @@ async fn main() -> Result<()> {
 
     let session: Session = SessionBuilder::new().known_node(uri).build().await?;
 
-    session.query("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
+    session.query_unpaged("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS ks.t (a int, b int, c text, primary key (a, b))",
             &[],
         )
@@ -22,7 +22,7 @@ async fn main() -> Result<()> {
 
     for i in 0..16_i32 {
         session
-            .query(
+            .query_unpaged(
                 "INSERT INTO ks.t (a, b, c) VALUES (?, ?, 'abc')",
                 (i, 2 * i),
             )
@@ -41,14 +41,16 @@ async fn main() -> Result<()> {
     }
 
     let paged_query = Query::new("SELECT a, b, c FROM ks.t").with_page_size(6);
-    let res1 = session.query(paged_query.clone(), &[]).await?;
+    let res1 = session
+        .query_single_page(paged_query.clone(), &[], None)
+        .await?;
     println!(
         "Paging state: {:#?} ({} rows)",
         res1.paging_state,
         res1.rows.unwrap().len()
     );
     let res2 = session
-        .query_paged(paged_query.clone(), &[], res1.paging_state)
+        .query_single_page(paged_query.clone(), &[], res1.paging_state)
         .await?;
     println!(
         "Paging state: {:#?} ({} rows)",
@@ -56,7 +58,7 @@ async fn main() -> Result<()> {
         res2.rows.unwrap().len()
     );
     let res3 = session
-        .query_paged(paged_query.clone(), &[], res2.paging_state)
+        .query_single_page(paged_query.clone(), &[], res2.paging_state)
         .await?;
     println!(
         "Paging state: {:#?} ({} rows)",
@@ -67,14 +69,16 @@ async fn main() -> Result<()> {
     let paged_prepared = session
         .prepare(Query::new("SELECT a, b, c FROM ks.t").with_page_size(7))
         .await?;
-    let res4 = session.execute(&paged_prepared, &[]).await?;
+    let res4 = session
+        .execute_single_page(&paged_prepared, &[], None)
+        .await?;
     println!(
         "Paging state from the prepared statement execution: {:#?} ({} rows)",
         res4.paging_state,
         res4.rows.unwrap().len()
     );
     let res5 = session
-        .execute_paged(&paged_prepared, &[], res4.paging_state)
+        .execute_single_page(&paged_prepared, &[], res4.paging_state)
         .await?;
     println!(
         "Paging state from the second prepared statement execution: {:#?} ({} rows)",
@@ -82,7 +86,7 @@ async fn main() -> Result<()> {
         res5.rows.unwrap().len()
     );
     let res6 = session
-        .execute_paged(&paged_prepared, &[], res5.paging_state)
+        .execute_single_page(&paged_prepared, &[], res5.paging_state)
         .await?;
     println!(
         "Paging state from the third prepared statement execution: {:#?} ({} rows)",
@@ -0,0 +1,90 @@
+use anyhow::Result;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use scylla::{Session, SessionBuilder};
+use std::env;
+
+// Number of sub-ranges each token range of the ring is split into.
+const SPLITS_PER_RANGE: usize = 4;
+// Number of sub-ranges scanned concurrently.
+const PARALLELISM: usize = 16;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let uri = env::var("SCYLLA_URI").unwrap_or_else(|_| "127.0.0.1:9042".to_string());
+
+    println!("Connecting to {} ...", uri);
+
+    let session: Session = SessionBuilder::new().known_node(uri).build().await?;
+
+    session.query_unpaged("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
+
+    session
+        .query_unpaged(
+            "CREATE TABLE IF NOT EXISTS ks.scan (pk bigint primary key, v int)",
+            &[],
+        )
+        .await?;
+
+    let insert = session
+        .prepare("INSERT INTO ks.scan (pk, v) VALUES (?, ?)")
+        .await?;
+    for pk in 0..1000_i64 {
+        session.execute_unpaged(&insert, (pk, pk as i32)).await?;
+    }
+
+    let select = session
+        .prepare("SELECT pk, v FROM ks.scan WHERE token(pk) > ? AND token(pk) <= ?")
+        .await?;
+
+    let cluster_data = session.get_cluster_data();
+    let token_ring = cluster_data.token_ring("ks");
+    println!("The ring consists of {} token ranges", token_ring.len());
+    for (range, replicas) in token_ring.iter().take(3) {
+        let addresses: Vec<_> = replicas.iter().map(|node| node.address).collect();
+        println!(
+            "({}, {}] is owned by {:?}",
+            range.start().value,
+            range.end().value,
+            addresses
+        );
+    }
+
+    // Wrapping ranges can't be expressed with a single pair of token restrictions,
+    // so they are unwrapped after splitting.
+    let sub_ranges: Vec<_> = token_ring
+        .iter()
+        .flat_map(|(range, _replicas)| range.split(SPLITS_PER_RANGE))
+        .flat_map(|sub_range| sub_range.unwrap_ranges())
+        .collect();
+
+    let row_counts: Vec<usize> = stream::iter(sub_ranges)
+        .map(|sub_range| {
+            let session = &session;
+            let select = &select;
+            async move {
+                let mut rows = session
+                    .execute_iter(
+                        select.clone(),
+                        (sub_range.start().value, sub_range.end().value),
+                    )
+                    .await?
+                    .into_typed::<(i64, i32)>();
+                let mut count = 0;
+                while let Some((_pk, _v)) = rows.try_next().await? {
+                    count += 1;
+                }
+                Ok::<_, anyhow::Error>(count)
+            }
+        })
+        .buffer_unordered(PARALLELISM)
+        .try_collect()
+        .await?;
+
+    println!(
+        "Scanned {} rows in {} sub-ranges",
+        row_counts.iter().sum::<usize>(),
+        row_counts.len()
+    );
+
+    Ok(())
+}
@@ -11,17 +11,17 @@ async fn main() -> Result<()> {
 
     let session: Session = SessionBuilder::new().known_node(uri).build().await?;
 
-    session.query("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
+    session.query_unpaged("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TYPE IF NOT EXISTS ks.my_type (int_val int, text_val text)",
             &[],
         )
         .await?;
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS ks.udt_tab (k int, my my_type, primary key (k))",
             &[],
         )
@@ -42,11 +42,15 @@ async fn main() -> Result<()> {
 
     // It can be inserted like a normal value
     session
-        .query("INSERT INTO ks.udt_tab (k, my) VALUES (5, ?)", (to_insert,))
+        .query_unpaged("INSERT INTO ks.udt_tab (k, my) VALUES (5, ?)", (to_insert,))
         .await?;
 
     // And read like any normal value
-    if let Some(rows) = session.query("SELECT my FROM ks.udt_tab", &[]).await?.rows {
+    if let Some(rows) = session
+        .query_unpaged("SELECT my FROM ks.udt_tab", &[])
+        .await?
+        .rows
+    {
         for row in rows.into_typed::<(MyType,)>() {
             let (my_type_value,): (MyType,) = row?;
             println!("{:?}", my_type_value)
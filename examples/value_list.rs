@@ -9,10 +9,10 @@ async fn main() {
 
     let session: Session = SessionBuilder::new().known_node(uri).build().await.unwrap();
 
-    session.query("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await.unwrap();
+    session.query_unpaged("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await.unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS ks.my_type (k int, my text, primary key (k))",
             &[],
         )
@@ -31,7 +31,7 @@ async fn main() {
     };
 
     session
-        .query("INSERT INTO ks.my_type (k, my) VALUES (?, ?)", to_insert)
+        .query_unpaged("INSERT INTO ks.my_type (k, my) VALUES (?, ?)", to_insert)
         .await
         .unwrap();
 
@@ -48,12 +48,12 @@ async fn main() {
     };
 
     session
-        .query("INSERT INTO ks.my_type (k, my) VALUES (?, ?)", to_insert_2)
+        .query_unpaged("INSERT INTO ks.my_type (k, my) VALUES (?, ?)", to_insert_2)
         .await
         .unwrap();
 
     let q = session
-        .query("SELECT * FROM ks.my_type", &[])
+        .query_unpaged("SELECT * FROM ks.my_type", &[])
         .await
         .unwrap();
 
@@ -0,0 +1,80 @@
+use anyhow::Result;
+use scylla::prepared_statement::PreparedStatement;
+use scylla::{Session, SessionBuilder};
+use std::env;
+use std::sync::Arc;
+use std::time::Instant;
+
+use tokio::sync::Semaphore;
+
+// Measures the throughput of small concurrent writes with different write coalescing settings.
+#[tokio::main]
+async fn main() -> Result<()> {
+    let uri = env::var("SCYLLA_URI").unwrap_or_else(|_| "127.0.0.1:9042".to_string());
+
+    println!("Connecting to {} ...", uri);
+
+    let configs = [
+        ("no coalescing", false, 8 * 1024),
+        ("coalescing, 1 KiB buffer", true, 1024),
+        ("coalescing, 8 KiB buffer", true, 8 * 1024),
+        ("coalescing, 64 KiB buffer", true, 64 * 1024),
+    ];
+
+    for (name, enable, buffer_size) in configs {
+        let session: Session = SessionBuilder::new()
+            .known_node(&uri)
+            .write_coalescing(enable)
+            .write_coalescing_buffer_size(buffer_size)
+            .build()
+            .await?;
+        let session = Arc::new(session);
+
+        session.query_unpaged("CREATE KEYSPACE IF NOT EXISTS ks WITH REPLICATION = {'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}", &[]).await?;
+        session
+            .query_unpaged(
+                "CREATE TABLE IF NOT EXISTS ks.t_coalescing (a int, b int, primary key (a))",
+                &[],
+            )
+            .await?;
+
+        let prepared: Arc<PreparedStatement> = Arc::new(
+            session
+                .prepare("INSERT INTO ks.t_coalescing (a, b) VALUES (?, ?)")
+                .await?,
+        );
+
+        let requests = 100_000;
+        let parallelism = 1024;
+        let sem = Arc::new(Semaphore::new(parallelism));
+
+        let start = Instant::now();
+        for i in 0..requests {
+            let session = session.clone();
+            let prepared = prepared.clone();
+            let permit = sem.clone().acquire_owned().await;
+            tokio::task::spawn(async move {
+                session
+                    .execute_unpaged(&prepared, (i, 2 * i))
+                    .await
+                    .unwrap();
+
+                let _permit = permit;
+            });
+        }
+
+        // Wait for all in-flight requests to finish
+        for _ in 0..parallelism {
+            sem.acquire().await.unwrap().forget();
+        }
+
+        let elapsed = start.elapsed();
+        println!(
+            "{}: {:.0} requests/s",
+            name,
+            requests as f64 / elapsed.as_secs_f64()
+        );
+    }
+
+    Ok(())
+}
@@ -1,11 +1,18 @@
 use std::borrow::Cow;
 
+use bytes::{BufMut, BytesMut};
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 
 use scylla_cql::frame::request::SerializableRequest;
-use scylla_cql::frame::response::result::ColumnType;
+use scylla_cql::frame::response::result::{self, ColumnType, CqlValue};
+use scylla_cql::frame::types;
+use scylla_cql::frame::value::CqlDuration;
 use scylla_cql::frame::{request::query, Compression, SerializedRequest};
+use scylla_cql::macros::SerializeCql;
 use scylla_cql::types::serialize::row::SerializedValues;
+use scylla_cql::types::serialize::value::SerializeCql;
+use scylla_cql::types::serialize::writers::WrittenCellProof;
+use scylla_cql::types::serialize::{CellWriter, RowWriter, SerializationError};
 
 fn make_query(contents: &str, values: SerializedValues) -> query::Query<'_> {
     query::Query {
@@ -17,6 +24,7 @@ fn make_query(contents: &str, values: SerializedValues) -> query::Query<'_> {
             page_size: None,
             paging_state: None,
             timestamp: None,
+            skip_metadata: false,
         },
     }
 }
@@ -55,6 +63,7 @@ fn serialized_request_make_bench(c: &mut Criterion) {
                         query,
                         Some(Compression::Lz4),
                         false,
+                        None,
                     ));
                 })
             },
@@ -62,5 +71,325 @@ fn serialized_request_make_bench(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, serialized_request_make_bench);
+fn duration_serialize_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("SerializeCql.CqlDuration");
+    let durations = [
+        (
+            "small",
+            CqlDuration {
+                months: 1,
+                days: 2,
+                nanoseconds: 3,
+            },
+        ),
+        (
+            "max",
+            CqlDuration {
+                months: i32::MIN,
+                days: i32::MIN,
+                nanoseconds: i64::MIN,
+            },
+        ),
+    ];
+
+    let mut buf = Vec::with_capacity(64);
+    for (name, duration) in durations {
+        group.bench_with_input(
+            BenchmarkId::new("serialize", name),
+            &duration,
+            |b, duration| {
+                b.iter(|| {
+                    buf.clear();
+                    let writer = CellWriter::new(&mut buf);
+                    let _ = criterion::black_box(duration.serialize(&ColumnType::Duration, writer));
+                })
+            },
+        );
+    }
+}
+
+fn udt_type_check_failure_bench(c: &mut Criterion) {
+    let field_types = (0..20)
+        .map(|i| {
+            (
+                format!("field_{}", i),
+                ColumnType::Map(
+                    Box::new(ColumnType::Text),
+                    Box::new(ColumnType::List(Box::new(ColumnType::BigInt))),
+                ),
+            )
+        })
+        .collect();
+    let udt = ColumnType::UserDefinedType {
+        type_name: "big_udt".to_owned(),
+        keyspace: "ks".to_owned(),
+        field_types,
+    };
+
+    let mut buf = Vec::with_capacity(64);
+    c.bench_function("SerializeCql.UdtTypeCheckFailure", |b| {
+        b.iter(|| {
+            buf.clear();
+            let writer = CellWriter::new(&mut buf);
+            let _ = criterion::black_box(123i32.serialize(&udt, writer));
+        })
+    });
+}
+
+fn insert_frame_assembly_bench(c: &mut Criterion) {
+    const STATEMENT: &str = "INSERT INTO ks.table_name (a, b, c) VALUES (?, ?, ?)";
+    let text = "i am storing a string".repeat(8);
+    let list: Vec<i64> = (0..64).collect();
+
+    fn write_frame_header(frame: &mut BytesMut) {
+        frame.clear();
+        frame.put_slice(&[0x04, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00]);
+        frame.put_i32(STATEMENT.len() as i32);
+        frame.put_slice(STATEMENT.as_bytes());
+        frame.put_u16(scylla_cql::Consistency::LocalQuorum as u16);
+        frame.put_u8(0x01);
+    }
+
+    let write_values = |mut writer: RowWriter| {
+        1234i32
+            .serialize(&ColumnType::Int, writer.make_cell_writer())
+            .unwrap();
+        text.serialize(&ColumnType::Text, writer.make_cell_writer())
+            .unwrap();
+        list.serialize(
+            &ColumnType::List(Box::new(ColumnType::BigInt)),
+            writer.make_cell_writer(),
+        )
+        .unwrap();
+        writer.value_count() as u16
+    };
+
+    let mut group = c.benchmark_group("InsertFrameAssembly");
+    let mut frame = BytesMut::with_capacity(1024);
+    let mut values = Vec::with_capacity(1024);
+    group.bench_function("serialize_to_vec_and_copy", |b| {
+        b.iter(|| {
+            write_frame_header(&mut frame);
+            values.clear();
+            let value_count = write_values(RowWriter::new(&mut values));
+            frame.put_u16(value_count);
+            frame.put_slice(&values);
+            criterion::black_box(&frame);
+        })
+    });
+    group.bench_function("serialize_to_bytes_mut", |b| {
+        b.iter(|| {
+            write_frame_header(&mut frame);
+            let count_pos = frame.len();
+            frame.put_u16(0);
+            let value_count = write_values(RowWriter::new_bytes_mut(&mut frame));
+            frame[count_pos..count_pos + 2].copy_from_slice(&value_count.to_be_bytes());
+            criterion::black_box(&frame);
+        })
+    });
+}
+
+// Serializes the same way as the wrapped value, but without a size estimate.
+struct NotEstimated<T>(T);
+
+impl<T: SerializeCql> SerializeCql for NotEstimated<T> {
+    fn serialize<'b>(
+        &self,
+        typ: &ColumnType,
+        writer: CellWriter<'b>,
+    ) -> Result<WrittenCellProof<'b>, SerializationError> {
+        self.0.serialize(typ, writer)
+    }
+}
+
+macro_rules! bench_udt {
+    ($name:ident { $($field:ident),* }) => {
+        #[derive(SerializeCql)]
+        #[scylla(crate = scylla_cql)]
+        struct $name {
+            $($field: i32,)*
+        }
+
+        impl $name {
+            const FIELD_NAMES: &'static [&'static str] = &[$(stringify!($field)),*];
+
+            fn new() -> Self {
+                let mut value = 0;
+                Self {
+                    $($field: {
+                        value += 1;
+                        value
+                    },)*
+                }
+            }
+        }
+    };
+}
+
+bench_udt!(Udt5 {
+    f00,
+    f01,
+    f02,
+    f03,
+    f04
+});
+bench_udt!(Udt15 {
+    f00,
+    f01,
+    f02,
+    f03,
+    f04,
+    f05,
+    f06,
+    f07,
+    f08,
+    f09,
+    f10,
+    f11,
+    f12,
+    f13,
+    f14
+});
+bench_udt!(Udt30 {
+    f00,
+    f01,
+    f02,
+    f03,
+    f04,
+    f05,
+    f06,
+    f07,
+    f08,
+    f09,
+    f10,
+    f11,
+    f12,
+    f13,
+    f14,
+    f15,
+    f16,
+    f17,
+    f18,
+    f19,
+    f20,
+    f21,
+    f22,
+    f23,
+    f24,
+    f25,
+    f26,
+    f27,
+    f28,
+    f29
+});
+
+// Compares the derived `match_by_name` serialization, with fields of the UDT in the order
+// of the struct and in the reverse order, to the serialization of an equivalent `CqlValue`.
+// To compare with another revision, run with `--save-baseline` on one and `--baseline` on the other.
+fn udt_match_by_name_bench(c: &mut Criterion) {
+    fn bench<T: SerializeCql>(c: &mut Criterion, value: T, field_names: &[&str]) {
+        let udt_type = |names: Vec<&str>| ColumnType::UserDefinedType {
+            type_name: "udt".to_owned(),
+            keyspace: "ks".to_owned(),
+            field_types: names
+                .into_iter()
+                .map(|name| (name.to_owned(), ColumnType::Int))
+                .collect(),
+        };
+        let in_order = udt_type(field_names.to_vec());
+        let reversed = udt_type(field_names.iter().rev().copied().collect());
+        let cql_value = CqlValue::UserDefinedType {
+            keyspace: "ks".to_owned(),
+            type_name: "udt".to_owned(),
+            fields: field_names
+                .iter()
+                .zip(1..)
+                .map(|(name, value)| (name.to_string(), Some(CqlValue::Int(value))))
+                .collect(),
+        };
+
+        let mut group =
+            c.benchmark_group(format!("SerializeCql.UdtMatchByName.{}", field_names.len()));
+        let mut buf = Vec::with_capacity(1024);
+        let mut bench_value = |name: &str, value: &dyn SerializeCql, typ: &ColumnType| {
+            group.bench_function(name, |b| {
+                b.iter(|| {
+                    buf.clear();
+                    let writer = CellWriter::new(&mut buf);
+                    let _ = criterion::black_box(value.serialize(typ, writer).unwrap());
+                })
+            });
+        };
+        bench_value("derived_in_order", &value, &in_order);
+        bench_value("derived_reversed", &value, &reversed);
+        bench_value("cql_value", &cql_value, &in_order);
+    }
+
+    bench(c, Udt5::new(), Udt5::FIELD_NAMES);
+    bench(c, Udt15::new(), Udt15::FIELD_NAMES);
+    bench(c, Udt30::new(), Udt30::FIELD_NAMES);
+}
+
+fn large_blobs_bench(c: &mut Criterion) {
+    let blobs = vec![vec![0xAB_u8; 1024 * 1024]; 8];
+    let typ = ColumnType::List(Box::new(ColumnType::Blob));
+
+    let mut group = c.benchmark_group("SerializeCql.LargeBlobs");
+    group.bench_function("estimated_size", |b| {
+        b.iter(|| {
+            let mut values = SerializedValues::new();
+            values.add_value(&blobs, &typ).unwrap();
+            criterion::black_box(values);
+        })
+    });
+    let not_estimated = NotEstimated(&blobs);
+    group.bench_function("no_estimated_size", |b| {
+        b.iter(|| {
+            let mut values = SerializedValues::new();
+            values.add_value(&not_estimated, &typ).unwrap();
+            criterion::black_box(values);
+        })
+    });
+}
+
+fn wide_blob_rows_deserialize_bench(c: &mut Criterion) {
+    const COLUMNS: usize = 8;
+    const ROWS: usize = 100;
+    const BLOB_SIZE: usize = 4096;
+
+    let mut body = Vec::new();
+    types::write_int(0x0002, &mut body);
+    types::write_int(0x0001, &mut body); // Global table spec
+    types::write_int(COLUMNS as i32, &mut body);
+    types::write_string("ks", &mut body).unwrap();
+    types::write_string("t", &mut body).unwrap();
+    for i in 0..COLUMNS {
+        types::write_string(&format!("b{}", i), &mut body).unwrap();
+        types::write_short(0x0003, &mut body);
+    }
+    types::write_int(ROWS as i32, &mut body);
+    for _ in 0..ROWS * COLUMNS {
+        types::write_bytes(&[0xAB; BLOB_SIZE], &mut body).unwrap();
+    }
+    let body = bytes::Bytes::from(body);
+
+    let mut group = c.benchmark_group("WideBlobRows.Deserialize");
+    group.bench_function("copied", |b| {
+        b.iter(|| criterion::black_box(result::deserialize(&mut &body[..]).unwrap()))
+    });
+    group.bench_function("shared", |b| {
+        b.iter(|| criterion::black_box(result::deserialize_shared(&body).unwrap()))
+    });
+}
+
+criterion_group!(
+    benches,
+    serialized_request_make_bench,
+    duration_serialize_bench,
+    udt_type_check_failure_bench,
+    udt_match_by_name_bench,
+    insert_frame_assembly_bench,
+    large_blobs_bench,
+    wide_blob_rows_deserialize_bench
+);
 criterion_main!(benches);
@@ -1,12 +1,15 @@
 //! This module contains various errors which can be returned by `scylla::Session`
 
-use crate::frame::frame_errors::{FrameError, ParseError};
+use crate::execution_history::ExecutionHistory;
+use crate::frame::frame_errors::{FrameError, ParseError, ResponseParseError, ResponseTooLarge};
 use crate::frame::protocol_features::ProtocolFeatures;
+use crate::frame::types::SerialConsistency;
 use crate::frame::value::SerializeValuesError;
 use crate::types::serialize::SerializationError;
 use crate::Consistency;
 use bytes::Bytes;
 use std::io::ErrorKind;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -43,6 +46,12 @@ pub enum QueryError {
     #[error("Unable to allocate stream id")]
     UnableToAllocStreamId,
 
+    /// All stream ids of the connection were in use, and the request couldn't wait
+    /// for one to be freed according to the configured `StreamExhaustionPolicy`.
+    /// The request was not sent.
+    #[error("Connection busy: all stream ids are in use")]
+    ConnectionBusy,
+
     /// Client timeout occurred before any response arrived
     #[error("Request timeout: {0}")]
     RequestTimeout(String),
@@ -50,6 +59,22 @@ pub enum QueryError {
     /// Address translation failed
     #[error("Address translation failed: {0}")]
     TranslationError(#[from] TranslationError),
+
+    /// The response, or its decompressed body, exceeded the maximum size configured
+    /// with `SessionBuilder::max_response_frame_size`. The response was discarded,
+    /// but the connection remains usable.
+    #[error("Response of {size} bytes exceeded the max size of {limit} bytes")]
+    ResponseTooLarge { size: usize, limit: usize },
+
+    /// The statement was denied by a `StatementGuard` configured with
+    /// `SessionBuilder::statement_guard`. The request was not sent.
+    #[error("Statement denied by a guard: {reason}")]
+    DeniedByGuard { reason: String },
+
+    /// The shared budget of an execution started with `Session::execute_with_budget`
+    /// was exhausted before the statement was sent. The request was not sent.
+    #[error("Execution budget exhausted: {reason}")]
+    BudgetExhausted { reason: String },
 }
 
 /// An error sent from the database in response to a query
@@ -356,6 +381,34 @@ pub enum BadQuery {
     #[error("Number of Queries in Batch Statement supplied is {0} which has exceeded the max value of 65,535")]
     TooManyQueriesInBatchStatement(usize),
 
+    /// Serialized batch exceeded the configured max size.
+    /// `statement_index` is the index of the statement whose values crossed the limit.
+    #[error(
+        "Serialized batch exceeded the max size of {max_size} bytes at statement {statement_index}"
+    )]
+    BatchTooLarge {
+        statement_index: usize,
+        max_size: usize,
+    },
+
+    /// The serialized request exceeded the maximum size configured
+    /// with `SessionBuilder::max_request_frame_size`, so it was not sent.
+    #[error("Serialized request of {size} bytes exceeded the max size of {limit} bytes")]
+    RequestTooLarge { size: usize, limit: usize },
+
+    /// Number of sets of values passed to a batch differs from the number of its statements
+    #[error("Length of provided values must be equal to number of batch statements (got {values_count} values, {statements_count} statements)")]
+    BatchValuesCountMismatch {
+        values_count: usize,
+        statements_count: usize,
+    },
+
+    /// Types of the bound values of a prepared statement changed when it was re-prepared,
+    /// e.g. because of a schema change, so values serialized for it can't be sent anymore.
+    /// The statement has to be prepared again.
+    #[error("Types of bound values changed when the prepared statement was re-prepared, it has to be prepared again")]
+    PreparedMetadataChanged,
+
     /// Other reasons of bad query
     #[error("{0}")]
     Other(String),
@@ -403,6 +456,10 @@ pub enum NewSessionError {
     #[error("Unable to allocate stream id")]
     UnableToAllocStreamId,
 
+    /// All stream ids of the connection were in use.
+    #[error("Connection busy: all stream ids are in use")]
+    ConnectionBusy,
+
     /// Client timeout occurred before a response arrived for some query
     /// during `Session` creation.
     #[error("Client timeout: {0}")]
@@ -411,6 +468,18 @@ pub enum NewSessionError {
     /// Address translation failed
     #[error("Address translation failed: {0}")]
     TranslationError(#[from] TranslationError),
+
+    /// A response exceeded the maximum size configured in the driver.
+    #[error("Response of {size} bytes exceeded the max size of {limit} bytes")]
+    ResponseTooLarge { size: usize, limit: usize },
+
+    /// A statement executed during `Session` creation was denied by a statement guard.
+    #[error("Statement denied by a guard: {reason}")]
+    DeniedByGuard { reason: String },
+
+    /// The execution budget of a statement executed during `Session` creation was exhausted.
+    #[error("Execution budget exhausted: {reason}")]
+    BudgetExhausted { reason: String },
 }
 
 /// Invalid keyspace name given to `Session::use_keyspace()`
@@ -455,13 +524,48 @@ impl From<SerializationError> for QueryError {
 
 impl From<ParseError> for QueryError {
     fn from(parse_error: ParseError) -> QueryError {
+        match parse_error {
+            ParseError::BatchTooLarge {
+                statement_index,
+                max_size,
+            } => QueryError::BadQuery(BadQuery::BatchTooLarge {
+                statement_index,
+                max_size,
+            }),
+            ParseError::BatchValuesCountMismatch {
+                values_count,
+                statements_count,
+            } => QueryError::BadQuery(BadQuery::BatchValuesCountMismatch {
+                values_count,
+                statements_count,
+            }),
+            _ => QueryError::InvalidMessage(format!("Error parsing message: {}", parse_error)),
+        }
+    }
+}
+
+impl From<ResponseParseError> for QueryError {
+    fn from(parse_error: ResponseParseError) -> QueryError {
         QueryError::InvalidMessage(format!("Error parsing message: {}", parse_error))
     }
 }
 
 impl From<FrameError> for QueryError {
     fn from(frame_error: FrameError) -> QueryError {
-        QueryError::InvalidMessage(format!("Frame error: {}", frame_error))
+        match frame_error {
+            FrameError::Parse(
+                parse_error @ (ParseError::BatchTooLarge { .. }
+                | ParseError::BatchValuesCountMismatch { .. }),
+            ) => parse_error.into(),
+            FrameError::ResponseTooLarge(too_large) => too_large.into(),
+            _ => QueryError::InvalidMessage(format!("Frame error: {}", frame_error)),
+        }
+    }
+}
+
+impl From<ResponseTooLarge> for QueryError {
+    fn from(ResponseTooLarge { size, limit }: ResponseTooLarge) -> QueryError {
+        QueryError::ResponseTooLarge { size, limit }
     }
 }
 
@@ -490,8 +594,14 @@ impl From<QueryError> for NewSessionError {
                 NewSessionError::TooManyOrphanedStreamIds(ids)
             }
             QueryError::UnableToAllocStreamId => NewSessionError::UnableToAllocStreamId,
+            QueryError::ConnectionBusy => NewSessionError::ConnectionBusy,
             QueryError::RequestTimeout(msg) => NewSessionError::RequestTimeout(msg),
             QueryError::TranslationError(e) => NewSessionError::TranslationError(e),
+            QueryError::ResponseTooLarge { size, limit } => {
+                NewSessionError::ResponseTooLarge { size, limit }
+            }
+            QueryError::DeniedByGuard { reason } => NewSessionError::DeniedByGuard { reason },
+            QueryError::BudgetExhausted { reason } => NewSessionError::BudgetExhausted { reason },
         }
     }
 }
@@ -517,6 +627,86 @@ impl QueryError {
 
         false
     }
+
+    /// Returns the class of the error, suitable for programmatic matching.
+    pub fn kind(&self) -> QueryErrorKind {
+        match self {
+            QueryError::DbError(DbError::Invalid, message) => classify_invalid(message),
+            QueryError::DbError(db_error, _) => db_error.kind(),
+            QueryError::BadQuery(_) => QueryErrorKind::BadQuery,
+            QueryError::IoError(_)
+            | QueryError::TooManyOrphanedStreamIds(_)
+            | QueryError::UnableToAllocStreamId
+            | QueryError::ConnectionBusy => QueryErrorKind::Connection,
+            QueryError::ProtocolError(_) | QueryError::InvalidMessage(_) => {
+                QueryErrorKind::Protocol
+            }
+            QueryError::TimeoutError | QueryError::RequestTimeout(_) => {
+                QueryErrorKind::ClientTimeout
+            }
+            QueryError::TranslationError(_) => QueryErrorKind::AddressTranslation,
+            QueryError::ResponseTooLarge { .. } => QueryErrorKind::ResponseTooLarge,
+            QueryError::DeniedByGuard { .. } => QueryErrorKind::DeniedByGuard,
+            QueryError::BudgetExhausted { .. } => QueryErrorKind::BudgetExhausted,
+        }
+    }
+
+    /// Checks if this error indicates that the keyspace or the table
+    /// that the statement refers to does not exist.
+    pub fn is_not_found_schema_object(&self) -> bool {
+        matches!(
+            self.kind(),
+            QueryErrorKind::KeyspaceNotFound | QueryErrorKind::TableNotFound
+        )
+    }
+
+    /// Checks if the driver timed out waiting for the response.
+    pub fn is_client_timeout(&self) -> bool {
+        self.kind() == QueryErrorKind::ClientTimeout
+    }
+
+    /// Checks if the coordinator timed out waiting for replicas.
+    pub fn is_coordinator_timeout(&self) -> bool {
+        matches!(
+            self.kind(),
+            QueryErrorKind::CoordinatorReadTimeout | QueryErrorKind::CoordinatorWriteTimeout
+        )
+    }
+
+    /// Checks if the error may go away when the request is retried.
+    /// See [`QueryErrorKind::is_transient`].
+    pub fn is_transient(&self) -> bool {
+        self.kind().is_transient()
+    }
+
+    /// Checks if the error guarantees that the request was not applied,
+    /// because it was rejected before being executed.
+    pub fn is_rejected_before_execution(&self) -> bool {
+        matches!(
+            self,
+            QueryError::DbError(
+                DbError::Unavailable { .. }
+                    | DbError::Overloaded
+                    | DbError::IsBootstrapping
+                    | DbError::Unprepared { .. }
+                    | DbError::RateLimitReached {
+                        rejected_by_coordinator: true,
+                        ..
+                    },
+                _
+            ) | QueryError::BadQuery(_)
+                | QueryError::UnableToAllocStreamId
+                | QueryError::ConnectionBusy
+                | QueryError::DeniedByGuard { .. }
+                | QueryError::BudgetExhausted { .. }
+        )
+    }
+
+    /// Checks if the request can be safely retried by the application: the error is transient
+    /// and either the statement is idempotent or the request was certainly not applied.
+    pub fn is_safe_to_retry(&self, is_idempotent: bool) -> bool {
+        self.is_transient() && (is_idempotent || self.is_rejected_before_execution())
+    }
 }
 
 impl From<u8> for OperationType {
@@ -561,10 +751,377 @@ impl WriteType {
     }
 }
 
+/// Class of an error, suitable for programmatic matching.
+///
+/// Returned by [`QueryError::kind`] and [`DbError::kind`]. Unlike [`QueryError`] it
+/// distinguishes errors which are only told apart by the server's message, e.g. a missing
+/// keyspace from a missing table, and client-side timeouts from coordinator timeouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum QueryErrorKind {
+    /// The statement has a syntax error.
+    Syntax,
+    /// The statement is syntactically correct but invalid.
+    Invalid,
+    /// The statement refers to a keyspace which does not exist.
+    KeyspaceNotFound,
+    /// The statement refers to a table which does not exist.
+    TableNotFound,
+    /// Attempted to create a keyspace or a table which already exists.
+    AlreadyExists,
+    /// A user defined function failed during execution.
+    FunctionFailure,
+    /// Authentication failed.
+    Authentication,
+    /// The logged user is not allowed to perform the request.
+    Unauthorized,
+    /// The statement is invalid because of a configuration issue.
+    Config,
+    /// Not enough replicas were alive to satisfy the requested consistency level.
+    Unavailable,
+    /// The coordinator was overloaded.
+    Overloaded,
+    /// The coordinator was still bootstrapping.
+    Bootstrapping,
+    /// Truncate operation failed.
+    Truncate,
+    /// The coordinator timed out waiting for replicas to respond to a read.
+    CoordinatorReadTimeout,
+    /// The coordinator timed out waiting for replicas to respond to a write.
+    CoordinatorWriteTimeout,
+    /// Replicas failed to execute a read.
+    ReadFailure,
+    /// Replicas failed to execute a write.
+    WriteFailure,
+    /// The prepared statement was not prepared on the node.
+    Unprepared,
+    /// Internal server error.
+    ServerError,
+    /// Rate limit was exceeded for a partition affected by the request.
+    RateLimited,
+    /// The server received an invalid protocol message, or sent one back.
+    Protocol,
+    /// The server returned an error not known to the driver.
+    OtherDbError,
+    /// The request was rejected by the driver, e.g. its values could not be serialized.
+    BadQuery,
+    /// The request could not be sent or its response did not arrive
+    /// because of a connection problem.
+    Connection,
+    /// The driver timed out waiting for a response.
+    ClientTimeout,
+    /// Address translation failed.
+    AddressTranslation,
+    /// The response exceeded the maximum size configured in the driver.
+    ResponseTooLarge,
+    /// The statement was denied by a statement guard configured in the session.
+    DeniedByGuard,
+    /// The shared budget of an execution was exhausted before the statement was sent.
+    BudgetExhausted,
+}
+
+impl QueryErrorKind {
+    /// Whether errors of this kind may go away when the request is retried.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            QueryErrorKind::Unavailable
+                | QueryErrorKind::Overloaded
+                | QueryErrorKind::Bootstrapping
+                | QueryErrorKind::Truncate
+                | QueryErrorKind::CoordinatorReadTimeout
+                | QueryErrorKind::CoordinatorWriteTimeout
+                | QueryErrorKind::ReadFailure
+                | QueryErrorKind::WriteFailure
+                | QueryErrorKind::Unprepared
+                | QueryErrorKind::ServerError
+                | QueryErrorKind::RateLimited
+                | QueryErrorKind::Connection
+                | QueryErrorKind::ClientTimeout
+        )
+    }
+}
+
+impl DbError {
+    /// Returns the class of the error.
+    ///
+    /// [`DbError::Invalid`] is always classified as [`QueryErrorKind::Invalid`], because
+    /// telling a missing keyspace or table apart requires the error message.
+    /// Use [`QueryError::kind`] for that.
+    pub fn kind(&self) -> QueryErrorKind {
+        match self {
+            DbError::SyntaxError => QueryErrorKind::Syntax,
+            DbError::Invalid => QueryErrorKind::Invalid,
+            DbError::AlreadyExists { .. } => QueryErrorKind::AlreadyExists,
+            DbError::FunctionFailure { .. } => QueryErrorKind::FunctionFailure,
+            DbError::AuthenticationError => QueryErrorKind::Authentication,
+            DbError::Unauthorized => QueryErrorKind::Unauthorized,
+            DbError::ConfigError => QueryErrorKind::Config,
+            DbError::Unavailable { .. } => QueryErrorKind::Unavailable,
+            DbError::Overloaded => QueryErrorKind::Overloaded,
+            DbError::IsBootstrapping => QueryErrorKind::Bootstrapping,
+            DbError::TruncateError => QueryErrorKind::Truncate,
+            DbError::ReadTimeout { .. } => QueryErrorKind::CoordinatorReadTimeout,
+            DbError::WriteTimeout { .. } => QueryErrorKind::CoordinatorWriteTimeout,
+            DbError::ReadFailure { .. } => QueryErrorKind::ReadFailure,
+            DbError::WriteFailure { .. } => QueryErrorKind::WriteFailure,
+            DbError::Unprepared { .. } => QueryErrorKind::Unprepared,
+            DbError::ServerError => QueryErrorKind::ServerError,
+            DbError::ProtocolError => QueryErrorKind::Protocol,
+            DbError::RateLimitReached { .. } => QueryErrorKind::RateLimited,
+            DbError::Other(_) => QueryErrorKind::OtherDbError,
+        }
+    }
+}
+
+// Servers report missing schema objects with `Invalid`, the only way
+// to tell them apart is the message, which differs between versions:
+// "unconfigured table t", "table t does not exist", "Keyspace ks does not exist", ...
+fn classify_invalid(message: &str) -> QueryErrorKind {
+    let message = message.to_lowercase();
+    let not_found = message.contains("does not exist")
+        || message.contains("doesn't exist")
+        || message.contains("not found")
+        || message.contains("can't find");
+
+    if message.contains("unconfigured table")
+        || (not_found && (message.contains("table") || message.contains("column family")))
+    {
+        QueryErrorKind::TableNotFound
+    } else if not_found && message.contains("keyspace") {
+        QueryErrorKind::KeyspaceNotFound
+    } else {
+        QueryErrorKind::Invalid
+    }
+}
+
+/// Error of a request executed by [`Session`](https://docs.rs/scylla/latest/scylla/transport/session/struct.Session.html),
+/// together with information about the execution. Passed to request interceptors,
+/// while `Session` methods return the underlying [`QueryError`].
+///
+/// Can be converted into the underlying [`QueryError`] with `From`/`?`.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ExecutionError {
+    error: QueryError,
+    coordinator: Option<SocketAddr>,
+    attempts: usize,
+    is_idempotent: bool,
+    statement_info: Option<Box<StatementInfo>>,
+    consistency: Option<Consistency>,
+    serial_consistency: Option<SerialConsistency>,
+    execution_history: Option<Box<ExecutionHistory>>,
+}
+
+impl ExecutionError {
+    pub fn new(
+        error: QueryError,
+        coordinator: Option<SocketAddr>,
+        attempts: usize,
+        is_idempotent: bool,
+    ) -> Self {
+        Self {
+            error,
+            coordinator,
+            attempts,
+            is_idempotent,
+            statement_info: None,
+            consistency: None,
+            serial_consistency: None,
+            execution_history: None,
+        }
+    }
+
+    /// Attaches the consistency and the serial consistency the last attempt was sent with.
+    pub fn with_consistency(
+        mut self,
+        consistency: Consistency,
+        serial_consistency: Option<SerialConsistency>,
+    ) -> Self {
+        self.consistency = Some(consistency);
+        self.serial_consistency = serial_consistency;
+        self
+    }
+
+    /// Attaches the history of attempts made to execute the request.
+    pub fn with_execution_history(mut self, execution_history: ExecutionHistory) -> Self {
+        self.execution_history = Some(Box::new(execution_history));
+        self
+    }
+
+    /// Attaches information about the statement which caused the error.
+    pub fn with_statement_info(mut self, statement_info: StatementInfo) -> Self {
+        self.statement_info = Some(Box::new(statement_info));
+        self
+    }
+
+    /// Returns the error of the last attempt, or the error which prevented
+    /// the request from being sent at all.
+    pub fn error(&self) -> &QueryError {
+        &self.error
+    }
+
+    /// Converts into the error of the last attempt.
+    pub fn into_query_error(self) -> QueryError {
+        self.error
+    }
+
+    /// Returns the class of the error. See [`QueryError::kind`].
+    pub fn kind(&self) -> QueryErrorKind {
+        self.error.kind()
+    }
+
+    /// Returns the address of the node which handled the last attempt,
+    /// or `None` if no attempt reached a node.
+    pub fn coordinator(&self) -> Option<SocketAddr> {
+        self.coordinator
+    }
+
+    /// Returns the number of attempts made, including retries and speculative executions.
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    /// Whether the statement was marked as idempotent.
+    pub fn is_idempotent(&self) -> bool {
+        self.is_idempotent
+    }
+
+    /// Whether the request can be safely retried by the application.
+    /// See [`QueryError::is_safe_to_retry`].
+    pub fn is_safe_to_retry(&self) -> bool {
+        self.error.is_safe_to_retry(self.is_idempotent)
+    }
+
+    /// Returns information about the statement which caused the error.
+    ///
+    /// Only available if the session was built with
+    /// [`SessionBuilder::attach_statement_to_errors`](https://docs.rs/scylla/latest/scylla/transport/session_builder/struct.GenericSessionBuilder.html#method.attach_statement_to_errors),
+    /// `None` otherwise.
+    pub fn statement_info(&self) -> Option<&StatementInfo> {
+        self.statement_info.as_deref()
+    }
+
+    /// Returns the consistency the last attempt was sent with, after applying the defaults
+    /// of the execution profile and the session, and the decisions of the retry policy.
+    /// `None` if no attempt reached a node.
+    pub fn consistency(&self) -> Option<Consistency> {
+        self.consistency
+    }
+
+    /// Returns the serial consistency the last attempt was sent with.
+    /// `None` if no attempt reached a node, or if the statement isn't conditional,
+    /// so that serial consistency doesn't apply to it.
+    pub fn serial_consistency(&self) -> Option<SerialConsistency> {
+        self.serial_consistency
+    }
+
+    /// Returns the attempts made to execute the request, including failed retries
+    /// and speculative executions.
+    ///
+    /// Only available if collecting the history was enabled on the statement with
+    /// `set_history_collection`, `None` otherwise.
+    pub fn execution_history(&self) -> Option<&ExecutionHistory> {
+        self.execution_history.as_deref()
+    }
+}
+
+impl std::fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)?;
+        if let Some(statement_info) = &self.statement_info {
+            write!(f, " (in {})", statement_info)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ExecutionError {}
+
+/// Statement which caused an [`ExecutionError`].
+///
+/// Never contains values bound to the statement, so that e.g. partition keys,
+/// which often are personal data, don't end up in logs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StatementInfo {
+    /// An unprepared statement.
+    Unprepared {
+        /// Text of the statement, truncated to the configured length.
+        text: String,
+    },
+    /// A prepared statement.
+    Prepared {
+        /// Id of the statement, assigned by the database when it was prepared.
+        id: Bytes,
+        /// Keyspace of the table the statement refers to, if known from the prepared metadata.
+        keyspace: Option<String>,
+        /// Table the statement refers to, if known from the prepared metadata.
+        table: Option<String>,
+    },
+    /// A batch, with information about each of its statements.
+    Batch {
+        /// Statements of the batch, in order.
+        statements: Vec<StatementInfo>,
+    },
+}
+
+impl std::fmt::Display for StatementInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatementInfo::Unprepared { text } => write!(f, "statement \"{}\"", text),
+            StatementInfo::Prepared {
+                id,
+                keyspace,
+                table,
+            } => {
+                write!(f, "prepared statement 0x")?;
+                for byte in id.iter() {
+                    write!(f, "{:02x}", byte)?;
+                }
+                if let (Some(keyspace), Some(table)) = (keyspace, table) {
+                    write!(f, " on {}.{}", keyspace, table)?;
+                }
+                Ok(())
+            }
+            StatementInfo::Batch { statements } => {
+                write!(f, "batch of {} statements", statements.len())?;
+                for (i, statement) in statements.iter().enumerate() {
+                    write!(f, "{} {}", if i == 0 { ":" } else { "," }, statement)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<QueryError> for ExecutionError {
+    fn from(error: QueryError) -> ExecutionError {
+        ExecutionError::new(error, None, 0, false)
+    }
+}
+
+impl From<ExecutionError> for QueryError {
+    fn from(error: ExecutionError) -> QueryError {
+        error.error
+    }
+}
+
+impl From<ExecutionError> for NewSessionError {
+    fn from(error: ExecutionError) -> NewSessionError {
+        error.error.into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{DbError, QueryError, WriteType};
+    use super::{
+        BadQuery, DbError, ExecutionError, OperationType, QueryError, QueryErrorKind,
+        StatementInfo, WriteType,
+    };
+    use crate::frame::frame_errors::{FrameError, ParseError, ResponseTooLarge};
     use crate::frame::types::Consistency;
+    use bytes::Bytes;
 
     #[test]
     fn write_type_from_str() {
@@ -619,4 +1176,278 @@ mod tests {
 
         assert_eq!(query_error_displayed, expected_querr_msg);
     }
+
+    #[test]
+    fn every_db_error_is_classified() {
+        let cases = [
+            (DbError::SyntaxError, QueryErrorKind::Syntax, false),
+            (DbError::Invalid, QueryErrorKind::Invalid, false),
+            (
+                DbError::AlreadyExists {
+                    keyspace: "ks".to_string(),
+                    table: "t".to_string(),
+                },
+                QueryErrorKind::AlreadyExists,
+                false,
+            ),
+            (
+                DbError::FunctionFailure {
+                    keyspace: "ks".to_string(),
+                    function: "f".to_string(),
+                    arg_types: vec!["int".to_string()],
+                },
+                QueryErrorKind::FunctionFailure,
+                false,
+            ),
+            (
+                DbError::AuthenticationError,
+                QueryErrorKind::Authentication,
+                false,
+            ),
+            (DbError::Unauthorized, QueryErrorKind::Unauthorized, false),
+            (DbError::ConfigError, QueryErrorKind::Config, false),
+            (
+                DbError::Unavailable {
+                    consistency: Consistency::Quorum,
+                    required: 2,
+                    alive: 1,
+                },
+                QueryErrorKind::Unavailable,
+                true,
+            ),
+            (DbError::Overloaded, QueryErrorKind::Overloaded, true),
+            (
+                DbError::IsBootstrapping,
+                QueryErrorKind::Bootstrapping,
+                true,
+            ),
+            (DbError::TruncateError, QueryErrorKind::Truncate, true),
+            (
+                DbError::ReadTimeout {
+                    consistency: Consistency::Quorum,
+                    received: 1,
+                    required: 2,
+                    data_present: false,
+                },
+                QueryErrorKind::CoordinatorReadTimeout,
+                true,
+            ),
+            (
+                DbError::WriteTimeout {
+                    consistency: Consistency::Quorum,
+                    received: 1,
+                    required: 2,
+                    write_type: WriteType::Simple,
+                },
+                QueryErrorKind::CoordinatorWriteTimeout,
+                true,
+            ),
+            (
+                DbError::ReadFailure {
+                    consistency: Consistency::Quorum,
+                    received: 1,
+                    required: 2,
+                    numfailures: 1,
+                    data_present: false,
+                },
+                QueryErrorKind::ReadFailure,
+                true,
+            ),
+            (
+                DbError::WriteFailure {
+                    consistency: Consistency::Quorum,
+                    received: 1,
+                    required: 2,
+                    numfailures: 1,
+                    write_type: WriteType::Simple,
+                },
+                QueryErrorKind::WriteFailure,
+                true,
+            ),
+            (
+                DbError::Unprepared {
+                    statement_id: Bytes::from_static(b"id"),
+                },
+                QueryErrorKind::Unprepared,
+                true,
+            ),
+            (DbError::ServerError, QueryErrorKind::ServerError, true),
+            (DbError::ProtocolError, QueryErrorKind::Protocol, false),
+            (
+                DbError::RateLimitReached {
+                    op_type: OperationType::Write,
+                    rejected_by_coordinator: true,
+                },
+                QueryErrorKind::RateLimited,
+                true,
+            ),
+            (DbError::Other(0x9999), QueryErrorKind::OtherDbError, false),
+        ];
+
+        for (db_error, kind, transient) in cases {
+            assert_eq!(db_error.kind(), kind, "{:?}", db_error);
+            let query_error = QueryError::DbError(db_error, "message".to_string());
+            assert_eq!(query_error.kind(), kind, "{:?}", query_error);
+            assert_eq!(query_error.is_transient(), transient, "{:?}", query_error);
+        }
+    }
+
+    #[test]
+    fn missing_schema_objects_are_told_apart() {
+        let cases = [
+            ("unconfigured table t", QueryErrorKind::TableNotFound),
+            ("table ks.t does not exist", QueryErrorKind::TableNotFound),
+            (
+                "Keyspace ks does not exist",
+                QueryErrorKind::KeyspaceNotFound,
+            ),
+            (
+                "Keyspace 'ks' does not exist",
+                QueryErrorKind::KeyspaceNotFound,
+            ),
+            ("Can't find a keyspace ks", QueryErrorKind::KeyspaceNotFound),
+            ("Undefined column name b", QueryErrorKind::Invalid),
+        ];
+
+        for (message, kind) in cases {
+            let error = QueryError::DbError(DbError::Invalid, message.to_string());
+            assert_eq!(error.kind(), kind, "{}", message);
+            assert_eq!(
+                error.is_not_found_schema_object(),
+                kind != QueryErrorKind::Invalid,
+                "{}",
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn client_errors_are_classified() {
+        assert!(QueryError::RequestTimeout("timeout".to_string()).is_client_timeout());
+        assert!(QueryError::TimeoutError.is_client_timeout());
+        assert!(!QueryError::TimeoutError.is_coordinator_timeout());
+        assert_eq!(
+            QueryError::BadQuery(BadQuery::Other("bad".to_string())).kind(),
+            QueryErrorKind::BadQuery
+        );
+        assert_eq!(
+            QueryError::from(std::io::Error::from(std::io::ErrorKind::BrokenPipe)).kind(),
+            QueryErrorKind::Connection
+        );
+        assert_eq!(
+            QueryError::ProtocolError("unexpected").kind(),
+            QueryErrorKind::Protocol
+        );
+
+        let too_large = QueryError::from(FrameError::Parse(ParseError::BatchTooLarge {
+            statement_index: 3,
+            max_size: 1024,
+        }));
+        assert!(matches!(
+            too_large,
+            QueryError::BadQuery(BadQuery::BatchTooLarge {
+                statement_index: 3,
+                max_size: 1024
+            })
+        ));
+        assert_eq!(too_large.kind(), QueryErrorKind::BadQuery);
+
+        let response_too_large = QueryError::from(FrameError::ResponseTooLarge(ResponseTooLarge {
+            size: 2048,
+            limit: 1024,
+        }));
+        assert!(matches!(
+            response_too_large,
+            QueryError::ResponseTooLarge {
+                size: 2048,
+                limit: 1024
+            }
+        ));
+        assert_eq!(response_too_large.kind(), QueryErrorKind::ResponseTooLarge);
+        assert!(!response_too_large.is_transient());
+
+        let denied = QueryError::DeniedByGuard {
+            reason: "unprepared statements are not allowed".to_string(),
+        };
+        assert_eq!(denied.kind(), QueryErrorKind::DeniedByGuard);
+        assert!(denied.is_rejected_before_execution());
+        assert!(!denied.is_safe_to_retry(true));
+
+        let exhausted = QueryError::BudgetExhausted {
+            reason: "no attempts left".to_string(),
+        };
+        assert_eq!(exhausted.kind(), QueryErrorKind::BudgetExhausted);
+        assert!(exhausted.is_rejected_before_execution());
+        assert!(!exhausted.is_safe_to_retry(false));
+    }
+
+    #[test]
+    fn retry_safety_depends_on_idempotency() {
+        let write_timeout = QueryError::DbError(
+            DbError::WriteTimeout {
+                consistency: Consistency::Quorum,
+                received: 1,
+                required: 2,
+                write_type: WriteType::Simple,
+            },
+            "timeout".to_string(),
+        );
+        assert!(write_timeout.is_coordinator_timeout());
+        assert!(write_timeout.is_safe_to_retry(true));
+        // The write might have been applied
+        assert!(!write_timeout.is_safe_to_retry(false));
+
+        // Rejected by the coordinator, so it was certainly not applied
+        let overloaded = QueryError::DbError(DbError::Overloaded, "overloaded".to_string());
+        assert!(overloaded.is_safe_to_retry(false));
+
+        let syntax = QueryError::DbError(DbError::SyntaxError, "syntax".to_string());
+        assert!(!syntax.is_safe_to_retry(true));
+
+        let error = ExecutionError::new(
+            write_timeout,
+            Some("127.0.0.1:9042".parse().unwrap()),
+            3,
+            false,
+        );
+        assert_eq!(error.attempts(), 3);
+        assert!(!error.is_safe_to_retry());
+        assert_eq!(error.kind(), QueryErrorKind::CoordinatorWriteTimeout);
+        assert_eq!(error.to_string(), error.error().to_string());
+        assert!(matches!(
+            QueryError::from(error),
+            QueryError::DbError(DbError::WriteTimeout { .. }, _)
+        ));
+    }
+
+    #[test]
+    fn statement_info_is_displayed_with_error() {
+        let error = ExecutionError::new(
+            QueryError::DbError(DbError::Invalid, "unconfigured table t".to_string()),
+            None,
+            1,
+            false,
+        );
+        assert!(error.statement_info().is_none());
+
+        let error = error.with_statement_info(StatementInfo::Batch {
+            statements: vec![
+                StatementInfo::Unprepared {
+                    text: "INSERT INTO ks.t (a) VALUES (1)".to_string(),
+                },
+                StatementInfo::Prepared {
+                    id: Bytes::from_static(&[0xca, 0xfe]),
+                    keyspace: Some("ks".to_string()),
+                    table: Some("t".to_string()),
+                },
+            ],
+        });
+        assert_eq!(
+            error.to_string(),
+            "Database returned an error: The query is syntactically correct but invalid, \
+             Error message: unconfigured table t \
+             (in batch of 2 statements: statement \"INSERT INTO ks.t (a) VALUES (1)\", \
+             prepared statement 0xcafe on ks.t)"
+        );
+    }
 }
@@ -0,0 +1,139 @@
+//! Compact history of the attempts made to execute a single request,
+//! attached to its result or error when collecting it is enabled on the statement.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::errors::QueryError;
+use crate::frame::types::Consistency;
+
+/// Maximal number of attempts recorded in an [`ExecutionHistory`].
+/// Further attempts are only counted, see [`ExecutionHistory::dropped_attempts`].
+pub const MAX_RECORDED_ATTEMPTS: usize = 16;
+
+/// Decision of a retry policy about a failed attempt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RetryDecision {
+    RetrySameNode(Option<Consistency>), // None means that the same consistency should be used as before
+    RetryNextNode(Option<Consistency>), // ditto
+    RetryNextNodeAfter(Option<Consistency>, Duration), // ditto, but waits for the given time first
+    DontRetry,
+    IgnoreWriteError,
+}
+
+/// Attempts made to execute a single request, in the order in which they were started.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionHistory {
+    attempts: Vec<AttemptRecord>,
+    dropped_attempts: usize,
+}
+
+/// A single attempt, i.e. a request sent to a node.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct AttemptRecord {
+    /// Node to which the attempt was sent
+    pub node: SocketAddr,
+    /// Whether the attempt was made by a speculative execution
+    pub speculative: bool,
+    /// Time the attempt took, `None` if it didn't finish before the request did
+    pub latency: Option<Duration>,
+    pub outcome: AttemptOutcome,
+}
+
+/// How an attempt ended.
+#[derive(Debug, Clone)]
+pub enum AttemptOutcome {
+    Success,
+    /// The attempt failed, and the retry policy decided what to do next
+    Error {
+        error: QueryError,
+        decision: RetryDecision,
+    },
+    /// The attempt didn't finish before the request did, e.g. because
+    /// another speculative execution succeeded first or the request timed out
+    Unfinished,
+}
+
+impl ExecutionHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the recorded attempts, at most [`MAX_RECORDED_ATTEMPTS`] first ones.
+    pub fn attempts(&self) -> &[AttemptRecord] {
+        &self.attempts
+    }
+
+    /// Returns the number of attempts which were made, but not recorded
+    /// because [`MAX_RECORDED_ATTEMPTS`] attempts were recorded already.
+    pub fn dropped_attempts(&self) -> usize {
+        self.dropped_attempts
+    }
+
+    /// Records the start of an attempt. Returns its index, to be passed to
+    /// [`finish_attempt`](Self::finish_attempt), or `None` if the history is full.
+    pub fn start_attempt(&mut self, node: SocketAddr, speculative: bool) -> Option<usize> {
+        if self.attempts.len() >= MAX_RECORDED_ATTEMPTS {
+            self.dropped_attempts += 1;
+            return None;
+        }
+        self.attempts.push(AttemptRecord {
+            node,
+            speculative,
+            latency: None,
+            outcome: AttemptOutcome::Unfinished,
+        });
+        Some(self.attempts.len() - 1)
+    }
+
+    /// Records the end of the attempt with the given index.
+    pub fn finish_attempt(&mut self, index: usize, latency: Duration, outcome: AttemptOutcome) {
+        if let Some(attempt) = self.attempts.get_mut(index) {
+            attempt.latency = Some(latency);
+            attempt.outcome = outcome;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AttemptOutcome, ExecutionHistory, RetryDecision, MAX_RECORDED_ATTEMPTS};
+    use crate::errors::QueryError;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    #[test]
+    fn history_is_bounded() {
+        let node: SocketAddr = "127.0.0.1:9042".parse().unwrap();
+        let mut history = ExecutionHistory::new();
+
+        let first = history.start_attempt(node, false).unwrap();
+        let speculative = history.start_attempt(node, true).unwrap();
+        history.finish_attempt(
+            first,
+            Duration::from_millis(3),
+            AttemptOutcome::Error {
+                error: QueryError::TimeoutError,
+                decision: RetryDecision::RetryNextNode(None),
+            },
+        );
+        assert!(matches!(
+            history.attempts()[first].outcome,
+            AttemptOutcome::Error {
+                decision: RetryDecision::RetryNextNode(None),
+                ..
+            }
+        ));
+        assert_eq!(history.attempts()[speculative].latency, None);
+        assert!(history.attempts()[speculative].speculative);
+
+        for _ in 2..MAX_RECORDED_ATTEMPTS {
+            assert!(history.start_attempt(node, false).is_some());
+        }
+        assert_eq!(history.start_attempt(node, false), None);
+        assert_eq!(history.start_attempt(node, false), None);
+        assert_eq!(history.attempts().len(), MAX_RECORDED_ATTEMPTS);
+        assert_eq!(history.dropped_attempts(), 2);
+    }
+}
@@ -1,4 +1,5 @@
-use super::response;
+use super::response::{self, ResponseOpcode};
+use super::FrameParams;
 use crate::cql_to_rust::CqlTypeError;
 use crate::frame::value::SerializeValuesError;
 use crate::types::serialize::SerializationError;
@@ -26,10 +27,126 @@ pub enum FrameError {
     StdIoError(#[from] std::io::Error),
     #[error("Unrecognized opcode{0}")]
     TryFromPrimitiveError(#[from] num_enum::TryFromPrimitiveError<response::ResponseOpcode>),
+    #[error(
+        "Received a frame with unknown opcode 0x{opcode:02x} (version: 0x{:02x}, flags: 0x{:02x}, stream: {}, length: {length})",
+        .params.version, .params.flags, .params.stream
+    )]
+    UnknownOpcode {
+        params: FrameParams,
+        opcode: u8,
+        length: u32,
+    },
     #[error("Error compressing lz4 data {0}")]
     Lz4CompressError(#[from] lz4_flex::block::CompressError),
     #[error("Error decompressing lz4 data {0}")]
     Lz4DecompressError(#[from] lz4_flex::block::DecompressError),
+    #[error(transparent)]
+    ResponseTooLarge(#[from] ResponseTooLarge),
+}
+
+/// The body of a response frame, or its decompressed form, exceeded the size limit.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("Response body of {size} bytes exceeds the limit of {limit} bytes")]
+pub struct ResponseTooLarge {
+    /// Size of the body in bytes.
+    pub size: usize,
+    /// The limit in bytes.
+    pub limit: usize,
+}
+
+/// The body of a response frame couldn't be parsed.
+///
+/// Besides the cause, it describes the frame and where in its body parsing failed.
+#[derive(Error, Debug)]
+#[error("{error} ({context})")]
+pub struct ResponseParseError {
+    pub error: ParseError,
+    pub context: FrameParseContext,
+}
+
+/// Describes a response frame and the offset in its body at which parsing failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FrameParseContext {
+    /// The header of the frame.
+    pub params: FrameParams,
+    pub opcode: ResponseOpcode,
+    /// Length of the parsed body: after decompression and without the tracing id,
+    /// warnings and custom payload, which precede it in the frame.
+    pub body_length: usize,
+    /// Offset in the body of the first byte which wasn't consumed when parsing failed.
+    pub offset: usize,
+    /// At most [`MAX_BYTES`](Self::MAX_BYTES) bytes of the body around the offset,
+    /// if they were requested. They may contain the data of the requests.
+    pub bytes: Option<FrameBytes>,
+}
+
+/// A part of the body of a frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameBytes {
+    /// Offset in the body of the first byte.
+    pub start: usize,
+    pub bytes: Vec<u8>,
+}
+
+impl FrameParseContext {
+    /// The maximal number of bytes of the body kept in the context.
+    pub const MAX_BYTES: usize = 64;
+
+    pub fn new(
+        params: FrameParams,
+        opcode: ResponseOpcode,
+        body: &[u8],
+        offset: usize,
+        include_bytes: bool,
+    ) -> Self {
+        let bytes = include_bytes.then(|| {
+            let end = (offset + Self::MAX_BYTES / 2)
+                .max(Self::MAX_BYTES)
+                .min(body.len());
+            let start = end.saturating_sub(Self::MAX_BYTES);
+            FrameBytes {
+                start,
+                bytes: body[start..end].to_vec(),
+            }
+        });
+        Self {
+            params,
+            opcode,
+            body_length: body.len(),
+            offset,
+            bytes,
+        }
+    }
+}
+
+impl std::fmt::Display for FrameParseContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} frame (version: 0x{:02x}, flags: 0x{:02x}, stream: {}) with a body of {} bytes, at offset {}",
+            self.opcode,
+            self.params.version,
+            self.params.flags,
+            self.params.stream,
+            self.body_length,
+            self.offset
+        )?;
+        if let Some(FrameBytes { start, bytes }) = &self.bytes {
+            write!(f, ", bytes {}..{}:", start, start + bytes.len())?;
+            // The offset is marked with `|`
+            for (i, byte) in bytes.iter().enumerate() {
+                if start + i == self.offset {
+                    write!(f, " |")?;
+                }
+                write!(f, " {:02x}", byte)?;
+            }
+            if start + bytes.len() == self.offset {
+                write!(f, " |")?;
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Error, Debug)]
@@ -48,4 +165,20 @@ pub enum ParseError {
     SerializationError(#[from] SerializationError),
     #[error(transparent)]
     CqlTypeError(#[from] CqlTypeError),
+    #[error(
+        "Serialized batch exceeded the max size of {max_size} bytes at statement {statement_index}"
+    )]
+    BatchTooLarge {
+        statement_index: usize,
+        max_size: usize,
+    },
+    #[error("Length of provided values must be equal to number of batch statements (got {values_count} values, {statements_count} statements)")]
+    BatchValuesCountMismatch {
+        values_count: usize,
+        statements_count: usize,
+    },
+    #[error("Rows with {col_count} columns were sent without metadata, but no matching metadata is cached")]
+    ResultMetadataMismatch { col_count: usize },
+    #[error("Metadata with the type of id {id:#06x} unknown to the driver couldn't be parsed consistently, the type probably has parameters: {reason}")]
+    InconsistentUnknownType { id: u16, reason: String },
 }
@@ -9,11 +9,12 @@ pub mod value;
 #[cfg(test)]
 mod value_tests;
 
-use crate::frame::frame_errors::FrameError;
+use crate::frame::frame_errors::{FrameError, ResponseTooLarge};
 use bytes::{Buf, BufMut, Bytes};
 use tokio::io::{AsyncRead, AsyncReadExt};
 use uuid::Uuid;
 
+use std::collections::HashMap;
 use std::convert::TryFrom;
 
 use request::SerializableRequest;
@@ -21,6 +22,9 @@ use response::ResponseOpcode;
 
 const HEADER_SIZE: usize = 9;
 
+/// The version of the native protocol used by the driver.
+pub const PROTOCOL_VERSION: u8 = 0x04;
+
 // Frame flags
 const FLAG_COMPRESSION: u8 = 0x01;
 const FLAG_TRACING: u8 = 0x02;
@@ -39,6 +43,8 @@ pub enum Authenticator {
 
 /// The wire protocol compression algorithm.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Compression {
     /// LZ4 compression algorithm.
     Lz4,
@@ -60,19 +66,48 @@ pub struct SerializedRequest {
 }
 
 impl SerializedRequest {
+    /// Serializes the request into a frame.
+    ///
+    /// If `custom_payload` is provided, it is sent in the frame
+    /// before the request body and the custom payload flag is set.
     pub fn make<R: SerializableRequest>(
         req: &R,
         compression: Option<Compression>,
         tracing: bool,
+        custom_payload: Option<&HashMap<String, Vec<u8>>>,
+    ) -> Result<SerializedRequest, FrameError> {
+        Self::make_in(Vec::new(), req, compression, tracing, custom_payload)
+    }
+
+    /// Same as [`SerializedRequest::make`], but serializes the frame into the given buffer,
+    /// reusing its capacity. The previous contents of the buffer are discarded.
+    pub fn make_in<R: SerializableRequest>(
+        mut data: Vec<u8>,
+        req: &R,
+        compression: Option<Compression>,
+        tracing: bool,
+        custom_payload: Option<&HashMap<String, Vec<u8>>>,
     ) -> Result<SerializedRequest, FrameError> {
         let mut flags = 0;
-        let mut data = vec![0; HEADER_SIZE];
+        data.clear();
+        data.resize(HEADER_SIZE, 0);
+
+        if custom_payload.is_some() {
+            flags |= FLAG_CUSTOM_PAYLOAD;
+        }
 
         if let Some(compression) = compression {
             flags |= FLAG_COMPRESSION;
-            let body = req.to_bytes()?;
+            let mut body = Vec::new();
+            if let Some(custom_payload) = custom_payload {
+                types::write_bytes_map(custom_payload, &mut body)?;
+            }
+            req.serialize(&mut body)?;
             compress_append(&body, compression, &mut data)?;
         } else {
+            if let Some(custom_payload) = custom_payload {
+                types::write_bytes_map(custom_payload, &mut data)?;
+            }
             req.serialize(&mut data)?;
         }
 
@@ -98,6 +133,17 @@ impl SerializedRequest {
     pub fn get_data(&self) -> &[u8] {
         &self.data[..]
     }
+
+    /// Returns the size of the body of the frame, i.e. of the frame without its header.
+    pub fn body_size(&self) -> usize {
+        self.data.len() - HEADER_SIZE
+    }
+
+    /// Returns the buffer holding the frame, so that it can be reused
+    /// with [`SerializedRequest::make_in`].
+    pub fn into_buffer(self) -> Vec<u8> {
+        self.data
+    }
 }
 
 // Parts of the frame header which are not determined by the request/response type.
@@ -111,7 +157,7 @@ pub struct FrameParams {
 impl Default for FrameParams {
     fn default() -> Self {
         Self {
-            version: 0x04,
+            version: PROTOCOL_VERSION,
             flags: 0x00,
             stream: 0,
         }
@@ -121,6 +167,17 @@ impl Default for FrameParams {
 pub async fn read_response_frame(
     reader: &mut (impl AsyncRead + Unpin),
 ) -> Result<(FrameParams, ResponseOpcode, Bytes), FrameError> {
+    let (params, opcode, body) = read_response_frame_with_limit(reader, None).await?;
+    Ok((params, opcode, body?))
+}
+
+/// Same as [`read_response_frame`], but the body of a frame larger than `max_body_size`
+/// is not buffered. It is read and discarded instead, and returned as an error,
+/// so that the following frames can still be read from the stream.
+pub async fn read_response_frame_with_limit(
+    reader: &mut (impl AsyncRead + Unpin),
+    max_body_size: Option<usize>,
+) -> Result<(FrameParams, ResponseOpcode, Result<Bytes, ResponseTooLarge>), FrameError> {
     let mut raw_header = [0u8; HEADER_SIZE];
     reader.read_exact(&mut raw_header[..]).await?;
 
@@ -131,7 +188,7 @@ pub async fn read_response_frame(
     if version & 0x80 != 0x80 {
         return Err(FrameError::FrameFromClient);
     }
-    if version & 0x7F != 0x04 {
+    if version & 0x7F != PROTOCOL_VERSION {
         return Err(FrameError::VersionNotSupported(version & 0x7f));
     }
 
@@ -144,10 +201,30 @@ pub async fn read_response_frame(
         stream,
     };
 
-    let opcode = ResponseOpcode::try_from(buf.get_u8())?;
+    let raw_opcode = buf.get_u8();
+    let raw_length = buf.get_u32();
+
+    let opcode = ResponseOpcode::try_from(raw_opcode).map_err(|_| FrameError::UnknownOpcode {
+        params: frame_params,
+        opcode: raw_opcode,
+        length: raw_length,
+    })?;
 
-    // TODO: Guard from frames that are too large
-    let length = buf.get_u32() as usize;
+    let length = raw_length as usize;
+
+    if let Some(limit) = max_body_size.filter(|limit| length > *limit) {
+        let skipped = tokio::io::copy(&mut reader.take(length as u64), &mut tokio::io::sink())
+            .await? as usize;
+        if skipped < length {
+            // EOF, too early
+            return Err(FrameError::ConnectionClosed(length - skipped, length));
+        }
+        let too_large = ResponseTooLarge {
+            size: length,
+            limit,
+        };
+        return Ok((frame_params, opcode, Err(too_large)));
+    }
 
     let mut raw_body = Vec::with_capacity(length).limit(length);
     while raw_body.has_remaining_mut() {
@@ -161,7 +238,7 @@ pub async fn read_response_frame(
         }
     }
 
-    Ok((frame_params, opcode, raw_body.into_inner().into()))
+    Ok((frame_params, opcode, Ok(raw_body.into_inner().into())))
 }
 
 pub struct ResponseBodyWithExtensions {
@@ -171,13 +248,25 @@ pub struct ResponseBodyWithExtensions {
 }
 
 pub fn parse_response_body_extensions(
+    flags: u8,
+    compression: Option<Compression>,
+    body: Bytes,
+) -> Result<ResponseBodyWithExtensions, FrameError> {
+    parse_response_body_extensions_with_limit(flags, compression, body, None)
+}
+
+/// Same as [`parse_response_body_extensions`], but fails with [`FrameError::ResponseTooLarge`]
+/// if a compressed body would be larger than `max_body_size` after decompression,
+/// before it is decompressed.
+pub fn parse_response_body_extensions_with_limit(
     flags: u8,
     compression: Option<Compression>,
     mut body: Bytes,
+    max_body_size: Option<usize>,
 ) -> Result<ResponseBodyWithExtensions, FrameError> {
     if flags & FLAG_COMPRESSION != 0 {
         if let Some(compression) = compression {
-            body = decompress(&body, compression)?.into();
+            body = decompress(&body, compression, max_body_size)?.into();
         } else {
             return Err(FrameError::NoCompressionNegotiated);
         }
@@ -246,21 +335,41 @@ fn compress_append(
     }
 }
 
-fn decompress(mut comp_body: &[u8], compression: Compression) -> Result<Vec<u8>, FrameError> {
+fn decompress(
+    mut comp_body: &[u8],
+    compression: Compression,
+    max_size: Option<usize>,
+) -> Result<Vec<u8>, FrameError> {
+    // The decompressed size is declared up front, so it is checked before anything
+    // is allocated, which protects from small frames expanding into huge bodies
+    let check_size = |size: usize| match max_size {
+        Some(limit) if size > limit => Err(ResponseTooLarge { size, limit }),
+        _ => Ok(()),
+    };
     match compression {
         Compression::Lz4 => {
+            if comp_body.len() < 4 {
+                return Err(FrameError::FrameDecompression);
+            }
             let uncomp_len = comp_body.get_u32() as usize;
+            check_size(uncomp_len)?;
             let uncomp_body = lz4_flex::decompress(comp_body, uncomp_len)?;
             Ok(uncomp_body)
         }
-        Compression::Snappy => snap::raw::Decoder::new()
-            .decompress_vec(comp_body)
-            .map_err(|_| FrameError::FrameDecompression),
+        Compression::Snappy => {
+            let uncomp_len =
+                snap::raw::decompress_len(comp_body).map_err(|_| FrameError::FrameDecompression)?;
+            check_size(uncomp_len)?;
+            snap::raw::Decoder::new()
+                .decompress_vec(comp_body)
+                .map_err(|_| FrameError::FrameDecompression)
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
+    use super::frame_errors::FrameParseContext;
     use super::*;
 
     #[test]
@@ -282,8 +391,199 @@ mod test {
         let uncomp_body = "Hello, World!".repeat(100);
         let compression = Compression::Lz4;
         compress_append(uncomp_body.as_bytes(), compression, &mut comp_body).unwrap();
-        let result = decompress(&comp_body[..], compression).unwrap();
+        let result = decompress(&comp_body[..], compression, None).unwrap();
         assert_eq!(32, comp_body.len());
         assert_eq!(uncomp_body.as_bytes(), result);
     }
+
+    #[test]
+    fn test_decompressed_size_limit() {
+        let uncomp_body = vec![0u8; 10_000];
+        for compression in [Compression::Lz4, Compression::Snappy] {
+            let mut comp_body = Vec::new();
+            compress_append(&uncomp_body, compression, &mut comp_body).unwrap();
+            assert!(comp_body.len() < 1000);
+
+            // The limit is checked against the size after decompression
+            let err = parse_response_body_extensions_with_limit(
+                FLAG_COMPRESSION,
+                Some(compression),
+                comp_body.clone().into(),
+                Some(1000),
+            )
+            .map(|_| ())
+            .unwrap_err();
+            assert!(matches!(
+                err,
+                FrameError::ResponseTooLarge(ResponseTooLarge {
+                    size: 10_000,
+                    limit: 1000
+                })
+            ));
+
+            let parsed = parse_response_body_extensions_with_limit(
+                FLAG_COMPRESSION,
+                Some(compression),
+                comp_body.into(),
+                Some(10_000),
+            )
+            .unwrap();
+            assert_eq!(&parsed.body[..], &uncomp_body[..]);
+        }
+    }
+
+    #[test]
+    fn test_parse_response_body_extensions_warnings() {
+        let trace_id = Uuid::from_u128(0x1234);
+        let warnings = vec![
+            "Aggregation query used without partition key".to_owned(),
+            "Batch modifying 20 partitions exceeded threshold".to_owned(),
+        ];
+        let rest = b"actual body";
+
+        let mut body = Vec::new();
+        types::write_uuid(&trace_id, &mut body);
+        types::write_string_list(&warnings, &mut body).unwrap();
+        body.extend_from_slice(rest);
+
+        let parsed =
+            parse_response_body_extensions(FLAG_TRACING | FLAG_WARNING, None, body.into()).unwrap();
+        assert_eq!(parsed.trace_id, Some(trace_id));
+        assert_eq!(parsed.warnings, warnings);
+        assert_eq!(&parsed.body[..], &rest[..]);
+
+        // Without the flag, the body must be left untouched
+        let parsed = parse_response_body_extensions(0, None, Bytes::from_static(rest)).unwrap();
+        assert_eq!(parsed.trace_id, None);
+        assert!(parsed.warnings.is_empty());
+        assert_eq!(&parsed.body[..], &rest[..]);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_opcode_error_describes_header() {
+        // Version, flags, stream, opcode, length
+        let frame: &[u8] = &[0x84, 0x02, 0x00, 0x07, 0x42, 0x00, 0x00, 0x00, 0x10];
+        let err = read_response_frame(&mut &frame[..]).await.unwrap_err();
+        assert!(matches!(
+            err,
+            FrameError::UnknownOpcode {
+                opcode: 0x42,
+                length: 16,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Received a frame with unknown opcode 0x42 (version: 0x84, flags: 0x02, stream: 7, length: 16)"
+        );
+    }
+
+    #[test]
+    fn test_truncated_body_error_describes_offset() {
+        let params = FrameParams {
+            version: 0x84,
+            flags: 0x00,
+            stream: 3,
+        };
+        // SUPPORTED with a multimap of one entry: {"COMPRESSION": ["lz4", ...
+        // declaring two values, but truncated after the first one
+        let mut body = Vec::new();
+        types::write_short(1, &mut body);
+        types::write_string("COMPRESSION", &mut body).unwrap();
+        types::write_short(2, &mut body);
+        types::write_string("lz4", &mut body).unwrap();
+        types::write_short(6, &mut body);
+        body.extend_from_slice(b"sna");
+        let body = Bytes::from(body);
+
+        let parse = |include_bytes| {
+            response::Response::deserialize_frame(
+                &Default::default(),
+                params,
+                ResponseOpcode::Supported,
+                &body,
+                false,
+                None,
+                include_bytes,
+                Default::default(),
+            )
+            .map(|_| ())
+            .unwrap_err()
+        };
+
+        let err = parse(false);
+        assert_eq!(err.context.offset, 24);
+        assert_eq!(err.context.body_length, 27);
+        assert_eq!(err.context.bytes, None);
+        assert_eq!(
+            err.to_string(),
+            "Could not deserialize frame: Not enough bytes! expected: 6 received: 3 \
+             (Supported frame (version: 0x84, flags: 0x00, stream: 3) with a body of 27 bytes, at offset 24)"
+        );
+
+        let err = parse(true);
+        assert_eq!(
+            err.to_string(),
+            "Could not deserialize frame: Not enough bytes! expected: 6 received: 3 \
+             (Supported frame (version: 0x84, flags: 0x00, stream: 3) with a body of 27 bytes, at offset 24, \
+             bytes 0..27: 00 01 00 0b 43 4f 4d 50 52 45 53 53 49 4f 4e 00 02 00 03 6c 7a 34 00 06 | 73 6e 61)"
+        );
+    }
+
+    #[test]
+    fn test_frame_bytes_are_limited_around_offset() {
+        let body: Vec<u8> = (0..=255).collect();
+        let context = |offset| {
+            FrameParseContext::new(
+                FrameParams::default(),
+                ResponseOpcode::Result,
+                &body,
+                offset,
+                true,
+            )
+            .bytes
+            .unwrap()
+        };
+
+        let bytes = context(100);
+        assert_eq!(bytes.start, 68);
+        assert_eq!(bytes.bytes, body[68..132]);
+        // The window is moved, so that it's full at the ends of the body
+        assert_eq!(context(10).start, 0);
+        assert_eq!(context(10).bytes.len(), FrameParseContext::MAX_BYTES);
+        assert_eq!(context(256).start, 192);
+        assert_eq!(context(256).bytes.len(), FrameParseContext::MAX_BYTES);
+    }
+
+    #[test]
+    fn test_serialized_request_custom_payload() {
+        let mut custom_payload = HashMap::new();
+        custom_payload.insert("tenant".to_owned(), b"acme".to_vec());
+        let mut expected_body = Vec::new();
+        types::write_bytes_map(&custom_payload, &mut expected_body).unwrap();
+
+        let request =
+            SerializedRequest::make(&request::Options, None, false, Some(&custom_payload)).unwrap();
+        let data = request.get_data();
+        assert_eq!(data[1], FLAG_CUSTOM_PAYLOAD);
+        assert_eq!(&data[HEADER_SIZE..], &expected_body[..]);
+
+        // The custom payload is a part of the compressed body
+        let request = SerializedRequest::make(
+            &request::Options,
+            Some(Compression::Lz4),
+            false,
+            Some(&custom_payload),
+        )
+        .unwrap();
+        let data = request.get_data();
+        assert_eq!(data[1], FLAG_CUSTOM_PAYLOAD | FLAG_COMPRESSION);
+        let body = decompress(&data[HEADER_SIZE..], Compression::Lz4, None).unwrap();
+        assert_eq!(body, expected_body);
+
+        let request = SerializedRequest::make(&request::Options, None, false, None).unwrap();
+        assert_eq!(request.get_data()[1], 0);
+        assert_eq!(request.get_data().len(), HEADER_SIZE);
+        assert_eq!(request.body_size(), 0);
+    }
 }
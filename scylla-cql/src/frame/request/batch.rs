@@ -35,6 +35,9 @@ where
     pub serial_consistency: Option<types::SerialConsistency>,
     pub timestamp: Option<i64>,
     pub values: Values,
+    /// Upper bound on the serialized size of the statements and their values.
+    /// Serialization fails with [`ParseError::BatchTooLarge`] as soon as it is exceeded.
+    pub max_serialized_size: Option<usize>,
 }
 
 /// The type of a batch.
@@ -84,18 +87,19 @@ where
     const OPCODE: RequestOpcode = RequestOpcode::Batch;
 
     fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ParseError> {
+        let batch_start = buf.len();
+
         // Serializing type of batch
         buf.put_u8(self.batch_type as u8);
 
         // Serializing queries
         types::write_short(self.statements.len().try_into()?, buf);
 
-        let counts_mismatch_err = |n_values: usize, n_statements: usize| {
-            ParseError::BadDataToSerialize(format!(
-                "Length of provided values must be equal to number of batch statements \
-                    (got {n_values} values, {n_statements} statements)"
-            ))
-        };
+        let counts_mismatch_err =
+            |n_values: usize, n_statements: usize| ParseError::BatchValuesCountMismatch {
+                values_count: n_values,
+                statements_count: n_statements,
+            };
         let mut n_serialized_statements = 0usize;
         let mut value_lists = self.values.batch_values_iter();
         for (idx, statement) in self.statements.iter().enumerate() {
@@ -117,6 +121,17 @@ where
             };
             buf[length_pos..length_pos + 2].copy_from_slice(&count.to_be_bytes());
 
+            // Rows are written straight into the frame, so the limit is checked
+            // after each of them instead of once the whole batch is serialized.
+            if let Some(max_size) = self.max_serialized_size {
+                if buf.len() - batch_start > max_size {
+                    return Err(ParseError::BatchTooLarge {
+                        statement_index: idx,
+                        max_size,
+                    });
+                }
+            }
+
             n_serialized_statements += 1;
         }
         // At this point, we have all statements serialized. If any values are still left, we have a mismatch.
@@ -264,6 +279,7 @@ impl<'b> DeserializableRequest for Batch<'b, BatchStatement<'b>, Vec<SerializedV
             timestamp,
             statements: Cow::Owned(statements),
             values,
+            max_serialized_size: None,
         })
     }
 }
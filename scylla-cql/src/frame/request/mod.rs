@@ -100,20 +100,23 @@ impl<'r> Request<'r> {
 
 #[cfg(test)]
 mod tests {
-    use std::{borrow::Cow, ops::Deref};
+    use std::{borrow::Cow, collections::HashMap, ops::Deref};
 
     use bytes::Bytes;
 
     use crate::{
         frame::{
+            frame_errors::ParseError,
             request::{
                 batch::{Batch, BatchStatement, BatchType},
                 execute::Execute,
                 query::{Query, QueryParameters},
+                startup::{self, Startup},
                 DeserializableRequest, SerializableRequest,
             },
             response::result::ColumnType,
-            types::{self, SerialConsistency},
+            types::{self, RawValue, SerialConsistency},
+            value::{MaybeUnset, Unset},
         },
         types::serialize::row::SerializedValues,
         Consistency,
@@ -134,6 +137,7 @@ mod tests {
                 vals.add_value(&2137, &ColumnType::Int).unwrap();
                 Cow::Owned(vals)
             },
+            skip_metadata: false,
         };
         let query = Query {
             contents,
@@ -162,6 +166,7 @@ mod tests {
                 vals.add_value(&2137, &ColumnType::Int).unwrap();
                 Cow::Owned(vals)
             },
+            skip_metadata: true,
         };
         let execute = Execute { id, parameters };
         {
@@ -193,6 +198,7 @@ mod tests {
                 query.parameters.values.deref().clone(),
                 query.parameters.values.deref().clone(),
             ],
+            max_serialized_size: None,
         };
         {
             let mut buf = Vec::new();
@@ -214,6 +220,7 @@ mod tests {
             page_size: None,
             paging_state: None,
             values: Cow::Borrowed(SerializedValues::EMPTY),
+            skip_metadata: false,
         };
         let query = Query {
             contents: contents.clone(),
@@ -263,6 +270,7 @@ mod tests {
             timestamp: None,
 
             values: vec![query.parameters.values.deref().clone()],
+            max_serialized_size: None,
         };
         {
             let mut buf = Vec::new();
@@ -284,4 +292,207 @@ mod tests {
             let _parse_error = Batch::deserialize(&mut &buf[..]).unwrap_err();
         }
     }
+
+    #[test]
+    fn query_values_encode_unset_and_null() {
+        let mut values = SerializedValues::new();
+        values.add_value(&1, &ColumnType::Int).unwrap();
+        values.add_value(&Unset, &ColumnType::Int).unwrap();
+        values.add_value(&None::<i32>, &ColumnType::Int).unwrap();
+        values
+            .add_value(&MaybeUnset::<i32>::Unset, &ColumnType::Int)
+            .unwrap();
+        let query = Query {
+            contents: Cow::Borrowed("INSERT INTO ks.t (a, b, c, d) VALUES (?, ?, ?, ?)"),
+            parameters: QueryParameters {
+                values: Cow::Owned(values),
+                ..Default::default()
+            },
+        };
+
+        let mut buf = Vec::new();
+        query.serialize(&mut buf).unwrap();
+
+        // Values follow the contents, consistency and flags.
+        let mut buf_ptr = buf.as_slice();
+        types::read_long_string(&mut buf_ptr).unwrap();
+        types::read_consistency(&mut buf_ptr).unwrap();
+        assert_eq!(buf_ptr[0] & 0x01, 0x01);
+        let values_in_frame = &buf_ptr[1..];
+        assert_eq!(
+            values_in_frame,
+            &[
+                0, 4, // number of values
+                0, 0, 0, 4, 0, 0, 0, 1, // 1
+                255, 255, 255, 254, // Unset (encoded as -2)
+                255, 255, 255, 255, // Null (encoded as -1)
+                255, 255, 255, 254, // Unset (encoded as -2)
+            ]
+        );
+
+        let query_deserialized = Query::deserialize(&mut &buf[..]).unwrap();
+        assert_eq!(
+            query_deserialized
+                .parameters
+                .values
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![
+                RawValue::Value(&[0, 0, 0, 1]),
+                RawValue::Unset,
+                RawValue::Null,
+                RawValue::Unset
+            ]
+        );
+    }
+
+    #[test]
+    fn mixed_batch_ser_de_identity() {
+        let statements = vec![
+            BatchStatement::Query {
+                text: Cow::Borrowed("INSERT INTO ks.t (a, b) VALUES (?, ?)"),
+            },
+            BatchStatement::Prepared {
+                id: Cow::Borrowed(&[1, 2, 3, 4]),
+            },
+            BatchStatement::Query {
+                text: Cow::Borrowed("DELETE FROM ks.t WHERE a = 7"),
+            },
+            BatchStatement::Prepared {
+                id: Cow::Borrowed(&[5, 6]),
+            },
+        ];
+        let values = {
+            let mut query_values = SerializedValues::new();
+            query_values.add_value(&1, &ColumnType::Int).unwrap();
+            query_values.add_value(&"one", &ColumnType::Text).unwrap();
+            let mut prepared_values = SerializedValues::new();
+            prepared_values
+                .add_value(&vec![0xffu8; 32], &ColumnType::Blob)
+                .unwrap();
+            vec![
+                query_values,
+                prepared_values.clone(),
+                SerializedValues::new(),
+                prepared_values,
+            ]
+        };
+        let batch = Batch {
+            statements: Cow::Owned(statements),
+            batch_type: BatchType::Unlogged,
+            consistency: Consistency::Quorum,
+            serial_consistency: None,
+            timestamp: Some(1234),
+            values,
+            max_serialized_size: Some(1024),
+        };
+
+        let mut buf = Vec::new();
+        batch.serialize(&mut buf).unwrap();
+
+        let batch_deserialized = Batch::deserialize(&mut &buf[..]).unwrap();
+        assert_eq!(batch_deserialized.statements, batch.statements);
+        assert_eq!(batch_deserialized.values, batch.values);
+        assert_eq!(batch_deserialized.batch_type, batch.batch_type);
+        assert_eq!(batch_deserialized.consistency, batch.consistency);
+        assert_eq!(batch_deserialized.timestamp, batch.timestamp);
+    }
+
+    #[test]
+    fn batch_exceeding_max_size() {
+        let statements = vec![
+            BatchStatement::Prepared {
+                id: Cow::Borrowed(&[1, 2]),
+            };
+            10
+        ];
+        let mut row = SerializedValues::new();
+        row.add_value(&vec![0u8; 100], &ColumnType::Blob).unwrap();
+
+        // Batch type and statement count, followed by statements of 111 bytes each:
+        // kind, id with its length, value count, blob with its length.
+        let size_of_statements = |n: usize| 3 + 111 * n;
+
+        let batch_with_limit = |max_size: usize| Batch {
+            statements: Cow::Borrowed(&statements),
+            batch_type: BatchType::Logged,
+            consistency: Consistency::One,
+            serial_consistency: None,
+            timestamp: None,
+            values: vec![row.clone(); 10],
+            max_serialized_size: Some(max_size),
+        };
+
+        let mut buf = Vec::new();
+        batch_with_limit(size_of_statements(10))
+            .serialize(&mut buf)
+            .unwrap();
+
+        let max_size = size_of_statements(4);
+        let err = batch_with_limit(max_size)
+            .serialize(&mut Vec::new())
+            .unwrap_err();
+        match err {
+            ParseError::BatchTooLarge {
+                statement_index,
+                max_size: reported_max_size,
+            } => {
+                assert_eq!(statement_index, 4);
+                assert_eq!(reported_max_size, max_size);
+            }
+            other => panic!("Expected BatchTooLarge, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn startup_custom_options_are_merged_over_defaults() {
+        let map = |entries: &[(&str, &str)]| -> HashMap<String, String> {
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+        let defaults = map(&[
+            (startup::CQL_VERSION, "4.0.0"),
+            (startup::COMPRESSION, "lz4"),
+            (startup::DRIVER_NAME, "scylla-rust-driver"),
+            (startup::DRIVER_VERSION, "0.0.0"),
+            ("SCYLLA_RATE_LIMIT_ERROR", ""),
+        ]);
+        let custom = map(&[
+            (startup::DRIVER_NAME, "platform"),
+            ("AUDIT_TAG", "billing"),
+            (startup::COMPRESSION, "snappy"),
+            ("SCYLLA_RATE_LIMIT_ERROR", "off"),
+        ]);
+
+        // Reserved options are protected
+        let mut startup = Startup {
+            options: defaults.clone(),
+        };
+        let skipped = startup.merge_custom_options(&custom, false);
+        assert_eq!(skipped, [startup::COMPRESSION, "SCYLLA_RATE_LIMIT_ERROR"]);
+        let expected = map(&[
+            (startup::CQL_VERSION, "4.0.0"),
+            (startup::COMPRESSION, "lz4"),
+            (startup::DRIVER_NAME, "platform"),
+            (startup::DRIVER_VERSION, "0.0.0"),
+            ("SCYLLA_RATE_LIMIT_ERROR", ""),
+            ("AUDIT_TAG", "billing"),
+        ]);
+        assert_eq!(startup.options, expected);
+
+        // The merged map is what gets serialized
+        let mut buf = Vec::new();
+        startup.serialize(&mut buf).unwrap();
+        assert_eq!(types::read_short(&mut &buf[..]).unwrap(), 6);
+        let deserialized = Startup::deserialize(&mut &buf[..]).unwrap();
+        assert_eq!(deserialized.options, expected);
+
+        // Unless forced
+        let mut startup = Startup { options: defaults };
+        assert!(startup.merge_custom_options(&custom, true).is_empty());
+        assert_eq!(startup.options[startup::COMPRESSION], "snappy");
+        assert_eq!(startup.options["SCYLLA_RATE_LIMIT_ERROR"], "off");
+    }
 }
@@ -64,6 +64,9 @@ pub struct QueryParameters<'a> {
     pub page_size: Option<i32>,
     pub paging_state: Option<Bytes>,
     pub values: Cow<'a, SerializedValues>,
+    /// Asks the server not to send the metadata of result rows, which the client
+    /// already holds, e.g. as the result metadata of a prepared statement.
+    pub skip_metadata: bool,
 }
 
 impl Default for QueryParameters<'_> {
@@ -75,6 +78,7 @@ impl Default for QueryParameters<'_> {
             page_size: None,
             paging_state: None,
             values: Cow::Borrowed(SerializedValues::EMPTY),
+            skip_metadata: false,
         }
     }
 }
@@ -88,6 +92,10 @@ impl QueryParameters<'_> {
             flags |= FLAG_VALUES;
         }
 
+        if self.skip_metadata {
+            flags |= FLAG_SKIP_METADATA;
+        }
+
         if self.page_size.is_some() {
             flags |= FLAG_PAGE_SIZE;
         }
@@ -143,6 +151,7 @@ impl<'q> QueryParameters<'q> {
             )));
         }
         let values_flag = (flags & FLAG_VALUES) != 0;
+        let skip_metadata = (flags & FLAG_SKIP_METADATA) != 0;
         let page_size_flag = (flags & FLAG_PAGE_SIZE) != 0;
         let paging_state_flag = (flags & FLAG_WITH_PAGING_STATE) != 0;
         let serial_consistency_flag = (flags & FLAG_WITH_SERIAL_CONSISTENCY) != 0;
@@ -193,6 +202,7 @@ impl<'q> QueryParameters<'q> {
             page_size,
             paging_state,
             values,
+            skip_metadata,
         })
     }
 }
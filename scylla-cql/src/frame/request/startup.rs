@@ -3,14 +3,57 @@ use crate::frame::frame_errors::ParseError;
 use std::collections::HashMap;
 
 use crate::{
-    frame::request::{RequestOpcode, SerializableRequest},
+    frame::protocol_features::{
+        RATE_LIMIT_ERROR_EXTENSION, SCYLLA_LWT_ADD_METADATA_MARK_EXTENSION,
+    },
+    frame::request::{DeserializableRequest, RequestOpcode, SerializableRequest},
     frame::types,
 };
 
+pub const CQL_VERSION: &str = "CQL_VERSION";
+pub const COMPRESSION: &str = "COMPRESSION";
+pub const DRIVER_NAME: &str = "DRIVER_NAME";
+pub const DRIVER_VERSION: &str = "DRIVER_VERSION";
+
+/// Returns whether the option selects something negotiated with the server, which the driver
+/// relies on: the CQL version, the compression or one of the Scylla protocol extensions.
+/// Such options aren't overridden by custom ones unless forced, see [`Startup::merge_custom_options`].
+pub fn is_reserved_option(key: &str) -> bool {
+    [
+        CQL_VERSION,
+        COMPRESSION,
+        RATE_LIMIT_ERROR_EXTENSION,
+        SCYLLA_LWT_ADD_METADATA_MARK_EXTENSION,
+    ]
+    .contains(&key)
+}
+
 pub struct Startup {
     pub options: HashMap<String, String>,
 }
 
+impl Startup {
+    /// Sets the custom options, overriding the current ones with the same keys.
+    /// [Reserved](is_reserved_option) options are set only if `force_reserved` is true;
+    /// otherwise, they are skipped, and their keys are returned.
+    pub fn merge_custom_options(
+        &mut self,
+        custom_options: &HashMap<String, String>,
+        force_reserved: bool,
+    ) -> Vec<String> {
+        let mut skipped = Vec::new();
+        for (key, value) in custom_options {
+            if is_reserved_option(key) && !force_reserved {
+                skipped.push(key.clone());
+            } else {
+                self.options.insert(key.clone(), value.clone());
+            }
+        }
+        skipped.sort();
+        skipped
+    }
+}
+
 impl SerializableRequest for Startup {
     const OPCODE: RequestOpcode = RequestOpcode::Startup;
 
@@ -19,3 +62,10 @@ impl SerializableRequest for Startup {
         Ok(())
     }
 }
+
+impl DeserializableRequest for Startup {
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, ParseError> {
+        let options = types::read_string_map(buf)?;
+        Ok(Self { options })
+    }
+}
@@ -0,0 +1,857 @@
+//! Comparison of [`CqlValue`]s in the order the database sorts them,
+//! e.g. to sort or merge rows by their clustering key on the client side.
+//!
+//! The order isn't always the natural order of the Rust types:
+//! - text and blobs are compared byte by byte, as unsigned bytes, regardless of the locale,
+//! - timeuuids are compared by their timestamps first, and then by the remaining bytes
+//!   compared as signed bytes,
+//! - uuids are compared by their versions first, time-based ones by their timestamps next,
+//!   and then as unsigned bytes,
+//! - inet addresses are compared byte by byte, so IPv4 and IPv6 addresses are interleaved,
+//! - floating point numbers are ordered with `-0.0` before `0.0`, and NaN after everything else,
+//! - durations, which can't be clustering columns, are compared byte by byte in their
+//!   serialized form, the same way the database compares them elsewhere,
+//! - empty values are before all other values of the type, and nulls (in tuples
+//!   and user defined types) are before all other values.
+
+use std::cmp::Ordering;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::result::{ColumnType, CqlValue};
+use crate::frame::types::vint_encode;
+use crate::frame::value::CqlDuration;
+
+/// Error returned when [`CqlValue`]s can't be compared as a CQL type.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CqlCompareError {
+    /// The value doesn't match the type, e.g. a text value compared as an int.
+    #[error("A value of variant {value} can't be compared as the CQL type {}", typ.cql_type_name())]
+    MismatchedValue {
+        /// Name of the variant of the value.
+        value: &'static str,
+        /// The type the values were compared as.
+        typ: ColumnType,
+    },
+
+    /// Values of the type don't have a known order, e.g. custom types.
+    #[error("Values of the CQL type {} can't be compared", typ.cql_type_name())]
+    Incomparable {
+        /// The type the values were compared as.
+        typ: ColumnType,
+    },
+
+    /// A clustering prefix has more values than the clustering key has columns.
+    #[error("Got a clustering prefix of {got} values, but the clustering key has only {expected} columns")]
+    TooManyValues {
+        /// Number of values in the prefix.
+        got: usize,
+        /// Number of columns of the clustering key.
+        expected: usize,
+    },
+}
+
+impl CqlValue {
+    /// Compares the value with another value of the type `typ`, in the order
+    /// in which the database sorts values of clustering columns of the type
+    /// in ascending order. See the [module](self) documentation for the details.
+    ///
+    /// Fails if either of the values doesn't match the type, instead of panicking.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla_cql::frame::response::result::{ColumnType, CqlValue};
+    /// # use std::cmp::Ordering;
+    /// // Unlike Rust strings compared with a locale, uppercase letters are before lowercase
+    /// let apple = CqlValue::Text("apple".to_string());
+    /// let zebra = CqlValue::Text("Zebra".to_string());
+    /// assert_eq!(zebra.cmp_cql(&apple, &ColumnType::Text), Ok(Ordering::Less));
+    ///
+    /// // Blobs are compared as unsigned bytes
+    /// let low = CqlValue::Blob(vec![0x7f]);
+    /// let high = CqlValue::Blob(vec![0x80]);
+    /// assert_eq!(low.cmp_cql(&high, &ColumnType::Blob), Ok(Ordering::Less));
+    ///
+    /// assert!(low.cmp_cql(&zebra, &ColumnType::Blob).is_err());
+    /// ```
+    pub fn cmp_cql(&self, other: &CqlValue, typ: &ColumnType) -> Result<Ordering, CqlCompareError> {
+        use ColumnType as T;
+        use CqlValue as V;
+
+        let ordering = match (typ, self, other) {
+            (T::Custom(_) | T::Unknown { .. }, _, _) => {
+                return Err(CqlCompareError::Incomparable { typ: typ.clone() })
+            }
+
+            // Empty values of any type are the smallest; the other value still has to match
+            (_, V::Empty, V::Empty) => Ordering::Equal,
+            (_, V::Empty, value) => return value.cmp_cql(value, typ).map(|_| Ordering::Less),
+            (_, value, V::Empty) => return value.cmp_cql(value, typ).map(|_| Ordering::Greater),
+
+            (T::Ascii | T::Text, V::Ascii(a) | V::Text(a), V::Ascii(b) | V::Text(b)) => {
+                a.as_bytes().cmp(b.as_bytes())
+            }
+            (T::Blob, a, b) => match (blob_bytes(a), blob_bytes(b)) {
+                (Some(a), Some(b)) => a.cmp(b),
+                _ => return Err(mismatch(typ, a, b)),
+            },
+            (T::Boolean, V::Boolean(a), V::Boolean(b)) => a.cmp(b),
+            (T::TinyInt, V::TinyInt(a), V::TinyInt(b)) => a.cmp(b),
+            (T::SmallInt, V::SmallInt(a), V::SmallInt(b)) => a.cmp(b),
+            (T::Int, V::Int(a), V::Int(b)) => a.cmp(b),
+            (T::BigInt, V::BigInt(a), V::BigInt(b)) => a.cmp(b),
+            (T::Counter, V::Counter(a), V::Counter(b)) => a.0.cmp(&b.0),
+            (T::Float, V::Float(a), V::Float(b)) => cmp_floats(*a, *b, f32::is_nan, f32::total_cmp),
+            (T::Double, V::Double(a), V::Double(b)) => {
+                cmp_floats(*a, *b, f64::is_nan, f64::total_cmp)
+            }
+            (T::Decimal, V::Decimal(a), V::Decimal(b)) => a.cmp(b),
+            (T::Varint, V::Varint(a), V::Varint(b)) => a.cmp(b),
+            (T::Date, V::Date(a), V::Date(b)) => a.0.cmp(&b.0),
+            (T::Time, V::Time(a), V::Time(b)) => a.0.cmp(&b.0),
+            (T::Timestamp, V::Timestamp(a), V::Timestamp(b)) => a.0.cmp(&b.0),
+            (T::Duration, V::Duration(a), V::Duration(b)) => {
+                duration_bytes(a).cmp(&duration_bytes(b))
+            }
+            (T::Inet, V::Inet(a), V::Inet(b)) => inet_bytes(a).cmp(&inet_bytes(b)),
+            (T::Timeuuid, V::Timeuuid(a), V::Timeuuid(b)) => cmp_timeuuids(a, b),
+            (T::Uuid, V::Uuid(a), V::Uuid(b)) => cmp_uuids(a, b),
+
+            (T::List(elem) | T::Set(elem), V::List(a) | V::Set(a), V::List(b) | V::Set(b)) => {
+                cmp_sequences(a.iter().zip(b), a.len(), b.len(), |(a, b)| {
+                    a.cmp_cql(b, elem)
+                })?
+            }
+            (T::Map(key, value), V::Map(a), V::Map(b)) => {
+                cmp_sequences(a.iter().zip(b), a.len(), b.len(), |((ka, va), (kb, vb))| {
+                    Ok(ka.cmp_cql(kb, key)?.then(va.cmp_cql(vb, value)?))
+                })?
+            }
+            (T::Tuple(types), V::Tuple(a), V::Tuple(b)) => {
+                if a.len() > types.len() || b.len() > types.len() {
+                    return Err(mismatch(typ, self, other));
+                }
+                cmp_sequences(
+                    types.iter().zip(a.iter().zip(b)),
+                    a.len(),
+                    b.len(),
+                    |(typ, (a, b))| cmp_nullable(a.as_ref(), b.as_ref(), typ),
+                )?
+            }
+            (
+                T::UserDefinedType { field_types, .. },
+                V::UserDefinedType { fields: a, .. },
+                V::UserDefinedType { fields: b, .. },
+            ) => {
+                if a.len() > field_types.len() || b.len() > field_types.len() {
+                    return Err(mismatch(typ, self, other));
+                }
+                cmp_sequences(
+                    field_types.iter().zip(a.iter().zip(b)),
+                    a.len(),
+                    b.len(),
+                    |((_, typ), ((_, a), (_, b)))| cmp_nullable(a.as_ref(), b.as_ref(), typ),
+                )?
+            }
+
+            _ => return Err(mismatch(typ, self, other)),
+        };
+        Ok(ordering)
+    }
+}
+
+/// Direction in which a clustering column is sorted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// `ASC`, the default.
+    #[default]
+    Ascending,
+    /// `DESC`, i.e. reversed.
+    Descending,
+}
+
+/// Compares clustering keys, or their prefixes, in the order in which the database
+/// sorts rows of a partition.
+///
+/// # Example
+/// ```
+/// # use scylla_cql::frame::response::result::{ColumnType, CqlValue};
+/// # use scylla_cql::frame::response::cql_ordering::{ClusteringOrder, SortOrder};
+/// # use std::cmp::Ordering;
+/// // CLUSTERING ORDER BY (a ASC, b DESC)
+/// let order = ClusteringOrder::new(vec![
+///     (ColumnType::Int, SortOrder::Ascending),
+///     (ColumnType::Text, SortOrder::Descending),
+/// ]);
+///
+/// let first = [Some(CqlValue::Int(1)), Some(CqlValue::Text("b".to_string()))];
+/// let second = [Some(CqlValue::Int(1)), Some(CqlValue::Text("a".to_string()))];
+/// assert_eq!(order.compare(&first, &second), Ok(Ordering::Less));
+///
+/// // A prefix is before the keys it's a prefix of
+/// assert_eq!(order.compare(&first[..1], &first), Ok(Ordering::Less));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusteringOrder {
+    columns: Vec<(ColumnType, SortOrder)>,
+}
+
+impl ClusteringOrder {
+    /// Creates the order of a clustering key with given types and directions of the columns.
+    pub fn new(columns: Vec<(ColumnType, SortOrder)>) -> Self {
+        Self { columns }
+    }
+
+    /// Types and directions of the columns.
+    pub fn columns(&self) -> &[(ColumnType, SortOrder)] {
+        &self.columns
+    }
+
+    /// Compares two clustering prefixes, i.e. values of the first columns of clustering keys,
+    /// e.g. taken from the [`columns`](super::result::Row::columns) of rows.
+    ///
+    /// If one of the prefixes is a prefix of the other one, it's before it.
+    /// Nulls are before all other values, though the database doesn't allow them
+    /// in clustering keys.
+    pub fn compare(
+        &self,
+        a: &[Option<CqlValue>],
+        b: &[Option<CqlValue>],
+    ) -> Result<Ordering, CqlCompareError> {
+        for prefix in [a, b] {
+            if prefix.len() > self.columns.len() {
+                return Err(CqlCompareError::TooManyValues {
+                    got: prefix.len(),
+                    expected: self.columns.len(),
+                });
+            }
+        }
+        cmp_sequences(
+            self.columns.iter().zip(a.iter().zip(b)),
+            a.len(),
+            b.len(),
+            |((typ, order), (a, b))| {
+                let ordering = cmp_nullable(a.as_ref(), b.as_ref(), typ)?;
+                Ok(match order {
+                    SortOrder::Ascending => ordering,
+                    SortOrder::Descending => ordering.reverse(),
+                })
+            },
+        )
+    }
+}
+
+// Compares pairs of elements until they differ.
+// If all of them are equal, the shorter sequence is first.
+fn cmp_sequences<T>(
+    pairs: impl Iterator<Item = T>,
+    len_a: usize,
+    len_b: usize,
+    mut cmp: impl FnMut(T) -> Result<Ordering, CqlCompareError>,
+) -> Result<Ordering, CqlCompareError> {
+    for pair in pairs {
+        let ordering = cmp(pair)?;
+        if ordering != Ordering::Equal {
+            return Ok(ordering);
+        }
+    }
+    Ok(len_a.cmp(&len_b))
+}
+
+fn cmp_nullable(
+    a: Option<&CqlValue>,
+    b: Option<&CqlValue>,
+    typ: &ColumnType,
+) -> Result<Ordering, CqlCompareError> {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp_cql(b, typ),
+        (None, None) => Ok(Ordering::Equal),
+        (None, Some(b)) => b.cmp_cql(b, typ).map(|_| Ordering::Less),
+        (Some(a), None) => a.cmp_cql(a, typ).map(|_| Ordering::Greater),
+    }
+}
+
+// Same as `Float.compare` and `Double.compare` of Java: NaNs are equal to each other
+// and after everything else, regardless of their sign
+fn cmp_floats<F: Copy>(
+    a: F,
+    b: F,
+    is_nan: impl Fn(F) -> bool,
+    total_cmp: impl Fn(&F, &F) -> Ordering,
+) -> Ordering {
+    match (is_nan(a), is_nan(b)) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => total_cmp(&a, &b),
+    }
+}
+
+fn blob_bytes(value: &CqlValue) -> Option<&[u8]> {
+    match value {
+        CqlValue::Blob(blob) => Some(blob),
+        CqlValue::SharedBlob(blob) => Some(blob),
+        _ => None,
+    }
+}
+
+fn duration_bytes(duration: &CqlDuration) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    vint_encode(duration.months as i64, &mut bytes);
+    vint_encode(duration.days as i64, &mut bytes);
+    vint_encode(duration.nanoseconds, &mut bytes);
+    bytes
+}
+
+fn inet_bytes(addr: &std::net::IpAddr) -> Vec<u8> {
+    match addr {
+        std::net::IpAddr::V4(addr) => addr.octets().to_vec(),
+        std::net::IpAddr::V6(addr) => addr.octets().to_vec(),
+    }
+}
+
+// The 60-bit timestamp of a time-based uuid, with the version in the top bits,
+// which are the same for the compared uuids.
+fn uuid_timestamp(uuid: &Uuid) -> u64 {
+    let msb = (uuid.as_u128() >> 64) as u64;
+    (msb << 48) | ((msb << 16) & 0xFFFF_0000_0000) | (msb >> 32)
+}
+
+fn uuid_lsb(uuid: &Uuid) -> [u8; 8] {
+    let mut lsb = [0; 8];
+    lsb.copy_from_slice(&uuid.as_bytes()[8..]);
+    lsb
+}
+
+fn cmp_timeuuids(a: &Uuid, b: &Uuid) -> Ordering {
+    uuid_timestamp(a).cmp(&uuid_timestamp(b)).then_with(|| {
+        // The rest is compared as signed bytes, for compatibility with old versions of Cassandra
+        let signed = |uuid: &Uuid| uuid_lsb(uuid).map(|byte| byte as i8);
+        signed(a).cmp(&signed(b))
+    })
+}
+
+fn cmp_uuids(a: &Uuid, b: &Uuid) -> Ordering {
+    let version = |uuid: &Uuid| uuid.as_bytes()[6] >> 4;
+    version(a).cmp(&version(b)).then_with(|| {
+        if version(a) == 1 {
+            uuid_timestamp(a)
+                .cmp(&uuid_timestamp(b))
+                .then_with(|| uuid_lsb(a).cmp(&uuid_lsb(b)))
+        } else {
+            a.as_bytes().cmp(b.as_bytes())
+        }
+    })
+}
+
+fn mismatch(typ: &ColumnType, a: &CqlValue, b: &CqlValue) -> CqlCompareError {
+    let value = if matches_shallowly(a, typ) { b } else { a };
+    CqlCompareError::MismatchedValue {
+        value: variant_name(value),
+        typ: typ.clone(),
+    }
+}
+
+// Checks only the variant of the value, not the values nested in it
+fn matches_shallowly(value: &CqlValue, typ: &ColumnType) -> bool {
+    use ColumnType as T;
+    use CqlValue as V;
+
+    matches!(
+        (typ, value),
+        (_, V::Empty)
+            | (T::Ascii | T::Text, V::Ascii(_) | V::Text(_))
+            | (T::Blob, V::Blob(_) | V::SharedBlob(_))
+            | (T::Boolean, V::Boolean(_))
+            | (T::TinyInt, V::TinyInt(_))
+            | (T::SmallInt, V::SmallInt(_))
+            | (T::Int, V::Int(_))
+            | (T::BigInt, V::BigInt(_))
+            | (T::Counter, V::Counter(_))
+            | (T::Float, V::Float(_))
+            | (T::Double, V::Double(_))
+            | (T::Decimal, V::Decimal(_))
+            | (T::Varint, V::Varint(_))
+            | (T::Date, V::Date(_))
+            | (T::Time, V::Time(_))
+            | (T::Timestamp, V::Timestamp(_))
+            | (T::Duration, V::Duration(_))
+            | (T::Inet, V::Inet(_))
+            | (T::Timeuuid, V::Timeuuid(_))
+            | (T::Uuid, V::Uuid(_))
+            | (T::List(_) | T::Set(_), V::List(_) | V::Set(_))
+            | (T::Map(_, _), V::Map(_))
+            | (T::Tuple(_), V::Tuple(_))
+            | (T::UserDefinedType { .. }, V::UserDefinedType { .. })
+    )
+}
+
+fn variant_name(value: &CqlValue) -> &'static str {
+    match value {
+        CqlValue::Ascii(_) => "Ascii",
+        CqlValue::Boolean(_) => "Boolean",
+        CqlValue::Blob(_) => "Blob",
+        CqlValue::SharedBlob(_) => "SharedBlob",
+        CqlValue::Counter(_) => "Counter",
+        CqlValue::Decimal(_) => "Decimal",
+        CqlValue::Date(_) => "Date",
+        CqlValue::Double(_) => "Double",
+        CqlValue::Duration(_) => "Duration",
+        CqlValue::Empty => "Empty",
+        CqlValue::Float(_) => "Float",
+        CqlValue::Int(_) => "Int",
+        CqlValue::BigInt(_) => "BigInt",
+        CqlValue::Text(_) => "Text",
+        CqlValue::Timestamp(_) => "Timestamp",
+        CqlValue::Inet(_) => "Inet",
+        CqlValue::List(_) => "List",
+        CqlValue::Map(_) => "Map",
+        CqlValue::Set(_) => "Set",
+        CqlValue::UserDefinedType { .. } => "UserDefinedType",
+        CqlValue::Custom { .. } => "Custom",
+        CqlValue::SmallInt(_) => "SmallInt",
+        CqlValue::TinyInt(_) => "TinyInt",
+        CqlValue::Time(_) => "Time",
+        CqlValue::Timeuuid(_) => "Timeuuid",
+        CqlValue::Tuple(_) => "Tuple",
+        CqlValue::Uuid(_) => "Uuid",
+        CqlValue::Varint(_) => "Varint",
+        CqlValue::Unknown { .. } => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use std::net::IpAddr;
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+    use num_bigint::BigInt;
+    use uuid::Uuid;
+
+    use super::{ClusteringOrder, CqlCompareError, SortOrder};
+    use crate::frame::response::result::{ColumnType, CqlValue};
+    use crate::frame::value::{CqlDate, CqlDuration, CqlTime, CqlTimestamp};
+
+    // Fixtures below list values in the order in which the database returns them
+    // as values of a clustering column of the type, in ascending order
+
+    // Checks the order of every pair of the values, which must be distinct
+    fn assert_ascending(typ: &ColumnType, values: &[CqlValue]) {
+        for (i, a) in values.iter().enumerate() {
+            for (j, b) in values.iter().enumerate() {
+                assert_eq!(a.cmp_cql(b, typ), Ok(i.cmp(&j)), "{:?} vs {:?}", a, b);
+            }
+        }
+    }
+
+    fn timeuuid(timestamp: u64, lsb: [u8; 8]) -> Uuid {
+        let time_low = timestamp & 0xFFFF_FFFF;
+        let time_mid = (timestamp >> 32) & 0xFFFF;
+        let time_hi_and_version = ((timestamp >> 48) & 0x0FFF) | 0x1000;
+        let msb = (time_low << 32) | (time_mid << 16) | time_hi_and_version;
+        Uuid::from_u64_pair(msb, u64::from_be_bytes(lsb))
+    }
+
+    #[test]
+    fn integers() {
+        assert_ascending(
+            &ColumnType::TinyInt,
+            &[i8::MIN, -1, 0, 1, i8::MAX].map(CqlValue::TinyInt),
+        );
+        assert_ascending(
+            &ColumnType::SmallInt,
+            &[i16::MIN, -1, 0, 1, i16::MAX].map(CqlValue::SmallInt),
+        );
+        assert_ascending(
+            &ColumnType::Int,
+            &[i32::MIN, -1, 0, 1, i32::MAX].map(CqlValue::Int),
+        );
+        assert_ascending(
+            &ColumnType::BigInt,
+            &[i64::MIN, -1, 0, 1, i64::MAX].map(CqlValue::BigInt),
+        );
+        let big = BigInt::from(2).pow(100);
+        assert_ascending(
+            &ColumnType::Varint,
+            &[
+                -big.clone(),
+                BigInt::from(-1),
+                BigInt::from(0),
+                BigInt::from(1),
+                big,
+            ]
+            .map(CqlValue::Varint),
+        );
+        assert_ascending(&ColumnType::Boolean, &[false, true].map(CqlValue::Boolean));
+    }
+
+    #[test]
+    fn floating_point_and_decimals() {
+        assert_ascending(
+            &ColumnType::Double,
+            &[
+                f64::NEG_INFINITY,
+                -1.5,
+                -f64::MIN_POSITIVE,
+                -0.0,
+                0.0,
+                f64::MIN_POSITIVE,
+                1.5,
+                f64::INFINITY,
+                f64::NAN,
+            ]
+            .map(CqlValue::Double),
+        );
+        assert_ascending(
+            &ColumnType::Float,
+            &[
+                f32::NEG_INFINITY,
+                -1.5,
+                -0.0,
+                0.0,
+                1.5,
+                f32::INFINITY,
+                f32::NAN,
+            ]
+            .map(CqlValue::Float),
+        );
+        // NaNs are equal regardless of their sign
+        assert_eq!(
+            CqlValue::Double(-f64::NAN).cmp_cql(&CqlValue::Double(f64::NAN), &ColumnType::Double),
+            Ok(Ordering::Equal)
+        );
+
+        assert_ascending(
+            &ColumnType::Decimal,
+            &["-10.5", "-1", "0", "0.0001", "1", "1.5", "100"]
+                .map(|d| CqlValue::Decimal(BigDecimal::from_str(d).unwrap())),
+        );
+        // Decimals are compared by their values, regardless of their scales
+        assert_eq!(
+            CqlValue::Decimal(BigDecimal::from_str("1.10").unwrap()).cmp_cql(
+                &CqlValue::Decimal(BigDecimal::from_str("1.1").unwrap()),
+                &ColumnType::Decimal
+            ),
+            Ok(Ordering::Equal)
+        );
+    }
+
+    #[test]
+    fn text_and_blobs_in_byte_order() {
+        let text = ["", "A", "Z", "Zebra", "a", "apple", "b", "é", "中"];
+        assert_ascending(
+            &ColumnType::Text,
+            &text.map(|s| CqlValue::Text(s.to_string())),
+        );
+        let ascii = ["", "0", "A", "Z", "_", "a", "z", "~"];
+        assert_ascending(
+            &ColumnType::Ascii,
+            &ascii.map(|s| CqlValue::Ascii(s.to_string())),
+        );
+
+        let blobs: [&[u8]; 9] = [
+            &[],
+            &[0x00],
+            &[0x00, 0x00],
+            &[0x00, 0x01],
+            &[0x01],
+            &[0x7f],
+            &[0x80],
+            &[0xff],
+            &[0xff, 0x00],
+        ];
+        assert_ascending(
+            &ColumnType::Blob,
+            &blobs.map(|b| CqlValue::Blob(b.to_vec())),
+        );
+        // Shared blobs are compared the same way
+        assert_eq!(
+            CqlValue::SharedBlob(vec![0x80].into())
+                .cmp_cql(&CqlValue::Blob(vec![0x7f]), &ColumnType::Blob),
+            Ok(Ordering::Greater)
+        );
+    }
+
+    #[test]
+    fn dates_and_times() {
+        assert_ascending(
+            &ColumnType::Date,
+            &[0, 1 << 31, u32::MAX].map(|d| CqlValue::Date(CqlDate(d))),
+        );
+        assert_ascending(
+            &ColumnType::Time,
+            &[0, 1, 86_399_999_999_999].map(|t| CqlValue::Time(CqlTime(t))),
+        );
+        assert_ascending(
+            &ColumnType::Timestamp,
+            &[i64::MIN, -1, 0, 1, i64::MAX].map(|t| CqlValue::Timestamp(CqlTimestamp(t))),
+        );
+        assert_ascending(
+            &ColumnType::Duration,
+            &[
+                (0, 0, 0),
+                (0, 0, -1),
+                (0, 0, 1),
+                (0, 0, 1_000_000_000),
+                (0, -1, 0),
+                (0, 1, 0),
+                (1, 0, 0),
+                (100, 0, 0),
+            ]
+            .map(|(months, days, nanoseconds)| {
+                CqlValue::Duration(CqlDuration {
+                    months,
+                    days,
+                    nanoseconds,
+                })
+            }),
+        );
+    }
+
+    #[test]
+    fn timeuuids_time_first() {
+        let values = [
+            // The rest of the bytes are compared as signed bytes
+            timeuuid(1, [0x80, 0, 0, 0, 0, 0, 0, 0]),
+            timeuuid(1, [0xff, 0, 0, 0, 0, 0, 0, 0]),
+            timeuuid(1, [0x00, 0x80, 0, 0, 0, 0, 0, 0]),
+            timeuuid(1, [0x00, 0, 0, 0, 0, 0, 0, 0]),
+            timeuuid(1, [0x00, 0x00, 0, 0, 0, 0, 0, 1]),
+            timeuuid(1, [0x7f, 0, 0, 0, 0, 0, 0, 0]),
+            timeuuid(0xFFFF_FFFF, [0; 8]),
+            timeuuid(0x1_0000_0000, [0; 8]),
+            timeuuid(0x0FFF_FFFF_FFFF_FFFF, [0; 8]),
+        ];
+        // Unlike in the byte order of uuids
+        assert!(values[6] > values[7]);
+        assert_ascending(&ColumnType::Timeuuid, &values.map(CqlValue::Timeuuid));
+    }
+
+    #[test]
+    fn uuids_version_first() {
+        let values = [
+            Uuid::nil(),
+            // Time-based uuids are compared by their timestamps, then as unsigned bytes
+            timeuuid(5, [0x00, 0, 0, 0, 0, 0, 0, 0]),
+            timeuuid(5, [0x80, 0, 0, 0, 0, 0, 0, 0]),
+            timeuuid(0x1_0000_0000, [0; 8]),
+            // Other ones as unsigned bytes
+            Uuid::from_u128(0x00000000_0000_4000_8000_000000000000),
+            Uuid::from_u128(0x7fffffff_ffff_4fff_bfff_ffffffffffff),
+            Uuid::from_u128(0x80000000_0000_4000_8000_000000000000),
+            Uuid::from_u128(0xffffffff_ffff_4fff_bfff_ffffffffffff),
+            Uuid::from_u128(0x00000000_0000_5000_8000_000000000000),
+        ];
+        assert_ascending(&ColumnType::Uuid, &values.map(CqlValue::Uuid));
+    }
+
+    #[test]
+    fn inet_addresses_in_byte_order() {
+        let addrs = [
+            "0.0.0.0",
+            "::",
+            "::1",
+            "10.0.0.1",
+            "192.168.0.1",
+            "ff02::1",
+            "255.255.255.255",
+        ];
+        assert_ascending(
+            &ColumnType::Inet,
+            &addrs.map(|addr| CqlValue::Inet(IpAddr::from_str(addr).unwrap())),
+        );
+    }
+
+    #[test]
+    fn empty_values_first() {
+        assert_ascending(
+            &ColumnType::Int,
+            &[CqlValue::Empty, CqlValue::Int(i32::MIN), CqlValue::Int(0)],
+        );
+        assert_eq!(
+            CqlValue::Empty.cmp_cql(&CqlValue::Empty, &ColumnType::Uuid),
+            Ok(Ordering::Equal)
+        );
+    }
+
+    #[test]
+    fn collections_tuples_and_udts() {
+        let ints = |values: &[i32]| values.iter().copied().map(CqlValue::Int).collect();
+        let list = ColumnType::List(Box::new(ColumnType::Int));
+        let lists = [
+            CqlValue::List(ints(&[])),
+            CqlValue::List(ints(&[i32::MIN])),
+            CqlValue::List(ints(&[-1])),
+            CqlValue::List(ints(&[-1, 0])),
+            CqlValue::List(ints(&[0])),
+            CqlValue::List(ints(&[1, i32::MIN])),
+            CqlValue::List(ints(&[1, 0])),
+        ];
+        assert_ascending(&list, &lists);
+        assert_ascending(
+            &ColumnType::Set(Box::new(ColumnType::Int)),
+            &lists.map(|list| CqlValue::Set(list.into_vec().unwrap())),
+        );
+
+        let map = ColumnType::Map(Box::new(ColumnType::Int), Box::new(ColumnType::Text));
+        let entries = |entries: &[(i32, &str)]| {
+            CqlValue::Map(
+                entries
+                    .iter()
+                    .map(|(k, v)| (CqlValue::Int(*k), CqlValue::Text(v.to_string())))
+                    .collect(),
+            )
+        };
+        assert_ascending(
+            &map,
+            &[
+                entries(&[]),
+                entries(&[(1, "")]),
+                entries(&[(1, "a")]),
+                entries(&[(1, "a"), (2, "")]),
+                entries(&[(1, "b")]),
+                entries(&[(2, "")]),
+            ],
+        );
+
+        let tuple = ColumnType::Tuple(vec![ColumnType::Int, ColumnType::Text]);
+        let text = |s: &str| Some(CqlValue::Text(s.to_string()));
+        let tuples = [
+            vec![None, text("z")],
+            vec![Some(CqlValue::Int(1))],
+            vec![Some(CqlValue::Int(1)), None],
+            vec![Some(CqlValue::Int(1)), text("")],
+            vec![Some(CqlValue::Int(1)), text("a")],
+            vec![Some(CqlValue::Int(2)), None],
+        ];
+        assert_ascending(&tuple, &tuples.clone().map(CqlValue::Tuple));
+
+        let udt = ColumnType::UserDefinedType {
+            type_name: "udt".to_string(),
+            keyspace: "ks".to_string(),
+            field_types: vec![
+                ("a".to_string(), ColumnType::Int),
+                ("b".to_string(), ColumnType::Text),
+            ]
+            .into(),
+        };
+        let udts = tuples.map(|fields| CqlValue::UserDefinedType {
+            keyspace: "ks".to_string(),
+            type_name: "udt".to_string(),
+            fields: ["a", "b"]
+                .iter()
+                .map(|name| name.to_string())
+                .zip(fields)
+                .collect(),
+        });
+        assert_ascending(&udt, &udts);
+    }
+
+    #[test]
+    fn mismatched_values_are_errors() {
+        let mismatch = |value: &'static str, typ: ColumnType| {
+            Err(CqlCompareError::MismatchedValue { value, typ })
+        };
+
+        let int = CqlValue::Int(1);
+        let text = CqlValue::Text("a".to_string());
+        assert_eq!(
+            int.cmp_cql(&text, &ColumnType::Int),
+            mismatch("Text", ColumnType::Int)
+        );
+        assert_eq!(
+            int.cmp_cql(&text, &ColumnType::Text),
+            mismatch("Int", ColumnType::Text)
+        );
+        assert_eq!(
+            int.cmp_cql(&CqlValue::Empty, &ColumnType::BigInt),
+            mismatch("Int", ColumnType::BigInt)
+        );
+        assert_eq!(
+            CqlValue::Timeuuid(Uuid::nil())
+                .cmp_cql(&CqlValue::Uuid(Uuid::nil()), &ColumnType::Uuid),
+            mismatch("Timeuuid", ColumnType::Uuid)
+        );
+
+        // Nested values are checked against their own types
+        let list = ColumnType::List(Box::new(ColumnType::Int));
+        assert_eq!(
+            CqlValue::List(vec![int.clone()]).cmp_cql(&CqlValue::List(vec![text.clone()]), &list),
+            mismatch("Text", ColumnType::Int)
+        );
+        let tuple = ColumnType::Tuple(vec![ColumnType::Int]);
+        let long_tuple = CqlValue::Tuple(vec![Some(int.clone()), Some(int.clone())]);
+        assert_eq!(
+            long_tuple.cmp_cql(&CqlValue::Tuple(vec![]), &tuple),
+            mismatch("Tuple", tuple.clone())
+        );
+        assert_eq!(
+            CqlValue::Tuple(vec![None]).cmp_cql(&CqlValue::Tuple(vec![Some(text)]), &tuple),
+            mismatch("Text", ColumnType::Int)
+        );
+
+        let custom = ColumnType::Custom("com.example.Type".to_string());
+        assert_eq!(
+            CqlValue::Empty.cmp_cql(&CqlValue::Empty, &custom),
+            Err(CqlCompareError::Incomparable { typ: custom })
+        );
+    }
+
+    #[test]
+    fn clustering_order() {
+        // CLUSTERING ORDER BY (a ASC, b DESC, c ASC)
+        let order = ClusteringOrder::new(vec![
+            (ColumnType::Int, SortOrder::Ascending),
+            (ColumnType::Text, SortOrder::Descending),
+            (ColumnType::Timeuuid, SortOrder::Ascending),
+        ]);
+        let key = |a: i32, b: &str, c: u64| {
+            vec![
+                Some(CqlValue::Int(a)),
+                Some(CqlValue::Text(b.to_string())),
+                Some(CqlValue::Timeuuid(timeuuid(c, [0; 8]))),
+            ]
+        };
+        let expected = vec![
+            key(0, "b", 0x1_0000_0000),
+            key(0, "a", 0xFFFF_FFFF),
+            key(0, "a", 0x1_0000_0000),
+            key(0, "B", 1),
+            key(1, "é", 1),
+            key(1, "a", 1),
+        ];
+        let mut keys = expected.clone();
+        keys.reverse();
+        keys.swap(1, 4);
+        keys.sort_by(|a, b| order.compare(a, b).unwrap());
+        assert_eq!(keys, expected);
+
+        // Prefixes are before the keys they are prefixes of, and a reversed
+        // column reverses the order of its values, but not of the prefixes
+        let full = key(0, "a", 1);
+        assert_eq!(order.compare(&[], &full), Ok(Ordering::Less));
+        assert_eq!(order.compare(&full[..2], &full), Ok(Ordering::Less));
+        assert_eq!(
+            order.compare(&full[..2], &key(0, "b", 1)),
+            Ok(Ordering::Greater)
+        );
+        assert_eq!(order.compare(&full[..2], &full[..2]), Ok(Ordering::Equal));
+
+        let mut too_long = full.clone();
+        too_long.push(None);
+        assert_eq!(
+            order.compare(&full, &too_long),
+            Err(CqlCompareError::TooManyValues {
+                got: 4,
+                expected: 3
+            })
+        );
+        assert!(order
+            .compare(&full, &[Some(CqlValue::Text("a".to_string()))])
+            .is_err());
+    }
+}
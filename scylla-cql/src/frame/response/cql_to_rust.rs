@@ -1,6 +1,7 @@
-use super::result::{CqlValue, Row};
+use super::result::{ColumnDecodeError, ColumnSpec, CqlValue, Row};
 use crate::frame::value::{Counter, CqlDate, CqlDuration, CqlTime, CqlTimestamp};
 use bigdecimal::BigDecimal;
+use bytes::Bytes;
 use num_bigint::BigInt;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::hash::{BuildHasher, Hash};
@@ -20,6 +21,14 @@ pub enum FromRowError {
     BadCqlVal { err: FromCqlValError, column: usize },
     #[error("Wrong row size: expected {expected}, actual {actual}")]
     WrongRowSize { expected: usize, actual: usize },
+    /// Two columns of the row have the same name, e.g. because an alias
+    /// collides with a name of another column.
+    #[error("Duplicate column name {name} in the column with index {column}")]
+    DuplicateColumnName { name: String, column: usize },
+    /// The value of a column failed to decode, see
+    /// [`ColumnDecodePolicy::Lenient`](super::result::ColumnDecodePolicy::Lenient).
+    #[error(transparent)]
+    UndecodableColumn(Box<ColumnDecodeError>),
 }
 
 #[derive(Error, Clone, Debug, PartialEq, Eq)]
@@ -48,6 +57,88 @@ pub enum FromCqlValError {
 /// This trait defines a way to convert CQL Row into some rust type
 pub trait FromRow: Sized {
     fn from_row(row: Row) -> Result<Self, FromRowError>;
+
+    /// Converts a borrowed row, cloning only the values which are needed.\
+    /// By default the whole row is cloned and passed to [`from_row`](FromRow::from_row).
+    fn from_row_ref(row: &Row) -> Result<Self, FromRowError> {
+        Self::from_row(row.clone())
+    }
+}
+
+/// This trait defines a way to convert CQL Row into some rust type using
+/// the names of its columns, which are taken from the result metadata.
+pub trait FromRowNamed: Sized {
+    fn from_named_row(row: Row, col_specs: &[ColumnSpec]) -> Result<Self, FromRowError>;
+}
+
+/// Values of the remaining columns of a row, nulls included.
+///
+/// Converting a row to `Rest` captures all of its columns, and a tuple ending
+/// with `Rest` captures the columns which follow the ones converted to other
+/// elements of the tuple, so that rows with more columns than expected can be read:
+/// ```
+/// # use scylla_cql::frame::response::cql_to_rust::{FromRow, Rest};
+/// # use scylla_cql::frame::response::result::{CqlValue, Row};
+/// let row = Row {
+///     columns: vec![Some(CqlValue::Int(1)), None, Some(CqlValue::Int(3))],
+/// };
+/// let (first, Rest(rest)) = <(i32, Rest)>::from_row(row).unwrap();
+/// assert_eq!(first, 1);
+/// assert_eq!(rest, vec![None, Some(CqlValue::Int(3))]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rest(pub Vec<Option<CqlValue>>);
+
+impl FromRow for Rest {
+    fn from_row(row: Row) -> Result<Self, FromRowError> {
+        Ok(Rest(row.columns))
+    }
+}
+
+// Pairs the values of the row with the names of their columns
+fn named_columns(
+    row: Row,
+    col_specs: &[ColumnSpec],
+) -> Result<impl Iterator<Item = (usize, String, Option<CqlValue>)> + '_, FromRowError> {
+    if col_specs.len() != row.columns.len() {
+        return Err(FromRowError::WrongRowSize {
+            expected: col_specs.len(),
+            actual: row.columns.len(),
+        });
+    }
+    Ok(col_specs
+        .iter()
+        .zip(row.columns)
+        .enumerate()
+        .map(|(column, (spec, value))| (column, spec.name.clone(), value)))
+}
+
+// Maps column names to values, nulls included.
+// Duplicate names are an error rather than one value silently overwriting another.
+impl<S: BuildHasher + Default> FromRowNamed for HashMap<String, Option<CqlValue>, S> {
+    fn from_named_row(row: Row, col_specs: &[ColumnSpec]) -> Result<Self, FromRowError> {
+        let mut res = HashMap::with_capacity_and_hasher(row.columns.len(), S::default());
+        for (column, name, value) in named_columns(row, col_specs)? {
+            if res.contains_key(&name) {
+                return Err(FromRowError::DuplicateColumnName { name, column });
+            }
+            res.insert(name, value);
+        }
+        Ok(res)
+    }
+}
+
+impl FromRowNamed for BTreeMap<String, Option<CqlValue>> {
+    fn from_named_row(row: Row, col_specs: &[ColumnSpec]) -> Result<Self, FromRowError> {
+        let mut res = BTreeMap::new();
+        for (column, name, value) in named_columns(row, col_specs)? {
+            if res.contains_key(&name) {
+                return Err(FromRowError::DuplicateColumnName { name, column });
+            }
+            res.insert(name, value);
+        }
+        Ok(res)
+    }
 }
 
 // CqlValue can be converted to CqlValue
@@ -76,6 +167,27 @@ impl<T: FromCqlVal<CqlValue>> FromCqlVal<Option<CqlValue>> for Option<T> {
         }
     }
 }
+
+/// Wraps a collection, or another type with a default value, so that null is read
+/// as the default value, e.g. an empty `Vec`, instead of failing.
+///
+/// The database doesn't tell an empty collection from null: an empty collection written
+/// to a column is read back as null, which can't be converted to a `Vec<T>` or a `HashMap<K, V>`.\
+/// `NullAsEmpty` is serialized the same as the wrapped value.
+/// Fields of structs deriving `FromRow` or `FromUserType` can be annotated
+/// with `#[scylla(null_as_default)]` instead.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NullAsEmpty<T>(pub T);
+
+impl<T: FromCqlVal<CqlValue> + Default> FromCqlVal<Option<CqlValue>> for NullAsEmpty<T> {
+    fn from_cql(cql_val_opt: Option<CqlValue>) -> Result<Self, FromCqlValError> {
+        match cql_val_opt {
+            Some(cql_val) => Ok(NullAsEmpty(T::from_cql(cql_val)?)),
+            None => Ok(NullAsEmpty(T::default())),
+        }
+    }
+}
+
 /// This macro implements FromCqlVal given a type and method of CqlValue that returns this type.
 ///
 /// It can be useful in client code in case you have an extension trait for CqlValue
@@ -132,6 +244,7 @@ impl_from_cql_value_from_method!(f64, as_double); // f64::from_cql<CqlValue>
 impl_from_cql_value_from_method!(bool, as_boolean); // bool::from_cql<CqlValue>
 impl_from_cql_value_from_method!(String, into_string); // String::from_cql<CqlValue>
 impl_from_cql_value_from_method!(Vec<u8>, into_blob); // Vec<u8>::from_cql<CqlValue>
+impl_from_cql_value_from_method!(Bytes, into_blob_bytes); // Bytes::from_cql<CqlValue>
 impl_from_cql_value_from_method!(IpAddr, as_inet); // IpAddr::from_cql<CqlValue>
 impl_from_cql_value_from_method!(Uuid, as_uuid); // Uuid::from_cql<CqlValue>
 impl_from_cql_value_from_method!(BigDecimal, into_decimal); // BigDecimal::from_cql<CqlValue>
@@ -142,8 +255,10 @@ impl_from_cql_value_from_method!(CqlTimestamp, as_cql_timestamp); // CqlTimestam
 
 impl<const N: usize> FromCqlVal<CqlValue> for [u8; N] {
     fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
-        let val = cql_val.into_blob().ok_or(FromCqlValError::BadCqlType)?;
-        val.try_into().map_err(|_| FromCqlValError::BadVal)
+        let val = cql_val
+            .into_blob_bytes()
+            .ok_or(FromCqlValError::BadCqlType)?;
+        val[..].try_into().map_err(|_| FromCqlValError::BadVal)
     }
 }
 
@@ -323,6 +438,76 @@ macro_rules! impl_tuple_from_row {
                     ,)+
                 ))
             }
+
+            fn from_row_ref(row: &Row) -> Result<Self, FromRowError> {
+                let expected_len = <[()]>::len(&[$(replace_expr!(($Ti) ())),*]);
+
+                if expected_len != row.columns.len() {
+                    return Err(FromRowError::WrongRowSize {
+                        expected: expected_len,
+                        actual: row.columns.len(),
+                    });
+                }
+                let mut vals_iter = row.columns.iter().enumerate();
+
+                Ok((
+                    $(
+                        {
+                            let (col_ix, col_value) = vals_iter
+                                .next()
+                                .unwrap(); // vals_iter size is checked before this code is reached,
+                                           // so it is safe to unwrap
+
+                            $Ti::from_cql(col_value.clone())
+                                .map_err(|e| FromRowError::BadCqlVal {
+                                    err: e,
+                                    column: col_ix,
+                                })?
+                        }
+                    ,)+
+                ))
+            }
+        }
+    }
+}
+
+// This macro implements FromRow for tuple of types that have FromCqlVal followed by Rest,
+// which takes the values of all the remaining columns
+macro_rules! impl_tuple_with_rest_from_row {
+    ( $($Ti:tt),+ ) => {
+        impl<$($Ti),+> FromRow for ($($Ti,)+ Rest)
+        where
+            $($Ti: FromCqlVal<Option<CqlValue>>),+
+        {
+            fn from_row(row: Row) -> Result<Self, FromRowError> {
+                let expected_len = <[()]>::len(&[$(replace_expr!(($Ti) ())),*]);
+
+                if expected_len > row.columns.len() {
+                    return Err(FromRowError::WrongRowSize {
+                        expected: expected_len,
+                        actual: row.columns.len(),
+                    });
+                }
+                let mut vals_iter = row.columns.into_iter().enumerate();
+
+                Ok((
+                    $(
+                        {
+                            let (col_ix, col_value) = vals_iter
+                                .next()
+                                .unwrap(); // vals_iter size is checked before this code is reached,
+                                           // so it is safe to unwrap
+
+                            $Ti::from_cql(col_value)
+                                .map_err(|e| FromRowError::BadCqlVal {
+                                    err: e,
+                                    column: col_ix,
+                                })?
+                        }
+                    ,)+
+                    Rest(vals_iter.map(|(_, col_value)| col_value).collect()),
+                ))
+            }
         }
     }
 }
@@ -345,6 +530,23 @@ impl_tuple_from_row!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14
 impl_tuple_from_row!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15);
 impl_tuple_from_row!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16);
 
+// Implement FromRow for tuples of size up to 16 ending with Rest
+impl_tuple_with_rest_from_row!(T1);
+impl_tuple_with_rest_from_row!(T1, T2);
+impl_tuple_with_rest_from_row!(T1, T2, T3);
+impl_tuple_with_rest_from_row!(T1, T2, T3, T4);
+impl_tuple_with_rest_from_row!(T1, T2, T3, T4, T5);
+impl_tuple_with_rest_from_row!(T1, T2, T3, T4, T5, T6);
+impl_tuple_with_rest_from_row!(T1, T2, T3, T4, T5, T6, T7);
+impl_tuple_with_rest_from_row!(T1, T2, T3, T4, T5, T6, T7, T8);
+impl_tuple_with_rest_from_row!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+impl_tuple_with_rest_from_row!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+impl_tuple_with_rest_from_row!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+impl_tuple_with_rest_from_row!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+impl_tuple_with_rest_from_row!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13);
+impl_tuple_with_rest_from_row!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14);
+impl_tuple_with_rest_from_row!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15);
+
 macro_rules! impl_tuple_from_cql {
     ( $($Ti:tt),+ ) => {
         impl<$($Ti),+> FromCqlVal<CqlValue> for ($($Ti,)+)
@@ -388,13 +590,18 @@ impl_tuple_from_cql!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14
 
 #[cfg(test)]
 mod tests {
-    use super::{CqlValue, FromCqlVal, FromCqlValError, FromRow, FromRowError, Row};
+    use super::{
+        CqlValue, FromCqlVal, FromCqlValError, FromRow, FromRowError, FromRowNamed, NullAsEmpty,
+        Rest, Row,
+    };
     use crate as scylla;
+    use crate::frame::response::result::{ColumnSpec, ColumnType, TableSpec};
     use crate::frame::value::{Counter, CqlDate, CqlDuration, CqlTime, CqlTimestamp};
-    use crate::macros::FromRow;
+    use crate::macros::{FromRow, FromUserType, SerializeCql};
     use bigdecimal::BigDecimal;
+    use bytes::Bytes;
     use num_bigint::{BigInt, ToBigInt};
-    use std::collections::HashSet;
+    use std::collections::{BTreeMap, HashMap, HashSet};
     use std::net::{IpAddr, Ipv4Addr};
     use std::str::FromStr;
     use uuid::Uuid;
@@ -449,6 +656,30 @@ mod tests {
     fn u8_array_from_cql() {
         let val = [1u8; 4];
         assert_eq!(Ok(val), <[u8; 4]>::from_cql(CqlValue::Blob(val.to_vec())));
+        assert_eq!(
+            Ok(val),
+            <[u8; 4]>::from_cql(CqlValue::SharedBlob(Bytes::from_static(&[1; 4])))
+        );
+    }
+
+    #[test]
+    fn blob_from_cql() {
+        let shared = Bytes::from_static(&[1, 2, 3]);
+        let bytes = Bytes::from_cql(CqlValue::SharedBlob(shared.clone())).unwrap();
+        assert_eq!(bytes, shared);
+        assert_eq!(bytes.as_ptr(), shared.as_ptr());
+        assert_eq!(
+            Ok(Bytes::from_static(&[1, 2, 3])),
+            Bytes::from_cql(CqlValue::Blob(vec![1, 2, 3]))
+        );
+        assert_eq!(
+            Ok(vec![1, 2, 3]),
+            Vec::<u8>::from_cql(CqlValue::SharedBlob(shared))
+        );
+        assert_eq!(
+            Err(FromCqlValError::BadCqlType),
+            Bytes::from_cql(CqlValue::Int(1))
+        );
     }
 
     #[test]
@@ -808,6 +1039,34 @@ mod tests {
         assert_eq!(c, None);
     }
 
+    #[test]
+    fn tuple_from_row_ref() {
+        let row = Row {
+            columns: vec![
+                Some(CqlValue::Int(1)),
+                Some(CqlValue::Text("some_text".to_string())),
+                None,
+            ],
+        };
+
+        type RowT = (i32, Option<String>, Option<i64>);
+        assert_eq!(RowT::from_row_ref(&row), RowT::from_row(row.clone()));
+        assert_eq!(
+            <(i32, String, i64)>::from_row_ref(&row),
+            Err(FromRowError::BadCqlVal {
+                err: FromCqlValError::ValIsNull,
+                column: 2
+            })
+        );
+        assert_eq!(
+            <(i32,)>::from_row_ref(&row),
+            Err(FromRowError::WrongRowSize {
+                expected: 1,
+                actual: 3
+            })
+        );
+    }
+
     #[test]
     fn from_cql_null() {
         assert_eq!(i32::from_cql(None), Err(FromCqlValError::ValIsNull));
@@ -891,6 +1150,129 @@ mod tests {
         );
     }
 
+    #[test]
+    fn tuple_with_rest_from_row() {
+        let row = Row {
+            columns: vec![
+                Some(CqlValue::Int(1)),
+                None,
+                Some(CqlValue::Text("some_text".to_string())),
+                None,
+            ],
+        };
+
+        let (a, b, Rest(rest)) = <(i32, Option<i64>, Rest)>::from_row(row.clone()).unwrap();
+        assert_eq!(a, 1);
+        assert_eq!(b, None);
+        assert_eq!(
+            rest,
+            vec![Some(CqlValue::Text("some_text".to_string())), None]
+        );
+
+        assert_eq!(Rest::from_row(row.clone()), Ok(Rest(row.columns.clone())));
+        assert_eq!(
+            <(i32, Option<i64>, Option<String>, Option<i32>, Rest)>::from_row(row.clone()),
+            Ok((1, None, Some("some_text".to_string()), None, Rest(vec![])))
+        );
+        assert_eq!(
+            <(i32, Option<i64>, Option<String>, Option<i32>, i32, Rest)>::from_row(row),
+            Err(FromRowError::WrongRowSize {
+                expected: 5,
+                actual: 4
+            })
+        );
+    }
+
+    fn col_specs(names: &[&str]) -> Vec<ColumnSpec> {
+        names
+            .iter()
+            .map(|name| ColumnSpec {
+                table_spec: TableSpec {
+                    ks_name: "ks".to_string(),
+                    table_name: "t".to_string(),
+                },
+                name: name.to_string(),
+                typ: ColumnType::Int,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn map_from_named_row() {
+        let row = Row {
+            columns: vec![Some(CqlValue::Int(1)), None],
+        };
+        let specs = col_specs(&["a", "b"]);
+
+        let expected: Vec<(String, Option<CqlValue>)> = vec![
+            ("a".to_string(), Some(CqlValue::Int(1))),
+            ("b".to_string(), None),
+        ];
+        assert_eq!(
+            HashMap::from_named_row(row.clone(), &specs),
+            Ok(expected.iter().cloned().collect::<HashMap<_, _>>())
+        );
+        assert_eq!(
+            BTreeMap::from_named_row(row.clone(), &specs),
+            Ok(expected.into_iter().collect::<BTreeMap<_, _>>())
+        );
+
+        assert_eq!(
+            <BTreeMap<String, Option<CqlValue>>>::from_named_row(row, &specs[..1]),
+            Err(FromRowError::WrongRowSize {
+                expected: 1,
+                actual: 2
+            })
+        );
+    }
+
+    #[test]
+    fn named_row_with_duplicate_names() {
+        // SELECT a, b AS a FROM t
+        let row = Row {
+            columns: vec![Some(CqlValue::Int(1)), Some(CqlValue::Int(2))],
+        };
+        let specs = col_specs(&["a", "a"]);
+        let expected = FromRowError::DuplicateColumnName {
+            name: "a".to_string(),
+            column: 1,
+        };
+
+        assert_eq!(
+            <HashMap<String, Option<CqlValue>>>::from_named_row(row.clone(), &specs),
+            Err(expected.clone())
+        );
+        assert_eq!(
+            <BTreeMap<String, Option<CqlValue>>>::from_named_row(row, &specs),
+            Err(expected)
+        );
+    }
+
+    #[test]
+    fn wide_named_row() {
+        let names: Vec<String> = (0..1000).map(|i| format!("c{}", i)).collect();
+        let specs = col_specs(&names.iter().map(String::as_str).collect::<Vec<_>>());
+        let row = Row {
+            columns: (0..1000)
+                .map(|i| match i % 2 {
+                    0 => Some(CqlValue::Int(i)),
+                    _ => None,
+                })
+                .collect(),
+        };
+
+        let map = <HashMap<String, Option<CqlValue>>>::from_named_row(row.clone(), &specs).unwrap();
+        assert_eq!(map.len(), 1000);
+        assert_eq!(map["c998"], Some(CqlValue::Int(998)));
+        assert_eq!(map["c999"], None);
+
+        // Wider than the largest tuple
+        let (first, Rest(rest)) = <(i32, Rest)>::from_row(row).unwrap();
+        assert_eq!(first, 0);
+        assert_eq!(rest.len(), 999);
+        assert_eq!(rest.iter().filter(|value| value.is_none()).count(), 500);
+    }
+
     #[test]
     fn struct_from_row() {
         #[derive(FromRow)]
@@ -915,6 +1297,98 @@ mod tests {
         assert_eq!(my_row.c, Some(vec![1, 2]));
     }
 
+    #[test]
+    fn null_as_empty_from_cql() {
+        assert_eq!(
+            NullAsEmpty::<Vec<i32>>::from_cql(None),
+            Ok(NullAsEmpty(vec![]))
+        );
+        assert_eq!(
+            NullAsEmpty::<HashMap<String, i32>>::from_cql(None),
+            Ok(NullAsEmpty(HashMap::new()))
+        );
+        assert_eq!(
+            NullAsEmpty::<HashSet<i32>>::from_cql(None),
+            Ok(NullAsEmpty(HashSet::new()))
+        );
+        assert_eq!(
+            NullAsEmpty::<Vec<i32>>::from_cql(Some(CqlValue::List(vec![CqlValue::Int(1)]))),
+            Ok(NullAsEmpty(vec![1]))
+        );
+        assert_eq!(
+            NullAsEmpty::<Vec<i32>>::from_cql(Some(CqlValue::Int(1))),
+            Err(FromCqlValError::BadCqlType)
+        );
+    }
+
+    #[test]
+    fn struct_with_null_as_default_fields() {
+        // The attribute doesn't get in the way of serialization derives
+        #[derive(FromRow, FromUserType, SerializeCql, PartialEq, Eq, Debug)]
+        #[scylla(crate = crate)]
+        struct MyRow {
+            a: i32,
+            #[scylla(null_as_default, rename = "b")]
+            b: Vec<i32>,
+            #[scylla(null_as_default)]
+            c: HashMap<String, i32>,
+            #[scylla(null_as_default)]
+            d: String,
+        }
+
+        let row = Row {
+            columns: vec![Some(CqlValue::Int(1)), None, None, None],
+        };
+        let expected = MyRow {
+            a: 1,
+            b: vec![],
+            c: HashMap::new(),
+            d: String::new(),
+        };
+        assert_eq!(MyRow::from_row(row), Ok(expected));
+
+        let row = Row {
+            columns: vec![
+                Some(CqlValue::Int(1)),
+                Some(CqlValue::List(vec![CqlValue::Int(2)])),
+                None,
+                Some(CqlValue::Text("d".to_string())),
+            ],
+        };
+        assert_eq!(MyRow::from_row(row).unwrap().b, vec![2]);
+
+        // Fields without the attribute still reject null
+        let row = Row {
+            columns: vec![None, None, None, None],
+        };
+        assert_eq!(
+            MyRow::from_row(row),
+            Err(FromRowError::BadCqlVal {
+                err: FromCqlValError::ValIsNull,
+                column: 0
+            })
+        );
+
+        // Null and missing fields of a UDT
+        let udt = CqlValue::UserDefinedType {
+            keyspace: "ks".to_string(),
+            type_name: "t".to_string(),
+            fields: vec![
+                ("a".to_string(), Some(CqlValue::Int(1))),
+                ("b".to_string(), None),
+            ],
+        };
+        assert_eq!(
+            MyRow::from_cql(udt),
+            Ok(MyRow {
+                a: 1,
+                b: vec![],
+                c: HashMap::new(),
+                d: String::new(),
+            })
+        );
+    }
+
     #[test]
     fn struct_from_row_wrong_size() {
         #[derive(FromRow, PartialEq, Eq, Debug)]
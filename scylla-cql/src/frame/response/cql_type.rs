@@ -0,0 +1,440 @@
+//! Conversions between [`ColumnType`] and CQL type strings,
+//! as used in `system_schema` tables and in schema statements,
+//! e.g. `map<frozen<tuple<int, text>>, uuid>`.
+
+use std::borrow::Cow;
+
+use thiserror::Error;
+
+use super::result::ColumnType;
+use crate::utils::parse::{ParseErrorCause, ParseResult, ParserState};
+
+/// Error returned by [`ColumnType::from_cql_type_str`].
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("error parsing type {type_:?} at position {position}: {reason}")]
+pub struct CqlTypeParseError {
+    /// The type string which failed to parse.
+    pub type_: String,
+    /// 1-based position of the error in characters.
+    pub position: usize,
+    /// Description of the problem.
+    pub reason: String,
+}
+
+impl ColumnType {
+    /// Parses a CQL type string, e.g. `map<frozen<tuple<int, text>>, uuid>`.
+    ///
+    /// `udt_resolver` is called with the (unquoted) name of every user defined type
+    /// referenced in the string and should return its definition, i.e. a
+    /// [`ColumnType::UserDefinedType`] with field types. Parsing fails if it returns `None`.
+    ///
+    /// Custom types are expected in the form used by `system_schema`: a quoted
+    /// class name, e.g. `'org.apache.cassandra.db.marshal.DynamicCompositeType'`.
+    ///
+    /// `ColumnType` does not track frozenness, so `frozen<...>` is accepted and dropped.
+    pub fn from_cql_type_str(
+        type_: &str,
+        mut udt_resolver: impl FnMut(&str) -> Option<ColumnType>,
+    ) -> Result<ColumnType, CqlTypeParseError> {
+        let p = ParserState::new(type_).skip_white();
+        match parse_type(p, &mut udt_resolver) {
+            Err(err) => Err(CqlTypeParseError {
+                type_: type_.to_owned(),
+                position: err.calculate_position(type_).unwrap_or(0),
+                reason: err.get_cause().to_string(),
+            }),
+            Ok((_, p)) if !p.skip_white().is_at_eof() => Err(CqlTypeParseError {
+                type_: type_.to_owned(),
+                position: p.skip_white().calculate_position(type_).unwrap_or(0),
+                reason: "leftover characters".to_owned(),
+            }),
+            Ok((typ, _)) => Ok(typ),
+        }
+    }
+
+    /// Renders the type as a CQL type string, which can be used in schema statements
+    /// and parsed back with [`ColumnType::from_cql_type_str`].
+    ///
+    /// Collections, tuples and user defined types nested in other types are rendered
+    /// as `frozen<...>`, as CQL requires; the top-level type is never frozen.
+    /// User defined types are rendered by name only, quoted if necessary.
+    /// Types unknown to the driver are rendered as `unknown(<id>)`, which isn't valid CQL.
+    pub fn to_cql_string(&self) -> String {
+        let mut out = String::new();
+        write_type(self, false, &mut out);
+        out
+    }
+
+    /// Same as [`ColumnType::to_cql_string`], but doesn't allocate for native types.
+    pub fn cql_type_name(&self) -> Cow<'static, str> {
+        match native_type_name(self) {
+            Some(name) => Cow::Borrowed(name),
+            None => Cow::Owned(self.to_cql_string()),
+        }
+    }
+}
+
+type UdtResolver<'r> = dyn FnMut(&str) -> Option<ColumnType> + 'r;
+
+fn parse_type<'s>(
+    p: ParserState<'s>,
+    udt_resolver: &mut UdtResolver<'_>,
+) -> ParseResult<(ColumnType, ParserState<'s>)> {
+    if let Ok(p) = p.accept("'") {
+        let (class_name, p) = parse_quoted(p, "'")?;
+        return Ok((ColumnType::Custom(class_name), p));
+    }
+    if let Ok(p) = p.accept("\"") {
+        let (name, p) = parse_quoted(p, "\"")?;
+        return resolve_udt(p, &name, udt_resolver);
+    }
+
+    let (ident, after_ident) =
+        p.take_while(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '$');
+    if ident.is_empty() {
+        return Err(p.error(ParseErrorCause::Other("invalid cql type")));
+    }
+
+    if let Some(p) = open_parameters(after_ident) {
+        let keyword = ident.to_ascii_lowercase();
+        let (typ, p) = match keyword.as_str() {
+            "frozen" => parse_type(p, udt_resolver)?,
+            "list" => {
+                let (elem, p) = parse_type(p, udt_resolver)?;
+                (ColumnType::List(Box::new(elem)), p)
+            }
+            "set" => {
+                let (elem, p) = parse_type(p, udt_resolver)?;
+                (ColumnType::Set(Box::new(elem)), p)
+            }
+            "map" => {
+                let (key, p) = parse_type(p, udt_resolver)?;
+                let p = p.skip_white().accept(",")?.skip_white();
+                let (value, p) = parse_type(p, udt_resolver)?;
+                (ColumnType::Map(Box::new(key), Box::new(value)), p)
+            }
+            "tuple" => {
+                let mut types = Vec::new();
+                let p = p.parse_while(|p| {
+                    let (elem, p) = parse_type(p, udt_resolver)?;
+                    types.push(elem);
+                    let p = p.skip_white();
+                    match p.accept(",") {
+                        Ok(p) => Ok((true, p.skip_white())),
+                        Err(_) => Ok((false, p)),
+                    }
+                })?;
+                (ColumnType::Tuple(types), p)
+            }
+            _ => return Err(p.error(ParseErrorCause::Other("unknown parameterized type"))),
+        };
+        let p = p.skip_white().accept(">")?;
+        return Ok((typ, p));
+    }
+
+    match native_type(ident) {
+        Some(typ) => Ok((typ, after_ident)),
+        None => resolve_udt(after_ident, ident, udt_resolver),
+    }
+}
+
+/// Accepts the `<` opening type parameters, possibly surrounded by whitespace.
+fn open_parameters(p: ParserState<'_>) -> Option<ParserState<'_>> {
+    p.skip_white().accept("<").ok().map(|p| p.skip_white())
+}
+
+/// Parses the rest of a quoted string, whose opening quote was already accepted.
+/// Quotes inside are escaped by doubling them.
+fn parse_quoted<'s>(
+    mut p: ParserState<'s>,
+    quote: &'static str,
+) -> ParseResult<(String, ParserState<'s>)> {
+    let quote_char = quote.chars().next().unwrap();
+    let mut contents = String::new();
+    loop {
+        let (chunk, rest) = p.take_while(|c| c != quote_char);
+        contents.push_str(chunk);
+        let rest = rest.accept(quote)?;
+        match rest.accept(quote) {
+            Ok(rest) => {
+                contents.push(quote_char);
+                p = rest;
+            }
+            Err(_) => return Ok((contents, rest)),
+        }
+    }
+}
+
+fn resolve_udt<'s>(
+    p: ParserState<'s>,
+    name: &str,
+    udt_resolver: &mut UdtResolver<'_>,
+) -> ParseResult<(ColumnType, ParserState<'s>)> {
+    match udt_resolver(name) {
+        Some(typ) => Ok((typ, p)),
+        None => Err(p.error(ParseErrorCause::Other("unknown user defined type"))),
+    }
+}
+
+fn native_type(name: &str) -> Option<ColumnType> {
+    let typ = match name.to_ascii_lowercase().as_str() {
+        "ascii" => ColumnType::Ascii,
+        "bigint" => ColumnType::BigInt,
+        "blob" => ColumnType::Blob,
+        "boolean" => ColumnType::Boolean,
+        "counter" => ColumnType::Counter,
+        "date" => ColumnType::Date,
+        "decimal" => ColumnType::Decimal,
+        "double" => ColumnType::Double,
+        "duration" => ColumnType::Duration,
+        "float" => ColumnType::Float,
+        "inet" => ColumnType::Inet,
+        "int" => ColumnType::Int,
+        "smallint" => ColumnType::SmallInt,
+        "text" | "varchar" => ColumnType::Text,
+        "time" => ColumnType::Time,
+        "timestamp" => ColumnType::Timestamp,
+        "timeuuid" => ColumnType::Timeuuid,
+        "tinyint" => ColumnType::TinyInt,
+        "uuid" => ColumnType::Uuid,
+        "varint" => ColumnType::Varint,
+        _ => return None,
+    };
+    Some(typ)
+}
+
+fn write_type(typ: &ColumnType, nested: bool, out: &mut String) {
+    match typ {
+        ColumnType::Custom(class_name) => {
+            out.push('\'');
+            out.push_str(&class_name.replace('\'', "''"));
+            out.push('\'');
+        }
+        ColumnType::List(elem) => {
+            write_frozen(nested, out, |out| {
+                out.push_str("list<");
+                write_type(elem, true, out);
+                out.push('>');
+            });
+        }
+        ColumnType::Set(elem) => {
+            write_frozen(nested, out, |out| {
+                out.push_str("set<");
+                write_type(elem, true, out);
+                out.push('>');
+            });
+        }
+        ColumnType::Map(key, value) => {
+            write_frozen(nested, out, |out| {
+                out.push_str("map<");
+                write_type(key, true, out);
+                out.push_str(", ");
+                write_type(value, true, out);
+                out.push('>');
+            });
+        }
+        ColumnType::Tuple(types) => {
+            write_frozen(nested, out, |out| {
+                out.push_str("tuple<");
+                for (i, elem) in types.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    write_type(elem, true, out);
+                }
+                out.push('>');
+            });
+        }
+        ColumnType::UserDefinedType { type_name, .. } => {
+            write_frozen(nested, out, |out| write_identifier(type_name, out));
+        }
+        // Such a type has no name, so it's rendered in a form which fails to parse
+        ColumnType::Unknown { id, .. } => out.push_str(&format!("unknown({:#06x})", id)),
+        // All other types are native.
+        _ => out.push_str(native_type_name(typ).unwrap()),
+    }
+}
+
+fn native_type_name(typ: &ColumnType) -> Option<&'static str> {
+    let name = match typ {
+        ColumnType::Ascii => "ascii",
+        ColumnType::Boolean => "boolean",
+        ColumnType::Blob => "blob",
+        ColumnType::Counter => "counter",
+        ColumnType::Date => "date",
+        ColumnType::Decimal => "decimal",
+        ColumnType::Double => "double",
+        ColumnType::Duration => "duration",
+        ColumnType::Float => "float",
+        ColumnType::Int => "int",
+        ColumnType::BigInt => "bigint",
+        ColumnType::Text => "text",
+        ColumnType::Timestamp => "timestamp",
+        ColumnType::Inet => "inet",
+        ColumnType::SmallInt => "smallint",
+        ColumnType::TinyInt => "tinyint",
+        ColumnType::Time => "time",
+        ColumnType::Timeuuid => "timeuuid",
+        ColumnType::Uuid => "uuid",
+        ColumnType::Varint => "varint",
+        ColumnType::Custom(_)
+        | ColumnType::List(_)
+        | ColumnType::Set(_)
+        | ColumnType::Map(_, _)
+        | ColumnType::Tuple(_)
+        | ColumnType::UserDefinedType { .. }
+        | ColumnType::Unknown { .. } => return None,
+    };
+    Some(name)
+}
+
+fn write_frozen(frozen: bool, out: &mut String, write_inner: impl FnOnce(&mut String)) {
+    if frozen {
+        out.push_str("frozen<");
+        write_inner(out);
+        out.push('>');
+    } else {
+        write_inner(out);
+    }
+}
+
+/// Writes the identifier as is if CQL allows it unquoted, otherwise in double quotes.
+fn write_identifier(name: &str, out: &mut String) {
+    let is_plain = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if is_plain && native_type(name).is_none() {
+        out.push_str(name);
+    } else {
+        out.push('"');
+        out.push_str(&name.replace('"', "\"\""));
+        out.push('"');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ColumnType;
+
+    fn udt(name: &str) -> ColumnType {
+        ColumnType::UserDefinedType {
+            type_name: name.to_owned(),
+            keyspace: "ks".to_owned(),
+            field_types: vec![
+                ("street".to_owned(), ColumnType::Text),
+                ("number".to_owned(), ColumnType::Int),
+            ]
+            .into(),
+        }
+    }
+
+    fn resolver(name: &str) -> Option<ColumnType> {
+        match name {
+            "address" | "MyType" | "with\"quote" => Some(udt(name)),
+            _ => None,
+        }
+    }
+
+    fn parse(type_: &str) -> ColumnType {
+        ColumnType::from_cql_type_str(type_, resolver)
+            .unwrap_or_else(|err| panic!("failed to parse {:?}: {}", type_, err))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let corpus = [
+            "int",
+            "text",
+            "list<int>",
+            "set<frozen<list<text>>>",
+            "map<frozen<tuple<int, text>>, uuid>",
+            "map<text, frozen<map<frozen<set<bigint>>, frozen<list<frozen<tuple<date, time>>>>>>>",
+            "tuple<int, frozen<tuple<boolean, frozen<tuple<blob>>>>, frozen<list<varint>>>",
+            "address",
+            "list<frozen<address>>",
+            "map<frozen<address>, frozen<list<frozen<address>>>>",
+            "tuple<frozen<\"MyType\">, timeuuid>",
+            "\"with\"\"quote\"",
+            "'org.apache.cassandra.db.marshal.DynamicCompositeType(s=>UTF8Type)'",
+            "list<'com.example.It''sCustom'>",
+            "map<ascii, frozen<tuple<counter, decimal, double, duration, float, inet, smallint, \
+            timestamp, tinyint>>>",
+        ];
+        for type_ in corpus {
+            let parsed = parse(type_);
+            assert_eq!(parsed.to_cql_string(), type_);
+            assert_eq!(parse(&parsed.to_cql_string()), parsed);
+        }
+    }
+
+    #[test]
+    fn test_cql_type_name() {
+        use std::borrow::Cow;
+
+        assert!(matches!(
+            ColumnType::Varint.cql_type_name(),
+            Cow::Borrowed("varint")
+        ));
+        let list = ColumnType::List(Box::new(udt("address")));
+        assert_eq!(list.cql_type_name(), list.to_cql_string());
+        assert_eq!(udt("MyType").cql_type_name(), "\"MyType\"");
+    }
+
+    #[test]
+    fn test_parse_non_canonical() {
+        let cases = [
+            ("varchar", ColumnType::Text),
+            ("BIGINT", ColumnType::BigInt),
+            (
+                "frozen<list<int>>",
+                ColumnType::List(Box::new(ColumnType::Int)),
+            ),
+            (
+                " map < text ,frozen <set< int > > > ",
+                ColumnType::Map(
+                    Box::new(ColumnType::Text),
+                    Box::new(ColumnType::Set(Box::new(ColumnType::Int))),
+                ),
+            ),
+            (
+                "tuple<int,text>",
+                ColumnType::Tuple(vec![ColumnType::Int, ColumnType::Text]),
+            ),
+            ("frozen<address>", udt("address")),
+        ];
+        for (type_, expected) in cases {
+            assert_eq!(parse(type_), expected, "parsing {:?}", type_);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for type_ in [
+            "",
+            "unknown_udt",
+            "list<int",
+            "list<int>>",
+            "map<int>",
+            "tuple<>",
+            "vector<float, 3>",
+            "'unterminated",
+            "int text",
+        ] {
+            let err = ColumnType::from_cql_type_str(type_, resolver).unwrap_err();
+            assert_eq!(err.type_, type_);
+        }
+
+        let err = ColumnType::from_cql_type_str("list<unknown_udt>", resolver).unwrap_err();
+        assert_eq!(err.position, 17);
+        assert_eq!(err.reason, "unknown user defined type");
+    }
+
+    #[test]
+    fn test_udt_identifiers_are_quoted_when_needed() {
+        assert_eq!(udt("address").to_cql_string(), "address");
+        assert_eq!(udt("MyType").to_cql_string(), "\"MyType\"");
+        assert_eq!(udt("int").to_cql_string(), "\"int\"");
+        assert_eq!(udt("1st").to_cql_string(), "\"1st\"");
+    }
+}
@@ -0,0 +1,274 @@
+//! Support for custom types, e.g. `DateRangeType` or `PointType` of DataStax Enterprise,
+//! which the database identifies by the name of the Java class implementing them.
+//!
+//! Values of custom types are opaque to the driver, so they can be neither deserialized
+//! nor serialized unless a [`CustomTypeCodec`] is registered for the class name in
+//! the [global registry](CustomTypeRegistry::global). Registered codecs are used whenever
+//! a value of the type is deserialized from a result, and when a [`CqlValue`] is serialized
+//! as a bound value of the type.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+
+use super::result::CqlValue;
+use crate::frame::frame_errors::ParseError;
+use crate::types::serialize::SerializationError;
+
+/// Converts values of a custom type between their serialized form and [`CqlValue`].
+///
+/// # Example
+/// ```
+/// # use scylla_cql::frame::frame_errors::ParseError;
+/// # use scylla_cql::frame::response::custom_types::{CustomTypeCodec, CustomTypeRegistry};
+/// # use scylla_cql::frame::response::result::CqlValue;
+/// # use scylla_cql::types::serialize::SerializationError;
+/// # use std::sync::Arc;
+/// // A point serialized as two big endian doubles
+/// #[derive(Debug)]
+/// struct PointCodec;
+///
+/// impl CustomTypeCodec for PointCodec {
+///     fn deserialize(&self, class_name: &str, buf: &[u8]) -> Result<CqlValue, ParseError> {
+///         let coordinates: [u8; 16] = buf
+///             .try_into()
+///             .map_err(|_| ParseError::BadIncomingData("Invalid point".to_string()))?;
+///         let (x, y) = coordinates.split_at(8);
+///         let coordinate = |bytes: &[u8]| f64::from_be_bytes(bytes.try_into().unwrap());
+///         Ok(CqlValue::Custom {
+///             class_name: class_name.to_string(),
+///             fields: vec![
+///                 ("x".to_string(), Some(CqlValue::Double(coordinate(x)))),
+///                 ("y".to_string(), Some(CqlValue::Double(coordinate(y)))),
+///             ],
+///         })
+///     }
+///
+///     fn serialize(&self, value: &CqlValue, buf: &mut Vec<u8>) -> Result<(), SerializationError> {
+///         match value {
+///             CqlValue::Custom { fields, .. } => {
+///                 for (_, coordinate) in fields {
+///                     let coordinate = coordinate.as_ref().and_then(CqlValue::as_double);
+///                     buf.extend(coordinate.unwrap_or_default().to_be_bytes());
+///                 }
+///                 Ok(())
+///             }
+///             _ => Err(SerializationError::new(std::fmt::Error)),
+///         }
+///     }
+/// }
+///
+/// CustomTypeRegistry::global().register(
+///     "org.apache.cassandra.db.marshal.PointType",
+///     Arc::new(PointCodec),
+/// );
+/// ```
+pub trait CustomTypeCodec: Debug + Send + Sync {
+    /// Deserializes a non-null, non-empty value of the type named `class_name`.
+    ///
+    /// It can return a [`CqlValue::Custom`] with structured contents, or any other value
+    /// representing the type well, e.g. a [`CqlValue::UserDefinedType`].
+    fn deserialize(&self, class_name: &str, buf: &[u8]) -> Result<CqlValue, ParseError>;
+
+    /// Serializes a value of the type, appending it to `buf`.
+    ///
+    /// It should accept the values returned by [`deserialize`](Self::deserialize),
+    /// so that they can be written back.
+    fn serialize(&self, value: &CqlValue, buf: &mut Vec<u8>) -> Result<(), SerializationError>;
+}
+
+/// Codecs of custom types by the names of their Java classes.
+#[derive(Debug, Default)]
+pub struct CustomTypeRegistry {
+    codecs: RwLock<HashMap<String, Arc<dyn CustomTypeCodec>>>,
+}
+
+static GLOBAL_REGISTRY: Lazy<CustomTypeRegistry> = Lazy::new(CustomTypeRegistry::default);
+
+impl CustomTypeRegistry {
+    /// The registry consulted when values of custom types are deserialized and serialized.
+    /// It's shared by all sessions in the process.
+    pub fn global() -> &'static CustomTypeRegistry {
+        &GLOBAL_REGISTRY
+    }
+
+    /// Registers the codec of the type named `class_name`, e.g.
+    /// `org.apache.cassandra.db.marshal.DateRangeType`, returning the codec it replaced.
+    pub fn register(
+        &self,
+        class_name: impl Into<String>,
+        codec: Arc<dyn CustomTypeCodec>,
+    ) -> Option<Arc<dyn CustomTypeCodec>> {
+        self.codecs
+            .write()
+            .unwrap()
+            .insert(class_name.into(), codec)
+    }
+
+    /// Removes the codec of the type named `class_name`, returning it.
+    pub fn unregister(&self, class_name: &str) -> Option<Arc<dyn CustomTypeCodec>> {
+        self.codecs.write().unwrap().remove(class_name)
+    }
+
+    /// Returns the codec of the type named `class_name`, if registered.
+    pub fn get(&self, class_name: &str) -> Option<Arc<dyn CustomTypeCodec>> {
+        self.codecs.read().unwrap().get(class_name).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CustomTypeCodec, CustomTypeRegistry};
+    use crate::frame::frame_errors::ParseError;
+    use crate::frame::response::result::{self, ColumnType, CqlValue};
+    use crate::frame::types;
+    use crate::types::serialize::value::SerializeCql;
+    use crate::types::serialize::writers::CellWriter;
+    use crate::types::serialize::SerializationError;
+    use std::sync::Arc;
+
+    // A point serialized as two big endian ints
+    #[derive(Debug)]
+    struct PointCodec;
+
+    impl CustomTypeCodec for PointCodec {
+        fn deserialize(&self, class_name: &str, buf: &[u8]) -> Result<CqlValue, ParseError> {
+            if buf.len() != 8 {
+                return Err(ParseError::BadIncomingData("Invalid point".to_string()));
+            }
+            let coordinate = |bytes: &[u8]| i32::from_be_bytes(bytes.try_into().unwrap());
+            Ok(CqlValue::Custom {
+                class_name: class_name.to_string(),
+                fields: vec![
+                    ("x".to_string(), Some(CqlValue::Int(coordinate(&buf[..4])))),
+                    ("y".to_string(), Some(CqlValue::Int(coordinate(&buf[4..])))),
+                ],
+            })
+        }
+
+        fn serialize(&self, value: &CqlValue, buf: &mut Vec<u8>) -> Result<(), SerializationError> {
+            match value {
+                CqlValue::Custom { fields, .. } => {
+                    for (_, coordinate) in fields {
+                        let coordinate = coordinate.as_ref().and_then(CqlValue::as_int);
+                        buf.extend(coordinate.unwrap_or_default().to_be_bytes());
+                    }
+                    Ok(())
+                }
+                _ => Err(SerializationError::new(std::fmt::Error)),
+            }
+        }
+    }
+
+    fn point(class_name: &str, x: i32, y: i32) -> CqlValue {
+        CqlValue::Custom {
+            class_name: class_name.to_string(),
+            fields: vec![
+                ("x".to_string(), Some(CqlValue::Int(x))),
+                ("y".to_string(), Some(CqlValue::Int(y))),
+            ],
+        }
+    }
+
+    // Rows with a column of the custom type and a list of values of the custom type
+    fn custom_rows_body(class_name: &str, rows: &[(&[u8], &[&[u8]])]) -> Vec<u8> {
+        let mut body = Vec::new();
+        types::write_int(0x0002, &mut body);
+        types::write_int(0x0001, &mut body); // Global table spec
+        types::write_int(2, &mut body);
+        types::write_string("ks", &mut body).unwrap();
+        types::write_string("t", &mut body).unwrap();
+        types::write_string("p", &mut body).unwrap();
+        types::write_short(0x0000, &mut body);
+        types::write_string(class_name, &mut body).unwrap();
+        types::write_string("l", &mut body).unwrap();
+        types::write_short(0x0020, &mut body);
+        types::write_short(0x0000, &mut body);
+        types::write_string(class_name, &mut body).unwrap();
+
+        types::write_int(rows.len() as i32, &mut body);
+        for (value, list) in rows {
+            types::write_bytes(value, &mut body).unwrap();
+            let mut serialized_list = Vec::new();
+            types::write_int(list.len() as i32, &mut serialized_list);
+            for el in list.iter() {
+                types::write_bytes(el, &mut serialized_list).unwrap();
+            }
+            types::write_bytes(&serialized_list, &mut body).unwrap();
+        }
+        body
+    }
+
+    fn serialize(value: &CqlValue, typ: &ColumnType) -> Result<Vec<u8>, SerializationError> {
+        let mut buf = Vec::new();
+        value.serialize(typ, CellWriter::new(&mut buf))?;
+        Ok(buf)
+    }
+
+    #[test]
+    fn registered_custom_types_round_trip() {
+        let class_name = "com.example.RoundTripPointType";
+        let typ = ColumnType::Custom(class_name.to_string());
+        let serialized: &[u8] = &[0, 0, 0, 1, 0, 0, 0, 2];
+        let body = custom_rows_body(class_name, &[(serialized, &[serialized, &[]])]);
+
+        // Unregistered types can be neither deserialized nor serialized
+        assert!(result::deserialize(&mut &body[..]).is_err());
+        assert!(serialize(&point(class_name, 1, 2), &typ).is_err());
+
+        CustomTypeRegistry::global().register(class_name, Arc::new(PointCodec));
+        let rows = match result::deserialize(&mut &body[..]).unwrap() {
+            result::Result::Rows(rows) => rows,
+            _ => panic!("Expected rows"),
+        };
+        assert_eq!(rows.metadata.col_specs[0].typ, typ);
+        assert_eq!(
+            rows.rows[0].columns,
+            vec![
+                Some(point(class_name, 1, 2)),
+                Some(CqlValue::List(vec![
+                    point(class_name, 1, 2),
+                    CqlValue::Empty
+                ])),
+            ]
+        );
+
+        // The deserialized value is written back the same
+        let value = rows.rows[0].columns[0].as_ref().unwrap();
+        let mut expected = Vec::new();
+        types::write_bytes(serialized, &mut expected).unwrap();
+        assert_eq!(serialize(value, &typ).unwrap(), expected);
+        let list_typ = ColumnType::List(Box::new(typ.clone()));
+        let list = CqlValue::List(vec![value.clone()]);
+        assert_eq!(serialize(&list, &list_typ).unwrap()[8..], expected[..]);
+
+        // A value of a custom type can't be written as another type
+        assert!(serialize(value, &ColumnType::Blob).is_err());
+
+        assert!(CustomTypeRegistry::global()
+            .unregister(class_name)
+            .is_some());
+        assert!(result::deserialize(&mut &body[..]).is_err());
+    }
+
+    #[test]
+    fn codec_errors_are_returned() {
+        let class_name = "com.example.InvalidPointType";
+        let typ = ColumnType::Custom(class_name.to_string());
+        let registry = CustomTypeRegistry::global();
+        assert!(registry
+            .register(class_name, Arc::new(PointCodec))
+            .is_none());
+        assert!(registry
+            .register(class_name, Arc::new(PointCodec))
+            .is_some());
+
+        let body = custom_rows_body(class_name, &[(&[1, 2, 3], &[])]);
+        assert!(result::deserialize(&mut &body[..]).is_err());
+        assert!(serialize(&CqlValue::Int(1), &typ).is_err());
+
+        registry.unregister(class_name);
+    }
+}
@@ -22,7 +22,11 @@ pub enum StatusChangeEvent {
     Down(SocketAddr),
 }
 
-#[derive(Debug)]
+/// A change of the schema, by the kind of the changed object.
+///
+/// Pushed by the server as an event, and returned in the result of a statement
+/// which changed the schema, as [`SchemaChangeResult`](super::result::SchemaChangeResult).
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SchemaChangeEvent {
     KeyspaceChange {
         change_type: SchemaChangeType,
@@ -42,21 +46,25 @@ pub enum SchemaChangeEvent {
         change_type: SchemaChangeType,
         keyspace_name: String,
         function_name: String,
+        /// CQL types of the arguments, e.g. `frozen<list<int>>`,
+        /// which can be parsed with [`ColumnType::from_cql_type_str`](super::result::ColumnType::from_cql_type_str).
         arguments: Vec<String>,
     },
     AggregateChange {
         change_type: SchemaChangeType,
         keyspace_name: String,
         aggregate_name: String,
+        /// CQL types of the arguments, same as for functions.
         arguments: Vec<String>,
     },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SchemaChangeType {
     Created,
     Updated,
     Dropped,
+    /// The server sent a type of change unknown to the driver.
     Invalid,
 }
 
@@ -74,6 +82,40 @@ impl Event {
 }
 
 impl SchemaChangeEvent {
+    /// Returns whether the object was created, updated or dropped.
+    pub fn change_type(&self) -> SchemaChangeType {
+        match self {
+            Self::KeyspaceChange { change_type, .. }
+            | Self::TableChange { change_type, .. }
+            | Self::TypeChange { change_type, .. }
+            | Self::FunctionChange { change_type, .. }
+            | Self::AggregateChange { change_type, .. } => *change_type,
+        }
+    }
+
+    /// Returns the changed keyspace, or the keyspace of the changed object.
+    pub fn keyspace_name(&self) -> &str {
+        match self {
+            Self::KeyspaceChange { keyspace_name, .. }
+            | Self::TableChange { keyspace_name, .. }
+            | Self::TypeChange { keyspace_name, .. }
+            | Self::FunctionChange { keyspace_name, .. }
+            | Self::AggregateChange { keyspace_name, .. } => keyspace_name,
+        }
+    }
+
+    /// Returns the name of the changed table, type, function or aggregate,
+    /// or `None` if a keyspace was changed.
+    pub fn object_name(&self) -> Option<&str> {
+        match self {
+            Self::KeyspaceChange { .. } => None,
+            Self::TableChange { object_name, .. } => Some(object_name),
+            Self::TypeChange { type_name, .. } => Some(type_name),
+            Self::FunctionChange { function_name, .. } => Some(function_name),
+            Self::AggregateChange { aggregate_name, .. } => Some(aggregate_name),
+        }
+    }
+
     pub fn deserialize(buf: &mut &[u8]) -> Result<Self, ParseError> {
         let type_of_change_string = types::read_string(buf)?;
         let type_of_change = match type_of_change_string {
@@ -1,15 +1,24 @@
 pub mod authenticate;
+pub mod cql_ordering;
 pub mod cql_to_rust;
+pub mod cql_type;
+pub mod custom_types;
 pub mod error;
 pub mod event;
 pub mod result;
 pub mod supported;
+pub mod type_interner;
+pub mod udt_value;
 
-use crate::{errors::QueryError, frame::frame_errors::ParseError};
+use crate::errors::QueryError;
+use crate::frame::frame_errors::{FrameParseContext, ParseError, ResponseParseError};
+use crate::frame::FrameParams;
+use bytes::Bytes;
 use num_enum::TryFromPrimitive;
 
 use crate::frame::protocol_features::ProtocolFeatures;
 pub use error::Error;
+use result::{ResultMetadata, ResultParseOptions};
 pub use supported::Supported;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive)]
@@ -42,6 +51,96 @@ impl Response {
         features: &ProtocolFeatures,
         opcode: ResponseOpcode,
         buf: &mut &[u8],
+    ) -> Result<Response, ParseError> {
+        Self::deserialize_with_metadata(features, opcode, buf, None)
+    }
+
+    /// Deserializes a response to a request sent with skipped result metadata,
+    /// see [`result::deserialize_with_metadata`].
+    pub fn deserialize_with_metadata(
+        features: &ProtocolFeatures,
+        opcode: ResponseOpcode,
+        buf: &mut &[u8],
+        cached_metadata: Option<&ResultMetadata>,
+    ) -> Result<Response, ParseError> {
+        Self::deserialize_in(
+            features,
+            opcode,
+            buf,
+            None,
+            cached_metadata,
+            ResultParseOptions::default(),
+        )
+    }
+
+    /// Deserializes a response kept in a shared buffer. Blobs in the rows of a result
+    /// aren't copied, see [`result::deserialize_shared`].
+    pub fn deserialize_shared(
+        features: &ProtocolFeatures,
+        opcode: ResponseOpcode,
+        body: &Bytes,
+    ) -> Result<Response, ParseError> {
+        Self::deserialize_shared_with_metadata(features, opcode, body, None)
+    }
+
+    /// Combines [`Response::deserialize_shared`] and [`Response::deserialize_with_metadata`].
+    pub fn deserialize_shared_with_metadata(
+        features: &ProtocolFeatures,
+        opcode: ResponseOpcode,
+        body: &Bytes,
+        cached_metadata: Option<&ResultMetadata>,
+    ) -> Result<Response, ParseError> {
+        Self::deserialize_in(
+            features,
+            opcode,
+            &mut &body[..],
+            Some(body),
+            cached_metadata,
+            ResultParseOptions::default(),
+        )
+    }
+
+    /// Deserializes the body of a response frame like [`Response::deserialize_with_metadata`],
+    /// or like [`Response::deserialize_shared_with_metadata`] if `shared`.
+    ///
+    /// On failure, the error describes the frame and the offset in the body at which
+    /// parsing failed. With `include_bytes`, it also contains the bytes of the body
+    /// around the offset, see [`FrameParseContext`]. They may contain the data of the requests,
+    /// so they shouldn't be enabled where the errors are logged or reported publicly.
+    ///
+    /// Results are parsed according to `options`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn deserialize_frame(
+        features: &ProtocolFeatures,
+        params: FrameParams,
+        opcode: ResponseOpcode,
+        body: &Bytes,
+        shared: bool,
+        cached_metadata: Option<&ResultMetadata>,
+        include_bytes: bool,
+        options: ResultParseOptions,
+    ) -> Result<Response, ResponseParseError> {
+        let buf = &mut &body[..];
+        let shared_body = if shared { Some(body) } else { None };
+        Self::deserialize_in(features, opcode, buf, shared_body, cached_metadata, options).map_err(
+            |error| {
+                let offset = body.len() - buf.len();
+                ResponseParseError {
+                    error,
+                    context: FrameParseContext::new(params, opcode, body, offset, include_bytes),
+                }
+            },
+        )
+    }
+
+    // `buf` is a part of `shared_body`, if given
+    fn deserialize_in(
+        features: &ProtocolFeatures,
+        opcode: ResponseOpcode,
+        buf: &mut &[u8],
+        shared_body: Option<&Bytes>,
+        cached_metadata: Option<&ResultMetadata>,
+        options: ResultParseOptions,
     ) -> Result<Response, ParseError> {
         let response = match opcode {
             ResponseOpcode::Error => Response::Error(Error::deserialize(features, buf)?),
@@ -50,7 +149,12 @@ impl Response {
                 Response::Authenticate(authenticate::Authenticate::deserialize(buf)?)
             }
             ResponseOpcode::Supported => Response::Supported(Supported::deserialize(buf)?),
-            ResponseOpcode::Result => Response::Result(result::deserialize(buf)?),
+            ResponseOpcode::Result => Response::Result(result::deserialize_in(
+                buf,
+                shared_body,
+                cached_metadata,
+                options,
+            )?),
             ResponseOpcode::Event => Response::Event(event::Event::deserialize(buf)?),
             ResponseOpcode::AuthChallenge => {
                 Response::AuthChallenge(authenticate::AuthChallenge::deserialize(buf)?)
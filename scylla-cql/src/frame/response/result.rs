@@ -1,5 +1,7 @@
-use crate::cql_to_rust::{FromRow, FromRowError};
+use crate::cql_to_rust::{FromCqlVal, FromCqlValError, FromRow, FromRowError};
+use crate::frame::response::custom_types::CustomTypeRegistry;
 use crate::frame::response::event::SchemaChangeEvent;
+use crate::frame::response::type_interner::{TypeInterner, UdtFields};
 use crate::frame::types::vint_decode;
 use crate::frame::value::{Counter, CqlDate, CqlDuration, CqlTime, CqlTimestamp};
 use crate::frame::{frame_errors::ParseError, types};
@@ -7,12 +9,17 @@ use bigdecimal::BigDecimal;
 use byteorder::{BigEndian, ReadBytesExt};
 use bytes::{Buf, Bytes};
 use num_bigint::BigInt;
+use once_cell::sync::OnceCell;
 use std::{
+    borrow::Cow,
+    collections::HashMap,
     convert::{TryFrom, TryInto},
     net::IpAddr,
     result::Result as StdResult,
     str,
+    sync::Arc,
 };
+use thiserror::Error;
 use uuid::Uuid;
 
 #[cfg(feature = "chrono")]
@@ -35,6 +42,12 @@ pub struct SchemaChange {
     pub event: SchemaChangeEvent,
 }
 
+/// Schema change described in the result of a statement which changed the schema,
+/// e.g. `CREATE TABLE`.
+///
+/// It's the same type as the schema change events pushed by the server.
+pub type SchemaChangeResult = SchemaChangeEvent;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TableSpec {
     pub ks_name: String,
@@ -64,7 +77,8 @@ pub enum ColumnType {
     UserDefinedType {
         type_name: String,
         keyspace: String,
-        field_types: Vec<(String, ColumnType)>,
+        /// Shared by equal definitions of the type, see [`type_interner`](super::type_interner).
+        field_types: UdtFields,
     },
     SmallInt,
     TinyInt,
@@ -73,6 +87,81 @@ pub enum ColumnType {
     Tuple(Vec<ColumnType>),
     Uuid,
     Varint,
+    /// A type with an id unknown to the driver, e.g. introduced by a newer version
+    /// of the database. It's returned only with [`UnknownTypePolicy::Accept`].
+    ///
+    /// Values of the type are deserialized as raw bytes into [`CqlValue::Unknown`],
+    /// and no other value can be serialized as the type.
+    Unknown {
+        /// The id of the type in the metadata.
+        id: u16,
+        /// The type option as serialized in the metadata.
+        raw: Vec<u8>,
+    },
+}
+
+/// How types with ids unknown to the driver, e.g. introduced by a newer version
+/// of the database, are handled when the metadata of results and prepared statements is parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownTypePolicy {
+    /// Parsing the metadata fails with [`ParseError::TypeNotImplemented`],
+    /// so the request fails.
+    #[default]
+    Fail,
+
+    /// Unknown types are parsed as [`ColumnType::Unknown`]. Rows with columns of such types
+    /// can be fetched, with the values of the columns available only as raw bytes,
+    /// and statements binding them can be prepared, but values can't be serialized as them.
+    ///
+    /// Note that the format of parameters of an unknown type can't be known either,
+    /// so it's assumed that the type has no parameters. If the rest of the result can't be
+    /// parsed consistently with that, parsing fails with [`ParseError::InconsistentUnknownType`].
+    Accept,
+}
+
+/// Options of parsing results, chosen per session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResultParseOptions {
+    /// How types unknown to the driver are handled in the metadata.
+    pub unknown_type_policy: UnknownTypePolicy,
+    /// How values of columns which fail to decode are handled in the rows.
+    pub column_decode_policy: ColumnDecodePolicy,
+}
+
+/// Parses column types in the metadata of a single result,
+/// remembering the first unknown type it accepted.
+struct TypeParser {
+    policy: UnknownTypePolicy,
+    accepted_unknown: Option<u16>,
+}
+
+impl TypeParser {
+    fn new(policy: UnknownTypePolicy) -> Self {
+        Self {
+            policy,
+            accepted_unknown: None,
+        }
+    }
+
+    /// Unknown types are assumed to have no parameters. If one has them,
+    /// the bytes following its id are misinterpreted, which shows as
+    /// the rest of the result failing to parse or not ending with the body.
+    fn check_consistency<T>(
+        &self,
+        result: StdResult<T, ParseError>,
+        rest: &[u8],
+    ) -> StdResult<T, ParseError> {
+        let id = match self.accepted_unknown {
+            Some(id) => id,
+            None => return result,
+        };
+        let reason = match result {
+            Ok(_) if rest.is_empty() => return result,
+            Ok(_) => format!("{} bytes left after the result", rest.len()),
+            Err(err) => err.to_string(),
+        };
+        Err(ParseError::InconsistentUnknownType { id, reason })
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -80,6 +169,10 @@ pub enum CqlValue {
     Ascii(String),
     Boolean(bool),
     Blob(Vec<u8>),
+    /// A blob sharing the buffer of the response frame it was received in,
+    /// returned instead of [`CqlValue::Blob`] by [`deserialize_shared`].
+    /// The frame is freed when the last value referencing it is dropped.
+    SharedBlob(Bytes),
     Counter(Counter),
     Decimal(BigDecimal),
     /// Days since -5877641-06-23 i.e. 2^31 days before unix epoch
@@ -101,9 +194,18 @@ pub enum CqlValue {
     UserDefinedType {
         keyspace: String,
         type_name: String,
-        /// Order of `fields` vector must match the order of fields as defined in the UDT. The
-        /// driver does not check it by itself, so incorrect data will be written if the order is
-        /// wrong.
+        /// When serialized with [`SerializeCql`](crate::types::serialize::value::SerializeCql),
+        /// fields are matched with the fields of the UDT by name, so their order doesn't matter.
+        /// Fields missing from the vector are null, and a field present twice is an error.\
+        /// The legacy [`Value`](crate::frame::value::Value) serialization doesn't know the type,
+        /// so there the order of `fields` vector must match the order of fields as defined in the UDT.
+        /// The driver does not check it by itself, so incorrect data will be written if the order is wrong.
+        fields: Vec<(String, Option<CqlValue>)>,
+    },
+    /// A value of a custom type, decoded by a [`CustomTypeCodec`](super::custom_types::CustomTypeCodec)
+    /// registered for its class name. The codec defines the fields.
+    Custom {
+        class_name: String,
         fields: Vec<(String, Option<CqlValue>)>,
     },
     SmallInt(i16),
@@ -114,6 +216,13 @@ pub enum CqlValue {
     Tuple(Vec<Option<CqlValue>>),
     Uuid(Uuid),
     Varint(BigInt),
+    /// Raw bytes of a value of a type unknown to the driver, see [`ColumnType::Unknown`].
+    /// It can be serialized back only as the same type.
+    Unknown {
+        /// The id of the type.
+        id: u16,
+        raw: Vec<u8>,
+    },
 }
 
 impl ColumnType {
@@ -141,7 +250,53 @@ impl ColumnType {
     }
 }
 
+macro_rules! impl_from_for_cql_value {
+    ($($t:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$t> for CqlValue {
+                fn from(value: $t) -> Self {
+                    CqlValue::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_for_cql_value!(
+    bool => Boolean,
+    i8 => TinyInt,
+    i16 => SmallInt,
+    i32 => Int,
+    i64 => BigInt,
+    f32 => Float,
+    f64 => Double,
+    String => Text,
+    Vec<u8> => Blob,
+    IpAddr => Inet,
+    Uuid => Uuid,
+    BigInt => Varint,
+    BigDecimal => Decimal,
+    Counter => Counter,
+    CqlDate => Date,
+    CqlTime => Time,
+    CqlTimestamp => Timestamp,
+    CqlDuration => Duration,
+);
+
+impl From<&str> for CqlValue {
+    fn from(value: &str) -> Self {
+        CqlValue::Text(value.to_owned())
+    }
+}
+
+/// Accessors of the contents of a value.
+///
+/// `as_*` methods borrow the value, and `into_*` methods consume it, so that
+/// the contents of strings, blobs, collections and the like can be taken without cloning.
+/// Both return `None` if the value is of another type. Variants with `Copy` contents
+/// have only `as_*` methods, which return the contents by value.
 impl CqlValue {
+    /// Returns the contents of an `ascii` value.
     pub fn as_ascii(&self) -> Option<&String> {
         match self {
             Self::Ascii(s) => Some(s),
@@ -149,6 +304,15 @@ impl CqlValue {
         }
     }
 
+    /// Returns the contents of an `ascii` value, without cloning them.
+    pub fn into_ascii(self) -> Option<String> {
+        match self {
+            Self::Ascii(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns a `date` value.
     pub fn as_cql_date(&self) -> Option<CqlDate> {
         match self {
             Self::Date(d) => Some(*d),
@@ -156,16 +320,19 @@ impl CqlValue {
         }
     }
 
+    /// Returns a `date` value, or `None` if it is out of the range of [`NaiveDate`].
     #[cfg(feature = "chrono")]
     pub fn as_naive_date(&self) -> Option<NaiveDate> {
         self.as_cql_date().and_then(|date| date.try_into().ok())
     }
 
+    /// Returns a `date` value, or `None` if it is out of the range of [`time::Date`].
     #[cfg(feature = "time")]
     pub fn as_date(&self) -> Option<time::Date> {
         self.as_cql_date().and_then(|date| date.try_into().ok())
     }
 
+    /// Returns a `timestamp` value.
     pub fn as_cql_timestamp(&self) -> Option<CqlTimestamp> {
         match self {
             Self::Timestamp(i) => Some(*i),
@@ -173,16 +340,19 @@ impl CqlValue {
         }
     }
 
+    /// Returns a `timestamp` value, or `None` if it is out of the range of [`DateTime`].
     #[cfg(feature = "chrono")]
     pub fn as_datetime(&self) -> Option<DateTime<Utc>> {
         self.as_cql_timestamp().and_then(|ts| ts.try_into().ok())
     }
 
+    /// Returns a `timestamp` value, or `None` if it is out of the range of [`time::OffsetDateTime`].
     #[cfg(feature = "time")]
     pub fn as_offset_date_time(&self) -> Option<time::OffsetDateTime> {
         self.as_cql_timestamp().and_then(|ts| ts.try_into().ok())
     }
 
+    /// Returns a `time` value.
     pub fn as_cql_time(&self) -> Option<CqlTime> {
         match self {
             Self::Time(i) => Some(*i),
@@ -190,16 +360,19 @@ impl CqlValue {
         }
     }
 
+    /// Returns a `time` value, or `None` if it is not a valid [`chrono::NaiveTime`].
     #[cfg(feature = "chrono")]
     pub fn as_naive_time(&self) -> Option<chrono::NaiveTime> {
         self.as_cql_time().and_then(|ts| ts.try_into().ok())
     }
 
+    /// Returns a `time` value, or `None` if it is not a valid [`time::Time`].
     #[cfg(feature = "time")]
     pub fn as_time(&self) -> Option<time::Time> {
         self.as_cql_time().and_then(|ts| ts.try_into().ok())
     }
 
+    /// Returns a `duration` value.
     pub fn as_cql_duration(&self) -> Option<CqlDuration> {
         match self {
             Self::Duration(i) => Some(*i),
@@ -207,6 +380,7 @@ impl CqlValue {
         }
     }
 
+    /// Returns a `counter` value.
     pub fn as_counter(&self) -> Option<Counter> {
         match self {
             Self::Counter(i) => Some(*i),
@@ -214,6 +388,7 @@ impl CqlValue {
         }
     }
 
+    /// Returns a `boolean` value.
     pub fn as_boolean(&self) -> Option<bool> {
         match self {
             Self::Boolean(i) => Some(*i),
@@ -221,6 +396,7 @@ impl CqlValue {
         }
     }
 
+    /// Returns a `double` value.
     pub fn as_double(&self) -> Option<f64> {
         match self {
             Self::Double(d) => Some(*d),
@@ -228,6 +404,7 @@ impl CqlValue {
         }
     }
 
+    /// Returns a `uuid` or a `timeuuid` value.
     pub fn as_uuid(&self) -> Option<Uuid> {
         match self {
             Self::Uuid(u) => Some(*u),
@@ -236,6 +413,7 @@ impl CqlValue {
         }
     }
 
+    /// Returns a `float` value.
     pub fn as_float(&self) -> Option<f32> {
         match self {
             Self::Float(f) => Some(*f),
@@ -243,6 +421,7 @@ impl CqlValue {
         }
     }
 
+    /// Returns an `int` value.
     pub fn as_int(&self) -> Option<i32> {
         match self {
             Self::Int(i) => Some(*i),
@@ -250,6 +429,7 @@ impl CqlValue {
         }
     }
 
+    /// Returns a `bigint` value.
     pub fn as_bigint(&self) -> Option<i64> {
         match self {
             Self::BigInt(i) => Some(*i),
@@ -257,6 +437,7 @@ impl CqlValue {
         }
     }
 
+    /// Returns a `tinyint` value.
     pub fn as_tinyint(&self) -> Option<i8> {
         match self {
             Self::TinyInt(i) => Some(*i),
@@ -264,6 +445,7 @@ impl CqlValue {
         }
     }
 
+    /// Returns a `smallint` value.
     pub fn as_smallint(&self) -> Option<i16> {
         match self {
             Self::SmallInt(i) => Some(*i),
@@ -271,6 +453,8 @@ impl CqlValue {
         }
     }
 
+    /// Returns the contents of a [`CqlValue::Blob`].
+    /// See [`as_blob_slice`](Self::as_blob_slice) for one which accepts [`CqlValue::SharedBlob`] too.
     pub fn as_blob(&self) -> Option<&Vec<u8>> {
         match self {
             Self::Blob(v) => Some(v),
@@ -278,6 +462,24 @@ impl CqlValue {
         }
     }
 
+    /// Returns the raw bytes of a value of a type unknown to the driver.
+    pub fn as_unknown_raw(&self) -> Option<&[u8]> {
+        match self {
+            Self::Unknown { raw, .. } => Some(raw),
+            _ => None,
+        }
+    }
+
+    /// Returns the contents of a blob.
+    pub fn as_blob_slice(&self) -> Option<&[u8]> {
+        match self {
+            Self::Blob(v) => Some(v),
+            Self::SharedBlob(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Returns the contents of a `text` value.
     pub fn as_text(&self) -> Option<&String> {
         match self {
             Self::Text(s) => Some(s),
@@ -285,6 +487,15 @@ impl CqlValue {
         }
     }
 
+    /// Returns the contents of a `text` value, without cloning them.
+    pub fn into_text(self) -> Option<String> {
+        match self {
+            Self::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns a `timeuuid` value.
     pub fn as_timeuuid(&self) -> Option<Uuid> {
         match self {
             Self::Timeuuid(u) => Some(*u),
@@ -292,6 +503,7 @@ impl CqlValue {
         }
     }
 
+    /// Returns the contents of an `ascii` or a `text` value, without cloning them.
     pub fn into_string(self) -> Option<String> {
         match self {
             Self::Ascii(s) => Some(s),
@@ -300,13 +512,25 @@ impl CqlValue {
         }
     }
 
+    /// Returns the contents of a blob. [`CqlValue::SharedBlob`] is copied.
     pub fn into_blob(self) -> Option<Vec<u8>> {
         match self {
             Self::Blob(b) => Some(b),
+            Self::SharedBlob(b) => Some(b.to_vec()),
             _ => None,
         }
     }
 
+    /// Returns the contents of a blob without copying it.
+    pub fn into_blob_bytes(self) -> Option<Bytes> {
+        match self {
+            Self::Blob(b) => Some(b.into()),
+            Self::SharedBlob(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Returns an `inet` value.
     pub fn as_inet(&self) -> Option<IpAddr> {
         match self {
             Self::Inet(a) => Some(*a),
@@ -314,6 +538,7 @@ impl CqlValue {
         }
     }
 
+    /// Returns the elements of a `list`.
     pub fn as_list(&self) -> Option<&Vec<CqlValue>> {
         match self {
             Self::List(s) => Some(s),
@@ -321,6 +546,15 @@ impl CqlValue {
         }
     }
 
+    /// Returns the elements of a `list`, without cloning them.
+    pub fn into_list(self) -> Option<Vec<CqlValue>> {
+        match self {
+            Self::List(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of a `set`.
     pub fn as_set(&self) -> Option<&Vec<CqlValue>> {
         match self {
             Self::Set(s) => Some(s),
@@ -328,6 +562,16 @@ impl CqlValue {
         }
     }
 
+    /// Returns the elements of a `set`, without cloning them.
+    pub fn into_set(self) -> Option<Vec<CqlValue>> {
+        match self {
+            Self::Set(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the entries of a `map`, in the order in which they were received.
+    /// See [`into_typed`](Self::into_typed) to get them as a typed map.
     pub fn as_map(&self) -> Option<&Vec<(CqlValue, CqlValue)>> {
         match self {
             Self::Map(s) => Some(s),
@@ -335,6 +579,7 @@ impl CqlValue {
         }
     }
 
+    /// Returns the fields of a user defined type, in the order of their definition.
     pub fn as_udt(&self) -> Option<&Vec<(String, Option<CqlValue>)>> {
         match self {
             Self::UserDefinedType { fields, .. } => Some(fields),
@@ -342,6 +587,43 @@ impl CqlValue {
         }
     }
 
+    /// Returns the field of a user defined type with the given name.\
+    /// The outer `Option` is `None` if the value is not a user defined type or
+    /// has no such field, the inner one if the field is null.
+    pub fn as_udt_field(&self, name: &str) -> Option<Option<&CqlValue>> {
+        self.as_udt()?
+            .iter()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, value)| value.as_ref())
+    }
+
+    /// Returns the field of a user defined type with the given name, without cloning it.\
+    /// The outer `Option` is `None` if the value is not a user defined type or
+    /// has no such field, the inner one if the field is null.
+    pub fn into_udt_field(self, name: &str) -> Option<Option<CqlValue>> {
+        self.into_udt_pair_vec()?
+            .into_iter()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the elements of a `tuple`, nulls included.
+    pub fn as_tuple(&self) -> Option<&Vec<Option<CqlValue>>> {
+        match self {
+            Self::Tuple(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of a `tuple`, nulls included, without cloning them.
+    pub fn into_tuple(self) -> Option<Vec<Option<CqlValue>>> {
+        match self {
+            Self::Tuple(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of a `list` or a `set`, without cloning them.
     pub fn into_vec(self) -> Option<Vec<CqlValue>> {
         match self {
             Self::List(s) => Some(s),
@@ -350,6 +632,7 @@ impl CqlValue {
         }
     }
 
+    /// Returns the entries of a `map`, in the order in which they were received, without cloning them.
     pub fn into_pair_vec(self) -> Option<Vec<(CqlValue, CqlValue)>> {
         match self {
             Self::Map(s) => Some(s),
@@ -357,6 +640,8 @@ impl CqlValue {
         }
     }
 
+    /// Returns the fields of a user defined type, in the order of their definition,
+    /// without cloning them.
     pub fn into_udt_pair_vec(self) -> Option<Vec<(String, Option<CqlValue>)>> {
         match self {
             Self::UserDefinedType { fields, .. } => Some(fields),
@@ -364,6 +649,15 @@ impl CqlValue {
         }
     }
 
+    /// Returns a `varint` value.
+    pub fn as_varint(&self) -> Option<&BigInt> {
+        match self {
+            Self::Varint(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Returns a `varint` value, without cloning it.
     pub fn into_varint(self) -> Option<BigInt> {
         match self {
             Self::Varint(i) => Some(i),
@@ -371,15 +665,44 @@ impl CqlValue {
         }
     }
 
+    /// Returns a `decimal` value.
+    pub fn as_decimal(&self) -> Option<&BigDecimal> {
+        match self {
+            Self::Decimal(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Returns a `decimal` value, without cloning it.
     pub fn into_decimal(self) -> Option<BigDecimal> {
         match self {
             Self::Decimal(i) => Some(i),
             _ => None,
         }
     }
-    // TODO
+
+    /// Whether this is the special empty value, which some types allow in addition to their regular values.
+    pub fn is_empty_value(&self) -> bool {
+        matches!(self, Self::Empty)
+    }
+
+    /// Converts the value to the given rust type, the same way as values of rows are converted.
+    /// Unlike the accessors above, fails with an error telling why the conversion failed.
+    ///
+    /// Useful for collections, e.g. to get a `map<text, int>` as a typed map:
+    /// ```
+    /// # use scylla_cql::frame::response::result::CqlValue;
+    /// # use std::collections::HashMap;
+    /// let value = CqlValue::Map(vec![(CqlValue::Text("a".to_string()), CqlValue::Int(1))]);
+    /// let map: HashMap<String, i32> = value.into_typed().unwrap();
+    /// assert_eq!(map["a"], 1);
+    /// ```
+    pub fn into_typed<T: FromCqlVal<CqlValue>>(self) -> StdResult<T, FromCqlValError> {
+        T::from_cql(self)
+    }
 }
 
+/// Specification of a column of a result or of a bind marker of a prepared statement.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ColumnSpec {
     pub table_spec: TableSpec,
@@ -387,11 +710,153 @@ pub struct ColumnSpec {
     pub typ: ColumnType,
 }
 
-#[derive(Debug, Default)]
+impl ColumnSpec {
+    /// Name of the column, or its alias if one was given in the `SELECT` clause.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Type of the values in the column.
+    pub fn typ(&self) -> &ColumnType {
+        &self.typ
+    }
+
+    /// Keyspace and table of the column.
+    pub fn table_spec(&self) -> &TableSpec {
+        &self.table_spec
+    }
+}
+
+/// Metadata of the rows returned by a query: specifications of the columns
+/// and the paging state of the next page.
+#[derive(Debug, Default, Clone)]
 pub struct ResultMetadata {
     col_count: usize,
     pub paging_state: Option<Bytes>,
     pub col_specs: Vec<ColumnSpec>,
+    // Built on the first lookup by name
+    column_names: OnceCell<Arc<ColumnNameIndex>>,
+}
+
+impl ResultMetadata {
+    /// Creates metadata of rows with given columns.
+    pub fn new(col_specs: Vec<ColumnSpec>, paging_state: Option<Bytes>) -> Self {
+        ResultMetadata {
+            col_count: col_specs.len(),
+            paging_state,
+            col_specs,
+            column_names: OnceCell::new(),
+        }
+    }
+
+    /// Specifications of the columns, in the order of the values in the rows.
+    pub fn columns(&self) -> &[ColumnSpec] {
+        &self.col_specs
+    }
+
+    /// Number of columns in the rows.
+    pub fn col_count(&self) -> usize {
+        self.col_count
+    }
+
+    /// Whether the server returned a paging state, i.e. there may be more pages to fetch.
+    pub fn paging_state_present(&self) -> bool {
+        self.paging_state.is_some()
+    }
+
+    /// Returns the index of the column with given name, see [`ColumnNameIndex::get`]
+    /// for the details of matching the names.
+    ///
+    /// The lookup map is built once, on the first call.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.column_name_index().get(name)
+    }
+
+    /// Returns the specification of the column with given name.
+    pub fn column(&self, name: &str) -> Option<&ColumnSpec> {
+        self.column_index(name).map(|index| &self.col_specs[index])
+    }
+
+    /// Map from the names of the columns to their indexes, built on the first call.
+    pub fn column_name_index(&self) -> &Arc<ColumnNameIndex> {
+        self.column_names
+            .get_or_init(|| Arc::new(ColumnNameIndex::new(&self.col_specs)))
+    }
+
+    /// Reuses the map from column names built for `previous` metadata, if it describes
+    /// the same columns. Pages of a single query return the same columns, so the map
+    /// only has to be built once for all of them.
+    pub fn share_column_name_index(&mut self, previous: &ResultMetadata) {
+        if let Some(index) = previous.column_names.get() {
+            if self.column_names.get().is_none() && self.col_specs == previous.col_specs {
+                self.column_names = OnceCell::with_value(index.clone());
+            }
+        }
+    }
+}
+
+/// Map from names of columns to their indexes in the rows.
+///
+/// Column names are case-sensitive in the results, so that a column created or aliased
+/// as `"MixedCase"` is returned under this exact name, while an unquoted `MixedCase`
+/// is returned as `mixedcase`. Names are matched accordingly:
+/// - a name in double quotes, like `"MixedCase"`, matches only the exact name,
+/// - any other name matches the exact name, or the name folded to lowercase,
+///   just like an unquoted identifier in a CQL statement.
+///
+/// If several columns have the same name, the first one is found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnNameIndex {
+    indexes: HashMap<String, usize>,
+}
+
+impl ColumnNameIndex {
+    pub fn new(col_specs: &[ColumnSpec]) -> Self {
+        let mut indexes = HashMap::with_capacity(col_specs.len());
+        for (index, spec) in col_specs.iter().enumerate() {
+            indexes.entry(spec.name.clone()).or_insert(index);
+        }
+        ColumnNameIndex { indexes }
+    }
+
+    /// Returns the index of the column with given name.
+    pub fn get(&self, name: &str) -> Option<usize> {
+        let (exact, folded) = Self::lookup_names(name);
+        self.indexes
+            .get(exact.as_ref())
+            .or_else(|| folded.and_then(|folded| self.indexes.get(&folded)))
+            .copied()
+    }
+
+    /// Finds the column with given name without building the map,
+    /// matching the names in the same way as [`get`](Self::get).
+    pub fn find(col_specs: &[ColumnSpec], name: &str) -> Option<usize> {
+        let (exact, folded) = Self::lookup_names(name);
+        let position = |name: &str| col_specs.iter().position(|spec| spec.name == name);
+        position(&exact).or_else(|| folded.and_then(|folded| position(&folded)))
+    }
+
+    // Name matched exactly and, for unquoted names, the name folded to lowercase
+    fn lookup_names(name: &str) -> (Cow<'_, str>, Option<String>) {
+        match name
+            .strip_prefix('"')
+            .and_then(|name| name.strip_suffix('"'))
+        {
+            Some(quoted) => (Cow::Owned(quoted.replace("\"\"", "\"")), None),
+            None => {
+                let folded = name.to_lowercase();
+                (Cow::Borrowed(name), (folded != name).then_some(folded))
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.indexes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indexes.is_empty()
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -412,12 +877,94 @@ pub struct PreparedMetadata {
     pub col_specs: Vec<ColumnSpec>,
 }
 
-#[derive(Debug, Default, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Row {
     pub columns: Vec<Option<CqlValue>>,
 }
 
+/// A value of a column failed to decode, see [`ColumnDecodePolicy::Lenient`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error(
+    "Failed to decode {raw_len} bytes of the column {column_name} (index {index}) of type {} in the row {row}: {reason}",
+    column_type.cql_type_name()
+)]
+pub struct ColumnDecodeError {
+    /// Index of the row in the result, or page of results.
+    pub row: usize,
+    /// Index of the column in the row.
+    pub index: usize,
+    /// Name of the column.
+    pub column_name: String,
+    /// Type of the column, as declared in the metadata of the result.
+    pub column_type: ColumnType,
+    /// Length of the serialized value, in bytes.
+    pub raw_len: usize,
+    /// Description of the problem.
+    pub reason: String,
+}
+
+/// How values of columns which fail to decode, e.g. because they are corrupted
+/// or don't match the type in the metadata, are handled when rows are parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColumnDecodePolicy {
+    /// Parsing of the rows fails, so the whole result, or page of results, is lost.
+    #[default]
+    Strict,
+
+    /// Each column is decoded independently. Values which fail to decode are left
+    /// as nulls in [`Row::columns`], and their errors are kept in [`Rows::decode_errors`].
+    /// Converting such rows to Rust types with the typed accessors of the results
+    /// still fails, with [`FromRowError::UndecodableColumn`].
+    Lenient,
+}
+
+impl ColumnDecodeError {
+    /// Returns the errors of the row with given index, out of `errors` sorted by rows,
+    /// like [`Rows::decode_errors`].
+    pub fn of_row(errors: &[ColumnDecodeError], row: usize) -> &[ColumnDecodeError] {
+        let start = errors.partition_point(|err| err.row < row);
+        let end = start + errors[start..].partition_point(|err| err.row == row);
+        &errors[start..end]
+    }
+
+    /// Returns the values of the columns of a row, or the errors of the ones which failed
+    /// to decode, out of `errors` sorted by rows, like [`Rows::decode_errors`].
+    pub fn columns_lenient(
+        errors: &[ColumnDecodeError],
+        row_index: usize,
+        row: &Row,
+    ) -> Vec<StdResult<Option<CqlValue>, ColumnDecodeError>> {
+        let mut columns: Vec<_> = row.columns.iter().cloned().map(Ok).collect();
+        for err in Self::of_row(errors, row_index) {
+            columns[err.index] = Err(err.clone());
+        }
+        columns
+    }
+
+    /// Fails with the error of the first column of the row which failed to decode, if any,
+    /// out of `errors` sorted by rows, like [`Rows::decode_errors`].
+    pub fn check_row(errors: &[ColumnDecodeError], row: usize) -> StdResult<(), FromRowError> {
+        match Self::of_row(errors, row).first() {
+            Some(err) => Err(FromRowError::UndecodableColumn(Box::new(err.clone()))),
+            None => Ok(()),
+        }
+    }
+}
+
 impl Row {
+    /// Creates a row with given values of the columns, `None` for nulls.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use scylla_cql::frame::response::result::{CqlValue, Row};
+    /// let row = Row::new(vec![Some(CqlValue::Int(1)), None]);
+    /// let (id, name): (i32, Option<String>) = row.into_typed().unwrap();
+    /// assert_eq!((id, name), (1, None));
+    /// ```
+    pub fn new(columns: Vec<Option<CqlValue>>) -> Self {
+        Row { columns }
+    }
+
     /// Allows converting Row into tuple of rust types or custom struct deriving FromRow
     pub fn into_typed<RowT: FromRow>(self) -> StdResult<RowT, FromRowError> {
         RowT::from_row(self)
@@ -429,8 +976,69 @@ pub struct Rows {
     pub metadata: ResultMetadata,
     pub rows_count: usize,
     pub rows: Vec<Row>,
+    /// Errors of the columns which failed to decode, sorted by rows and columns.
+    /// Always empty unless the rows were decoded with [`ColumnDecodePolicy::Lenient`].
+    pub decode_errors: Vec<ColumnDecodeError>,
     /// Original size of the serialized rows.
     pub serialized_size: usize,
+    /// Serialized rows, present if the result was deserialized with [`deserialize_shared`].
+    pub raw_rows: Option<RawRows>,
+}
+
+/// Serialized rows of a result, sharing the buffer of the response frame.
+///
+/// Boundaries of the cells are recorded when the rows are parsed, so contents
+/// of any cell can be accessed without parsing the rows again.
+#[derive(Debug, Clone)]
+pub struct RawRows {
+    /// Serialized rows, without the preceding row count.
+    contents: Bytes,
+    rows_count: usize,
+    col_count: usize,
+    /// Offsets of the cells in `contents`, row after row.
+    cell_offsets: Vec<usize>,
+}
+
+impl Rows {
+    /// Returns the values of the columns of the row with given index, or the errors
+    /// of the ones which failed to decode with [`ColumnDecodePolicy::Lenient`].
+    pub fn columns_lenient(
+        &self,
+        row: usize,
+    ) -> Vec<StdResult<Option<CqlValue>, ColumnDecodeError>> {
+        ColumnDecodeError::columns_lenient(&self.decode_errors, row, &self.rows[row])
+    }
+}
+
+impl RawRows {
+    pub fn rows_count(&self) -> usize {
+        self.rows_count
+    }
+
+    pub fn col_count(&self) -> usize {
+        self.col_count
+    }
+
+    /// Serialized rows, without the preceding row count.
+    pub fn contents(&self) -> &Bytes {
+        &self.contents
+    }
+
+    /// Returns the serialized value of a cell, without copying it,
+    /// or `None` if the cell is null.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the row or the column is out of range.
+    pub fn cell(&self, row: usize, col: usize) -> Option<Bytes> {
+        assert!(row < self.rows_count && col < self.col_count);
+        let offset = self.cell_offsets[row * self.col_count + col];
+        let mut buf = &self.contents[offset..];
+        // The cell was already parsed successfully
+        types::read_bytes_opt(&mut buf)
+            .unwrap()
+            .map(|value| self.contents.slice_ref(value))
+    }
 }
 
 #[derive(Debug)]
@@ -451,7 +1059,7 @@ fn deser_table_spec(buf: &mut &[u8]) -> StdResult<TableSpec, ParseError> {
     })
 }
 
-fn deser_type(buf: &mut &[u8]) -> StdResult<ColumnType, ParseError> {
+fn deser_type(buf: &mut &[u8], parser: &mut TypeParser) -> StdResult<ColumnType, ParseError> {
     use ColumnType::*;
     let id = types::read_short(buf)?;
     Ok(match id {
@@ -482,9 +1090,12 @@ fn deser_type(buf: &mut &[u8]) -> StdResult<ColumnType, ParseError> {
         0x0013 => SmallInt,
         0x0014 => TinyInt,
         0x0015 => Duration,
-        0x0020 => List(Box::new(deser_type(buf)?)),
-        0x0021 => Map(Box::new(deser_type(buf)?), Box::new(deser_type(buf)?)),
-        0x0022 => Set(Box::new(deser_type(buf)?)),
+        0x0020 => List(Box::new(deser_type(buf, parser)?)),
+        0x0021 => Map(
+            Box::new(deser_type(buf, parser)?),
+            Box::new(deser_type(buf, parser)?),
+        ),
+        0x0022 => Set(Box::new(deser_type(buf, parser)?)),
         0x0030 => {
             let keyspace_name: String = types::read_string(buf)?.to_string();
             let type_name: String = types::read_string(buf)?.to_string();
@@ -494,11 +1105,13 @@ fn deser_type(buf: &mut &[u8]) -> StdResult<ColumnType, ParseError> {
 
             for _ in 0..fields_size {
                 let field_name: String = types::read_string(buf)?.to_string();
-                let field_type: ColumnType = deser_type(buf)?;
+                let field_type: ColumnType = deser_type(buf, parser)?;
 
                 field_types.push((field_name, field_type));
             }
 
+            let field_types =
+                TypeInterner::global().intern_udt(&keyspace_name, &type_name, field_types);
             UserDefinedType {
                 type_name,
                 keyspace: keyspace_name,
@@ -509,14 +1122,20 @@ fn deser_type(buf: &mut &[u8]) -> StdResult<ColumnType, ParseError> {
             let len: usize = types::read_short(buf)?.into();
             let mut types = Vec::with_capacity(len);
             for _ in 0..len {
-                types.push(deser_type(buf)?);
+                types.push(deser_type(buf, parser)?);
             }
             Tuple(types)
         }
-        id => {
-            // TODO implement other types
-            return Err(ParseError::TypeNotImplemented(id));
-        }
+        id => match parser.policy {
+            UnknownTypePolicy::Accept => {
+                parser.accepted_unknown.get_or_insert(id);
+                Unknown {
+                    id,
+                    raw: id.to_be_bytes().to_vec(),
+                }
+            }
+            UnknownTypePolicy::Fail => return Err(ParseError::TypeNotImplemented(id)),
+        },
     })
 }
 
@@ -524,6 +1143,7 @@ fn deser_col_specs(
     buf: &mut &[u8],
     global_table_spec: &Option<TableSpec>,
     col_count: usize,
+    parser: &mut TypeParser,
 ) -> StdResult<Vec<ColumnSpec>, ParseError> {
     let mut col_specs = Vec::with_capacity(col_count);
     for _ in 0..col_count {
@@ -533,7 +1153,7 @@ fn deser_col_specs(
             deser_table_spec(buf)?
         };
         let name = types::read_string(buf)?.to_owned();
-        let typ = deser_type(buf)?;
+        let typ = deser_type(buf, parser)?;
         col_specs.push(ColumnSpec {
             table_spec,
             name,
@@ -543,7 +1163,10 @@ fn deser_col_specs(
     Ok(col_specs)
 }
 
-fn deser_result_metadata(buf: &mut &[u8]) -> StdResult<ResultMetadata, ParseError> {
+fn deser_result_metadata(
+    buf: &mut &[u8],
+    parser: &mut TypeParser,
+) -> StdResult<ResultMetadata, ParseError> {
     let flags = types::read_int(buf)?;
     let global_tables_spec = flags & 0x0001 != 0;
     let has_more_pages = flags & 0x0002 != 0;
@@ -562,6 +1185,7 @@ fn deser_result_metadata(buf: &mut &[u8]) -> StdResult<ResultMetadata, ParseErro
             col_count,
             paging_state,
             col_specs: vec![],
+            column_names: OnceCell::new(),
         });
     }
 
@@ -571,16 +1195,15 @@ fn deser_result_metadata(buf: &mut &[u8]) -> StdResult<ResultMetadata, ParseErro
         None
     };
 
-    let col_specs = deser_col_specs(buf, &global_table_spec, col_count)?;
+    let col_specs = deser_col_specs(buf, &global_table_spec, col_count, parser)?;
 
-    Ok(ResultMetadata {
-        col_count,
-        paging_state,
-        col_specs,
-    })
+    Ok(ResultMetadata::new(col_specs, paging_state))
 }
 
-fn deser_prepared_metadata(buf: &mut &[u8]) -> StdResult<PreparedMetadata, ParseError> {
+fn deser_prepared_metadata(
+    buf: &mut &[u8],
+    parser: &mut TypeParser,
+) -> StdResult<PreparedMetadata, ParseError> {
     let flags = types::read_int(buf)?;
     let global_tables_spec = flags & 0x0001 != 0;
 
@@ -603,7 +1226,7 @@ fn deser_prepared_metadata(buf: &mut &[u8]) -> StdResult<PreparedMetadata, Parse
         None
     };
 
-    let col_specs = deser_col_specs(buf, &global_table_spec, col_count)?;
+    let col_specs = deser_col_specs(buf, &global_table_spec, col_count, parser)?;
 
     Ok(PreparedMetadata {
         flags,
@@ -614,11 +1237,21 @@ fn deser_prepared_metadata(buf: &mut &[u8]) -> StdResult<PreparedMetadata, Parse
 }
 
 pub fn deser_cql_value(typ: &ColumnType, buf: &mut &[u8]) -> StdResult<CqlValue, ParseError> {
+    deser_cql_value_in(typ, buf, None)
+}
+
+/// Deserializes a value from `buf`. If `frame` is given, `buf` must be its part,
+/// and blobs are returned as [`CqlValue::SharedBlob`] slices of it.
+fn deser_cql_value_in(
+    typ: &ColumnType,
+    buf: &mut &[u8],
+    frame: Option<&Bytes>,
+) -> StdResult<CqlValue, ParseError> {
     use ColumnType::*;
 
     if buf.is_empty() {
         match typ {
-            Ascii | Blob | Text => {
+            Ascii | Blob | Text | Unknown { .. } => {
                 // can't be empty
             }
             _ => return Ok(CqlValue::Empty),
@@ -626,11 +1259,23 @@ pub fn deser_cql_value(typ: &ColumnType, buf: &mut &[u8]) -> StdResult<CqlValue,
     }
 
     Ok(match typ {
-        Custom(type_str) => {
-            return Err(ParseError::BadIncomingData(format!(
-                "Support for custom types is not yet implemented: {}",
-                type_str
-            )));
+        Custom(type_str) => match CustomTypeRegistry::global().get(type_str) {
+            Some(codec) => {
+                let value = codec.deserialize(type_str, buf)?;
+                buf.advance(buf.len());
+                value
+            }
+            None => {
+                return Err(ParseError::BadIncomingData(format!(
+                    "Support for custom types is not yet implemented: {}",
+                    type_str
+                )));
+            }
+        },
+        Unknown { id, .. } => {
+            let raw = buf.to_vec();
+            buf.advance(buf.len());
+            CqlValue::Unknown { id: *id, raw }
         }
         Ascii => {
             if !buf.is_ascii() {
@@ -649,7 +1294,10 @@ pub fn deser_cql_value(typ: &ColumnType, buf: &mut &[u8]) -> StdResult<CqlValue,
             }
             CqlValue::Boolean(buf[0] != 0x00)
         }
-        Blob => CqlValue::Blob(buf.to_vec()),
+        Blob => match frame {
+            Some(frame) => CqlValue::SharedBlob(frame.slice_ref(buf)),
+            None => CqlValue::Blob(buf.to_vec()),
+        },
         Date => {
             if buf.len() != 4 {
                 return Err(ParseError::BadIncomingData(format!(
@@ -817,7 +1465,7 @@ pub fn deser_cql_value(typ: &ColumnType, buf: &mut &[u8]) -> StdResult<CqlValue,
             let mut res = Vec::with_capacity(len);
             for _ in 0..len {
                 let mut b = types::read_bytes(buf)?;
-                res.push(deser_cql_value(type_name, &mut b)?);
+                res.push(deser_cql_value_in(type_name, &mut b, frame)?);
             }
             CqlValue::List(res)
         }
@@ -826,9 +1474,9 @@ pub fn deser_cql_value(typ: &ColumnType, buf: &mut &[u8]) -> StdResult<CqlValue,
             let mut res = Vec::with_capacity(len);
             for _ in 0..len {
                 let mut b = types::read_bytes(buf)?;
-                let key = deser_cql_value(key_type, &mut b)?;
+                let key = deser_cql_value_in(key_type, &mut b, frame)?;
                 b = types::read_bytes(buf)?;
-                let val = deser_cql_value(value_type, &mut b)?;
+                let val = deser_cql_value_in(value_type, &mut b, frame)?;
                 res.push((key, val));
             }
             CqlValue::Map(res)
@@ -839,7 +1487,7 @@ pub fn deser_cql_value(typ: &ColumnType, buf: &mut &[u8]) -> StdResult<CqlValue,
             for _ in 0..len {
                 // TODO: is `null` allowed as set element? Should we use read_bytes_opt?
                 let mut b = types::read_bytes(buf)?;
-                res.push(deser_cql_value(type_name, &mut b)?);
+                res.push(deser_cql_value_in(type_name, &mut b, frame)?);
             }
             CqlValue::Set(res)
         }
@@ -850,7 +1498,7 @@ pub fn deser_cql_value(typ: &ColumnType, buf: &mut &[u8]) -> StdResult<CqlValue,
         } => {
             let mut fields: Vec<(String, Option<CqlValue>)> = Vec::new();
 
-            for (field_name, field_type) in field_types {
+            for (field_name, field_type) in field_types.iter() {
                 // If a field is added to a UDT and we read an old (frozen ?) version of it,
                 // the driver will fail to parse the whole UDT.
                 // This is why we break the parsing after we reach the end of the serialized UDT.
@@ -860,7 +1508,8 @@ pub fn deser_cql_value(typ: &ColumnType, buf: &mut &[u8]) -> StdResult<CqlValue,
 
                 let mut field_value: Option<CqlValue> = None;
                 if let Some(mut field_val_bytes) = types::read_bytes_opt(buf)? {
-                    field_value = Some(deser_cql_value(field_type, &mut field_val_bytes)?);
+                    field_value =
+                        Some(deser_cql_value_in(field_type, &mut field_val_bytes, frame)?);
                 }
 
                 fields.push((field_name.clone(), field_value));
@@ -876,7 +1525,7 @@ pub fn deser_cql_value(typ: &ColumnType, buf: &mut &[u8]) -> StdResult<CqlValue,
             let mut res = Vec::with_capacity(type_names.len());
             for type_name in type_names {
                 match types::read_bytes_opt(buf)? {
-                    Some(mut b) => res.push(Some(deser_cql_value(type_name, &mut b)?)),
+                    Some(mut b) => res.push(Some(deser_cql_value_in(type_name, &mut b, frame)?)),
                     None => res.push(None),
                 };
             }
@@ -886,25 +1535,68 @@ pub fn deser_cql_value(typ: &ColumnType, buf: &mut &[u8]) -> StdResult<CqlValue,
     })
 }
 
-fn deser_rows(buf: &mut &[u8]) -> StdResult<Rows, ParseError> {
-    let metadata = deser_result_metadata(buf)?;
+fn deser_rows(
+    buf: &mut &[u8],
+    frame: Option<&Bytes>,
+    cached_metadata: Option<&ResultMetadata>,
+    parser: &mut TypeParser,
+    column_decode_policy: ColumnDecodePolicy,
+) -> StdResult<Rows, ParseError> {
+    let mut metadata = deser_result_metadata(buf, parser)?;
 
     let original_size = buf.len();
 
-    // TODO: the protocol allows an optimization (which must be explicitly requested on query by
-    // the driver) where the column metadata is not sent with the result.
-    // Implement this optimization. We'll then need to take the column types by a parameter.
-    // Beware of races; our column types may be outdated.
-    assert!(metadata.col_count == metadata.col_specs.len());
+    if metadata.col_count != metadata.col_specs.len() {
+        // The metadata was skipped on request, so the cached one has to be used.
+        // It may be outdated, but a changed number of columns can at least be detected.
+        match cached_metadata {
+            Some(cached) if cached.col_specs.len() == metadata.col_count => {
+                metadata.col_specs = cached.col_specs.clone();
+                metadata.share_column_name_index(cached);
+            }
+            _ => {
+                return Err(ParseError::ResultMetadataMismatch {
+                    col_count: metadata.col_count,
+                })
+            }
+        }
+    }
 
     let rows_count: usize = types::read_int(buf)?.try_into()?;
+    let rows_start = *buf;
+    let lenient = column_decode_policy == ColumnDecodePolicy::Lenient;
 
     let mut rows = Vec::with_capacity(rows_count);
-    for _ in 0..rows_count {
+    let mut cell_offsets = match frame {
+        Some(_) => Vec::with_capacity(rows_count * metadata.col_count),
+        None => Vec::new(),
+    };
+    let mut decode_errors = Vec::new();
+    for row in 0..rows_count {
         let mut columns = Vec::with_capacity(metadata.col_count);
         for i in 0..metadata.col_count {
+            if frame.is_some() {
+                cell_offsets.push(rows_start.len() - buf.len());
+            }
             let v = if let Some(mut b) = types::read_bytes_opt(buf)? {
-                Some(deser_cql_value(&metadata.col_specs[i].typ, &mut b)?)
+                let raw_len = b.len();
+                let col_spec = &metadata.col_specs[i];
+                match deser_cql_value_in(&col_spec.typ, &mut b, frame) {
+                    Ok(value) => Some(value),
+                    // The cell is framed by its length, so the following ones can still be decoded
+                    Err(err) if lenient => {
+                        decode_errors.push(ColumnDecodeError {
+                            row,
+                            index: i,
+                            column_name: col_spec.name.clone(),
+                            column_type: col_spec.typ.clone(),
+                            raw_len,
+                            reason: err.to_string(),
+                        });
+                        None
+                    }
+                    Err(err) => return Err(err),
+                }
             } else {
                 None
             };
@@ -912,11 +1604,20 @@ fn deser_rows(buf: &mut &[u8]) -> StdResult<Rows, ParseError> {
         }
         rows.push(Row { columns });
     }
+
+    let raw_rows = frame.map(|frame| RawRows {
+        contents: frame.slice_ref(&rows_start[..rows_start.len() - buf.len()]),
+        rows_count,
+        col_count: metadata.col_count,
+        cell_offsets,
+    });
     Ok(Rows {
         metadata,
         rows_count,
         rows,
+        decode_errors,
         serialized_size: original_size - buf.len(),
+        raw_rows,
     })
 }
 
@@ -926,12 +1627,12 @@ fn deser_set_keyspace(buf: &mut &[u8]) -> StdResult<SetKeyspace, ParseError> {
     Ok(SetKeyspace { keyspace_name })
 }
 
-fn deser_prepared(buf: &mut &[u8]) -> StdResult<Prepared, ParseError> {
+fn deser_prepared(buf: &mut &[u8], parser: &mut TypeParser) -> StdResult<Prepared, ParseError> {
     let id_len = types::read_short(buf)? as usize;
     let id: Bytes = buf[0..id_len].to_owned().into();
     buf.advance(id_len);
-    let prepared_metadata = deser_prepared_metadata(buf)?;
-    let result_metadata = deser_result_metadata(buf)?;
+    let prepared_metadata = deser_prepared_metadata(buf, parser)?;
+    let result_metadata = deser_result_metadata(buf, parser)?;
     Ok(Prepared {
         id,
         prepared_metadata,
@@ -947,25 +1648,82 @@ fn deser_schema_change(buf: &mut &[u8]) -> StdResult<SchemaChange, ParseError> {
 }
 
 pub fn deserialize(buf: &mut &[u8]) -> StdResult<Result, ParseError> {
+    deserialize_in(buf, None, None, ResultParseOptions::default())
+}
+
+/// Deserializes a result of a request sent with [`skip_metadata`](crate::frame::request::query::QueryParameters::skip_metadata).
+/// Rows sent without metadata are decoded using `cached_metadata`, and fail with
+/// [`ParseError::ResultMetadataMismatch`] if it is missing or has a different number of columns.
+pub fn deserialize_with_metadata(
+    buf: &mut &[u8],
+    cached_metadata: Option<&ResultMetadata>,
+) -> StdResult<Result, ParseError> {
+    deserialize_in(buf, None, cached_metadata, ResultParseOptions::default())
+}
+
+/// Deserializes a result kept in a shared buffer. Unlike [`deserialize`],
+/// it doesn't copy blobs in the rows, which are returned as [`CqlValue::SharedBlob`]
+/// slices of `body` instead, and keeps the serialized rows in [`Rows::raw_rows`].
+/// The buffer is freed when the last value referencing it is dropped.
+pub fn deserialize_shared(body: &Bytes) -> StdResult<Result, ParseError> {
+    deserialize_in(
+        &mut &body[..],
+        Some(body),
+        None,
+        ResultParseOptions::default(),
+    )
+}
+
+/// Combines [`deserialize_shared`] and [`deserialize_with_metadata`].
+pub fn deserialize_shared_with_metadata(
+    body: &Bytes,
+    cached_metadata: Option<&ResultMetadata>,
+) -> StdResult<Result, ParseError> {
+    deserialize_in(
+        &mut &body[..],
+        Some(body),
+        cached_metadata,
+        ResultParseOptions::default(),
+    )
+}
+
+pub(crate) fn deserialize_in(
+    buf: &mut &[u8],
+    frame: Option<&Bytes>,
+    cached_metadata: Option<&ResultMetadata>,
+    options: ResultParseOptions,
+) -> StdResult<Result, ParseError> {
     use self::Result::*;
-    Ok(match types::read_int(buf)? {
-        0x0001 => Void,
-        0x0002 => Rows(deser_rows(buf)?),
-        0x0003 => SetKeyspace(deser_set_keyspace(buf)?),
-        0x0004 => Prepared(deser_prepared(buf)?),
-        0x0005 => SchemaChange(deser_schema_change(buf)?),
+    let mut parser = TypeParser::new(options.unknown_type_policy);
+    let result = match types::read_int(buf)? {
+        0x0001 => Ok(Void),
+        0x0002 => deser_rows(
+            buf,
+            frame,
+            cached_metadata,
+            &mut parser,
+            options.column_decode_policy,
+        )
+        .map(Rows),
+        0x0003 => Ok(SetKeyspace(deser_set_keyspace(buf)?)),
+        0x0004 => deser_prepared(buf, &mut parser).map(Prepared),
+        0x0005 => Ok(SchemaChange(deser_schema_change(buf)?)),
         k => {
             return Err(ParseError::BadIncomingData(format!(
                 "Unknown query result id: {}",
                 k
             )))
         }
-    })
+    };
+    parser.check_consistency(result, buf)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::SchemaChangeResult;
     use crate as scylla;
+    use crate::frame::response::event::SchemaChangeType;
+    use crate::frame::types;
     use crate::frame::value::{Counter, CqlDate, CqlDuration, CqlTime, CqlTimestamp};
     use bigdecimal::BigDecimal;
     use num_bigint::BigInt;
@@ -1705,7 +2463,7 @@ mod tests {
                 ColumnType::UserDefinedType {
                     type_name: "".to_owned(),
                     keyspace: "".to_owned(),
-                    field_types: vec![],
+                    field_types: vec![].into(),
                 },
                 CqlValue::Empty,
             ),
@@ -1760,4 +2518,925 @@ mod tests {
             }
         }
     }
+
+    // A blob and a list<blob>.
+    type BlobRow<'a> = (Option<&'a [u8]>, &'a [&'a [u8]]);
+
+    // Body of a result with rows of a blob and a list<blob> columns.
+    fn blob_rows_body(rows: &[BlobRow<'_>]) -> Vec<u8> {
+        use crate::frame::types;
+
+        let mut body = Vec::new();
+        types::write_int(0x0002, &mut body);
+        types::write_int(0x0001, &mut body); // Global table spec
+        types::write_int(2, &mut body);
+        types::write_string("ks", &mut body).unwrap();
+        types::write_string("t", &mut body).unwrap();
+        types::write_string("b", &mut body).unwrap();
+        types::write_short(0x0003, &mut body);
+        types::write_string("l", &mut body).unwrap();
+        types::write_short(0x0020, &mut body);
+        types::write_short(0x0003, &mut body);
+
+        types::write_int(rows.len() as i32, &mut body);
+        for (blob, list) in rows {
+            match blob {
+                Some(blob) => types::write_bytes(blob, &mut body).unwrap(),
+                None => types::write_int(-1, &mut body),
+            }
+            let mut serialized_list = Vec::new();
+            types::write_int(list.len() as i32, &mut serialized_list);
+            for el in list.iter() {
+                types::write_bytes(el, &mut serialized_list).unwrap();
+            }
+            types::write_bytes(&serialized_list, &mut body).unwrap();
+        }
+        body
+    }
+
+    #[test]
+    fn test_deserialize_shared() {
+        let body = bytes::Bytes::from(blob_rows_body(&[
+            (Some(&[1, 2, 3]), &[&[4], &[]]),
+            (None, &[&[5, 6]]),
+        ]));
+        let shared = match super::deserialize_shared(&body).unwrap() {
+            super::Result::Rows(rows) => rows,
+            _ => panic!("Expected rows"),
+        };
+        let copied = match super::deserialize(&mut &body[..]).unwrap() {
+            super::Result::Rows(rows) => rows,
+            _ => panic!("Expected rows"),
+        };
+        assert!(copied.raw_rows.is_none());
+
+        // Blobs are slices of the frame, equal to the copied ones
+        let in_body = |blob: &bytes::Bytes| body.as_ptr_range().contains(&blob.as_ptr());
+        assert_eq!(shared.rows.len(), 2);
+        for (shared_row, copied_row) in shared.rows.iter().zip(&copied.rows) {
+            for (shared_val, copied_val) in shared_row.columns.iter().zip(&copied_row.columns) {
+                match (shared_val, copied_val) {
+                    (Some(CqlValue::SharedBlob(shared)), Some(CqlValue::Blob(copied))) => {
+                        assert!(in_body(shared));
+                        assert_eq!(shared, copied);
+                    }
+                    (Some(CqlValue::List(shared)), Some(CqlValue::List(copied))) => {
+                        assert_eq!(shared.len(), copied.len());
+                        for (shared, copied) in shared.iter().zip(copied) {
+                            let shared = shared.clone().into_blob_bytes().unwrap();
+                            assert!(shared.is_empty() || in_body(&shared));
+                            assert_eq!(shared, copied.as_blob().unwrap());
+                        }
+                    }
+                    (None, None) => {}
+                    other => panic!("Unexpected values: {:?}", other),
+                }
+            }
+        }
+
+        let raw_rows = shared.raw_rows.unwrap();
+        assert_eq!(raw_rows.rows_count(), 2);
+        assert_eq!(raw_rows.col_count(), 2);
+        assert_eq!(raw_rows.contents().len(), copied.serialized_size - 4);
+        assert_eq!(raw_rows.cell(0, 0).unwrap(), &[1, 2, 3][..]);
+        assert_eq!(raw_rows.cell(1, 0), None);
+        assert_eq!(
+            raw_rows.cell(1, 1).unwrap(),
+            &[0, 0, 0, 1, 0, 0, 0, 2, 5, 6][..]
+        );
+    }
+
+    // Body of a prepared result binding a column of a user defined type
+    // and a list of values of the type.
+    fn udt_prepared_body(type_name: &str, field_type: u16) -> Vec<u8> {
+        fn write_udt_type(type_name: &str, field_type: u16, body: &mut Vec<u8>) {
+            types::write_short(0x0030, body);
+            types::write_string("ks", body).unwrap();
+            types::write_string(type_name, body).unwrap();
+            types::write_short(2, body);
+            types::write_string("a", body).unwrap();
+            types::write_short(0x0009, body);
+            types::write_string("b", body).unwrap();
+            types::write_short(field_type, body);
+        }
+
+        let mut body = Vec::new();
+        types::write_int(0x0004, &mut body);
+        types::write_short_bytes(&[0xca, 0xfe], &mut body).unwrap();
+        types::write_int(0x0001, &mut body); // Global table spec
+        types::write_int(2, &mut body);
+        types::write_int(0, &mut body); // No partition key columns
+        types::write_string("ks", &mut body).unwrap();
+        types::write_string("t", &mut body).unwrap();
+        types::write_string("u", &mut body).unwrap();
+        write_udt_type(type_name, field_type, &mut body);
+        types::write_string("l", &mut body).unwrap();
+        types::write_short(0x0020, &mut body);
+        write_udt_type(type_name, field_type, &mut body);
+        types::write_int(0x0004, &mut body); // No result metadata
+        types::write_int(0, &mut body);
+        body
+    }
+
+    fn udt_fields(typ: &ColumnType) -> &std::sync::Arc<[(String, ColumnType)]> {
+        match typ {
+            ColumnType::UserDefinedType { field_types, .. } => field_types,
+            ColumnType::List(elem) => udt_fields(elem),
+            _ => panic!("Not a user defined type: {:?}", typ),
+        }
+    }
+
+    #[test]
+    fn test_udt_definitions_are_shared_by_prepared_statements() {
+        use std::sync::Arc;
+
+        let prepared = |body: Vec<u8>| match super::deserialize(&mut &body[..]).unwrap() {
+            super::Result::Prepared(prepared) => prepared.prepared_metadata,
+            _ => panic!("Expected prepared"),
+        };
+        let first = prepared(udt_prepared_body("shared_udt", 0x000D));
+        let second = prepared(udt_prepared_body("shared_udt", 0x000D));
+
+        let fields = udt_fields(&first.col_specs[0].typ);
+        assert_eq!(
+            **fields,
+            [
+                ("a".to_string(), ColumnType::Int),
+                ("b".to_string(), ColumnType::Text)
+            ]
+        );
+        assert!(Arc::ptr_eq(fields, udt_fields(&first.col_specs[1].typ)));
+        assert!(Arc::ptr_eq(fields, udt_fields(&second.col_specs[0].typ)));
+        assert!(Arc::ptr_eq(fields, udt_fields(&second.col_specs[1].typ)));
+
+        // After the type is altered, the new definition isn't shared with the old one
+        let altered = prepared(udt_prepared_body("shared_udt", 0x0003));
+        let altered_fields = udt_fields(&altered.col_specs[0].typ);
+        assert!(!Arc::ptr_eq(fields, altered_fields));
+        assert_eq!(altered_fields[1], ("b".to_string(), ColumnType::Blob));
+        assert!(Arc::ptr_eq(
+            altered_fields,
+            udt_fields(&altered.col_specs[1].typ)
+        ));
+    }
+
+    #[test]
+    fn test_unknown_types_with_policies() {
+        use super::{ResultParseOptions, UnknownTypePolicy};
+        use crate::frame::frame_errors::ParseError;
+        use crate::frame::response::{Response, ResponseOpcode};
+        use crate::frame::FrameParams;
+        use crate::types::serialize::row::SerializedValues;
+
+        // A type id which isn't defined by the protocol
+        const UNKNOWN_ID: u16 = 0x00FF;
+
+        let mut rows_body = Vec::new();
+        types::write_int(0x0002, &mut rows_body);
+        types::write_int(0x0001, &mut rows_body); // Global table spec
+        types::write_int(2, &mut rows_body);
+        types::write_string("ks", &mut rows_body).unwrap();
+        types::write_string("t", &mut rows_body).unwrap();
+        types::write_string("k", &mut rows_body).unwrap();
+        types::write_short(0x0009, &mut rows_body);
+        types::write_string("v", &mut rows_body).unwrap();
+        types::write_short(UNKNOWN_ID, &mut rows_body);
+        types::write_int(1, &mut rows_body);
+        types::write_bytes(&7_i32.to_be_bytes(), &mut rows_body).unwrap();
+        types::write_bytes(&[0xde, 0xad], &mut rows_body).unwrap();
+
+        let mut prepared_body = Vec::new();
+        types::write_int(0x0004, &mut prepared_body);
+        types::write_short_bytes(&[0xca, 0xfe], &mut prepared_body).unwrap();
+        types::write_int(0x0001, &mut prepared_body); // Global table spec
+        types::write_int(1, &mut prepared_body);
+        types::write_int(0, &mut prepared_body); // No partition key columns
+        types::write_string("ks", &mut prepared_body).unwrap();
+        types::write_string("t", &mut prepared_body).unwrap();
+        types::write_string("v", &mut prepared_body).unwrap();
+        types::write_short(UNKNOWN_ID, &mut prepared_body);
+        types::write_int(0x0004, &mut prepared_body); // No result metadata
+        types::write_int(0, &mut prepared_body);
+
+        let accept = ResultParseOptions {
+            unknown_type_policy: UnknownTypePolicy::Accept,
+            ..Default::default()
+        };
+        let deserialize_accepting =
+            |body: &[u8]| super::deserialize_in(&mut &body[..], None, None, accept);
+
+        // By default, the metadata fails to parse
+        assert_eq!(
+            ResultParseOptions::default().unknown_type_policy,
+            UnknownTypePolicy::Fail
+        );
+        for body in [&rows_body, &prepared_body] {
+            assert!(matches!(
+                super::deserialize(&mut &body[..]),
+                Err(ParseError::TypeNotImplemented(UNKNOWN_ID))
+            ));
+        }
+
+        // The error of parsing the frame points right after the type id
+        let err = Response::deserialize_frame(
+            &Default::default(),
+            FrameParams::default(),
+            ResponseOpcode::Result,
+            &rows_body.clone().into(),
+            false,
+            None,
+            true,
+            ResultParseOptions::default(),
+        )
+        .unwrap_err();
+        assert_eq!(err.context.offset, 29);
+        assert_eq!(
+            err.to_string(),
+            "type not yet implemented, id: 255 \
+             (Result frame (version: 0x04, flags: 0x00, stream: 0) with a body of 47 bytes, at offset 29, \
+             bytes 0..47: 00 00 00 02 00 00 00 01 00 00 00 02 00 02 6b 73 00 01 74 00 01 6b 00 09 00 01 76 00 ff \
+             | 00 00 00 01 00 00 00 04 00 00 00 07 00 00 00 02 de ad)"
+        );
+
+        let rows = deserialize_accepting(&rows_body).map(rows_of);
+        let prepared = deserialize_accepting(&prepared_body);
+
+        // Rows are fetched, with values of the unknown type as raw bytes
+        let rows = rows.unwrap();
+        let unknown_type = ColumnType::Unknown {
+            id: UNKNOWN_ID,
+            raw: vec![0x00, 0xff],
+        };
+        assert_eq!(rows.metadata.columns()[1].typ, unknown_type);
+        let value = rows.rows[0].columns[1].clone().unwrap();
+        assert_eq!(value.as_unknown_raw(), Some(&[0xde, 0xad][..]));
+        assert_eq!(rows.rows[0].columns[0], Some(CqlValue::Int(7)));
+
+        // They can't be converted to typed values
+        assert!(rows.rows[0].clone().into_typed::<(i32, Vec<u8>)>().is_err());
+        assert_eq!(
+            rows.rows[0]
+                .clone()
+                .into_typed::<(i32, CqlValue)>()
+                .unwrap(),
+            (7, value.clone())
+        );
+
+        // Statements are prepared, but values can't be serialized as the unknown type
+        let prepared = match prepared.unwrap() {
+            super::Result::Prepared(prepared) => prepared.prepared_metadata,
+            _ => panic!("Expected prepared"),
+        };
+        let typ = &prepared.col_specs[0].typ;
+        assert_eq!(*typ, unknown_type);
+
+        let mut values = SerializedValues::new();
+        let err = values.add_value(&vec![0xde_u8, 0xad], typ).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("the CQL type with id 0x00ff is unknown to the driver"),
+            "{}",
+            err
+        );
+        let other_value = CqlValue::Unknown {
+            id: 0x00FE,
+            raw: vec![0xde, 0xad],
+        };
+        let err = values.add_value(&other_value, typ).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("the value of the unknown CQL type with id 0x00fe"),
+            "{}",
+            err
+        );
+
+        // Except for the raw values of the same type
+        values.add_value(&value, typ).unwrap();
+        assert_eq!(
+            values.iter().next().unwrap(),
+            crate::frame::types::RawValue::Value(&[0xde, 0xad])
+        );
+
+        // If the unknown type has parameters, the rest of the metadata is misparsed,
+        // which fails instead of giving wrong results
+        let mut with_parameter = prepared_body.clone();
+        let type_end = with_parameter.len() - 8;
+        with_parameter.splice(type_end..type_end, [0x00, 0x09]);
+        let err = deserialize_accepting(&with_parameter).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ParseError::InconsistentUnknownType { id: UNKNOWN_ID, .. }
+            ),
+            "{}",
+            err
+        );
+
+        let mut with_parameters = rows_body.clone();
+        with_parameters.splice(29..29, [0x00, 0x20, 0x00, 0x09]);
+        assert!(matches!(
+            deserialize_accepting(&with_parameters),
+            Err(ParseError::InconsistentUnknownType { id: UNKNOWN_ID, .. })
+        ));
+    }
+
+    #[test]
+    fn test_lenient_column_decoding() {
+        use super::{ColumnDecodeError, ColumnDecodePolicy, ResultParseOptions};
+        use crate::cql_to_rust::FromRowError;
+
+        let mut body = Vec::new();
+        types::write_int(0x0002, &mut body);
+        types::write_int(0x0001, &mut body); // Global table spec
+        types::write_int(4, &mut body);
+        types::write_string("ks", &mut body).unwrap();
+        types::write_string("t", &mut body).unwrap();
+        for (name, typ) in [
+            ("k", 0x0009),
+            ("bad", 0x0009),
+            ("txt", 0x000D),
+            ("lst", 0x0020),
+        ] {
+            types::write_string(name, &mut body).unwrap();
+            types::write_short(typ, &mut body);
+            if typ == 0x0020 {
+                types::write_short(0x0009, &mut body);
+            }
+        }
+        let list = [1_i32.to_be_bytes(), 4_i32.to_be_bytes()].concat();
+        types::write_int(2, &mut body);
+        // The second cell of the first row is malformed: an int of 3 bytes
+        for bad in [&[0xab, 0xcd, 0xef][..], &5_i32.to_be_bytes()] {
+            types::write_bytes(&7_i32.to_be_bytes(), &mut body).unwrap();
+            types::write_bytes(bad, &mut body).unwrap();
+            types::write_bytes(b"text", &mut body).unwrap();
+            let mut list_cell = Vec::new();
+            types::write_int(2, &mut list_cell);
+            types::write_bytes(&list[..4], &mut list_cell).unwrap();
+            types::write_bytes(&list[4..], &mut list_cell).unwrap();
+            types::write_bytes(&list_cell, &mut body).unwrap();
+        }
+
+        // By default the whole result fails
+        assert_eq!(
+            ResultParseOptions::default().column_decode_policy,
+            ColumnDecodePolicy::Strict
+        );
+        assert!(super::deserialize(&mut &body[..]).is_err());
+
+        let lenient = ResultParseOptions {
+            column_decode_policy: ColumnDecodePolicy::Lenient,
+            ..Default::default()
+        };
+        let result = super::deserialize_in(&mut &body[..], None, None, lenient).map(rows_of);
+        let result = result.unwrap();
+        let rows = &result.rows;
+
+        // The other columns of the row are decoded
+        let columns = result.columns_lenient(0);
+        assert_eq!(columns[0], Ok(Some(CqlValue::Int(7))));
+        let err = columns[1].clone().unwrap_err();
+        assert_eq!(
+            (
+                err.row,
+                err.index,
+                err.column_name.as_str(),
+                &err.column_type,
+                err.raw_len
+            ),
+            (0, 1, "bad", &ColumnType::Int, 3)
+        );
+        assert!(err
+            .to_string()
+            .contains("column bad (index 1) of type int in the row 0"));
+        assert_eq!(columns[2], Ok(Some(CqlValue::Text("text".to_string()))));
+        assert_eq!(
+            columns[3],
+            Ok(Some(CqlValue::List(vec![
+                CqlValue::Int(1),
+                CqlValue::Int(4)
+            ])))
+        );
+        assert_eq!(result.decode_errors, std::slice::from_ref(&err));
+        assert_eq!(rows[0].columns[1], None);
+
+        // Rows with such columns can't be converted to Rust types
+        assert_eq!(
+            ColumnDecodeError::check_row(&result.decode_errors, 0),
+            Err(FromRowError::UndecodableColumn(Box::new(err)))
+        );
+
+        // Rows without them are unaffected
+        assert_eq!(
+            ColumnDecodeError::of_row(&result.decode_errors, 1),
+            &[] as &[ColumnDecodeError]
+        );
+        assert_eq!(
+            ColumnDecodeError::check_row(&result.decode_errors, 1),
+            Ok(())
+        );
+        assert_eq!(
+            rows[1]
+                .clone()
+                .into_typed::<(i32, Option<i32>, String, Vec<i32>)>()
+                .unwrap(),
+            (7, Some(5), "text".to_string(), vec![1, 4])
+        );
+    }
+
+    // Body of a result with a single row of `col_count` int columns,
+    // sent with or without their metadata.
+    fn int_row_body(col_count: usize, with_metadata: bool) -> Vec<u8> {
+        use crate::frame::types;
+
+        let mut body = Vec::new();
+        types::write_int(0x0002, &mut body);
+        match with_metadata {
+            true => types::write_int(0x0001, &mut body), // Global table spec
+            false => types::write_int(0x0004, &mut body), // No metadata
+        }
+        types::write_int(col_count as i32, &mut body);
+        if with_metadata {
+            types::write_string("ks", &mut body).unwrap();
+            types::write_string("wide", &mut body).unwrap();
+            for i in 0..col_count {
+                types::write_string(&format!("c{}", i), &mut body).unwrap();
+                types::write_short(0x0009, &mut body);
+            }
+        }
+
+        types::write_int(1, &mut body);
+        for i in 0..col_count {
+            types::write_bytes(&(i as i32).to_be_bytes(), &mut body).unwrap();
+        }
+        body
+    }
+
+    fn rows_of(result: super::Result) -> super::Rows {
+        match result {
+            super::Result::Rows(rows) => rows,
+            _ => panic!("Expected rows"),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_with_cached_metadata() {
+        use crate::frame::frame_errors::ParseError;
+
+        let with_metadata = int_row_body(50, true);
+        let without_metadata = int_row_body(50, false);
+        assert!(without_metadata.len() < with_metadata.len());
+
+        let full = rows_of(super::deserialize(&mut &with_metadata[..]).unwrap());
+        let cached = full.metadata;
+        let expected: Vec<_> = (0..50).map(|i| Some(CqlValue::Int(i))).collect();
+        assert_eq!(full.rows[0].columns, expected);
+
+        let skipped = rows_of(
+            super::deserialize_with_metadata(&mut &without_metadata[..], Some(&cached)).unwrap(),
+        );
+        assert_eq!(skipped.rows[0].columns, expected);
+        assert_eq!(skipped.metadata.col_specs, cached.col_specs);
+
+        let body = bytes::Bytes::from(without_metadata.clone());
+        let shared =
+            rows_of(super::deserialize_shared_with_metadata(&body, Some(&cached)).unwrap());
+        assert_eq!(shared.rows[0].columns, expected);
+
+        // Metadata sent along with the rows takes precedence over the cached one
+        let narrow = rows_of(super::deserialize(&mut &int_row_body(3, true)[..]).unwrap());
+        let sent = rows_of(
+            super::deserialize_with_metadata(&mut &with_metadata[..], Some(&narrow.metadata))
+                .unwrap(),
+        );
+        assert_eq!(sent.metadata.col_specs, cached.col_specs);
+
+        // Missing or outdated cached metadata can't be used
+        for cached in [None, Some(&narrow.metadata)] {
+            match super::deserialize_with_metadata(&mut &without_metadata[..], cached) {
+                Err(ParseError::ResultMetadataMismatch { col_count: 50 }) => {}
+                other => panic!("Unexpected result: {:?}", other),
+            }
+        }
+    }
+
+    fn columns_named(names: &[&str]) -> Vec<super::ColumnSpec> {
+        names
+            .iter()
+            .map(|name| super::ColumnSpec {
+                table_spec: super::TableSpec {
+                    ks_name: "ks".to_owned(),
+                    table_name: "t".to_owned(),
+                },
+                name: (*name).to_owned(),
+                typ: ColumnType::Int,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_column_index_by_name() {
+        use super::ResultMetadata;
+
+        // Columns of `SELECT a, b AS "MixedCase", c AS Unquoted, d AS "lower", e AS "Quo""te", a`
+        let metadata = ResultMetadata::new(
+            columns_named(&["a", "MixedCase", "unquoted", "lower", "Quo\"te", "a"]),
+            None,
+        );
+        assert_eq!(metadata.col_count(), 6);
+        assert!(!metadata.paging_state_present());
+
+        let cases: &[(&str, Option<usize>)] = &[
+            ("a", Some(0)),
+            ("A", Some(0)),
+            ("\"a\"", Some(0)),
+            ("\"A\"", None),
+            // Exact name of a quoted alias
+            ("MixedCase", Some(1)),
+            ("\"MixedCase\"", Some(1)),
+            ("mixedcase", None),
+            ("\"mixedcase\"", None),
+            // Unquoted alias is folded to lowercase by the server
+            ("unquoted", Some(2)),
+            ("Unquoted", Some(2)),
+            ("UNQUOTED", Some(2)),
+            ("\"Unquoted\"", None),
+            ("lower", Some(3)),
+            ("\"lower\"", Some(3)),
+            ("Quo\"te", Some(4)),
+            ("\"Quo\"\"te\"", Some(4)),
+            ("missing", None),
+            ("\"", None),
+            ("\"\"", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(metadata.column_index(name), *expected, "name: {}", name);
+            assert_eq!(
+                super::ColumnNameIndex::find(metadata.columns(), name),
+                *expected,
+                "name: {}",
+                name
+            );
+        }
+        assert_eq!(metadata.column("MixedCase").unwrap().name(), "MixedCase");
+        assert!(metadata.column("mixedcase").is_none());
+    }
+
+    #[test]
+    fn test_column_name_index_shared_between_pages() {
+        use super::ResultMetadata;
+        use std::sync::Arc;
+
+        let first = ResultMetadata::new(columns_named(&["a", "b"]), Some(vec![1].into()));
+        assert!(first.paging_state_present());
+        assert_eq!(first.column_index("b"), Some(1));
+
+        let mut second = ResultMetadata::new(columns_named(&["a", "b"]), None);
+        second.share_column_name_index(&first);
+        assert!(Arc::ptr_eq(
+            first.column_name_index(),
+            second.column_name_index()
+        ));
+
+        // Different columns get their own index
+        let mut other = ResultMetadata::new(columns_named(&["a", "c"]), None);
+        other.share_column_name_index(&first);
+        assert!(!Arc::ptr_eq(
+            first.column_name_index(),
+            other.column_name_index()
+        ));
+        assert_eq!(other.column_index("c"), Some(1));
+
+        // Rows sent without metadata reuse the index of the cached metadata
+        let cached = rows_of(super::deserialize(&mut &int_row_body(3, true)[..]).unwrap()).metadata;
+        assert_eq!(cached.column_index("c2"), Some(2));
+        let skipped = rows_of(
+            super::deserialize_with_metadata(&mut &int_row_body(3, false)[..], Some(&cached))
+                .unwrap(),
+        );
+        assert!(Arc::ptr_eq(
+            cached.column_name_index(),
+            skipped.metadata.column_name_index()
+        ));
+    }
+
+    // One value of each variant
+    fn values_of_all_variants() -> Vec<CqlValue> {
+        let uuid = Uuid::from_u128(1);
+        vec![
+            CqlValue::Ascii("ascii".to_string()),
+            CqlValue::Boolean(true),
+            CqlValue::Blob(vec![1, 2]),
+            CqlValue::SharedBlob(bytes::Bytes::from_static(&[1, 2])),
+            CqlValue::Counter(Counter(1)),
+            CqlValue::Decimal(BigDecimal::from_str("1.5").unwrap()),
+            CqlValue::Date(CqlDate(1 << 31)),
+            CqlValue::Double(1.5),
+            CqlValue::Duration(CqlDuration {
+                months: 1,
+                days: 2,
+                nanoseconds: 3,
+            }),
+            CqlValue::Empty,
+            CqlValue::Float(1.5),
+            CqlValue::Int(1),
+            CqlValue::BigInt(1),
+            CqlValue::Text("text".to_string()),
+            CqlValue::Timestamp(CqlTimestamp(1)),
+            CqlValue::Inet("127.0.0.1".parse().unwrap()),
+            CqlValue::List(vec![CqlValue::Int(1)]),
+            CqlValue::Map(vec![(CqlValue::Text("a".to_string()), CqlValue::Int(1))]),
+            CqlValue::Set(vec![CqlValue::Int(2)]),
+            CqlValue::UserDefinedType {
+                keyspace: "ks".to_string(),
+                type_name: "udt".to_string(),
+                fields: vec![
+                    ("a".to_string(), Some(CqlValue::Int(1))),
+                    ("b".to_string(), None),
+                ],
+            },
+            CqlValue::SmallInt(1),
+            CqlValue::TinyInt(1),
+            CqlValue::Time(CqlTime(1)),
+            CqlValue::Timeuuid(uuid),
+            CqlValue::Tuple(vec![Some(CqlValue::Int(1)), None]),
+            CqlValue::Uuid(uuid),
+            CqlValue::Varint(BigInt::from(1)),
+        ]
+    }
+
+    fn variant_name(value: &CqlValue) -> String {
+        format!("{:?}", value)
+            .chars()
+            .take_while(|c| c.is_alphanumeric())
+            .collect()
+    }
+
+    #[test]
+    fn accessors_accept_only_their_variants() {
+        let values = values_of_all_variants();
+        let check = |name: &str, accessor: &dyn Fn(CqlValue) -> bool, expected: &[&str]| {
+            let accepted: Vec<String> = values
+                .iter()
+                .filter(|value| accessor((*value).clone()))
+                .map(variant_name)
+                .collect();
+            assert_eq!(accepted, expected, "{}", name);
+        };
+
+        check("as_ascii", &|v| v.as_ascii().is_some(), &["Ascii"]);
+        check("into_ascii", &|v| v.into_ascii().is_some(), &["Ascii"]);
+        check("as_boolean", &|v| v.as_boolean().is_some(), &["Boolean"]);
+        check("as_blob", &|v| v.as_blob().is_some(), &["Blob"]);
+        let blobs = &["Blob", "SharedBlob"];
+        check("as_blob_slice", &|v| v.as_blob_slice().is_some(), blobs);
+        check("into_blob", &|v| v.into_blob().is_some(), blobs);
+        check("into_blob_bytes", &|v| v.into_blob_bytes().is_some(), blobs);
+        check("as_counter", &|v| v.as_counter().is_some(), &["Counter"]);
+        check("as_decimal", &|v| v.as_decimal().is_some(), &["Decimal"]);
+        check(
+            "into_decimal",
+            &|v| v.into_decimal().is_some(),
+            &["Decimal"],
+        );
+        check("as_cql_date", &|v| v.as_cql_date().is_some(), &["Date"]);
+        check("as_double", &|v| v.as_double().is_some(), &["Double"]);
+        check(
+            "as_cql_duration",
+            &|v| v.as_cql_duration().is_some(),
+            &["Duration"],
+        );
+        check("is_empty_value", &|v| v.is_empty_value(), &["Empty"]);
+        check("as_float", &|v| v.as_float().is_some(), &["Float"]);
+        check("as_int", &|v| v.as_int().is_some(), &["Int"]);
+        check("as_bigint", &|v| v.as_bigint().is_some(), &["BigInt"]);
+        check("as_text", &|v| v.as_text().is_some(), &["Text"]);
+        check("into_text", &|v| v.into_text().is_some(), &["Text"]);
+        check(
+            "into_string",
+            &|v| v.into_string().is_some(),
+            &["Ascii", "Text"],
+        );
+        check(
+            "as_cql_timestamp",
+            &|v| v.as_cql_timestamp().is_some(),
+            &["Timestamp"],
+        );
+        check("as_inet", &|v| v.as_inet().is_some(), &["Inet"]);
+        check("as_list", &|v| v.as_list().is_some(), &["List"]);
+        check("into_list", &|v| v.into_list().is_some(), &["List"]);
+        check("as_map", &|v| v.as_map().is_some(), &["Map"]);
+        check("into_pair_vec", &|v| v.into_pair_vec().is_some(), &["Map"]);
+        check("as_set", &|v| v.as_set().is_some(), &["Set"]);
+        check("into_set", &|v| v.into_set().is_some(), &["Set"]);
+        check("into_vec", &|v| v.into_vec().is_some(), &["List", "Set"]);
+        let udt = &["UserDefinedType"];
+        check("as_udt", &|v| v.as_udt().is_some(), udt);
+        check(
+            "into_udt_pair_vec",
+            &|v| v.into_udt_pair_vec().is_some(),
+            udt,
+        );
+        check("as_udt_field", &|v| v.as_udt_field("a").is_some(), udt);
+        check("into_udt_field", &|v| v.into_udt_field("a").is_some(), udt);
+        check("as_smallint", &|v| v.as_smallint().is_some(), &["SmallInt"]);
+        check("as_tinyint", &|v| v.as_tinyint().is_some(), &["TinyInt"]);
+        check("as_cql_time", &|v| v.as_cql_time().is_some(), &["Time"]);
+        check("as_timeuuid", &|v| v.as_timeuuid().is_some(), &["Timeuuid"]);
+        check("as_tuple", &|v| v.as_tuple().is_some(), &["Tuple"]);
+        check("into_tuple", &|v| v.into_tuple().is_some(), &["Tuple"]);
+        check("as_uuid", &|v| v.as_uuid().is_some(), &["Timeuuid", "Uuid"]);
+        check("as_varint", &|v| v.as_varint().is_some(), &["Varint"]);
+        check("into_varint", &|v| v.into_varint().is_some(), &["Varint"]);
+
+        #[cfg(feature = "chrono")]
+        {
+            check("as_naive_date", &|v| v.as_naive_date().is_some(), &["Date"]);
+            check(
+                "as_datetime",
+                &|v| v.as_datetime().is_some(),
+                &["Timestamp"],
+            );
+            check("as_naive_time", &|v| v.as_naive_time().is_some(), &["Time"]);
+        }
+        #[cfg(feature = "time")]
+        {
+            check("as_date", &|v| v.as_date().is_some(), &["Date"]);
+            check(
+                "as_offset_date_time",
+                &|v| v.as_offset_date_time().is_some(),
+                &["Timestamp"],
+            );
+            check("as_time", &|v| v.as_time().is_some(), &["Time"]);
+        }
+    }
+
+    #[test]
+    fn accessors_return_contents() {
+        let blob = CqlValue::SharedBlob(bytes::Bytes::from_static(&[1, 2]));
+        assert_eq!(blob.as_blob_slice(), Some(&[1, 2][..]));
+        assert_eq!(blob.into_blob(), Some(vec![1, 2]));
+
+        let udt = CqlValue::UserDefinedType {
+            keyspace: "ks".to_string(),
+            type_name: "udt".to_string(),
+            fields: vec![
+                ("a".to_string(), Some(CqlValue::Int(1))),
+                ("b".to_string(), None),
+            ],
+        };
+        assert_eq!(udt.as_udt_field("a"), Some(Some(&CqlValue::Int(1))));
+        assert_eq!(udt.as_udt_field("b"), Some(None));
+        assert_eq!(udt.as_udt_field("c"), None);
+        assert_eq!(
+            udt.clone().into_udt_field("a"),
+            Some(Some(CqlValue::Int(1)))
+        );
+        assert_eq!(udt.clone().into_udt_field("b"), Some(None));
+        assert_eq!(udt.into_udt_field("c"), None);
+
+        let tuple = CqlValue::Tuple(vec![Some(CqlValue::Int(1)), None]);
+        assert_eq!(tuple.as_tuple(), Some(&vec![Some(CqlValue::Int(1)), None]));
+        assert_eq!(tuple.into_tuple(), Some(vec![Some(CqlValue::Int(1)), None]));
+
+        let varint = CqlValue::Varint(BigInt::from(7));
+        assert_eq!(varint.as_varint(), Some(&BigInt::from(7)));
+        let decimal = CqlValue::Decimal(BigDecimal::from_str("1.5").unwrap());
+        assert_eq!(
+            decimal.as_decimal(),
+            Some(&BigDecimal::from_str("1.5").unwrap())
+        );
+    }
+
+    #[test]
+    fn value_into_typed() {
+        use crate::frame::response::cql_to_rust::FromCqlValError;
+        use std::collections::{BTreeMap, HashMap};
+
+        let map = CqlValue::Map(vec![
+            (CqlValue::Text("a".to_string()), CqlValue::Int(1)),
+            (CqlValue::Text("b".to_string()), CqlValue::Int(2)),
+        ]);
+        let hash_map: HashMap<String, i32> = map.clone().into_typed().unwrap();
+        assert_eq!(
+            hash_map,
+            HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)])
+        );
+        let btree_map: BTreeMap<String, i32> = map.clone().into_typed().unwrap();
+        assert_eq!(
+            btree_map,
+            BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 2)])
+        );
+        assert_eq!(
+            map.into_typed::<HashMap<String, String>>(),
+            Err(FromCqlValError::BadCqlType)
+        );
+
+        assert_eq!(CqlValue::Int(1).into_typed::<i32>(), Ok(1));
+        assert_eq!(
+            CqlValue::Int(1).into_typed::<i64>(),
+            Err(FromCqlValError::BadCqlType)
+        );
+    }
+
+    // Body of a RESULT frame of kind SCHEMA_CHANGE
+    fn schema_change_body(strings: &[&str], arguments: Option<&[&str]>) -> Vec<u8> {
+        let mut body = Vec::new();
+        types::write_int(0x0005, &mut body);
+        for string in strings {
+            types::write_string(string, &mut body).unwrap();
+        }
+        if let Some(arguments) = arguments {
+            let arguments: Vec<String> = arguments.iter().map(|a| a.to_string()).collect();
+            types::write_string_list(&arguments, &mut body).unwrap();
+        }
+        body
+    }
+
+    fn deserialize_schema_change(body: &[u8]) -> SchemaChangeResult {
+        match super::deserialize(&mut &body[..]).unwrap() {
+            super::Result::SchemaChange(schema_change) => schema_change.event,
+            other => panic!("expected a schema change, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_schema_change() {
+        let keyspace =
+            deserialize_schema_change(&schema_change_body(&["CREATED", "KEYSPACE", "ks"], None));
+        assert_eq!(
+            keyspace,
+            SchemaChangeResult::KeyspaceChange {
+                change_type: SchemaChangeType::Created,
+                keyspace_name: "ks".to_owned(),
+            }
+        );
+        assert_eq!(keyspace.object_name(), None);
+
+        let table =
+            deserialize_schema_change(&schema_change_body(&["UPDATED", "TABLE", "ks", "t"], None));
+        assert_eq!(
+            table,
+            SchemaChangeResult::TableChange {
+                change_type: SchemaChangeType::Updated,
+                keyspace_name: "ks".to_owned(),
+                object_name: "t".to_owned(),
+            }
+        );
+        assert_eq!(table.change_type(), SchemaChangeType::Updated);
+        assert_eq!(table.keyspace_name(), "ks");
+        assert_eq!(table.object_name(), Some("t"));
+
+        let udt = deserialize_schema_change(&schema_change_body(
+            &["DROPPED", "TYPE", "ks", "address"],
+            None,
+        ));
+        assert_eq!(
+            udt,
+            SchemaChangeResult::TypeChange {
+                change_type: SchemaChangeType::Dropped,
+                keyspace_name: "ks".to_owned(),
+                type_name: "address".to_owned(),
+            }
+        );
+
+        let function = deserialize_schema_change(&schema_change_body(
+            &["CREATED", "FUNCTION", "ks", "f"],
+            Some(&["int", "frozen<list<text>>"]),
+        ));
+        assert_eq!(
+            function,
+            SchemaChangeResult::FunctionChange {
+                change_type: SchemaChangeType::Created,
+                keyspace_name: "ks".to_owned(),
+                function_name: "f".to_owned(),
+                arguments: vec!["int".to_owned(), "frozen<list<text>>".to_owned()],
+            }
+        );
+
+        let aggregate = deserialize_schema_change(&schema_change_body(
+            &["DROPPED", "AGGREGATE", "ks", "agg"],
+            Some(&[]),
+        ));
+        assert_eq!(
+            aggregate,
+            SchemaChangeResult::AggregateChange {
+                change_type: SchemaChangeType::Dropped,
+                keyspace_name: "ks".to_owned(),
+                aggregate_name: "agg".to_owned(),
+                arguments: vec![],
+            }
+        );
+
+        // Unknown types of change are tolerated, unknown targets are not
+        let unknown_change =
+            deserialize_schema_change(&schema_change_body(&["RENAMED", "TABLE", "ks", "t"], None));
+        assert_eq!(unknown_change.change_type(), SchemaChangeType::Invalid);
+        let unknown_target = schema_change_body(&["CREATED", "INDEX", "ks", "i"], None);
+        super::deserialize(&mut &unknown_target[..]).unwrap_err();
+
+        // A truncated body fails to parse
+        let truncated = schema_change_body(&["CREATED", "FUNCTION", "ks", "f"], None);
+        super::deserialize(&mut &truncated[..]).unwrap_err();
+    }
 }
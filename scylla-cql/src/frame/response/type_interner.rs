@@ -0,0 +1,192 @@
+//! Sharing of user defined type definitions between column types.
+//!
+//! Metadata of every prepared statement and result carries the full types of its columns,
+//! including the definitions of user defined types, which are often large and repeated
+//! across many statements. To avoid keeping a copy of the same definition per column,
+//! the fields of [`ColumnType::UserDefinedType`] are kept in an `Arc`, and definitions
+//! received from the database, or built from the cluster metadata, are interned in
+//! the [global interner](TypeInterner::global), so that equal definitions share one allocation.
+//!
+//! A definition is interned by its keyspace and name. When the type is altered, the new
+//! definition replaces the old one, which is freed as soon as no column type refers to it.
+
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::sync::{Arc, Mutex, Weak};
+
+use once_cell::sync::Lazy;
+
+use super::result::ColumnType;
+
+/// Fields of a user defined type, as kept in [`ColumnType::UserDefinedType`].
+pub type UdtFields = Arc<[(String, ColumnType)]>;
+
+struct InternedUdt {
+    fields: Weak<[(String, ColumnType)]>,
+    // Approximate size of the definition, in bytes
+    size: usize,
+}
+
+/// Interns the definitions of user defined types, keyed by their keyspace and name.
+///
+/// Only weak references are kept, so the interner doesn't keep alive definitions
+/// which are no longer used.
+#[derive(Default)]
+pub struct TypeInterner {
+    udts: Mutex<HashMap<(String, String), InternedUdt>>,
+}
+
+static GLOBAL_INTERNER: Lazy<TypeInterner> = Lazy::new(TypeInterner::default);
+
+impl TypeInterner {
+    /// The interner used when types are deserialized from the metadata of prepared
+    /// statements and results. It's shared by all sessions in the process.
+    pub fn global() -> &'static TypeInterner {
+        &GLOBAL_INTERNER
+    }
+
+    /// Returns the fields of the user defined type, sharing the allocation with
+    /// the previously interned definition of the type if it's the same.
+    /// Otherwise, the given definition replaces it.
+    pub fn intern_udt(
+        &self,
+        keyspace: &str,
+        type_name: &str,
+        fields: Vec<(String, ColumnType)>,
+    ) -> UdtFields {
+        let mut udts = self.udts.lock().unwrap();
+        let key = (keyspace.to_owned(), type_name.to_owned());
+        if let Some(interned) = udts.get(&key).and_then(|udt| udt.fields.upgrade()) {
+            if *interned == *fields {
+                return interned;
+            }
+        }
+
+        // Drop the entries of unused definitions from time to time
+        if udts.len() >= 64 && udts.len().is_power_of_two() {
+            udts.retain(|_, udt| udt.fields.strong_count() > 0);
+        }
+
+        let size = fields_size(&fields);
+        let fields: UdtFields = fields.into();
+        udts.insert(
+            key,
+            InternedUdt {
+                fields: Arc::downgrade(&fields),
+                size,
+            },
+        );
+        fields
+    }
+
+    /// Approximate memory used by the interned definitions which are still in use, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.udts
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|udt| udt.fields.strong_count() > 0)
+            .map(|udt| udt.size)
+            .sum()
+    }
+
+    /// Number of the interned definitions which are still in use.
+    pub fn len(&self) -> usize {
+        self.udts
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|udt| udt.fields.strong_count() > 0)
+            .count()
+    }
+
+    /// Returns whether no interned definition is in use.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl std::fmt::Debug for TypeInterner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TypeInterner")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+fn fields_size(fields: &[(String, ColumnType)]) -> usize {
+    fields
+        .iter()
+        .map(|(name, typ)| size_of::<(String, ColumnType)>() + name.len() + heap_size(typ))
+        .sum()
+}
+
+// Approximate size of the allocations owned by the type. Fields of nested
+// user defined types are shared, so they are counted only once, as their own entries.
+fn heap_size(typ: &ColumnType) -> usize {
+    match typ {
+        ColumnType::Custom(class_name) => class_name.len(),
+        ColumnType::List(elem) | ColumnType::Set(elem) => size_of::<ColumnType>() + heap_size(elem),
+        ColumnType::Map(key, value) => {
+            2 * size_of::<ColumnType>() + heap_size(key) + heap_size(value)
+        }
+        ColumnType::Tuple(elems) => elems
+            .iter()
+            .map(|elem| size_of::<ColumnType>() + heap_size(elem))
+            .sum(),
+        ColumnType::UserDefinedType {
+            type_name,
+            keyspace,
+            ..
+        } => type_name.len() + keyspace.len(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TypeInterner;
+    use crate::frame::response::result::ColumnType;
+    use std::sync::Arc;
+
+    fn fields(field_type: ColumnType) -> Vec<(String, ColumnType)> {
+        vec![
+            ("a".to_string(), ColumnType::Int),
+            ("b".to_string(), ColumnType::List(Box::new(field_type))),
+        ]
+    }
+
+    #[test]
+    fn equal_definitions_are_shared() {
+        let interner = TypeInterner::default();
+        let first = interner.intern_udt("ks", "udt", fields(ColumnType::Text));
+        let second = interner.intern_udt("ks", "udt", fields(ColumnType::Text));
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(interner.len(), 1);
+        assert!(interner.memory_usage() > 0);
+
+        // Types of the same name in other keyspaces are separate
+        let other = interner.intern_udt("other_ks", "udt", fields(ColumnType::Text));
+        assert!(!Arc::ptr_eq(&first, &other));
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn altered_definitions_replace_interned_ones() {
+        let interner = TypeInterner::default();
+        let old = interner.intern_udt("ks", "udt", fields(ColumnType::Text));
+        let altered = interner.intern_udt("ks", "udt", fields(ColumnType::Blob));
+        assert!(!Arc::ptr_eq(&old, &altered));
+        assert_eq!(*altered, *fields(ColumnType::Blob));
+        assert!(Arc::ptr_eq(
+            &altered,
+            &interner.intern_udt("ks", "udt", fields(ColumnType::Blob))
+        ));
+
+        // Definitions which are no longer used aren't kept alive
+        drop(old);
+        drop(altered);
+        assert!(interner.is_empty());
+        assert_eq!(interner.memory_usage(), 0);
+    }
+}
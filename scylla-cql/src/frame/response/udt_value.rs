@@ -0,0 +1,712 @@
+//! Building values of user defined types validated against their definitions,
+//! and reading them without Rust structs.
+
+use std::collections::BTreeMap;
+
+use super::cql_to_rust::{FromCqlVal, FromCqlValError};
+use super::result::{ColumnType, CqlValue};
+use super::type_interner::UdtFields;
+use crate::types::serialize::value::SerializeCql;
+use crate::types::serialize::writers::CellWriter;
+use crate::types::serialize::SerializationError;
+use thiserror::Error;
+
+/// Builds a [`CqlValue::UserDefinedType`] according to the definition of the type,
+/// e.g. taken from the schema metadata of the cluster.
+///
+/// Values of fields are checked against the types of the fields as soon as they are set,
+/// and the fields of the built value are in the order of the definition.
+/// Fields which weren't set are null.
+///
+/// # Example
+/// ```
+/// # use scylla_cql::frame::response::result::{ColumnType, CqlValue};
+/// # use scylla_cql::frame::response::udt_value::UdtValueBuilder;
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let typ = ColumnType::UserDefinedType {
+///     keyspace: "ks".to_string(),
+///     type_name: "address".to_string(),
+///     field_types: vec![
+///         ("street".to_string(), ColumnType::Text),
+///         ("number".to_string(), ColumnType::Int),
+///     ]
+///     .into(),
+/// };
+///
+/// let address: CqlValue = UdtValueBuilder::new(&typ)?
+///     .set("number", 7)?
+///     .set("street", "Baker Street")?
+///     .build();
+///
+/// // The type of a value is checked at once
+/// assert!(UdtValueBuilder::new(&typ)?.set("number", "seven").is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct UdtValueBuilder {
+    keyspace: String,
+    type_name: String,
+    field_types: UdtFields,
+    values: Vec<Option<CqlValue>>,
+}
+
+/// Setting or reading a field of a [`UdtValueBuilder`] or a [`DynamicUdt`] failed.
+#[derive(Debug, Clone, Error)]
+pub enum UdtValueError {
+    /// The builder was created with a type which is not a user defined type.
+    #[error("Type {0} is not a user defined type")]
+    NotUdt(String),
+
+    /// The value is of another user defined type than the given one.
+    #[error("Value of user defined type {found} can't be converted to {expected}")]
+    WrongUdt {
+        /// Name of the given type, with its keyspace
+        expected: String,
+        /// Name of the type of the value, with its keyspace
+        found: String,
+    },
+
+    /// The type has no field with the given name.
+    #[error("User defined type {keyspace}.{type_name} has no field {field}")]
+    NoSuchField {
+        keyspace: String,
+        type_name: String,
+        field: String,
+    },
+
+    /// The value doesn't match the type of the field.
+    #[error("Value of field {field} of user defined type {keyspace}.{type_name} doesn't match type {field_type}: {error}")]
+    WrongFieldType {
+        keyspace: String,
+        type_name: String,
+        field: String,
+        /// CQL type of the field
+        field_type: String,
+        #[source]
+        error: SerializationError,
+    },
+
+    /// The value of the field couldn't be converted to the Rust type.
+    #[error("Field {field} of user defined type {keyspace}.{type_name} can't be converted to {rust_type}: {error}")]
+    FieldConversion {
+        keyspace: String,
+        type_name: String,
+        field: String,
+        rust_type: &'static str,
+        #[source]
+        error: FromCqlValError,
+    },
+}
+
+impl UdtValueBuilder {
+    /// Creates a builder of a value of the given user defined type, with all fields null.
+    ///
+    /// Fails if the type is not a [`ColumnType::UserDefinedType`].
+    pub fn new(typ: &ColumnType) -> Result<Self, UdtValueError> {
+        match typ {
+            ColumnType::UserDefinedType {
+                type_name,
+                keyspace,
+                field_types,
+            } => Ok(Self {
+                keyspace: keyspace.clone(),
+                type_name: type_name.clone(),
+                field_types: field_types.clone(),
+                values: vec![None; field_types.len()],
+            }),
+            _ => Err(UdtValueError::NotUdt(typ.cql_type_name().into_owned())),
+        }
+    }
+
+    /// Returns the type of the field with the given name, e.g. to build
+    /// a value of a nested user defined type.
+    pub fn field_type(&self, field: &str) -> Option<&ColumnType> {
+        self.field_types
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, typ)| typ)
+    }
+
+    /// Sets the value of the field.
+    ///
+    /// Fails if the type has no such field, or if the value doesn't match the type of the field.
+    /// `text` values are accepted for `ascii` fields and `uuid` values for `timeuuid` fields
+    /// (and the other way around), and converted to the type of the field.
+    pub fn set(mut self, field: &str, value: impl Into<CqlValue>) -> Result<Self, UdtValueError> {
+        let index = self.field_index(field)?;
+        let (_, field_type) = &self.field_types[index];
+        let value = coerce(value.into(), field_type);
+
+        let mut buf = Vec::new();
+        if let Err(error) = value.serialize(field_type, CellWriter::new(&mut buf)) {
+            return Err(UdtValueError::WrongFieldType {
+                keyspace: self.keyspace,
+                type_name: self.type_name,
+                field: field.to_owned(),
+                field_type: field_type.cql_type_name().into_owned(),
+                error,
+            });
+        }
+
+        self.values[index] = Some(value);
+        Ok(self)
+    }
+
+    /// Sets the field to null.
+    ///
+    /// Fails if the type has no such field.
+    pub fn set_null(mut self, field: &str) -> Result<Self, UdtValueError> {
+        let index = self.field_index(field)?;
+        self.values[index] = None;
+        Ok(self)
+    }
+
+    /// Returns the value, with fields in the order of the definition of the type.
+    pub fn build(self) -> CqlValue {
+        CqlValue::UserDefinedType {
+            keyspace: self.keyspace,
+            type_name: self.type_name,
+            fields: self
+                .field_types
+                .iter()
+                .map(|(name, _)| name.clone())
+                .zip(self.values)
+                .collect(),
+        }
+    }
+
+    fn field_index(&self, field: &str) -> Result<usize, UdtValueError> {
+        self.field_types
+            .iter()
+            .position(|(name, _)| name == field)
+            .ok_or_else(|| UdtValueError::NoSuchField {
+                keyspace: self.keyspace.clone(),
+                type_name: self.type_name.clone(),
+                field: field.to_owned(),
+            })
+    }
+}
+
+/// A value of a user defined type read without a Rust struct for the type,
+/// e.g. by a generic layer exposing any schema.
+///
+/// It's converted from a [`CqlValue::UserDefinedType`], also as a part of other values,
+/// e.g. `Vec<DynamicUdt>` for a list of user defined types. The fields are in the order
+/// in which they were received, i.e. the order of the definition of the type,
+/// and can be read with typed accessors.
+///
+/// # Example
+/// ```
+/// # use scylla_cql::frame::response::result::CqlValue;
+/// # use scylla_cql::frame::response::cql_to_rust::FromCqlVal;
+/// # use scylla_cql::frame::response::udt_value::DynamicUdt;
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let value = CqlValue::UserDefinedType {
+///     keyspace: "ks".to_string(),
+///     type_name: "address".to_string(),
+///     fields: vec![
+///         ("street".to_string(), Some(CqlValue::Text("Baker Street".to_string()))),
+///         ("number".to_string(), None),
+///     ],
+/// };
+///
+/// let address = DynamicUdt::from_cql(value)?;
+/// assert_eq!(address.get::<String>("street")?, "Baker Street");
+/// assert_eq!(address.get::<Option<i32>>("number")?, None);
+///
+/// // Errors name the field
+/// let err = address.get::<i32>("street").unwrap_err();
+/// assert!(err.to_string().starts_with("Field street of user defined type ks.address"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicUdt {
+    keyspace: String,
+    type_name: String,
+    fields: Vec<(String, Option<CqlValue>)>,
+}
+
+impl DynamicUdt {
+    /// Creates a value of the user defined type without any fields,
+    /// e.g. to be filled and converted with [`to_cql_value`](Self::to_cql_value).
+    pub fn new(keyspace: impl Into<String>, type_name: impl Into<String>) -> Self {
+        Self {
+            keyspace: keyspace.into(),
+            type_name: type_name.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn keyspace(&self) -> &str {
+        &self.keyspace
+    }
+
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// Returns the fields in their order, nulls included.
+    pub fn fields(&self) -> &[(String, Option<CqlValue>)] {
+        &self.fields
+    }
+
+    /// Returns the value of the field, `None` if it's null.
+    ///
+    /// Fails if the value has no such field.
+    pub fn get_value(&self, field: &str) -> Result<Option<&CqlValue>, UdtValueError> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, value)| value.as_ref())
+            .ok_or_else(|| UdtValueError::NoSuchField {
+                keyspace: self.keyspace.clone(),
+                type_name: self.type_name.clone(),
+                field: field.to_owned(),
+            })
+    }
+
+    /// Returns the value of the field converted to `T`, like values of columns
+    /// are converted with [`FromCqlVal`]. A null is converted only to an `Option`.
+    ///
+    /// Fails if the value has no such field, or if the field can't be converted to `T`.
+    pub fn get<T: FromCqlVal<Option<CqlValue>>>(&self, field: &str) -> Result<T, UdtValueError> {
+        let value = self.get_value(field)?.cloned();
+        T::from_cql(value).map_err(|error| UdtValueError::FieldConversion {
+            keyspace: self.keyspace.clone(),
+            type_name: self.type_name.clone(),
+            field: field.to_owned(),
+            rust_type: std::any::type_name::<T>(),
+            error,
+        })
+    }
+
+    /// Sets the value of the field. A field which isn't present yet is added after the others.
+    pub fn set(&mut self, field: &str, value: impl Into<CqlValue>) {
+        self.set_value(field, Some(value.into()));
+    }
+
+    /// Sets the field to null. A field which isn't present yet is added after the others.
+    pub fn set_null(&mut self, field: &str) {
+        self.set_value(field, None);
+    }
+
+    /// Converts the value to a [`CqlValue::UserDefinedType`] of the given type, e.g. taken
+    /// from the schema metadata of the cluster, with the fields in the order of its definition.
+    /// Fields which aren't present are null.
+    ///
+    /// Fails if `typ` is another type, if it has no field of one of the fields of the value,
+    /// or if the value of a field doesn't match its type, see [`UdtValueBuilder::set`].
+    /// Nested values of user defined types are validated, but their fields aren't reordered,
+    /// so they should be converted with their own types first.
+    pub fn to_cql_value(&self, typ: &ColumnType) -> Result<CqlValue, UdtValueError> {
+        let mut builder = UdtValueBuilder::new(typ)?;
+        if builder.keyspace != self.keyspace || builder.type_name != self.type_name {
+            return Err(UdtValueError::WrongUdt {
+                expected: format!("{}.{}", builder.keyspace, builder.type_name),
+                found: format!("{}.{}", self.keyspace, self.type_name),
+            });
+        }
+        for (field, value) in &self.fields {
+            builder = match value {
+                Some(value) => builder.set(field, value.clone())?,
+                None => builder.set_null(field)?,
+            };
+        }
+        Ok(builder.build())
+    }
+
+    fn set_value(&mut self, field: &str, value: Option<CqlValue>) {
+        match self.fields.iter_mut().find(|(name, _)| name == field) {
+            Some((_, old_value)) => *old_value = value,
+            None => self.fields.push((field.to_owned(), value)),
+        }
+    }
+}
+
+impl FromCqlVal<CqlValue> for DynamicUdt {
+    fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
+        match cql_val {
+            CqlValue::UserDefinedType {
+                keyspace,
+                type_name,
+                fields,
+            } => Ok(Self {
+                keyspace,
+                type_name,
+                fields,
+            }),
+            _ => Err(FromCqlValError::BadCqlType),
+        }
+    }
+}
+
+impl From<DynamicUdt> for CqlValue {
+    fn from(udt: DynamicUdt) -> Self {
+        CqlValue::UserDefinedType {
+            keyspace: udt.keyspace,
+            type_name: udt.type_name,
+            fields: udt.fields,
+        }
+    }
+}
+
+/// The fields by name. Their order is lost, but it's kept by [`DynamicUdt::fields`].
+impl From<DynamicUdt> for BTreeMap<String, Option<CqlValue>> {
+    fn from(udt: DynamicUdt) -> Self {
+        udt.fields.into_iter().collect()
+    }
+}
+
+// Converts values which are serialized in the same way as values of the given type
+// to that type, so that the built value is equal to the one read from the database.
+fn coerce(value: CqlValue, typ: &ColumnType) -> CqlValue {
+    match (value, typ) {
+        (CqlValue::Text(s), ColumnType::Ascii) => CqlValue::Ascii(s),
+        (CqlValue::Ascii(s), ColumnType::Text) => CqlValue::Text(s),
+        (CqlValue::Uuid(u), ColumnType::Timeuuid) => CqlValue::Timeuuid(u),
+        (CqlValue::Timeuuid(u), ColumnType::Uuid) => CqlValue::Uuid(u),
+        (CqlValue::List(elements), ColumnType::List(element_type)) => CqlValue::List(
+            elements
+                .into_iter()
+                .map(|element| coerce(element, element_type))
+                .collect(),
+        ),
+        (CqlValue::Set(elements), ColumnType::Set(element_type)) => CqlValue::Set(
+            elements
+                .into_iter()
+                .map(|element| coerce(element, element_type))
+                .collect(),
+        ),
+        (CqlValue::Map(entries), ColumnType::Map(key_type, value_type)) => CqlValue::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (coerce(key, key_type), coerce(value, value_type)))
+                .collect(),
+        ),
+        (CqlValue::Tuple(elements), ColumnType::Tuple(element_types)) => CqlValue::Tuple(
+            elements
+                .into_iter()
+                .zip(element_types)
+                .map(|(element, element_type)| element.map(|e| coerce(e, element_type)))
+                .collect(),
+        ),
+        (value, _) => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DynamicUdt, UdtValueBuilder, UdtValueError};
+    use crate::frame::response::cql_to_rust::{FromCqlVal, FromCqlValError};
+    use crate::frame::response::result::{ColumnType, CqlValue};
+    use std::collections::BTreeMap;
+    use uuid::Uuid;
+
+    fn inner_type() -> ColumnType {
+        ColumnType::UserDefinedType {
+            keyspace: "ks".to_string(),
+            type_name: "inner".to_string(),
+            field_types: vec![
+                ("a".to_string(), ColumnType::Int),
+                ("b".to_string(), ColumnType::Ascii),
+            ]
+            .into(),
+        }
+    }
+
+    fn outer_type() -> ColumnType {
+        ColumnType::UserDefinedType {
+            keyspace: "ks".to_string(),
+            type_name: "outer".to_string(),
+            field_types: vec![
+                ("id".to_string(), ColumnType::Timeuuid),
+                ("list".to_string(), ColumnType::List(Box::new(inner_type()))),
+                (
+                    "map".to_string(),
+                    ColumnType::Map(Box::new(ColumnType::Text), Box::new(inner_type())),
+                ),
+                ("single".to_string(), inner_type()),
+            ]
+            .into(),
+        }
+    }
+
+    fn inner(a: i32, b: &str) -> CqlValue {
+        UdtValueBuilder::new(&inner_type())
+            .unwrap()
+            .set("b", b)
+            .unwrap()
+            .set("a", a)
+            .unwrap()
+            .build()
+    }
+
+    #[test]
+    fn fields_are_in_definition_order() {
+        assert_eq!(
+            inner(1, "x"),
+            CqlValue::UserDefinedType {
+                keyspace: "ks".to_string(),
+                type_name: "inner".to_string(),
+                fields: vec![
+                    ("a".to_string(), Some(CqlValue::Int(1))),
+                    ("b".to_string(), Some(CqlValue::Ascii("x".to_string()))),
+                ],
+            }
+        );
+
+        let unset = UdtValueBuilder::new(&inner_type())
+            .unwrap()
+            .set("a", 1)
+            .unwrap()
+            .set_null("a")
+            .unwrap()
+            .build();
+        assert_eq!(
+            unset.as_udt().unwrap(),
+            &vec![("a".to_string(), None), ("b".to_string(), None)]
+        );
+    }
+
+    #[test]
+    fn nested_udts_in_collections() {
+        let builder = UdtValueBuilder::new(&outer_type()).unwrap();
+        let single_type = builder.field_type("single").unwrap().clone();
+        let single = UdtValueBuilder::new(&single_type)
+            .unwrap()
+            .set("a", 3)
+            .unwrap()
+            .build();
+
+        let id = Uuid::from_u128(1);
+        let outer = builder
+            .set("single", single.clone())
+            .unwrap()
+            .set("map", CqlValue::Map(vec![("k".into(), inner(2, "y"))]))
+            .unwrap()
+            .set("list", CqlValue::List(vec![inner(1, "x")]))
+            .unwrap()
+            .set("id", id)
+            .unwrap()
+            .build();
+
+        assert_eq!(
+            outer.into_udt_pair_vec().unwrap(),
+            vec![
+                ("id".to_string(), Some(CqlValue::Timeuuid(id))),
+                (
+                    "list".to_string(),
+                    Some(CqlValue::List(vec![inner(1, "x")]))
+                ),
+                (
+                    "map".to_string(),
+                    Some(CqlValue::Map(vec![(
+                        CqlValue::Text("k".to_string()),
+                        inner(2, "y")
+                    )]))
+                ),
+                ("single".to_string(), Some(single)),
+            ]
+        );
+    }
+
+    #[test]
+    fn wrong_fields_are_rejected() {
+        let builder = UdtValueBuilder::new(&outer_type()).unwrap();
+
+        match builder.clone().set("nope", 1) {
+            Err(UdtValueError::NoSuchField { field, .. }) => assert_eq!(field, "nope"),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        assert!(matches!(
+            builder.clone().set_null("nope"),
+            Err(UdtValueError::NoSuchField { .. })
+        ));
+
+        match builder.clone().set("id", 1) {
+            Err(UdtValueError::WrongFieldType {
+                field, field_type, ..
+            }) => {
+                assert_eq!(field, "id");
+                assert_eq!(field_type, "timeuuid");
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        // A nested value of another type is rejected too
+        let other_udt = CqlValue::UserDefinedType {
+            keyspace: "ks".to_string(),
+            type_name: "other".to_string(),
+            fields: vec![],
+        };
+        assert!(matches!(
+            builder.clone().set("list", CqlValue::List(vec![other_udt])),
+            Err(UdtValueError::WrongFieldType { .. })
+        ));
+        let with_unknown_field = CqlValue::UserDefinedType {
+            keyspace: "ks".to_string(),
+            type_name: "inner".to_string(),
+            fields: vec![("c".to_string(), Some(CqlValue::Int(1)))],
+        };
+        assert!(matches!(
+            builder.set("map", CqlValue::Map(vec![("k".into(), with_unknown_field)])),
+            Err(UdtValueError::WrongFieldType { .. })
+        ));
+
+        assert!(matches!(
+            UdtValueBuilder::new(&ColumnType::Int),
+            Err(UdtValueError::NotUdt(_))
+        ));
+    }
+
+    fn outer(id: Uuid) -> CqlValue {
+        UdtValueBuilder::new(&outer_type())
+            .unwrap()
+            .set("id", CqlValue::Timeuuid(id))
+            .unwrap()
+            .set("list", CqlValue::List(vec![inner(1, "x"), inner(2, "y")]))
+            .unwrap()
+            .build()
+    }
+
+    #[test]
+    fn dynamic_udt_reads_nested_values() {
+        let id = Uuid::from_u128(0x8e14e760_7fa8_11eb_bc66_000000000001);
+        let outer = DynamicUdt::from_cql(outer(id)).unwrap();
+        assert_eq!(outer.keyspace(), "ks");
+        assert_eq!(outer.type_name(), "outer");
+        let names: Vec<&str> = outer.fields().iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["id", "list", "map", "single"]);
+
+        assert_eq!(outer.get::<Uuid>("id").unwrap(), id);
+        assert_eq!(outer.get::<Option<DynamicUdt>>("single").unwrap(), None);
+        assert_eq!(outer.get_value("map").unwrap(), None);
+
+        // User defined types in a list in a user defined type
+        let list = outer.get::<Vec<DynamicUdt>>("list").unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].get::<i32>("a").unwrap(), 1);
+        assert_eq!(list[1].get::<String>("b").unwrap(), "y");
+        assert_eq!(list[1].get::<Option<i32>>("a").unwrap(), Some(2));
+
+        assert!(matches!(
+            DynamicUdt::from_cql(CqlValue::Int(1)),
+            Err(FromCqlValError::BadCqlType)
+        ));
+    }
+
+    #[test]
+    fn dynamic_udt_errors_name_the_field() {
+        let outer = DynamicUdt::from_cql(outer(Uuid::nil())).unwrap();
+
+        match outer.get::<i32>("list") {
+            Err(UdtValueError::FieldConversion {
+                keyspace,
+                type_name,
+                field,
+                rust_type,
+                error: FromCqlValError::BadCqlType,
+            }) => {
+                assert_eq!(keyspace, "ks");
+                assert_eq!(type_name, "outer");
+                assert_eq!(field, "list");
+                assert_eq!(rust_type, "i32");
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        // A null is converted only to an Option
+        let err = outer.get::<DynamicUdt>("single").unwrap_err();
+        assert!(matches!(
+            err,
+            UdtValueError::FieldConversion {
+                error: FromCqlValError::ValIsNull,
+                ..
+            }
+        ));
+        assert!(err
+            .to_string()
+            .starts_with("Field single of user defined type ks.outer can't be converted to"));
+
+        // Nested values name the field of their own type
+        let list = outer.get::<Vec<DynamicUdt>>("list").unwrap();
+        assert!(matches!(
+            list[0].get::<i64>("a"),
+            Err(UdtValueError::FieldConversion { type_name, field, .. })
+                if type_name == "inner" && field == "a"
+        ));
+
+        assert!(matches!(
+            outer.get::<i32>("missing"),
+            Err(UdtValueError::NoSuchField { field, .. }) if field == "missing"
+        ));
+    }
+
+    #[test]
+    fn dynamic_udt_to_cql_value_validates_the_type() {
+        let id = Uuid::from_u128(0x8e14e760_7fa8_11eb_bc66_000000000001);
+        let mut first = DynamicUdt::new("ks", "inner");
+        first.set("b", CqlValue::Ascii("x".to_string()));
+        first.set("a", 1);
+        let mut second = DynamicUdt::new("ks", "inner");
+        second.set("b", CqlValue::Ascii("y".to_string()));
+        second.set("a", 2);
+
+        // Fields are put in the order of the definition, missing ones are null.
+        // Nested values are only validated, so they are converted with their own types
+        let list = vec![
+            first.to_cql_value(&inner_type()).unwrap(),
+            second.to_cql_value(&inner_type()).unwrap(),
+        ];
+        let mut value = DynamicUdt::new("ks", "outer");
+        value.set("list", CqlValue::List(list));
+        value.set("single", 0);
+        value.set_null("single");
+        value.set("id", CqlValue::Timeuuid(id));
+        assert_eq!(value.fields().len(), 3);
+        assert_eq!(value.to_cql_value(&outer_type()).unwrap(), outer(id));
+
+        // Converted back, the value keeps the definition order
+        let read = DynamicUdt::from_cql(value.to_cql_value(&outer_type()).unwrap()).unwrap();
+        assert_eq!(read.fields()[0].0, "id");
+        assert_eq!(read, DynamicUdt::from_cql(outer(id)).unwrap());
+
+        match value.to_cql_value(&inner_type()) {
+            Err(UdtValueError::WrongUdt { expected, found }) => {
+                assert_eq!(expected, "ks.inner");
+                assert_eq!(found, "ks.outer");
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        let mut nested_wrong = DynamicUdt::new("ks", "inner");
+        nested_wrong.set("a", "not an int");
+        let mut wrong = DynamicUdt::new("ks", "outer");
+        wrong.set("single", nested_wrong);
+        assert!(matches!(
+            wrong.to_cql_value(&outer_type()),
+            Err(UdtValueError::WrongFieldType { field, .. }) if field == "single"
+        ));
+
+        let mut unknown = DynamicUdt::new("ks", "inner");
+        unknown.set("c", 1);
+        assert!(matches!(
+            unknown.to_cql_value(&inner_type()),
+            Err(UdtValueError::NoSuchField { .. })
+        ));
+    }
+
+    #[test]
+    fn dynamic_udt_into_map() {
+        let value = DynamicUdt::from_cql(outer(Uuid::nil())).unwrap();
+        let map: BTreeMap<String, Option<CqlValue>> = value.clone().into();
+        assert_eq!(map.len(), 4);
+        assert_eq!(map["id"], Some(CqlValue::Timeuuid(Uuid::nil())));
+        assert_eq!(map["single"], None);
+        assert_eq!(&map["list"], &value.fields()[1].1);
+
+        assert_eq!(CqlValue::from(value), outer(Uuid::nil()));
+    }
+}
@@ -1,6 +1,7 @@
 //! CQL binary protocol in-wire types.
 
 use super::frame_errors::ParseError;
+use crate::types::serialize::redaction;
 use byteorder::{BigEndian, ReadBytesExt};
 use bytes::{Buf, BufMut};
 use num_enum::TryFromPrimitive;
@@ -104,13 +105,28 @@ impl From<std::array::TryFromSliceError> for ParseError {
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq)]
 pub enum RawValue<'a> {
     Null,
     Unset,
     Value(&'a [u8]),
 }
 
+/// Renders the value [redacted](crate::types::serialize::redaction) by default.
+impl std::fmt::Debug for RawValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RawValue::Null => f.write_str("Null"),
+            RawValue::Unset => f.write_str("Unset"),
+            RawValue::Value(_) => {
+                f.write_str("Value(")?;
+                redaction::fmt_raw_value(self, f)?;
+                f.write_str(")")
+            }
+        }
+    }
+}
+
 impl<'a> RawValue<'a> {
     #[inline]
     pub fn as_value(&self) -> Option<&'a [u8]> {
@@ -589,7 +605,7 @@ fn zig_zag_decode(v: u64) -> i64 {
     ((v >> 1) as i64) ^ -((v & 1) as i64)
 }
 
-fn unsigned_vint_encode(v: u64, buf: &mut Vec<u8>) {
+fn unsigned_vint_encode(v: u64, buf: &mut impl BufMut) {
     let mut v = v;
     let mut number_of_bytes = (639 - 9 * v.leading_zeros()) >> 6;
     if number_of_bytes <= 1 {
@@ -625,7 +641,9 @@ fn unsigned_vint_decode(buf: &mut &[u8]) -> Result<u64, ParseError> {
     Ok(v)
 }
 
-pub(crate) fn vint_encode(v: i64, buf: &mut Vec<u8>) {
+/// Encodes the value as a vint of at most 9 bytes. Writing into a fixed-size buffer,
+/// e.g. `&mut [u8]`, avoids allocations.
+pub(crate) fn vint_encode(v: i64, buf: &mut impl BufMut) {
     unsigned_vint_encode(zig_zag_encode(v), buf)
 }
 
@@ -904,3 +922,34 @@ fn vint_encode_and_decode_test() {
     check(-i64::MAX);
     check(i64::MIN)
 }
+
+#[test]
+fn vint_encode_into_slice_test() {
+    let check = |n: i64| {
+        let mut expected = Vec::new();
+        vint_encode(n, &mut expected);
+
+        let mut buf = [0u8; 9];
+        let mut rest = &mut buf[..];
+        vint_encode(n, &mut rest);
+        let len = 9 - rest.len();
+        assert_eq!(&buf[..len], expected.as_slice(), "{}", n);
+    };
+
+    for i in 0..64 {
+        let power = 1i64.wrapping_shl(i);
+        for v in [
+            power.wrapping_sub(1),
+            power,
+            power.wrapping_add(1),
+            power.wrapping_neg().wrapping_sub(1),
+            power.wrapping_neg(),
+            power.wrapping_neg().wrapping_add(1),
+        ] {
+            check(v);
+        }
+    }
+    check(0);
+    check(i64::MAX);
+    check(i64::MIN);
+}
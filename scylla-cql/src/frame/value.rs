@@ -1,5 +1,6 @@
 use crate::frame::frame_errors::ParseError;
 use crate::frame::types;
+use crate::types::serialize::redaction::RedactedValues;
 use bigdecimal::BigDecimal;
 use bytes::BufMut;
 use num_bigint::BigInt;
@@ -14,6 +15,7 @@ use uuid::Uuid;
 #[cfg(feature = "chrono")]
 use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
 
+use super::response::custom_types::CustomTypeRegistry;
 use super::response::result::CqlValue;
 use super::types::vint_encode;
 use super::types::RawValue;
@@ -43,6 +45,22 @@ pub struct Unset;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Counter(pub i64);
 
+/// Wraps an `f32` or an `f64`, typically a part of a primary key, to serialize it strictly.
+///
+/// Floating point values are serialized as their IEEE 754 bytes in big endian order,
+/// and the partition key is hashed by the database as it was serialized, so is the token
+/// computed by the driver. Thus, values which are equal in Rust may end up in different
+/// partitions, and NaN written to a key is hard to query back. A `StrictFloat`:
+/// - fails to serialize if the value is NaN, with
+///   [`BuiltinSerializationErrorKind::NanNotAllowed`](crate::types::serialize::value::BuiltinSerializationErrorKind::NanNotAllowed),
+/// - serializes `-0.0` as `0.0`, i.e. as `00 00 00 00 00 00 00 00` instead of `80 00 00 00 00 00 00 00`,
+/// - serializes other values, subnormal ones included, the same as the wrapped type.
+///
+/// Without it, `f64::NAN` is serialized as `7f f8 00 00 00 00 00 00`, and NaNs with other
+/// payloads keep their bits, so they are all different keys.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct StrictFloat<T>(pub T);
+
 /// Enum providing a way to represent a value that might be unset
 #[derive(Clone, Copy, Default)]
 pub enum MaybeUnset<V> {
@@ -257,13 +275,25 @@ impl TryInto<time::Time> for CqlTime {
 
 /// Keeps a buffer with serialized Values
 /// Allows adding new Values and iterating over serialized ones
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct LegacySerializedValues {
     serialized_values: Vec<u8>,
     values_num: u16,
     contains_names: bool,
 }
 
+/// Renders the values with [`RedactedValues`], so only their names and lengths are shown by default.
+impl std::fmt::Debug for LegacySerializedValues {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LegacySerializedValues")
+            .field(
+                "values",
+                &RedactedValues::named(self.iter_name_value_pairs()),
+            )
+            .finish()
+    }
+}
+
 /// Represents a CQL Duration value
 #[derive(Clone, Debug, Copy, PartialEq, Eq)]
 pub struct CqlDuration {
@@ -959,6 +989,7 @@ impl Value for CqlValue {
             CqlValue::List(v) | CqlValue::Set(v) => v.serialize(buf),
 
             CqlValue::Blob(b) => b.serialize(buf),
+            CqlValue::SharedBlob(b) => (&b[..]).serialize(buf),
             CqlValue::Boolean(b) => b.serialize(buf),
             CqlValue::Counter(c) => c.serialize(buf),
             CqlValue::Decimal(d) => d.serialize(buf),
@@ -973,6 +1004,21 @@ impl Value for CqlValue {
             CqlValue::Uuid(u) => u.serialize(buf),
             CqlValue::Varint(v) => v.serialize(buf),
 
+            CqlValue::Custom { class_name, .. } => {
+                // The legacy serialization can't report other errors, so values
+                // which can't be serialized by their codec are reported as too big
+                let codec = CustomTypeRegistry::global()
+                    .get(class_name)
+                    .ok_or(ValueTooBig)?;
+                let mut serialized = Vec::new();
+                codec
+                    .serialize(self, &mut serialized)
+                    .map_err(|_| ValueTooBig)?;
+                serialized.as_slice().serialize(buf)
+            }
+
+            CqlValue::Unknown { raw, .. } => raw.as_slice().serialize(buf),
+
             CqlValue::Empty => serialize_empty(buf),
         }
     }
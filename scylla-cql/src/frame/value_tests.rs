@@ -709,7 +709,8 @@ fn cqlvalue_serialization() {
         field_types: vec![
             ("foo".to_string(), ColumnType::Int),
             ("bar".to_string(), ColumnType::Text),
-        ],
+        ]
+        .into(),
     };
 
     assert_eq!(
@@ -1,12 +1,16 @@
 pub mod errors;
+pub mod execution_history;
 pub mod frame;
 #[macro_use]
 pub mod macros;
 
 pub mod types;
 
+#[doc(hidden)]
+pub mod utils;
+
 pub use crate::frame::response::cql_to_rust;
-pub use crate::frame::response::cql_to_rust::FromRow;
+pub use crate::frame::response::cql_to_rust::{FromRow, FromRowNamed};
 
 pub use crate::frame::types::Consistency;
 
@@ -35,7 +39,7 @@ pub mod _macro_internal {
         BuiltinTypeCheckErrorKind as BuiltinTypeTypeCheckErrorKind, SerializeCql,
         UdtSerializationErrorKind, UdtTypeCheckErrorKind,
     };
-    pub use crate::types::serialize::writers::WrittenCellProof;
+    pub use crate::types::serialize::writers::{NullOrUnset, WrittenCellProof};
     pub use crate::types::serialize::{
         CellValueBuilder, CellWriter, RowWriter, SerializationError,
     };
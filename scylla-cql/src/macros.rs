@@ -1,9 +1,23 @@
 /// #[derive(FromRow)] derives FromRow for struct
 /// Works only on simple structs without generics etc
+///
+/// # Field attributes
+///
+/// `#[scylla(null_as_default)]`
+///
+/// Converts null to the default value of the field's type instead of failing,
+/// e.g. to read an empty collection, which the database stores as null, as an empty `Vec`.
 pub use scylla_macros::FromRow;
 
 /// #[derive(FromUserType)] allows to parse struct as a User Defined Type
 /// Works only on simple structs without generics etc
+///
+/// # Field attributes
+///
+/// `#[scylla(null_as_default)]`
+///
+/// Converts null, or a field missing from the received value, to the default value
+/// of the field's type instead of failing.
 pub use scylla_macros::FromUserType;
 
 /// #[derive(IntoUserType)] allows to pass struct a User Defined Type Value in queries
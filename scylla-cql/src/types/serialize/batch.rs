@@ -57,7 +57,8 @@ pub trait BatchValuesIterator<'bv> {
     }
 }
 
-/// Implements `BatchValuesIterator` from an `Iterator` over references to things that implement `SerializeRow`
+/// Implements `BatchValuesIterator` from an `Iterator` over things that implement `SerializeRow`,
+/// either references or owned values.
 ///
 /// Essentially used internally by this lib to provide implementers of `BatchValuesIterator` for cases
 /// that always serialize the same concrete `SerializeRow` type
@@ -65,10 +66,10 @@ pub struct BatchValuesIteratorFromIterator<IT: Iterator> {
     it: IT,
 }
 
-impl<'bv, 'sr: 'bv, IT, SR> BatchValuesIterator<'bv> for BatchValuesIteratorFromIterator<IT>
+impl<'bv, IT> BatchValuesIterator<'bv> for BatchValuesIteratorFromIterator<IT>
 where
-    IT: Iterator<Item = &'sr SR>,
-    SR: SerializeRow + 'sr,
+    IT: Iterator,
+    IT::Item: SerializeRow,
 {
     #[inline]
     fn serialize_next(
@@ -113,16 +114,23 @@ where
 // BatchValues impls
 //
 
-/// Implements `BatchValues` from an `Iterator` over references to things that implement `SerializeRow`
+/// Implements `BatchValues` from an `Iterator` over things that implement `SerializeRow`
 ///
 /// This is to avoid requiring allocating a new `Vec` containing all the `SerializeRow`s directly:
 /// with this, one can write:
 /// `session.batch(&batch, BatchValuesFromIter::from(lines_to_insert.iter().map(|l| &l.value_list)))`
 /// where `lines_to_insert` may also contain e.g. data to pick the statement...
 ///
+/// The iterator may also produce the rows itself, e.g.
+/// `BatchValuesFromIterator::from((0..10_000).map(|i| (i, i.to_string())))`,
+/// in which case they are generated lazily while the batch is serialized.
+///
 /// The underlying iterator will always be cloned at least once, once to compute the length if it can't be known
-/// in advance, and be re-cloned at every retry.
+/// in advance, and be re-cloned at every retry, which then produces the rows again.
 /// It is consequently expected that the provided iterator is cheap to clone (e.g. `slice.iter().map(...)`).
+///
+/// The number of rows is checked against the number of statements in the batch while serializing it;
+/// a mismatch results in [`BadQuery::BatchValuesCountMismatch`](crate::errors::BadQuery::BatchValuesCountMismatch).
 pub struct BatchValuesFromIterator<'sr, IT> {
     it: IT,
 
@@ -132,10 +140,10 @@ pub struct BatchValuesFromIterator<'sr, IT> {
     _phantom: std::marker::PhantomData<&'sr ()>,
 }
 
-impl<'sr, IT, SR> BatchValuesFromIterator<'sr, IT>
+impl<'sr, IT> BatchValuesFromIterator<'sr, IT>
 where
-    IT: Iterator<Item = &'sr SR> + Clone,
-    SR: SerializeRow + 'sr,
+    IT: Iterator + Clone,
+    IT::Item: SerializeRow,
 {
     /// Creates a new `BatchValuesFromIter`` object.
     #[inline]
@@ -147,10 +155,10 @@ where
     }
 }
 
-impl<'sr, IT, SR> From<IT> for BatchValuesFromIterator<'sr, IT>
+impl<'sr, IT> From<IT> for BatchValuesFromIterator<'sr, IT>
 where
-    IT: Iterator<Item = &'sr SR> + Clone,
-    SR: SerializeRow + 'sr,
+    IT: Iterator + Clone,
+    IT::Item: SerializeRow,
 {
     #[inline]
     fn from(it: IT) -> Self {
@@ -158,10 +166,10 @@ where
     }
 }
 
-impl<'sr, IT, SR> BatchValues for BatchValuesFromIterator<'sr, IT>
+impl<'sr, IT> BatchValues for BatchValuesFromIterator<'sr, IT>
 where
-    IT: Iterator<Item = &'sr SR> + Clone,
-    SR: SerializeRow + 'sr,
+    IT: Iterator + Clone,
+    IT::Item: SerializeRow,
 {
     type BatchValuesIter<'r> = BatchValuesIteratorFromIterator<IT> where Self: 'r;
 
@@ -362,3 +370,99 @@ where
         self.0.skip_next()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use crate::frame::frame_errors::ParseError;
+    use crate::frame::request::batch::{Batch, BatchStatement, BatchType};
+    use crate::frame::request::{DeserializableRequest, SerializableRequest};
+    use crate::frame::response::result::{ColumnSpec, ColumnType, TableSpec};
+    use crate::types::serialize::raw_batch::RawBatchValuesAdapter;
+    use crate::types::serialize::row::{RowSerializationContext, SerializedValues};
+    use crate::Consistency;
+
+    use super::BatchValuesFromIterator;
+
+    fn col_spec(name: &str, typ: ColumnType) -> ColumnSpec {
+        ColumnSpec {
+            table_spec: TableSpec {
+                ks_name: "ks".to_string(),
+                table_name: "tbl".to_string(),
+            },
+            name: name.to_string(),
+            typ,
+        }
+    }
+
+    // Serializes a batch of `statements_count` statements inserting the generated rows.
+    fn serialize_generated_batch(
+        statements_count: usize,
+        rows_count: i32,
+    ) -> Result<Vec<u8>, ParseError> {
+        let columns = [
+            col_spec("a", ColumnType::Int),
+            col_spec("b", ColumnType::Text),
+        ];
+        let statements = vec![
+            BatchStatement::Prepared {
+                id: Cow::Borrowed(&[1, 2, 3]),
+            };
+            statements_count
+        ];
+        let values = BatchValuesFromIterator::from((0..rows_count).map(|i| (i, i.to_string())));
+        let contexts = (0..statements_count).map(|_| RowSerializationContext { columns: &columns });
+
+        let batch = Batch {
+            statements: Cow::Owned(statements),
+            batch_type: BatchType::Unlogged,
+            consistency: Consistency::One,
+            serial_consistency: None,
+            timestamp: None,
+            values: RawBatchValuesAdapter::new(values, contexts),
+            max_serialized_size: None,
+        };
+        let mut buf = Vec::new();
+        batch.serialize(&mut buf)?;
+
+        // Rows are generated anew for every serialization, e.g. when the batch is retried.
+        let mut buf_again = Vec::new();
+        batch.serialize(&mut buf_again)?;
+        assert_eq!(buf, buf_again);
+
+        Ok(buf)
+    }
+
+    #[test]
+    fn test_batch_values_from_generator() {
+        let buf = serialize_generated_batch(10_000, 10_000).unwrap();
+
+        let batch = Batch::deserialize(&mut &buf[..]).unwrap();
+        assert_eq!(batch.values.len(), 10_000);
+        for i in [0, 1234, 9999] {
+            let mut expected = SerializedValues::new();
+            expected.add_value(&i, &ColumnType::Int).unwrap();
+            expected
+                .add_value(&i.to_string(), &ColumnType::Text)
+                .unwrap();
+            assert_eq!(batch.values[i as usize], expected);
+        }
+    }
+
+    #[test]
+    fn test_batch_values_from_iterator_count_mismatch() {
+        for (statements_count, rows_count) in [(10, 9), (10, 12), (10, 0)] {
+            match serialize_generated_batch(statements_count, rows_count) {
+                Err(ParseError::BatchValuesCountMismatch {
+                    values_count,
+                    statements_count: reported_statements_count,
+                }) => {
+                    assert_eq!(values_count, rows_count as usize);
+                    assert_eq!(reported_statements_count, statements_count);
+                }
+                other => panic!("Expected BatchValuesCountMismatch, got {:?}", other),
+            }
+        }
+    }
+}
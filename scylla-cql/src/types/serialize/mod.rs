@@ -6,8 +6,11 @@ use std::{error::Error, fmt::Display, sync::Arc};
 
 use thiserror::Error;
 
+use crate::frame::value::SerializeValuesError;
+
 pub mod batch;
 pub mod raw_batch;
+pub mod redaction;
 pub mod row;
 pub mod value;
 pub mod writers;
@@ -28,10 +31,15 @@ pub use writers::{CellValueBuilder, CellWriter, RowWriter};
 ///   instead both in the case of builtin impls and impls generated by the
 ///   `SerializeCql` macro. It won't be returned by the `Session` directly,
 ///   but it might be nested in the [`row::BuiltinSerializationError`].
+/// - [`row::ValueCountMismatchError`] is returned when the number of values
+///   doesn't match the number of bind markers of a prepared statement.
 /// - [`row::ValueListToSerializeRowAdapterError`] is returned in case when
 ///   a list of named values encoded with the legacy `ValueList` trait is passed
 ///   as an argument to the statement, and rewriting it using the new
 ///   `SerializeRow` interface fails.
+///
+/// In order to handle the errors programmatically without downcasting them
+/// to each of the above types, use [`kind`](SerializationError::kind).
 #[derive(Debug, Clone, Error)]
 pub struct SerializationError(Arc<dyn Error + Send + Sync>);
 
@@ -41,6 +49,111 @@ impl SerializationError {
     pub fn new(err: impl Error + Send + Sync + 'static) -> SerializationError {
         SerializationError(Arc::new(err))
     }
+
+    /// Returns the underlying error if it's of the given type,
+    /// e.g. one of the errors returned by the impls built into the driver.
+    #[inline]
+    pub fn downcast_ref<E: Error + 'static>(&self) -> Option<&E> {
+        self.0.downcast_ref()
+    }
+
+    /// Returns a coarse classification of the error.
+    ///
+    /// Errors returned by the impls built into the driver or generated by
+    /// the `SerializeCql` and `SerializeRow` macros are classified according
+    /// to their cause. If the error was caused by a failure to serialize
+    /// a nested value, e.g. a column or an element of a collection,
+    /// the classification of the nested error is returned. Errors of other
+    /// types, e.g. the ones returned by custom impls, are classified
+    /// as [`SerializationErrorKind::Other`].
+    ///
+    /// ```rust
+    /// # use scylla_cql::frame::response::result::{ColumnSpec, ColumnType, TableSpec};
+    /// # use scylla_cql::types::serialize::{SerializationErrorKind, RowWriter};
+    /// # use scylla_cql::types::serialize::row::{RowSerializationContext, SerializeRow};
+    /// let columns = [ColumnSpec {
+    ///     table_spec: TableSpec {
+    ///         ks_name: "ks".to_owned(),
+    ///         table_name: "tbl".to_owned(),
+    ///     },
+    ///     name: "a".to_owned(),
+    ///     typ: ColumnType::TinyInt,
+    /// }];
+    /// let ctx = RowSerializationContext::from_specs(&columns);
+    /// let mut data = Vec::new();
+    ///
+    /// // An i32 can't be serialized as a tinyint
+    /// let err = (1000i32,)
+    ///     .serialize(&ctx, &mut RowWriter::new(&mut data))
+    ///     .unwrap_err();
+    /// assert_eq!(err.kind(), SerializationErrorKind::TypeMismatch);
+    /// ```
+    pub fn kind(&self) -> SerializationErrorKind {
+        fn classify<E: ClassifySerializationError + Error + 'static>(
+            err: &(dyn Error + Send + Sync + 'static),
+        ) -> Option<SerializationErrorKind> {
+            err.downcast_ref::<E>().map(E::classify)
+        }
+
+        let err = &*self.0;
+        classify::<row::BuiltinTypeCheckError>(err)
+            .or_else(|| classify::<row::BuiltinSerializationError>(err))
+            .or_else(|| classify::<row::ValueCountMismatchError>(err))
+            .or_else(|| classify::<row::ValueListToSerializeRowAdapterError>(err))
+            .or_else(|| classify::<value::BuiltinTypeCheckError>(err))
+            .or_else(|| classify::<value::BuiltinSerializationError>(err))
+            .or_else(|| classify::<value::ValueToSerializeCqlAdapterError>(err))
+            .or_else(|| classify::<writers::CellOverflowError>(err))
+            .or_else(|| classify::<SerializeValuesError>(err))
+            .unwrap_or(SerializationErrorKind::Other)
+    }
+}
+
+/// A coarse classification of a [`SerializationError`],
+/// returned by [`SerializationError::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SerializationErrorKind {
+    /// The Rust type can't be serialized as the CQL type, e.g. a string
+    /// was provided for an `int` column.
+    TypeMismatch,
+
+    /// The Rust value is of a compatible type, but the value itself can't
+    /// be serialized, e.g. it doesn't fit in the range of the CQL type
+    /// or it's a null element of a collection.
+    ValueOutOfRange,
+
+    /// The serialized value or the number of values exceeds the limits
+    /// of the protocol.
+    SizeLimit,
+
+    /// A value required by the statement or by a UDT is not provided.
+    MissingField,
+
+    /// A value is provided for a bind marker or a UDT field that does not exist.
+    ExtraField,
+
+    /// Any other failure, including the ones returned by custom impls
+    /// and the ones indicating malformed output of the legacy traits.
+    Other,
+}
+
+/// Implemented by the errors returned by the impls built into the driver,
+/// so that [`SerializationError::kind`] can classify them.
+pub(crate) trait ClassifySerializationError {
+    fn classify(&self) -> SerializationErrorKind;
+}
+
+impl ClassifySerializationError for SerializeValuesError {
+    fn classify(&self) -> SerializationErrorKind {
+        match self {
+            SerializeValuesError::TooManyValues | SerializeValuesError::ValueTooBig(_) => {
+                SerializationErrorKind::SizeLimit
+            }
+            SerializeValuesError::MixingNamedAndNotNamedValues
+            | SerializeValuesError::ParseError => SerializationErrorKind::Other,
+        }
+    }
 }
 
 impl Display for SerializationError {
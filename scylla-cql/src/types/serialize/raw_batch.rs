@@ -147,16 +147,18 @@ where
         self.batch_values_iterator.serialize_next(&ctx, writer)
     }
 
+    // Values are advanced regardless of the contexts, so that values left over
+    // after the contexts are exhausted are counted as well.
     fn is_empty_next(&mut self) -> Option<bool> {
-        self.contexts.next()?;
         let ret = self.batch_values_iterator.is_empty_next()?;
+        self.contexts.next();
         Some(ret)
     }
 
     #[inline]
     fn skip_next(&mut self) -> Option<()> {
-        self.contexts.next()?;
         self.batch_values_iterator.skip_next()?;
+        self.contexts.next();
         Some(())
     }
 }
@@ -0,0 +1,373 @@
+//! Rendering of bound values in logs and error messages.
+//!
+//! Bound values often contain data which must not end up in logs. Therefore, wherever
+//! the driver renders serialized values, e.g. in the `Debug` output of [`SerializedValues`],
+//! it does it through [`RedactedValues`], which by default renders only the types
+//! and lengths of the values, e.g. `[text(12), int, null]`.
+//!
+//! Full values can be rendered instead by setting the [global](ValueRedaction::set_global)
+//! redaction to [`ValueRedaction::Full`], which is meant for development only,
+//! or only while a future runs, with [`ValueRedaction::scope`]. The latter is how
+//! a session renders values according to its own setting.
+//!
+//! [`SerializedValues`]: super::row::SerializedValues
+
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::frame::response::result::{deser_cql_value, ColumnType};
+use crate::frame::types::RawValue;
+
+static FULL_VALUES: AtomicBool = AtomicBool::new(false);
+
+tokio::task_local! {
+    static SCOPED: ValueRedaction;
+}
+
+/// How bound values are rendered by [`RedactedValues`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValueRedaction {
+    /// Only types (if known) and lengths of values are rendered.
+    #[default]
+    Redacted,
+
+    /// Full values are rendered. Meant for development only.
+    Full,
+}
+
+impl ValueRedaction {
+    /// The redaction used by the `Debug` implementations of serialized values,
+    /// and by [`RedactedValues`] unless set with [`RedactedValues::with_redaction`].
+    /// It's shared by all sessions in the process.
+    pub fn global() -> ValueRedaction {
+        if FULL_VALUES.load(Ordering::Relaxed) {
+            ValueRedaction::Full
+        } else {
+            ValueRedaction::Redacted
+        }
+    }
+
+    /// Sets the [global](Self::global) redaction, for all sessions in the process.
+    /// No session changes it, so it's up to the application, e.g. to render full values
+    /// only in a development build.
+    pub fn set_global(redaction: ValueRedaction) {
+        FULL_VALUES.store(redaction == ValueRedaction::Full, Ordering::Relaxed);
+    }
+
+    /// The redaction set with [`scope`](Self::scope) for the current task, if any.
+    pub fn scoped() -> Option<ValueRedaction> {
+        SCOPED.try_with(|redaction| *redaction).ok()
+    }
+
+    /// The redaction used to render values: the [scoped](Self::scoped) one if set,
+    /// the [global](Self::global) one otherwise.
+    pub fn current() -> ValueRedaction {
+        Self::scoped().unwrap_or_else(Self::global)
+    }
+
+    /// Runs the future with this redaction overriding the [global](Self::global) one,
+    /// for values rendered by the task running it. Tasks spawned by the future don't
+    /// inherit it.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        SCOPED.scope(self, future).await
+    }
+}
+
+/// Renders bound values according to a [`ValueRedaction`], in both `Debug` and `Display`.
+///
+/// # Example
+/// ```
+/// # use scylla_cql::frame::response::result::ColumnType;
+/// # use scylla_cql::frame::types::RawValue;
+/// # use scylla_cql::types::serialize::redaction::{RedactedValues, ValueRedaction};
+/// let values = [
+///     RawValue::Value(b"Hello, World"),
+///     RawValue::Value(&[0, 0, 0, 42]),
+///     RawValue::Null,
+/// ];
+/// let types = [ColumnType::Text, ColumnType::Int, ColumnType::Int];
+///
+/// let redacted = RedactedValues::new(values).with_types(&types);
+/// assert_eq!(
+///     redacted.with_redaction(ValueRedaction::Redacted).to_string(),
+///     "[text(12), int, null]"
+/// );
+/// ```
+#[derive(Clone)]
+pub struct RedactedValues<'a> {
+    values: Vec<(Option<&'a str>, RawValue<'a>)>,
+    types: Option<&'a [ColumnType]>,
+    redaction: Option<ValueRedaction>,
+}
+
+impl<'a> RedactedValues<'a> {
+    /// Creates a renderer of the values.
+    pub fn new(values: impl IntoIterator<Item = RawValue<'a>>) -> Self {
+        Self::named(values.into_iter().map(|value| (None, value)))
+    }
+
+    /// Creates a renderer of the values, which are rendered with their names if they have any.
+    pub fn named(values: impl IntoIterator<Item = (Option<&'a str>, RawValue<'a>)>) -> Self {
+        Self {
+            values: values.into_iter().collect(),
+            types: None,
+            redaction: None,
+        }
+    }
+
+    /// Sets the types of the values, in the same order. Without types,
+    /// only the lengths of values are rendered, and full values are rendered as bytes.
+    pub fn with_types(mut self, types: &'a [ColumnType]) -> Self {
+        self.types = Some(types);
+        self
+    }
+
+    /// Sets the redaction, overriding the [current](ValueRedaction::current) one.
+    pub fn with_redaction(mut self, redaction: ValueRedaction) -> Self {
+        self.redaction = Some(redaction);
+        self
+    }
+}
+
+impl fmt::Display for RedactedValues<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[")?;
+        for (idx, (name, value)) in self.values.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            if let Some(name) = name {
+                write!(f, "{}: ", name)?;
+            }
+            let typ = self.types.and_then(|types| types.get(idx));
+            let redaction = self.redaction.unwrap_or_else(ValueRedaction::current);
+            fmt_value(value, typ, redaction, f)?;
+        }
+        f.write_str("]")
+    }
+}
+
+impl fmt::Debug for RedactedValues<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Renders a single value, without its type, with the current redaction.
+pub(crate) fn fmt_raw_value(value: &RawValue<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt_value(value, None, ValueRedaction::current(), f)
+}
+
+fn fmt_value(
+    value: &RawValue<'_>,
+    typ: Option<&ColumnType>,
+    redaction: ValueRedaction,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let bytes = match value {
+        RawValue::Null => return f.write_str("null"),
+        RawValue::Unset => return f.write_str("unset"),
+        RawValue::Value(bytes) => *bytes,
+    };
+    match (redaction, typ) {
+        (ValueRedaction::Redacted, Some(typ)) if has_fixed_size(typ) => {
+            write!(f, "{}", CqlTypeName(typ))
+        }
+        (ValueRedaction::Redacted, Some(typ)) => write!(f, "{}({})", CqlTypeName(typ), bytes.len()),
+        (ValueRedaction::Redacted, None) => write!(f, "value({})", bytes.len()),
+        (ValueRedaction::Full, Some(typ)) => match deser_cql_value(typ, &mut &*bytes) {
+            Ok(value) => write!(f, "{:?}", value),
+            Err(_) => write!(f, "{}({:02x?})", CqlTypeName(typ), bytes),
+        },
+        (ValueRedaction::Full, None) => write!(f, "{:02x?}", bytes),
+    }
+}
+
+// Values of these types always have the same length, so it isn't worth rendering.
+fn has_fixed_size(typ: &ColumnType) -> bool {
+    matches!(
+        typ,
+        ColumnType::Boolean
+            | ColumnType::Counter
+            | ColumnType::Date
+            | ColumnType::Double
+            | ColumnType::Float
+            | ColumnType::Int
+            | ColumnType::BigInt
+            | ColumnType::Timestamp
+            | ColumnType::SmallInt
+            | ColumnType::TinyInt
+            | ColumnType::Time
+            | ColumnType::Timeuuid
+            | ColumnType::Uuid
+    )
+}
+
+// Renders a type the way it's written in CQL, e.g. `map<text, int>`.
+struct CqlTypeName<'a>(&'a ColumnType);
+
+impl fmt::Display for CqlTypeName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.0 {
+            ColumnType::Custom(class_name) => return write!(f, "'{}'", class_name),
+            ColumnType::Ascii => "ascii",
+            ColumnType::Boolean => "boolean",
+            ColumnType::Blob => "blob",
+            ColumnType::Counter => "counter",
+            ColumnType::Date => "date",
+            ColumnType::Decimal => "decimal",
+            ColumnType::Double => "double",
+            ColumnType::Duration => "duration",
+            ColumnType::Float => "float",
+            ColumnType::Int => "int",
+            ColumnType::BigInt => "bigint",
+            ColumnType::Text => "text",
+            ColumnType::Timestamp => "timestamp",
+            ColumnType::Inet => "inet",
+            ColumnType::List(elem) => return write!(f, "list<{}>", CqlTypeName(elem)),
+            ColumnType::Map(key, value) => {
+                return write!(f, "map<{}, {}>", CqlTypeName(key), CqlTypeName(value))
+            }
+            ColumnType::Set(elem) => return write!(f, "set<{}>", CqlTypeName(elem)),
+            ColumnType::UserDefinedType { type_name, .. } => type_name,
+            ColumnType::SmallInt => "smallint",
+            ColumnType::TinyInt => "tinyint",
+            ColumnType::Time => "time",
+            ColumnType::Timeuuid => "timeuuid",
+            ColumnType::Tuple(elems) => {
+                f.write_str("tuple<")?;
+                for (idx, elem) in elems.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", CqlTypeName(elem))?;
+                }
+                return f.write_str(">");
+            }
+            ColumnType::Uuid => "uuid",
+            ColumnType::Varint => "varint",
+            ColumnType::Unknown { id, .. } => return write!(f, "unknown({:#06x})", id),
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RedactedValues, ValueRedaction};
+    use crate::frame::response::result::ColumnType;
+    use crate::frame::types::RawValue;
+    use crate::frame::value::LegacySerializedValues;
+    use crate::types::serialize::row::SerializedValues;
+
+    const SECRET: &str = "very secret data";
+
+    fn secret_values() -> SerializedValues {
+        let mut values = SerializedValues::new();
+        values.add_value(&SECRET, &ColumnType::Text).unwrap();
+        values.add_value(&42_i32, &ColumnType::Int).unwrap();
+        values.add_value(&None::<i32>, &ColumnType::Int).unwrap();
+        values
+            .add_value(&vec![SECRET], &ColumnType::List(Box::new(ColumnType::Text)))
+            .unwrap();
+        values
+    }
+
+    fn assert_no_secret(rendered: &str) {
+        assert!(!rendered.contains(SECRET), "{}", rendered);
+        // Neither as bytes
+        let bytes = format!("{:?}", SECRET.as_bytes());
+        assert!(
+            !rendered.contains(&bytes[1..bytes.len() - 1]),
+            "{}",
+            rendered
+        );
+        let hex = format!("{:02x?}", SECRET.as_bytes());
+        assert!(!rendered.contains(&hex[1..hex.len() - 1]), "{}", rendered);
+        assert!(!rendered.contains("42"), "{}", rendered);
+    }
+
+    #[test]
+    fn values_are_redacted_by_default() {
+        let values = secret_values();
+        assert_eq!(ValueRedaction::global(), ValueRedaction::Redacted);
+
+        let debug = format!("{:?}", values);
+        assert_no_secret(&debug);
+        assert_eq!(
+            debug,
+            "SerializedValues { values: [value(16), value(4), null, value(24)] }"
+        );
+        assert_no_secret(&format!("{:#?}", values));
+        for value in values.iter() {
+            assert_no_secret(&format!("{:?}", value));
+        }
+
+        let mut legacy = LegacySerializedValues::new();
+        legacy.add_named_value("secret", &SECRET).unwrap();
+        assert_no_secret(&format!("{:?}", legacy));
+
+        let types = [
+            ColumnType::Text,
+            ColumnType::Int,
+            ColumnType::Int,
+            ColumnType::List(Box::new(ColumnType::Text)),
+        ];
+        let typed = RedactedValues::new(values.iter()).with_types(&types);
+        assert_no_secret(&typed.to_string());
+        assert_eq!(typed.to_string(), "[text(16), int, null, list<text>(24)]");
+    }
+
+    #[test]
+    fn full_values_are_rendered_on_request() {
+        let values = secret_values();
+        let types = [
+            ColumnType::Text,
+            ColumnType::Int,
+            ColumnType::Int,
+            ColumnType::List(Box::new(ColumnType::Text)),
+        ];
+        let full = RedactedValues::new(values.iter())
+            .with_types(&types)
+            .with_redaction(ValueRedaction::Full);
+        assert_eq!(
+            full.to_string(),
+            format!(
+                "[Text(\"{0}\"), Int(42), null, List([Text(\"{0}\")])]",
+                SECRET
+            )
+        );
+
+        let untyped = RedactedValues::new([RawValue::Value(&[0xca, 0xfe]), RawValue::Unset])
+            .with_redaction(ValueRedaction::Full);
+        assert_eq!(untyped.to_string(), "[[ca, fe], unset]");
+    }
+
+    #[tokio::test]
+    async fn scoped_redaction_applies_only_within_scope() {
+        let values = secret_values();
+        assert_eq!(ValueRedaction::scoped(), None);
+
+        let debug = ValueRedaction::Full
+            .scope(async {
+                assert_eq!(ValueRedaction::current(), ValueRedaction::Full);
+                format!("{:?}", values)
+            })
+            .await;
+        let full = RedactedValues::new(values.iter()).with_redaction(ValueRedaction::Full);
+        assert_eq!(debug, format!("SerializedValues {{ values: {} }}", full));
+
+        // Outside of the scope, and in tasks spawned within it, values are redacted again
+        assert_no_secret(&format!("{:?}", values));
+        let spawned = ValueRedaction::Full
+            .scope(async {
+                let values = secret_values();
+                tokio::spawn(async move { format!("{:?}", values) })
+                    .await
+                    .unwrap()
+            })
+            .await;
+        assert_no_secret(&spawned);
+    }
+}
@@ -17,8 +17,11 @@ use crate::frame::value::SerializeValuesError;
 use crate::frame::value::{LegacySerializedValues, ValueList};
 use crate::frame::{response::result::ColumnSpec, types::RawValue};
 
+use super::redaction::RedactedValues;
 use super::value::SerializeCql;
-use super::{CellWriter, RowWriter, SerializationError};
+use super::{
+    CellWriter, ClassifySerializationError, RowWriter, SerializationError, SerializationErrorKind,
+};
 
 /// Contains information needed to serialize a row.
 pub struct RowSerializationContext<'a> {
@@ -41,17 +44,124 @@ impl<'a> RowSerializationContext<'a> {
         Self { columns: &[] }
     }
 
+    /// Creates the serialization context from specifications of the bind markers,
+    /// e.g. to test a [`SerializeRow`] impl.
+    #[inline]
+    pub const fn from_specs(columns: &'a [ColumnSpec]) -> Self {
+        Self { columns }
+    }
+
     /// Returns column/bind marker specifications for given query.
     #[inline]
     pub fn columns(&self) -> &'a [ColumnSpec] {
         self.columns
     }
 
-    /// Looks up and returns a column/bind marker by name.
+    /// Looks up a column/bind marker by name and returns its index,
+    /// i.e. the position of its value in the row, along with its specification.
     // TODO: change RowSerializationContext to make this faster
     #[inline]
-    pub fn column_by_name(&self, target: &str) -> Option<&ColumnSpec> {
-        self.columns.iter().find(|&c| c.name == target)
+    pub fn column_by_name(&self, target: &str) -> Option<(usize, &'a ColumnSpec)> {
+        self.columns
+            .iter()
+            .enumerate()
+            .find(|(_, c)| c.name == target)
+    }
+}
+
+impl RowWriter<'_> {
+    /// Type checks and writes the value of the column/bind marker with the given name.
+    ///
+    /// Values have to be written in the order of the columns in the context, so the named
+    /// column must be the one following the columns written so far. Otherwise, or if there
+    /// is no column with this name, a [`BuiltinTypeCheckError`] is returned: a column
+    /// which was skipped is reported as [`ValueMissingForColumn`](BuiltinTypeCheckErrorKind::ValueMissingForColumn).
+    /// If the value doesn't match the type of the column, [`BuiltinSerializationError`]
+    /// is returned. Errors are named after the type `T` of the value.
+    ///
+    /// # Example
+    /// A manual impl for a row whose columns are known only at runtime:
+    /// ```
+    /// # use scylla_cql::frame::response::result::{ColumnSpec, ColumnType, TableSpec};
+    /// use scylla_cql::types::serialize::row::{RowSerializationContext, SerializeRow};
+    /// use scylla_cql::types::serialize::{RowWriter, SerializationError};
+    /// use std::collections::HashMap;
+    ///
+    /// struct DynamicRow(HashMap<String, i32>);
+    ///
+    /// impl SerializeRow for DynamicRow {
+    ///     fn serialize(
+    ///         &self,
+    ///         ctx: &RowSerializationContext<'_>,
+    ///         writer: &mut RowWriter,
+    ///     ) -> Result<(), SerializationError> {
+    ///         for column in ctx.columns() {
+    ///             // Columns missing from the map are null
+    ///             let value = self.0.get(&column.name);
+    ///             writer.write_cell_for(ctx, &column.name, &value)?;
+    ///         }
+    ///         Ok(())
+    ///     }
+    ///
+    ///     fn is_empty(&self) -> bool {
+    ///         self.0.is_empty()
+    ///     }
+    /// }
+    ///
+    /// # let col = |name: &str| ColumnSpec {
+    /// #     table_spec: TableSpec { ks_name: "ks".to_string(), table_name: "t".to_string() },
+    /// #     name: name.to_string(),
+    /// #     typ: ColumnType::Int,
+    /// # };
+    /// let columns = [col("a"), col("b")];
+    /// let ctx = RowSerializationContext::from_specs(&columns);
+    ///
+    /// let row = DynamicRow(HashMap::from([("b".to_string(), 7)]));
+    /// let mut data = Vec::new();
+    /// row.serialize(&ctx, &mut RowWriter::new(&mut data)).unwrap();
+    /// // A null followed by an int
+    /// assert_eq!(data, [255, 255, 255, 255, 0, 0, 0, 4, 0, 0, 0, 7]);
+    /// ```
+    pub fn write_cell_for<T: SerializeCql + ?Sized>(
+        &mut self,
+        ctx: &RowSerializationContext<'_>,
+        name: &str,
+        value: &T,
+    ) -> Result<(), SerializationError> {
+        let (index, spec) = ctx.column_by_name(name).ok_or_else(|| {
+            mk_typck_err::<T>(BuiltinTypeCheckErrorKind::NoColumnWithName {
+                name: name.to_owned(),
+            })
+        })?;
+
+        let next_index = self.value_count();
+        if index != next_index {
+            let kind = match ctx.columns().get(next_index) {
+                Some(next) if index > next_index => {
+                    BuiltinTypeCheckErrorKind::ValueMissingForColumn {
+                        name: next.name.clone(),
+                    }
+                }
+                Some(next) => BuiltinTypeCheckErrorKind::ColumnNameMismatch {
+                    rust_column_name: name.to_owned(),
+                    db_column_name: next.name.clone(),
+                },
+                None => BuiltinTypeCheckErrorKind::WrongColumnCount {
+                    actual: next_index + 1,
+                    asked_for: ctx.columns().len(),
+                },
+            };
+            return Err(mk_typck_err::<T>(kind));
+        }
+
+        let cell_writer = make_cell_writer_for(self, value, &spec.typ);
+        value.serialize(&spec.typ, cell_writer).map_err(|err| {
+            mk_ser_err::<T>(BuiltinSerializationErrorKind::ColumnSerializationFailed {
+                name: name.to_owned(),
+                err,
+            })
+        })?;
+        Ok(())
     }
 }
 
@@ -82,6 +192,20 @@ pub trait SerializeRow {
     fn is_empty(&self) -> bool;
 }
 
+/// Appends a new value to the row and returns a writer for it, with space
+/// reserved for the value if its size can be estimated.
+fn make_cell_writer_for<'w, T: SerializeCql + ?Sized>(
+    writer: &'w mut RowWriter,
+    value: &T,
+    typ: &ColumnType,
+) -> CellWriter<'w> {
+    let mut cell_writer = writer.make_cell_writer();
+    if let Some(size) = value.estimated_size(typ) {
+        cell_writer.reserve(size);
+    }
+    cell_writer
+}
+
 macro_rules! fallback_impl_contents {
     () => {
         fn serialize(
@@ -148,16 +272,13 @@ macro_rules! impl_serialize_row_for_slice {
                 ));
             }
             for (col, val) in ctx.columns().iter().zip(self.iter()) {
-                <T as SerializeCql>::serialize(val, &col.typ, writer.make_cell_writer()).map_err(
-                    |err| {
-                        mk_ser_err::<Self>(
-                            BuiltinSerializationErrorKind::ColumnSerializationFailed {
-                                name: col.name.clone(),
-                                err,
-                            },
-                        )
-                    },
-                )?;
+                let cell_writer = make_cell_writer_for(writer, val, &col.typ);
+                <T as SerializeCql>::serialize(val, &col.typ, cell_writer).map_err(|err| {
+                    mk_ser_err::<Self>(BuiltinSerializationErrorKind::ColumnSerializationFailed {
+                        name: col.name.clone(),
+                        err,
+                    })
+                })?;
             }
             Ok(())
         }
@@ -199,15 +320,17 @@ macro_rules! impl_serialize_row_for_map {
                         ))
                     }
                     Some(v) => {
-                        <T as SerializeCql>::serialize(v, &col.typ, writer.make_cell_writer())
-                            .map_err(|err| {
+                        let cell_writer = make_cell_writer_for(writer, v, &col.typ);
+                        <T as SerializeCql>::serialize(v, &col.typ, cell_writer).map_err(
+                            |err| {
                                 mk_ser_err::<Self>(
                                     BuiltinSerializationErrorKind::ColumnSerializationFailed {
                                         name: col.name.clone(),
                                         err,
                                     },
                                 )
-                            })?;
+                            },
+                        )?;
                         let _ = unused_columns.remove(col.name.as_str());
                     }
                 }
@@ -296,7 +419,8 @@ macro_rules! impl_tuple {
                 };
                 let ($($fidents,)*) = self;
                 $(
-                    <$typs as SerializeCql>::serialize($fidents, &$tidents.typ, writer.make_cell_writer()).map_err(|err| {
+                    let cell_writer = make_cell_writer_for(writer, $fidents, &$tidents.typ);
+                    <$typs as SerializeCql>::serialize($fidents, &$tidents.typ, cell_writer).map_err(|err| {
                         mk_ser_err::<Self>(BuiltinSerializationErrorKind::ColumnSerializationFailed {
                             name: $tidents.name.clone(),
                             err,
@@ -538,7 +662,7 @@ pub struct BuiltinTypeCheckError {
     pub kind: BuiltinTypeCheckErrorKind,
 }
 
-fn mk_typck_err<T>(kind: impl Into<BuiltinTypeCheckErrorKind>) -> SerializationError {
+fn mk_typck_err<T: ?Sized>(kind: impl Into<BuiltinTypeCheckErrorKind>) -> SerializationError {
     mk_typck_err_named(std::any::type_name::<T>(), kind)
 }
 
@@ -564,7 +688,7 @@ pub struct BuiltinSerializationError {
     pub kind: BuiltinSerializationErrorKind,
 }
 
-fn mk_ser_err<T>(kind: impl Into<BuiltinSerializationErrorKind>) -> SerializationError {
+fn mk_ser_err<T: ?Sized>(kind: impl Into<BuiltinSerializationErrorKind>) -> SerializationError {
     mk_ser_err_named(std::any::type_name::<T>(), kind)
 }
 
@@ -641,6 +765,33 @@ impl Display for BuiltinTypeCheckErrorKind {
     }
 }
 
+impl ClassifySerializationError for BuiltinTypeCheckError {
+    fn classify(&self) -> SerializationErrorKind {
+        match &self.kind {
+            BuiltinTypeCheckErrorKind::WrongColumnCount { actual, asked_for } => {
+                count_mismatch_kind(*actual, *asked_for)
+            }
+            BuiltinTypeCheckErrorKind::NoColumnWithName { .. } => {
+                SerializationErrorKind::ExtraField
+            }
+            BuiltinTypeCheckErrorKind::ValueMissingForColumn { .. } => {
+                SerializationErrorKind::MissingField
+            }
+            BuiltinTypeCheckErrorKind::ColumnNameMismatch { .. } => {
+                SerializationErrorKind::TypeMismatch
+            }
+        }
+    }
+}
+
+fn count_mismatch_kind(actual: usize, expected: usize) -> SerializationErrorKind {
+    if actual < expected {
+        SerializationErrorKind::MissingField
+    } else {
+        SerializationErrorKind::ExtraField
+    }
+}
+
 /// Describes why serializing values for a statement failed.
 #[derive(Debug, Clone)]
 #[non_exhaustive]
@@ -665,6 +816,63 @@ impl Display for BuiltinSerializationErrorKind {
     }
 }
 
+impl ClassifySerializationError for BuiltinSerializationError {
+    fn classify(&self) -> SerializationErrorKind {
+        match &self.kind {
+            BuiltinSerializationErrorKind::ColumnSerializationFailed { err, .. } => err.kind(),
+        }
+    }
+}
+
+/// The number of values bound to a statement differs from the number
+/// of its bind markers.
+///
+/// Returned by [`SerializedValues::from_serializable`] before the values
+/// are sent, instead of the type check error of the Rust type (if any).
+#[derive(Error, Debug, Clone)]
+#[error(
+    "Wrong number of values bound to the statement: it has {expected} bind markers{}, but {actual} values were provided",
+    DisplayColumnNames(column_names)
+)]
+pub struct ValueCountMismatchError {
+    /// The number of bind markers of the statement.
+    pub expected: usize,
+
+    /// The number of values provided.
+    pub actual: usize,
+
+    /// Names of the bind markers, in order. Empty if they are not known,
+    /// e.g. for unprepared statements.
+    pub column_names: Vec<String>,
+}
+
+impl ValueCountMismatchError {
+    fn for_columns(columns: &[ColumnSpec], actual: usize) -> Self {
+        ValueCountMismatchError {
+            expected: columns.len(),
+            actual,
+            column_names: columns.iter().map(|col| col.name.clone()).collect(),
+        }
+    }
+}
+
+impl ClassifySerializationError for ValueCountMismatchError {
+    fn classify(&self) -> SerializationErrorKind {
+        count_mismatch_kind(self.actual, self.expected)
+    }
+}
+
+struct DisplayColumnNames<'a>(&'a [String]);
+
+impl Display for DisplayColumnNames<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        write!(f, " ({})", self.0.join(", "))
+    }
+}
+
 /// Describes a failure to translate the output of the [`ValueList`] legacy trait
 /// into an output of the [`SerializeRow`] trait.
 #[derive(Error, Debug)]
@@ -688,6 +896,19 @@ pub enum ValueListToSerializeRowAdapterError {
     },
 }
 
+impl ClassifySerializationError for ValueListToSerializeRowAdapterError {
+    fn classify(&self) -> SerializationErrorKind {
+        match self {
+            ValueListToSerializeRowAdapterError::ValueMissingForBindMarker { .. } => {
+                SerializationErrorKind::MissingField
+            }
+            ValueListToSerializeRowAdapterError::NoBindMarkerWithName { .. } => {
+                SerializationErrorKind::ExtraField
+            }
+        }
+    }
+}
+
 /// A buffer containing already serialized values.
 ///
 /// It is not aware of the types of contained values,
@@ -695,12 +916,22 @@ pub enum ValueListToSerializeRowAdapterError {
 /// Usually there is no need for a user of a driver to use this struct, it is mostly internal.
 /// The exception are APIs like `ClusterData::compute_token` / `ClusterData::get_endpoints`.
 /// Allows adding new values to the buffer and iterating over the content.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct SerializedValues {
     serialized_values: Vec<u8>,
     element_count: u16,
 }
 
+/// Renders the values with [`RedactedValues`](super::redaction::RedactedValues),
+/// so only their lengths are shown by default.
+impl std::fmt::Debug for SerializedValues {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SerializedValues")
+            .field("values", &RedactedValues::new(self.iter()))
+            .finish()
+    }
+}
+
 impl SerializedValues {
     /// Constructs a new, empty `SerializedValues`.
     pub const fn new() -> Self {
@@ -714,11 +945,46 @@ impl SerializedValues {
     pub const EMPTY: &'static SerializedValues = &SerializedValues::new();
 
     /// Constructs `SerializedValues` from given [`SerializeRow`] object.
+    ///
+    /// Fails with [`ValueCountMismatchError`] if the number of serialized values
+    /// differs from the number of columns in the context.
     pub fn from_serializable<T: SerializeRow>(
         ctx: &RowSerializationContext,
         row: &T,
     ) -> Result<Self, SerializationError> {
-        Self::from_closure(|writer| row.serialize(ctx, writer)).map(|(sr, _)| sr)
+        Self::from_serializable_in(Vec::new(), ctx, row)
+    }
+
+    /// Same as [`SerializedValues::from_serializable`], but serializes the values
+    /// into the given buffer, reusing its capacity. The previous contents
+    /// of the buffer are discarded.
+    pub fn from_serializable_in<T: SerializeRow>(
+        buffer: Vec<u8>,
+        ctx: &RowSerializationContext,
+        row: &T,
+    ) -> Result<Self, SerializationError> {
+        let values = Self::from_closure_in(buffer, |writer| row.serialize(ctx, writer))
+            .map_err(|err| match err.0.downcast_ref::<BuiltinTypeCheckError>() {
+                Some(BuiltinTypeCheckError {
+                    kind: BuiltinTypeCheckErrorKind::WrongColumnCount { actual, .. },
+                    ..
+                }) => SerializationError::new(ValueCountMismatchError::for_columns(
+                    ctx.columns(),
+                    *actual,
+                )),
+                _ => err,
+            })?
+            .0;
+        // Impls which don't check the count themselves, e.g. the legacy ones
+        if values.element_count() as usize != ctx.columns().len() {
+            return Err(SerializationError::new(
+                ValueCountMismatchError::for_columns(
+                    ctx.columns(),
+                    values.element_count() as usize,
+                ),
+            ));
+        }
+        Ok(values)
     }
 
     /// Constructs `SerializedValues` via given closure.
@@ -726,7 +992,14 @@ impl SerializedValues {
     where
         F: FnOnce(&mut RowWriter) -> Result<R, SerializationError>,
     {
-        let mut data = Vec::new();
+        Self::from_closure_in(Vec::new(), f)
+    }
+
+    fn from_closure_in<F, R>(mut data: Vec<u8>, f: F) -> Result<(Self, R), SerializationError>
+    where
+        F: FnOnce(&mut RowWriter) -> Result<R, SerializationError>,
+    {
+        data.clear();
         let mut writer = RowWriter::new(&mut data);
         let ret = f(&mut writer)?;
         let element_count = match writer.value_count().try_into() {
@@ -773,6 +1046,12 @@ impl SerializedValues {
         self.serialized_values.len()
     }
 
+    /// Returns the buffer holding the serialized values, so that it can be reused
+    /// with [`SerializedValues::from_serializable_in`].
+    pub fn into_buffer(self) -> Vec<u8> {
+        self.serialized_values
+    }
+
     pub(crate) fn write_to_request(&self, buf: &mut impl BufMut) {
         buf.put_u16(self.element_count);
         buf.put(self.serialized_values.as_slice())
@@ -797,7 +1076,10 @@ impl SerializedValues {
 
         let len_before_serialize: usize = self.serialized_values.len();
 
-        let writer = CellWriter::new(&mut self.serialized_values);
+        let mut writer = CellWriter::new(&mut self.serialized_values);
+        if let Some(size) = val.estimated_size(typ) {
+            writer.reserve(size);
+        }
         if let Err(e) = val.serialize(typ, writer) {
             self.serialized_values.resize(len_before_serialize, 0);
             Err(e)
@@ -855,13 +1137,20 @@ mod tests {
 
     use crate::frame::response::result::{ColumnSpec, ColumnType, TableSpec};
     use crate::frame::types::RawValue;
-    use crate::frame::value::{LegacySerializedValues, MaybeUnset, SerializedResult, ValueList};
+    use crate::frame::value::{
+        LegacySerializedValues, MaybeUnset, SerializeValuesError, SerializedResult, ValueList,
+        ValueTooBig,
+    };
     use crate::types::serialize::row::ValueListAdapter;
-    use crate::types::serialize::{RowWriter, SerializationError};
+    use crate::types::serialize::writers::WrittenCellProof;
+    use crate::types::serialize::{
+        CellWriter, RowWriter, SerializationError, SerializationErrorKind,
+    };
 
     use super::{
         BuiltinSerializationError, BuiltinSerializationErrorKind, BuiltinTypeCheckError,
         BuiltinTypeCheckErrorKind, RowSerializationContext, SerializeCql, SerializeRow,
+        ValueCountMismatchError, ValueListToSerializeRowAdapterError,
     };
 
     use super::SerializedValues;
@@ -944,6 +1233,53 @@ mod tests {
         assert_eq!(&sorted_row_data[2..], unsorted_row_data);
     }
 
+    fn get_count_mismatch_err(err: &SerializationError) -> &ValueCountMismatchError {
+        match err.0.downcast_ref() {
+            Some(err) => err,
+            None => panic!("not a ValueCountMismatchError: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn test_value_count_mismatch() {
+        let ctx = RowSerializationContext {
+            columns: &[
+                col_spec("a", ColumnType::Int),
+                col_spec("b", ColumnType::Text),
+            ],
+        };
+
+        // Too many values
+        let err = SerializedValues::from_serializable(&ctx, &(1i32, "Ala", "kota")).unwrap_err();
+        let err = get_count_mismatch_err(&err);
+        assert_eq!((err.expected, err.actual), (2, 3));
+        assert_eq!(err.column_names, ["a", "b"]);
+        assert_eq!(
+            err.to_string(),
+            "Wrong number of values bound to the statement: it has 2 bind markers (a, b), but 3 values were provided"
+        );
+
+        // Too few values
+        let err = SerializedValues::from_serializable(&ctx, &(1i32,)).unwrap_err();
+        let err = get_count_mismatch_err(&err);
+        assert_eq!((err.expected, err.actual), (2, 1));
+        let err = SerializedValues::from_serializable(&ctx, &()).unwrap_err();
+        assert_eq!(get_count_mismatch_err(&err).actual, 0);
+
+        // The legacy impls don't check the count themselves
+        let mut legacy = LegacySerializedValues::new();
+        legacy.add_value(&1i32).unwrap();
+        let err = SerializedValues::from_serializable(&ctx, &legacy).unwrap_err();
+        let err = get_count_mismatch_err(&err);
+        assert_eq!((err.expected, err.actual), (2, 1));
+
+        // Errors other than the count mismatch are passed through
+        let err = SerializedValues::from_serializable(&ctx, &(1i32, 2i32)).unwrap_err();
+        assert!(err.0.downcast_ref::<ValueCountMismatchError>().is_none());
+
+        SerializedValues::from_serializable(&ctx, &(1i32, "Ala")).unwrap();
+    }
+
     #[test]
     fn test_dyn_serialize_row() {
         let row = (
@@ -977,6 +1313,247 @@ mod tests {
         assert_eq!(typed_data, erased_data);
     }
 
+    #[test]
+    fn test_column_by_name() {
+        let columns = [
+            col_spec("a", ColumnType::Int),
+            col_spec("b", ColumnType::Text),
+        ];
+        let ctx = RowSerializationContext::from_specs(&columns);
+        let (index, spec) = ctx.column_by_name("b").unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(spec.typ, ColumnType::Text);
+        assert!(ctx.column_by_name("c").is_none());
+    }
+
+    #[test]
+    fn test_write_cell_for() {
+        let columns = [
+            col_spec("a", ColumnType::Int),
+            col_spec("b", ColumnType::Text),
+            col_spec("c", ColumnType::BigInt),
+        ];
+        let ctx = RowSerializationContext::from_specs(&columns);
+
+        let mut data = Vec::new();
+        let mut writer = RowWriter::new(&mut data);
+        writer.write_cell_for(&ctx, "a", &1i32).unwrap();
+        writer.write_cell_for(&ctx, "b", &"Ala ma kota").unwrap();
+        writer.write_cell_for(&ctx, "c", &2i64).unwrap();
+        assert_eq!(writer.value_count(), 3);
+        assert_eq!(data, do_serialize((1i32, "Ala ma kota", 2i64), &columns));
+
+        // A column skipped by writing a later one is missing a value
+        let mut data = Vec::new();
+        let mut writer = RowWriter::new(&mut data);
+        let err = writer.write_cell_for(&ctx, "b", &"Ala").unwrap_err();
+        let err = get_typeck_err(&err);
+        assert_eq!(err.rust_name, std::any::type_name::<&str>());
+        assert!(matches!(
+            &err.kind,
+            BuiltinTypeCheckErrorKind::ValueMissingForColumn { name } if name == "a"
+        ));
+
+        // A column written again is out of order
+        let mut data = Vec::new();
+        let mut writer = RowWriter::new(&mut data);
+        writer.write_cell_for(&ctx, "a", &1i32).unwrap();
+        let err = writer.write_cell_for(&ctx, "a", &1i32).unwrap_err();
+        assert!(matches!(
+            &get_typeck_err(&err).kind,
+            BuiltinTypeCheckErrorKind::ColumnNameMismatch { rust_column_name, db_column_name }
+                if rust_column_name == "a" && db_column_name == "b"
+        ));
+
+        // No such column
+        let err = writer.write_cell_for(&ctx, "d", &1i32).unwrap_err();
+        assert!(matches!(
+            &get_typeck_err(&err).kind,
+            BuiltinTypeCheckErrorKind::NoColumnWithName { name } if name == "d"
+        ));
+
+        // Wrong type of the value
+        let err = writer.write_cell_for(&ctx, "b", &1i32).unwrap_err();
+        let BuiltinSerializationErrorKind::ColumnSerializationFailed { name, err: _ } =
+            &get_ser_err(&err).kind;
+        assert_eq!(name, "b");
+
+        // More values than columns
+        let mut data = Vec::new();
+        let mut writer = RowWriter::new(&mut data);
+        writer.write_cell_for(&ctx, "a", &1i32).unwrap();
+        writer.write_cell_for(&ctx, "b", &"Ala").unwrap();
+        writer.write_cell_for(&ctx, "c", &2i64).unwrap();
+        let err = writer.write_cell_for(&ctx, "a", &1i32).unwrap_err();
+        assert!(matches!(
+            get_typeck_err(&err).kind,
+            BuiltinTypeCheckErrorKind::WrongColumnCount {
+                actual: 4,
+                asked_for: 3
+            }
+        ));
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        fn typck_kind(kind: BuiltinTypeCheckErrorKind) -> SerializationErrorKind {
+            SerializationError::new(BuiltinTypeCheckError {
+                rust_name: "T",
+                kind,
+            })
+            .kind()
+        }
+        let name = || "a".to_owned();
+
+        for (kind, expected) in [
+            (
+                BuiltinTypeCheckErrorKind::WrongColumnCount {
+                    actual: 1,
+                    asked_for: 2,
+                },
+                SerializationErrorKind::MissingField,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::WrongColumnCount {
+                    actual: 2,
+                    asked_for: 1,
+                },
+                SerializationErrorKind::ExtraField,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::NoColumnWithName { name: name() },
+                SerializationErrorKind::ExtraField,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::ValueMissingForColumn { name: name() },
+                SerializationErrorKind::MissingField,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::ColumnNameMismatch {
+                    rust_column_name: "a".to_owned(),
+                    db_column_name: "b".to_owned(),
+                },
+                SerializationErrorKind::TypeMismatch,
+            ),
+        ] {
+            assert_eq!(typck_kind(kind.clone()), expected, "{:?}", kind);
+        }
+
+        // Failures of columns are classified by their cause
+        let columns = [col_spec("a", ColumnType::TinyInt)];
+        let ctx = RowSerializationContext::from_specs(&columns);
+        let mut data = Vec::new();
+        let err = <_ as SerializeRow>::serialize(&(1000i32,), &ctx, &mut RowWriter::new(&mut data))
+            .unwrap_err();
+        assert_eq!(err.kind(), SerializationErrorKind::TypeMismatch);
+        let err = SerializationError::new(BuiltinSerializationError {
+            rust_name: "T",
+            kind: BuiltinSerializationErrorKind::ColumnSerializationFailed {
+                name: name(),
+                err: SerializationError::new(std::fmt::Error),
+            },
+        });
+        assert_eq!(err.kind(), SerializationErrorKind::Other);
+
+        for (err, expected) in [
+            (
+                ValueCountMismatchError {
+                    expected: 2,
+                    actual: 1,
+                    column_names: Vec::new(),
+                },
+                SerializationErrorKind::MissingField,
+            ),
+            (
+                ValueCountMismatchError {
+                    expected: 1,
+                    actual: 2,
+                    column_names: Vec::new(),
+                },
+                SerializationErrorKind::ExtraField,
+            ),
+        ] {
+            assert_eq!(SerializationError::new(err).kind(), expected);
+        }
+
+        // Errors of the legacy traits
+        for (err, expected) in [
+            (
+                ValueListToSerializeRowAdapterError::ValueMissingForBindMarker { name: name() },
+                SerializationErrorKind::MissingField,
+            ),
+            (
+                ValueListToSerializeRowAdapterError::NoBindMarkerWithName { name: name() },
+                SerializationErrorKind::ExtraField,
+            ),
+        ] {
+            assert_eq!(SerializationError::new(err).kind(), expected);
+        }
+        for (err, expected) in [
+            (
+                SerializeValuesError::TooManyValues,
+                SerializationErrorKind::SizeLimit,
+            ),
+            (
+                SerializeValuesError::ValueTooBig(ValueTooBig),
+                SerializationErrorKind::SizeLimit,
+            ),
+            (
+                SerializeValuesError::MixingNamedAndNotNamedValues,
+                SerializationErrorKind::Other,
+            ),
+            (
+                SerializeValuesError::ParseError,
+                SerializationErrorKind::Other,
+            ),
+        ] {
+            assert_eq!(SerializationError::new(err).kind(), expected);
+        }
+    }
+
+    #[test]
+    fn test_error_kinds_of_derived_impls() {
+        let row = TestRowWithColumnSorting {
+            a: "Ala ma kota".to_owned(),
+            b: 42,
+            c: vec![1, 2, 3],
+        };
+        let mut data = Vec::new();
+
+        let spec_without_c = [
+            col_spec("a", ColumnType::Text),
+            col_spec("b", ColumnType::Int),
+        ];
+        let ctx = RowSerializationContext::from_specs(&spec_without_c);
+        let err = row
+            .serialize(&ctx, &mut RowWriter::new(&mut data))
+            .unwrap_err();
+        assert_eq!(err.kind(), SerializationErrorKind::ExtraField);
+
+        let spec_with_d = [
+            col_spec("a", ColumnType::Text),
+            col_spec("b", ColumnType::Int),
+            col_spec("c", ColumnType::List(Box::new(ColumnType::BigInt))),
+            col_spec("d", ColumnType::Int),
+        ];
+        let ctx = RowSerializationContext::from_specs(&spec_with_d);
+        let err = row
+            .serialize(&ctx, &mut RowWriter::new(&mut data))
+            .unwrap_err();
+        assert_eq!(err.kind(), SerializationErrorKind::MissingField);
+
+        let spec_wrong_type = [
+            col_spec("a", ColumnType::Text),
+            col_spec("b", ColumnType::Int),
+            col_spec("c", ColumnType::List(Box::new(ColumnType::TinyInt))),
+        ];
+        let ctx = RowSerializationContext::from_specs(&spec_wrong_type);
+        let err = row
+            .serialize(&ctx, &mut RowWriter::new(&mut data))
+            .unwrap_err();
+        assert_eq!(err.kind(), SerializationErrorKind::TypeMismatch);
+    }
+
     fn do_serialize<T: SerializeRow>(t: T, columns: &[ColumnSpec]) -> Vec<u8> {
         let ctx = RowSerializationContext { columns };
         let mut ret = Vec::new();
@@ -1220,8 +1797,8 @@ mod tests {
         let err = <_ as SerializeRow>::serialize(&row, &ctx, &mut row_writer).unwrap_err();
         let err = err.0.downcast_ref::<BuiltinTypeCheckError>().unwrap();
         assert!(matches!(
-            err.kind,
-            BuiltinTypeCheckErrorKind::ValueMissingForColumn { .. }
+            &err.kind,
+            BuiltinTypeCheckErrorKind::NoColumnWithName { name } if name == "c"
         ));
 
         let spec_duplicate_column = [
@@ -1238,8 +1815,8 @@ mod tests {
         let err = <_ as SerializeRow>::serialize(&row, &ctx, &mut row_writer).unwrap_err();
         let err = err.0.downcast_ref::<BuiltinTypeCheckError>().unwrap();
         assert!(matches!(
-            err.kind,
-            BuiltinTypeCheckErrorKind::NoColumnWithName { .. }
+            &err.kind,
+            BuiltinTypeCheckErrorKind::ValueMissingForColumn { name } if name == "d"
         ));
 
         let spec_wrong_type = [
@@ -1347,8 +1924,8 @@ mod tests {
         let err = <_ as SerializeRow>::serialize(&row, &ctx, &mut writer).unwrap_err();
         let err = err.0.downcast_ref::<BuiltinTypeCheckError>().unwrap();
         assert!(matches!(
-            err.kind,
-            BuiltinTypeCheckErrorKind::ValueMissingForColumn { .. }
+            &err.kind,
+            BuiltinTypeCheckErrorKind::NoColumnWithName { name } if name == "c"
         ));
 
         let spec_duplicate_column = [
@@ -1365,8 +1942,8 @@ mod tests {
         let err = <_ as SerializeRow>::serialize(&row, &ctx, &mut writer).unwrap_err();
         let err = err.0.downcast_ref::<BuiltinTypeCheckError>().unwrap();
         assert!(matches!(
-            err.kind,
-            BuiltinTypeCheckErrorKind::NoColumnWithName { .. }
+            &err.kind,
+            BuiltinTypeCheckErrorKind::ValueMissingForColumn { name } if name == "d"
         ));
 
         let spec_wrong_type = [
@@ -1519,4 +2096,92 @@ mod tests {
 
         assert_eq!(reference, row);
     }
+
+    // A blob with an arbitrary size estimate.
+    struct EstimatedBlob {
+        blob: Vec<u8>,
+        estimate: Option<usize>,
+    }
+
+    impl SerializeCql for EstimatedBlob {
+        fn serialize<'b>(
+            &self,
+            typ: &ColumnType,
+            writer: CellWriter<'b>,
+        ) -> Result<WrittenCellProof<'b>, SerializationError> {
+            self.blob.serialize(typ, writer)
+        }
+
+        fn estimated_size(&self, _typ: &ColumnType) -> Option<usize> {
+            self.estimate
+        }
+    }
+
+    #[derive(SerializeRow)]
+    #[scylla(crate = crate)]
+    struct EstimatedBlobsRow {
+        a: EstimatedBlob,
+        b: EstimatedBlob,
+    }
+
+    #[derive(SerializeRow)]
+    #[scylla(crate = crate, flavor = "enforce_order")]
+    struct EstimatedBlobsOrderedRow {
+        a: EstimatedBlob,
+        b: EstimatedBlob,
+    }
+
+    #[test]
+    fn test_wrong_size_estimates() {
+        let columns = [col("a", ColumnType::Blob), col("b", ColumnType::Blob)];
+        let blob = |estimate| EstimatedBlob {
+            blob: vec![7; 100],
+            estimate,
+        };
+        let reference = do_serialize((vec![7u8; 100], vec![7u8; 100]), &columns);
+
+        for estimate in [None, Some(0), Some(3), Some(1_000_000), Some(usize::MAX)] {
+            assert_eq!(
+                do_serialize((blob(estimate), blob(estimate)), &columns),
+                reference
+            );
+            assert_eq!(
+                do_serialize(vec![blob(estimate), blob(estimate)], &columns),
+                reference
+            );
+            let row = EstimatedBlobsRow {
+                a: blob(estimate),
+                b: blob(estimate),
+            };
+            assert_eq!(do_serialize(row, &columns), reference);
+            let row = EstimatedBlobsOrderedRow {
+                a: blob(estimate),
+                b: blob(estimate),
+            };
+            assert_eq!(do_serialize(row, &columns), reference);
+
+            let mut values = SerializedValues::new();
+            values
+                .add_value(&blob(estimate), &ColumnType::Blob)
+                .unwrap();
+            values
+                .add_value(&blob(estimate), &ColumnType::Blob)
+                .unwrap();
+            assert_eq!(values.get_contents(), reference);
+        }
+    }
+
+    #[test]
+    fn test_capacity_reserved_from_estimates() {
+        let blobs = vec![vec![1u8; 1000]; 10];
+        let typ = ColumnType::List(Box::new(ColumnType::Blob));
+
+        // Reserving capacity for an empty buffer allocates exactly as much as needed
+        let mut values = SerializedValues::new();
+        values.add_value(&blobs, &typ).unwrap();
+        assert_eq!(
+            values.get_contents().len(),
+            values.serialized_values.capacity()
+        );
+    }
 }
@@ -1,5 +1,6 @@
 //! Contains the [`SerializeCql`] trait and its implementations.
 
+use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::Display;
 use std::hash::BuildHasher;
@@ -7,7 +8,7 @@ use std::net::IpAddr;
 use std::sync::Arc;
 
 use bigdecimal::BigDecimal;
-use num_bigint::BigInt;
+use num_bigint::{BigInt, Sign};
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -17,17 +18,19 @@ use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 #[cfg(feature = "secret")]
 use secrecy::{ExposeSecret, Secret, Zeroize};
 
+use crate::frame::response::cql_to_rust::NullAsEmpty;
+use crate::frame::response::custom_types::CustomTypeRegistry;
 use crate::frame::response::result::{ColumnType, CqlValue};
 use crate::frame::types::vint_encode;
 use crate::frame::value::{
-    Counter, CqlDate, CqlDuration, CqlTime, CqlTimestamp, MaybeUnset, Unset, Value,
+    Counter, CqlDate, CqlDuration, CqlTime, CqlTimestamp, MaybeUnset, StrictFloat, Unset, Value,
 };
 
 #[cfg(feature = "chrono")]
 use crate::frame::value::ValueOverflow;
 
-use super::writers::WrittenCellProof;
-use super::{CellWriter, SerializationError};
+use super::writers::{CellValueBuilder, NullOrUnset, WrittenCellProof};
+use super::{CellWriter, ClassifySerializationError, SerializationError, SerializationErrorKind};
 
 /// A type that can be serialized and sent along with a CQL statement.
 ///
@@ -56,6 +59,18 @@ pub trait SerializeCql {
         typ: &ColumnType,
         writer: CellWriter<'b>,
     ) -> Result<WrittenCellProof<'b>, SerializationError>;
+
+    /// Returns an estimate of the number of bytes that the value takes when
+    /// serialized to given CQL type, including the length of the `[value]`.
+    ///
+    /// The estimate is only used to reserve space in the buffer before
+    /// the value is serialized, so a wrong one costs performance,
+    /// but never affects the serialized data. `None`, the default,
+    /// means that the size is unknown.
+    #[inline]
+    fn estimated_size(&self, _typ: &ColumnType) -> Option<usize> {
+        None
+    }
 }
 
 macro_rules! exact_type_check {
@@ -72,6 +87,15 @@ macro_rules! exact_type_check {
     };
 }
 
+macro_rules! impl_fixed_estimated_size {
+    ($size:expr) => {
+        #[inline]
+        fn estimated_size(&self, _typ: &ColumnType) -> Option<usize> {
+            Some(4 + $size)
+        }
+    };
+}
+
 macro_rules! impl_serialize_via_writer {
     (|$me:ident, $writer:ident| $e:expr) => {
         impl_serialize_via_writer!(|$me, _typ, $writer| $e);
@@ -96,24 +120,28 @@ impl SerializeCql for i8 {
         exact_type_check!(typ, TinyInt);
         writer.set_value(me.to_be_bytes().as_slice()).unwrap()
     });
+    impl_fixed_estimated_size!(1);
 }
 impl SerializeCql for i16 {
     impl_serialize_via_writer!(|me, typ, writer| {
         exact_type_check!(typ, SmallInt);
         writer.set_value(me.to_be_bytes().as_slice()).unwrap()
     });
+    impl_fixed_estimated_size!(2);
 }
 impl SerializeCql for i32 {
     impl_serialize_via_writer!(|me, typ, writer| {
         exact_type_check!(typ, Int);
         writer.set_value(me.to_be_bytes().as_slice()).unwrap()
     });
+    impl_fixed_estimated_size!(4);
 }
 impl SerializeCql for i64 {
     impl_serialize_via_writer!(|me, typ, writer| {
         exact_type_check!(typ, BigInt);
         writer.set_value(me.to_be_bytes().as_slice()).unwrap()
     });
+    impl_fixed_estimated_size!(8);
 }
 impl SerializeCql for BigDecimal {
     impl_serialize_via_writer!(|me, typ, writer| {
@@ -124,7 +152,7 @@ impl SerializeCql for BigDecimal {
             .try_into()
             .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::ValueOverflow))?;
         builder.append_bytes(&scale.to_be_bytes());
-        builder.append_bytes(&value.to_signed_bytes_be());
+        append_varint(&mut builder, &value);
         builder
             .finish()
             .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
@@ -135,18 +163,21 @@ impl SerializeCql for CqlDate {
         exact_type_check!(typ, Date);
         writer.set_value(me.0.to_be_bytes().as_slice()).unwrap()
     });
+    impl_fixed_estimated_size!(4);
 }
 impl SerializeCql for CqlTimestamp {
     impl_serialize_via_writer!(|me, typ, writer| {
         exact_type_check!(typ, Timestamp);
         writer.set_value(me.0.to_be_bytes().as_slice()).unwrap()
     });
+    impl_fixed_estimated_size!(8);
 }
 impl SerializeCql for CqlTime {
     impl_serialize_via_writer!(|me, typ, writer| {
         exact_type_check!(typ, Time);
         writer.set_value(me.0.to_be_bytes().as_slice()).unwrap()
     });
+    impl_fixed_estimated_size!(8);
 }
 #[cfg(feature = "chrono")]
 impl SerializeCql for NaiveDate {
@@ -202,42 +233,130 @@ impl<V: SerializeCql + Zeroize> SerializeCql for Secret<V> {
     ) -> Result<WrittenCellProof<'b>, SerializationError> {
         V::serialize(self.expose_secret(), typ, writer)
     }
+
+    #[inline]
+    fn estimated_size(&self, typ: &ColumnType) -> Option<usize> {
+        V::estimated_size(self.expose_secret(), typ)
+    }
 }
 impl SerializeCql for bool {
     impl_serialize_via_writer!(|me, typ, writer| {
         exact_type_check!(typ, Boolean);
         writer.set_value(&[*me as u8]).unwrap()
     });
+    impl_fixed_estimated_size!(1);
 }
 impl SerializeCql for f32 {
     impl_serialize_via_writer!(|me, typ, writer| {
         exact_type_check!(typ, Float);
         writer.set_value(me.to_be_bytes().as_slice()).unwrap()
     });
+    impl_fixed_estimated_size!(4);
 }
 impl SerializeCql for f64 {
     impl_serialize_via_writer!(|me, typ, writer| {
         exact_type_check!(typ, Double);
         writer.set_value(me.to_be_bytes().as_slice()).unwrap()
     });
+    impl_fixed_estimated_size!(8);
+}
+impl SerializeCql for StrictFloat<f32> {
+    impl_serialize_via_writer!(|me, typ, writer| {
+        exact_type_check!(typ, Float);
+        if me.0.is_nan() {
+            return Err(mk_ser_err::<Self>(
+                typ,
+                BuiltinSerializationErrorKind::NanNotAllowed,
+            ));
+        }
+        // Adding positive zero turns negative zero into positive, and keeps other values
+        writer
+            .set_value((me.0 + 0.0).to_be_bytes().as_slice())
+            .unwrap()
+    });
+    impl_fixed_estimated_size!(4);
+}
+impl SerializeCql for StrictFloat<f64> {
+    impl_serialize_via_writer!(|me, typ, writer| {
+        exact_type_check!(typ, Double);
+        if me.0.is_nan() {
+            return Err(mk_ser_err::<Self>(
+                typ,
+                BuiltinSerializationErrorKind::NanNotAllowed,
+            ));
+        }
+        writer
+            .set_value((me.0 + 0.0).to_be_bytes().as_slice())
+            .unwrap()
+    });
+    impl_fixed_estimated_size!(8);
 }
 impl SerializeCql for Uuid {
     impl_serialize_via_writer!(|me, typ, writer| {
         exact_type_check!(typ, Uuid, Timeuuid);
         writer.set_value(me.as_bytes().as_ref()).unwrap()
     });
+    impl_fixed_estimated_size!(16);
 }
 impl SerializeCql for BigInt {
     impl_serialize_via_writer!(|me, typ, writer| {
         exact_type_check!(typ, Varint);
-        // TODO: The allocation here can be avoided and we can reimplement
-        // `to_signed_bytes_be` by using `to_u64_digits` and a bit of custom
-        // logic. Need better tests in order to do this.
-        writer
-            .set_value(me.to_signed_bytes_be().as_slice())
+        let mut builder = writer.into_value_builder();
+        append_varint(&mut builder, me);
+        builder
+            .finish()
             .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
     });
 }
+
+/// Appends the minimal big-endian two's complement representation of the value,
+/// the same as [`BigInt::to_signed_bytes_be`] returns, without allocating it.
+fn append_varint(builder: &mut CellValueBuilder<'_>, value: &BigInt) {
+    let negative = value.sign() == Sign::Minus;
+    // Two's complement of a negative number is `!(magnitude - 1)`. The borrow of
+    // the subtraction reaches all digits up to the lowest non-zero one.
+    let lowest_nonzero = value.iter_u64_digits().position(|digit| digit != 0);
+    let two_complement_digit = |idx: usize, digit: u64| {
+        if !negative {
+            return digit;
+        }
+        let borrow = matches!(lowest_nonzero, Some(lowest) if idx <= lowest);
+        !digit.wrapping_sub(borrow as u64)
+    };
+    let sign_byte = if negative { 0xFF } else { 0x00 };
+
+    // Digits can only be iterated from the least significant one, so the length
+    // is computed first, and the digits are written from the end.
+    // Leading bytes equal to the sign byte are redundant, unless the following
+    // byte would be read with the opposite sign without them.
+    let digit_count = value.iter_u64_digits().len();
+    let len = match value.iter_u64_digits().last() {
+        // The value is 0
+        None => 1,
+        Some(top) => {
+            let top_bytes = two_complement_digit(digit_count - 1, top).to_be_bytes();
+            let lower_len = 8 * (digit_count - 1);
+            match top_bytes.iter().position(|byte| *byte != sign_byte) {
+                Some(first) => {
+                    let needs_sign_byte = (top_bytes[first] & 0x80 != 0) != negative;
+                    needs_sign_byte as usize + 8 - first + lower_len
+                }
+                // Only -2^(64 * k), whose lower digits are all 0x00, so the sign byte is needed.
+                None => 1 + lower_len,
+            }
+        }
+    };
+
+    let bytes = builder.append_zeroed(len);
+    bytes[0] = sign_byte;
+    for (idx, digit) in value.iter_u64_digits().enumerate() {
+        let digit_bytes = two_complement_digit(idx, digit).to_be_bytes();
+        // The top digit may start before the value, with redundant sign bytes
+        let end = len - 8 * idx;
+        let skipped = 8usize.saturating_sub(end);
+        bytes[end + skipped - 8..end].copy_from_slice(&digit_bytes[skipped..]);
+    }
+}
 impl SerializeCql for &str {
     impl_serialize_via_writer!(|me, typ, writer| {
         exact_type_check!(typ, Ascii, Text);
@@ -245,6 +364,10 @@ impl SerializeCql for &str {
             .set_value(me.as_bytes())
             .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
     });
+    #[inline]
+    fn estimated_size(&self, _typ: &ColumnType) -> Option<usize> {
+        Some(4 + self.len())
+    }
 }
 impl SerializeCql for Vec<u8> {
     impl_serialize_via_writer!(|me, typ, writer| {
@@ -253,6 +376,10 @@ impl SerializeCql for Vec<u8> {
             .set_value(me.as_ref())
             .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
     });
+    #[inline]
+    fn estimated_size(&self, _typ: &ColumnType) -> Option<usize> {
+        Some(4 + self.len())
+    }
 }
 impl SerializeCql for &[u8] {
     impl_serialize_via_writer!(|me, typ, writer| {
@@ -261,6 +388,10 @@ impl SerializeCql for &[u8] {
             .set_value(me)
             .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
     });
+    #[inline]
+    fn estimated_size(&self, _typ: &ColumnType) -> Option<usize> {
+        Some(4 + self.len())
+    }
 }
 impl<const N: usize> SerializeCql for [u8; N] {
     impl_serialize_via_writer!(|me, typ, writer| {
@@ -269,6 +400,10 @@ impl<const N: usize> SerializeCql for [u8; N] {
             .set_value(me.as_ref())
             .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
     });
+    #[inline]
+    fn estimated_size(&self, _typ: &ColumnType) -> Option<usize> {
+        Some(4 + N)
+    }
 }
 impl SerializeCql for IpAddr {
     impl_serialize_via_writer!(|me, typ, writer| {
@@ -278,6 +413,13 @@ impl SerializeCql for IpAddr {
             IpAddr::V6(ip) => writer.set_value(&ip.octets()).unwrap(),
         }
     });
+    #[inline]
+    fn estimated_size(&self, _typ: &ColumnType) -> Option<usize> {
+        match self {
+            IpAddr::V4(_) => Some(4 + 4),
+            IpAddr::V6(_) => Some(4 + 16),
+        }
+    }
 }
 impl SerializeCql for String {
     impl_serialize_via_writer!(|me, typ, writer| {
@@ -286,6 +428,10 @@ impl SerializeCql for String {
             .set_value(me.as_bytes())
             .map_err(|_| mk_ser_err::<Self>(typ, BuiltinSerializationErrorKind::SizeOverflow))?
     });
+    #[inline]
+    fn estimated_size(&self, _typ: &ColumnType) -> Option<usize> {
+        Some(4 + self.len())
+    }
 }
 impl<T: SerializeCql> SerializeCql for Option<T> {
     fn serialize<'b>(
@@ -298,25 +444,50 @@ impl<T: SerializeCql> SerializeCql for Option<T> {
             None => Ok(writer.set_null()),
         }
     }
+
+    #[inline]
+    fn estimated_size(&self, typ: &ColumnType) -> Option<usize> {
+        match self {
+            Some(v) => v.estimated_size(typ),
+            None => Some(4),
+        }
+    }
+}
+impl<T: SerializeCql> SerializeCql for NullAsEmpty<T> {
+    fn serialize<'b>(
+        &self,
+        typ: &ColumnType,
+        writer: CellWriter<'b>,
+    ) -> Result<WrittenCellProof<'b>, SerializationError> {
+        self.0.serialize(typ, writer)
+    }
+
+    #[inline]
+    fn estimated_size(&self, typ: &ColumnType) -> Option<usize> {
+        self.0.estimated_size(typ)
+    }
 }
 impl SerializeCql for Unset {
     impl_serialize_via_writer!(|_me, writer| writer.set_unset());
+    impl_fixed_estimated_size!(0);
 }
 impl SerializeCql for Counter {
     impl_serialize_via_writer!(|me, typ, writer| {
         exact_type_check!(typ, Counter);
         writer.set_value(me.0.to_be_bytes().as_slice()).unwrap()
     });
+    impl_fixed_estimated_size!(8);
 }
 impl SerializeCql for CqlDuration {
     impl_serialize_via_writer!(|me, typ, writer| {
         exact_type_check!(typ, Duration);
-        // TODO: adjust vint_encode to use CellValueBuilder or something like that
-        let mut buf = Vec::with_capacity(27); // worst case size is 27
-        vint_encode(me.months as i64, &mut buf);
-        vint_encode(me.days as i64, &mut buf);
-        vint_encode(me.nanoseconds, &mut buf);
-        writer.set_value(buf.as_slice()).unwrap()
+        let mut buf = [0u8; 27]; // worst case size is 27
+        let mut rest = &mut buf[..];
+        vint_encode(me.months as i64, &mut rest);
+        vint_encode(me.days as i64, &mut rest);
+        vint_encode(me.nanoseconds, &mut rest);
+        let len = 27 - rest.len();
+        writer.set_value(&buf[..len]).unwrap()
     });
 }
 impl<V: SerializeCql> SerializeCql for MaybeUnset<V> {
@@ -330,6 +501,14 @@ impl<V: SerializeCql> SerializeCql for MaybeUnset<V> {
             MaybeUnset::Unset => Ok(writer.set_unset()),
         }
     }
+
+    #[inline]
+    fn estimated_size(&self, typ: &ColumnType) -> Option<usize> {
+        match self {
+            MaybeUnset::Set(v) => v.estimated_size(typ),
+            MaybeUnset::Unset => Some(4),
+        }
+    }
 }
 impl<T: SerializeCql + ?Sized> SerializeCql for &T {
     fn serialize<'b>(
@@ -339,6 +518,11 @@ impl<T: SerializeCql + ?Sized> SerializeCql for &T {
     ) -> Result<WrittenCellProof<'b>, SerializationError> {
         T::serialize(*self, typ, writer)
     }
+
+    #[inline]
+    fn estimated_size(&self, typ: &ColumnType) -> Option<usize> {
+        T::estimated_size(*self, typ)
+    }
 }
 impl<T: SerializeCql + ?Sized> SerializeCql for Box<T> {
     fn serialize<'b>(
@@ -348,6 +532,11 @@ impl<T: SerializeCql + ?Sized> SerializeCql for Box<T> {
     ) -> Result<WrittenCellProof<'b>, SerializationError> {
         T::serialize(&**self, typ, writer)
     }
+
+    #[inline]
+    fn estimated_size(&self, typ: &ColumnType) -> Option<usize> {
+        T::estimated_size(&**self, typ)
+    }
 }
 impl<V: SerializeCql, S: BuildHasher + Default> SerializeCql for HashSet<V, S> {
     fn serialize<'b>(
@@ -363,6 +552,10 @@ impl<V: SerializeCql, S: BuildHasher + Default> SerializeCql for HashSet<V, S> {
             writer,
         )
     }
+
+    fn estimated_size(&self, typ: &ColumnType) -> Option<usize> {
+        estimate_sequence_size(self.iter(), typ)
+    }
 }
 impl<K: SerializeCql, V: SerializeCql, S: BuildHasher> SerializeCql for HashMap<K, V, S> {
     fn serialize<'b>(
@@ -378,6 +571,10 @@ impl<K: SerializeCql, V: SerializeCql, S: BuildHasher> SerializeCql for HashMap<
             writer,
         )
     }
+
+    fn estimated_size(&self, typ: &ColumnType) -> Option<usize> {
+        estimate_mapping_size(self.iter(), typ)
+    }
 }
 impl<V: SerializeCql> SerializeCql for BTreeSet<V> {
     fn serialize<'b>(
@@ -393,6 +590,10 @@ impl<V: SerializeCql> SerializeCql for BTreeSet<V> {
             writer,
         )
     }
+
+    fn estimated_size(&self, typ: &ColumnType) -> Option<usize> {
+        estimate_sequence_size(self.iter(), typ)
+    }
 }
 impl<K: SerializeCql, V: SerializeCql> SerializeCql for BTreeMap<K, V> {
     fn serialize<'b>(
@@ -408,6 +609,10 @@ impl<K: SerializeCql, V: SerializeCql> SerializeCql for BTreeMap<K, V> {
             writer,
         )
     }
+
+    fn estimated_size(&self, typ: &ColumnType) -> Option<usize> {
+        estimate_mapping_size(self.iter(), typ)
+    }
 }
 impl<T: SerializeCql> SerializeCql for Vec<T> {
     fn serialize<'b>(
@@ -423,6 +628,10 @@ impl<T: SerializeCql> SerializeCql for Vec<T> {
             writer,
         )
     }
+
+    fn estimated_size(&self, typ: &ColumnType) -> Option<usize> {
+        estimate_sequence_size(self.iter(), typ)
+    }
 }
 impl<'a, T: SerializeCql + 'a> SerializeCql for &'a [T] {
     fn serialize<'b>(
@@ -438,6 +647,10 @@ impl<'a, T: SerializeCql + 'a> SerializeCql for &'a [T] {
             writer,
         )
     }
+
+    fn estimated_size(&self, typ: &ColumnType) -> Option<usize> {
+        estimate_sequence_size(self.iter(), typ)
+    }
 }
 impl SerializeCql for CqlValue {
     fn serialize<'b>(
@@ -454,16 +667,23 @@ fn serialize_cql_value<'b>(
     typ: &ColumnType,
     writer: CellWriter<'b>,
 ) -> Result<WrittenCellProof<'b>, SerializationError> {
-    if let ColumnType::Custom(_) = typ {
-        return Err(mk_typck_err::<CqlValue>(
-            typ,
-            BuiltinTypeCheckErrorKind::CustomTypeUnsupported,
-        ));
+    if let ColumnType::Custom(class_name) = typ {
+        let codec = CustomTypeRegistry::global()
+            .get(class_name)
+            .ok_or_else(|| {
+                mk_typck_err::<CqlValue>(typ, BuiltinTypeCheckErrorKind::CustomTypeUnsupported)
+            })?;
+        let mut buf = Vec::new();
+        codec.serialize(value, &mut buf)?;
+        return writer
+            .set_value(&buf)
+            .map_err(|_| mk_ser_err::<CqlValue>(typ, BuiltinSerializationErrorKind::SizeOverflow));
     }
     match value {
         CqlValue::Ascii(a) => <_ as SerializeCql>::serialize(&a, typ, writer),
         CqlValue::Boolean(b) => <_ as SerializeCql>::serialize(&b, typ, writer),
         CqlValue::Blob(b) => <_ as SerializeCql>::serialize(&b, typ, writer),
+        CqlValue::SharedBlob(b) => <_ as SerializeCql>::serialize(&&b[..], typ, writer),
         CqlValue::Counter(c) => <_ as SerializeCql>::serialize(&c, typ, writer),
         CqlValue::Decimal(d) => <_ as SerializeCql>::serialize(&d, typ, writer),
         CqlValue::Date(d) => <_ as SerializeCql>::serialize(&d, typ, writer),
@@ -498,6 +718,21 @@ fn serialize_cql_value<'b>(
             type_name,
             fields,
         } => serialize_udt(typ, keyspace, type_name, fields, writer),
+        CqlValue::Custom { .. } => Err(mk_typck_err::<CqlValue>(
+            typ,
+            BuiltinTypeCheckErrorKind::NotCustomType,
+        )),
+        CqlValue::Unknown { id, raw } => match typ {
+            ColumnType::Unknown { id: typ_id, .. } if typ_id == id => {
+                writer.set_value(raw).map_err(|_| {
+                    mk_ser_err::<CqlValue>(typ, BuiltinSerializationErrorKind::SizeOverflow)
+                })
+            }
+            _ => Err(mk_typck_err::<CqlValue>(
+                typ,
+                BuiltinTypeCheckErrorKind::NotSameUnknownType { id: *id },
+            )),
+        },
         CqlValue::SmallInt(s) => <_ as SerializeCql>::serialize(&s, typ, writer),
         CqlValue::TinyInt(t) => <_ as SerializeCql>::serialize(&t, typ, writer),
         CqlValue::Time(t) => <_ as SerializeCql>::serialize(&t, typ, writer),
@@ -601,11 +836,22 @@ fn serialize_udt<'b>(
     }
 
     // Allow columns present in the CQL type which are not present in CqlValue,
-    // but not the other way around
-    let mut indexed_fields: HashMap<_, _> = values.iter().map(|(k, v)| (k.as_str(), v)).collect();
+    // but not the other way around. Fields are matched by name, so their order doesn't matter,
+    // which makes a field given twice ambiguous.
+    let mut indexed_fields: HashMap<&str, &Option<CqlValue>> = HashMap::with_capacity(values.len());
+    for (fname, fvalue) in values {
+        if indexed_fields.insert(fname.as_str(), fvalue).is_some() {
+            return Err(mk_typck_err::<CqlValue>(
+                typ,
+                UdtTypeCheckErrorKind::DuplicateField {
+                    field_name: fname.clone(),
+                },
+            ));
+        }
+    }
 
     let mut builder = writer.into_value_builder();
-    for (fname, ftyp) in field_types {
+    for (fname, ftyp) in field_types.iter() {
         // Take a value from the original list.
         // If a field is missing, write null instead.
         let fvalue = indexed_fields
@@ -687,8 +933,10 @@ macro_rules! impl_tuple {
                 writer: CellWriter<'b>,
             ) -> Result<WrittenCellProof<'b>, SerializationError> {
                 let ($($tidents,)*) = match typ {
+                    // Elements missing at the end of the Rust tuple are not written,
+                    // which makes them null, just like in the case of CqlValue
                     ColumnType::Tuple(typs) => match typs.as_slice() {
-                        [$($tidents),*] => ($($tidents,)*),
+                        [$($tidents,)* ..] => ($($tidents,)*),
                         _ => return Err(mk_typck_err::<Self>(
                             typ,
                             TupleTypeCheckErrorKind::WrongElementCount {
@@ -706,7 +954,8 @@ macro_rules! impl_tuple {
                 let mut builder = writer.into_value_builder();
                 let index = 0;
                 $(
-                    <$typs as SerializeCql>::serialize($fidents, $tidents, builder.make_sub_writer())
+                    let null_or_unset = builder
+                        .serialize_sub_value(|writer| <$typs as SerializeCql>::serialize($fidents, $tidents, writer))
                         .map_err(|err| mk_ser_err::<Self>(
                             typ,
                             TupleSerializationErrorKind::ElementSerializationFailed {
@@ -714,6 +963,13 @@ macro_rules! impl_tuple {
                                 err,
                             }
                         ))?;
+                    // Null elements are allowed in tuples
+                    if null_or_unset == Some(NullOrUnset::Unset) {
+                        return Err(mk_ser_err::<Self>(
+                            typ,
+                            TupleSerializationErrorKind::UnsetElement { index },
+                        ));
+                    }
                     let index = index + 1;
                 )*
                 let _ = index;
@@ -784,14 +1040,23 @@ fn serialize_sequence<'t, 'b, T: SerializeCql + 't>(
     })?;
     builder.append_bytes(&element_count.to_be_bytes());
 
-    for el in iter {
-        T::serialize(el, elt, builder.make_sub_writer()).map_err(|err| {
-            mk_ser_err_named(
+    for (index, el) in iter.enumerate() {
+        let null_or_unset = builder
+            .serialize_sub_value(|writer| T::serialize(el, elt, writer))
+            .map_err(|err| {
+                mk_ser_err_named(
+                    rust_name,
+                    typ,
+                    SetOrListSerializationErrorKind::ElementSerializationFailed(err),
+                )
+            })?;
+        if let Some(value) = null_or_unset {
+            return Err(mk_ser_err_named(
                 rust_name,
                 typ,
-                SetOrListSerializationErrorKind::ElementSerializationFailed(err),
-            )
-        })?;
+                SetOrListSerializationErrorKind::NullOrUnsetElement { index, value },
+            ));
+        }
     }
 
     builder
@@ -799,6 +1064,37 @@ fn serialize_sequence<'t, 'b, T: SerializeCql + 't>(
         .map_err(|_| mk_ser_err_named(rust_name, typ, BuiltinSerializationErrorKind::SizeOverflow))
 }
 
+/// Sums the size estimates of the elements, together with the length
+/// of the value and the element count.
+fn estimate_sequence_size<'t, T: SerializeCql + 't>(
+    mut iter: impl Iterator<Item = &'t T>,
+    typ: &ColumnType,
+) -> Option<usize> {
+    let elt = match typ {
+        ColumnType::List(elt) | ColumnType::Set(elt) => elt,
+        _ => return None,
+    };
+    iter.try_fold(4 + 4, |size: usize, el| {
+        size.checked_add(el.estimated_size(elt)?)
+    })
+}
+
+/// Sums the size estimates of the keys and values, together with the length
+/// of the value and the entry count.
+fn estimate_mapping_size<'t, K: SerializeCql + 't, V: SerializeCql + 't>(
+    mut iter: impl Iterator<Item = (&'t K, &'t V)>,
+    typ: &ColumnType,
+) -> Option<usize> {
+    let (ktyp, vtyp) = match typ {
+        ColumnType::Map(k, v) => (k, v),
+        _ => return None,
+    };
+    iter.try_fold(4 + 4, |size: usize, (k, v)| {
+        size.checked_add(k.estimated_size(ktyp)?)?
+            .checked_add(v.estimated_size(vtyp)?)
+    })
+}
+
 fn serialize_mapping<'t, 'b, K: SerializeCql + 't, V: SerializeCql + 't>(
     rust_name: &'static str,
     len: usize,
@@ -824,21 +1120,39 @@ fn serialize_mapping<'t, 'b, K: SerializeCql + 't, V: SerializeCql + 't>(
     })?;
     builder.append_bytes(&element_count.to_be_bytes());
 
-    for (k, v) in iter {
-        K::serialize(k, ktyp, builder.make_sub_writer()).map_err(|err| {
-            mk_ser_err_named(
+    for (index, (k, v)) in iter.enumerate() {
+        let null_or_unset = builder
+            .serialize_sub_value(|writer| K::serialize(k, ktyp, writer))
+            .map_err(|err| {
+                mk_ser_err_named(
+                    rust_name,
+                    typ,
+                    MapSerializationErrorKind::KeySerializationFailed(err),
+                )
+            })?;
+        if let Some(value) = null_or_unset {
+            return Err(mk_ser_err_named(
                 rust_name,
                 typ,
-                MapSerializationErrorKind::KeySerializationFailed(err),
-            )
-        })?;
-        V::serialize(v, vtyp, builder.make_sub_writer()).map_err(|err| {
-            mk_ser_err_named(
+                MapSerializationErrorKind::NullOrUnsetKey { index, value },
+            ));
+        }
+        let null_or_unset = builder
+            .serialize_sub_value(|writer| V::serialize(v, vtyp, writer))
+            .map_err(|err| {
+                mk_ser_err_named(
+                    rust_name,
+                    typ,
+                    MapSerializationErrorKind::ValueSerializationFailed(err),
+                )
+            })?;
+        if let Some(value) = null_or_unset {
+            return Err(mk_ser_err_named(
                 rust_name,
                 typ,
-                MapSerializationErrorKind::ValueSerializationFailed(err),
-            )
-        })?;
+                MapSerializationErrorKind::NullOrUnsetValue { index, value },
+            ));
+        }
     }
 
     builder
@@ -987,13 +1301,15 @@ pub fn serialize_legacy_value<'b, T: Value>(
 
 /// Type checking of one of the built-in types failed.
 #[derive(Debug, Error, Clone)]
-#[error("Failed to type check Rust type {rust_name} against CQL type {got:?}: {kind}")]
+#[error("Failed to type check Rust type {rust_name} against CQL type {got}: {kind}")]
 pub struct BuiltinTypeCheckError {
     /// Name of the Rust type being serialized.
     pub rust_name: &'static str,
 
-    /// The CQL type that the Rust type was being serialized to.
-    pub got: ColumnType,
+    /// The CQL type that the Rust type was being serialized to, rendered
+    /// with [`ColumnType::cql_type_name`], e.g. `list<frozen<set<int>>>`.
+    /// User defined types are rendered by name only.
+    pub got: Cow<'static, str>,
 
     /// Detailed information about the failure.
     pub kind: BuiltinTypeCheckErrorKind,
@@ -1011,27 +1327,139 @@ fn mk_typck_err_named(
     got: &ColumnType,
     kind: impl Into<BuiltinTypeCheckErrorKind>,
 ) -> SerializationError {
+    // Whatever the reason of the failure is, the underlying one is that nothing
+    // can be checked against an unknown type, unless it's a raw value of another one
+    let kind = match (got, kind.into()) {
+        (_, kind @ BuiltinTypeCheckErrorKind::NotSameUnknownType { .. }) => kind,
+        (ColumnType::Unknown { id, .. }, _) => BuiltinTypeCheckErrorKind::UnknownType { id: *id },
+        (_, kind) => kind,
+    };
     SerializationError::new(BuiltinTypeCheckError {
         rust_name: name,
-        got: got.clone(),
-        kind: kind.into(),
+        got: got.cql_type_name(),
+        kind,
     })
 }
 
 /// Serialization of one of the built-in types failed.
+///
+/// If the serialization of a value nested in a collection, tuple or UDT failed,
+/// the error is displayed together with the path to that value,
+/// see [`innermost_error`](Self::innermost_error).
 #[derive(Debug, Error, Clone)]
-#[error("Failed to serialize Rust type {rust_name} into CQL type {got:?}: {kind}")]
 pub struct BuiltinSerializationError {
     /// Name of the Rust type being serialized.
     pub rust_name: &'static str,
 
-    /// The CQL type that the Rust type was being serialized to.
-    pub got: ColumnType,
+    /// The CQL type that the Rust type was being serialized to, rendered
+    /// with [`ColumnType::cql_type_name`], e.g. `list<frozen<set<int>>>`.
+    /// User defined types are rendered by name only.
+    pub got: Cow<'static, str>,
 
     /// Detailed information about the failure.
     pub kind: BuiltinSerializationErrorKind,
 }
 
+impl BuiltinSerializationError {
+    /// Returns the path to the most deeply nested value which failed to serialize,
+    /// e.g. `map value → tuple element 1`, and the error of that value.
+    ///
+    /// If the failure is not caused by a nested value, the path is empty
+    /// and the error is `self`.
+    pub fn innermost_error(&self) -> (ValuePath, &(dyn std::error::Error + Send + Sync + 'static)) {
+        let mut path = Vec::new();
+        let mut current = self;
+        loop {
+            let (segment, err) = match &current.kind {
+                BuiltinSerializationErrorKind::SetOrListError(
+                    SetOrListSerializationErrorKind::ElementSerializationFailed(err),
+                ) => (ValuePathSegment::Element, err),
+                BuiltinSerializationErrorKind::MapError(
+                    MapSerializationErrorKind::KeySerializationFailed(err),
+                ) => (ValuePathSegment::MapKey, err),
+                BuiltinSerializationErrorKind::MapError(
+                    MapSerializationErrorKind::ValueSerializationFailed(err),
+                ) => (ValuePathSegment::MapValue, err),
+                BuiltinSerializationErrorKind::TupleError(
+                    TupleSerializationErrorKind::ElementSerializationFailed { index, err },
+                ) => (ValuePathSegment::TupleElement(*index), err),
+                BuiltinSerializationErrorKind::UdtError(
+                    UdtSerializationErrorKind::FieldSerializationFailed { field_name, err },
+                ) => (ValuePathSegment::UdtField(field_name.clone()), err),
+                _ => return (ValuePath(path), current),
+            };
+            path.push(segment);
+            match err.0.downcast_ref::<BuiltinSerializationError>() {
+                Some(nested) => current = nested,
+                None => return (ValuePath(path), &*err.0),
+            }
+        }
+    }
+}
+
+impl Display for BuiltinSerializationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to serialize Rust type {} into CQL type {}: ",
+            self.rust_name, self.got
+        )?;
+        match self.innermost_error() {
+            (path, err) if !path.0.is_empty() => write!(f, "{path}: {err}"),
+            _ => self.kind.fmt(f),
+        }
+    }
+}
+
+/// The path from a serialized value to a value nested in it,
+/// returned by [`BuiltinSerializationError::innermost_error`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ValuePath(pub Vec<ValuePathSegment>);
+
+impl Display for ValuePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " → ")?;
+            }
+            segment.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A single step of a [`ValuePath`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValuePathSegment {
+    /// An element of a list or a set.
+    Element,
+
+    /// A key of a map.
+    MapKey,
+
+    /// A value of a map.
+    MapValue,
+
+    /// The element of a tuple with the given index.
+    TupleElement(usize),
+
+    /// The field of a UDT with the given name.
+    UdtField(String),
+}
+
+impl Display for ValuePathSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValuePathSegment::Element => write!(f, "element"),
+            ValuePathSegment::MapKey => write!(f, "map key"),
+            ValuePathSegment::MapValue => write!(f, "map value"),
+            ValuePathSegment::TupleElement(index) => write!(f, "tuple element {index}"),
+            ValuePathSegment::UdtField(name) => write!(f, "field {name}"),
+        }
+    }
+}
+
 fn mk_ser_err<T>(
     got: &ColumnType,
     kind: impl Into<BuiltinSerializationErrorKind>,
@@ -1046,7 +1474,7 @@ fn mk_ser_err_named(
 ) -> SerializationError {
     SerializationError::new(BuiltinSerializationError {
         rust_name: name,
-        got: got.clone(),
+        got: got.cql_type_name(),
         kind: kind.into(),
     })
 }
@@ -1076,9 +1504,25 @@ pub enum BuiltinTypeCheckErrorKind {
     /// A type check failure specific to a CQL UDT.
     UdtError(UdtTypeCheckErrorKind),
 
-    /// Custom CQL type - unsupported
-    // TODO: Should we actually support it? Counters used to be implemented like that.
+    /// Custom CQL type - unsupported, unless a codec is registered for it in the
+    /// [`CustomTypeRegistry`](crate::frame::response::custom_types::CustomTypeRegistry).
     CustomTypeUnsupported,
+
+    /// A [`CqlValue::Custom`] can only be serialized as a custom type.
+    NotCustomType,
+
+    /// The type has an id unknown to the driver (see [`ColumnType::Unknown`]),
+    /// so values can't be serialized as it, except for a [`CqlValue::Unknown`] of the same id.
+    UnknownType {
+        /// The id of the type.
+        id: u16,
+    },
+
+    /// A [`CqlValue::Unknown`] can only be serialized as the unknown type of the same id.
+    NotSameUnknownType {
+        /// The id of the type of the value.
+        id: u16,
+    },
 }
 
 impl From<SetOrListTypeCheckErrorKind> for BuiltinTypeCheckErrorKind {
@@ -1124,6 +1568,24 @@ impl Display for BuiltinTypeCheckErrorKind {
             BuiltinTypeCheckErrorKind::CustomTypeUnsupported => {
                 write!(f, "custom CQL types are unsupported")
             }
+            BuiltinTypeCheckErrorKind::NotCustomType => {
+                write!(
+                    f,
+                    "the value of a custom type can only be serialized as a custom type"
+                )
+            }
+            BuiltinTypeCheckErrorKind::UnknownType { id } => {
+                write!(
+                    f,
+                    "the CQL type with id {id:#06x} is unknown to the driver, so values can't be serialized as it"
+                )
+            }
+            BuiltinTypeCheckErrorKind::NotSameUnknownType { id } => {
+                write!(
+                    f,
+                    "the value of the unknown CQL type with id {id:#06x} can only be serialized as the same type"
+                )
+            }
         }
     }
 }
@@ -1150,6 +1612,9 @@ pub enum BuiltinSerializationErrorKind {
 
     /// A serialization failure specific to a CQL UDT.
     UdtError(UdtSerializationErrorKind),
+
+    /// The value is NaN, which [`StrictFloat`] doesn't allow.
+    NanNotAllowed,
 }
 
 impl From<SetOrListSerializationErrorKind> for BuiltinSerializationErrorKind {
@@ -1195,6 +1660,9 @@ impl Display for BuiltinSerializationErrorKind {
             BuiltinSerializationErrorKind::MapError(err) => err.fmt(f),
             BuiltinSerializationErrorKind::TupleError(err) => err.fmt(f),
             BuiltinSerializationErrorKind::UdtError(err) => err.fmt(f),
+            BuiltinSerializationErrorKind::NanNotAllowed => {
+                write!(f, "the value is NaN, which is not allowed by StrictFloat")
+            }
         }
     }
 }
@@ -1232,6 +1700,26 @@ pub enum MapSerializationErrorKind {
 
     /// One of the values in the map failed to serialize.
     ValueSerializationFailed(SerializationError),
+
+    /// The key of the entry with the given index was serialized as null or unset,
+    /// which is not allowed in a map.
+    NullOrUnsetKey {
+        /// Index of the entry, in the order of iteration over the map.
+        index: usize,
+
+        /// Whether the key was null or unset.
+        value: NullOrUnset,
+    },
+
+    /// The value of the entry with the given index was serialized as null or unset,
+    /// which is not allowed in a map.
+    NullOrUnsetValue {
+        /// Index of the entry, in the order of iteration over the map.
+        index: usize,
+
+        /// Whether the value was null or unset.
+        value: NullOrUnset,
+    },
 }
 
 impl Display for MapSerializationErrorKind {
@@ -1249,6 +1737,18 @@ impl Display for MapSerializationErrorKind {
             MapSerializationErrorKind::ValueSerializationFailed(err) => {
                 write!(f, "failed to serialize one of the values: {}", err)
             }
+            MapSerializationErrorKind::NullOrUnsetKey { index, value } => {
+                write!(
+                    f,
+                    "{value} key of the entry at index {index} is not allowed inside a CQL map"
+                )
+            }
+            MapSerializationErrorKind::NullOrUnsetValue { index, value } => {
+                write!(
+                    f,
+                    "{value} value of the entry at index {index} is not allowed inside a CQL map"
+                )
+            }
         }
     }
 }
@@ -1283,6 +1783,16 @@ pub enum SetOrListSerializationErrorKind {
 
     /// One of the elements of the set/list failed to serialize.
     ElementSerializationFailed(SerializationError),
+
+    /// The element with the given index was serialized as null or unset,
+    /// which is not allowed in a set/list.
+    NullOrUnsetElement {
+        /// Index of the element, in the order of iteration over the collection.
+        index: usize,
+
+        /// Whether the element was null or unset.
+        value: NullOrUnset,
+    },
 }
 
 impl Display for SetOrListSerializationErrorKind {
@@ -1297,6 +1807,12 @@ impl Display for SetOrListSerializationErrorKind {
             SetOrListSerializationErrorKind::ElementSerializationFailed(err) => {
                 write!(f, "failed to serialize one of the elements: {err}")
             }
+            SetOrListSerializationErrorKind::NullOrUnsetElement { index, value } => {
+                write!(
+                    f,
+                    "{value} element at index {index} is not allowed inside a CQL list or set"
+                )
+            }
         }
     }
 }
@@ -1349,6 +1865,13 @@ pub enum TupleSerializationErrorKind {
         /// The error that caused the tuple field serialization to fail.
         err: SerializationError,
     },
+
+    /// The tuple element with the given index was serialized as unset,
+    /// which is not allowed in a tuple. Null elements are allowed.
+    UnsetElement {
+        /// Index of the unset tuple element.
+        index: usize,
+    },
 }
 
 impl Display for TupleSerializationErrorKind {
@@ -1357,6 +1880,12 @@ impl Display for TupleSerializationErrorKind {
             TupleSerializationErrorKind::ElementSerializationFailed { index, err } => {
                 write!(f, "element no. {index} failed to serialize: {err}")
             }
+            TupleSerializationErrorKind::UnsetElement { index } => {
+                write!(
+                    f,
+                    "unset element no. {index} is not allowed inside a CQL tuple"
+                )
+            }
         }
     }
 }
@@ -1397,6 +1926,12 @@ pub enum UdtTypeCheckErrorKind {
         /// The name of the CQL UDT field.
         db_field_name: String,
     },
+
+    /// The Rust data contains the same field more than once.
+    DuplicateField {
+        /// Name of the duplicated field.
+        field_name: String,
+    },
 }
 
 impl Display for UdtTypeCheckErrorKind {
@@ -1424,6 +1959,10 @@ impl Display for UdtTypeCheckErrorKind {
                 f,
                 "expected field with name {db_field_name} at given position, but the Rust field name is {rust_field_name}"
             ),
+            UdtTypeCheckErrorKind::DuplicateField { field_name } => write!(
+                f,
+                "the field {field_name} is present more than once in the Rust data"
+            ),
         }
     }
 }
@@ -1440,6 +1979,13 @@ pub enum UdtSerializationErrorKind {
         /// The error that caused the UDT field serialization to fail.
         err: SerializationError,
     },
+
+    /// The field was serialized as unset, which is not allowed in a UDT.
+    /// Null fields are allowed.
+    UnsetField {
+        /// Name of the unset field.
+        field_name: String,
+    },
 }
 
 impl Display for UdtSerializationErrorKind {
@@ -1448,6 +1994,12 @@ impl Display for UdtSerializationErrorKind {
             UdtSerializationErrorKind::FieldSerializationFailed { field_name, err } => {
                 write!(f, "field {field_name} failed to serialize: {err}")
             }
+            UdtSerializationErrorKind::UnsetField { field_name } => {
+                write!(
+                    f,
+                    "unset field {field_name} is not allowed inside a CQL UDT"
+                )
+            }
         }
     }
 }
@@ -1485,23 +2037,118 @@ pub enum ValueToSerializeCqlAdapterError {
     },
 }
 
-#[cfg(test)]
-mod tests {
-    use std::collections::BTreeMap;
-
-    use crate::frame::response::result::{ColumnType, CqlValue};
-    use crate::frame::value::{Counter, MaybeUnset, Unset, Value, ValueTooBig};
-    use crate::types::serialize::value::{
-        BuiltinSerializationError, BuiltinSerializationErrorKind, BuiltinTypeCheckError,
-        BuiltinTypeCheckErrorKind, MapSerializationErrorKind, MapTypeCheckErrorKind,
-        SetOrListSerializationErrorKind, SetOrListTypeCheckErrorKind, TupleSerializationErrorKind,
-        TupleTypeCheckErrorKind, ValueAdapter,
-    };
-    use crate::types::serialize::{CellWriter, SerializationError};
-
-    use bigdecimal::BigDecimal;
-    use num_bigint::BigInt;
-    use scylla_macros::SerializeCql;
+impl ClassifySerializationError for ValueToSerializeCqlAdapterError {
+    fn classify(&self) -> SerializationErrorKind {
+        match self {
+            ValueToSerializeCqlAdapterError::TooBig => SerializationErrorKind::SizeLimit,
+            ValueToSerializeCqlAdapterError::TooShort { .. }
+            | ValueToSerializeCqlAdapterError::DeclaredVsActualSizeMismatch { .. }
+            | ValueToSerializeCqlAdapterError::InvalidDeclaredSize { .. } => {
+                SerializationErrorKind::Other
+            }
+        }
+    }
+}
+
+impl ClassifySerializationError for BuiltinTypeCheckError {
+    fn classify(&self) -> SerializationErrorKind {
+        match &self.kind {
+            BuiltinTypeCheckErrorKind::MismatchedType { .. }
+            | BuiltinTypeCheckErrorKind::NotEmptyable
+            | BuiltinTypeCheckErrorKind::CustomTypeUnsupported
+            | BuiltinTypeCheckErrorKind::NotCustomType
+            | BuiltinTypeCheckErrorKind::UnknownType { .. }
+            | BuiltinTypeCheckErrorKind::NotSameUnknownType { .. }
+            | BuiltinTypeCheckErrorKind::SetOrListError(
+                SetOrListTypeCheckErrorKind::NotSetOrList,
+            )
+            | BuiltinTypeCheckErrorKind::MapError(MapTypeCheckErrorKind::NotMap)
+            | BuiltinTypeCheckErrorKind::TupleError(TupleTypeCheckErrorKind::NotTuple)
+            | BuiltinTypeCheckErrorKind::UdtError(
+                UdtTypeCheckErrorKind::NotUdt
+                | UdtTypeCheckErrorKind::NameMismatch { .. }
+                | UdtTypeCheckErrorKind::FieldNameMismatch { .. },
+            ) => SerializationErrorKind::TypeMismatch,
+            // Rust tuples shorter than the CQL type are allowed
+            BuiltinTypeCheckErrorKind::TupleError(TupleTypeCheckErrorKind::WrongElementCount {
+                ..
+            })
+            | BuiltinTypeCheckErrorKind::UdtError(
+                UdtTypeCheckErrorKind::NoSuchFieldInUdt { .. }
+                | UdtTypeCheckErrorKind::DuplicateField { .. },
+            ) => SerializationErrorKind::ExtraField,
+            BuiltinTypeCheckErrorKind::UdtError(
+                UdtTypeCheckErrorKind::ValueMissingForUdtField { .. },
+            ) => SerializationErrorKind::MissingField,
+        }
+    }
+}
+
+impl ClassifySerializationError for BuiltinSerializationError {
+    fn classify(&self) -> SerializationErrorKind {
+        match &self.kind {
+            BuiltinSerializationErrorKind::SizeOverflow
+            | BuiltinSerializationErrorKind::SetOrListError(
+                SetOrListSerializationErrorKind::TooManyElements,
+            )
+            | BuiltinSerializationErrorKind::MapError(MapSerializationErrorKind::TooManyElements) => {
+                SerializationErrorKind::SizeLimit
+            }
+            BuiltinSerializationErrorKind::ValueOverflow
+            | BuiltinSerializationErrorKind::NanNotAllowed
+            | BuiltinSerializationErrorKind::SetOrListError(
+                SetOrListSerializationErrorKind::NullOrUnsetElement { .. },
+            )
+            | BuiltinSerializationErrorKind::MapError(
+                MapSerializationErrorKind::NullOrUnsetKey { .. }
+                | MapSerializationErrorKind::NullOrUnsetValue { .. },
+            )
+            | BuiltinSerializationErrorKind::TupleError(
+                TupleSerializationErrorKind::UnsetElement { .. },
+            )
+            | BuiltinSerializationErrorKind::UdtError(UdtSerializationErrorKind::UnsetField {
+                ..
+            }) => SerializationErrorKind::ValueOutOfRange,
+            // Failures of nested values are classified by their cause
+            BuiltinSerializationErrorKind::SetOrListError(
+                SetOrListSerializationErrorKind::ElementSerializationFailed(err),
+            )
+            | BuiltinSerializationErrorKind::MapError(
+                MapSerializationErrorKind::KeySerializationFailed(err)
+                | MapSerializationErrorKind::ValueSerializationFailed(err),
+            )
+            | BuiltinSerializationErrorKind::TupleError(
+                TupleSerializationErrorKind::ElementSerializationFailed { err, .. },
+            )
+            | BuiltinSerializationErrorKind::UdtError(
+                UdtSerializationErrorKind::FieldSerializationFailed { err, .. },
+            ) => err.kind(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+    use crate::frame::response::result::{ColumnType, CqlValue};
+    use crate::frame::response::udt_value::UdtValueBuilder;
+    use crate::frame::value::{
+        Counter, CqlDuration, MaybeUnset, StrictFloat, Unset, Value, ValueTooBig,
+    };
+    use crate::types::serialize::value::{
+        BuiltinSerializationError, BuiltinSerializationErrorKind, BuiltinTypeCheckError,
+        BuiltinTypeCheckErrorKind, MapSerializationErrorKind, MapTypeCheckErrorKind,
+        SetOrListSerializationErrorKind, SetOrListTypeCheckErrorKind, TupleSerializationErrorKind,
+        TupleTypeCheckErrorKind, ValueAdapter, ValuePath, ValuePathSegment,
+        ValueToSerializeCqlAdapterError,
+    };
+    use crate::types::serialize::writers::{CellOverflowError, NullOrUnset};
+    use crate::types::serialize::{CellWriter, SerializationError, SerializationErrorKind};
+
+    use bigdecimal::BigDecimal;
+    use num_bigint::BigInt;
+    use scylla_macros::SerializeCql;
 
     use super::{SerializeCql, UdtSerializationErrorKind, UdtTypeCheckErrorKind};
 
@@ -1592,7 +2239,7 @@ mod tests {
         let err = do_serialize_err(v, &ColumnType::Double);
         let err = get_typeck_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<i32>());
-        assert_eq!(err.got, ColumnType::Double);
+        assert_eq!(err.got, "double");
         assert!(matches!(
             err.kind,
             BuiltinTypeCheckErrorKind::MismatchedType {
@@ -1606,7 +2253,7 @@ mod tests {
         let err = do_serialize_err(v, &ColumnType::Double);
         let err = get_typeck_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<&str>());
-        assert_eq!(err.got, ColumnType::Double);
+        assert_eq!(err.got, "double");
         assert!(matches!(
             err.kind,
             BuiltinTypeCheckErrorKind::MismatchedType {
@@ -1622,13 +2269,100 @@ mod tests {
         let err = do_serialize_err(v, &ColumnType::Decimal);
         let err = get_ser_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<BigDecimal>());
-        assert_eq!(err.got, ColumnType::Decimal);
+        assert_eq!(err.got, "decimal");
         assert!(matches!(
             err.kind,
             BuiltinSerializationErrorKind::ValueOverflow,
         ));
     }
 
+    fn check_varint(value: BigInt) {
+        let expected = value.to_signed_bytes_be();
+        let serialized = do_serialize(value.clone(), &ColumnType::Varint);
+        assert_eq!(
+            serialized[..4],
+            (expected.len() as i32).to_be_bytes(),
+            "{}",
+            value
+        );
+        assert_eq!(serialized[4..], expected, "{}", value);
+
+        let decimal = BigDecimal::new(value.clone(), 3);
+        let serialized = do_serialize(decimal, &ColumnType::Decimal);
+        assert_eq!(serialized[4..8], 3i32.to_be_bytes());
+        assert_eq!(serialized[8..], expected, "{}", value);
+    }
+
+    #[test]
+    fn test_varint_matches_to_signed_bytes_be() {
+        // Boundary values around changes of the byte length and of the u64 digit count.
+        for bits in 0..=260usize {
+            let power = BigInt::from(1) << bits;
+            for delta in -2i32..=2 {
+                let value = &power + delta;
+                check_varint(value.clone());
+                check_varint(-value);
+            }
+        }
+
+        for value in [
+            i64::MIN as i128,
+            i64::MAX as i128,
+            u64::MAX as i128,
+            i128::MIN,
+            i128::MAX,
+        ] {
+            check_varint(BigInt::from(value));
+            check_varint(-BigInt::from(value));
+        }
+
+        // Pseudo-random values of various lengths, including ones with
+        // runs of 0x00 and 0xFF bytes.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..5000 {
+            let len = (next() % 48) as usize + 1;
+            let bytes = (0..len)
+                .map(|_| match next() % 4 {
+                    0 => 0x00,
+                    1 => 0xFF,
+                    _ => next() as u8,
+                })
+                .collect::<Vec<u8>>();
+            check_varint(BigInt::from_signed_bytes_be(&bytes));
+        }
+    }
+
+    #[test]
+    fn test_duration_matches_legacy() {
+        let edge_cases = (0..64)
+            .map(|i| 1i64.wrapping_shl(i))
+            .flat_map(|power| {
+                [
+                    power.wrapping_sub(1),
+                    power,
+                    power.wrapping_add(1),
+                    power.wrapping_neg(),
+                ]
+            })
+            .chain([0, i64::MAX, i64::MIN]);
+        for v in edge_cases {
+            let duration = CqlDuration {
+                months: v as i32,
+                days: (v >> 32) as i32,
+                nanoseconds: v,
+            };
+            let mut legacy = Vec::new();
+            Value::serialize(&duration, &mut legacy).unwrap();
+            assert_eq!(do_serialize(duration, &ColumnType::Duration), legacy);
+        }
+    }
+
     #[test]
     fn test_set_or_list_errors() {
         // Not a set or list
@@ -1636,7 +2370,7 @@ mod tests {
         let err = do_serialize_err(v, &ColumnType::Double);
         let err = get_typeck_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<Vec<i32>>());
-        assert_eq!(err.got, ColumnType::Double);
+        assert_eq!(err.got, "double");
         assert!(matches!(
             err.kind,
             BuiltinTypeCheckErrorKind::SetOrListError(SetOrListTypeCheckErrorKind::NotSetOrList),
@@ -1651,7 +2385,7 @@ mod tests {
         let err = do_serialize_err(v, &typ);
         let err = get_ser_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<&[Unset]>());
-        assert_eq!(err.got, typ);
+        assert_eq!(err.got, typ.cql_type_name());
         assert!(matches!(
             err.kind,
             BuiltinSerializationErrorKind::SetOrListError(
@@ -1665,7 +2399,7 @@ mod tests {
         let err = do_serialize_err(v, &typ);
         let err = get_ser_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<Vec<i32>>());
-        assert_eq!(err.got, typ);
+        assert_eq!(err.got, typ.cql_type_name());
         let BuiltinSerializationErrorKind::SetOrListError(
             SetOrListSerializationErrorKind::ElementSerializationFailed(err),
         ) = &err.kind
@@ -1688,7 +2422,7 @@ mod tests {
         let err = do_serialize_err(v, &ColumnType::Double);
         let err = get_typeck_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<BTreeMap<&str, &str>>());
-        assert_eq!(err.got, ColumnType::Double);
+        assert_eq!(err.got, "double");
         assert!(matches!(
             err.kind,
             BuiltinTypeCheckErrorKind::MapError(MapTypeCheckErrorKind::NotMap),
@@ -1703,7 +2437,7 @@ mod tests {
         let err = do_serialize_err(v, &typ);
         let err = get_ser_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<BTreeMap<i32, i32>>());
-        assert_eq!(err.got, typ);
+        assert_eq!(err.got, typ.cql_type_name());
         let BuiltinSerializationErrorKind::MapError(
             MapSerializationErrorKind::KeySerializationFailed(err),
         ) = &err.kind
@@ -1724,7 +2458,7 @@ mod tests {
         let err = do_serialize_err(v, &typ);
         let err = get_ser_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<BTreeMap<i32, i32>>());
-        assert_eq!(err.got, typ);
+        assert_eq!(err.got, typ.cql_type_name());
         let BuiltinSerializationErrorKind::MapError(
             MapSerializationErrorKind::ValueSerializationFailed(err),
         ) = &err.kind
@@ -1740,6 +2474,177 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_null_and_unset_nested_values() {
+        fn nested_err<T: SerializeCql>(v: T, typ: &ColumnType) -> BuiltinSerializationErrorKind {
+            let err = do_serialize_err(v, typ);
+            get_ser_err(&err).kind.clone()
+        }
+        let int_list = ColumnType::List(Box::new(ColumnType::Int));
+        let int_set = ColumnType::Set(Box::new(ColumnType::Int));
+        let int_map = ColumnType::Map(Box::new(ColumnType::Int), Box::new(ColumnType::Int));
+
+        // List element
+        let kind = nested_err(vec![Some(1), Some(2), Some(3), None], &int_list);
+        assert!(matches!(
+            kind,
+            BuiltinSerializationErrorKind::SetOrListError(
+                SetOrListSerializationErrorKind::NullOrUnsetElement {
+                    index: 3,
+                    value: NullOrUnset::Null
+                }
+            )
+        ));
+        assert_eq!(
+            kind.to_string(),
+            "null element at index 3 is not allowed inside a CQL list or set"
+        );
+        let kind = nested_err(vec![MaybeUnset::Set(1), MaybeUnset::Unset], &int_list);
+        assert!(matches!(
+            kind,
+            BuiltinSerializationErrorKind::SetOrListError(
+                SetOrListSerializationErrorKind::NullOrUnsetElement {
+                    index: 1,
+                    value: NullOrUnset::Unset
+                }
+            )
+        ));
+
+        // Set element
+        let kind = nested_err(BTreeSet::from([None, Some(1)]), &int_set);
+        assert!(matches!(
+            kind,
+            BuiltinSerializationErrorKind::SetOrListError(
+                SetOrListSerializationErrorKind::NullOrUnsetElement {
+                    index: 0,
+                    value: NullOrUnset::Null
+                }
+            )
+        ));
+
+        // Map key
+        let kind = nested_err(BTreeMap::from([(Some(1), 1), (None, 2)]), &int_map);
+        assert!(matches!(
+            kind,
+            BuiltinSerializationErrorKind::MapError(MapSerializationErrorKind::NullOrUnsetKey {
+                index: 0,
+                value: NullOrUnset::Null
+            })
+        ));
+
+        // Map value
+        let kind = nested_err(
+            BTreeMap::from([(1, MaybeUnset::Set(1)), (2, MaybeUnset::Unset)]),
+            &int_map,
+        );
+        assert!(matches!(
+            kind,
+            BuiltinSerializationErrorKind::MapError(MapSerializationErrorKind::NullOrUnsetValue {
+                index: 1,
+                value: NullOrUnset::Unset
+            })
+        ));
+        assert_eq!(
+            kind.to_string(),
+            "unset value of the entry at index 1 is not allowed inside a CQL map"
+        );
+
+        // Tuple elements may be null, but not unset
+        let tuple = ColumnType::Tuple(vec![ColumnType::Int, ColumnType::Int]);
+        do_serialize((1, None::<i32>), &tuple);
+        let kind = nested_err((1, MaybeUnset::<i32>::Unset), &tuple);
+        assert!(matches!(
+            kind,
+            BuiltinSerializationErrorKind::TupleError(TupleSerializationErrorKind::UnsetElement {
+                index: 1
+            })
+        ));
+
+        // Nested values are checked at every level
+        let err = do_serialize_err(
+            vec![vec![Some(1)], vec![None]],
+            &ColumnType::List(Box::new(int_list.clone())),
+        );
+        let (path, err) = get_ser_err(&err).innermost_error();
+        assert_eq!(path, ValuePath(vec![ValuePathSegment::Element]));
+        assert_eq!(
+            err.to_string(),
+            "Failed to serialize Rust type alloc::vec::Vec<core::option::Option<i32>> \
+             into CQL type list<int>: null element at index 0 is not allowed inside a CQL list or set"
+        );
+
+        // Valid collections are serialized as before
+        do_serialize(vec![Some(1), Some(2)], &int_list);
+        do_serialize(BTreeMap::from([(1, MaybeUnset::Set(1))]), &int_map);
+    }
+
+    #[derive(SerializeCql)]
+    #[scylla(crate = crate)]
+    struct TestUdtWithMaybeUnset {
+        a: i32,
+        b: MaybeUnset<Option<i32>>,
+    }
+
+    #[derive(SerializeCql)]
+    #[scylla(crate = crate, flavor = "enforce_order")]
+    struct TestOrderedUdtWithMaybeUnset {
+        a: i32,
+        b: MaybeUnset<Option<i32>>,
+    }
+
+    #[test]
+    fn test_udt_fields_may_be_null_but_not_unset() {
+        let typ = ColumnType::UserDefinedType {
+            type_name: "typ".to_string(),
+            keyspace: "ks".to_string(),
+            field_types: vec![
+                ("a".to_string(), ColumnType::Int),
+                ("b".to_string(), ColumnType::Int),
+            ]
+            .into(),
+        };
+        do_serialize(
+            TestUdtWithMaybeUnset {
+                a: 1,
+                b: MaybeUnset::Set(None),
+            },
+            &typ,
+        );
+        do_serialize(
+            TestOrderedUdtWithMaybeUnset {
+                a: 1,
+                b: MaybeUnset::Set(None),
+            },
+            &typ,
+        );
+
+        let errs = [
+            do_serialize_err(
+                TestUdtWithMaybeUnset {
+                    a: 1,
+                    b: MaybeUnset::Unset,
+                },
+                &typ,
+            ),
+            do_serialize_err(
+                TestOrderedUdtWithMaybeUnset {
+                    a: 1,
+                    b: MaybeUnset::Unset,
+                },
+                &typ,
+            ),
+        ];
+        for err in errs {
+            let err = get_ser_err(&err);
+            assert!(matches!(
+                &err.kind,
+                BuiltinSerializationErrorKind::UdtError(UdtSerializationErrorKind::UnsetField {
+                    field_name
+                }) if field_name == "b"
+            ));
+        }
+    }
+
     #[test]
     fn test_tuple_errors() {
         // Not a tuple
@@ -1747,7 +2652,7 @@ mod tests {
         let err = do_serialize_err(v, &ColumnType::Double);
         let err = get_typeck_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<(i32, i32, i32)>());
-        assert_eq!(err.got, ColumnType::Double);
+        assert_eq!(err.got, "double");
         assert!(matches!(
             err.kind,
             BuiltinTypeCheckErrorKind::TupleError(TupleTypeCheckErrorKind::NotTuple),
@@ -1759,7 +2664,7 @@ mod tests {
         let err = do_serialize_err(v, &typ);
         let err = get_typeck_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<(i32, i32, i32)>());
-        assert_eq!(err.got, typ);
+        assert_eq!(err.got, typ.cql_type_name());
         assert!(matches!(
             err.kind,
             BuiltinTypeCheckErrorKind::TupleError(TupleTypeCheckErrorKind::WrongElementCount {
@@ -1774,7 +2679,7 @@ mod tests {
         let err = do_serialize_err(v, &typ);
         let err = get_ser_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<(i32, &str, f64)>());
-        assert_eq!(err.got, typ);
+        assert_eq!(err.got, typ.cql_type_name());
         let BuiltinSerializationErrorKind::TupleError(
             TupleSerializationErrorKind::ElementSerializationFailed { index: 2, err },
         ) = &err.kind
@@ -1797,7 +2702,7 @@ mod tests {
         let err = do_serialize_err(v, &ColumnType::Counter);
         let err = get_typeck_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<CqlValue>());
-        assert_eq!(err.got, ColumnType::Counter);
+        assert_eq!(err.got, "counter");
         assert!(matches!(err.kind, BuiltinTypeCheckErrorKind::NotEmptyable));
 
         // Handle tuples and UDTs in separate tests, as they have some
@@ -1815,7 +2720,7 @@ mod tests {
         let err = do_serialize_err(v, &ColumnType::Double);
         let err = get_typeck_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<CqlValue>());
-        assert_eq!(err.got, ColumnType::Double);
+        assert_eq!(err.got, "double");
         assert!(matches!(
             err.kind,
             BuiltinTypeCheckErrorKind::TupleError(TupleTypeCheckErrorKind::NotTuple),
@@ -1831,7 +2736,7 @@ mod tests {
         let err = do_serialize_err(v, &typ);
         let err = get_typeck_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<CqlValue>());
-        assert_eq!(err.got, typ);
+        assert_eq!(err.got, typ.cql_type_name());
         assert!(matches!(
             err.kind,
             BuiltinTypeCheckErrorKind::TupleError(TupleTypeCheckErrorKind::WrongElementCount {
@@ -1850,7 +2755,7 @@ mod tests {
         let err = do_serialize_err(v, &typ);
         let err = get_ser_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<CqlValue>());
-        assert_eq!(err.got, typ);
+        assert_eq!(err.got, typ.cql_type_name());
         let BuiltinSerializationErrorKind::TupleError(
             TupleSerializationErrorKind::ElementSerializationFailed { index: 2, err },
         ) = &err.kind
@@ -1881,7 +2786,7 @@ mod tests {
         let err = do_serialize_err(v, &ColumnType::Double);
         let err = get_typeck_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<CqlValue>());
-        assert_eq!(err.got, ColumnType::Double);
+        assert_eq!(err.got, "double");
         assert!(matches!(
             err.kind,
             BuiltinTypeCheckErrorKind::UdtError(UdtTypeCheckErrorKind::NotUdt),
@@ -1904,12 +2809,13 @@ mod tests {
                 ("a".to_string(), ColumnType::Int),
                 ("b".to_string(), ColumnType::Int),
                 ("c".to_string(), ColumnType::Int),
-            ],
+            ]
+            .into(),
         };
         let err = do_serialize_err(v, &typ);
         let err = get_typeck_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<CqlValue>());
-        assert_eq!(err.got, typ);
+        assert_eq!(err.got, typ.cql_type_name());
         let BuiltinTypeCheckErrorKind::UdtError(UdtTypeCheckErrorKind::NameMismatch {
             keyspace,
             type_name,
@@ -1937,12 +2843,13 @@ mod tests {
                 ("a".to_string(), ColumnType::Int),
                 ("b".to_string(), ColumnType::Int),
                 // c is missing
-            ],
+            ]
+            .into(),
         };
         let err = do_serialize_err(v, &typ);
         let err = get_typeck_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<CqlValue>());
-        assert_eq!(err.got, typ);
+        assert_eq!(err.got, typ.cql_type_name());
         let BuiltinTypeCheckErrorKind::UdtError(UdtTypeCheckErrorKind::NoSuchFieldInUdt {
             field_name,
         }) = &err.kind
@@ -1971,12 +2878,13 @@ mod tests {
                 ("a".to_string(), ColumnType::Int),
                 ("b".to_string(), ColumnType::Int),
                 ("c".to_string(), ColumnType::Double),
-            ],
+            ]
+            .into(),
         };
         let err = do_serialize_err(v, &typ);
         let err = get_ser_err(&err);
         assert_eq!(err.rust_name, std::any::type_name::<CqlValue>());
-        assert_eq!(err.got, typ);
+        assert_eq!(err.got, typ.cql_type_name());
         let BuiltinSerializationErrorKind::UdtError(
             UdtSerializationErrorKind::FieldSerializationFailed { field_name, err },
         ) = &err.kind
@@ -2020,7 +2928,8 @@ mod tests {
                     "c".to_string(),
                     ColumnType::List(Box::new(ColumnType::BigInt)),
                 ),
-            ],
+            ]
+            .into(),
         };
 
         let reference = do_serialize(
@@ -2070,21 +2979,21 @@ mod tests {
                     "c".to_string(),
                     ColumnType::List(Box::new(ColumnType::BigInt)),
                 ),
-            ],
+            ]
+            .into(),
         };
 
+        // Fields of CqlValue are matched by name, so they don't need to be in the order of the type
         let reference = do_serialize(
             CqlValue::UserDefinedType {
                 keyspace: "ks".to_string(),
                 type_name: "typ".to_string(),
                 fields: vec![
-                    // FIXME: UDTs in CqlValue should also honor the order
-                    // For now, it's swapped here as well
-                    ("b".to_string(), Some(CqlValue::Int(42))),
                     (
                         "a".to_string(),
                         Some(CqlValue::Text(String::from("Ala ma kota"))),
                     ),
+                    ("b".to_string(), Some(CqlValue::Int(42))),
                     (
                         "c".to_string(),
                         Some(CqlValue::List(vec![
@@ -2097,6 +3006,26 @@ mod tests {
             },
             &typ,
         );
+        let built = do_serialize(
+            UdtValueBuilder::new(&typ)
+                .unwrap()
+                .set("a", "Ala ma kota")
+                .unwrap()
+                .set("b", 42)
+                .unwrap()
+                .set(
+                    "c",
+                    CqlValue::List(vec![
+                        CqlValue::BigInt(1),
+                        CqlValue::BigInt(2),
+                        CqlValue::BigInt(3),
+                    ]),
+                )
+                .unwrap()
+                .build(),
+            &typ,
+        );
+        assert_eq!(reference, built);
         let udt = do_serialize(
             TestUdtWithFieldSorting {
                 a: "Ala ma kota".to_owned(),
@@ -2109,6 +3038,247 @@ mod tests {
         assert_eq!(reference, udt);
     }
 
+    // Declaration order differs from the lexicographical one
+    #[derive(SerializeCql)]
+    #[scylla(crate = crate)]
+    struct TestUdtWithManyFields {
+        zeta: i32,
+        alpha: i32,
+        mu: i32,
+        beta: i32,
+        omega: i32,
+        gamma: i32,
+    }
+
+    #[test]
+    fn test_udt_serialization_with_field_sorting_many_fields() {
+        let udt = TestUdtWithManyFields {
+            zeta: 1,
+            alpha: 2,
+            mu: 3,
+            beta: 4,
+            omega: 5,
+            gamma: 6,
+        };
+        let values = [
+            ("zeta", 1),
+            ("alpha", 2),
+            ("mu", 3),
+            ("beta", 4),
+            ("omega", 5),
+            ("gamma", 6),
+        ];
+        let orders: [&[&str]; 4] = [
+            &["zeta", "alpha", "mu", "beta", "omega", "gamma"],
+            &["gamma", "omega", "beta", "mu", "alpha", "zeta"],
+            &["alpha", "beta", "gamma", "mu", "omega", "zeta"],
+            // Fields missing in the struct are interleaved
+            &[
+                "zeta", "extra1", "alpha", "mu", "extra2", "gamma", "beta", "omega",
+            ],
+        ];
+        for order in orders {
+            let typ = ColumnType::UserDefinedType {
+                type_name: "typ".to_string(),
+                keyspace: "ks".to_string(),
+                field_types: order
+                    .iter()
+                    .map(|name| (name.to_string(), ColumnType::Int))
+                    .collect(),
+            };
+            let reference = do_serialize(
+                CqlValue::UserDefinedType {
+                    keyspace: "ks".to_string(),
+                    type_name: "typ".to_string(),
+                    fields: values
+                        .iter()
+                        .map(|(name, value)| (name.to_string(), Some(CqlValue::Int(*value))))
+                        .collect(),
+                },
+                &typ,
+            );
+            assert_eq!(do_serialize(&udt, &typ), reference, "{:?}", order);
+        }
+
+        // A field of the struct missing in the UDT is still an error
+        let typ = ColumnType::UserDefinedType {
+            type_name: "typ".to_string(),
+            keyspace: "ks".to_string(),
+            field_types: ["zeta", "alpha", "beta", "omega", "gamma"]
+                .iter()
+                .map(|name| (name.to_string(), ColumnType::Int))
+                .collect(),
+        };
+        let err = udt
+            .serialize(&typ, CellWriter::new(&mut Vec::new()))
+            .unwrap_err();
+        let err = err.0.downcast_ref::<BuiltinTypeCheckError>().unwrap();
+        assert!(matches!(
+            &err.kind,
+            BuiltinTypeCheckErrorKind::UdtError(
+                UdtTypeCheckErrorKind::NoSuchFieldInUdt { field_name }
+            ) if field_name == "mu"
+        ));
+    }
+
+    #[test]
+    fn test_float_byte_patterns() {
+        // Values are serialized as they are, NaN payloads and the sign of zero included
+        let doubles: [(f64, [u8; 8]); 5] = [
+            (0.0, [0, 0, 0, 0, 0, 0, 0, 0]),
+            (-0.0, [0x80, 0, 0, 0, 0, 0, 0, 0]),
+            (f64::NAN, [0x7f, 0xf8, 0, 0, 0, 0, 0, 0]),
+            (
+                f64::from_bits(0x7ff8_0000_0000_0001),
+                [0x7f, 0xf8, 0, 0, 0, 0, 0, 1],
+            ),
+            // The smallest subnormal
+            (f64::from_bits(1), [0, 0, 0, 0, 0, 0, 0, 1]),
+        ];
+        for (value, bytes) in doubles {
+            let expected = [&[0, 0, 0, 8][..], &bytes[..]].concat();
+            assert_eq!(do_serialize(value, &ColumnType::Double), expected);
+        }
+
+        let floats: [(f32, [u8; 4]); 4] = [
+            (0.0, [0, 0, 0, 0]),
+            (-0.0, [0x80, 0, 0, 0]),
+            (f32::NAN, [0x7f, 0xc0, 0, 0]),
+            (f32::from_bits(1), [0, 0, 0, 1]),
+        ];
+        for (value, bytes) in floats {
+            let expected = [&[0, 0, 0, 4][..], &bytes[..]].concat();
+            assert_eq!(do_serialize(value, &ColumnType::Float), expected);
+        }
+    }
+
+    #[test]
+    fn test_strict_float_serialization() {
+        // Negative zero becomes positive, other values are kept
+        assert_eq!(
+            do_serialize(StrictFloat(-0.0f64), &ColumnType::Double),
+            do_serialize(0.0f64, &ColumnType::Double)
+        );
+        assert_eq!(
+            do_serialize(StrictFloat(-0.0f32), &ColumnType::Float),
+            do_serialize(0.0f32, &ColumnType::Float)
+        );
+        for value in [
+            0.0,
+            1.5,
+            -1.5,
+            f64::from_bits(1),
+            -f64::from_bits(1),
+            f64::INFINITY,
+        ] {
+            assert_eq!(
+                do_serialize(StrictFloat(value), &ColumnType::Double),
+                do_serialize(value, &ColumnType::Double)
+            );
+        }
+        assert_eq!(
+            do_serialize(StrictFloat(f32::from_bits(1)), &ColumnType::Float),
+            do_serialize(f32::from_bits(1), &ColumnType::Float)
+        );
+
+        // NaN fails, whatever its payload
+        for value in [f64::NAN, -f64::NAN, f64::from_bits(0x7ff0_0000_0000_0001)] {
+            let err = do_serialize_err(StrictFloat(value), &ColumnType::Double);
+            let err = get_ser_err(&err);
+            assert_eq!(err.rust_name, std::any::type_name::<StrictFloat<f64>>());
+            assert!(matches!(
+                err.kind,
+                BuiltinSerializationErrorKind::NanNotAllowed
+            ));
+        }
+        let err = do_serialize_err(StrictFloat(f32::NAN), &ColumnType::Float);
+        assert!(matches!(
+            get_ser_err(&err).kind,
+            BuiltinSerializationErrorKind::NanNotAllowed
+        ));
+
+        // The type is still checked
+        let err = do_serialize_err(StrictFloat(1.0f32), &ColumnType::Double);
+        assert!(matches!(
+            get_typeck_err(&err).kind,
+            BuiltinTypeCheckErrorKind::MismatchedType { .. }
+        ));
+    }
+
+    #[test]
+    fn test_cql_value_udt_fields_are_matched_by_name() {
+        let typ = ColumnType::UserDefinedType {
+            type_name: "typ".to_string(),
+            keyspace: "ks".to_string(),
+            field_types: vec![
+                ("a".to_string(), ColumnType::Int),
+                ("b".to_string(), ColumnType::Text),
+                ("c".to_string(), ColumnType::BigInt),
+            ]
+            .into(),
+        };
+        let udt = |fields: &[(&str, Option<CqlValue>)]| CqlValue::UserDefinedType {
+            keyspace: "ks".to_string(),
+            type_name: "typ".to_string(),
+            fields: fields
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+        };
+        let a = Some(CqlValue::Int(1));
+        let b = Some(CqlValue::Text("x".to_string()));
+        let c = Some(CqlValue::BigInt(2));
+
+        let expected = vec![
+            0, 0, 0, 25, // total length
+            0, 0, 0, 4, 0, 0, 0, 1, // a
+            0, 0, 0, 1, b'x', // b
+            0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 2, // c
+        ];
+        for fields in [
+            [("a", a.clone()), ("b", b.clone()), ("c", c.clone())],
+            [("c", c.clone()), ("a", a.clone()), ("b", b.clone())],
+            [("b", b.clone()), ("c", c.clone()), ("a", a.clone())],
+        ] {
+            assert_eq!(do_serialize(udt(&fields), &typ), expected);
+        }
+
+        // A missing field is null, wherever it is
+        let expected_without_b = vec![
+            0, 0, 0, 24, // total length
+            0, 0, 0, 4, 0, 0, 0, 1, // a
+            255, 255, 255, 255, // b
+            0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 2, // c
+        ];
+        assert_eq!(
+            do_serialize(udt(&[("c", c.clone()), ("a", a.clone())]), &typ),
+            expected_without_b
+        );
+        assert_eq!(
+            do_serialize(
+                udt(&[("a", a.clone()), ("b", None), ("c", c.clone())]),
+                &typ
+            ),
+            expected_without_b
+        );
+
+        // A field given twice is ambiguous, even with the same value
+        for fields in [
+            [("a", a.clone()), ("b", b.clone()), ("a", a.clone())],
+            [("b", b.clone()), ("a", a.clone()), ("a", None)],
+        ] {
+            let err = do_serialize_err(udt(&fields), &typ);
+            let err = get_typeck_err(&err);
+            assert_eq!(err.rust_name, std::any::type_name::<CqlValue>());
+            assert!(matches!(
+                &err.kind,
+                BuiltinTypeCheckErrorKind::UdtError(UdtTypeCheckErrorKind::DuplicateField {
+                    field_name,
+                }) if field_name == "a"
+            ));
+        }
+    }
+
     #[test]
     fn test_udt_serialization_with_missing_rust_fields_at_end() {
         let udt = TestUdtWithFieldSorting::default();
@@ -2123,7 +3293,8 @@ mod tests {
                     "c".to_string(),
                     ColumnType::List(Box::new(ColumnType::BigInt)),
                 ),
-            ],
+            ]
+            .into(),
         };
 
         let typ_unexpected_field = ColumnType::UserDefinedType {
@@ -2139,7 +3310,8 @@ mod tests {
                 // Unexpected fields
                 ("d".to_string(), ColumnType::Counter),
                 ("e".to_string(), ColumnType::Counter),
-            ],
+            ]
+            .into(),
         };
 
         let result_normal = do_serialize(&udt, &typ_normal);
@@ -2187,7 +3359,8 @@ mod tests {
                     "c".to_string(),
                     ColumnType::List(Box::new(ColumnType::BigInt)),
                 ),
-            ],
+            ]
+            .into(),
         };
 
         let result_1 = do_serialize(udt, &typ);
@@ -2220,7 +3393,8 @@ mod tests {
                 ("a".to_string(), ColumnType::Text),
                 ("b".to_string(), ColumnType::Int),
                 // Last field is missing
-            ],
+            ]
+            .into(),
         };
 
         let err = udt
@@ -2229,9 +3403,7 @@ mod tests {
         let err = err.0.downcast_ref::<BuiltinTypeCheckError>().unwrap();
         assert!(matches!(
             err.kind,
-            BuiltinTypeCheckErrorKind::UdtError(
-                UdtTypeCheckErrorKind::ValueMissingForUdtField { .. }
-            )
+            BuiltinTypeCheckErrorKind::UdtError(UdtTypeCheckErrorKind::NoSuchFieldInUdt { .. })
         ));
 
         let typ_wrong_type = ColumnType::UserDefinedType {
@@ -2241,7 +3413,8 @@ mod tests {
                 ("a".to_string(), ColumnType::Text),
                 ("b".to_string(), ColumnType::Int),
                 ("c".to_string(), ColumnType::TinyInt), // Wrong column type
-            ],
+            ]
+            .into(),
         };
 
         let err = udt
@@ -2270,7 +3443,8 @@ mod tests {
             let typ = ColumnType::UserDefinedType {
                 type_name: "typ".to_string(),
                 keyspace: "ks".to_string(),
-                field_types: vec![("a".to_string(), ColumnType::Text), ("b".to_string(), typ)],
+                field_types: vec![("a".to_string(), ColumnType::Text), ("b".to_string(), typ)]
+                    .into(),
             };
             let reference = do_serialize(
                 CqlValue::UserDefinedType {
@@ -2320,7 +3494,8 @@ mod tests {
                     "c".to_string(),
                     ColumnType::List(Box::new(ColumnType::BigInt)),
                 ),
-            ],
+            ]
+            .into(),
         };
 
         let reference = do_serialize(
@@ -2371,7 +3546,8 @@ mod tests {
                     "c".to_string(),
                     ColumnType::List(Box::new(ColumnType::BigInt)),
                 ),
-            ],
+            ]
+            .into(),
         };
 
         let typ_unexpected_field = ColumnType::UserDefinedType {
@@ -2386,7 +3562,8 @@ mod tests {
                 ),
                 // Unexpected field
                 ("d".to_string(), ColumnType::Counter),
-            ],
+            ]
+            .into(),
         };
 
         let result_normal = do_serialize(&udt, &typ_normal);
@@ -2421,7 +3598,8 @@ mod tests {
                     "c".to_string(),
                     ColumnType::List(Box::new(ColumnType::BigInt)),
                 ),
-            ],
+            ]
+            .into(),
         };
 
         let err =
@@ -2439,7 +3617,8 @@ mod tests {
                 ("a".to_string(), ColumnType::Text),
                 ("b".to_string(), ColumnType::Int),
                 // Last field is missing
-            ],
+            ]
+            .into(),
         };
 
         let err = <_ as SerializeCql>::serialize(&udt, &typ_without_c, CellWriter::new(&mut data))
@@ -2447,9 +3626,7 @@ mod tests {
         let err = err.0.downcast_ref::<BuiltinTypeCheckError>().unwrap();
         assert!(matches!(
             err.kind,
-            BuiltinTypeCheckErrorKind::UdtError(
-                UdtTypeCheckErrorKind::ValueMissingForUdtField { .. }
-            )
+            BuiltinTypeCheckErrorKind::UdtError(UdtTypeCheckErrorKind::NoSuchFieldInUdt { .. })
         ));
 
         let typ_unexpected_field = ColumnType::UserDefinedType {
@@ -2459,7 +3636,8 @@ mod tests {
                 ("a".to_string(), ColumnType::Text),
                 ("b".to_string(), ColumnType::Int),
                 ("c".to_string(), ColumnType::TinyInt), // Wrong column type
-            ],
+            ]
+            .into(),
         };
 
         let err =
@@ -2498,7 +3676,8 @@ mod tests {
             field_types: vec![
                 ("x".to_string(), ColumnType::Int),
                 ("a".to_string(), ColumnType::Text),
-            ],
+            ]
+            .into(),
         };
 
         let mut reference = Vec::new();
@@ -2530,7 +3709,8 @@ mod tests {
             field_types: vec![
                 ("a".to_string(), ColumnType::Text),
                 ("x".to_string(), ColumnType::Int),
-            ],
+            ]
+            .into(),
         };
 
         let mut reference = Vec::new();
@@ -2569,7 +3749,8 @@ mod tests {
             field_types: vec![
                 ("a".to_string(), ColumnType::Text),
                 ("x".to_string(), ColumnType::Int),
-            ],
+            ]
+            .into(),
         };
 
         let mut reference = Vec::new();
@@ -2618,7 +3799,8 @@ mod tests {
                 ),
                 // Unexpected field
                 ("d".to_string(), ColumnType::Counter),
-            ],
+            ]
+            .into(),
         };
 
         let err = udt
@@ -2627,7 +3809,9 @@ mod tests {
         let err = err.0.downcast_ref::<BuiltinTypeCheckError>().unwrap();
         assert!(matches!(
             err.kind,
-            BuiltinTypeCheckErrorKind::UdtError(UdtTypeCheckErrorKind::NoSuchFieldInUdt { .. })
+            BuiltinTypeCheckErrorKind::UdtError(
+                UdtTypeCheckErrorKind::ValueMissingForUdtField { .. }
+            )
         ));
 
         let typ_unexpected_field_middle = ColumnType::UserDefinedType {
@@ -2642,7 +3826,8 @@ mod tests {
                     "c".to_string(),
                     ColumnType::List(Box::new(ColumnType::BigInt)),
                 ),
-            ],
+            ]
+            .into(),
         };
 
         let err = udt
@@ -2651,7 +3836,9 @@ mod tests {
         let err = err.0.downcast_ref::<BuiltinTypeCheckError>().unwrap();
         assert!(matches!(
             err.kind,
-            BuiltinTypeCheckErrorKind::UdtError(UdtTypeCheckErrorKind::NoSuchFieldInUdt { .. })
+            BuiltinTypeCheckErrorKind::UdtError(
+                UdtTypeCheckErrorKind::ValueMissingForUdtField { .. }
+            )
         ));
     }
 
@@ -2680,7 +3867,8 @@ mod tests {
                 ),
                 // Unexpected field
                 ("d".to_string(), ColumnType::Counter),
-            ],
+            ]
+            .into(),
         };
 
         let err =
@@ -2689,7 +3877,627 @@ mod tests {
         let err = err.0.downcast_ref::<BuiltinTypeCheckError>().unwrap();
         assert!(matches!(
             err.kind,
-            BuiltinTypeCheckErrorKind::UdtError(UdtTypeCheckErrorKind::NoSuchFieldInUdt { .. })
+            BuiltinTypeCheckErrorKind::UdtError(
+                UdtTypeCheckErrorKind::ValueMissingForUdtField { .. }
+            )
+        ));
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        fn typck_kind(kind: BuiltinTypeCheckErrorKind) -> SerializationErrorKind {
+            SerializationError::new(BuiltinTypeCheckError {
+                rust_name: "T",
+                got: "int".into(),
+                kind,
+            })
+            .kind()
+        }
+        fn ser_kind(kind: BuiltinSerializationErrorKind) -> SerializationErrorKind {
+            SerializationError::new(BuiltinSerializationError {
+                rust_name: "T",
+                got: "int".into(),
+                kind,
+            })
+            .kind()
+        }
+        let field_name = || "f".to_owned();
+
+        // Type check errors
+        for (kind, expected) in [
+            (
+                BuiltinTypeCheckErrorKind::MismatchedType { expected: &[] },
+                SerializationErrorKind::TypeMismatch,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::NotEmptyable,
+                SerializationErrorKind::TypeMismatch,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::CustomTypeUnsupported,
+                SerializationErrorKind::TypeMismatch,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::NotCustomType,
+                SerializationErrorKind::TypeMismatch,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::UnknownType { id: 0x42 },
+                SerializationErrorKind::TypeMismatch,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::NotSameUnknownType { id: 0x42 },
+                SerializationErrorKind::TypeMismatch,
+            ),
+            (
+                SetOrListTypeCheckErrorKind::NotSetOrList.into(),
+                SerializationErrorKind::TypeMismatch,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::MapError(MapTypeCheckErrorKind::NotMap),
+                SerializationErrorKind::TypeMismatch,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::TupleError(TupleTypeCheckErrorKind::NotTuple),
+                SerializationErrorKind::TypeMismatch,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::TupleError(TupleTypeCheckErrorKind::WrongElementCount {
+                    actual: 3,
+                    asked_for: 2,
+                }),
+                SerializationErrorKind::ExtraField,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::UdtError(UdtTypeCheckErrorKind::NotUdt),
+                SerializationErrorKind::TypeMismatch,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::UdtError(UdtTypeCheckErrorKind::NameMismatch {
+                    keyspace: "ks".to_owned(),
+                    type_name: "typ".to_owned(),
+                }),
+                SerializationErrorKind::TypeMismatch,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::UdtError(
+                    UdtTypeCheckErrorKind::ValueMissingForUdtField {
+                        field_name: field_name(),
+                    },
+                ),
+                SerializationErrorKind::MissingField,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::UdtError(UdtTypeCheckErrorKind::NoSuchFieldInUdt {
+                    field_name: field_name(),
+                }),
+                SerializationErrorKind::ExtraField,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::UdtError(UdtTypeCheckErrorKind::FieldNameMismatch {
+                    rust_field_name: "a".to_owned(),
+                    db_field_name: "b".to_owned(),
+                }),
+                SerializationErrorKind::TypeMismatch,
+            ),
+            (
+                BuiltinTypeCheckErrorKind::UdtError(UdtTypeCheckErrorKind::DuplicateField {
+                    field_name: field_name(),
+                }),
+                SerializationErrorKind::ExtraField,
+            ),
+        ] {
+            assert_eq!(typck_kind(kind.clone()), expected, "{:?}", kind);
+        }
+
+        // Serialization errors, nested ones are classified by their cause
+        let overflow = || {
+            SerializationError::new(BuiltinSerializationError {
+                rust_name: "T",
+                got: "int".into(),
+                kind: BuiltinSerializationErrorKind::ValueOverflow,
+            })
+        };
+        for (kind, expected) in [
+            (
+                BuiltinSerializationErrorKind::SizeOverflow,
+                SerializationErrorKind::SizeLimit,
+            ),
+            (
+                BuiltinSerializationErrorKind::ValueOverflow,
+                SerializationErrorKind::ValueOutOfRange,
+            ),
+            (
+                BuiltinSerializationErrorKind::NanNotAllowed,
+                SerializationErrorKind::ValueOutOfRange,
+            ),
+            (
+                SetOrListSerializationErrorKind::TooManyElements.into(),
+                SerializationErrorKind::SizeLimit,
+            ),
+            (
+                SetOrListSerializationErrorKind::ElementSerializationFailed(overflow()).into(),
+                SerializationErrorKind::ValueOutOfRange,
+            ),
+            (
+                SetOrListSerializationErrorKind::NullOrUnsetElement {
+                    index: 0,
+                    value: NullOrUnset::Null,
+                }
+                .into(),
+                SerializationErrorKind::ValueOutOfRange,
+            ),
+            (
+                BuiltinSerializationErrorKind::MapError(MapSerializationErrorKind::TooManyElements),
+                SerializationErrorKind::SizeLimit,
+            ),
+            (
+                BuiltinSerializationErrorKind::MapError(
+                    MapSerializationErrorKind::KeySerializationFailed(overflow()),
+                ),
+                SerializationErrorKind::ValueOutOfRange,
+            ),
+            (
+                BuiltinSerializationErrorKind::MapError(
+                    MapSerializationErrorKind::ValueSerializationFailed(SerializationError::new(
+                        ValueToSerializeCqlAdapterError::TooBig,
+                    )),
+                ),
+                SerializationErrorKind::SizeLimit,
+            ),
+            (
+                BuiltinSerializationErrorKind::MapError(
+                    MapSerializationErrorKind::NullOrUnsetKey {
+                        index: 0,
+                        value: NullOrUnset::Unset,
+                    },
+                ),
+                SerializationErrorKind::ValueOutOfRange,
+            ),
+            (
+                BuiltinSerializationErrorKind::MapError(
+                    MapSerializationErrorKind::NullOrUnsetValue {
+                        index: 0,
+                        value: NullOrUnset::Null,
+                    },
+                ),
+                SerializationErrorKind::ValueOutOfRange,
+            ),
+            (
+                BuiltinSerializationErrorKind::TupleError(
+                    TupleSerializationErrorKind::ElementSerializationFailed {
+                        index: 0,
+                        err: SerializationError::new(std::fmt::Error),
+                    },
+                ),
+                SerializationErrorKind::Other,
+            ),
+            (
+                BuiltinSerializationErrorKind::TupleError(
+                    TupleSerializationErrorKind::UnsetElement { index: 0 },
+                ),
+                SerializationErrorKind::ValueOutOfRange,
+            ),
+            (
+                BuiltinSerializationErrorKind::UdtError(
+                    UdtSerializationErrorKind::FieldSerializationFailed {
+                        field_name: field_name(),
+                        err: SerializationError::new(CellOverflowError),
+                    },
+                ),
+                SerializationErrorKind::SizeLimit,
+            ),
+            (
+                BuiltinSerializationErrorKind::UdtError(UdtSerializationErrorKind::UnsetField {
+                    field_name: field_name(),
+                }),
+                SerializationErrorKind::ValueOutOfRange,
+            ),
+        ] {
+            assert_eq!(ser_kind(kind.clone()), expected, "{:?}", kind);
+        }
+
+        // Adapter of the legacy trait
+        for (err, expected) in [
+            (
+                ValueToSerializeCqlAdapterError::TooBig,
+                SerializationErrorKind::SizeLimit,
+            ),
+            (
+                ValueToSerializeCqlAdapterError::TooShort { size: 2 },
+                SerializationErrorKind::Other,
+            ),
+            (
+                ValueToSerializeCqlAdapterError::DeclaredVsActualSizeMismatch {
+                    declared: 4,
+                    actual: 2,
+                },
+                SerializationErrorKind::Other,
+            ),
+            (
+                ValueToSerializeCqlAdapterError::InvalidDeclaredSize { size: -3 },
+                SerializationErrorKind::Other,
+            ),
+        ] {
+            assert_eq!(SerializationError::new(err).kind(), expected);
+        }
+    }
+
+    #[test]
+    fn test_error_kinds_of_derived_impls() {
+        let udt = TestUdtWithFieldSorting::default();
+
+        let typ_without_c = ColumnType::UserDefinedType {
+            type_name: "typ".to_string(),
+            keyspace: "ks".to_string(),
+            field_types: vec![
+                ("a".to_string(), ColumnType::Text),
+                ("b".to_string(), ColumnType::Int),
+            ]
+            .into(),
+        };
+        let err = do_serialize_err(&udt, &typ_without_c);
+        assert_eq!(err.kind(), SerializationErrorKind::ExtraField);
+
+        let typ_wrong_type = ColumnType::UserDefinedType {
+            type_name: "typ".to_string(),
+            keyspace: "ks".to_string(),
+            field_types: vec![
+                ("a".to_string(), ColumnType::Text),
+                ("b".to_string(), ColumnType::Int),
+                ("c".to_string(), ColumnType::TinyInt),
+            ]
+            .into(),
+        };
+        let err = do_serialize_err(&udt, &typ_wrong_type);
+        assert_eq!(err.kind(), SerializationErrorKind::TypeMismatch);
+
+        let udt = TestStrictUdtWithEnforcedOrder::default();
+        let typ_unexpected_field = ColumnType::UserDefinedType {
+            type_name: "typ".to_string(),
+            keyspace: "ks".to_string(),
+            field_types: vec![
+                ("a".to_string(), ColumnType::Text),
+                ("b".to_string(), ColumnType::Int),
+                (
+                    "c".to_string(),
+                    ColumnType::List(Box::new(ColumnType::BigInt)),
+                ),
+                ("d".to_string(), ColumnType::Counter),
+            ]
+            .into(),
+        };
+        let err = do_serialize_err(&udt, &typ_unexpected_field);
+        assert_eq!(err.kind(), SerializationErrorKind::MissingField);
+    }
+
+    fn check_estimated_size<T: SerializeCql>(value: T, typ: ColumnType) {
+        let mut data = Vec::new();
+        value.serialize(&typ, CellWriter::new(&mut data)).unwrap();
+        assert_eq!(value.estimated_size(&typ), Some(data.len()));
+    }
+
+    #[test]
+    fn test_estimated_size() {
+        check_estimated_size(1i8, ColumnType::TinyInt);
+        check_estimated_size(1i64, ColumnType::BigInt);
+        check_estimated_size(true, ColumnType::Boolean);
+        check_estimated_size(1.0f64, ColumnType::Double);
+        check_estimated_size(uuid::Uuid::nil(), ColumnType::Uuid);
+        check_estimated_size(
+            std::net::IpAddr::V6(std::net::Ipv6Addr::LOCALHOST),
+            ColumnType::Inet,
+        );
+        check_estimated_size("text", ColumnType::Text);
+        check_estimated_size(vec![1u8; 1000], ColumnType::Blob);
+        check_estimated_size([1u8; 3], ColumnType::Blob);
+        check_estimated_size(None::<i32>, ColumnType::Int);
+        check_estimated_size(Unset, ColumnType::Int);
+        check_estimated_size(
+            vec![Some("a"), Some(""), Some("bcd")],
+            ColumnType::List(Box::new(ColumnType::Text)),
+        );
+        check_estimated_size(
+            BTreeMap::from([(1i32, vec![vec![1u8; 10]]), (2, vec![])]),
+            ColumnType::Map(
+                Box::new(ColumnType::Int),
+                Box::new(ColumnType::Set(Box::new(ColumnType::Blob))),
+            ),
+        );
+
+        // Sizes of values without an estimate and of collections containing them are unknown
+        let decimal = ColumnType::List(Box::new(ColumnType::Decimal));
+        assert_eq!(vec![BigDecimal::from(1)].estimated_size(&decimal), None);
+        assert_eq!(Vec::<BigDecimal>::new().estimated_size(&decimal), Some(8));
+    }
+
+    fn frozen_list(typ: ColumnType) -> ColumnType {
+        ColumnType::List(Box::new(typ))
+    }
+
+    fn udt_key_type(b_type: ColumnType) -> ColumnType {
+        ColumnType::UserDefinedType {
+            type_name: "key".to_string(),
+            keyspace: "ks".to_string(),
+            field_types: vec![
+                ("a".to_string(), ColumnType::Int),
+                ("b".to_string(), b_type),
+            ]
+            .into(),
+        }
+    }
+
+    #[derive(SerializeCql, PartialEq, Eq, PartialOrd, Ord)]
+    #[scylla(crate = crate)]
+    struct UdtKey {
+        a: i32,
+        b: String,
+    }
+
+    // Checks that the Rust value is serialized in the same way as the equivalent CqlValue.
+    fn check_nested<T: SerializeCql>(rust_value: T, cql_value: CqlValue, typ: &ColumnType) {
+        assert_eq!(
+            do_serialize(rust_value, typ),
+            do_serialize(cql_value, typ),
+            "{}",
+            typ.cql_type_name()
+        );
+    }
+
+    #[test]
+    fn test_nested_and_frozen_collections() {
+        let int_lists = frozen_list(frozen_list(ColumnType::Int));
+        check_nested(
+            vec![vec![1i32, 2], vec![], vec![3]],
+            CqlValue::List(vec![
+                CqlValue::List(vec![CqlValue::Int(1), CqlValue::Int(2)]),
+                CqlValue::List(vec![]),
+                CqlValue::List(vec![CqlValue::Int(3)]),
+            ]),
+            &int_lists,
+        );
+        check_nested(
+            BTreeSet::from([vec![1i32], vec![2, 3]]),
+            CqlValue::Set(vec![
+                CqlValue::List(vec![CqlValue::Int(1)]),
+                CqlValue::List(vec![CqlValue::Int(2), CqlValue::Int(3)]),
+            ]),
+            &ColumnType::Set(Box::new(frozen_list(ColumnType::Int))),
+        );
+
+        // Sets of frozen tuples
+        check_nested(
+            BTreeSet::from([(1i32, "a".to_string()), (2, "b".to_string())]),
+            CqlValue::Set(vec![
+                CqlValue::Tuple(vec![Some(CqlValue::Int(1)), Some("a".into())]),
+                CqlValue::Tuple(vec![Some(CqlValue::Int(2)), Some("b".into())]),
+            ]),
+            &ColumnType::Set(Box::new(ColumnType::Tuple(vec![
+                ColumnType::Int,
+                ColumnType::Text,
+            ]))),
+        );
+
+        // Tuples with elements missing at the end, which are not written
+        let typ = ColumnType::Tuple(vec![ColumnType::Int, ColumnType::Text, ColumnType::Int]);
+        check_nested((1i32,), CqlValue::Tuple(vec![Some(CqlValue::Int(1))]), &typ);
+        check_nested(
+            (1i32, None::<String>),
+            CqlValue::Tuple(vec![Some(CqlValue::Int(1)), None]),
+            &typ,
+        );
+
+        // Maps with frozen UDT keys
+        let typ = ColumnType::Map(
+            Box::new(udt_key_type(ColumnType::Text)),
+            Box::new(frozen_list(ColumnType::Int)),
+        );
+        let key = |a: i32, b: &str| CqlValue::UserDefinedType {
+            keyspace: "ks".to_string(),
+            type_name: "key".to_string(),
+            fields: vec![
+                ("a".to_string(), Some(CqlValue::Int(a))),
+                ("b".to_string(), Some(b.into())),
+            ],
+        };
+        check_nested(
+            BTreeMap::from([
+                (
+                    UdtKey {
+                        a: 1,
+                        b: "x".to_string(),
+                    },
+                    vec![1i32],
+                ),
+                (
+                    UdtKey {
+                        a: 2,
+                        b: "y".to_string(),
+                    },
+                    vec![],
+                ),
+            ]),
+            CqlValue::Map(vec![
+                (key(1, "x"), CqlValue::List(vec![CqlValue::Int(1)])),
+                (key(2, "y"), CqlValue::List(vec![])),
+            ]),
+            &typ,
+        );
+
+        // Maps nested in maps
+        check_nested(
+            HashMap::from([("k", BTreeMap::from([(1i32, (2i32, Some("v")))]))]),
+            CqlValue::Map(vec![(
+                "k".into(),
+                CqlValue::Map(vec![(
+                    CqlValue::Int(1),
+                    CqlValue::Tuple(vec![Some(CqlValue::Int(2)), Some("v".into())]),
+                )]),
+            )]),
+            &ColumnType::Map(
+                Box::new(ColumnType::Text),
+                Box::new(ColumnType::Map(
+                    Box::new(ColumnType::Int),
+                    Box::new(ColumnType::Tuple(vec![ColumnType::Int, ColumnType::Text])),
+                )),
+            ),
+        );
+    }
+
+    // Checks that both the Rust value and the equivalent CqlValue fail to serialize
+    // because of the nested value at the given path, and returns the errors of that value.
+    fn check_nested_err<T: SerializeCql>(
+        rust_value: T,
+        cql_value: CqlValue,
+        typ: &ColumnType,
+        expected_path: &str,
+    ) -> [BuiltinTypeCheckError; 2] {
+        [
+            do_serialize_err(rust_value, typ),
+            do_serialize_err(cql_value, typ),
+        ]
+        .map(|err| {
+            let err = get_ser_err(&err);
+            let (path, innermost) = err.innermost_error();
+            assert_eq!(path.to_string(), expected_path);
+            let innermost = innermost
+                .downcast_ref::<BuiltinTypeCheckError>()
+                .unwrap_or_else(|| panic!("not a BuiltinTypeCheckError: {}", innermost))
+                .clone();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "Failed to serialize Rust type {} into CQL type {}: {}: {}",
+                    err.rust_name, err.got, expected_path, innermost
+                )
+            );
+            innermost
+        })
+    }
+
+    #[test]
+    fn test_nested_errors_name_the_innermost_path() {
+        let errs = check_nested_err(
+            BTreeMap::from([(1i32, (2i32, 3i32))]),
+            CqlValue::Map(vec![(
+                CqlValue::Int(1),
+                CqlValue::Tuple(vec![Some(CqlValue::Int(2)), Some(CqlValue::Int(3))]),
+            )]),
+            &ColumnType::Map(
+                Box::new(ColumnType::Int),
+                Box::new(ColumnType::Tuple(vec![ColumnType::Int, ColumnType::Text])),
+            ),
+            "map value → tuple element 1",
+        );
+        for err in errs {
+            assert_eq!(err.got, "text");
+            assert!(matches!(
+                err.kind,
+                BuiltinTypeCheckErrorKind::MismatchedType {
+                    expected: &[ColumnType::Int],
+                }
+            ));
+        }
+
+        let errs = check_nested_err(
+            vec![vec![1i32]],
+            CqlValue::List(vec![CqlValue::List(vec![CqlValue::Int(1)])]),
+            &frozen_list(frozen_list(ColumnType::Text)),
+            "element → element",
+        );
+        for err in errs {
+            assert_eq!(err.got, "text");
+        }
+
+        let errs = check_nested_err(
+            BTreeMap::from([(
+                UdtKey {
+                    a: 1,
+                    b: "x".to_string(),
+                },
+                vec![1i32],
+            )]),
+            CqlValue::Map(vec![(
+                CqlValue::UserDefinedType {
+                    keyspace: "ks".to_string(),
+                    type_name: "key".to_string(),
+                    fields: vec![
+                        ("a".to_string(), Some(CqlValue::Int(1))),
+                        ("b".to_string(), Some("x".into())),
+                    ],
+                },
+                CqlValue::List(vec![CqlValue::Int(1)]),
+            )]),
+            &ColumnType::Map(
+                Box::new(udt_key_type(ColumnType::BigInt)),
+                Box::new(frozen_list(ColumnType::Int)),
+            ),
+            "map key → field b",
+        );
+        for err in errs {
+            assert_eq!(err.got, "bigint");
+        }
+
+        // Type check failures of nested collections and tuples are reported
+        // at the nested value too
+        let errs = check_nested_err(
+            BTreeSet::from([(1i32, 2i32, 3i32)]),
+            CqlValue::Set(vec![CqlValue::Tuple(vec![
+                Some(CqlValue::Int(1)),
+                Some(CqlValue::Int(2)),
+                Some(CqlValue::Int(3)),
+            ])]),
+            &ColumnType::Set(Box::new(ColumnType::Tuple(vec![ColumnType::Int; 2]))),
+            "element",
+        );
+        for err in errs {
+            assert!(matches!(
+                err.kind,
+                BuiltinTypeCheckErrorKind::TupleError(TupleTypeCheckErrorKind::WrongElementCount {
+                    actual: 3,
+                    asked_for: 2,
+                })
+            ));
+        }
+        let errs = check_nested_err(
+            vec![vec![1i32]],
+            CqlValue::List(vec![CqlValue::List(vec![CqlValue::Int(1)])]),
+            &frozen_list(ColumnType::Map(
+                Box::new(ColumnType::Int),
+                Box::new(ColumnType::Int),
+            )),
+            "element",
+        );
+        for err in errs {
+            assert!(matches!(
+                err.kind,
+                BuiltinTypeCheckErrorKind::SetOrListError(
+                    SetOrListTypeCheckErrorKind::NotSetOrList
+                )
+            ));
+        }
+
+        // Errors not caused by nested values have an empty path
+        let err = do_serialize_err(StrictFloat(f32::NAN), &ColumnType::Float);
+        let err = get_ser_err(&err);
+        let (path, innermost) = err.innermost_error();
+        assert_eq!(path, ValuePath::default());
+        assert!(std::ptr::eq(
+            innermost
+                .downcast_ref::<BuiltinSerializationError>()
+                .unwrap(),
+            err
+        ));
+
+        let err = do_serialize_err(vec![StrictFloat(f32::NAN)], &frozen_list(ColumnType::Float));
+        let (path, innermost) = get_ser_err(&err).innermost_error();
+        assert_eq!(path.0, [ValuePathSegment::Element]);
+        assert!(matches!(
+            innermost
+                .downcast_ref::<BuiltinSerializationError>()
+                .unwrap()
+                .kind,
+            BuiltinSerializationErrorKind::NanNotAllowed
         ));
     }
 }
@@ -1,13 +1,76 @@
 //! Contains types and traits used for safe serialization of values for a CQL statement.
 
+use bytes::BytesMut;
 use thiserror::Error;
 
 use super::row::SerializedValues;
+use super::{ClassifySerializationError, SerializationErrorKind};
+
+/// Buffer that the writers append serialized data to.
+enum WriterBuffer<'buf> {
+    Vec(&'buf mut Vec<u8>),
+    BytesMut(&'buf mut BytesMut),
+}
+
+impl<'buf> WriterBuffer<'buf> {
+    #[inline]
+    fn extend_from_slice(&mut self, data: &[u8]) {
+        match self {
+            WriterBuffer::Vec(buf) => buf.extend_from_slice(data),
+            WriterBuffer::BytesMut(buf) => buf.extend_from_slice(data),
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        match self {
+            WriterBuffer::Vec(buf) => buf.len(),
+            WriterBuffer::BytesMut(buf) => buf.len(),
+        }
+    }
+
+    #[inline]
+    fn resize(&mut self, new_len: usize) {
+        match self {
+            WriterBuffer::Vec(buf) => buf.resize(new_len, 0),
+            WriterBuffer::BytesMut(buf) => buf.resize(new_len, 0),
+        }
+    }
+
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        match self {
+            WriterBuffer::Vec(buf) => buf.as_mut_slice(),
+            WriterBuffer::BytesMut(buf) => buf.as_mut(),
+        }
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        // No request can be larger than 256 MiB, so larger reservations
+        // can only result from wrong size estimates.
+        const MAX_RESERVATION: usize = 256 * 1024 * 1024;
+        if additional > MAX_RESERVATION {
+            return;
+        }
+        match self {
+            WriterBuffer::Vec(buf) => buf.reserve(additional),
+            WriterBuffer::BytesMut(buf) => buf.reserve(additional),
+        }
+    }
+
+    #[inline]
+    fn reborrow(&mut self) -> WriterBuffer<'_> {
+        match self {
+            WriterBuffer::Vec(buf) => WriterBuffer::Vec(buf),
+            WriterBuffer::BytesMut(buf) => WriterBuffer::BytesMut(buf),
+        }
+    }
+}
 
 /// An interface that facilitates writing values for a CQL query.
 pub struct RowWriter<'buf> {
     // Buffer that this value should be serialized to.
-    buf: &'buf mut Vec<u8>,
+    buf: WriterBuffer<'buf>,
 
     // Number of values written so far.
     value_count: usize,
@@ -20,7 +83,19 @@ impl<'buf> RowWriter<'buf> {
     #[inline]
     pub fn new(buf: &'buf mut Vec<u8>) -> Self {
         Self {
-            buf,
+            buf: WriterBuffer::Vec(buf),
+            value_count: 0,
+        }
+    }
+
+    /// Creates a new row writer based on an existing `BytesMut`,
+    /// e.g. a buffer of the frame which the values are a part of.
+    ///
+    /// The newly created row writer will append data to the end of the buffer.
+    #[inline]
+    pub fn new_bytes_mut(buf: &'buf mut BytesMut) -> Self {
+        Self {
+            buf: WriterBuffer::BytesMut(buf),
             value_count: 0,
         }
     }
@@ -39,7 +114,9 @@ impl<'buf> RowWriter<'buf> {
     #[inline]
     pub fn make_cell_writer(&mut self) -> CellWriter<'_> {
         self.value_count += 1;
-        CellWriter::new(self.buf)
+        CellWriter {
+            buf: self.buf.reborrow(),
+        }
     }
 
     /// Appends the values from an existing [`SerializedValues`] object to the
@@ -70,7 +147,7 @@ impl<'buf> RowWriter<'buf> {
 /// Dropping this type without calling any of its methods will result
 /// in nothing being written.
 pub struct CellWriter<'buf> {
-    buf: &'buf mut Vec<u8>,
+    buf: WriterBuffer<'buf>,
 }
 
 impl<'buf> CellWriter<'buf> {
@@ -79,19 +156,31 @@ impl<'buf> CellWriter<'buf> {
     /// The newly created row writer will append data to the end of the vec.
     #[inline]
     pub fn new(buf: &'buf mut Vec<u8>) -> Self {
-        Self { buf }
+        Self {
+            buf: WriterBuffer::Vec(buf),
+        }
+    }
+
+    /// Creates a new cell writer based on an existing `BytesMut`.
+    ///
+    /// The newly created cell writer will append data to the end of the buffer.
+    #[inline]
+    pub fn new_bytes_mut(buf: &'buf mut BytesMut) -> Self {
+        Self {
+            buf: WriterBuffer::BytesMut(buf),
+        }
     }
 
     /// Sets this value to be null, consuming this object.
     #[inline]
-    pub fn set_null(self) -> WrittenCellProof<'buf> {
+    pub fn set_null(mut self) -> WrittenCellProof<'buf> {
         self.buf.extend_from_slice(&(-1i32).to_be_bytes());
         WrittenCellProof::new()
     }
 
     /// Sets this value to represent an unset value, consuming this object.
     #[inline]
-    pub fn set_unset(self) -> WrittenCellProof<'buf> {
+    pub fn set_unset(mut self) -> WrittenCellProof<'buf> {
         self.buf.extend_from_slice(&(-2i32).to_be_bytes());
         WrittenCellProof::new()
     }
@@ -105,13 +194,26 @@ impl<'buf> CellWriter<'buf> {
     /// Fails if the contents size overflows the maximum allowed CQL cell size
     /// (which is i32::MAX).
     #[inline]
-    pub fn set_value(self, contents: &[u8]) -> Result<WrittenCellProof<'buf>, CellOverflowError> {
+    pub fn set_value(
+        mut self,
+        contents: &[u8],
+    ) -> Result<WrittenCellProof<'buf>, CellOverflowError> {
         let value_len: i32 = contents.len().try_into().map_err(|_| CellOverflowError)?;
         self.buf.extend_from_slice(&value_len.to_be_bytes());
         self.buf.extend_from_slice(contents);
         Ok(WrittenCellProof::new())
     }
 
+    /// Reserves space for at least `additional` more bytes in the buffer,
+    /// so that writing a large value doesn't reallocate it repeatedly.
+    ///
+    /// It doesn't affect the written data, and reservations too large
+    /// for any request to fit are ignored.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.buf.reserve(additional);
+    }
+
     /// Turns this writter into a [`CellValueBuilder`] which can be used
     /// to gradually initialize the CQL value.
     ///
@@ -132,7 +234,7 @@ impl<'buf> CellWriter<'buf> {
 /// data to be misinterpreted.
 pub struct CellValueBuilder<'buf> {
     // Buffer that this value should be serialized to.
-    buf: &'buf mut Vec<u8>,
+    buf: WriterBuffer<'buf>,
 
     // Starting position of the value in the buffer.
     starting_pos: usize,
@@ -140,7 +242,7 @@ pub struct CellValueBuilder<'buf> {
 
 impl<'buf> CellValueBuilder<'buf> {
     #[inline]
-    fn new(buf: &'buf mut Vec<u8>) -> Self {
+    fn new(mut buf: WriterBuffer<'buf>) -> Self {
         // "Length" of a [bytes] frame can either be a non-negative i32,
         // -1 (null) or -1 (not set). Push an invalid value here. It will be
         // overwritten eventually either by set_null, set_unset or Drop.
@@ -158,11 +260,43 @@ impl<'buf> CellValueBuilder<'buf> {
         self.buf.extend_from_slice(bytes);
     }
 
+    /// Appends `len` zero bytes to this cell and returns them to be filled in,
+    /// for values which are easier to write out of order.
+    #[inline]
+    pub(crate) fn append_zeroed(&mut self, len: usize) -> &mut [u8] {
+        let start = self.buf.len();
+        self.buf.resize(start + len);
+        &mut self.buf.as_mut_slice()[start..]
+    }
+
     /// Appends a sub-value to the end of the current contents of the cell
     /// and returns an object that allows to fill it in.
     #[inline]
     pub fn make_sub_writer(&mut self) -> CellWriter<'_> {
-        CellWriter::new(self.buf)
+        CellWriter {
+            buf: self.buf.reborrow(),
+        }
+    }
+
+    /// Appends a sub-value serialized with `serialize`, like with
+    /// [`make_sub_writer`](Self::make_sub_writer), and returns whether it was written
+    /// as null or unset.
+    ///
+    /// It's useful for values which can't contain nulls or unset values,
+    /// e.g. elements of collections, which the database would reject.
+    #[inline]
+    pub fn serialize_sub_value<E>(
+        &mut self,
+        serialize: impl for<'a> FnOnce(CellWriter<'a>) -> Result<WrittenCellProof<'a>, E>,
+    ) -> Result<Option<NullOrUnset>, E> {
+        let pos = self.buf.len();
+        serialize(self.make_sub_writer())?;
+        let header = self.buf.as_mut_slice()[pos..pos + 4].try_into().unwrap();
+        Ok(match i32::from_be_bytes(header) {
+            -1 => Some(NullOrUnset::Null),
+            -2 => Some(NullOrUnset::Unset),
+            _ => None,
+        })
     }
 
     /// Finishes serializing the value.
@@ -170,16 +304,35 @@ impl<'buf> CellValueBuilder<'buf> {
     /// Fails if the constructed cell size overflows the maximum allowed
     /// CQL cell size (which is i32::MAX).
     #[inline]
-    pub fn finish(self) -> Result<WrittenCellProof<'buf>, CellOverflowError> {
+    pub fn finish(mut self) -> Result<WrittenCellProof<'buf>, CellOverflowError> {
         let value_len: i32 = (self.buf.len() - self.starting_pos - 4)
             .try_into()
             .map_err(|_| CellOverflowError)?;
-        self.buf[self.starting_pos..self.starting_pos + 4]
+        self.buf.as_mut_slice()[self.starting_pos..self.starting_pos + 4]
             .copy_from_slice(&value_len.to_be_bytes());
         Ok(WrittenCellProof::new())
     }
 }
 
+/// A null or unset value, as reported by [`CellValueBuilder::serialize_sub_value`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NullOrUnset {
+    /// The value is null.
+    Null,
+
+    /// The value is unset.
+    Unset,
+}
+
+impl std::fmt::Display for NullOrUnset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NullOrUnset::Null => f.write_str("null"),
+            NullOrUnset::Unset => f.write_str("unset"),
+        }
+    }
+}
+
 /// An object that indicates a type-level proof that something was written
 /// by a [`CellWriter`] or [`CellValueBuilder`] with lifetime parameter `'buf`.
 ///
@@ -219,8 +372,21 @@ impl<'buf> WrittenCellProof<'buf> {
 #[error("CQL cell overflowed the maximum allowed size of 2^31 - 1")]
 pub struct CellOverflowError;
 
+impl ClassifySerializationError for CellOverflowError {
+    fn classify(&self) -> SerializationErrorKind {
+        SerializationErrorKind::SizeLimit
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
+    use bytes::BytesMut;
+
+    use crate::frame::response::result::ColumnType;
+    use crate::types::serialize::value::SerializeCql;
+
     use super::{CellWriter, RowWriter};
 
     #[test]
@@ -278,4 +444,64 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn test_bytes_mut_writers() {
+        fn write_row(mut writer: RowWriter) {
+            let mut builder = writer.make_cell_writer().into_value_builder();
+            builder.append_bytes(&[1, 2]);
+            builder.make_sub_writer().set_value(&[3]).unwrap();
+            builder.finish().unwrap();
+            writer.make_cell_writer().set_null();
+            writer.make_cell_writer().set_unset();
+        }
+
+        let mut vec_data = Vec::new();
+        write_row(RowWriter::new(&mut vec_data));
+
+        // Data already present in the buffer is kept, like in the case of Vec
+        let mut bytes_data = BytesMut::from(&[9, 9][..]);
+        write_row(RowWriter::new_bytes_mut(&mut bytes_data));
+        assert_eq!(&bytes_data[..2], &[9, 9]);
+        assert_eq!(&bytes_data[2..], &vec_data[..]);
+    }
+
+    #[test]
+    fn test_builtin_types_bytes_mut_serialization() {
+        fn check<T: SerializeCql>(value: T, typ: ColumnType) {
+            let mut vec_data = Vec::new();
+            value
+                .serialize(&typ, CellWriter::new(&mut vec_data))
+                .unwrap();
+
+            let mut bytes_data = BytesMut::new();
+            value
+                .serialize(&typ, CellWriter::new_bytes_mut(&mut bytes_data))
+                .unwrap();
+
+            assert_eq!(&bytes_data[..], &vec_data[..]);
+        }
+
+        check(123i32, ColumnType::Int);
+        check("text", ColumnType::Text);
+        check(
+            vec![Some(1i64), Some(2), Some(3)],
+            ColumnType::List(Box::new(ColumnType::BigInt)),
+        );
+        check(
+            BTreeMap::from([("a".to_owned(), vec![1i32]), ("b".to_owned(), vec![])]),
+            ColumnType::Map(
+                Box::new(ColumnType::Text),
+                Box::new(ColumnType::Set(Box::new(ColumnType::Int))),
+            ),
+        );
+        check(
+            (1i32, "a", Some(false)),
+            ColumnType::Tuple(vec![
+                ColumnType::Int,
+                ColumnType::Ascii,
+                ColumnType::Boolean,
+            ]),
+        );
+    }
 }
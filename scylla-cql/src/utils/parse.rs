@@ -1,6 +1,6 @@
 /// An error that can occur during parsing.
 #[derive(Copy, Clone)]
-pub(crate) struct ParseError {
+pub struct ParseError {
     pub(crate) remaining: usize,
     pub(crate) cause: ParseErrorCause,
 }
@@ -9,12 +9,12 @@ impl ParseError {
     /// Given the original string, returns the 1-based position
     /// of the error in characters.
     /// If an incorrect string was given, the function may return 0.
-    pub(crate) fn calculate_position(&self, original: &str) -> Option<usize> {
+    pub fn calculate_position(&self, original: &str) -> Option<usize> {
         calculate_position(original, self.remaining)
     }
 
     /// Returns the error cause.
-    pub(crate) fn get_cause(&self) -> ParseErrorCause {
+    pub fn get_cause(&self) -> ParseErrorCause {
         self.cause
     }
 }
@@ -22,7 +22,7 @@ impl ParseError {
 /// Cause of the parsing error.
 /// Should be lightweight so that it can be quickly discarded.
 #[derive(Copy, Clone)]
-pub(crate) enum ParseErrorCause {
+pub enum ParseErrorCause {
     Expected(&'static str),
     Other(&'static str),
 }
@@ -36,26 +36,26 @@ impl ToString for ParseErrorCause {
     }
 }
 
-pub(crate) type ParseResult<T> = Result<T, ParseError>;
+pub type ParseResult<T> = Result<T, ParseError>;
 
 /// A utility class for building simple recursive-descent parsers.
 ///
 /// Basically, a wrapper over &str with nice methods that help with parsing.
 #[derive(Clone, Copy)]
 #[must_use]
-pub(crate) struct ParserState<'s> {
+pub struct ParserState<'s> {
     s: &'s str,
 }
 
 impl<'s> ParserState<'s> {
     /// Creates a new parser from given input string.
-    pub(crate) fn new(s: &'s str) -> Self {
+    pub fn new(s: &'s str) -> Self {
         Self { s }
     }
 
     /// Applies given parsing function until it returns false
     /// and returns the final parser state.
-    pub(crate) fn parse_while(
+    pub fn parse_while(
         self,
         mut parser: impl FnMut(Self) -> ParseResult<(bool, Self)>,
     ) -> ParseResult<Self> {
@@ -72,7 +72,7 @@ impl<'s> ParserState<'s> {
     /// If the input string contains given string at the beginning,
     /// returns a new parser state with given string skipped.
     /// Otherwise, returns an error.
-    pub(crate) fn accept(self, part: &'static str) -> ParseResult<Self> {
+    pub fn accept(self, part: &'static str) -> ParseResult<Self> {
         match self.s.strip_prefix(part) {
             Some(s) => Ok(Self { s }),
             None => Err(self.error(ParseErrorCause::Expected(part))),
@@ -80,31 +80,31 @@ impl<'s> ParserState<'s> {
     }
 
     /// Returns new parser state with whitespace skipped from the beginning.
-    pub(crate) fn skip_white(self) -> Self {
+    pub fn skip_white(self) -> Self {
         let (_, me) = self.take_while(char::is_whitespace);
         me
     }
 
     /// Skips characters from the beginning while they satisfy given predicate
     /// and returns new parser state which
-    pub(crate) fn take_while(self, mut pred: impl FnMut(char) -> bool) -> (&'s str, Self) {
+    pub fn take_while(self, mut pred: impl FnMut(char) -> bool) -> (&'s str, Self) {
         let idx = self.s.find(move |c| !pred(c)).unwrap_or(self.s.len());
         let new = Self { s: &self.s[idx..] };
         (&self.s[..idx], new)
     }
 
     /// Returns the number of remaining bytes to parse.
-    pub(crate) fn get_remaining(self) -> usize {
+    pub fn get_remaining(self) -> usize {
         self.s.len()
     }
 
     /// Returns true if the input string was parsed completely.
-    pub(crate) fn is_at_eof(self) -> bool {
+    pub fn is_at_eof(self) -> bool {
         self.s.is_empty()
     }
 
     /// Returns an error with given cause, associated with given position.
-    pub(crate) fn error(self, cause: ParseErrorCause) -> ParseError {
+    pub fn error(self, cause: ParseErrorCause) -> ParseError {
         ParseError {
             remaining: self.get_remaining(),
             cause,
@@ -114,7 +114,7 @@ impl<'s> ParserState<'s> {
     /// Given the original string, returns the 1-based position
     /// of the error in characters.
     /// If an incorrect string was given, the function may return None.
-    pub(crate) fn calculate_position(self, original: &str) -> Option<usize> {
+    pub fn calculate_position(self, original: &str) -> Option<usize> {
         calculate_position(original, self.get_remaining())
     }
 }
@@ -0,0 +1,131 @@
+//! Checks that serializing values and assembling frames into reused buffers doesn't allocate.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::borrow::Cow;
+use std::cell::Cell;
+
+use bytes::Bytes;
+use scylla_cql::frame::request::{query::QueryParameters, Execute};
+use scylla_cql::frame::response::result::{ColumnSpec, ColumnType, PreparedMetadata, TableSpec};
+use scylla_cql::frame::SerializedRequest;
+use scylla_cql::types::serialize::row::{RowSerializationContext, SerializedValues};
+use scylla_cql::Consistency;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+fn metadata() -> PreparedMetadata {
+    let table_spec = TableSpec {
+        ks_name: "ks".to_owned(),
+        table_name: "t".to_owned(),
+    };
+    PreparedMetadata {
+        flags: 0,
+        col_count: 2,
+        pk_indexes: Vec::new(),
+        col_specs: vec![
+            ColumnSpec {
+                table_spec: table_spec.clone(),
+                name: "a".to_owned(),
+                typ: ColumnType::Int,
+            },
+            ColumnSpec {
+                table_spec,
+                name: "b".to_owned(),
+                typ: ColumnType::Text,
+            },
+        ],
+    }
+}
+
+/// Serializes the values and the EXECUTE frame, returning the buffers for reuse.
+fn serialize_request(
+    ctx: &RowSerializationContext,
+    id: &Bytes,
+    values_buffer: Vec<u8>,
+    frame_buffer: Vec<u8>,
+) -> (Vec<u8>, Vec<u8>) {
+    let values =
+        SerializedValues::from_serializable_in(values_buffer, ctx, &(42, "some text")).unwrap();
+    let execute = Execute {
+        id: id.clone(),
+        parameters: QueryParameters {
+            consistency: Consistency::LocalQuorum,
+            serial_consistency: None,
+            values: Cow::Borrowed(&values),
+            page_size: Some(5000),
+            paging_state: None,
+            timestamp: None,
+            skip_metadata: false,
+        },
+    };
+    let request = SerializedRequest::make_in(frame_buffer, &execute, None, false, None).unwrap();
+    (values.into_buffer(), request.into_buffer())
+}
+
+#[test]
+fn reused_buffers_avoid_allocations() {
+    let metadata = metadata();
+    let ctx = RowSerializationContext::from_prepared(&metadata);
+    let id = Bytes::from_static(b"statement id");
+
+    let fresh = allocations_during(|| {
+        for _ in 0..100 {
+            serialize_request(&ctx, &id, Vec::new(), Vec::new());
+        }
+    });
+
+    let (mut values_buffer, mut frame_buffer) =
+        serialize_request(&ctx, &id, Vec::new(), Vec::new());
+    let reused = allocations_during(|| {
+        for _ in 0..100 {
+            (values_buffer, frame_buffer) =
+                serialize_request(&ctx, &id, values_buffer, frame_buffer);
+        }
+    });
+
+    assert!(fresh >= 200, "{} allocations with fresh buffers", fresh);
+    assert_eq!(reused, 0);
+}
+
+#[test]
+fn reused_buffers_produce_the_same_bytes() {
+    let metadata = metadata();
+    let ctx = RowSerializationContext::from_prepared(&metadata);
+    let id = Bytes::from_static(b"statement id");
+
+    let (values, frame) = serialize_request(&ctx, &id, Vec::new(), Vec::new());
+    // Garbage left in the buffers must not leak into the results.
+    let (reused_values, reused_frame) =
+        serialize_request(&ctx, &id, vec![0xAB; 1000], vec![0xCD; 1000]);
+    assert_eq!(values, reused_values);
+    assert_eq!(frame, reused_frame);
+}
@@ -0,0 +1,92 @@
+//! Checks that blobs deserialized without copying keep the response frame alive
+//! only as long as they are alive themselves.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use bytes::Bytes;
+use scylla_cql::frame::response::result::{self, CqlValue};
+use scylla_cql::frame::types;
+
+struct TrackingAllocator;
+
+thread_local! {
+    static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+fn track(delta: isize) {
+    let _ = LIVE_BYTES.try_with(|live| live.set(live.get() + delta));
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        track(layout.size() as isize);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        track(-(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        track(new_size as isize - layout.size() as isize);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+fn live_bytes() -> isize {
+    LIVE_BYTES.with(Cell::get)
+}
+
+const BLOB_SIZE: usize = 256 * 1024;
+
+// Body of a result with `rows_count` rows of a single blob column.
+fn blob_rows_body(rows_count: usize) -> Vec<u8> {
+    let mut body = Vec::new();
+    types::write_int(0x0002, &mut body);
+    types::write_int(0x0001, &mut body); // Global table spec
+    types::write_int(1, &mut body);
+    types::write_string("ks", &mut body).unwrap();
+    types::write_string("t", &mut body).unwrap();
+    types::write_string("b", &mut body).unwrap();
+    types::write_short(0x0003, &mut body);
+    types::write_int(rows_count as i32, &mut body);
+    for i in 0..rows_count {
+        types::write_bytes(&vec![i as u8; BLOB_SIZE], &mut body).unwrap();
+    }
+    body
+}
+
+#[test]
+fn frame_is_freed_with_the_last_blob() {
+    let before = live_bytes();
+
+    let body = Bytes::from(blob_rows_body(4));
+    // Serialized rows, which share the frame too, are dropped here
+    let mut rows = match result::deserialize_shared(&body).unwrap() {
+        result::Result::Rows(rows) => rows.rows,
+        _ => panic!("Expected rows"),
+    };
+    drop(body);
+
+    let last = rows.pop().unwrap().columns.pop().unwrap();
+    drop(rows);
+    let blob = match last {
+        Some(CqlValue::SharedBlob(blob)) => blob,
+        other => panic!("Expected a shared blob, got {:?}", other),
+    };
+    assert_eq!(blob.len(), BLOB_SIZE);
+    assert!(blob.iter().all(|b| *b == 3));
+
+    // The whole frame is kept alive by the blob, and nothing was copied out of it
+    let retained = live_bytes() - before;
+    assert!(retained >= 4 * BLOB_SIZE as isize);
+    assert!(retained < 5 * BLOB_SIZE as isize);
+
+    drop(blob);
+    assert!(live_bytes() - before < BLOB_SIZE as isize);
+}
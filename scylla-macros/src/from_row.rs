@@ -12,25 +12,41 @@ pub fn from_row_derive(tokens_input: TokenStream) -> Result<TokenStream, syn::Er
     let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
 
     // Generates tokens for field_name: field_type::from_cql(vals_iter.next().ok_or(...)?), ...
-    let set_fields_code = struct_fields.named.iter().map(|field| {
-        let field_name = &field.ident;
-        let field_type = &field.ty;
-
-        quote_spanned! {field.span() =>
-            #field_name: {
-                let (col_ix, col_value) = vals_iter
-                    .next()
-                    .unwrap(); // vals_iter size is checked before this code is reached, so
-                               // it is safe to unwrap
-
-                <#field_type as FromCqlVal<::std::option::Option<CqlValue>>>::from_cql(col_value)
-                    .map_err(|e| FromRowError::BadCqlVal {
-                        err: e,
-                        column: col_ix,
-                    })?
-            },
-        }
-    });
+    let set_fields_code = struct_fields
+        .named
+        .iter()
+        .map(|field| {
+            let field_name = &field.ident;
+            let field_type = &field.ty;
+
+            // With #[scylla(null_as_default)], null becomes the default value instead of an error
+            let null_code = match crate::parser::is_null_as_default(field)? {
+                true => quote_spanned! {field.span() =>
+                    if col_value.is_none() {
+                        <#field_type as ::std::default::Default>::default()
+                    } else
+                },
+                false => quote! {},
+            };
+
+            Ok(quote_spanned! {field.span() =>
+                #field_name: {
+                    let (col_ix, col_value) = vals_iter
+                        .next()
+                        .unwrap(); // vals_iter size is checked before this code is reached, so
+                                   // it is safe to unwrap
+
+                    #null_code {
+                        <#field_type as FromCqlVal<::std::option::Option<CqlValue>>>::from_cql(col_value)
+                            .map_err(|e| FromRowError::BadCqlVal {
+                                err: e,
+                                column: col_ix,
+                            })?
+                    }
+                },
+            })
+        })
+        .collect::<Result<Vec<_>, syn::Error>>()?;
 
     let fields_count = struct_fields.named.len();
     let generated = quote! {
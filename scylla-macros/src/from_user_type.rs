@@ -16,9 +16,19 @@ pub fn from_user_type_derive(tokens_input: TokenStream) -> Result<TokenStream, s
         let field_name = &field.ident;
         let field_type = &field.ty;
 
-        quote_spanned! {field.span() =>
-            #field_name: <#field_type as FromCqlVal<::std::option::Option<CqlValue>>>::from_cql(
-                {
+        // With #[scylla(null_as_default)], null becomes the default value instead of an error
+        let null_code = match crate::parser::is_null_as_default(field)? {
+            true => quote_spanned! {field.span() =>
+                if field_value.is_none() {
+                    <#field_type as ::std::default::Default>::default()
+                } else
+            },
+            false => quote! {},
+        };
+
+        Ok(quote_spanned! {field.span() =>
+            #field_name: {
+                let field_value: Option<CqlValue> = {
                     let received_field_name: Option<&::std::string::String> = fields_iter
                         .peek()
                         .map(|(ref name, _)| name);
@@ -38,10 +48,14 @@ pub fn from_user_type_derive(tokens_input: TokenStream) -> Result<TokenStream, s
                     } else {
                         None
                     }
+                };
+
+                #null_code {
+                    <#field_type as FromCqlVal<::std::option::Option<CqlValue>>>::from_cql(field_value)?
                 }
-            ) ?,
-        }
-    });
+            },
+        })
+    }).collect::<Result<Vec<_>, syn::Error>>()?;
 
     let generated = quote! {
         impl #impl_generics #path::FromCqlVal<#path::CqlValue> for #struct_name #ty_generics #where_clause {
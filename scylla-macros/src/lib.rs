@@ -29,7 +29,7 @@ pub fn serialize_row_derive(tokens_input: TokenStream) -> TokenStream {
 
 /// #[derive(FromRow)] derives FromRow for struct
 /// Works only on simple structs without generics etc
-#[proc_macro_derive(FromRow, attributes(scylla_crate))]
+#[proc_macro_derive(FromRow, attributes(scylla_crate, scylla))]
 pub fn from_row_derive(tokens_input: TokenStream) -> TokenStream {
     let res = from_row::from_row_derive(tokens_input);
     res.unwrap_or_else(|e| e.into_compile_error().into())
@@ -37,7 +37,7 @@ pub fn from_row_derive(tokens_input: TokenStream) -> TokenStream {
 
 /// #[derive(FromUserType)] allows to parse a struct as User Defined Type
 /// Works only on simple structs without generics etc
-#[proc_macro_derive(FromUserType, attributes(scylla_crate))]
+#[proc_macro_derive(FromUserType, attributes(scylla_crate, scylla))]
 pub fn from_user_type_derive(tokens_input: TokenStream) -> TokenStream {
     let res = from_user_type::from_user_type_derive(tokens_input);
     res.unwrap_or_else(|e| e.into_compile_error().into())
@@ -1,4 +1,4 @@
-use syn::{Data, DeriveInput, ExprLit, Fields, FieldsNamed, Lit};
+use syn::{Data, DeriveInput, ExprLit, Field, Fields, FieldsNamed, Lit};
 use syn::{Expr, Meta};
 
 /// Parses the tokens_input to a DeriveInput and returns the struct name from which it derives and
@@ -73,3 +73,24 @@ pub(crate) fn get_path(input: &DeriveInput) -> Result<proc_macro2::TokenStream,
     }
     Ok(this_path.unwrap_or_else(|| quote::quote!(scylla::_macro_internal)))
 }
+
+/// Whether the field is annotated with `#[scylla(null_as_default)]`, i.e. a null received
+/// for it should be converted to the default value of its type.
+/// Other `scylla` attributes of the field are left for the serialization derives.
+pub(crate) fn is_null_as_default(field: &Field) -> Result<bool, syn::Error> {
+    let mut null_as_default = false;
+    for attr in field.attrs.iter() {
+        if !attr.path().is_ident("scylla") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("null_as_default") {
+                null_as_default = true;
+            } else if meta.input.peek(syn::Token![=]) {
+                meta.value()?.parse::<Expr>()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(null_as_default)
+}
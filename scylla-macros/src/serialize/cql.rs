@@ -51,6 +51,10 @@ impl Field {
 #[darling(attributes(scylla))]
 struct FieldAttributes {
     rename: Option<String>,
+
+    // Used by the deserialization derives, accepted so that they can be derived together
+    #[darling(default, rename = "null_as_default")]
+    _null_as_default: bool,
 }
 
 struct Context {
@@ -161,7 +165,7 @@ impl Context {
                 #crate_path::SerializationError::new(
                     #crate_path::BuiltinTypeTypeCheckError {
                         rust_name: ::std::any::type_name::<Self>(),
-                        got: <_ as ::std::clone::Clone>::clone(typ),
+                        got: #crate_path::ColumnType::cql_type_name(typ),
                         kind: #crate_path::BuiltinTypeTypeCheckErrorKind::UdtError(kind),
                     }
                 )
@@ -176,7 +180,7 @@ impl Context {
                 #crate_path::SerializationError::new(
                     #crate_path::BuiltinTypeSerializationError {
                         rust_name: ::std::any::type_name::<Self>(),
-                        got: <_ as ::std::clone::Clone>::clone(typ),
+                        got: #crate_path::ColumnType::cql_type_name(typ),
                         kind: #crate_path::BuiltinTypeSerializationErrorKind::UdtError(kind),
                     }
                 )
@@ -222,7 +226,7 @@ impl<'a> Generator for FieldSortingGenerator<'a> {
         let missing_rust_field_expression: syn::Expr = if self.ctx.attributes.force_exact_match {
             parse_quote! {
                 return ::std::result::Result::Err(mk_typck_err(
-                    #crate_path::UdtTypeCheckErrorKind::NoSuchFieldInUdt {
+                    #crate_path::UdtTypeCheckErrorKind::ValueMissingForUdtField {
                         field_name: <_ as ::std::clone::Clone>::clone(field_name),
                     }
                 ))
@@ -290,17 +294,45 @@ impl<'a> Generator for FieldSortingGenerator<'a> {
             let mut builder = #crate_path::CellWriter::into_value_builder(writer);
         });
 
-        // Generate a loop over the fields and a `match` block to match on
-        // the field name.
+        let field_indices = (0..field_count).collect::<Vec<_>>();
+        statements.extend::<Vec<_>>(parse_quote! {
+            const FIELD_NAMES: &[&::std::primitive::str] = &[#(#udt_field_names),*];
+            let mut next_field_idx: ::std::primitive::usize = 0;
+        });
+
+        // Generate a loop over the fields, which finds the index of the field
+        // of each name and matches on it. The fields of the UDT are usually
+        // in the same order as the fields of the struct, so the field following
+        // the previously matched one is checked first, before matching on the name.
         statements.push(parse_quote! {
-            for (field_name, field_type) in field_types {
-                match ::std::string::String::as_str(field_name) {
+            for (field_name, field_type) in field_types.iter() {
+                let field_name_str = ::std::string::String::as_str(field_name);
+                let field_idx = match FIELD_NAMES.get(next_field_idx) {
+                    ::std::option::Option::Some(name) if *name == field_name_str => {
+                        ::std::option::Option::Some(next_field_idx)
+                    }
+                    _ => match field_name_str {
+                        #(#udt_field_names => ::std::option::Option::Some(#field_indices),)*
+                        _ => ::std::option::Option::None,
+                    },
+                };
+                match field_idx {
                     #(
-                        #udt_field_names => {
+                        ::std::option::Option::Some(#field_indices) => {
+                            next_field_idx = #field_indices + 1;
                             #serialize_missing_nulls_statement
-                            let sub_builder = #crate_path::CellValueBuilder::make_sub_writer(&mut builder);
-                            match <#field_types as #crate_path::SerializeCql>::serialize(&self.#rust_field_idents, field_type, sub_builder) {
-                                ::std::result::Result::Ok(_proof) => {}
+                            match #crate_path::CellValueBuilder::serialize_sub_value(
+                                &mut builder,
+                                |writer| <#field_types as #crate_path::SerializeCql>::serialize(&self.#rust_field_idents, field_type, writer),
+                            ) {
+                                ::std::result::Result::Ok(::std::option::Option::Some(#crate_path::NullOrUnset::Unset)) => {
+                                    return ::std::result::Result::Err(mk_ser_err(
+                                        #crate_path::UdtSerializationErrorKind::UnsetField {
+                                            field_name: <_ as ::std::clone::Clone>::clone(field_name),
+                                        }
+                                    ));
+                                }
+                                ::std::result::Result::Ok(_) => {}
                                 ::std::result::Result::Err(err) => {
                                     return ::std::result::Result::Err(mk_ser_err(
                                         #crate_path::UdtSerializationErrorKind::FieldSerializationFailed {
@@ -328,7 +360,7 @@ impl<'a> Generator for FieldSortingGenerator<'a> {
                 #(
                     if !#visited_flag_names {
                         return ::std::result::Result::Err(mk_typck_err(
-                            #crate_path::UdtTypeCheckErrorKind::ValueMissingForUdtField {
+                            #crate_path::UdtTypeCheckErrorKind::NoSuchFieldInUdt {
                                 field_name: <_ as ::std::string::ToString>::to_string(#rust_field_names),
                             }
                         ));
@@ -349,7 +381,7 @@ impl<'a> Generator for FieldSortingGenerator<'a> {
                     .map_err(|_| #crate_path::SerializationError::new(
                         #crate_path::BuiltinTypeSerializationError {
                             rust_name: ::std::any::type_name::<Self>(),
-                            got: <_ as ::std::clone::Clone>::clone(typ),
+                            got: #crate_path::ColumnType::cql_type_name(typ),
                             kind: #crate_path::BuiltinTypeSerializationErrorKind::SizeOverflow,
                         }
                     ) as #crate_path::SerializationError)?;
@@ -405,10 +437,19 @@ impl<'a> Generator for FieldOrderedGenerator<'a> {
                 match field_iter.next() {
                     Some((field_name, typ)) => {
                         if #name_check_expression {
-                            let sub_builder = #crate_path::CellValueBuilder::make_sub_writer(&mut builder);
-                            match <#typ as #crate_path::SerializeCql>::serialize(&self.#rust_field_ident, typ, sub_builder) {
-                                Ok(_proof) => {},
-                                Err(err) => {
+                            match #crate_path::CellValueBuilder::serialize_sub_value(
+                                &mut builder,
+                                |writer| <#typ as #crate_path::SerializeCql>::serialize(&self.#rust_field_ident, typ, writer),
+                            ) {
+                                ::std::result::Result::Ok(::std::option::Option::Some(#crate_path::NullOrUnset::Unset)) => {
+                                    return ::std::result::Result::Err(mk_ser_err(
+                                        #crate_path::UdtSerializationErrorKind::UnsetField {
+                                            field_name: <_ as ::std::clone::Clone>::clone(field_name),
+                                        }
+                                    ));
+                                }
+                                ::std::result::Result::Ok(_) => {}
+                                ::std::result::Result::Err(err) => {
                                     return ::std::result::Result::Err(mk_ser_err(
                                         #crate_path::UdtSerializationErrorKind::FieldSerializationFailed {
                                             field_name: <_ as ::std::clone::Clone>::clone(field_name),
@@ -428,7 +469,7 @@ impl<'a> Generator for FieldOrderedGenerator<'a> {
                     }
                     None => {
                         return ::std::result::Result::Err(mk_typck_err(
-                            #crate_path::UdtTypeCheckErrorKind::ValueMissingForUdtField {
+                            #crate_path::UdtTypeCheckErrorKind::NoSuchFieldInUdt {
                                 field_name: <_ as ::std::string::ToString>::to_string(#rust_field_name),
                             }
                         ));
@@ -442,7 +483,7 @@ impl<'a> Generator for FieldOrderedGenerator<'a> {
             statements.push(parse_quote! {
                 if let Some((field_name, typ)) = field_iter.next() {
                     return ::std::result::Result::Err(mk_typck_err(
-                        #crate_path::UdtTypeCheckErrorKind::NoSuchFieldInUdt {
+                        #crate_path::UdtTypeCheckErrorKind::ValueMissingForUdtField {
                             field_name: <_ as ::std::clone::Clone>::clone(field_name),
                         }
                     ));
@@ -461,7 +502,7 @@ impl<'a> Generator for FieldOrderedGenerator<'a> {
                     .map_err(|_| #crate_path::SerializationError::new(
                         #crate_path::BuiltinTypeSerializationError {
                             rust_name: ::std::any::type_name::<Self>(),
-                            got: <_ as ::std::clone::Clone>::clone(typ),
+                            got: #crate_path::ColumnType::cql_type_name(typ),
                             kind: #crate_path::BuiltinTypeSerializationErrorKind::SizeOverflow,
                         }
                     ) as #crate_path::SerializationError)?;
@@ -48,6 +48,10 @@ impl Field {
 #[darling(attributes(scylla))]
 struct FieldAttributes {
     rename: Option<String>,
+
+    // Used by the deserialization derives, accepted so that they can be derived together
+    #[darling(default, rename = "null_as_default")]
+    _null_as_default: bool,
 }
 
 struct Context {
@@ -230,7 +234,10 @@ impl<'a> Generator for ColumnSortingGenerator<'a> {
                 match ::std::string::String::as_str(&spec.name) {
                     #(
                         #udt_field_names => {
-                            let sub_writer = #crate_path::RowWriter::make_cell_writer(writer);
+                            let mut sub_writer = #crate_path::RowWriter::make_cell_writer(writer);
+                            if let ::std::option::Option::Some(size) = <#field_types as #crate_path::SerializeCql>::estimated_size(&self.#rust_field_idents, &spec.typ) {
+                                #crate_path::CellWriter::reserve(&mut sub_writer, size);
+                            }
                             match <#field_types as #crate_path::SerializeCql>::serialize(&self.#rust_field_idents, &spec.typ, sub_writer) {
                                 ::std::result::Result::Ok(_proof) => {}
                                 ::std::result::Result::Err(err) => {
@@ -249,7 +256,7 @@ impl<'a> Generator for ColumnSortingGenerator<'a> {
                         }
                     )*
                     _ => return ::std::result::Result::Err(mk_typck_err(
-                        #crate_path::BuiltinRowTypeCheckErrorKind::NoColumnWithName {
+                        #crate_path::BuiltinRowTypeCheckErrorKind::ValueMissingForColumn {
                             name: <_ as ::std::clone::Clone>::clone(&&spec.name),
                         }
                     )),
@@ -264,7 +271,7 @@ impl<'a> Generator for ColumnSortingGenerator<'a> {
                 #(
                     if !#visited_flag_names {
                         return ::std::result::Result::Err(mk_typck_err(
-                            #crate_path::BuiltinRowTypeCheckErrorKind::ValueMissingForColumn {
+                            #crate_path::BuiltinRowTypeCheckErrorKind::NoColumnWithName {
                                 name: <_ as ::std::string::ToString>::to_string(#rust_field_names),
                             }
                         ));
@@ -332,7 +339,10 @@ impl<'a> Generator for ColumnOrderedGenerator<'a> {
                 match column_iter.next() {
                     Some(spec) => {
                         if #name_check_expression {
-                            let cell_writer = #crate_path::RowWriter::make_cell_writer(writer);
+                            let mut cell_writer = #crate_path::RowWriter::make_cell_writer(writer);
+                            if let ::std::option::Option::Some(size) = <#typ as #crate_path::SerializeCql>::estimated_size(&self.#rust_field_ident, &spec.typ) {
+                                #crate_path::CellWriter::reserve(&mut cell_writer, size);
+                            }
                             match <#typ as #crate_path::SerializeCql>::serialize(&self.#rust_field_ident, &spec.typ, cell_writer) {
                                 Ok(_proof) => {},
                                 Err(err) => {
@@ -355,7 +365,7 @@ impl<'a> Generator for ColumnOrderedGenerator<'a> {
                     }
                     None => {
                         return ::std::result::Result::Err(mk_typck_err(
-                            #crate_path::BuiltinRowTypeCheckErrorKind::ValueMissingForColumn {
+                            #crate_path::BuiltinRowTypeCheckErrorKind::NoColumnWithName {
                                 name: <_ as ::std::string::ToString>::to_string(#rust_field_name),
                             }
                         ));
@@ -368,7 +378,7 @@ impl<'a> Generator for ColumnOrderedGenerator<'a> {
         statements.push(parse_quote! {
             if let Some(spec) = column_iter.next() {
                 return ::std::result::Result::Err(mk_typck_err(
-                    #crate_path::BuiltinRowTypeCheckErrorKind::NoColumnWithName {
+                    #crate_path::BuiltinRowTypeCheckErrorKind::ValueMissingForColumn {
                         name: <_ as ::std::clone::Clone>::clone(&spec.name),
                     }
                 ));
@@ -3,9 +3,18 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use bytes::BytesMut;
 use scylla::{
     frame::types,
-    transport::partitioner::{calculate_token_for_partition_key, Murmur3Partitioner},
+    transport::metrics::LatencyHistogram,
+    transport::partitioner::{
+        calculate_token_for_partition_key, Murmur3Partitioner, Partitioner, PartitionerHasher,
+    },
 };
-use scylla_cql::{frame::response::result::ColumnType, types::serialize::row::SerializedValues};
+use scylla_cql::frame::response::result::{
+    ColumnSpec, ColumnType, PartitionKeyIndex, PreparedMetadata, TableSpec,
+};
+use scylla_cql::types::serialize::row::{RowSerializationContext, SerializedValues};
+use std::collections::HashMap;
+use std::time::Duration;
+use uuid::Uuid;
 
 fn types_benchmark(c: &mut Criterion) {
     let mut buf = BytesMut::with_capacity(64);
@@ -117,5 +126,81 @@ fn calculate_token_bench(c: &mut Criterion) {
     );
 }
 
-criterion_group!(benches, types_benchmark, calculate_token_bench);
+// The work done on each execution of a prepared statement, which executing
+// a bound statement skips: serializing the values and computing the token.
+fn bind_values_bench(c: &mut Criterion) {
+    let table_spec = TableSpec {
+        ks_name: "ks".to_owned(),
+        table_name: "heartbeats".to_owned(),
+    };
+    let col_specs = [
+        ("id", ColumnType::Uuid),
+        ("service", ColumnType::Text),
+        ("seen_at", ColumnType::Timestamp),
+        (
+            "labels",
+            ColumnType::Map(Box::new(ColumnType::Text), Box::new(ColumnType::Text)),
+        ),
+    ]
+    .into_iter()
+    .map(|(name, typ)| ColumnSpec {
+        table_spec: table_spec.clone(),
+        name: name.to_owned(),
+        typ,
+    })
+    .collect::<Vec<_>>();
+    let metadata = PreparedMetadata {
+        flags: 0,
+        col_count: col_specs.len(),
+        pk_indexes: vec![PartitionKeyIndex {
+            index: 0,
+            sequence: 0,
+        }],
+        col_specs,
+    };
+    let labels: HashMap<String, String> = [("region", "eu-west"), ("zone", "b"), ("tier", "gold")]
+        .into_iter()
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect();
+    let values = (
+        Uuid::from_u128(0x1234),
+        "checkout-service",
+        scylla::frame::value::CqlTimestamp(1_700_000_000_000),
+        labels,
+    );
+
+    c.bench_function("serialize values and calculate token", |b| {
+        b.iter(|| {
+            let ctx = RowSerializationContext::from_prepared(&metadata);
+            let serialized = SerializedValues::from_serializable(&ctx, &values).unwrap();
+            let mut hasher = Murmur3Partitioner.build_hasher();
+            hasher.write(serialized.iter().next().unwrap().as_value().unwrap());
+            (serialized, hasher.finish())
+        })
+    });
+}
+
+fn latency_histogram_bench(c: &mut Criterion) {
+    let histogram = LatencyHistogram::new();
+    let latencies: Vec<Duration> = (0..1024_u64)
+        .map(|i| Duration::from_micros(100 + i * i))
+        .collect();
+
+    c.bench_function("latency_histogram record", |b| {
+        let mut latencies = latencies.iter().cycle();
+        b.iter(|| histogram.record(*latencies.next().unwrap()))
+    });
+
+    c.bench_function("latency_histogram percentiles", |b| {
+        b.iter(|| histogram.percentiles())
+    });
+}
+
+criterion_group!(
+    benches,
+    types_benchmark,
+    calculate_token_bench,
+    bind_values_bench,
+    latency_histogram_bench
+);
 criterion_main!(benches);
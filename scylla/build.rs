@@ -0,0 +1,5 @@
+fn main() {
+    // Declares the cfg set with `RUSTFLAGS="--cfg tokio_unstable"` for the `task-names` feature.
+    // Cargo versions older than 1.77 ignore this instruction.
+    println!("cargo::rustc-check-cfg=cfg(tokio_unstable)");
+}
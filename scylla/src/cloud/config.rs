@@ -24,6 +24,27 @@ pub enum CloudConfigError {
 
     #[error("Error during key/cert parsing: {0}")]
     Ssl(#[from] openssl::error::ErrorStack),
+
+    #[error("Invalid {field}: {error}")]
+    InvalidField {
+        field: &'static str,
+        #[source]
+        error: Box<CloudConfigError>,
+    },
+
+    #[error("Invalid datacenter {name}: {error}")]
+    InvalidDatacenter {
+        name: String,
+        #[source]
+        error: Box<CloudConfigError>,
+    },
+
+    #[error("Invalid authInfo {name}: {error}")]
+    InvalidAuthInfo {
+        name: String,
+        #[source]
+        error: Box<CloudConfigError>,
+    },
 }
 
 /// Configuration for creating a session to a serverless cluster.
@@ -293,14 +314,23 @@ mod deserialize {
         }
     }
 
-    fn get_pem_data_from_string_or_load_from_file(
-        data_name: &str,    // how data in string is called
-        path_name: &str,    // how data in file is called
-        data: Option<&str>, // data in string
-        path: Option<&str>, // path to data in file
-    ) -> Result<Box<[u8]>, CloudConfigError> {
-        let pem = if let Some(data) = data {
-            general_purpose::STANDARD.decode(data)?
+    // Loads PEM data from the string or the file and parses it, naming the field
+    // which the data came from in errors.
+    fn parse_pem_from_string_or_file<T>(
+        data_name: &'static str, // how data in string is called
+        path_name: &'static str, // how data in file is called
+        data: Option<&str>,      // data in string
+        path: Option<&str>,      // path to data in file
+        parse: impl FnOnce(&[u8]) -> Result<T, openssl::error::ErrorStack>,
+    ) -> Result<T, CloudConfigError> {
+        let (field, pem) = if let Some(data) = data {
+            let pem = general_purpose::STANDARD.decode(data).map_err(|err| {
+                CloudConfigError::InvalidField {
+                    field: data_name,
+                    error: Box::new(err.into()),
+                }
+            })?;
+            (data_name, pem)
         } else if let Some(path) = path {
             let mut buf = vec![];
             File::open(path)
@@ -311,14 +341,17 @@ mod deserialize {
                         path_name, path,
                     ))
                 })?;
-            buf
+            (path_name, buf)
         } else {
             return Err(CloudConfigError::Validation(format!(
-                "Either {} or {} has to be provided for authInfo.",
+                "Either {} or {} has to be provided.",
                 data_name, path_name,
             )));
         };
-        Ok(pem.into_boxed_slice())
+        parse(&pem).map_err(|err| CloudConfigError::InvalidField {
+            field,
+            error: Box::new(err.into()),
+        })
     }
 
     impl TryFrom<RawCloudConfig> for super::CloudConfig {
@@ -345,9 +378,15 @@ mod deserialize {
             let datacenters = config
                 .datacenters
                 .into_iter()
-                .map(|(dc_name, dc_data)| {
-                    super::Datacenter::try_from(dc_data).map(|dc_data| (dc_name, dc_data))
-                })
+                .map(
+                    |(dc_name, dc_data)| match super::Datacenter::try_from(dc_data) {
+                        Ok(dc_data) => Ok((dc_name, dc_data)),
+                        Err(err) => Err(CloudConfigError::InvalidDatacenter {
+                            name: dc_name,
+                            error: Box::new(err),
+                        }),
+                    },
+                )
                 .collect::<Result<HashMap<String, super::Datacenter>, CloudConfigError>>()?;
 
             let auth_infos = config
@@ -356,7 +395,10 @@ mod deserialize {
                 .map(|(auth_info_name, auth_info_data)| {
                     match super::AuthInfo::try_from(auth_info_data) {
                         Ok(auth_info_data) => Ok((auth_info_name, auth_info_data)),
-                        Err(err) => Err(err),
+                        Err(err) => Err(CloudConfigError::InvalidAuthInfo {
+                            name: auth_info_name,
+                            error: Box::new(err),
+                        }),
                     }
                 })
                 .collect::<Result<HashMap<String, super::AuthInfo>, CloudConfigError>>()?;
@@ -368,18 +410,21 @@ mod deserialize {
                 .collect::<HashMap<String, super::Context>>();
 
             let default_context = contexts.get(&config.currentContext).ok_or_else(|| {
-                CloudConfigError::Validation("currentContext points to unknown context.".into())
+                CloudConfigError::Validation(format!(
+                    "currentContext points to unknown context {}.",
+                    &config.currentContext
+                ))
             })?;
             if !datacenters.contains_key(&default_context.datacenter_name) {
                 return Err(CloudConfigError::Validation(format!(
-                    "context {} datacenter points to unknown datacenter.",
-                    &config.currentContext
+                    "context {} datacenter points to unknown datacenter {}.",
+                    &config.currentContext, &default_context.datacenter_name
                 )));
             }
             if !auth_infos.contains_key(&default_context.auth_info_name) {
                 return Err(CloudConfigError::Validation(format!(
-                    "context {} authInfo points to unknown authInfo.",
-                    &config.currentContext
+                    "context {} authInfo points to unknown authInfo {}.",
+                    &config.currentContext, &default_context.auth_info_name
                 )));
             }
 
@@ -404,23 +449,21 @@ mod deserialize {
         type Error = CloudConfigError;
 
         fn try_from(auth_info: AuthInfo) -> Result<Self, Self::Error> {
-            let cert_pem = get_pem_data_from_string_or_load_from_file(
+            let cert = parse_pem_from_string_or_file(
                 "clientCertificateData",
                 "clientCertificatePath",
                 auth_info.clientCertificateData.as_deref(),
                 auth_info.clientCertificatePath.as_deref(),
+                X509::from_pem,
             )?;
-            let key_pem = get_pem_data_from_string_or_load_from_file(
+            let key = parse_pem_from_string_or_file(
                 "clientKeyData",
                 "clientKeyPath",
                 auth_info.clientKeyData.as_deref(),
                 auth_info.clientKeyPath.as_deref(),
+                PKey::private_key_from_pem,
             )?;
 
-            let cert = X509::from_pem(&cert_pem[..]).map_err(CloudConfigError::Ssl)?;
-
-            let key = PKey::private_key_from_pem(&key_pem[..]).map_err(CloudConfigError::Ssl)?;
-
             Ok(super::AuthInfo {
                 key,
                 cert,
@@ -510,16 +553,14 @@ mod deserialize {
                 ))
             })?;
 
-            let cert_pem = get_pem_data_from_string_or_load_from_file(
+            let certificate_authority = parse_pem_from_string_or_file(
                 "certificateAuthorityData",
                 "certificateAuthorityPath",
                 datacenter.certificateAuthorityData.as_deref(),
                 datacenter.certificateAuthorityPath.as_deref(),
+                X509::from_pem,
             )?;
 
-            let certificate_authority =
-                X509::from_pem(&cert_pem[..]).map_err(CloudConfigError::Ssl)?;
-
             Ok(super::Datacenter {
                 certificate_authority,
                 server: datacenter.server,
@@ -784,6 +825,46 @@ mod deserialize {
             }
         }
 
+        #[test]
+        fn test_cloud_config_errors_name_the_invalid_part() {
+            let assert_error = |config: RawCloudConfig, expected: &[&str]| {
+                let message = CloudConfig::try_from(config).unwrap_err().to_string();
+                for part in expected {
+                    assert!(message.contains(part), "{:?} in {:?}", part, message);
+                }
+            };
+
+            let mut config = RawCloudConfig::try_from(FULL_CONFIG).unwrap();
+            let dc = config.datacenters.get_mut("eu-west-1").unwrap();
+            dc.certificateAuthorityData = Some("not base64!".into());
+            assert_error(
+                config,
+                &["datacenter eu-west-1", "certificateAuthorityData"],
+            );
+
+            let mut config = RawCloudConfig::try_from(FULL_CONFIG).unwrap();
+            let dc = config.datacenters.get_mut("eu-west-1").unwrap();
+            dc.certificateAuthorityData = Some(general_purpose::STANDARD.encode("not a pem"));
+            assert_error(
+                config,
+                &["datacenter eu-west-1", "certificateAuthorityData"],
+            );
+
+            let mut config = RawCloudConfig::try_from(FULL_CONFIG).unwrap();
+            let dc = config.datacenters.get_mut("eu-west-1").unwrap();
+            dc.nodeDomain = "cql.-bad.com".into();
+            assert_error(config, &["datacenter eu-west-1", "nodeDomain"]);
+
+            let mut config = RawCloudConfig::try_from(FULL_CONFIG).unwrap();
+            let auth_info = config.authInfos.get_mut("two").unwrap();
+            auth_info.clientKeyData = Some(TEST_CA.into());
+            assert_error(config, &["authInfo two", "clientKeyData"]);
+
+            let mut config = RawCloudConfig::try_from(CCM_CONFIG).unwrap();
+            config.contexts.get_mut("default").unwrap().datacenterName = "us-east-1".into();
+            assert_error(config, &["context default", "us-east-1"]);
+        }
+
         #[test]
         fn test_cloud_config_validation() {
             {
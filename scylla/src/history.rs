@@ -13,6 +13,10 @@ use chrono::{DateTime, Utc};
 use scylla_cql::errors::QueryError;
 use tracing::warn;
 
+pub use scylla_cql::execution_history::{
+    AttemptOutcome, AttemptRecord, ExecutionHistory, MAX_RECORDED_ATTEMPTS,
+};
+
 /// Id of a single query, i.e. a single call to Session::query/execute/etc.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct QueryId(pub usize);
@@ -914,7 +918,7 @@ mod tests {
         let history_collector = Arc::new(HistoryCollector::new());
         query.set_history_listener(history_collector.clone());
 
-        session.query(query.clone(), ()).await.unwrap();
+        session.query_unpaged(query.clone(), ()).await.unwrap();
 
         let history: StructuredHistory = history_collector.clone_structured_history();
 
@@ -939,7 +943,7 @@ mod tests {
 
         // Prepared queries retain the history listener set in Query.
         let prepared = session.prepare(query).await.unwrap();
-        session.execute(&prepared, ()).await.unwrap();
+        session.execute_unpaged(&prepared, ()).await.unwrap();
 
         let history2: StructuredHistory = history_collector.clone_structured_history();
 
@@ -980,7 +984,7 @@ mod tests {
         let history_collector = Arc::new(HistoryCollector::new());
         query.set_history_listener(history_collector.clone());
 
-        assert!(session.query(query.clone(), ()).await.is_err());
+        assert!(session.query_unpaged(query.clone(), ()).await.is_err());
 
         let history: StructuredHistory = history_collector.clone_structured_history();
 
@@ -1013,18 +1017,18 @@ mod tests {
         let session = create_new_session_builder().build().await.unwrap();
         let ks = unique_keyspace_name();
         session
-        .query(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+        .query_unpaged(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
         .await
         .unwrap();
         session.use_keyspace(ks, true).await.unwrap();
 
         session
-            .query("CREATE TABLE t (p int primary key)", ())
+            .query_unpaged("CREATE TABLE t (p int primary key)", ())
             .await
             .unwrap();
         for i in 0..32 {
             session
-                .query("INSERT INTO t (p) VALUES (?)", (i,))
+                .query_unpaged("INSERT INTO t (p) VALUES (?)", (i,))
                 .await
                 .unwrap();
         }
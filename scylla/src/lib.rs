@@ -37,9 +37,9 @@
 //! ### Making queries
 //! After successfully connecting to the cluster we can make queries.\
 //! The driver supports multiple query types:
-//! * [Simple](crate::Session::query)
+//! * [Simple](crate::Session::query_unpaged)
 //! * [Simple paged](crate::Session::query_iter)
-//! * [Prepare](crate::Session::execute) (need to be [prepared](crate::Session::prepare) before use)
+//! * [Prepare](crate::Session::execute_unpaged) (need to be [prepared](crate::Session::prepare) before use)
 //! * [Prepared paged](crate::Session::execute_iter)
 //! * [Batch](crate::Session::batch)
 //!
@@ -55,7 +55,7 @@
 //! # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
 //! // Insert an int and text into the table
 //! session
-//!     .query(
+//!     .query_unpaged(
 //!         "INSERT INTO ks.tab (a, b) VALUES(?, ?)",
 //!         (2_i32, "some text")
 //!     )
@@ -76,7 +76,7 @@
 //!
 //! // Read rows containing an int and text
 //! let rows_opt = session
-//! .query("SELECT a, b FROM ks.tab", &[])
+//! .query_unpaged("SELECT a, b FROM ks.tab", &[])
 //!     .await?
 //!     .rows;
 //!
@@ -109,6 +109,8 @@ pub mod cloud;
 pub mod history;
 pub mod routing;
 pub mod statement;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod tracing;
 pub mod transport;
 
@@ -120,17 +122,22 @@ pub(crate) mod utils;
 pub use utils::test_utils;
 
 pub use statement::batch;
+pub use statement::bound_statement;
 pub use statement::prepared_statement;
 pub use statement::query;
 
 pub use frame::response::cql_to_rust;
-pub use frame::response::cql_to_rust::FromRow;
+pub use frame::response::cql_to_rust::{FromRow, FromRowNamed};
+pub use frame::response::result::{
+    ColumnNameIndex, ColumnSpec, ColumnType, ResultMetadata, TableSpec,
+};
 
-pub use transport::caching_session::CachingSession;
+pub use transport::caching_session::{CacheStats, CachingSession, CachingSessionBuilder};
 pub use transport::execution_profile::ExecutionProfile;
 pub use transport::query_result::QueryResult;
-pub use transport::session::{IntoTypedRows, Session, SessionConfig};
+pub use transport::session::{AsTypedRows, IntoTypedRows, Session, SessionConfig};
 pub use transport::session_builder::SessionBuilder;
+pub use transport::session_ops::SessionOps;
 
 #[cfg(feature = "cloud")]
 pub use transport::session_builder::CloudSessionBuilder;
@@ -4,11 +4,89 @@ use std::convert::TryFrom;
 use std::num::NonZeroU16;
 use thiserror::Error;
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub use crate::transport::partitioner::murmur3_token;
+
+/// A token on the token ring of the Murmur3 partitioner.
+///
+/// Tokens are ordered the same as by the database, from [`Token::MIN`] to [`Token::MAX`],
+/// after which the ring wraps around.
+///
+/// With the `serde` feature enabled, a token is serialized as its value.
+///
+/// # Example
+/// ```rust
+/// use scylla::routing::{murmur3_token, Token};
+///
+/// let token = murmur3_token(b"123");
+/// assert_eq!(token, Token::from(-7468325962851647638));
+/// assert_eq!(token.to_string().parse::<Token>().unwrap(), token);
+///
+/// // The middle of the range which wraps around the end of the ring
+/// assert_eq!(Token::MAX.midpoint(Token::new(i64::MIN + 2)), Token::new(i64::MIN));
+/// ```
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct Token {
     pub value: i64,
 }
 
+impl Token {
+    /// The lowest token. The Murmur3 partitioner never hashes a partition key to it.
+    pub const MIN: Token = Token { value: i64::MIN };
+    /// The highest token.
+    pub const MAX: Token = Token { value: i64::MAX };
+
+    /// Creates a token of the given value.
+    pub const fn new(value: i64) -> Self {
+        Token { value }
+    }
+
+    /// Value of the token.
+    pub const fn value(&self) -> i64 {
+        self.value
+    }
+
+    /// Returns the token halfway between this token and `other`, going forward on the ring.
+    ///
+    /// If `other` is not greater than this token, the way wraps around the end of the ring,
+    /// the same as in [`TokenRange`](crate::transport::locator::token_range::TokenRange).
+    /// In particular, the midpoint between a token and itself is on the opposite side of the ring.
+    /// The midpoint is rounded towards this token.
+    pub fn midpoint(self, other: Token) -> Token {
+        const RING_SIZE: i128 = 1 << 64;
+        let distance = match (other.value as i128 - self.value as i128).rem_euclid(RING_SIZE) {
+            0 => RING_SIZE,
+            distance => distance,
+        };
+        // Casting to i64 wraps the midpoint around the ring.
+        Token {
+            value: (self.value as i128 + distance / 2) as i64,
+        }
+    }
+}
+
+impl From<i64> for Token {
+    fn from(value: i64) -> Self {
+        Token { value }
+    }
+}
+
+impl From<Token> for i64 {
+    fn from(token: Token) -> Self {
+        token.value
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
 pub type Shard = u32;
 pub type ShardCount = NonZeroU16;
 
@@ -135,10 +213,74 @@ impl<'a> TryFrom<&'a HashMap<String, Vec<String>>> for ShardInfo {
 
 #[cfg(test)]
 mod tests {
-    use super::Token;
+    use super::{murmur3_token, Token};
     use super::{ShardCount, Sharder};
     use std::collections::HashSet;
 
+    #[test]
+    fn test_token_midpoint() {
+        let midpoint = |a: i64, b: i64| Token::new(a).midpoint(Token::new(b)).value();
+
+        assert_eq!(midpoint(0, 10), 5);
+        assert_eq!(midpoint(-10, 11), 0);
+        assert_eq!(midpoint(i64::MIN, i64::MAX), -1);
+        assert_eq!(midpoint(i64::MAX - 2, i64::MAX), i64::MAX - 1);
+
+        // Ranges which wrap around the end of the ring
+        assert_eq!(midpoint(i64::MAX, i64::MIN + 2), i64::MIN);
+        assert_eq!(midpoint(i64::MAX - 1, i64::MIN + 5), i64::MIN + 1);
+        assert_eq!(midpoint(10, -10), i64::MIN);
+        assert_eq!(midpoint(i64::MAX, i64::MIN), i64::MAX);
+
+        // The whole ring
+        assert_eq!(midpoint(0, 0), i64::MIN);
+        assert_eq!(midpoint(i64::MIN, i64::MIN), 0);
+        assert_eq!(midpoint(-5, -5), i64::MAX - 4);
+    }
+
+    #[test]
+    fn test_token_conversions() {
+        let token = Token::from(-42);
+        assert_eq!(token, Token::new(-42));
+        assert_eq!(i64::from(token), -42);
+        assert_eq!(token.to_string(), "-42");
+        assert_eq!("-42".parse::<Token>().unwrap(), token);
+        assert!("0x2a".parse::<Token>().is_err());
+        assert!("9223372036854775808".parse::<Token>().is_err());
+
+        assert!(Token::MIN < token && token < Token::MAX);
+        assert_eq!(Token::MIN.to_string(), i64::MIN.to_string());
+        assert_eq!(Token::MAX.to_string().parse::<Token>().unwrap(), Token::MAX);
+    }
+
+    #[test]
+    fn test_murmur3_token() {
+        // Tokens computed by the Murmur3Partitioner of Cassandra. Bytes of the tail
+        // of the key are sign-extended, as Java bytes are signed, and the 64-bit
+        // arithmetic overflows as in Java.
+        for (key, token) in [
+            (&b""[..], 0),
+            (b"123", -7468325962851647638),
+            (b"9223372036854775807", 7162290910810015547),
+            (&[0x10; 8], 1446172840243228796),
+            (&[0xfe; 8], -8927430733708461935),
+            (&b"\x00\xff\x10\xfa\x99".repeat(10), 5837342703291459765),
+            ("kremówki".as_bytes(), 4354931215268080151),
+        ] {
+            assert_eq!(murmur3_token(key), Token::new(token), "key: {:?}", key);
+        }
+    }
+
+    // serde_yaml is available with the `config-file` feature
+    #[cfg(all(feature = "serde", feature = "config-file"))]
+    #[test]
+    fn test_token_serde() {
+        let token = Token::new(-7468325962851647638);
+        let serialized = serde_yaml::to_string(&token).unwrap();
+        assert_eq!(serialized.trim(), "-7468325962851647638");
+        assert_eq!(serde_yaml::from_str::<Token>(&serialized).unwrap(), token);
+    }
+
     #[test]
     fn test_shard_of() {
         /* Test values taken from the gocql driver.  */
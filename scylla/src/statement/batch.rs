@@ -1,9 +1,11 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::history::HistoryListener;
 use crate::retry_policy::RetryPolicy;
 use crate::statement::{prepared_statement::PreparedStatement, query::Query};
+use crate::transport::errors::StatementInfo;
 use crate::transport::execution_profile::ExecutionProfileHandle;
 
 use super::StatementConfig;
@@ -110,6 +112,19 @@ impl Batch {
         self.config.timestamp
     }
 
+    /// Sets the custom payload which is sent to the server together with this batch.
+    /// The custom payload is a map of opaque values, which can be interpreted
+    /// by server-side extensions (e.g. custom query handlers).
+    /// If None, no custom payload is sent.
+    pub fn set_custom_payload(&mut self, custom_payload: Option<HashMap<String, Vec<u8>>>) {
+        self.config.custom_payload = custom_payload;
+    }
+
+    /// Gets the custom payload which is sent to the server together with this batch.
+    pub fn get_custom_payload(&self) -> Option<&HashMap<String, Vec<u8>>> {
+        self.config.custom_payload.as_ref()
+    }
+
     /// Set the retry policy for this batch, overriding the one from execution profile if not None.
     #[inline]
     pub fn set_retry_policy(&mut self, retry_policy: Option<Arc<dyn RetryPolicy>>) {
@@ -132,6 +147,33 @@ impl Batch {
         self.config.history_listener.take()
     }
 
+    /// Enables or disables collecting the history of attempts made to execute the batch.
+    /// See [`Query::set_history_collection`](crate::query::Query::set_history_collection).
+    pub fn set_history_collection(&mut self, collect_history: bool) {
+        self.config.collect_history = collect_history;
+    }
+
+    /// Gets whether collecting the history of attempts is enabled for the batch.
+    pub fn get_history_collection(&self) -> bool {
+        self.config.collect_history
+    }
+
+    /// Sets the label of the batch, under which its requests are counted in metrics
+    /// and reported. See [`Query::set_label`](crate::query::Query::set_label).
+    pub fn set_label(&mut self, label: impl Into<Arc<str>>) {
+        self.config.label = Some(label.into());
+    }
+
+    /// Removes the label set by `set_label`.
+    pub fn remove_label(&mut self) {
+        self.config.label = None;
+    }
+
+    /// Gets the label set by `set_label`.
+    pub fn get_label(&self) -> Option<&str> {
+        self.config.label.as_deref()
+    }
+
     /// Associates the batch with execution profile referred by the provided handle.
     /// Handle may be later remapped to another profile, and batch will reflect those changes.
     pub fn set_execution_profile_handle(&mut self, profile_handle: Option<ExecutionProfileHandle>) {
@@ -142,6 +184,30 @@ impl Batch {
     pub fn get_execution_profile_handle(&self) -> Option<&ExecutionProfileHandle> {
         self.config.execution_profile_handle.as_ref()
     }
+
+    /// Describes the batch for errors, with texts of unprepared statements
+    /// truncated to `max_length` bytes.
+    pub(crate) fn statement_info(&self, max_length: usize) -> StatementInfo {
+        StatementInfo::Batch {
+            statements: self
+                .statements
+                .iter()
+                .map(|statement| match statement {
+                    BatchStatement::Query(query) => query.statement_info(max_length),
+                    BatchStatement::PreparedStatement(prepared) => prepared.statement_info(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether any statement of the batch may be conditional,
+    /// so that serial consistency applies to the batch.
+    pub(crate) fn is_conditional(&self) -> bool {
+        self.statements.iter().any(|statement| match statement {
+            BatchStatement::Query(query) => query.is_conditional(),
+            BatchStatement::PreparedStatement(prepared) => prepared.is_conditional(),
+        })
+    }
 }
 
 impl Default for Batch {
@@ -272,9 +338,10 @@ pub(crate) mod batch_values {
     where
         BV: BatchValues,
     {
-        type BatchValuesIter<'r> = BatchValuesFirstSerializedIterator<'r, BV::BatchValuesIter<'r>>
-    where
-        Self: 'r;
+        type BatchValuesIter<'r>
+            = BatchValuesFirstSerializedIterator<'r, BV::BatchValuesIter<'r>>
+        where
+            Self: 'r;
 
         fn batch_values_iter(&self) -> Self::BatchValuesIter<'_> {
             BatchValuesFirstSerializedIterator {
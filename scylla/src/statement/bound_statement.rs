@@ -0,0 +1,72 @@
+//! Prepared statements with values bound to them in advance.
+
+use bytes::Bytes;
+use scylla_cql::types::serialize::row::SerializedValues;
+
+use super::prepared_statement::PreparedStatement;
+use crate::routing::Token;
+
+/// A prepared statement with values bound to it.
+///
+/// The values are serialized and checked against the types of the statement's bind
+/// markers once, when the statement is [bound](PreparedStatement::bind), and so are
+/// the routing key and the token computed. The statement can be then executed
+/// many times with [`Session::execute_bound`](crate::Session::execute_bound)
+/// without repeating any of this work.
+///
+/// If the statement is re-prepared and the types of its bind markers turn out to have
+/// changed, e.g. because of a schema change, the bound values can't be sent anymore.
+/// Executing the statement fails with
+/// [`BadQuery::PreparedMetadataChanged`](crate::transport::errors::BadQuery::PreparedMetadataChanged)
+/// then, and it has to be prepared and bound again.
+#[derive(Debug, Clone)]
+pub struct BoundStatement {
+    pub(crate) prepared: PreparedStatement,
+    pub(crate) values: SerializedValues,
+    routing_key: Option<Bytes>,
+    token: Option<Token>,
+}
+
+impl BoundStatement {
+    pub(crate) fn new(
+        prepared: PreparedStatement,
+        values: SerializedValues,
+        routing_key: Option<Bytes>,
+        token: Option<Token>,
+    ) -> Self {
+        Self {
+            prepared,
+            values,
+            routing_key,
+            token,
+        }
+    }
+
+    /// Returns the statement to which the values are bound.
+    pub fn get_prepared(&self) -> &PreparedStatement {
+        &self.prepared
+    }
+
+    /// Returns the serialized values.
+    pub fn get_values(&self) -> &SerializedValues {
+        &self.values
+    }
+
+    /// Returns the partition key of the bound values, serialized as in the `routing_key`
+    /// of the native protocol, or `None` if the statement isn't token aware.
+    pub fn get_routing_key(&self) -> Option<&Bytes> {
+        self.routing_key.as_ref()
+    }
+
+    /// Returns the token the statement is routed by, or `None` if the statement
+    /// isn't token aware.
+    pub fn get_token(&self) -> Option<Token> {
+        self.token
+    }
+
+    /// Whether the statement was re-prepared with different types of bind markers,
+    /// so that the bound values can't be sent anymore.
+    pub fn is_outdated(&self) -> bool {
+        self.prepared.has_outdated_metadata()
+    }
+}
@@ -0,0 +1,710 @@
+//! Building simple CQL statements whose columns are only known at runtime.
+//!
+//! The builders never put values into the statement text. Every value gets a bind
+//! marker and is returned alongside the statement, so that the pair can be passed
+//! straight to [`Session::query_unpaged`](crate::Session::query_unpaged) or prepared.
+//! Keyspace, table and column names are always quoted, which means that they are
+//! case sensitive and may be reserved words. The names are validated as [`CqlIdentifier`]s,
+//! and the name of an identifier can be passed to the builders with [`CqlIdentifier::name`].
+//!
+//! This is not an ORM - only the most common forms of the statements are supported.
+//!
+//! ```rust
+//! # use scylla::Session;
+//! # use std::error::Error;
+//! # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+//! use scylla::statement::builder::{Insert, Select};
+//!
+//! let (statement, values) = Insert::into("ks", "tab")
+//!     .value("a", 1)
+//!     .value("b", "some text")
+//!     .ttl(300)
+//!     .if_not_exists()
+//!     .build()?;
+//! assert_eq!(
+//!     statement,
+//!     r#"INSERT INTO "ks"."tab" ("a", "b") VALUES (?, ?) IF NOT EXISTS USING TTL ?"#
+//! );
+//! session.query_unpaged(statement, values).await?;
+//!
+//! let (statement, values) = Select::from("ks", "tab")
+//!     .column("b")
+//!     .where_in("a", vec![1, 2, 3])
+//!     .build()?;
+//! let rows = session.query_unpaged(statement, values).await?.rows_typed::<(String,)>()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use scylla_cql::types::serialize::value::SerializeCql;
+use thiserror::Error;
+
+use super::identifier::{BadCqlIdentifier, CqlIdentifier};
+
+/// A value bound to one of the markers of a built statement.
+pub type BoundValue<'a> = Box<dyn SerializeCql + Send + Sync + 'a>;
+
+/// Values bound to the markers of a built statement, in order of the markers.
+pub type BoundValues<'a> = Vec<BoundValue<'a>>;
+
+/// A built statement with its bound values.
+pub type BuiltStatement<'a> = (String, BoundValues<'a>);
+
+/// The statement can't be built out of what was given to the builder.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BuildStatementError {
+    /// One of the keyspace, table or column names is empty
+    #[error("Identifiers must not be empty")]
+    EmptyIdentifier,
+
+    /// One of the keyspace, table or column names is not a valid identifier
+    #[error("Invalid identifier: {0}")]
+    BadIdentifier(BadCqlIdentifier),
+
+    /// No column is inserted or updated
+    #[error("The statement has to set at least one column")]
+    NoValues,
+
+    /// The rows to update or delete aren't restricted
+    #[error("The statement has to have at least one WHERE condition")]
+    NoConditions,
+}
+
+fn bad_identifier(err: BadCqlIdentifier) -> BuildStatementError {
+    match err {
+        BadCqlIdentifier::Empty => BuildStatementError::EmptyIdentifier,
+        err => BuildStatementError::BadIdentifier(err),
+    }
+}
+
+/// Validates and quotes a keyspace, table or column name.
+fn checked_identifier(name: &str) -> Result<String, BuildStatementError> {
+    let identifier = CqlIdentifier::new(name).map_err(bad_identifier)?;
+    Ok(identifier.quoted())
+}
+
+fn checked_identifiers(names: &[String]) -> Result<Vec<String>, BuildStatementError> {
+    names.iter().map(|name| checked_identifier(name)).collect()
+}
+
+fn bound<'a>(value: impl SerializeCql + Send + Sync + 'a) -> BoundValue<'a> {
+    Box::new(value)
+}
+
+#[derive(Clone, Copy)]
+enum Relation {
+    Eq,
+    In,
+}
+
+/// Conditions of a WHERE clause, joined with `AND`.
+#[derive(Default)]
+struct WhereClause<'a> {
+    conditions: Vec<(String, Relation, BoundValue<'a>)>,
+}
+
+impl<'a> WhereClause<'a> {
+    fn push(&mut self, column: &str, relation: Relation, value: BoundValue<'a>) {
+        self.conditions.push((column.to_owned(), relation, value));
+    }
+
+    fn write(
+        self,
+        statement: &mut String,
+        values: &mut BoundValues<'a>,
+    ) -> Result<(), BuildStatementError> {
+        for (i, (column, relation, value)) in self.conditions.into_iter().enumerate() {
+            statement.push_str(if i == 0 { " WHERE " } else { " AND " });
+            statement.push_str(&checked_identifier(&column)?);
+            statement.push_str(match relation {
+                Relation::Eq => " = ?",
+                Relation::In => " IN ?",
+            });
+            values.push(value);
+        }
+        Ok(())
+    }
+}
+
+/// `USING TTL ? AND TIMESTAMP ?` of modifying statements.
+#[derive(Default)]
+struct Using {
+    ttl: Option<i32>,
+    timestamp: Option<i64>,
+}
+
+impl Using {
+    fn write(self, statement: &mut String, values: &mut BoundValues<'_>) {
+        let mut prefix = " USING ";
+        if let Some(ttl) = self.ttl {
+            statement.push_str(prefix);
+            statement.push_str("TTL ?");
+            values.push(bound(ttl));
+            prefix = " AND ";
+        }
+        if let Some(timestamp) = self.timestamp {
+            statement.push_str(prefix);
+            statement.push_str("TIMESTAMP ?");
+            values.push(bound(timestamp));
+        }
+    }
+}
+
+fn table_name(keyspace: &str, table: &str) -> Result<String, BuildStatementError> {
+    Ok(format!(
+        "{}.{}",
+        checked_identifier(keyspace)?,
+        checked_identifier(table)?
+    ))
+}
+
+/// Builder of an `INSERT` statement.
+pub struct Insert<'a> {
+    keyspace: String,
+    table: String,
+    columns: Vec<String>,
+    values: BoundValues<'a>,
+    if_not_exists: bool,
+    using: Using,
+}
+
+impl<'a> Insert<'a> {
+    /// Starts building an `INSERT` into the given table.
+    pub fn into(keyspace: &str, table: &str) -> Self {
+        Self {
+            keyspace: keyspace.to_owned(),
+            table: table.to_owned(),
+            columns: Vec::new(),
+            values: Vec::new(),
+            if_not_exists: false,
+            using: Using::default(),
+        }
+    }
+
+    /// Inserts the value into the given column.
+    pub fn value(mut self, column: &str, value: impl SerializeCql + Send + Sync + 'a) -> Self {
+        self.columns.push(column.to_owned());
+        self.values.push(bound(value));
+        self
+    }
+
+    /// Inserts the row only if it doesn't exist yet, making the statement a lightweight transaction.
+    pub fn if_not_exists(mut self) -> Self {
+        self.if_not_exists = true;
+        self
+    }
+
+    /// Sets the time to live of the inserted values, in seconds.
+    pub fn ttl(mut self, ttl: i32) -> Self {
+        self.using.ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the timestamp of the write, in microseconds.
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.using.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Builds the statement, returning it with the values to bind.
+    pub fn build(self) -> Result<BuiltStatement<'a>, BuildStatementError> {
+        if self.columns.is_empty() {
+            return Err(BuildStatementError::NoValues);
+        }
+        let mut statement = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            table_name(&self.keyspace, &self.table)?,
+            checked_identifiers(&self.columns)?.join(", "),
+            vec!["?"; self.columns.len()].join(", ")
+        );
+        if self.if_not_exists {
+            statement.push_str(" IF NOT EXISTS");
+        }
+        let mut values = self.values;
+        self.using.write(&mut statement, &mut values);
+        Ok((statement, values))
+    }
+}
+
+/// Builder of a `SELECT` statement.
+pub struct Select<'a> {
+    keyspace: String,
+    table: String,
+    columns: Vec<String>,
+    where_clause: WhereClause<'a>,
+    limit: Option<i32>,
+    allow_filtering: bool,
+}
+
+impl<'a> Select<'a> {
+    /// Starts building a `SELECT` from the given table.\
+    /// Selects all columns, unless some are chosen with [`column`](Select::column).
+    pub fn from(keyspace: &str, table: &str) -> Self {
+        Self {
+            keyspace: keyspace.to_owned(),
+            table: table.to_owned(),
+            columns: Vec::new(),
+            where_clause: WhereClause::default(),
+            limit: None,
+            allow_filtering: false,
+        }
+    }
+
+    /// Selects the given column.
+    pub fn column(mut self, column: &str) -> Self {
+        self.columns.push(column.to_owned());
+        self
+    }
+
+    /// Selects only the rows in which the column is equal to the value.
+    pub fn where_eq(mut self, column: &str, value: impl SerializeCql + Send + Sync + 'a) -> Self {
+        self.where_clause.push(column, Relation::Eq, bound(value));
+        self
+    }
+
+    /// Selects only the rows in which the column is equal to one of the values.
+    pub fn where_in<T: SerializeCql + Send + Sync + 'a>(
+        mut self,
+        column: &str,
+        values: Vec<T>,
+    ) -> Self {
+        self.where_clause.push(column, Relation::In, bound(values));
+        self
+    }
+
+    /// Limits the number of returned rows.
+    pub fn limit(mut self, limit: i32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Allows conditions which require filtering the data on the server side.
+    pub fn allow_filtering(mut self) -> Self {
+        self.allow_filtering = true;
+        self
+    }
+
+    /// Builds the statement, returning it with the values to bind.
+    pub fn build(self) -> Result<BuiltStatement<'a>, BuildStatementError> {
+        let columns = if self.columns.is_empty() {
+            "*".to_owned()
+        } else {
+            checked_identifiers(&self.columns)?.join(", ")
+        };
+        let mut statement = format!(
+            "SELECT {} FROM {}",
+            columns,
+            table_name(&self.keyspace, &self.table)?
+        );
+        let mut values = Vec::new();
+        self.where_clause.write(&mut statement, &mut values)?;
+        if let Some(limit) = self.limit {
+            statement.push_str(" LIMIT ?");
+            values.push(bound(limit));
+        }
+        if self.allow_filtering {
+            statement.push_str(" ALLOW FILTERING");
+        }
+        Ok((statement, values))
+    }
+}
+
+/// Builder of an `UPDATE` statement.
+pub struct Update<'a> {
+    keyspace: String,
+    table: String,
+    columns: Vec<String>,
+    values: BoundValues<'a>,
+    where_clause: WhereClause<'a>,
+    if_exists: bool,
+    using: Using,
+}
+
+impl<'a> Update<'a> {
+    /// Starts building an `UPDATE` of the given table.
+    pub fn table(keyspace: &str, table: &str) -> Self {
+        Self {
+            keyspace: keyspace.to_owned(),
+            table: table.to_owned(),
+            columns: Vec::new(),
+            values: Vec::new(),
+            where_clause: WhereClause::default(),
+            if_exists: false,
+            using: Using::default(),
+        }
+    }
+
+    /// Sets the column to the value.
+    pub fn set(mut self, column: &str, value: impl SerializeCql + Send + Sync + 'a) -> Self {
+        self.columns.push(column.to_owned());
+        self.values.push(bound(value));
+        self
+    }
+
+    /// Updates only the rows in which the column is equal to the value.
+    pub fn where_eq(mut self, column: &str, value: impl SerializeCql + Send + Sync + 'a) -> Self {
+        self.where_clause.push(column, Relation::Eq, bound(value));
+        self
+    }
+
+    /// Updates only the rows in which the column is equal to one of the values.
+    pub fn where_in<T: SerializeCql + Send + Sync + 'a>(
+        mut self,
+        column: &str,
+        values: Vec<T>,
+    ) -> Self {
+        self.where_clause.push(column, Relation::In, bound(values));
+        self
+    }
+
+    /// Updates the row only if it exists, making the statement a lightweight transaction.
+    pub fn if_exists(mut self) -> Self {
+        self.if_exists = true;
+        self
+    }
+
+    /// Sets the time to live of the updated values, in seconds.
+    pub fn ttl(mut self, ttl: i32) -> Self {
+        self.using.ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the timestamp of the write, in microseconds.
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.using.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Builds the statement, returning it with the values to bind.
+    pub fn build(self) -> Result<BuiltStatement<'a>, BuildStatementError> {
+        if self.columns.is_empty() {
+            return Err(BuildStatementError::NoValues);
+        }
+        if self.where_clause.conditions.is_empty() {
+            return Err(BuildStatementError::NoConditions);
+        }
+        let mut statement = format!("UPDATE {}", table_name(&self.keyspace, &self.table)?);
+        let mut values = Vec::new();
+        self.using.write(&mut statement, &mut values);
+        let assignments: Vec<String> = checked_identifiers(&self.columns)?
+            .into_iter()
+            .map(|column| format!("{} = ?", column))
+            .collect();
+        statement.push_str(" SET ");
+        statement.push_str(&assignments.join(", "));
+        values.extend(self.values);
+        self.where_clause.write(&mut statement, &mut values)?;
+        if self.if_exists {
+            statement.push_str(" IF EXISTS");
+        }
+        Ok((statement, values))
+    }
+}
+
+/// Builder of a `DELETE` statement.
+pub struct Delete<'a> {
+    keyspace: String,
+    table: String,
+    columns: Vec<String>,
+    where_clause: WhereClause<'a>,
+    if_exists: bool,
+    using: Using,
+}
+
+impl<'a> Delete<'a> {
+    /// Starts building a `DELETE` from the given table.\
+    /// Deletes whole rows, unless some columns are chosen with [`column`](Delete::column).
+    pub fn from(keyspace: &str, table: &str) -> Self {
+        Self {
+            keyspace: keyspace.to_owned(),
+            table: table.to_owned(),
+            columns: Vec::new(),
+            where_clause: WhereClause::default(),
+            if_exists: false,
+            using: Using::default(),
+        }
+    }
+
+    /// Deletes the given column instead of whole rows.
+    pub fn column(mut self, column: &str) -> Self {
+        self.columns.push(column.to_owned());
+        self
+    }
+
+    /// Deletes only the rows in which the column is equal to the value.
+    pub fn where_eq(mut self, column: &str, value: impl SerializeCql + Send + Sync + 'a) -> Self {
+        self.where_clause.push(column, Relation::Eq, bound(value));
+        self
+    }
+
+    /// Deletes only the rows in which the column is equal to one of the values.
+    pub fn where_in<T: SerializeCql + Send + Sync + 'a>(
+        mut self,
+        column: &str,
+        values: Vec<T>,
+    ) -> Self {
+        self.where_clause.push(column, Relation::In, bound(values));
+        self
+    }
+
+    /// Deletes the row only if it exists, making the statement a lightweight transaction.
+    pub fn if_exists(mut self) -> Self {
+        self.if_exists = true;
+        self
+    }
+
+    /// Sets the timestamp of the deletion, in microseconds.
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.using.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Builds the statement, returning it with the values to bind.
+    pub fn build(self) -> Result<BuiltStatement<'a>, BuildStatementError> {
+        if self.where_clause.conditions.is_empty() {
+            return Err(BuildStatementError::NoConditions);
+        }
+        let mut statement = "DELETE".to_owned();
+        if !self.columns.is_empty() {
+            statement.push(' ');
+            statement.push_str(&checked_identifiers(&self.columns)?.join(", "));
+        }
+        statement.push_str(" FROM ");
+        statement.push_str(&table_name(&self.keyspace, &self.table)?);
+        let mut values = Vec::new();
+        self.using.write(&mut statement, &mut values);
+        self.where_clause.write(&mut statement, &mut values)?;
+        if self.if_exists {
+            statement.push_str(" IF EXISTS");
+        }
+        Ok((statement, values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoundValues, BuildStatementError, Delete, Insert, Select, Update};
+    use crate::frame::response::result::{ColumnSpec, ColumnType, PreparedMetadata, TableSpec};
+    use crate::serialize::row::{RowSerializationContext, SerializeRow};
+    use crate::serialize::RowWriter;
+    use crate::statement::identifier::BadCqlIdentifier;
+    use assert_matches::assert_matches;
+
+    // Serializes the values as if they were bound to markers of the given types.
+    fn serialize(values: &BoundValues<'_>, types: &[ColumnType]) {
+        let col_specs = types
+            .iter()
+            .enumerate()
+            .map(|(i, typ)| ColumnSpec {
+                table_spec: TableSpec {
+                    ks_name: "ks".to_owned(),
+                    table_name: "t".to_owned(),
+                },
+                name: format!("c{}", i),
+                typ: typ.clone(),
+            })
+            .collect();
+        let metadata = PreparedMetadata {
+            flags: 0,
+            col_count: types.len(),
+            pk_indexes: Vec::new(),
+            col_specs,
+        };
+        let mut data = Vec::new();
+        let mut writer = RowWriter::new(&mut data);
+        values
+            .serialize(
+                &RowSerializationContext::from_prepared(&metadata),
+                &mut writer,
+            )
+            .unwrap();
+        assert_eq!(writer.value_count(), types.len());
+    }
+
+    #[test]
+    fn insert_binds_all_values() {
+        let (statement, values) = Insert::into("ks", "t")
+            .value("a", 1)
+            .value("b", "x'); DROP TABLE t; --")
+            .ttl(300)
+            .timestamp(42)
+            .if_not_exists()
+            .build()
+            .unwrap();
+        assert_eq!(
+            statement,
+            r#"INSERT INTO "ks"."t" ("a", "b") VALUES (?, ?) IF NOT EXISTS USING TTL ? AND TIMESTAMP ?"#
+        );
+        assert_eq!(values.len(), 4);
+        serialize(
+            &values,
+            &[
+                ColumnType::Int,
+                ColumnType::Text,
+                ColumnType::Int,
+                ColumnType::BigInt,
+            ],
+        );
+    }
+
+    #[test]
+    fn identifiers_are_quoted() {
+        let (statement, _) = Select::from("MyKeyspace", "select")
+            .column("from")
+            .column("MixedCase")
+            .column(r#"with "quotes""#)
+            .where_eq("key", 1)
+            .build()
+            .unwrap();
+        assert_eq!(
+            statement,
+            r#"SELECT "from", "MixedCase", "with ""quotes""" FROM "MyKeyspace"."select" WHERE "key" = ?"#
+        );
+
+        assert_eq!(
+            Insert::into("ks", "").value("a", 1).build().err(),
+            Some(BuildStatementError::EmptyIdentifier)
+        );
+        assert_eq!(
+            Select::from("ks", "t").column("").build().err(),
+            Some(BuildStatementError::EmptyIdentifier)
+        );
+        assert_matches!(
+            Select::from("ks", &"t".repeat(49)).build().err(),
+            Some(BuildStatementError::BadIdentifier(
+                BadCqlIdentifier::TooLong(_, 49)
+            ))
+        );
+        assert_matches!(
+            Select::from("ks\n", "t").build().err(),
+            Some(BuildStatementError::BadIdentifier(
+                BadCqlIdentifier::ControlCharacter(_, '\n')
+            ))
+        );
+
+        // Column names are validated too
+        assert_matches!(
+            Select::from("ks", "t").column("a\tb").build().err(),
+            Some(BuildStatementError::BadIdentifier(
+                BadCqlIdentifier::ControlCharacter(_, '\t')
+            ))
+        );
+        assert_matches!(
+            Insert::into("ks", "t").value("a\0", 1).build().err(),
+            Some(BuildStatementError::BadIdentifier(
+                BadCqlIdentifier::ControlCharacter(_, '\0')
+            ))
+        );
+        assert_matches!(
+            Select::from("ks", "t")
+                .column(&"c".repeat(100))
+                .build()
+                .err(),
+            Some(BuildStatementError::BadIdentifier(
+                BadCqlIdentifier::TooLong(_, 100)
+            ))
+        );
+        assert_matches!(
+            Select::from("ks", "t")
+                .where_eq(&"c".repeat(49), 1)
+                .build()
+                .err(),
+            Some(BuildStatementError::BadIdentifier(
+                BadCqlIdentifier::TooLong(_, 49)
+            ))
+        );
+    }
+
+    #[test]
+    fn select_with_conditions() {
+        let (statement, values) = Select::from("ks", "t").build().unwrap();
+        assert_eq!(statement, r#"SELECT * FROM "ks"."t""#);
+        assert!(values.is_empty());
+
+        let (statement, values) = Select::from("ks", "t")
+            .column("c")
+            .where_eq("a", 1)
+            .where_in("b", vec!["x", "y"])
+            .limit(10)
+            .allow_filtering()
+            .build()
+            .unwrap();
+        assert_eq!(
+            statement,
+            r#"SELECT "c" FROM "ks"."t" WHERE "a" = ? AND "b" IN ? LIMIT ? ALLOW FILTERING"#
+        );
+        serialize(
+            &values,
+            &[
+                ColumnType::Int,
+                ColumnType::List(Box::new(ColumnType::Text)),
+                ColumnType::Int,
+            ],
+        );
+    }
+
+    #[test]
+    fn update_orders_values_as_markers() {
+        let (statement, values) = Update::table("ks", "t")
+            .set("v", "value")
+            .where_eq("a", 1)
+            .where_in("b", vec![2, 3])
+            .ttl(5)
+            .if_exists()
+            .build()
+            .unwrap();
+        assert_eq!(
+            statement,
+            r#"UPDATE "ks"."t" USING TTL ? SET "v" = ? WHERE "a" = ? AND "b" IN ? IF EXISTS"#
+        );
+        serialize(
+            &values,
+            &[
+                ColumnType::Int,
+                ColumnType::Text,
+                ColumnType::Int,
+                ColumnType::List(Box::new(ColumnType::Int)),
+            ],
+        );
+
+        assert_eq!(
+            Update::table("ks", "t").where_eq("a", 1).build().err(),
+            Some(BuildStatementError::NoValues)
+        );
+        assert_eq!(
+            Update::table("ks", "t").set("v", 1).build().err(),
+            Some(BuildStatementError::NoConditions)
+        );
+    }
+
+    #[test]
+    fn delete_rows_and_columns() {
+        let (statement, values) = Delete::from("ks", "t").where_eq("a", 1).build().unwrap();
+        assert_eq!(statement, r#"DELETE FROM "ks"."t" WHERE "a" = ?"#);
+        assert_eq!(values.len(), 1);
+
+        let (statement, values) = Delete::from("ks", "t")
+            .column("Value")
+            .timestamp(7)
+            .where_in("a", vec![1, 2])
+            .if_exists()
+            .build()
+            .unwrap();
+        assert_eq!(
+            statement,
+            r#"DELETE "Value" FROM "ks"."t" USING TIMESTAMP ? WHERE "a" IN ? IF EXISTS"#
+        );
+        serialize(
+            &values,
+            &[
+                ColumnType::BigInt,
+                ColumnType::List(Box::new(ColumnType::Int)),
+            ],
+        );
+
+        assert_eq!(
+            Delete::from("ks", "t").build().err(),
+            Some(BuildStatementError::NoConditions)
+        );
+    }
+}
@@ -0,0 +1,384 @@
+//! Names of keyspaces, tables, columns and other schema objects.
+//!
+//! A CQL identifier written without quotes is case insensitive and is
+//! lowercased by the server, while a quoted one is taken verbatim - it may contain
+//! uppercase letters, spaces or quotes (doubled), and may be a reserved word.
+//! [`CqlIdentifier`] stores the name as the server knows it - the way it appears
+//! in the schema metadata - and renders it quoted only when it has to be.
+//!
+//! ```rust
+//! use scylla::statement::identifier::CqlIdentifier;
+//!
+//! let unquoted: CqlIdentifier = "MyTable".parse().unwrap();
+//! assert_eq!(unquoted.name(), "mytable");
+//! assert_eq!(unquoted.to_string(), "mytable");
+//!
+//! let quoted: CqlIdentifier = r#""MyTable""#.parse().unwrap();
+//! assert_eq!(quoted.name(), "MyTable");
+//! assert_eq!(quoted.to_string(), r#""MyTable""#);
+//!
+//! let keyword = CqlIdentifier::new("select").unwrap();
+//! assert_eq!(keyword.to_string(), r#""select""#);
+//! ```
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+/// Maximum length of an identifier, in characters.
+///
+/// It's the limit the server imposes on keyspace and table names. It's applied to all
+/// identifiers, so that a name valid for one kind of schema object is valid for any other.
+pub const MAX_IDENTIFIER_LENGTH: usize = 48;
+
+// Words which can't be used as identifiers unless quoted.
+// https://cassandra.apache.org/doc/latest/cassandra/developing/cql/appendices.html#appendix-A
+const RESERVED_KEYWORDS: &[&str] = &[
+    "add",
+    "allow",
+    "alter",
+    "and",
+    "apply",
+    "asc",
+    "authorize",
+    "batch",
+    "begin",
+    "by",
+    "columnfamily",
+    "create",
+    "default",
+    "delete",
+    "desc",
+    "describe",
+    "drop",
+    "entries",
+    "execute",
+    "from",
+    "full",
+    "grant",
+    "if",
+    "in",
+    "index",
+    "infinity",
+    "insert",
+    "into",
+    "is",
+    "keyspace",
+    "limit",
+    "materialized",
+    "mbean",
+    "mbeans",
+    "modify",
+    "nan",
+    "norecursive",
+    "not",
+    "null",
+    "of",
+    "on",
+    "or",
+    "order",
+    "primary",
+    "rename",
+    "replace",
+    "revoke",
+    "schema",
+    "select",
+    "set",
+    "table",
+    "to",
+    "token",
+    "truncate",
+    "unlogged",
+    "unset",
+    "update",
+    "use",
+    "using",
+    "view",
+    "where",
+    "with",
+];
+
+fn is_reserved_keyword(word: &str) -> bool {
+    RESERVED_KEYWORDS
+        .iter()
+        .any(|keyword| keyword.eq_ignore_ascii_case(word))
+}
+
+/// Invalid CQL identifier.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BadCqlIdentifier {
+    /// Identifier is empty
+    #[error("Identifier is empty")]
+    Empty,
+
+    /// Identifier too long, must be up to [`MAX_IDENTIFIER_LENGTH`] characters
+    #[error("Identifier too long, must be up to {MAX_IDENTIFIER_LENGTH} characters, found {1} characters. Bad identifier: '{0}'")]
+    TooLong(String, usize),
+
+    /// Identifier contains a control character, which is not allowed even when quoted
+    #[error("Control character found: {1:?}. Bad identifier: {0:?}")]
+    ControlCharacter(String, char),
+
+    /// Unquoted identifier contains a character other than an alphanumeric or underscore,
+    /// or doesn't start with a letter
+    #[error("Illegal character found: '{1}', unquoted identifiers must start with a letter and contain only alphanumerics and underscores. Bad identifier: '{0}'")]
+    IllegalCharacter(String, char),
+
+    /// Unquoted identifier is a reserved keyword
+    #[error("'{0}' is a reserved keyword, it has to be quoted to be used as an identifier")]
+    ReservedKeyword(String),
+
+    /// Quoted identifier is missing its closing quote, or has a quote inside which is not doubled
+    #[error("Malformed quoted identifier: {0}")]
+    MalformedQuotes(String),
+}
+
+/// Name of a keyspace, table, column or other schema object.
+///
+/// Holds the name as the server knows it, i.e. case sensitive and unescaped,
+/// and knows whether it requires quoting.
+/// [`Display`] renders it the way it has to be written in a statement,
+/// and [`FromStr`] parses it from a statement, quoted or not.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CqlIdentifier {
+    name: String,
+}
+
+impl CqlIdentifier {
+    /// Creates an identifier of the given name, taken verbatim -
+    /// the way it would be written between quotes, but without escaping.
+    ///
+    /// Fails if the name is empty, contains control characters or is longer
+    /// than [`MAX_IDENTIFIER_LENGTH`] characters.
+    pub fn new(name: impl Into<String>) -> Result<Self, BadCqlIdentifier> {
+        let name = name.into();
+        if name.is_empty() {
+            return Err(BadCqlIdentifier::Empty);
+        }
+        if let Some(character) = name.chars().find(|c| c.is_control()) {
+            return Err(BadCqlIdentifier::ControlCharacter(name, character));
+        }
+        let length = name.chars().count();
+        if length > MAX_IDENTIFIER_LENGTH {
+            return Err(BadCqlIdentifier::TooLong(name, length));
+        }
+        Ok(CqlIdentifier { name })
+    }
+
+    /// The name as the server knows it, e.g. as used in the keys of the schema metadata.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Consumes the identifier, returning the name as the server knows it.
+    pub fn into_name(self) -> String {
+        self.name
+    }
+
+    /// Whether the name has to be quoted in a statement: it's not all lowercase letters,
+    /// digits and underscores starting with a letter, or it's a reserved keyword.
+    pub fn requires_quoting(&self) -> bool {
+        let mut chars = self.name.chars();
+        let is_plain = matches!(chars.next(), Some(c) if c.is_ascii_lowercase())
+            && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        !is_plain || is_reserved_keyword(&self.name)
+    }
+
+    /// The name in quotes, regardless of whether they are required.
+    pub fn quoted(&self) -> String {
+        quote_identifier(&self.name)
+    }
+}
+
+/// Quotes a keyspace, table or column name, so that it's case sensitive
+/// and can be a reserved word.
+pub(crate) fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+impl Display for CqlIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.requires_quoting() {
+            write!(f, "{}", self.quoted())
+        } else {
+            write!(f, "{}", self.name)
+        }
+    }
+}
+
+impl FromStr for CqlIdentifier {
+    type Err = BadCqlIdentifier;
+
+    /// Parses an identifier the way the server does: a quoted one is taken verbatim,
+    /// with doubled quotes unescaped, and an unquoted one is lowercased.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(quoted) = s.strip_prefix('"') {
+            let inner = quoted
+                .strip_suffix('"')
+                .ok_or_else(|| BadCqlIdentifier::MalformedQuotes(s.to_owned()))?;
+            // Every quote inside has to be doubled
+            if inner.replace("\"\"", "").contains('"') {
+                return Err(BadCqlIdentifier::MalformedQuotes(s.to_owned()));
+            }
+            return CqlIdentifier::new(inner.replace("\"\"", "\""));
+        }
+
+        let mut chars = s.chars();
+        match chars.next() {
+            None => return Err(BadCqlIdentifier::Empty),
+            Some(c) if c.is_control() => {
+                return Err(BadCqlIdentifier::ControlCharacter(s.to_owned(), c))
+            }
+            Some(c) if !c.is_ascii_alphabetic() => {
+                return Err(BadCqlIdentifier::IllegalCharacter(s.to_owned(), c))
+            }
+            Some(_) => {}
+        }
+        if let Some(c) = chars.find(|c| !(c.is_ascii_alphanumeric() || *c == '_')) {
+            return Err(if c.is_control() {
+                BadCqlIdentifier::ControlCharacter(s.to_owned(), c)
+            } else {
+                BadCqlIdentifier::IllegalCharacter(s.to_owned(), c)
+            });
+        }
+        if is_reserved_keyword(s) {
+            return Err(BadCqlIdentifier::ReservedKeyword(s.to_owned()));
+        }
+        CqlIdentifier::new(s.to_ascii_lowercase())
+    }
+}
+
+impl TryFrom<&str> for CqlIdentifier {
+    type Error = BadCqlIdentifier;
+
+    /// Same as [`CqlIdentifier::new`] - the name is taken verbatim.
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        CqlIdentifier::new(name)
+    }
+}
+
+impl TryFrom<String> for CqlIdentifier {
+    type Error = BadCqlIdentifier;
+
+    /// Same as [`CqlIdentifier::new`] - the name is taken verbatim.
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        CqlIdentifier::new(name)
+    }
+}
+
+impl AsRef<str> for CqlIdentifier {
+    fn as_ref(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BadCqlIdentifier, CqlIdentifier};
+
+    #[test]
+    fn tricky_identifiers_are_parsed_and_rendered() {
+        // (input, name as the server knows it, rendered form)
+        for (input, name, rendered) in [
+            ("users", "users", "users"),
+            ("Users", "users", "users"),
+            ("USERS_2", "users_2", "users_2"),
+            (r#""users""#, "users", "users"),
+            (r#""Users""#, "Users", r#""Users""#),
+            (r#""select""#, "select", r#""select""#),
+            (r#""SELECT""#, "SELECT", r#""SELECT""#),
+            (r#""2fast""#, "2fast", r#""2fast""#),
+            (r#""_private""#, "_private", r#""_private""#),
+            (r#""with space""#, "with space", r#""with space""#),
+            (r#""a""b""#, r#"a"b"#, r#""a""b""#),
+            (r#""""""#, r#"""#, r#""""""#),
+            (r#""zażółć""#, "zażółć", r#""zażółć""#),
+            (r#""a.b""#, "a.b", r#""a.b""#),
+            ("token_", "token_", "token_"),
+        ] {
+            let identifier: CqlIdentifier = input.parse().unwrap();
+            assert_eq!(identifier.name(), name, "{}", input);
+            assert_eq!(identifier.to_string(), rendered, "{}", input);
+            // The rendered form parses back to the same identifier
+            assert_eq!(rendered.parse::<CqlIdentifier>().unwrap(), identifier);
+            assert_eq!(CqlIdentifier::new(name).unwrap(), identifier);
+        }
+    }
+
+    #[test]
+    fn invalid_identifiers_are_rejected() {
+        for (input, error) in [
+            ("", BadCqlIdentifier::Empty),
+            (r#""""#, BadCqlIdentifier::Empty),
+            ("select", BadCqlIdentifier::ReservedKeyword("select".into())),
+            ("Where", BadCqlIdentifier::ReservedKeyword("Where".into())),
+            (
+                "MixedCase!",
+                BadCqlIdentifier::IllegalCharacter("MixedCase!".into(), '!'),
+            ),
+            (
+                "1abc",
+                BadCqlIdentifier::IllegalCharacter("1abc".into(), '1'),
+            ),
+            (
+                "_abc",
+                BadCqlIdentifier::IllegalCharacter("_abc".into(), '_'),
+            ),
+            ("a b", BadCqlIdentifier::IllegalCharacter("a b".into(), ' ')),
+            (
+                "ks.t",
+                BadCqlIdentifier::IllegalCharacter("ks.t".into(), '.'),
+            ),
+            (
+                "tab\tle",
+                BadCqlIdentifier::ControlCharacter("tab\tle".into(), '\t'),
+            ),
+            (
+                "\"new\nline\"",
+                BadCqlIdentifier::ControlCharacter("new\nline".into(), '\n'),
+            ),
+            (
+                r#""abc"#,
+                BadCqlIdentifier::MalformedQuotes(r#""abc"#.into()),
+            ),
+            (r#"""#, BadCqlIdentifier::MalformedQuotes(r#"""#.into())),
+            (
+                r#""a"b""#,
+                BadCqlIdentifier::MalformedQuotes(r#""a"b""#.into()),
+            ),
+        ] {
+            assert_eq!(input.parse::<CqlIdentifier>(), Err(error), "{}", input);
+        }
+
+        assert_eq!(CqlIdentifier::new(""), Err(BadCqlIdentifier::Empty));
+        assert_eq!(
+            CqlIdentifier::new("\0"),
+            Err(BadCqlIdentifier::ControlCharacter("\0".into(), '\0'))
+        );
+    }
+
+    #[test]
+    fn long_identifiers_are_rejected() {
+        let max_length = "ż".repeat(48);
+        let identifier = CqlIdentifier::new(max_length.clone()).unwrap();
+        assert_eq!(identifier.name(), max_length);
+        let quoted = format!("\"{}\"", max_length);
+        assert_eq!(quoted.parse::<CqlIdentifier>().unwrap(), identifier);
+
+        assert_eq!(
+            CqlIdentifier::new(max_length.clone() + "a"),
+            Err(BadCqlIdentifier::TooLong(max_length + "a", 49))
+        );
+        let long = "a".repeat(100);
+        assert_eq!(
+            long.parse::<CqlIdentifier>(),
+            Err(BadCqlIdentifier::TooLong(long, 100))
+        );
+        let quoted = format!("\"{}\"", "A".repeat(49));
+        assert_eq!(
+            quoted.parse::<CqlIdentifier>(),
+            Err(BadCqlIdentifier::TooLong("A".repeat(49), 49))
+        );
+    }
+}
@@ -1,9 +1,14 @@
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
-use crate::transport::execution_profile::ExecutionProfileHandle;
+use crate::routing::Token;
+use crate::transport::execution_profile::{ExecutionProfileHandle, ExecutionProfileInner};
 use crate::{history::HistoryListener, retry_policy::RetryPolicy};
 
 pub mod batch;
+pub mod bound_statement;
+pub mod builder;
+pub mod identifier;
+pub(crate) mod normalize;
 pub mod prepared_statement;
 pub mod query;
 
@@ -19,11 +24,19 @@ pub(crate) struct StatementConfig {
     pub(crate) tracing: bool,
     pub(crate) timestamp: Option<i64>,
     pub(crate) request_timeout: Option<Duration>,
+    pub(crate) custom_payload: Option<HashMap<String, Vec<u8>>>,
 
     pub(crate) history_listener: Option<Arc<dyn HistoryListener>>,
+    pub(crate) collect_history: bool,
+
+    /// Label under which requests of the statement are counted in metrics and reported.
+    pub(crate) label: Option<Arc<str>>,
 
     pub(crate) execution_profile_handle: Option<ExecutionProfileHandle>,
     pub(crate) retry_policy: Option<Arc<dyn RetryPolicy>>,
+
+    /// Token used for routing instead of the one of the bound partition key.
+    pub(crate) routing_token: Option<Token>,
 }
 
 impl StatementConfig {
@@ -32,4 +45,174 @@ impl StatementConfig {
     pub(crate) fn determine_consistency(&self, default_consistency: Consistency) -> Consistency {
         self.consistency.unwrap_or(default_consistency)
     }
+
+    /// Determines the consistency of a request: the one set on the statement takes
+    /// precedence over the one set in its execution profile, which takes precedence
+    /// over the session default.
+    pub(crate) fn resolve_consistency(
+        &self,
+        execution_profile: &ExecutionProfileInner,
+        session_default: Consistency,
+    ) -> Consistency {
+        self.consistency
+            .or(execution_profile.consistency)
+            .unwrap_or(session_default)
+    }
+
+    /// Determines the serial consistency of a request, in the same order of precedence
+    /// as [`resolve_consistency`](Self::resolve_consistency). Serial consistency only
+    /// applies to conditional statements, so the session default isn't used for other ones.
+    pub(crate) fn resolve_serial_consistency(
+        &self,
+        execution_profile: &ExecutionProfileInner,
+        session_default: Option<SerialConsistency>,
+        is_conditional: bool,
+    ) -> Option<SerialConsistency> {
+        match self
+            .serial_consistency
+            .or(execution_profile.serial_consistency)
+        {
+            Some(serial_consistency) => serial_consistency,
+            None if is_conditional => session_default,
+            None => None,
+        }
+    }
+}
+
+/// Truncates the statement text to at most `max_length` bytes, at a char boundary,
+/// e.g. so that long statements don't flood logs.
+pub(crate) fn truncate_statement(statement: &str, max_length: usize) -> &str {
+    if statement.len() <= max_length {
+        return statement;
+    }
+    let mut end = max_length;
+    while !statement.is_char_boundary(end) {
+        end -= 1;
+    }
+    &statement[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{truncate_statement, StatementConfig};
+    use crate::statement::{Consistency, SerialConsistency};
+    use crate::transport::execution_profile::ExecutionProfile;
+
+    #[test]
+    fn long_statements_are_truncated_at_char_boundary() {
+        assert_eq!(truncate_statement("SELECT 1", 8), "SELECT 1");
+        assert_eq!(truncate_statement("SELECT 1", 6), "SELECT");
+
+        let statement = "ą".repeat(10);
+        assert_eq!(truncate_statement(&statement, 5), "ąą");
+        assert_eq!(truncate_statement(&statement, 6), "ąąą");
+        assert_eq!(truncate_statement(&statement, 0), "");
+    }
+
+    #[test]
+    fn non_positive_page_sizes_are_rejected() {
+        use crate::query::Query;
+        use crate::transport::iterator::IterConfig;
+        use crate::transport::table_scan::ScanConfig;
+        use std::panic::catch_unwind;
+
+        for page_size in [0, -1, i32::MIN] {
+            assert!(catch_unwind(|| Query::new("SELECT 1").with_page_size(page_size)).is_err());
+            assert!(catch_unwind(|| Query::new("SELECT 1").set_page_size(page_size)).is_err());
+            assert!(catch_unwind(|| IterConfig::new().page_size(page_size)).is_err());
+            assert!(catch_unwind(|| ScanConfig::new().page_size(page_size)).is_err());
+        }
+        assert_eq!(
+            Query::new("SELECT 1").with_page_size(1).get_page_size(),
+            Some(1)
+        );
+        assert_eq!(IterConfig::new().page_size(7).get_page_size(), Some(7));
+    }
+
+    #[test]
+    fn conditionality_of_queries_follows_their_contents() {
+        use crate::batch::{Batch, BatchStatement, BatchType};
+        use crate::query::Query;
+
+        let mut query = Query::new("INSERT INTO t (a) VALUES (1) IF NOT EXISTS");
+        assert!(query.is_conditional());
+        let mut batch = Batch::new_with_statements(
+            BatchType::Logged,
+            vec![BatchStatement::Query(Query::new("INSERT INTO t (a) VALUES (2)"))],
+        );
+        assert!(!batch.is_conditional());
+        batch.append_statement(query.clone());
+        assert!(batch.is_conditional());
+
+        // The contents can be changed directly
+        query.contents = "INSERT INTO t (a) VALUES (1)".to_owned();
+        assert!(!query.is_conditional());
+        query.contents.push_str(" IF NOT EXISTS");
+        assert!(query.is_conditional());
+    }
+
+    #[test]
+    fn consistency_is_resolved_in_order_of_precedence() {
+        let unset_profile = ExecutionProfile::builder().build();
+        let profile = ExecutionProfile::builder()
+            .consistency(Consistency::Two)
+            .serial_consistency(Some(SerialConsistency::Serial))
+            .build();
+        let unset_statement = StatementConfig::default();
+        let statement = StatementConfig {
+            consistency: Some(Consistency::Three),
+            serial_consistency: Some(None),
+            ..Default::default()
+        };
+        let session_default = Consistency::One;
+        let session_serial_default = Some(SerialConsistency::LocalSerial);
+
+        // Session default
+        assert_eq!(
+            unset_statement.resolve_consistency(&unset_profile.0, session_default),
+            Consistency::One
+        );
+        assert_eq!(
+            unset_statement.resolve_serial_consistency(
+                &unset_profile.0,
+                session_serial_default,
+                true
+            ),
+            Some(SerialConsistency::LocalSerial)
+        );
+
+        // Execution profile
+        assert_eq!(
+            unset_statement.resolve_consistency(&profile.0, session_default),
+            Consistency::Two
+        );
+        assert_eq!(
+            unset_statement.resolve_serial_consistency(&profile.0, session_serial_default, true),
+            Some(SerialConsistency::Serial)
+        );
+
+        // Statement, even if it explicitly unsets the serial consistency
+        assert_eq!(
+            statement.resolve_consistency(&profile.0, session_default),
+            Consistency::Three
+        );
+        assert_eq!(
+            statement.resolve_serial_consistency(&profile.0, session_serial_default, true),
+            None
+        );
+
+        // The session default doesn't apply to statements which aren't conditional
+        assert_eq!(
+            unset_statement.resolve_serial_consistency(
+                &unset_profile.0,
+                session_serial_default,
+                false
+            ),
+            None
+        );
+        assert_eq!(
+            unset_statement.resolve_serial_consistency(&profile.0, session_serial_default, false),
+            Some(SerialConsistency::Serial)
+        );
+    }
 }
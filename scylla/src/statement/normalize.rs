@@ -0,0 +1,520 @@
+//! Normalization of statement text, so that statements differing only in formatting
+//! can share a single prepared statement, and other inspection of statement text.
+
+/// Returns the statement with formatting which doesn't affect its meaning removed:
+/// - runs of whitespace and comments are replaced by a single space, which is dropped
+///   entirely next to punctuation and at the ends of the statement,
+/// - keywords and unquoted identifiers, which are case insensitive, are lowercased.
+///
+/// String literals (`'...'` and `$$...$$`) and quoted identifiers (`"..."`)
+/// are kept byte for byte.
+pub(crate) fn normalize_statement(statement: &str) -> String {
+    let mut normalized = String::with_capacity(statement.len());
+    for token in Tokens::new(statement) {
+        match token.is_word {
+            true => push_token(
+                &mut normalized,
+                &token.text.to_ascii_lowercase(),
+                token.separated,
+            ),
+            false => push_token(&mut normalized, token.text, token.separated),
+        }
+    }
+    normalized
+}
+
+/// Whether the statement may be conditional, i.e. a lightweight transaction.
+///
+/// Conditional statements can't be told apart from `IF [NOT] EXISTS` in schema
+/// statements without parsing them, so the latter are considered conditional too.
+pub(crate) fn is_conditional(statement: &str) -> bool {
+    Tokens::new(statement).any(|token| token.is_word && token.text.eq_ignore_ascii_case("if"))
+}
+
+/// Returns the number of positional bind markers (`?`) in the statement,
+/// not counting question marks in literals, quoted identifiers and comments.
+pub(crate) fn count_bind_markers(statement: &str) -> usize {
+    Tokens::new(statement)
+        .filter(|token| token.text == "?")
+        .count()
+}
+
+/// Returns the statement with string literals replaced by `''` and runs of whitespace
+/// and comments replaced by a single space, so that patterns matched against it
+/// don't match the contents of literals or comments. The case is kept.
+#[cfg(any(feature = "regex", test))]
+pub(crate) fn mask_literals(statement: &str) -> String {
+    let mut masked = String::with_capacity(statement.len());
+    for token in Tokens::new(statement) {
+        if token.separated && !masked.is_empty() {
+            masked.push(' ');
+        }
+        match token.text.starts_with('\'') || token.text.starts_with("$$") {
+            true => masked.push_str("''"),
+            false => masked.push_str(token.text),
+        }
+    }
+    masked
+}
+
+/// Table a statement reads from or modifies, as found by [`statement_target`].
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct StatementTarget {
+    pub(crate) keyspace: Option<String>,
+    pub(crate) table: String,
+    pub(crate) has_where: bool,
+}
+
+/// Finds the table of a `SELECT`, `UPDATE`, `DELETE` or `TRUNCATE` statement,
+/// and whether the statement has a `WHERE` clause.
+///
+/// This is a best-effort parse: `None` is returned for other statements
+/// and ones which can't be understood without a full parser.
+pub(crate) fn statement_target(statement: &str) -> Option<StatementTarget> {
+    let mut tokens = Tokens::new(statement).peekable();
+    let first = tokens.next()?;
+    if !first.is_word {
+        return None;
+    }
+    match first.text.to_ascii_lowercase().as_str() {
+        "select" | "delete" => {
+            // Skip the selectors, which may contain function calls, up to the `FROM`
+            let mut depth = 0usize;
+            loop {
+                let token = tokens.next()?;
+                match token.text {
+                    "(" => depth += 1,
+                    ")" => depth = depth.saturating_sub(1),
+                    text if depth == 0 && token.is_word && text.eq_ignore_ascii_case("from") => {
+                        break
+                    }
+                    _ => {}
+                }
+            }
+        }
+        "update" => {}
+        "truncate" => {
+            if matches!(tokens.peek(), Some(token) if token.is_word && token.text.eq_ignore_ascii_case("table"))
+            {
+                tokens.next();
+            }
+        }
+        _ => return None,
+    }
+    let (keyspace, table) = qualified_name(&mut tokens)?;
+    let has_where = tokens.any(|token| token.is_word && token.text.eq_ignore_ascii_case("where"));
+    Some(StatementTarget {
+        keyspace,
+        table,
+        has_where,
+    })
+}
+
+/// Parses a table name, optionally qualified with a keyspace, the way it's written
+/// in a statement: quoted names are unescaped and unquoted ones lowercased.
+pub(crate) fn parse_table_name(name: &str) -> Option<(Option<String>, String)> {
+    let mut tokens = Tokens::new(name).peekable();
+    let parsed = qualified_name(&mut tokens)?;
+    match tokens.next() {
+        None => Some(parsed),
+        Some(_) => None,
+    }
+}
+
+fn qualified_name<'a>(
+    tokens: &mut std::iter::Peekable<Tokens<'a>>,
+) -> Option<(Option<String>, String)> {
+    let first = identifier(&tokens.next()?)?;
+    if matches!(tokens.peek(), Some(token) if token.text == ".") {
+        tokens.next();
+        let second = identifier(&tokens.next()?)?;
+        return Some((Some(first), second));
+    }
+    Some((None, first))
+}
+
+fn identifier(token: &Token) -> Option<String> {
+    if token.is_word {
+        return Some(token.text.to_ascii_lowercase());
+    }
+    let quoted = token.text.strip_prefix('"')?.strip_suffix('"')?;
+    Some(quoted.replace("\"\"", "\""))
+}
+
+struct Token<'a> {
+    text: &'a str,
+    // Keywords and unquoted identifiers
+    is_word: bool,
+    // Whether whitespace or a comment was skipped before the token
+    separated: bool,
+}
+
+/// Splits the statement into words, literals, quoted identifiers and single
+/// characters of punctuation, skipping whitespace and comments.
+struct Tokens<'a> {
+    statement: &'a str,
+    i: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(statement: &'a str) -> Self {
+        Self { statement, i: 0 }
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let statement = self.statement;
+        let bytes = statement.as_bytes();
+        let mut separated = false;
+
+        while self.i < bytes.len() {
+            let i = self.i;
+            let rest = &bytes[i..];
+            if bytes[i].is_ascii_whitespace() {
+                separated = true;
+                self.i += 1;
+                continue;
+            }
+            if rest.starts_with(b"--") || rest.starts_with(b"//") {
+                self.i = find(bytes, i + 2, b"\n").map_or(bytes.len(), |end| end + 1);
+                separated = true;
+                continue;
+            }
+            if rest.starts_with(b"/*") {
+                match find(bytes, i + 2, b"*/") {
+                    Some(end) => {
+                        self.i = end + 2;
+                        separated = true;
+                        continue;
+                    }
+                    // The statement is invalid anyway, leave the rest for the server to complain about
+                    None => {
+                        self.i = bytes.len();
+                        return Some(Token {
+                            text: &statement[i..],
+                            is_word: false,
+                            separated,
+                        });
+                    }
+                }
+            }
+
+            let (end, is_word) = match bytes[i] {
+                quote @ (b'\'' | b'"') => (quoted_end(bytes, i, quote), false),
+                b'$' if rest.starts_with(b"$$") => (
+                    find(bytes, i + 2, b"$$").map_or(bytes.len(), |end| end + 2),
+                    false,
+                ),
+                b if is_word_byte(b) => {
+                    let len = rest.iter().take_while(|b| is_word_byte(**b)).count();
+                    (i + len, true)
+                }
+                // Non-ASCII bytes are a part of words, so this is a single ASCII character
+                _ => (i + 1, false),
+            };
+            self.i = end;
+            return Some(Token {
+                text: &statement[i..end],
+                is_word,
+                separated,
+            });
+        }
+        None
+    }
+}
+
+fn push_token(normalized: &mut String, token: &str, separated: bool) {
+    if let (Some(last), Some(first)) = (normalized.bytes().last(), token.bytes().next()) {
+        if separated && !can_join(last, first) {
+            normalized.push(' ');
+        }
+    }
+    normalized.push_str(token);
+}
+
+/// Whether the whitespace between the two characters can be dropped without
+/// making them a part of the same token.
+fn can_join(left: u8, right: u8) -> bool {
+    const PUNCTUATION: &[u8] = b"(),;?";
+    // Can form `<=`, `>=`, `!=`, `+=`, `-=` and the like
+    const OPERATOR: &[u8] = b"<>!+-=:";
+
+    if PUNCTUATION.contains(&left) || PUNCTUATION.contains(&right) {
+        return true;
+    }
+    (left == b'=' && !OPERATOR.contains(&right)) || (right == b'=' && !OPERATOR.contains(&left))
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || !b.is_ascii()
+}
+
+/// Returns the index just past the literal or identifier quoted with `quote`
+/// starting at `start`, in which the quote is escaped by doubling it.
+fn quoted_end(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if bytes[i] == quote {
+            if bytes.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes[from..]
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|pos| from + pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        count_bind_markers, is_conditional, mask_literals, normalize_statement, parse_table_name,
+        statement_target, StatementTarget,
+    };
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn formatting_is_normalized() {
+        let expected = "select * from t where id=?";
+        for statement in [
+            "select * from t where id=?",
+            "SELECT * FROM t WHERE id = ?",
+            "  Select *\n\tfrom T  where ID =? ",
+            "SELECT * -- all columns\nFROM t /* the table */ WHERE id=?",
+        ] {
+            assert_eq!(normalize_statement(statement), expected);
+        }
+
+        assert_eq!(
+            normalize_statement("INSERT INTO ks.t (a, b) VALUES (?, ?) USING TTL ?"),
+            "insert into ks.t(a,b)values(?,?)using ttl?"
+        );
+        assert_eq!(
+            normalize_statement("UPDATE t SET c = c + ? WHERE a <= 1 AND b >= - 1"),
+            "update t set c=c +?where a <=1 and b >= - 1"
+        );
+    }
+
+    #[test]
+    fn literals_and_quoted_identifiers_are_kept() {
+        for (statement, expected) in [
+            (
+                "SELECT \"MyColumn\" FROM \"My  Table\" WHERE a = 'Some  ''Text'' -- here'",
+                "select \"MyColumn\" from \"My  Table\" where a='Some  ''Text'' -- here'",
+            ),
+            (
+                "SELECT \"with \"\" quote\" FROM t WHERE a = $$ Don't  /* touch */ $$",
+                "select \"with \"\" quote\" from t where a=$$ Don't  /* touch */ $$",
+            ),
+            (
+                "SELECT 'zażółć  GĘŚLĄ' FROM Tąble",
+                "select 'zażółć  GĘŚLĄ' from tąble",
+            ),
+            // Unterminated literals and comments are left for the server to reject
+            ("SELECT 'Open  ", "select 'Open  "),
+            ("SELECT a /* Open  ", "select a /* Open  "),
+        ] {
+            assert_eq!(normalize_statement(statement), expected);
+        }
+    }
+
+    #[test]
+    fn conditional_statements_are_recognized() {
+        for statement in [
+            "UPDATE t SET b = 1 WHERE a = 0 IF b = 0",
+            "insert into t (a) values (0) if not exists",
+            "DELETE FROM t WHERE a = 0 If EXISTS",
+            "UPDATE t SET b = 1 WHERE a = 0 IF(b)=0",
+        ] {
+            assert!(is_conditional(statement), "{}", statement);
+        }
+
+        for statement in [
+            "SELECT * FROM t WHERE a = ?",
+            "UPDATE t SET b = 'IF' WHERE a = 0 -- IF b = 0",
+            "UPDATE \"if\" SET b = 1 WHERE a = 0 /* IF b = 0 */",
+            "SELECT iff, if_exists FROM t",
+        ] {
+            assert!(!is_conditional(statement), "{}", statement);
+        }
+    }
+
+    #[test]
+    fn bind_markers_are_counted() {
+        for (statement, count) in [
+            ("SELECT * FROM t", 0),
+            ("SELECT * FROM t WHERE a = ?", 1),
+            ("INSERT INTO t (a, b) VALUES (?,?) USING TTL ?", 3),
+            ("SELECT * FROM t WHERE a = '?' AND b = ?", 1),
+            ("SELECT \"?\" FROM t WHERE a = $$ ?? $$", 0),
+            (
+                "SELECT * FROM t -- WHERE a = ?\n WHERE b = ? /* AND c = ? */",
+                1,
+            ),
+            ("SELECT * FROM t WHERE a = 'It''s ?'", 0),
+        ] {
+            assert_eq!(count_bind_markers(statement), count, "{}", statement);
+        }
+    }
+
+    #[test]
+    fn literals_are_masked() {
+        for (statement, expected) in [
+            (
+                "INSERT INTO t (a) VALUES ('TRUNCATE t')",
+                "INSERT INTO t (a) VALUES ('')",
+            ),
+            (
+                "SELECT * FROM \"Drop\" -- DROP TABLE\n WHERE a = $$ DROP $$",
+                "SELECT * FROM \"Drop\" WHERE a = ''",
+            ),
+            ("  Select\n\t$a /* x */ FROM t", "Select $a FROM t"),
+        ] {
+            assert_eq!(mask_literals(statement), expected);
+        }
+    }
+
+    #[test]
+    fn statement_targets_are_found() {
+        let target = |keyspace: Option<&str>, table: &str, has_where: bool| {
+            Some(StatementTarget {
+                keyspace: keyspace.map(str::to_owned),
+                table: table.to_owned(),
+                has_where,
+            })
+        };
+        for (statement, expected) in [
+            (
+                "SELECT * FROM ks.T WHERE a = 1",
+                target(Some("ks"), "t", true),
+            ),
+            (
+                "select count(*), max(b) from \"Ks\".\"My \"\"T\"\"\"",
+                target(Some("Ks"), "My \"T\"", false),
+            ),
+            (
+                "UPDATE t SET a = 'WHERE' WHERE b = 0",
+                target(None, "t", true),
+            ),
+            ("UPDATE t SET a = 'WHERE'", target(None, "t", false)),
+            (
+                "DELETE a, b FROM t -- WHERE b = 0",
+                target(None, "t", false),
+            ),
+            (
+                "DELETE FROM ks.t WHERE b = 0",
+                target(Some("ks"), "t", true),
+            ),
+            ("TRUNCATE TABLE ks.t", target(Some("ks"), "t", false)),
+            ("truncate t", target(None, "t", false)),
+            ("INSERT INTO t (a) VALUES (0)", None),
+            ("BEGIN BATCH DELETE FROM t APPLY BATCH", None),
+            ("SELECT a FROM", None),
+        ] {
+            assert_eq!(statement_target(statement), expected, "{}", statement);
+        }
+    }
+
+    #[test]
+    fn table_names_are_parsed() {
+        assert_eq!(
+            parse_table_name("Ks.Users"),
+            Some((Some("ks".to_owned()), "users".to_owned()))
+        );
+        assert_eq!(
+            parse_table_name("\"Users\""),
+            Some((None, "Users".to_owned()))
+        );
+        assert_eq!(parse_table_name("ks.users x"), None);
+        assert_eq!(parse_table_name(""), None);
+    }
+
+    // Tokens of which the random statements are built.
+    const WORDS: &[&str] = &[
+        "SELECT", "from", "Where", "AND", "t", "Ks", "a1", "b_2", "1", "0xAB", "-", "+", "=", "<",
+        ">", "!", "(", ")", ",", "?", ";", ".", "*", ":", "/", "$",
+    ];
+    const LITERALS: &[&str] = &[
+        "'plain'",
+        "'It''s  -- not a comment'",
+        "'/* nor */ this'",
+        "'\"quoted\"'",
+        "''",
+        "$$ $ body  'with' \"quotes\" $$",
+        "$$$$",
+        "\"Quoted  Identifier\"",
+        "\"with \"\" quote\"",
+        "\"SELECT\"",
+        "'Zażółć'",
+    ];
+    const SEPARATORS: &[&str] = &[" ", "  ", "\n", "\t", " -- comment\n", "/* comment */", ""];
+
+    // Returns a random statement, the same statement with keywords and identifiers
+    // in random case, and the literals it contains.
+    fn random_statement(rng: &mut ChaCha8Rng) -> (String, String, Vec<&'static str>) {
+        let mut statement = String::new();
+        let mut recased = String::new();
+        let mut literals = Vec::new();
+        for _ in 0..rng.gen_range(1..30) {
+            let separator = *SEPARATORS.choose(rng).unwrap();
+            statement.push_str(separator);
+            recased.push_str(separator);
+            if rng.gen_bool(0.3) {
+                let literal = *LITERALS.choose(rng).unwrap();
+                statement.push_str(literal);
+                recased.push_str(literal);
+                literals.push(literal);
+            } else {
+                let word = *WORDS.choose(rng).unwrap();
+                statement.push_str(word);
+                for c in word.chars() {
+                    match rng.gen_bool(0.5) {
+                        true => recased.push(c.to_ascii_uppercase()),
+                        false => recased.push(c.to_ascii_lowercase()),
+                    }
+                }
+            }
+            // Keep the tokens apart, so that they can't merge into a comment or a literal
+            statement.push(' ');
+            recased.push(' ');
+        }
+        (statement, recased, literals)
+    }
+
+    #[test]
+    fn normalization_keeps_literals() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0x5eed);
+        for _ in 0..2000 {
+            let (statement, recased, literals) = random_statement(&mut rng);
+            let normalized = normalize_statement(&statement);
+
+            // All literals are present, unchanged and in order
+            let mut rest = normalized.as_str();
+            for literal in &literals {
+                let pos = rest
+                    .find(literal)
+                    .unwrap_or_else(|| panic!("{:?} lost {:?}", statement, literal));
+                rest = &rest[pos + literal.len()..];
+            }
+
+            // Normalization is idempotent
+            assert_eq!(normalize_statement(&normalized), normalized);
+
+            // The case of keywords and identifiers doesn't matter
+            assert_eq!(normalize_statement(&recased), normalized, "{:?}", statement);
+        }
+    }
+}
@@ -1,19 +1,24 @@
+use arc_swap::ArcSwap;
 use bytes::{Bytes, BytesMut};
-use scylla_cql::errors::{BadQuery, QueryError};
+use scylla_cql::errors::{BadQuery, QueryError, StatementInfo};
 use scylla_cql::frame::types::RawValue;
 use scylla_cql::types::serialize::row::{RowSerializationContext, SerializeRow, SerializedValues};
+use scylla_cql::types::serialize::writers::RowWriter;
 use scylla_cql::types::serialize::SerializationError;
 use smallvec::{smallvec, SmallVec};
+use std::collections::HashMap;
 use std::convert::TryInto;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
 use std::time::Duration;
 use thiserror::Error;
 use uuid::Uuid;
 
-use scylla_cql::frame::response::result::ColumnSpec;
+use scylla_cql::frame::response::result::{ColumnSpec, ColumnType, PartitionKeyIndex, TableSpec};
 
-use super::StatementConfig;
-use crate::frame::response::result::PreparedMetadata;
+use super::bound_statement::BoundStatement;
+use super::{normalize, StatementConfig};
+use crate::frame::response::result::{PreparedMetadata, ResultMetadata};
 use crate::frame::types::{Consistency, SerialConsistency};
 use crate::history::HistoryListener;
 use crate::retry_policy::RetryPolicy;
@@ -35,9 +40,37 @@ pub struct PreparedStatement {
 }
 
 #[derive(Debug)]
-struct PreparedStatementSharedData {
+pub(crate) struct PreparedStatementSharedData {
     metadata: PreparedMetadata,
-    statement: String,
+    result_metadata: ResultMetadata,
+    // Result metadata from the latest (re-)preparation, used to decode rows
+    // which are sent without metadata
+    current_result_metadata: ArcSwap<ResultMetadata>,
+    pub(crate) statement: String,
+    is_conditional: bool,
+    // Set when re-preparation returned different types of bound values
+    metadata_outdated: AtomicBool,
+    // Set when the table the statement operates on was dropped,
+    // until the statement is re-prepared
+    stale: AtomicBool,
+    // Bumped on every schema change which may have changed the result columns
+    result_metadata_version: AtomicUsize,
+    // The latest version for which rows were received together with their metadata
+    // and compared with `current_result_metadata`
+    confirmed_result_metadata_version: AtomicUsize,
+}
+
+impl PreparedStatementSharedData {
+    /// Marks the statement to be re-prepared before its next execution.
+    pub(crate) fn mark_stale(&self) {
+        self.stale.store(true, Ordering::Relaxed);
+    }
+
+    /// Makes the next executions of the statement receive rows together with their
+    /// metadata, until it's confirmed that the cached result metadata is up to date.
+    pub(crate) fn mark_result_metadata_changed(&self) {
+        self.result_metadata_version.fetch_add(1, Ordering::Relaxed);
+    }
 }
 
 impl Clone for PreparedStatement {
@@ -59,6 +92,7 @@ impl PreparedStatement {
         id: Bytes,
         is_lwt: bool,
         metadata: PreparedMetadata,
+        result_metadata: ResultMetadata,
         statement: String,
         page_size: Option<i32>,
         config: StatementConfig,
@@ -67,7 +101,14 @@ impl PreparedStatement {
             id,
             shared: Arc::new(PreparedStatementSharedData {
                 metadata,
+                current_result_metadata: ArcSwap::from_pointee(result_metadata.clone()),
+                result_metadata,
+                is_conditional: is_lwt || normalize::is_conditional(&statement),
                 statement,
+                metadata_outdated: AtomicBool::new(false),
+                stale: AtomicBool::new(false),
+                result_metadata_version: AtomicUsize::new(0),
+                confirmed_result_metadata_version: AtomicUsize::new(0),
             }),
             prepare_tracing_ids: Vec::new(),
             page_size,
@@ -86,6 +127,10 @@ impl PreparedStatement {
     }
 
     /// Sets the page size for this CQL query.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `page_size` is not positive.
     pub fn set_page_size(&mut self, page_size: i32) {
         assert!(page_size > 0, "page size must be larger than 0");
         self.page_size = Some(page_size);
@@ -124,6 +169,11 @@ impl PreparedStatement {
         self.is_confirmed_lwt
     }
 
+    /// Whether the statement may be conditional, so that serial consistency applies to it.
+    pub(crate) fn is_conditional(&self) -> bool {
+        self.shared.is_conditional
+    }
+
     /// Computes the partition key of the target table from given values —
     /// it assumes that all partition key columns are passed in values.
     /// Partition keys have specific serialization rules.
@@ -157,6 +207,64 @@ impl PreparedStatement {
         PartitionKey::new(self.get_prepared_metadata(), bound_values)
     }
 
+    /// Whether the types of bound values changed when the statement was re-prepared,
+    /// e.g. because of a schema change. Such a statement can't be executed anymore
+    /// and has to be prepared again.
+    pub fn has_outdated_metadata(&self) -> bool {
+        self.shared.metadata_outdated.load(Ordering::Relaxed)
+    }
+
+    /// Whether the table the statement operates on was dropped since the statement
+    /// was last prepared, e.g. to be created again. Such a statement is transparently
+    /// re-prepared before its next execution, so that it doesn't use stale metadata.
+    pub fn is_stale(&self) -> bool {
+        self.shared.stale.load(Ordering::Relaxed)
+    }
+
+    /// Clears the stale marker, returning whether it was set. The marker has to be
+    /// restored if re-preparation of the statement fails.
+    pub(crate) fn take_stale(&self) -> bool {
+        self.shared.stale.swap(false, Ordering::Relaxed)
+    }
+
+    /// Restores the stale marker, see [`Self::take_stale`].
+    pub(crate) fn mark_stale(&self) {
+        self.shared.mark_stale()
+    }
+
+    /// Checks the metadata returned by re-preparation of the statement, marking
+    /// the statement as outdated if the types of bound values changed.
+    pub(crate) fn check_reprepared_metadata(
+        &self,
+        reprepared: &PreparedMetadata,
+    ) -> Result<(), QueryError> {
+        fn types_of(metadata: &PreparedMetadata) -> impl Iterator<Item = &ColumnType> {
+            metadata.col_specs.iter().map(|spec| &spec.typ)
+        }
+        if !types_of(self.get_prepared_metadata()).eq(types_of(reprepared)) {
+            self.shared.metadata_outdated.store(true, Ordering::Relaxed);
+        }
+        match self.has_outdated_metadata() {
+            true => Err(QueryError::BadQuery(BadQuery::PreparedMetadataChanged)),
+            false => Ok(()),
+        }
+    }
+
+    /// Returns the partition key and the token to route a request with,
+    /// preferring the [routing token](Self::set_routing_token) if one is set.
+    /// The partition key is then unknown.
+    pub(crate) fn partition_key_and_routing_token<'ps>(
+        &'ps self,
+        serialized_values: &'ps SerializedValues,
+    ) -> Result<(Option<PartitionKey<'ps>>, Option<Token>), QueryError> {
+        if let Some(token) = self.config.routing_token {
+            return Ok((None, Some(token)));
+        }
+        Ok(self
+            .extract_partition_key_and_calculate_token(&self.partitioner_name, serialized_values)?
+            .unzip())
+    }
+
     pub(crate) fn extract_partition_key_and_calculate_token<'ps>(
         &'ps self,
         partitioner_name: &'ps PartitionerName,
@@ -184,6 +292,38 @@ impl PreparedStatement {
         Ok(Some((partition_key, token)))
     }
 
+    /// Binds the values to the statement, so that it can be executed many times
+    /// with [`Session::execute_bound`](crate::Session::execute_bound) without serializing
+    /// the values and computing the token on each execution.
+    ///
+    /// The values are checked against the types of the bind markers here,
+    /// so a mismatch is reported by this function rather than by the execution.
+    pub fn bind(&self, values: &impl SerializeRow) -> Result<BoundStatement, PartitionKeyError> {
+        let values = self.serialize_values(values)?;
+        let (routing_key, token) = match self.is_token_aware() {
+            true => {
+                let mut routing_key = BytesMut::new();
+                self.extract_partition_key(&values)?
+                    .write_encoded_partition_key(&mut |chunk: &[u8]| {
+                        routing_key.extend_from_slice(chunk)
+                    })?;
+                let token = self.config.routing_token.unwrap_or_else(|| {
+                    let mut hasher = self.partitioner_name.build_hasher();
+                    hasher.write(&routing_key);
+                    hasher.finish()
+                });
+                (Some(routing_key.freeze()), Some(token))
+            }
+            false => (None, self.config.routing_token),
+        };
+        Ok(BoundStatement::new(
+            self.clone(),
+            values,
+            routing_key,
+            token,
+        ))
+    }
+
     /// Calculates the token for given prepared statement and values.
     ///
     /// Returns the token that would be computed for executing the provided
@@ -201,24 +341,127 @@ impl PreparedStatement {
         &self,
         values: &SerializedValues,
     ) -> Result<Option<Token>, QueryError> {
-        self.extract_partition_key_and_calculate_token(&self.partitioner_name, values)
-            .map(|opt| opt.map(|(_pk, token)| token))
+        self.partition_key_and_routing_token(values)
+            .map(|(_pk, token)| token)
+    }
+
+    /// Makes the driver route requests of this statement to the replicas owning
+    /// the given token, instead of the token of the partition key in the bound values.
+    ///
+    /// Useful when the partition key can't be taken from the bound values,
+    /// e.g. for `SELECT ... WHERE id IN ?`, but it's known which partition is
+    /// the best to route by. Passing `None` restores the automatic routing.
+    pub fn set_routing_token(&mut self, token: Option<Token>) {
+        self.config.routing_token = token;
+    }
+
+    /// Gets the token set with [`set_routing_token`](Self::set_routing_token),
+    /// [`set_routing_key`](Self::set_routing_key) or [`set_raw_routing_key`](Self::set_raw_routing_key).
+    pub fn get_routing_token(&self) -> Option<Token> {
+        self.config.routing_token
+    }
+
+    /// Makes the driver route requests of this statement to the replicas owning
+    /// the given partition key, instead of the one in the bound values.
+    ///
+    /// The values are those of the partition key columns only, in the order
+    /// in which they appear in the partition key of the table. The override
+    /// is removed by passing `None` to [`set_routing_token`](Self::set_routing_token).
+    pub fn set_routing_key(
+        &mut self,
+        partition_key: &impl SerializeRow,
+    ) -> Result<(), PartitionKeyError> {
+        let metadata = self.partition_key_metadata();
+        let ctx = RowSerializationContext::from_prepared(&metadata);
+        let serialized = SerializedValues::from_serializable(&ctx, partition_key)?;
+        let token =
+            PartitionKey::new(&metadata, &serialized)?.calculate_token(&self.partitioner_name)?;
+        self.config.routing_token = Some(token);
+        Ok(())
+    }
+
+    /// Makes the driver route requests of this statement to the replicas owning
+    /// the given partition key, serialized as in the `routing_key` of the native protocol.
+    /// See [`Query::set_raw_routing_key`](crate::query::Query::set_raw_routing_key).
+    pub fn set_raw_routing_key(&mut self, key: &[u8]) {
+        let mut hasher = self.partitioner_name.build_hasher();
+        hasher.write(key);
+        self.config.routing_token = Some(hasher.finish());
     }
 
-    /// Returns the name of the keyspace this statement is operating on.
+    // Metadata of the partition key columns alone, in the order of the partition key.
+    fn partition_key_metadata(&self) -> PreparedMetadata {
+        let prepared = self.get_prepared_metadata();
+        let mut pk_indexes = prepared.pk_indexes.clone();
+        pk_indexes.sort_unstable_by_key(|pk_index| pk_index.sequence);
+        let col_specs: Vec<ColumnSpec> = pk_indexes
+            .iter()
+            .map(|pk_index| prepared.col_specs[pk_index.index as usize].clone())
+            .collect();
+        PreparedMetadata {
+            flags: 0,
+            col_count: col_specs.len(),
+            pk_indexes: (0..col_specs.len() as u16)
+                .map(|i| PartitionKeyIndex {
+                    index: i,
+                    sequence: i,
+                })
+                .collect(),
+            col_specs,
+        }
+    }
+
+    /// Returns the name of the keyspace this statement is operating on,
+    /// or `None` if the statement has neither bind markers nor result columns.
     pub fn get_keyspace_name(&self) -> Option<&str> {
-        self.get_prepared_metadata()
-            .col_specs
-            .first()
-            .map(|col_spec| col_spec.table_spec.ks_name.as_str())
+        self.get_target_table()
+            .map(|table_spec| table_spec.ks_name.as_str())
     }
 
-    /// Returns the name of the table this statement is operating on.
+    /// Returns the name of the table this statement is operating on,
+    /// or `None` if the statement has neither bind markers nor result columns.
     pub fn get_table_name(&self) -> Option<&str> {
-        self.get_prepared_metadata()
-            .col_specs
-            .first()
-            .map(|col_spec| col_spec.table_spec.table_name.as_str())
+        self.get_target_table()
+            .map(|table_spec| table_spec.table_name.as_str())
+    }
+
+    /// Returns specifications (names and types) of the bind markers of the statement,
+    /// in the order in which values have to be bound.
+    pub fn get_variable_col_specs(&self) -> &[ColumnSpec] {
+        &self.get_prepared_metadata().col_specs
+    }
+
+    /// Returns specifications (names and types) of the columns of rows returned
+    /// by the statement. Empty for statements which do not return rows.
+    pub fn get_result_col_specs(&self) -> &[ColumnSpec] {
+        &self.get_result_metadata().col_specs
+    }
+
+    /// Returns the indexes of the bind markers which constitute the partition key,
+    /// sorted by `index`. Empty if the statement is not token aware.
+    pub fn get_partition_key_indexes(&self) -> &[PartitionKeyIndex] {
+        &self.get_prepared_metadata().pk_indexes
+    }
+
+    /// Checks whether the given values can be bound to the statement, without sending it.
+    ///
+    /// The values are type checked against [`get_variable_col_specs`](Self::get_variable_col_specs)
+    /// and serialized the same way as when the statement is executed,
+    /// so an `Ok` result means that executing the statement with the values
+    /// will not fail with a serialization error.
+    pub fn check_values(&self, values: &impl SerializeRow) -> Result<(), SerializationError> {
+        let ctx = RowSerializationContext::from_prepared(self.get_prepared_metadata());
+        let mut buf = Vec::new();
+        values.serialize(&ctx, &mut RowWriter::new(&mut buf))
+    }
+
+    /// Describes the statement for errors - by its id and the table it operates on.
+    pub(crate) fn statement_info(&self) -> StatementInfo {
+        StatementInfo::Prepared {
+            id: self.id.clone(),
+            keyspace: self.get_keyspace_name().map(ToOwned::to_owned),
+            table: self.get_table_name().map(ToOwned::to_owned),
+        }
     }
 
     /// Sets the consistency to be used when executing this statement.
@@ -259,7 +502,7 @@ impl PreparedStatement {
     }
 
     /// Enable or disable CQL Tracing for this statement
-    /// If enabled session.execute() will return a QueryResult containing tracing_id
+    /// If enabled session.execute_unpaged() will return a QueryResult containing tracing_id
     /// which can be used to query tracing information about the execution of this query
     pub fn set_tracing(&mut self, should_trace: bool) {
         self.config.tracing = should_trace;
@@ -283,6 +526,19 @@ impl PreparedStatement {
         self.config.timestamp
     }
 
+    /// Sets the custom payload which is sent to the server together with this statement.
+    /// The custom payload is a map of opaque values, which can be interpreted
+    /// by server-side extensions (e.g. custom query handlers).
+    /// If None, no custom payload is sent.
+    pub fn set_custom_payload(&mut self, custom_payload: Option<HashMap<String, Vec<u8>>>) {
+        self.config.custom_payload = custom_payload;
+    }
+
+    /// Gets the custom payload which is sent to the server together with this statement.
+    pub fn get_custom_payload(&self) -> Option<&HashMap<String, Vec<u8>>> {
+        self.config.custom_payload.as_ref()
+    }
+
     /// Sets the client-side timeout for this statement.
     /// If not None, the driver will stop waiting for the request
     /// to finish after `timeout` passed.
@@ -306,6 +562,57 @@ impl PreparedStatement {
         &self.shared.metadata
     }
 
+    /// Metadata of the rows returned by the statement, as returned by the database
+    /// when the statement was prepared.
+    pub(crate) fn get_result_metadata(&self) -> &ResultMetadata {
+        &self.shared.result_metadata
+    }
+
+    /// Result metadata returned by the latest preparation of the statement,
+    /// with which rows sent without metadata are decoded.
+    pub(crate) fn get_current_result_metadata(&self) -> Arc<ResultMetadata> {
+        self.shared.current_result_metadata.load_full()
+    }
+
+    /// Replaces the result metadata with the one returned by re-preparation
+    /// of the statement, which can differ after a schema change.
+    pub(crate) fn update_result_metadata(&self, result_metadata: ResultMetadata) {
+        self.shared
+            .current_result_metadata
+            .store(Arc::new(result_metadata));
+    }
+
+    /// Returns the version of the result metadata to confirm if a schema change, which may
+    /// have changed the result columns, was announced since rows of the statement were last
+    /// received together with their metadata. Until then, rows aren't sent without metadata.
+    pub(crate) fn unconfirmed_result_metadata_version(&self) -> Option<usize> {
+        let version = self.shared.result_metadata_version.load(Ordering::Relaxed);
+        let confirmed = self
+            .shared
+            .confirmed_result_metadata_version
+            .load(Ordering::Relaxed);
+        (version != confirmed).then_some(version)
+    }
+
+    /// Marks the result metadata as up to date with schema changes
+    /// up to the given version, see [`Self::unconfirmed_result_metadata_version`].
+    pub(crate) fn confirm_result_metadata(&self, version: usize) {
+        self.shared
+            .confirmed_result_metadata_version
+            .fetch_max(version, Ordering::Relaxed);
+    }
+
+    /// Returns the table the statement operates on, known from the metadata
+    /// of either its bind markers or its result.
+    pub(crate) fn get_target_table(&self) -> Option<&TableSpec> {
+        target_table(self.get_prepared_metadata(), self.get_result_metadata())
+    }
+
+    /// Used to keep track of prepared statements without keeping them alive.
+    pub(crate) fn downgrade_shared(&self) -> Weak<PreparedStatementSharedData> {
+        Arc::downgrade(&self.shared)
+    }
+
     /// Get the name of the partitioner used for this statement.
     pub(crate) fn get_partitioner_name(&self) -> &PartitionerName {
         &self.partitioner_name
@@ -333,6 +640,33 @@ impl PreparedStatement {
         self.config.history_listener.take()
     }
 
+    /// Enables or disables collecting the history of attempts made to execute the statement.
+    /// See [`Query::set_history_collection`](crate::query::Query::set_history_collection).
+    pub fn set_history_collection(&mut self, collect_history: bool) {
+        self.config.collect_history = collect_history;
+    }
+
+    /// Gets whether collecting the history of attempts is enabled for the statement.
+    pub fn get_history_collection(&self) -> bool {
+        self.config.collect_history
+    }
+
+    /// Sets the label of the statement, under which its requests are counted in metrics
+    /// and reported. See [`Query::set_label`](crate::query::Query::set_label).
+    pub fn set_label(&mut self, label: impl Into<Arc<str>>) {
+        self.config.label = Some(label.into());
+    }
+
+    /// Removes the label set by `set_label`.
+    pub fn remove_label(&mut self) {
+        self.config.label = None;
+    }
+
+    /// Gets the label set by `set_label`.
+    pub fn get_label(&self) -> Option<&str> {
+        self.config.label.as_deref()
+    }
+
     /// Associates the query with execution profile referred by the provided handle.
     /// Handle may be later remapped to another profile, and query will reflect those changes.
     pub fn set_execution_profile_handle(&mut self, profile_handle: Option<ExecutionProfileHandle>) {
@@ -351,6 +685,29 @@ impl PreparedStatement {
         let ctx = RowSerializationContext::from_prepared(self.get_prepared_metadata());
         SerializedValues::from_serializable(&ctx, values)
     }
+
+    /// Same as `serialize_values`, but reuses the given buffer.
+    pub(crate) fn serialize_values_in(
+        &self,
+        buffer: Vec<u8>,
+        values: &impl SerializeRow,
+    ) -> Result<SerializedValues, SerializationError> {
+        let ctx = RowSerializationContext::from_prepared(self.get_prepared_metadata());
+        SerializedValues::from_serializable_in(buffer, &ctx, values)
+    }
+}
+
+/// Returns the table a prepared statement operates on. Statements without bind markers
+/// (e.g. `SELECT * FROM t`) only have it in the metadata of their result.
+pub(crate) fn target_table<'a>(
+    metadata: &'a PreparedMetadata,
+    result_metadata: &'a ResultMetadata,
+) -> Option<&'a TableSpec> {
+    metadata
+        .col_specs
+        .first()
+        .or_else(|| result_metadata.col_specs.first())
+        .map(|col_spec| &col_spec.table_spec)
 }
 
 #[derive(Clone, Debug, Error, PartialEq, Eq, PartialOrd, Ord)]
@@ -483,14 +840,16 @@ impl<'ps> PartitionKey<'ps> {
 
 #[cfg(test)]
 mod tests {
+    use bytes::Bytes;
     use scylla_cql::{
         frame::response::result::{
-            ColumnSpec, ColumnType, PartitionKeyIndex, PreparedMetadata, TableSpec,
+            ColumnSpec, ColumnType, PartitionKeyIndex, PreparedMetadata, ResultMetadata, TableSpec,
         },
         types::serialize::row::SerializedValues,
     };
 
-    use crate::prepared_statement::PartitionKey;
+    use crate::prepared_statement::{PartitionKey, PreparedStatement};
+    use crate::statement::StatementConfig;
 
     fn make_meta(
         cols: impl IntoIterator<Item = ColumnType>,
@@ -526,6 +885,82 @@ mod tests {
         }
     }
 
+    fn make_prepared(
+        cols: impl IntoIterator<Item = ColumnType>,
+        idx: impl IntoIterator<Item = usize>,
+    ) -> PreparedStatement {
+        PreparedStatement::new(
+            Bytes::from_static(b"id"),
+            false,
+            make_meta(cols, idx),
+            ResultMetadata::default(),
+            "INSERT INTO ks.t (col_0, col_1) VALUES (?, ?)".to_owned(),
+            None,
+            StatementConfig::default(),
+        )
+    }
+
+    #[test]
+    fn test_metadata_accessors() {
+        let prepared = make_prepared([ColumnType::Int, ColumnType::Text], [0]);
+
+        assert_eq!(prepared.get_keyspace_name(), Some("ks"));
+        assert_eq!(prepared.get_table_name(), Some("t"));
+        let names: Vec<_> = prepared
+            .get_variable_col_specs()
+            .iter()
+            .map(|spec| (spec.name.as_str(), spec.typ.clone()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("col_0", ColumnType::Int), ("col_1", ColumnType::Text)]
+        );
+        assert_eq!(prepared.get_partition_key_indexes().len(), 1);
+        assert_eq!(prepared.get_partition_key_indexes()[0].index, 0);
+        assert!(prepared.get_result_col_specs().is_empty());
+    }
+
+    #[test]
+    fn test_float_keys_tokens() {
+        use crate::transport::partitioner::{Murmur3Partitioner, Partitioner};
+        use scylla_cql::frame::value::StrictFloat;
+        use scylla_cql::types::serialize::row::SerializeRow;
+
+        let prepared = make_prepared([ColumnType::Double, ColumnType::Int], [0]);
+        let token = |values: &dyn SerializeRow| prepared.calculate_token(&values).unwrap().unwrap();
+
+        // The token is the hash of the serialized key, as computed by the database
+        for (value, bytes) in [
+            (0.0f64, [0, 0, 0, 0, 0, 0, 0, 0]),
+            (-0.0, [0x80, 0, 0, 0, 0, 0, 0, 0]),
+            (f64::NAN, [0x7f, 0xf8, 0, 0, 0, 0, 0, 0]),
+            (f64::from_bits(1), [0, 0, 0, 0, 0, 0, 0, 1]),
+        ] {
+            assert_eq!(token(&(value, 1)), Murmur3Partitioner.hash_one(&bytes));
+        }
+        assert_ne!(token(&(0.0f64, 1)), token(&(-0.0f64, 1)));
+
+        // Strict floats route both zeros to the same token, and don't route NaN at all
+        assert_eq!(token(&(StrictFloat(-0.0f64), 1)), token(&(0.0f64, 1)));
+        assert!(prepared
+            .calculate_token(&(StrictFloat(f64::NAN), 1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_check_values() {
+        let prepared = make_prepared([ColumnType::Int, ColumnType::Text], [0]);
+
+        prepared.check_values(&(1i32, "text")).unwrap();
+        prepared.check_values(&(None::<i32>, "text")).unwrap();
+
+        // Wrong type of the first value.
+        assert!(prepared.check_values(&("text", "text")).is_err());
+        // Wrong number of values.
+        assert!(prepared.check_values(&(1i32,)).is_err());
+        assert!(prepared.check_values(&(1i32, "text", 2i32)).is_err());
+    }
+
     #[test]
     fn test_partition_key_multiple_columns_shuffled() {
         let meta = make_meta(
@@ -558,4 +993,86 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn bound_values_match_values_serialized_on_execution() {
+        let prepared = make_prepared([ColumnType::Int, ColumnType::Text, ColumnType::Int], [2, 0]);
+        let values = (1i32, "text", 2i32);
+
+        let bound = prepared.bind(&values).unwrap();
+        assert_eq!(
+            bound.get_values(),
+            &prepared.serialize_values(&values).unwrap()
+        );
+        assert_eq!(
+            bound.get_routing_key(),
+            Some(&prepared.compute_partition_key(&values).unwrap())
+        );
+        assert_eq!(
+            bound.get_token(),
+            prepared.calculate_token(&values).unwrap()
+        );
+        assert!(prepared.bind(&(1i32, 2i32, 3i32)).is_err());
+
+        // Re-preparation with the same types of bind markers changes nothing
+        let mut reprepared =
+            make_meta([ColumnType::Int, ColumnType::Text, ColumnType::Int], [2, 0]);
+        reprepared.col_specs[1].name = "renamed".to_owned();
+        prepared.check_reprepared_metadata(&reprepared).unwrap();
+        assert!(!bound.is_outdated());
+
+        let reprepared = make_meta([ColumnType::Int, ColumnType::Int, ColumnType::Int], [2, 0]);
+        assert!(prepared.check_reprepared_metadata(&reprepared).is_err());
+        assert!(bound.is_outdated());
+        assert!(prepared.has_outdated_metadata());
+        // The statement stays outdated even if re-prepared again the old way
+        let reprepared = make_meta([ColumnType::Int, ColumnType::Text, ColumnType::Int], [2, 0]);
+        assert!(prepared.check_reprepared_metadata(&reprepared).is_err());
+    }
+
+    #[test]
+    fn routing_key_overrides_bound_partition_key() {
+        let mut prepared =
+            make_prepared([ColumnType::Int, ColumnType::Text, ColumnType::Int], [2, 0]);
+        let bound = prepared.serialize_values(&(1i32, "text", 2i32)).unwrap();
+        let bound_token = prepared.calculate_token(&(1i32, "text", 2i32)).unwrap();
+        // Whether the partition key is known, and the token
+        let routing = |prepared: &PreparedStatement| {
+            let (pk, token) = prepared.partition_key_and_routing_token(&bound).unwrap();
+            (pk.is_some(), token)
+        };
+        assert_eq!(routing(&prepared), (true, bound_token));
+
+        // The routing key is given in the order of the partition key
+        prepared.set_routing_key(&(2i32, 1i32)).unwrap();
+        assert_eq!(prepared.get_routing_token(), bound_token);
+        assert!(prepared.set_routing_key(&(2i32,)).is_err());
+
+        prepared.set_routing_key(&(3i32, 4i32)).unwrap();
+        let overridden = prepared.get_routing_token();
+        assert!(overridden.is_some());
+        assert_ne!(overridden, bound_token);
+        assert_eq!(routing(&prepared), (false, overridden));
+        assert_eq!(
+            prepared.calculate_token(&(1i32, "text", 2i32)).unwrap(),
+            overridden
+        );
+
+        // A raw key is encoded as a composite partition key
+        let raw_key = [
+            &[0, 4][..],
+            &3i32.to_be_bytes(),
+            &[0, 0, 4],
+            &4i32.to_be_bytes(),
+            &[0],
+        ]
+        .concat();
+        prepared.set_routing_token(None);
+        prepared.set_raw_routing_key(&raw_key);
+        assert_eq!(prepared.get_routing_token(), overridden);
+
+        // Clearing the override restores routing by the bound values
+        prepared.set_routing_token(None);
+        assert_eq!(routing(&prepared), (true, bound_token));
+    }
 }
@@ -1,8 +1,12 @@
-use super::StatementConfig;
+use super::{normalize, truncate_statement, StatementConfig};
 use crate::frame::types::{Consistency, SerialConsistency};
 use crate::history::HistoryListener;
 use crate::retry_policy::RetryPolicy;
+use crate::routing::Token;
+use crate::transport::errors::StatementInfo;
 use crate::transport::execution_profile::ExecutionProfileHandle;
+use crate::transport::partitioner::{Murmur3Partitioner, Partitioner, PartitionerHasher};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -15,25 +19,55 @@ pub struct Query {
 
     pub contents: String,
     page_size: Option<i32>,
+    routing_keyspace: Option<String>,
+    conditional: ConditionalText,
+}
+
+// Whether the text of the query may be conditional, computed when the query is created.
+// `contents` can be changed directly, so the text it was computed for is kept to detect it.
+#[derive(Clone)]
+struct ConditionalText {
+    text: Arc<str>,
+    is_conditional: bool,
+}
+
+impl ConditionalText {
+    fn new(text: &str) -> Self {
+        Self {
+            text: text.into(),
+            is_conditional: normalize::is_conditional(text),
+        }
+    }
 }
 
 impl Query {
     /// Creates a new `Query` from a CQL query string.
     pub fn new(query_text: impl Into<String>) -> Self {
+        let contents = query_text.into();
         Self {
-            contents: query_text.into(),
+            conditional: ConditionalText::new(&contents),
+            contents,
             page_size: None,
+            routing_keyspace: None,
             config: Default::default(),
         }
     }
 
     /// Returns self with page size set to the given value
+    ///
+    /// # Panics
+    ///
+    /// Panics if `page_size` is not positive.
     pub fn with_page_size(mut self, page_size: i32) -> Self {
-        self.page_size = Some(page_size);
+        self.set_page_size(page_size);
         self
     }
 
     /// Sets the page size for this CQL query.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `page_size` is not positive.
     pub fn set_page_size(&mut self, page_size: i32) {
         assert!(page_size > 0, "page size must be larger than 0");
         self.page_size = Some(page_size);
@@ -87,7 +121,7 @@ impl Query {
     }
 
     /// Enable or disable CQL Tracing for this statement
-    /// If enabled session.query() will return a QueryResult containing tracing_id
+    /// If enabled session.query_unpaged() will return a QueryResult containing tracing_id
     /// which can be used to query tracing information about the execution of this query
     pub fn set_tracing(&mut self, should_trace: bool) {
         self.config.tracing = should_trace;
@@ -111,6 +145,19 @@ impl Query {
         self.config.timestamp
     }
 
+    /// Sets the custom payload which is sent to the server together with this statement.
+    /// The custom payload is a map of opaque values, which can be interpreted
+    /// by server-side extensions (e.g. custom query handlers).
+    /// If None, no custom payload is sent.
+    pub fn set_custom_payload(&mut self, custom_payload: Option<HashMap<String, Vec<u8>>>) {
+        self.config.custom_payload = custom_payload;
+    }
+
+    /// Gets the custom payload which is sent to the server together with this statement.
+    pub fn get_custom_payload(&self) -> Option<&HashMap<String, Vec<u8>>> {
+        self.config.custom_payload.as_ref()
+    }
+
     /// Sets the client-side timeout for this statement.
     /// If not None, the driver will stop waiting for the request
     /// to finish after `timeout` passed.
@@ -146,6 +193,47 @@ impl Query {
         self.config.history_listener.take()
     }
 
+    /// Enables or disables collecting the history of attempts made to execute the query:
+    /// the nodes tried, errors, decisions of the retry policy and speculative executions.
+    ///
+    /// The history is attached to the result ([`QueryResult::execution_history`](crate::QueryResult::execution_history))
+    /// of [`Session::query_unpaged`](crate::Session::query_unpaged) and similar methods, and to the error
+    /// passed to request interceptors ([`ExecutionError::execution_history`](crate::transport::errors::ExecutionError::execution_history)), but not to pages
+    /// fetched by paging iterators. Unlike a [`HistoryListener`], it is collected separately
+    /// for each request. Disabled by default.
+    pub fn set_history_collection(&mut self, collect_history: bool) {
+        self.config.collect_history = collect_history;
+    }
+
+    /// Gets whether collecting the history of attempts is enabled for the query.
+    pub fn get_history_collection(&self) -> bool {
+        self.config.collect_history
+    }
+
+    /// Sets the label of the query, e.g. `get_user_by_id`.
+    ///
+    /// Requests of labelled statements are counted per label in
+    /// [`Metrics::statement_labels_snapshot`](crate::transport::metrics::Metrics::statement_labels_snapshot),
+    /// and the label is recorded in their tracing spans and slow query reports.
+    /// Statements prepared from the query inherit its label.
+    ///
+    /// Labels should name statements, not requests: metrics are kept for at most
+    /// [`MAX_STATEMENT_LABELS`](crate::transport::metrics::MAX_STATEMENT_LABELS) labels,
+    /// and requests with further labels are counted together.
+    pub fn set_label(&mut self, label: impl Into<Arc<str>>) {
+        self.config.label = Some(label.into());
+    }
+
+    /// Removes the label set by `set_label`.
+    pub fn remove_label(&mut self) {
+        self.config.label = None;
+    }
+
+    /// Gets the label set by `set_label`.
+    pub fn get_label(&self) -> Option<&str> {
+        self.config.label.as_deref()
+    }
+
     /// Associates the query with execution profile referred by the provided handle.
     /// Handle may be later remapped to another profile, and query will reflect those changes.
     pub fn set_execution_profile_handle(&mut self, profile_handle: Option<ExecutionProfileHandle>) {
@@ -156,6 +244,82 @@ impl Query {
     pub fn get_execution_profile_handle(&self) -> Option<&ExecutionProfileHandle> {
         self.config.execution_profile_handle.as_ref()
     }
+
+    /// Makes the driver route this query to the replicas owning the given token.
+    ///
+    /// Unprepared queries are routed to random nodes by default, as the driver
+    /// doesn't know which of the values form the partition key. Passing `None`
+    /// restores this behavior.
+    ///
+    /// The replicas are looked up in the [routing keyspace](Self::set_routing_keyspace).
+    pub fn set_routing_token(&mut self, token: Option<Token>) {
+        self.config.routing_token = token;
+    }
+
+    /// Gets the token set with [`set_routing_token`](Self::set_routing_token)
+    /// or [`set_raw_routing_key`](Self::set_raw_routing_key).
+    pub fn get_routing_token(&self) -> Option<Token> {
+        self.config.routing_token
+    }
+
+    /// Makes the driver route this query to the replicas owning the given partition key,
+    /// serialized as in the `routing_key` of the native protocol: the value of a single
+    /// partition key column as is, or for composite keys the value of each column
+    /// prefixed with its length as a 2-byte big-endian integer and followed by a zero byte.
+    ///
+    /// The token is computed with the default `Murmur3Partitioner`. For tables using
+    /// another partitioner, compute the token and use [`set_routing_token`](Self::set_routing_token).
+    pub fn set_raw_routing_key(&mut self, key: &[u8]) {
+        let mut hasher = Murmur3Partitioner.build_hasher();
+        hasher.write(key);
+        self.config.routing_token = Some(hasher.finish());
+    }
+
+    /// Sets the keyspace in which the replicas of the [routing token](Self::set_routing_token)
+    /// are looked up. If not set, the keyspace used by the session is assumed.
+    pub fn set_routing_keyspace(&mut self, keyspace: Option<String>) {
+        self.routing_keyspace = keyspace;
+    }
+
+    /// Gets the keyspace set with [`set_routing_keyspace`](Self::set_routing_keyspace).
+    pub fn get_routing_keyspace(&self) -> Option<&str> {
+        self.routing_keyspace.as_deref()
+    }
+
+    /// Returns the token and the keyspace to route the query with, if a token is set.
+    pub(crate) fn routing<'a>(
+        &'a self,
+        session_keyspace: Option<&'a str>,
+    ) -> (Option<Token>, Option<&'a str>) {
+        match self.config.routing_token {
+            Some(token) => (
+                Some(token),
+                self.routing_keyspace.as_deref().or(session_keyspace),
+            ),
+            None => (None, None),
+        }
+    }
+
+    /// Describes the query for errors, with the text truncated to `max_length` bytes.
+    pub(crate) fn statement_info(&self, max_length: usize) -> StatementInfo {
+        StatementInfo::Unprepared {
+            text: truncate_statement(&self.contents, max_length).to_owned(),
+        }
+    }
+
+    /// Whether the query may be conditional, so that serial consistency applies to it.
+    pub(crate) fn is_conditional(&self) -> bool {
+        if *self.conditional.text == self.contents {
+            self.conditional.is_conditional
+        } else {
+            normalize::is_conditional(&self.contents)
+        }
+    }
+
+    /// Number of positional bind markers in the query text, see [`normalize::count_bind_markers`].
+    pub(crate) fn bind_marker_count(&self) -> usize {
+        normalize::count_bind_markers(&self.contents)
+    }
 }
 
 impl From<String> for Query {
@@ -0,0 +1,761 @@
+//! Utilities for unit testing application code without a cluster.
+//!
+//! Requires the `testing` feature. [`MockSession`] implements [`SessionOps`], so it can be passed
+//! to code which is generic over [`SessionOps`] instead of a [`Session`](crate::Session).
+//! It's programmed with [`Expectation`]s, which match the executed statements and tell what
+//! to respond with, and it records the calls made together with the bound values,
+//! so that tests can assert what the code under test sent.
+//!
+//! Canned results are built with [`QueryResultBuilder`], returned by [`QueryResult::mock_builder`].
+//!
+//! # Example
+//! ```rust
+//! # use std::error::Error;
+//! # async fn check_only_compiles() -> Result<(), Box<dyn Error>> {
+//! use scylla::frame::response::result::{ColumnType, CqlValue};
+//! use scylla::testing::{Expectation, MockSession};
+//! use scylla::{QueryResult, SessionOps};
+//!
+//! // Code under test
+//! async fn user_name(session: &impl SessionOps, id: i32) -> Result<String, Box<dyn Error>> {
+//!     let result = session
+//!         .query("SELECT name FROM ks.users WHERE id = ?", (id,))
+//!         .await?;
+//!     let (name,) = result.single_row_typed::<(String,)>()?;
+//!     Ok(name)
+//! }
+//!
+//! let session = MockSession::new();
+//! session.expect(
+//!     Expectation::query("SELECT name FROM ks.users WHERE id = ?")
+//!         .bind_markers([("id", ColumnType::Int)])
+//!         .returning(
+//!             QueryResult::mock_builder()
+//!                 .column("name", ColumnType::Text)
+//!                 .row([CqlValue::Text("Alice".to_owned())])
+//!                 .build()?,
+//!         ),
+//! );
+//!
+//! assert_eq!(user_name(&session, 7).await?, "Alice");
+//! assert_eq!(session.calls()[0].values(), [Some(CqlValue::Int(7))]);
+//! session.verify();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use scylla_cql::frame::response::result::{
+    deser_cql_value, ColumnSpec, ColumnType, CqlValue, PreparedMetadata, ResultMetadata, TableSpec,
+};
+use scylla_cql::frame::types::RawValue;
+use scylla_cql::types::serialize::row::{
+    RowSerializationContext, SerializedValues, ValueCountMismatchError,
+};
+use scylla_cql::types::serialize::SerializationError;
+
+use crate::batch::{Batch, BatchStatement};
+use crate::prepared_statement::PreparedStatement;
+use crate::query::Query;
+use crate::serialize::batch::{BatchValues, BatchValuesIterator};
+use crate::serialize::row::SerializeRow;
+use crate::transport::connection::VerifiedKeyspaceName;
+use crate::transport::errors::{BadQuery, QueryError};
+use crate::transport::iterator::RowIterator;
+use crate::{QueryResult, SessionOps};
+
+pub use crate::transport::query_result::QueryResultBuilder;
+pub use crate::transport::runtime::TestRuntime;
+
+/// Kind of a request made to a [`MockSession`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RequestKind {
+    Query,
+    QueryIter,
+    Prepare,
+    Execute,
+    Batch,
+    UseKeyspace,
+}
+
+/// Decides whether an [`Expectation`] matches the text of a statement.
+#[derive(Clone)]
+pub struct StatementMatcher {
+    description: String,
+    predicate: Arc<dyn Fn(&str) -> bool + Send + Sync>,
+}
+
+impl StatementMatcher {
+    /// Matches the statement with exactly the given text.
+    pub fn exact(statement: impl Into<String>) -> Self {
+        let statement = statement.into();
+        Self {
+            description: format!("{:?}", statement),
+            predicate: Arc::new(move |contents| contents == statement),
+        }
+    }
+
+    /// Matches statements for which the predicate returns `true`,
+    /// e.g. ones with a given prefix, or matching a regular expression.
+    ///
+    /// # Example
+    /// ```rust
+    /// use scylla::testing::StatementMatcher;
+    ///
+    /// let matcher = StatementMatcher::matching(|statement| statement.starts_with("SELECT"));
+    /// ```
+    pub fn matching(predicate: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            description: "<predicate>".to_owned(),
+            predicate: Arc::new(predicate),
+        }
+    }
+
+    fn matches(&self, statement: &str) -> bool {
+        (self.predicate)(statement)
+    }
+}
+
+impl fmt::Debug for StatementMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.description)
+    }
+}
+
+impl From<&str> for StatementMatcher {
+    fn from(statement: &str) -> Self {
+        Self::exact(statement)
+    }
+}
+
+impl From<String> for StatementMatcher {
+    fn from(statement: String) -> Self {
+        Self::exact(statement)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum MockResponse {
+    Pages(Vec<QueryResult>),
+    Error(QueryError),
+}
+
+impl MockResponse {
+    fn into_result(self) -> Result<QueryResult, QueryError> {
+        match self {
+            MockResponse::Pages(pages) => Ok(pages.into_iter().next().unwrap_or_default()),
+            MockResponse::Error(error) => Err(error),
+        }
+    }
+}
+
+/// A request expected by a [`MockSession`], and the response to it.
+///
+/// Unless configured otherwise, the response is a result without rows.
+#[derive(Debug)]
+pub struct Expectation {
+    kinds: &'static [RequestKind],
+    matcher: StatementMatcher,
+    bind_markers: PreparedMetadata,
+    response: MockResponse,
+    times: Option<usize>,
+    calls: usize,
+}
+
+impl Expectation {
+    fn new(kinds: &'static [RequestKind], matcher: StatementMatcher) -> Self {
+        Self {
+            kinds,
+            matcher,
+            bind_markers: PreparedMetadata {
+                flags: 0,
+                col_count: 0,
+                pk_indexes: Vec::new(),
+                col_specs: Vec::new(),
+            },
+            response: MockResponse::Pages(Vec::new()),
+            times: None,
+            calls: 0,
+        }
+    }
+
+    /// Expects an unprepared statement, executed with `query` or `query_iter`.
+    pub fn query(matcher: impl Into<StatementMatcher>) -> Self {
+        Self::new(
+            &[RequestKind::Query, RequestKind::QueryIter],
+            matcher.into(),
+        )
+    }
+
+    /// Expects a statement to be prepared.
+    pub fn prepare(matcher: impl Into<StatementMatcher>) -> Self {
+        Self::new(&[RequestKind::Prepare], matcher.into())
+    }
+
+    /// Expects a prepared statement to be executed.
+    pub fn execute(matcher: impl Into<StatementMatcher>) -> Self {
+        Self::new(&[RequestKind::Execute], matcher.into())
+    }
+
+    /// Expects a batch, which matches if any of its statements matches.
+    pub fn batch(matcher: impl Into<StatementMatcher>) -> Self {
+        Self::new(&[RequestKind::Batch], matcher.into())
+    }
+
+    /// Sets the names and types of the bind markers of the statement.
+    ///
+    /// The values bound to unprepared statements are serialized with these types,
+    /// so that they can be recorded. Statements prepared by the mock have these bind markers,
+    /// and values bound to them when executed are serialized with their types.
+    pub fn bind_markers(
+        mut self,
+        bind_markers: impl IntoIterator<Item = (impl Into<String>, ColumnType)>,
+    ) -> Self {
+        let col_specs: Vec<ColumnSpec> = bind_markers
+            .into_iter()
+            .map(|(name, typ)| ColumnSpec {
+                table_spec: TableSpec {
+                    ks_name: String::new(),
+                    table_name: String::new(),
+                },
+                name: name.into(),
+                typ,
+            })
+            .collect();
+        self.bind_markers.col_count = col_specs.len();
+        self.bind_markers.col_specs = col_specs;
+        self
+    }
+
+    /// Responds with the given result. Statements prepared by the mock have the columns
+    /// of this result as their result metadata.
+    pub fn returning(self, result: QueryResult) -> Self {
+        self.returning_pages(vec![result])
+    }
+
+    /// Responds with the given pages to `query_iter`, and with the first one to other requests.
+    pub fn returning_pages(mut self, pages: Vec<QueryResult>) -> Self {
+        self.response = MockResponse::Pages(pages);
+        self
+    }
+
+    /// Responds with the given error.
+    pub fn returning_error(mut self, error: QueryError) -> Self {
+        self.response = MockResponse::Error(error);
+        self
+    }
+
+    /// Expects exactly `times` matching requests. Once they are made,
+    /// the expectation doesn't match anymore.
+    pub fn times(mut self, times: usize) -> Self {
+        self.times = Some(times);
+        self
+    }
+
+    fn matches(&self, kind: RequestKind, statements: &[&str]) -> bool {
+        self.kinds.contains(&kind)
+            && !matches!(self.times, Some(times) if self.calls >= times)
+            && statements
+                .iter()
+                .any(|statement| self.matcher.matches(statement))
+    }
+
+    fn is_satisfied(&self) -> bool {
+        match self.times {
+            Some(times) => self.calls == times,
+            None => self.calls > 0,
+        }
+    }
+
+    fn call(&mut self) -> MockResponse {
+        self.calls += 1;
+        self.response.clone()
+    }
+}
+
+/// A statement of a request made to a [`MockSession`], with the values bound to it.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct RecordedStatement {
+    /// Text of the statement. For `use_keyspace`, the name of the keyspace.
+    pub contents: String,
+    /// Bound values, deserialized with the types of the bind markers.
+    /// Nulls and unset values are `None`.
+    pub values: Vec<Option<CqlValue>>,
+}
+
+/// A request made to a [`MockSession`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct RecordedCall {
+    pub kind: RequestKind,
+    /// The statement of the request, or all statements of a batch.
+    pub statements: Vec<RecordedStatement>,
+}
+
+impl RecordedCall {
+    /// Returns the text of the statement, or of the first statement of a batch.
+    pub fn statement(&self) -> &str {
+        self.statements
+            .first()
+            .map_or("", |statement| statement.contents.as_str())
+    }
+
+    /// Returns the values bound to the statement, or to the first statement of a batch.
+    pub fn values(&self) -> &[Option<CqlValue>] {
+        self.statements
+            .first()
+            .map_or(&[], |statement| statement.values.as_slice())
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    expectations: Vec<Expectation>,
+    calls: Vec<RecordedCall>,
+    keyspace: Option<Arc<String>>,
+}
+
+impl MockState {
+    fn find_expectation(
+        &mut self,
+        kind: RequestKind,
+        statements: &[&str],
+    ) -> Result<&mut Expectation, QueryError> {
+        self.expectations
+            .iter_mut()
+            .find(|expectation| expectation.matches(kind, statements))
+            .ok_or_else(|| {
+                QueryError::BadQuery(BadQuery::Other(format!(
+                    "MockSession: unexpected {:?} of {:?}",
+                    kind, statements
+                )))
+            })
+    }
+}
+
+/// An implementation of [`SessionOps`] which doesn't connect to a cluster,
+/// but responds to requests as programmed with [`Expectation`]s.
+///
+/// A request is matched against the expectations in the order in which they were added,
+/// and the first one that matches gives the response. A request which doesn't match any
+/// expectation fails with [`BadQuery::Other`]. Requests which matched an expectation
+/// are recorded, see [`calls`](MockSession::calls).
+///
+/// Values bound to unprepared statements can only be serialized if the types
+/// of the bind markers are given with [`Expectation::bind_markers`].
+#[derive(Default)]
+pub struct MockSession {
+    state: Mutex<MockState>,
+}
+
+impl MockSession {
+    /// Creates a mock without expectations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an expectation, matched after the ones added before.
+    pub fn expect(&self, expectation: Expectation) -> &Self {
+        self.state.lock().unwrap().expectations.push(expectation);
+        self
+    }
+
+    /// Returns the requests which matched an expectation, in the order in which they were made.
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    /// Returns the keyspace set with `use_keyspace`.
+    pub fn get_keyspace(&self) -> Option<Arc<String>> {
+        self.state.lock().unwrap().keyspace.clone()
+    }
+
+    /// Panics if some expectation wasn't satisfied: it was matched a different number of times
+    /// than set with [`Expectation::times`], or wasn't matched at all if the number wasn't set.
+    pub fn verify(&self) {
+        let state = self.state.lock().unwrap();
+        let unsatisfied: Vec<String> = state
+            .expectations
+            .iter()
+            .filter(|expectation| !expectation.is_satisfied())
+            .map(|expectation| {
+                format!(
+                    "{:?} of {:?} expected {} times, matched {} times",
+                    expectation.kinds,
+                    expectation.matcher,
+                    expectation
+                        .times
+                        .map_or_else(|| "at least 1".to_owned(), |times| times.to_string()),
+                    expectation.calls
+                )
+            })
+            .collect();
+        assert!(
+            unsatisfied.is_empty(),
+            "MockSession: unsatisfied expectations:\n{}",
+            unsatisfied.join("\n")
+        );
+    }
+
+    // Matches a single statement, records the call and returns the response.
+    fn respond(
+        &self,
+        kind: RequestKind,
+        contents: &str,
+        prepared: Option<&PreparedMetadata>,
+        values: &impl SerializeRow,
+    ) -> Result<MockResponse, QueryError> {
+        let mut state = self.state.lock().unwrap();
+        let expectation = state.find_expectation(kind, &[contents])?;
+        let metadata = prepared.unwrap_or(&expectation.bind_markers);
+        let serialized = SerializedValues::from_serializable(
+            &RowSerializationContext::from_prepared(metadata),
+            values,
+        )?;
+        let values = decode_values(&metadata.col_specs, &serialized)?;
+        let response = expectation.call();
+        state.calls.push(RecordedCall {
+            kind,
+            statements: vec![RecordedStatement {
+                contents: contents.to_owned(),
+                values,
+            }],
+        });
+        Ok(response)
+    }
+}
+
+fn decode_values(
+    col_specs: &[ColumnSpec],
+    values: &SerializedValues,
+) -> Result<Vec<Option<CqlValue>>, QueryError> {
+    values
+        .iter()
+        .zip(col_specs)
+        .map(|(value, spec)| match value {
+            RawValue::Value(mut bytes) => Ok(Some(deser_cql_value(&spec.typ, &mut bytes)?)),
+            RawValue::Null | RawValue::Unset => Ok(None),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl SessionOps for MockSession {
+    async fn query(
+        &self,
+        query: impl Into<Query> + Send,
+        values: impl SerializeRow + Send + Sync,
+    ) -> Result<QueryResult, QueryError> {
+        let query: Query = query.into();
+        self.respond(RequestKind::Query, &query.contents, None, &values)
+            .and_then(MockResponse::into_result)
+    }
+
+    async fn execute(
+        &self,
+        prepared: &PreparedStatement,
+        values: impl SerializeRow + Send + Sync,
+    ) -> Result<QueryResult, QueryError> {
+        self.respond(
+            RequestKind::Execute,
+            prepared.get_statement(),
+            Some(prepared.get_prepared_metadata()),
+            &values,
+        )
+        .and_then(MockResponse::into_result)
+    }
+
+    async fn batch(
+        &self,
+        batch: &Batch,
+        values: impl BatchValues + Send + Sync,
+    ) -> Result<QueryResult, QueryError> {
+        let contents: Vec<&str> = batch
+            .statements
+            .iter()
+            .map(|statement| match statement {
+                BatchStatement::Query(query) => query.contents.as_str(),
+                BatchStatement::PreparedStatement(prepared) => prepared.get_statement(),
+            })
+            .collect();
+
+        let response = (|| {
+            let mut state = self.state.lock().unwrap();
+            let expectation = state.find_expectation(RequestKind::Batch, &contents)?;
+            let mut values_iter = values.batch_values_iter();
+            let mut statements = Vec::with_capacity(contents.len());
+            for (statement, contents) in batch.statements.iter().zip(&contents) {
+                let metadata = match statement {
+                    BatchStatement::Query(_) => &expectation.bind_markers,
+                    BatchStatement::PreparedStatement(prepared) => prepared.get_prepared_metadata(),
+                };
+                let ctx = RowSerializationContext::from_prepared(metadata);
+                let (serialized, ()) = SerializedValues::from_closure(|writer| {
+                    values_iter.serialize_next(&ctx, writer).unwrap_or(Ok(()))
+                })?;
+                if serialized.element_count() as usize != metadata.col_specs.len() {
+                    return Err(SerializationError::new(ValueCountMismatchError {
+                        expected: metadata.col_specs.len(),
+                        actual: serialized.element_count() as usize,
+                        column_names: Vec::new(),
+                    })
+                    .into());
+                }
+                statements.push(RecordedStatement {
+                    contents: (*contents).to_owned(),
+                    values: decode_values(&metadata.col_specs, &serialized)?,
+                });
+            }
+            let response = expectation.call();
+            state.calls.push(RecordedCall {
+                kind: RequestKind::Batch,
+                statements,
+            });
+            Ok(response)
+        })();
+
+        response.and_then(MockResponse::into_result)
+    }
+
+    async fn prepare(
+        &self,
+        query: impl Into<Query> + Send,
+    ) -> Result<PreparedStatement, QueryError> {
+        let query: Query = query.into();
+        let mut state = self.state.lock().unwrap();
+        let expectation = state.find_expectation(RequestKind::Prepare, &[&query.contents])?;
+        let metadata = expectation.bind_markers.clone();
+        let result_col_specs = match expectation.call() {
+            MockResponse::Pages(pages) => pages
+                .into_iter()
+                .next()
+                .map(|page| page.col_specs)
+                .unwrap_or_default(),
+            MockResponse::Error(error) => return Err(error),
+        };
+        state.calls.push(RecordedCall {
+            kind: RequestKind::Prepare,
+            statements: vec![RecordedStatement {
+                contents: query.contents.clone(),
+                values: Vec::new(),
+            }],
+        });
+        Ok(PreparedStatement::new(
+            Bytes::new(),
+            false,
+            metadata,
+            ResultMetadata::new(result_col_specs, None),
+            query.contents.clone(),
+            query.get_page_size(),
+            query.config.clone(),
+        ))
+    }
+
+    async fn query_iter(
+        &self,
+        query: impl Into<Query> + Send,
+        values: impl SerializeRow + Send + Sync,
+    ) -> Result<RowIterator, QueryError> {
+        let query: Query = query.into();
+        match self.respond(RequestKind::QueryIter, &query.contents, None, &values)? {
+            MockResponse::Pages(pages) => RowIterator::new_from_results(pages).await,
+            MockResponse::Error(error) => Err(error),
+        }
+    }
+
+    async fn use_keyspace(
+        &self,
+        keyspace_name: impl Into<String> + Send,
+        case_sensitive: bool,
+    ) -> Result<Option<Arc<String>>, QueryError> {
+        let keyspace_name = keyspace_name.into();
+        let verified_ks_name = VerifiedKeyspaceName::new(keyspace_name.clone(), case_sensitive)?;
+        let used_name = if case_sensitive {
+            verified_ks_name.as_str().to_owned()
+        } else {
+            verified_ks_name.as_str().to_lowercase()
+        };
+
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(RecordedCall {
+            kind: RequestKind::UseKeyspace,
+            statements: vec![RecordedStatement {
+                contents: keyspace_name,
+                values: Vec::new(),
+            }],
+        });
+        Ok(state.keyspace.replace(Arc::new(used_name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Expectation, MockSession, QueryResultBuilder, RequestKind, StatementMatcher};
+    use crate::batch::Batch;
+    use crate::frame::response::result::{ColumnType, CqlValue};
+    use crate::transport::errors::QueryError;
+    use crate::SessionOps;
+    use futures::TryStreamExt;
+
+    // Code under test, written against the trait
+    async fn save_user(
+        session: &impl SessionOps,
+        id: i32,
+        name: Option<&str>,
+    ) -> Result<(), QueryError> {
+        let prepared = session
+            .prepare("INSERT INTO ks.users (id, name) VALUES (?, ?)")
+            .await?;
+        session.execute(&prepared, (id, name)).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn records_statements_and_bound_values() {
+        let session = MockSession::new();
+        session
+            .expect(
+                Expectation::prepare("INSERT INTO ks.users (id, name) VALUES (?, ?)")
+                    .bind_markers([("id", ColumnType::Int), ("name", ColumnType::Text)])
+                    .times(1),
+            )
+            .expect(Expectation::execute(
+                "INSERT INTO ks.users (id, name) VALUES (?, ?)",
+            ));
+
+        save_user(&session, 1, Some("Alice")).await.unwrap();
+        // The statement is expected to be prepared only once
+        assert!(save_user(&session, 2, None).await.is_err());
+
+        let calls = session.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].kind, RequestKind::Prepare);
+        assert_eq!(calls[1].kind, RequestKind::Execute);
+        assert_eq!(
+            calls[1].statement(),
+            "INSERT INTO ks.users (id, name) VALUES (?, ?)"
+        );
+        assert_eq!(
+            calls[1].values(),
+            [
+                Some(CqlValue::Int(1)),
+                Some(CqlValue::Text("Alice".to_owned()))
+            ]
+        );
+        session.verify();
+    }
+
+    #[tokio::test]
+    async fn returns_canned_results_and_errors() {
+        let session = MockSession::new();
+        session
+            .expect(
+                Expectation::query(StatementMatcher::matching(|s| s.contains("ks.missing")))
+                    .returning_error(QueryError::RequestTimeout("canned".to_owned())),
+            )
+            .expect(
+                Expectation::query("SELECT id, name FROM ks.users").returning(
+                    QueryResultBuilder::new()
+                        .columns([("id", ColumnType::Int), ("name", ColumnType::Text)])
+                        .rows([
+                            vec![Some(CqlValue::Int(1)), Some(CqlValue::Text("a".to_owned()))],
+                            vec![Some(CqlValue::Int(2)), None],
+                        ])
+                        .build()
+                        .unwrap(),
+                ),
+            );
+
+        let rows = session
+            .query("SELECT id, name FROM ks.users", ())
+            .await
+            .unwrap()
+            .rows_typed::<(i32, Option<String>)>()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows, [(1, Some("a".to_owned())), (2, None)]);
+
+        let error = session
+            .query("SELECT * FROM ks.missing", ())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, QueryError::RequestTimeout(_)));
+
+        // Not expected at all
+        assert!(session.query("TRUNCATE ks.users", ()).await.is_err());
+        assert_eq!(session.calls().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn query_iter_returns_pages() {
+        let page = |values: [i32; 2]| {
+            QueryResultBuilder::new()
+                .columns([("v", ColumnType::Int)])
+                .rows(values.map(|v| [CqlValue::Int(v)]))
+                .build()
+                .unwrap()
+        };
+        let session = MockSession::new();
+        session.expect(
+            Expectation::query("SELECT v FROM ks.t WHERE p = ?")
+                .bind_markers([("p", ColumnType::BigInt)])
+                .returning_pages(vec![page([0, 1]), page([2, 3])]),
+        );
+
+        let rows: Vec<(i32,)> = session
+            .query_iter("SELECT v FROM ks.t WHERE p = ?", (5_i64,))
+            .await
+            .unwrap()
+            .into_typed::<(i32,)>()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(rows, [(0,), (1,), (2,), (3,)]);
+        assert_eq!(session.calls()[0].kind, RequestKind::QueryIter);
+        assert_eq!(session.calls()[0].values(), [Some(CqlValue::BigInt(5))]);
+    }
+
+    #[tokio::test]
+    async fn records_batches_and_keyspace() {
+        let session = MockSession::new();
+        session.expect(
+            Expectation::batch(StatementMatcher::matching(|s| s.starts_with("INSERT")))
+                .bind_markers([("v", ColumnType::Int)]),
+        );
+
+        let mut batch = Batch::default();
+        batch.append_statement("INSERT INTO ks.a (v) VALUES (?)");
+        batch.append_statement("INSERT INTO ks.b (v) VALUES (?)");
+        session.batch(&batch, ((1,), (2,))).await.unwrap();
+        // The values don't match the bind markers
+        assert!(session.batch(&batch, ((1,), ())).await.is_err());
+
+        let calls = session.calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].statements.len(), 2);
+        assert_eq!(
+            calls[0].statements[1].contents,
+            "INSERT INTO ks.b (v) VALUES (?)"
+        );
+        assert_eq!(calls[0].statements[1].values, [Some(CqlValue::Int(2))]);
+
+        assert_eq!(session.use_keyspace("Ks", false).await.unwrap(), None);
+        assert_eq!(session.get_keyspace().unwrap().as_str(), "ks");
+        assert!(session.use_keyspace("bad name", false).await.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "unsatisfied expectations")]
+    fn verify_panics_on_unmatched_expectations() {
+        let session = MockSession::new();
+        session.expect(Expectation::query("SELECT * FROM ks.t"));
+        session.verify();
+    }
+}
@@ -0,0 +1,984 @@
+//! Conversion of query results into [Apache Arrow](https://arrow.apache.org) record batches.
+//!
+//! Requires the `arrow` feature. Rows of a [`QueryResult`](crate::QueryResult) are converted with
+//! [`QueryResult::to_arrow`](crate::QueryResult::to_arrow), and rows fetched by a
+//! [`RowIterator`](crate::transport::iterator::RowIterator) with
+//! [`RowIterator::into_record_batches`](crate::transport::iterator::RowIterator::into_record_batches),
+//! which returns a record batch for each page.
+//!
+//! The arrays are built column by column, reading the values of the rows by reference.
+//! CQL types are mapped to Arrow types as follows:
+//!
+//! | CQL type                      | Arrow type                                  |
+//! |-------------------------------|---------------------------------------------|
+//! | `ascii`, `text`               | `Utf8`                                      |
+//! | `blob`                        | `Binary`                                    |
+//! | `boolean`                     | `Boolean`                                   |
+//! | `tinyint`                     | `Int8`                                      |
+//! | `smallint`                    | `Int16`                                     |
+//! | `int`                         | `Int32`                                     |
+//! | `bigint`, `counter`           | `Int64`                                     |
+//! | `float`                       | `Float32`                                   |
+//! | `double`                      | `Float64`                                   |
+//! | `timestamp`                   | `Timestamp(Millisecond, "UTC")`             |
+//! | `date`                        | `Date32`                                    |
+//! | `time`                        | `Time64(Nanosecond)`                        |
+//! | `duration`                    | `Interval(MonthDayNano)`                    |
+//! | `uuid`, `timeuuid`            | `FixedSizeBinary(16)`                       |
+//! | `inet`                        | `Utf8`                                      |
+//! | `varint`                      | `Binary`, big-endian two's complement       |
+//! | `decimal`                     | `Decimal128(38, scale)`, see below          |
+//! | `list<T>`, `set<T>`           | `List<T>`                                   |
+//! | `map<K, V>`                   | `Map<K, V>`                                 |
+//! | user defined type             | `Struct` with the fields of the type        |
+//! | `tuple<...>`                  | `Struct` with fields named `0`, `1`, ...    |
+//!
+//! CQL decimals have a scale of their own, so a `decimal` column is converted to
+//! `Decimal128(38, scale)` with the largest scale of its values, if all of them fit
+//! in 38 digits with that scale. Otherwise the column is converted to `Utf8`,
+//! with the decimals formatted as strings, so that no value is rounded.
+//! The types of decimal columns in batches of a [`RecordBatchStream`] can differ for this reason.
+//!
+//! Nulls, including empty values, are preserved as Arrow nulls.
+//! Columns of custom types can't be converted.
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use arrow_array::builder::{BinaryBuilder, StringBuilder};
+use arrow_array::types::IntervalMonthDayNanoType;
+use arrow_array::{
+    Array, ArrayRef, BooleanArray, Date32Array, Decimal128Array, FixedSizeBinaryArray,
+    Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array, ListArray, MapArray,
+    PrimitiveArray, RecordBatch, RecordBatchOptions, StructArray, Time64NanosecondArray,
+    TimestampMillisecondArray,
+};
+use arrow_buffer::{IntervalMonthDayNano, NullBuffer, OffsetBuffer};
+use arrow_schema::{ArrowError, Field, Fields, Schema};
+use bigdecimal::BigDecimal;
+use futures::Stream;
+use num_bigint::BigInt;
+use thiserror::Error;
+
+use crate::frame::response::result::{ColumnSpec, ColumnType, CqlValue, Row};
+use crate::transport::errors::QueryError;
+use crate::transport::iterator::RowIterator;
+use crate::transport::query_result::RowsExpectedError;
+
+/// Maximal precision of `Decimal128`.
+const DECIMAL128_MAX_PRECISION: u8 = 38;
+
+/// An error of converting rows into a record batch.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ArrowConversionError {
+    #[error(transparent)]
+    RowsExpected(#[from] RowsExpectedError),
+
+    #[error("Column {column} of type {typ:?} can't be converted to Arrow")]
+    UnsupportedType { column: String, typ: ColumnType },
+
+    #[error("Value of column {column} doesn't match its type {typ:?}: {value:?}")]
+    ValueTypeMismatch {
+        column: String,
+        typ: ColumnType,
+        value: Box<CqlValue>,
+    },
+
+    #[error("Row has {actual} columns, but {expected} were expected")]
+    ColumnCountMismatch { expected: usize, actual: usize },
+
+    #[error("Failed to build Arrow array of column {column}: {error}")]
+    Arrow { column: String, error: ArrowError },
+
+    #[error("Failed to fetch a page of rows: {0}")]
+    Query(#[from] QueryError),
+}
+
+/// Converts rows described by `col_specs` into a record batch.
+pub fn rows_to_record_batch(
+    col_specs: &[ColumnSpec],
+    rows: &[Row],
+) -> Result<RecordBatch, ArrowConversionError> {
+    for row in rows {
+        if row.columns.len() != col_specs.len() {
+            return Err(ArrowConversionError::ColumnCountMismatch {
+                expected: col_specs.len(),
+                actual: row.columns.len(),
+            });
+        }
+    }
+
+    let mut fields = Vec::with_capacity(col_specs.len());
+    let mut columns = Vec::with_capacity(col_specs.len());
+    for (idx, spec) in col_specs.iter().enumerate() {
+        let values: Vec<Option<&CqlValue>> =
+            rows.iter().map(|row| row.columns[idx].as_ref()).collect();
+        let array = ColumnConverter { column: &spec.name }.convert(&spec.typ, &values)?;
+        fields.push(Field::new(&spec.name, array.data_type().clone(), true));
+        columns.push(array);
+    }
+
+    // The row count has to be given explicitly for results without columns
+    let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+    RecordBatch::try_new_with_options(Arc::new(Schema::new(fields)), columns, &options).map_err(
+        |error| ArrowConversionError::Arrow {
+            column: String::new(),
+            error,
+        },
+    )
+}
+
+/// Stream of record batches, one for each page of rows fetched by a [`RowIterator`],
+/// returned by [`RowIterator::into_record_batches`].
+/// Empty pages are skipped.
+pub struct RecordBatchStream {
+    row_iterator: RowIterator,
+}
+
+impl RecordBatchStream {
+    pub(crate) fn new(row_iterator: RowIterator) -> Self {
+        Self { row_iterator }
+    }
+
+    /// Returns the underlying row iterator, e.g. to inspect its tracing ids and warnings.
+    pub fn row_iterator(&self) -> &RowIterator {
+        &self.row_iterator
+    }
+}
+
+impl Stream for RecordBatchStream {
+    type Item = Result<RecordBatch, ArrowConversionError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let row_iterator = &mut self.row_iterator;
+        let rows = match row_iterator.poll_next_rows(cx) {
+            Poll::Ready(Some(Ok(rows))) => rows,
+            Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err.into()))),
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        };
+        Poll::Ready(Some(rows_to_record_batch(
+            row_iterator.get_column_specs(),
+            &rows,
+        )))
+    }
+}
+
+/// Builds arrays of values of a single column, possibly nested in collections.
+struct ColumnConverter<'a> {
+    column: &'a str,
+}
+
+macro_rules! primitive_array {
+    ($self:ident, $typ:ident, $values:ident, $array:ty, $($pattern:pat => $value:expr),+) => {{
+        let values = $values
+            .iter()
+            .map(|value| match value {
+                None => Ok(None),
+                $(Some($pattern) => Ok(Some($value)),)+
+                Some(other) => Err($self.mismatch($typ, other)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        <$array>::from(values)
+    }};
+}
+
+impl ColumnConverter<'_> {
+    fn convert(
+        &self,
+        typ: &ColumnType,
+        values: &[Option<&CqlValue>],
+    ) -> Result<ArrayRef, ArrowConversionError> {
+        // Empty values are nulls for Arrow
+        let values: Vec<Option<&CqlValue>> = values
+            .iter()
+            .map(|value| value.filter(|value| !matches!(value, CqlValue::Empty)))
+            .collect();
+
+        let array: ArrayRef = match typ {
+            ColumnType::Custom(_) | ColumnType::Unknown { .. } => {
+                return Err(ArrowConversionError::UnsupportedType {
+                    column: self.column.to_owned(),
+                    typ: typ.clone(),
+                })
+            }
+            ColumnType::Ascii | ColumnType::Text => {
+                let mut builder = StringBuilder::with_capacity(values.len(), 0);
+                for value in &values {
+                    match value {
+                        None => builder.append_null(),
+                        Some(CqlValue::Ascii(s) | CqlValue::Text(s)) => builder.append_value(s),
+                        Some(other) => return Err(self.mismatch(typ, other)),
+                    }
+                }
+                Arc::new(builder.finish())
+            }
+            ColumnType::Inet => {
+                let mut builder = StringBuilder::with_capacity(values.len(), 0);
+                for value in &values {
+                    match value {
+                        None => builder.append_null(),
+                        Some(CqlValue::Inet(addr)) => builder.append_value(addr.to_string()),
+                        Some(other) => return Err(self.mismatch(typ, other)),
+                    }
+                }
+                Arc::new(builder.finish())
+            }
+            ColumnType::Blob => {
+                let mut builder = BinaryBuilder::with_capacity(values.len(), 0);
+                for value in &values {
+                    match value {
+                        None => builder.append_null(),
+                        Some(CqlValue::Blob(blob)) => builder.append_value(blob),
+                        Some(CqlValue::SharedBlob(blob)) => builder.append_value(blob),
+                        Some(other) => return Err(self.mismatch(typ, other)),
+                    }
+                }
+                Arc::new(builder.finish())
+            }
+            ColumnType::Varint => {
+                let mut builder = BinaryBuilder::with_capacity(values.len(), 0);
+                for value in &values {
+                    match value {
+                        None => builder.append_null(),
+                        Some(CqlValue::Varint(varint)) => {
+                            builder.append_value(varint.to_signed_bytes_be())
+                        }
+                        Some(other) => return Err(self.mismatch(typ, other)),
+                    }
+                }
+                Arc::new(builder.finish())
+            }
+            ColumnType::Boolean => Arc::new(primitive_array!(
+                self, typ, values, BooleanArray,
+                CqlValue::Boolean(v) => *v
+            )),
+            ColumnType::TinyInt => Arc::new(primitive_array!(
+                self, typ, values, Int8Array,
+                CqlValue::TinyInt(v) => *v
+            )),
+            ColumnType::SmallInt => Arc::new(primitive_array!(
+                self, typ, values, Int16Array,
+                CqlValue::SmallInt(v) => *v
+            )),
+            ColumnType::Int => Arc::new(primitive_array!(
+                self, typ, values, Int32Array,
+                CqlValue::Int(v) => *v
+            )),
+            ColumnType::BigInt | ColumnType::Counter => Arc::new(primitive_array!(
+                self, typ, values, Int64Array,
+                CqlValue::BigInt(v) => *v,
+                CqlValue::Counter(v) => v.0
+            )),
+            ColumnType::Float => Arc::new(primitive_array!(
+                self, typ, values, Float32Array,
+                CqlValue::Float(v) => *v
+            )),
+            ColumnType::Double => Arc::new(primitive_array!(
+                self, typ, values, Float64Array,
+                CqlValue::Double(v) => *v
+            )),
+            ColumnType::Timestamp => Arc::new(
+                primitive_array!(
+                    self, typ, values, TimestampMillisecondArray,
+                    CqlValue::Timestamp(timestamp) => timestamp.0
+                )
+                .with_timezone("UTC"),
+            ),
+            ColumnType::Date => Arc::new(primitive_array!(
+                self, typ, values, Date32Array,
+                // CQL dates are shifted by 2^31, so that the epoch is in the middle of the range
+                CqlValue::Date(date) => (date.0 as i64 - (1 << 31)) as i32
+            )),
+            ColumnType::Time => Arc::new(primitive_array!(
+                self, typ, values, Time64NanosecondArray,
+                CqlValue::Time(time) => time.0
+            )),
+            ColumnType::Duration => Arc::new(primitive_array!(
+                self, typ, values, PrimitiveArray<IntervalMonthDayNanoType>,
+                CqlValue::Duration(d) => IntervalMonthDayNano::new(d.months, d.days, d.nanoseconds)
+            )),
+            ColumnType::Uuid | ColumnType::Timeuuid => {
+                let uuids = values
+                    .iter()
+                    .map(|value| match value {
+                        None => Ok(None),
+                        Some(CqlValue::Uuid(uuid) | CqlValue::Timeuuid(uuid)) => {
+                            Ok(Some(*uuid.as_bytes()))
+                        }
+                        Some(other) => Err(self.mismatch(typ, other)),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Arc::new(
+                    FixedSizeBinaryArray::try_from_sparse_iter_with_size(uuids.into_iter(), 16)
+                        .map_err(|error| self.arrow_error(error))?,
+                )
+            }
+            ColumnType::Decimal => self.convert_decimals(typ, &values)?,
+            ColumnType::List(elem_type) | ColumnType::Set(elem_type) => {
+                let mut lengths = Vec::with_capacity(values.len());
+                let mut elements = Vec::new();
+                for value in &values {
+                    match value {
+                        None => lengths.push(0),
+                        Some(CqlValue::List(list) | CqlValue::Set(list)) => {
+                            lengths.push(list.len());
+                            elements.extend(list.iter().map(Some));
+                        }
+                        Some(other) => return Err(self.mismatch(typ, other)),
+                    }
+                }
+                let elements = self.convert(elem_type, &elements)?;
+                let field = Field::new("item", elements.data_type().clone(), true);
+                Arc::new(
+                    ListArray::try_new(
+                        Arc::new(field),
+                        OffsetBuffer::from_lengths(lengths),
+                        elements,
+                        nulls(&values),
+                    )
+                    .map_err(|error| self.arrow_error(error))?,
+                )
+            }
+            ColumnType::Map(key_type, value_type) => {
+                let mut lengths = Vec::with_capacity(values.len());
+                let mut keys = Vec::new();
+                let mut map_values = Vec::new();
+                for value in &values {
+                    match value {
+                        None => lengths.push(0),
+                        Some(CqlValue::Map(map)) => {
+                            lengths.push(map.len());
+                            for (key, value) in map {
+                                keys.push(Some(key));
+                                map_values.push(Some(value));
+                            }
+                        }
+                        Some(other) => return Err(self.mismatch(typ, other)),
+                    }
+                }
+                let keys = self.convert(key_type, &keys)?;
+                let map_values = self.convert(value_type, &map_values)?;
+                let entry_fields = Fields::from(vec![
+                    Field::new("keys", keys.data_type().clone(), false),
+                    Field::new("values", map_values.data_type().clone(), true),
+                ]);
+                let entries = StructArray::try_new(entry_fields, vec![keys, map_values], None)
+                    .map_err(|error| self.arrow_error(error))?;
+                let field = Field::new("entries", entries.data_type().clone(), false);
+                Arc::new(
+                    MapArray::try_new(
+                        Arc::new(field),
+                        OffsetBuffer::from_lengths(lengths),
+                        entries,
+                        nulls(&values),
+                        false,
+                    )
+                    .map_err(|error| self.arrow_error(error))?,
+                )
+            }
+            ColumnType::UserDefinedType { field_types, .. } => {
+                let mut fields = Vec::with_capacity(field_types.len());
+                let mut arrays = Vec::with_capacity(field_types.len());
+                for (field_name, field_type) in field_types.iter() {
+                    let field_values = values
+                        .iter()
+                        .map(|value| match value {
+                            None => Ok(None),
+                            // Fields are matched by name, missing ones are null
+                            Some(CqlValue::UserDefinedType { fields, .. }) => Ok(fields
+                                .iter()
+                                .find(|(name, _)| name == field_name)
+                                .and_then(|(_, value)| value.as_ref())),
+                            Some(other) => Err(self.mismatch(typ, other)),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let array = self.convert(field_type, &field_values)?;
+                    fields.push(Field::new(field_name, array.data_type().clone(), true));
+                    arrays.push(array);
+                }
+                self.struct_array(fields, arrays, &values)?
+            }
+            ColumnType::Tuple(elem_types) => {
+                let mut fields = Vec::with_capacity(elem_types.len());
+                let mut arrays = Vec::with_capacity(elem_types.len());
+                for (idx, elem_type) in elem_types.iter().enumerate() {
+                    let elem_values = values
+                        .iter()
+                        .map(|value| match value {
+                            None => Ok(None),
+                            Some(CqlValue::Tuple(elems)) => {
+                                Ok(elems.get(idx).and_then(|elem| elem.as_ref()))
+                            }
+                            Some(other) => Err(self.mismatch(typ, other)),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let array = self.convert(elem_type, &elem_values)?;
+                    fields.push(Field::new(idx.to_string(), array.data_type().clone(), true));
+                    arrays.push(array);
+                }
+                self.struct_array(fields, arrays, &values)?
+            }
+        };
+        Ok(array)
+    }
+
+    /// Converts decimals to `Decimal128` with the largest scale of the values,
+    /// or to strings if any of them doesn't fit with that scale.
+    fn convert_decimals(
+        &self,
+        typ: &ColumnType,
+        values: &[Option<&CqlValue>],
+    ) -> Result<ArrayRef, ArrowConversionError> {
+        let decimals = values
+            .iter()
+            .map(|value| match value {
+                None => Ok(None),
+                Some(CqlValue::Decimal(decimal)) => Ok(Some(decimal)),
+                Some(other) => Err(self.mismatch(typ, other)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(array) = decimals_to_decimal128(&decimals) {
+            return Ok(Arc::new(array));
+        }
+        let mut builder = StringBuilder::with_capacity(decimals.len(), 0);
+        for decimal in decimals {
+            builder.append_option(decimal.map(BigDecimal::to_string));
+        }
+        Ok(Arc::new(builder.finish()))
+    }
+
+    fn struct_array(
+        &self,
+        fields: Vec<Field>,
+        arrays: Vec<ArrayRef>,
+        values: &[Option<&CqlValue>],
+    ) -> Result<ArrayRef, ArrowConversionError> {
+        let nulls = nulls(values);
+        if fields.is_empty() {
+            return Ok(Arc::new(StructArray::new_empty_fields(values.len(), nulls)));
+        }
+        StructArray::try_new(Fields::from(fields), arrays, nulls)
+            .map(|array| Arc::new(array) as ArrayRef)
+            .map_err(|error| self.arrow_error(error))
+    }
+
+    fn mismatch(&self, typ: &ColumnType, value: &CqlValue) -> ArrowConversionError {
+        ArrowConversionError::ValueTypeMismatch {
+            column: self.column.to_owned(),
+            typ: typ.clone(),
+            value: Box::new(value.clone()),
+        }
+    }
+
+    fn arrow_error(&self, error: ArrowError) -> ArrowConversionError {
+        ArrowConversionError::Arrow {
+            column: self.column.to_owned(),
+            error,
+        }
+    }
+}
+
+fn nulls(values: &[Option<&CqlValue>]) -> Option<NullBuffer> {
+    let validity: Vec<bool> = values.iter().map(Option::is_some).collect();
+    let nulls = NullBuffer::from(validity);
+    (nulls.null_count() > 0).then_some(nulls)
+}
+
+fn decimals_to_decimal128(decimals: &[Option<&BigDecimal>]) -> Option<Decimal128Array> {
+    let parts: Vec<Option<(BigInt, i64)>> = decimals
+        .iter()
+        .map(|decimal| decimal.map(BigDecimal::as_bigint_and_exponent))
+        .collect();
+    let scale = parts
+        .iter()
+        .flatten()
+        .map(|(_, scale)| *scale)
+        .max()
+        .unwrap_or(0);
+    let max_scale = DECIMAL128_MAX_PRECISION as i64;
+    if !(-max_scale..=max_scale).contains(&scale) {
+        return None;
+    }
+    let limit = 10_i128.pow(DECIMAL128_MAX_PRECISION as u32);
+
+    let mut unscaled_values = Vec::with_capacity(parts.len());
+    for part in parts {
+        let unscaled = match part {
+            None => None,
+            Some((unscaled, value_scale)) => {
+                let unscaled = bigint_to_i128(&unscaled)?;
+                let factor = 10_i128.checked_pow(u32::try_from(scale - value_scale).ok()?)?;
+                let rescaled = unscaled.checked_mul(factor)?;
+                if rescaled <= -limit || rescaled >= limit {
+                    return None;
+                }
+                Some(rescaled)
+            }
+        };
+        unscaled_values.push(unscaled);
+    }
+    Decimal128Array::from(unscaled_values)
+        .with_precision_and_scale(DECIMAL128_MAX_PRECISION, scale as i8)
+        .ok()
+}
+
+fn bigint_to_i128(value: &BigInt) -> Option<i128> {
+    let bytes = value.to_signed_bytes_be();
+    if bytes.len() > 16 {
+        return None;
+    }
+    // Sign extension of the big-endian two's complement representation
+    let fill = if matches!(bytes.first(), Some(byte) if byte & 0x80 != 0) {
+        0xff
+    } else {
+        0
+    };
+    let mut buf = [fill; 16];
+    buf[16 - bytes.len()..].copy_from_slice(&bytes);
+    Some(i128::from_be_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rows_to_record_batch, ArrowConversionError};
+    use crate::frame::response::result::{self, ColumnSpec, ColumnType, CqlValue, Row, TableSpec};
+    use crate::frame::value::{Counter, CqlDate, CqlDuration, CqlTime, CqlTimestamp};
+    use crate::QueryResult;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{
+        Date32Type, Decimal128Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type,
+        Int8Type, IntervalMonthDayNanoType, Time64NanosecondType, TimestampMillisecondType,
+    };
+    use arrow_array::{Array, RecordBatch};
+    use arrow_buffer::IntervalMonthDayNano;
+    use arrow_schema::{DataType, IntervalUnit, TimeUnit};
+    use bigdecimal::BigDecimal;
+    use num_bigint::BigInt;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::str::FromStr;
+    use uuid::Uuid;
+
+    fn spec(name: &str, typ: ColumnType) -> ColumnSpec {
+        ColumnSpec {
+            table_spec: TableSpec {
+                ks_name: "ks".to_owned(),
+                table_name: "t".to_owned(),
+            },
+            name: name.to_owned(),
+            typ,
+        }
+    }
+
+    // Converts a single column with the given values
+    fn convert(typ: ColumnType, values: Vec<Option<CqlValue>>) -> RecordBatch {
+        let rows: Vec<Row> = values
+            .into_iter()
+            .map(|value| Row {
+                columns: vec![value],
+            })
+            .collect();
+        rows_to_record_batch(&[spec("c", typ)], &rows).unwrap()
+    }
+
+    #[test]
+    fn text_types() {
+        for (typ, value) in [
+            (ColumnType::Text, CqlValue::Text("abc".to_owned())),
+            (ColumnType::Ascii, CqlValue::Ascii("abc".to_owned())),
+        ] {
+            let batch = convert(typ, vec![Some(value), None]);
+            assert_eq!(batch.schema().field(0).data_type(), &DataType::Utf8);
+            let column = batch.column(0).as_string::<i32>();
+            assert_eq!(column.value(0), "abc");
+            assert!(column.is_null(1));
+        }
+    }
+
+    #[test]
+    fn binary_types() {
+        let batch = convert(
+            ColumnType::Blob,
+            vec![
+                Some(CqlValue::Blob(vec![1, 2])),
+                Some(CqlValue::SharedBlob(vec![3].into())),
+                None,
+            ],
+        );
+        assert_eq!(batch.schema().field(0).data_type(), &DataType::Binary);
+        let column = batch.column(0).as_binary::<i32>();
+        assert_eq!(column.value(0), [1, 2]);
+        assert_eq!(column.value(1), [3]);
+        assert!(column.is_null(2));
+
+        let batch = convert(
+            ColumnType::Varint,
+            vec![
+                Some(CqlValue::Varint(BigInt::from(-129))),
+                Some(CqlValue::Varint(BigInt::from(256))),
+            ],
+        );
+        assert_eq!(batch.schema().field(0).data_type(), &DataType::Binary);
+        let column = batch.column(0).as_binary::<i32>();
+        assert_eq!(column.value(0), [0xff, 0x7f]);
+        assert_eq!(column.value(1), [0x01, 0x00]);
+    }
+
+    #[test]
+    fn numeric_types() {
+        let batch = convert(
+            ColumnType::Boolean,
+            vec![Some(CqlValue::Boolean(true)), None],
+        );
+        assert_eq!(batch.schema().field(0).data_type(), &DataType::Boolean);
+        assert!(batch.column(0).as_boolean().value(0));
+        assert!(batch.column(0).is_null(1));
+
+        let batch = convert(ColumnType::TinyInt, vec![Some(CqlValue::TinyInt(-1))]);
+        assert_eq!(batch.column(0).as_primitive::<Int8Type>().value(0), -1);
+
+        let batch = convert(ColumnType::SmallInt, vec![Some(CqlValue::SmallInt(-2))]);
+        assert_eq!(batch.column(0).as_primitive::<Int16Type>().value(0), -2);
+
+        let batch = convert(ColumnType::Int, vec![Some(CqlValue::Int(3)), None]);
+        let column = batch.column(0).as_primitive::<Int32Type>();
+        assert_eq!(column.value(0), 3);
+        assert!(column.is_null(1));
+
+        let batch = convert(ColumnType::BigInt, vec![Some(CqlValue::BigInt(i64::MAX))]);
+        assert_eq!(
+            batch.column(0).as_primitive::<Int64Type>().value(0),
+            i64::MAX
+        );
+
+        let batch = convert(
+            ColumnType::Counter,
+            vec![Some(CqlValue::Counter(Counter(7)))],
+        );
+        assert_eq!(batch.column(0).as_primitive::<Int64Type>().value(0), 7);
+
+        let batch = convert(ColumnType::Float, vec![Some(CqlValue::Float(1.5))]);
+        assert_eq!(batch.column(0).as_primitive::<Float32Type>().value(0), 1.5);
+
+        let batch = convert(ColumnType::Double, vec![Some(CqlValue::Double(-2.5))]);
+        assert_eq!(batch.column(0).as_primitive::<Float64Type>().value(0), -2.5);
+    }
+
+    #[test]
+    fn time_types() {
+        let batch = convert(
+            ColumnType::Timestamp,
+            vec![Some(CqlValue::Timestamp(CqlTimestamp(-1000))), None],
+        );
+        assert_eq!(
+            batch.schema().field(0).data_type(),
+            &DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+        );
+        let column = batch.column(0).as_primitive::<TimestampMillisecondType>();
+        assert_eq!(column.value(0), -1000);
+        assert!(column.is_null(1));
+
+        let batch = convert(
+            ColumnType::Date,
+            vec![
+                Some(CqlValue::Date(CqlDate(1 << 31))),
+                Some(CqlValue::Date(CqlDate(0))),
+                Some(CqlValue::Date(CqlDate(u32::MAX))),
+            ],
+        );
+        assert_eq!(batch.schema().field(0).data_type(), &DataType::Date32);
+        let column = batch.column(0).as_primitive::<Date32Type>();
+        assert_eq!(column.values(), &[0, i32::MIN, i32::MAX]);
+
+        let batch = convert(ColumnType::Time, vec![Some(CqlValue::Time(CqlTime(5_000)))]);
+        assert_eq!(
+            batch.schema().field(0).data_type(),
+            &DataType::Time64(TimeUnit::Nanosecond)
+        );
+        assert_eq!(
+            batch
+                .column(0)
+                .as_primitive::<Time64NanosecondType>()
+                .value(0),
+            5_000
+        );
+
+        let duration = CqlDuration {
+            months: 1,
+            days: -2,
+            nanoseconds: 3,
+        };
+        let batch = convert(
+            ColumnType::Duration,
+            vec![Some(CqlValue::Duration(duration))],
+        );
+        assert_eq!(
+            batch.schema().field(0).data_type(),
+            &DataType::Interval(IntervalUnit::MonthDayNano)
+        );
+        assert_eq!(
+            batch
+                .column(0)
+                .as_primitive::<IntervalMonthDayNanoType>()
+                .value(0),
+            IntervalMonthDayNano::new(1, -2, 3)
+        );
+    }
+
+    #[test]
+    fn uuid_and_inet_types() {
+        let uuid = Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+        for typ in [ColumnType::Uuid, ColumnType::Timeuuid] {
+            let value = match typ {
+                ColumnType::Uuid => CqlValue::Uuid(uuid),
+                _ => CqlValue::Timeuuid(uuid),
+            };
+            let batch = convert(typ, vec![Some(value), None]);
+            assert_eq!(
+                batch.schema().field(0).data_type(),
+                &DataType::FixedSizeBinary(16)
+            );
+            let column = batch.column(0).as_fixed_size_binary();
+            assert_eq!(column.value(0), uuid.as_bytes());
+            assert!(column.is_null(1));
+        }
+
+        let batch = convert(
+            ColumnType::Inet,
+            vec![Some(CqlValue::Inet(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))))],
+        );
+        assert_eq!(batch.schema().field(0).data_type(), &DataType::Utf8);
+        assert_eq!(batch.column(0).as_string::<i32>().value(0), "10.0.0.1");
+    }
+
+    #[test]
+    fn decimals_fitting_decimal128() {
+        let decimal = |s: &str| Some(CqlValue::Decimal(BigDecimal::from_str(s).unwrap()));
+        let batch = convert(
+            ColumnType::Decimal,
+            vec![decimal("1.5"), decimal("-0.125"), decimal("12"), None],
+        );
+        assert_eq!(
+            batch.schema().field(0).data_type(),
+            &DataType::Decimal128(38, 3)
+        );
+        let column = batch.column(0).as_primitive::<Decimal128Type>();
+        assert_eq!(&column.values()[..3], &[1500, -125, 12000]);
+        assert!(column.is_null(3));
+    }
+
+    #[test]
+    fn decimals_not_fitting_decimal128_are_strings() {
+        let decimal = |s: &str| Some(CqlValue::Decimal(BigDecimal::from_str(s).unwrap()));
+        let batch = convert(
+            ColumnType::Decimal,
+            vec![
+                decimal("123456789012345678901234567890"),
+                decimal("0.123456789012"),
+                None,
+            ],
+        );
+        assert_eq!(batch.schema().field(0).data_type(), &DataType::Utf8);
+        let column = batch.column(0).as_string::<i32>();
+        assert_eq!(column.value(0), "123456789012345678901234567890");
+        assert_eq!(column.value(1), "0.123456789012");
+        assert!(column.is_null(2));
+    }
+
+    #[test]
+    fn collection_types() {
+        let batch = convert(
+            ColumnType::List(Box::new(ColumnType::Int)),
+            vec![
+                Some(CqlValue::List(vec![CqlValue::Int(1), CqlValue::Int(2)])),
+                None,
+                Some(CqlValue::List(vec![])),
+            ],
+        );
+        let column = batch.column(0).as_list::<i32>();
+        assert_eq!(column.value_type(), DataType::Int32);
+        assert_eq!(column.value_offsets(), &[0, 2, 2, 2]);
+        assert!(column.is_null(1));
+        assert!(!column.is_null(2));
+        assert_eq!(
+            column.values().as_primitive::<Int32Type>().values(),
+            &[1, 2]
+        );
+
+        let batch = convert(
+            ColumnType::Set(Box::new(ColumnType::Text)),
+            vec![Some(CqlValue::Set(vec![CqlValue::Text("a".to_owned())]))],
+        );
+        let column = batch.column(0).as_list::<i32>();
+        assert_eq!(column.value_type(), DataType::Utf8);
+
+        let batch = convert(
+            ColumnType::Map(Box::new(ColumnType::Text), Box::new(ColumnType::BigInt)),
+            vec![
+                Some(CqlValue::Map(vec![
+                    (CqlValue::Text("a".to_owned()), CqlValue::BigInt(1)),
+                    (CqlValue::Text("b".to_owned()), CqlValue::BigInt(2)),
+                ])),
+                None,
+            ],
+        );
+        let column = batch.column(0).as_map();
+        assert_eq!(column.value_offsets(), &[0, 2, 2]);
+        assert!(column.is_null(1));
+        assert_eq!(column.keys().as_string::<i32>().value(1), "b");
+        assert_eq!(column.values().as_primitive::<Int64Type>().value(1), 2);
+    }
+
+    #[test]
+    fn udt_and_tuple_types() {
+        let udt_type = ColumnType::UserDefinedType {
+            type_name: "address".to_owned(),
+            keyspace: "ks".to_owned(),
+            field_types: vec![
+                ("street".to_owned(), ColumnType::Text),
+                ("number".to_owned(), ColumnType::Int),
+            ]
+            .into(),
+        };
+        let batch = convert(
+            udt_type,
+            vec![
+                Some(CqlValue::UserDefinedType {
+                    keyspace: "ks".to_owned(),
+                    type_name: "address".to_owned(),
+                    // Missing fields are null
+                    fields: vec![("street".to_owned(), Some(CqlValue::Text("Main".to_owned())))],
+                }),
+                None,
+            ],
+        );
+        let column = batch.column(0).as_struct();
+        assert_eq!(column.column_names(), ["street", "number"]);
+        assert_eq!(column.column(0).as_string::<i32>().value(0), "Main");
+        assert!(column.column(1).is_null(0));
+        assert!(column.is_null(1));
+
+        let batch = convert(
+            ColumnType::Tuple(vec![ColumnType::Int, ColumnType::Text]),
+            vec![Some(CqlValue::Tuple(vec![Some(CqlValue::Int(1)), None]))],
+        );
+        let column = batch.column(0).as_struct();
+        assert_eq!(column.column_names(), ["0", "1"]);
+        assert_eq!(column.column(0).as_primitive::<Int32Type>().value(0), 1);
+        assert!(column.column(1).is_null(0));
+    }
+
+    #[test]
+    fn empty_values_are_nulls() {
+        let batch = convert(ColumnType::Int, vec![Some(CqlValue::Empty)]);
+        assert!(batch.column(0).is_null(0));
+    }
+
+    #[test]
+    fn unsupported_and_mismatched_values_are_rejected() {
+        let rows = vec![Row {
+            columns: vec![Some(CqlValue::Blob(vec![]))],
+        }];
+        assert!(matches!(
+            rows_to_record_batch(&[spec("c", ColumnType::Custom("x".to_owned()))], &rows),
+            Err(ArrowConversionError::UnsupportedType { .. })
+        ));
+        assert!(matches!(
+            rows_to_record_batch(&[spec("c", ColumnType::Int)], &rows),
+            Err(ArrowConversionError::ValueTypeMismatch { .. })
+        ));
+    }
+
+    fn append_string(buf: &mut Vec<u8>, s: &str) {
+        buf.extend_from_slice(&(s.len() as u16).to_be_bytes());
+        buf.extend_from_slice(s.as_bytes());
+    }
+
+    fn append_bytes(buf: &mut Vec<u8>, bytes: Option<&[u8]>) {
+        match bytes {
+            Some(bytes) => {
+                buf.extend_from_slice(&(bytes.len() as i32).to_be_bytes());
+                buf.extend_from_slice(bytes);
+            }
+            None => buf.extend_from_slice(&(-1_i32).to_be_bytes()),
+        }
+    }
+
+    #[test]
+    fn result_frame_round_trip() {
+        // RESULT body of kind Rows, with a global table spec and columns
+        // (id int, name text, tags list<int>)
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&0x0002_i32.to_be_bytes());
+        frame.extend_from_slice(&0x0001_i32.to_be_bytes());
+        frame.extend_from_slice(&3_i32.to_be_bytes());
+        append_string(&mut frame, "ks");
+        append_string(&mut frame, "t");
+        append_string(&mut frame, "id");
+        frame.extend_from_slice(&0x0009_u16.to_be_bytes());
+        append_string(&mut frame, "name");
+        frame.extend_from_slice(&0x000D_u16.to_be_bytes());
+        append_string(&mut frame, "tags");
+        frame.extend_from_slice(&0x0020_u16.to_be_bytes());
+        frame.extend_from_slice(&0x0009_u16.to_be_bytes());
+
+        let mut tags = Vec::new();
+        tags.extend_from_slice(&2_i32.to_be_bytes());
+        append_bytes(&mut tags, Some(&10_i32.to_be_bytes()));
+        append_bytes(&mut tags, Some(&20_i32.to_be_bytes()));
+
+        frame.extend_from_slice(&2_i32.to_be_bytes());
+        append_bytes(&mut frame, Some(&1_i32.to_be_bytes()));
+        append_bytes(&mut frame, Some(b"first"));
+        append_bytes(&mut frame, Some(&tags));
+        append_bytes(&mut frame, Some(&2_i32.to_be_bytes()));
+        append_bytes(&mut frame, None);
+        append_bytes(&mut frame, None);
+
+        let rows = match result::deserialize(&mut &frame[..]).unwrap() {
+            result::Result::Rows(rows) => rows,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        let query_result = QueryResult {
+            col_specs: rows.metadata.col_specs.clone(),
+            rows: Some(rows.rows),
+            ..Default::default()
+        };
+        let batch = query_result.to_arrow().unwrap();
+
+        assert_eq!(batch.num_rows(), 2);
+        let schema = batch.schema();
+        let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, ["id", "name", "tags"]);
+
+        let ids = batch.column(0).as_primitive::<Int32Type>();
+        assert_eq!(ids.values(), &[1, 2]);
+        let names = batch.column(1).as_string::<i32>();
+        assert_eq!(names.value(0), "first");
+        assert!(names.is_null(1));
+        let tags = batch.column(2).as_list::<i32>();
+        assert_eq!(
+            tags.value(0).as_primitive::<Int32Type>().values(),
+            &[10, 20]
+        );
+        assert!(tags.is_null(1));
+
+        // Converting the columns back gives the rows decoded from the frame
+        for (idx, row) in query_result.rows.unwrap().iter().enumerate() {
+            assert_eq!(row.columns[0], Some(CqlValue::Int(ids.value(idx))));
+            let name = (!names.is_null(idx)).then(|| CqlValue::Text(names.value(idx).to_owned()));
+            assert_eq!(row.columns[1], name);
+        }
+    }
+
+    #[test]
+    fn results_without_rows_are_rejected() {
+        assert!(matches!(
+            QueryResult::default().to_arrow(),
+            Err(ArrowConversionError::RowsExpected(_))
+        ));
+    }
+}
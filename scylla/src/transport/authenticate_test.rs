@@ -19,9 +19,12 @@ async fn authenticate_superuser() {
         .unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session.use_keyspace(ks, false).await.unwrap();
-    session.query("DROP TABLE IF EXISTS t;", &[]).await.unwrap();
+    session
+        .query_unpaged("DROP TABLE IF EXISTS t;", &[])
+        .await
+        .unwrap();
 
     println!("Ok.");
 }
@@ -74,9 +77,12 @@ async fn custom_authentication() {
         .unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session.use_keyspace(ks, false).await.unwrap();
-    session.query("DROP TABLE IF EXISTS t;", &[]).await.unwrap();
+    session
+        .query_unpaged("DROP TABLE IF EXISTS t;", &[])
+        .await
+        .unwrap();
 
     println!("Ok.");
 }
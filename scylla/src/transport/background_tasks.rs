@@ -0,0 +1,366 @@
+//! Monitoring of the tasks that the driver runs in the background.
+//!
+//! Besides the requests made by the user, the driver runs tasks that keep
+//! the cluster metadata and the connection pools up to date, route requests
+//! through connections and prefetch pages for iterators.
+//! [`Session::background_tasks_status`](crate::Session::background_tasks_status)
+//! returns a snapshot of the tasks which are currently running.
+//!
+//! With the `task-names` feature enabled and the crate built with `--cfg tokio_unstable`
+//! (needed by tokio to name tasks), [`TokioRuntime`](crate::transport::runtime::TokioRuntime)
+//! spawns the tasks with names like
+//! `scylla::pool_refiller 10.0.0.1:9042`, so they can be told apart in tools
+//! like [tokio-console](https://github.com/tokio-rs/console).
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::{AbortHandle, Abortable, RemoteHandle};
+use futures::FutureExt;
+use tracing::instrument::WithSubscriber;
+
+use super::runtime::{default_runtime, Runtime};
+
+/// The kind of a task run by the driver in the background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BackgroundTaskKind {
+    /// Keeps the cluster metadata up to date through the control connection
+    /// and reacts to the events sent by the cluster.
+    ClusterWorker,
+
+    /// Keeps the connection pool of a node filled.
+    PoolRefiller,
+
+    /// Sends the requests and receives the responses of a connection,
+    /// including the keepalive requests.
+    ConnectionRouter,
+
+    /// Switches the connections of a session to the keyspace requested
+    /// with [`Session::use_keyspace`](crate::Session::use_keyspace).
+    UseKeyspace,
+
+    /// Prepares the statements in use again, after a node came up
+    /// or a schema change affected them.
+    Reprepare,
+
+    /// Fetches the pages of a [`RowIterator`](crate::transport::iterator::RowIterator).
+    /// Unlike other tasks, it's tied to the iterator instead of the session.
+    IteratorWorker,
+}
+
+impl BackgroundTaskKind {
+    /// Returns the name of the kind, which is also a part of the names of the tasks.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackgroundTaskKind::ClusterWorker => "cluster_worker",
+            BackgroundTaskKind::PoolRefiller => "pool_refiller",
+            BackgroundTaskKind::ConnectionRouter => "connection_router",
+            BackgroundTaskKind::UseKeyspace => "use_keyspace",
+            BackgroundTaskKind::Reprepare => "reprepare",
+            BackgroundTaskKind::IteratorWorker => "iterator_worker",
+        }
+    }
+}
+
+/// A background task, as seen in a snapshot returned by
+/// [`BackgroundTasks::status`].
+#[derive(Debug, Clone)]
+pub struct BackgroundTaskStatus {
+    /// What the task does.
+    pub kind: BackgroundTaskKind,
+
+    /// The node which the task works with, if it's specific to one.
+    pub node: Option<SocketAddr>,
+
+    /// When the task was spawned.
+    pub started: Instant,
+
+    /// When the task was last active, e.g. received a frame or refilled a pool.
+    /// Tasks waiting for work only report being active when they wake up.
+    pub last_activity: Instant,
+}
+
+/// The background tasks of a session.
+///
+/// Obtained with [`Session::background_tasks`](crate::Session::background_tasks).
+/// It's cheap to clone and may outlive the session, e.g. to check that the tasks
+/// stopped after the session was dropped.
+#[derive(Clone, Debug)]
+pub struct BackgroundTasks {
+    registry: Arc<Registry>,
+}
+
+#[derive(Debug)]
+struct Registry {
+    runtime: Arc<dyn Runtime>,
+    // Activity timestamps are stored as offsets from this instant
+    epoch: Instant,
+    next_id: AtomicU64,
+    tasks: Mutex<HashMap<u64, TaskEntry>>,
+}
+
+#[derive(Debug)]
+struct TaskEntry {
+    kind: BackgroundTaskKind,
+    node: Option<SocketAddr>,
+    started: Instant,
+    last_activity: Arc<AtomicU64>,
+    // Present for the tasks which aren't cancelled by dropping a handle
+    abort_handle: Option<AbortHandle>,
+}
+
+impl Default for BackgroundTasks {
+    fn default() -> Self {
+        Self::new(default_runtime())
+    }
+}
+
+impl BackgroundTasks {
+    pub(crate) fn new(runtime: Arc<dyn Runtime>) -> Self {
+        BackgroundTasks {
+            registry: Arc::new(Registry {
+                runtime,
+                epoch: Instant::now(),
+                next_id: AtomicU64::new(0),
+                tasks: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// The runtime on which the tasks are spawned.
+    pub(crate) fn runtime(&self) -> &Arc<dyn Runtime> {
+        &self.registry.runtime
+    }
+
+    /// Returns a snapshot of the tasks which are running, in the order in which
+    /// they were spawned.
+    pub fn status(&self) -> Vec<BackgroundTaskStatus> {
+        let epoch = self.registry.epoch;
+        let tasks = self.registry.tasks.lock().unwrap();
+        let mut status: Vec<_> = tasks
+            .iter()
+            .map(|(id, entry)| {
+                let last_activity = entry.last_activity.load(Ordering::Relaxed);
+                let status = BackgroundTaskStatus {
+                    kind: entry.kind,
+                    node: entry.node,
+                    started: entry.started,
+                    last_activity: epoch + Duration::from_nanos(last_activity),
+                };
+                (*id, status)
+            })
+            .collect();
+        status.sort_unstable_by_key(|(id, _)| *id);
+        status.into_iter().map(|(_, status)| status).collect()
+    }
+
+    /// Spawns a task which is cancelled when the returned handle is dropped.
+    pub(crate) fn spawn<Fut>(
+        &self,
+        kind: BackgroundTaskKind,
+        node: Option<SocketAddr>,
+        task: impl FnOnce(TaskActivity) -> Fut,
+    ) -> RemoteHandle<()>
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (registration, activity) = self.register(kind, node, None);
+        // Boxed right away, so that large futures like the cluster worker
+        // aren't moved around on the stack of the caller
+        let task = task(activity).boxed();
+        let (task, handle) = async move {
+            let _registration = registration;
+            task.await
+        }
+        .remote_handle();
+        self.spawn_task(kind, node, task);
+        handle
+    }
+
+    /// Spawns a task which runs until it completes or is aborted
+    /// with [`abort_detached`](Self::abort_detached).
+    pub(crate) fn spawn_detached(
+        &self,
+        kind: BackgroundTaskKind,
+        node: Option<SocketAddr>,
+        task: impl Future<Output = ()> + Send + 'static,
+    ) {
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        let (registration, _activity) = self.register(kind, node, Some(abort_handle));
+        let task = Abortable::new(task, abort_registration);
+        self.spawn_task(kind, node, async move {
+            let _registration = registration;
+            let _ = task.await;
+        });
+    }
+
+    /// Aborts the tasks spawned with [`spawn_detached`](Self::spawn_detached).
+    pub(crate) fn abort_detached(&self) {
+        let tasks = self.registry.tasks.lock().unwrap();
+        for abort_handle in tasks
+            .values()
+            .filter_map(|entry| entry.abort_handle.as_ref())
+        {
+            abort_handle.abort();
+        }
+    }
+
+    fn spawn_task(
+        &self,
+        kind: BackgroundTaskKind,
+        node: Option<SocketAddr>,
+        task: impl Future<Output = ()> + Send + 'static,
+    ) {
+        let name = match node {
+            Some(node) => format!("scylla::{} {}", kind.name(), node),
+            None => format!("scylla::{}", kind.name()),
+        };
+        self.registry
+            .runtime
+            .spawn(&name, task.with_current_subscriber().boxed());
+    }
+
+    fn register(
+        &self,
+        kind: BackgroundTaskKind,
+        node: Option<SocketAddr>,
+        abort_handle: Option<AbortHandle>,
+    ) -> (Registration, TaskActivity) {
+        let id = self.registry.next_id.fetch_add(1, Ordering::Relaxed);
+        let started = Instant::now();
+        let activity = TaskActivity {
+            epoch: self.registry.epoch,
+            last_activity: Arc::new(AtomicU64::new(nanos_since(self.registry.epoch, started))),
+        };
+        let entry = TaskEntry {
+            kind,
+            node,
+            started,
+            last_activity: activity.last_activity.clone(),
+            abort_handle,
+        };
+        self.registry.tasks.lock().unwrap().insert(id, entry);
+
+        let registration = Registration {
+            registry: self.registry.clone(),
+            id,
+        };
+        (registration, activity)
+    }
+}
+
+/// Lets a task report that it's active.
+#[derive(Clone, Debug)]
+pub(crate) struct TaskActivity {
+    epoch: Instant,
+    last_activity: Arc<AtomicU64>,
+}
+
+impl TaskActivity {
+    // For running the tasks directly in tests
+    #[cfg(test)]
+    pub(crate) fn untracked() -> Self {
+        TaskActivity {
+            epoch: Instant::now(),
+            last_activity: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub(crate) fn touch(&self) {
+        let elapsed = nanos_since(self.epoch, Instant::now());
+        self.last_activity.store(elapsed, Ordering::Relaxed);
+    }
+}
+
+fn nanos_since(epoch: Instant, instant: Instant) -> u64 {
+    instant.saturating_duration_since(epoch).as_nanos() as u64
+}
+
+// Removes the task from the registry when it completes or is cancelled,
+// as it's dropped together with the future of the task.
+struct Registration {
+    registry: Arc<Registry>,
+    id: u64,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.registry.tasks.lock().unwrap().remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{BackgroundTaskKind, BackgroundTasks};
+
+    #[tokio::test]
+    async fn tasks_are_unregistered_when_cancelled_or_completed() {
+        let tasks = BackgroundTasks::default();
+        let node = "127.0.0.1:9042".parse().unwrap();
+
+        let (finish_sender, finish_receiver) = tokio::sync::oneshot::channel::<()>();
+        let handle = tasks.spawn(
+            BackgroundTaskKind::PoolRefiller,
+            Some(node),
+            |activity| async move {
+                activity.touch();
+                std::future::pending::<()>().await
+            },
+        );
+        tasks.spawn_detached(BackgroundTaskKind::Reprepare, None, async move {
+            let _ = finish_receiver.await;
+        });
+
+        let status = tasks.status();
+        assert_eq!(status.len(), 2);
+        assert_eq!(status[0].kind, BackgroundTaskKind::PoolRefiller);
+        assert_eq!(status[0].node, Some(node));
+        assert!(status[0].last_activity >= status[0].started);
+        assert_eq!(status[1].kind, BackgroundTaskKind::Reprepare);
+        assert_eq!(status[1].node, None);
+
+        drop(handle);
+        finish_sender.send(()).unwrap();
+        wait_until_empty(&tasks).await;
+    }
+
+    #[tokio::test]
+    async fn detached_tasks_are_aborted() {
+        let tasks = BackgroundTasks::default();
+        tasks.spawn_detached(
+            BackgroundTaskKind::UseKeyspace,
+            None,
+            std::future::pending(),
+        );
+        let _handle = tasks.spawn(BackgroundTaskKind::ClusterWorker, None, |_| {
+            std::future::pending()
+        });
+
+        tasks.abort_detached();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while tasks.status().len() != 1 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(tasks.status()[0].kind, BackgroundTaskKind::ClusterWorker);
+    }
+
+    async fn wait_until_empty(tasks: &BackgroundTasks) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !tasks.status().is_empty() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+//! Reusing buffers for serialized values and request frames across requests.
+//!
+//! Enabled with [`SessionBuilder::buffer_pool_max_retained_bytes`](crate::transport::session_builder::GenericSessionBuilder::buffer_pool_max_retained_bytes).
+//! Buffers are taken from the pool when values are serialized or a frame is assembled,
+//! and given back once the request is sent, so under steady load requests don't allocate
+//! memory for them. Hits and misses of the pool are counted in [`Metrics`].
+
+use std::sync::{Arc, Mutex};
+
+use crate::transport::metrics::Metrics;
+
+/// A pool of byte buffers shared by the session and its connections.
+#[derive(Debug)]
+pub(crate) struct BufferPool {
+    inner: Mutex<PoolInner>,
+    max_retained_bytes: usize,
+    metrics: Arc<Metrics>,
+}
+
+#[derive(Debug, Default)]
+struct PoolInner {
+    buffers: Vec<Vec<u8>>,
+    retained_bytes: usize,
+}
+
+impl BufferPool {
+    /// Creates a pool which holds buffers of at most `max_retained_bytes` capacity in total.
+    pub(crate) fn new(max_retained_bytes: usize, metrics: Arc<Metrics>) -> Self {
+        Self {
+            inner: Mutex::new(PoolInner::default()),
+            max_retained_bytes,
+            metrics,
+        }
+    }
+
+    /// Takes a buffer from the pool, or returns a new one if the pool is empty.
+    pub(crate) fn take(&self) -> Vec<u8> {
+        let buffer = {
+            let mut inner = self.inner.lock().unwrap();
+            let buffer = inner.buffers.pop();
+            if let Some(buffer) = &buffer {
+                inner.retained_bytes -= buffer.capacity();
+            }
+            buffer
+        };
+        match buffer {
+            Some(buffer) => {
+                self.metrics.inc_buffer_pool_hits();
+                buffer
+            }
+            None => {
+                self.metrics.inc_buffer_pool_misses();
+                Vec::new()
+            }
+        }
+    }
+
+    /// Returns the buffer to the pool. The buffer is freed instead
+    /// if keeping it would exceed the retention cap.
+    pub(crate) fn give_back(&self, mut buffer: Vec<u8>) {
+        let capacity = buffer.capacity();
+        if capacity == 0 || capacity > self.max_retained_bytes {
+            return;
+        }
+        buffer.clear();
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.retained_bytes + capacity <= self.max_retained_bytes {
+            inner.retained_bytes += capacity;
+            inner.buffers.push(buffer);
+        }
+    }
+}
+
+/// Takes a buffer from the pool if there is one.
+pub(crate) fn take_buffer(pool: Option<&BufferPool>) -> Vec<u8> {
+    match pool {
+        Some(pool) => pool.take(),
+        None => Vec::new(),
+    }
+}
+
+/// Returns the buffer to the pool if there is one.
+pub(crate) fn give_back_buffer(pool: Option<&BufferPool>, buffer: Vec<u8>) {
+    if let Some(pool) = pool {
+        pool.give_back(buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BufferPool;
+    use crate::transport::metrics::Metrics;
+    use std::sync::Arc;
+
+    #[test]
+    fn reuses_buffers_up_to_the_cap() {
+        let metrics = Arc::new(Metrics::new());
+        let pool = BufferPool::new(1024, metrics.clone());
+
+        let mut buffer = pool.take();
+        assert_eq!(metrics.get_buffer_pool_misses(), 1);
+        buffer.extend_from_slice(&[1; 600]);
+        let capacity = buffer.capacity();
+        pool.give_back(buffer);
+
+        let buffer = pool.take();
+        assert_eq!(metrics.get_buffer_pool_hits(), 1);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.capacity(), capacity);
+
+        // Buffers exceeding the cap, alone or together with the retained ones, are dropped.
+        pool.give_back(buffer);
+        pool.give_back(Vec::with_capacity(2048));
+        pool.give_back(Vec::with_capacity(600));
+        assert_eq!(pool.inner.lock().unwrap().buffers.len(), 1);
+        assert!(pool.inner.lock().unwrap().retained_bytes <= 1024);
+
+        pool.take();
+        let buffer = pool.take();
+        assert_eq!(buffer.capacity(), 0);
+        assert_eq!(metrics.get_buffer_pool_hits(), 2);
+        assert_eq!(metrics.get_buffer_pool_misses(), 2);
+    }
+}
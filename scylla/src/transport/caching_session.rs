@@ -1,18 +1,27 @@
 use crate::batch::{Batch, BatchStatement};
+use crate::prepared_statement::target_table;
 use crate::prepared_statement::PreparedStatement;
 use crate::query::Query;
+use crate::statement::normalize::normalize_statement;
 use crate::transport::errors::QueryError;
-use crate::transport::iterator::RowIterator;
+use crate::transport::iterator::{IterConfig, RowIterator};
 use crate::transport::partitioner::PartitionerName;
+use crate::transport::schema_changes::SchemaChange;
 use crate::{QueryResult, Session};
 use bytes::Bytes;
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use futures::future::try_join_all;
-use scylla_cql::frame::response::result::PreparedMetadata;
+use scylla_cql::frame::response::result::{PreparedMetadata, ResultMetadata};
 use scylla_cql::types::serialize::batch::BatchValues;
 use scylla_cql::types::serialize::row::SerializeRow;
 use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
 use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+use tokio::sync::OnceCell;
 
 /// Contains just the parts of a prepared statement that were returned
 /// from the database. All remaining parts (query string, page size,
@@ -23,10 +32,57 @@ struct RawPreparedStatementData {
     id: Bytes,
     is_confirmed_lwt: bool,
     metadata: PreparedMetadata,
+    result_metadata: ResultMetadata,
     partitioner_name: PartitionerName,
 }
 
+/// Identifies a cached statement. The same statement text may refer
+/// to different tables depending on the keyspace used by the session.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    keyspace: Option<Arc<String>>,
+    statement: String,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    /// Set once the statement is prepared. Concurrent requests for a statement
+    /// which is being prepared wait for the result instead of preparing it again.
+    data: Arc<OnceCell<RawPreparedStatementData>>,
+    /// Value of the cache clock when the entry was last used.
+    last_used: AtomicU64,
+    /// Value of the cache clock under which the entry is in the usage order.
+    /// It's older than `last_used` if the entry was used since.
+    indexed_at: AtomicU64,
+}
+
+/// Statistics of the prepared statement cache of a [`CachingSession`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CacheStats {
+    /// Requests which used an already prepared (or being prepared) statement.
+    pub hits: u64,
+    /// Requests which had to prepare the statement.
+    pub misses: u64,
+    /// Statements removed from the cache because it was full.
+    pub evictions: u64,
+    /// Number of statements currently in the cache.
+    pub size: usize,
+}
+
+#[derive(Debug, Default)]
+struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    /// Logical clock used to find the least recently used entry.
+    clock: AtomicU64,
+}
+
 /// Provides auto caching while executing queries
+///
+/// Statements are cached per keyspace used by the session at the time of the request,
+/// so executing `USE` doesn't make the cache serve statements prepared for another keyspace.
 #[derive(Debug)]
 pub struct CachingSession<S = RandomState>
 where
@@ -34,10 +90,23 @@ where
 {
     session: Session,
     /// The prepared statement cache size
-    /// If a prepared statement is added while the limit is reached, the oldest prepared statement
-    /// is removed from the cache
+    /// If a prepared statement is added while the limit is reached, the least recently used
+    /// prepared statement is removed from the cache
     max_capacity: usize,
-    cache: DashMap<String, RawPreparedStatementData, S>,
+    cache: DashMap<CacheKey, CacheEntry, S>,
+    /// Keys of the cached statements by the value of the cache clock when they were
+    /// added to it, used to find the least recently used statement when the cache is full.
+    /// Hits don't lock it: a used statement is moved only when it's found here
+    /// to be used since, while evicting.
+    ///
+    /// It's never locked while holding a reference into `cache`.
+    usage_order: Mutex<BTreeMap<u64, CacheKey>>,
+    counters: CacheCounters,
+    /// Statements touching objects changed in the meantime are removed from the cache
+    /// before it is used.
+    schema_changes: Mutex<broadcast::Receiver<SchemaChange>>,
+    /// Whether statements are normalized before being looked up in the cache and prepared
+    normalize_statements: bool,
 }
 
 impl<S> CachingSession<S>
@@ -45,11 +114,9 @@ where
     S: Default + BuildHasher + Clone,
 {
     pub fn from(session: Session, cache_size: usize) -> Self {
-        Self {
-            session,
-            max_capacity: cache_size,
-            cache: Default::default(),
-        }
+        CachingSessionBuilder::new(session, cache_size)
+            .hasher(S::default())
+            .build()
     }
 }
 
@@ -60,22 +127,33 @@ where
     /// Builds a [`CachingSession`] from a [`Session`], a cache size, and a [`BuildHasher`].,
     /// using a customer hasher.
     pub fn with_hasher(session: Session, cache_size: usize, hasher: S) -> Self {
-        Self {
-            session,
-            max_capacity: cache_size,
-            cache: DashMap::with_hasher(hasher),
-        }
+        CachingSessionBuilder::new(session, cache_size)
+            .hasher(hasher)
+            .build()
     }
 
-    /// Does the same thing as [`Session::execute`] but uses the prepared statement cache
-    pub async fn execute(
+    /// Does the same thing as [`Session::execute_unpaged`] but uses the prepared statement cache
+    pub async fn execute_unpaged(
         &self,
         query: impl Into<Query>,
         values: impl SerializeRow,
     ) -> Result<QueryResult, QueryError> {
         let query = query.into();
         let prepared = self.add_prepared_statement_owned(query).await?;
-        self.session.execute(&prepared, values).await
+        self.session.execute_unpaged(&prepared, values).await
+    }
+
+    /// Same as [`execute_unpaged`](CachingSession::execute_unpaged).
+    #[deprecated(
+        since = "0.12.0",
+        note = "use `execute_unpaged`, `execute_single_page` or `execute_iter`, which tell how the result is paged"
+    )]
+    pub async fn execute(
+        &self,
+        query: impl Into<Query>,
+        values: impl SerializeRow,
+    ) -> Result<QueryResult, QueryError> {
+        self.execute_unpaged(query, values).await
     }
 
     /// Does the same thing as [`Session::execute_iter`] but uses the prepared statement cache
@@ -89,7 +167,39 @@ where
         self.session.execute_iter(prepared, values).await
     }
 
-    /// Does the same thing as [`Session::execute_paged`] but uses the prepared statement cache
+    /// Does the same thing as [`Session::execute_iter_with_config`] but uses the prepared statement cache
+    pub async fn execute_iter_with_config(
+        &self,
+        query: impl Into<Query>,
+        values: impl SerializeRow,
+        config: IterConfig,
+    ) -> Result<RowIterator, QueryError> {
+        let query = query.into();
+        let prepared = self.add_prepared_statement_owned(query).await?;
+        self.session
+            .execute_iter_with_config(prepared, values, config)
+            .await
+    }
+
+    /// Does the same thing as [`Session::execute_single_page`] but uses the prepared statement cache
+    pub async fn execute_single_page(
+        &self,
+        query: impl Into<Query>,
+        values: impl SerializeRow,
+        paging_state: Option<Bytes>,
+    ) -> Result<QueryResult, QueryError> {
+        let query = query.into();
+        let prepared = self.add_prepared_statement_owned(query).await?;
+        self.session
+            .execute_single_page(&prepared, values, paging_state)
+            .await
+    }
+
+    /// Does the same thing as [`Session::execute_single_page`] but uses the prepared statement cache
+    #[deprecated(
+        since = "0.12.0",
+        note = "use `execute_single_page`, which always fetches a single page"
+    )]
     pub async fn execute_paged(
         &self,
         query: impl Into<Query>,
@@ -98,8 +208,9 @@ where
     ) -> Result<QueryResult, QueryError> {
         let query = query.into();
         let prepared = self.add_prepared_statement_owned(query).await?;
+        #[allow(deprecated)]
         self.session
-            .execute_paged(&prepared, values, paging_state.clone())
+            .execute_paged(&prepared, values, paging_state)
             .await
     }
 
@@ -160,49 +271,223 @@ where
         &self,
         query: impl Into<Query>,
     ) -> Result<PreparedStatement, QueryError> {
-        let query = query.into();
+        let mut query = query.into();
+        if self.normalize_statements {
+            // The normalized statement is prepared, so that all of its variants
+            // share a single statement prepared on the server
+            query.contents = normalize_statement(&query.contents);
+        }
 
-        if let Some(raw) = self.cache.get(&query.contents) {
-            let page_size = query.get_page_size();
-            let mut stmt = PreparedStatement::new(
-                raw.id.clone(),
-                raw.is_confirmed_lwt,
-                raw.metadata.clone(),
-                query.contents,
-                page_size,
-                query.config,
-            );
-            stmt.set_partitioner_name(raw.partitioner_name.clone());
-            Ok(stmt)
-        } else {
-            let query_contents = query.contents.clone();
-            let prepared = self.session.prepare(query).await?;
-
-            if self.max_capacity == self.cache.len() {
-                // Cache is full, remove the first entry
-                // Don't hold a reference into the map (that's why the to_string() is called)
-                // This is because the documentation of the remove fn tells us that it may deadlock
-                // when holding some sort of reference into the map
-                let query = self.cache.iter().next().map(|c| c.key().to_string());
-
-                // Don't inline this: https://stackoverflow.com/questions/69873846/an-owned-value-is-still-references-somehow
-                if let Some(q) = query {
-                    self.cache.remove(&q);
-                }
+        // The statement text is moved into the key and back, so that looking up
+        // a cached statement doesn't copy it.
+        let key = CacheKey {
+            keyspace: self.session.get_keyspace(),
+            statement: std::mem::take(&mut query.contents),
+        };
+        let data = self.cache_entry(&key);
+        query.contents = key.statement;
+
+        let mut prepared_now = None;
+        let result = data
+            .get_or_try_init(|| async {
+                self.counters.misses.fetch_add(1, Ordering::Relaxed);
+                let prepared = self.session.prepare(query.clone()).await?;
+                let raw = RawPreparedStatementData {
+                    id: prepared.get_id().clone(),
+                    is_confirmed_lwt: prepared.is_confirmed_lwt(),
+                    metadata: prepared.get_prepared_metadata().clone(),
+                    result_metadata: prepared.get_result_metadata().clone(),
+                    partitioner_name: prepared.get_partitioner_name().clone(),
+                };
+                prepared_now = Some(prepared);
+                Ok(raw)
+            })
+            .await;
+
+        let raw = match result {
+            Ok(raw) => raw,
+            Err(err) => {
+                // Don't keep statements which failed to prepare, unless someone else
+                // managed to prepare the statement in the meantime.
+                let key = CacheKey {
+                    keyspace: key.keyspace,
+                    statement: query.contents,
+                };
+                self.cache.remove_if(&key, |_, entry| {
+                    Arc::ptr_eq(&entry.data, &data) && !entry.data.initialized()
+                });
+                return Err(err);
+            }
+        };
+
+        if let Some(prepared) = prepared_now {
+            return Ok(prepared);
+        }
+        self.counters.hits.fetch_add(1, Ordering::Relaxed);
+
+        let page_size = query.get_page_size();
+        let mut stmt = PreparedStatement::new(
+            raw.id.clone(),
+            raw.is_confirmed_lwt,
+            raw.metadata.clone(),
+            raw.result_metadata.clone(),
+            query.contents,
+            page_size,
+            query.config,
+        );
+        stmt.set_partitioner_name(raw.partitioner_name.clone());
+        Ok(stmt)
+    }
+
+    /// Returns the entry for the statement, inserting an empty one if it's not cached.
+    fn cache_entry(&self, key: &CacheKey) -> Arc<OnceCell<RawPreparedStatementData>> {
+        self.apply_schema_changes();
+        let now = self.counters.clock.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(entry) = self.cache.get(key) {
+            entry.last_used.fetch_max(now, Ordering::Relaxed);
+            return entry.data.clone();
+        }
+
+        // Don't hold a reference into the map while evicting, removing from the map
+        // may deadlock when holding some sort of reference into it
+        let data = match self.cache.entry(key.clone()) {
+            Entry::Occupied(entry) => {
+                entry.get().last_used.fetch_max(now, Ordering::Relaxed);
+                return entry.get().data.clone();
+            }
+            Entry::Vacant(entry) => entry
+                .insert(CacheEntry {
+                    data: Default::default(),
+                    last_used: AtomicU64::new(now),
+                    indexed_at: AtomicU64::new(now),
+                })
+                .data
+                .clone(),
+        };
+
+        let mut usage_order = self.usage_order.lock().unwrap();
+        usage_order.insert(now, key.clone());
+        self.prune_usage_order(&mut usage_order);
+        self.evict_if_full(&mut usage_order);
+        data
+    }
+
+    /// Statements removed from the cache otherwise than by eviction leave stale keys
+    /// in the usage order. They are dropped once there are about as many of them
+    /// as cached statements, so that pruning takes amortized constant time.
+    fn prune_usage_order(&self, usage_order: &mut BTreeMap<u64, CacheKey>) {
+        if usage_order.len() <= 2 * self.cache.len().max(16) {
+            return;
+        }
+        usage_order.retain(|tick, key| {
+            matches!(
+                self.cache.get(key),
+                Some(entry) if entry.indexed_at.load(Ordering::Relaxed) == *tick
+            )
+        });
+    }
+
+    /// Removes statements affected by schema changes announced since the last call.
+    /// Statements which are still being prepared are kept.
+    fn apply_schema_changes(&self) {
+        let mut schema_changes = match self.schema_changes.try_lock() {
+            Ok(schema_changes) => schema_changes,
+            // Someone else is already applying the changes
+            Err(_) => return,
+        };
+
+        loop {
+            match schema_changes.try_recv() {
+                Ok(change) => self.cache.retain(|_, entry| match entry.data.get() {
+                    Some(raw) => !change.affects(target_table(&raw.metadata, &raw.result_metadata)),
+                    None => true,
+                }),
+                // Some changes were missed, so any statement might be affected
+                Err(TryRecvError::Lagged(_)) => self.cache.clear(),
+                Err(TryRecvError::Empty | TryRecvError::Closed) => break,
             }
+        }
+    }
 
-            let raw = RawPreparedStatementData {
-                id: prepared.get_id().clone(),
-                is_confirmed_lwt: prepared.is_confirmed_lwt(),
-                metadata: prepared.get_prepared_metadata().clone(),
-                partitioner_name: prepared.get_partitioner_name().clone(),
+    /// Removes least recently used statements until the cache fits in its capacity.
+    /// Statements which are still being prepared are never removed, so that requests
+    /// waiting for them don't lead to preparing the statement again.
+    ///
+    /// Statements are taken from the front of the usage order. One used since it was put
+    /// there is put back under the time of its last use instead, so each hit costs
+    /// at most one reordering, done here rather than on the hit.
+    fn evict_if_full(&self, usage_order: &mut BTreeMap<u64, CacheKey>) {
+        let mut being_prepared = Vec::new();
+        while self.cache.len() > self.max_capacity {
+            let (tick, key) = match usage_order.pop_first() {
+                Some(oldest) => oldest,
+                None => break,
             };
-            self.cache.insert(query_contents, raw);
 
-            Ok(prepared)
+            let mut used_at = None;
+            let evicted = self.cache.remove_if(&key, |_, entry| {
+                if entry.indexed_at.load(Ordering::Relaxed) != tick {
+                    // A stale key of a statement removed in the meantime
+                    return false;
+                }
+                if !entry.data.initialized() {
+                    being_prepared.push((tick, key.clone()));
+                    return false;
+                }
+                let last_used = entry.last_used.load(Ordering::Relaxed);
+                if last_used > tick {
+                    entry.indexed_at.store(last_used, Ordering::Relaxed);
+                    used_at = Some(last_used);
+                    return false;
+                }
+                true
+            });
+
+            if evicted.is_some() {
+                self.counters.evictions.fetch_add(1, Ordering::Relaxed);
+            } else if let Some(used_at) = used_at {
+                usage_order.insert(used_at, key);
+            }
+        }
+        usage_order.extend(being_prepared);
+    }
+
+    /// Returns statistics of the prepared statement cache.
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            evictions: self.counters.evictions.load(Ordering::Relaxed),
+            size: self.cache.len(),
         }
     }
 
+    /// Removes the statement from the cache, in all keyspaces it was cached for,
+    /// so that it is prepared again when used next time.
+    /// Returns whether the statement was cached.
+    pub fn invalidate(&self, query: &str) -> bool {
+        let normalized;
+        let query = if self.normalize_statements {
+            normalized = normalize_statement(query);
+            normalized.as_str()
+        } else {
+            query
+        };
+        let mut removed = false;
+        self.cache.retain(|key, _| {
+            let matches = key.statement == query;
+            removed |= matches;
+            !matches
+        });
+        removed
+    }
+
+    /// Removes all statements from the cache.
+    pub fn clear(&self) {
+        self.cache.clear();
+    }
+
     pub fn get_max_capacity(&self) -> usize {
         self.max_capacity
     }
@@ -212,8 +497,81 @@ where
     }
 }
 
+/// Builder of a [`CachingSession`]
+///
+/// # Example
+///
+/// ```rust
+/// # use scylla::Session;
+/// # async fn example(session: Session) {
+/// use scylla::CachingSessionBuilder;
+///
+/// let caching_session = CachingSessionBuilder::new(session, 100)
+///     .normalize_statements(true)
+///     .build();
+/// # }
+/// ```
+pub struct CachingSessionBuilder<S = RandomState> {
+    session: Session,
+    max_capacity: usize,
+    hasher: S,
+    normalize_statements: bool,
+}
+
+impl CachingSessionBuilder<RandomState> {
+    /// Starts building a [`CachingSession`] caching at most `cache_size` statements.
+    pub fn new(session: Session, cache_size: usize) -> Self {
+        Self {
+            session,
+            max_capacity: cache_size,
+            hasher: RandomState::default(),
+            normalize_statements: false,
+        }
+    }
+}
+
+impl<S> CachingSessionBuilder<S>
+where
+    S: BuildHasher + Clone,
+{
+    /// Sets the hasher used by the cache.
+    pub fn hasher<S2: BuildHasher + Clone>(self, hasher: S2) -> CachingSessionBuilder<S2> {
+        CachingSessionBuilder {
+            session: self.session,
+            max_capacity: self.max_capacity,
+            hasher,
+            normalize_statements: self.normalize_statements,
+        }
+    }
+
+    /// Makes statements differing only in whitespace, comments and the case of keywords
+    /// and unquoted identifiers share a cache entry, and a statement prepared on the server.\
+    /// String literals and quoted identifiers are never changed.
+    /// The statement text prepared, and later visible e.g. in the tracing information,
+    /// is the normalized one.\
+    /// Disabled by default.
+    pub fn normalize_statements(mut self, normalize: bool) -> Self {
+        self.normalize_statements = normalize;
+        self
+    }
+
+    /// Builds the [`CachingSession`].
+    pub fn build(self) -> CachingSession<S> {
+        CachingSession {
+            schema_changes: Mutex::new(self.session.subscribe_schema_changes()),
+            session: self.session,
+            max_capacity: self.max_capacity,
+            cache: DashMap::with_hasher(self.hasher),
+            usage_order: Default::default(),
+            counters: Default::default(),
+            normalize_statements: self.normalize_statements,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::CacheStats;
     use crate::query::Query;
     use crate::test_utils::create_new_session_builder;
     use crate::transport::partitioner::PartitionerName;
@@ -221,7 +579,7 @@ mod tests {
     use crate::{
         batch::{Batch, BatchStatement},
         prepared_statement::PreparedStatement,
-        CachingSession, Session,
+        CachingSession, CachingSessionBuilder, Session,
     };
     use futures::TryStreamExt;
     use std::collections::BTreeSet;
@@ -234,12 +592,12 @@ mod tests {
         let ks = unique_keyspace_name();
 
         session
-            .query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+            .query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
             .await
             .expect("Could not create keyspace");
 
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "CREATE TABLE IF NOT EXISTS {}.test_table (a int primary key, b int)",
                     ks
@@ -262,20 +620,27 @@ mod tests {
 
         // Add a row, this makes it easier to check if the caching works combined with the regular execute fn on Session
         session
-            .execute("insert into test_table(a, b) values (1, 2)", &[])
+            .execute_unpaged("insert into test_table(a, b) values (1, 2)", &[])
             .await
             .unwrap();
 
         // Clear the cache because it now contains an insert
-        assert_eq!(session.cache.len(), 1);
+        assert_eq!(session.cache_stats().size, 1);
 
-        session.cache.clear();
+        session.clear();
+
+        session
+    }
 
+    fn is_cached(session: &CachingSession, query: &str) -> bool {
         session
+            .cache
+            .iter()
+            .any(|entry| entry.key().statement == query)
     }
 
     /// Test that when the cache is full and a different query comes in, that query will be added
-    /// to the cache and a random query is removed
+    /// to the cache and the least recently used query is removed
     #[tokio::test]
     async fn test_full() {
         let session = create_caching_session().await;
@@ -292,21 +657,248 @@ mod tests {
             .add_prepared_statement(&middle_query.into())
             .await
             .unwrap();
+        // Use the first query again, so that the middle one is the least recently used
+        session
+            .add_prepared_statement(&first_query.into())
+            .await
+            .unwrap();
         session
             .add_prepared_statement(&last_query.into())
             .await
             .unwrap();
 
-        assert_eq!(2, session.cache.len());
+        assert!(is_cached(&session, first_query));
+        assert!(!is_cached(&session, middle_query));
+        assert!(is_cached(&session, last_query));
+        // Statistics include the insert executed by create_caching_session
+        assert_eq!(
+            session.cache_stats(),
+            CacheStats {
+                hits: 1,
+                misses: 4,
+                evictions: 1,
+                size: 2,
+            }
+        );
+    }
+
+    /// Checks that a statement which failed to prepare is not cached
+    #[tokio::test]
+    async fn test_failed_prepare_is_not_cached() {
+        let session = create_caching_session().await;
+
+        assert!(session
+            .add_prepared_statement(&"This isnt even CQL".into())
+            .await
+            .is_err());
+        assert_eq!(session.cache_stats().size, 0);
+    }
+
+    /// Checks that invalidated statements are prepared again
+    #[tokio::test]
+    async fn test_invalidate_and_clear() {
+        let session = create_caching_session().await;
+        let query = "select * from test_table";
+
+        session.execute_unpaged(query, &[]).await.unwrap();
+        assert!(session.invalidate(query));
+        assert!(!session.invalidate(query));
+        assert_eq!(session.cache_stats().size, 0);
+
+        session.execute_unpaged(query, &[]).await.unwrap();
+        session.execute_unpaged(query, &[]).await.unwrap();
+        let stats = session.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.size), (1, 3, 1));
+
+        session.clear();
+        assert_eq!(session.cache_stats().size, 0);
+
+        // Statements cached again after being removed are still evicted in the order of use
+        let other_query = "select a from test_table";
+        let last_query = "select b from test_table";
+        session.execute_unpaged(query, &[]).await.unwrap();
+        session.execute_unpaged(other_query, &[]).await.unwrap();
+        session.execute_unpaged(query, &[]).await.unwrap();
+        session.execute_unpaged(last_query, &[]).await.unwrap();
+        assert!(is_cached(&session, query));
+        assert!(!is_cached(&session, other_query));
+        assert!(is_cached(&session, last_query));
+        assert_eq!(session.cache_stats().evictions, 1);
+    }
+
+    /// Checks that statements differing only in formatting share a cache entry
+    /// when normalization is enabled
+    #[tokio::test]
+    async fn test_normalized_statements() {
+        let session = CachingSessionBuilder::new(new_for_test().await, 10)
+            .normalize_statements(true)
+            .build();
+
+        let variants = [
+            "select b from test_table where a=?",
+            "SELECT b FROM test_table WHERE a = ?",
+            "Select b\n  From Test_Table -- any row\n  Where A=?",
+        ];
+        for variant in variants {
+            let prepared = session
+                .add_prepared_statement(&variant.into())
+                .await
+                .unwrap();
+            assert_eq!(prepared.get_statement(), variants[0]);
+            session.execute_unpaged(variant, (1,)).await.unwrap();
+        }
+        let stats = session.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.size), (5, 1, 1));
+
+        // Quoted identifiers are never changed
+        session
+            .execute_unpaged("SELECT \"b\" FROM Test_Table WHERE \"a\" = ?", (1,))
+            .await
+            .unwrap();
+        assert!(is_cached(
+            &session,
+            "select \"b\" from test_table where \"a\"=?"
+        ));
+
+        assert!(session.invalidate("SELECT b FROM test_table WHERE a = ?"));
+    }
 
-        // This query should be in the cache
-        assert!(session.cache.get(last_query).is_some());
+    /// Checks that the same statement text is cached separately for every keyspace,
+    /// so that `USE` doesn't make the session execute statements prepared for another keyspace
+    #[tokio::test]
+    async fn test_cache_key_includes_keyspace() {
+        let session = create_caching_session().await;
+        let first_ks = session.get_session().get_keyspace().unwrap();
+        let second_ks = unique_keyspace_name();
 
-        // Either the first or middle query should be removed
-        let first_query_removed = session.cache.get(first_query).is_none();
-        let middle_query_removed = session.cache.get(middle_query).is_none();
+        session
+            .execute_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", second_ks), &[])
+            .await
+            .unwrap();
+        session
+            .execute_unpaged(
+                format!(
+                    "CREATE TABLE {}.test_table (a int primary key, b int)",
+                    second_ks
+                ),
+                &[],
+            )
+            .await
+            .unwrap();
+        session.clear();
+
+        let query = "select a, b from test_table";
+        let first = session.add_prepared_statement(&query.into()).await.unwrap();
+        session
+            .get_session()
+            .use_keyspace(&second_ks, false)
+            .await
+            .unwrap();
+        let second = session.add_prepared_statement(&query.into()).await.unwrap();
+
+        assert_eq!(first.get_keyspace_name(), Some(first_ks.as_str()));
+        assert_eq!(second.get_keyspace_name(), Some(second_ks.as_str()));
+        assert_eq!(session.cache_stats().size, 2);
+
+        // Both entries are removed
+        assert!(session.invalidate(query));
+        assert_eq!(session.cache_stats().size, 0);
+    }
+
+    /// Checks that statements touching an altered table are removed from the cache,
+    /// while statements touching other tables are kept
+    #[tokio::test]
+    async fn test_schema_change_invalidates_cache() {
+        let session = create_caching_session().await;
+        session
+            .execute_unpaged("CREATE TABLE other_table (a int primary key)", &[])
+            .await
+            .unwrap();
+        session.clear();
+
+        let altered_query = "select * from test_table where a = ?";
+        let other_query = "select * from other_table";
+        session.execute_unpaged(altered_query, (1,)).await.unwrap();
+        session.execute_unpaged(other_query, &[]).await.unwrap();
+
+        session
+            .get_session()
+            .query_unpaged("ALTER TABLE test_table ADD c int", &[])
+            .await
+            .unwrap();
+
+        // The schema change is announced asynchronously
+        let mut invalidated = false;
+        for _ in 0..50 {
+            session.apply_schema_changes();
+            if !is_cached(&session, altered_query) {
+                invalidated = true;
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert!(invalidated);
+        assert!(is_cached(&session, other_query));
+
+        let result = session.execute_unpaged(altered_query, (1,)).await.unwrap();
+        assert_eq!(result.col_specs.len(), 3);
+    }
+
+    /// Checks that statements touching a dropped table are removed from the cache,
+    /// so that the table can be created again without errors
+    #[tokio::test]
+    async fn test_table_drop_invalidates_cache() {
+        let session = create_caching_session().await;
+        session
+            .execute_unpaged("CREATE TABLE dropped_table (a int primary key, b int)", &[])
+            .await
+            .unwrap();
+        session.clear();
+
+        let query = "select * from dropped_table";
+        session.execute_unpaged(query, &[]).await.unwrap();
+
+        let inner = session.get_session();
+        inner
+            .query_unpaged("DROP TABLE dropped_table", &[])
+            .await
+            .unwrap();
+        inner
+            .query_unpaged(
+                "CREATE TABLE dropped_table (a int primary key, b text, c int)",
+                &[],
+            )
+            .await
+            .unwrap();
+
+        // The schema change is announced asynchronously
+        let mut invalidated = false;
+        for _ in 0..50 {
+            session.apply_schema_changes();
+            if !is_cached(&session, query) {
+                invalidated = true;
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert!(invalidated);
+
+        let result = session.execute_unpaged(query, &[]).await.unwrap();
+        assert_eq!(result.col_specs.len(), 3);
+    }
+
+    /// Checks that concurrent requests for the same uncached statement prepare it only once
+    #[tokio::test]
+    async fn test_concurrent_prepare_single_flight() {
+        let session = create_caching_session().await;
+        let query = "select * from test_table where a = ?";
+
+        futures::future::try_join_all((0..32).map(|_| session.execute_unpaged(query, (1,))))
+            .await
+            .unwrap();
 
-        assert!(first_query_removed || middle_query_removed);
+        let stats = session.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.size), (31, 2, 1));
     }
 
     /// Checks that the same prepared statement is reused when executing the same query twice
@@ -314,19 +906,19 @@ mod tests {
     async fn test_execute_cached() {
         let session = create_caching_session().await;
         let result = session
-            .execute("select * from test_table", &[])
+            .execute_unpaged("select * from test_table", &[])
             .await
             .unwrap();
 
-        assert_eq!(1, session.cache.len());
+        assert_eq!(1, session.cache_stats().size);
         assert_eq!(1, result.rows.unwrap().len());
 
         let result = session
-            .execute("select * from test_table", &[])
+            .execute_unpaged("select * from test_table", &[])
             .await
             .unwrap();
 
-        assert_eq!(1, session.cache.len());
+        assert_eq!(1, session.cache_stats().size);
         assert_eq!(1, result.rows.unwrap().len());
     }
 
@@ -335,7 +927,7 @@ mod tests {
     async fn test_execute_iter_cached() {
         let session = create_caching_session().await;
 
-        assert!(session.cache.is_empty());
+        assert_eq!(0, session.cache_stats().size);
 
         let iter = session
             .execute_iter("select * from test_table", &[])
@@ -345,22 +937,22 @@ mod tests {
         let rows = iter.try_collect::<Vec<_>>().await.unwrap().len();
 
         assert_eq!(1, rows);
-        assert_eq!(1, session.cache.len());
+        assert_eq!(1, session.cache_stats().size);
     }
 
-    /// Checks that caching works with execute_paged
+    /// Checks that caching works with execute_single_page
     #[tokio::test]
     async fn test_execute_paged_cached() {
         let session = create_caching_session().await;
 
-        assert!(session.cache.is_empty());
+        assert_eq!(0, session.cache_stats().size);
 
         let result = session
-            .execute_paged("select * from test_table", &[], None)
+            .execute_single_page("select * from test_table", &[], None)
             .await
             .unwrap();
 
-        assert_eq!(1, session.cache.len());
+        assert_eq!(1, session.cache_stats().size);
         assert_eq!(1, result.rows.unwrap().len());
     }
 
@@ -369,7 +961,7 @@ mod tests {
         expected_rows: &[(i32, i32)],
     ) {
         let selected_rows: BTreeSet<(i32, i32)> = sess
-            .execute("SELECT a, b FROM test_batch_table", ())
+            .execute_unpaged("SELECT a, b FROM test_batch_table", ())
             .await
             .unwrap()
             .rows_typed::<(i32, i32)>()
@@ -423,7 +1015,7 @@ mod tests {
         let session: CachingSession = create_caching_session().await;
 
         session
-            .execute(
+            .execute_unpaged(
                 "CREATE TABLE IF NOT EXISTS test_batch_table (a int, b int, primary key (a, b))",
                 (),
             )
@@ -545,7 +1137,7 @@ mod tests {
         let session: CachingSession = CachingSession::from(new_for_test().await, 100);
 
         session
-            .execute("CREATE TABLE tbl (a int PRIMARY KEY, b int)", ())
+            .execute_unpaged("CREATE TABLE tbl (a int PRIMARY KEY, b int)", ())
             .await
             .unwrap();
 
@@ -556,7 +1148,7 @@ mod tests {
         q1.set_timestamp(Some(1000));
 
         session
-            .execute(q1, (1, 1))
+            .execute_unpaged(q1, (1, 1))
             .await
             .unwrap()
             .result_not_rows()
@@ -567,7 +1159,7 @@ mod tests {
         q2.set_timestamp(Some(2000));
 
         session
-            .execute(q2, (2, 2))
+            .execute_unpaged(q2, (2, 2))
             .await
             .unwrap()
             .result_not_rows()
@@ -575,7 +1167,7 @@ mod tests {
 
         // Fetch both rows with their timestamps
         let mut rows = session
-            .execute("SELECT b, WRITETIME(b) FROM tbl", ())
+            .execute_unpaged("SELECT b, WRITETIME(b) FROM tbl", ())
             .await
             .unwrap()
             .rows_typed_or_empty::<(i32, i64)>()
@@ -596,7 +1188,7 @@ mod tests {
         let session: CachingSession = CachingSession::from(new_for_test().await, 100);
 
         session
-            .execute(
+            .execute_unpaged(
                 "CREATE TABLE tbl (a int PRIMARY KEY) with cdc = {'enabled': true}",
                 (),
             )
@@ -1,34 +1,41 @@
 /// Cluster manages up to date information and connections to database nodes
 use crate::frame::response::event::{Event, StatusChangeEvent};
-use crate::prepared_statement::TokenCalculationError;
+use crate::prepared_statement::{PreparedStatement, TokenCalculationError};
+use crate::query::Query;
 use crate::routing::Token;
+use crate::statement::identifier::CqlIdentifier;
+use crate::transport::control_connection_policy::{
+    ControlConnectionPolicy, ControlConnectionStatus,
+};
 use crate::transport::host_filter::HostFilter;
 use crate::transport::{
+    background_tasks::{BackgroundTaskKind, BackgroundTasks, TaskActivity},
     connection::{Connection, VerifiedKeyspaceName},
     connection_pool::PoolConfig,
     errors::QueryError,
     node::Node,
     partitioner::PartitionerName,
+    runtime::{self, Runtime},
+    schema_changes::{PreparedStatementRegistry, SchemaChange},
     topology::{Keyspace, Metadata, MetadataReader},
 };
 
 use arc_swap::ArcSwap;
 use futures::future::join_all;
-use futures::{future::RemoteHandle, FutureExt};
+use futures::future::RemoteHandle;
 use itertools::Itertools;
 use scylla_cql::errors::{BadQuery, NewSessionError};
 use scylla_cql::types::serialize::row::SerializedValues;
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::Duration;
-use tracing::instrument::WithSubscriber;
+use std::time::{Duration, Instant};
 use tracing::{debug, warn};
 use uuid::Uuid;
 
-use super::node::{KnownNode, NodeAddr};
+use super::node::{ContactPoints, NodeAddr};
 
-use super::locator::ReplicaLocator;
+use super::locator::{ReplicaLocator, TokenRange};
 use super::partitioner::calculate_token_for_partition_key;
 use super::topology::Strategy;
 
@@ -42,9 +49,25 @@ pub(crate) struct Cluster {
     refresh_channel: tokio::sync::mpsc::Sender<RefreshRequest>,
     use_keyspace_channel: tokio::sync::mpsc::Sender<UseKeyspaceRequest>,
 
+    // Schema changes relevant to prepared statements are broadcast here
+    schema_changes: tokio::sync::broadcast::Sender<SchemaChange>,
+    prepared_statements: Arc<PreparedStatementRegistry>,
+
+    // Kept up to date by the MetadataReader of ClusterWorker
+    control_connection_status: Arc<ArcSwap<ControlConnectionStatus>>,
+
+    // Tasks not owned by a handle are aborted when the cluster is dropped
+    background_tasks: BackgroundTasks,
+
     _worker_handle: RemoteHandle<()>,
 }
 
+impl Drop for Cluster {
+    fn drop(&mut self) {
+        self.background_tasks.abort_detached();
+    }
+}
+
 /// Enables printing [Cluster] struct in a neat way, by skipping the rather useless
 /// print of channels state and printing [ClusterData] neatly.
 pub(crate) struct ClusterNeatDebug<'a>(pub(crate) &'a Cluster);
@@ -111,6 +134,15 @@ struct ClusterWorker {
     // Channel used to receive server events
     server_events_channel: tokio::sync::mpsc::Receiver<Event>,
 
+    // Channel used to notify about schema changes, e.g. to invalidate caches
+    schema_changes: tokio::sync::broadcast::Sender<SchemaChange>,
+
+    // Prepared statements in use, re-prepared on schema changes
+    prepared_statements: Arc<PreparedStatementRegistry>,
+
+    // Whether prepared statements in use are prepared on nodes which come back up
+    prepare_on_all_hosts: bool,
+
     // Channel used to receive signals that control connection is broken
     control_connection_repair_channel: tokio::sync::broadcast::Receiver<()>,
 
@@ -126,6 +158,10 @@ struct ClusterWorker {
     cluster_metadata_refresh_interval: Duration,
 }
 
+// How long to wait for connections to a node which came up,
+// to prepare statements in use on them
+const NODE_UP_PREPARE_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Debug)]
 struct RefreshRequest {
     response_chan: tokio::sync::oneshot::Sender<Result<(), QueryError>>,
@@ -138,22 +174,27 @@ struct UseKeyspaceRequest {
 }
 
 impl Cluster {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn new(
-        known_nodes: Vec<KnownNode>,
+        contact_points: ContactPoints,
         pool_config: PoolConfig,
         keyspaces_to_fetch: Vec<String>,
         fetch_schema_metadata: bool,
         host_filter: Option<Arc<dyn HostFilter>>,
         cluster_metadata_refresh_interval: Duration,
+        control_connection_policy: ControlConnectionPolicy,
+        prepare_on_all_hosts: bool,
     ) -> Result<Cluster, NewSessionError> {
         let (refresh_sender, refresh_receiver) = tokio::sync::mpsc::channel(32);
         let (use_keyspace_sender, use_keyspace_receiver) = tokio::sync::mpsc::channel(32);
         let (server_events_sender, server_events_receiver) = tokio::sync::mpsc::channel(32);
         let (control_connection_repair_sender, control_connection_repair_receiver) =
             tokio::sync::broadcast::channel(32);
+        let (schema_changes_sender, _) = tokio::sync::broadcast::channel(32);
+        let prepared_statements = Arc::new(PreparedStatementRegistry::default());
 
         let mut metadata_reader = MetadataReader::new(
-            known_nodes,
+            contact_points,
             control_connection_repair_sender,
             pool_config.connection_config.clone(),
             pool_config.keepalive_interval,
@@ -161,10 +202,13 @@ impl Cluster {
             keyspaces_to_fetch,
             fetch_schema_metadata,
             &host_filter,
+            control_connection_policy,
+            pool_config.metrics.clone(),
         )
         .await?;
 
         let metadata = metadata_reader.read_metadata(true).await?;
+        let control_connection_status = metadata_reader.status();
         let cluster_data = ClusterData::new(
             metadata,
             &pool_config,
@@ -185,6 +229,9 @@ impl Cluster {
 
             refresh_channel: refresh_receiver,
             server_events_channel: server_events_receiver,
+            schema_changes: schema_changes_sender.clone(),
+            prepared_statements: prepared_statements.clone(),
+            prepare_on_all_hosts,
             control_connection_repair_channel: control_connection_repair_receiver,
 
             use_keyspace_channel: use_keyspace_receiver,
@@ -194,13 +241,24 @@ impl Cluster {
             cluster_metadata_refresh_interval,
         };
 
-        let (fut, worker_handle) = worker.work().remote_handle();
-        tokio::spawn(fut.with_current_subscriber());
+        let background_tasks = worker
+            .pool_config
+            .connection_config
+            .background_tasks
+            .clone();
+        let worker_handle =
+            background_tasks.spawn(BackgroundTaskKind::ClusterWorker, None, |activity| {
+                worker.work(activity)
+            });
 
         let result = Cluster {
             data: cluster_data,
             refresh_channel: refresh_sender,
             use_keyspace_channel: use_keyspace_sender,
+            schema_changes: schema_changes_sender,
+            prepared_statements,
+            control_connection_status,
+            background_tasks,
             _worker_handle: worker_handle,
         };
 
@@ -211,6 +269,29 @@ impl Cluster {
         self.data.load_full()
     }
 
+    pub(crate) fn get_control_connection_status(&self) -> ControlConnectionStatus {
+        ControlConnectionStatus::clone(&self.control_connection_status.load())
+    }
+
+    /// Returns a receiver of schema changes which may affect prepared statements.
+    pub(crate) fn subscribe_schema_changes(
+        &self,
+    ) -> tokio::sync::broadcast::Receiver<SchemaChange> {
+        self.schema_changes.subscribe()
+    }
+
+    /// Makes the statement re-prepared on schema changes of its table, for as long as it is in use.
+    pub(crate) fn register_prepared_statement(&self, prepared: &PreparedStatement) {
+        self.prepared_statements.register(prepared);
+    }
+
+    /// Makes statements affected by a schema change made by the session ask for result
+    /// metadata right away, without waiting for the change to be announced by the cluster.
+    pub(crate) fn mark_result_metadata_changed(&self, change: &SchemaChange) {
+        self.prepared_statements
+            .mark_result_metadata_changed(change);
+    }
+
     pub(crate) async fn refresh_metadata(&self) -> Result<(), QueryError> {
         let (response_sender, response_receiver) = tokio::sync::oneshot::channel();
 
@@ -361,6 +442,12 @@ impl ClusterData {
         &self.keyspaces
     }
 
+    /// Looks up a keyspace by its identifier, e.g. parsed from a statement with
+    /// [`CqlIdentifier::from_str`](std::str::FromStr::from_str).
+    pub fn get_keyspace(&self, keyspace: &CqlIdentifier) -> Option<&Keyspace> {
+        self.keyspaces.get(keyspace.name())
+    }
+
     /// Access datacenter details collected by the driver
     /// Returned `HashMap` is indexed by names of datacenters
     pub fn get_datacenters_info(&self) -> HashMap<String, Datacenter> {
@@ -430,6 +517,19 @@ impl ClusterData {
         replica_set.into_iter()
     }
 
+    /// Access the token ring of a keyspace: token ranges covering the whole ring, sorted by
+    /// their ends, together with the replicas owning them according to the keyspace's
+    /// replication strategy (similar to `nodetool describering`).
+    ///
+    /// Replicas of unknown keyspaces are computed as for `SimpleStrategy` with replication factor 1.
+    pub fn token_ring(&self, keyspace: &str) -> Vec<(TokenRange, Vec<Arc<Node>>)> {
+        self.locator
+            .token_ranges()
+            .into_iter()
+            .map(|range| (range, self.get_token_endpoints(keyspace, range.end())))
+            .collect()
+    }
+
     /// Access to replicas owning a given partition key (similar to `nodetool getendpoints`)
     pub fn get_endpoints(
         &self,
@@ -473,17 +573,35 @@ impl ClusterData {
         // By an invariant `self.known_peers` is nonempty, so the returned iterator
         // is nonempty, too.
     }
+
+    /// Returns nonempty list of working connections to all shards of a single node.
+    pub(crate) fn any_working_node_connections(&self) -> Result<Vec<Arc<Connection>>, QueryError> {
+        self.known_peers
+            .values()
+            .map(|node| node.get_working_connections())
+            .find_or_first(Result::is_ok)
+            .expect("impossible: known_peers is nonempty by an invariant")
+    }
 }
 
 impl ClusterWorker {
-    pub(crate) async fn work(mut self) {
-        use tokio::time::Instant;
+    fn background_tasks(&self) -> &BackgroundTasks {
+        &self.pool_config.connection_config.background_tasks
+    }
+
+    fn runtime(&self) -> &Arc<dyn Runtime> {
+        self.pool_config.connection_config.runtime()
+    }
 
+    pub(crate) async fn work(mut self, activity: TaskActivity) {
+        let runtime = self.runtime().clone();
         let control_connection_repair_duration = Duration::from_secs(1); // Attempt control connection repair every second
-        let mut last_refresh_time = Instant::now();
+        let mut last_refresh_time = runtime.now();
+        let mut last_heartbeat_time = runtime.now();
         let mut control_connection_works = true;
 
         loop {
+            activity.touch();
             let mut cur_request: Option<RefreshRequest> = None;
 
             // Wait until it's time for the next refresh
@@ -493,13 +611,31 @@ impl ClusterWorker {
                 } else {
                     control_connection_repair_duration
                 })
-                .unwrap_or_else(Instant::now);
+                .unwrap_or_else(|| runtime.now());
 
-            let sleep_future = tokio::time::sleep_until(sleep_until);
-            tokio::pin!(sleep_future);
+            let sleep_future = runtime.sleep_until(sleep_until);
+
+            let heartbeat_interval = self.metadata_reader.heartbeat_interval();
+            let heartbeat_future = async {
+                match heartbeat_interval {
+                    Some(interval) => runtime.sleep_until(last_heartbeat_time + interval).await,
+                    None => std::future::pending().await,
+                }
+            };
 
             tokio::select! {
                 _ = sleep_future => {},
+                _ = heartbeat_future => {
+                    last_heartbeat_time = runtime.now();
+                    match self.metadata_reader.check_control_connection().await {
+                        Ok(()) => continue, // Don't go to refreshing
+                        Err(err) => {
+                            // Refreshing below moves the control connection to another node,
+                            // which registers for events again.
+                            warn!(error = %err, "Control connection heartbeat failed");
+                        }
+                    }
+                }
                 recv_res = self.refresh_channel.recv() => {
                     match recv_res {
                         Some(request) => cur_request = Some(request),
@@ -516,12 +652,23 @@ impl ClusterWorker {
                                 // later as planned.
 
                                 match status {
-                                    StatusChangeEvent::Down(addr) => self.change_node_down_marker(addr, true),
-                                    StatusChangeEvent::Up(addr) => self.change_node_down_marker(addr, false),
+                                    StatusChangeEvent::Down(addr) => {
+                                        self.change_node_down_marker(addr, true);
+                                    }
+                                    StatusChangeEvent::Up(addr) => {
+                                        if let Some(node) = self.change_node_down_marker(addr, false) {
+                                            self.handle_node_up(node);
+                                        }
+                                    }
                                 }
                                 continue;
                             },
-                            _ => continue, // Don't go to refreshing
+                            Event::SchemaChange(event) => {
+                                if let Some(change) = SchemaChange::from_event(&event) {
+                                    self.handle_schema_change(change);
+                                }
+                                continue; // Don't go to refreshing
+                            }
                         }
                     } else {
                         // If server_events_channel was closed, than TopologyReader was dropped,
@@ -536,7 +683,7 @@ impl ClusterWorker {
 
                             let cluster_data = self.cluster_data.load_full();
                             let use_keyspace_future = Self::handle_use_keyspace_request(cluster_data, request);
-                            tokio::spawn(use_keyspace_future.with_current_subscriber());
+                            self.background_tasks().spawn_detached(BackgroundTaskKind::UseKeyspace, None, use_keyspace_future);
                         },
                         None => return, // If use_keyspace_channel was closed then cluster was dropped, we can stop working
                     }
@@ -567,7 +714,7 @@ impl ClusterWorker {
 
             // Perform the refresh
             debug!("Requesting topology refresh");
-            last_refresh_time = Instant::now();
+            last_refresh_time = runtime.now();
             let refresh_res = self.perform_refresh().await;
 
             control_connection_works = refresh_res.is_ok();
@@ -580,7 +727,8 @@ impl ClusterWorker {
         }
     }
 
-    fn change_node_down_marker(&mut self, addr: SocketAddr, is_down: bool) {
+    // Returns the node with the given address, if it's known.
+    fn change_node_down_marker(&mut self, addr: SocketAddr, is_down: bool) -> Option<Arc<Node>> {
         let cluster_data = self.cluster_data.load_full();
 
         // We need to iterate through the whole map here, but there will rarely more than ~100 nodes,
@@ -594,11 +742,118 @@ impl ClusterWorker {
             Some(node) => node,
             None => {
                 warn!("Unknown node address {}", addr);
-                return;
+                return None;
             }
         };
 
         node.change_down_marker(is_down);
+        Some(node.clone())
+    }
+
+    fn handle_node_up(&self, node: Arc<Node>) {
+        if !self.prepare_on_all_hosts {
+            return;
+        }
+        let statements = self.prepared_statements.statements_in_use();
+        if statements.is_empty() {
+            return;
+        }
+
+        let node_address = node.address.into_inner();
+        let runtime = self.runtime().clone();
+        let reprepare_future = async move {
+            // The node may come up before the pool reconnects to it
+            let connections = runtime::timeout(
+                runtime.as_ref(),
+                NODE_UP_PREPARE_TIMEOUT,
+                node.wait_until_pool_ready(),
+            )
+            .await
+            .map_err(|_| "timed out waiting for connections".to_owned())
+            .and_then(|()| {
+                node.get_working_connections()
+                    .map_err(|err| err.to_string())
+            });
+            match connections {
+                Ok(connections) => {
+                    Self::reprepare_statements(connections, statements, "after a node came up")
+                        .await
+                }
+                Err(err) => debug!(
+                    "Could not prepare statements on node {} after it came up: {}",
+                    node.address, err
+                ),
+            }
+        };
+        self.background_tasks().spawn_detached(
+            BackgroundTaskKind::Reprepare,
+            Some(node_address),
+            reprepare_future,
+        );
+    }
+
+    fn handle_schema_change(&self, change: SchemaChange) {
+        self.prepared_statements
+            .mark_result_metadata_changed(&change);
+        if change.dropped {
+            self.prepared_statements.mark_stale(&change);
+        } else {
+            let statements = self.prepared_statements.affected_statements(&change);
+            if !statements.is_empty() {
+                let cluster_data = self.cluster_data.load_full();
+                let reprepare_future = async move {
+                    match cluster_data.iter_working_connections() {
+                        Ok(connections) => {
+                            let connections = connections.collect();
+                            Self::reprepare_statements(
+                                connections,
+                                statements,
+                                "after a schema change",
+                            )
+                            .await
+                        }
+                        Err(err) => debug!(
+                            "Could not re-prepare statements after a schema change: {}",
+                            err
+                        ),
+                    }
+                };
+                self.background_tasks().spawn_detached(
+                    BackgroundTaskKind::Reprepare,
+                    None,
+                    reprepare_future,
+                );
+            }
+        }
+
+        // Nobody may be listening
+        let _ = self.schema_changes.send(change);
+    }
+
+    // Prepares the statements again on the connections, so that their executions
+    // don't have to re-prepare them after the database invalidated or forgot them.
+    async fn reprepare_statements(
+        connections: Vec<Arc<Connection>>,
+        statements: Vec<String>,
+        occasion: &str,
+    ) {
+        let queries: Vec<Query> = statements.into_iter().map(Query::new).collect();
+        let reprepare_futures: Vec<_> = connections
+            .iter()
+            .flat_map(|connection| {
+                queries
+                    .iter()
+                    .map(move |query| async move { (query, connection.prepare(query).await) })
+            })
+            .collect();
+        for (query, result) in join_all(reprepare_futures).await {
+            if let Err(err) = result {
+                debug!(
+                    "Failed to re-prepare statement {} {}: {}",
+                    query.contents, occasion, err
+                );
+            }
+        }
     }
 
     async fn handle_use_keyspace_request(
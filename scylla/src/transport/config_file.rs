@@ -0,0 +1,1000 @@
+//! Session configuration which can be deserialized from a configuration file.
+//!
+//! [`SessionConfigFile`] mirrors the options of [`SessionBuilder`] in a form which can
+//! be deserialized with [serde](https://serde.rs), so that the configuration of a session
+//! can be kept outside of the code and differ between environments. It is applied
+//! to a builder with [`SessionBuilder::apply_config`].
+//!
+//! YAML (and JSON, which is a subset of it) files can be loaded directly with
+//! [`SessionConfigFile::from_file`]. Other formats, like TOML, can be parsed with
+//! their serde implementations, because [`SessionConfigFile`] implements `Deserialize`.
+//!
+//! ```yaml
+//! known_nodes: ["10.0.0.1:9042", "10.0.0.2:9042"]
+//! compression: lz4
+//! keyspace:
+//!   name: my_keyspace
+//! user:
+//!   username: scylla
+//!   password: ${SCYLLA_PASSWORD}
+//! pool_size:
+//!   kind: per_shard
+//!   size: 2
+//! connection_timeout_ms: 3000
+//! default_execution_profile: local
+//! execution_profiles:
+//!   local:
+//!     consistency: LOCAL_QUORUM
+//!     request_timeout_ms: 5000
+//!     load_balancing:
+//!       preferred_datacenter: dc1
+//!   analytics:
+//!     consistency: ONE
+//!     request_timeout_ms: 0
+//!     retry_policy: fallthrough
+//! ```
+//!
+//! Unknown fields are rejected, so that a typo doesn't silently leave an option unset.
+//! Options which aren't present in the file keep their defaults.
+//!
+//! The username and the password may refer to environment variables with `${NAME}`,
+//! which is resolved when the configuration is applied, so that secrets don't have
+//! to be stored in the file.
+//!
+//! Options taking user-provided implementations of traits can't be set in a file
+//! and have to be set on the builder: the address translator, the host filter,
+//! the authenticator provider, request interceptors, the slow query listener
+//! and the metrics observer.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::IpAddr;
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use scylla_cql::errors::BadKeyspaceName;
+use scylla_cql::frame::types::SerialConsistency;
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::Level;
+
+use crate::load_balancing::{DefaultPolicy, LatencyAwarenessBuilder};
+use crate::retry_policy::{DefaultRetryPolicy, FallthroughRetryPolicy, RetryPolicy};
+use crate::speculative_execution::{
+    PercentileSpeculativeExecutionPolicy, SimpleSpeculativeExecutionPolicy,
+    SpeculativeExecutionPolicy,
+};
+use crate::statement::Consistency;
+use crate::transport::connection::VerifiedKeyspaceName;
+use crate::transport::connection_pool::{PoolSize, StreamExhaustionPolicy};
+use crate::transport::control_connection_policy::ControlConnectionPolicy;
+use crate::transport::downgrading_consistency_retry_policy::DowngradingConsistencyRetryPolicy;
+use crate::transport::execution_profile::{ExecutionProfile, ExecutionProfileHandle};
+use crate::transport::priority::{PriorityMode, PriorityScheduling, RequestPriority};
+use crate::transport::session_builder::SessionBuilder;
+use crate::transport::{AddressFamilyPreference, Compression};
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum ConfigFileError {
+    #[error("Error while reading session config file: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Error while parsing session config: {0}")]
+    Parse(#[from] serde_yaml::Error),
+
+    #[error("Environment variable {0} referenced by session config is not set")]
+    MissingEnvVar(String),
+
+    #[error("Invalid keyspace name in session config: {0}")]
+    BadKeyspaceName(#[from] BadKeyspaceName),
+
+    #[error("Error during session config validation: {0}")]
+    Validation(String),
+
+    #[cfg(feature = "ssl")]
+    #[error("Error while loading TLS certificates or keys: {0}")]
+    Ssl(#[from] openssl::error::ErrorStack),
+}
+
+/// Configuration of a session, which can be deserialized from a configuration file.
+///
+/// Each field corresponds to the [`SessionBuilder`] method of the same name,
+/// with durations given in milliseconds in fields with the `_ms` suffix.
+/// See the [module documentation](self) for an example.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct SessionConfigFile {
+    /// Addresses of the nodes to connect to, as `hostname:port`.
+    #[serde(default)]
+    pub known_nodes: Vec<String>,
+    /// Compression of the connections: `lz4` or `snappy`.
+    pub compression: Option<Compression>,
+    /// Keyspace to use on all connections.
+    pub keyspace: Option<KeyspaceConfig>,
+    /// Credentials for plain text authentication.
+    pub user: Option<UserConfig>,
+    /// TLS configuration. Requires the `ssl` feature.
+    pub tls: Option<TlsConfig>,
+    pub pool_size: Option<PoolSizeConfig>,
+    pub max_requests_per_connection: Option<NonZeroUsize>,
+    pub stream_exhaustion_policy: Option<StreamExhaustionPolicyConfig>,
+    pub priority_scheduling: Option<PrioritySchedulingConfig>,
+    pub connection_timeout_ms: Option<u64>,
+    /// `prefer_ipv4`, `prefer_ipv6`, `ipv4_only` or `ipv6_only`.
+    pub address_family_preference: Option<AddressFamilyPreference>,
+    pub hostname_resolution_interval_ms: Option<u64>,
+    pub connection_attempt_delay_ms: Option<u64>,
+    pub disallow_shard_aware_port: Option<bool>,
+    pub tcp_nodelay: Option<bool>,
+    pub tcp_keepalive_interval_ms: Option<u64>,
+    pub keepalive_interval_ms: Option<u64>,
+    pub keepalive_timeout_ms: Option<u64>,
+    pub write_coalescing: Option<bool>,
+    pub write_coalescing_buffer_size: Option<usize>,
+    pub schema_agreement_interval_ms: Option<u64>,
+    pub schema_agreement_timeout_ms: Option<u64>,
+    pub auto_await_schema_agreement: Option<bool>,
+    pub refresh_metadata_on_auto_schema_agreement: Option<bool>,
+    pub prepare_on_all_hosts: Option<bool>,
+    pub default_consistency: Option<Consistency>,
+    pub default_serial_consistency: Option<SerialConsistency>,
+    pub keyspaces_to_fetch: Option<Vec<String>>,
+    pub fetch_schema_metadata: Option<bool>,
+    pub cluster_metadata_refresh_interval_ms: Option<u64>,
+    pub control_connection: Option<ControlConnectionConfig>,
+    pub tracing_info_fetch_attempts: Option<NonZeroU32>,
+    pub tracing_info_fetch_interval_ms: Option<u64>,
+    pub tracing_info_fetch_consistency: Option<Consistency>,
+    pub slow_query_threshold_ms: Option<u64>,
+    /// Level of the request spans: `trace`, `debug`, `info`, `warn` or `error`.
+    pub request_span_level: Option<String>,
+    pub attach_statement_to_errors: Option<bool>,
+    pub error_statement_max_length: Option<usize>,
+    pub buffer_pool_max_retained_bytes: Option<usize>,
+    pub zero_copy_blobs: Option<bool>,
+    pub include_frame_bytes_in_errors: Option<bool>,
+    pub custom_startup_options: Option<HashMap<String, String>>,
+    pub force_reserved_startup_options: Option<bool>,
+    pub skip_result_metadata: Option<bool>,
+    pub check_unprepared_bind_markers: Option<bool>,
+    pub ddl_ignore_already_exists: Option<bool>,
+    pub max_batch_serialized_size: Option<usize>,
+    pub max_request_frame_size: Option<usize>,
+    pub max_response_frame_size: Option<usize>,
+    /// Name of the profile from `execution_profiles` to use as the default one.
+    pub default_execution_profile: Option<String>,
+    /// Named execution profiles, available with [`SessionConfigFile::execution_profile_handles`].
+    #[serde(default)]
+    pub execution_profiles: HashMap<String, ExecutionProfileConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct KeyspaceConfig {
+    pub name: String,
+    #[serde(default)]
+    pub case_sensitive: bool,
+}
+
+/// Credentials, in which `${NAME}` is replaced with the value of the environment variable `NAME`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct UserConfig {
+    pub username: String,
+    pub password: String,
+}
+
+/// Paths to PEM files used to set up TLS connections.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct TlsConfig {
+    /// Certificate of the authority which signed the certificates of the nodes.
+    pub ca_file: Option<String>,
+    /// Client certificate, requires `key_file`.
+    pub cert_file: Option<String>,
+    /// Private key of the client certificate.
+    pub key_file: Option<String>,
+    /// Whether the certificates of the nodes are verified, `true` by default.
+    #[serde(default = "default_verify_peer")]
+    pub verify_peer: bool,
+}
+
+fn default_verify_peer() -> bool {
+    true
+}
+
+/// Size of the connection pools, e.g. `{kind: per_shard, size: 2}`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(tag = "kind", content = "size", rename_all = "snake_case")]
+pub enum PoolSizeConfig {
+    PerHost(NonZeroUsize),
+    PerShard(NonZeroUsize),
+}
+
+/// Policy for requests which find all stream ids of the connection in use,
+/// e.g. `{type: queue, max_queued: 1024, timeout_ms: 500}` or `{type: fail_fast}`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum StreamExhaustionPolicyConfig {
+    FailFast,
+    Queue { max_queued: usize, timeout_ms: u64 },
+}
+
+/// Dispatching of requests by their priority, e.g.
+/// `{mode: {type: weighted, high: 10, normal: 5, low: 1}, max_in_flight: {low: 64}}`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct PrioritySchedulingConfig {
+    pub mode: PriorityModeConfig,
+    /// In-flight caps of the classes on a single connection, larger than 0.
+    #[serde(default)]
+    pub max_in_flight: PriorityClassesConfig,
+}
+
+/// `{type: strict}` or `{type: weighted, high: 10, normal: 5, low: 1}`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PriorityModeConfig {
+    Strict,
+    Weighted { high: u32, normal: u32, low: u32 },
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct PriorityClassesConfig {
+    pub high: Option<usize>,
+    pub normal: Option<usize>,
+    pub low: Option<usize>,
+}
+
+/// Configuration of the [`ControlConnectionPolicy`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ControlConnectionConfig {
+    pub preferred_datacenter: Option<String>,
+    #[serde(default)]
+    pub excluded_nodes: Vec<IpAddr>,
+    pub unhealthy_threshold_ms: Option<u64>,
+    pub heartbeat_interval_ms: Option<u64>,
+}
+
+/// Configuration of an [`ExecutionProfile`]. Options which aren't set keep their defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ExecutionProfileConfig {
+    pub consistency: Option<Consistency>,
+    pub serial_consistency: Option<SerialConsistency>,
+    /// Client-side request timeout, 0 disables it.
+    pub request_timeout_ms: Option<u64>,
+    pub load_balancing: Option<LoadBalancingConfig>,
+    pub retry_policy: Option<RetryPolicyConfig>,
+    pub speculative_execution: Option<SpeculativeExecutionConfig>,
+    /// `high`, `normal` or `low`.
+    pub priority: Option<RequestPriority>,
+}
+
+/// Configuration of the [`DefaultPolicy`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct LoadBalancingConfig {
+    pub preferred_datacenter: Option<String>,
+    /// Requires `preferred_datacenter`.
+    pub preferred_rack: Option<String>,
+    pub token_aware: Option<bool>,
+    pub permit_dc_failover: Option<bool>,
+    pub shuffle_replicas: Option<bool>,
+    pub lwt_optimisation: Option<bool>,
+    pub latency_awareness: Option<LatencyAwarenessConfig>,
+}
+
+/// Configuration of the latency awareness of the [`DefaultPolicy`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct LatencyAwarenessConfig {
+    pub exclusion_threshold: Option<f64>,
+    pub retry_period_ms: Option<u64>,
+    pub update_rate_ms: Option<u64>,
+    pub minimum_measurements: Option<usize>,
+    pub scale_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryPolicyConfig {
+    Default,
+    Fallthrough,
+    DowngradingConsistency,
+}
+
+/// Speculative execution policy, e.g. `{type: simple, max_retry_count: 2, retry_interval_ms: 100}`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SpeculativeExecutionConfig {
+    Simple {
+        max_retry_count: usize,
+        retry_interval_ms: u64,
+    },
+    Percentile {
+        max_retry_count: usize,
+        percentile: f64,
+    },
+}
+
+impl SessionConfigFile {
+    /// Parses the configuration from YAML or JSON.
+    pub fn from_yaml_str(config: &str) -> Result<Self, ConfigFileError> {
+        Ok(serde_yaml::from_str(config)?)
+    }
+
+    /// Reads the configuration from a YAML or JSON file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigFileError> {
+        let file = std::fs::File::open(path)?;
+        Ok(serde_yaml::from_reader(file)?)
+    }
+
+    /// Builds the execution profiles from `execution_profiles`, labelled with their names.
+    pub fn execution_profile_handles(
+        &self,
+    ) -> Result<HashMap<String, ExecutionProfileHandle>, ConfigFileError> {
+        self.execution_profiles
+            .iter()
+            .map(|(name, profile)| {
+                let handle = profile.build()?.into_handle_with_label(name.clone());
+                Ok((name.clone(), handle))
+            })
+            .collect()
+    }
+
+    pub(crate) fn apply(
+        &self,
+        mut builder: SessionBuilder,
+    ) -> Result<SessionBuilder, ConfigFileError> {
+        builder = builder.known_nodes(&self.known_nodes);
+        if let Some(compression) = self.compression {
+            builder = builder.compression(Some(compression));
+        }
+        if let Some(keyspace) = &self.keyspace {
+            VerifiedKeyspaceName::new(keyspace.name.clone(), keyspace.case_sensitive)?;
+            builder = builder.use_keyspace(&keyspace.name, keyspace.case_sensitive);
+        }
+        if let Some(user) = &self.user {
+            builder = builder.user(
+                resolve_env_vars(&user.username)?,
+                resolve_env_vars(&user.password)?,
+            );
+        }
+        if let Some(tls) = &self.tls {
+            builder = apply_tls(builder, tls)?;
+        }
+        if let Some(pool_size) = self.pool_size {
+            builder = builder.pool_size(match pool_size {
+                PoolSizeConfig::PerHost(size) => PoolSize::PerHost(size),
+                PoolSizeConfig::PerShard(size) => PoolSize::PerShard(size),
+            });
+        }
+        if let Some(max) = self.max_requests_per_connection {
+            builder = builder.max_requests_per_connection(max);
+        }
+        if let Some(policy) = self.stream_exhaustion_policy {
+            builder = builder.stream_exhaustion_policy(match policy {
+                StreamExhaustionPolicyConfig::FailFast => StreamExhaustionPolicy::FailFast,
+                StreamExhaustionPolicyConfig::Queue {
+                    max_queued,
+                    timeout_ms,
+                } => StreamExhaustionPolicy::Queue {
+                    max_queued,
+                    timeout: millis(timeout_ms),
+                },
+            });
+        }
+        if let Some(scheduling) = &self.priority_scheduling {
+            builder = builder.priority_scheduling(scheduling.build()?);
+        }
+        if let Some(timeout) = self.connection_timeout_ms {
+            builder = builder.connection_timeout(millis(timeout));
+        }
+        if let Some(preference) = self.address_family_preference {
+            builder = builder.address_family_preference(preference);
+        }
+        if let Some(interval) = self.hostname_resolution_interval_ms {
+            builder = builder.hostname_resolution_interval(millis(interval));
+        }
+        if let Some(delay) = self.connection_attempt_delay_ms {
+            builder = builder.connection_attempt_delay(millis(delay));
+        }
+        if let Some(disallow) = self.disallow_shard_aware_port {
+            builder = builder.disallow_shard_aware_port(disallow);
+        }
+        if let Some(nodelay) = self.tcp_nodelay {
+            builder = builder.tcp_nodelay(nodelay);
+        }
+        if let Some(interval) = self.tcp_keepalive_interval_ms {
+            builder = builder.tcp_keepalive_interval(millis(interval));
+        }
+        if let Some(interval) = self.keepalive_interval_ms {
+            builder = builder.keepalive_interval(millis(interval));
+        }
+        if let Some(timeout) = self.keepalive_timeout_ms {
+            builder = builder.keepalive_timeout(millis(timeout));
+        }
+        if let Some(enable) = self.write_coalescing {
+            builder = builder.write_coalescing(enable);
+        }
+        if let Some(size) = self.write_coalescing_buffer_size {
+            builder = builder.write_coalescing_buffer_size(size);
+        }
+        if let Some(interval) = self.schema_agreement_interval_ms {
+            builder = builder.schema_agreement_interval(millis(interval));
+        }
+        if let Some(timeout) = self.schema_agreement_timeout_ms {
+            builder = builder.schema_agreement_timeout(millis(timeout));
+        }
+        if let Some(enabled) = self.auto_await_schema_agreement {
+            builder = builder.auto_await_schema_agreement(enabled);
+        }
+        if let Some(refresh) = self.refresh_metadata_on_auto_schema_agreement {
+            builder = builder.refresh_metadata_on_auto_schema_agreement(refresh);
+        }
+        if let Some(prepare_on_all_hosts) = self.prepare_on_all_hosts {
+            builder = builder.prepare_on_all_hosts(prepare_on_all_hosts);
+        }
+        if let Some(consistency) = self.default_consistency {
+            builder = builder.default_consistency(consistency);
+        }
+        if let Some(serial_consistency) = self.default_serial_consistency {
+            builder = builder.default_serial_consistency(Some(serial_consistency));
+        }
+        if let Some(keyspaces) = &self.keyspaces_to_fetch {
+            builder = builder.keyspaces_to_fetch(keyspaces.iter().cloned());
+        }
+        if let Some(fetch) = self.fetch_schema_metadata {
+            builder = builder.fetch_schema_metadata(fetch);
+        }
+        if let Some(interval) = self.cluster_metadata_refresh_interval_ms {
+            builder = builder.cluster_metadata_refresh_interval(millis(interval));
+        }
+        if let Some(control_connection) = &self.control_connection {
+            builder = builder.control_connection_policy(control_connection.build());
+        }
+        if let Some(attempts) = self.tracing_info_fetch_attempts {
+            builder = builder.tracing_info_fetch_attempts(attempts);
+        }
+        if let Some(interval) = self.tracing_info_fetch_interval_ms {
+            builder = builder.tracing_info_fetch_interval(millis(interval));
+        }
+        if let Some(consistency) = self.tracing_info_fetch_consistency {
+            builder = builder.tracing_info_fetch_consistency(consistency);
+        }
+        if let Some(threshold) = self.slow_query_threshold_ms {
+            builder = builder.slow_query_threshold(millis(threshold));
+        }
+        if let Some(level) = &self.request_span_level {
+            let level: Level = level.parse().map_err(|_| {
+                ConfigFileError::Validation(format!("invalid request span level {:?}", level))
+            })?;
+            builder = builder.request_span_level(level);
+        }
+        if let Some(attach) = self.attach_statement_to_errors {
+            builder = builder.attach_statement_to_errors(attach);
+        }
+        if let Some(max_length) = self.error_statement_max_length {
+            builder = builder.error_statement_max_length(max_length);
+        }
+        if let Some(max_retained_bytes) = self.buffer_pool_max_retained_bytes {
+            builder = builder.buffer_pool_max_retained_bytes(max_retained_bytes);
+        }
+        if let Some(enabled) = self.zero_copy_blobs {
+            builder = builder.zero_copy_blobs(enabled);
+        }
+        if let Some(enabled) = self.include_frame_bytes_in_errors {
+            builder = builder.include_frame_bytes_in_errors(enabled);
+        }
+        if let Some(options) = self.custom_startup_options.clone() {
+            builder = builder.custom_startup_options(options);
+        }
+        if let Some(force) = self.force_reserved_startup_options {
+            builder = builder.force_reserved_startup_options(force);
+        }
+        if let Some(enabled) = self.skip_result_metadata {
+            builder = builder.skip_result_metadata(enabled);
+        }
+        if let Some(enabled) = self.check_unprepared_bind_markers {
+            builder = builder.check_unprepared_bind_markers(enabled);
+        }
+        if let Some(enabled) = self.ddl_ignore_already_exists {
+            builder = builder.ddl_ignore_already_exists(enabled);
+        }
+        if let Some(max_size) = self.max_batch_serialized_size {
+            builder = builder.max_batch_serialized_size(max_size);
+        }
+        if let Some(max_size) = self.max_request_frame_size {
+            builder = builder.max_request_frame_size(max_size);
+        }
+        if let Some(max_size) = self.max_response_frame_size {
+            builder = builder.max_response_frame_size(max_size);
+        }
+
+        let mut profiles = self.execution_profile_handles()?;
+        if let Some(name) = &self.default_execution_profile {
+            let profile = profiles.remove(name).ok_or_else(|| {
+                ConfigFileError::Validation(format!(
+                    "default execution profile {:?} is not defined in execution_profiles",
+                    name
+                ))
+            })?;
+            builder = builder.default_execution_profile_handle(profile);
+        }
+
+        Ok(builder)
+    }
+}
+
+impl ControlConnectionConfig {
+    fn build(&self) -> ControlConnectionPolicy {
+        let mut policy = ControlConnectionPolicy::new();
+        if let Some(datacenter) = &self.preferred_datacenter {
+            policy = policy.preferred_datacenter(datacenter.clone());
+        }
+        for address in &self.excluded_nodes {
+            policy = policy.exclude_node(*address);
+        }
+        if let Some(threshold) = self.unhealthy_threshold_ms {
+            policy = policy.unhealthy_threshold(millis(threshold));
+        }
+        if let Some(interval) = self.heartbeat_interval_ms {
+            policy = policy.heartbeat_interval(millis(interval));
+        }
+        policy
+    }
+}
+
+impl PrioritySchedulingConfig {
+    fn build(&self) -> Result<PriorityScheduling, ConfigFileError> {
+        let mut scheduling = PriorityScheduling::new(match self.mode {
+            PriorityModeConfig::Strict => PriorityMode::Strict,
+            PriorityModeConfig::Weighted { high, normal, low } => {
+                PriorityMode::Weighted { high, normal, low }
+            }
+        });
+        let caps = [
+            (RequestPriority::High, self.max_in_flight.high),
+            (RequestPriority::Normal, self.max_in_flight.normal),
+            (RequestPriority::Low, self.max_in_flight.low),
+        ];
+        for (priority, cap) in caps {
+            match cap {
+                Some(0) => {
+                    return Err(ConfigFileError::Validation(format!(
+                        "in-flight cap of {} priority requests must be larger than 0",
+                        priority
+                    )))
+                }
+                Some(max) => scheduling = scheduling.max_in_flight(priority, max),
+                None => {}
+            }
+        }
+        Ok(scheduling)
+    }
+}
+
+impl ExecutionProfileConfig {
+    /// Builds the execution profile, with the options which aren't set left at their defaults.
+    pub fn build(&self) -> Result<ExecutionProfile, ConfigFileError> {
+        let mut builder = ExecutionProfile::builder();
+        if let Some(consistency) = self.consistency {
+            builder = builder.consistency(consistency);
+        }
+        if let Some(serial_consistency) = self.serial_consistency {
+            builder = builder.serial_consistency(Some(serial_consistency));
+        }
+        if let Some(timeout) = self.request_timeout_ms {
+            builder = builder.request_timeout((timeout != 0).then(|| millis(timeout)));
+        }
+        if let Some(load_balancing) = &self.load_balancing {
+            builder = builder.load_balancing_policy(load_balancing.build()?);
+        }
+        if let Some(priority) = self.priority {
+            builder = builder.priority(priority);
+        }
+        if let Some(retry_policy) = self.retry_policy {
+            let retry_policy: Box<dyn RetryPolicy> = match retry_policy {
+                RetryPolicyConfig::Default => Box::new(DefaultRetryPolicy::new()),
+                RetryPolicyConfig::Fallthrough => Box::new(FallthroughRetryPolicy::new()),
+                RetryPolicyConfig::DowngradingConsistency => {
+                    Box::new(DowngradingConsistencyRetryPolicy::new())
+                }
+            };
+            builder = builder.retry_policy(retry_policy);
+        }
+        if let Some(speculative_execution) = &self.speculative_execution {
+            let policy: Arc<dyn SpeculativeExecutionPolicy> = match *speculative_execution {
+                SpeculativeExecutionConfig::Simple {
+                    max_retry_count,
+                    retry_interval_ms,
+                } => Arc::new(SimpleSpeculativeExecutionPolicy {
+                    max_retry_count,
+                    retry_interval: millis(retry_interval_ms),
+                }),
+                SpeculativeExecutionConfig::Percentile {
+                    max_retry_count,
+                    percentile,
+                } => {
+                    if !(0.0..=100.0).contains(&percentile) {
+                        return Err(ConfigFileError::Validation(format!(
+                            "speculative execution percentile {} is not between 0 and 100",
+                            percentile
+                        )));
+                    }
+                    Arc::new(PercentileSpeculativeExecutionPolicy {
+                        max_retry_count,
+                        percentile,
+                    })
+                }
+            };
+            builder = builder.speculative_execution_policy(Some(policy));
+        }
+        Ok(builder.build())
+    }
+}
+
+impl LoadBalancingConfig {
+    fn build(
+        &self,
+    ) -> Result<Arc<dyn crate::load_balancing::LoadBalancingPolicy>, ConfigFileError> {
+        let mut builder = DefaultPolicy::builder();
+        match (&self.preferred_datacenter, &self.preferred_rack) {
+            (Some(datacenter), Some(rack)) => {
+                builder = builder.prefer_datacenter_and_rack(datacenter.clone(), rack.clone());
+            }
+            (Some(datacenter), None) => {
+                builder = builder.prefer_datacenter(datacenter.clone());
+            }
+            (None, Some(_)) => {
+                return Err(ConfigFileError::Validation(
+                    "preferred_rack requires preferred_datacenter".to_owned(),
+                ));
+            }
+            (None, None) => {}
+        }
+        if let Some(token_aware) = self.token_aware {
+            builder = builder.token_aware(token_aware);
+        }
+        if let Some(permit) = self.permit_dc_failover {
+            builder = builder.permit_dc_failover(permit);
+        }
+        if let Some(enable) = self.shuffle_replicas {
+            builder = builder.enable_shuffling_replicas(enable);
+        }
+        if let Some(enable) = self.lwt_optimisation {
+            builder = builder.enable_lwt_optimisation(enable);
+        }
+        if let Some(latency_awareness) = &self.latency_awareness {
+            builder = builder.latency_awareness(latency_awareness.builder());
+        }
+        Ok(builder.build())
+    }
+}
+
+impl LatencyAwarenessConfig {
+    fn builder(&self) -> LatencyAwarenessBuilder {
+        let mut builder = LatencyAwarenessBuilder::new();
+        if let Some(threshold) = self.exclusion_threshold {
+            builder = builder.exclusion_threshold(threshold);
+        }
+        if let Some(period) = self.retry_period_ms {
+            builder = builder.retry_period(millis(period));
+        }
+        if let Some(rate) = self.update_rate_ms {
+            builder = builder.update_rate(millis(rate));
+        }
+        if let Some(minimum) = self.minimum_measurements {
+            builder = builder.minimum_measurements(minimum);
+        }
+        if let Some(scale) = self.scale_ms {
+            builder = builder.scale(millis(scale));
+        }
+        builder
+    }
+}
+
+#[cfg(feature = "ssl")]
+fn apply_tls(builder: SessionBuilder, tls: &TlsConfig) -> Result<SessionBuilder, ConfigFileError> {
+    use openssl::ssl::{SslContextBuilder, SslFiletype, SslMethod, SslVerifyMode};
+
+    let mut context = SslContextBuilder::new(SslMethod::tls())?;
+    if let Some(ca_file) = &tls.ca_file {
+        context.set_ca_file(ca_file)?;
+    }
+    match (&tls.cert_file, &tls.key_file) {
+        (Some(cert_file), Some(key_file)) => {
+            context.set_certificate_file(cert_file, SslFiletype::PEM)?;
+            context.set_private_key_file(key_file, SslFiletype::PEM)?;
+            context.check_private_key()?;
+        }
+        (None, None) => {}
+        _ => {
+            return Err(ConfigFileError::Validation(
+                "cert_file and key_file have to be set together".to_owned(),
+            ));
+        }
+    }
+    context.set_verify(match tls.verify_peer {
+        true => SslVerifyMode::PEER,
+        false => SslVerifyMode::NONE,
+    });
+    Ok(builder.ssl_context(Some(context.build())))
+}
+
+#[cfg(not(feature = "ssl"))]
+fn apply_tls(
+    _builder: SessionBuilder,
+    _tls: &TlsConfig,
+) -> Result<SessionBuilder, ConfigFileError> {
+    Err(ConfigFileError::Validation(
+        "tls requires the ssl feature of the driver".to_owned(),
+    ))
+}
+
+fn millis(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Replaces every `${NAME}` in `value` with the value of the environment variable `NAME`.
+fn resolve_env_vars(value: &str) -> Result<String, ConfigFileError> {
+    let mut resolved = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        resolved.push_str(&rest[..start]);
+        let end = rest[start..].find('}').ok_or_else(|| {
+            ConfigFileError::Validation(format!("unterminated ${{ in {:?}", value))
+        })?;
+        let name = &rest[start + 2..start + end];
+        let var =
+            std::env::var(name).map_err(|_| ConfigFileError::MissingEnvVar(name.to_owned()))?;
+        resolved.push_str(&var);
+        rest = &rest[start + end + 1..];
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigFileError, SessionConfigFile};
+    use crate::statement::{Consistency, SerialConsistency};
+    use crate::transport::connection_pool::{PoolSize, StreamExhaustionPolicy};
+    use crate::transport::priority::{PriorityScheduling, RequestPriority};
+    use crate::transport::{AddressFamilyPreference, Compression};
+    use crate::SessionBuilder;
+    use std::num::NonZeroUsize;
+    use std::time::Duration;
+    use tracing::Level;
+
+    const YAML: &str = r#"
+known_nodes: ["10.0.0.1:9042", "10.0.0.2:9042"]
+compression: lz4
+keyspace:
+  name: My_Keyspace
+  case_sensitive: true
+user:
+  username: scylla
+  password: pass-${SCYLLA_CONFIG_FILE_TEST_PASSWORD}
+pool_size:
+  kind: per_shard
+  size: 2
+connection_timeout_ms: 3000
+address_family_preference: prefer_ipv6
+connection_attempt_delay_ms: 100
+keepalive_interval_ms: 1000
+keyspaces_to_fetch: [ks1, ks2]
+prepare_on_all_hosts: false
+default_consistency: ONE
+default_serial_consistency: LOCAL_SERIAL
+control_connection:
+  preferred_datacenter: dc1
+  excluded_nodes: [10.0.0.2]
+  unhealthy_threshold_ms: 2000
+  heartbeat_interval_ms: 500
+request_span_level: info
+error_statement_max_length: 100
+skip_result_metadata: false
+check_unprepared_bind_markers: true
+ddl_ignore_already_exists: true
+max_response_frame_size: 1048576
+write_coalescing_buffer_size: 65536
+max_requests_per_connection: 2048
+stream_exhaustion_policy:
+  type: queue
+  max_queued: 128
+  timeout_ms: 500
+priority_scheduling:
+  mode: {type: weighted, high: 10, normal: 5, low: 1}
+  max_in_flight: {low: 64}
+default_execution_profile: local
+execution_profiles:
+  local:
+    consistency: LOCAL_QUORUM
+    serial_consistency: LOCAL_SERIAL
+    request_timeout_ms: 5000
+    load_balancing:
+      preferred_datacenter: dc1
+      preferred_rack: rack1
+      token_aware: true
+      lwt_optimisation: false
+      latency_awareness:
+        exclusion_threshold: 3.0
+    retry_policy: downgrading_consistency
+    speculative_execution:
+      type: simple
+      max_retry_count: 2
+      retry_interval_ms: 100
+  analytics:
+    request_timeout_ms: 0
+    priority: low
+    retry_policy: fallthrough
+    speculative_execution:
+      type: percentile
+      max_retry_count: 1
+      percentile: 99.0
+"#;
+
+    // Latency awareness spawns a task, which requires a runtime
+    #[tokio::test]
+    async fn config_is_applied_to_builder() {
+        std::env::set_var("SCYLLA_CONFIG_FILE_TEST_PASSWORD", "secret");
+        let config = SessionConfigFile::from_yaml_str(YAML).unwrap();
+        let builder = SessionBuilder::new().apply_config(&config).unwrap();
+        let session_config = &builder.config;
+
+        assert_eq!(session_config.known_nodes.len(), 2);
+        assert_eq!(session_config.compression, Some(Compression::Lz4));
+        assert_eq!(session_config.used_keyspace.as_deref(), Some("My_Keyspace"));
+        assert!(session_config.keyspace_case_sensitive);
+        assert_eq!(session_config.connect_timeout, Duration::from_millis(3000));
+        assert_eq!(
+            session_config.address_family_preference,
+            AddressFamilyPreference::PreferIpv6
+        );
+        assert_eq!(
+            session_config.connection_attempt_delay,
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            session_config.keepalive_interval,
+            Some(Duration::from_millis(1000))
+        );
+        assert!(matches!(
+            session_config.connection_pool_size,
+            PoolSize::PerShard(size) if size == NonZeroUsize::new(2).unwrap()
+        ));
+        assert_eq!(session_config.keyspaces_to_fetch, ["ks1", "ks2"]);
+        assert!(!session_config.prepare_on_all_hosts);
+        assert_eq!(session_config.default_consistency, Consistency::One);
+        assert_eq!(
+            session_config.default_serial_consistency,
+            Some(SerialConsistency::LocalSerial)
+        );
+        let control_connection_policy = &session_config.control_connection_policy;
+        assert_eq!(
+            control_connection_policy.get_unhealthy_threshold(),
+            Some(Duration::from_millis(2000))
+        );
+        assert_eq!(
+            control_connection_policy.get_heartbeat_interval(),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(session_config.request_span_level, Level::INFO);
+        assert_eq!(session_config.error_statement_max_length, 100);
+        assert!(!session_config.skip_result_metadata);
+        assert!(session_config.check_unprepared_bind_markers);
+        assert!(session_config.ddl_ignore_already_exists);
+        assert_eq!(session_config.max_response_frame_size, Some(1048576));
+        assert_eq!(session_config.write_coalescing_buffer_size, 65536);
+        assert_eq!(session_config.max_requests_per_connection.get(), 2048);
+        assert_eq!(
+            session_config.stream_exhaustion_policy,
+            StreamExhaustionPolicy::Queue {
+                max_queued: 128,
+                timeout: Duration::from_millis(500)
+            }
+        );
+
+        assert_eq!(
+            session_config.priority_scheduling,
+            Some(PriorityScheduling::weighted(10, 5, 1).max_in_flight(RequestPriority::Low, 64))
+        );
+
+        let default_profile = session_config.default_execution_profile_handle.access();
+        assert_eq!(default_profile.consistency, Some(Consistency::LocalQuorum));
+        assert_eq!(
+            default_profile.request_timeout,
+            Some(Duration::from_millis(5000))
+        );
+
+        let profiles = config.execution_profile_handles().unwrap();
+        let analytics = profiles["analytics"].access();
+        assert_eq!(analytics.request_timeout, None);
+        assert_eq!(analytics.priority, RequestPriority::Low);
+    }
+
+    #[test]
+    fn json_is_accepted() {
+        let config = SessionConfigFile::from_yaml_str(
+            r#"{"known_nodes": ["127.0.0.1:9042"], "compression": "snappy", "tcp_nodelay": false}"#,
+        )
+        .unwrap();
+        assert_eq!(config.known_nodes, ["127.0.0.1:9042"]);
+        assert_eq!(config.compression, Some(Compression::Snappy));
+        assert_eq!(config.tcp_nodelay, Some(false));
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        for config in [
+            "known_node: [\"127.0.0.1:9042\"]",
+            "keyspace: {name: ks, case_sensitve: true}",
+            "execution_profiles: {p: {consistncy: ONE}}",
+            "execution_profiles: {p: {retry_policy: sometimes}}",
+        ] {
+            assert!(
+                matches!(
+                    SessionConfigFile::from_yaml_str(config),
+                    Err(ConfigFileError::Parse(_))
+                ),
+                "{}",
+                config
+            );
+        }
+    }
+
+    #[test]
+    fn missing_env_var_is_reported() {
+        let config = SessionConfigFile::from_yaml_str(
+            "user: {username: u, password: \"${SCYLLA_CONFIG_FILE_TEST_UNSET}\"}",
+        )
+        .unwrap();
+        match SessionBuilder::new().apply_config(&config) {
+            Err(ConfigFileError::MissingEnvVar(name)) => {
+                assert_eq!(name, "SCYLLA_CONFIG_FILE_TEST_UNSET")
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected() {
+        for config in [
+            "keyspace: {name: \"bad name\"}",
+            "request_span_level: loud",
+            "default_execution_profile: missing",
+            "execution_profiles: {p: {load_balancing: {preferred_rack: r1}}}",
+            "execution_profiles: {p: {speculative_execution: {type: percentile, max_retry_count: 1, percentile: 120.0}}}",
+            "user: {username: u, password: \"${UNTERMINATED\"}",
+            "priority_scheduling: {mode: {type: strict}, max_in_flight: {high: 0}}",
+            #[cfg(not(feature = "ssl"))]
+            "tls: {}",
+            #[cfg(feature = "ssl")]
+            "tls: {cert_file: client.crt}",
+        ] {
+            let parsed = SessionConfigFile::from_yaml_str(config).unwrap();
+            assert!(
+                SessionBuilder::new().apply_config(&parsed).is_err(),
+                "{}",
+                config
+            );
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use bytes::Bytes;
-use futures::{future::RemoteHandle, FutureExt};
+use futures::stream::FuturesUnordered;
+use futures::{future::RemoteHandle, StreamExt};
 use scylla_cql::errors::TranslationError;
 use scylla_cql::frame::request::options::Options;
 use scylla_cql::frame::response::Error;
@@ -10,16 +11,15 @@ use scylla_cql::types::serialize::row::{RowSerializationContext, SerializedValue
 use socket2::{SockRef, TcpKeepalive};
 use tokio::io::{split, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, BufWriter};
 use tokio::net::{TcpSocket, TcpStream};
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, Semaphore};
 use tokio::time::Instant;
-use tracing::instrument::WithSubscriber;
 use tracing::{debug, error, trace, warn};
 use uuid::Uuid;
 
 use std::borrow::Cow;
 #[cfg(feature = "ssl")]
 use std::pin::Pin;
-use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicU64, AtomicUsize};
 use std::time::Duration;
 #[cfg(feature = "ssl")]
 use tokio_openssl::SslStream;
@@ -40,8 +40,19 @@ use std::{
     net::{Ipv4Addr, Ipv6Addr},
 };
 
-use super::errors::{BadKeyspaceName, DbError, QueryError};
+use super::background_tasks::{BackgroundTaskKind, BackgroundTasks, TaskActivity};
+use super::buffer_pool::{give_back_buffer, take_buffer, BufferPool};
+use super::connection_pool::StreamExhaustionPolicy;
+use super::errors::{BadKeyspaceName, BadQuery, DbError, QueryError};
 use super::iterator::RowIterator;
+use super::metrics::Metrics;
+use super::node::{
+    AddressFamilyPreference, HostnameResolver, NegotiatedSettings, NodeCapabilities,
+    ResolvedContactPoint, SystemHostnameResolver,
+};
+use super::priority::{PriorityScheduler, PriorityScheduling, RequestPriority, StreamPermit};
+use super::repreparation::Repreparations;
+use super::runtime::{self, Interval, Runtime};
 use super::session::AddressTranslator;
 use super::topology::{PeerEndpoint, UntranslatedEndpoint, UntranslatedPeer};
 use super::NodeAddr;
@@ -52,15 +63,22 @@ use crate::batch::{Batch, BatchStatement};
 use crate::frame::protocol_features::ProtocolFeatures;
 use crate::frame::{
     self,
-    request::{self, batch, execute, query, register, SerializableRequest},
-    response::{event::Event, result, NonErrorResponse, Response, ResponseOpcode},
+    frame_errors::{ParseError, ResponseParseError},
+    request::{self, batch, execute, query, register, startup, SerializableRequest},
+    response::{
+        event::Event,
+        result,
+        result::{ResultMetadata, ResultParseOptions},
+        NonErrorResponse, Response, ResponseOpcode,
+    },
     server_event_type::EventType,
-    FrameParams, SerializedRequest,
+    FrameParams, SerializedRequest, PROTOCOL_VERSION,
 };
 use crate::query::Query;
 use crate::routing::ShardInfo;
 use crate::statement::prepared_statement::PreparedStatement;
 use crate::statement::Consistency;
+use crate::transport::query_result::QueryResultBuilder;
 use crate::transport::session::IntoTypedRows;
 use crate::transport::Compression;
 use crate::QueryResult;
@@ -74,18 +92,53 @@ const LOCAL_VERSION: &str = "SELECT schema_version FROM system.local WHERE key='
 // a stream id that is orphaned for a long time. This long time is defined below
 // (`OLD_AGE_ORPHAN_THRESHOLD`). Connection that has a big number (`OLD_ORPHAN_COUNT_THRESHOLD`)
 // of old orphans is shut down (and created again by a connection management layer).
+// The protocol has no message which would let the server know that nobody waits for
+// a response anymore, so an orphaned stream id is freed only when its late response arrives.
 const OLD_ORPHAN_COUNT_THRESHOLD: usize = 1024;
 const OLD_AGE_ORPHAN_THRESHOLD: std::time::Duration = std::time::Duration::from_secs(1);
 
+pub(crate) const DEFAULT_WRITE_COALESCING_BUFFER_SIZE: usize = 8192;
+
+// Sent in the STARTUP options, unless overridden by custom ones.
+const DEFAULT_DRIVER_NAME: &str = "scylla-rust-driver";
+const DEFAULT_DRIVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// The number of stream ids available in protocol v4.
+pub(crate) const MAX_STREAM_IDS: usize = i16::MAX as usize + 1;
+
 pub(crate) struct Connection {
     _worker_handle: RemoteHandle<()>,
 
+    id: u64,
     connect_address: SocketAddr,
     config: ConnectionConfig,
     features: ConnectionFeatures,
     router_handle: Arc<RouterHandle>,
 }
 
+// Source of `Connection::id`s.
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The error a connection is broken with when a keepalive request isn't answered in time.
+#[derive(Debug, thiserror::Error)]
+#[error("Timed out while waiting for response to keepalive request on connection to node {node_address}")]
+pub(crate) struct KeepaliveTimeoutError {
+    pub(crate) node_address: IpAddr,
+}
+
+impl KeepaliveTimeoutError {
+    /// Whether `err` is the error of a connection broken by a keepalive timeout.
+    pub(crate) fn is_cause_of(err: &QueryError) -> bool {
+        match err {
+            QueryError::IoError(io_error) => matches!(
+                io_error.get_ref(),
+                Some(inner) if inner.is::<KeepaliveTimeoutError>()
+            ),
+            _ => false,
+        }
+    }
+}
+
 struct RouterHandle {
     submit_channel: mpsc::Sender<Task>,
 
@@ -98,6 +151,44 @@ struct RouterHandle {
     // pushing values in a synchronous way (without an `.await`), which is
     // needed for pushing values in `Drop` implementations.
     orphan_notification_sender: mpsc::UnboundedSender<RequestId>,
+
+    buffer_pool: Option<Arc<BufferPool>>,
+
+    // Requests with a larger body are rejected without being sent.
+    max_request_frame_size: Option<usize>,
+
+    // Each request holds a permit from the time it's submitted until its stream id
+    // is freed, which for orphaned requests happens only when the late response arrives.
+    // The number of permits is the maximum number of requests in flight.
+    stream_permits: Arc<Semaphore>,
+    stream_exhaustion_policy: StreamExhaustionPolicy,
+    // Number of requests waiting for a permit.
+    queued_requests: AtomicUsize,
+    // Dispatches waiting requests by their priority, `None` if priorities are ignored.
+    scheduler: Option<Arc<PriorityScheduler>>,
+    metrics: Option<Arc<Metrics>>,
+    runtime: Arc<dyn Runtime>,
+}
+
+// Removes the request from the queue, also when it's cancelled while waiting.
+struct QueuedRequestGuard<'a> {
+    queued_requests: &'a AtomicUsize,
+    metrics: Option<&'a Metrics>,
+    // Set if the request is queued in its priority class
+    priority: Option<RequestPriority>,
+}
+
+impl Drop for QueuedRequestGuard<'_> {
+    fn drop(&mut self) {
+        self.queued_requests
+            .fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+        if let Some(metrics) = self.metrics {
+            metrics.dec_queued_requests();
+            if let Some(priority) = self.priority {
+                metrics.priority_class(priority).dec_queued();
+            }
+        }
+    }
 }
 
 impl RouterHandle {
@@ -106,30 +197,131 @@ impl RouterHandle {
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
     }
 
+    fn is_busy(&self) -> bool {
+        self.stream_permits.available_permits() == 0
+    }
+
+    async fn acquire_stream_permit(
+        &self,
+        priority: RequestPriority,
+    ) -> Result<StreamPermit, QueryError> {
+        let immediate = match &self.scheduler {
+            Some(scheduler) => scheduler.try_acquire(priority, &self.stream_permits),
+            None => self
+                .stream_permits
+                .clone()
+                .try_acquire_owned()
+                .ok()
+                .map(StreamPermit::from),
+        };
+        if let Some(permit) = immediate {
+            return Ok(permit);
+        }
+
+        let (max_queued, timeout) = match self.stream_exhaustion_policy {
+            StreamExhaustionPolicy::FailFast => return Err(QueryError::ConnectionBusy),
+            StreamExhaustionPolicy::Queue {
+                max_queued,
+                timeout,
+            } => (max_queued, timeout),
+        };
+
+        let queued = self
+            .queued_requests
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let class_priority = self.scheduler.as_ref().map(|_| priority);
+        if let Some(metrics) = &self.metrics {
+            metrics.inc_queued_requests();
+            if let Some(priority) = class_priority {
+                metrics.priority_class(priority).inc_queued();
+            }
+        }
+        let _guard = QueuedRequestGuard {
+            queued_requests: &self.queued_requests,
+            metrics: self.metrics.as_deref(),
+            priority: class_priority,
+        };
+        if queued >= max_queued {
+            return Err(QueryError::ConnectionBusy);
+        }
+
+        match &self.scheduler {
+            Some(scheduler) => {
+                let acquire = scheduler.acquire(priority, &self.stream_permits);
+                runtime::timeout(self.runtime.as_ref(), timeout, acquire)
+                    .await
+                    .map_err(|_| QueryError::ConnectionBusy)
+            }
+            // The semaphore is fair, so queued requests are sent in order
+            None => {
+                let acquire = self.stream_permits.clone().acquire_owned();
+                match runtime::timeout(self.runtime.as_ref(), timeout, acquire).await {
+                    Ok(Ok(permit)) => Ok(permit.into()),
+                    // The semaphore is never closed
+                    Ok(Err(_)) | Err(_) => Err(QueryError::ConnectionBusy),
+                }
+            }
+        }
+    }
+
     async fn send_request(
         &self,
         request: &impl SerializableRequest,
         compression: Option<Compression>,
         tracing: bool,
+        custom_payload: Option<&HashMap<String, Vec<u8>>>,
+        priority: RequestPriority,
     ) -> Result<TaskResponse, QueryError> {
-        let serialized_request = SerializedRequest::make(request, compression, tracing)?;
+        // The permit is acquired before the frame is serialized, so that requests waiting
+        // for a stream id don't hold serialized frames when the connection is overloaded.
+        let queued_at = Instant::now();
+        let mut stream_permit = self.acquire_stream_permit(priority).await?;
+        if let Some(metrics) = &self.metrics {
+            metrics.record_queue_time(queued_at.elapsed());
+            stream_permit = stream_permit.counted_in(metrics.clone());
+        }
+
+        let serialized_request = SerializedRequest::make_in(
+            take_buffer(self.buffer_pool.as_deref()),
+            request,
+            compression,
+            tracing,
+            custom_payload,
+        )?;
+        if let Some(limit) = self.max_request_frame_size {
+            let size = serialized_request.body_size();
+            if size > limit {
+                give_back_buffer(
+                    self.buffer_pool.as_deref(),
+                    serialized_request.into_buffer(),
+                );
+                return Err(QueryError::BadQuery(BadQuery::RequestTooLarge {
+                    size,
+                    limit,
+                }));
+            }
+        }
         let request_id = self.allocate_request_id();
 
+        // Dropping `notifier` (before calling `notifier.disable()`) will send a notification to
+        // `Connection::router`. This notification is then used to mark a `stream_id` associated
+        // with this request as orphaned and free associated resources.
+        // It's created before `receiver`, so that it's dropped after it: the writer then sees
+        // the request as cancelled if it didn't allocate its stream id before the notification.
+        let notifier = OrphanhoodNotifier::new(request_id, &self.orphan_notification_sender);
+
         let (response_sender, receiver) = oneshot::channel();
         let response_handler = ResponseHandler {
             response_sender,
             request_id,
         };
 
-        // Dropping `notifier` (before calling `notifier.disable()`) will send a notification to
-        // `Connection::router`. This notification is then used to mark a `stream_id` associated
-        // with this request as orphaned and free associated resources.
-        let notifier = OrphanhoodNotifier::new(request_id, &self.orphan_notification_sender);
-
+        let sent_at = Instant::now();
         self.submit_channel
             .send(Task {
                 serialized_request,
                 response_handler,
+                stream_permit,
             })
             .await
             .map_err(|_| {
@@ -149,6 +341,14 @@ impl RouterHandle {
         // Response was successfully received, so it's time to disable
         // notification about orphaning.
         notifier.disable();
+        if let Some(metrics) = &self.metrics {
+            metrics.record_service_time(sent_at.elapsed());
+            if self.scheduler.is_some() {
+                metrics
+                    .priority_class(priority)
+                    .record_latency(queued_at.elapsed());
+            }
+        }
 
         task_response
     }
@@ -159,6 +359,8 @@ pub(crate) struct ConnectionFeatures {
     shard_info: Option<ShardInfo>,
     shard_aware_port: Option<u16>,
     protocol_features: ProtocolFeatures,
+    capabilities: Arc<NodeCapabilities>,
+    startup_options: HashMap<String, String>,
 }
 
 type RequestId = u64;
@@ -204,6 +406,7 @@ impl<'a> Drop for OrphanhoodNotifier<'a> {
 struct Task {
     serialized_request: SerializedRequest,
     response_handler: ResponseHandler,
+    stream_permit: StreamPermit,
 }
 
 struct TaskResponse {
@@ -255,14 +458,27 @@ impl NonErrorQueryResponse {
     }
 
     pub(crate) fn into_query_result(self) -> Result<QueryResult, QueryError> {
-        let (rows, paging_state, col_specs, serialized_size) = match self.response {
-            NonErrorResponse::Result(result::Result::Rows(rs)) => (
-                Some(rs.rows),
-                rs.metadata.paging_state,
-                rs.metadata.col_specs,
-                rs.serialized_size,
-            ),
-            NonErrorResponse::Result(_) => (None, None, vec![], 0),
+        let mut builder = QueryResult::mock_builder().warnings(self.warnings);
+        if let Some(tracing_id) = self.tracing_id {
+            builder = builder.tracing_id(tracing_id);
+        }
+        builder = match self.response {
+            NonErrorResponse::Result(result::Result::Rows(rs)) => {
+                let mut builder = rs
+                    .metadata
+                    .col_specs
+                    .into_iter()
+                    .fold(builder, QueryResultBuilder::col_spec)
+                    .received_rows(rs.rows, rs.decode_errors, rs.serialized_size);
+                if let Some(paging_state) = rs.metadata.paging_state {
+                    builder = builder.paging_state(paging_state);
+                }
+                builder
+            }
+            NonErrorResponse::Result(result::Result::SchemaChange(sc)) => {
+                builder.schema_change(sc.event)
+            }
+            NonErrorResponse::Result(_) => builder,
             _ => {
                 return Err(QueryError::ProtocolError(
                     "Unexpected server response, expected Result or Error",
@@ -270,16 +486,84 @@ impl NonErrorQueryResponse {
             }
         };
 
-        Ok(QueryResult {
-            rows,
-            warnings: self.warnings,
-            tracing_id: self.tracing_id,
-            paging_state,
-            col_specs,
-            serialized_size,
+        builder.build().map_err(|_| {
+            QueryError::ProtocolError(
+                "Received rows have a different number of values than columns",
+            )
         })
     }
 }
+
+// Server warnings are logged along with a prefix of the statement that caused them.
+// A workload that keeps triggering the same warning could easily flood the logs,
+// so at most `MAX_WARNINGS_LOGGED_PER_INTERVAL` warnings are logged in each
+// `WARNINGS_LOG_INTERVAL`. Warnings over the limit are only counted and the count
+// is reported once the next interval starts.
+const WARNINGS_LOG_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_WARNINGS_LOGGED_PER_INTERVAL: usize = 10;
+const WARNING_STATEMENT_PREFIX_LEN: usize = 100;
+
+struct WarningsLogLimiter {
+    interval_start: Option<std::time::Instant>,
+    logged_in_interval: usize,
+    suppressed: usize,
+}
+
+static WARNINGS_LOG_LIMITER: StdMutex<WarningsLogLimiter> = StdMutex::new(WarningsLogLimiter {
+    interval_start: None,
+    logged_in_interval: 0,
+    suppressed: 0,
+});
+
+fn log_server_warnings(warnings: &[String], statement: &str) {
+    if warnings.is_empty() {
+        return;
+    }
+
+    let (to_log, previously_suppressed) = {
+        let mut limiter = WARNINGS_LOG_LIMITER.lock().unwrap();
+        let now = std::time::Instant::now();
+        let interval_elapsed = match limiter.interval_start {
+            Some(start) => now.duration_since(start) >= WARNINGS_LOG_INTERVAL,
+            None => true,
+        };
+        let mut previously_suppressed = 0;
+        if interval_elapsed {
+            previously_suppressed = limiter.suppressed;
+            *limiter = WarningsLogLimiter {
+                interval_start: Some(now),
+                logged_in_interval: 0,
+                suppressed: 0,
+            };
+        }
+        let to_log = warnings
+            .len()
+            .min(MAX_WARNINGS_LOGGED_PER_INTERVAL - limiter.logged_in_interval);
+        limiter.logged_in_interval += to_log;
+        limiter.suppressed += warnings.len() - to_log;
+        (to_log, previously_suppressed)
+    };
+
+    if previously_suppressed > 0 {
+        warn!(
+            suppressed = previously_suppressed,
+            "Some warnings from the database were not logged due to rate limiting",
+        );
+    }
+
+    let statement = match statement.char_indices().nth(WARNING_STATEMENT_PREFIX_LEN) {
+        Some((end, _)) => &statement[..end],
+        None => statement,
+    };
+    for warning in &warnings[..to_log] {
+        warn!(
+            warning = warning.as_str(),
+            statement = statement,
+            "Response from the database contains a warning",
+        );
+    }
+}
+
 #[cfg(feature = "ssl")]
 mod ssl_config {
     use openssl::{
@@ -363,9 +647,33 @@ pub struct ConnectionConfig {
     pub authenticator: Option<Arc<dyn AuthenticatorProvider>>,
     pub address_translator: Option<Arc<dyn AddressTranslator>>,
     pub enable_write_coalescing: bool,
+    pub write_coalescing_buffer_size: usize,
 
     pub keepalive_interval: Option<Duration>,
     pub keepalive_timeout: Option<Duration>,
+
+    pub(crate) buffer_pool: Option<Arc<BufferPool>>,
+    pub zero_copy_blobs: bool,
+    pub include_frame_bytes_in_errors: bool,
+    pub result_parse_options: ResultParseOptions,
+    pub custom_startup_options: HashMap<String, String>,
+    pub force_reserved_startup_options: bool,
+    pub skip_result_metadata: bool,
+    pub max_batch_serialized_size: Option<usize>,
+    pub max_request_frame_size: Option<usize>,
+    pub max_response_frame_size: Option<usize>,
+    pub(crate) repreparations: Option<Arc<Repreparations>>,
+    pub(crate) metrics: Option<Arc<Metrics>>,
+    pub(crate) background_tasks: BackgroundTasks,
+    // At most `MAX_STREAM_IDS`.
+    pub max_requests_per_connection: usize,
+    pub stream_exhaustion_policy: StreamExhaustionPolicy,
+    pub priority_scheduling: Option<PriorityScheduling>,
+    // Delay between starting connection attempts to successive addresses of a contact point.
+    pub connection_attempt_delay: Duration,
+    // Resolves the hostnames met after startup, like the ones of the Cloud datacenters.
+    pub(crate) hostname_resolver: Arc<dyn HostnameResolver>,
+    pub(crate) address_family_preference: AddressFamilyPreference,
 }
 
 impl Default for ConnectionConfig {
@@ -384,15 +692,40 @@ impl Default for ConnectionConfig {
             #[cfg(feature = "cloud")]
             cloud_config: None,
             enable_write_coalescing: true,
+            write_coalescing_buffer_size: DEFAULT_WRITE_COALESCING_BUFFER_SIZE,
 
             // Note: this is different than SessionConfig default values.
             keepalive_interval: None,
             keepalive_timeout: None,
+
+            buffer_pool: None,
+            zero_copy_blobs: false,
+            include_frame_bytes_in_errors: false,
+            result_parse_options: ResultParseOptions::default(),
+            custom_startup_options: HashMap::new(),
+            force_reserved_startup_options: false,
+            skip_result_metadata: true,
+            max_batch_serialized_size: None,
+            max_request_frame_size: None,
+            max_response_frame_size: None,
+            repreparations: None,
+            metrics: None,
+            background_tasks: BackgroundTasks::default(),
+            max_requests_per_connection: MAX_STREAM_IDS,
+            stream_exhaustion_policy: StreamExhaustionPolicy::default(),
+            priority_scheduling: None,
+            connection_attempt_delay: Duration::from_millis(250),
+            hostname_resolver: Arc::new(SystemHostnameResolver),
+            address_family_preference: AddressFamilyPreference::default(),
         }
     }
 }
 
 impl ConnectionConfig {
+    pub(crate) fn runtime(&self) -> &Arc<dyn Runtime> {
+        self.background_tasks.runtime()
+    }
+
     #[cfg(feature = "ssl")]
     pub fn is_ssl(&self) -> bool {
         #[cfg(feature = "cloud")]
@@ -412,20 +745,39 @@ impl ConnectionConfig {
 pub(crate) type ErrorReceiver = tokio::sync::oneshot::Receiver<QueryError>;
 
 impl Connection {
-    // Returns new connection and ErrorReceiver which can be used to wait for a fatal error
+    // Returns new connection and ErrorReceiver which can be used to wait for a fatal error.
+    // The connection is established to the first of the addresses which accepts it,
+    // in order of preference (see `connect_to_any`).
     pub(crate) async fn new(
-        addr: SocketAddr,
+        addrs: &[SocketAddr],
         source_port: Option<u16>,
         config: ConnectionConfig,
     ) -> Result<(Self, ErrorReceiver), QueryError> {
         let stream_connector = match source_port {
             Some(p) => {
-                tokio::time::timeout(config.connect_timeout, connect_with_source_port(addr, p))
-                    .await
+                let addr = addrs[0];
+                runtime::timeout(
+                    config.runtime().as_ref(),
+                    config.connect_timeout,
+                    connect_with_source_port(addr, p),
+                )
+                .await
+                .map(|stream| stream.map(|stream| (stream, addr)))
+            }
+            None => {
+                runtime::timeout(
+                    config.runtime().as_ref(),
+                    config.connect_timeout,
+                    connect_to_any(
+                        addrs,
+                        config.connection_attempt_delay,
+                        config.runtime().as_ref(),
+                    ),
+                )
+                .await
             }
-            None => tokio::time::timeout(config.connect_timeout, TcpStream::connect(addr)).await,
         };
-        let stream = match stream_connector {
+        let (stream, addr) = match stream_connector {
             Ok(stream) => stream?,
             Err(_) => {
                 return Err(QueryError::TimeoutError);
@@ -492,6 +844,18 @@ impl Connection {
             submit_channel: sender,
             request_id_generator: AtomicU64::new(0),
             orphan_notification_sender,
+            buffer_pool: config.buffer_pool.clone(),
+            max_request_frame_size: config.max_request_frame_size,
+            stream_permits: Arc::new(Semaphore::new(
+                config.max_requests_per_connection.min(MAX_STREAM_IDS),
+            )),
+            stream_exhaustion_policy: config.stream_exhaustion_policy,
+            queued_requests: AtomicUsize::new(0),
+            scheduler: config
+                .priority_scheduling
+                .map(|scheduling| Arc::new(PriorityScheduler::new(scheduling))),
+            metrics: config.metrics.clone(),
+            runtime: config.runtime().clone(),
         });
 
         let _worker_handle = Self::run_router(
@@ -501,12 +865,13 @@ impl Connection {
             error_sender,
             orphan_notification_receiver,
             router_handle.clone(),
-            addr.ip(),
+            addr,
         )
         .await?;
 
         let connection = Connection {
             _worker_handle,
+            id: NEXT_CONNECTION_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
             config,
             features: Default::default(),
             connect_address: addr,
@@ -516,19 +881,16 @@ impl Connection {
         Ok((connection, error_receiver))
     }
 
-    pub(crate) async fn startup(
-        &self,
-        options: HashMap<String, String>,
-    ) -> Result<Response, QueryError> {
+    pub(crate) async fn startup(&self, startup: request::Startup) -> Result<Response, QueryError> {
         Ok(self
-            .send_request(&request::Startup { options }, false, false)
+            .send_request(&startup, false, false, None)
             .await?
             .response)
     }
 
     pub(crate) async fn get_options(&self) -> Result<Response, QueryError> {
         Ok(self
-            .send_request(&request::Options {}, false, false)
+            .send_request(&request::Options {}, false, false, None)
             .await?
             .response)
     }
@@ -541,8 +903,10 @@ impl Connection {
                 },
                 true,
                 query.config.tracing,
+                None,
             )
             .await?;
+        log_server_warnings(&query_response.warnings, &query.contents);
 
         let mut prepared_statement = match query_response.response {
             Response::Error(err) => return Err(err.into()),
@@ -552,6 +916,7 @@ impl Connection {
                     .protocol_features
                     .prepared_flags_contain_lwt_mark(p.prepared_metadata.flags as u32),
                 p.prepared_metadata,
+                p.result_metadata,
                 query.contents.clone(),
                 query.get_page_size(),
                 query.config.clone(),
@@ -583,7 +948,26 @@ impl Connection {
                 "Prepared statement Id changed, md5 sum should stay the same",
             ))
         } else {
-            Ok(())
+            previous_prepared.update_result_metadata(reprepared.get_result_metadata().clone());
+            previous_prepared.check_reprepared_metadata(reprepared.get_prepared_metadata())
+        }
+    }
+
+    /// Re-prepares the statement after a request sent at `sent_at` got `Unprepared`
+    /// in response. Concurrent re-preparations on the same node are collapsed into one.
+    async fn reprepare_unprepared(
+        &self,
+        prepared: &PreparedStatement,
+        sent_at: Instant,
+    ) -> Result<(), QueryError> {
+        let reprepare = || self.reprepare(prepared.get_statement(), prepared);
+        match &self.config.repreparations {
+            Some(repreparations) => {
+                repreparations
+                    .reprepare(prepared.get_id(), sent_at, reprepare)
+                    .await
+            }
+            None => reprepare().await,
         }
     }
 
@@ -591,7 +975,7 @@ impl Connection {
         &self,
         response: Option<Vec<u8>>,
     ) -> Result<QueryResponse, QueryError> {
-        self.send_request(&request::AuthResponse { response }, false, false)
+        self.send_request(&request::AuthResponse { response }, false, false, None)
             .await
     }
 
@@ -618,9 +1002,15 @@ impl Connection {
         serial_consistency: Option<SerialConsistency>,
     ) -> Result<QueryResult, QueryError> {
         let query: Query = query.into();
-        self.query_with_consistency(&query, consistency, serial_consistency, None)
-            .await?
-            .into_query_result()
+        self.query_with_consistency(
+            &query,
+            consistency,
+            serial_consistency,
+            RequestPriority::Normal,
+            None,
+        )
+        .await?
+        .into_query_result()
     }
 
     pub(crate) async fn query(
@@ -635,6 +1025,7 @@ impl Connection {
                 .config
                 .determine_consistency(self.config.default_consistency),
             query.config.serial_consistency.flatten(),
+            RequestPriority::Normal,
             paging_state,
         )
         .await
@@ -645,6 +1036,7 @@ impl Connection {
         query: &Query,
         consistency: Consistency,
         serial_consistency: Option<SerialConsistency>,
+        priority: RequestPriority,
         paging_state: Option<Bytes>,
     ) -> Result<QueryResponse, QueryError> {
         let query_frame = query::Query {
@@ -656,11 +1048,23 @@ impl Connection {
                 page_size: query.get_page_size(),
                 paging_state,
                 timestamp: query.get_timestamp(),
+                skip_metadata: false,
             },
         };
 
-        self.send_request(&query_frame, true, query.config.tracing)
-            .await
+        let query_response = self
+            .send_request_with_result_metadata(
+                &query_frame,
+                true,
+                query.config.tracing,
+                query.get_custom_payload(),
+                priority,
+                None,
+            )
+            .await??;
+        log_server_warnings(&query_response.warnings, &query.contents);
+
+        Ok(query_response)
     }
 
     #[allow(dead_code)]
@@ -678,6 +1082,7 @@ impl Connection {
                 .config
                 .determine_consistency(self.config.default_consistency),
             prepared.config.serial_consistency.flatten(),
+            RequestPriority::Normal,
             paging_state,
         )
         .await
@@ -689,9 +1094,10 @@ impl Connection {
         values: &SerializedValues,
         consistency: Consistency,
         serial_consistency: Option<SerialConsistency>,
+        priority: RequestPriority,
         paging_state: Option<Bytes>,
     ) -> Result<QueryResponse, QueryError> {
-        let execute_frame = execute::Execute {
+        let execute_frame = |skip_metadata: bool| execute::Execute {
             id: prepared_statement.get_id().to_owned(),
             parameters: query::QueryParameters {
                 consistency,
@@ -699,28 +1105,108 @@ impl Connection {
                 values: Cow::Borrowed(values),
                 page_size: prepared_statement.get_page_size(),
                 timestamp: prepared_statement.get_timestamp(),
-                paging_state,
+                paging_state: paging_state.clone(),
+                skip_metadata,
             },
         };
 
-        let query_response = self
-            .send_request(&execute_frame, true, prepared_statement.config.tracing)
+        let sent_at = Instant::now();
+        // Rows of the statement are received with their metadata until it's confirmed
+        let unconfirmed_result_metadata = match self.config.skip_result_metadata {
+            true => prepared_statement.unconfirmed_result_metadata_version(),
+            false => None,
+        };
+        let result_metadata = self.result_metadata_to_skip(prepared_statement);
+        let mut query_response = self
+            .send_execute(
+                prepared_statement,
+                &execute_frame(result_metadata.is_some()),
+                priority,
+                result_metadata.as_deref(),
+            )
             .await?;
 
-        match &query_response.response {
-            Response::Error(frame::response::Error {
-                error: DbError::Unprepared { statement_id },
+        if let Ok(QueryResponse {
+            response:
+                Response::Error(frame::response::Error {
+                    error: DbError::Unprepared { statement_id },
+                    ..
+                }),
+            ..
+        }) = &query_response
+        {
+            debug!(
+                "Connection::execute: Got DbError::Unprepared - repreparing statement with id {:?}",
+                statement_id
+            );
+            // Repreparation of a statement is needed
+            self.reprepare_unprepared(prepared_statement, sent_at)
+                .await?;
+            // The result metadata could change along with the schema
+            let result_metadata = self.result_metadata_to_skip(prepared_statement);
+            query_response = self
+                .send_execute(
+                    prepared_statement,
+                    &execute_frame(result_metadata.is_some()),
+                    priority,
+                    result_metadata.as_deref(),
+                )
+                .await?;
+        }
+
+        let query_response = match query_response {
+            Ok(query_response) => query_response,
+            Err(ResponseParseError {
+                error: ParseError::ResultMetadataMismatch { col_count },
                 ..
             }) => {
-                debug!("Connection::execute: Got DbError::Unprepared - repreparing statement with id {:?}", statement_id);
-                // Repreparation of a statement is needed
-                self.reprepare(prepared_statement.get_statement(), prepared_statement)
+                debug!(
+                    "Connection::execute: Got rows with {} columns not matching the cached result metadata - repreparing statement with id {:?}",
+                    col_count,
+                    prepared_statement.get_id()
+                );
+                self.reprepare_unprepared(prepared_statement, sent_at)
                     .await?;
-                self.send_request(&execute_frame, true, prepared_statement.config.tracing)
-                    .await
+                // The schema may be still changing, so this time the metadata is sent with the rows
+                self.send_execute(prepared_statement, &execute_frame(false), priority, None)
+                    .await??
             }
-            _ => Ok(query_response),
+            Err(err) => return Err(err.into()),
+        };
+        log_server_warnings(&query_response.warnings, prepared_statement.get_statement());
+
+        if let Some(version) = unconfirmed_result_metadata {
+            self.confirm_result_metadata(prepared_statement, &query_response, version, sent_at)
+                .await?;
         }
+
+        Ok(query_response)
+    }
+
+    /// Compares the metadata of rows received together with it with the cached result
+    /// metadata of the statement, which a schema change may have made outdated.
+    /// The statement is re-prepared if they differ.
+    async fn confirm_result_metadata(
+        &self,
+        prepared_statement: &PreparedStatement,
+        query_response: &QueryResponse,
+        version: usize,
+        sent_at: Instant,
+    ) -> Result<(), QueryError> {
+        let rows = match &query_response.response {
+            Response::Result(result::Result::Rows(rows)) => rows,
+            _ => return Ok(()),
+        };
+        if rows.metadata.col_specs != prepared_statement.get_current_result_metadata().col_specs {
+            debug!(
+                "Connection::execute: Got rows with metadata not matching the cached result metadata - repreparing statement with id {:?}",
+                prepared_statement.get_id()
+            );
+            self.reprepare_unprepared(prepared_statement, sent_at)
+                .await?;
+        }
+        prepared_statement.confirm_result_metadata(version);
+        Ok(())
     }
 
     /// Executes a query and fetches its results over multiple pages, using
@@ -773,6 +1259,7 @@ impl Connection {
                 .config
                 .determine_consistency(self.config.default_consistency),
             batch.config.serial_consistency.flatten(),
+            RequestPriority::Normal,
         )
         .await
     }
@@ -783,6 +1270,7 @@ impl Connection {
         values: impl BatchValues,
         consistency: Consistency,
         serial_consistency: Option<SerialConsistency>,
+        priority: RequestPriority,
     ) -> Result<QueryResult, QueryError> {
         let batch = self.prepare_batch(init_batch, &values).await?;
 
@@ -802,12 +1290,32 @@ impl Connection {
             consistency,
             serial_consistency,
             timestamp: batch.get_timestamp(),
+            max_serialized_size: self.config.max_batch_serialized_size,
         };
 
+        // The frame is serialized anew from `values` for each request sent here,
+        // and so it is on retries, which call this function again. Only the frame
+        // being sent is kept in memory, never a buffered copy of all the rows.
         loop {
+            let sent_at = Instant::now();
             let query_response = self
-                .send_request(&batch_frame, true, batch.config.tracing)
-                .await?;
+                .send_request_with_result_metadata(
+                    &batch_frame,
+                    true,
+                    batch.config.tracing,
+                    batch.get_custom_payload(),
+                    priority,
+                    None,
+                )
+                .await??;
+            if !query_response.warnings.is_empty() {
+                let first_statement = match batch.statements.first() {
+                    Some(BatchStatement::Query(query)) => query.contents.as_str(),
+                    Some(BatchStatement::PreparedStatement(prepared)) => prepared.get_statement(),
+                    None => "",
+                };
+                log_server_warnings(&query_response.warnings, first_statement);
+            }
 
             return match query_response.response {
                 Response::Error(err) => match err.error {
@@ -820,7 +1328,7 @@ impl Connection {
                             _ => None,
                         });
                         if let Some(p) = prepared_statement {
-                            self.reprepare(p.get_statement(), p).await?;
+                            self.reprepare_unprepared(p, sent_at).await?;
                             continue;
                         } else {
                             return Err(QueryError::ProtocolError(
@@ -928,7 +1436,7 @@ impl Connection {
         };
 
         match self
-            .send_request(&register_frame, true, false)
+            .send_request(&register_frame, true, false, None)
             .await?
             .response
         {
@@ -958,7 +1466,32 @@ impl Connection {
         request: &impl SerializableRequest,
         compress: bool,
         tracing: bool,
+        custom_payload: Option<&HashMap<String, Vec<u8>>>,
     ) -> Result<QueryResponse, QueryError> {
+        Ok(self
+            .send_request_with_result_metadata(
+                request,
+                compress,
+                tracing,
+                custom_payload,
+                RequestPriority::Normal,
+                None,
+            )
+            .await??)
+    }
+
+    /// Sends the request, decoding rows which are sent without metadata with `result_metadata`.
+    /// Errors of parsing the response are returned separately, so that the caller can tell
+    /// that the result metadata is outdated.
+    async fn send_request_with_result_metadata(
+        &self,
+        request: &impl SerializableRequest,
+        compress: bool,
+        tracing: bool,
+        custom_payload: Option<&HashMap<String, Vec<u8>>>,
+        priority: RequestPriority,
+        result_metadata: Option<&ResultMetadata>,
+    ) -> Result<Result<QueryResponse, ResponseParseError>, QueryError> {
         let compression = if compress {
             self.config.compression
         } else {
@@ -967,42 +1500,91 @@ impl Connection {
 
         let task_response = self
             .router_handle
-            .send_request(request, compression, tracing)
+            .send_request(request, compression, tracing, custom_payload, priority)
             .await?;
 
         Self::parse_response(
             task_response,
             self.config.compression,
+            self.config.max_response_frame_size,
             &self.features.protocol_features,
+            self.config.zero_copy_blobs,
+            self.config.include_frame_bytes_in_errors,
+            result_metadata,
+            self.config.result_parse_options,
         )
     }
 
+    async fn send_execute(
+        &self,
+        prepared_statement: &PreparedStatement,
+        execute_frame: &execute::Execute<'_>,
+        priority: RequestPriority,
+        result_metadata: Option<&ResultMetadata>,
+    ) -> Result<Result<QueryResponse, ResponseParseError>, QueryError> {
+        self.send_request_with_result_metadata(
+            execute_frame,
+            true,
+            prepared_statement.config.tracing,
+            prepared_statement.get_custom_payload(),
+            priority,
+            result_metadata,
+        )
+        .await
+    }
+
+    /// Returns the cached result metadata of the statement if the server should be asked
+    /// not to send it with the rows. Results of conditional statements don't have stable
+    /// columns, so their metadata is always sent. So is the metadata which may have been
+    /// changed by a schema change, until it's confirmed.
+    fn result_metadata_to_skip(&self, prepared: &PreparedStatement) -> Option<Arc<ResultMetadata>> {
+        if !self.config.skip_result_metadata
+            || prepared.is_conditional()
+            || prepared.unconfirmed_result_metadata_version().is_some()
+        {
+            return None;
+        }
+        let result_metadata = prepared.get_current_result_metadata();
+        match result_metadata.col_specs.is_empty() {
+            true => None,
+            false => Some(result_metadata),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn parse_response(
         task_response: TaskResponse,
         compression: Option<Compression>,
+        max_body_size: Option<usize>,
         features: &ProtocolFeatures,
-    ) -> Result<QueryResponse, QueryError> {
-        let body_with_ext = frame::parse_response_body_extensions(
+        zero_copy_blobs: bool,
+        include_frame_bytes: bool,
+        result_metadata: Option<&ResultMetadata>,
+        parse_options: ResultParseOptions,
+    ) -> Result<Result<QueryResponse, ResponseParseError>, QueryError> {
+        let body_with_ext = frame::parse_response_body_extensions_with_limit(
             task_response.params.flags,
             compression,
             task_response.body,
+            max_body_size,
         )?;
 
-        for warn_description in &body_with_ext.warnings {
-            warn!(
-                warning = warn_description.as_str(),
-                "Response from the database contains a warning",
-            );
-        }
-
-        let response =
-            Response::deserialize(features, task_response.opcode, &mut &*body_with_ext.body)?;
+        let response = Response::deserialize_frame(
+            features,
+            task_response.params,
+            task_response.opcode,
+            &body_with_ext.body,
+            zero_copy_blobs,
+            result_metadata,
+            include_frame_bytes,
+            parse_options,
+        );
 
-        Ok(QueryResponse {
+        Ok(response.map(|response| QueryResponse {
             response,
             warnings: body_with_ext.warnings,
             tracing_id: body_with_ext.trace_id,
-        })
+        }))
     }
 
     async fn run_router(
@@ -1012,42 +1594,55 @@ impl Connection {
         error_sender: tokio::sync::oneshot::Sender<QueryError>,
         orphan_notification_receiver: mpsc::UnboundedReceiver<RequestId>,
         router_handle: Arc<RouterHandle>,
-        node_address: IpAddr,
+        node_address: SocketAddr,
     ) -> Result<RemoteHandle<()>, std::io::Error> {
+        let background_tasks = config.background_tasks.clone();
+
         #[cfg(feature = "ssl")]
         if let Some(ssl_config) = &config.ssl_config {
             let ssl = ssl_config.new_ssl()?;
             let mut stream = SslStream::new(ssl, stream)?;
             let _pin = Pin::new(&mut stream).connect().await;
 
-            let (task, handle) = Self::router(
-                config,
-                stream,
-                receiver,
-                error_sender,
-                orphan_notification_receiver,
-                router_handle,
-                node_address,
-            )
-            .remote_handle();
-            tokio::task::spawn(task.with_current_subscriber());
+            let handle = background_tasks.spawn(
+                BackgroundTaskKind::ConnectionRouter,
+                Some(node_address),
+                |activity| {
+                    Self::router(
+                        config,
+                        stream,
+                        receiver,
+                        error_sender,
+                        orphan_notification_receiver,
+                        router_handle,
+                        node_address.ip(),
+                        activity,
+                    )
+                },
+            );
             return Ok(handle);
         }
 
-        let (task, handle) = Self::router(
-            config,
-            stream,
-            receiver,
-            error_sender,
-            orphan_notification_receiver,
-            router_handle,
-            node_address,
-        )
-        .remote_handle();
-        tokio::task::spawn(task.with_current_subscriber());
+        let handle = background_tasks.spawn(
+            BackgroundTaskKind::ConnectionRouter,
+            Some(node_address),
+            |activity| {
+                Self::router(
+                    config,
+                    stream,
+                    receiver,
+                    error_sender,
+                    orphan_notification_receiver,
+                    router_handle,
+                    node_address.ip(),
+                    activity,
+                )
+            },
+        );
         Ok(handle)
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn router(
         config: ConnectionConfig,
         stream: (impl AsyncRead + AsyncWrite),
@@ -1056,6 +1651,7 @@ impl Connection {
         orphan_notification_receiver: mpsc::UnboundedReceiver<RequestId>,
         router_handle: Arc<RouterHandle>,
         node_address: IpAddr,
+        activity: TaskActivity,
     ) {
         let (read_half, write_half) = split(stream);
         // Why are we using a mutex here?
@@ -1073,6 +1669,10 @@ impl Connection {
         let handler_map = StdMutex::new(ResponseHandlerMap::new());
 
         let enable_write_coalescing = config.enable_write_coalescing;
+        let write_coalescing_buffer_size = config.write_coalescing_buffer_size;
+        let buffer_pool = config.buffer_pool.clone();
+        let metrics = config.metrics.clone();
+        let runtime = config.runtime().clone();
 
         let k = Self::keepaliver(
             router_handle,
@@ -1085,14 +1685,23 @@ impl Connection {
             BufReader::with_capacity(8192, read_half),
             &handler_map,
             config,
+            &activity,
         );
         let w = Self::writer(
-            BufWriter::with_capacity(8192, write_half),
+            BufWriter::with_capacity(write_coalescing_buffer_size, write_half),
             &handler_map,
             receiver,
             enable_write_coalescing,
+            buffer_pool.as_deref(),
+            metrics.as_deref(),
+            &activity,
+        );
+        let o = Self::orphaner(
+            &handler_map,
+            orphan_notification_receiver,
+            metrics.as_deref(),
+            runtime,
         );
-        let o = Self::orphaner(&handler_map, orphan_notification_receiver);
 
         let result = futures::try_join!(r, w, o, k);
 
@@ -1118,14 +1727,22 @@ impl Connection {
         mut read_half: (impl AsyncRead + Unpin),
         handler_map: &StdMutex<ResponseHandlerMap>,
         config: ConnectionConfig,
+        activity: &TaskActivity,
     ) -> Result<(), QueryError> {
         loop {
-            let (params, opcode, body) = frame::read_response_frame(&mut read_half).await?;
-            let response = TaskResponse {
+            // Bodies of responses exceeding the limit are drained from the connection
+            // without buffering them, so the connection remains usable
+            let (params, opcode, body) = frame::read_response_frame_with_limit(
+                &mut read_half,
+                config.max_response_frame_size,
+            )
+            .await?;
+            activity.touch();
+            let response = body.map(|body| TaskResponse {
                 params,
                 opcode,
                 body,
-            };
+            });
 
             match params.stream.cmp(&-1) {
                 Ordering::Less => {
@@ -1136,7 +1753,19 @@ impl Connection {
                 }
                 Ordering::Equal => {
                     if let Some(event_sender) = config.event_sender.as_ref() {
-                        Self::handle_event(response, config.compression, event_sender).await?;
+                        match response {
+                            Ok(response) => {
+                                Self::handle_event(
+                                    response,
+                                    config.compression,
+                                    config.max_response_frame_size,
+                                    config.include_frame_bytes_in_errors,
+                                    event_sender,
+                                )
+                                .await?
+                            }
+                            Err(err) => warn!("Skipped an event: {}", err),
+                        }
                     }
                     continue;
                 }
@@ -1156,7 +1785,7 @@ impl Connection {
                     // Don't care if sending of the response fails. This must
                     // mean that the receiver side was impatient and is not
                     // waiting for the result anymore.
-                    let _ = handler.response_sender.send(Ok(response));
+                    let _ = handler.response_sender.send(response.map_err(Into::into));
                 }
                 Missing => {
                     // Unsolicited frame. This should not happen and indicates
@@ -1172,6 +1801,9 @@ impl Connection {
                 Orphaned => {
                     // Do nothing, handler was freed because this stream_id has
                     // been marked as orphaned
+                    if let Some(metrics) = &config.metrics {
+                        metrics.inc_late_responses();
+                    }
                 }
             }
         }
@@ -1180,11 +1812,12 @@ impl Connection {
     fn alloc_stream_id(
         handler_map: &StdMutex<ResponseHandlerMap>,
         response_handler: ResponseHandler,
+        stream_permit: StreamPermit,
     ) -> Option<i16> {
         // We are guaranteed here that handler_map will not be locked
         // by anybody else, so we can do try_lock().unwrap()
         let mut handler_map_guard = handler_map.try_lock().unwrap();
-        match handler_map_guard.allocate(response_handler) {
+        match handler_map_guard.allocate(response_handler, stream_permit) {
             Ok(stream_id) => Some(stream_id),
             Err(response_handler) => {
                 error!("Could not allocate stream id");
@@ -1201,6 +1834,9 @@ impl Connection {
         handler_map: &StdMutex<ResponseHandlerMap>,
         mut task_receiver: mpsc::Receiver<Task>,
         enable_write_coalescing: bool,
+        buffer_pool: Option<&BufferPool>,
+        metrics: Option<&Metrics>,
+        activity: &TaskActivity,
     ) -> Result<(), QueryError> {
         // When the Connection object is dropped, the sender half
         // of the channel will be dropped, this task will return an error
@@ -1208,13 +1844,31 @@ impl Connection {
         while let Some(mut task) = task_receiver.recv().await {
             let mut num_requests = 0;
             let mut total_sent = 0;
-            while let Some(stream_id) = Self::alloc_stream_id(handler_map, task.response_handler) {
-                let mut req = task.serialized_request;
-                req.set_stream(stream_id);
-                let req_data: &[u8] = req.get_data();
-                total_sent += req_data.len();
-                num_requests += 1;
-                write_half.write_all(req_data).await?;
+            loop {
+                if task.response_handler.response_sender.is_closed() {
+                    // The request was dropped by its caller before being written, so it's
+                    // not sent at all, and its stream permit is freed right away
+                    give_back_buffer(buffer_pool, task.serialized_request.into_buffer());
+                    if let Some(metrics) = metrics {
+                        metrics.inc_cancelled_requests();
+                    }
+                } else {
+                    let stream_id = match Self::alloc_stream_id(
+                        handler_map,
+                        task.response_handler,
+                        task.stream_permit,
+                    ) {
+                        Some(stream_id) => stream_id,
+                        None => break,
+                    };
+                    let mut req = task.serialized_request;
+                    req.set_stream(stream_id);
+                    let req_data: &[u8] = req.get_data();
+                    total_sent += req_data.len();
+                    num_requests += 1;
+                    write_half.write_all(req_data).await?;
+                    give_back_buffer(buffer_pool, req.into_buffer());
+                }
                 task = match task_receiver.try_recv() {
                     Ok(t) => t,
                     Err(_) if enable_write_coalescing => {
@@ -1232,6 +1886,7 @@ impl Connection {
             }
             trace!("Sending {} requests; {} bytes", num_requests, total_sent);
             write_half.flush().await?;
+            activity.touch();
         }
 
         Ok(())
@@ -1244,8 +1899,10 @@ impl Connection {
     async fn orphaner(
         handler_map: &StdMutex<ResponseHandlerMap>,
         mut orphan_receiver: mpsc::UnboundedReceiver<RequestId>,
+        metrics: Option<&Metrics>,
+        runtime: Arc<dyn Runtime>,
     ) -> Result<(), QueryError> {
-        let mut interval = tokio::time::interval(OLD_AGE_ORPHAN_THRESHOLD);
+        let mut interval = Interval::new(runtime, OLD_AGE_ORPHAN_THRESHOLD);
         loop {
             tokio::select! {
                 _ = interval.tick() => {
@@ -1267,7 +1924,11 @@ impl Connection {
                         request_id,
                     );
                     let mut handler_map_guard = handler_map.try_lock().unwrap(); // Same as above
-                    handler_map_guard.orphan(request_id);
+                    if handler_map_guard.orphan(request_id) {
+                        if let Some(metrics) = metrics {
+                            metrics.inc_orphaned_requests();
+                        }
+                    }
                 }
                 else => { break }
             }
@@ -1284,48 +1945,51 @@ impl Connection {
     ) -> Result<(), QueryError> {
         async fn issue_keepalive_query(router_handle: &RouterHandle) -> Result<(), QueryError> {
             router_handle
-                .send_request(&Options, None, false)
+                .send_request(&Options, None, false, None, RequestPriority::Normal)
                 .await
                 .map(|_| ())
         }
 
         if let Some(keepalive_interval) = keepalive_interval {
-            let mut interval = tokio::time::interval(keepalive_interval);
+            // Missed ticks are delayed rather than sent in a burst, which suits keepalives.
+            let mut interval = Interval::new(router_handle.runtime.clone(), keepalive_interval);
             interval.tick().await; // Use up the first, instant tick.
 
-            // Default behaviour (Burst) is not suitable for sending keepalives.
-            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
-
             loop {
                 interval.tick().await;
 
+                // A connection with all stream ids in use is evidently alive
+                if router_handle.is_busy() {
+                    continue;
+                }
+
                 let keepalive_query = issue_keepalive_query(&router_handle);
                 let query_result = if let Some(timeout) = keepalive_timeout {
-                    match tokio::time::timeout(timeout, keepalive_query).await {
+                    match runtime::timeout(router_handle.runtime.as_ref(), timeout, keepalive_query)
+                        .await
+                    {
                         Ok(res) => res,
                         Err(_) => {
-                            warn!(
-                                "Timed out while waiting for response to keepalive request on connection to node {}",
-                                node_address
-                            );
+                            let err = KeepaliveTimeoutError { node_address };
+                            warn!("{}", err);
                             return Err(QueryError::IoError(Arc::new(std::io::Error::new(
                                 std::io::ErrorKind::Other,
-                                format!(
-                                    "Timed out while waiting for response to keepalive request on connection to node {}",
-                                        node_address
-                                )
+                                err,
                             ))));
                         }
                     }
                 } else {
                     keepalive_query.await
                 };
-                if let Err(err) = query_result {
-                    warn!(
-                        "Failed to execute keepalive request on connection to node {} - {}",
-                        node_address, err
-                    );
-                    return Err(err);
+                match query_result {
+                    Ok(()) | Err(QueryError::ConnectionBusy) => {}
+                    Err(err) => {
+                        warn!(
+                            "Failed to execute keepalive request on connection to node {} - {}",
+                            node_address, err
+                        );
+                        return Err(err);
+                    }
                 }
             }
         } else {
@@ -1337,6 +2001,8 @@ impl Connection {
     async fn handle_event(
         task_response: TaskResponse,
         compression: Option<Compression>,
+        max_body_size: Option<usize>,
+        include_frame_bytes: bool,
         event_sender: &mpsc::Sender<Event>,
     ) -> Result<(), QueryError> {
         // Protocol features are negotiated during connection handshake.
@@ -1350,7 +2016,17 @@ impl Connection {
         // future implementers.
         let features = ProtocolFeatures::default(); // TODO: Use the right features
 
-        let response = Self::parse_response(task_response, compression, &features)?.response;
+        let response = Self::parse_response(
+            task_response,
+            compression,
+            max_body_size,
+            &features,
+            false,
+            include_frame_bytes,
+            None,
+            ResultParseOptions::default(),
+        )??
+        .response;
         let event = match response {
             Response::Event(e) => e,
             _ => {
@@ -1375,6 +2051,18 @@ impl Connection {
         self.features.shard_aware_port
     }
 
+    pub(crate) fn get_capabilities(&self) -> &Arc<NodeCapabilities> {
+        &self.features.capabilities
+    }
+
+    pub(crate) fn get_negotiated_settings(&self) -> NegotiatedSettings {
+        NegotiatedSettings {
+            protocol_version: PROTOCOL_VERSION,
+            compression: self.config.compression,
+            startup_options: self.features.startup_options.clone(),
+        }
+    }
+
     fn set_features(&mut self, features: ConnectionFeatures) {
         self.features = features;
     }
@@ -1382,6 +2070,21 @@ impl Connection {
     pub(crate) fn get_connect_address(&self) -> SocketAddr {
         self.connect_address
     }
+
+    pub(crate) fn get_background_tasks(&self) -> &BackgroundTasks {
+        &self.config.background_tasks
+    }
+
+    /// Identifier of the connection, unique within the process,
+    /// which allows correlating log events of the connection.
+    pub(crate) fn get_id(&self) -> u64 {
+        self.id
+    }
+
+    /// Whether all stream ids of the connection are in use.
+    pub(crate) fn is_busy(&self) -> bool {
+        self.router_handle.is_busy()
+    }
 }
 
 async fn maybe_translated_addr(
@@ -1428,19 +2131,28 @@ pub(crate) async fn open_connection(
     source_port: Option<u16>,
     config: ConnectionConfig,
 ) -> Result<(Connection, ErrorReceiver), QueryError> {
-    let addr = maybe_translated_addr(endpoint, config.address_translator.as_deref()).await?;
+    let addrs = match endpoint {
+        UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+            address,
+            fallback_addresses,
+            ..
+        }) => std::iter::once(address).chain(fallback_addresses).collect(),
+        endpoint => {
+            vec![maybe_translated_addr(endpoint, config.address_translator.as_deref()).await?]
+        }
+    };
     open_named_connection(
-        addr,
+        &addrs,
         source_port,
         config,
-        Some("scylla-rust-driver".to_string()),
-        option_env!("CARGO_PKG_VERSION").map(|v| v.to_string()),
+        Some(DEFAULT_DRIVER_NAME.to_string()),
+        Some(DEFAULT_DRIVER_VERSION.to_string()),
     )
     .await
 }
 
 pub(crate) async fn open_named_connection(
-    addr: SocketAddr,
+    addrs: &[SocketAddr],
     source_port: Option<u16>,
     config: ConnectionConfig,
     driver_name: Option<String>,
@@ -1448,7 +2160,7 @@ pub(crate) async fn open_named_connection(
 ) -> Result<(Connection, ErrorReceiver), QueryError> {
     // TODO: shouldn't all this logic be in Connection::new?
     let (mut connection, error_receiver) =
-        Connection::new(addr, source_port, config.clone()).await?;
+        Connection::new(addrs, source_port, config.clone()).await?;
 
     let options_result = connection.get_options().await?;
 
@@ -1467,6 +2179,7 @@ pub(crate) async fn open_named_connection(
         }
     };
 
+    let capabilities = Arc::new(NodeCapabilities::from_supported(supported.options.clone()));
     let shard_info = ShardInfo::try_from(&supported.options).ok();
     let supported_compression = supported.options.remove("COMPRESSION").unwrap_or_default();
     let shard_aware_port = supported
@@ -1482,32 +2195,47 @@ pub(crate) async fn open_named_connection(
     let mut options = HashMap::new();
     protocol_features.add_startup_options(&mut options);
 
-    let features = ConnectionFeatures {
-        shard_info,
-        shard_aware_port,
-        protocol_features,
-    };
-    connection.set_features(features);
-
-    options.insert("CQL_VERSION".to_string(), "4.0.0".to_string()); // FIXME: hardcoded values
+    options.insert(startup::CQL_VERSION.to_string(), "4.0.0".to_string()); // FIXME: hardcoded values
     if let Some(name) = driver_name {
-        options.insert("DRIVER_NAME".to_string(), name);
+        options.insert(startup::DRIVER_NAME.to_string(), name);
     }
     if let Some(version) = driver_version {
-        options.insert("DRIVER_VERSION".to_string(), version);
+        options.insert(startup::DRIVER_VERSION.to_string(), version);
     }
     if let Some(compression) = &config.compression {
         let compression_str = compression.to_string();
         if supported_compression.iter().any(|c| c == &compression_str) {
             // Compression is reported to be supported by the server,
             // request it from the server
-            options.insert("COMPRESSION".to_string(), compression.to_string());
+            options.insert(startup::COMPRESSION.to_string(), compression.to_string());
         } else {
             // Fall back to no compression
             connection.config.compression = None;
         }
     }
-    let result = connection.startup(options).await?;
+
+    let mut startup = request::Startup { options };
+    let skipped = startup.merge_custom_options(
+        &config.custom_startup_options,
+        config.force_reserved_startup_options,
+    );
+    if !skipped.is_empty() {
+        warn!(
+            "Custom STARTUP options {:?} weren't sent, as they are set by the driver",
+            skipped
+        );
+    }
+
+    let features = ConnectionFeatures {
+        shard_info,
+        shard_aware_port,
+        protocol_features,
+        capabilities,
+        startup_options: startup.options.clone(),
+    };
+    connection.set_features(features);
+
+    let result = connection.startup(startup).await?;
     match result {
         Response::Ready => {}
         Response::Authenticate(authenticate) => {
@@ -1586,6 +2314,56 @@ async fn perform_authenticate(
     Ok(())
 }
 
+// Connects to the first of the addresses which accepts the connection, "happy eyeballs" style
+// (RFC 8305): connection attempts are started in order, each one after the previous one fails
+// or `attempt_delay` passes, and the attempts which are still in progress when one succeeds
+// are cancelled. Returns the error of the last attempt if all of them fail.
+async fn connect_to_any(
+    addrs: &[SocketAddr],
+    attempt_delay: Duration,
+    runtime: &dyn Runtime,
+) -> Result<(TcpStream, SocketAddr), std::io::Error> {
+    connect_to_any_with(addrs, attempt_delay, runtime, TcpStream::connect).await
+}
+
+async fn connect_to_any_with<S, F, Fut>(
+    addrs: &[SocketAddr],
+    attempt_delay: Duration,
+    runtime: &dyn Runtime,
+    connect: F,
+) -> Result<(S, SocketAddr), std::io::Error>
+where
+    F: Fn(SocketAddr) -> Fut,
+    Fut: std::future::Future<Output = Result<S, std::io::Error>>,
+{
+    let mut attempts = FuturesUnordered::new();
+    let mut remaining = addrs.iter().copied();
+    let mut last_error = None;
+    loop {
+        if let Some(addr) = remaining.next() {
+            let attempt = connect(addr);
+            attempts.push(async move { (attempt.await, addr) });
+        }
+        if attempts.is_empty() {
+            return Err(last_error.unwrap_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "No address to connect to")
+            }));
+        }
+
+        let next_attempt = runtime.sleep(attempt_delay);
+        tokio::select! {
+            Some((result, addr)) = attempts.next() => match result {
+                Ok(stream) => return Ok((stream, addr)),
+                Err(err) => {
+                    debug!("Failed to connect to {}: {}", addr, err);
+                    last_error = Some(err);
+                }
+            },
+            _ = next_attempt, if remaining.len() > 0 => {}
+        }
+    }
+}
+
 async fn connect_with_source_port(
     addr: SocketAddr,
     source_port: u16,
@@ -1653,6 +2431,8 @@ impl OrphanageTracker {
 struct ResponseHandlerMap {
     stream_set: StreamIdSet,
     handlers: HashMap<i16, ResponseHandler>,
+    // Permits of the requests, kept until their stream ids are freed.
+    stream_permits: HashMap<i16, StreamPermit>,
 
     request_to_stream: HashMap<RequestId, i16>,
     orphanage_tracker: OrphanageTracker,
@@ -1669,13 +2449,19 @@ impl ResponseHandlerMap {
         Self {
             stream_set: StreamIdSet::new(),
             handlers: HashMap::new(),
+            stream_permits: HashMap::new(),
             request_to_stream: HashMap::new(),
             orphanage_tracker: OrphanageTracker::new(),
         }
     }
 
-    fn allocate(&mut self, response_handler: ResponseHandler) -> Result<i16, ResponseHandler> {
+    fn allocate(
+        &mut self,
+        response_handler: ResponseHandler,
+        stream_permit: StreamPermit,
+    ) -> Result<i16, ResponseHandler> {
         if let Some(stream_id) = self.stream_set.allocate() {
+            self.stream_permits.insert(stream_id, stream_permit);
             self.request_to_stream
                 .insert(response_handler.request_id, stream_id);
             let prev_handler = self.handlers.insert(stream_id, response_handler);
@@ -1688,8 +2474,9 @@ impl ResponseHandlerMap {
     }
 
     // Orphan stream_id (associated with this request_id) by moving it to
-    // `orphanage_tracker`, and freeing its handler
-    fn orphan(&mut self, request_id: RequestId) {
+    // `orphanage_tracker`, and freeing its handler. Returns whether the request
+    // was orphaned, i.e. it was sent and its response hasn't arrived yet.
+    fn orphan(&mut self, request_id: RequestId) -> bool {
         if let Some(stream_id) = self.request_to_stream.get(&request_id) {
             debug!(
                 "Orphaning stream_id = {} associated with request_id = {}",
@@ -1698,7 +2485,9 @@ impl ResponseHandlerMap {
             self.orphanage_tracker.insert(*stream_id);
             self.handlers.remove(stream_id);
             self.request_to_stream.remove(&request_id);
+            return true;
         }
+        false
     }
 
     fn old_orphans_count(&self) -> usize {
@@ -1708,6 +2497,7 @@ impl ResponseHandlerMap {
 
     fn lookup(&mut self, stream_id: i16) -> HandlerLookupResult {
         self.stream_set.free(stream_id);
+        self.stream_permits.remove(&stream_id);
 
         if self.orphanage_tracker.contains(stream_id) {
             self.orphanage_tracker.remove(stream_id);
@@ -1830,7 +2620,6 @@ impl VerifiedKeyspaceName {
 #[cfg(test)]
 mod tests {
     use assert_matches::assert_matches;
-    use scylla_cql::errors::QueryError;
     use scylla_cql::frame::protocol_features::{
         LWT_OPTIMIZATION_META_BIT_MASK_KEY, SCYLLA_LWT_ADD_METADATA_MARK_EXTENSION,
     };
@@ -1843,18 +2632,33 @@ mod tests {
     use tokio::select;
     use tokio::sync::mpsc;
 
-    use super::ConnectionConfig;
+    use super::{
+        Connection, ConnectionConfig, KeepaliveTimeoutError, ResponseHandler, ResponseHandlerMap,
+        RouterHandle, Task, TaskResponse,
+    };
+    use crate::frame::request::{query, DeserializableRequest, SerializableRequest};
+    use crate::frame::response::ResponseOpcode;
+    use crate::frame::{FrameParams, SerializedRequest};
     use crate::query::Query;
+    use crate::transport::background_tasks::{BackgroundTasks, TaskActivity};
     use crate::transport::connection::open_connection;
+    use crate::transport::metrics::Metrics;
     use crate::transport::node::ResolvedContactPoint;
+    use crate::transport::priority::RequestPriority;
+    use crate::transport::runtime::{Runtime, TestRuntime, TokioRuntime};
     use crate::transport::topology::UntranslatedEndpoint;
     use crate::utils::test_utils::unique_keyspace_name;
     use crate::{IntoTypedRows, SessionBuilder};
-    use futures::{StreamExt, TryStreamExt};
+    use bytes::Bytes;
+    use futures::{FutureExt, StreamExt, TryStreamExt};
     use std::collections::HashMap;
-    use std::net::SocketAddr;
-    use std::sync::Arc;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex as StdMutex};
     use std::time::Duration;
+    use tokio::io::{AsyncReadExt, BufWriter};
+    use tokio::sync::{oneshot, Semaphore};
+    use tokio::time::Instant;
 
     // Just like resolve_hostname in session.rs
     async fn resolve_hostname(hostname: &str) -> SocketAddr {
@@ -1870,6 +2674,215 @@ mod tests {
         }
     }
 
+    // Sends queries of various sizes through the writer and reads them back
+    // from the other end, returning their stream ids and contents.
+    async fn write_and_read_back(
+        contents: &[String],
+        enable_write_coalescing: bool,
+        buffer_size: usize,
+    ) -> Vec<(i16, String)> {
+        let (client, mut server) = tokio::io::duplex(1024 * 1024);
+        let handler_map = StdMutex::new(ResponseHandlerMap::new());
+        let (task_sender, task_receiver) = mpsc::channel(contents.len());
+        let stream_permits = Arc::new(Semaphore::new(contents.len()));
+        let mut response_receivers = Vec::new();
+        for (request_id, contents) in contents.iter().enumerate() {
+            let request = query::Query {
+                contents: contents.as_str().into(),
+                parameters: Default::default(),
+            };
+            let (response_sender, response_receiver) = oneshot::channel();
+            response_receivers.push(response_receiver);
+            task_sender
+                .try_send(Task {
+                    serialized_request: SerializedRequest::make(&request, None, false, None)
+                        .unwrap(),
+                    response_handler: ResponseHandler {
+                        response_sender,
+                        request_id: request_id as u64,
+                    },
+                    stream_permit: stream_permits.clone().try_acquire_owned().unwrap().into(),
+                })
+                .unwrap();
+        }
+        drop(task_sender);
+
+        Connection::writer(
+            BufWriter::with_capacity(buffer_size, client),
+            &handler_map,
+            task_receiver,
+            enable_write_coalescing,
+            None,
+            None,
+            &TaskActivity::untracked(),
+        )
+        .await
+        .unwrap();
+
+        let mut read_back = Vec::new();
+        for _ in contents {
+            let mut header = [0u8; 9];
+            server.read_exact(&mut header).await.unwrap();
+            assert_eq!(header[4], query::Query::OPCODE as u8);
+            let stream = i16::from_be_bytes([header[2], header[3]]);
+            let length = u32::from_be_bytes([header[5], header[6], header[7], header[8]]);
+            let mut body = vec![0u8; length as usize];
+            server.read_exact(&mut body).await.unwrap();
+            let request = query::Query::deserialize(&mut &body[..]).unwrap();
+            read_back.push((stream, request.contents.into_owned()));
+        }
+        read_back
+    }
+
+    #[tokio::test]
+    async fn coalesced_frames_keep_boundaries() {
+        // Small and large requests, some of them larger than the buffer
+        let contents: Vec<String> = (0..200)
+            .map(|i| format!("SELECT {}", "x".repeat((i * 37) % 300)))
+            .collect();
+
+        for (enable_write_coalescing, buffer_size) in
+            [(true, 64), (true, 1024), (true, 8192), (false, 8192)]
+        {
+            let read_back =
+                write_and_read_back(&contents, enable_write_coalescing, buffer_size).await;
+            let read_contents: Vec<&String> = read_back.iter().map(|(_, c)| c).collect();
+            assert_eq!(read_contents, contents.iter().collect::<Vec<_>>());
+
+            let streams: std::collections::HashSet<i16> =
+                read_back.iter().map(|(stream, _)| *stream).collect();
+            assert_eq!(streams.len(), contents.len());
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelled_requests_are_not_written() {
+        let (client, mut server) = tokio::io::duplex(1024 * 1024);
+        let handler_map = StdMutex::new(ResponseHandlerMap::new());
+        let metrics = Metrics::new();
+        let (task_sender, task_receiver) = mpsc::channel(3);
+        let stream_permits = Arc::new(Semaphore::new(3));
+        let mut response_receivers = Vec::new();
+        for request_id in 0..3 {
+            let request = query::Query {
+                contents: format!("SELECT {}", request_id).into(),
+                parameters: Default::default(),
+            };
+            let (response_sender, response_receiver) = oneshot::channel();
+            response_receivers.push(response_receiver);
+            task_sender
+                .try_send(Task {
+                    serialized_request: SerializedRequest::make(&request, None, false, None)
+                        .unwrap(),
+                    response_handler: ResponseHandler {
+                        response_sender,
+                        request_id,
+                    },
+                    stream_permit: stream_permits.clone().try_acquire_owned().unwrap().into(),
+                })
+                .unwrap();
+        }
+        drop(task_sender);
+        // The caller of the second request gives up before it's written
+        drop(response_receivers.remove(1));
+
+        Connection::writer(
+            BufWriter::new(client),
+            &handler_map,
+            task_receiver,
+            true,
+            None,
+            Some(&metrics),
+            &TaskActivity::untracked(),
+        )
+        .await
+        .unwrap();
+
+        let mut written = Vec::new();
+        server.read_to_end(&mut written).await.unwrap();
+        let written = String::from_utf8_lossy(&written);
+        assert!(written.contains("SELECT 0"));
+        assert!(!written.contains("SELECT 1"));
+        assert!(written.contains("SELECT 2"));
+
+        // Only the sent requests hold their stream permits
+        assert_eq!(stream_permits.available_permits(), 1);
+        assert_eq!(metrics.get_cancelled_requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn requests_waiting_for_stream_id_are_not_serialized() {
+        use crate::transport::buffer_pool::BufferPool;
+        use crate::transport::connection_pool::StreamExhaustionPolicy;
+        use std::sync::atomic::AtomicU64;
+
+        let metrics = Arc::new(Metrics::new());
+        let (submit_channel, mut tasks) = mpsc::channel(16);
+        let (orphan_notification_sender, _orphan_notifications) = mpsc::unbounded_channel();
+        let router_handle = Arc::new(RouterHandle {
+            submit_channel,
+            request_id_generator: AtomicU64::new(0),
+            orphan_notification_sender,
+            buffer_pool: Some(Arc::new(BufferPool::new(0, metrics.clone()))),
+            max_request_frame_size: None,
+            stream_permits: Arc::new(Semaphore::new(1)),
+            stream_exhaustion_policy: StreamExhaustionPolicy::Queue {
+                max_queued: 16,
+                timeout: Duration::from_secs(60),
+            },
+            queued_requests: AtomicUsize::new(0),
+            scheduler: None,
+            metrics: Some(metrics.clone()),
+            runtime: Arc::new(TokioRuntime),
+        });
+        // Buffers taken from the pool count every serialized frame
+        let frames_serialized =
+            || metrics.get_buffer_pool_hits() + metrics.get_buffer_pool_misses();
+
+        // The only stream id is in use
+        let permit = router_handle
+            .stream_permits
+            .clone()
+            .acquire_owned()
+            .await
+            .unwrap();
+        let requests: Vec<_> = (0..5)
+            .map(|i| {
+                let router_handle = router_handle.clone();
+                tokio::spawn(async move {
+                    let request = query::Query {
+                        contents: format!("SELECT {}", "x".repeat(1000 * i)).into(),
+                        parameters: Default::default(),
+                    };
+                    router_handle
+                        .send_request(&request, None, false, None, RequestPriority::Normal)
+                        .await
+                })
+            })
+            .collect();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(metrics.get_queued_requests(), 5);
+        assert_eq!(frames_serialized(), 0);
+        assert!(tasks.try_recv().is_err());
+
+        // Requests are serialized one by one, as stream ids get freed
+        drop(permit);
+        for sent in 1..=5 {
+            let task = tasks.recv().await.unwrap();
+            assert_eq!(frames_serialized(), sent);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            assert_eq!(frames_serialized(), sent);
+            // Dropping the task fails the request and frees its stream id
+            drop(task);
+        }
+        for request in requests {
+            assert!(request.await.unwrap().is_err());
+        }
+        assert_eq!(metrics.get_queued_requests(), 0);
+        assert_eq!(metrics.queue_time_percentiles().unwrap().count, 5);
+        assert!(metrics.queue_time_percentiles().unwrap().max >= Duration::from_millis(100));
+    }
+
     /// Tests for Connection::query_iter
     /// 1. SELECT from an empty table.
     /// 2. Create table and insert ints 0..100.
@@ -1885,6 +2898,7 @@ mod tests {
             UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
                 address: addr,
                 datacenter: None,
+                fallback_addresses: Vec::new(),
             }),
             None,
             ConnectionConfig::default(),
@@ -1902,14 +2916,14 @@ mod tests {
                 .build()
                 .await
                 .unwrap();
-            session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks.clone()), &[]).await.unwrap();
+            session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks.clone()), &[]).await.unwrap();
             session.use_keyspace(ks.clone(), false).await.unwrap();
             session
-                .query("DROP TABLE IF EXISTS connection_query_iter_tab", &[])
+                .query_unpaged("DROP TABLE IF EXISTS connection_query_iter_tab", &[])
                 .await
                 .unwrap();
             session
-                .query(
+                .query_unpaged(
                     "CREATE TABLE IF NOT EXISTS connection_query_iter_tab (p int primary key)",
                     &[],
                 )
@@ -1993,10 +3007,10 @@ mod tests {
                 .build()
                 .await
                 .unwrap();
-            session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks.clone()), &[]).await.unwrap();
+            session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks.clone()), &[]).await.unwrap();
             session.use_keyspace(ks.clone(), false).await.unwrap();
             session
-                .query(
+                .query_unpaged(
                     "CREATE TABLE IF NOT EXISTS t (p int primary key, v blob)",
                     &[],
                 )
@@ -2009,6 +3023,7 @@ mod tests {
                 UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
                     address: addr,
                     datacenter: None,
+                    fallback_addresses: Vec::new(),
                 }),
                 None,
                 ConnectionConfig {
@@ -2139,7 +3154,7 @@ mod tests {
 
         // We must interrupt the driver's full connection opening, because our proxy does not interact further after Startup.
         let (startup_without_lwt_optimisation, _shard) = select! {
-            _ = open_connection(UntranslatedEndpoint::ContactPoint(ResolvedContactPoint{address: proxy_addr, datacenter: None}), None, config.clone()) => unreachable!(),
+            _ = open_connection(UntranslatedEndpoint::ContactPoint(ResolvedContactPoint{address: proxy_addr, datacenter: None, fallback_addresses: Vec::new()}), None, config.clone()) => unreachable!(),
             startup = startup_rx.recv() => startup.unwrap(),
         };
 
@@ -2147,7 +3162,7 @@ mod tests {
             .change_request_rules(Some(make_rules(options_with_lwt_optimisation_support)));
 
         let (startup_with_lwt_optimisation, _shard) = select! {
-            _ = open_connection(UntranslatedEndpoint::ContactPoint(ResolvedContactPoint{address: proxy_addr, datacenter: None}), None, config.clone()) => unreachable!(),
+            _ = open_connection(UntranslatedEndpoint::ContactPoint(ResolvedContactPoint{address: proxy_addr, datacenter: None, fallback_addresses: Vec::new()}), None, config.clone()) => unreachable!(),
             startup = startup_rx.recv() => startup.unwrap(),
         };
 
@@ -2181,9 +3196,12 @@ mod tests {
             RequestReaction::drop_frame(),
         );
 
+        // Time is advanced by the test, so that it doesn't wait for the keepalives
+        let runtime = TestRuntime::new();
         let config = ConnectionConfig {
             keepalive_interval: Some(Duration::from_millis(500)),
             keepalive_timeout: Some(Duration::from_secs(1)),
+            background_tasks: BackgroundTasks::new(Arc::new(runtime.clone())),
             ..Default::default()
         };
 
@@ -2205,6 +3223,7 @@ mod tests {
             UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
                 address: proxy_addr,
                 datacenter: None,
+                fallback_addresses: Vec::new(),
             }),
             None,
             config,
@@ -2214,7 +3233,7 @@ mod tests {
 
         // As everything is normal, these queries should succeed.
         for _ in 0..3 {
-            tokio::time::sleep(Duration::from_millis(500)).await;
+            runtime.advance(Duration::from_millis(500)).await;
             conn.query_single_page("SELECT host_id FROM system.local")
                 .await
                 .unwrap();
@@ -2228,10 +3247,17 @@ mod tests {
         // Set up proxy to drop keepalive messages
         proxy.running_nodes[0].change_request_rules(Some(vec![drop_options_rule]));
 
-        // Wait until keepaliver gots impatient and terminates router.
+        // Advance the time until keepaliver gets impatient and terminates router.
         // Then, the error from keepaliver will be propagated to the error receiver.
-        let err = error_receiver.await.unwrap();
-        assert_matches!(err, QueryError::IoError(_));
+        // The short real sleeps let the keepalives reach the proxy.
+        let err = loop {
+            runtime.advance(Duration::from_millis(500)).await;
+            tokio::select! {
+                err = &mut error_receiver => break err.unwrap(),
+                _ = tokio::time::sleep(Duration::from_millis(10)) => {}
+            }
+        };
+        assert!(KeepaliveTimeoutError::is_cause_of(&err));
 
         // As the router is invalidated, all further queries should immediately
         // return error.
@@ -2241,4 +3267,135 @@ mod tests {
 
         let _ = proxy.finish().await;
     }
+
+    // A handle of a router which isn't running, so that the requests submitted
+    // through it can be received and answered by the test.
+    fn detached_router_handle(
+        runtime: Arc<dyn Runtime>,
+    ) -> (Arc<RouterHandle>, mpsc::Receiver<Task>) {
+        let (submit_channel, task_receiver) = mpsc::channel(16);
+        let (orphan_notification_sender, _) = mpsc::unbounded_channel();
+        let router_handle = RouterHandle {
+            submit_channel,
+            request_id_generator: Default::default(),
+            orphan_notification_sender,
+            buffer_pool: None,
+            max_request_frame_size: None,
+            stream_permits: Arc::new(Semaphore::new(16)),
+            stream_exhaustion_policy: Default::default(),
+            queued_requests: Default::default(),
+            scheduler: None,
+            metrics: None,
+            runtime,
+        };
+        (Arc::new(router_handle), task_receiver)
+    }
+
+    #[tokio::test]
+    async fn keepaliver_fails_when_keepalive_is_not_answered_in_time() {
+        let runtime = TestRuntime::new();
+        let (router_handle, mut tasks) = detached_router_handle(Arc::new(runtime.clone()));
+        let mut keepaliver = tokio::spawn(Connection::keepaliver(
+            router_handle,
+            Some(Duration::from_secs(30)),
+            Some(Duration::from_secs(10)),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+        ));
+        while runtime.pending_timers() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        // The first keepalive is sent after the interval, and answered
+        runtime.advance(Duration::from_secs(29)).await;
+        assert!(tasks.try_recv().is_err());
+        runtime.advance(Duration::from_secs(1)).await;
+        let keepalive = tasks.recv().await.unwrap();
+        let response = TaskResponse {
+            params: FrameParams::default(),
+            opcode: ResponseOpcode::Supported,
+            body: Bytes::new(),
+        };
+        let _ = keepalive
+            .response_handler
+            .response_sender
+            .send(Ok(response));
+
+        // The next one isn't answered, which breaks the connection after the timeout
+        runtime.advance(Duration::from_secs(30)).await;
+        let _unanswered = tasks.recv().await.unwrap();
+        runtime.advance(Duration::from_secs(9)).await;
+        assert!((&mut keepaliver).now_or_never().is_none());
+        runtime.advance(Duration::from_secs(1)).await;
+        let err = keepaliver.await.unwrap().unwrap_err();
+        assert!(KeepaliveTimeoutError::is_cause_of(&err));
+    }
+
+    // Connections to `live()` are established after 50ms, to `refused()` fail after 50ms
+    // and to other addresses hang. Dropped attempts are counted.
+    async fn mock_connect(addr: SocketAddr, cancelled: &AtomicUsize) -> std::io::Result<()> {
+        struct CancelGuard<'a>(&'a AtomicUsize, bool);
+        impl Drop for CancelGuard<'_> {
+            fn drop(&mut self) {
+                if !self.1 {
+                    self.0.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+
+        let mut guard = CancelGuard(cancelled, false);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let result = if addr == live() {
+            Ok(())
+        } else if addr == refused() {
+            Err(std::io::ErrorKind::ConnectionRefused.into())
+        } else {
+            futures::future::pending().await
+        };
+        guard.1 = true;
+        result
+    }
+
+    fn live() -> SocketAddr {
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 9042)
+    }
+
+    fn refused() -> SocketAddr {
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 9042)
+    }
+
+    fn dead() -> SocketAddr {
+        SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 9042)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn connect_to_any_uses_first_established_connection() {
+        let cancelled = AtomicUsize::new(0);
+        let connect = |addr| mock_connect(addr, &cancelled);
+        let delay = Duration::from_millis(250);
+
+        // A hanging attempt is raced with the next one after the delay, and cancelled
+        let started = Instant::now();
+        let (_, addr) =
+            super::connect_to_any_with(&[dead(), live()], delay, &TokioRuntime, connect)
+                .await
+                .unwrap();
+        assert_eq!(addr, live());
+        assert_eq!(started.elapsed(), Duration::from_millis(300));
+        assert_eq!(cancelled.load(Ordering::Relaxed), 1);
+
+        // A failed attempt starts the next one right away
+        let started = Instant::now();
+        let (_, addr) =
+            super::connect_to_any_with(&[refused(), live()], delay, &TokioRuntime, connect)
+                .await
+                .unwrap();
+        assert_eq!(addr, live());
+        assert_eq!(started.elapsed(), Duration::from_millis(100));
+
+        let err =
+            super::connect_to_any_with(&[refused(), refused()], delay, &TokioRuntime, connect)
+                .await
+                .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+    }
 }
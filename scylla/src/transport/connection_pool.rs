@@ -3,31 +3,39 @@ use crate::cloud::set_ssl_config_for_scylla_cloud_host;
 
 use crate::routing::{Shard, ShardCount, Sharder, Token};
 use crate::transport::errors::QueryError;
+use crate::transport::metrics::Metrics;
+#[cfg(feature = "metrics-exporter")]
+use crate::transport::metrics_exporter::{MetricsObserver, NodeLabels};
 use crate::transport::{
+    background_tasks::{BackgroundTaskKind, TaskActivity},
     connection,
-    connection::{Connection, ConnectionConfig, ErrorReceiver, VerifiedKeyspaceName},
+    connection::{
+        Connection, ConnectionConfig, ErrorReceiver, KeepaliveTimeoutError, VerifiedKeyspaceName,
+    },
 };
 
 #[cfg(feature = "cloud")]
-use super::node::resolve_hostname;
+use super::node::resolve_hostname_with;
 
 #[cfg(feature = "cloud")]
 use super::node::ResolvedContactPoint;
+use super::repreparation::Repreparations;
+use super::runtime;
 use super::topology::{PeerEndpoint, UntranslatedEndpoint};
-use super::NodeAddr;
+use super::{NegotiatedSettings, NodeAddr, NodeCapabilities};
 
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use futures::{future::RemoteHandle, stream::FuturesUnordered, Future, FutureExt, StreamExt};
 use rand::Rng;
 use std::convert::TryInto;
 use std::io::ErrorKind;
 use std::num::NonZeroUsize;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock, Weak};
 use std::time::Duration;
 
 use tokio::sync::{broadcast, mpsc, Notify};
-use tracing::instrument::WithSubscriber;
 use tracing::{debug, trace, warn};
 
 /// The target size of a per-node connection pool.
@@ -54,12 +62,99 @@ impl Default for PoolSize {
     }
 }
 
+/// What happens to a request when all stream ids of the connection chosen for it are in use.
+///
+/// Before this policy is applied, the request is sent through another connection
+/// of the pool which has free stream ids, if there is any, preferring connections
+/// to the same shard.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StreamExhaustionPolicy {
+    /// The request fails at once with [`QueryError::ConnectionBusy`].
+    #[default]
+    FailFast,
+
+    /// The request waits for a stream id to be freed, in order with other waiting requests.
+    /// It fails with [`QueryError::ConnectionBusy`] if `max_queued` requests are already
+    /// waiting on the connection, or if no stream id is freed within `timeout`.
+    Queue {
+        max_queued: usize,
+        timeout: Duration,
+    },
+}
+
+/// Why a connection of a pool was closed.
+///
+/// Connections are opened and closed by the pools in the background, so the closes
+/// are reported in `tracing` events with this reason in the `reason` field,
+/// and counted per node and reason in the [metrics](crate::transport::metrics::Metrics::node_connections_snapshot).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ConnectionCloseReason {
+    /// The connection was broken by an error, e.g. an I/O error or the node closing it.
+    Error,
+
+    /// The node didn't respond to a keepalive request on the connection in time.
+    KeepaliveTimeout,
+
+    /// The connection was broken by an error after the node had been reported down
+    /// by a status change event.
+    NodeDown,
+
+    /// The pool closed the connection because it exceeded the target pool size,
+    /// or because the node's sharding changed.
+    PoolResize,
+
+    /// The pool was closed, because the node was removed from the cluster
+    /// or ignored by the host filter, or because the session was dropped.
+    PoolShutdown,
+}
+
+impl ConnectionCloseReason {
+    pub(crate) const ALL: [ConnectionCloseReason; 5] = [
+        ConnectionCloseReason::Error,
+        ConnectionCloseReason::KeepaliveTimeout,
+        ConnectionCloseReason::NodeDown,
+        ConnectionCloseReason::PoolResize,
+        ConnectionCloseReason::PoolShutdown,
+    ];
+
+    /// The name of the reason used in `tracing` events, e.g. `keepalive_timeout`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionCloseReason::Error => "error",
+            ConnectionCloseReason::KeepaliveTimeout => "keepalive_timeout",
+            ConnectionCloseReason::NodeDown => "node_down",
+            ConnectionCloseReason::PoolResize => "pool_resize",
+            ConnectionCloseReason::PoolShutdown => "pool_shutdown",
+        }
+    }
+
+    fn of_error(error: &QueryError, node_down: bool) -> Self {
+        if KeepaliveTimeoutError::is_cause_of(error) {
+            ConnectionCloseReason::KeepaliveTimeout
+        } else if node_down {
+            ConnectionCloseReason::NodeDown
+        } else {
+            ConnectionCloseReason::Error
+        }
+    }
+}
+
+impl std::fmt::Display for ConnectionCloseReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct PoolConfig {
     pub(crate) connection_config: ConnectionConfig,
     pub(crate) pool_size: PoolSize,
     pub(crate) can_use_shard_aware_port: bool,
     pub(crate) keepalive_interval: Option<Duration>,
+    pub(crate) metrics: Option<Arc<Metrics>>,
+    #[cfg(feature = "metrics-exporter")]
+    pub(crate) metrics_observer: Option<Arc<dyn MetricsObserver>>,
 }
 
 impl Default for PoolConfig {
@@ -69,6 +164,9 @@ impl Default for PoolConfig {
             pool_size: Default::default(),
             can_use_shard_aware_port: true,
             keepalive_interval: None,
+            metrics: None,
+            #[cfg(feature = "metrics-exporter")]
+            metrics_observer: None,
         }
     }
 }
@@ -155,6 +253,16 @@ pub(crate) struct NodeConnectionPool {
     _refiller_handle: Arc<RemoteHandle<()>>,
     pool_updated_notify: Arc<Notify>,
     endpoint: Arc<RwLock<UntranslatedEndpoint>>,
+    metrics: Option<Arc<Metrics>>,
+    node_down: Arc<AtomicBool>,
+    negotiation: Arc<ArcSwapOption<Negotiation>>,
+}
+
+// What the node advertised and what was negotiated with it
+// when the last connection of the pool was opened.
+struct Negotiation {
+    capabilities: Arc<NodeCapabilities>,
+    settings: NegotiatedSettings,
 }
 
 impl std::fmt::Debug for NodeConnectionPool {
@@ -168,19 +276,24 @@ impl std::fmt::Debug for NodeConnectionPool {
 impl NodeConnectionPool {
     pub(crate) fn new(
         endpoint: UntranslatedEndpoint,
-        #[allow(unused_mut)] mut pool_config: PoolConfig, // `mut` needed only with "cloud" feature
+        mut pool_config: PoolConfig,
         current_keyspace: Option<VerifiedKeyspaceName>,
         pool_empty_notifier: broadcast::Sender<()>,
     ) -> Self {
         let (use_keyspace_request_sender, use_keyspace_request_receiver) = mpsc::channel(1);
         let pool_updated_notify = Arc::new(Notify::new());
 
+        // Statements are re-prepared once per node, not once per connection.
+        pool_config.connection_config.repreparations =
+            Some(Arc::new(Repreparations::new(pool_config.metrics.clone())));
+
         #[cfg(feature = "cloud")]
         if pool_config.connection_config.cloud_config.is_some() {
             let (host_id, address, dc) = match endpoint {
                 UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
                     address,
                     ref datacenter,
+                    ..
                 }) => (None, address, datacenter.as_deref()), // FIXME: Pass DC in ContactPoint
                 UntranslatedEndpoint::Peer(PeerEndpoint {
                     host_id,
@@ -201,6 +314,11 @@ impl NodeConnectionPool {
         }
 
         let arced_endpoint = Arc::new(RwLock::new(endpoint));
+        let metrics = pool_config.metrics.clone();
+        let node_down = Arc::new(AtomicBool::new(false));
+        let negotiation = Arc::new(ArcSwapOption::empty());
+        let background_tasks = pool_config.connection_config.background_tasks.clone();
+        let node_address = arced_endpoint.read().unwrap().address().into_inner();
 
         let refiller = PoolRefiller::new(
             arced_endpoint.clone(),
@@ -208,11 +326,16 @@ impl NodeConnectionPool {
             current_keyspace,
             pool_updated_notify.clone(),
             pool_empty_notifier,
+            node_down.clone(),
+            negotiation.clone(),
         );
 
         let conns = refiller.get_shared_connections();
-        let (fut, refiller_handle) = refiller.run(use_keyspace_request_receiver).remote_handle();
-        tokio::spawn(fut.with_current_subscriber());
+        let refiller_handle = background_tasks.spawn(
+            BackgroundTaskKind::PoolRefiller,
+            Some(node_address),
+            |activity| refiller.run(use_keyspace_request_receiver, activity),
+        );
 
         Self {
             conns,
@@ -220,6 +343,9 @@ impl NodeConnectionPool {
             _refiller_handle: Arc::new(refiller_handle),
             pool_updated_notify,
             endpoint: arced_endpoint,
+            metrics,
+            node_down,
+            negotiation,
         }
     }
 
@@ -227,6 +353,20 @@ impl NodeConnectionPool {
         *self.endpoint.write().unwrap() = UntranslatedEndpoint::Peer(new_endpoint);
     }
 
+    // Connections broken while the node is reported down are closed with
+    // `ConnectionCloseReason::NodeDown`.
+    pub(crate) fn set_node_down(&self, is_down: bool) {
+        self.node_down.store(is_down, Ordering::Relaxed);
+    }
+
+    pub(crate) fn capabilities(&self) -> Option<Arc<NodeCapabilities>> {
+        Some(self.negotiation.load().as_ref()?.capabilities.clone())
+    }
+
+    pub(crate) fn negotiated_settings(&self) -> Option<NegotiatedSettings> {
+        Some(self.negotiation.load().as_ref()?.settings.clone())
+    }
+
     pub(crate) fn sharder(&self) -> Option<Sharder> {
         self.with_connections(|pool_conns| match pool_conns {
             PoolConnections::NotSharded(_) => None,
@@ -239,7 +379,8 @@ impl NodeConnectionPool {
         trace!(token = token.value, "Selecting connection for token");
         self.with_connections(|pool_conns| match pool_conns {
             PoolConnections::NotSharded(conns) => {
-                Self::choose_random_connection_from_slice(conns).unwrap()
+                let conn = Self::choose_random_connection_from_slice(conns).unwrap();
+                self.spill_if_busy(conn, pool_conns, None)
             }
             PoolConnections::Sharded {
                 sharder,
@@ -250,7 +391,9 @@ impl NodeConnectionPool {
                     .try_into()
                     .expect("Shard number doesn't fit in u16");
                 trace!(shard = shard, "Selecting connection for token");
-                Self::connection_for_shard(shard, sharder.nr_shards, connections.as_slice())
+                let conn =
+                    Self::connection_for_shard(shard, sharder.nr_shards, connections.as_slice());
+                self.spill_if_busy(conn, pool_conns, Some(shard))
             }
         })
     }
@@ -259,18 +402,55 @@ impl NodeConnectionPool {
         trace!("Selecting random connection");
         self.with_connections(|pool_conns| match pool_conns {
             PoolConnections::NotSharded(conns) => {
-                Self::choose_random_connection_from_slice(conns).unwrap()
+                let conn = Self::choose_random_connection_from_slice(conns).unwrap();
+                self.spill_if_busy(conn, pool_conns, None)
             }
             PoolConnections::Sharded {
                 sharder,
                 connections,
             } => {
                 let shard: u16 = rand::thread_rng().gen_range(0..sharder.nr_shards.get());
-                Self::connection_for_shard(shard, sharder.nr_shards, connections.as_slice())
+                let conn =
+                    Self::connection_for_shard(shard, sharder.nr_shards, connections.as_slice());
+                self.spill_if_busy(conn, pool_conns, Some(shard))
             }
         })
     }
 
+    // If all stream ids of the chosen connection are in use, returns another connection
+    // with free stream ids, preferring connections to the same shard. Otherwise returns
+    // the chosen connection, whose `StreamExhaustionPolicy` will handle the request.
+    fn spill_if_busy(
+        &self,
+        conn: Arc<Connection>,
+        pool_conns: &PoolConnections,
+        shard: Option<u16>,
+    ) -> Arc<Connection> {
+        if !conn.is_busy() {
+            return conn;
+        }
+
+        let spill_to = match pool_conns {
+            PoolConnections::NotSharded(conns) => conns.iter().find(|c| !c.is_busy()),
+            PoolConnections::Sharded { connections, .. } => shard
+                .and_then(|shard| connections.get(shard as usize))
+                .into_iter()
+                .chain(connections.iter())
+                .flatten()
+                .find(|c| !c.is_busy()),
+        };
+        match spill_to {
+            Some(spill_to) => {
+                trace!("Chosen connection is busy, spilling over to another one");
+                if let Some(metrics) = &self.metrics {
+                    metrics.inc_spilled_requests();
+                }
+                spill_to.clone()
+            }
+            None => conn,
+        }
+    }
+
     // Tries to get a connection to given shard, if it's broken returns any working connection
     fn connection_for_shard(
         shard: u16,
@@ -339,6 +519,20 @@ impl NodeConnectionPool {
         }
     }
 
+    // Waits until the pool has some usable connections.
+    pub(crate) async fn wait_until_ready(&self) {
+        loop {
+            // First, register for the notification
+            // so that we don't miss it
+            let notified = self.pool_updated_notify.notified();
+
+            if let MaybePoolConnections::Ready(_) = **self.conns.load() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
     pub(crate) fn get_working_connections(&self) -> Result<Vec<Arc<Connection>>, QueryError> {
         self.with_connections(|pool_conns| match pool_conns {
             PoolConnections::NotSharded(conns) => conns.clone(),
@@ -478,6 +672,12 @@ struct PoolRefiller {
 
     // Signaled when the connection pool becomes empty
     pool_empty_notifier: broadcast::Sender<()>,
+
+    // Set while the node is reported down by a status change event
+    node_down: Arc<AtomicBool>,
+
+    // Updated whenever a connection is opened
+    negotiation: Arc<ArcSwapOption<Negotiation>>,
 }
 
 #[derive(Debug)]
@@ -493,6 +693,8 @@ impl PoolRefiller {
         current_keyspace: Option<VerifiedKeyspaceName>,
         pool_updated_notify: Arc<Notify>,
         pool_empty_notifier: broadcast::Sender<()>,
+        node_down: Arc<AtomicBool>,
+        negotiation: Arc<ArcSwapOption<Negotiation>>,
     ) -> Self {
         // At the beginning, we assume the node does not have any shards
         // and assume that the node is a Cassandra node
@@ -521,6 +723,8 @@ impl PoolRefiller {
 
             pool_updated_notify,
             pool_empty_notifier,
+            node_down,
+            negotiation,
         }
     }
 
@@ -536,18 +740,20 @@ impl PoolRefiller {
     pub(crate) async fn run(
         mut self,
         mut use_keyspace_request_receiver: mpsc::Receiver<UseKeyspaceRequest>,
+        activity: TaskActivity,
     ) {
         debug!(
             "[{}] Started asynchronous pool worker",
             self.endpoint_description()
         );
 
-        let mut next_refill_time = tokio::time::Instant::now();
+        let runtime = self.pool_config.connection_config.runtime().clone();
+        let mut next_refill_time = runtime.now();
         let mut refill_scheduled = true;
 
         loop {
             tokio::select! {
-                _ = tokio::time::sleep_until(next_refill_time), if refill_scheduled => {
+                _ = runtime.sleep_until(next_refill_time), if refill_scheduled => {
                     self.had_error_since_last_refill = false;
                     self.start_filling();
                     refill_scheduled = false;
@@ -562,14 +768,17 @@ impl PoolRefiller {
                             self.endpoint_description(),
                             self.excess_connections.len()
                         );
-                        self.excess_connections.clear();
+                        self.clear_excess_connections();
                     }
                 }
 
                 evt = self.connection_errors.select_next_some(), if !self.connection_errors.is_empty() => {
                     if let Some(conn) = evt.connection.upgrade() {
                         debug!("[{}] Got error for connection {:p}: {:?}", self.endpoint_description(), Arc::as_ptr(&conn), evt.error);
-                        self.remove_connection(conn, evt.error);
+                        let reason = ConnectionCloseReason::of_error(&evt.error, self.node_down.load(Ordering::Relaxed));
+                        if self.remove_connection(conn.clone(), evt.error) {
+                            self.report_connection_closed(&conn, reason);
+                        }
                     }
                 }
 
@@ -586,6 +795,7 @@ impl PoolRefiller {
                     }
                 }
             }
+            activity.touch();
             trace!(
                 pool_state = format!("{:?}", ShardedConnectionVectorWrapper(&self.conns)).as_str()
             );
@@ -604,7 +814,7 @@ impl PoolRefiller {
                     delay.as_millis(),
                 );
 
-                next_refill_time = tokio::time::Instant::now() + delay;
+                next_refill_time = runtime.now() + delay;
                 refill_scheduled = true;
             }
         }
@@ -706,6 +916,7 @@ impl PoolRefiller {
     fn handle_ready_connection(&mut self, evt: OpenedConnectionEvent) {
         match evt.result {
             Err(err) => {
+                self.report_connection_failed(evt.requested_shard, &err);
                 if evt.requested_shard.is_some() {
                     // If we failed to connect to a shard-aware port,
                     // fall back to the non-shard-aware port.
@@ -743,6 +954,11 @@ impl PoolRefiller {
                 }
             }
             Ok((connection, error_receiver)) => {
+                self.negotiation.store(Some(Arc::new(Negotiation {
+                    capabilities: connection.get_capabilities().clone(),
+                    settings: connection.get_negotiated_settings(),
+                })));
+
                 // Update sharding and optionally reshard
                 let shard_info = connection.get_shard_info().as_ref();
                 let sharder = shard_info.map(|s| s.get_sharder());
@@ -799,6 +1015,7 @@ impl PoolRefiller {
                         self.active_connection_count() + 1,
                     );
 
+                    self.report_connection_opened(&conn);
                     self.connection_errors
                         .push(wait_for_error(Arc::downgrade(&conn), error_receiver).boxed());
                     self.conns[shard_id].push(conn);
@@ -816,6 +1033,8 @@ impl PoolRefiller {
                         self.endpoint_description(),
                         shard_id,
                     );
+                    self.report_connection_opened(&connection);
+                    self.report_connection_closed(&connection, ConnectionCloseReason::PoolResize);
 
                     self.start_opening_connection(None);
                 } else {
@@ -832,6 +1051,7 @@ impl PoolRefiller {
                         shard_id,
                     );
 
+                    self.report_connection_opened(&conn);
                     self.connection_errors
                         .push(wait_for_error(Arc::downgrade(&conn), error_receiver).boxed());
                     self.excess_connections.push(conn);
@@ -843,7 +1063,7 @@ impl PoolRefiller {
                             self.endpoint_description(),
                             excess_connection_limit,
                         );
-                        self.excess_connections.clear();
+                        self.clear_excess_connections();
                     }
                 }
             }
@@ -864,7 +1084,10 @@ impl PoolRefiller {
         &self,
         mut endpoint: UntranslatedEndpoint,
     ) -> impl Future<Output = UntranslatedEndpoint> {
-        let cloud_config = self.pool_config.connection_config.cloud_config.clone();
+        let connection_config = &self.pool_config.connection_config;
+        let cloud_config = connection_config.cloud_config.clone();
+        let resolver = connection_config.hostname_resolver.clone();
+        let preference = connection_config.address_family_preference;
         async move {
             if let Some(cloud_config) = cloud_config {
                 // If we operate in the serverless Cloud, then we substitute every node's address
@@ -879,8 +1102,10 @@ impl PoolRefiller {
                     if let Some(dc) = datacenter.as_deref() {
                         if let Some(dc_config) = cloud_config.get_datacenters().get(dc) {
                             let hostname = dc_config.get_server();
-                            if let Ok(resolved) = resolve_hostname(hostname).await {
-                                *address = NodeAddr::Untranslatable(resolved)
+                            if let Ok(resolved) =
+                                resolve_hostname_with(resolver.as_ref(), preference, hostname).await
+                            {
+                                *address = NodeAddr::Untranslatable(resolved[0])
                             } else {
                                 warn!(
                                         "Couldn't resolve address: {} of datacenter {} that node {} resides in; therefore address \
@@ -975,12 +1200,60 @@ impl PoolRefiller {
         // If the sharder has changed, we can throw away all previous connections.
         // All connections to the same live node will have the same sharder,
         // so the old ones will become dead very soon anyway.
-        self.conns.clear();
+        for conn in std::mem::take(&mut self.conns).into_iter().flatten() {
+            self.report_connection_closed(&conn, ConnectionCloseReason::PoolResize);
+        }
 
         let shard_count = new_sharder.map_or(1, |s| s.nr_shards.get() as usize);
         self.conns.resize_with(shard_count, Vec::new);
 
-        self.excess_connections.clear();
+        self.clear_excess_connections();
+    }
+
+    fn clear_excess_connections(&mut self) {
+        for conn in std::mem::take(&mut self.excess_connections) {
+            self.report_connection_closed(&conn, ConnectionCloseReason::PoolResize);
+        }
+    }
+
+    fn report_connection_opened(&self, connection: &Connection) {
+        let address = self.endpoint_description();
+        debug!(
+            node = %address,
+            shard = connection.get_shard_info().as_ref().map(|info| info.shard),
+            connection_id = connection.get_id(),
+            "Connection opened",
+        );
+        if let Some(metrics) = &self.pool_config.metrics {
+            metrics.inc_opened_connections(address.into_inner());
+        }
+    }
+
+    fn report_connection_failed(&self, requested_shard: Option<Shard>, error: &QueryError) {
+        let address = self.endpoint_description();
+        debug!(
+            node = %address,
+            shard = requested_shard,
+            error = %error,
+            "Failed to open connection",
+        );
+        if let Some(metrics) = &self.pool_config.metrics {
+            metrics.inc_failed_connections(address.into_inner());
+        }
+    }
+
+    fn report_connection_closed(&self, connection: &Connection, reason: ConnectionCloseReason) {
+        let address = self.endpoint_description();
+        debug!(
+            node = %address,
+            shard = connection.get_shard_info().as_ref().map(|info| info.shard),
+            connection_id = connection.get_id(),
+            reason = reason.as_str(),
+            "Connection closed",
+        );
+        if let Some(metrics) = &self.pool_config.metrics {
+            metrics.inc_closed_connections(address.into_inner(), reason);
+        }
     }
 
     // Updates `shared_conns` based on `conns`.
@@ -1006,13 +1279,16 @@ impl PoolRefiller {
         // Make the connection list available
         self.shared_conns.store(new_conns);
 
+        #[cfg(feature = "metrics-exporter")]
+        self.report_pool_size();
+
         // Notify potential waiters
         self.pool_updated_notify.notify_waiters();
     }
 
     // Removes given connection from the pool. It looks both into active
-    // connections and excess connections.
-    fn remove_connection(&mut self, connection: Arc<Connection>, last_error: QueryError) {
+    // connections and excess connections. Returns whether the connection was found.
+    fn remove_connection(&mut self, connection: Arc<Connection>, last_error: QueryError) -> bool {
         let ptr = Arc::as_ptr(&connection);
 
         let maybe_remove_in_vec = |v: &mut Vec<Arc<Connection>>| -> bool {
@@ -1049,7 +1325,7 @@ impl PoolRefiller {
                 let _ = self.pool_empty_notifier.send(());
             }
             self.update_shared_conns(Some(last_error));
-            return;
+            return true;
         }
 
         // If we didn't find it, it might sit in the excess_connections bucket
@@ -1059,7 +1335,7 @@ impl PoolRefiller {
                 self.endpoint_description(),
                 ptr,
             );
-            return;
+            return true;
         }
 
         trace!(
@@ -1067,6 +1343,7 @@ impl PoolRefiller {
             self.endpoint_description(),
             ptr,
         );
+        false
     }
 
     // Sets current keyspace for available connections.
@@ -1085,6 +1362,7 @@ impl PoolRefiller {
         let keyspace_name = keyspace_name.clone();
         let address = self.endpoint.read().unwrap().address();
         let connect_timeout = self.pool_config.connection_config.connect_timeout;
+        let runtime = self.pool_config.connection_config.runtime().clone();
 
         let fut = async move {
             let mut use_keyspace_futures = Vec::new();
@@ -1100,7 +1378,8 @@ impl PoolRefiller {
                 return Ok(());
             }
 
-            let use_keyspace_results: Vec<Result<(), QueryError>> = tokio::time::timeout(
+            let use_keyspace_results: Vec<Result<(), QueryError>> = runtime::timeout(
+                runtime.as_ref(),
                 connect_timeout,
                 futures::future::join_all(use_keyspace_futures),
             )
@@ -1134,17 +1413,21 @@ impl PoolRefiller {
             Err(QueryError::IoError(io_error.unwrap()))
         };
 
-        tokio::task::spawn(
-            async move {
-                let res = fut.await;
-                match &res {
-                    Ok(()) => debug!("[{}] Successfully changed current keyspace", address),
-                    Err(err) => warn!("[{}] Failed to change keyspace: {:?}", address, err),
-                }
-                let _ = response_sender.send(res);
-            }
-            .with_current_subscriber(),
-        );
+        self.pool_config
+            .connection_config
+            .background_tasks
+            .spawn_detached(
+                BackgroundTaskKind::UseKeyspace,
+                Some(address.into_inner()),
+                async move {
+                    let res = fut.await;
+                    match &res {
+                        Ok(()) => debug!("[{}] Successfully changed current keyspace", address),
+                        Err(err) => warn!("[{}] Failed to change keyspace: {:?}", address, err),
+                    }
+                    let _ = response_sender.send(res);
+                },
+            );
     }
 
     // Requires the keyspace to be set
@@ -1186,6 +1469,25 @@ impl PoolRefiller {
         self.conns.iter().map(Vec::len).sum::<usize>()
     }
 
+    #[cfg(feature = "metrics-exporter")]
+    fn report_pool_size(&self) {
+        let observer = match self.pool_config.metrics_observer.as_deref() {
+            Some(observer) => observer,
+            None => return,
+        };
+        let endpoint = self.endpoint.read().unwrap();
+        let datacenter = match &*endpoint {
+            UntranslatedEndpoint::ContactPoint(contact_point) => &contact_point.datacenter,
+            UntranslatedEndpoint::Peer(peer) => &peer.datacenter,
+        };
+        let labels = NodeLabels {
+            address: endpoint.address().into_inner(),
+            datacenter: datacenter.as_deref(),
+            shard: None,
+        };
+        observer.on_pool_size(&labels, self.active_connection_count());
+    }
+
     fn excess_connection_limit(&self) -> usize {
         match self.pool_config.pool_size {
             PoolSize::PerShard(_) => {
@@ -1202,6 +1504,15 @@ impl PoolRefiller {
     }
 }
 
+impl Drop for PoolRefiller {
+    fn drop(&mut self) {
+        // The refiller is dropped together with the pool, closing its connections.
+        for conn in self.conns.iter().flatten().chain(&self.excess_connections) {
+            self.report_connection_closed(conn, ConnectionCloseReason::PoolShutdown);
+        }
+    }
+}
+
 struct BrokenConnectionEvent {
     connection: Weak<Connection>,
     error: QueryError,
@@ -1257,12 +1568,51 @@ async fn open_connection_to_shard_aware_port(
 
 #[cfg(test)]
 mod tests {
-    use super::open_connection_to_shard_aware_port;
+    use super::{open_connection_to_shard_aware_port, ConnectionCloseReason};
     use crate::routing::{ShardCount, Sharder};
-    use crate::transport::connection::ConnectionConfig;
+    use crate::transport::connection::{ConnectionConfig, KeepaliveTimeoutError};
+    use crate::transport::errors::QueryError;
     use crate::transport::node::ResolvedContactPoint;
     use crate::transport::topology::UntranslatedEndpoint;
-    use std::net::{SocketAddr, ToSocketAddrs};
+    use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs};
+    use std::sync::Arc;
+
+    #[test]
+    fn close_reasons_of_errors() {
+        let io_error = |inner: Box<dyn std::error::Error + Send + Sync>| {
+            QueryError::IoError(Arc::new(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                inner,
+            )))
+        };
+        let keepalive_timeout = io_error(Box::new(KeepaliveTimeoutError {
+            node_address: Ipv4Addr::LOCALHOST.into(),
+        }));
+        let broken = io_error("Connection broken".into());
+
+        for node_down in [false, true] {
+            assert_eq!(
+                ConnectionCloseReason::of_error(&keepalive_timeout, node_down),
+                ConnectionCloseReason::KeepaliveTimeout
+            );
+        }
+        assert_eq!(
+            ConnectionCloseReason::of_error(&broken, false),
+            ConnectionCloseReason::Error
+        );
+        assert_eq!(
+            ConnectionCloseReason::of_error(&broken, true),
+            ConnectionCloseReason::NodeDown
+        );
+        assert_eq!(
+            ConnectionCloseReason::of_error(&QueryError::TimeoutError, false),
+            ConnectionCloseReason::Error
+        );
+        assert_eq!(
+            ConnectionCloseReason::KeepaliveTimeout.to_string(),
+            "keepalive_timeout"
+        );
+    }
 
     // Open many connections to a node
     // Port collision should occur
@@ -1300,6 +1650,7 @@ mod tests {
                 UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
                     address: connect_address,
                     datacenter: None,
+                    fallback_addresses: Vec::new(),
                 }),
                 0,
                 sharder.clone(),
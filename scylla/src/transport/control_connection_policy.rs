@@ -0,0 +1,217 @@
+//! Control connection policy.
+//!
+//! The control connection is the single connection which the driver uses to fetch
+//! cluster metadata and to receive events about topology, status and schema changes.
+//! [`ControlConnectionPolicy`] decides which node it is established to,
+//! and when it should be moved to another node.
+
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::transport::node::{NodeAddr, ResolvedContactPoint};
+use crate::transport::topology::{PeerEndpoint, UntranslatedEndpoint};
+
+/// Decides which node the control connection is established to, and when
+/// it is moved to another node.
+///
+/// The default policy picks nodes at random and moves the control connection
+/// only when fetching metadata on it fails.
+///
+/// # Example
+/// ```
+/// # use std::time::Duration;
+/// # use scylla::transport::control_connection_policy::ControlConnectionPolicy;
+/// let policy = ControlConnectionPolicy::new()
+///     .preferred_datacenter("my-local-dc")
+///     .exclude_node("10.0.0.7".parse().unwrap())
+///     .unhealthy_threshold(Duration::from_secs(5))
+///     .heartbeat_interval(Duration::from_secs(1));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ControlConnectionPolicy {
+    preferred_datacenter: Option<String>,
+    excluded_nodes: HashSet<IpAddr>,
+    unhealthy_threshold: Option<Duration>,
+    heartbeat_interval: Option<Duration>,
+}
+
+impl ControlConnectionPolicy {
+    /// Creates the default policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the driver establish the control connection to a node of the given
+    /// datacenter whenever one is known, and fall back to other datacenters
+    /// only when none of its nodes is reachable.
+    ///
+    /// Until the first metadata is fetched, the datacenters of the contact points
+    /// are not known, so the control connection may be established to a node
+    /// of another datacenter at first. It is moved to the preferred datacenter
+    /// as soon as the first metadata is fetched.
+    pub fn preferred_datacenter(mut self, datacenter: impl Into<String>) -> Self {
+        self.preferred_datacenter = Some(datacenter.into());
+        self
+    }
+
+    /// Makes the driver never establish the control connection to the node
+    /// with the given address, e.g. because it is overloaded by other duties.
+    ///
+    /// The address is compared with the address broadcast by the node,
+    /// before address translation, and with the resolved addresses of contact points.
+    pub fn exclude_node(mut self, address: IpAddr) -> Self {
+        self.excluded_nodes.insert(address);
+        self
+    }
+
+    /// Sets how long the control node may take to answer a metadata fetch
+    /// or a heartbeat before it is considered unhealthy. The control connection
+    /// is then moved to another node.
+    ///
+    /// By default there is no threshold, so the control connection is moved only
+    /// when a request on it fails.
+    pub fn unhealthy_threshold(mut self, threshold: Duration) -> Self {
+        self.unhealthy_threshold = Some(threshold);
+        self
+    }
+
+    /// Makes the driver send a heartbeat on the control connection at the given interval,
+    /// to detect a control node which stopped responding, and thus delivering events,
+    /// between metadata refreshes.
+    ///
+    /// A heartbeat fails if the control connection is broken, or if the node doesn't
+    /// answer it within the [unhealthy threshold](Self::unhealthy_threshold).
+    /// The control connection is then moved to another node.
+    ///
+    /// By default no heartbeats are sent.
+    pub fn heartbeat_interval(mut self, interval: Duration) -> Self {
+        self.heartbeat_interval = Some(interval);
+        self
+    }
+
+    pub(crate) fn get_unhealthy_threshold(&self) -> Option<Duration> {
+        self.unhealthy_threshold
+    }
+
+    pub(crate) fn get_heartbeat_interval(&self) -> Option<Duration> {
+        self.heartbeat_interval
+    }
+
+    /// Whether the control connection may be established to the node.
+    pub(crate) fn accepts(&self, endpoint: &UntranslatedEndpoint) -> bool {
+        !self.excluded_nodes.contains(&endpoint.address().ip())
+    }
+
+    /// Whether the node belongs to the preferred datacenter, if there is one.
+    pub(crate) fn prefers(&self, endpoint: &UntranslatedEndpoint) -> bool {
+        let datacenter = match endpoint {
+            UntranslatedEndpoint::ContactPoint(ResolvedContactPoint { datacenter, .. }) => {
+                datacenter
+            }
+            UntranslatedEndpoint::Peer(PeerEndpoint { datacenter, .. }) => datacenter,
+        };
+        match &self.preferred_datacenter {
+            Some(preferred) => datacenter.as_ref() == Some(preferred),
+            None => true,
+        }
+    }
+
+    /// Returns the nodes to which the control connection may be established,
+    /// the ones from the preferred datacenter first, otherwise keeping their order.
+    pub(crate) fn candidates(
+        &self,
+        endpoints: impl IntoIterator<Item = UntranslatedEndpoint>,
+    ) -> Vec<UntranslatedEndpoint> {
+        let mut candidates: Vec<UntranslatedEndpoint> = endpoints
+            .into_iter()
+            .filter(|endpoint| self.accepts(endpoint))
+            .collect();
+        candidates.sort_by_key(|endpoint| !self.prefers(endpoint));
+        candidates
+    }
+}
+
+/// The state of the control connection, as reported by
+/// [`Session::get_control_connection_status`](crate::transport::session::Session::get_control_connection_status).
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct ControlConnectionStatus {
+    /// Address of the node the control connection is established to.
+    pub address: NodeAddr,
+    /// Datacenter of the node, if known.
+    pub datacenter: Option<String>,
+    /// How many times the control connection was moved to another node
+    /// because the previous one failed or was unhealthy.
+    pub failovers: u64,
+}
+
+impl ControlConnectionStatus {
+    pub(crate) fn new(endpoint: &UntranslatedEndpoint, failovers: u64) -> Self {
+        let datacenter = match endpoint {
+            UntranslatedEndpoint::ContactPoint(ResolvedContactPoint { datacenter, .. }) => {
+                datacenter.clone()
+            }
+            UntranslatedEndpoint::Peer(PeerEndpoint { datacenter, .. }) => datacenter.clone(),
+        };
+        Self {
+            address: endpoint.address(),
+            datacenter,
+            failovers,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ControlConnectionPolicy;
+    use crate::transport::node::{NodeAddr, ResolvedContactPoint};
+    use crate::transport::topology::{PeerEndpoint, UntranslatedEndpoint};
+    use uuid::Uuid;
+
+    fn peer(address: &str, datacenter: &str) -> UntranslatedEndpoint {
+        UntranslatedEndpoint::Peer(PeerEndpoint {
+            host_id: Uuid::new_v4(),
+            address: NodeAddr::Translatable(address.parse().unwrap()),
+            datacenter: Some(datacenter.to_owned()),
+            rack: None,
+        })
+    }
+
+    fn addresses(endpoints: &[UntranslatedEndpoint]) -> Vec<String> {
+        endpoints
+            .iter()
+            .map(|endpoint| endpoint.address().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn candidates_prefer_datacenter_and_skip_excluded_nodes() {
+        let endpoints = vec![
+            peer("10.0.0.1:9042", "dc2"),
+            peer("10.0.0.2:9042", "dc1"),
+            UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                address: "10.0.0.3:9042".parse().unwrap(),
+                datacenter: None,
+                fallback_addresses: Vec::new(),
+            }),
+            peer("10.0.0.4:9042", "dc1"),
+            peer("10.0.0.5:9042", "dc2"),
+        ];
+
+        let default_policy = ControlConnectionPolicy::new();
+        assert_eq!(
+            addresses(&default_policy.candidates(endpoints.clone())),
+            addresses(&endpoints)
+        );
+
+        let policy = ControlConnectionPolicy::new()
+            .preferred_datacenter("dc1")
+            .exclude_node("10.0.0.4".parse().unwrap())
+            .exclude_node("10.0.0.5".parse().unwrap());
+        assert_eq!(
+            addresses(&policy.candidates(endpoints)),
+            ["10.0.0.2:9042", "10.0.0.1:9042", "10.0.0.3:9042"]
+        );
+    }
+}
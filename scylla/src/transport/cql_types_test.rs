@@ -25,7 +25,7 @@ async fn init_test(table_name: &str, type_name: &str) -> Session {
     let ks = unique_keyspace_name();
 
     session
-        .query(
+        .query_unpaged(
             format!(
                 "CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = \
             {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}",
@@ -38,12 +38,12 @@ async fn init_test(table_name: &str, type_name: &str) -> Session {
     session.use_keyspace(ks, false).await.unwrap();
 
     session
-        .query(format!("DROP TABLE IF EXISTS {}", table_name), &[])
+        .query_unpaged(format!("DROP TABLE IF EXISTS {}", table_name), &[])
         .await
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             format!(
                 "CREATE TABLE IF NOT EXISTS {} (id int PRIMARY KEY, val {})",
                 table_name, type_name
@@ -75,20 +75,20 @@ where
         let insert_string_encoded_value =
             format!("INSERT INTO {} (id, val) VALUES (0, {})", type_name, test);
         session
-            .query(insert_string_encoded_value, &[])
+            .query_unpaged(insert_string_encoded_value, &[])
             .await
             .unwrap();
 
         let insert_bound_value = format!("INSERT INTO {} (id, val) VALUES (1, ?)", type_name);
         let value_to_bound = T::from_str(test).ok().unwrap();
         session
-            .query(insert_bound_value, (value_to_bound,))
+            .query_unpaged(insert_bound_value, (value_to_bound,))
             .await
             .unwrap();
 
         let select_values = format!("SELECT val from {}", type_name);
         let read_values: Vec<T> = session
-            .query(select_values, &[])
+            .query_unpaged(select_values, &[])
             .await
             .unwrap()
             .rows
@@ -172,13 +172,13 @@ async fn test_counter() {
         let update_bound_value = format!("UPDATE {} SET val = val + ? WHERE id = ?", type_name);
         let value_to_bound = Counter(i64::from_str(test).unwrap());
         session
-            .query(update_bound_value, (value_to_bound, i as i32))
+            .query_unpaged(update_bound_value, (value_to_bound, i as i32))
             .await
             .unwrap();
 
         let select_values = format!("SELECT val FROM {} WHERE id = ?", type_name);
         let read_values: Vec<Counter> = session
-            .query(select_values, (i as i32,))
+            .query_unpaged(select_values, (i as i32,))
             .await
             .unwrap()
             .rows
@@ -250,7 +250,7 @@ async fn test_naive_date() {
 
     for (date_text, date) in tests.iter() {
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "INSERT INTO chrono_naive_date_tests (id, val) VALUES (0, '{}')",
                     date_text
@@ -261,7 +261,7 @@ async fn test_naive_date() {
             .unwrap();
 
         let read_date: Option<NaiveDate> = session
-            .query("SELECT val from chrono_naive_date_tests", &[])
+            .query_unpaged("SELECT val from chrono_naive_date_tests", &[])
             .await
             .unwrap()
             .rows
@@ -277,7 +277,7 @@ async fn test_naive_date() {
         // If date is representable by NaiveDate try inserting it and reading again
         if let Some(naive_date) = date {
             session
-                .query(
+                .query_unpaged(
                     "INSERT INTO chrono_naive_date_tests (id, val) VALUES (0, ?)",
                     (naive_date,),
                 )
@@ -285,7 +285,7 @@ async fn test_naive_date() {
                 .unwrap();
 
             let (read_date,): (NaiveDate,) = session
-                .query("SELECT val from chrono_naive_date_tests", &[])
+                .query_unpaged("SELECT val from chrono_naive_date_tests", &[])
                 .await
                 .unwrap()
                 .rows
@@ -316,7 +316,7 @@ async fn test_cql_date() {
 
     for (date_text, date) in &tests {
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "INSERT INTO cql_date_tests (id, val) VALUES (0, '{}')",
                     date_text
@@ -327,7 +327,7 @@ async fn test_cql_date() {
             .unwrap();
 
         let read_date: CqlDate = session
-            .query("SELECT val from cql_date_tests", &[])
+            .query_unpaged("SELECT val from cql_date_tests", &[])
             .await
             .unwrap()
             .rows
@@ -342,7 +342,7 @@ async fn test_cql_date() {
 
     // 1 less/more than min/max values allowed by the database should cause error
     session
-        .query(
+        .query_unpaged(
             "INSERT INTO cql_date_tests (id, val) VALUES (0, '-5877641-06-22')",
             &[],
         )
@@ -350,7 +350,7 @@ async fn test_cql_date() {
         .unwrap_err();
 
     session
-        .query(
+        .query_unpaged(
             "INSERT INTO cql_date_tests (id, val) VALUES (0, '5881580-07-12')",
             &[],
         )
@@ -402,7 +402,7 @@ async fn test_date() {
 
     for (date_text, date) in tests.iter() {
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "INSERT INTO time_date_tests (id, val) VALUES (0, '{}')",
                     date_text
@@ -413,7 +413,7 @@ async fn test_date() {
             .unwrap();
 
         let read_date = session
-            .query("SELECT val from time_date_tests", &[])
+            .query_unpaged("SELECT val from time_date_tests", &[])
             .await
             .unwrap()
             .first_row_typed::<(Date,)>()
@@ -425,7 +425,7 @@ async fn test_date() {
         // If date is representable by time::Date try inserting it and reading again
         if let Some(date) = date {
             session
-                .query(
+                .query_unpaged(
                     "INSERT INTO time_date_tests (id, val) VALUES (0, ?)",
                     (date,),
                 )
@@ -433,7 +433,7 @@ async fn test_date() {
                 .unwrap();
 
             let (read_date,) = session
-                .query("SELECT val from time_date_tests", &[])
+                .query_unpaged("SELECT val from time_date_tests", &[])
                 .await
                 .unwrap()
                 .first_row_typed::<(Date,)>()
@@ -464,7 +464,7 @@ async fn test_cql_time() {
     for (time_str, time_duration) in &tests {
         // Insert time as a string and verify that it matches
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "INSERT INTO cql_time_tests (id, val) VALUES (0, '{}')",
                     time_str
@@ -475,7 +475,7 @@ async fn test_cql_time() {
             .unwrap();
 
         let (read_time,) = session
-            .query("SELECT val from cql_time_tests", &[])
+            .query_unpaged("SELECT val from cql_time_tests", &[])
             .await
             .unwrap()
             .rows
@@ -489,7 +489,7 @@ async fn test_cql_time() {
 
         // Insert time as a bound CqlTime value and verify that it matches
         session
-            .query(
+            .query_unpaged(
                 "INSERT INTO cql_time_tests (id, val) VALUES (0, ?)",
                 (*time_duration,),
             )
@@ -497,7 +497,7 @@ async fn test_cql_time() {
             .unwrap();
 
         let (read_time,) = session
-            .query("SELECT val from cql_time_tests", &[])
+            .query_unpaged("SELECT val from cql_time_tests", &[])
             .await
             .unwrap()
             .rows
@@ -524,7 +524,7 @@ async fn test_cql_time() {
 
     for time_str in &invalid_tests {
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "INSERT INTO cql_time_tests (id, val) VALUES (0, '{}')",
                     time_str
@@ -567,7 +567,7 @@ async fn test_naive_time() {
     for (time_text, time) in tests.iter() {
         // Insert as string and read it again
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "INSERT INTO chrono_time_tests (id, val) VALUES (0, '{}')",
                     time_text
@@ -578,7 +578,7 @@ async fn test_naive_time() {
             .unwrap();
 
         let (read_time,) = session
-            .query("SELECT val from chrono_time_tests", &[])
+            .query_unpaged("SELECT val from chrono_time_tests", &[])
             .await
             .unwrap()
             .first_row_typed::<(NaiveTime,)>()
@@ -588,7 +588,7 @@ async fn test_naive_time() {
 
         // Insert as type and read it again
         session
-            .query(
+            .query_unpaged(
                 "INSERT INTO chrono_time_tests (id, val) VALUES (0, ?)",
                 (time,),
             )
@@ -596,7 +596,7 @@ async fn test_naive_time() {
             .unwrap();
 
         let (read_time,) = session
-            .query("SELECT val from chrono_time_tests", &[])
+            .query_unpaged("SELECT val from chrono_time_tests", &[])
             .await
             .unwrap()
             .first_row_typed::<(NaiveTime,)>()
@@ -607,7 +607,7 @@ async fn test_naive_time() {
     // chrono can represent leap seconds, this should not panic
     let leap_second = NaiveTime::from_hms_nano_opt(23, 59, 59, 1_500_000_000);
     session
-        .query(
+        .query_unpaged(
             "INSERT INTO cql_time_tests (id, val) VALUES (0, ?)",
             (leap_second,),
         )
@@ -646,7 +646,7 @@ async fn test_time() {
     for (time_text, time) in tests.iter() {
         // Insert as string and read it again
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "INSERT INTO time_time_tests (id, val) VALUES (0, '{}')",
                     time_text
@@ -657,7 +657,7 @@ async fn test_time() {
             .unwrap();
 
         let (read_time,) = session
-            .query("SELECT val from time_time_tests", &[])
+            .query_unpaged("SELECT val from time_time_tests", &[])
             .await
             .unwrap()
             .first_row_typed::<(Time,)>()
@@ -667,7 +667,7 @@ async fn test_time() {
 
         // Insert as type and read it again
         session
-            .query(
+            .query_unpaged(
                 "INSERT INTO time_time_tests (id, val) VALUES (0, ?)",
                 (time,),
             )
@@ -675,7 +675,7 @@ async fn test_time() {
             .unwrap();
 
         let (read_time,) = session
-            .query("SELECT val from time_time_tests", &[])
+            .query_unpaged("SELECT val from time_time_tests", &[])
             .await
             .unwrap()
             .first_row_typed::<(Time,)>()
@@ -716,7 +716,7 @@ async fn test_cql_timestamp() {
     for (timestamp_str, timestamp_duration) in &tests {
         // Insert timestamp as a string and verify that it matches
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "INSERT INTO cql_timestamp_tests (id, val) VALUES (0, '{}')",
                     timestamp_str
@@ -727,7 +727,7 @@ async fn test_cql_timestamp() {
             .unwrap();
 
         let (read_timestamp,) = session
-            .query("SELECT val from cql_timestamp_tests", &[])
+            .query_unpaged("SELECT val from cql_timestamp_tests", &[])
             .await
             .unwrap()
             .rows
@@ -741,7 +741,7 @@ async fn test_cql_timestamp() {
 
         // Insert timestamp as a bound CqlTimestamp value and verify that it matches
         session
-            .query(
+            .query_unpaged(
                 "INSERT INTO cql_timestamp_tests (id, val) VALUES (0, ?)",
                 (*timestamp_duration,),
             )
@@ -749,7 +749,7 @@ async fn test_cql_timestamp() {
             .unwrap();
 
         let (read_timestamp,) = session
-            .query("SELECT val from cql_timestamp_tests", &[])
+            .query_unpaged("SELECT val from cql_timestamp_tests", &[])
             .await
             .unwrap()
             .rows
@@ -821,7 +821,7 @@ async fn test_date_time() {
     for (datetime_text, datetime) in tests.iter() {
         // Insert as string and read it again
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "INSERT INTO chrono_datetime_tests (id, val) VALUES (0, '{}')",
                     datetime_text
@@ -832,7 +832,7 @@ async fn test_date_time() {
             .unwrap();
 
         let (read_datetime,) = session
-            .query("SELECT val from chrono_datetime_tests", &[])
+            .query_unpaged("SELECT val from chrono_datetime_tests", &[])
             .await
             .unwrap()
             .first_row_typed::<(DateTime<Utc>,)>()
@@ -842,7 +842,7 @@ async fn test_date_time() {
 
         // Insert as type and read it again
         session
-            .query(
+            .query_unpaged(
                 "INSERT INTO chrono_datetime_tests (id, val) VALUES (0, ?)",
                 (datetime,),
             )
@@ -850,7 +850,7 @@ async fn test_date_time() {
             .unwrap();
 
         let (read_datetime,) = session
-            .query("SELECT val from chrono_datetime_tests", &[])
+            .query_unpaged("SELECT val from chrono_datetime_tests", &[])
             .await
             .unwrap()
             .first_row_typed::<(DateTime<Utc>,)>()
@@ -870,7 +870,7 @@ async fn test_date_time() {
     )
     .and_utc();
     session
-        .query(
+        .query_unpaged(
             "INSERT INTO chrono_datetime_tests (id, val) VALUES (0, ?)",
             (nanosecond_precision_1st_half,),
         )
@@ -878,7 +878,7 @@ async fn test_date_time() {
         .unwrap();
 
     let (read_datetime,) = session
-        .query("SELECT val from chrono_datetime_tests", &[])
+        .query_unpaged("SELECT val from chrono_datetime_tests", &[])
         .await
         .unwrap()
         .first_row_typed::<(DateTime<Utc>,)>()
@@ -896,7 +896,7 @@ async fn test_date_time() {
     )
     .and_utc();
     session
-        .query(
+        .query_unpaged(
             "INSERT INTO chrono_datetime_tests (id, val) VALUES (0, ?)",
             (nanosecond_precision_2nd_half,),
         )
@@ -904,7 +904,7 @@ async fn test_date_time() {
         .unwrap();
 
     let (read_datetime,) = session
-        .query("SELECT val from chrono_datetime_tests", &[])
+        .query_unpaged("SELECT val from chrono_datetime_tests", &[])
         .await
         .unwrap()
         .first_row_typed::<(DateTime<Utc>,)>()
@@ -918,7 +918,7 @@ async fn test_date_time() {
     )
     .and_utc();
     session
-        .query(
+        .query_unpaged(
             "INSERT INTO cql_datetime_tests (id, val) VALUES (0, ?)",
             (leap_second,),
         )
@@ -981,7 +981,7 @@ async fn test_offset_date_time() {
     for (datetime_text, datetime) in tests.iter() {
         // Insert as string and read it again
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "INSERT INTO time_datetime_tests (id, val) VALUES (0, '{}')",
                     datetime_text
@@ -992,7 +992,7 @@ async fn test_offset_date_time() {
             .unwrap();
 
         let (read_datetime,) = session
-            .query("SELECT val from time_datetime_tests", &[])
+            .query_unpaged("SELECT val from time_datetime_tests", &[])
             .await
             .unwrap()
             .first_row_typed::<(OffsetDateTime,)>()
@@ -1002,7 +1002,7 @@ async fn test_offset_date_time() {
 
         // Insert as type and read it again
         session
-            .query(
+            .query_unpaged(
                 "INSERT INTO time_datetime_tests (id, val) VALUES (0, ?)",
                 (datetime,),
             )
@@ -1010,7 +1010,7 @@ async fn test_offset_date_time() {
             .unwrap();
 
         let (read_datetime,) = session
-            .query("SELECT val from time_datetime_tests", &[])
+            .query_unpaged("SELECT val from time_datetime_tests", &[])
             .await
             .unwrap()
             .first_row_typed::<(OffsetDateTime,)>()
@@ -1030,7 +1030,7 @@ async fn test_offset_date_time() {
     )
     .assume_utc();
     session
-        .query(
+        .query_unpaged(
             "INSERT INTO time_datetime_tests (id, val) VALUES (0, ?)",
             (nanosecond_precision_1st_half,),
         )
@@ -1038,7 +1038,7 @@ async fn test_offset_date_time() {
         .unwrap();
 
     let (read_datetime,) = session
-        .query("SELECT val from time_datetime_tests", &[])
+        .query_unpaged("SELECT val from time_datetime_tests", &[])
         .await
         .unwrap()
         .first_row_typed::<(OffsetDateTime,)>()
@@ -1056,7 +1056,7 @@ async fn test_offset_date_time() {
     )
     .assume_utc();
     session
-        .query(
+        .query_unpaged(
             "INSERT INTO time_datetime_tests (id, val) VALUES (0, ?)",
             (nanosecond_precision_2nd_half,),
         )
@@ -1064,7 +1064,7 @@ async fn test_offset_date_time() {
         .unwrap();
 
     let (read_datetime,) = session
-        .query("SELECT val from time_datetime_tests", &[])
+        .query_unpaged("SELECT val from time_datetime_tests", &[])
         .await
         .unwrap()
         .first_row_typed::<(OffsetDateTime,)>()
@@ -1101,7 +1101,7 @@ async fn test_timeuuid() {
     for (timeuuid_str, timeuuid_bytes) in &tests {
         // Insert timeuuid as a string and verify that it matches
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "INSERT INTO timeuuid_tests (id, val) VALUES (0, {})",
                     timeuuid_str
@@ -1112,7 +1112,7 @@ async fn test_timeuuid() {
             .unwrap();
 
         let (read_timeuuid,): (Uuid,) = session
-            .query("SELECT val from timeuuid_tests", &[])
+            .query_unpaged("SELECT val from timeuuid_tests", &[])
             .await
             .unwrap()
             .rows
@@ -1127,7 +1127,7 @@ async fn test_timeuuid() {
         // Insert timeuuid as a bound value and verify that it matches
         let test_uuid: Uuid = Uuid::from_slice(timeuuid_bytes.as_ref()).unwrap();
         session
-            .query(
+            .query_unpaged(
                 "INSERT INTO timeuuid_tests (id, val) VALUES (0, ?)",
                 (test_uuid,),
             )
@@ -1135,7 +1135,7 @@ async fn test_timeuuid() {
             .unwrap();
 
         let (read_timeuuid,): (Uuid,) = session
-            .query("SELECT val from timeuuid_tests", &[])
+            .query_unpaged("SELECT val from timeuuid_tests", &[])
             .await
             .unwrap()
             .rows
@@ -1193,7 +1193,7 @@ async fn test_inet() {
     for (inet_str, inet) in &tests {
         // Insert inet as a string and verify that it matches
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "INSERT INTO inet_tests (id, val) VALUES (0, '{}')",
                     inet_str
@@ -1204,7 +1204,7 @@ async fn test_inet() {
             .unwrap();
 
         let (read_inet,): (IpAddr,) = session
-            .query("SELECT val from inet_tests WHERE id = 0", &[])
+            .query_unpaged("SELECT val from inet_tests WHERE id = 0", &[])
             .await
             .unwrap()
             .rows
@@ -1218,12 +1218,12 @@ async fn test_inet() {
 
         // Insert inet as a bound value and verify that it matches
         session
-            .query("INSERT INTO inet_tests (id, val) VALUES (0, ?)", (inet,))
+            .query_unpaged("INSERT INTO inet_tests (id, val) VALUES (0, ?)", (inet,))
             .await
             .unwrap();
 
         let (read_inet,): (IpAddr,) = session
-            .query("SELECT val from inet_tests WHERE id = 0", &[])
+            .query_unpaged("SELECT val from inet_tests WHERE id = 0", &[])
             .await
             .unwrap()
             .rows
@@ -1269,7 +1269,7 @@ async fn test_blob() {
     for (blob_str, blob) in &tests {
         // Insert blob as a string and verify that it matches
         session
-            .query(
+            .query_unpaged(
                 format!("INSERT INTO blob_tests (id, val) VALUES (0, {})", blob_str),
                 &[],
             )
@@ -1277,7 +1277,7 @@ async fn test_blob() {
             .unwrap();
 
         let (read_blob,): (Vec<u8>,) = session
-            .query("SELECT val from blob_tests WHERE id = 0", &[])
+            .query_unpaged("SELECT val from blob_tests WHERE id = 0", &[])
             .await
             .unwrap()
             .rows
@@ -1291,12 +1291,12 @@ async fn test_blob() {
 
         // Insert blob as a bound value and verify that it matches
         session
-            .query("INSERT INTO blob_tests (id, val) VALUES (0, ?)", (blob,))
+            .query_unpaged("INSERT INTO blob_tests (id, val) VALUES (0, ?)", (blob,))
             .await
             .unwrap();
 
         let (read_blob,): (Vec<u8>,) = session
-            .query("SELECT val from blob_tests WHERE id = 0", &[])
+            .query_unpaged("SELECT val from blob_tests WHERE id = 0", &[])
             .await
             .unwrap()
             .rows
@@ -1319,7 +1319,7 @@ async fn test_udt_after_schema_update() {
     let ks = unique_keyspace_name();
 
     session
-        .query(
+        .query_unpaged(
             format!(
                 "CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = \
             {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}",
@@ -1332,17 +1332,17 @@ async fn test_udt_after_schema_update() {
     session.use_keyspace(ks, false).await.unwrap();
 
     session
-        .query(format!("DROP TABLE IF EXISTS {}", table_name), &[])
+        .query_unpaged(format!("DROP TABLE IF EXISTS {}", table_name), &[])
         .await
         .unwrap();
 
     session
-        .query(format!("DROP TYPE IF EXISTS {}", type_name), &[])
+        .query_unpaged(format!("DROP TYPE IF EXISTS {}", type_name), &[])
         .await
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             format!(
                 "CREATE TYPE IF NOT EXISTS {} (first int, second boolean)",
                 type_name
@@ -1353,7 +1353,7 @@ async fn test_udt_after_schema_update() {
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             format!(
                 "CREATE TABLE IF NOT EXISTS {} (id int PRIMARY KEY, val {})",
                 table_name, type_name
@@ -1376,7 +1376,7 @@ async fn test_udt_after_schema_update() {
     };
 
     session
-        .query(
+        .query_unpaged(
             format!(
                 "INSERT INTO {}(id,val) VALUES (0, {})",
                 table_name, "{first: 123, second: true}"
@@ -1387,7 +1387,7 @@ async fn test_udt_after_schema_update() {
         .unwrap();
 
     let (read_udt,): (UdtV1,) = session
-        .query(format!("SELECT val from {} WHERE id = 0", table_name), &[])
+        .query_unpaged(format!("SELECT val from {} WHERE id = 0", table_name), &[])
         .await
         .unwrap()
         .rows
@@ -1400,7 +1400,7 @@ async fn test_udt_after_schema_update() {
     assert_eq!(read_udt, v1);
 
     session
-        .query(
+        .query_unpaged(
             format!("INSERT INTO {}(id,val) VALUES (0, ?)", table_name),
             &(&v1,),
         )
@@ -1408,7 +1408,7 @@ async fn test_udt_after_schema_update() {
         .unwrap();
 
     let (read_udt,): (UdtV1,) = session
-        .query(format!("SELECT val from {} WHERE id = 0", table_name), &[])
+        .query_unpaged(format!("SELECT val from {} WHERE id = 0", table_name), &[])
         .await
         .unwrap()
         .rows
@@ -1421,7 +1421,7 @@ async fn test_udt_after_schema_update() {
     assert_eq!(read_udt, v1);
 
     session
-        .query(format!("ALTER TYPE {} ADD third text;", type_name), &[])
+        .query_unpaged(format!("ALTER TYPE {} ADD third text;", type_name), &[])
         .await
         .unwrap();
 
@@ -1433,7 +1433,7 @@ async fn test_udt_after_schema_update() {
     }
 
     let (read_udt,): (UdtV2,) = session
-        .query(format!("SELECT val from {} WHERE id = 0", table_name), &[])
+        .query_unpaged(format!("SELECT val from {} WHERE id = 0", table_name), &[])
         .await
         .unwrap()
         .rows
@@ -1458,7 +1458,7 @@ async fn test_empty() {
     let session: Session = init_test("empty_tests", "int").await;
 
     session
-        .query(
+        .query_unpaged(
             "INSERT INTO empty_tests (id, val) VALUES (0, blobasint(0x))",
             (),
         )
@@ -1466,7 +1466,7 @@ async fn test_empty() {
         .unwrap();
 
     let (empty,) = session
-        .query("SELECT val FROM empty_tests WHERE id = 0", ())
+        .query_unpaged("SELECT val FROM empty_tests WHERE id = 0", ())
         .await
         .unwrap()
         .first_row_typed::<(CqlValue,)>()
@@ -1475,7 +1475,7 @@ async fn test_empty() {
     assert_eq!(empty, CqlValue::Empty);
 
     session
-        .query(
+        .query_unpaged(
             "INSERT INTO empty_tests (id, val) VALUES (1, ?)",
             (CqlValue::Empty,),
         )
@@ -1483,7 +1483,7 @@ async fn test_empty() {
         .unwrap();
 
     let (empty,) = session
-        .query("SELECT val FROM empty_tests WHERE id = 1", ())
+        .query_unpaged("SELECT val FROM empty_tests WHERE id = 1", ())
         .await
         .unwrap()
         .first_row_typed::<(CqlValue,)>()
@@ -1501,7 +1501,7 @@ async fn test_udt_with_missing_field() {
     let ks = unique_keyspace_name();
 
     session
-        .query(
+        .query_unpaged(
             format!(
                 "CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = \
             {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}",
@@ -1514,17 +1514,17 @@ async fn test_udt_with_missing_field() {
     session.use_keyspace(ks, false).await.unwrap();
 
     session
-        .query(format!("DROP TABLE IF EXISTS {}", table_name), &[])
+        .query_unpaged(format!("DROP TABLE IF EXISTS {}", table_name), &[])
         .await
         .unwrap();
 
     session
-        .query(format!("DROP TYPE IF EXISTS {}", type_name), &[])
+        .query_unpaged(format!("DROP TYPE IF EXISTS {}", type_name), &[])
         .await
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             format!(
                 "CREATE TYPE IF NOT EXISTS {} (first int, second boolean, third float, fourth blob)",
                 type_name
@@ -1535,7 +1535,7 @@ async fn test_udt_with_missing_field() {
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             format!(
                 "CREATE TABLE IF NOT EXISTS {} (id int PRIMARY KEY, val {})",
                 table_name, type_name
@@ -1558,14 +1558,14 @@ async fn test_udt_with_missing_field() {
         TR: FromCqlVal<CqlValue> + PartialEq + Debug,
     {
         session
-            .query(
+            .query_unpaged(
                 format!("INSERT INTO {}(id,val) VALUES (?,?)", table_name),
                 &(id, &element),
             )
             .await
             .unwrap();
         let result = session
-            .query(
+            .query_unpaged(
                 format!("SELECT val from {} WHERE id = ?", table_name),
                 &(id,),
             )
@@ -1695,3 +1695,78 @@ async fn test_udt_with_missing_field() {
     )
     .await;
 }
+
+#[tokio::test]
+async fn test_empty_collections_are_read_as_null() {
+    use crate::cql_to_rust::NullAsEmpty;
+    use crate::macros::FromRow;
+
+    let session: Session = init_test("empty_collection_tests", "list<int>").await;
+
+    session
+        .query_unpaged(
+            "INSERT INTO empty_collection_tests (id, val) VALUES (0, ?)",
+            (Vec::<i32>::new(),),
+        )
+        .await
+        .unwrap();
+
+    // The database stores an empty collection as null
+    let (val,) = session
+        .query_unpaged("SELECT val FROM empty_collection_tests WHERE id = 0", ())
+        .await
+        .unwrap()
+        .first_row_typed::<(Option<Vec<i32>>,)>()
+        .unwrap();
+    assert_eq!(val, None);
+    assert!(session
+        .query_unpaged("SELECT val FROM empty_collection_tests WHERE id = 0", ())
+        .await
+        .unwrap()
+        .first_row_typed::<(Vec<i32>,)>()
+        .is_err());
+
+    // Unless asked to read it as empty
+    let (val,) = session
+        .query_unpaged("SELECT val FROM empty_collection_tests WHERE id = 0", ())
+        .await
+        .unwrap()
+        .first_row_typed::<(NullAsEmpty<Vec<i32>>,)>()
+        .unwrap();
+    assert_eq!(val, NullAsEmpty(vec![]));
+
+    #[derive(FromRow, Debug, PartialEq)]
+    #[scylla_crate = "crate"]
+    struct Row {
+        id: i32,
+        #[scylla(null_as_default)]
+        val: Vec<i32>,
+    }
+
+    session
+        .query_unpaged(
+            "INSERT INTO empty_collection_tests (id, val) VALUES (1, ?)",
+            (NullAsEmpty(vec![1, 2]),),
+        )
+        .await
+        .unwrap();
+    let mut rows: Vec<Row> = session
+        .query_unpaged("SELECT id, val FROM empty_collection_tests", ())
+        .await
+        .unwrap()
+        .rows_typed::<Row>()
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    rows.sort_by_key(|row| row.id);
+    assert_eq!(
+        rows,
+        vec![
+            Row { id: 0, val: vec![] },
+            Row {
+                id: 1,
+                val: vec![1, 2]
+            }
+        ]
+    );
+}
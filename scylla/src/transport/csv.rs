@@ -0,0 +1,413 @@
+//! Export of query results as CSV.
+//!
+//! Requires the `csv` feature. Rows of a [`QueryResult`](crate::QueryResult) are written with
+//! [`QueryResult::write_csv`](crate::QueryResult::write_csv), and rows fetched by a
+//! [`RowIterator`](crate::transport::iterator::RowIterator) with
+//! [`RowIterator::write_csv`](crate::transport::iterator::RowIterator::write_csv),
+//! which writes the rows page by page.
+//!
+//! The output follows RFC 4180: fields containing the delimiter, quotes or line breaks
+//! are quoted, and quotes inside them are doubled. The first record is a header
+//! with the names of the columns, unless disabled in [`CsvOptions`].
+//!
+//! Values are rendered as follows:
+//! * `text` and `ascii` values are written as they are, without CQL quotes,
+//! * `blob` values are encoded as configured with [`CsvOptions::blob_encoding`],
+//! * `date`, `time`, `timestamp` and `inet` values are written without the quotes of their CQL literals,
+//!   e.g. `2023-01-31 12:00:00.000+0000`,
+//! * collections, tuples and user defined types are written as CQL literals, e.g. `{'a':1,'b':2}`.
+//!   Their nested values always use the CQL literal syntax, so nested blobs are written as `0x...`
+//!   and nested nulls as `null`,
+//! * other values are written as CQL literals,
+//! * nulls are written as configured with [`CsvOptions::null_value`], and empty values as empty fields.
+//!
+//! Rendering is deterministic, elements of collections are written in the order
+//! in which they were received from the database.
+
+use std::io::Write;
+
+use base64::{engine::general_purpose, Engine as _};
+use thiserror::Error;
+
+use crate::frame::response::result::{ColumnSpec, CqlValue, Row};
+use crate::transport::errors::QueryError;
+use crate::transport::query_result::RowsExpectedError;
+use crate::utils::pretty::{CqlValueDisplayer, HexBytes};
+
+/// An error of writing rows as CSV.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum CsvExportError {
+    #[error(transparent)]
+    RowsExpected(#[from] RowsExpectedError),
+
+    #[error("Failed to write CSV: {0}")]
+    Csv(#[from] ::csv::Error),
+
+    #[error("Failed to fetch a page of rows: {0}")]
+    Query(#[from] QueryError),
+}
+
+impl From<std::io::Error> for CsvExportError {
+    fn from(error: std::io::Error) -> Self {
+        CsvExportError::Csv(error.into())
+    }
+}
+
+/// Encoding of `blob` values in CSV fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum BlobEncoding {
+    /// Lowercase hex digits prefixed with `0x`, same as the CQL literal, e.g. `0xcafe`.
+    #[default]
+    Hex,
+    /// Standard base64 with padding, e.g. `yv4=`.
+    Base64,
+}
+
+/// Options of writing rows as CSV.
+///
+/// # Example
+/// ```rust
+/// use scylla::transport::csv::{BlobEncoding, CsvOptions};
+///
+/// let options = CsvOptions::default()
+///     .delimiter(b';')
+///     .null_value("NULL")
+///     .blob_encoding(BlobEncoding::Base64);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    delimiter: u8,
+    header: bool,
+    null_value: String,
+    blob_encoding: BlobEncoding,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            header: true,
+            null_value: String::new(),
+            blob_encoding: BlobEncoding::Hex,
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Sets the field delimiter. The default is `,`.
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets whether a header with the names of the columns is written. The default is `true`.
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Sets the representation of nulls. The default is an empty field.
+    pub fn null_value(mut self, null_value: impl Into<String>) -> Self {
+        self.null_value = null_value.into();
+        self
+    }
+
+    /// Sets the encoding of `blob` values. The default is [`BlobEncoding::Hex`].
+    pub fn blob_encoding(mut self, blob_encoding: BlobEncoding) -> Self {
+        self.blob_encoding = blob_encoding;
+        self
+    }
+
+    /// Returns the field delimiter.
+    pub fn get_delimiter(&self) -> u8 {
+        self.delimiter
+    }
+
+    /// Returns whether a header is written.
+    pub fn get_header(&self) -> bool {
+        self.header
+    }
+
+    /// Returns the representation of nulls.
+    pub fn get_null_value(&self) -> &str {
+        &self.null_value
+    }
+
+    /// Returns the encoding of `blob` values.
+    pub fn get_blob_encoding(&self) -> BlobEncoding {
+        self.blob_encoding
+    }
+
+    /// Creates a CSV writer writing to `writer` with the delimiter of these options.
+    pub(crate) fn writer<W: Write>(&self, writer: W) -> ::csv::Writer<W> {
+        ::csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .from_writer(writer)
+    }
+}
+
+/// Writes the header with the names of the columns, if enabled in `options`.
+pub fn write_header<W: Write>(
+    writer: &mut ::csv::Writer<W>,
+    col_specs: &[ColumnSpec],
+    options: &CsvOptions,
+) -> Result<(), CsvExportError> {
+    if options.header {
+        writer.write_record(col_specs.iter().map(|spec| spec.name.as_str()))?;
+    }
+    Ok(())
+}
+
+/// Writes rows as CSV records, rendering their values as described in the [module documentation](self).
+///
+/// Only the null representation and the blob encoding of `options` are used,
+/// the format of the records is determined by `writer`.
+pub fn write_rows<W: Write>(
+    writer: &mut ::csv::Writer<W>,
+    rows: &[Row],
+    options: &CsvOptions,
+) -> Result<(), CsvExportError> {
+    let mut record = Vec::new();
+    for row in rows {
+        record.clear();
+        record.extend(
+            row.columns
+                .iter()
+                .map(|value| render_field(value.as_ref(), options)),
+        );
+        writer.write_record(&record)?;
+    }
+    Ok(())
+}
+
+/// Writes the header and the rows described by `col_specs` to `writer`, and flushes it.
+pub(crate) fn write_csv(
+    writer: &mut impl Write,
+    col_specs: &[ColumnSpec],
+    rows: &[Row],
+    options: &CsvOptions,
+) -> Result<(), CsvExportError> {
+    let mut csv_writer = options.writer(writer);
+    write_header(&mut csv_writer, col_specs, options)?;
+    write_rows(&mut csv_writer, rows, options)?;
+    csv_writer.flush()?;
+    Ok(())
+}
+
+fn render_field(value: Option<&CqlValue>, options: &CsvOptions) -> String {
+    match value {
+        None => options.null_value.clone(),
+        Some(CqlValue::Empty) => String::new(),
+        Some(CqlValue::Ascii(s) | CqlValue::Text(s)) => s.clone(),
+        Some(CqlValue::Blob(b)) => render_blob(b, options.blob_encoding),
+        Some(CqlValue::SharedBlob(b)) => render_blob(b, options.blob_encoding),
+        Some(CqlValue::Inet(addr)) => addr.to_string(),
+        Some(value @ (CqlValue::Date(_) | CqlValue::Time(_) | CqlValue::Timestamp(_))) => {
+            // The literals of these types are quoted, unless they are out of representable range
+            let literal = CqlValueDisplayer(value).to_string();
+            match literal
+                .strip_prefix('\'')
+                .and_then(|literal| literal.strip_suffix('\''))
+            {
+                Some(unquoted) => unquoted.to_owned(),
+                None => literal,
+            }
+        }
+        Some(value) => CqlValueDisplayer(value).to_string(),
+    }
+}
+
+fn render_blob(blob: &[u8], encoding: BlobEncoding) -> String {
+    match encoding {
+        BlobEncoding::Hex => format!("0x{:x}", HexBytes(blob)),
+        BlobEncoding::Base64 => general_purpose::STANDARD.encode(blob),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlobEncoding, CsvExportError, CsvOptions};
+    use crate::frame::response::result::{ColumnSpec, ColumnType, CqlValue, Row, TableSpec};
+    use crate::frame::value::{CqlDate, CqlTime, CqlTimestamp};
+    use crate::QueryResult;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn spec(name: &str, typ: ColumnType) -> ColumnSpec {
+        ColumnSpec {
+            table_spec: TableSpec {
+                ks_name: "ks".to_owned(),
+                table_name: "t".to_owned(),
+            },
+            name: name.to_owned(),
+            typ,
+        }
+    }
+
+    fn result(col_specs: Vec<ColumnSpec>, rows: Vec<Vec<Option<CqlValue>>>) -> QueryResult {
+        QueryResult {
+            rows: Some(rows.into_iter().map(|columns| Row { columns }).collect()),
+            col_specs,
+            ..Default::default()
+        }
+    }
+
+    fn to_csv(result: &QueryResult, options: CsvOptions) -> String {
+        let mut out = Vec::new();
+        result.write_csv(&mut out, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn text_fields_are_quoted_when_needed() {
+        let result = result(
+            vec![spec("id", ColumnType::Int), spec("name", ColumnType::Text)],
+            vec![
+                vec![Some(CqlValue::Int(1)), Some(CqlValue::Text("plain".into()))],
+                vec![Some(CqlValue::Int(2)), Some(CqlValue::Text("a,b".into()))],
+                vec![
+                    Some(CqlValue::Int(3)),
+                    Some(CqlValue::Text("say \"hi\"".into())),
+                ],
+                vec![
+                    Some(CqlValue::Int(4)),
+                    Some(CqlValue::Text("two\nlines".into())),
+                ],
+                vec![Some(CqlValue::Int(5)), Some(CqlValue::Text("it's".into()))],
+            ],
+        );
+        assert_eq!(
+            to_csv(&result, CsvOptions::default()),
+            "id,name\n1,plain\n2,\"a,b\"\n3,\"say \"\"hi\"\"\"\n4,\"two\nlines\"\n5,it's\n"
+        );
+    }
+
+    #[test]
+    fn custom_delimiter_and_no_header() {
+        let result = result(
+            vec![spec("a", ColumnType::Text), spec("b", ColumnType::Text)],
+            vec![vec![
+                Some(CqlValue::Text("x,y".into())),
+                Some(CqlValue::Text("x;y".into())),
+            ]],
+        );
+        let options = CsvOptions::default().delimiter(b';').header(false);
+        assert_eq!(to_csv(&result, options), "x,y;\"x;y\"\n");
+    }
+
+    #[test]
+    fn nulls_and_empty_values() {
+        let result = result(
+            vec![spec("a", ColumnType::Int), spec("b", ColumnType::Int)],
+            vec![vec![None, Some(CqlValue::Empty)]],
+        );
+        assert_eq!(to_csv(&result, CsvOptions::default()), "a,b\n,\n");
+        assert_eq!(
+            to_csv(&result, CsvOptions::default().null_value("NULL")),
+            "a,b\nNULL,\n"
+        );
+    }
+
+    #[test]
+    fn blob_encodings() {
+        let result = result(
+            vec![
+                spec("b", ColumnType::Blob),
+                spec("l", ColumnType::List(Box::new(ColumnType::Blob))),
+            ],
+            vec![vec![
+                Some(CqlValue::Blob(vec![0xca, 0xfe])),
+                Some(CqlValue::List(vec![CqlValue::Blob(vec![0x01])])),
+            ]],
+        );
+        assert_eq!(
+            to_csv(&result, CsvOptions::default()),
+            "b,l\n0xcafe,[0x01]\n"
+        );
+        assert_eq!(
+            to_csv(
+                &result,
+                CsvOptions::default().blob_encoding(BlobEncoding::Base64)
+            ),
+            "b,l\nyv4=,[0x01]\n"
+        );
+    }
+
+    #[test]
+    fn scalar_types_are_unquoted() {
+        let result = result(
+            vec![
+                spec("d", ColumnType::Date),
+                spec("t", ColumnType::Time),
+                spec("ts", ColumnType::Timestamp),
+                spec("i", ColumnType::Inet),
+                spec("f", ColumnType::Double),
+            ],
+            vec![vec![
+                Some(CqlValue::Date(CqlDate((1 << 31) + 1))),
+                Some(CqlValue::Time(CqlTime(3_600_000_000_001))),
+                Some(CqlValue::Timestamp(CqlTimestamp(1_000))),
+                Some(CqlValue::Inet(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)))),
+                Some(CqlValue::Double(1.5)),
+            ]],
+        );
+        assert_eq!(
+            to_csv(&result, CsvOptions::default().header(false)),
+            "1970-01-02,01:00:00.000000001,1970-01-01 00:00:01.000+0000,127.0.0.1,1.5\n"
+        );
+    }
+
+    #[test]
+    fn compound_types_are_cql_literals() {
+        let result = result(
+            vec![
+                spec(
+                    "m",
+                    ColumnType::Map(Box::new(ColumnType::Text), Box::new(ColumnType::Int)),
+                ),
+                spec(
+                    "u",
+                    ColumnType::UserDefinedType {
+                        type_name: "address".into(),
+                        keyspace: "ks".into(),
+                        field_types: vec![
+                            ("street".into(), ColumnType::Text),
+                            ("number".into(), ColumnType::Int),
+                        ]
+                        .into(),
+                    },
+                ),
+            ],
+            vec![vec![
+                Some(CqlValue::Map(vec![
+                    (CqlValue::Text("b".into()), CqlValue::Int(2)),
+                    (CqlValue::Text("a".into()), CqlValue::Int(1)),
+                ])),
+                Some(CqlValue::UserDefinedType {
+                    keyspace: "ks".into(),
+                    type_name: "address".into(),
+                    fields: vec![
+                        ("street".into(), Some(CqlValue::Text("O'Hara, 1".into()))),
+                        ("number".into(), None),
+                    ],
+                }),
+            ]],
+        );
+        assert_eq!(
+            to_csv(&result, CsvOptions::default().header(false)),
+            "\"{'b':2,'a':1}\",\"{street:'O''Hara, 1',number:null}\"\n"
+        );
+    }
+
+    #[test]
+    fn non_rows_result_is_an_error() {
+        let result = QueryResult::default();
+        let mut out = Vec::new();
+        assert!(matches!(
+            result.write_csv(&mut out, CsvOptions::default()),
+            Err(CsvExportError::RowsExpected(_))
+        ));
+        assert!(out.is_empty());
+    }
+}
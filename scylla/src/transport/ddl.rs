@@ -0,0 +1,152 @@
+//! Running schema changes with [`Session::ddl`](crate::Session::ddl).
+//!
+//! Tools applying migrations usually run DDL statements one after another, and each
+//! of them has to be visible on the whole cluster before the next one can rely on it.
+//! `Session::ddl` executes a statement, waits for schema agreement, and returns
+//! the [`SchemaChange`] reported by the server.
+//!
+//! Statements creating an object race with each other when several clients apply
+//! the same migration, and `IF NOT EXISTS` doesn't fully protect against it - the loser
+//! may still get an `AlreadyExists` error. With
+//! [`SessionBuilder::ddl_ignore_already_exists`](crate::transport::session_builder::GenericSessionBuilder::ddl_ignore_already_exists)
+//! enabled, such errors are treated as success.
+
+use std::fmt::Display;
+
+use scylla_cql::frame::response::event::SchemaChangeEvent;
+pub use scylla_cql::frame::response::event::SchemaChangeType;
+
+use crate::transport::errors::{DbError, QueryError, QueryErrorKind};
+
+/// Kind of the schema object changed by a statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SchemaChangeTarget {
+    Keyspace,
+    Table,
+    Type,
+    Function,
+    Aggregate,
+}
+
+impl Display for SchemaChangeTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SchemaChangeTarget::Keyspace => "keyspace",
+            SchemaChangeTarget::Table => "table",
+            SchemaChangeTarget::Type => "type",
+            SchemaChangeTarget::Function => "function",
+            SchemaChangeTarget::Aggregate => "aggregate",
+        })
+    }
+}
+
+/// Schema change reported by the server in response to a DDL statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaChange {
+    pub change_type: SchemaChangeType,
+    pub target: SchemaChangeTarget,
+    /// Keyspace of the changed object, or the changed keyspace itself.
+    pub keyspace: String,
+    /// Name of the changed object, `None` if a keyspace was changed.
+    /// Tables also stand for materialized views here.
+    pub name: Option<String>,
+}
+
+impl SchemaChange {
+    pub(crate) fn from_event(event: &SchemaChangeEvent) -> Self {
+        let target = match event {
+            SchemaChangeEvent::KeyspaceChange { .. } => SchemaChangeTarget::Keyspace,
+            SchemaChangeEvent::TableChange { .. } => SchemaChangeTarget::Table,
+            SchemaChangeEvent::TypeChange { .. } => SchemaChangeTarget::Type,
+            SchemaChangeEvent::FunctionChange { .. } => SchemaChangeTarget::Function,
+            SchemaChangeEvent::AggregateChange { .. } => SchemaChangeTarget::Aggregate,
+        };
+
+        SchemaChange {
+            change_type: event.change_type(),
+            target,
+            keyspace: event.keyspace_name().to_owned(),
+            name: event.object_name().map(str::to_owned),
+        }
+    }
+}
+
+/// Whether the error means that the object a statement tried to create already exists.
+///
+/// Besides `AlreadyExists`, some server versions report losing the race of two concurrent
+/// `CREATE ... IF NOT EXISTS` with a configuration error saying that the object already exists.
+pub(crate) fn is_already_exists(error: &QueryError) -> bool {
+    match error {
+        QueryError::DbError(DbError::ConfigError, message) => {
+            message.to_lowercase().contains("already exist")
+        }
+        _ => error.kind() == QueryErrorKind::AlreadyExists,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use scylla_cql::frame::response::event::{SchemaChangeEvent, SchemaChangeType};
+
+    use super::{is_already_exists, SchemaChange, SchemaChangeTarget};
+    use crate::transport::errors::{DbError, QueryError};
+
+    #[test]
+    fn schema_change_from_event() {
+        let keyspace = SchemaChangeEvent::KeyspaceChange {
+            change_type: SchemaChangeType::Created,
+            keyspace_name: "ks".to_owned(),
+        };
+        assert_eq!(
+            SchemaChange::from_event(&keyspace),
+            SchemaChange {
+                change_type: SchemaChangeType::Created,
+                target: SchemaChangeTarget::Keyspace,
+                keyspace: "ks".to_owned(),
+                name: None,
+            }
+        );
+
+        let function = SchemaChangeEvent::FunctionChange {
+            change_type: SchemaChangeType::Dropped,
+            keyspace_name: "ks".to_owned(),
+            function_name: "f".to_owned(),
+            arguments: vec!["int".to_owned()],
+        };
+        assert_eq!(
+            SchemaChange::from_event(&function),
+            SchemaChange {
+                change_type: SchemaChangeType::Dropped,
+                target: SchemaChangeTarget::Function,
+                keyspace: "ks".to_owned(),
+                name: Some("f".to_owned()),
+            }
+        );
+    }
+
+    #[test]
+    fn already_exists_errors() {
+        let already_exists = QueryError::DbError(
+            DbError::AlreadyExists {
+                keyspace: "ks".to_owned(),
+                table: "t".to_owned(),
+            },
+            "Cannot add already existing table \"t\" to keyspace \"ks\"".to_owned(),
+        );
+        assert!(is_already_exists(&already_exists));
+
+        let config_error =
+            QueryError::DbError(DbError::ConfigError, "Table ks.t already exists".to_owned());
+        assert!(is_already_exists(&config_error));
+
+        let other_config_error = QueryError::DbError(
+            DbError::ConfigError,
+            "Unknown compaction strategy".to_owned(),
+        );
+        assert!(!is_already_exists(&other_config_error));
+        assert!(!is_already_exists(&QueryError::DbError(
+            DbError::Invalid,
+            "Table ks.t already exists".to_owned()
+        )));
+    }
+}
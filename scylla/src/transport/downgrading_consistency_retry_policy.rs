@@ -36,13 +36,12 @@ impl RetryPolicy for DowngradingConsistencyRetryPolicy {
     }
 }
 
-pub struct DowngradingConsistencyRetrySession {
-    was_retry: bool,
-}
+/// Decides based on the error and the number of the attempt only, so it's stateless.
+pub struct DowngradingConsistencyRetrySession;
 
 impl DowngradingConsistencyRetrySession {
     pub fn new() -> DowngradingConsistencyRetrySession {
-        DowngradingConsistencyRetrySession { was_retry: false }
+        DowngradingConsistencyRetrySession
     }
 }
 
@@ -107,8 +106,7 @@ impl RetrySession for DowngradingConsistencyRetrySession {
             // Unavailable - the current node believes that not enough nodes
             // are alive to satisfy specified consistency requirements.
             QueryError::DbError(DbError::Unavailable { alive, .. }, _) => {
-                if !self.was_retry {
-                    self.was_retry = true;
+                if query_info.is_first_attempt() {
                     max_likely_to_work_cl(*alive, cl)
                 } else {
                     RetryDecision::DontRetry
@@ -124,13 +122,11 @@ impl RetrySession for DowngradingConsistencyRetrySession {
                 },
                 _,
             ) => {
-                if self.was_retry {
+                if !query_info.is_first_attempt() {
                     RetryDecision::DontRetry
                 } else if received < required {
-                    self.was_retry = true;
                     max_likely_to_work_cl(*received, cl)
                 } else if !*data_present {
-                    self.was_retry = true;
                     RetryDecision::RetrySameNode(None)
                 } else {
                     RetryDecision::DontRetry
@@ -145,10 +141,9 @@ impl RetrySession for DowngradingConsistencyRetrySession {
                 },
                 _,
             ) => {
-                if self.was_retry || !query_info.is_idempotent {
+                if !query_info.is_first_attempt() || !query_info.is_idempotent {
                     RetryDecision::DontRetry
                 } else {
-                    self.was_retry = true;
                     match write_type {
                         WriteType::Batch | WriteType::Simple if *received > 0 => {
                             RetryDecision::IgnoreWriteError
@@ -168,15 +163,15 @@ impl RetrySession for DowngradingConsistencyRetrySession {
             // The node is still bootstrapping it can't execute the query, we should try another one
             QueryError::DbError(DbError::IsBootstrapping, _) => RetryDecision::RetryNextNode(None),
             // Connection to the contacted node is overloaded, try another one
-            QueryError::UnableToAllocStreamId => RetryDecision::RetryNextNode(None),
+            QueryError::UnableToAllocStreamId | QueryError::ConnectionBusy => {
+                RetryDecision::RetryNextNode(None)
+            }
             // In all other cases propagate the error to the user
             _ => RetryDecision::DontRetry,
         }
     }
 
-    fn reset(&mut self) {
-        *self = DowngradingConsistencyRetrySession::new();
-    }
+    fn reset(&mut self) {}
 }
 
 #[cfg(test)]
@@ -205,11 +200,20 @@ mod tests {
         is_idempotent: bool,
         cl: Consistency,
     ) -> QueryInfo<'_> {
-        QueryInfo {
-            error,
-            is_idempotent,
-            consistency: cl,
-        }
+        QueryInfo::new(error, is_idempotent, cl)
+    }
+
+    // Information about the second attempt, sent with `attempt_cl` after the first one failed
+    fn make_retried_query_info_with_cl(
+        error: &QueryError,
+        is_idempotent: bool,
+        cl: Consistency,
+        attempt_cl: Consistency,
+    ) -> QueryInfo<'_> {
+        let mut query_info = make_query_info_with_cl(error, is_idempotent, cl);
+        query_info.attempt_number = 2;
+        query_info.attempt_consistency = attempt_cl;
+        query_info
     }
 
     // Asserts that downgrading consistency policy never retries for this Error
@@ -274,11 +278,10 @@ mod tests {
             }
 
             downgrading_consistency_policy_assert_never_retries(
-                QueryError::BadQuery(BadQuery::Other(
-                    "Length of provided values must be equal to number of batch statements \
-                        (got 1 values, 2 statements)"
-                        .to_owned(),
-                )),
+                QueryError::BadQuery(BadQuery::BatchValuesCountMismatch {
+                    values_count: 1,
+                    statements_count: 2,
+                }),
                 cl,
             );
             downgrading_consistency_policy_assert_never_retries(
@@ -376,7 +379,7 @@ mod tests {
             );
             assert_eq!(
                 policy_not_idempotent
-                    .decide_should_retry(make_query_info_with_cl(&error, false, cl)),
+                    .decide_should_retry(make_retried_query_info_with_cl(&error, false, cl, cl)),
                 RetryDecision::DontRetry
             );
 
@@ -386,7 +389,8 @@ mod tests {
                 max_likely_to_work_cl(alive, cl)
             );
             assert_eq!(
-                policy_idempotent.decide_should_retry(make_query_info_with_cl(&error, true, cl)),
+                policy_idempotent
+                    .decide_should_retry(make_retried_query_info_with_cl(&error, true, cl, cl)),
                 RetryDecision::DontRetry
             );
         }
@@ -418,9 +422,10 @@ mod tests {
                 RetryDecision::RetrySameNode(None)
             );
             assert_eq!(
-                policy.decide_should_retry(make_query_info_with_cl(
+                policy.decide_should_retry(make_retried_query_info_with_cl(
                     &enough_responses_no_data,
                     false,
+                    cl,
                     cl
                 )),
                 RetryDecision::DontRetry
@@ -437,9 +442,10 @@ mod tests {
                 RetryDecision::RetrySameNode(None)
             );
             assert_eq!(
-                policy.decide_should_retry(make_query_info_with_cl(
+                policy.decide_should_retry(make_retried_query_info_with_cl(
                     &enough_responses_no_data,
                     true,
+                    cl,
                     cl
                 )),
                 RetryDecision::DontRetry
@@ -507,9 +513,10 @@ mod tests {
             );
             if let RetryDecision::RetrySameNode(new_cl) = expected_decision {
                 assert_eq!(
-                    policy.decide_should_retry(make_query_info_with_cl(
+                    policy.decide_should_retry(make_retried_query_info_with_cl(
                         &not_enough_responses_with_data,
                         false,
+                        cl,
                         new_cl.unwrap_or(cl)
                     )),
                     RetryDecision::DontRetry
@@ -528,9 +535,10 @@ mod tests {
             );
             if let RetryDecision::RetrySameNode(new_cl) = expected_decision {
                 assert_eq!(
-                    policy.decide_should_retry(make_query_info_with_cl(
+                    policy.decide_should_retry(make_retried_query_info_with_cl(
                         &not_enough_responses_with_data,
                         true,
+                        cl,
                         new_cl.unwrap_or(cl)
                     )),
                     RetryDecision::DontRetry
@@ -577,9 +585,10 @@ mod tests {
                     RetryDecision::RetrySameNode(None)
                 );
                 assert_eq!(
-                    policy.decide_should_retry(make_query_info_with_cl(
+                    policy.decide_should_retry(make_retried_query_info_with_cl(
                         &write_type_batchlog,
                         true,
+                        cl,
                         cl
                     )),
                     RetryDecision::DontRetry
@@ -620,9 +629,10 @@ mod tests {
                     max_likely_to_work_cl(received, cl)
                 );
                 assert_eq!(
-                    policy.decide_should_retry(make_query_info_with_cl(
+                    policy.decide_should_retry(make_retried_query_info_with_cl(
                         &write_type_unlogged_batch,
                         true,
+                        cl,
                         cl
                     )),
                     RetryDecision::DontRetry
@@ -663,9 +673,10 @@ mod tests {
                     RetryDecision::IgnoreWriteError
                 );
                 assert_eq!(
-                    policy.decide_should_retry(make_query_info_with_cl(
+                    policy.decide_should_retry(make_retried_query_info_with_cl(
                         &write_type_other,
                         true,
+                        cl,
                         cl
                     )),
                     RetryDecision::DontRetry
@@ -0,0 +1,449 @@
+//! Executing a group of statements with a shared budget of time and attempts.
+//!
+//! [`Session::execute_with_budget`] calls a closure with a [`SessionHandle`], through which
+//! the closure executes its statements, e.g. the steps of a saga built on lightweight
+//! transactions. All requests sent through the handle draw from one [`ExecutionBudget`]:
+//! - each request is sent with a timeout not exceeding the time left in the budget,
+//! - the first attempt of each request, and each retry decided by the retry policy,
+//!   use up one attempt of the budget.
+//!
+//! Once the budget is exhausted, decisions of the retry policy to retry are overridden,
+//! and new requests fail with [`QueryError::BudgetExhausted`] without being sent.
+//! Speculative executions are limited by the time budget, but don't use up attempts.
+//!
+//! The handle keeps an ordered log of the statements which completed successfully.
+//! The log is returned in [`BudgetedExecution::completed`] whether the closure succeeded or not,
+//! so that compensation logic knows which steps were applied.
+//! Statements are logged by their [label](crate::query::Query::set_label),
+//! or by their CQL text if they have none.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use scylla_cql::types::serialize::batch::BatchValues;
+use scylla_cql::types::serialize::row::SerializeRow;
+
+use crate::batch::{Batch, BatchStatement};
+use crate::prepared_statement::PreparedStatement;
+use crate::query::Query;
+use crate::statement::StatementConfig;
+use crate::transport::errors::QueryError;
+use crate::transport::retry_policy::{QueryInfo, RetryDecision, RetryPolicy, RetrySession};
+use crate::{QueryResult, Session};
+
+/// Limits shared by all requests of an execution started with [`Session::execute_with_budget`].
+///
+/// The default budget is unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionBudget {
+    /// Maximum time of the whole execution, counted from the start of
+    /// [`Session::execute_with_budget`]. `None` means no limit.
+    pub max_total_time: Option<Duration>,
+    /// Maximum number of attempts of all requests together, including retries.
+    /// `None` means no limit.
+    pub max_attempts: Option<usize>,
+}
+
+/// Outcome of an execution started with [`Session::execute_with_budget`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct BudgetedExecution<T, E> {
+    /// Result returned by the closure.
+    pub result: Result<T, E>,
+    /// Labels of the statements which completed successfully, in the order of completion.
+    pub completed: Vec<String>,
+    /// Number of attempts used.
+    pub attempts: usize,
+    /// Time the whole execution took.
+    pub elapsed: Duration,
+}
+
+#[derive(Debug)]
+struct BudgetState {
+    budget: ExecutionBudget,
+    started_at: Instant,
+    attempts: Mutex<usize>,
+    completed: Mutex<Vec<String>>,
+}
+
+impl BudgetState {
+    fn new(budget: ExecutionBudget) -> Self {
+        Self {
+            budget,
+            started_at: Instant::now(),
+            attempts: Mutex::new(0),
+            completed: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn remaining_time(&self) -> Option<Duration> {
+        self.budget
+            .max_total_time
+            .map(|max| max.saturating_sub(self.started_at.elapsed()))
+    }
+
+    fn remaining_attempts(&self) -> Option<usize> {
+        let attempts = *self.attempts.lock().unwrap();
+        self.budget
+            .max_attempts
+            .map(|max| max.saturating_sub(attempts))
+    }
+
+    // Uses up an attempt, returns false if none is left
+    fn take_attempt(&self) -> bool {
+        let mut attempts = self.attempts.lock().unwrap();
+        if matches!(self.budget.max_attempts, Some(max) if *attempts >= max) {
+            return false;
+        }
+        *attempts += 1;
+        true
+    }
+
+    // Uses up the first attempt of a new request
+    fn start_request(&self) -> Result<(), QueryError> {
+        if self.remaining_time() == Some(Duration::ZERO) {
+            return Err(QueryError::BudgetExhausted {
+                reason: format!(
+                    "the time budget of {}ms elapsed",
+                    self.budget.max_total_time.unwrap_or_default().as_millis()
+                ),
+            });
+        }
+        if !self.take_attempt() {
+            return Err(QueryError::BudgetExhausted {
+                reason: format!(
+                    "all {} attempts were used",
+                    self.budget.max_attempts.unwrap_or_default()
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Wraps the retry policy of a statement, so that retries draw from the budget.
+#[derive(Debug)]
+struct BudgetedRetryPolicy {
+    inner: Arc<dyn RetryPolicy>,
+    state: Arc<BudgetState>,
+}
+
+impl RetryPolicy for BudgetedRetryPolicy {
+    fn new_session(&self) -> Box<dyn RetrySession> {
+        Box::new(BudgetedRetrySession {
+            inner: self.inner.new_session(),
+            state: self.state.clone(),
+        })
+    }
+
+    fn clone_boxed(&self) -> Box<dyn RetryPolicy> {
+        Box::new(BudgetedRetryPolicy {
+            inner: self.inner.clone(),
+            state: self.state.clone(),
+        })
+    }
+}
+
+struct BudgetedRetrySession {
+    inner: Box<dyn RetrySession>,
+    state: Arc<BudgetState>,
+}
+
+impl RetrySession for BudgetedRetrySession {
+    fn decide_should_retry(&mut self, query_info: QueryInfo) -> RetryDecision {
+        let decision = self.inner.decide_should_retry(query_info);
+        let delay = match &decision {
+            RetryDecision::RetrySameNode(_) | RetryDecision::RetryNextNode(_) => Duration::ZERO,
+            RetryDecision::RetryNextNodeAfter(_, delay) => *delay,
+            RetryDecision::DontRetry | RetryDecision::IgnoreWriteError => return decision,
+        };
+
+        // A retry which couldn't complete before the deadline would only waste an attempt
+        if matches!(self.state.remaining_time(), Some(left) if left <= delay) {
+            return RetryDecision::DontRetry;
+        }
+        if !self.state.take_attempt() {
+            return RetryDecision::DontRetry;
+        }
+        decision
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+/// Executes requests drawing from the budget of an execution started with
+/// [`Session::execute_with_budget`].
+///
+/// The handle is cheap to clone. Clones share the budget and the log of completed statements.
+#[derive(Debug, Clone)]
+pub struct SessionHandle<'a> {
+    session: &'a Session,
+    state: Arc<BudgetState>,
+}
+
+impl<'a> SessionHandle<'a> {
+    pub(crate) fn new(session: &'a Session, budget: ExecutionBudget) -> Self {
+        Self {
+            session,
+            state: Arc::new(BudgetState::new(budget)),
+        }
+    }
+
+    /// The session the requests are executed on. Requests sent directly through the session,
+    /// e.g. to prepare statements, don't draw from the budget and aren't logged.
+    pub fn session(&self) -> &'a Session {
+        self.session
+    }
+
+    /// Executes an unprepared statement, see [`Session::query_unpaged`].
+    pub async fn query(
+        &self,
+        query: impl Into<Query>,
+        values: impl SerializeRow,
+    ) -> Result<QueryResult, QueryError> {
+        let mut query: Query = query.into();
+        let label = query.get_label().unwrap_or(&query.contents).to_owned();
+        self.apply_budget(&mut query.config)?;
+        let result = self.session.query_unpaged(query, values).await;
+        self.log_completion(label, &result);
+        result
+    }
+
+    /// Executes a prepared statement, see [`Session::execute_unpaged`].
+    pub async fn execute(
+        &self,
+        prepared: &PreparedStatement,
+        values: impl SerializeRow,
+    ) -> Result<QueryResult, QueryError> {
+        let mut prepared = prepared.clone();
+        let label = prepared
+            .get_label()
+            .unwrap_or_else(|| prepared.get_statement())
+            .to_owned();
+        self.apply_budget(&mut prepared.config)?;
+        let result = self.session.execute_unpaged(&prepared, values).await;
+        self.log_completion(label, &result);
+        result
+    }
+
+    /// Executes a batch, see [`Session::batch`].
+    ///
+    /// A batch without a label is logged by the texts of its statements, separated with `; `.
+    pub async fn batch(
+        &self,
+        batch: &Batch,
+        values: impl BatchValues,
+    ) -> Result<QueryResult, QueryError> {
+        let mut batch = batch.clone();
+        let label = match batch.get_label() {
+            Some(label) => label.to_owned(),
+            None => batch
+                .statements
+                .iter()
+                .map(|statement| match statement {
+                    BatchStatement::Query(query) => query.contents.as_str(),
+                    BatchStatement::PreparedStatement(prepared) => prepared.get_statement(),
+                })
+                .collect::<Vec<_>>()
+                .join("; "),
+        };
+        self.apply_budget(&mut batch.config)?;
+        let result = self.session.batch(&batch, values).await;
+        self.log_completion(label, &result);
+        result
+    }
+
+    /// Labels of the statements which completed successfully so far, in the order of completion.
+    pub fn completed(&self) -> Vec<String> {
+        self.state.completed.lock().unwrap().clone()
+    }
+
+    /// Number of attempts used so far.
+    pub fn attempts(&self) -> usize {
+        *self.state.attempts.lock().unwrap()
+    }
+
+    /// Time left in the budget, `None` if the time is not limited.
+    pub fn remaining_time(&self) -> Option<Duration> {
+        self.state.remaining_time()
+    }
+
+    /// Attempts left in the budget, `None` if the number of attempts is not limited.
+    pub fn remaining_attempts(&self) -> Option<usize> {
+        self.state.remaining_attempts()
+    }
+
+    pub(crate) fn finish<T, E>(self, result: Result<T, E>) -> BudgetedExecution<T, E> {
+        BudgetedExecution {
+            result,
+            completed: self.completed(),
+            attempts: self.attempts(),
+            elapsed: self.state.started_at.elapsed(),
+        }
+    }
+
+    // Uses up the first attempt of the request and makes its retries and timeout
+    // draw from the budget. The retry policy and the timeout are resolved the same way
+    // as when the request is executed: the statement's take precedence over its profile's.
+    fn apply_budget(&self, config: &mut StatementConfig) -> Result<(), QueryError> {
+        self.state.start_request()?;
+
+        let execution_profile = config
+            .execution_profile_handle
+            .as_ref()
+            .unwrap_or_else(|| self.session.get_default_execution_profile_handle())
+            .access();
+        let retry_policy: Arc<dyn RetryPolicy> = match &config.retry_policy {
+            Some(retry_policy) => retry_policy.clone(),
+            None => Arc::from(execution_profile.retry_policy.clone()),
+        };
+        config.retry_policy = Some(Arc::new(BudgetedRetryPolicy {
+            inner: retry_policy,
+            state: self.state.clone(),
+        }));
+
+        if let Some(left) = self.state.remaining_time() {
+            let timeout = config
+                .request_timeout
+                .or(execution_profile.request_timeout)
+                .map_or(left, |timeout| timeout.min(left));
+            config.request_timeout = Some(timeout);
+        }
+        Ok(())
+    }
+
+    fn log_completion(&self, label: String, result: &Result<QueryResult, QueryError>) {
+        if result.is_ok() {
+            self.state.completed.lock().unwrap().push(label);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use scylla_cql::errors::DbError;
+    use scylla_cql::frame::types::Consistency;
+
+    use super::{BudgetState, BudgetedRetryPolicy, ExecutionBudget};
+    use crate::transport::errors::QueryError;
+    use crate::transport::retry_policy::{QueryInfo, RetryDecision, RetryPolicy, RetrySession};
+
+    // Retries every error with the given decision
+    #[derive(Debug, Clone)]
+    struct AlwaysRetry(RetryDecision);
+
+    impl RetryPolicy for AlwaysRetry {
+        fn new_session(&self) -> Box<dyn RetrySession> {
+            Box::new(self.clone())
+        }
+
+        fn clone_boxed(&self) -> Box<dyn RetryPolicy> {
+            Box::new(self.clone())
+        }
+    }
+
+    impl RetrySession for AlwaysRetry {
+        fn decide_should_retry(&mut self, _: QueryInfo) -> RetryDecision {
+            self.0.clone()
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    fn budgeted(decision: RetryDecision, state: &Arc<BudgetState>) -> Box<dyn RetrySession> {
+        BudgetedRetryPolicy {
+            inner: Arc::new(AlwaysRetry(decision)),
+            state: state.clone(),
+        }
+        .new_session()
+    }
+
+    fn decide(session: &mut dyn RetrySession) -> RetryDecision {
+        let error = QueryError::DbError(DbError::Overloaded, String::new());
+        session.decide_should_retry(QueryInfo::new(&error, true, Consistency::One))
+    }
+
+    #[test]
+    fn requests_and_retries_share_attempts() {
+        let state = Arc::new(BudgetState::new(ExecutionBudget {
+            max_attempts: Some(4),
+            ..Default::default()
+        }));
+
+        // The first request fails twice and succeeds on the third attempt
+        state.start_request().unwrap();
+        let mut first = budgeted(RetryDecision::RetrySameNode(None), &state);
+        assert_eq!(decide(&mut *first), RetryDecision::RetrySameNode(None));
+        assert_eq!(decide(&mut *first), RetryDecision::RetrySameNode(None));
+        assert_eq!(state.remaining_attempts(), Some(1));
+
+        // The second request may not be retried, as its first attempt uses up the budget
+        state.start_request().unwrap();
+        let mut second = budgeted(RetryDecision::RetryNextNode(Some(Consistency::One)), &state);
+        assert_eq!(decide(&mut *second), RetryDecision::DontRetry);
+        assert_eq!(state.remaining_attempts(), Some(0));
+
+        // The third request isn't sent at all
+        let error = state.start_request().unwrap_err();
+        assert!(matches!(
+            error,
+            QueryError::BudgetExhausted { reason } if reason == "all 4 attempts were used"
+        ));
+        assert_eq!(*state.attempts.lock().unwrap(), 4);
+    }
+
+    #[test]
+    fn final_decisions_are_kept() {
+        let state = Arc::new(BudgetState::new(ExecutionBudget {
+            max_attempts: Some(1),
+            ..Default::default()
+        }));
+        state.start_request().unwrap();
+
+        for decision in [RetryDecision::DontRetry, RetryDecision::IgnoreWriteError] {
+            let mut session = budgeted(decision.clone(), &state);
+            assert_eq!(decide(&mut *session), decision);
+        }
+        assert_eq!(state.remaining_attempts(), Some(0));
+    }
+
+    #[test]
+    fn retries_are_limited_by_time() {
+        let state = Arc::new(BudgetState::new(ExecutionBudget {
+            max_total_time: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        }));
+        state.start_request().unwrap();
+
+        // A retry after a delay longer than the time left would be cut short by the deadline
+        let mut delayed = budgeted(
+            RetryDecision::RetryNextNodeAfter(None, Duration::from_secs(7200)),
+            &state,
+        );
+        assert_eq!(decide(&mut *delayed), RetryDecision::DontRetry);
+
+        let mut immediate = budgeted(RetryDecision::RetryNextNode(None), &state);
+        assert_eq!(decide(&mut *immediate), RetryDecision::RetryNextNode(None));
+        assert_eq!(state.remaining_attempts(), None);
+        assert_eq!(*state.attempts.lock().unwrap(), 2);
+
+        // Nothing is sent after the deadline
+        let expired = Arc::new(BudgetState::new(ExecutionBudget {
+            max_total_time: Some(Duration::ZERO),
+            max_attempts: Some(10),
+        }));
+        let error = expired.start_request().unwrap_err();
+        assert!(matches!(
+            error,
+            QueryError::BudgetExhausted { reason } if reason == "the time budget of 0ms elapsed"
+        ));
+        let mut session = budgeted(RetryDecision::RetrySameNode(None), &expired);
+        assert_eq!(decide(&mut *session), RetryDecision::DontRetry);
+        assert_eq!(expired.remaining_attempts(), Some(10));
+    }
+}
@@ -167,7 +167,7 @@ use scylla_cql::{frame::types::SerialConsistency, Consistency};
 
 use crate::{
     load_balancing::LoadBalancingPolicy, retry_policy::RetryPolicy,
-    speculative_execution::SpeculativeExecutionPolicy,
+    speculative_execution::SpeculativeExecutionPolicy, transport::priority::RequestPriority,
 };
 
 pub(crate) mod defaults {
@@ -175,6 +175,7 @@ pub(crate) mod defaults {
     use crate::retry_policy::{DefaultRetryPolicy, RetryPolicy};
     use crate::speculative_execution::SpeculativeExecutionPolicy;
     use crate::transport::execution_profile::ExecutionProfileInner;
+    use crate::transport::priority::RequestPriority;
     use scylla_cql::frame::types::SerialConsistency;
     use scylla_cql::Consistency;
     use std::sync::Arc;
@@ -197,16 +198,20 @@ pub(crate) mod defaults {
     pub fn speculative_execution_policy() -> Option<Arc<dyn SpeculativeExecutionPolicy>> {
         None
     }
+    pub fn priority() -> RequestPriority {
+        RequestPriority::Normal
+    }
 
     impl Default for ExecutionProfileInner {
         fn default() -> Self {
             Self {
                 request_timeout: request_timeout(),
-                consistency: consistency(),
-                serial_consistency: serial_consistency(),
+                consistency: None,
+                serial_consistency: None,
                 load_balancing_policy: load_balancing_policy(),
                 retry_policy: retry_policy(),
                 speculative_execution_policy: speculative_execution_policy(),
+                priority: priority(),
             }
         }
     }
@@ -234,6 +239,7 @@ pub struct ExecutionProfileBuilder {
     load_balancing_policy: Option<Arc<dyn LoadBalancingPolicy>>,
     retry_policy: Option<Box<dyn RetryPolicy>>,
     speculative_execution_policy: Option<Option<Arc<dyn SpeculativeExecutionPolicy>>>,
+    priority: Option<RequestPriority>,
 }
 
 impl ExecutionProfileBuilder {
@@ -258,14 +264,16 @@ impl ExecutionProfileBuilder {
 
     /// Specify a default consistency to be used for queries.
     /// It's possible to override it by explicitly setting a consistency on the chosen query.
+    /// If not set, the [session default](crate::SessionBuilder::default_consistency) is used.
     pub fn consistency(mut self, consistency: Consistency) -> Self {
         self.consistency = Some(consistency);
         self
     }
 
-    /// Specify a default serial consistency to be used for queries.
+    /// Specify a default serial consistency to be used for conditional queries.
     /// It's possible to override it by explicitly setting a serial consistency
     /// on the chosen query.
+    /// If not set, the [session default](crate::SessionBuilder::default_serial_consistency) is used.
     pub fn serial_consistency(mut self, serial_consistency: Option<SerialConsistency>) -> Self {
         self.serial_consistency = Some(serial_consistency);
         self
@@ -346,6 +354,29 @@ impl ExecutionProfileBuilder {
         self
     }
 
+    /// Sets the priority class of requests using this profile.
+    /// The default is [`RequestPriority::Normal`].
+    ///
+    /// Priorities matter only if [priority scheduling](crate::SessionBuilder::priority_scheduling)
+    /// is enabled on the session. Then, requests waiting for a stream id on a busy
+    /// connection are sent in the order determined by their classes.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::transport::ExecutionProfile;
+    /// # use scylla::transport::priority::RequestPriority;
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let profile: ExecutionProfile = ExecutionProfile::builder()
+    ///     .priority(RequestPriority::Low)
+    ///     .build();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn priority(mut self, priority: RequestPriority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
     /// Builds the ExecutionProfile after setting all the options.
     ///
     /// # Example
@@ -364,10 +395,8 @@ impl ExecutionProfileBuilder {
             request_timeout: self
                 .request_timeout
                 .unwrap_or_else(defaults::request_timeout),
-            consistency: self.consistency.unwrap_or_else(defaults::consistency),
-            serial_consistency: self
-                .serial_consistency
-                .unwrap_or_else(defaults::serial_consistency),
+            consistency: self.consistency,
+            serial_consistency: self.serial_consistency,
             load_balancing_policy: self
                 .load_balancing_policy
                 .unwrap_or_else(defaults::load_balancing_policy),
@@ -375,6 +404,7 @@ impl ExecutionProfileBuilder {
             speculative_execution_policy: self
                 .speculative_execution_policy
                 .unwrap_or_else(defaults::speculative_execution_policy),
+            priority: self.priority.unwrap_or_else(defaults::priority),
         }))
     }
 }
@@ -398,12 +428,15 @@ pub struct ExecutionProfile(pub(crate) Arc<ExecutionProfileInner>);
 pub(crate) struct ExecutionProfileInner {
     pub(crate) request_timeout: Option<Duration>,
 
-    pub(crate) consistency: Consistency,
-    pub(crate) serial_consistency: Option<SerialConsistency>,
+    // `None` if not set in the profile, so that the session default is used
+    pub(crate) consistency: Option<Consistency>,
+    pub(crate) serial_consistency: Option<Option<SerialConsistency>>,
 
     pub(crate) load_balancing_policy: Arc<dyn LoadBalancingPolicy>,
     pub(crate) retry_policy: Box<dyn RetryPolicy>,
     pub(crate) speculative_execution_policy: Option<Arc<dyn SpeculativeExecutionPolicy>>,
+
+    pub(crate) priority: RequestPriority,
 }
 
 impl ExecutionProfileInner {
@@ -411,11 +444,12 @@ impl ExecutionProfileInner {
     pub(crate) fn to_builder(&self) -> ExecutionProfileBuilder {
         ExecutionProfileBuilder {
             request_timeout: Some(self.request_timeout),
-            consistency: Some(self.consistency),
-            serial_consistency: Some(self.serial_consistency),
+            consistency: self.consistency,
+            serial_consistency: self.serial_consistency,
             load_balancing_policy: Some(self.load_balancing_policy.clone()),
             retry_policy: Some(self.retry_policy.clone()),
             speculative_execution_policy: Some(self.speculative_execution_policy.clone()),
+            priority: Some(self.priority),
         }
     }
 }
@@ -434,6 +468,7 @@ impl ExecutionProfile {
             load_balancing_policy: None,
             retry_policy: None,
             speculative_execution_policy: None,
+            priority: None,
         }
     }
 
@@ -0,0 +1,260 @@
+//! Intercepting requests issued by the [`Session`](crate::Session).
+//!
+//! A [`RequestInterceptor`] is a single place to inspect, modify or deny every request
+//! sent through `query`, `execute`, `batch` and their paged/iterator variants.
+//! Interceptors are installed as an ordered chain with
+//! [`SessionBuilder::request_interceptor`](crate::transport::session_builder::GenericSessionBuilder::request_interceptor).
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use scylla_cql::frame::response::result::ColumnSpec;
+use scylla_cql::frame::types::{Consistency, SerialConsistency};
+
+use crate::batch::Batch;
+use crate::prepared_statement::PreparedStatement;
+use crate::query::Query;
+use crate::statement::StatementConfig;
+use crate::transport::errors::{ExecutionError, QueryError};
+use crate::transport::node::NodeRef;
+use crate::QueryResult;
+
+/// Statement that an intercepted request was issued for.
+#[derive(Clone, Copy)]
+#[non_exhaustive]
+pub enum InterceptedStatement<'a> {
+    /// An unprepared statement, issued with `query*` methods.
+    Query(&'a Query),
+    /// A prepared statement, issued with `execute*` methods.
+    Prepared(&'a PreparedStatement),
+    /// A batch, issued with [`Session::batch`](crate::Session::batch).
+    Batch(&'a Batch),
+}
+
+impl<'a> InterceptedStatement<'a> {
+    pub(crate) fn config(&self) -> &'a StatementConfig {
+        match self {
+            InterceptedStatement::Query(query) => &query.config,
+            InterceptedStatement::Prepared(prepared) => &prepared.config,
+            InterceptedStatement::Batch(batch) => &batch.config,
+        }
+    }
+
+    pub(crate) fn is_conditional(&self) -> bool {
+        match self {
+            InterceptedStatement::Query(query) => query.is_conditional(),
+            InterceptedStatement::Prepared(prepared) => prepared.is_conditional(),
+            InterceptedStatement::Batch(batch) => batch.is_conditional(),
+        }
+    }
+}
+
+/// Information about a request which is about to be sent, passed to [`RequestInterceptor`]s.
+///
+/// Interceptors may modify the custom payload and the timestamp of the request.
+/// The modifications are visible to interceptors later in the chain and are applied
+/// to the request when it is sent. The statement passed by the user is not modified.
+pub struct RequestContext<'a> {
+    statement: InterceptedStatement<'a>,
+    values_count: Option<usize>,
+    consistency: Consistency,
+    serial_consistency: Option<SerialConsistency>,
+    custom_payload: HashMap<String, Vec<u8>>,
+    timestamp: Option<i64>,
+}
+
+impl<'a> RequestContext<'a> {
+    pub(crate) fn new(
+        statement: InterceptedStatement<'a>,
+        values_count: Option<usize>,
+        consistency: Consistency,
+        serial_consistency: Option<SerialConsistency>,
+    ) -> Self {
+        let config = statement.config();
+        Self {
+            statement,
+            values_count,
+            consistency,
+            serial_consistency,
+            custom_payload: config.custom_payload.clone().unwrap_or_default(),
+            timestamp: config.timestamp,
+        }
+    }
+
+    /// Returns the statement that the request was issued for.
+    pub fn statement(&self) -> InterceptedStatement<'a> {
+        self.statement
+    }
+
+    /// Returns the CQL text of the statement, or `None` for batches.
+    pub fn statement_text(&self) -> Option<&'a str> {
+        match self.statement {
+            InterceptedStatement::Query(query) => Some(&query.contents),
+            InterceptedStatement::Prepared(prepared) => Some(prepared.get_statement()),
+            InterceptedStatement::Batch(_) => None,
+        }
+    }
+
+    /// Returns the id of the prepared statement, or `None` if the statement is not prepared.
+    pub fn prepared_id(&self) -> Option<&'a Bytes> {
+        match self.statement {
+            InterceptedStatement::Prepared(prepared) => Some(prepared.get_id()),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of values bound to the statement.
+    ///
+    /// Known for prepared statements and for unprepared statements without values,
+    /// `None` otherwise.
+    pub fn values_count(&self) -> Option<usize> {
+        self.values_count
+    }
+
+    /// Returns specifications (names and types) of values bound to the statement,
+    /// or `None` if the statement is not prepared.
+    pub fn value_specs(&self) -> Option<&'a [ColumnSpec]> {
+        match self.statement {
+            InterceptedStatement::Prepared(prepared) => {
+                Some(&prepared.get_prepared_metadata().col_specs)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the consistency the request is going to be sent with,
+    /// as set on the statement, in its execution profile or as the session default.
+    ///
+    /// Note that the retry policy may still decide to retry with a different consistency.
+    pub fn consistency(&self) -> Consistency {
+        self.consistency
+    }
+
+    /// Returns the serial consistency the request is going to be sent with,
+    /// as set on the statement, in its execution profile or as the session default.
+    /// The session default is used only for conditional statements.
+    pub fn serial_consistency(&self) -> Option<SerialConsistency> {
+        self.serial_consistency
+    }
+
+    /// Returns the custom payload that will be sent with the request.
+    pub fn custom_payload(&self) -> &HashMap<String, Vec<u8>> {
+        &self.custom_payload
+    }
+
+    /// Returns the custom payload that will be sent with the request, allowing to modify it.
+    pub fn custom_payload_mut(&mut self) -> &mut HashMap<String, Vec<u8>> {
+        &mut self.custom_payload
+    }
+
+    /// Returns the default timestamp (in microseconds) that will be sent with the request.
+    pub fn timestamp(&self) -> Option<i64> {
+        self.timestamp
+    }
+
+    /// Sets the default timestamp (in microseconds) that will be sent with the request.
+    /// See [`Query::set_timestamp`] for details.
+    pub fn set_timestamp(&mut self, timestamp: Option<i64>) {
+        self.timestamp = timestamp;
+    }
+
+    /// Returns the statement configuration with modifications made by the interceptors,
+    /// or `None` if there were no modifications.
+    pub(crate) fn modified_config(&self) -> Option<StatementConfig> {
+        let config = self.statement.config();
+        let custom_payload = if self.custom_payload.is_empty() {
+            None
+        } else {
+            Some(&self.custom_payload)
+        };
+        if config.timestamp == self.timestamp && config.custom_payload.as_ref() == custom_payload {
+            return None;
+        }
+
+        let mut config = config.clone();
+        config.timestamp = self.timestamp;
+        config.custom_payload = custom_payload.cloned();
+        Some(config)
+    }
+}
+
+/// Intercepts requests issued by the [`Session`](crate::Session).
+///
+/// `on_request` and `on_response` are called once per request issued by the user,
+/// no matter how many times the request is retried or speculatively executed.
+/// `on_attempt` is called for every single attempt.
+///
+/// For paging iterators ([`Session::query_iter`](crate::Session::query_iter),
+/// [`Session::execute_iter`](crate::Session::execute_iter)) `on_request` is called
+/// before the first page is fetched, and the modifications apply to all pages.
+/// `on_attempt` is called for every attempt to fetch a page. `on_response` is called
+/// once the last page was received or fetching a page failed; a successful result has
+/// no rows, as they are returned by the iterator. If the iterator is dropped before that,
+/// `on_response` may not be called.
+#[async_trait]
+pub trait RequestInterceptor: std::fmt::Debug + Send + Sync {
+    /// Called before the request is sent. Interceptors are called in the order
+    /// in which they were installed.
+    ///
+    /// Returning an error denies the request: it is not sent, the following interceptors'
+    /// `on_request` is not called and the error is returned to the user.
+    async fn on_request(&self, ctx: &mut RequestContext<'_>) -> Result<(), QueryError>;
+
+    /// Called with the final result of the request, including the requests denied
+    /// by `on_request` of some interceptor. Interceptors are called in the order
+    /// in which they were installed.
+    fn on_response(
+        &self,
+        _ctx: &RequestContext<'_>,
+        _result: &Result<QueryResult, ExecutionError>,
+    ) {
+    }
+
+    /// Called before every attempt to send the request to a node,
+    /// including retries and speculative executions.
+    fn on_attempt(&self, _ctx: &RequestContext<'_>, _node: NodeRef<'_>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InterceptedStatement, RequestContext};
+    use crate::query::Query;
+    use crate::statement::Consistency;
+
+    #[test]
+    fn modified_config_reflects_changes() {
+        let mut query = Query::new("SELECT * FROM ks.t");
+        query.set_timestamp(Some(1));
+
+        let mut ctx = RequestContext::new(
+            InterceptedStatement::Query(&query),
+            Some(0),
+            Consistency::One,
+            None,
+        );
+        assert_eq!(ctx.statement_text(), Some("SELECT * FROM ks.t"));
+        assert_eq!(ctx.timestamp(), Some(1));
+        assert!(ctx.custom_payload().is_empty());
+        assert!(ctx.modified_config().is_none());
+
+        ctx.set_timestamp(Some(2));
+        let config = ctx.modified_config().unwrap();
+        assert_eq!(config.timestamp, Some(2));
+        assert_eq!(config.custom_payload, None);
+
+        ctx.set_timestamp(Some(1));
+        ctx.custom_payload_mut()
+            .insert("tenant".to_owned(), b"acme".to_vec());
+        let config = ctx.modified_config().unwrap();
+        assert_eq!(config.timestamp, Some(1));
+        assert_eq!(
+            config
+                .custom_payload
+                .unwrap()
+                .get("tenant")
+                .map(Vec::as_slice),
+            Some(&b"acme"[..])
+        );
+    }
+}
@@ -1,5 +1,6 @@
 //! Iterators over rows returned by paged queries
 
+use std::borrow::Cow;
 use std::future::Future;
 use std::mem;
 use std::net::SocketAddr;
@@ -7,25 +8,32 @@ use std::ops::ControlFlow;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Instant;
 
 use bytes::Bytes;
-use futures::Stream;
+use futures::future::RemoteHandle;
+use futures::{FutureExt, Stream, StreamExt};
 use scylla_cql::frame::response::NonErrorResponse;
 use scylla_cql::frame::types::SerialConsistency;
+use scylla_cql::types::serialize::redaction::ValueRedaction;
 use scylla_cql::types::serialize::row::SerializedValues;
 use std::result::Result;
 use thiserror::Error;
 use tokio::sync::mpsc;
-use tracing::instrument::WithSubscriber;
 
-use super::errors::QueryError;
+use super::background_tasks::{BackgroundTaskKind, BackgroundTasks};
+use super::errors::{ExecutionError, QueryError};
 use super::execution_profile::ExecutionProfileInner;
-use super::session::RequestSpan;
+use super::interceptor::{InterceptedStatement, RequestContext, RequestInterceptor};
+use super::priority::RequestPriority;
+use super::runtime::Runtime;
+use super::session::{new_attempt_span, outcome_str, RequestSpan};
 use crate::cql_to_rust::{FromRow, FromRowError};
+use crate::transport::query_result::RowTypeError;
 
 use crate::frame::response::{
     result,
-    result::{ColumnSpec, Row, Rows},
+    result::{ColumnDecodeError, ColumnSpec, ResultMetadata, Row, Rows},
 };
 use crate::history::{self, HistoryListener};
 use crate::statement::Consistency;
@@ -33,10 +41,14 @@ use crate::statement::{prepared_statement::PreparedStatement, query::Query};
 use crate::transport::cluster::ClusterData;
 use crate::transport::connection::{Connection, NonErrorQueryResponse, QueryResponse};
 use crate::transport::load_balancing::{self, RoutingInfo};
-use crate::transport::metrics::Metrics;
+use crate::transport::metrics::{LabelMetrics, Metrics};
+#[cfg(feature = "metrics-exporter")]
+use crate::transport::metrics_exporter::{NodeLabels, RequestEvent, RequestOutcome};
 use crate::transport::retry_policy::{QueryInfo, RetryDecision, RetrySession};
+use crate::transport::slow_query_log::{RequestStats, SlowQueryLog};
 use crate::transport::{Node, NodeRef};
-use tracing::{trace, trace_span, warn, Instrument};
+use crate::QueryResult;
+use tracing::{trace, warn, Instrument, Level};
 use uuid::Uuid;
 
 // #424
@@ -55,26 +67,210 @@ use uuid::Uuid;
 // value at the beginning of `query_iter` and `execute_iter`.
 const DEFAULT_ITER_PAGE_SIZE: i32 = 5000;
 
+/// Options of a single [`Session::query_iter_with_config`](crate::Session::query_iter_with_config)
+/// or [`Session::execute_iter_with_config`](crate::Session::execute_iter_with_config) call.
+/// They override the options of the statement without modifying it, so a statement
+/// shared by many iterators can be read with a different page size by some of them.
+///
+/// They also limit how much is read by the iterator, see [`IterConfig::max_rows`]
+/// and [`IterConfig::max_bytes`].
+///
+/// # Example
+/// ```
+/// # use scylla::transport::iterator::IterConfig;
+/// let config = IterConfig::new().page_size(100).max_rows(1000);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct IterConfig {
+    page_size: Option<i32>,
+    max_rows: Option<u64>,
+    max_bytes: Option<u64>,
+}
+
+impl IterConfig {
+    /// Creates a configuration which doesn't override anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the page size of the iterator, overriding the one of the statement.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `page_size` is not positive.
+    pub fn page_size(mut self, page_size: i32) -> Self {
+        assert!(page_size > 0, "page size must be larger than 0");
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Returns the page size overriding the one of the statement, if set.
+    pub fn get_page_size(&self) -> Option<i32> {
+        self.page_size
+    }
+
+    /// Sets the maximum number of rows returned by the iterator.
+    ///
+    /// If the result has more rows, the stream ends after returning `max_rows` of them,
+    /// even in the middle of a page, and [`RowIterator::truncation`] tells that it was truncated.
+    pub fn max_rows(mut self, max_rows: u64) -> Self {
+        self.max_rows = Some(max_rows);
+        self
+    }
+
+    /// Returns the maximum number of rows returned by the iterator, if set.
+    pub fn get_max_rows(&self) -> Option<u64> {
+        self.max_rows
+    }
+
+    /// Sets the maximum number of bytes of the pages received by the iterator,
+    /// counted as the serialized size of the rows sent by the database.
+    ///
+    /// The limit is checked between pages: once the received pages reach `max_bytes`,
+    /// no more pages are read. The rows of the last received page are still returned,
+    /// and then the stream ends, with [`RowIterator::truncation`] telling that it was truncated.
+    /// Pages are fetched one ahead of the rows being read, so the database may already
+    /// be sending the next page, which is discarded.
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Returns the maximum number of bytes of the pages received by the iterator, if set.
+    pub fn get_max_bytes(&self) -> Option<u64> {
+        self.max_bytes
+    }
+}
+
+/// Tells why and where a [`RowIterator`] was truncated by the limits of its [`IterConfig`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruncationInfo {
+    /// The limit which was reached.
+    pub reason: TruncationReason,
+    /// Number of rows returned before the stream ended.
+    pub rows: u64,
+    /// Number of bytes of the pages received before the stream ended.
+    pub bytes: u64,
+}
+
+/// Limit of a [`RowIterator`] which caused the stream to end before the last row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TruncationReason {
+    /// The iterator returned [`IterConfig::max_rows`] rows.
+    MaxRows,
+    /// The iterator received [`IterConfig::max_bytes`] bytes.
+    MaxBytes,
+}
+
+// Counts a worker task in `Metrics::get_running_iterator_workers` until it finishes,
+// or is cancelled together with its iterator.
+struct RunningWorkerGuard(Arc<Metrics>);
+
+impl RunningWorkerGuard {
+    fn new(metrics: Arc<Metrics>) -> Self {
+        metrics.inc_running_iterator_workers();
+        Self(metrics)
+    }
+}
+
+impl Drop for RunningWorkerGuard {
+    fn drop(&mut self) {
+        self.0.dec_running_iterator_workers();
+    }
+}
+
 /// Iterator over rows returned by paged queries\
 /// Allows to easily access rows without worrying about handling multiple pages
+///
+/// Pages are fetched by a background task, one page ahead of the rows being read.
+/// Dropping the iterator cancels the task, including the request for the next page
+/// if it's in flight, so an iterator can be abandoned between pages at any time.
 pub struct RowIterator {
     current_row_idx: usize,
     current_page: Rows,
     page_receiver: mpsc::Receiver<Result<ReceivedPage, QueryError>>,
     tracing_ids: Vec<Uuid>,
+    warnings: Vec<String>,
+    pages_received: usize,
+    rows_processed: usize,
+    // Paging state with which the current page was requested
+    current_page_paging_state: Option<Bytes>,
+    current_page_coordinator: Option<Arc<Node>>,
+    current_page_tracing_id: Option<Uuid>,
+    page_observer: Option<PageObserver>,
+    // Whether the observer was already called for the current page
+    current_page_observed: bool,
+    // Set when the observer asked to stop, no more pages are received then
+    stopped: bool,
+    max_rows: Option<u64>,
+    max_bytes: Option<u64>,
+    // Serialized size of all pages received so far
+    bytes_received: u64,
+    truncation: Option<TruncationInfo>,
+    // Dropping the handle cancels the worker task fetching the pages
+    _worker_handle: RemoteHandle<()>,
 }
 
+type PageObserver = Box<dyn FnMut(PageInfo) -> ControlFlow<()> + Send + Sync>;
+
 struct ReceivedPage {
     rows: Rows,
     tracing_id: Option<Uuid>,
+    warnings: Vec<String>,
+    coordinator: Option<Arc<Node>>,
+}
+
+#[cfg(feature = "testing")]
+impl From<crate::QueryResult> for ReceivedPage {
+    fn from(result: crate::QueryResult) -> Self {
+        let rows = result.rows.unwrap_or_default();
+        ReceivedPage {
+            rows: Rows {
+                metadata: ResultMetadata::new(result.col_specs, result.paging_state),
+                rows_count: rows.len(),
+                rows,
+                decode_errors: result.decode_errors,
+                serialized_size: result.serialized_size,
+                raw_rows: None,
+            },
+            tracing_id: result.tracing_id,
+            warnings: result.warnings,
+            coordinator: None,
+        }
+    }
+}
+
+/// Information about a page of rows received by a [`RowIterator`],
+/// passed to the observer set with [`RowIterator::with_page_observer`].
+#[derive(Debug, Clone)]
+pub struct PageInfo {
+    /// Zero-based ordinal of the page.
+    pub page: usize,
+    /// Number of rows in the page.
+    pub rows: usize,
+    /// Node which served the page, `None` for iterators over a single connection.
+    pub coordinator: Option<Arc<Node>>,
+    /// Tracing id of the page query, if tracing was enabled.
+    pub tracing_id: Option<Uuid>,
+    /// Paging state of the next page, `None` if this page is the last one.
+    /// Passing it to [`Session::query_single_page`](crate::Session::query_single_page) or
+    /// [`Session::execute_single_page`](crate::Session::execute_single_page) resumes reading
+    /// right after this page, so it can be saved as a checkpoint.
+    pub paging_state: Option<Bytes>,
 }
 
 pub(crate) struct PreparedIteratorConfig {
     pub(crate) prepared: PreparedStatement,
     pub(crate) values: SerializedValues,
     pub(crate) execution_profile: Arc<ExecutionProfileInner>,
+    pub(crate) consistency: Consistency,
+    pub(crate) serial_consistency: Option<SerialConsistency>,
     pub(crate) cluster_data: Arc<ClusterData>,
     pub(crate) metrics: Arc<Metrics>,
+    pub(crate) slow_query_log: Option<SlowQueryLog>,
+    pub(crate) request_span_level: Level,
+    pub(crate) background_tasks: BackgroundTasks,
+    pub(crate) request_interceptors: Vec<Arc<dyn RequestInterceptor>>,
 }
 
 /// Fetching pages is asynchronous so `RowIterator` does not implement the `Iterator` trait.\
@@ -85,26 +281,18 @@ impl Stream for RowIterator {
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let mut s = self.as_mut();
 
-        if s.is_current_page_exhausted() {
-            match Pin::new(&mut s.page_receiver).poll_recv(cx) {
-                Poll::Ready(Some(Ok(received_page))) => {
-                    s.current_page = received_page.rows;
-                    s.current_row_idx = 0;
-
-                    if let Some(tracing_id) = received_page.tracing_id {
-                        s.tracing_ids.push(tracing_id);
-                    }
-                }
-                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
-                Poll::Ready(None) => return Poll::Ready(None),
-                Poll::Pending => return Poll::Pending,
-            }
+        match s.poll_current_page(cx) {
+            Poll::Ready(Some(Ok(()))) => {}
+            Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
         }
 
         let idx = s.current_row_idx;
         if idx < s.current_page.rows.len() {
             let row = mem::take(&mut s.current_page.rows[idx]);
             s.current_row_idx += 1;
+            s.rows_processed += 1;
             return Poll::Ready(Some(Ok(row)));
         }
 
@@ -124,43 +312,53 @@ impl RowIterator {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn new_for_query(
         mut query: Query,
         execution_profile: Arc<ExecutionProfileInner>,
+        consistency: Consistency,
+        serial_consistency: Option<SerialConsistency>,
         cluster_data: Arc<ClusterData>,
         metrics: Arc<Metrics>,
+        slow_query_log: Option<SlowQueryLog>,
+        request_span_level: Level,
+        background_tasks: BackgroundTasks,
+        request_interceptors: Vec<Arc<dyn RequestInterceptor>>,
     ) -> Result<RowIterator, QueryError> {
         if query.get_page_size().is_none() {
             query.set_page_size(DEFAULT_ITER_PAGE_SIZE);
         }
         let (sender, receiver) = mpsc::channel(1);
 
-        let consistency = query
-            .config
-            .consistency
-            .unwrap_or(execution_profile.consistency);
-        let serial_consistency = query
-            .config
-            .serial_consistency
-            .unwrap_or(execution_profile.serial_consistency);
-
-        let routing_info = RoutingInfo {
-            consistency,
-            serial_consistency,
-            ..Default::default()
-        };
-
         let retry_session = query
             .get_retry_policy()
             .map(|rp| &**rp)
             .unwrap_or(&*execution_profile.retry_policy)
             .new_session();
+        let priority = execution_profile.priority;
 
         let parent_span = tracing::Span::current();
+        let running = RunningWorkerGuard::new(metrics.clone());
+        let runtime = background_tasks.runtime().clone();
         let worker_task = async move {
+            let _running = running;
             let query_ref = &query;
 
-            let choose_connection = |node: Arc<Node>| async move { node.random_connection().await };
+            let (token, keyspace) = query.routing(None);
+            let routing_info = RoutingInfo {
+                consistency,
+                serial_consistency,
+                token,
+                keyspace,
+                ..Default::default()
+            };
+
+            let choose_connection = |node: Arc<Node>| async move {
+                match token {
+                    Some(token) => node.connection_for_token(token).await,
+                    None => node.random_connection().await,
+                }
+            };
 
             let page_query = |connection: Arc<Connection>,
                               consistency: Consistency,
@@ -171,6 +369,7 @@ impl RowIterator {
                             query_ref,
                             consistency,
                             serial_consistency,
+                            priority,
                             paging_state,
                         )
                         .await
@@ -179,9 +378,18 @@ impl RowIterator {
 
             let query_ref = &query;
 
+            let interceptors = IteratorInterceptors::new(
+                request_interceptors,
+                InterceptedStatement::Query(query_ref),
+                Some(0),
+                consistency,
+                serial_consistency,
+            );
+
             let span_creator = move || {
-                let span = RequestSpan::new_query(&query_ref.contents);
+                let span = RequestSpan::new_query(&query_ref.contents, request_span_level);
                 span.record_request_size(0);
+                span.record_label(query_ref.config.label.as_deref());
                 span
             };
 
@@ -194,19 +402,29 @@ impl RowIterator {
                 query_consistency: consistency,
                 retry_session,
                 execution_profile,
+                label_metrics: query.get_label().map(|label| metrics.label_metrics(label)),
                 metrics,
                 paging_state: None,
                 history_listener: query.config.history_listener.clone(),
                 current_query_id: None,
                 current_attempt_id: None,
+                statement: &query.contents,
+                slow_query_log,
+                request_span_level,
+                page_started_at: Instant::now(),
+                page_stats: RequestStats::default(),
+                page_attempts: 0,
+                label: query.config.label.clone(),
+                interceptors,
                 parent_span,
                 span_creator,
+                runtime,
             };
 
             worker.work(cluster_data).await
         };
 
-        Self::new_from_worker_future(worker_task, receiver).await
+        Self::new_from_worker_future(worker_task, receiver, &background_tasks).await
     }
 
     pub(crate) async fn new_for_prepared_statement(
@@ -217,16 +435,9 @@ impl RowIterator {
         }
         let (sender, receiver) = mpsc::channel(1);
 
-        let consistency = config
-            .prepared
-            .config
-            .consistency
-            .unwrap_or(config.execution_profile.consistency);
-        let serial_consistency = config
-            .prepared
-            .config
-            .serial_consistency
-            .unwrap_or(config.execution_profile.serial_consistency);
+        let consistency = config.consistency;
+        let serial_consistency = config.serial_consistency;
+        let priority = config.execution_profile.priority;
         let retry_session = config
             .prepared
             .get_retry_policy()
@@ -235,21 +446,38 @@ impl RowIterator {
             .new_session();
 
         let parent_span = tracing::Span::current();
+        let running = RunningWorkerGuard::new(config.metrics.clone());
+        let background_tasks = config.background_tasks.clone();
+        let runtime = background_tasks.runtime().clone();
         let worker_task = async move {
+            let _running = running;
             let prepared_ref = &config.prepared;
             let values_ref = &config.values;
 
-            let (partition_key, token) = match prepared_ref
-                .extract_partition_key_and_calculate_token(
-                    prepared_ref.get_partitioner_name(),
-                    values_ref,
-                ) {
-                Ok(res) => res.unzip(),
-                Err(err) => {
-                    let (proof, _res) = ProvingSender::from(sender).send(Err(err)).await;
-                    return proof;
-                }
-            };
+            let interceptors = IteratorInterceptors::new(
+                config.request_interceptors,
+                InterceptedStatement::Prepared(prepared_ref),
+                Some(values_ref.element_count() as usize),
+                consistency,
+                serial_consistency,
+            );
+
+            let (partition_key, token) =
+                match prepared_ref.partition_key_and_routing_token(values_ref) {
+                    Ok(res) => res,
+                    Err(err) => {
+                        if let Some(interceptors) = &interceptors {
+                            interceptors.on_response(&Err(ExecutionError::new(
+                                err.clone(),
+                                None,
+                                0,
+                                prepared_ref.config.is_idempotent,
+                            )));
+                        }
+                        let (proof, _res) = ProvingSender::from(sender).send(Err(err)).await;
+                        return proof;
+                    }
+                };
 
             let statement_info = RoutingInfo {
                 consistency,
@@ -275,12 +503,14 @@ impl RowIterator {
                         values_ref,
                         consistency,
                         serial_consistency,
+                        priority,
                         paging_state,
                     )
                     .await
             };
 
             let serialized_values_size = config.values.buffer_size();
+            let request_span_level = config.request_span_level;
 
             let replicas: Option<smallvec::SmallVec<[_; 8]>> =
                 if let (Some(keyspace), Some(token)) =
@@ -302,7 +532,13 @@ impl RowIterator {
                     partition_key.as_ref().map(|pk| pk.iter()),
                     token,
                     serialized_values_size,
+                    request_span_level,
                 );
+                span.record_table(
+                    prepared_ref.get_keyspace_name(),
+                    prepared_ref.get_table_name(),
+                );
+                span.record_label(prepared_ref.get_label());
                 if let Some(replicas) = replicas.as_ref() {
                     span.record_replicas(replicas);
                 }
@@ -318,19 +554,32 @@ impl RowIterator {
                 query_consistency: consistency,
                 retry_session,
                 execution_profile: config.execution_profile,
+                label_metrics: config
+                    .prepared
+                    .get_label()
+                    .map(|label| config.metrics.label_metrics(label)),
                 metrics: config.metrics,
                 paging_state: None,
                 history_listener: config.prepared.config.history_listener.clone(),
                 current_query_id: None,
                 current_attempt_id: None,
+                statement: config.prepared.get_statement(),
+                slow_query_log: config.slow_query_log,
+                request_span_level,
+                page_started_at: Instant::now(),
+                page_stats: RequestStats::default(),
+                page_attempts: 0,
+                label: config.prepared.config.label.clone(),
+                interceptors,
                 parent_span,
                 span_creator,
+                runtime,
             };
 
             worker.work(config.cluster_data).await
         };
 
-        Self::new_from_worker_future(worker_task, receiver).await
+        Self::new_from_worker_future(worker_task, receiver, &background_tasks).await
     }
 
     pub(crate) async fn new_for_connection_query_iter(
@@ -344,6 +593,7 @@ impl RowIterator {
         }
         let (sender, receiver) = mpsc::channel::<Result<ReceivedPage, QueryError>>(1);
 
+        let background_tasks = connection.get_background_tasks().clone();
         let worker_task = async move {
             let worker = SingleConnectionRowIteratorWorker {
                 sender: sender.into(),
@@ -352,6 +602,7 @@ impl RowIterator {
                         &query,
                         consistency,
                         serial_consistency,
+                        RequestPriority::Normal,
                         paging_state,
                     )
                 },
@@ -359,7 +610,7 @@ impl RowIterator {
             worker.work().await
         };
 
-        Self::new_from_worker_future(worker_task, receiver).await
+        Self::new_from_worker_future(worker_task, receiver, &background_tasks).await
     }
 
     pub(crate) async fn new_for_connection_execute_iter(
@@ -374,6 +625,7 @@ impl RowIterator {
         }
         let (sender, receiver) = mpsc::channel::<Result<ReceivedPage, QueryError>>(1);
 
+        let background_tasks = connection.get_background_tasks().clone();
         let worker_task = async move {
             let worker = SingleConnectionRowIteratorWorker {
                 sender: sender.into(),
@@ -383,6 +635,7 @@ impl RowIterator {
                         &values,
                         consistency,
                         serial_consistency,
+                        RequestPriority::Normal,
                         paging_state,
                     )
                 },
@@ -390,21 +643,56 @@ impl RowIterator {
             worker.work().await
         };
 
-        Self::new_from_worker_future(worker_task, receiver).await
+        Self::new_from_worker_future(worker_task, receiver, &background_tasks).await
+    }
+
+    /// Creates an iterator returning the rows of the given results, one page for each result.
+    /// The first result makes the first page, so at least one is always sent.
+    #[cfg(feature = "testing")]
+    pub(crate) async fn new_from_results(
+        results: Vec<crate::QueryResult>,
+    ) -> Result<RowIterator, QueryError> {
+        let (sender, receiver) = mpsc::channel(1);
+        let sender: ProvingSender<_> = sender.into();
+        let worker_task = async move {
+            let mut results = results.into_iter();
+            let first = results.next().unwrap_or_default();
+            let (mut proof, mut sent) = sender.send(Ok(ReceivedPage::from(first))).await;
+            for result in results {
+                if sent.is_err() {
+                    break;
+                }
+                (proof, sent) = sender.send(Ok(ReceivedPage::from(result))).await;
+            }
+            proof
+        };
+        Self::new_from_worker_future(worker_task, receiver, &BackgroundTasks::default()).await
     }
 
     async fn new_from_worker_future(
         worker_task: impl Future<Output = PageSendAttemptedProof> + Send + 'static,
         mut receiver: mpsc::Receiver<Result<ReceivedPage, QueryError>>,
+        background_tasks: &BackgroundTasks,
     ) -> Result<RowIterator, QueryError> {
-        tokio::task::spawn(worker_task.with_current_subscriber());
+        // Values are rendered in the worker the same way as by the session which created it
+        let worker_task = match ValueRedaction::scoped() {
+            Some(redaction) => redaction.scope(worker_task).left_future(),
+            None => worker_task.right_future(),
+        };
+        // The task is cancelled when the handle is dropped - together with the iterator,
+        // or with this future if it's dropped before the first page arrives
+        let worker_handle =
+            background_tasks.spawn(BackgroundTaskKind::IteratorWorker, None, |_activity| {
+                worker_task.map(|_proof| ())
+            });
 
         // This unwrap is safe because:
         // - The future returned by worker.work sends at least one item
         //   to the channel (the PageSendAttemptedProof helps enforce this)
-        // - That future is polled in a tokio::task which isn't going to be
-        //   cancelled
+        // - That future is polled in a tokio::task which isn't cancelled
+        //   while `worker_handle` is alive
         let pages_received = receiver.recv().await.unwrap()?;
+        let bytes_received = pages_received.rows.serialized_size as u64;
 
         Ok(RowIterator {
             current_row_idx: 0,
@@ -415,22 +703,373 @@ impl RowIterator {
             } else {
                 Vec::new()
             },
+            warnings: pages_received.warnings,
+            pages_received: 1,
+            rows_processed: 0,
+            current_page_paging_state: None,
+            current_page_coordinator: pages_received.coordinator,
+            current_page_tracing_id: pages_received.tracing_id,
+            page_observer: None,
+            current_page_observed: false,
+            stopped: false,
+            max_rows: None,
+            max_bytes: None,
+            bytes_received,
+            truncation: None,
+            _worker_handle: worker_handle,
         })
     }
 
+    /// Applies the limits of the given config to the iterator, which didn't return any rows yet.
+    pub(crate) fn with_limits(mut self, config: &IterConfig) -> Self {
+        self.max_rows = config.max_rows;
+        self.max_bytes = config.max_bytes;
+        self
+    }
+
+    /// Sets a function called for each page after it's received, before its rows are returned.
+    /// It can be used to report progress of a long scan, or to save the paging state
+    /// of the next page as a checkpoint.
+    ///
+    /// If it returns [`ControlFlow::Break`], no more pages are fetched: the rows of the current
+    /// page are returned, and then the stream ends without an error.
+    /// Reading can be resumed later with the [`PageInfo::paging_state`] of that page.
+    ///
+    /// The first page is already received when the iterator is created; the observer
+    /// is called for it if none of its rows were returned yet.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use scylla::Session;
+    /// # use std::error::Error;
+    /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+    /// use futures::stream::StreamExt;
+    /// use std::ops::ControlFlow;
+    ///
+    /// let mut rows_stream = session
+    ///     .query_iter("SELECT a, b FROM ks.t", &[])
+    ///     .await?
+    ///     .with_page_observer(|page| {
+    ///         println!("Page {} with {} rows", page.page, page.rows);
+    ///         match page.page {
+    ///             0..=9 => ControlFlow::Continue(()),
+    ///             _ => ControlFlow::Break(()),
+    ///         }
+    ///     });
+    ///
+    /// while let Some(row) = rows_stream.next().await {
+    ///     let row = row?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_page_observer(
+        mut self,
+        observer: impl FnMut(PageInfo) -> ControlFlow<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.page_observer = Some(Box::new(observer));
+        if self.current_row_idx == 0 {
+            self.current_page_observed = false;
+        }
+        self
+    }
+
+    fn observe_current_page(&mut self) {
+        if self.current_page_observed {
+            return;
+        }
+        self.current_page_observed = true;
+        let observer = match self.page_observer.as_mut() {
+            Some(observer) => observer,
+            None => return,
+        };
+
+        let info = PageInfo {
+            page: self.pages_received - 1,
+            rows: self.current_page.rows.len(),
+            coordinator: self.current_page_coordinator.clone(),
+            tracing_id: self.current_page_tracing_id,
+            paging_state: self.current_page.metadata.paging_state.clone(),
+        };
+        if observer(info).is_break() {
+            self.stopped = true;
+            // The worker stops once it fails to send the next page
+            self.page_receiver.close();
+        }
+    }
+
     /// If tracing was enabled returns tracing ids of all finished page queries
     pub fn get_tracing_ids(&self) -> &[Uuid] {
         &self.tracing_ids
     }
 
+    /// Returns warnings attached by the database to all pages received so far
+    pub fn get_warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
     /// Returns specification of row columns
     pub fn get_column_specs(&self) -> &[ColumnSpec] {
         &self.current_page.metadata.col_specs
     }
 
+    /// Returns metadata of the current page.
+    pub fn metadata(&self) -> &ResultMetadata {
+        &self.current_page.metadata
+    }
+
+    /// Returns the index of the column with given name, matched as described in
+    /// [`ColumnNameIndex::get`](crate::ColumnNameIndex::get).
+    /// The lookup map is built once and shared by all pages.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.current_page.metadata.column_index(name)
+    }
+
+    /// Returns the errors of the columns of the last returned row which failed to decode.\
+    /// Always empty unless the session was built with
+    /// [`ColumnDecodePolicy::Lenient`](crate::frame::response::result::ColumnDecodePolicy::Lenient).
+    pub fn last_row_decode_errors(&self) -> &[ColumnDecodeError] {
+        match self.current_row_idx.checked_sub(1) {
+            Some(row) => ColumnDecodeError::of_row(&self.current_page.decode_errors, row),
+            None => &[],
+        }
+    }
+
+    /// Returns the number of rows returned by the iterator so far.
+    pub fn rows_processed(&self) -> usize {
+        self.rows_processed
+    }
+
+    /// Returns the number of bytes of the pages received so far,
+    /// counted as the serialized size of their rows.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
+
+    /// Tells whether the stream ended before the last row of the result, because it reached
+    /// a limit set with [`IterConfig::max_rows`] or [`IterConfig::max_bytes`].
+    /// `None` if the stream didn't end yet, or ended after the last row.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use scylla::Session;
+    /// # use std::error::Error;
+    /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+    /// use futures::stream::StreamExt;
+    /// use scylla::transport::iterator::IterConfig;
+    ///
+    /// let config = IterConfig::new().max_rows(1000).max_bytes(1 << 20);
+    /// let mut rows_stream = session
+    ///     .query_iter_with_config("SELECT a, b FROM ks.t", &[], config)
+    ///     .await?;
+    ///
+    /// while let Some(row) = rows_stream.next().await {
+    ///     let row = row?;
+    /// }
+    /// if let Some(truncation) = rows_stream.truncation() {
+    ///     println!("Result truncated after {} rows", truncation.rows);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn truncation(&self) -> Option<&TruncationInfo> {
+        self.truncation.as_ref()
+    }
+
+    /// Converts this iterator into a stream of Arrow record batches, one for each page
+    /// of rows, see the [`arrow`](crate::transport::arrow) module for the mapping of the types.
+    /// Rows of the current page which weren't returned yet make the first batch.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use scylla::Session;
+    /// # use std::error::Error;
+    /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+    /// use futures::stream::StreamExt;
+    ///
+    /// let mut batches = session
+    ///     .query_iter("SELECT a, b FROM ks.t", &[])
+    ///     .await?
+    ///     .into_record_batches();
+    ///
+    /// while let Some(batch) = batches.next().await {
+    ///     let batch = batch?;
+    ///     println!("Received {} rows", batch.num_rows());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "arrow")]
+    pub fn into_record_batches(self) -> crate::transport::arrow::RecordBatchStream {
+        crate::transport::arrow::RecordBatchStream::new(self)
+    }
+
+    /// Writes all remaining rows as CSV, page by page, with a header with the names of the columns,
+    /// see the [`csv`](crate::transport::csv) module for the rendering of the values.
+    /// The writer is flushed after each page.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use scylla::Session;
+    /// # use std::error::Error;
+    /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+    /// use scylla::transport::csv::CsvOptions;
+    ///
+    /// let mut file = std::fs::File::create("dump.csv")?;
+    /// session
+    ///     .query_iter("SELECT a, b FROM ks.big_table", &[])
+    ///     .await?
+    ///     .write_csv(&mut file, CsvOptions::default())
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "csv")]
+    pub async fn write_csv(
+        mut self,
+        writer: &mut impl std::io::Write,
+        options: crate::transport::csv::CsvOptions,
+    ) -> Result<(), crate::transport::csv::CsvExportError> {
+        use crate::transport::csv::{write_header, write_rows};
+
+        let mut csv_writer = options.writer(writer);
+        write_header(&mut csv_writer, self.get_column_specs(), &options)?;
+        while let Some(rows) = futures::future::poll_fn(|cx| self.poll_next_rows(cx)).await {
+            write_rows(&mut csv_writer, &rows?, &options)?;
+            csv_writer.flush()?;
+        }
+        csv_writer.flush()?;
+        Ok(())
+    }
+
     fn is_current_page_exhausted(&self) -> bool {
         self.current_row_idx >= self.current_page.rows.len()
     }
+
+    /// Receives the next page if all rows of the current one were returned,
+    /// and calls the page observer for it.
+    fn poll_current_page(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<(), QueryError>>> {
+        if self.truncation.is_some() {
+            return Poll::Ready(None);
+        }
+        if let Some(reason) = self.reached_limit() {
+            self.truncation = Some(TruncationInfo {
+                reason,
+                rows: self.rows_processed as u64,
+                bytes: self.bytes_received,
+            });
+            self.stopped = true;
+            // The worker stops once it fails to send the next page
+            self.page_receiver.close();
+            return Poll::Ready(None);
+        }
+
+        if self.is_current_page_exhausted() {
+            if self.stopped {
+                return Poll::Ready(None);
+            }
+            match Pin::new(&mut self.page_receiver).poll_recv(cx) {
+                Poll::Ready(Some(Ok(received_page))) => {
+                    self.current_page_paging_state = self.current_page.metadata.paging_state.take();
+                    self.bytes_received += received_page.rows.serialized_size as u64;
+                    let mut rows = received_page.rows;
+                    rows.metadata
+                        .share_column_name_index(&self.current_page.metadata);
+                    self.current_page = rows;
+                    self.current_page_coordinator = received_page.coordinator;
+                    self.current_page_tracing_id = received_page.tracing_id;
+                    self.current_page_observed = false;
+                    self.current_row_idx = 0;
+                    self.pages_received += 1;
+
+                    if let Some(tracing_id) = received_page.tracing_id {
+                        self.tracing_ids.push(tracing_id);
+                    }
+                    self.warnings.extend(received_page.warnings);
+                }
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        self.observe_current_page();
+        Poll::Ready(Some(Ok(())))
+    }
+
+    /// Returns the limit which ends the stream before the next row, if any rows are left.
+    fn reached_limit(&self) -> Option<TruncationReason> {
+        let has_more_pages = !self.stopped && self.current_page.metadata.paging_state.is_some();
+        let has_more_rows = !self.is_current_page_exhausted() || has_more_pages;
+
+        if matches!(self.max_rows, Some(max_rows) if self.rows_processed as u64 >= max_rows)
+            && has_more_rows
+        {
+            return Some(TruncationReason::MaxRows);
+        }
+        if matches!(self.max_bytes, Some(max_bytes) if self.bytes_received >= max_bytes)
+            && self.is_current_page_exhausted()
+            && has_more_pages
+        {
+            return Some(TruncationReason::MaxBytes);
+        }
+        None
+    }
+
+    /// Takes all rows of the current page which weren't returned yet,
+    /// receiving pages until there is a non-empty one.
+    #[cfg(any(feature = "arrow", feature = "csv"))]
+    pub(crate) fn poll_next_rows(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Vec<Row>, QueryError>>> {
+        loop {
+            match self.poll_current_page(cx) {
+                Poll::Ready(Some(Ok(()))) => {}
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+
+            let end = match self.max_rows {
+                Some(max_rows) => {
+                    let rows_left = max_rows.saturating_sub(self.rows_processed as u64);
+                    let page_rows_left = self.current_page.rows.len() - self.current_row_idx;
+                    self.current_row_idx + page_rows_left.min(rows_left as usize)
+                }
+                None => self.current_page.rows.len(),
+            };
+            let rows: Vec<Row> = self
+                .current_page
+                .rows
+                .drain(self.current_row_idx..end)
+                .collect();
+            if !rows.is_empty() {
+                self.rows_processed += rows.len();
+                return Poll::Ready(Some(Ok(rows)));
+            }
+        }
+    }
+
+    /// Describes where in the stream the error occurred. The failed row is either
+    /// the last one returned, which couldn't be parsed, or the next one,
+    /// whose page couldn't be fetched.
+    fn error_context(&self, error: NextRowError) -> RowStreamError {
+        match error {
+            NextRowError::QueryError(_) => RowStreamError {
+                row_index: self.rows_processed,
+                page: self.pages_received,
+                paging_state: self.current_page.metadata.paging_state.clone(),
+                error,
+            },
+            NextRowError::FromRowError(_) => RowStreamError {
+                row_index: self.rows_processed - 1,
+                page: self.pages_received - 1,
+                paging_state: self.current_page_paging_state.clone(),
+                error,
+            },
+        }
+    }
 }
 
 // A separate module is used here so that the parent module cannot construct
@@ -472,6 +1111,7 @@ mod checked_channel_sender {
         pub(crate) async fn send_empty_page(
             &self,
             tracing_id: Option<Uuid>,
+            warnings: Vec<String>,
         ) -> (
             SendAttemptedProof<ResultPage>,
             Result<(), mpsc::error::SendError<ResultPage>>,
@@ -481,9 +1121,13 @@ mod checked_channel_sender {
                     metadata: Default::default(),
                     rows_count: 0,
                     rows: Vec::new(),
+                    decode_errors: Vec::new(),
                     serialized_size: 0,
+                    raw_rows: None,
                 },
                 tracing_id,
+                warnings,
+                coordinator: None,
             };
             self.send(Ok(empty_page)).await
         }
@@ -492,6 +1136,45 @@ mod checked_channel_sender {
 
 use checked_channel_sender::{ProvingSender, SendAttemptedProof};
 
+// Interceptors of the session, called by the worker with every attempt
+// and with the outcome of the whole request. `on_request` was called by the session,
+// the modifications it made are already applied to the statement the context is built from.
+struct IteratorInterceptors<'a> {
+    interceptors: Vec<Arc<dyn RequestInterceptor>>,
+    context: RequestContext<'a>,
+}
+
+impl<'a> IteratorInterceptors<'a> {
+    // Returns `None` if there are no interceptors
+    fn new(
+        interceptors: Vec<Arc<dyn RequestInterceptor>>,
+        statement: InterceptedStatement<'a>,
+        values_count: Option<usize>,
+        consistency: Consistency,
+        serial_consistency: Option<SerialConsistency>,
+    ) -> Option<Self> {
+        if interceptors.is_empty() {
+            return None;
+        }
+        Some(Self {
+            interceptors,
+            context: RequestContext::new(statement, values_count, consistency, serial_consistency),
+        })
+    }
+
+    fn on_attempt(&self, node: NodeRef<'_>) {
+        for interceptor in self.interceptors.iter() {
+            interceptor.on_attempt(&self.context, node);
+        }
+    }
+
+    fn on_response(&self, result: &Result<QueryResult, ExecutionError>) {
+        for interceptor in self.interceptors.iter() {
+            interceptor.on_response(&self.context, result);
+        }
+    }
+}
+
 type PageSendAttemptedProof = SendAttemptedProof<Result<ReceivedPage, QueryError>>;
 
 // RowIteratorWorker works in the background to fetch pages
@@ -513,6 +1196,7 @@ struct RowIteratorWorker<'a, ConnFunc, QueryFunc, SpanCreatorFunc> {
     retry_session: Box<dyn RetrySession>,
     execution_profile: Arc<ExecutionProfileInner>,
     metrics: Arc<Metrics>,
+    label_metrics: Option<Arc<LabelMetrics>>,
 
     paging_state: Option<Bytes>,
 
@@ -520,8 +1204,24 @@ struct RowIteratorWorker<'a, ConnFunc, QueryFunc, SpanCreatorFunc> {
     current_query_id: Option<history::QueryId>,
     current_attempt_id: Option<history::AttemptId>,
 
+    // Slow pages are reported with the time elapsed since the page was requested,
+    // including retries
+    statement: &'a str,
+    slow_query_log: Option<SlowQueryLog>,
+    request_span_level: Level,
+    page_started_at: Instant,
+    page_stats: RequestStats,
+    // Attempts to fetch the current page, passed to the retry policy
+    page_attempts: usize,
+    label: Option<Arc<str>>,
+
+    // `None` if the session has no interceptors
+    interceptors: Option<IteratorInterceptors<'a>>,
+
     parent_span: tracing::Span,
     span_creator: SpanCreatorFunc,
+    // Measures the delays between retries
+    runtime: Arc<dyn Runtime>,
 }
 
 impl<ConnFunc, ConnFut, QueryFunc, QueryFut, SpanCreator>
@@ -545,10 +1245,11 @@ where
         let mut current_consistency: Consistency = self.query_consistency;
 
         self.log_query_start();
+        self.start_page_measurement();
 
         'nodes_in_plan: for node in query_plan {
-            let span =
-                trace_span!(parent: &self.parent_span, "Executing query", node = %node.address);
+            let mut span =
+                new_attempt_span(self.request_span_level, &self.parent_span, node, false);
             // For each node in the plan choose a connection to use
             // This connection will be reused for same node retries to preserve paging cache on the shard
             let connection: Arc<Connection> = match (self.choose_connection)(node.clone())
@@ -576,9 +1277,11 @@ where
                     .instrument(span.clone())
                     .await;
 
+                span.record("outcome", outcome_str(queries_result.is_ok()));
                 last_error = match queries_result {
                     Ok(proof) => {
                         trace!(parent: &span, "Query succeeded");
+                        self.intercept_response(None);
                         // query_pages returned Ok, so we are guaranteed
                         // that it attempted to send at least one page
                         // through self.sender and we can safely return now.
@@ -595,11 +1298,15 @@ where
                 };
 
                 // Use retry policy to decide what to do next
-                let query_info = QueryInfo {
-                    error: &last_error,
-                    is_idempotent: self.query_is_idempotent,
-                    consistency: self.query_consistency,
-                };
+                let mut query_info = QueryInfo::new(
+                    &last_error,
+                    self.query_is_idempotent,
+                    self.query_consistency,
+                );
+                query_info.attempt_consistency = current_consistency;
+                query_info.attempt_number = self.page_attempts;
+                query_info.elapsed_since_first_attempt = self.page_started_at.elapsed();
+                query_info.node = Some(node);
 
                 let retry_decision = self.retry_session.decide_should_retry(query_info);
                 trace!(
@@ -610,24 +1317,71 @@ where
                 match retry_decision {
                     RetryDecision::RetrySameNode(cl) => {
                         self.metrics.inc_retries_num();
+                        self.page_stats.inc_retries();
+                        if let Some(label_metrics) = &self.label_metrics {
+                            label_metrics.inc_retries();
+                        }
+                        #[cfg(feature = "metrics-exporter")]
+                        self.metrics.notify(|observer| {
+                            observer.on_retry(&NodeLabels::new(
+                                node,
+                                connection.get_shard_info().as_ref(),
+                            ))
+                        });
                         current_consistency = cl.unwrap_or(current_consistency);
+                        span = new_attempt_span(
+                            self.request_span_level,
+                            &self.parent_span,
+                            node,
+                            false,
+                        );
                         continue 'same_node_retries;
                     }
                     RetryDecision::RetryNextNode(cl) => {
                         self.metrics.inc_retries_num();
+                        self.page_stats.inc_retries();
+                        if let Some(label_metrics) = &self.label_metrics {
+                            label_metrics.inc_retries();
+                        }
+                        #[cfg(feature = "metrics-exporter")]
+                        self.metrics.notify(|observer| {
+                            observer.on_retry(&NodeLabels::new(
+                                node,
+                                connection.get_shard_info().as_ref(),
+                            ))
+                        });
                         current_consistency = cl.unwrap_or(current_consistency);
                         continue 'nodes_in_plan;
                     }
+                    RetryDecision::RetryNextNodeAfter(cl, delay) => {
+                        self.metrics.inc_retries_num();
+                        self.page_stats.inc_retries();
+                        if let Some(label_metrics) = &self.label_metrics {
+                            label_metrics.inc_retries();
+                        }
+                        #[cfg(feature = "metrics-exporter")]
+                        self.metrics.notify(|observer| {
+                            observer.on_retry(&NodeLabels::new(
+                                node,
+                                connection.get_shard_info().as_ref(),
+                            ))
+                        });
+                        current_consistency = cl.unwrap_or(current_consistency);
+                        self.runtime.sleep(delay).await;
+                        continue 'nodes_in_plan;
+                    }
                     RetryDecision::DontRetry => break 'nodes_in_plan,
                     RetryDecision::IgnoreWriteError => {
                         warn!("Ignoring error during fetching pages; stopping fetching.");
+                        self.report_slow_page(None);
+                        self.intercept_response(None);
                         // If we are here then, most likely, we didn't send
                         // anything through the self.sender channel.
                         // Although we are in an awkward situation (_iter
                         // interface isn't meant for sending writes),
                         // we must attempt to send something because
                         // the iterator expects it.
-                        let (proof, _) = self.sender.send_empty_page(None).await;
+                        let (proof, _) = self.sender.send_empty_page(None, Vec::new()).await;
                         return proof;
                     }
                 };
@@ -636,6 +1390,8 @@ where
 
         // Send last_error to RowIterator - query failed fully
         self.log_query_error(&last_error);
+        self.report_slow_page(Some(&last_error));
+        self.intercept_response(Some(&last_error));
         let (proof, _) = self.sender.send(Err(last_error)).await;
         proof
     }
@@ -653,6 +1409,7 @@ where
     ) -> Result<PageSendAttemptedProof, QueryError> {
         loop {
             let request_span = (self.span_creator)();
+            request_span.record_consistency(consistency);
             match self
                 .query_one_page(connection, consistency, node, &request_span)
                 .instrument(request_span.span().clone())
@@ -672,6 +1429,10 @@ where
         request_span: &RequestSpan,
     ) -> Result<ControlFlow<PageSendAttemptedProof, ()>, QueryError> {
         self.metrics.inc_total_paged_queries();
+        self.page_attempts += 1;
+        if let Some(label_metrics) = &self.label_metrics {
+            label_metrics.inc_requests();
+        }
         let query_start = std::time::Instant::now();
 
         trace!(
@@ -679,6 +1440,9 @@ where
             "Sending"
         );
         self.log_attempt_start(connection.get_connect_address());
+        if let Some(interceptors) = &self.interceptors {
+            interceptors.on_attempt(node);
+        }
 
         let query_response =
             (self.page_query)(connection.clone(), consistency, self.paging_state.clone())
@@ -688,25 +1452,65 @@ where
         let elapsed = query_start.elapsed();
 
         request_span.record_shard_id(connection);
+        request_span.inc_attempts();
+        request_span.record_coordinator(node.address.into_inner());
+        request_span.record_outcome(matches!(
+            query_response,
+            Ok(NonErrorQueryResponse {
+                response: NonErrorResponse::Result(_),
+                ..
+            })
+        ));
+        self.page_stats
+            .record_coordinator(node.address.into_inner());
+
+        #[cfg(feature = "metrics-exporter")]
+        self.metrics.notify(|observer| {
+            observer.on_request(&RequestEvent {
+                kind: request_span.statement_kind(),
+                node: NodeLabels::new(node, connection.get_shard_info().as_ref()),
+                outcome: match query_response {
+                    Ok(NonErrorQueryResponse {
+                        response: NonErrorResponse::Result(_),
+                        ..
+                    }) => RequestOutcome::Success,
+                    _ => RequestOutcome::Error,
+                },
+                latency: elapsed,
+                paged: true,
+            })
+        });
 
         match query_response {
             Ok(NonErrorQueryResponse {
-                response: NonErrorResponse::Result(result::Result::Rows(mut rows)),
+                response: NonErrorResponse::Result(result::Result::Rows(rows)),
                 tracing_id,
-                ..
+                warnings,
             }) => {
                 let _ = self.metrics.log_query_latency(elapsed.as_millis() as u64);
+                self.metrics
+                    .log_node_latency(node, connection.get_shard_info().as_ref(), elapsed);
+                if let Some(label_metrics) = &self.label_metrics {
+                    label_metrics.inc_pages();
+                    label_metrics.record_latency(elapsed);
+                }
                 self.log_attempt_success();
                 self.log_query_success();
+                self.report_slow_page(None);
                 self.execution_profile
                     .load_balancing_policy
                     .on_query_success(&self.statement_info, elapsed, node);
 
-                self.paging_state = rows.metadata.paging_state.take();
+                self.paging_state = rows.metadata.paging_state.clone();
 
                 request_span.record_rows_fields(&rows);
 
-                let received_page = ReceivedPage { rows, tracing_id };
+                let received_page = ReceivedPage {
+                    rows,
+                    tracing_id,
+                    warnings,
+                    coordinator: Some(node.clone()),
+                };
 
                 // Send next page to RowIterator
                 let (proof, res) = self.sender.send(Ok(received_page)).await;
@@ -723,11 +1527,15 @@ where
                 // Query succeeded, reset retry policy for future retries
                 self.retry_session.reset();
                 self.log_query_start();
+                self.start_page_measurement();
 
                 Ok(ControlFlow::Continue(()))
             }
             Err(err) => {
                 self.metrics.inc_failed_paged_queries();
+                if let Some(label_metrics) = &self.label_metrics {
+                    label_metrics.inc_errors();
+                }
                 self.execution_profile
                     .load_balancing_policy
                     .on_query_failure(&self.statement_info, elapsed, node, &err);
@@ -736,17 +1544,22 @@ where
             Ok(NonErrorQueryResponse {
                 response: NonErrorResponse::Result(_),
                 tracing_id,
-                ..
+                warnings,
             }) => {
                 // We have most probably sent a modification statement (e.g. INSERT or UPDATE),
                 // so let's return an empty iterator as suggested in #631.
 
+                self.report_slow_page(None);
+
                 // We must attempt to send something because the iterator expects it.
-                let (proof, _) = self.sender.send_empty_page(tracing_id).await;
+                let (proof, _) = self.sender.send_empty_page(tracing_id, warnings).await;
                 Ok(ControlFlow::Break(proof))
             }
             Ok(_) => {
                 self.metrics.inc_failed_paged_queries();
+                if let Some(label_metrics) = &self.label_metrics {
+                    label_metrics.inc_errors();
+                }
                 let err = QueryError::ProtocolError("Unexpected response to next page query");
                 self.execution_profile
                     .load_balancing_policy
@@ -756,6 +1569,37 @@ where
         }
     }
 
+    fn start_page_measurement(&mut self) {
+        self.page_started_at = Instant::now();
+        self.page_stats = RequestStats::with_label(self.label.clone());
+        self.page_attempts = 0;
+    }
+
+    // Tells the interceptors that the request finished, successfully if `error` is `None`.
+    // The rows have been sent to the iterator, so a successful result has none.
+    fn intercept_response(&self, error: Option<&QueryError>) {
+        if let Some(interceptors) = &self.interceptors {
+            interceptors.on_response(&match error {
+                None => Ok(QueryResult::default()),
+                Some(error) => Err(self
+                    .page_stats
+                    .execution_error(error.clone(), self.query_is_idempotent)),
+            });
+        }
+    }
+
+    fn report_slow_page(&self, error: Option<&QueryError>) {
+        if let Some(slow_query_log) = &self.slow_query_log {
+            slow_query_log.report(
+                self.page_started_at.elapsed(),
+                || Cow::Borrowed(self.statement),
+                &self.page_stats,
+                true,
+                error,
+            );
+        }
+    }
+
     fn log_query_start(&mut self) {
         let history_listener: &dyn HistoryListener = match &self.history_listener {
             Some(hl) => &**hl,
@@ -866,13 +1710,15 @@ where
             let result = (self.fetcher)(paging_state).await?;
             let response = result.into_non_error_query_response()?;
             match response.response {
-                NonErrorResponse::Result(result::Result::Rows(mut rows)) => {
-                    paging_state = rows.metadata.paging_state.take();
+                NonErrorResponse::Result(result::Result::Rows(rows)) => {
+                    paging_state = rows.metadata.paging_state.clone();
                     let (proof, send_result) = self
                         .sender
                         .send(Ok(ReceivedPage {
                             rows,
                             tracing_id: response.tracing_id,
+                            warnings: response.warnings,
+                            coordinator: None,
                         }))
                         .await;
                     if paging_state.is_none() || send_result.is_err() {
@@ -884,7 +1730,10 @@ where
                     // so let's return an empty iterator as suggested in #631.
 
                     // We must attempt to send something because the iterator expects it.
-                    let (proof, _) = self.sender.send_empty_page(response.tracing_id).await;
+                    let (proof, _) = self
+                        .sender
+                        .send_empty_page(response.tracing_id, response.warnings)
+                        .await;
                     return Ok(proof);
                 }
                 _ => {
@@ -911,28 +1760,129 @@ impl<RowT> TypedRowIterator<RowT> {
         self.row_iterator.get_tracing_ids()
     }
 
+    /// Returns warnings attached by the database to all pages received so far
+    pub fn get_warnings(&self) -> &[String] {
+        self.row_iterator.get_warnings()
+    }
+
     /// Returns specification of row columns
     pub fn get_column_specs(&self) -> &[ColumnSpec] {
         self.row_iterator.get_column_specs()
     }
+
+    /// Returns metadata of the current page.
+    pub fn metadata(&self) -> &ResultMetadata {
+        self.row_iterator.metadata()
+    }
+
+    /// Returns the index of the column with given name, see [`RowIterator::column_index`].
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.row_iterator.column_index(name)
+    }
+
+    /// Sets a function called for each received page, see [`RowIterator::with_page_observer`].
+    pub fn with_page_observer(
+        mut self,
+        observer: impl FnMut(PageInfo) -> ControlFlow<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.row_iterator = self.row_iterator.with_page_observer(observer);
+        self
+    }
+
+    /// Returns the number of rows taken from the stream so far,
+    /// including a row which failed to be parsed.
+    pub fn rows_processed(&self) -> usize {
+        self.row_iterator.rows_processed()
+    }
+
+    /// Returns the number of bytes of the pages received so far, see [`RowIterator::bytes_received`].
+    pub fn bytes_received(&self) -> u64 {
+        self.row_iterator.bytes_received()
+    }
+
+    /// Tells whether the stream was truncated by its limits, see [`RowIterator::truncation`].
+    pub fn truncation(&self) -> Option<&TruncationInfo> {
+        self.row_iterator.truncation()
+    }
+}
+
+impl<RowT: FromRow> TypedRowIterator<RowT> {
+    /// Collects all the remaining rows. Unlike [`TryStreamExt::try_collect`](futures::TryStreamExt::try_collect),
+    /// on failure it returns the rows collected before it, so that a long read
+    /// doesn't have to be repeated from the start.
+    pub async fn try_collect_with_context(mut self) -> Result<Vec<RowT>, CollectRowsError<RowT>> {
+        let mut rows = Vec::new();
+        while let Some(row) = self.next().await {
+            match row {
+                Ok(row) => rows.push(row),
+                Err(error) => return Err(CollectRowsError { rows, error }),
+            }
+        }
+        Ok(rows)
+    }
 }
 
 /// Couldn't get next typed row from the iterator
 #[derive(Error, Debug, Clone)]
 pub enum NextRowError {
-    /// Query to fetch next page has failed
+    /// Query to fetch next page has failed, e.g. because of a timeout or a lost connection.
+    /// Reading can be resumed from the page which failed, see [`RowStreamError::paging_state`].
     #[error(transparent)]
     QueryError(#[from] QueryError),
 
-    /// Parsing values in row as given types failed
+    /// Parsing values in row as given types failed. The row doesn't match the type,
+    /// so reading it again would fail too.
     #[error(transparent)]
-    FromRowError(#[from] FromRowError),
+    FromRowError(#[from] RowTypeError),
+}
+
+impl NextRowError {
+    /// Whether fetching a page of rows failed, in which case reading can be resumed.
+    pub fn is_transport_error(&self) -> bool {
+        matches!(self, NextRowError::QueryError(_))
+    }
+
+    /// Whether a received row couldn't be parsed, which is a problem with the data.
+    pub fn is_deserialization_error(&self) -> bool {
+        matches!(self, NextRowError::FromRowError(_))
+    }
+}
+
+/// Error returned by [`TypedRowIterator`], telling which row of the stream failed.
+#[derive(Error, Debug, Clone)]
+#[error("Reading row {row_index} (page {page}) failed: {error}")]
+pub struct RowStreamError {
+    /// Zero-based index of the failed row in the stream.
+    pub row_index: usize,
+    /// Zero-based ordinal of the page containing the failed row,
+    /// or of the page which failed to be fetched.
+    pub page: usize,
+    /// Paging state with which the page was requested, `None` for the first page.
+    /// After a failure to fetch the page, reading can be resumed by passing it to
+    /// [`Session::query_single_page`](crate::Session::query_single_page) or
+    /// [`Session::execute_single_page`](crate::Session::execute_single_page).
+    pub paging_state: Option<Bytes>,
+    /// The error which occurred.
+    #[source]
+    pub error: NextRowError,
+}
+
+/// Error returned by [`TypedRowIterator::try_collect_with_context`], along with the rows
+/// collected before it occurred.
+#[derive(Error, Debug)]
+#[error("{error}")]
+pub struct CollectRowsError<RowT> {
+    /// Rows read successfully before the failed one.
+    pub rows: Vec<RowT>,
+    /// The error which occurred.
+    #[source]
+    pub error: RowStreamError,
 }
 
 /// Fetching pages is asynchronous so `TypedRowIterator` does not implement the `Iterator` trait.\
 /// Instead it uses the asynchronous `Stream` trait
 impl<RowT: FromRow> Stream for TypedRowIterator<RowT> {
-    type Item = Result<RowT, NextRowError>;
+    type Item = Result<RowT, RowStreamError>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let mut s = self.as_mut();
@@ -944,8 +1894,19 @@ impl<RowT: FromRow> Stream for TypedRowIterator<RowT> {
             };
 
         let next_ready: Option<Self::Item> = match next_elem {
-            Some(Ok(next_row)) => Some(RowT::from_row(next_row).map_err(|e| e.into())),
-            Some(Err(e)) => Some(Err(e.into())),
+            Some(Ok(next_row)) => {
+                // Columns which failed to decode are nulls, so the row can't be parsed
+                let parsed = match s.row_iterator.last_row_decode_errors().first() {
+                    Some(err) => Err(FromRowError::UndecodableColumn(Box::new(err.clone()))),
+                    None => RowT::from_row(next_row),
+                };
+                Some(parsed.map_err(|e| {
+                    let error =
+                        RowTypeError::new::<RowT>(e, s.row_iterator.get_column_specs().to_vec());
+                    s.row_iterator.error_context(error.into())
+                }))
+            }
+            Some(Err(e)) => Some(Err(s.row_iterator.error_context(e.into()))),
             None => None,
         };
 
@@ -955,3 +1916,363 @@ impl<RowT: FromRow> Stream for TypedRowIterator<RowT> {
 
 // TypedRowIterator can be moved freely for any RowT so it's Unpin
 impl<RowT> Unpin for TypedRowIterator<RowT> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{IterConfig, ProvingSender, ReceivedPage, RowIterator, TruncationReason};
+    use crate::frame::response::result;
+    use crate::transport::background_tasks::BackgroundTasks;
+    use crate::transport::errors::QueryError;
+    use bytes::Bytes;
+    use futures::StreamExt;
+    use scylla_cql::frame::types;
+    use std::ops::ControlFlow;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::mpsc;
+
+    // A page of rows of a single int column, parsed from a frame.
+    fn int_page(values: &[Option<i32>], paging_state: Option<&[u8]>) -> ReceivedPage {
+        let mut body = Vec::new();
+        types::write_int(0x0002, &mut body); // Rows
+        match paging_state {
+            Some(_) => types::write_int(0x0001 | 0x0002, &mut body),
+            None => types::write_int(0x0001, &mut body),
+        }
+        types::write_int(1, &mut body);
+        if let Some(paging_state) = paging_state {
+            types::write_bytes(paging_state, &mut body).unwrap();
+        }
+        types::write_string("ks", &mut body).unwrap();
+        types::write_string("t", &mut body).unwrap();
+        types::write_string("v", &mut body).unwrap();
+        types::write_short(0x0009, &mut body); // Int
+        types::write_int(values.len() as i32, &mut body);
+        for value in values {
+            match value {
+                Some(value) => types::write_bytes(&value.to_be_bytes(), &mut body).unwrap(),
+                None => types::write_int(-1, &mut body),
+            }
+        }
+
+        let rows = match result::deserialize(&mut &body[..]).unwrap() {
+            result::Result::Rows(rows) => rows,
+            _ => panic!("Expected rows"),
+        };
+        ReceivedPage {
+            rows,
+            tracing_id: None,
+            warnings: Vec::new(),
+            coordinator: None,
+        }
+    }
+
+    // Streams two pages, the second one with a null which can't be parsed as `i32`,
+    // after which fetching the third page fails.
+    async fn failing_iterator() -> RowIterator {
+        let pages = vec![
+            Ok(int_page(&[Some(0), Some(1)], Some(&[1]))),
+            Ok(int_page(&[Some(2), None, Some(4)], Some(&[2]))),
+            Err(QueryError::RequestTimeout("Page timed out".to_string())),
+        ];
+        let (sender, receiver) = mpsc::channel(1);
+        let sender: ProvingSender<_> = sender.into();
+        let worker = async move {
+            let mut proof = None;
+            for page in pages {
+                proof = Some(sender.send(page).await.0);
+            }
+            proof.unwrap()
+        };
+        RowIterator::new_from_worker_future(worker, receiver, &BackgroundTasks::default())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn typed_row_errors_tell_where_they_occurred() {
+        let mut rows = failing_iterator().await.into_typed::<(i32,)>();
+        for expected in 0..3 {
+            assert_eq!(rows.next().await.unwrap().unwrap(), (expected,));
+        }
+
+        let bad_row = rows.next().await.unwrap().unwrap_err();
+        assert!(bad_row.error.is_deserialization_error());
+        assert_eq!(bad_row.row_index, 3);
+        assert_eq!(bad_row.page, 1);
+        assert_eq!(bad_row.paging_state, Some(Bytes::from_static(&[1])));
+        assert_eq!(rows.rows_processed(), 4);
+
+        // A row which can't be parsed doesn't end the stream
+        assert_eq!(rows.next().await.unwrap().unwrap(), (4,));
+
+        let failed_page = rows.next().await.unwrap().unwrap_err();
+        assert!(failed_page.error.is_transport_error());
+        assert_eq!(failed_page.row_index, 5);
+        assert_eq!(failed_page.page, 2);
+        assert_eq!(failed_page.paging_state, Some(Bytes::from_static(&[2])));
+        assert!(rows.next().await.is_none());
+    }
+
+    // Streams pages of two rows each, numbered from 0 to 9 across the pages,
+    // starting from the page encoded in the paging state.
+    async fn resumable_iterator(paging_state: Option<Bytes>) -> RowIterator {
+        let first_page = paging_state.map_or(0, |state| state[0]);
+        let pages: Vec<_> = (first_page..5)
+            .map(|page| {
+                let values = [Some(page as i32 * 2), Some(page as i32 * 2 + 1)];
+                let next_page = [page + 1];
+                Ok(int_page(&values, (page < 4).then_some(&next_page[..])))
+            })
+            .collect();
+        let (sender, receiver) = mpsc::channel(1);
+        let sender: ProvingSender<_> = sender.into();
+        let worker = async move {
+            let mut proof = None;
+            for page in pages {
+                let (page_proof, result) = sender.send(page).await;
+                proof = Some(page_proof);
+                if result.is_err() {
+                    break;
+                }
+            }
+            proof.unwrap()
+        };
+        RowIterator::new_from_worker_future(worker, receiver, &BackgroundTasks::default())
+            .await
+            .unwrap()
+    }
+
+    // Waits until the worker holding the other reference to `alive` is dropped.
+    async fn worker_dropped(alive: &Arc<()>) {
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while Arc::strong_count(alive) > 1 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("the worker task should be cancelled");
+    }
+
+    #[tokio::test]
+    async fn dropping_iterator_cancels_worker() {
+        let alive = Arc::new(());
+        let worker_alive = alive.clone();
+        let (sender, receiver) = mpsc::channel(1);
+        let sender: ProvingSender<_> = sender.into();
+        let worker = async move {
+            let _alive = worker_alive;
+            let (proof, _) = sender.send(Ok(int_page(&[Some(0)], Some(&[1])))).await;
+            // Fetching the next page never completes
+            futures::future::pending::<()>().await;
+            proof
+        };
+        let iterator =
+            RowIterator::new_from_worker_future(worker, receiver, &BackgroundTasks::default())
+                .await
+                .unwrap();
+        assert_eq!(Arc::strong_count(&alive), 2);
+
+        drop(iterator);
+        worker_dropped(&alive).await;
+    }
+
+    #[tokio::test]
+    async fn dropping_iterator_creation_cancels_worker() {
+        let alive = Arc::new(());
+        let worker_alive = alive.clone();
+        let (sender, receiver) = mpsc::channel(1);
+        let sender: ProvingSender<_> = sender.into();
+        let worker = async move {
+            let _alive = worker_alive;
+            // Fetching the first page never completes
+            futures::future::pending::<()>().await;
+            sender.send(Ok(int_page(&[], None))).await.0
+        };
+        let background_tasks = BackgroundTasks::default();
+        let creation = tokio::time::timeout(
+            std::time::Duration::from_millis(50),
+            RowIterator::new_from_worker_future(worker, receiver, &background_tasks),
+        );
+        assert!(creation.await.is_err());
+        worker_dropped(&alive).await;
+    }
+
+    #[tokio::test]
+    async fn page_observer_checkpoints_and_resumes() {
+        let observed = Arc::new(Mutex::new(Vec::new()));
+        let observed_ref = observed.clone();
+        let checkpoint = Arc::new(Mutex::new(None));
+        let checkpoint_ref = checkpoint.clone();
+
+        let rows = resumable_iterator(None)
+            .await
+            .into_typed::<(i32,)>()
+            .with_page_observer(move |page| {
+                observed_ref.lock().unwrap().push((page.page, page.rows));
+                if page.page == 2 {
+                    *checkpoint_ref.lock().unwrap() = page.paging_state;
+                    return ControlFlow::Break(());
+                }
+                ControlFlow::Continue(())
+            });
+        let rows: Vec<_> = rows.map(|row| row.unwrap().0).collect().await;
+
+        // Rows of the page on which the observer stopped are still returned
+        assert_eq!(rows, (0..6).collect::<Vec<_>>());
+        assert_eq!(*observed.lock().unwrap(), [(0, 2), (1, 2), (2, 2)]);
+
+        let checkpoint = checkpoint.lock().unwrap().take();
+        assert_eq!(checkpoint, Some(Bytes::from_static(&[3])));
+
+        let pages = Arc::new(Mutex::new(Vec::new()));
+        let pages_ref = pages.clone();
+        let resumed: Vec<_> = resumable_iterator(checkpoint)
+            .await
+            .with_page_observer(move |page| {
+                pages_ref
+                    .lock()
+                    .unwrap()
+                    .push((page.page, page.paging_state));
+                ControlFlow::Continue(())
+            })
+            .into_typed::<(i32,)>()
+            .map(|row| row.unwrap().0)
+            .collect()
+            .await;
+        assert_eq!(resumed, (6..10).collect::<Vec<_>>());
+        assert_eq!(
+            *pages.lock().unwrap(),
+            [(0, Some(Bytes::from_static(&[4]))), (1, None)]
+        );
+    }
+
+    #[tokio::test]
+    async fn page_observer_is_called_before_rows_are_returned() {
+        let observed = Arc::new(Mutex::new(0));
+        let observed_ref = observed.clone();
+        let mut rows = resumable_iterator(None).await.with_page_observer(move |_| {
+            *observed_ref.lock().unwrap() += 1;
+            ControlFlow::Continue(())
+        });
+
+        for (row, pages) in [(0, 1), (1, 1), (2, 2), (3, 2), (4, 3)] {
+            rows.next().await.unwrap().unwrap();
+            assert_eq!(*observed.lock().unwrap(), pages, "row {}", row);
+        }
+    }
+
+    #[tokio::test]
+    async fn rows_collected_before_error_are_returned() {
+        let rows = failing_iterator().await.into_typed::<(i32,)>();
+        let error = rows.try_collect_with_context().await.unwrap_err();
+        assert_eq!(error.rows, [(0,), (1,), (2,)]);
+        assert_eq!(error.error.row_index, 3);
+        assert!(error.to_string().contains("row 3 (page 1)"), "{}", error);
+    }
+
+    // Serialized size of a page of `resumable_iterator` which isn't the last one.
+    fn resumable_page_size() -> u64 {
+        int_page(&[Some(0), Some(1)], Some(&[1]))
+            .rows
+            .serialized_size as u64
+    }
+
+    #[tokio::test]
+    async fn max_rows_truncates_in_the_middle_of_a_page() {
+        let config = IterConfig::new().max_rows(5);
+        let mut rows = resumable_iterator(None)
+            .await
+            .with_limits(&config)
+            .into_typed::<(i32,)>();
+        let mut read = Vec::new();
+        while let Some(row) = rows.next().await {
+            read.push(row.unwrap().0);
+        }
+        assert_eq!(read, (0..5).collect::<Vec<_>>());
+
+        let truncation = rows.truncation().unwrap();
+        assert_eq!(truncation.reason, TruncationReason::MaxRows);
+        assert_eq!(truncation.rows, 5);
+        assert_eq!(truncation.bytes, 3 * resumable_page_size());
+        assert_eq!(rows.bytes_received(), truncation.bytes);
+        assert!(rows.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn max_bytes_truncates_between_pages() {
+        let config = IterConfig::new().max_bytes(2 * resumable_page_size() - 1);
+        let mut rows = resumable_iterator(None).await.with_limits(&config);
+        let mut read = 0;
+        while let Some(row) = rows.next().await {
+            row.unwrap();
+            read += 1;
+        }
+
+        // Rows of the page which reached the limit are returned
+        assert_eq!(read, 4);
+        let truncation = rows.truncation().unwrap();
+        assert_eq!(truncation.reason, TruncationReason::MaxBytes);
+        assert_eq!(truncation.rows, 4);
+        assert_eq!(truncation.bytes, 2 * resumable_page_size());
+    }
+
+    #[tokio::test]
+    async fn limits_not_reached_dont_truncate() {
+        let config = IterConfig::new().max_rows(10).max_bytes(u64::MAX);
+        let mut rows = resumable_iterator(None).await.with_limits(&config);
+        let mut read = 0;
+        while let Some(row) = rows.next().await {
+            row.unwrap();
+            read += 1;
+        }
+        assert_eq!(read, 10);
+        assert_eq!(rows.truncation(), None);
+    }
+
+    #[cfg(feature = "csv")]
+    #[tokio::test]
+    async fn csv_is_written_up_to_max_rows() {
+        use crate::transport::csv::CsvOptions;
+
+        let mut out = Vec::new();
+        resumable_iterator(None)
+            .await
+            .with_limits(&IterConfig::new().max_rows(3))
+            .write_csv(&mut out, CsvOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "v\n0\n1\n2\n");
+    }
+
+    #[cfg(feature = "csv")]
+    #[tokio::test]
+    async fn csv_is_written_across_pages() {
+        use crate::transport::csv::CsvOptions;
+
+        let mut out = Vec::new();
+        resumable_iterator(None)
+            .await
+            .write_csv(&mut out, CsvOptions::default())
+            .await
+            .unwrap();
+        let expected: String = std::iter::once("v".to_owned())
+            .chain((0..10).map(|v| v.to_string()))
+            .map(|line| line + "\n")
+            .collect();
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[cfg(feature = "csv")]
+    #[tokio::test]
+    async fn csv_rows_written_before_page_error_are_kept() {
+        use crate::transport::csv::{CsvExportError, CsvOptions};
+
+        let mut out = Vec::new();
+        let result = failing_iterator()
+            .await
+            .write_csv(&mut out, CsvOptions::default().null_value("NULL"))
+            .await;
+        assert!(matches!(result, Err(CsvExportError::Query(_))));
+        assert_eq!(String::from_utf8(out).unwrap(), "v\n0\n1\n2\nNULL\n4\n");
+    }
+}
@@ -77,6 +77,7 @@ pub struct DefaultPolicy {
     pick_predicate: Box<dyn Fn(&NodeRef) -> bool + Send + Sync>,
     latency_awareness: Option<LatencyAwareness>,
     fixed_shuffle_seed: Option<u64>,
+    lwt_optimisation: bool,
 }
 
 impl fmt::Debug for DefaultPolicy {
@@ -87,6 +88,7 @@ impl fmt::Debug for DefaultPolicy {
             .field("permit_dc_failover", &self.permit_dc_failover)
             .field("latency_awareness", &self.latency_awareness)
             .field("fixed_shuffle_seed", &self.fixed_shuffle_seed)
+            .field("lwt_optimisation", &self.lwt_optimisation)
             .finish_non_exhaustive()
     }
 }
@@ -110,11 +112,7 @@ or refrain from preferring datacenters (which may ban all other datacenters, if
                 );
             }
         }
-        let statement_type = if query.is_confirmed_lwt {
-            StatementType::Lwt
-        } else {
-            StatementType::NonLwt
-        };
+        let statement_type = self.statement_type(query);
         if let Some(ts) = &routing_info.token_with_strategy {
             if let NodeLocationPreference::DatacenterAndRack(dc, rack) = &self.preferences {
                 // Try to pick some alive local rack random replica.
@@ -223,11 +221,7 @@ or refrain from preferring datacenters (which may ban all other datacenters, if
         cluster: &'a ClusterData,
     ) -> FallbackPlan<'a> {
         let routing_info = self.routing_info(query, cluster);
-        let statement_type = if query.is_confirmed_lwt {
-            StatementType::Lwt
-        } else {
-            StatementType::NonLwt
-        };
+        let statement_type = self.statement_type(query);
 
         // If token is available, get a shuffled list of alive replicas.
         let maybe_replicas = if let Some(ts) = &routing_info.token_with_strategy {
@@ -333,10 +327,13 @@ or refrain from preferring datacenters (which may ban all other datacenters, if
             .chain(maybe_down_nodes)
             .unique();
 
-        if let Some(latency_awareness) = self.latency_awareness.as_ref() {
-            Box::new(latency_awareness.wrap(plan))
-        } else {
-            Box::new(plan)
+        // Latency awareness would reorder the replicas of LWT statements, which have to be
+        // tried in the same order by all clients, also when the request is retried.
+        match (self.latency_awareness.as_ref(), statement_type) {
+            (Some(latency_awareness), StatementType::NonLwt) => {
+                Box::new(latency_awareness.wrap(plan))
+            }
+            _ => Box::new(plan),
         }
     }
 
@@ -464,7 +461,11 @@ impl DefaultPolicy {
         statement_type: StatementType,
     ) -> Option<NodeRef<'a>> {
         match statement_type {
-            StatementType::Lwt => self.pick_first_replica(ts, replica_location, predicate, cluster),
+            // The latency-aware predicate is not used for LWT statements, so that
+            // the choice of the replica doesn't depend on the latencies measured by this client.
+            StatementType::Lwt => {
+                self.pick_first_replica(ts, replica_location, &Self::is_alive, cluster)
+            }
             StatementType::NonLwt => {
                 self.pick_random_replica(ts, replica_location, predicate, cluster)
             }
@@ -617,6 +618,14 @@ impl DefaultPolicy {
         vec.into_iter()
     }
 
+    fn statement_type(&self, query: &RoutingInfo) -> StatementType {
+        if query.is_confirmed_lwt && self.lwt_optimisation {
+            StatementType::Lwt
+        } else {
+            StatementType::NonLwt
+        }
+    }
+
     fn is_alive(node: &NodeRef<'_>) -> bool {
         // For now, we leave this as stub, until we have time to improve node events.
         // node.is_enabled() && !node.is_down()
@@ -639,6 +648,7 @@ impl Default for DefaultPolicy {
             pick_predicate: Box::new(Self::is_alive),
             latency_awareness: None,
             fixed_shuffle_seed: None,
+            lwt_optimisation: true,
         }
     }
 }
@@ -664,6 +674,7 @@ pub struct DefaultPolicyBuilder {
     permit_dc_failover: bool,
     latency_awareness: Option<LatencyAwarenessBuilder>,
     enable_replica_shuffle: bool,
+    enable_lwt_optimisation: bool,
 }
 
 impl DefaultPolicyBuilder {
@@ -675,6 +686,7 @@ impl DefaultPolicyBuilder {
             permit_dc_failover: false,
             latency_awareness: None,
             enable_replica_shuffle: true,
+            enable_lwt_optimisation: true,
         }
     }
 
@@ -696,6 +708,7 @@ impl DefaultPolicyBuilder {
             pick_predicate,
             latency_awareness,
             fixed_shuffle_seed: (!self.enable_replica_shuffle).then(rand::random),
+            lwt_optimisation: self.enable_lwt_optimisation,
         })
     }
 
@@ -819,6 +832,19 @@ impl DefaultPolicyBuilder {
         self.enable_replica_shuffle = enable;
         self
     }
+
+    /// Sets whether LWT statements, which Scylla marks as such when they are prepared,
+    /// should be routed to their replicas in the ring order, starting with the primary
+    /// replica, instead of a random one. All clients then coordinate the Paxos rounds
+    /// of a partition on the same replica, which reduces contention between them.
+    /// The order is kept when the statement is retried, and the replicas of LWT
+    /// statements are neither shuffled nor reordered by latency awareness.
+    ///
+    /// This option is enabled by default.
+    pub fn enable_lwt_optimisation(mut self, enable: bool) -> Self {
+        self.enable_lwt_optimisation = enable;
+        self
+    }
 }
 
 impl Default for DefaultPolicyBuilder {
@@ -877,6 +903,7 @@ mod tests {
         load_balancing::{
             default::tests::framework::mock_cluster_data_for_token_aware_tests, RoutingInfo,
         },
+        query::Query,
         routing::Token,
         transport::{
             locator::test::{KEYSPACE_NTS_RF_2, KEYSPACE_NTS_RF_3, KEYSPACE_SS_RF_2},
@@ -1667,6 +1694,49 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_default_policy_with_routing_token_of_query() {
+        use crate::transport::locator::test::{A, B, C, D, E, F, G};
+
+        let cluster = mock_cluster_data_for_token_aware_tests().await;
+        let policy = DefaultPolicy {
+            preferences: NodeLocationPreference::Datacenter("eu".to_owned()),
+            is_token_aware: true,
+            permit_dc_failover: true,
+            ..Default::default()
+        };
+
+        let mut query = Query::new("SELECT * FROM t WHERE a IN (1, 2)");
+        query.set_routing_token(Some(Token { value: 160 }));
+        query.set_routing_keyspace(Some(KEYSPACE_NTS_RF_2.to_owned()));
+        // The keyspace set on the query wins over the one used by the session
+        let (token, keyspace) = query.routing(Some(KEYSPACE_SS_RF_2));
+        let routing_info = RoutingInfo {
+            token,
+            keyspace,
+            consistency: Consistency::Two,
+            ..Default::default()
+        };
+        // The same plan as for a prepared statement with a partition key of this token
+        let expected_groups = ExpectedGroupsBuilder::new()
+            .group([A, G])
+            .group([F, D])
+            .group([C, B])
+            .group([E])
+            .build();
+        test_default_policy_with_given_cluster_and_routing_info(
+            &policy,
+            &cluster,
+            &routing_info,
+            &expected_groups,
+        )
+        .await;
+
+        // Clearing the token makes the query token unaware again
+        query.set_routing_token(None);
+        assert_eq!(query.routing(Some(KEYSPACE_SS_RF_2)), (None, None));
+    }
+
     #[tokio::test]
     async fn test_default_policy_with_lwt_statements() {
         use crate::transport::locator::test::{A, B, C, D, E, F, G};
@@ -2109,6 +2179,32 @@ mod tests {
                     .group([C, G, B]) // local nodes
                     .build(),
             },
+            // Keyspace NTS with RF=2 with enabled DC failover and disabled LWT optimisation
+            Test {
+                policy: DefaultPolicy {
+                    preferences: NodeLocationPreference::Datacenter("eu".to_owned()),
+                    is_token_aware: true,
+                    permit_dc_failover: true,
+                    lwt_optimisation: false,
+                    ..Default::default()
+                },
+                routing_info: RoutingInfo {
+                    token: Some(Token { value: 160 }),
+                    keyspace: Some(KEYSPACE_NTS_RF_2),
+                    consistency: Consistency::Two,
+                    is_confirmed_lwt: true,
+                    ..Default::default()
+                },
+                // going through the ring, we get order: F , A , C , D , G , B , E
+                //                                      us  eu  eu  us  eu  eu  us
+                //                                      r2  r1  r1  r1  r2  r1  r1
+                expected_groups: ExpectedGroupsBuilder::new()
+                    .group([A, G]) // pick + fallback local replicas
+                    .group([F, D]) // remote replicas
+                    .group([C, B]) // local nodes
+                    .group([E]) // remote nodes
+                    .build(),
+            },
         ];
 
         for Test {
@@ -2401,6 +2497,9 @@ mod latency_awareness {
                 QueryError::BadQuery(_)
                 | QueryError::TooManyOrphanedStreamIds(_)
                 | QueryError::UnableToAllocStreamId
+                | QueryError::ConnectionBusy
+                | QueryError::DeniedByGuard { .. }
+                | QueryError::BudgetExhausted { .. }
                 | QueryError::DbError(DbError::IsBootstrapping, _)
                 | QueryError::DbError(DbError::Unavailable { .. }, _)
                 | QueryError::DbError(DbError::Unprepared { .. }, _)
@@ -2414,7 +2513,8 @@ mod latency_awareness {
                 | QueryError::IoError(_)
                 | QueryError::ProtocolError(_)
                 | QueryError::TimeoutError
-                | QueryError::RequestTimeout(_) => true,
+                | QueryError::RequestTimeout(_)
+                | QueryError::ResponseTooLarge { .. } => true,
             }
         }
     }
@@ -2816,6 +2916,7 @@ mod latency_awareness {
                 pick_predicate,
                 latency_awareness: Some(latency_awareness),
                 fixed_shuffle_seed: None,
+                lwt_optimisation: true,
             }
         }
 
@@ -3358,6 +3459,31 @@ mod latency_awareness {
                         .group([D]) // penalised remote replica
                         .build(),
                 },
+                Test {
+                    // Replicas of LWT statements are not reordered, even though C and D are penalised.
+                    preset_min_avg: None,
+                    latency_stats: &[
+                        (A, fast_leader()),
+                        (C, slow_penalised()),
+                        (D, slow_penalised()),
+                        (E, too_few_measurements_slow()),
+                    ],
+                    routing_info: RoutingInfo {
+                        token: Some(Token { value: 160 }),
+                        keyspace: Some(KEYSPACE_NTS_RF_3),
+                        consistency: Consistency::Quorum,
+                        is_confirmed_lwt: true,
+                        ..Default::default()
+                    },
+                    // going through the ring, we get order: F , A , C , D , G , B , E
+                    //                                      us  eu  eu  us  eu  eu  us
+                    //                                      r2  r1  r1  r1  r2  r1  r1
+                    expected_groups: ExpectedGroupsBuilder::new()
+                        .ordered([A, C, G]) // local replicas
+                        .ordered([F, D, E]) // remote replicas
+                        .group([B]) // local nodes
+                        .build(),
+                },
                 Test {
                     // Latency-awareness has old minimum average cached, so does not fire.
                     preset_min_avg: Some(100 * min_avg),
@@ -3477,7 +3603,7 @@ mod latency_awareness {
                 .await
                 .unwrap();
 
-            session.query("whatever", ()).await.unwrap_err();
+            session.query_unpaged("whatever", ()).await.unwrap_err();
         }
 
         #[tokio::test]
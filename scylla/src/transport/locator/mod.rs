@@ -3,9 +3,11 @@ mod replicas;
 mod replication_info;
 #[cfg(test)]
 pub(crate) mod test;
+mod token_range;
 mod token_ring;
 
 use rand::{seq::IteratorRandom, Rng};
+pub use token_range::TokenRange;
 pub use token_ring::TokenRing;
 
 use super::{topology::Strategy, Node, NodeRef};
@@ -139,6 +141,28 @@ impl ReplicaLocator {
         self.replication_data.get_global_ring()
     }
 
+    /// Returns the token ranges between consecutive tokens of the ring, sorted by their ends.
+    /// The first range wraps around the end of the ring. Together the ranges cover the whole ring.
+    ///
+    /// Replicas of a range are the replicas of its end token, see [`Self::replicas_for_token`].
+    pub fn token_ranges(&self) -> Vec<TokenRange> {
+        let tokens: Vec<Token> = self
+            .ring()
+            .iter()
+            .map(|(token, _)| *token)
+            .dedup()
+            .collect();
+
+        match tokens.last() {
+            Some(last) => std::iter::once(*last)
+                .chain(tokens.iter().copied())
+                .tuple_windows()
+                .map(|(start, end)| TokenRange::new(start, end))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
     /// Gives a list of all nodes in the token ring.
     pub fn unique_nodes_in_global_ring(&self) -> &[Arc<Node>] {
         self.replication_data.unique_nodes_in_global_ring()
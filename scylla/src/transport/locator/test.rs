@@ -2,7 +2,7 @@ use rand::SeedableRng;
 use rand_chacha::ChaCha8Rng;
 use uuid::Uuid;
 
-use super::{ReplicaLocator, ReplicaSet};
+use super::{ReplicaLocator, ReplicaSet, TokenRange};
 use crate::routing::Token;
 use crate::transport::{
     connection_pool::PoolConfig,
@@ -126,6 +126,8 @@ pub(crate) fn mock_metadata_for_token_aware_tests() -> Metadata {
                 tables: HashMap::new(),
                 views: HashMap::new(),
                 user_defined_types: HashMap::new(),
+                functions: HashMap::new(),
+                aggregates: HashMap::new(),
             },
         ),
         (
@@ -139,6 +141,8 @@ pub(crate) fn mock_metadata_for_token_aware_tests() -> Metadata {
                 tables: HashMap::new(),
                 views: HashMap::new(),
                 user_defined_types: HashMap::new(),
+                functions: HashMap::new(),
+                aggregates: HashMap::new(),
             },
         ),
         (
@@ -152,6 +156,8 @@ pub(crate) fn mock_metadata_for_token_aware_tests() -> Metadata {
                 tables: HashMap::new(),
                 views: HashMap::new(),
                 user_defined_types: HashMap::new(),
+                functions: HashMap::new(),
+                aggregates: HashMap::new(),
             },
         ),
     ]
@@ -215,6 +221,7 @@ async fn test_locator() {
     test_replica_set_len(&locator);
     test_replica_set_choose(&locator);
     test_replica_set_choose_filtered(&locator);
+    test_token_ranges(&locator);
 }
 
 fn test_datacenter_info(locator: &ReplicaLocator) {
@@ -247,6 +254,42 @@ fn test_datacenter_info(locator: &ReplicaLocator) {
     );
 }
 
+fn test_token_ranges(locator: &ReplicaLocator) {
+    let ranges = locator.token_ranges();
+    let range = |start, end| TokenRange::new(Token { value: start }, Token { value: end });
+
+    assert_eq!(ranges.len(), 17);
+    assert_eq!(ranges[0], range(900, 50));
+    assert_eq!(ranges[1], range(50, 100));
+    assert_eq!(ranges[8], range(400, 450));
+    assert_eq!(ranges[16], range(800, 900));
+    for pair in ranges.windows(2) {
+        assert_eq!(pair[0].end(), pair[1].start());
+    }
+
+    let simple = Strategy::SimpleStrategy {
+        replication_factor: 3,
+    };
+    assert_replica_set_equal_to(
+        locator.replicas_for_token(ranges[0].end(), &simple, None),
+        &[A, B, E],
+    );
+    assert_replica_set_equal_to(
+        locator.replicas_for_token(ranges[16].end(), &simple, None),
+        &[B, A, E],
+    );
+
+    let nts = Strategy::NetworkTopologyStrategy {
+        datacenter_repfactors: [("eu".to_owned(), 2), ("us".to_owned(), 1)]
+            .into_iter()
+            .collect(),
+    };
+    assert_replica_set_equal_to(
+        locator.replicas_for_token(ranges[8].end(), &nts, None),
+        &[F, G, B],
+    );
+}
+
 fn test_simple_strategy_replicas(locator: &ReplicaLocator) {
     assert_replica_set_equal_to(
         locator.replicas_for_token(
@@ -0,0 +1,206 @@
+use crate::routing::Token;
+
+/// A range of tokens on the token ring: `(start, end]`, i.e. the start is exclusive
+/// and the end is inclusive, the same as in CQL restrictions
+/// `token(pk) > start AND token(pk) <= end`.
+///
+/// If `start >= end`, the range wraps around: it contains tokens greater than `start`
+/// and tokens not greater than `end`. In particular, a range with `start == end`
+/// covers the whole ring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenRange {
+    start: Token,
+    end: Token,
+}
+
+impl TokenRange {
+    /// The lowest possible token. No partition key is hashed to it,
+    /// so it never belongs to data and can always be used as an exclusive start.
+    pub const MIN_TOKEN: Token = Token::MIN;
+    /// The highest possible token.
+    pub const MAX_TOKEN: Token = Token::MAX;
+
+    /// Creates the range `(start, end]`.
+    pub fn new(start: Token, end: Token) -> Self {
+        Self { start, end }
+    }
+
+    /// Exclusive start of the range.
+    pub fn start(&self) -> Token {
+        self.start
+    }
+
+    /// Inclusive end of the range.
+    pub fn end(&self) -> Token {
+        self.end
+    }
+
+    /// Returns true if the range wraps around the end of the ring.
+    pub fn is_wrap_around(&self) -> bool {
+        self.start >= self.end
+    }
+
+    /// Returns true if the token belongs to the range.
+    pub fn contains(&self, token: Token) -> bool {
+        if self.is_wrap_around() {
+            token > self.start || token <= self.end
+        } else {
+            token > self.start && token <= self.end
+        }
+    }
+
+    /// Returns the range as one or two ranges which do not wrap around,
+    /// so that each of them can be expressed as `token(pk) > start AND token(pk) <= end`.
+    pub fn unwrap_ranges(&self) -> Vec<TokenRange> {
+        if !self.is_wrap_around() {
+            return vec![*self];
+        }
+
+        let mut ranges = Vec::with_capacity(2);
+        if self.start < Self::MAX_TOKEN {
+            ranges.push(TokenRange::new(self.start, Self::MAX_TOKEN));
+        }
+        if self.end > Self::MIN_TOKEN {
+            ranges.push(TokenRange::new(Self::MIN_TOKEN, self.end));
+        }
+        ranges
+    }
+
+    /// Splits the range into `n` contiguous sub-ranges of (almost) equal sizes,
+    /// which together cover exactly this range. Sub-ranges are returned in ring order,
+    /// starting at the start of this range.
+    ///
+    /// Fewer than `n` sub-ranges are returned if the range contains fewer than `n` tokens.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0.
+    pub fn split(&self, n: usize) -> Vec<TokenRange> {
+        assert!(n > 0, "cannot split a token range into 0 parts");
+
+        let width = self.width();
+        let n = (n as u128).min(width);
+        let start = self.start.value as i128;
+
+        let mut ranges = Vec::with_capacity(n as usize);
+        let mut sub_start = self.start;
+        for i in 1..n {
+            // Casting to i64 wraps the boundary around the ring.
+            let sub_end = Token {
+                value: (start + (width * i / n) as i128) as i64,
+            };
+            ranges.push(TokenRange::new(sub_start, sub_end));
+            sub_start = sub_end;
+        }
+        ranges.push(TokenRange::new(sub_start, self.end));
+        ranges
+    }
+
+    /// Number of tokens in the range.
+    fn width(&self) -> u128 {
+        const RING_SIZE: i128 = 1 << 64;
+        match (self.end.value as i128 - self.start.value as i128).rem_euclid(RING_SIZE) {
+            0 => RING_SIZE as u128,
+            width => width as u128,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenRange;
+    use crate::routing::Token;
+
+    fn range(start: i64, end: i64) -> TokenRange {
+        TokenRange::new(Token { value: start }, Token { value: end })
+    }
+
+    #[test]
+    fn test_contains() {
+        let plain = range(-10, 20);
+        assert!(!plain.is_wrap_around());
+        assert!(!plain.contains(Token { value: -10 }));
+        assert!(plain.contains(Token { value: -9 }));
+        assert!(plain.contains(Token { value: 20 }));
+        assert!(!plain.contains(Token { value: 21 }));
+
+        let wrapping = range(20, -10);
+        assert!(wrapping.is_wrap_around());
+        assert!(wrapping.contains(Token { value: i64::MAX }));
+        assert!(wrapping.contains(Token {
+            value: i64::MIN + 1
+        }));
+        assert!(wrapping.contains(Token { value: -10 }));
+        assert!(!wrapping.contains(Token { value: 0 }));
+        assert!(!wrapping.contains(Token { value: 20 }));
+
+        let full = range(5, 5);
+        assert!(full.contains(Token { value: 5 }));
+        assert!(full.contains(Token { value: 6 }));
+    }
+
+    #[test]
+    fn test_unwrap_ranges() {
+        assert_eq!(range(-10, 20).unwrap_ranges(), vec![range(-10, 20)]);
+        assert_eq!(
+            range(20, -10).unwrap_ranges(),
+            vec![range(20, i64::MAX), range(i64::MIN, -10)]
+        );
+        assert_eq!(
+            range(20, i64::MIN).unwrap_ranges(),
+            vec![range(20, i64::MAX)]
+        );
+        assert_eq!(
+            range(i64::MIN, i64::MIN).unwrap_ranges(),
+            vec![range(i64::MIN, i64::MAX)]
+        );
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(
+            range(0, 100).split(4),
+            vec![range(0, 25), range(25, 50), range(50, 75), range(75, 100)]
+        );
+        assert_eq!(
+            range(0, 10).split(3),
+            vec![range(0, 3), range(3, 6), range(6, 10)]
+        );
+        assert_eq!(range(0, 2).split(5), vec![range(0, 1), range(1, 2)]);
+        assert_eq!(range(0, 100).split(1), vec![range(0, 100)]);
+
+        // Wrap-around range is split across the end of the ring.
+        assert_eq!(
+            range(i64::MAX - 10, i64::MIN + 10).split(2),
+            vec![
+                range(i64::MAX - 10, i64::MAX),
+                range(i64::MAX, i64::MIN + 10)
+            ]
+        );
+
+        // The whole ring.
+        assert_eq!(
+            range(i64::MIN, i64::MIN).split(4),
+            vec![
+                range(i64::MIN, i64::MIN / 2),
+                range(i64::MIN / 2, 0),
+                range(0, i64::MAX / 2 + 1),
+                range(i64::MAX / 2 + 1, i64::MIN)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_covers_range() {
+        for r in [range(-1000, 1000), range(900, -900), range(7, 7)] {
+            let parts = r.split(7);
+            assert_eq!(parts.first().unwrap().start(), r.start());
+            assert_eq!(parts.last().unwrap().end(), r.end());
+            for pair in parts.windows(2) {
+                assert_eq!(pair[0].end(), pair[1].start());
+            }
+            let width: u128 = parts.iter().map(|part| part.width()).sum();
+            assert_eq!(width, r.width());
+        }
+    }
+}
@@ -1,9 +1,38 @@
+use crate::routing::{Shard, ShardInfo};
+use crate::transport::connection_pool::ConnectionCloseReason;
+#[cfg(feature = "metrics-exporter")]
+use crate::transport::metrics_exporter::MetricsObserver;
+use crate::transport::node::{Node, NodeRef};
+use crate::transport::priority::RequestPriority;
+use arc_swap::ArcSwap;
 use histogram::Histogram;
+use scylla_cql::frame::response::type_interner::TypeInterner;
+use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+use std::time::Duration;
 
 const ORDER_TYPE: Ordering = Ordering::Relaxed;
 
+// Latencies are recorded in microseconds into log-linear buckets: values below
+// `SUB_BUCKETS` get a bucket each, and every further power-of-two range is split
+// into `SUB_BUCKETS` equally wide buckets. This keeps the memory of a histogram
+// fixed and bounds the relative error of a reported percentile by 1/SUB_BUCKETS.
+const SUB_BUCKET_BITS: u32 = 3;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+// Latencies of 2^36 microseconds (~19 hours) and above are recorded as the maximum value.
+const MAX_LATENCY_BITS: u32 = 36;
+const BUCKETS: usize = (MAX_LATENCY_BITS - SUB_BUCKET_BITS + 1) as usize * SUB_BUCKETS;
+
+/// Maximal number of distinct [statement labels](crate::query::Query::set_label)
+/// for which metrics are kept. Requests of statements with further labels
+/// are counted under [`OTHER_STATEMENT_LABEL`].
+pub const MAX_STATEMENT_LABELS: usize = 100;
+
+/// Label under which requests are counted once [`MAX_STATEMENT_LABELS`] labels are tracked.
+pub const OTHER_STATEMENT_LABEL: &str = "other";
+
 #[derive(Debug)]
 pub enum MetricsError<'a> {
     Poison(PoisonError<MutexGuard<'a, Histogram>>),
@@ -35,7 +64,28 @@ pub struct Metrics {
     errors_iter_num: AtomicU64,
     queries_iter_num: AtomicU64,
     retries_num: AtomicU64,
+    speculative_executions_num: AtomicU64,
+    buffer_pool_hits: AtomicU64,
+    buffer_pool_misses: AtomicU64,
+    reprepares_num: AtomicU64,
+    control_connection_failovers: AtomicU64,
+    orphaned_requests: AtomicU64,
+    late_responses: AtomicU64,
+    queued_requests: AtomicU64,
+    spilled_requests: AtomicU64,
+    denied_statements: AtomicU64,
+    in_flight_requests: AtomicU64,
+    cancelled_requests: AtomicU64,
+    running_iterator_workers: AtomicU64,
     histogram: Arc<Mutex<Histogram>>,
+    queue_time: LatencyHistogram,
+    service_time: LatencyHistogram,
+    priority_classes: [PriorityClassMetrics; 3],
+    node_latencies: ArcSwap<HashMap<SocketAddr, Arc<NodeLatencies>>>,
+    node_connections: ArcSwap<HashMap<SocketAddr, Arc<NodeConnectionMetrics>>>,
+    statement_labels: ArcSwap<HashMap<Arc<str>, Arc<LabelMetrics>>>,
+    #[cfg(feature = "metrics-exporter")]
+    observer: Option<Arc<dyn MetricsObserver>>,
 }
 
 impl Metrics {
@@ -46,7 +96,45 @@ impl Metrics {
             errors_iter_num: AtomicU64::new(0),
             queries_iter_num: AtomicU64::new(0),
             retries_num: AtomicU64::new(0),
+            speculative_executions_num: AtomicU64::new(0),
+            buffer_pool_hits: AtomicU64::new(0),
+            buffer_pool_misses: AtomicU64::new(0),
+            reprepares_num: AtomicU64::new(0),
+            control_connection_failovers: AtomicU64::new(0),
+            orphaned_requests: AtomicU64::new(0),
+            late_responses: AtomicU64::new(0),
+            queued_requests: AtomicU64::new(0),
+            spilled_requests: AtomicU64::new(0),
+            denied_statements: AtomicU64::new(0),
+            in_flight_requests: AtomicU64::new(0),
+            cancelled_requests: AtomicU64::new(0),
+            running_iterator_workers: AtomicU64::new(0),
             histogram: Arc::new(Mutex::new(Histogram::new())),
+            queue_time: LatencyHistogram::new(),
+            service_time: LatencyHistogram::new(),
+            priority_classes: Default::default(),
+            node_latencies: Default::default(),
+            node_connections: Default::default(),
+            statement_labels: Default::default(),
+            #[cfg(feature = "metrics-exporter")]
+            observer: None,
+        }
+    }
+
+    /// Creates metrics which additionally report every recorded event to `observer`.
+    #[cfg(feature = "metrics-exporter")]
+    pub(crate) fn with_observer(observer: Option<Arc<dyn MetricsObserver>>) -> Self {
+        Self {
+            observer,
+            ..Self::new()
+        }
+    }
+
+    /// Passes the installed observer, if there is one, to `f`.
+    #[cfg(feature = "metrics-exporter")]
+    pub(crate) fn notify(&self, f: impl FnOnce(&dyn MetricsObserver)) {
+        if let Some(observer) = self.observer.as_deref() {
+            f(observer);
         }
     }
 
@@ -76,6 +164,101 @@ impl Metrics {
         self.retries_num.fetch_add(1, ORDER_TYPE);
     }
 
+    /// Increments counter of speculative executions started by the speculative execution policy.
+    pub(crate) fn inc_speculative_executions_num(&self) {
+        self.speculative_executions_num.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Increments counter of buffers reused from the serialization buffer pool.
+    pub(crate) fn inc_buffer_pool_hits(&self) {
+        self.buffer_pool_hits.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Increments counter of buffers allocated because the serialization buffer pool was empty.
+    pub(crate) fn inc_buffer_pool_misses(&self) {
+        self.buffer_pool_misses.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Increments counter of statements re-prepared after a node reported them as unprepared.
+    pub(crate) fn inc_reprepares(&self) {
+        self.reprepares_num.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Increments counter of control connections moved to another node.
+    pub(crate) fn inc_control_connection_failovers(&self) {
+        self.control_connection_failovers.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Increments counter of requests abandoned while waiting for their response.
+    pub(crate) fn inc_orphaned_requests(&self) {
+        self.orphaned_requests.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Increments counter of responses which arrived after their request was abandoned.
+    pub(crate) fn inc_late_responses(&self) {
+        self.late_responses.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Increments the number of requests waiting for a free stream id.
+    pub(crate) fn inc_queued_requests(&self) {
+        self.queued_requests.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Decrements the number of requests waiting for a free stream id.
+    pub(crate) fn dec_queued_requests(&self) {
+        self.queued_requests.fetch_sub(1, ORDER_TYPE);
+    }
+
+    /// Increments counter of requests sent through another connection of the pool,
+    /// because the chosen one had no free stream ids.
+    pub(crate) fn inc_spilled_requests(&self) {
+        self.spilled_requests.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Increments counter of requests denied by a statement guard.
+    pub(crate) fn inc_denied_statements(&self) {
+        self.denied_statements.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Increments the number of requests holding a stream id or waiting to be written.
+    pub(crate) fn inc_in_flight_requests(&self) {
+        self.in_flight_requests.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Decrements the number of requests holding a stream id or waiting to be written.
+    pub(crate) fn dec_in_flight_requests(&self) {
+        self.in_flight_requests.fetch_sub(1, ORDER_TYPE);
+    }
+
+    /// Increments counter of requests dropped by their caller before being written.
+    pub(crate) fn inc_cancelled_requests(&self) {
+        self.cancelled_requests.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Increments the number of running background tasks fetching pages for iterators.
+    pub(crate) fn inc_running_iterator_workers(&self) {
+        self.running_iterator_workers.fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Decrements the number of running background tasks fetching pages for iterators.
+    pub(crate) fn dec_running_iterator_workers(&self) {
+        self.running_iterator_workers.fetch_sub(1, ORDER_TYPE);
+    }
+
+    /// Records the time a request waited for a free stream id on its connection.
+    pub(crate) fn record_queue_time(&self, queue_time: Duration) {
+        self.queue_time.record(queue_time);
+    }
+
+    /// Records the time from sending a request on a connection until receiving the response.
+    pub(crate) fn record_service_time(&self, service_time: Duration) {
+        self.service_time.record(service_time);
+    }
+
+    pub(crate) fn priority_class(&self, priority: RequestPriority) -> &PriorityClassMetrics {
+        &self.priority_classes[priority.index()]
+    }
+
     /// Saves to histogram latency of completing single query.
     /// For paged queries it should log latency for every page.
     ///
@@ -88,6 +271,218 @@ impl Metrics {
         Ok(())
     }
 
+    /// Saves latency of completing a single query to the histograms of the node
+    /// (and the shard, if known) that served it.
+    /// For paged queries it should log latency for every page.
+    ///
+    /// Recording does not take any locks, unless this is the first latency
+    /// recorded for the node.
+    pub(crate) fn log_node_latency(
+        &self,
+        node: NodeRef<'_>,
+        shard_info: Option<&ShardInfo>,
+        latency: Duration,
+    ) {
+        let nr_shards = shard_info.map_or(0, |info| info.nr_shards.get() as usize);
+        let node_latencies = self.node_latencies_for(node, nr_shards);
+        node_latencies.histogram.record(latency);
+        if let Some(shard_histogram) =
+            shard_info.and_then(|info| node_latencies.shards.get(info.shard as usize))
+        {
+            shard_histogram.record(latency);
+        }
+    }
+
+    fn node_latencies_for(&self, node: NodeRef<'_>, nr_shards: usize) -> Arc<NodeLatencies> {
+        let address = node.address.into_inner();
+        if let Some(node_latencies) = self.node_latencies.load().get(&address) {
+            if node_latencies.is_for(node, nr_shards) {
+                return node_latencies.clone();
+            }
+        }
+
+        // Slow path - the node is seen for the first time (or its number of shards changed).
+        // Entries of nodes that are no longer part of the cluster are removed here,
+        // so that the memory used for latencies doesn't grow when nodes come and go.
+        let new_entry = Arc::new(NodeLatencies::new(node, nr_shards));
+        self.node_latencies.rcu(|node_latencies| {
+            let mut node_latencies: HashMap<SocketAddr, Arc<NodeLatencies>> = node_latencies
+                .iter()
+                .filter(|(_, entry)| entry.node.strong_count() > 0)
+                .map(|(addr, entry)| (*addr, entry.clone()))
+                .collect();
+            match node_latencies.get(&address) {
+                Some(entry) if entry.is_for(node, nr_shards) => {}
+                _ => {
+                    node_latencies.insert(address, new_entry.clone());
+                }
+            }
+            node_latencies
+        });
+
+        self.node_latencies
+            .load()
+            .get(&address)
+            .cloned()
+            .unwrap_or(new_entry)
+    }
+
+    /// Returns latency percentiles of queries served by the node with the given address,
+    /// or `None` if no latency was recorded for this node.
+    pub fn node_latency_percentiles(&self, address: SocketAddr) -> Option<Percentiles> {
+        self.node_latencies
+            .load()
+            .get(&address)?
+            .histogram
+            .percentiles()
+    }
+
+    /// Returns latency percentiles of queries served by the given shard of the node
+    /// with the given address, or `None` if no latency was recorded for this shard.
+    pub fn shard_latency_percentiles(
+        &self,
+        address: SocketAddr,
+        shard: Shard,
+    ) -> Option<Percentiles> {
+        self.node_latencies
+            .load()
+            .get(&address)?
+            .shards
+            .get(shard as usize)?
+            .percentiles()
+    }
+
+    /// Returns latency percentiles of all nodes that are part of the cluster
+    /// and served at least one query.
+    pub fn node_latencies_snapshot(&self) -> Vec<NodeLatencySnapshot> {
+        self.node_latencies
+            .load()
+            .iter()
+            .filter(|(_, entry)| entry.node.strong_count() > 0)
+            .filter_map(|(address, entry)| {
+                Some(NodeLatencySnapshot {
+                    address: *address,
+                    percentiles: entry.histogram.percentiles()?,
+                    shards: entry
+                        .shards
+                        .iter()
+                        .map(LatencyHistogram::percentiles)
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn inc_opened_connections(&self, address: SocketAddr) {
+        self.node_connections_for(address)
+            .opened
+            .fetch_add(1, ORDER_TYPE);
+    }
+
+    pub(crate) fn inc_failed_connections(&self, address: SocketAddr) {
+        self.node_connections_for(address)
+            .failed
+            .fetch_add(1, ORDER_TYPE);
+    }
+
+    pub(crate) fn inc_closed_connections(
+        &self,
+        address: SocketAddr,
+        reason: ConnectionCloseReason,
+    ) {
+        self.node_connections_for(address).closed[reason as usize].fetch_add(1, ORDER_TYPE);
+    }
+
+    fn node_connections_for(&self, address: SocketAddr) -> Arc<NodeConnectionMetrics> {
+        if let Some(node_connections) = self.node_connections.load().get(&address) {
+            return node_connections.clone();
+        }
+
+        // Slow path - the node is seen for the first time.
+        self.node_connections.rcu(|node_connections| {
+            let mut node_connections = HashMap::clone(node_connections);
+            node_connections.entry(address).or_default();
+            node_connections
+        });
+
+        self.node_connections
+            .load()
+            .get(&address)
+            .cloned()
+            .expect("the node's entry was just inserted")
+    }
+
+    /// Returns the counts of connections opened, failed to open and closed by the pool
+    /// of the node with the given address, or `None` if the pool didn't try to open any.
+    pub fn node_connections(&self, address: SocketAddr) -> Option<NodeConnectionsSnapshot> {
+        self.node_connections
+            .load()
+            .get(&address)
+            .map(|entry| entry.snapshot(address))
+    }
+
+    /// Returns the counts of connections opened, failed to open and closed by the pools
+    /// of all nodes the session has tried to connect to.
+    pub fn node_connections_snapshot(&self) -> Vec<NodeConnectionsSnapshot> {
+        self.node_connections
+            .load()
+            .iter()
+            .map(|(address, entry)| entry.snapshot(*address))
+            .collect()
+    }
+
+    /// Returns the metrics of requests of statements with the given label,
+    /// or of [`OTHER_STATEMENT_LABEL`] if [`MAX_STATEMENT_LABELS`] other labels are already tracked.
+    ///
+    /// Doesn't take any locks, unless this is the first request with the label.
+    pub(crate) fn label_metrics(&self, label: &str) -> Arc<LabelMetrics> {
+        if let Some(label_metrics) = self.statement_labels.load().get(label) {
+            return label_metrics.clone();
+        }
+
+        // Slow path - the label is seen for the first time.
+        self.statement_labels.rcu(|statement_labels| {
+            let mut statement_labels = HashMap::clone(statement_labels);
+            let tracked = statement_labels.len()
+                - usize::from(statement_labels.contains_key(OTHER_STATEMENT_LABEL));
+            let label = if statement_labels.contains_key(label) || tracked < MAX_STATEMENT_LABELS {
+                label
+            } else {
+                OTHER_STATEMENT_LABEL
+            };
+            statement_labels
+                .entry(Arc::from(label))
+                .or_insert_with(|| Arc::new(LabelMetrics::new(label)));
+            statement_labels
+        });
+
+        let statement_labels = self.statement_labels.load();
+        statement_labels
+            .get(label)
+            .or_else(|| statement_labels.get(OTHER_STATEMENT_LABEL))
+            .cloned()
+            .expect("the label or the overflow label was just inserted")
+    }
+
+    /// Returns the metrics of requests of all statements with a [label](crate::query::Query::set_label),
+    /// broken down by the label.
+    pub fn statement_labels_snapshot(&self) -> Vec<StatementLabelSnapshot> {
+        self.statement_labels
+            .load()
+            .values()
+            .map(|label_metrics| label_metrics.snapshot())
+            .collect()
+    }
+
+    /// Returns the metrics of requests of statements with the given label,
+    /// or `None` if no such request was made.
+    pub fn statement_label_snapshot(&self, label: &str) -> Option<StatementLabelSnapshot> {
+        self.statement_labels
+            .load()
+            .get(label)
+            .map(|label_metrics| label_metrics.snapshot())
+    }
+
     /// Returns average latency in milliseconds
     pub fn get_latency_avg_ms(&self) -> Result<u64, MetricsError> {
         let histogram_unlocked = self.histogram.lock().unwrap();
@@ -127,4 +522,620 @@ impl Metrics {
     pub fn get_retries_num(&self) -> u64 {
         self.retries_num.load(ORDER_TYPE)
     }
+
+    /// Returns counter of speculative executions started.
+    pub fn get_speculative_executions_num(&self) -> u64 {
+        self.speculative_executions_num.load(ORDER_TYPE)
+    }
+
+    /// Returns counter of buffers reused from the serialization buffer pool.
+    /// Always 0 unless the pool is enabled with
+    /// [`SessionBuilder::buffer_pool_max_retained_bytes`](crate::transport::session_builder::GenericSessionBuilder::buffer_pool_max_retained_bytes).
+    pub fn get_buffer_pool_hits(&self) -> u64 {
+        self.buffer_pool_hits.load(ORDER_TYPE)
+    }
+
+    /// Returns counter of buffers which had to be allocated
+    /// because the serialization buffer pool was empty.
+    pub fn get_buffer_pool_misses(&self) -> u64 {
+        self.buffer_pool_misses.load(ORDER_TYPE)
+    }
+
+    /// Returns counter of statements re-prepared after a node reported them as unprepared.
+    /// Concurrent requests which get a statement reported as unprepared by the same node
+    /// share a single re-preparation, counted once.
+    pub fn get_reprepares(&self) -> u64 {
+        self.reprepares_num.load(ORDER_TYPE)
+    }
+
+    /// Returns counter of control connections moved to another node because
+    /// the previous one failed or was unhealthy.
+    pub fn get_control_connection_failovers(&self) -> u64 {
+        self.control_connection_failovers.load(ORDER_TYPE)
+    }
+
+    /// Returns counter of requests which were sent, but abandoned before their response
+    /// arrived, e.g. because of a client-side timeout. The stream id of such a request
+    /// can't be reused until its late response arrives.
+    pub fn get_orphaned_requests(&self) -> u64 {
+        self.orphaned_requests.load(ORDER_TYPE)
+    }
+
+    /// Returns counter of responses which arrived after their request was abandoned,
+    /// freeing the stream id of the request.
+    pub fn get_late_responses(&self) -> u64 {
+        self.late_responses.load(ORDER_TYPE)
+    }
+
+    /// Returns the number of requests currently waiting for a free stream id
+    /// on any connection, see [`StreamExhaustionPolicy`](crate::transport::session::StreamExhaustionPolicy).
+    pub fn get_queued_requests(&self) -> u64 {
+        self.queued_requests.load(ORDER_TYPE)
+    }
+
+    /// Returns counter of requests sent through another connection to the same node,
+    /// because all stream ids of the connection chosen for them were in use.
+    pub fn get_spilled_requests(&self) -> u64 {
+        self.spilled_requests.load(ORDER_TYPE)
+    }
+
+    /// Returns counter of requests which were not sent, because a
+    /// [`StatementGuard`](crate::transport::statement_guard::StatementGuard) denied them.
+    pub fn get_denied_statements(&self) -> u64 {
+        self.denied_statements.load(ORDER_TYPE)
+    }
+
+    /// Returns the number of requests which took a stream permit of their connection
+    /// and didn't free it yet, i.e. are about to be written or are waiting for the response.
+    /// Requests dropped by their caller keep the stream id until the late response arrives,
+    /// see [`get_orphaned_requests`](Self::get_orphaned_requests).
+    pub fn get_in_flight_requests(&self) -> u64 {
+        self.in_flight_requests.load(ORDER_TYPE)
+    }
+
+    /// Returns counter of requests which were dropped by their caller, e.g. because of
+    /// a timeout, before they were written to the connection. Such requests are not sent
+    /// and free their stream permits immediately.
+    pub fn get_cancelled_requests(&self) -> u64 {
+        self.cancelled_requests.load(ORDER_TYPE)
+    }
+
+    /// Returns the number of running background tasks fetching pages for
+    /// [`RowIterator`](crate::transport::iterator::RowIterator)s. A task stops
+    /// as soon as its iterator is dropped.
+    pub fn get_running_iterator_workers(&self) -> u64 {
+        self.running_iterator_workers.load(ORDER_TYPE)
+    }
+
+    /// Returns the approximate memory, in bytes, used by the definitions of user defined
+    /// types shared by the types of columns in the metadata of prepared statements, results
+    /// and the cluster, see [`type_interner`](scylla_cql::frame::response::type_interner).
+    /// The definitions are shared by all sessions, so it's the same for every session in the process.
+    pub fn get_type_metadata_memory_usage(&self) -> usize {
+        TypeInterner::global().memory_usage()
+    }
+
+    /// Returns percentiles of the time requests waited for a free stream id on their
+    /// connections ("queue time"), or `None` if no request was sent yet.
+    /// Requests are serialized only once they get a stream id, so waiting requests
+    /// don't hold serialized frames.
+    pub fn queue_time_percentiles(&self) -> Option<Percentiles> {
+        self.queue_time.percentiles()
+    }
+
+    /// Returns percentiles of the time from sending requests until receiving their
+    /// responses ("service time"), not including the queue time,
+    /// or `None` if no response was received yet.
+    pub fn service_time_percentiles(&self) -> Option<Percentiles> {
+        self.service_time.percentiles()
+    }
+
+    /// Returns the metrics of requests of the given priority class.
+    /// They are collected only if [priority scheduling](crate::SessionBuilder::priority_scheduling)
+    /// is enabled.
+    pub fn priority_class_snapshot(&self, priority: RequestPriority) -> PriorityClassSnapshot {
+        self.priority_class(priority).snapshot(priority)
+    }
+}
+
+/// Latency percentiles computed from a [`LatencyHistogram`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Percentiles {
+    /// Number of recorded latencies
+    pub count: u64,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+/// Latency percentiles of a single node, as returned by [`Metrics::node_latencies_snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct NodeLatencySnapshot {
+    pub address: SocketAddr,
+    /// Percentiles of all queries served by the node
+    pub percentiles: Percentiles,
+    /// Percentiles of queries served by each shard of the node, indexed by shard id.\
+    /// `None` for shards that didn't serve any query. Empty if the node isn't sharded.
+    pub shards: Vec<Option<Percentiles>>,
+}
+
+/// Connections of a single node's pool, as returned by [`Metrics::node_connections_snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct NodeConnectionsSnapshot {
+    pub address: SocketAddr,
+    /// Number of connections opened
+    pub opened: u64,
+    /// Number of attempts to open a connection that failed
+    pub failed: u64,
+    /// Number of connections closed, for every reason
+    pub closed: HashMap<ConnectionCloseReason, u64>,
+}
+
+impl NodeConnectionsSnapshot {
+    /// Number of connections closed for the given reason
+    pub fn closed_by(&self, reason: ConnectionCloseReason) -> u64 {
+        self.closed.get(&reason).copied().unwrap_or(0)
+    }
+}
+
+/// Metrics of requests of statements with a single label,
+/// as returned by [`Metrics::statement_labels_snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StatementLabelSnapshot {
+    pub label: String,
+    /// Number of attempts to execute the statement or to fetch a page of its results,
+    /// including retries and speculative executions
+    pub requests: u64,
+    /// Number of failed attempts
+    pub errors: u64,
+    /// Number of retries decided by the retry policy
+    pub retries: u64,
+    /// Number of speculative executions started
+    pub speculative_executions: u64,
+    /// Number of pages fetched by paging iterators
+    pub pages: u64,
+    /// Percentiles of latencies of successful attempts, `None` if there were none
+    pub latency: Option<Percentiles>,
+}
+
+/// Metrics of requests of a single priority class,
+/// as returned by [`Metrics::priority_class_snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PriorityClassSnapshot {
+    pub priority: RequestPriority,
+    /// Number of requests of the class currently waiting for a free stream id
+    pub queued: u64,
+    /// Percentiles of latencies of the requests on their connections,
+    /// from queueing until receiving the response, `None` if there were none
+    pub latency: Option<Percentiles>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct PriorityClassMetrics {
+    queued: AtomicU64,
+    latency: LatencyHistogram,
+}
+
+impl PriorityClassMetrics {
+    pub(crate) fn inc_queued(&self) {
+        self.queued.fetch_add(1, ORDER_TYPE);
+    }
+
+    pub(crate) fn dec_queued(&self) {
+        self.queued.fetch_sub(1, ORDER_TYPE);
+    }
+
+    pub(crate) fn record_latency(&self, latency: Duration) {
+        self.latency.record(latency);
+    }
+
+    fn snapshot(&self, priority: RequestPriority) -> PriorityClassSnapshot {
+        PriorityClassSnapshot {
+            priority,
+            queued: self.queued.load(ORDER_TYPE),
+            latency: self.latency.percentiles(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct LabelMetrics {
+    label: Arc<str>,
+    requests: AtomicU64,
+    errors: AtomicU64,
+    retries: AtomicU64,
+    speculative_executions: AtomicU64,
+    pages: AtomicU64,
+    latency: LatencyHistogram,
+}
+
+impl LabelMetrics {
+    fn new(label: &str) -> Self {
+        Self {
+            label: Arc::from(label),
+            requests: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            retries: AtomicU64::new(0),
+            speculative_executions: AtomicU64::new(0),
+            pages: AtomicU64::new(0),
+            latency: LatencyHistogram::new(),
+        }
+    }
+
+    pub(crate) fn inc_requests(&self) {
+        self.requests.fetch_add(1, ORDER_TYPE);
+    }
+
+    pub(crate) fn inc_errors(&self) {
+        self.errors.fetch_add(1, ORDER_TYPE);
+    }
+
+    pub(crate) fn inc_retries(&self) {
+        self.retries.fetch_add(1, ORDER_TYPE);
+    }
+
+    pub(crate) fn inc_speculative_executions(&self) {
+        self.speculative_executions.fetch_add(1, ORDER_TYPE);
+    }
+
+    pub(crate) fn inc_pages(&self) {
+        self.pages.fetch_add(1, ORDER_TYPE);
+    }
+
+    pub(crate) fn record_latency(&self, latency: Duration) {
+        self.latency.record(latency);
+    }
+
+    fn snapshot(&self) -> StatementLabelSnapshot {
+        StatementLabelSnapshot {
+            label: self.label.to_string(),
+            requests: self.requests.load(ORDER_TYPE),
+            errors: self.errors.load(ORDER_TYPE),
+            retries: self.retries.load(ORDER_TYPE),
+            speculative_executions: self.speculative_executions.load(ORDER_TYPE),
+            pages: self.pages.load(ORDER_TYPE),
+            latency: self.latency.percentiles(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct NodeConnectionMetrics {
+    opened: AtomicU64,
+    failed: AtomicU64,
+    // Indexed by `ConnectionCloseReason`
+    closed: [AtomicU64; ConnectionCloseReason::ALL.len()],
+}
+
+impl NodeConnectionMetrics {
+    fn snapshot(&self, address: SocketAddr) -> NodeConnectionsSnapshot {
+        NodeConnectionsSnapshot {
+            address,
+            opened: self.opened.load(ORDER_TYPE),
+            failed: self.failed.load(ORDER_TYPE),
+            closed: ConnectionCloseReason::ALL
+                .iter()
+                .map(|reason| (*reason, self.closed[*reason as usize].load(ORDER_TYPE)))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct NodeLatencies {
+    node: Weak<Node>,
+    histogram: LatencyHistogram,
+    shards: Box<[LatencyHistogram]>,
+}
+
+impl NodeLatencies {
+    fn new(node: NodeRef<'_>, nr_shards: usize) -> Self {
+        Self {
+            node: Arc::downgrade(node),
+            histogram: LatencyHistogram::new(),
+            shards: (0..nr_shards).map(|_| LatencyHistogram::new()).collect(),
+        }
+    }
+
+    fn is_for(&self, node: NodeRef<'_>, nr_shards: usize) -> bool {
+        std::ptr::eq(self.node.as_ptr(), Arc::as_ptr(node)) && self.shards.len() == nr_shards
+    }
+}
+
+/// A latency histogram with a fixed memory footprint.
+///
+/// Recording a latency is a single atomic increment, so it can be safely shared
+/// between threads and used on hot paths. Reported percentiles are accurate
+/// up to 12.5% of the reported value.
+pub struct LatencyHistogram {
+    buckets: Box<[AtomicU64]>,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self {
+            buckets: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    /// Records a single latency
+    pub fn record(&self, latency: Duration) {
+        let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+        self.buckets[Self::bucket_index(micros)].fetch_add(1, ORDER_TYPE);
+    }
+
+    /// Returns the number of recorded latencies
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().map(|b| b.load(ORDER_TYPE)).sum()
+    }
+
+    /// Returns latency for a given percentile, or `None` if nothing was recorded
+    /// # Arguments
+    ///
+    /// * `percentile` - float value (0.0 - 100.0)
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let counts = self.load_counts();
+        let total = counts.iter().sum();
+        Self::percentile_of(&counts, total, percentile)
+    }
+
+    /// Returns the most commonly used percentiles, or `None` if nothing was recorded
+    pub fn percentiles(&self) -> Option<Percentiles> {
+        let counts = self.load_counts();
+        let count = counts.iter().sum();
+        Some(Percentiles {
+            count,
+            p50: Self::percentile_of(&counts, count, 50.0)?,
+            p95: Self::percentile_of(&counts, count, 95.0)?,
+            p99: Self::percentile_of(&counts, count, 99.0)?,
+            max: Self::percentile_of(&counts, count, 100.0)?,
+        })
+    }
+
+    fn load_counts(&self) -> Vec<u64> {
+        self.buckets.iter().map(|b| b.load(ORDER_TYPE)).collect()
+    }
+
+    fn percentile_of(counts: &[u64], total: u64, percentile: f64) -> Option<Duration> {
+        if total == 0 {
+            return None;
+        }
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0) * total as f64).ceil() as u64;
+        let rank = rank.clamp(1, total);
+
+        let mut seen = 0;
+        for (idx, count) in counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Some(Duration::from_micros(Self::bucket_highest_value(idx)));
+            }
+        }
+        None
+    }
+
+    fn bucket_index(micros: u64) -> usize {
+        let micros = micros.min((1 << MAX_LATENCY_BITS) - 1);
+        if micros < SUB_BUCKETS as u64 {
+            return micros as usize;
+        }
+        let msb = 63 - micros.leading_zeros();
+        let group = (msb - SUB_BUCKET_BITS + 1) as usize;
+        let sub_bucket = (micros >> (msb - SUB_BUCKET_BITS)) as usize - SUB_BUCKETS;
+        group * SUB_BUCKETS + sub_bucket
+    }
+
+    fn bucket_highest_value(idx: usize) -> u64 {
+        let group = idx / SUB_BUCKETS;
+        let sub_bucket = (idx % SUB_BUCKETS) as u64;
+        if group == 0 {
+            return sub_bucket;
+        }
+        let width = 1u64 << (group - 1);
+        (SUB_BUCKETS as u64 + sub_bucket) * width + width - 1
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for LatencyHistogram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LatencyHistogram")
+            .field("count", &self.count())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing::ShardInfo;
+    use crate::transport::NodeAddr;
+    use std::num::NonZeroU16;
+
+    fn assert_close(reported: Duration, expected: Duration) {
+        let (reported, expected) = (reported.as_micros(), expected.as_micros());
+        assert!(
+            reported >= expected && reported <= expected + expected / SUB_BUCKETS as u128,
+            "reported {}us, expected {}us",
+            reported,
+            expected
+        );
+    }
+
+    #[test]
+    fn bucket_bounds() {
+        for micros in (0..100_000).chain([u32::MAX as u64, u64::MAX]) {
+            let idx = LatencyHistogram::bucket_index(micros);
+            assert!(idx < BUCKETS);
+            assert!(
+                LatencyHistogram::bucket_highest_value(idx)
+                    >= micros.min((1 << MAX_LATENCY_BITS) - 1)
+            );
+            if idx > 0 {
+                assert!(LatencyHistogram::bucket_highest_value(idx - 1) < micros);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_histogram() {
+        let histogram = LatencyHistogram::new();
+        assert_eq!(histogram.count(), 0);
+        assert_eq!(histogram.percentile(50.0), None);
+        assert_eq!(histogram.percentiles(), None);
+    }
+
+    #[test]
+    fn percentiles_respond_to_latencies() {
+        let histogram = LatencyHistogram::new();
+        for _ in 0..90 {
+            histogram.record(Duration::from_millis(1));
+        }
+        for _ in 0..9 {
+            histogram.record(Duration::from_millis(20));
+        }
+        histogram.record(Duration::from_secs(3));
+
+        let percentiles = histogram.percentiles().unwrap();
+        assert_eq!(percentiles.count, 100);
+        assert_close(percentiles.p50, Duration::from_millis(1));
+        assert_close(percentiles.p95, Duration::from_millis(20));
+        assert_close(percentiles.p99, Duration::from_millis(20));
+        assert_close(percentiles.max, Duration::from_secs(3));
+
+        // A slow tail moves the high percentiles, but not the median
+        for _ in 0..20 {
+            histogram.record(Duration::from_millis(500));
+        }
+        let percentiles = histogram.percentiles().unwrap();
+        assert_close(percentiles.p50, Duration::from_millis(1));
+        assert_close(percentiles.p95, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn node_and_shard_latencies() {
+        let metrics = Metrics::new();
+        let addr1: SocketAddr = "127.0.0.1:9042".parse().unwrap();
+        let addr2: SocketAddr = "127.0.0.2:9042".parse().unwrap();
+        let node1 = Arc::new(Node::new_for_test(
+            NodeAddr::Translatable(addr1),
+            None,
+            None,
+        ));
+        let node2 = Arc::new(Node::new_for_test(
+            NodeAddr::Translatable(addr2),
+            None,
+            None,
+        ));
+        let shard_info = ShardInfo::new(1, NonZeroU16::new(2).unwrap(), 12);
+
+        for _ in 0..10 {
+            metrics.log_node_latency(&node1, Some(&shard_info), Duration::from_millis(1));
+            metrics.log_node_latency(&node2, None, Duration::from_millis(100));
+        }
+
+        assert_close(
+            metrics.node_latency_percentiles(addr1).unwrap().p99,
+            Duration::from_millis(1),
+        );
+        assert_close(
+            metrics.node_latency_percentiles(addr2).unwrap().p99,
+            Duration::from_millis(100),
+        );
+        assert_eq!(metrics.shard_latency_percentiles(addr1, 0), None);
+        assert_eq!(
+            metrics.shard_latency_percentiles(addr1, 1).unwrap().count,
+            10
+        );
+        assert_eq!(metrics.shard_latency_percentiles(addr2, 0), None);
+        assert_eq!(metrics.node_latencies_snapshot().len(), 2);
+
+        // A node which left the cluster is not reported and its entry
+        // is removed once another node is registered.
+        drop(node2);
+        assert_eq!(metrics.node_latencies_snapshot().len(), 1);
+        let addr3: SocketAddr = "127.0.0.3:9042".parse().unwrap();
+        let node3 = Arc::new(Node::new_for_test(
+            NodeAddr::Translatable(addr3),
+            None,
+            None,
+        ));
+        metrics.log_node_latency(&node3, None, Duration::from_millis(5));
+        assert_eq!(metrics.node_latencies.load().len(), 2);
+        assert_eq!(metrics.node_latency_percentiles(addr2), None);
+    }
+
+    #[test]
+    fn node_connections_are_counted_by_reason() {
+        let metrics = Metrics::new();
+        let addr1: SocketAddr = "127.0.0.1:9042".parse().unwrap();
+        let addr2: SocketAddr = "127.0.0.2:9042".parse().unwrap();
+        assert_eq!(metrics.node_connections(addr1), None);
+
+        for _ in 0..3 {
+            metrics.inc_opened_connections(addr1);
+        }
+        metrics.inc_failed_connections(addr1);
+        metrics.inc_closed_connections(addr1, ConnectionCloseReason::KeepaliveTimeout);
+        metrics.inc_closed_connections(addr1, ConnectionCloseReason::PoolResize);
+        metrics.inc_closed_connections(addr1, ConnectionCloseReason::PoolResize);
+        metrics.inc_failed_connections(addr2);
+
+        let node1 = metrics.node_connections(addr1).unwrap();
+        assert_eq!((node1.opened, node1.failed), (3, 1));
+        assert_eq!(node1.closed_by(ConnectionCloseReason::Error), 0);
+        assert_eq!(node1.closed_by(ConnectionCloseReason::KeepaliveTimeout), 1);
+        assert_eq!(node1.closed_by(ConnectionCloseReason::PoolResize), 2);
+        assert_eq!(node1.closed.len(), ConnectionCloseReason::ALL.len());
+
+        let node2 = metrics.node_connections(addr2).unwrap();
+        assert_eq!((node2.opened, node2.failed), (0, 1));
+        assert!(node2.closed.values().all(|count| *count == 0));
+        assert_eq!(metrics.node_connections_snapshot().len(), 2);
+    }
+
+    #[test]
+    fn statement_labels_are_bounded() {
+        let metrics = Metrics::new();
+        for i in 0..MAX_STATEMENT_LABELS {
+            let label_metrics = metrics.label_metrics(&format!("label_{}", i));
+            label_metrics.inc_requests();
+            label_metrics.record_latency(Duration::from_millis(1));
+        }
+
+        // Further labels are counted together
+        metrics.label_metrics("label_a").inc_requests();
+        metrics.label_metrics("label_b").inc_errors();
+        metrics.label_metrics(OTHER_STATEMENT_LABEL).inc_retries();
+        // Already tracked labels are still counted separately
+        metrics.label_metrics("label_0").inc_pages();
+
+        let snapshot = metrics.statement_labels_snapshot();
+        assert_eq!(snapshot.len(), MAX_STATEMENT_LABELS + 1);
+        assert_eq!(metrics.statement_label_snapshot("label_a"), None);
+
+        let other = metrics
+            .statement_label_snapshot(OTHER_STATEMENT_LABEL)
+            .unwrap();
+        assert_eq!(
+            (other.requests, other.errors, other.retries, other.pages),
+            (1, 1, 1, 0)
+        );
+        assert_eq!(other.latency, None);
+
+        let first = metrics.statement_label_snapshot("label_0").unwrap();
+        assert_eq!(first.label, "label_0");
+        assert_eq!((first.requests, first.pages), (1, 1));
+        assert_eq!(first.latency.unwrap().count, 1);
+    }
 }
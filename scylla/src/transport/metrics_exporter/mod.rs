@@ -0,0 +1,136 @@
+//! Exporting driver metrics to external monitoring systems.
+//!
+//! Enabled with the `metrics-exporter` feature. Instead of periodically polling
+//! [`Metrics`](crate::transport::metrics::Metrics), an application can install
+//! a [`MetricsObserver`] with
+//! [`SessionBuilder::metrics_observer`](crate::transport::session_builder::GenericSessionBuilder::metrics_observer).
+//! The driver calls the observer's methods as the events happen,
+//! together with labels that describe them.
+//!
+//! A ready-made adapter for the [`prometheus`](https://docs.rs/prometheus) crate
+//! is available in the [`prometheus`] module, behind the `prometheus` feature.
+
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+
+use std::fmt::Debug;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::routing::{Shard, ShardInfo};
+use crate::transport::node::Node;
+
+/// Kind of the statement that a request was sent for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum StatementKind {
+    /// An unprepared statement - [`Query`](crate::query::Query).
+    Unprepared,
+    /// A [`PreparedStatement`](crate::prepared_statement::PreparedStatement).
+    Prepared,
+    /// A [`Batch`](crate::batch::Batch).
+    Batch,
+}
+
+impl StatementKind {
+    /// Returns a stable, lowercase name of the statement kind,
+    /// suitable for use as a label value.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatementKind::Unprepared => "unprepared",
+            StatementKind::Prepared => "prepared",
+            StatementKind::Batch => "batch",
+        }
+    }
+}
+
+/// Outcome of a single request sent to a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RequestOutcome {
+    /// The node responded with a non-error response.
+    Success,
+    /// The request failed - either the node responded with an error
+    /// or the connection was broken before the response arrived.
+    Error,
+}
+
+impl RequestOutcome {
+    /// Returns a stable, lowercase name of the outcome,
+    /// suitable for use as a label value.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RequestOutcome::Success => "success",
+            RequestOutcome::Error => "error",
+        }
+    }
+}
+
+/// Labels that identify the node (and possibly the shard) that an event relates to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct NodeLabels<'a> {
+    /// Address the driver connects to.
+    pub address: SocketAddr,
+    /// Datacenter of the node, if known.
+    pub datacenter: Option<&'a str>,
+    /// Shard that handled the request. `None` if the node is not sharded
+    /// or the event does not relate to a single shard.
+    pub shard: Option<Shard>,
+}
+
+impl<'a> NodeLabels<'a> {
+    pub(crate) fn new(node: &'a Node, shard_info: Option<&ShardInfo>) -> Self {
+        Self {
+            address: node.address.into_inner(),
+            datacenter: node.datacenter.as_deref(),
+            shard: shard_info.map(|info| info.shard as Shard),
+        }
+    }
+}
+
+/// A single request (attempt) sent to a node, together with its outcome.
+///
+/// Retries and speculative executions are reported as separate requests.
+/// Every page fetched by a paging iterator is reported as a separate request
+/// with `paged` set to `true`.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct RequestEvent<'a> {
+    /// Kind of the statement that was sent.
+    pub kind: StatementKind,
+    /// Node (and shard) that the request was sent to.
+    pub node: NodeLabels<'a>,
+    /// Whether the request succeeded.
+    pub outcome: RequestOutcome,
+    /// Time between sending the request and receiving the response (or an error).
+    pub latency: Duration,
+    /// Whether the request fetched a page for a paging iterator
+    /// ([`Session::query_iter`](crate::Session::query_iter) and
+    /// [`Session::execute_iter`](crate::Session::execute_iter)).
+    pub paged: bool,
+}
+
+/// Receives driver metrics as they are recorded.
+///
+/// All methods have empty default implementations, so that an observer
+/// may implement only the callbacks it is interested in and new callbacks
+/// can be added without breaking existing implementations.
+///
+/// The methods are called on the hot path of request execution,
+/// so they should be cheap and must never block.
+pub trait MetricsObserver: Debug + Send + Sync {
+    /// Called after every request sent to a node completes.
+    fn on_request(&self, _event: &RequestEvent<'_>) {}
+
+    /// Called when the retry policy decides to retry a request.
+    /// `node` identifies the node on which the retried request failed.
+    fn on_retry(&self, _node: &NodeLabels<'_>) {}
+
+    /// Called when a speculative execution of a request is started.
+    fn on_speculative_execution(&self, _kind: StatementKind) {}
+
+    /// Called when the number of open connections in the pool of a node changes.
+    /// `node.shard` is always `None`.
+    fn on_pool_size(&self, _node: &NodeLabels<'_>, _connections: usize) {}
+}
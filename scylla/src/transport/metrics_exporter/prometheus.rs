@@ -0,0 +1,279 @@
+//! [`MetricsObserver`] that exposes driver metrics through a Prometheus [`Registry`].
+//!
+//! # Exported metrics
+//!
+//! The names and labels below are considered a stable interface.
+//!
+//! | Name | Type | Labels | Description |
+//! |------|------|--------|-------------|
+//! | `scylla_requests_total` | counter | `node`, `dc`, `kind`, `outcome` | Requests sent by non-paged calls (`query`, `execute`, `batch`, ...), including retries and speculative executions |
+//! | `scylla_page_requests_total` | counter | `node`, `dc`, `kind`, `outcome` | Requests for single pages sent by paging iterators (`query_iter`, `execute_iter`) |
+//! | `scylla_request_latency_seconds` | histogram | `node`, `dc`, `shard` | Latency of successful requests, both non-paged and paged |
+//! | `scylla_retries_total` | counter | `node`, `dc` | Retries decided by the retry policy, labelled with the node on which the request failed |
+//! | `scylla_speculative_executions_total` | counter | `kind` | Speculative executions started |
+//! | `scylla_pool_connections` | gauge | `node`, `dc` | Open connections in the connection pool of a node |
+//!
+//! Label values:
+//! * `node` - address the driver connects to, e.g. `127.0.0.1:9042`,
+//! * `dc` - datacenter of the node, empty if unknown,
+//! * `shard` - shard that handled the request, empty if the node is not sharded,
+//! * `kind` - one of `unprepared`, `prepared`, `batch` (see [`StatementKind::as_str`]),
+//! * `outcome` - one of `success`, `error` (see [`RequestOutcome::as_str`]).
+//!
+//! # Example
+//! ```rust
+//! # use scylla::{Session, SessionBuilder};
+//! # use std::sync::Arc;
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! use scylla::transport::metrics_exporter::prometheus::PrometheusObserver;
+//! use prometheus::{Encoder, TextEncoder};
+//!
+//! let observer = Arc::new(PrometheusObserver::new()?);
+//! let session: Session = SessionBuilder::new()
+//!     .known_node("127.0.0.1:9042")
+//!     .metrics_observer(observer.clone())
+//!     .build()
+//!     .await?;
+//!
+//! // Serve this from the scrape endpoint.
+//! let mut exposition = Vec::new();
+//! TextEncoder::new().encode(&observer.registry().gather(), &mut exposition)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use ::prometheus::{
+    HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry,
+    Result as PrometheusResult,
+};
+
+use super::{MetricsObserver, NodeLabels, RequestEvent, RequestOutcome, StatementKind};
+
+const LATENCY_BUCKETS_SECONDS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Observer that records driver metrics in a Prometheus [`Registry`].
+///
+/// See the [module documentation](self) for the list of exported metrics.
+#[derive(Clone)]
+pub struct PrometheusObserver {
+    registry: Registry,
+    requests: IntCounterVec,
+    page_requests: IntCounterVec,
+    request_latency: HistogramVec,
+    retries: IntCounterVec,
+    speculative_executions: IntCounterVec,
+    pool_connections: IntGaugeVec,
+}
+
+impl PrometheusObserver {
+    /// Creates an observer with metrics registered in a new [`Registry`].
+    pub fn new() -> PrometheusResult<Self> {
+        Self::with_registry(Registry::new())
+    }
+
+    /// Creates an observer with metrics registered in the given [`Registry`].
+    ///
+    /// Fails if any of the metrics is already registered there,
+    /// e.g. because another observer uses the same registry.
+    pub fn with_registry(registry: Registry) -> PrometheusResult<Self> {
+        let requests = IntCounterVec::new(
+            Opts::new(
+                "scylla_requests_total",
+                "Requests sent by non-paged calls, including retries and speculative executions",
+            ),
+            &["node", "dc", "kind", "outcome"],
+        )?;
+        let page_requests = IntCounterVec::new(
+            Opts::new(
+                "scylla_page_requests_total",
+                "Requests for single pages sent by paging iterators",
+            ),
+            &["node", "dc", "kind", "outcome"],
+        )?;
+        let request_latency = HistogramVec::new(
+            HistogramOpts::new(
+                "scylla_request_latency_seconds",
+                "Latency of successful requests",
+            )
+            .buckets(LATENCY_BUCKETS_SECONDS.to_vec()),
+            &["node", "dc", "shard"],
+        )?;
+        let retries = IntCounterVec::new(
+            Opts::new(
+                "scylla_retries_total",
+                "Retries decided by the retry policy",
+            ),
+            &["node", "dc"],
+        )?;
+        let speculative_executions = IntCounterVec::new(
+            Opts::new(
+                "scylla_speculative_executions_total",
+                "Speculative executions started",
+            ),
+            &["kind"],
+        )?;
+        let pool_connections = IntGaugeVec::new(
+            Opts::new(
+                "scylla_pool_connections",
+                "Open connections in the connection pool of a node",
+            ),
+            &["node", "dc"],
+        )?;
+
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(page_requests.clone()))?;
+        registry.register(Box::new(request_latency.clone()))?;
+        registry.register(Box::new(retries.clone()))?;
+        registry.register(Box::new(speculative_executions.clone()))?;
+        registry.register(Box::new(pool_connections.clone()))?;
+
+        Ok(Self {
+            registry,
+            requests,
+            page_requests,
+            request_latency,
+            retries,
+            speculative_executions,
+            pool_connections,
+        })
+    }
+
+    /// Returns the registry that the metrics are registered in.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+}
+
+impl std::fmt::Debug for PrometheusObserver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrometheusObserver").finish_non_exhaustive()
+    }
+}
+
+impl MetricsObserver for PrometheusObserver {
+    fn on_request(&self, event: &RequestEvent<'_>) {
+        let node = event.node.address.to_string();
+        let dc = event.node.datacenter.unwrap_or_default();
+
+        let counter = if event.paged {
+            &self.page_requests
+        } else {
+            &self.requests
+        };
+        counter
+            .with_label_values(&[&node, dc, event.kind.as_str(), event.outcome.as_str()])
+            .inc();
+
+        if event.outcome == RequestOutcome::Success {
+            let shard = event
+                .node
+                .shard
+                .map(|shard| shard.to_string())
+                .unwrap_or_default();
+            self.request_latency
+                .with_label_values(&[&node, dc, &shard])
+                .observe(event.latency.as_secs_f64());
+        }
+    }
+
+    fn on_retry(&self, node: &NodeLabels<'_>) {
+        self.retries
+            .with_label_values(&[
+                &node.address.to_string(),
+                node.datacenter.unwrap_or_default(),
+            ])
+            .inc();
+    }
+
+    fn on_speculative_execution(&self, kind: StatementKind) {
+        self.speculative_executions
+            .with_label_values(&[kind.as_str()])
+            .inc();
+    }
+
+    fn on_pool_size(&self, node: &NodeLabels<'_>, connections: usize) {
+        self.pool_connections
+            .with_label_values(&[
+                &node.address.to_string(),
+                node.datacenter.unwrap_or_default(),
+            ])
+            .set(connections as i64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PrometheusObserver;
+    use crate::transport::metrics_exporter::{
+        MetricsObserver, NodeLabels, RequestEvent, RequestOutcome, StatementKind,
+    };
+    use ::prometheus::{Encoder, Registry, TextEncoder};
+    use std::time::Duration;
+
+    fn render(observer: &PrometheusObserver) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&observer.registry().gather(), &mut buffer)
+            .unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    fn node_labels(shard: Option<u32>) -> NodeLabels<'static> {
+        NodeLabels {
+            address: "127.0.0.1:9042".parse().unwrap(),
+            datacenter: Some("dc1"),
+            shard,
+        }
+    }
+
+    #[test]
+    fn exposition_contains_key_series() {
+        let observer = PrometheusObserver::new().unwrap();
+
+        let event = RequestEvent {
+            kind: StatementKind::Prepared,
+            node: node_labels(Some(3)),
+            outcome: RequestOutcome::Success,
+            latency: Duration::from_millis(2),
+            paged: false,
+        };
+        observer.on_request(&event);
+        observer.on_request(&event);
+        observer.on_request(&RequestEvent {
+            kind: StatementKind::Unprepared,
+            outcome: RequestOutcome::Error,
+            paged: true,
+            ..event
+        });
+        observer.on_retry(&node_labels(None));
+        observer.on_speculative_execution(StatementKind::Batch);
+        observer.on_pool_size(&node_labels(None), 8);
+
+        let exposition = render(&observer);
+        for series in [
+            r#"scylla_requests_total{dc="dc1",kind="prepared",node="127.0.0.1:9042",outcome="success"} 2"#,
+            r#"scylla_page_requests_total{dc="dc1",kind="unprepared",node="127.0.0.1:9042",outcome="error"} 1"#,
+            r#"scylla_request_latency_seconds_count{dc="dc1",node="127.0.0.1:9042",shard="3"} 2"#,
+            r#"scylla_request_latency_seconds_bucket{dc="dc1",node="127.0.0.1:9042",shard="3",le="0.0025"} 2"#,
+            r#"scylla_retries_total{dc="dc1",node="127.0.0.1:9042"} 1"#,
+            r#"scylla_speculative_executions_total{kind="batch"} 1"#,
+            r#"scylla_pool_connections{dc="dc1",node="127.0.0.1:9042"} 8"#,
+        ] {
+            assert!(
+                exposition.lines().any(|line| line == series),
+                "missing series {}, exposition:\n{}",
+                series,
+                exposition
+            );
+        }
+    }
+
+    #[test]
+    fn registering_twice_in_one_registry_fails() {
+        let registry = Registry::new();
+        PrometheusObserver::with_registry(registry.clone()).unwrap();
+        assert!(PrometheusObserver::with_registry(registry).is_err());
+    }
+}
@@ -1,21 +1,45 @@
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod background_tasks;
+pub(crate) mod buffer_pool;
 pub(crate) mod caching_session;
 mod cluster;
+#[cfg(feature = "config-file")]
+pub mod config_file;
 pub(crate) mod connection;
 mod connection_pool;
+pub mod control_connection_policy;
+#[cfg(feature = "csv")]
+pub mod csv;
+pub mod ddl;
 pub mod downgrading_consistency_retry_policy;
+pub mod execution_budget;
 pub mod execution_profile;
 pub mod host_filter;
+pub mod interceptor;
 pub mod iterator;
 pub mod load_balancing;
 pub mod locator;
-pub(crate) mod metrics;
+pub mod metrics;
+#[cfg(feature = "metrics-exporter")]
+pub mod metrics_exporter;
 mod node;
 pub mod partitioner;
+pub mod priority;
 pub mod query_result;
+mod repreparation;
 pub mod retry_policy;
+pub mod runtime;
+pub(crate) mod schema_changes;
+pub mod schema_check;
 pub mod session;
 pub mod session_builder;
+pub mod session_ops;
+pub mod shard_aware_batcher;
+pub mod slow_query_log;
 pub mod speculative_execution;
+pub mod statement_guard;
+pub mod table_scan;
 pub mod topology;
 
 pub use crate::frame::{Authenticator, Compression};
@@ -39,4 +63,7 @@ mod cql_value_test;
 mod large_batch_statements_test;
 
 pub use cluster::ClusterData;
-pub use node::{KnownNode, Node, NodeAddr, NodeRef};
+pub use node::{
+    AddressFamilyPreference, HostnameResolver, KnownNode, NegotiatedSettings, Node, NodeAddr,
+    NodeCapabilities, NodeRef, SrvLookup, SrvRecord, SrvResolver, SystemHostnameResolver,
+};
@@ -1,17 +1,24 @@
+use async_trait::async_trait;
 use tokio::net::lookup_host;
+use tokio::time::Instant;
 use tracing::warn;
 use uuid::Uuid;
 
 /// Node represents a cluster node along with it's data and connections
-use crate::routing::{Sharder, Token};
+use crate::frame::protocol_features::ProtocolFeatures;
+use crate::frame::Compression;
+use crate::routing::{ShardInfo, Sharder, Token};
 use crate::transport::connection::Connection;
 use crate::transport::connection::VerifiedKeyspaceName;
 use crate::transport::connection_pool::{NodeConnectionPool, PoolConfig};
 use crate::transport::errors::QueryError;
 
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt::Display;
 use std::io;
 use std::net::IpAddr;
+use std::time::Duration;
 use std::{
     hash::{Hash, Hasher},
     net::SocketAddr,
@@ -179,6 +186,9 @@ impl Node {
 
     pub(crate) fn change_down_marker(&self, is_down: bool) {
         self.down_marker.store(is_down, Ordering::Relaxed);
+        if let Some(pool) = &self.pool {
+            pool.set_node_down(is_down);
+        }
     }
 
     pub(crate) async fn use_keyspace(
@@ -201,6 +211,29 @@ impl Node {
         }
     }
 
+    /// Waits until the pool has some usable connections.
+    /// Never returns if the node has been disabled by the host filter.
+    pub(crate) async fn wait_until_pool_ready(&self) {
+        match &self.pool {
+            Some(pool) => pool.wait_until_ready().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Returns the capabilities advertised by the node in the SUPPORTED response
+    /// when the last connection of its pool was opened, or `None` if no connection
+    /// was opened yet or the node has been disabled by the host filter.
+    pub fn capabilities(&self) -> Option<Arc<NodeCapabilities>> {
+        self.pool.as_ref()?.capabilities()
+    }
+
+    /// Returns the settings negotiated with the node when the last connection
+    /// of its pool was opened, or `None` if no connection was opened yet
+    /// or the node has been disabled by the host filter.
+    pub fn negotiated_settings(&self) -> Option<NegotiatedSettings> {
+        self.pool.as_ref()?.negotiated_settings()
+    }
+
     fn get_pool(&self) -> Result<&NodeConnectionPool, QueryError> {
         self.pool.as_ref().ok_or_else(|| {
             QueryError::IoError(Arc::new(std::io::Error::new(
@@ -226,6 +259,72 @@ impl Hash for Node {
     }
 }
 
+/// Capabilities of a node, as advertised in its response to the OPTIONS request
+/// sent when opening a connection.
+///
+/// See [`Node::capabilities`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct NodeCapabilities {
+    /// Supported versions of the native protocol, e.g. `4/v4`
+    pub protocol_versions: Vec<String>,
+    /// Supported versions of CQL, e.g. `3.3.1`
+    pub cql_versions: Vec<String>,
+    /// Supported compression algorithms, e.g. `lz4`
+    pub compression: Vec<String>,
+    /// Sharding of the node, `None` if the node isn't sharded (e.g. it's a Cassandra node)
+    pub sharder: Option<Sharder>,
+    /// The shard-aware port
+    pub shard_aware_port: Option<u16>,
+    /// The shard-aware port for encrypted connections
+    pub shard_aware_port_ssl: Option<u16>,
+    /// Scylla protocol extensions, e.g. the rate limit error
+    pub protocol_features: ProtocolFeatures,
+    /// All options of the SUPPORTED response, including the ones above
+    pub options: HashMap<String, Vec<String>>,
+}
+
+impl NodeCapabilities {
+    pub(crate) fn from_supported(options: HashMap<String, Vec<String>>) -> Self {
+        let values = |key: &str| options.get(key).cloned().unwrap_or_default();
+        let port = |key: &str| {
+            options
+                .get(key)?
+                .first()
+                .and_then(|port| port.parse::<u16>().ok())
+        };
+        Self {
+            protocol_versions: values("PROTOCOL_VERSIONS"),
+            cql_versions: values("CQL_VERSION"),
+            compression: values("COMPRESSION"),
+            sharder: ShardInfo::try_from(&options)
+                .ok()
+                .map(|info| info.get_sharder()),
+            shard_aware_port: port("SCYLLA_SHARD_AWARE_PORT"),
+            shard_aware_port_ssl: port("SCYLLA_SHARD_AWARE_PORT_SSL"),
+            protocol_features: ProtocolFeatures::parse_from_supported(&options),
+            options,
+        }
+    }
+}
+
+/// Settings of connections to a node, negotiated when the connections are opened.
+///
+/// See [`Node::negotiated_settings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct NegotiatedSettings {
+    /// Version of the native protocol
+    pub protocol_version: u8,
+    /// Compression of frames, `None` if the node doesn't support the compression
+    /// set with [`SessionBuilder::compression`](crate::SessionBuilder::compression)
+    /// or if no compression was set
+    pub compression: Option<Compression>,
+    /// Options sent in the STARTUP request, including the ones set with
+    /// [`SessionBuilder::custom_startup_options`](crate::transport::session_builder::GenericSessionBuilder::custom_startup_options)
+    pub startup_options: HashMap<String, String>,
+}
+
 /// Describes a database server known on `Session` startup.
 ///
 /// The name derives from SessionBuilder's `known_node()` family of methods.
@@ -253,81 +352,297 @@ pub struct CloudEndpoint {
 pub struct ResolvedContactPoint {
     pub address: SocketAddr,
     pub datacenter: Option<String>,
+    /// Other addresses the hostname resolved to, which connections are attempted to
+    /// when connecting to `address` fails or takes longer than the connection attempt delay.
+    pub(crate) fallback_addresses: Vec<SocketAddr>,
 }
 
-// Resolve the given hostname using a DNS lookup if necessary.
-// The resolution may return multiple IPs and the function returns one of them.
-// It prefers to return IPv4s first, and only if there are none, IPv6s.
-pub(crate) async fn resolve_hostname(hostname: &str) -> Result<SocketAddr, io::Error> {
-    let mut ret = None;
-    let addrs: Vec<SocketAddr> = match lookup_host(hostname).await {
-        Ok(addrs) => addrs.collect(),
-        // Use a default port in case of error, but propagate the original error on failure
-        Err(e) => lookup_host((hostname, 9042)).await.or(Err(e))?.collect(),
-    };
-    for a in addrs {
-        match a {
-            SocketAddr::V4(_) => return Ok(a),
-            _ => {
-                ret = Some(a);
-            }
+/// Resolves hostnames of the known nodes into addresses.
+///
+/// By default the system resolver is used, through [`tokio::net::lookup_host`].
+/// A custom resolver, e.g. one querying a service discovery system, can be set with
+/// [`SessionBuilder::hostname_resolver`](crate::transport::session_builder::GenericSessionBuilder::hostname_resolver).
+#[async_trait]
+pub trait HostnameResolver: Send + Sync {
+    /// Returns all addresses of the hostname, given as `hostname:port` or just `hostname`,
+    /// in which case the port 9042 should be used.
+    async fn resolve_hostname(&self, hostname: &str) -> Result<Vec<SocketAddr>, io::Error>;
+}
+
+/// Resolves hostnames using the resolver of the operating system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemHostnameResolver;
+
+#[async_trait]
+impl HostnameResolver for SystemHostnameResolver {
+    async fn resolve_hostname(&self, hostname: &str) -> Result<Vec<SocketAddr>, io::Error> {
+        match lookup_host(hostname).await {
+            Ok(addrs) => Ok(addrs.collect()),
+            // Use a default port in case of error, but propagate the original error on failure
+            Err(e) => Ok(lookup_host((hostname, 9042)).await.or(Err(e))?.collect()),
         }
     }
+}
 
-    ret.ok_or_else(|| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            format!("Empty address list returned by DNS for {}", hostname),
-        )
-    })
+/// A record returned by an SRV lookup, pointing at a host and port which provide the service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SrvRecord {
+    /// Targets with lower priority are attempted first.
+    pub priority: u16,
+    /// Among targets with the same priority, the ones with higher weight are attempted first.
+    pub weight: u16,
+    pub port: u16,
+    /// Hostname of the target, possibly ending with a dot.
+    pub target: String,
 }
 
-/// Transforms the given [`KnownNode`]s into [`ContactPoint`]s.
+impl SrvRecord {
+    pub fn new(priority: u16, weight: u16, port: u16, target: impl Into<String>) -> Self {
+        Self {
+            priority,
+            weight,
+            port,
+            target: target.into(),
+        }
+    }
+}
+
+/// Looks up SRV records, e.g. in DNS or in a service discovery system like consul.
+#[async_trait]
+pub trait SrvLookup: Send + Sync {
+    /// Returns the SRV records of the name, e.g. `_cql._tcp.scylla.service.consul`.
+    async fn lookup_srv(&self, name: &str) -> Result<Vec<SrvRecord>, io::Error>;
+}
+
+/// A [`HostnameResolver`] which expands SRV names into the addresses and ports of their targets.
 ///
-/// In case of a hostname, resolves it using a DNS lookup.
-/// In case of a plain IP address, parses it and uses straight.
-pub(crate) async fn resolve_contact_points(
-    known_nodes: &[KnownNode],
-) -> (Vec<ResolvedContactPoint>, Vec<String>) {
-    // Find IP addresses of all known nodes passed in the config
-    let mut initial_peers: Vec<ResolvedContactPoint> = Vec::with_capacity(known_nodes.len());
-
-    let mut to_resolve: Vec<(&String, Option<String>)> = Vec::new();
-    let mut hostnames: Vec<String> = Vec::new();
-
-    for node in known_nodes.iter() {
-        match node {
-            KnownNode::Hostname(hostname) => {
-                to_resolve.push((hostname, None));
-                hostnames.push(hostname.clone());
+/// Names starting with an underscore, like `_cql._tcp.scylla.service.consul`, are looked up
+/// with the [`SrvLookup`], and the targets of the records are resolved with the hostname resolver,
+/// together with the ports from the records. Other hostnames are resolved with the hostname
+/// resolver directly, which is [`SystemHostnameResolver`] unless set with
+/// [`with_hostname_resolver`](SrvResolver::with_hostname_resolver).
+pub struct SrvResolver {
+    srv_lookup: Arc<dyn SrvLookup>,
+    hostname_resolver: Arc<dyn HostnameResolver>,
+}
+
+impl SrvResolver {
+    pub fn new(srv_lookup: Arc<dyn SrvLookup>) -> Self {
+        Self {
+            srv_lookup,
+            hostname_resolver: Arc::new(SystemHostnameResolver),
+        }
+    }
+
+    /// Sets the resolver of the targets of SRV records, and of hostnames which aren't SRV names.
+    pub fn with_hostname_resolver(mut self, hostname_resolver: Arc<dyn HostnameResolver>) -> Self {
+        self.hostname_resolver = hostname_resolver;
+        self
+    }
+}
+
+#[async_trait]
+impl HostnameResolver for SrvResolver {
+    async fn resolve_hostname(&self, hostname: &str) -> Result<Vec<SocketAddr>, io::Error> {
+        if !hostname.starts_with('_') {
+            return self.hostname_resolver.resolve_hostname(hostname).await;
+        }
+
+        let mut records = self.srv_lookup.lookup_srv(hostname).await?;
+        records.sort_by(|a, b| {
+            a.priority
+                .cmp(&b.priority)
+                .then_with(|| b.weight.cmp(&a.weight))
+        });
+        let resolve_futures = records.iter().map(|record| async move {
+            let target = record.target.trim_end_matches('.');
+            let resolved = self
+                .hostname_resolver
+                .resolve_hostname(&format!("{}:{}", target, record.port))
+                .await;
+            if let Err(e) = &resolved {
+                warn!(
+                    "Resolution of {}, a target of SRV record {}, failed: {}",
+                    target, hostname, e
+                );
             }
-            KnownNode::Address(address) => initial_peers.push(ResolvedContactPoint {
-                address: *address,
-                datacenter: None,
-            }),
-            #[cfg(feature = "cloud")]
-            KnownNode::CloudEndpoint(CloudEndpoint {
-                hostname,
-                datacenter,
-            }) => to_resolve.push((hostname, Some(datacenter.clone()))),
+            resolved.unwrap_or_default()
+        });
+        let addresses = futures::future::join_all(resolve_futures).await;
+        Ok(addresses.into_iter().flatten().collect())
+    }
+}
+
+/// Which of the addresses a hostname resolves to is attempted first, when there are
+/// both IPv4 (A record) and IPv6 (AAAA record) addresses.
+///
+/// Connections are attempted to all addresses of the allowed families, alternating
+/// between the families, and the first one established is used
+/// (see [`SessionBuilder::connection_attempt_delay`](crate::transport::session_builder::GenericSessionBuilder::connection_attempt_delay)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "config-file", derive(serde::Deserialize))]
+#[cfg_attr(feature = "config-file", serde(rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum AddressFamilyPreference {
+    /// Attempt IPv4 addresses first.
+    #[default]
+    PreferIpv4,
+    /// Attempt IPv6 addresses first.
+    PreferIpv6,
+    /// Use only IPv4 addresses.
+    Ipv4Only,
+    /// Use only IPv6 addresses.
+    Ipv6Only,
+}
+
+impl AddressFamilyPreference {
+    /// Returns the addresses in the order in which connections should be attempted:
+    /// alternating between the families, starting with the preferred one.
+    fn order(self, addrs: &[SocketAddr]) -> Vec<SocketAddr> {
+        let mut v4 = Vec::new();
+        let mut v6 = Vec::new();
+        for addr in addrs {
+            let family = if addr.is_ipv4() { &mut v4 } else { &mut v6 };
+            if !family.contains(addr) {
+                family.push(*addr);
+            }
+        }
+        let (preferred, other) = match self {
+            AddressFamilyPreference::PreferIpv4 => (v4, v6),
+            AddressFamilyPreference::PreferIpv6 => (v6, v4),
+            AddressFamilyPreference::Ipv4Only => (v4, Vec::new()),
+            AddressFamilyPreference::Ipv6Only => (v6, Vec::new()),
         };
+
+        let mut ordered = Vec::with_capacity(preferred.len() + other.len());
+        let mut preferred = preferred.into_iter();
+        let mut other = other.into_iter();
+        loop {
+            match (preferred.next(), other.next()) {
+                (None, None) => return ordered,
+                (first, second) => ordered.extend(first.into_iter().chain(second)),
+            }
+        }
     }
-    let resolve_futures = to_resolve.iter().map(|(hostname, datacenter)| async move {
-        match resolve_hostname(hostname).await {
-            Ok(address) => Some(ResolvedContactPoint {
-                address,
-                datacenter: datacenter.clone(),
-            }),
-            Err(e) => {
-                warn!("Hostname resolution failed for {}: {}", hostname, &e);
-                None
+}
+
+// Returns the addresses of the hostname in the order in which connections should be
+// attempted, which is never empty.
+pub(crate) async fn resolve_hostname_with(
+    resolver: &dyn HostnameResolver,
+    preference: AddressFamilyPreference,
+    hostname: &str,
+) -> Result<Vec<SocketAddr>, io::Error> {
+    let addrs = preference.order(&resolver.resolve_hostname(hostname).await?);
+    if !addrs.is_empty() {
+        return Ok(addrs);
+    }
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        format!(
+            "No address matching {:?} returned by DNS for {}",
+            preference, hostname
+        ),
+    ))
+}
+
+/// The known nodes given to the session, which are resolved into contact points
+/// on startup and again whenever the control connection can't be re-established
+/// to any of the peers known from the cluster metadata, as their addresses may change.
+pub(crate) struct ContactPoints {
+    known_nodes: Vec<KnownNode>,
+    resolver: Arc<dyn HostnameResolver>,
+    preference: AddressFamilyPreference,
+    // Resolved contact points are reused for this long before resolving the hostnames again.
+    resolution_interval: Duration,
+    last_resolution: Option<(Instant, Vec<ResolvedContactPoint>)>,
+}
+
+impl ContactPoints {
+    pub(crate) fn new(
+        known_nodes: Vec<KnownNode>,
+        resolver: Arc<dyn HostnameResolver>,
+        preference: AddressFamilyPreference,
+        resolution_interval: Duration,
+    ) -> Self {
+        Self {
+            known_nodes,
+            resolver,
+            preference,
+            resolution_interval,
+            last_resolution: None,
+        }
+    }
+
+    /// Transforms the known nodes into [`ResolvedContactPoint`]s.
+    ///
+    /// In case of a hostname, resolves it using the resolver, unless it was resolved
+    /// less than the resolution interval ago. In case of a plain IP address, uses it straight.
+    /// Returns the hostnames which were resolved too, for error reporting.
+    pub(crate) async fn resolve(&mut self) -> (Vec<ResolvedContactPoint>, Vec<String>) {
+        let hostnames = self.hostnames();
+        if let Some((resolved_at, contact_points)) = &self.last_resolution {
+            if resolved_at.elapsed() < self.resolution_interval {
+                return (contact_points.clone(), hostnames);
             }
         }
-    });
-    let resolved: Vec<_> = futures::future::join_all(resolve_futures).await;
-    initial_peers.extend(resolved.into_iter().flatten());
 
-    (initial_peers, hostnames)
+        let contact_points = self.resolve_known_nodes().await;
+        self.last_resolution = Some((Instant::now(), contact_points.clone()));
+        (contact_points, hostnames)
+    }
+
+    fn hostnames(&self) -> Vec<String> {
+        self.known_nodes
+            .iter()
+            .filter_map(|node| match node {
+                KnownNode::Hostname(hostname) => Some(hostname.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    async fn resolve_known_nodes(&self) -> Vec<ResolvedContactPoint> {
+        // Find IP addresses of all known nodes passed in the config
+        let mut initial_peers: Vec<ResolvedContactPoint> =
+            Vec::with_capacity(self.known_nodes.len());
+
+        let mut to_resolve: Vec<(&String, Option<String>)> = Vec::new();
+
+        for node in self.known_nodes.iter() {
+            match node {
+                KnownNode::Hostname(hostname) => to_resolve.push((hostname, None)),
+                KnownNode::Address(address) => initial_peers.push(ResolvedContactPoint {
+                    address: *address,
+                    datacenter: None,
+                    fallback_addresses: Vec::new(),
+                }),
+                #[cfg(feature = "cloud")]
+                KnownNode::CloudEndpoint(CloudEndpoint {
+                    hostname,
+                    datacenter,
+                }) => to_resolve.push((hostname, Some(datacenter.clone()))),
+            };
+        }
+        let resolve_futures = to_resolve.iter().map(|(hostname, datacenter)| async move {
+            match resolve_hostname_with(self.resolver.as_ref(), self.preference, hostname).await {
+                Ok(mut addresses) => Some(ResolvedContactPoint {
+                    address: addresses.remove(0),
+                    datacenter: datacenter.clone(),
+                    fallback_addresses: addresses,
+                }),
+                Err(e) => {
+                    warn!("Hostname resolution failed for {}: {}", hostname, &e);
+                    None
+                }
+            }
+        });
+        let resolved: Vec<_> = futures::future::join_all(resolve_futures).await;
+        initial_peers.extend(resolved.into_iter().flatten());
+
+        initial_peers
+    }
 }
 
 #[cfg(test)]
@@ -350,4 +665,238 @@ mod tests {
             }
         }
     }
+
+    // Resolves all hostnames to the addresses it currently holds.
+    struct SwitchingResolver(std::sync::Mutex<Vec<SocketAddr>>);
+
+    #[async_trait]
+    impl HostnameResolver for SwitchingResolver {
+        async fn resolve_hostname(&self, _hostname: &str) -> Result<Vec<SocketAddr>, io::Error> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+    }
+
+    fn addresses(contact_points: &[ResolvedContactPoint]) -> Vec<SocketAddr> {
+        contact_points.iter().map(|point| point.address).collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn contact_points_are_resolved_again_after_interval() {
+        let old: SocketAddr = "10.0.0.1:9042".parse().unwrap();
+        let new: SocketAddr = "10.0.0.2:9042".parse().unwrap();
+        let fixed: SocketAddr = "10.0.0.3:9042".parse().unwrap();
+        let resolver = Arc::new(SwitchingResolver(std::sync::Mutex::new(vec![old])));
+        let mut contact_points = ContactPoints::new(
+            vec![
+                KnownNode::Hostname("scylla.local:9042".to_owned()),
+                KnownNode::Address(fixed),
+            ],
+            resolver.clone(),
+            AddressFamilyPreference::PreferIpv4,
+            Duration::from_secs(10),
+        );
+
+        let (resolved, hostnames) = contact_points.resolve().await;
+        assert_eq!(addresses(&resolved), [fixed, old]);
+        assert!(resolved[1].fallback_addresses.is_empty());
+        assert_eq!(hostnames, ["scylla.local:9042"]);
+
+        // The addresses changed, but the previous ones are still reused
+        *resolver.0.lock().unwrap() = vec![new];
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(addresses(&contact_points.resolve().await.0), [fixed, old]);
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(addresses(&contact_points.resolve().await.0), [fixed, new]);
+
+        // A hostname which doesn't resolve is skipped
+        *resolver.0.lock().unwrap() = vec![];
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(addresses(&contact_points.resolve().await.0), [fixed]);
+    }
+
+    // Resolves the hostnames it knows, using the port given with the hostname or 9042.
+    struct StubHostnameResolver(HashMap<&'static str, Vec<IpAddr>>);
+
+    #[async_trait]
+    impl HostnameResolver for StubHostnameResolver {
+        async fn resolve_hostname(&self, hostname: &str) -> Result<Vec<SocketAddr>, io::Error> {
+            let (host, port) = match hostname.rsplit_once(':') {
+                Some((host, port)) => (host, port.parse().unwrap()),
+                None => (hostname, 9042),
+            };
+            match self.0.get(host) {
+                Some(ips) => Ok(ips.iter().map(|ip| SocketAddr::new(*ip, port)).collect()),
+                None => Err(io::Error::new(io::ErrorKind::NotFound, host.to_owned())),
+            }
+        }
+    }
+
+    struct StubSrvLookup(HashMap<&'static str, Vec<SrvRecord>>);
+
+    #[async_trait]
+    impl SrvLookup for StubSrvLookup {
+        async fn lookup_srv(&self, name: &str) -> Result<Vec<SrvRecord>, io::Error> {
+            self.0
+                .get(name)
+                .cloned()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, name.to_owned()))
+        }
+    }
+
+    fn srv_resolver() -> SrvResolver {
+        let hosts = HashMap::from([
+            ("node1.consul", vec!["10.0.0.1".parse().unwrap()]),
+            (
+                "node2.consul",
+                vec!["10.0.0.2".parse().unwrap(), "fd00::2".parse().unwrap()],
+            ),
+            ("node3.consul", vec!["10.0.0.3".parse().unwrap()]),
+        ]);
+        let services = HashMap::from([(
+            "_cql._tcp.scylla.service.consul",
+            vec![
+                SrvRecord::new(20, 100, 9044, "node3.consul."),
+                SrvRecord::new(10, 1, 9043, "node2.consul."),
+                SrvRecord::new(10, 5, 19042, "node1.consul."),
+                SrvRecord::new(10, 5, 9042, "gone.consul."),
+            ],
+        )]);
+        SrvResolver::new(Arc::new(StubSrvLookup(services)))
+            .with_hostname_resolver(Arc::new(StubHostnameResolver(hosts)))
+    }
+
+    #[tokio::test]
+    async fn srv_resolver_resolves_hostnames_directly() {
+        let resolver = srv_resolver();
+        assert_eq!(
+            resolver
+                .resolve_hostname("node2.consul:9050")
+                .await
+                .unwrap(),
+            [
+                "10.0.0.2:9050".parse::<SocketAddr>().unwrap(),
+                "[fd00::2]:9050".parse().unwrap()
+            ]
+        );
+        assert_eq!(
+            resolver.resolve_hostname("node1.consul").await.unwrap(),
+            ["10.0.0.1:9042".parse::<SocketAddr>().unwrap()]
+        );
+        resolver.resolve_hostname("gone.consul").await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn srv_resolver_expands_srv_records_by_priority_and_weight() {
+        let resolver = srv_resolver();
+        // The target which doesn't resolve is skipped
+        let expected: Vec<SocketAddr> = [
+            "10.0.0.1:19042",
+            "10.0.0.2:9043",
+            "[fd00::2]:9043",
+            "10.0.0.3:9044",
+        ]
+        .iter()
+        .map(|addr| addr.parse().unwrap())
+        .collect();
+        assert_eq!(
+            resolver
+                .resolve_hostname("_cql._tcp.scylla.service.consul")
+                .await
+                .unwrap(),
+            expected
+        );
+        resolver
+            .resolve_hostname("_cql._tcp.missing.service.consul")
+            .await
+            .unwrap_err();
+
+        // Contact points get the addresses of all targets
+        let mut contact_points = ContactPoints::new(
+            vec![KnownNode::Hostname(
+                "_cql._tcp.scylla.service.consul".to_owned(),
+            )],
+            Arc::new(resolver),
+            AddressFamilyPreference::Ipv4Only,
+            Duration::from_secs(10),
+        );
+        let (resolved, _) = contact_points.resolve().await;
+        assert_eq!(addresses(&resolved), [expected[0]]);
+        assert_eq!(resolved[0].fallback_addresses, [expected[1], expected[3]]);
+    }
+
+    #[test]
+    fn addresses_are_ordered_by_family_preference() {
+        let v4_1: SocketAddr = "10.0.0.1:9042".parse().unwrap();
+        let v4_2: SocketAddr = "10.0.0.2:9042".parse().unwrap();
+        let v6_1: SocketAddr = "[fd00::1]:9042".parse().unwrap();
+        let v6_2: SocketAddr = "[fd00::2]:9042".parse().unwrap();
+        let all = [v6_1, v6_2, v4_1, v6_1, v4_2];
+
+        assert_eq!(
+            AddressFamilyPreference::PreferIpv4.order(&all),
+            [v4_1, v6_1, v4_2, v6_2]
+        );
+        assert_eq!(
+            AddressFamilyPreference::PreferIpv6.order(&all),
+            [v6_1, v4_1, v6_2, v4_2]
+        );
+        assert_eq!(AddressFamilyPreference::Ipv4Only.order(&all), [v4_1, v4_2]);
+        assert_eq!(AddressFamilyPreference::Ipv6Only.order(&all), [v6_1, v6_2]);
+
+        assert_eq!(AddressFamilyPreference::PreferIpv4.order(&[v6_1]), [v6_1]);
+        assert_eq!(AddressFamilyPreference::PreferIpv6.order(&[v4_1]), [v4_1]);
+        assert!(AddressFamilyPreference::Ipv4Only.order(&[v6_1]).is_empty());
+        assert!(AddressFamilyPreference::Ipv6Only.order(&[v4_1]).is_empty());
+    }
+
+    #[test]
+    fn capabilities_from_supported() {
+        let options = |entries: &[(&str, &[&str])]| -> HashMap<String, Vec<String>> {
+            entries
+                .iter()
+                .map(|(key, values)| {
+                    (
+                        key.to_string(),
+                        values.iter().map(|v| v.to_string()).collect(),
+                    )
+                })
+                .collect()
+        };
+
+        let scylla = options(&[
+            ("PROTOCOL_VERSIONS", &["3/v3", "4/v4", "5/v5-beta"]),
+            ("CQL_VERSION", &["3.3.1"]),
+            ("COMPRESSION", &["lz4", "snappy"]),
+            ("SCYLLA_SHARD", &["3"]),
+            ("SCYLLA_NR_SHARDS", &["8"]),
+            ("SCYLLA_SHARDING_IGNORE_MSB", &["12"]),
+            ("SCYLLA_SHARD_AWARE_PORT", &["19042"]),
+            ("SCYLLA_SHARD_AWARE_PORT_SSL", &["19142"]),
+            ("SCYLLA_RATE_LIMIT_ERROR", &["ERROR_CODE=61440"]),
+        ]);
+        let capabilities = NodeCapabilities::from_supported(scylla.clone());
+        assert_eq!(
+            capabilities.protocol_versions,
+            ["3/v3", "4/v4", "5/v5-beta"]
+        );
+        assert_eq!(capabilities.cql_versions, ["3.3.1"]);
+        assert_eq!(capabilities.compression, ["lz4", "snappy"]);
+        assert_eq!(
+            capabilities.sharder,
+            Some(Sharder::new(8.try_into().unwrap(), 12))
+        );
+        assert_eq!(capabilities.shard_aware_port, Some(19042));
+        assert_eq!(capabilities.shard_aware_port_ssl, Some(19142));
+        assert_eq!(capabilities.protocol_features.rate_limit_error, Some(61440));
+        assert_eq!(capabilities.options, scylla);
+
+        let cassandra = options(&[("CQL_VERSION", &["3.4.5"]), ("COMPRESSION", &[])]);
+        let capabilities = NodeCapabilities::from_supported(cassandra);
+        assert!(capabilities.compression.is_empty());
+        assert!(capabilities.protocol_versions.is_empty());
+        assert_eq!(capabilities.sharder, None);
+        assert_eq!(capabilities.shard_aware_port, None);
+        assert_eq!(capabilities.protocol_features.rate_limit_error, None);
+    }
 }
@@ -255,8 +255,10 @@ impl PartitionerHasher for Murmur3PartitionerHasher {
         h1 += h2;
         h2 += h1;
 
-        Token {
-            value: (((h2.0 as i128) << 64) | h1.0 as i128) as i64,
+        // The lowest token is reserved, so the database maps it to the highest one.
+        match (((h2.0 as i128) << 64) | h1.0 as i128) as i64 {
+            i64::MIN => Token::MAX,
+            value => Token { value },
         }
     }
 }
@@ -321,6 +323,15 @@ impl PartitionerHasher for CDCPartitionerHasher {
     }
 }
 
+/// Calculates the token of the serialized partition key with the Murmur3 partitioner,
+/// which is the default partitioner of tables.
+///
+/// A partition key consisting of a single column is serialized as the value of the column.
+/// For compound partition keys, see [`calculate_token_for_partition_key`].
+pub fn murmur3_token(partition_key: &[u8]) -> Token {
+    Murmur3Partitioner.hash_one(partition_key)
+}
+
 /// Calculates the token for given partitioner and serialized partition key.
 ///
 /// The ordinary way to calculate token is based on a PreparedStatement
@@ -0,0 +1,415 @@
+//! Priority classes of requests sharing connections.
+//!
+//! Every request has a [`RequestPriority`], taken from its [execution profile](crate::transport::ExecutionProfile).
+//! By default priorities are ignored and all requests wait for a free stream id of a connection
+//! in a single queue. With [`SessionBuilder::priority_scheduling`](crate::transport::session_builder::GenericSessionBuilder::priority_scheduling)
+//! each connection dispatches waiting requests by their priority, so that e.g. maintenance scans
+//! run with [`RequestPriority::Low`] don't starve interactive traffic:
+//! - [`PriorityMode::Strict`] dispatches a request only if no request of a higher priority is waiting,
+//! - [`PriorityMode::Weighted`] shares the stream ids between the waiting classes in proportion to their weights.
+//!
+//! Additionally, the number of requests of a class in flight on a single connection can be capped.
+//! Queue depths and latencies of the classes are available with
+//! [`Metrics::priority_class_snapshot`](crate::transport::metrics::Metrics::priority_class_snapshot).
+
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+
+use crate::transport::metrics::Metrics;
+
+/// Priority class of a request, set in the execution profile with
+/// [`ExecutionProfileBuilder::priority`](crate::transport::execution_profile::ExecutionProfileBuilder::priority).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "config-file", derive(serde::Deserialize))]
+#[cfg_attr(feature = "config-file", serde(rename_all = "snake_case"))]
+pub enum RequestPriority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl RequestPriority {
+    /// All priorities, from the highest.
+    pub const ALL: [RequestPriority; 3] = [
+        RequestPriority::High,
+        RequestPriority::Normal,
+        RequestPriority::Low,
+    ];
+
+    pub(crate) fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl Display for RequestPriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RequestPriority::High => "high",
+            RequestPriority::Normal => "normal",
+            RequestPriority::Low => "low",
+        })
+    }
+}
+
+/// How a connection chooses which waiting request gets the next free stream id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityMode {
+    /// Requests of a class are dispatched only if no requests of higher classes are waiting.
+    Strict,
+    /// Stream ids are shared between the classes with waiting requests in proportion
+    /// to their weights, so that lower classes make progress under constant load.
+    /// Weights of 0 are treated as 1.
+    Weighted { high: u32, normal: u32, low: u32 },
+}
+
+/// Configuration of dispatching requests by their priority.
+///
+/// # Example
+/// ```
+/// use scylla::transport::priority::{PriorityScheduling, RequestPriority};
+///
+/// // Low priority requests get 1 of 11 stream ids freed while high priority
+/// // requests are waiting too, and at most 64 of them are in flight on a connection.
+/// let scheduling = PriorityScheduling::weighted(10, 5, 1)
+///     .max_in_flight(RequestPriority::Low, 64);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityScheduling {
+    mode: PriorityMode,
+    max_in_flight: [Option<usize>; 3],
+}
+
+impl PriorityScheduling {
+    /// Strict priority between the classes, see [`PriorityMode::Strict`].
+    pub fn strict() -> Self {
+        Self::new(PriorityMode::Strict)
+    }
+
+    /// Weighted priority between the classes, see [`PriorityMode::Weighted`].
+    pub fn weighted(high: u32, normal: u32, low: u32) -> Self {
+        Self::new(PriorityMode::Weighted { high, normal, low })
+    }
+
+    pub fn new(mode: PriorityMode) -> Self {
+        Self {
+            mode,
+            max_in_flight: [None; 3],
+        }
+    }
+
+    /// Caps the number of requests of the class in flight on a single connection.
+    /// Further requests of the class wait, even if there are free stream ids.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is 0.
+    pub fn max_in_flight(mut self, priority: RequestPriority, max: usize) -> Self {
+        assert!(max > 0, "in-flight cap must be larger than 0");
+        self.max_in_flight[priority.index()] = Some(max);
+        self
+    }
+
+    pub fn get_mode(&self) -> PriorityMode {
+        self.mode
+    }
+
+    pub fn get_max_in_flight(&self, priority: RequestPriority) -> Option<usize> {
+        self.max_in_flight[priority.index()]
+    }
+
+    fn weight(&self, priority: usize) -> f64 {
+        let weight = match self.mode {
+            PriorityMode::Strict => 1,
+            PriorityMode::Weighted { high, normal, low } => [high, normal, low][priority],
+        };
+        weight.max(1) as f64
+    }
+}
+
+/// Stream permit of a request, freeing the stream id when dropped.
+/// With priority scheduling enabled it also counts the request as in flight in its class.
+pub(crate) struct StreamPermit {
+    _permit: OwnedSemaphorePermit,
+    // Dropped after the permit, so that woken waiters can take it
+    _in_flight: Option<InFlightGuard>,
+    _counted: Option<CountedInFlight>,
+}
+
+impl From<OwnedSemaphorePermit> for StreamPermit {
+    fn from(permit: OwnedSemaphorePermit) -> Self {
+        Self {
+            _permit: permit,
+            _in_flight: None,
+            _counted: None,
+        }
+    }
+}
+
+impl StreamPermit {
+    /// Counts the request in [`Metrics::get_in_flight_requests`] until the permit is dropped.
+    pub(crate) fn counted_in(mut self, metrics: Arc<Metrics>) -> Self {
+        metrics.inc_in_flight_requests();
+        self._counted = Some(CountedInFlight(metrics));
+        self
+    }
+}
+
+struct CountedInFlight(Arc<Metrics>);
+
+impl Drop for CountedInFlight {
+    fn drop(&mut self) {
+        self.0.dec_in_flight_requests();
+    }
+}
+
+struct InFlightGuard {
+    scheduler: Arc<PriorityScheduler>,
+    priority: usize,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.scheduler.state.lock().unwrap().in_flight[self.priority] -= 1;
+        self.scheduler.notify.notify_waiters();
+    }
+}
+
+/// Dispatches requests of a connection waiting for stream ids by their priority.
+pub(crate) struct PriorityScheduler {
+    config: PriorityScheduling,
+    state: Mutex<SchedulerState>,
+    // Notified when a stream id is freed, or the set of waiting requests changes
+    notify: Notify,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    waiting: [usize; 3],
+    in_flight: [usize; 3],
+    // Weighted mode uses start-time fair queuing: a class is served in the order
+    // of virtual start times, which advance by the inverse of its weight.
+    finish: [f64; 3],
+    virtual_time: f64,
+}
+
+// Removes the request from the waiting ones, also when it's cancelled.
+struct WaitingGuard<'a> {
+    scheduler: &'a PriorityScheduler,
+    priority: usize,
+}
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.scheduler.state.lock().unwrap().waiting[self.priority] -= 1;
+        self.scheduler.notify.notify_waiters();
+    }
+}
+
+impl PriorityScheduler {
+    pub(crate) fn new(config: PriorityScheduling) -> Self {
+        Self {
+            config,
+            state: Default::default(),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Takes a stream permit if a request of the priority may be dispatched now.
+    pub(crate) fn try_acquire(
+        self: &Arc<Self>,
+        priority: RequestPriority,
+        permits: &Arc<Semaphore>,
+    ) -> Option<StreamPermit> {
+        let _waiting = self.start_waiting(priority);
+        self.try_dispatch(priority.index(), permits)
+    }
+
+    /// Waits until a request of the priority may be dispatched, and takes a stream permit.
+    pub(crate) async fn acquire(
+        self: &Arc<Self>,
+        priority: RequestPriority,
+        permits: &Arc<Semaphore>,
+    ) -> StreamPermit {
+        let _waiting = self.start_waiting(priority);
+        loop {
+            // Registered before checking, so that no notification is missed
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if let Some(permit) = self.try_dispatch(priority.index(), permits) {
+                return permit;
+            }
+            notified.await;
+        }
+    }
+
+    fn start_waiting(&self, priority: RequestPriority) -> WaitingGuard<'_> {
+        self.state.lock().unwrap().waiting[priority.index()] += 1;
+        WaitingGuard {
+            scheduler: self,
+            priority: priority.index(),
+        }
+    }
+
+    fn try_dispatch(
+        self: &Arc<Self>,
+        priority: usize,
+        permits: &Arc<Semaphore>,
+    ) -> Option<StreamPermit> {
+        let mut state = self.state.lock().unwrap();
+        if !self.may_dispatch(&state, priority) {
+            return None;
+        }
+        let permit = permits.clone().try_acquire_owned().ok()?;
+
+        let start = self.start_time(&state, priority);
+        state.virtual_time = start;
+        state.finish[priority] = start + 1.0 / self.config.weight(priority);
+        state.in_flight[priority] += 1;
+        drop(state);
+        // Lower classes may have been waiting for this request to be dispatched
+        self.notify.notify_waiters();
+
+        Some(StreamPermit {
+            _permit: permit,
+            _in_flight: Some(InFlightGuard {
+                scheduler: self.clone(),
+                priority,
+            }),
+            _counted: None,
+        })
+    }
+
+    fn below_cap(&self, state: &SchedulerState, priority: usize) -> bool {
+        match self.config.max_in_flight[priority] {
+            Some(max) => state.in_flight[priority] < max,
+            None => true,
+        }
+    }
+
+    fn start_time(&self, state: &SchedulerState, priority: usize) -> f64 {
+        state.finish[priority].max(state.virtual_time)
+    }
+
+    fn may_dispatch(&self, state: &SchedulerState, priority: usize) -> bool {
+        if !self.below_cap(state, priority) {
+            return false;
+        }
+        // Only classes whose requests could be dispatched now take precedence
+        let competing = (0..3)
+            .filter(|&other| other != priority)
+            .filter(|&other| state.waiting[other] > 0 && self.below_cap(state, other));
+        match self.config.mode {
+            PriorityMode::Strict => competing.into_iter().all(|other| other > priority),
+            PriorityMode::Weighted { .. } => {
+                let start = self.start_time(state, priority);
+                competing.into_iter().all(|other| {
+                    let other_start = self.start_time(state, other);
+                    start < other_start || (start == other_start && priority < other)
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PriorityScheduler, PriorityScheduling, RequestPriority, StreamPermit};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::{mpsc, Semaphore};
+
+    // Queues `count` requests of each class on a connection with a single stream id,
+    // in use by a normal priority request. Then completes the requests one by one
+    // and returns the order in which the classes were dispatched.
+    async fn dispatch_order(
+        config: PriorityScheduling,
+        count: usize,
+        classes: &[RequestPriority],
+    ) -> Vec<RequestPriority> {
+        let scheduler = Arc::new(PriorityScheduler::new(config));
+        let permits = Arc::new(Semaphore::new(1));
+        let mut in_flight = scheduler
+            .try_acquire(RequestPriority::Normal, &permits)
+            .unwrap();
+        let (dispatched_sender, mut dispatched) = mpsc::unbounded_channel();
+
+        let mut waiters = Vec::new();
+        for &priority in classes {
+            for _ in 0..count {
+                let scheduler = scheduler.clone();
+                let permits = permits.clone();
+                let dispatched_sender = dispatched_sender.clone();
+                waiters.push(tokio::spawn(async move {
+                    let permit: StreamPermit = scheduler.acquire(priority, &permits).await;
+                    dispatched_sender.send((priority, permit)).unwrap();
+                }));
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let mut order = Vec::new();
+        for _ in 0..count * classes.len() {
+            drop(in_flight);
+            let (priority, permit) = dispatched.recv().await.unwrap();
+            order.push(priority);
+            in_flight = permit;
+        }
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        order
+    }
+
+    #[tokio::test]
+    async fn strict_priority_dispatches_higher_classes_first() {
+        use RequestPriority::*;
+
+        let order = dispatch_order(PriorityScheduling::strict(), 3, &[Low, Normal, High]).await;
+        assert_eq!(
+            order,
+            [High, High, High, Normal, Normal, Normal, Low, Low, Low]
+        );
+    }
+
+    #[tokio::test]
+    async fn weighted_priority_shares_stream_ids() {
+        use RequestPriority::*;
+
+        let order = dispatch_order(PriorityScheduling::weighted(3, 1, 1), 6, &[Low, High]).await;
+        // Low priority requests make progress while high priority ones are waiting
+        assert_eq!(&order[..8], [High, Low, High, High, High, Low, High, High]);
+        assert_eq!(order.iter().filter(|&&p| p == Low).count(), 6);
+    }
+
+    #[tokio::test]
+    async fn in_flight_cap_lets_lower_classes_through() {
+        use RequestPriority::*;
+
+        let scheduler = Arc::new(PriorityScheduler::new(
+            PriorityScheduling::strict().max_in_flight(High, 1),
+        ));
+        let permits = Arc::new(Semaphore::new(10));
+
+        let high = scheduler.try_acquire(High, &permits).unwrap();
+        // The cap is reached, so a waiting high priority request doesn't block lower classes
+        assert!(scheduler.try_acquire(High, &permits).is_none());
+        let waiting_high = {
+            let (scheduler, permits) = (scheduler.clone(), permits.clone());
+            tokio::spawn(async move { scheduler.acquire(High, &permits).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(scheduler.try_acquire(Low, &permits).is_some());
+
+        // Completing the request in flight lets the next one through
+        drop(high);
+        let _high = tokio::time::timeout(Duration::from_secs(1), waiting_high)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(permits.available_permits(), 9);
+    }
+}
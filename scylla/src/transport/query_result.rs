@@ -1,7 +1,16 @@
-use crate::frame::response::cql_to_rust::{FromRow, FromRowError};
-use crate::frame::response::result::ColumnSpec;
+use crate::frame::response::cql_to_rust::{FromRow, FromRowError, FromRowNamed};
 use crate::frame::response::result::Row;
-use crate::transport::session::{IntoTypedRows, TypedRowIter};
+use crate::frame::response::result::SchemaChangeResult;
+use crate::frame::response::result::{
+    ColumnDecodeError, ColumnNameIndex, ColumnSpec, ColumnType, CqlValue, TableSpec,
+};
+use crate::frame::types::{Consistency, SerialConsistency};
+use crate::history::ExecutionHistory;
+#[cfg(feature = "arrow")]
+use crate::transport::arrow::ArrowConversionError;
+#[cfg(feature = "csv")]
+use crate::transport::csv::{CsvExportError, CsvOptions};
+use crate::transport::session::{AsTypedRows, IntoTypedRows, TypedRowIter, TypedRowRefIter};
 use bytes::Bytes;
 use thiserror::Error;
 use uuid::Uuid;
@@ -9,7 +18,7 @@ use uuid::Uuid;
 /// Result of a single query\
 /// Contains all rows returned by the database and some more information
 #[non_exhaustive]
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct QueryResult {
     /// Rows returned by the database.\
     /// Queries like `SELECT` will have `Some(Vec)`, while queries like `INSERT` will have `None`.\
@@ -25,6 +34,18 @@ pub struct QueryResult {
     pub col_specs: Vec<ColumnSpec>,
     /// The original size of the serialized rows in request
     pub serialized_size: usize,
+    /// Consistency the request was sent with, after applying the defaults of the execution
+    /// profile and the session, and the decisions of the retry policy
+    pub consistency: Option<Consistency>,
+    /// Serial consistency the request was sent with.
+    /// The session default is used only for conditional statements, so it's `None` for other ones
+    /// unless it was set on the statement or in its execution profile
+    pub serial_consistency: Option<SerialConsistency>,
+    pub(crate) execution_history: Option<Box<ExecutionHistory>>,
+    /// Schema change reported by the server, if the statement changed the schema
+    pub(crate) schema_change: Option<SchemaChangeResult>,
+    /// Errors of the columns which failed to decode, sorted by rows and columns
+    pub(crate) decode_errors: Vec<ColumnDecodeError>,
 }
 
 impl QueryResult {
@@ -37,6 +58,35 @@ impl QueryResult {
         }
     }
 
+    /// Returns the schema change reported by the server, if the statement changed the schema,
+    /// e.g. it was a `CREATE TABLE`. `None` for other statements, and for schema statements
+    /// which didn't change anything, e.g. because of `IF NOT EXISTS`.
+    pub fn schema_change(&self) -> Option<&SchemaChangeResult> {
+        self.schema_change.as_ref()
+    }
+
+    /// Returns the errors of the columns which failed to decode, sorted by rows and columns.\
+    /// Always empty unless the session was built with
+    /// [`ColumnDecodePolicy::Lenient`](crate::frame::response::result::ColumnDecodePolicy::Lenient).
+    pub fn decode_errors(&self) -> &[ColumnDecodeError] {
+        &self.decode_errors
+    }
+
+    /// Returns the values of the columns of the row with given index, or the errors
+    /// of the ones which failed to decode, see [`decode_errors()`](QueryResult::decode_errors).\
+    /// Returns `None` if there is no such row.
+    pub fn columns_lenient(
+        &self,
+        row: usize,
+    ) -> Option<Vec<Result<Option<CqlValue>, ColumnDecodeError>>> {
+        let columns = self.rows.as_deref()?.get(row)?;
+        Some(ColumnDecodeError::columns_lenient(
+            &self.decode_errors,
+            row,
+            columns,
+        ))
+    }
+
     /// Returns the received rows when present.\
     /// If `QueryResult.rows` is `None`, which means that this query is not supposed to return rows (e.g `INSERT`), returns an error.\
     /// Can return an empty `Vec`.
@@ -50,8 +100,39 @@ impl QueryResult {
     /// Returns the received rows parsed as the given type.\
     /// Equal to `rows()?.into_typed()`.\
     /// Fails when the query isn't of a type that could return rows, same as [`rows()`](QueryResult::rows).
-    pub fn rows_typed<RowT: FromRow>(self) -> Result<TypedRowIter<RowT>, RowsExpectedError> {
-        Ok(self.rows()?.into_typed())
+    pub fn rows_typed<RowT: FromRow>(mut self) -> Result<TypedRowIter<RowT>, RowsExpectedError> {
+        let decode_errors = std::mem::take(&mut self.decode_errors);
+        Ok(self.rows()?.into_typed().with_decode_errors(decode_errors))
+    }
+
+    /// Returns the received rows converted using the names of their columns,
+    /// e.g. to `HashMap<String, Option<CqlValue>>`.\
+    /// Fails when the query isn't of a type that could return rows, same as [`rows()`](QueryResult::rows),
+    /// or when two columns have the same name, e.g. because of an alias.
+    pub fn rows_named<RowT: FromRowNamed>(self) -> Result<Vec<RowT>, RowsNamedError> {
+        let col_specs = self.col_specs;
+        let decode_errors = self.decode_errors;
+        let rows = self.rows.ok_or(RowsExpectedError)?;
+        rows.into_iter()
+            .enumerate()
+            .map(|(i, row)| {
+                ColumnDecodeError::check_row(&decode_errors, i)
+                    .and_then(|()| RowT::from_named_row(row, &col_specs))
+                    .map_err(|error| RowTypeError::new::<RowT>(error, col_specs.clone()).into())
+            })
+            .collect()
+    }
+
+    /// Returns the received rows parsed as the given type, without consuming the result.\
+    /// Rows are parsed as they are iterated over, cloning only the values which are needed.\
+    /// Fails when the query isn't of a type that could return rows, same as [`rows()`](QueryResult::rows).
+    pub fn rows_typed_ref<RowT: FromRow>(
+        &self,
+    ) -> Result<TypedRowRefIter<'_, RowT>, RowsExpectedError> {
+        match &self.rows {
+            Some(rows) => Ok(rows.as_typed().with_decode_errors(&self.decode_errors)),
+            None => Err(RowsExpectedError),
+        }
     }
 
     /// Returns `Ok` for a result of a query that shouldn't contain any rows.\
@@ -73,8 +154,21 @@ impl QueryResult {
     /// Returns rows parsed as the given type.\
     /// When `QueryResult.rows` is `None`, returns 0 rows.\
     /// Equal to `rows_or_empty().into_typed::<RowT>()`.
-    pub fn rows_typed_or_empty<RowT: FromRow>(self) -> TypedRowIter<RowT> {
-        self.rows_or_empty().into_typed::<RowT>()
+    pub fn rows_typed_or_empty<RowT: FromRow>(mut self) -> TypedRowIter<RowT> {
+        let decode_errors = std::mem::take(&mut self.decode_errors);
+        self.rows_or_empty()
+            .into_typed::<RowT>()
+            .with_decode_errors(decode_errors)
+    }
+
+    /// Returns rows parsed as the given type, without consuming the result.\
+    /// When `QueryResult.rows` is `None`, returns 0 rows.
+    pub fn rows_typed_or_empty_ref<RowT: FromRow>(&self) -> TypedRowRefIter<'_, RowT> {
+        self.rows
+            .as_deref()
+            .unwrap_or_default()
+            .as_typed::<RowT>()
+            .with_decode_errors(&self.decode_errors)
     }
 
     /// Returns first row from the received rows.\
@@ -88,8 +182,10 @@ impl QueryResult {
 
     /// Returns first row from the received rows parsed as the given type.\
     /// When the first row is not available, returns an error.
-    pub fn first_row_typed<RowT: FromRow>(self) -> Result<RowT, FirstRowTypedError> {
-        Ok(self.first_row()?.into_typed()?)
+    pub fn first_row_typed<RowT: FromRow>(mut self) -> Result<RowT, FirstRowTypedError> {
+        let col_specs = std::mem::take(&mut self.col_specs);
+        let decode_errors = std::mem::take(&mut self.decode_errors);
+        Ok(parse_row(self.first_row()?, &decode_errors, col_specs)?)
     }
 
     /// Returns `Option<RowT>` containing the first of a result.\
@@ -101,10 +197,12 @@ impl QueryResult {
     /// Returns `Option<RowT>` containing the first of a result.\
     /// Fails when the query isn't of a type that could return rows, same as [`rows()`](QueryResult::rows).
     pub fn maybe_first_row_typed<RowT: FromRow>(
-        self,
+        mut self,
     ) -> Result<Option<RowT>, MaybeFirstRowTypedError> {
+        let col_specs = std::mem::take(&mut self.col_specs);
+        let decode_errors = std::mem::take(&mut self.decode_errors);
         match self.maybe_first_row()? {
-            Some(row) => Ok(Some(row.into_typed::<RowT>()?)),
+            Some(row) => Ok(Some(parse_row(row, &decode_errors, col_specs)?)),
             None => Ok(None),
         }
     }
@@ -123,8 +221,10 @@ impl QueryResult {
 
     /// Returns the only received row parsed as the given type.\
     /// Fails if the result is anything else than a single row.\
-    pub fn single_row_typed<RowT: FromRow>(self) -> Result<RowT, SingleRowTypedError> {
-        Ok(self.single_row()?.into_typed::<RowT>()?)
+    pub fn single_row_typed<RowT: FromRow>(mut self) -> Result<RowT, SingleRowTypedError> {
+        let col_specs = std::mem::take(&mut self.col_specs);
+        let decode_errors = std::mem::take(&mut self.decode_errors);
+        Ok(parse_row(self.single_row()?, &decode_errors, col_specs)?)
     }
 
     /// Returns a column specification for a column with given name, or None if not found
@@ -134,6 +234,291 @@ impl QueryResult {
             .enumerate()
             .find(|(_id, spec)| spec.name == name)
     }
+
+    /// Returns the index of the column with given name, matched as described in
+    /// [`ColumnNameIndex::get`](crate::ColumnNameIndex::get): quoted names match exactly,
+    /// others also match the name folded to lowercase.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        ColumnNameIndex::find(&self.col_specs, name)
+    }
+
+    /// Converts the received rows into an Arrow record batch, see the [`arrow`](crate::transport::arrow)
+    /// module for the mapping of the types.\
+    /// Fails when the query isn't of a type that could return rows, same as [`rows()`](QueryResult::rows).
+    #[cfg(feature = "arrow")]
+    pub fn to_arrow(&self) -> Result<arrow_array::RecordBatch, ArrowConversionError> {
+        let rows = self.rows.as_deref().ok_or(RowsExpectedError)?;
+        crate::transport::arrow::rows_to_record_batch(&self.col_specs, rows)
+    }
+
+    /// Writes the received rows as CSV, with a header with the names of the columns,
+    /// see the [`csv`](crate::transport::csv) module for the rendering of the values.\
+    /// Fails when the query isn't of a type that could return rows, same as [`rows()`](QueryResult::rows).
+    ///
+    /// # Example
+    /// ```rust
+    /// # use scylla::Session;
+    /// # use std::error::Error;
+    /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+    /// use scylla::transport::csv::CsvOptions;
+    ///
+    /// let mut out = std::io::stdout();
+    /// session
+    ///     .query_unpaged("SELECT a, b FROM ks.t", &[])
+    ///     .await?
+    ///     .write_csv(&mut out, CsvOptions::default().null_value("NULL"))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "csv")]
+    pub fn write_csv(
+        &self,
+        writer: &mut impl std::io::Write,
+        options: CsvOptions,
+    ) -> Result<(), CsvExportError> {
+        let rows = self.rows.as_deref().ok_or(RowsExpectedError)?;
+        crate::transport::csv::write_csv(writer, &self.col_specs, rows, &options)
+    }
+
+    /// Returns the warnings attached by the database to the response.\
+    /// Examples are an aggregation query used without a partition key,
+    /// or a batch whose size exceeds the warning threshold.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Returns the CQL tracing id of this query.\
+    /// It is `Some` only if tracing was enabled for the query,
+    /// see [`Session::get_tracing_info`](crate::Session::get_tracing_info) to fetch the trace.
+    pub fn tracing_id(&self) -> Option<Uuid> {
+        self.tracing_id
+    }
+
+    /// Returns the attempts made to execute the request, including failed retries
+    /// and speculative executions.\
+    /// It is `Some` only if collecting the history was enabled on the statement with
+    /// `set_history_collection`, e.g. [`Query::set_history_collection`](crate::query::Query::set_history_collection).
+    pub fn execution_history(&self) -> Option<&ExecutionHistory> {
+        self.execution_history.as_deref()
+    }
+
+    /// Returns a builder of a result with given columns and rows,
+    /// e.g. to test code mapping rows without querying the database.
+    ///
+    /// # Example
+    /// ```rust
+    /// use bytes::Bytes;
+    /// use scylla::frame::response::result::{ColumnType, CqlValue};
+    /// use scylla::QueryResult;
+    ///
+    /// let result = QueryResult::mock_builder()
+    ///     .column("id", ColumnType::Int)
+    ///     .column("name", ColumnType::Text)
+    ///     .row([Some(CqlValue::Int(1)), Some(CqlValue::Text("Alice".to_owned()))])
+    ///     .row([Some(CqlValue::Int(2)), None])
+    ///     .paging_state(Bytes::from_static(b"next page"))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let rows: Vec<(i32, Option<String>)> = result
+    ///     .rows_typed()
+    ///     .unwrap()
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(rows, [(1, Some("Alice".to_owned())), (2, None)]);
+    /// ```
+    pub fn mock_builder() -> QueryResultBuilder {
+        QueryResultBuilder::new()
+    }
+}
+
+/// Builds a [`QueryResult`], returned by [`QueryResult::mock_builder`].
+///
+/// A result without columns and rows is a result of a statement which doesn't return rows,
+/// such as `INSERT`. Once a column or a row is added, the result has rows, possibly none.
+#[derive(Debug, Default)]
+pub struct QueryResultBuilder {
+    rows: Option<Vec<Row>>,
+    col_specs: Vec<ColumnSpec>,
+    paging_state: Option<Bytes>,
+    tracing_id: Option<Uuid>,
+    warnings: Vec<String>,
+    serialized_size: usize,
+    schema_change: Option<SchemaChangeResult>,
+    decode_errors: Vec<ColumnDecodeError>,
+}
+
+impl QueryResultBuilder {
+    /// Creates a builder of a result of a statement which doesn't return rows.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a column with given name and type. Its table is left unnamed.
+    pub fn column(self, name: impl Into<String>, typ: ColumnType) -> Self {
+        self.col_spec(ColumnSpec {
+            table_spec: TableSpec {
+                ks_name: String::new(),
+                table_name: String::new(),
+            },
+            name: name.into(),
+            typ,
+        })
+    }
+
+    /// Adds columns with given names and types, same as calling [`column`](Self::column) for each of them.
+    pub fn columns(
+        self,
+        columns: impl IntoIterator<Item = (impl Into<String>, ColumnType)>,
+    ) -> Self {
+        columns
+            .into_iter()
+            .fold(self, |builder, (name, typ)| builder.column(name, typ))
+    }
+
+    /// Adds a column with given specification, which also names its table.
+    pub fn col_spec(mut self, col_spec: ColumnSpec) -> Self {
+        self.col_specs.push(col_spec);
+        self.rows.get_or_insert_with(Vec::new);
+        self
+    }
+
+    /// Adds a row with given values of the columns.
+    /// The values can be either [`CqlValue`]s, or `Option<CqlValue>`s with `None` for nulls.
+    pub fn row<V: Into<Option<CqlValue>>>(mut self, values: impl IntoIterator<Item = V>) -> Self {
+        self.rows
+            .get_or_insert_with(Vec::new)
+            .push(Row::new(values.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Adds rows, same as calling [`row`](Self::row) for each of them.
+    pub fn rows<R, V>(self, rows: impl IntoIterator<Item = R>) -> Self
+    where
+        R: IntoIterator<Item = V>,
+        V: Into<Option<CqlValue>>,
+    {
+        rows.into_iter().fold(self, |builder, row| builder.row(row))
+    }
+
+    /// Sets the paging state of the next page.
+    pub fn paging_state(mut self, paging_state: Bytes) -> Self {
+        self.paging_state = Some(paging_state);
+        self
+    }
+
+    /// Sets the CQL tracing id.
+    pub fn tracing_id(mut self, tracing_id: Uuid) -> Self {
+        self.tracing_id = Some(tracing_id);
+        self
+    }
+
+    /// Adds a warning attached by the database.
+    pub fn warning(mut self, warning: impl Into<String>) -> Self {
+        self.warnings.push(warning.into());
+        self
+    }
+
+    pub(crate) fn warnings(mut self, warnings: Vec<String>) -> Self {
+        self.warnings.extend(warnings);
+        self
+    }
+
+    // Rows received from the database, which have as many values as there are columns
+    pub(crate) fn received_rows(
+        mut self,
+        rows: Vec<Row>,
+        decode_errors: Vec<ColumnDecodeError>,
+        serialized_size: usize,
+    ) -> Self {
+        self.rows.get_or_insert_with(Vec::new).extend(rows);
+        self.decode_errors = decode_errors;
+        self.serialized_size = serialized_size;
+        self
+    }
+
+    pub(crate) fn schema_change(mut self, schema_change: SchemaChangeResult) -> Self {
+        self.schema_change = Some(schema_change);
+        self
+    }
+
+    /// Builds the result.\
+    /// Fails if a row has a different number of values than there are columns.
+    pub fn build(self) -> Result<QueryResult, ColumnCountMismatchError> {
+        let rows = self.rows.as_deref().unwrap_or_default();
+        if let Some((row, mismatched)) = rows
+            .iter()
+            .enumerate()
+            .find(|(_, row)| row.columns.len() != self.col_specs.len())
+        {
+            return Err(ColumnCountMismatchError {
+                row,
+                expected: self.col_specs.len(),
+                actual: mismatched.columns.len(),
+            });
+        }
+
+        Ok(QueryResult {
+            rows: self.rows,
+            warnings: self.warnings,
+            tracing_id: self.tracing_id,
+            paging_state: self.paging_state,
+            col_specs: self.col_specs,
+            serialized_size: self.serialized_size,
+            consistency: None,
+            serial_consistency: None,
+            execution_history: None,
+            schema_change: self.schema_change,
+            decode_errors: self.decode_errors,
+        })
+    }
+}
+
+// Parses the first row of a result, failing if any of its columns failed to decode
+fn parse_row<RowT: FromRow>(
+    row: Row,
+    decode_errors: &[ColumnDecodeError],
+    col_specs: Vec<ColumnSpec>,
+) -> Result<RowT, RowTypeError> {
+    ColumnDecodeError::check_row(decode_errors, 0)
+        .and_then(|()| RowT::from_row(row))
+        .map_err(|error| RowTypeError::new::<RowT>(error, col_specs))
+}
+
+/// Parsing a received row as the given type failed.\
+/// Tells which type the row was parsed as and which columns were received,
+/// so that a mismatch between the two can be spotted.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error(
+    "Parsing row as {rust_type} failed: {error}. Received columns: ({})",
+    display_columns(.col_specs)
+)]
+pub struct RowTypeError {
+    /// The error which occurred while parsing the row
+    #[source]
+    pub error: FromRowError,
+    /// Name of the type the row was parsed as
+    pub rust_type: &'static str,
+    /// Specification of the received columns
+    pub col_specs: Vec<ColumnSpec>,
+}
+
+impl RowTypeError {
+    pub(crate) fn new<RowT>(error: FromRowError, col_specs: Vec<ColumnSpec>) -> Self {
+        Self {
+            error,
+            rust_type: std::any::type_name::<RowT>(),
+            col_specs,
+        }
+    }
+}
+
+fn display_columns(col_specs: &[ColumnSpec]) -> String {
+    col_specs
+        .iter()
+        .map(|spec| format!("{}: {}", spec.name, spec.typ.cql_type_name()))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 /// [`QueryResult::rows()`](QueryResult::rows) or a similar function called on a bad QueryResult.\
@@ -162,6 +547,19 @@ pub struct RowsExpectedError;
 )]
 pub struct RowsNotExpectedError;
 
+/// [`QueryResultBuilder::build()`] called with a row which has a different number of values
+/// than there are columns.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("Row {row} has {actual} values, but there are {expected} columns")]
+pub struct ColumnCountMismatchError {
+    /// Index of the row
+    pub row: usize,
+    /// Number of the columns
+    pub expected: usize,
+    /// Number of the values of the row
+    pub actual: usize,
+}
+
 #[derive(Debug, Clone, Error, PartialEq, Eq)]
 pub enum FirstRowError {
     /// [`QueryResult::first_row()`](QueryResult::first_row) called on a bad QueryResult.\
@@ -191,7 +589,21 @@ pub enum FirstRowTypedError {
 
     /// Parsing row as the given type failed
     #[error(transparent)]
-    FromRowError(#[from] FromRowError),
+    FromRowError(#[from] RowTypeError),
+}
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum RowsNamedError {
+    /// [`QueryResult::rows_named()`](QueryResult::rows_named) called on a bad QueryResult.\
+    /// Expected `QueryResult.rows` to be `Some`, but it was `None`.\
+    /// `QueryResult.rows` is `Some` for queries that can return rows (e.g `SELECT`).\
+    /// It is `None` for queries that can't return rows (e.g `INSERT`).
+    #[error(transparent)]
+    RowsExpected(#[from] RowsExpectedError),
+
+    /// Converting a row failed
+    #[error(transparent)]
+    FromRowError(#[from] RowTypeError),
 }
 
 #[derive(Debug, Clone, Error, PartialEq, Eq)]
@@ -205,7 +617,7 @@ pub enum MaybeFirstRowTypedError {
 
     /// Parsing row as the given type failed
     #[error(transparent)]
-    FromRowError(#[from] FromRowError),
+    FromRowError(#[from] RowTypeError),
 }
 
 #[derive(Debug, Clone, Error, PartialEq, Eq)]
@@ -237,7 +649,7 @@ pub enum SingleRowTypedError {
 
     /// Parsing row as the given type failed
     #[error(transparent)]
-    FromRowError(#[from] FromRowError),
+    FromRowError(#[from] RowTypeError),
 }
 
 impl From<FirstRowError> for FirstRowTypedError {
@@ -308,6 +720,11 @@ mod tests {
             paging_state: None,
             col_specs: vec![column_spec],
             serialized_size: 0,
+            consistency: None,
+            serial_consistency: None,
+            execution_history: None,
+            schema_change: None,
+            decode_errors: vec![],
         }
     }
 
@@ -372,6 +789,42 @@ mod tests {
         assert_eq!(rows2, vec![(0,), (1,)]);
     }
 
+    #[test]
+    fn rows_named_test() {
+        use std::collections::BTreeMap;
+        type NamedRow = BTreeMap<String, Option<CqlValue>>;
+
+        assert_eq!(
+            make_not_rows_query_result().rows_named::<NamedRow>(),
+            Err(RowsNamedError::RowsExpected(RowsExpectedError))
+        );
+
+        let rows = make_rows_query_result(2).rows_named::<NamedRow>().unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                NamedRow::from([("column0".to_string(), Some(CqlValue::Int(0)))]),
+                NamedRow::from([("column0".to_string(), Some(CqlValue::Int(1)))]),
+            ]
+        );
+
+        let mut res = make_rows_query_result(1);
+        res.col_specs.push(res.col_specs[0].clone());
+        res.rows.as_mut().unwrap()[0].columns.push(None);
+        match res.rows_named::<NamedRow>() {
+            Err(RowsNamedError::FromRowError(err)) => {
+                assert_eq!(
+                    err.error,
+                    FromRowError::DuplicateColumnName {
+                        name: "column0".to_string(),
+                        column: 1
+                    }
+                )
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
     #[test]
     fn result_not_rows_test() {
         assert_eq!(make_not_rows_query_result().result_not_rows(), Ok(()));
@@ -428,6 +881,37 @@ mod tests {
         assert_eq!(rows2, vec![(0,), (1,)]);
     }
 
+    #[test]
+    fn rows_typed_ref_test() {
+        assert!(make_not_rows_query_result()
+            .rows_typed_ref::<(i32,)>()
+            .is_err());
+        assert_eq!(
+            make_not_rows_query_result()
+                .rows_typed_or_empty_ref::<(i32,)>()
+                .count(),
+            0
+        );
+
+        for rows_num in 0..3 {
+            let result = make_rows_query_result(rows_num);
+            let borrowed: Vec<_> = result.rows_typed_ref::<(i32,)>().unwrap().collect();
+            let borrowed_or_empty: Vec<_> = result.rows_typed_or_empty_ref::<(i32,)>().collect();
+            // The result is still usable after being iterated over
+            assert_eq!(result.col_specs.len(), 1);
+            let consumed: Vec<_> = result.rows_typed::<(i32,)>().unwrap().collect();
+
+            assert_eq!(borrowed, consumed);
+            assert_eq!(borrowed_or_empty, consumed);
+        }
+
+        let result = make_string_rows_query_result(2);
+        let borrowed: Vec<_> = result.rows_typed_ref::<(i32,)>().unwrap().collect();
+        let consumed: Vec<_> = result.rows_typed::<(i32,)>().unwrap().collect();
+        assert!(borrowed.iter().all(Result::is_err));
+        assert_eq!(borrowed, consumed);
+    }
+
     #[test]
     fn first_row_test() {
         assert_eq!(
@@ -587,4 +1071,166 @@ mod tests {
             Err(SingleRowTypedError::FromRowError(_))
         ));
     }
+
+    #[test]
+    fn row_type_error_describes_mismatch() {
+        let err = match make_string_rows_query_result(1).single_row_typed::<(i32,)>() {
+            Err(SingleRowTypedError::FromRowError(err)) => err,
+            other => panic!("Expected a row type error, got {:?}", other),
+        };
+        assert_eq!(err.rust_type, "(i32,)");
+        assert_eq!(err.col_specs, make_not_rows_query_result().col_specs);
+        assert_eq!(
+            err.error,
+            FromRowError::BadCqlVal {
+                err: crate::cql_to_rust::FromCqlValError::BadCqlType,
+                column: 0,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Parsing row as (i32,) failed: {}. Received columns: (column0: int)",
+                err.error
+            )
+        );
+
+        match make_string_rows_query_result(1).maybe_first_row_typed::<(i32, i32)>() {
+            Err(MaybeFirstRowTypedError::FromRowError(err)) => {
+                assert_eq!(err.rust_type, "(i32, i32)");
+                assert_eq!(
+                    err.error,
+                    FromRowError::WrongRowSize {
+                        expected: 2,
+                        actual: 1
+                    }
+                );
+            }
+            other => panic!("Expected a row type error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn mock_builder_builds_results() {
+        let tracing_id = Uuid::from_u128(1);
+        let result = QueryResult::mock_builder()
+            .column("a", ColumnType::Int)
+            .column("b", ColumnType::Text)
+            .row([CqlValue::Int(1), CqlValue::Text("x".to_owned())])
+            .row([None, None])
+            .paging_state(Bytes::from_static(&[1, 2]))
+            .tracing_id(tracing_id)
+            .warning("careful")
+            .build()
+            .unwrap();
+
+        assert_eq!(result.col_specs.len(), 2);
+        assert_eq!(result.col_specs[1].name, "b");
+        assert_eq!(result.column_index("b"), Some(1));
+        assert_eq!(result.paging_state, Some(Bytes::from_static(&[1, 2])));
+        assert_eq!(result.tracing_id(), Some(tracing_id));
+        assert_eq!(result.warnings(), ["careful"]);
+        assert_eq!(
+            result.rows.unwrap(),
+            [
+                Row::new(vec![
+                    Some(CqlValue::Int(1)),
+                    Some(CqlValue::Text("x".to_owned()))
+                ]),
+                Row::new(vec![None, None]),
+            ]
+        );
+
+        // Without columns and rows the result is of a statement which doesn't return rows
+        let not_rows = QueryResult::mock_builder().build().unwrap();
+        assert_eq!(not_rows.result_not_rows(), Ok(()));
+        let empty = QueryResult::mock_builder()
+            .column("a", ColumnType::Int)
+            .build()
+            .unwrap();
+        assert_eq!(empty.rows_num(), Ok(0));
+    }
+
+    #[test]
+    fn mock_builder_checks_row_length() {
+        let error = QueryResult::mock_builder()
+            .columns([("a", ColumnType::Int), ("b", ColumnType::Int)])
+            .row([CqlValue::Int(1), CqlValue::Int(2)])
+            .row([CqlValue::Int(3)])
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            ColumnCountMismatchError {
+                row: 1,
+                expected: 2,
+                actual: 1
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Row 1 has 1 values, but there are 2 columns"
+        );
+    }
+    #[test]
+    fn rows_with_undecodable_columns_fail_to_parse() {
+        // The value of the second row failed to decode, so it's a null
+        let mut result = make_rows_query_result(3);
+        result.rows.as_mut().unwrap()[1].columns[0] = None;
+        let error = ColumnDecodeError {
+            row: 1,
+            index: 0,
+            column_name: "column0".to_string(),
+            column_type: ColumnType::Int,
+            raw_len: 3,
+            reason: "bad int".to_string(),
+        };
+        result.decode_errors = vec![error.clone()];
+        let undecodable = FromRowError::UndecodableColumn(Box::new(error.clone()));
+
+        assert_eq!(result.decode_errors(), std::slice::from_ref(&error));
+        assert_eq!(result.columns_lenient(1), Some(vec![Err(error.clone())]));
+        assert_eq!(
+            result.columns_lenient(2),
+            Some(vec![Ok(Some(CqlValue::Int(2)))])
+        );
+        assert_eq!(result.columns_lenient(3), None);
+
+        let expected = vec![Ok((Some(0),)), Err(undecodable.clone()), Ok((Some(2),))];
+        let rows: Vec<_> = result.rows_typed_ref::<(Option<i32>,)>().unwrap().collect();
+        assert_eq!(rows, expected);
+        let rows: Vec<_> = result.rows_typed_or_empty_ref::<(Option<i32>,)>().collect();
+        assert_eq!(rows, expected);
+        let rows: Vec<_> = result
+            .clone()
+            .rows_typed_or_empty::<(Option<i32>,)>()
+            .collect();
+        assert_eq!(rows, expected);
+        let rows: Vec<_> = result
+            .clone()
+            .rows_typed::<(Option<i32>,)>()
+            .unwrap()
+            .collect();
+        assert_eq!(rows, expected);
+        assert!(matches!(
+            result.clone().rows_named::<std::collections::BTreeMap<String, Option<CqlValue>>>(),
+            Err(RowsNamedError::FromRowError(RowTypeError { error, .. })) if error == undecodable
+        ));
+
+        // Only the first row matters for the accessors of single rows
+        assert_eq!(result.clone().first_row_typed::<(i32,)>(), Ok((0,)));
+        result.decode_errors[0].row = 0;
+        let undecodable =
+            FromRowError::UndecodableColumn(Box::new(result.decode_errors[0].clone()));
+        let row_type_error =
+            RowTypeError::new::<(Option<i32>,)>(undecodable, result.col_specs.clone());
+        assert_eq!(
+            result.clone().first_row_typed::<(Option<i32>,)>(),
+            Err(FirstRowTypedError::FromRowError(row_type_error.clone()))
+        );
+        assert_eq!(
+            result.maybe_first_row_typed::<(Option<i32>,)>(),
+            Err(MaybeFirstRowTypedError::FromRowError(row_type_error))
+        );
+    }
 }
@@ -0,0 +1,193 @@
+//! Collapsing concurrent re-preparations of a statement on a node into a single PREPARE.
+//!
+//! When a node forgets a prepared statement (e.g. after a restart), every request
+//! in flight with that statement gets `Unprepared` back. Without coordination each
+//! of them would send its own PREPARE. Instead, the first one prepares the statement
+//! and the others wait for its result. Requests sent before the statement got
+//! re-prepared, whose `Unprepared` responses arrive only afterwards, don't prepare
+//! it again either.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use tokio::sync::OnceCell;
+use tokio::time::Instant;
+
+use crate::transport::errors::QueryError;
+use crate::transport::metrics::Metrics;
+
+/// Number of recently re-prepared statements remembered by a node.
+const MAX_REMEMBERED_STATEMENTS: usize = 1024;
+
+type Repreparation = Arc<OnceCell<Result<(), QueryError>>>;
+
+/// Re-preparations of statements on a single node, shared by all connections to the node.
+#[derive(Debug)]
+pub(crate) struct Repreparations {
+    state: Mutex<RepreparationsState>,
+    metrics: Option<Arc<Metrics>>,
+}
+
+#[derive(Debug, Default)]
+struct RepreparationsState {
+    in_flight: HashMap<Bytes, Repreparation>,
+    // When the statements were last re-prepared.
+    prepared_at: HashMap<Bytes, Instant>,
+}
+
+impl Repreparations {
+    pub(crate) fn new(metrics: Option<Arc<Metrics>>) -> Self {
+        Self {
+            state: Mutex::new(RepreparationsState::default()),
+            metrics,
+        }
+    }
+
+    /// Re-prepares the statement with the given id using `prepare`, after a request
+    /// sent at `sent_at` got `Unprepared` in response.
+    ///
+    /// If the statement is already being re-prepared, waits for the result of that instead.
+    /// If it was re-prepared after `sent_at`, returns immediately: the response
+    /// is outdated and the request can just be sent again.
+    pub(crate) async fn reprepare<F, Fut>(
+        &self,
+        id: &Bytes,
+        sent_at: Instant,
+        prepare: F,
+    ) -> Result<(), QueryError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(), QueryError>>,
+    {
+        let repreparation = {
+            let mut state = self.state.lock().unwrap();
+            if let Some(prepared_at) = state.prepared_at.get(id) {
+                if *prepared_at > sent_at {
+                    return Ok(());
+                }
+            }
+            state.in_flight.entry(id.clone()).or_default().clone()
+        };
+
+        // If the request preparing the statement is cancelled,
+        // one of the waiting requests takes over.
+        let result = repreparation
+            .get_or_init(|| async {
+                if let Some(metrics) = &self.metrics {
+                    metrics.inc_reprepares();
+                }
+                let result = prepare().await;
+                if result.is_ok() {
+                    self.state
+                        .lock()
+                        .unwrap()
+                        .remember_prepared(id.clone(), Instant::now());
+                }
+                result
+            })
+            .await
+            .clone();
+
+        let mut state = self.state.lock().unwrap();
+        if let Some(current) = state.in_flight.get(id) {
+            if Arc::ptr_eq(current, &repreparation) {
+                state.in_flight.remove(id);
+            }
+        }
+
+        result
+    }
+}
+
+impl RepreparationsState {
+    fn remember_prepared(&mut self, id: Bytes, prepared_at: Instant) {
+        if self.prepared_at.len() >= MAX_REMEMBERED_STATEMENTS
+            && !self.prepared_at.contains_key(&id)
+        {
+            let oldest = self
+                .prepared_at
+                .iter()
+                .min_by_key(|(_, prepared_at)| **prepared_at)
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                self.prepared_at.remove(&oldest);
+            }
+        }
+        self.prepared_at.insert(id, prepared_at);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Repreparations;
+    use crate::transport::errors::QueryError;
+    use crate::transport::metrics::Metrics;
+    use bytes::Bytes;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::time::Instant;
+
+    #[tokio::test]
+    async fn concurrent_repreparations_are_collapsed() {
+        let metrics = Arc::new(Metrics::new());
+        let repreparations = Repreparations::new(Some(metrics.clone()));
+        let prepares = AtomicUsize::new(0);
+        let id = Bytes::from_static(&[1, 2, 3]);
+
+        let sent_at = Instant::now();
+        let prepare = || async {
+            prepares.fetch_add(1, Ordering::Relaxed);
+            tokio::task::yield_now().await;
+            Ok(())
+        };
+        let results = futures::future::join_all(
+            (0..100).map(|_| repreparations.reprepare(&id, sent_at, prepare)),
+        )
+        .await;
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(prepares.load(Ordering::Relaxed), 1);
+
+        // Responses to requests sent before the statement got re-prepared are outdated.
+        repreparations
+            .reprepare(&id, sent_at, prepare)
+            .await
+            .unwrap();
+        assert_eq!(prepares.load(Ordering::Relaxed), 1);
+
+        // A request sent afterwards means that the node forgot the statement again.
+        repreparations
+            .reprepare(&id, Instant::now(), prepare)
+            .await
+            .unwrap();
+        assert_eq!(prepares.load(Ordering::Relaxed), 2);
+        assert_eq!(metrics.get_reprepares(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_repreparation_is_shared_and_not_remembered() {
+        let repreparations = Repreparations::new(None);
+        let prepares = AtomicUsize::new(0);
+        let id = Bytes::from_static(&[4, 5, 6]);
+
+        let sent_at = Instant::now();
+        let prepare = || async {
+            prepares.fetch_add(1, Ordering::Relaxed);
+            tokio::task::yield_now().await;
+            Err(QueryError::ProtocolError("prepare failed"))
+        };
+        let results = futures::future::join_all(
+            (0..10).map(|_| repreparations.reprepare(&id, sent_at, prepare)),
+        )
+        .await;
+        assert!(results.iter().all(Result::is_err));
+        assert_eq!(prepares.load(Ordering::Relaxed), 1);
+
+        repreparations
+            .reprepare(&id, sent_at, prepare)
+            .await
+            .unwrap_err();
+        assert_eq!(prepares.load(Ordering::Relaxed), 2);
+    }
+}
@@ -2,10 +2,14 @@
 //! To decide when to retry a query the `Session` can use any object which implements
 //! the `RetryPolicy` trait
 
+use std::time::Duration;
+
 use crate::frame::types::Consistency;
-use crate::transport::errors::{DbError, QueryError, WriteType};
+use crate::transport::errors::{DbError, OperationType, QueryError, WriteType};
+use crate::transport::node::NodeRef;
 
 /// Information about a failed query
+#[non_exhaustive]
 pub struct QueryInfo<'a> {
     /// The error with which the query failed
     pub error: &'a QueryError,
@@ -13,18 +17,47 @@ pub struct QueryInfo<'a> {
     /// If set to `true` we can be sure that it is idempotent\
     /// If set to `false` it is unknown whether it is idempotent
     pub is_idempotent: bool,
-    /// Consistency with which the query failed
+    /// Consistency of the query, before any changes made by the retry policy
     pub consistency: Consistency,
+    /// Consistency the failed attempt was sent with, which differs from `consistency`
+    /// if the retry policy decided to change it
+    pub attempt_consistency: Consistency,
+    /// Number of the failed attempt within the retry session, starting from 1.\
+    /// Each speculative execution has its own retry session, and so does every page
+    /// fetched by a paging iterator.
+    pub attempt_number: usize,
+    /// Time elapsed since the first attempt was started - of the whole request,
+    /// or of the page for paging iterators
+    pub elapsed_since_first_attempt: Duration,
+    /// Node to which the failed attempt was sent
+    pub node: Option<NodeRef<'a>>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub enum RetryDecision {
-    RetrySameNode(Option<Consistency>), // None means that the same consistency should be used as before
-    RetryNextNode(Option<Consistency>), // ditto
-    DontRetry,
-    IgnoreWriteError,
+impl<'a> QueryInfo<'a> {
+    /// Creates information about the first attempt of a query failing with `error`,
+    /// sent to an unknown node, e.g. to test a retry policy.
+    /// The other fields can be set afterwards.
+    pub fn new(error: &'a QueryError, is_idempotent: bool, consistency: Consistency) -> Self {
+        Self {
+            error,
+            is_idempotent,
+            consistency,
+            attempt_consistency: consistency,
+            attempt_number: 1,
+            elapsed_since_first_attempt: Duration::ZERO,
+            node: None,
+        }
+    }
+
+    /// Whether the failed attempt was the first one, i.e. the query wasn't retried yet.
+    pub fn is_first_attempt(&self) -> bool {
+        self.attempt_number <= 1
+    }
 }
 
+// Defined in scylla-cql, so that decisions can be recorded in the history attached to errors
+pub use scylla_cql::execution_history::RetryDecision;
+
 /// Specifies a policy used to decide when to retry a query
 pub trait RetryPolicy: std::fmt::Debug + Send + Sync {
     /// Called for each new query, starts a session of deciding about retries
@@ -112,19 +145,18 @@ impl RetryPolicy for DefaultRetryPolicy {
     }
 }
 
-pub struct DefaultRetrySession {
-    was_unavailable_retry: bool,
-    was_read_timeout_retry: bool,
-    was_write_timeout_retry: bool,
-}
+/// Decides based on the error and the number of the attempt only, so it's stateless.
+pub struct DefaultRetrySession;
 
 impl DefaultRetrySession {
+    /// How many times a read rejected by the rate limit is retried.
+    const MAX_RATE_LIMIT_RETRIES: usize = 3;
+    /// Delay before the first retry of a read rejected by the rate limit,
+    /// doubled before each subsequent one.
+    const RATE_LIMIT_BASE_DELAY: Duration = Duration::from_millis(50);
+
     pub fn new() -> DefaultRetrySession {
-        DefaultRetrySession {
-            was_unavailable_retry: false,
-            was_read_timeout_retry: false,
-            was_write_timeout_retry: false,
-        }
+        DefaultRetrySession
     }
 }
 
@@ -155,18 +187,17 @@ impl RetrySession for DefaultRetrySession {
             // Unavailable - the current node believes that not enough nodes
             // are alive to satisfy specified consistency requirements.
             // Maybe this node has network problems - try a different one.
-            // Perform at most one retry - it's unlikely that two nodes
+            // Retry only after the first attempt - it's unlikely that two nodes
             // have network problems at the same time
             QueryError::DbError(DbError::Unavailable { .. }, _) => {
-                if !self.was_unavailable_retry {
-                    self.was_unavailable_retry = true;
+                if query_info.is_first_attempt() {
                     RetryDecision::RetryNextNode(None)
                 } else {
                     RetryDecision::DontRetry
                 }
             }
             // ReadTimeout - coordinator didn't receive enough replies in time.
-            // Retry at most once and only if there were actually enough replies
+            // Retry only after the first attempt and only if there were actually enough replies
             // to satisfy consistency but they were all just checksums (data_present == false).
             // This happens when the coordinator picked replicas that were overloaded/dying.
             // Retried request should have some useful response because the node will detect
@@ -180,23 +211,21 @@ impl RetrySession for DefaultRetrySession {
                 },
                 _,
             ) => {
-                if !self.was_read_timeout_retry && received >= required && !*data_present {
-                    self.was_read_timeout_retry = true;
+                if query_info.is_first_attempt() && received >= required && !*data_present {
                     RetryDecision::RetrySameNode(None)
                 } else {
                     RetryDecision::DontRetry
                 }
             }
             // Write timeout - coordinator didn't receive enough replies in time.
-            // Retry at most once and only for BatchLog write.
+            // Retry only after the first attempt and only for BatchLog write.
             // Coordinator probably didn't detect the nodes as dead.
             // By the time we retry they should be detected as dead.
             QueryError::DbError(DbError::WriteTimeout { write_type, .. }, _) => {
-                if !self.was_write_timeout_retry
+                if query_info.is_first_attempt()
                     && query_info.is_idempotent
                     && *write_type == WriteType::BatchLog
                 {
-                    self.was_write_timeout_retry = true;
                     RetryDecision::RetrySameNode(None)
                 } else {
                     RetryDecision::DontRetry
@@ -204,33 +233,58 @@ impl RetrySession for DefaultRetrySession {
             }
             // The node is still bootstrapping it can't execute the query, we should try another one
             QueryError::DbError(DbError::IsBootstrapping, _) => RetryDecision::RetryNextNode(None),
+            // The per-partition rate limit was exceeded, so the read wasn't executed.
+            // Back off exponentially, so that the partition's rate drops below the limit.
+            // Writes aren't retried, as retrying them would only add to the rate.
+            QueryError::DbError(
+                DbError::RateLimitReached {
+                    op_type: OperationType::Read,
+                    ..
+                },
+                _,
+            ) => {
+                if query_info.attempt_number <= Self::MAX_RATE_LIMIT_RETRIES {
+                    let retries_so_far = query_info.attempt_number.saturating_sub(1) as u32;
+                    let delay = Self::RATE_LIMIT_BASE_DELAY * 2u32.pow(retries_so_far);
+                    RetryDecision::RetryNextNodeAfter(None, delay)
+                } else {
+                    RetryDecision::DontRetry
+                }
+            }
             // Connection to the contacted node is overloaded, try another one
-            QueryError::UnableToAllocStreamId => RetryDecision::RetryNextNode(None),
+            QueryError::UnableToAllocStreamId | QueryError::ConnectionBusy => {
+                RetryDecision::RetryNextNode(None)
+            }
             // In all other cases propagate the error to the user
             _ => RetryDecision::DontRetry,
         }
     }
 
-    fn reset(&mut self) {
-        *self = DefaultRetrySession::new();
-    }
+    fn reset(&mut self) {}
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{DefaultRetryPolicy, QueryInfo, RetryDecision, RetryPolicy};
+    use super::{DefaultRetryPolicy, QueryInfo, RetryDecision, RetryPolicy, RetrySession};
     use crate::statement::Consistency;
-    use crate::transport::errors::{BadQuery, DbError, QueryError, WriteType};
+    use crate::transport::errors::{BadQuery, DbError, OperationType, QueryError, WriteType};
     use bytes::Bytes;
     use std::io::ErrorKind;
     use std::sync::Arc;
+    use std::time::Duration;
 
     fn make_query_info(error: &QueryError, is_idempotent: bool) -> QueryInfo<'_> {
-        QueryInfo {
-            error,
-            is_idempotent,
-            consistency: Consistency::One,
-        }
+        QueryInfo::new(error, is_idempotent, Consistency::One)
+    }
+
+    fn make_nth_query_info(
+        error: &QueryError,
+        is_idempotent: bool,
+        attempt_number: usize,
+    ) -> QueryInfo<'_> {
+        let mut query_info = make_query_info(error, is_idempotent);
+        query_info.attempt_number = attempt_number;
+        query_info
     }
 
     // Asserts that default policy never retries for this Error
@@ -290,11 +344,12 @@ mod tests {
             default_policy_assert_never_retries(QueryError::DbError(dberror, String::new()));
         }
 
-        default_policy_assert_never_retries(QueryError::BadQuery(BadQuery::Other(
-            "Length of provided values must be equal to number of batch statements \
-                        (got 1 values, 2 statements)"
-                .to_owned(),
-        )));
+        default_policy_assert_never_retries(QueryError::BadQuery(
+            BadQuery::BatchValuesCountMismatch {
+                values_count: 1,
+                statements_count: 2,
+            },
+        ));
         default_policy_assert_never_retries(QueryError::ProtocolError("test"));
     }
 
@@ -345,6 +400,49 @@ mod tests {
         );
     }
 
+    // Reads rejected by the rate limit are retried with backoff no matter the idempotence, writes never
+    #[test]
+    fn default_rate_limit_reached() {
+        let rate_limit_error = |op_type| {
+            QueryError::DbError(
+                DbError::RateLimitReached {
+                    op_type,
+                    rejected_by_coordinator: true,
+                },
+                String::new(),
+            )
+        };
+
+        let read_error = rate_limit_error(OperationType::Read);
+        for is_idempotent in [false, true] {
+            let mut policy = DefaultRetryPolicy::new().new_session();
+            for (attempt_number, delay_ms) in [(1, 50), (2, 100), (3, 200)] {
+                assert_eq!(
+                    policy.decide_should_retry(make_nth_query_info(
+                        &read_error,
+                        is_idempotent,
+                        attempt_number
+                    )),
+                    RetryDecision::RetryNextNodeAfter(None, Duration::from_millis(delay_ms))
+                );
+            }
+            assert_eq!(
+                policy.decide_should_retry(make_nth_query_info(&read_error, is_idempotent, 4)),
+                RetryDecision::DontRetry
+            );
+
+            // The backoff starts over for the next query
+            policy.reset();
+            assert_eq!(
+                policy.decide_should_retry(make_query_info(&read_error, is_idempotent)),
+                RetryDecision::RetryNextNodeAfter(None, Duration::from_millis(50))
+            );
+        }
+
+        default_policy_assert_never_retries(rate_limit_error(OperationType::Write));
+        default_policy_assert_never_retries(rate_limit_error(OperationType::Other(2)));
+    }
+
     // On Unavailable error we retry one time no matter the idempotence
     #[test]
     fn default_unavailable() {
@@ -363,7 +461,7 @@ mod tests {
             RetryDecision::RetryNextNode(None)
         );
         assert_eq!(
-            policy_not_idempotent.decide_should_retry(make_query_info(&error, false)),
+            policy_not_idempotent.decide_should_retry(make_nth_query_info(&error, false, 2)),
             RetryDecision::DontRetry
         );
 
@@ -373,7 +471,7 @@ mod tests {
             RetryDecision::RetryNextNode(None)
         );
         assert_eq!(
-            policy_idempotent.decide_should_retry(make_query_info(&error, true)),
+            policy_idempotent.decide_should_retry(make_nth_query_info(&error, true, 2)),
             RetryDecision::DontRetry
         );
     }
@@ -399,7 +497,7 @@ mod tests {
             RetryDecision::RetrySameNode(None)
         );
         assert_eq!(
-            policy.decide_should_retry(make_query_info(&enough_responses_no_data, false)),
+            policy.decide_should_retry(make_nth_query_info(&enough_responses_no_data, false, 2)),
             RetryDecision::DontRetry
         );
 
@@ -410,7 +508,7 @@ mod tests {
             RetryDecision::RetrySameNode(None)
         );
         assert_eq!(
-            policy.decide_should_retry(make_query_info(&enough_responses_no_data, true)),
+            policy.decide_should_retry(make_nth_query_info(&enough_responses_no_data, true, 2)),
             RetryDecision::DontRetry
         );
 
@@ -494,7 +592,7 @@ mod tests {
             RetryDecision::RetrySameNode(None)
         );
         assert_eq!(
-            policy.decide_should_retry(make_query_info(&good_write_type, true)),
+            policy.decide_should_retry(make_nth_query_info(&good_write_type, true, 2)),
             RetryDecision::DontRetry
         );
 
@@ -523,4 +621,82 @@ mod tests {
             RetryDecision::DontRetry
         );
     }
+
+    // Retries on the next node at most `max_retries` times, and only
+    // if the request hasn't been running for longer than `budget`.
+    struct BudgetRetrySession {
+        max_retries: usize,
+        budget: Duration,
+    }
+
+    impl RetrySession for BudgetRetrySession {
+        fn decide_should_retry(&mut self, query_info: QueryInfo) -> RetryDecision {
+            if query_info.attempt_number <= self.max_retries
+                && query_info.elapsed_since_first_attempt < self.budget
+            {
+                RetryDecision::RetryNextNode(None)
+            } else {
+                RetryDecision::DontRetry
+            }
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn budget_based_policy() {
+        let error = QueryError::DbError(DbError::Overloaded, String::new());
+        let mut policy = BudgetRetrySession {
+            max_retries: 3,
+            budget: Duration::from_millis(200),
+        };
+
+        let query_info = |attempt_number, elapsed_ms| {
+            let mut query_info = make_nth_query_info(&error, true, attempt_number);
+            query_info.elapsed_since_first_attempt = Duration::from_millis(elapsed_ms);
+            query_info
+        };
+
+        for attempt_number in 1..=3 {
+            assert_eq!(
+                policy.decide_should_retry(query_info(attempt_number, 10)),
+                RetryDecision::RetryNextNode(None)
+            );
+        }
+        // Out of retries
+        assert_eq!(
+            policy.decide_should_retry(query_info(4, 10)),
+            RetryDecision::DontRetry
+        );
+        // Out of time
+        assert_eq!(
+            policy.decide_should_retry(query_info(2, 200)),
+            RetryDecision::DontRetry
+        );
+    }
+
+    #[test]
+    fn query_info_defaults_to_first_attempt() {
+        let error = QueryError::TimeoutError;
+        let query_info = QueryInfo::new(&error, true, Consistency::Quorum);
+        assert!(query_info.is_first_attempt());
+        assert_eq!(query_info.attempt_consistency, Consistency::Quorum);
+        assert_eq!(query_info.elapsed_since_first_attempt, Duration::ZERO);
+        assert!(query_info.node.is_none());
+
+        // The built-in policies don't repeat a retry which was already made, whatever the error
+        let unavailable = QueryError::DbError(
+            DbError::Unavailable {
+                consistency: Consistency::Quorum,
+                required: 2,
+                alive: 1,
+            },
+            String::new(),
+        );
+        let mut policy = DefaultRetryPolicy::new().new_session();
+        assert_eq!(
+            policy.decide_should_retry(make_nth_query_info(&unavailable, true, 2)),
+            RetryDecision::DontRetry
+        );
+    }
 }
@@ -0,0 +1,395 @@
+//! Abstraction of the timers and task spawning used by the driver.
+//!
+//! The driver waits for timeouts, sleeps between retries, sends keepalives periodically
+//! and spawns [background tasks](crate::transport::background_tasks). It does all of this
+//! through a [`Runtime`], which can be set with
+//! [`SessionBuilder::runtime`](crate::transport::session_builder::GenericSessionBuilder::runtime).
+//! By default, [`TokioRuntime`] is used.
+//!
+//! A custom runtime lets the driver be driven by a different executor or clock,
+//! e.g. a deterministic one in tests - see `TestRuntime`, available with the `testing` feature.
+//! Note that the I/O of the connections is still done with tokio, and that
+//! the latency-aware load balancing keeps its own timers.
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+
+/// Timers and task spawning used by the driver.
+pub trait Runtime: Send + Sync + Debug {
+    /// Returns the current time of the runtime's clock.
+    fn now(&self) -> Instant;
+
+    /// Returns a future which completes after `duration` passes.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+
+    /// Returns a future which completes at `deadline`.
+    fn sleep_until(&self, deadline: Instant) -> BoxFuture<'static, ()> {
+        self.sleep(deadline.saturating_duration_since(self.now()))
+    }
+
+    /// Runs `task` in the background.
+    ///
+    /// The `name` describes the task, e.g. `scylla::pool_refiller 10.0.0.1:9042`,
+    /// and may be used by runtimes which support naming tasks.
+    fn spawn(&self, name: &str, task: BoxFuture<'static, ()>);
+}
+
+/// The default [`Runtime`], which uses tokio.
+///
+/// Its clock follows tokio's, so it stops when time is paused with `tokio::time::pause`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioRuntime;
+
+impl Runtime for TokioRuntime {
+    fn now(&self) -> Instant {
+        tokio::time::Instant::now().into_std()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        tokio::time::sleep(duration).boxed()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> BoxFuture<'static, ()> {
+        tokio::time::sleep_until(deadline.into()).boxed()
+    }
+
+    #[cfg(all(feature = "task-names", tokio_unstable))]
+    fn spawn(&self, name: &str, task: BoxFuture<'static, ()>) {
+        tokio::task::Builder::new()
+            .name(name)
+            .spawn(task)
+            .expect("spawning a task failed");
+    }
+
+    #[cfg(not(all(feature = "task-names", tokio_unstable)))]
+    fn spawn(&self, _name: &str, task: BoxFuture<'static, ()>) {
+        tokio::spawn(task);
+    }
+}
+
+pub(crate) fn default_runtime() -> Arc<dyn Runtime> {
+    Arc::new(TokioRuntime)
+}
+
+/// Returned by [`timeout`] when the future didn't complete in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Elapsed;
+
+impl std::fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "deadline has elapsed")
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// Future returned by [`timeout`].
+    pub(crate) struct Timeout<F> {
+        #[pin]
+        future: F,
+        sleep: BoxFuture<'static, ()>,
+    }
+}
+
+/// Like `tokio::time::timeout`, but measures the time with `runtime`.
+pub(crate) fn timeout<F: Future>(
+    runtime: &dyn Runtime,
+    duration: Duration,
+    future: F,
+) -> Timeout<F> {
+    Timeout {
+        future,
+        sleep: runtime.sleep(duration),
+    }
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if let Poll::Ready(output) = this.future.poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+        this.sleep.poll_unpin(cx).map(|()| Err(Elapsed))
+    }
+}
+
+/// Like `tokio::time::Interval` with `MissedTickBehavior::Delay`, but measures the time
+/// with a [`Runtime`]. The first tick completes immediately.
+pub(crate) struct Interval {
+    runtime: Arc<dyn Runtime>,
+    period: Duration,
+    next_tick: Instant,
+    // Kept between calls to `tick`, so that cancelling it in `select!` doesn't lose the timer
+    sleep: Option<BoxFuture<'static, ()>>,
+}
+
+impl Interval {
+    pub(crate) fn new(runtime: Arc<dyn Runtime>, period: Duration) -> Self {
+        let next_tick = runtime.now();
+        Interval {
+            runtime,
+            period,
+            next_tick,
+            sleep: None,
+        }
+    }
+
+    pub(crate) async fn tick(&mut self) {
+        let sleep = match &mut self.sleep {
+            Some(sleep) => sleep,
+            None => self.sleep.insert(self.runtime.sleep_until(self.next_tick)),
+        };
+        sleep.await;
+        self.sleep = None;
+        self.next_tick = self.runtime.now() + self.period;
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+pub use test_runtime::TestRuntime;
+
+#[cfg(any(test, feature = "testing"))]
+mod test_runtime {
+    use std::collections::HashMap;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+    use std::time::{Duration, Instant};
+
+    use futures::future::BoxFuture;
+    use futures::FutureExt;
+
+    use super::Runtime;
+
+    /// A [`Runtime`] with a clock which only moves when [`advance`](TestRuntime::advance)d.
+    ///
+    /// It makes tests of timeouts, retries with backoff or keepalives take no time
+    /// and not depend on the load of the machine. Tasks are spawned with tokio.
+    ///
+    /// ```rust
+    /// # use std::time::Duration;
+    /// # async fn check_only_compiles() {
+    /// use scylla::testing::TestRuntime;
+    /// use scylla::transport::runtime::Runtime;
+    ///
+    /// let runtime = TestRuntime::new();
+    /// let sleep = runtime.sleep(Duration::from_secs(60));
+    /// runtime.advance(Duration::from_secs(60)).await;
+    /// sleep.await; // Completes right away
+    /// # }
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct TestRuntime {
+        clock: Arc<Mutex<Clock>>,
+    }
+
+    #[derive(Debug)]
+    struct Clock {
+        now: Instant,
+        next_timer_id: u64,
+        // Wakers of the sleeps which were polled and haven't completed yet
+        timers: HashMap<u64, (Instant, Waker)>,
+    }
+
+    impl TestRuntime {
+        /// Creates a runtime with the clock stopped at the current time.
+        pub fn new() -> Self {
+            TestRuntime {
+                clock: Arc::new(Mutex::new(Clock {
+                    now: Instant::now(),
+                    next_timer_id: 0,
+                    timers: HashMap::new(),
+                })),
+            }
+        }
+
+        /// Moves the clock forward by `duration`, completing the sleeps which
+        /// are due by then. Yields once, so that the woken up tasks can run.
+        pub async fn advance(&self, duration: Duration) {
+            let due: Vec<Waker> = {
+                let mut clock = self.clock.lock().unwrap();
+                clock.now += duration;
+                let now = clock.now;
+                let due_ids: Vec<u64> = clock
+                    .timers
+                    .iter()
+                    .filter(|(_, (deadline, _))| *deadline <= now)
+                    .map(|(id, _)| *id)
+                    .collect();
+                due_ids
+                    .into_iter()
+                    .filter_map(|id| clock.timers.remove(&id))
+                    .map(|(_, waker)| waker)
+                    .collect()
+            };
+            due.into_iter().for_each(Waker::wake);
+            tokio::task::yield_now().await;
+        }
+
+        /// Returns the number of sleeps which are being awaited and haven't completed yet.
+        pub fn pending_timers(&self) -> usize {
+            self.clock.lock().unwrap().timers.len()
+        }
+    }
+
+    impl Default for TestRuntime {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Runtime for TestRuntime {
+        fn now(&self) -> Instant {
+            self.clock.lock().unwrap().now
+        }
+
+        fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+            let deadline = self.now() + duration;
+            self.sleep_until(deadline)
+        }
+
+        fn sleep_until(&self, deadline: Instant) -> BoxFuture<'static, ()> {
+            TestSleep {
+                clock: self.clock.clone(),
+                deadline,
+                timer_id: None,
+            }
+            .boxed()
+        }
+
+        fn spawn(&self, _name: &str, task: BoxFuture<'static, ()>) {
+            tokio::spawn(task);
+        }
+    }
+
+    struct TestSleep {
+        clock: Arc<Mutex<Clock>>,
+        deadline: Instant,
+        timer_id: Option<u64>,
+    }
+
+    impl Future for TestSleep {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            let clock = self.clock.clone();
+            let mut clock = clock.lock().unwrap();
+            if clock.now >= self.deadline {
+                if let Some(id) = self.timer_id.take() {
+                    clock.timers.remove(&id);
+                }
+                return Poll::Ready(());
+            }
+            let id = match self.timer_id {
+                Some(id) => id,
+                None => {
+                    let id = clock.next_timer_id;
+                    clock.next_timer_id += 1;
+                    self.timer_id = Some(id);
+                    id
+                }
+            };
+            clock.timers.insert(id, (self.deadline, cx.waker().clone()));
+            Poll::Pending
+        }
+    }
+
+    impl Drop for TestSleep {
+        fn drop(&mut self) {
+            if let Some(id) = self.timer_id {
+                self.clock.lock().unwrap().timers.remove(&id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use futures::FutureExt;
+
+    use super::{timeout, Elapsed, Interval, Runtime, TestRuntime};
+
+    #[tokio::test]
+    async fn test_runtime_completes_sleeps_when_advanced() {
+        let runtime = TestRuntime::new();
+        let start = runtime.now();
+
+        let mut short = runtime.sleep(Duration::from_secs(1));
+        let mut long = runtime.sleep(Duration::from_secs(3));
+        assert!((&mut short).now_or_never().is_none());
+        assert!((&mut long).now_or_never().is_none());
+        assert_eq!(runtime.pending_timers(), 2);
+
+        runtime.advance(Duration::from_secs(2)).await;
+        assert_eq!(runtime.now() - start, Duration::from_secs(2));
+        assert!(short.now_or_never().is_some());
+        assert!((&mut long).now_or_never().is_none());
+        assert_eq!(runtime.pending_timers(), 1);
+
+        // Dropped sleeps don't leave their timers behind
+        drop(long);
+        assert_eq!(runtime.pending_timers(), 0);
+    }
+
+    #[tokio::test]
+    async fn timeout_elapses_with_the_runtime_clock() {
+        let runtime = TestRuntime::new();
+        let ready = timeout(&runtime, Duration::from_secs(10), async { 7 });
+        assert_eq!(ready.await, Ok(7));
+
+        let handle = tokio::spawn({
+            let runtime = runtime.clone();
+            async move {
+                timeout(
+                    &runtime,
+                    Duration::from_secs(10),
+                    std::future::pending::<()>(),
+                )
+                .await
+            }
+        });
+        while runtime.pending_timers() == 0 {
+            tokio::task::yield_now().await;
+        }
+        runtime.advance(Duration::from_secs(9)).await;
+        assert!(!handle.is_finished());
+        runtime.advance(Duration::from_secs(1)).await;
+        assert_eq!(handle.await.unwrap(), Err(Elapsed));
+    }
+
+    #[tokio::test]
+    async fn interval_ticks_with_the_period_after_the_first_tick() {
+        let runtime = TestRuntime::new();
+        let mut interval = Interval::new(Arc::new(runtime.clone()), Duration::from_secs(5));
+
+        // The first tick is immediate
+        assert!(interval.tick().now_or_never().is_some());
+        assert!(interval.tick().now_or_never().is_none());
+
+        // A cancelled tick keeps its deadline
+        runtime.advance(Duration::from_secs(4)).await;
+        assert!(interval.tick().now_or_never().is_none());
+        runtime.advance(Duration::from_secs(1)).await;
+        assert!(interval.tick().now_or_never().is_some());
+
+        // Late ticks delay the following ones
+        runtime.advance(Duration::from_secs(7)).await;
+        assert!(interval.tick().now_or_never().is_some());
+        runtime.advance(Duration::from_secs(3)).await;
+        assert!(interval.tick().now_or_never().is_none());
+        runtime.advance(Duration::from_secs(2)).await;
+        assert!(interval.tick().now_or_never().is_some());
+    }
+}
@@ -0,0 +1,428 @@
+//! Keeping prepared statements up to date with schema changes announced by the cluster.
+//!
+//! When a table is altered, the database invalidates statements prepared for it,
+//! so the next execution of such a statement fails with `UNPREPARED` and has to be
+//! re-prepared on the fly. To avoid that, statements prepared by the session and still
+//! in use are re-prepared in the background as soon as a schema change is announced,
+//! and [`CachingSession`](crate::CachingSession) drops cached statements touching
+//! the changed object.
+//!
+//! Statements operating on a dropped table are marked as stale instead, as there is
+//! nothing to prepare them for until the table is created again, possibly with
+//! different columns. Their next execution re-prepares them first, so that it's
+//! not sent with the id and metadata of the dropped table.
+//!
+//! The same statements are prepared on nodes which come back up, so that their first
+//! executions there don't have to re-prepare them either.
+//!
+//! Rows of prepared statements are by default sent without their metadata and decoded
+//! with the result metadata cached in the statement. A schema change may change the result
+//! columns without changing their number, e.g. when a field is added to a user defined type,
+//! which protocol v4 doesn't announce with the rows. So after a change affecting
+//! a statement, its next execution asks for the rows together with their metadata,
+//! and re-prepares the statement if it differs from the cached one.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, Weak};
+
+use scylla_cql::frame::response::event::{SchemaChangeEvent, SchemaChangeType};
+use scylla_cql::frame::response::result::TableSpec;
+
+use crate::statement::prepared_statement::{PreparedStatement, PreparedStatementSharedData};
+
+/// A schema change which may affect prepared statements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SchemaChange {
+    pub(crate) keyspace: String,
+    /// `None` if the change may affect any table in the keyspace,
+    /// e.g. a user defined type or a function was changed.
+    pub(crate) table: Option<String>,
+    /// Whether the changed object was dropped.
+    pub(crate) dropped: bool,
+}
+
+impl SchemaChange {
+    /// Returns `None` for changes which can't affect already prepared statements,
+    /// i.e. creating new objects and changing options of a keyspace.
+    pub(crate) fn from_event(event: &SchemaChangeEvent) -> Option<Self> {
+        let (change_type, keyspace, table) = match event {
+            SchemaChangeEvent::KeyspaceChange {
+                change_type: SchemaChangeType::Updated,
+                ..
+            } => return None,
+            SchemaChangeEvent::KeyspaceChange {
+                change_type,
+                keyspace_name,
+            } => (change_type, keyspace_name, None),
+            SchemaChangeEvent::TableChange {
+                change_type,
+                keyspace_name,
+                object_name,
+            } => (change_type, keyspace_name, Some(object_name)),
+            SchemaChangeEvent::TypeChange {
+                change_type,
+                keyspace_name,
+                ..
+            }
+            | SchemaChangeEvent::FunctionChange {
+                change_type,
+                keyspace_name,
+                ..
+            }
+            | SchemaChangeEvent::AggregateChange {
+                change_type,
+                keyspace_name,
+                ..
+            } => (change_type, keyspace_name, None),
+        };
+
+        let dropped = match change_type {
+            SchemaChangeType::Updated => false,
+            SchemaChangeType::Dropped => true,
+            SchemaChangeType::Created | SchemaChangeType::Invalid => return None,
+        };
+
+        Some(Self {
+            keyspace: keyspace.clone(),
+            table: table.cloned(),
+            dropped,
+        })
+    }
+
+    /// Whether the change may affect a statement operating on the given table.
+    /// Statements operating on an unknown table are assumed to be affected.
+    pub(crate) fn affects(&self, table: Option<&TableSpec>) -> bool {
+        match table {
+            Some(table) => match &self.table {
+                Some(changed) => table.ks_name == self.keyspace && *changed == table.table_name,
+                None => table.ks_name == self.keyspace,
+            },
+            None => true,
+        }
+    }
+}
+
+type TableStatements = HashMap<(String, String), Vec<Weak<PreparedStatementSharedData>>>;
+
+/// Keeps track of prepared statements which are still in use, by the table they operate on.
+#[derive(Debug, Default)]
+pub(crate) struct PreparedStatementRegistry {
+    statements: Mutex<TableStatements>,
+    // Statements operating on an unknown table, which are not affected by schema changes
+    untargeted: Mutex<Vec<Weak<PreparedStatementSharedData>>>,
+}
+
+impl PreparedStatementRegistry {
+    pub(crate) fn register(&self, prepared: &PreparedStatement) {
+        let table = match prepared.get_target_table() {
+            Some(table) => (table.ks_name.clone(), table.table_name.clone()),
+            None => {
+                let mut untargeted = self.untargeted.lock().unwrap();
+                untargeted.retain(|statement| statement.strong_count() > 0);
+                untargeted.push(prepared.downgrade_shared());
+                return;
+            }
+        };
+
+        let mut statements = self.statements.lock().unwrap();
+        let table_statements = statements.entry(table).or_default();
+        table_statements.retain(|statement| statement.strong_count() > 0);
+        table_statements.push(prepared.downgrade_shared());
+    }
+
+    /// Returns texts of statements in use which are affected by the change.
+    pub(crate) fn affected_statements(&self, change: &SchemaChange) -> Vec<String> {
+        let affected: HashSet<String> = self
+            .affected(change)
+            .into_iter()
+            .map(|statement| statement.statement.clone())
+            .collect();
+        affected.into_iter().collect()
+    }
+
+    /// Marks statements in use which are affected by the change as stale,
+    /// so that they are re-prepared before their next execution.
+    pub(crate) fn mark_stale(&self, change: &SchemaChange) {
+        for statement in self.affected(change) {
+            statement.mark_stale();
+        }
+    }
+
+    /// Makes the next executions of statements in use which are affected by the change
+    /// receive rows together with their metadata, so that a changed result metadata is noticed.
+    pub(crate) fn mark_result_metadata_changed(&self, change: &SchemaChange) {
+        for statement in self.affected(change) {
+            statement.mark_result_metadata_changed();
+        }
+    }
+
+    fn affected(&self, change: &SchemaChange) -> Vec<Arc<PreparedStatementSharedData>> {
+        let mut statements = self.statements.lock().unwrap();
+        let mut affected = Vec::new();
+
+        statements.retain(|(keyspace, table), table_statements| {
+            let table = TableSpec {
+                ks_name: keyspace.clone(),
+                table_name: table.clone(),
+            };
+            if change.affects(Some(&table)) {
+                affected.extend(table_statements.iter().filter_map(Weak::upgrade));
+                table_statements.retain(|statement| statement.strong_count() > 0);
+            }
+            !table_statements.is_empty()
+        });
+
+        affected
+    }
+
+    /// Returns texts of all statements in use.
+    pub(crate) fn statements_in_use(&self) -> Vec<String> {
+        let mut in_use = HashSet::new();
+
+        let mut statements = self.statements.lock().unwrap();
+        statements.retain(|_, table_statements| {
+            table_statements.retain(|statement| statement.strong_count() > 0);
+            !table_statements.is_empty()
+        });
+        in_use.extend(
+            statements
+                .values()
+                .flatten()
+                .filter_map(Weak::upgrade)
+                .map(|statement| statement.statement.clone()),
+        );
+        drop(statements);
+
+        let mut untargeted = self.untargeted.lock().unwrap();
+        untargeted.retain(|statement| statement.strong_count() > 0);
+        in_use.extend(
+            untargeted
+                .iter()
+                .filter_map(Weak::upgrade)
+                .map(|statement| statement.statement.clone()),
+        );
+
+        in_use.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PreparedStatementRegistry, SchemaChange};
+    use crate::prepared_statement::PreparedStatement;
+    use bytes::Bytes;
+    use scylla_cql::frame::response::event::{SchemaChangeEvent, SchemaChangeType};
+    use scylla_cql::frame::response::result::{
+        ColumnSpec, ColumnType, PreparedMetadata, TableSpec,
+    };
+
+    fn table(keyspace: &str, table: &str) -> TableSpec {
+        TableSpec {
+            ks_name: keyspace.to_owned(),
+            table_name: table.to_owned(),
+        }
+    }
+
+    #[test]
+    fn only_changes_of_existing_objects_are_relevant() {
+        let altered = SchemaChange::from_event(&SchemaChangeEvent::TableChange {
+            change_type: SchemaChangeType::Updated,
+            keyspace_name: "ks".to_owned(),
+            object_name: "t".to_owned(),
+        })
+        .unwrap();
+        assert!(!altered.dropped);
+        assert!(altered.affects(Some(&table("ks", "t"))));
+        assert!(!altered.affects(Some(&table("ks", "other"))));
+        assert!(!altered.affects(Some(&table("other", "t"))));
+        assert!(altered.affects(None));
+
+        let type_dropped = SchemaChange::from_event(&SchemaChangeEvent::TypeChange {
+            change_type: SchemaChangeType::Dropped,
+            keyspace_name: "ks".to_owned(),
+            type_name: "udt".to_owned(),
+        })
+        .unwrap();
+        assert!(type_dropped.dropped);
+        assert!(type_dropped.affects(Some(&table("ks", "any"))));
+        assert!(!type_dropped.affects(Some(&table("other", "any"))));
+
+        assert_eq!(
+            SchemaChange::from_event(&SchemaChangeEvent::TableChange {
+                change_type: SchemaChangeType::Created,
+                keyspace_name: "ks".to_owned(),
+                object_name: "t".to_owned(),
+            }),
+            None
+        );
+        assert_eq!(
+            SchemaChange::from_event(&SchemaChangeEvent::KeyspaceChange {
+                change_type: SchemaChangeType::Updated,
+                keyspace_name: "ks".to_owned(),
+            }),
+            None
+        );
+    }
+
+    fn prepared(statement: &str, keyspace: &str, table_name: &str) -> PreparedStatement {
+        prepared_with_columns(
+            statement,
+            vec![ColumnSpec {
+                table_spec: table(keyspace, table_name),
+                name: "a".to_owned(),
+                typ: ColumnType::Int,
+            }],
+        )
+    }
+
+    fn prepared_with_columns(statement: &str, col_specs: Vec<ColumnSpec>) -> PreparedStatement {
+        let metadata = PreparedMetadata {
+            flags: 0,
+            col_count: col_specs.len(),
+            pk_indexes: Vec::new(),
+            col_specs,
+        };
+        PreparedStatement::new(
+            Bytes::from_static(b"id"),
+            false,
+            metadata,
+            Default::default(),
+            statement.to_owned(),
+            None,
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn registry_returns_statements_in_use() {
+        let registry = PreparedStatementRegistry::default();
+        let select = prepared("SELECT * FROM ks.t WHERE a = ?", "ks", "t");
+        let select_clone = select.clone();
+        let other = prepared("SELECT * FROM ks.other WHERE a = ?", "ks", "other");
+        registry.register(&select);
+        registry.register(&select_clone);
+        registry.register(&other);
+
+        let change = SchemaChange {
+            keyspace: "ks".to_owned(),
+            table: Some("t".to_owned()),
+            dropped: false,
+        };
+        assert_eq!(
+            registry.affected_statements(&change),
+            vec!["SELECT * FROM ks.t WHERE a = ?".to_owned()]
+        );
+
+        // Statements no longer in use are forgotten
+        drop(select);
+        drop(select_clone);
+        assert!(registry.affected_statements(&change).is_empty());
+        assert_eq!(registry.statements.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn registry_returns_all_statements_in_use() {
+        let registry = PreparedStatementRegistry::default();
+        let select = prepared("SELECT * FROM ks.t WHERE a = ?", "ks", "t");
+        let untargeted = prepared_with_columns("TRUNCATE ks.t", Vec::new());
+        let dropped = prepared_with_columns("TRUNCATE ks.other", Vec::new());
+        registry.register(&select);
+        registry.register(&untargeted);
+        registry.register(&dropped);
+        drop(dropped);
+
+        let mut statements = registry.statements_in_use();
+        statements.sort();
+        assert_eq!(
+            statements,
+            ["SELECT * FROM ks.t WHERE a = ?", "TRUNCATE ks.t"]
+        );
+
+        // Statements with an unknown table aren't affected by schema changes
+        let change = SchemaChange {
+            keyspace: "ks".to_owned(),
+            table: Some("t".to_owned()),
+            dropped: false,
+        };
+        assert_eq!(
+            registry.affected_statements(&change),
+            ["SELECT * FROM ks.t WHERE a = ?"]
+        );
+
+        drop(select);
+        drop(untargeted);
+        assert!(registry.statements_in_use().is_empty());
+        assert!(registry.untargeted.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn registry_marks_statements_of_dropped_tables_stale() {
+        let registry = PreparedStatementRegistry::default();
+        let select = prepared("SELECT * FROM ks.t WHERE a = ?", "ks", "t");
+        let other = prepared("SELECT * FROM ks.other WHERE a = ?", "ks", "other");
+        let other_keyspace = prepared("SELECT * FROM ks2.t WHERE a = ?", "ks2", "t");
+        registry.register(&select);
+        registry.register(&other);
+        registry.register(&other_keyspace);
+
+        registry.mark_stale(&SchemaChange {
+            keyspace: "ks".to_owned(),
+            table: Some("t".to_owned()),
+            dropped: true,
+        });
+        assert!(select.is_stale());
+        assert!(select.clone().is_stale());
+        assert!(!other.is_stale());
+        assert!(!other_keyspace.is_stale());
+
+        // Re-preparation clears the marker for all copies of the statement
+        assert!(select.take_stale());
+        assert!(!select.is_stale());
+        assert!(!select.take_stale());
+
+        // Dropping a keyspace affects all of its tables
+        registry.mark_stale(&SchemaChange {
+            keyspace: "ks".to_owned(),
+            table: None,
+            dropped: true,
+        });
+        assert!(select.is_stale());
+        assert!(other.is_stale());
+        assert!(!other_keyspace.is_stale());
+    }
+
+    #[test]
+    fn registry_marks_result_metadata_of_altered_statements_unconfirmed() {
+        let registry = PreparedStatementRegistry::default();
+        let select = prepared("SELECT * FROM ks.t WHERE a = ?", "ks", "t");
+        let other = prepared("SELECT * FROM ks.other WHERE a = ?", "ks", "other");
+        registry.register(&select);
+        registry.register(&other);
+        assert_eq!(select.unconfirmed_result_metadata_version(), None);
+
+        let altered = SchemaChange {
+            keyspace: "ks".to_owned(),
+            table: Some("t".to_owned()),
+            dropped: false,
+        };
+        registry.mark_result_metadata_changed(&altered);
+        let version = select
+            .clone()
+            .unconfirmed_result_metadata_version()
+            .unwrap();
+        assert_eq!(other.unconfirmed_result_metadata_version(), None);
+
+        // A change announced while the rows of the first one were fetched
+        // has to be confirmed separately
+        registry.mark_result_metadata_changed(&altered);
+        select.confirm_result_metadata(version);
+        let next_version = select.unconfirmed_result_metadata_version().unwrap();
+        assert!(next_version > version);
+        select.confirm_result_metadata(next_version);
+        assert_eq!(select.unconfirmed_result_metadata_version(), None);
+
+        // Confirmation of an older version doesn't undo a newer one
+        select.confirm_result_metadata(version);
+        assert_eq!(select.unconfirmed_result_metadata_version(), None);
+    }
+}
@@ -0,0 +1,615 @@
+//! Checking Rust types of statements against the schema, without executing them.
+//!
+//! Statements are usually written together with the types of their values and rows,
+//! but checked against the schema only when they are executed. [`check_statement`]
+//! prepares a statement and checks that its bind markers accept the values of a
+//! [`SerializeRow`] type and that its result columns can be converted to a [`FromRow`]
+//! type, so that a test run against a development cluster catches schema drift.
+//!
+//! The check reports every mismatched bind marker and column, see [`SchemaMismatch`]:
+//! after a mismatch, the column is given a type accepted by the Rust type, if one can be
+//! found among the native CQL types, and the remaining columns are checked.
+//!
+//! # Example
+//! ```rust
+//! # use scylla::Session;
+//! # async fn check_only_compiles(session: &Session) {
+//! use scylla::assert_statement_types;
+//!
+//! assert_statement_types!(
+//!     session,
+//!     "SELECT name, age FROM ks.users WHERE id = ?",
+//!     (String, Option<i32>),
+//!     (i32,)
+//! );
+//! assert_statement_types!(session, "INSERT INTO ks.users (id, name) VALUES (?, ?)", (i32, String));
+//! # }
+//! ```
+
+use std::fmt::{self, Display};
+use std::net::{IpAddr, Ipv4Addr};
+
+use bigdecimal::BigDecimal;
+use num_bigint::BigInt;
+use scylla_cql::frame::response::cql_to_rust::{FromRow, FromRowError};
+use scylla_cql::frame::response::result::{
+    ColumnSpec, ColumnType, CqlValue, PreparedMetadata, Row,
+};
+use scylla_cql::frame::value::{Counter, CqlDate, CqlDuration, CqlTime, CqlTimestamp};
+use scylla_cql::types::serialize::row::{
+    BuiltinSerializationError, BuiltinSerializationErrorKind, RowSerializationContext, SerializeRow,
+};
+use scylla_cql::types::serialize::{RowWriter, SerializationError};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::query::Query;
+use crate::transport::errors::QueryError;
+use crate::Session;
+
+/// Prepares the statement and checks that its bind markers accept values of type `ValuesT`,
+/// and that its result columns can be converted to `RowT`. The statement isn't executed.
+///
+/// Values are checked by serializing `ValuesT::default()`, so values which are `None`
+/// by default, e.g. fields of type `Option`, are checked only for their presence.
+/// Rows are checked by converting a row of sample values of the result columns.
+pub async fn check_statement<RowT, ValuesT>(
+    session: &Session,
+    statement: impl Into<Query>,
+) -> Result<(), StatementCheckError>
+where
+    RowT: FromRow,
+    ValuesT: SerializeRow + Default,
+{
+    let prepared = session.prepare(statement).await?;
+    let mut mismatches = check_values::<ValuesT>(prepared.get_variable_col_specs());
+    mismatches.extend(check_row::<RowT>(prepared.get_result_col_specs()));
+    SchemaMismatch::check(
+        prepared.get_statement(),
+        Some(std::any::type_name::<RowT>()),
+        std::any::type_name::<ValuesT>(),
+        mismatches,
+    )
+}
+
+/// Prepares the statement and checks that its bind markers accept values of type `ValuesT`,
+/// like [`check_statement`], for statements which don't return rows.
+pub async fn check_statement_values<ValuesT>(
+    session: &Session,
+    statement: impl Into<Query>,
+) -> Result<(), StatementCheckError>
+where
+    ValuesT: SerializeRow + Default,
+{
+    let prepared = session.prepare(statement).await?;
+    let mismatches = check_values::<ValuesT>(prepared.get_variable_col_specs());
+    SchemaMismatch::check(
+        prepared.get_statement(),
+        None,
+        std::any::type_name::<ValuesT>(),
+        mismatches,
+    )
+}
+
+/// Checks the Rust types of a statement against the schema in a test,
+/// panicking with the [`SchemaMismatch`] report if they don't match.
+///
+/// Takes a `&Session`, the statement, and either the type of rows and the type
+/// of values, like [`check_statement`], or only the type of values, like
+/// [`check_statement_values`]. It has to be used in an `async` function.
+///
+/// See the [`schema_check`](crate::transport::schema_check) module for an example.
+#[macro_export]
+macro_rules! assert_statement_types {
+    ($session:expr, $statement:expr, $values:ty $(,)?) => {
+        if let Err(err) =
+            $crate::transport::schema_check::check_statement_values::<$values>($session, $statement)
+                .await
+        {
+            panic!("{}", err);
+        }
+    };
+    ($session:expr, $statement:expr, $row:ty, $values:ty $(,)?) => {
+        if let Err(err) =
+            $crate::transport::schema_check::check_statement::<$row, $values>($session, $statement)
+                .await
+        {
+            panic!("{}", err);
+        }
+    };
+}
+
+/// Error returned by [`check_statement`] and [`check_statement_values`].
+#[derive(Error, Debug, Clone)]
+pub enum StatementCheckError {
+    /// The statement couldn't be prepared, e.g. because it refers to a column
+    /// which doesn't exist.
+    #[error("Preparing the statement failed: {0}")]
+    Prepare(#[from] QueryError),
+
+    /// The Rust types don't match the bind markers or the result columns of the statement.
+    #[error(transparent)]
+    Mismatch(#[from] SchemaMismatch),
+}
+
+/// Report of the differences between the Rust types of a statement and its metadata,
+/// listing every mismatched bind marker and result column.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub struct SchemaMismatch {
+    /// The checked statement.
+    pub statement: String,
+    /// Name of the Rust type of rows, `None` if rows weren't checked.
+    pub row_type: Option<&'static str>,
+    /// Name of the Rust type of values.
+    pub values_type: &'static str,
+    /// The mismatches, first of the values and then of the rows.
+    pub mismatches: Vec<ColumnMismatch>,
+}
+
+impl SchemaMismatch {
+    fn check(
+        statement: &str,
+        row_type: Option<&'static str>,
+        values_type: &'static str,
+        mismatches: Vec<ColumnMismatch>,
+    ) -> Result<(), StatementCheckError> {
+        if mismatches.is_empty() {
+            return Ok(());
+        }
+        Err(SchemaMismatch {
+            statement: statement.to_owned(),
+            row_type,
+            values_type,
+            mismatches,
+        }
+        .into())
+    }
+}
+
+impl Display for SchemaMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Statement \"{}\" doesn't match values {}",
+            self.statement, self.values_type
+        )?;
+        if let Some(row_type) = self.row_type {
+            write!(f, " and rows {}", row_type)?;
+        }
+        write!(f, ":")?;
+        for mismatch in &self.mismatches {
+            write!(f, "\n  {}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+/// A single difference between the Rust types of a statement and its metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnMismatch {
+    /// The value of a field can't be bound to a bind marker.
+    BindMarker {
+        /// Name of the bind marker.
+        name: String,
+        /// Type of the bind marker.
+        cql_type: ColumnType,
+        /// Why the value can't be bound.
+        reason: String,
+        /// A native type which the value can be bound to, if one was found.
+        accepted_type: Option<ColumnType>,
+    },
+    /// The values don't match the bind markers as a whole, e.g. there are more
+    /// values than bind markers. The bind markers aren't checked further.
+    Values {
+        /// Why the values don't match.
+        reason: String,
+    },
+    /// A result column can't be converted to a field of the row.
+    ResultColumn {
+        /// Zero-based index of the column.
+        index: usize,
+        /// Name of the column.
+        name: String,
+        /// Type of the column.
+        cql_type: ColumnType,
+        /// Why the column can't be converted.
+        reason: String,
+        /// A native type which can be converted to the field, if one was found.
+        accepted_type: Option<ColumnType>,
+    },
+    /// The result row doesn't match the Rust type as a whole, e.g. it has fewer
+    /// columns than the type has fields. The columns aren't checked further.
+    Row {
+        /// Why the row doesn't match.
+        reason: String,
+    },
+}
+
+impl Display for ColumnMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (cql_type, accepted_type) = match self {
+            ColumnMismatch::BindMarker {
+                name,
+                cql_type,
+                reason,
+                accepted_type,
+            } => {
+                write!(f, "bind marker {}: {}", name, reason)?;
+                (cql_type, accepted_type)
+            }
+            ColumnMismatch::Values { reason } => return write!(f, "values: {}", reason),
+            ColumnMismatch::ResultColumn {
+                index,
+                name,
+                cql_type,
+                reason,
+                accepted_type,
+            } => {
+                write!(f, "column {} ({}): {}", name, index, reason)?;
+                (cql_type, accepted_type)
+            }
+            ColumnMismatch::Row { reason } => return write!(f, "row: {}", reason),
+        };
+        write!(f, " (the column is {}", cql_type.cql_type_name())?;
+        if let Some(accepted_type) = accepted_type {
+            write!(
+                f,
+                ", the Rust type accepts {}",
+                accepted_type.cql_type_name()
+            )?;
+        }
+        write!(f, ")")
+    }
+}
+
+// Native types tried in place of a mismatched column, so that the following ones can be checked.
+fn candidate_types() -> [ColumnType; 20] {
+    [
+        ColumnType::Int,
+        ColumnType::BigInt,
+        ColumnType::Text,
+        ColumnType::Boolean,
+        ColumnType::Double,
+        ColumnType::Float,
+        ColumnType::SmallInt,
+        ColumnType::TinyInt,
+        ColumnType::Uuid,
+        ColumnType::Timeuuid,
+        ColumnType::Timestamp,
+        ColumnType::Date,
+        ColumnType::Time,
+        ColumnType::Duration,
+        ColumnType::Blob,
+        ColumnType::Inet,
+        ColumnType::Decimal,
+        ColumnType::Varint,
+        ColumnType::Counter,
+        ColumnType::Ascii,
+    ]
+}
+
+/// Checks that `ValuesT::default()` can be bound to the bind markers.
+fn check_values<ValuesT: SerializeRow + Default>(
+    bind_markers: &[ColumnSpec],
+) -> Vec<ColumnMismatch> {
+    let values = ValuesT::default();
+    let mut metadata = PreparedMetadata {
+        flags: 0,
+        col_count: bind_markers.len(),
+        pk_indexes: Vec::new(),
+        col_specs: bind_markers.to_vec(),
+    };
+    let mut mismatches = Vec::new();
+
+    // Each iteration either succeeds, stops, or moves past a mismatched bind marker
+    for _ in 0..=bind_markers.len() {
+        let error = match serialize_values(&values, &metadata) {
+            Ok(()) => break,
+            Err(error) => error,
+        };
+        let (name, reason) = match failed_column(&error) {
+            Some((name, column_error)) => (name.to_owned(), column_error.to_string()),
+            None => {
+                mismatches.push(ColumnMismatch::Values {
+                    reason: error.to_string(),
+                });
+                break;
+            }
+        };
+        let index = match metadata.col_specs.iter().position(|spec| spec.name == name) {
+            Some(index) => index,
+            None => {
+                mismatches.push(ColumnMismatch::Values { reason });
+                break;
+            }
+        };
+
+        let cql_type = bind_markers[index].typ.clone();
+        let accepted_type = candidate_types().into_iter().find(|candidate| {
+            let mut candidate_metadata = metadata.clone();
+            candidate_metadata.col_specs[index].typ = candidate.clone();
+            match serialize_values(&values, &candidate_metadata) {
+                Ok(()) => true,
+                Err(error) => !matches!(failed_column(&error), Some((failed, _)) if failed == name),
+            }
+        });
+        mismatches.push(ColumnMismatch::BindMarker {
+            name,
+            cql_type,
+            reason,
+            accepted_type: accepted_type.clone(),
+        });
+        match accepted_type {
+            Some(accepted_type) => metadata.col_specs[index].typ = accepted_type,
+            None => break,
+        }
+    }
+    mismatches
+}
+
+fn serialize_values(
+    values: &impl SerializeRow,
+    metadata: &PreparedMetadata,
+) -> Result<(), SerializationError> {
+    let ctx = RowSerializationContext::from_prepared(metadata);
+    let mut buffer = Vec::new();
+    values.serialize(&ctx, &mut RowWriter::new(&mut buffer))
+}
+
+// Name and error of the bind marker which failed to serialize, if that's what failed.
+fn failed_column(error: &SerializationError) -> Option<(&str, &SerializationError)> {
+    let error = error.downcast_ref::<BuiltinSerializationError>()?;
+    match &error.kind {
+        BuiltinSerializationErrorKind::ColumnSerializationFailed { name, err } => {
+            Some((name.as_str(), err))
+        }
+        _ => None,
+    }
+}
+
+/// Checks that a row of sample values of the columns can be converted to `RowT`.
+fn check_row<RowT: FromRow>(columns: &[ColumnSpec]) -> Vec<ColumnMismatch> {
+    let mut row = Row::new(
+        columns
+            .iter()
+            .map(|spec| Some(sample_value(&spec.typ)))
+            .collect(),
+    );
+    let mut mismatches = Vec::new();
+
+    // Each iteration either succeeds, stops, or moves past a mismatched column
+    for _ in 0..=columns.len() {
+        let (index, reason) = match RowT::from_row(row.clone()) {
+            Ok(_) => break,
+            Err(FromRowError::BadCqlVal { err, column }) if column < columns.len() => {
+                (column, err.to_string())
+            }
+            Err(error) => {
+                mismatches.push(ColumnMismatch::Row {
+                    reason: error.to_string(),
+                });
+                break;
+            }
+        };
+
+        let accepted_type = candidate_types().into_iter().find(|candidate| {
+            let mut candidate_row = row.clone();
+            candidate_row.columns[index] = Some(sample_value(candidate));
+            !matches!(
+                RowT::from_row(candidate_row),
+                Err(FromRowError::BadCqlVal { column, .. }) if column == index
+            )
+        });
+        mismatches.push(ColumnMismatch::ResultColumn {
+            index,
+            name: columns[index].name.clone(),
+            cql_type: columns[index].typ.clone(),
+            reason,
+            accepted_type: accepted_type.clone(),
+        });
+        match accepted_type {
+            Some(accepted_type) => row.columns[index] = Some(sample_value(&accepted_type)),
+            None => break,
+        }
+    }
+    mismatches
+}
+
+/// A value of the given type, as it could be received from the database.
+fn sample_value(typ: &ColumnType) -> CqlValue {
+    match typ {
+        ColumnType::Custom(_) => CqlValue::Blob(Vec::new()),
+        ColumnType::Ascii => CqlValue::Ascii(String::new()),
+        ColumnType::Boolean => CqlValue::Boolean(false),
+        ColumnType::Blob => CqlValue::Blob(Vec::new()),
+        ColumnType::Counter => CqlValue::Counter(Counter(0)),
+        ColumnType::Date => CqlValue::Date(CqlDate(1 << 31)),
+        ColumnType::Decimal => CqlValue::Decimal(BigDecimal::from(0)),
+        ColumnType::Double => CqlValue::Double(0.0),
+        ColumnType::Duration => CqlValue::Duration(CqlDuration {
+            months: 0,
+            days: 0,
+            nanoseconds: 0,
+        }),
+        ColumnType::Float => CqlValue::Float(0.0),
+        ColumnType::Int => CqlValue::Int(0),
+        ColumnType::BigInt => CqlValue::BigInt(0),
+        ColumnType::Text => CqlValue::Text(String::new()),
+        ColumnType::Timestamp => CqlValue::Timestamp(CqlTimestamp(0)),
+        ColumnType::Inet => CqlValue::Inet(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        ColumnType::List(_) => CqlValue::List(Vec::new()),
+        ColumnType::Map(_, _) => CqlValue::Map(Vec::new()),
+        ColumnType::Set(_) => CqlValue::Set(Vec::new()),
+        ColumnType::UserDefinedType {
+            type_name,
+            keyspace,
+            field_types,
+        } => CqlValue::UserDefinedType {
+            keyspace: keyspace.clone(),
+            type_name: type_name.clone(),
+            fields: field_types
+                .iter()
+                .map(|(name, typ)| (name.clone(), Some(sample_value(typ))))
+                .collect(),
+        },
+        ColumnType::SmallInt => CqlValue::SmallInt(0),
+        ColumnType::TinyInt => CqlValue::TinyInt(0),
+        ColumnType::Time => CqlValue::Time(CqlTime(0)),
+        ColumnType::Timeuuid => CqlValue::Timeuuid(Uuid::nil()),
+        ColumnType::Tuple(types) => {
+            CqlValue::Tuple(types.iter().map(|typ| Some(sample_value(typ))).collect())
+        }
+        ColumnType::Uuid => CqlValue::Uuid(Uuid::nil()),
+        ColumnType::Varint => CqlValue::Varint(BigInt::from(0)),
+        ColumnType::Unknown { id, .. } => CqlValue::Unknown {
+            id: *id,
+            raw: Vec::new(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_row, check_values, ColumnMismatch, SchemaMismatch};
+    use crate::frame::response::result::{ColumnSpec, ColumnType, TableSpec};
+    use crate::macros::{FromRow, SerializeRow};
+
+    fn col(name: &str, typ: ColumnType) -> ColumnSpec {
+        ColumnSpec {
+            table_spec: TableSpec {
+                ks_name: "ks".to_string(),
+                table_name: "t".to_string(),
+            },
+            name: name.to_string(),
+            typ,
+        }
+    }
+
+    #[derive(SerializeRow, Default)]
+    #[scylla(crate = crate)]
+    struct UserValues {
+        id: i32,
+        name: String,
+        tags: Vec<String>,
+    }
+
+    #[derive(FromRow)]
+    #[scylla_crate = "crate"]
+    struct UserRow {
+        _id: i32,
+        _name: Option<String>,
+        _age: i64,
+    }
+
+    #[test]
+    fn matching_types_pass() {
+        let bind_markers = [
+            col("id", ColumnType::Int),
+            col("name", ColumnType::Text),
+            col("tags", ColumnType::List(Box::new(ColumnType::Text))),
+        ];
+        assert_eq!(check_values::<UserValues>(&bind_markers), []);
+        assert_eq!(check_values::<(i32, String)>(&bind_markers[..2]), []);
+
+        let columns = [
+            col("id", ColumnType::Int),
+            col("name", ColumnType::Text),
+            col("age", ColumnType::BigInt),
+        ];
+        assert_eq!(check_row::<UserRow>(&columns), []);
+        assert_eq!(check_row::<(i32, Option<String>, i64)>(&columns), []);
+    }
+
+    #[test]
+    fn every_mismatched_bind_marker_is_reported() {
+        let bind_markers = [
+            col("id", ColumnType::BigInt),
+            col("name", ColumnType::Text),
+            col("tags", ColumnType::Int),
+        ];
+        let mismatches = check_values::<UserValues>(&bind_markers);
+        let names: Vec<_> = mismatches
+            .iter()
+            .map(|mismatch| match mismatch {
+                ColumnMismatch::BindMarker {
+                    name,
+                    cql_type,
+                    accepted_type,
+                    ..
+                } => (name.as_str(), cql_type.clone(), accepted_type.clone()),
+                _ => panic!("Unexpected mismatch: {:?}", mismatch),
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("id", ColumnType::BigInt, Some(ColumnType::Int)),
+                ("tags", ColumnType::Int, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn value_count_mismatch_is_reported() {
+        let bind_markers = [col("id", ColumnType::Int)];
+        let mismatches = check_values::<(i32, String)>(&bind_markers);
+        assert!(
+            matches!(&mismatches[..], [ColumnMismatch::Values { .. }]),
+            "{:?}",
+            mismatches
+        );
+    }
+
+    #[test]
+    fn every_mismatched_column_is_reported() {
+        let columns = [
+            col("id", ColumnType::Text),
+            col("name", ColumnType::Text),
+            col("age", ColumnType::Int),
+        ];
+        let mismatches = check_row::<UserRow>(&columns);
+        assert_eq!(
+            mismatches,
+            [
+                ColumnMismatch::ResultColumn {
+                    index: 0,
+                    name: "id".to_string(),
+                    cql_type: ColumnType::Text,
+                    reason: "Bad CQL type".to_string(),
+                    accepted_type: Some(ColumnType::Int),
+                },
+                ColumnMismatch::ResultColumn {
+                    index: 2,
+                    name: "age".to_string(),
+                    cql_type: ColumnType::Int,
+                    reason: "Bad CQL type".to_string(),
+                    accepted_type: Some(ColumnType::BigInt),
+                },
+            ]
+        );
+
+        let mismatches = check_row::<UserRow>(&columns[..2]);
+        assert!(
+            matches!(&mismatches[..], [.., ColumnMismatch::Row { .. }]),
+            "{:?}",
+            mismatches
+        );
+    }
+
+    #[test]
+    fn report_lists_mismatches() {
+        let columns = [col("id", ColumnType::Text), col("age", ColumnType::Int)];
+        let report = SchemaMismatch {
+            statement: "SELECT id, age FROM ks.t".to_string(),
+            row_type: Some("(i32, i64)"),
+            values_type: "()",
+            mismatches: check_row::<(i32, i64)>(&columns),
+        };
+        assert_eq!(
+            report.to_string(),
+            "Statement \"SELECT id, age FROM ks.t\" doesn't match values () and rows (i32, i64):\n  \
+             column id (0): Bad CQL type (the column is text, the Rust type accepts int)\n  \
+             column age (1): Bad CQL type (the column is int, the Rust type accepts bigint)"
+        );
+    }
+}
@@ -16,68 +16,114 @@ use futures::future::try_join_all;
 use itertools::{Either, Itertools};
 pub use scylla_cql::errors::TranslationError;
 use scylla_cql::frame::response::result::{deser_cql_value, ColumnSpec, Rows};
+use scylla_cql::frame::response::result::{
+    ColumnDecodeError, ColumnDecodePolicy, ResultParseOptions, UnknownTypePolicy,
+};
 use scylla_cql::frame::response::NonErrorResponse;
-use scylla_cql::types::serialize::batch::BatchValues;
-use scylla_cql::types::serialize::row::SerializeRow;
-use std::borrow::Borrow;
+use scylla_cql::types::serialize::batch::{BatchValues, BatchValuesIterator};
+use scylla_cql::types::serialize::redaction::ValueRedaction;
+use scylla_cql::types::serialize::row::{SerializeRow, SerializedValues, ValueCountMismatchError};
+use scylla_cql::types::serialize::SerializationError;
+use std::borrow::{Borrow, Cow};
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::future::Future;
 use std::net::SocketAddr;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::str::FromStr;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::time::timeout;
-use tracing::{debug, trace, trace_span, Instrument};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::{debug, trace, Instrument, Level};
 use uuid::Uuid;
 
+use super::background_tasks::{BackgroundTaskStatus, BackgroundTasks};
+use super::buffer_pool::{give_back_buffer, take_buffer, BufferPool};
 use super::connection::NonErrorQueryResponse;
 use super::connection::QueryResponse;
 #[cfg(feature = "ssl")]
 use super::connection::SslConfig;
-use super::errors::{NewSessionError, QueryError};
-use super::execution_profile::{ExecutionProfile, ExecutionProfileHandle, ExecutionProfileInner};
+use super::ddl;
+use super::errors::{ExecutionError, NewSessionError, QueryError, StatementInfo};
+use super::execution_profile::{
+    self, ExecutionProfile, ExecutionProfileHandle, ExecutionProfileInner,
+};
 #[cfg(feature = "cloud")]
 use super::node::CloudEndpoint;
-use super::node::KnownNode;
+use super::node::{
+    AddressFamilyPreference, ContactPoints, HostnameResolver, KnownNode, SystemHostnameResolver,
+};
 use super::partitioner::PartitionerName;
-use super::topology::UntranslatedPeer;
+use super::runtime::{self, Runtime};
+use super::schema_changes::SchemaChange;
+use super::topology::{Table, UntranslatedPeer};
 use super::NodeRef;
+use crate::bound_statement::BoundStatement;
 use crate::cql_to_rust::FromRow;
 use crate::frame::response::cql_to_rust::FromRowError;
 use crate::frame::response::result;
 use crate::prepared_statement::PreparedStatement;
 use crate::query::Query;
 use crate::routing::Token;
-use crate::statement::Consistency;
+use crate::statement::identifier::CqlIdentifier;
+use crate::statement::{Consistency, SerialConsistency};
 use crate::tracing::{TracingEvent, TracingInfo};
 use crate::transport::cluster::{Cluster, ClusterData, ClusterNeatDebug};
-use crate::transport::connection::{Connection, ConnectionConfig, VerifiedKeyspaceName};
+use crate::transport::connection::{
+    Connection, ConnectionConfig, VerifiedKeyspaceName, DEFAULT_WRITE_COALESCING_BUFFER_SIZE,
+    MAX_STREAM_IDS,
+};
 use crate::transport::connection_pool::PoolConfig;
+use crate::transport::control_connection_policy::{
+    ControlConnectionPolicy, ControlConnectionStatus,
+};
+use crate::transport::execution_budget::{BudgetedExecution, ExecutionBudget, SessionHandle};
 use crate::transport::host_filter::HostFilter;
-use crate::transport::iterator::{PreparedIteratorConfig, RowIterator};
+use crate::transport::interceptor::{InterceptedStatement, RequestContext, RequestInterceptor};
+use crate::transport::iterator::{IterConfig, PreparedIteratorConfig, RowIterator};
 use crate::transport::load_balancing::{self, RoutingInfo};
-use crate::transport::metrics::Metrics;
+use crate::transport::metrics::{LabelMetrics, Metrics};
+#[cfg(feature = "metrics-exporter")]
+use crate::transport::metrics_exporter::{
+    MetricsObserver, NodeLabels, RequestEvent, RequestOutcome, StatementKind,
+};
 use crate::transport::node::Node;
+use crate::transport::priority::PriorityScheduling;
 use crate::transport::query_result::QueryResult;
 use crate::transport::retry_policy::{QueryInfo, RetryDecision, RetrySession};
+use crate::transport::slow_query_log::{RequestStats, SlowQueryListener, SlowQueryLog};
 use crate::transport::speculative_execution;
+use crate::transport::statement_guard::{self, GuardDecision, StatementGuard};
+use crate::transport::table_scan::{ScanConfig, TableScan};
 use crate::transport::Compression;
 use crate::{
     batch::{Batch, BatchStatement},
     statement::StatementConfig,
 };
 
-pub use crate::transport::connection_pool::PoolSize;
+pub use crate::transport::connection_pool::{
+    ConnectionCloseReason, PoolSize, StreamExhaustionPolicy,
+};
 
 use crate::authentication::AuthenticatorProvider;
 #[cfg(feature = "ssl")]
 use openssl::ssl::SslContext;
 use scylla_cql::errors::BadQuery;
 
+/// Page size of single pages fetched for statements without a page size set.
+const DEFAULT_SINGLE_PAGE_SIZE: i32 = 5000;
+
+/// Part of the result fetched by a request.
+enum Paging {
+    /// The page starting at the paging state, or the whole result if the statement
+    /// has no page size set.
+    Page(Option<Bytes>),
+    /// All pages of the result, merged into one.
+    AllPages,
+}
+
 /// Translates IP addresses received from ScyllaDB nodes into locally reachable addresses.
 ///
 /// The driver auto-detects new ScyllaDB nodes added to the cluster through server side pushed
@@ -136,18 +182,46 @@ impl AddressTranslator for HashMap<&'static str, &'static str> {
 }
 
 /// `Session` manages connections to the cluster and allows to perform queries
+///
+/// # Cancellation
+/// All futures returned by the session can be dropped at any point, e.g. by wrapping
+/// them in [`tokio::time::timeout`]:
+/// - a request which wasn't written to a connection yet is not sent at all, and frees
+///   its stream permit and serialization buffer right away,
+/// - a request which was already sent can't be recalled - its stream id stays in use
+///   until the response arrives, as counted by [`Metrics::get_orphaned_requests`],
+/// - a [`RowIterator`] cancels the background task fetching its pages when it's dropped,
+///   or when the future creating it is dropped.
+///
+/// Dropping a request doesn't stop the server from executing it, so a dropped write
+/// may still be applied.
 pub struct Session {
     cluster: Cluster,
     default_execution_profile_handle: ExecutionProfileHandle,
+    default_consistency: Consistency,
+    default_serial_consistency: Option<SerialConsistency>,
     schema_agreement_interval: Duration,
     metrics: Arc<Metrics>,
     schema_agreement_timeout: Duration,
     schema_agreement_automatic_waiting: bool,
     refresh_metadata_on_auto_schema_agreement: bool,
+    prepare_on_all_hosts: bool,
     keyspace_name: ArcSwapOption<String>,
+    // Held while switching the keyspace, so that concurrent switches don't interleave
+    keyspace_switch: tokio::sync::Mutex<()>,
     tracing_info_fetch_attempts: NonZeroU32,
     tracing_info_fetch_interval: Duration,
     tracing_info_fetch_consistency: Consistency,
+    request_interceptors: Vec<Arc<dyn RequestInterceptor>>,
+    statement_guards: Vec<Arc<dyn StatementGuard>>,
+    slow_query_log: Option<SlowQueryLog>,
+    request_span_level: Level,
+    error_statement_max_length: Option<usize>,
+    value_redaction: Option<ValueRedaction>,
+    buffer_pool: Option<Arc<BufferPool>>,
+    background_tasks: BackgroundTasks,
+    check_unprepared_bind_markers: bool,
+    ddl_ignore_already_exists: bool,
 }
 
 /// This implementation deliberately omits some details from Cluster in order
@@ -189,6 +263,14 @@ pub struct SessionConfig {
 
     pub default_execution_profile_handle: ExecutionProfileHandle,
 
+    /// Consistency of requests whose statement and execution profile don't set it.
+    /// The default is `LocalQuorum`.
+    pub default_consistency: Consistency,
+
+    /// Serial consistency of conditional requests whose statement and execution profile
+    /// don't set it. The default is `Some(LocalSerial)`.
+    pub default_serial_consistency: Option<SerialConsistency>,
+
     pub used_keyspace: Option<String>,
     pub keyspace_case_sensitive: bool,
 
@@ -204,6 +286,20 @@ pub struct SessionConfig {
     /// The default is `PerShard(1)`, which is the recommended setting for Scylla clusters.
     pub connection_pool_size: PoolSize,
 
+    /// Maximal number of requests in flight on a single connection, at most 32768
+    /// (the number of stream ids in the protocol), which is the default.
+    pub max_requests_per_connection: NonZeroUsize,
+
+    /// What happens to a request when all stream ids of the connection chosen for it,
+    /// and of all other connections to the node, are in use.
+    /// The default is [`StreamExhaustionPolicy::FailFast`].
+    pub stream_exhaustion_policy: StreamExhaustionPolicy,
+
+    /// If set, requests waiting for a stream id of a connection are dispatched
+    /// by their [priority](crate::transport::priority::RequestPriority).
+    /// The default is `None`, in which case priorities are ignored.
+    pub priority_scheduling: Option<PriorityScheduling>,
+
     /// If true, prevents the driver from connecting to the shard-aware port, even if the node supports it.
     /// Generally, this options is best left as default (false).
     pub disallow_shard_aware_port: bool,
@@ -244,11 +340,39 @@ pub struct SessionConfig {
     /// between the nodes and the driver.
     pub address_translator: Option<Arc<dyn AddressTranslator>>,
 
+    /// The resolver of hostnames of the known nodes, the system resolver if `None`.
+    pub hostname_resolver: Option<Arc<dyn HostnameResolver>>,
+
+    /// Which address is used when a hostname of a known node resolves to both IPv4
+    /// and IPv6 addresses.
+    pub address_family_preference: AddressFamilyPreference,
+
+    /// How long the addresses which hostnames of the known nodes resolved to are reused,
+    /// before the hostnames are resolved again when the control connection can't be
+    /// re-established to any of the known peers.
+    pub hostname_resolution_interval: Duration,
+
+    /// When a hostname of a known node resolves to multiple addresses, connections
+    /// are attempted to them in turn, each one after this delay if the previous ones
+    /// haven't been established yet, and the first one established is used.
+    pub connection_attempt_delay: Duration,
+
     /// The host filter decides whether any connections should be opened
     /// to the node or not. The driver will also avoid filtered out nodes when
     /// re-establishing the control connection.
     pub host_filter: Option<Arc<dyn HostFilter>>,
 
+    /// Decides which node the control connection is established to,
+    /// and when it is moved to another node.
+    pub control_connection_policy: ControlConnectionPolicy,
+
+    /// If true, [`Session::prepare`] prepares the statement on all nodes, and statements
+    /// in use are prepared again on nodes which come back up. Otherwise statements are
+    /// prepared on a single node, and on other nodes only when executions there fail
+    /// because they aren't prepared.
+    /// The default is true.
+    pub prepare_on_all_hosts: bool,
+
     /// If the driver is to connect to ScyllaCloud, there is a config for it.
     #[cfg(feature = "cloud")]
     pub cloud_config: Option<Arc<CloudConfig>>,
@@ -265,6 +389,11 @@ pub struct SessionConfig {
     /// this option.
     pub enable_write_coalescing: bool,
 
+    /// Size of the buffer in which requests are collected before being written
+    /// to the socket. The buffer is written out when the next request doesn't fit in it,
+    /// or when no more requests are waiting to be sent.
+    pub write_coalescing_buffer_size: usize,
+
     /// Number of attempts to fetch [`TracingInfo`]
     /// in [`Session::get_tracing_info`]. Tracing info
     /// might not be available immediately on queried node - that's why
@@ -286,6 +415,126 @@ pub struct SessionConfig {
     /// for e.g: if they do not want unexpected traffic
     /// or they expect the topology to change frequently.
     pub cluster_metadata_refresh_interval: Duration,
+
+    /// Chain of interceptors that are called, in order, for every request
+    /// issued by the session. See [`RequestInterceptor`].
+    pub request_interceptors: Vec<Arc<dyn RequestInterceptor>>,
+
+    /// Guards consulted before every `query`, `execute` and `batch` request,
+    /// any of which may deny the request. See [`StatementGuard`].
+    pub statement_guards: Vec<Arc<dyn StatementGuard>>,
+
+    /// Requests which take longer than this are reported - logged as `tracing`
+    /// events at the `WARN` level, or passed to [`slow_query_listener`](Self::slow_query_listener).
+    /// `None` disables reporting slow requests.
+    pub slow_query_threshold: Option<Duration>,
+
+    /// Listener that receives slow requests instead of them being logged.
+    /// Has no effect unless [`slow_query_threshold`](Self::slow_query_threshold) is set.
+    pub slow_query_listener: Option<Arc<dyn SlowQueryListener>>,
+
+    /// Level of the `scylla.request` and `scylla.attempt` spans created for requests.
+    /// Default is `TRACE`; raise it to make the spans pass the subscriber's filter,
+    /// e.g. in order to export them to OpenTelemetry.
+    pub request_span_level: Level,
+
+    /// Whether errors passed to request interceptors carry information about the statement
+    /// which caused them, see [`ExecutionError::statement_info`].
+    /// Default is `false`.
+    pub attach_statement_to_errors: bool,
+
+    /// Texts of unprepared statements attached to errors are truncated to this many bytes.
+    /// Has no effect unless [`attach_statement_to_errors`](Self::attach_statement_to_errors) is set.
+    pub error_statement_max_length: usize,
+
+    /// How bound values are rendered while the session executes requests, e.g. in logs
+    /// of its request interceptors and listeners, see
+    /// [`ValueRedaction`](crate::serialize::redaction::ValueRedaction).
+    /// Default is `None`, i.e. the [global](crate::serialize::redaction::ValueRedaction::global)
+    /// redaction is used, which renders only types and lengths of values unless it was changed.
+    pub value_redaction: Option<ValueRedaction>,
+
+    /// How column types unknown to the driver are handled in the metadata of results
+    /// and prepared statements, see [`UnknownTypePolicy`].
+    /// Default is [`UnknownTypePolicy::Fail`], i.e. requests with such types fail.
+    pub unknown_type_policy: UnknownTypePolicy,
+
+    /// How values of columns which fail to decode are handled when rows are parsed,
+    /// see [`ColumnDecodePolicy`].
+    /// Default is [`ColumnDecodePolicy::Strict`], i.e. such values fail the whole result.
+    pub column_decode_policy: ColumnDecodePolicy,
+
+    /// Enables reusing buffers for serialized values and request frames across requests,
+    /// keeping at most this many bytes of buffer capacity for reuse.
+    /// Default is `None`, i.e. every request allocates its own buffers.
+    pub buffer_pool_max_retained_bytes: Option<usize>,
+
+    /// If true, blobs in query results share the buffer of the response frame instead of
+    /// being copied out of it, and are returned as [`CqlValue::SharedBlob`](crate::frame::response::result::CqlValue::SharedBlob).
+    /// Default is `false`.
+    pub zero_copy_blobs: bool,
+
+    /// If true, errors of parsing response frames contain the bytes of the frame around
+    /// the place where parsing failed, see [`FrameParseContext`](crate::frame::frame_errors::FrameParseContext).
+    /// Default is `false`, as the bytes may contain the data of the requests.
+    pub include_frame_bytes_in_errors: bool,
+
+    /// Options added to the STARTUP request of every connection, overriding the ones set by
+    /// the driver, e.g. `DRIVER_NAME` and `DRIVER_VERSION`. Options which select what is negotiated
+    /// with the server, like `COMPRESSION` or `CQL_VERSION`, aren't overridden unless
+    /// `force_reserved_startup_options` is true.
+    pub custom_startup_options: HashMap<String, String>,
+
+    /// If true, [`custom_startup_options`](Self::custom_startup_options) override
+    /// the options which select what is negotiated with the server too.
+    /// Default is `false`.
+    pub force_reserved_startup_options: bool,
+
+    /// If true, prepared statements are executed with the server asked not to send
+    /// the metadata of result rows, which are decoded with the result metadata
+    /// received when the statement was prepared instead.
+    /// See [`SessionBuilder::skip_result_metadata`] for how changes of the metadata are detected.
+    /// Default is `true`.
+    pub skip_result_metadata: bool,
+
+    /// If true, unprepared statements sent without values are checked for bind markers
+    /// (`?` outside literals and comments) before they are sent, failing with
+    /// [`ValueCountMismatchError`](scylla_cql::types::serialize::row::ValueCountMismatchError)
+    /// instead of an error from the server. Statements sent with values are prepared,
+    /// and their values are checked against the prepared metadata regardless of this option.
+    /// Default is `false`.
+    pub check_unprepared_bind_markers: bool,
+
+    /// If true, [`Session::ddl`] treats errors saying that the created object already exists
+    /// as success, e.g. when it loses a race with an identical statement sent by another client.
+    /// Default is `false`.
+    pub ddl_ignore_already_exists: bool,
+
+    /// Maximal size in bytes of the statements and values of a serialized batch.
+    /// A batch exceeding it fails on the client side with [`BadQuery::BatchTooLarge`](crate::transport::errors::BadQuery::BatchTooLarge),
+    /// which names the statement at which the limit was crossed.
+    /// Default is `None`, i.e. no limit.
+    pub max_batch_serialized_size: Option<usize>,
+
+    /// Maximal size in bytes of the body of a request frame, after compression.
+    /// A larger request fails on the client side with [`BadQuery::RequestTooLarge`](crate::transport::errors::BadQuery::RequestTooLarge).
+    /// Default is `None`, i.e. no limit.
+    pub max_request_frame_size: Option<usize>,
+
+    /// Maximal size in bytes of the body of a response frame, both as received
+    /// and after decompression. A larger response fails the request with
+    /// [`QueryError::ResponseTooLarge`](crate::transport::errors::QueryError::ResponseTooLarge).
+    /// Default is `None`, i.e. no limit.
+    pub max_response_frame_size: Option<usize>,
+
+    /// Timers and spawning of background tasks used by the session, see [`Runtime`].
+    /// Default is [`TokioRuntime`](crate::transport::runtime::TokioRuntime).
+    pub runtime: Arc<dyn Runtime>,
+
+    /// Observer that receives driver metrics as they are recorded,
+    /// e.g. in order to export them to a monitoring system.
+    #[cfg(feature = "metrics-exporter")]
+    pub metrics_observer: Option<Arc<dyn MetricsObserver>>,
 }
 
 impl SessionConfig {
@@ -308,6 +557,8 @@ impl SessionConfig {
             schema_agreement_interval: Duration::from_millis(200),
             default_execution_profile_handle: ExecutionProfile::new_from_inner(Default::default())
                 .into_handle(),
+            default_consistency: execution_profile::defaults::consistency(),
+            default_serial_consistency: execution_profile::defaults::serial_consistency(),
             used_keyspace: None,
             keyspace_case_sensitive: false,
             #[cfg(feature = "ssl")]
@@ -315,6 +566,9 @@ impl SessionConfig {
             authenticator: None,
             connect_timeout: Duration::from_secs(5),
             connection_pool_size: Default::default(),
+            max_requests_per_connection: NonZeroUsize::new(MAX_STREAM_IDS).unwrap(),
+            stream_exhaustion_policy: Default::default(),
+            priority_scheduling: None,
             disallow_shard_aware_port: false,
             keyspaces_to_fetch: Vec::new(),
             fetch_schema_metadata: true,
@@ -323,15 +577,46 @@ impl SessionConfig {
             schema_agreement_timeout: Duration::from_secs(60),
             schema_agreement_automatic_waiting: true,
             address_translator: None,
+            hostname_resolver: None,
+            address_family_preference: AddressFamilyPreference::default(),
+            hostname_resolution_interval: Duration::from_secs(1),
+            connection_attempt_delay: Duration::from_millis(250),
             host_filter: None,
+            control_connection_policy: ControlConnectionPolicy::default(),
+            prepare_on_all_hosts: true,
             refresh_metadata_on_auto_schema_agreement: true,
             #[cfg(feature = "cloud")]
             cloud_config: None,
             enable_write_coalescing: true,
+            write_coalescing_buffer_size: DEFAULT_WRITE_COALESCING_BUFFER_SIZE,
             tracing_info_fetch_attempts: NonZeroU32::new(5).unwrap(),
             tracing_info_fetch_interval: Duration::from_millis(3),
             tracing_info_fetch_consistency: Consistency::One,
             cluster_metadata_refresh_interval: Duration::from_secs(60),
+            request_interceptors: Vec::new(),
+            statement_guards: Vec::new(),
+            slow_query_threshold: None,
+            slow_query_listener: None,
+            request_span_level: Level::TRACE,
+            attach_statement_to_errors: false,
+            error_statement_max_length: 256,
+            value_redaction: None,
+            unknown_type_policy: UnknownTypePolicy::Fail,
+            column_decode_policy: ColumnDecodePolicy::Strict,
+            buffer_pool_max_retained_bytes: None,
+            zero_copy_blobs: false,
+            include_frame_bytes_in_errors: false,
+            custom_startup_options: HashMap::new(),
+            force_reserved_startup_options: false,
+            skip_result_metadata: true,
+            check_unprepared_bind_markers: false,
+            ddl_ignore_already_exists: false,
+            max_batch_serialized_size: None,
+            max_request_frame_size: None,
+            max_response_frame_size: None,
+            runtime: runtime::default_runtime(),
+            #[cfg(feature = "metrics-exporter")]
+            metrics_observer: None,
         }
     }
 
@@ -415,7 +700,8 @@ pub trait IntoTypedRows {
 impl IntoTypedRows for Vec<result::Row> {
     fn into_typed<RowT: FromRow>(self) -> TypedRowIter<RowT> {
         TypedRowIter {
-            row_iter: self.into_iter(),
+            row_iter: self.into_iter().enumerate(),
+            decode_errors: Vec::new(),
             phantom_data: Default::default(),
         }
     }
@@ -424,15 +710,72 @@ impl IntoTypedRows for Vec<result::Row> {
 /// Iterator over rows parsed as the given type\
 /// Returned by `rows.into_typed::<(...)>()`
 pub struct TypedRowIter<RowT: FromRow> {
-    row_iter: std::vec::IntoIter<result::Row>,
+    row_iter: std::iter::Enumerate<std::vec::IntoIter<result::Row>>,
+    decode_errors: Vec<ColumnDecodeError>,
     phantom_data: std::marker::PhantomData<RowT>,
 }
 
+impl<RowT: FromRow> TypedRowIter<RowT> {
+    // Rows with columns which failed to decode fail to parse
+    pub(crate) fn with_decode_errors(mut self, decode_errors: Vec<ColumnDecodeError>) -> Self {
+        self.decode_errors = decode_errors;
+        self
+    }
+}
+
 impl<RowT: FromRow> Iterator for TypedRowIter<RowT> {
     type Item = Result<RowT, FromRowError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.row_iter.next().map(RowT::from_row)
+        let (i, row) = self.row_iter.next()?;
+        Some(
+            ColumnDecodeError::check_row(&self.decode_errors, i).and_then(|()| RowT::from_row(row)),
+        )
+    }
+}
+
+/// Trait used to implement `[result::Row]::as_typed<RowT>`
+pub trait AsTypedRows {
+    fn as_typed<RowT: FromRow>(&self) -> TypedRowRefIter<'_, RowT>;
+}
+
+// Adds method [result::Row]::as_typed<RowT>(&self)
+// It parses the rows as custom row type without consuming them
+impl AsTypedRows for [result::Row] {
+    fn as_typed<RowT: FromRow>(&self) -> TypedRowRefIter<'_, RowT> {
+        TypedRowRefIter {
+            row_iter: self.iter().enumerate(),
+            decode_errors: &[],
+            phantom_data: Default::default(),
+        }
+    }
+}
+
+/// Iterator over borrowed rows parsed as the given type\
+/// Returned by `rows.as_typed::<(...)>()`
+pub struct TypedRowRefIter<'a, RowT: FromRow> {
+    row_iter: std::iter::Enumerate<std::slice::Iter<'a, result::Row>>,
+    decode_errors: &'a [ColumnDecodeError],
+    phantom_data: std::marker::PhantomData<RowT>,
+}
+
+impl<'a, RowT: FromRow> TypedRowRefIter<'a, RowT> {
+    // Rows with columns which failed to decode fail to parse
+    pub(crate) fn with_decode_errors(mut self, decode_errors: &'a [ColumnDecodeError]) -> Self {
+        self.decode_errors = decode_errors;
+        self
+    }
+}
+
+impl<'a, RowT: FromRow> Iterator for TypedRowRefIter<'a, RowT> {
+    type Item = Result<RowT, FromRowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, row) = self.row_iter.next()?;
+        Some(
+            ColumnDecodeError::check_row(self.decode_errors, i)
+                .and_then(|()| RowT::from_row_ref(row)),
+        )
     }
 }
 
@@ -493,6 +836,23 @@ impl Session {
             return Err(NewSessionError::EmptyKnownNodesList);
         }
 
+        #[cfg(not(feature = "metrics-exporter"))]
+        let metrics = Arc::new(Metrics::new());
+        #[cfg(feature = "metrics-exporter")]
+        let metrics = Arc::new(Metrics::with_observer(config.metrics_observer.clone()));
+
+        let buffer_pool = config
+            .buffer_pool_max_retained_bytes
+            .map(|max_retained_bytes| {
+                Arc::new(BufferPool::new(max_retained_bytes, metrics.clone()))
+            });
+
+        let background_tasks = BackgroundTasks::new(config.runtime);
+
+        let hostname_resolver = config
+            .hostname_resolver
+            .unwrap_or_else(|| Arc::new(SystemHostnameResolver));
+
         let connection_config = ConnectionConfig {
             compression: config.compression,
             tcp_nodelay: config.tcp_nodelay,
@@ -507,8 +867,31 @@ impl Session {
             #[cfg(feature = "cloud")]
             cloud_config: config.cloud_config,
             enable_write_coalescing: config.enable_write_coalescing,
+            write_coalescing_buffer_size: config.write_coalescing_buffer_size,
             keepalive_interval: config.keepalive_interval,
             keepalive_timeout: config.keepalive_timeout,
+            buffer_pool: buffer_pool.clone(),
+            zero_copy_blobs: config.zero_copy_blobs,
+            include_frame_bytes_in_errors: config.include_frame_bytes_in_errors,
+            result_parse_options: ResultParseOptions {
+                unknown_type_policy: config.unknown_type_policy,
+                column_decode_policy: config.column_decode_policy,
+            },
+            custom_startup_options: config.custom_startup_options,
+            force_reserved_startup_options: config.force_reserved_startup_options,
+            skip_result_metadata: config.skip_result_metadata,
+            max_batch_serialized_size: config.max_batch_serialized_size,
+            max_request_frame_size: config.max_request_frame_size,
+            max_response_frame_size: config.max_response_frame_size,
+            repreparations: None,
+            metrics: Some(metrics.clone()),
+            background_tasks: background_tasks.clone(),
+            max_requests_per_connection: config.max_requests_per_connection.get(),
+            stream_exhaustion_policy: config.stream_exhaustion_policy,
+            priority_scheduling: config.priority_scheduling,
+            connection_attempt_delay: config.connection_attempt_delay,
+            hostname_resolver: hostname_resolver.clone(),
+            address_family_preference: config.address_family_preference,
         };
 
         let pool_config = PoolConfig {
@@ -516,15 +899,27 @@ impl Session {
             pool_size: config.connection_pool_size,
             can_use_shard_aware_port: !config.disallow_shard_aware_port,
             keepalive_interval: config.keepalive_interval,
+            metrics: Some(metrics.clone()),
+            #[cfg(feature = "metrics-exporter")]
+            metrics_observer: config.metrics_observer.clone(),
         };
 
-        let cluster = Cluster::new(
+        let contact_points = ContactPoints::new(
             known_nodes,
+            hostname_resolver,
+            config.address_family_preference,
+            config.hostname_resolution_interval,
+        );
+
+        let cluster = Cluster::new(
+            contact_points,
             pool_config,
             config.keyspaces_to_fetch,
             config.fetch_schema_metadata,
             config.host_filter,
             config.cluster_metadata_refresh_interval,
+            config.control_connection_policy,
+            config.prepare_on_all_hosts,
         )
         .await?;
 
@@ -533,16 +928,35 @@ impl Session {
         let session = Session {
             cluster,
             default_execution_profile_handle,
+            default_consistency: config.default_consistency,
+            default_serial_consistency: config.default_serial_consistency,
             schema_agreement_interval: config.schema_agreement_interval,
-            metrics: Arc::new(Metrics::new()),
+            metrics,
             schema_agreement_timeout: config.schema_agreement_timeout,
             schema_agreement_automatic_waiting: config.schema_agreement_automatic_waiting,
             refresh_metadata_on_auto_schema_agreement: config
                 .refresh_metadata_on_auto_schema_agreement,
+            prepare_on_all_hosts: config.prepare_on_all_hosts,
             keyspace_name: ArcSwapOption::default(), // will be set by use_keyspace
+            keyspace_switch: tokio::sync::Mutex::new(()),
             tracing_info_fetch_attempts: config.tracing_info_fetch_attempts,
             tracing_info_fetch_interval: config.tracing_info_fetch_interval,
             tracing_info_fetch_consistency: config.tracing_info_fetch_consistency,
+            request_interceptors: config.request_interceptors,
+            statement_guards: config.statement_guards,
+            slow_query_log: SlowQueryLog::new(
+                config.slow_query_threshold,
+                config.slow_query_listener,
+            ),
+            request_span_level: config.request_span_level,
+            error_statement_max_length: config
+                .attach_statement_to_errors
+                .then_some(config.error_statement_max_length),
+            value_redaction: config.value_redaction,
+            buffer_pool,
+            background_tasks,
+            check_unprepared_bind_markers: config.check_unprepared_bind_markers,
+            ddl_ignore_already_exists: config.ddl_ignore_already_exists,
         };
 
         if let Some(keyspace_name) = config.used_keyspace {
@@ -554,14 +968,19 @@ impl Session {
         Ok(session)
     }
 
-    /// Sends a query to the database and receives a response.\
-    /// Returns only a single page of results, to receive multiple pages use [query_iter](Session::query_iter)
+    /// Sends a query to the database and receives all rows of the result.
+    ///
+    /// If the query has a [page size](Query::set_page_size) set, the pages are fetched one
+    /// after another and their rows are returned together, otherwise the database returns
+    /// all rows in a single response. Either way the whole result is kept in memory,
+    /// so use [`query_iter`](Session::query_iter) to go through large results, or
+    /// [`query_single_page`](Session::query_single_page) to fetch them page by page.
     ///
     /// This is the easiest way to make a query, but performance is worse than that of prepared queries.
     ///
     /// It is discouraged to use this method with non-empty values argument (`is_empty()` method from `SerializeRow`
     /// trait returns false). In such case, query first needs to be prepared (on a single connection), so
-    /// driver will perform 2 round trips instead of 1. Please use [`Session::execute()`] instead.
+    /// driver will perform 2 round trips instead of 1. Please use [`Session::execute_unpaged()`] instead.
     ///
     /// See [the book](https://rust-driver.docs.scylladb.com/stable/queries/simple.html) for more information
     /// # Arguments
@@ -575,7 +994,7 @@ impl Session {
     /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
     /// // Insert an int and text into a table
     /// session
-    ///     .query(
+    ///     .query_unpaged(
     ///         "INSERT INTO ks.tab (a, b) VALUES(?, ?)",
     ///         (2_i32, "some text")
     ///     )
@@ -591,7 +1010,7 @@ impl Session {
     ///
     /// // Read rows containing an int and text
     /// let rows_opt = session
-    /// .query("SELECT a, b FROM ks.tab", &[])
+    /// .query_unpaged("SELECT a, b FROM ks.tab", &[])
     ///     .await?
     ///     .rows;
     ///
@@ -604,67 +1023,282 @@ impl Session {
     /// # Ok(())
     /// # }
     /// ```
+    pub async fn query_unpaged(
+        &self,
+        query: impl Into<Query>,
+        values: impl SerializeRow,
+    ) -> Result<QueryResult, QueryError> {
+        self.with_value_redaction(self.query_page(&query.into(), values, Paging::AllPages))
+            .await
+            .map_err(QueryError::from)
+    }
+
+    /// Sends a query to the database and receives all rows of the result.
+    /// Same as [`query_unpaged`](Session::query_unpaged).
+    #[deprecated(
+        since = "0.12.0",
+        note = "use `query_unpaged`, `query_single_page` or `query_iter`, which tell how the result is paged"
+    )]
     pub async fn query(
         &self,
         query: impl Into<Query>,
         values: impl SerializeRow,
     ) -> Result<QueryResult, QueryError> {
-        self.query_paged(query, values, None).await
+        self.query_unpaged(query, values).await
     }
 
-    /// Queries the database with a custom paging state.
+    /// Queries the database for a single page of the result, starting at the given paging state.
+    ///
+    /// The page has at most [page size](Query::set_page_size) rows, or 5000 rows if the query
+    /// has no page size set. The [`paging_state`](QueryResult::paging_state) of the result
+    /// is `None` if it's the last page, otherwise it's passed to the next call to fetch
+    /// the following page.
     ///
     /// It is discouraged to use this method with non-empty values argument (`is_empty()` method from `SerializeRow`
     /// trait returns false). In such case, query first needs to be prepared (on a single connection), so
-    /// driver will perform 2 round trips instead of 1. Please use [`Session::execute_paged()`] instead.
+    /// driver will perform 2 round trips instead of 1. Please use [`Session::execute_single_page()`] instead.
     ///
     /// # Arguments
     ///
     /// * `query` - query to be performed
     /// * `values` - values bound to the query
     /// * `paging_state` - previously received paging state or None
+    ///
+    /// # Example
+    /// ```rust
+    /// # use scylla::Session;
+    /// # use std::error::Error;
+    /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+    /// use scylla::query::Query;
+    ///
+    /// let query = Query::new("SELECT a, b FROM ks.tab").with_page_size(100);
+    /// let mut paging_state = None;
+    /// loop {
+    ///     let page = session
+    ///         .query_single_page(query.clone(), &[], paging_state)
+    ///         .await?;
+    ///     paging_state = page.paging_state.clone();
+    ///
+    ///     for row in page.rows_typed::<(i32, String)>()? {
+    ///         let (a, b) = row?;
+    ///     }
+    ///     if paging_state.is_none() {
+    ///         break;
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn query_single_page(
+        &self,
+        query: impl Into<Query>,
+        values: impl SerializeRow,
+        paging_state: Option<Bytes>,
+    ) -> Result<QueryResult, QueryError> {
+        let mut query: Query = query.into();
+        if query.get_page_size().is_none() {
+            query.set_page_size(DEFAULT_SINGLE_PAGE_SIZE);
+        }
+        self.with_value_redaction(self.query_page(&query, values, Paging::Page(paging_state)))
+            .await
+            .map_err(QueryError::from)
+    }
+
+    /// Queries the database with a custom paging state.
+    ///
+    /// Unlike [`query_single_page`](Session::query_single_page), fetches all rows at once
+    /// if the query has no page size set.
+    #[deprecated(
+        since = "0.12.0",
+        note = "use `query_single_page`, which always fetches a single page"
+    )]
     pub async fn query_paged(
         &self,
         query: impl Into<Query>,
         values: impl SerializeRow,
         paging_state: Option<Bytes>,
     ) -> Result<QueryResult, QueryError> {
-        let query: Query = query.into();
+        self.with_value_redaction(self.query_page(
+            &query.into(),
+            values,
+            Paging::Page(paging_state),
+        ))
+        .await
+        .map_err(QueryError::from)
+    }
+
+    // Fetches the part of the result given by `paging` as a single request: the interceptors,
+    // statistics and the slow request report see it once, however many pages are fetched.
+    async fn query_page(
+        &self,
+        query: &Query,
+        values: impl SerializeRow,
+        paging: Paging,
+    ) -> Result<QueryResult, ExecutionError> {
+        let started_at = Instant::now();
+        let stats = RequestStats::for_statement(&query.config);
+        let mut context = (!self.request_interceptors.is_empty()).then(|| {
+            self.new_request_context(
+                InterceptedStatement::Query(query),
+                values.is_empty().then_some(0),
+            )
+        });
+
+        let result = match &mut context {
+            None => {
+                self.do_query_paged(query, values, paging, None, &stats)
+                    .await
+            }
+            Some(context) => match self.intercept_request(context).await {
+                Ok(Some(config)) => {
+                    let mut query = query.clone();
+                    query.config = config;
+                    self.do_query_paged(&query, values, paging, Some(context), &stats)
+                        .await
+                }
+                Ok(None) => {
+                    self.do_query_paged(query, values, paging, Some(context), &stats)
+                        .await
+                }
+                Err(e) => Err(e),
+            },
+        };
+
+        let result = result
+            .map(|result| stats.query_result(result))
+            .map_err(|error| {
+                self.attach_statement_info(
+                    stats.execution_error(error, query.config.is_idempotent),
+                    |max_length| query.statement_info(max_length),
+                )
+            });
+        if let Some(context) = &context {
+            self.intercept_response(context, &result);
+        }
+        self.report_slow_request(
+            started_at,
+            || Cow::Borrowed(&query.contents),
+            &stats,
+            &result,
+        );
+        result
+    }
+
+    /// With [`SessionConfig::check_unprepared_bind_markers`] enabled, fails if the query,
+    /// which is about to be sent unprepared and without values, has bind markers.
+    fn check_bind_markers(&self, query: &Query) -> Result<(), QueryError> {
+        if !self.check_unprepared_bind_markers {
+            return Ok(());
+        }
+        match query.bind_marker_count() {
+            0 => Ok(()),
+            expected => Err(SerializationError::new(ValueCountMismatchError {
+                expected,
+                actual: 0,
+                column_names: Vec::new(),
+            })
+            .into()),
+        }
+    }
+
+    /// Fails with [`QueryError::DeniedByGuard`] if any of the [`StatementGuard`]s denies the request.
+    fn check_statement_guards(
+        &self,
+        check: impl Fn(&dyn StatementGuard) -> GuardDecision,
+    ) -> Result<(), QueryError> {
+        for guard in self.statement_guards.iter() {
+            if let Err(error) = check(guard.as_ref()).into_result() {
+                self.metrics.inc_denied_statements();
+                debug!("Request denied by {:?}: {}", guard, error);
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    async fn do_query_paged(
+        &self,
+        query: &Query,
+        values: impl SerializeRow,
+        paging: Paging,
+        request_context: Option<&RequestContext<'_>>,
+        request_stats: &RequestStats,
+    ) -> Result<QueryResult, QueryError> {
+        self.check_statement_guards(|guard| {
+            guard.check_statement(&query.contents, statement_guard::StatementKind::Unprepared)
+        })?;
+        if values.is_empty() {
+            self.check_bind_markers(query)?;
+        }
+
+        match paging {
+            Paging::Page(paging_state) => {
+                self.do_query_page(query, &values, paging_state, request_context, request_stats)
+                    .await
+            }
+            Paging::AllPages => {
+                let first_page = self
+                    .do_query_page(query, &values, None, request_context, request_stats)
+                    .await?;
+                fetch_remaining_pages(first_page, |paging_state| {
+                    self.do_query_page(
+                        query,
+                        &values,
+                        Some(paging_state),
+                        request_context,
+                        request_stats,
+                    )
+                })
+                .await
+            }
+        }
+    }
 
+    async fn do_query_page(
+        &self,
+        query: &Query,
+        values: impl SerializeRow,
+        paging_state: Option<Bytes>,
+        request_context: Option<&RequestContext<'_>>,
+        request_stats: &RequestStats,
+    ) -> Result<QueryResult, QueryError> {
         let execution_profile = query
             .get_execution_profile_handle()
             .unwrap_or_else(|| self.get_default_execution_profile_handle())
             .access();
 
+        let serial_consistency = self.resolve_serial_consistency(
+            &query.config,
+            &execution_profile,
+            query.is_conditional(),
+        );
+        let priority = execution_profile.priority;
+        let session_keyspace = self.keyspace_name.load_full();
+        let (token, keyspace) = query.routing(session_keyspace.as_deref().map(String::as_str));
         let statement_info = RoutingInfo {
-            consistency: query
-                .config
-                .consistency
-                .unwrap_or(execution_profile.consistency),
-            serial_consistency: query
-                .config
-                .serial_consistency
-                .unwrap_or(execution_profile.serial_consistency),
+            consistency: self.resolve_consistency(&query.config, &execution_profile),
+            serial_consistency,
+            token,
+            keyspace,
             ..Default::default()
         };
 
-        let span = RequestSpan::new_query(&query.contents);
+        let span = RequestSpan::new_query(&query.contents, self.request_span_level);
         let span_ref = &span;
         let run_query_result = self
             .run_query(
                 statement_info,
                 &query.config,
                 execution_profile,
-                |node: Arc<Node>| async move { node.random_connection().await },
-                |connection: Arc<Connection>,
-                 consistency: Consistency,
-                 execution_profile: &ExecutionProfileInner| {
-                    let serial_consistency = query
-                        .config
-                        .serial_consistency
-                        .unwrap_or(execution_profile.serial_consistency);
+                |node: Arc<Node>| async move {
+                    match token {
+                        Some(token) => node.connection_for_token(token).await,
+                        None => node.random_connection().await,
+                    }
+                },
+                |connection: Arc<Connection>, consistency: Consistency| {
                     // Needed to avoid moving query and values into async move block
-                    let query_ref = &query;
+                    let query_ref = query;
                     let values_ref = &values;
                     let paging_state_ref = &paging_state;
                     async move {
@@ -675,6 +1309,7 @@ impl Session {
                                     query_ref,
                                     consistency,
                                     serial_consistency,
+                                    priority,
                                     paging_state_ref.clone(),
                                 )
                                 .await
@@ -689,6 +1324,7 @@ impl Session {
                                     &serialized,
                                     consistency,
                                     serial_consistency,
+                                    priority,
                                     paging_state_ref.clone(),
                                 )
                                 .await
@@ -697,6 +1333,8 @@ impl Session {
                     }
                 },
                 &span,
+                request_context,
+                request_stats,
             )
             .instrument(span.span().clone())
             .await?;
@@ -711,6 +1349,7 @@ impl Session {
         };
 
         self.handle_set_keyspace_response(&response).await?;
+        self.handle_schema_change_response(&response);
         self.handle_auto_await_schema_agreement(&response).await?;
 
         let result = response.into_query_result()?;
@@ -734,6 +1373,26 @@ impl Session {
         Ok(())
     }
 
+    /// Runs the future with the session's redaction of bound values, if it's set,
+    /// see [`SessionConfig::value_redaction`].
+    async fn with_value_redaction<F: Future>(&self, future: F) -> F::Output {
+        match self.value_redaction {
+            Some(redaction) => redaction.scope(future).await,
+            None => future.await,
+        }
+    }
+
+    /// Makes prepared statements affected by a schema change made by the session
+    /// confirm their result metadata, see [`SessionBuilder::skip_result_metadata`].
+    fn handle_schema_change_response(&self, response: &NonErrorQueryResponse) {
+        if let Some(change) = response
+            .as_schema_change()
+            .and_then(|schema_change| SchemaChange::from_event(&schema_change.event))
+        {
+            self.cluster.mark_result_metadata_changed(&change);
+        }
+    }
+
     async fn handle_auto_await_schema_agreement(
         &self,
         response: &NonErrorQueryResponse,
@@ -753,6 +1412,122 @@ impl Session {
         Ok(())
     }
 
+    fn new_request_context<'a>(
+        &self,
+        statement: InterceptedStatement<'a>,
+        values_count: Option<usize>,
+    ) -> RequestContext<'a> {
+        let config = statement.config();
+        let execution_profile = config
+            .execution_profile_handle
+            .as_ref()
+            .unwrap_or_else(|| self.get_default_execution_profile_handle())
+            .access();
+
+        RequestContext::new(
+            statement,
+            values_count,
+            self.resolve_consistency(config, &execution_profile),
+            self.resolve_serial_consistency(config, &execution_profile, statement.is_conditional()),
+        )
+    }
+
+    fn resolve_consistency(
+        &self,
+        config: &StatementConfig,
+        execution_profile: &ExecutionProfileInner,
+    ) -> Consistency {
+        config.resolve_consistency(execution_profile, self.default_consistency)
+    }
+
+    fn resolve_serial_consistency(
+        &self,
+        config: &StatementConfig,
+        execution_profile: &ExecutionProfileInner,
+        is_conditional: bool,
+    ) -> Option<SerialConsistency> {
+        config.resolve_serial_consistency(
+            execution_profile,
+            self.default_serial_consistency,
+            is_conditional,
+        )
+    }
+
+    /// Calls `on_request` of the interceptors in order, stopping at the first error.
+    /// Returns the statement config with the interceptors' modifications applied,
+    /// or `None` if nothing was modified.
+    async fn intercept_request(
+        &self,
+        context: &mut RequestContext<'_>,
+    ) -> Result<Option<StatementConfig>, QueryError> {
+        for interceptor in self.request_interceptors.iter() {
+            interceptor.on_request(context).await?;
+        }
+        Ok(context.modified_config())
+    }
+
+    /// Attaches information about the statement to the error if the session
+    /// is configured to do so. `statement_info` is given the maximum length of the statement text.
+    fn attach_statement_info(
+        &self,
+        error: ExecutionError,
+        statement_info: impl FnOnce(usize) -> StatementInfo,
+    ) -> ExecutionError {
+        match self.error_statement_max_length {
+            Some(max_length) => error.with_statement_info(statement_info(max_length)),
+            None => error,
+        }
+    }
+
+    fn intercept_response(
+        &self,
+        context: &RequestContext<'_>,
+        result: &Result<QueryResult, ExecutionError>,
+    ) {
+        for interceptor in self.request_interceptors.iter() {
+            interceptor.on_response(context, result);
+        }
+    }
+
+    /// Calls `on_response` of the interceptors with the error which prevented a paging iterator
+    /// from being created. The interceptors of a created iterator are called by its worker.
+    fn intercept_iterator_error(
+        &self,
+        statement: InterceptedStatement<'_>,
+        values_count: Option<usize>,
+        error: QueryError,
+    ) -> QueryError {
+        if !self.request_interceptors.is_empty() {
+            let context = self.new_request_context(statement, values_count);
+            let result = Err(ExecutionError::new(
+                error.clone(),
+                None,
+                0,
+                statement.config().is_idempotent,
+            ));
+            self.intercept_response(&context, &result);
+        }
+        error
+    }
+
+    fn report_slow_request<'s>(
+        &self,
+        started_at: Instant,
+        statement: impl FnOnce() -> Cow<'s, str>,
+        stats: &RequestStats,
+        result: &Result<QueryResult, ExecutionError>,
+    ) {
+        if let Some(slow_query_log) = &self.slow_query_log {
+            slow_query_log.report(
+                started_at.elapsed(),
+                statement,
+                stats,
+                false,
+                result.as_ref().err().map(ExecutionError::error),
+            );
+        }
+    }
+
     /// Run a simple query with paging\
     /// This method will query all pages of the result\
     ///
@@ -795,36 +1570,149 @@ impl Session {
         query: impl Into<Query>,
         values: impl SerializeRow,
     ) -> Result<RowIterator, QueryError> {
-        let query: Query = query.into();
+        self.query_iter_with_config(query, values, IterConfig::default())
+            .await
+    }
+
+    /// Does the same thing as [`Session::query_iter`], with options of the statement
+    /// overridden by the given [`IterConfig`], and the rows read limited by it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scylla::Session;
+    /// # use scylla::query::Query;
+    /// # use std::error::Error;
+    /// # async fn check_only_compiles(session: &Session, query: &Query) -> Result<(), Box<dyn Error>> {
+    /// use scylla::transport::iterator::IterConfig;
+    ///
+    /// // The page size of the shared query is left unchanged
+    /// let rows_stream = session
+    ///    .query_iter_with_config(query.clone(), &[], IterConfig::new().page_size(100))
+    ///    .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn query_iter_with_config(
+        &self,
+        query: impl Into<Query>,
+        values: impl SerializeRow,
+        config: IterConfig,
+    ) -> Result<RowIterator, QueryError> {
+        self.with_value_redaction(self.do_query_iter(query.into(), values, config))
+            .await
+    }
+
+    async fn do_query_iter(
+        &self,
+        mut query: Query,
+        values: impl SerializeRow,
+        config: IterConfig,
+    ) -> Result<RowIterator, QueryError> {
+        if let Some(page_size) = config.get_page_size() {
+            query.set_page_size(page_size);
+        }
+        let values_count = values.is_empty().then_some(0);
+
+        if !self.request_interceptors.is_empty() {
+            let mut context =
+                self.new_request_context(InterceptedStatement::Query(&query), values_count);
+            match self.intercept_request(&mut context).await {
+                Ok(Some(config)) => query.config = config,
+                Ok(None) => {}
+                Err(error) => {
+                    return Err(self.intercept_iterator_error(
+                        InterceptedStatement::Query(&query),
+                        values_count,
+                        error,
+                    ))
+                }
+            }
+        }
+
+        let checked = self
+            .check_statement_guards(|guard| {
+                guard.check_statement(&query.contents, statement_guard::StatementKind::Unprepared)
+            })
+            .and_then(|()| match values_count {
+                Some(_) => self.check_bind_markers(&query),
+                None => Ok(()),
+            });
+        if let Err(error) = checked {
+            return Err(self.intercept_iterator_error(
+                InterceptedStatement::Query(&query),
+                values_count,
+                error,
+            ));
+        }
 
         let execution_profile = query
             .get_execution_profile_handle()
             .unwrap_or_else(|| self.get_default_execution_profile_handle())
             .access();
 
-        if values.is_empty() {
+        let iterator = if values.is_empty() {
+            let consistency = self.resolve_consistency(&query.config, &execution_profile);
+            let serial_consistency = self.resolve_serial_consistency(
+                &query.config,
+                &execution_profile,
+                query.is_conditional(),
+            );
+            if query.get_routing_token().is_some() && query.get_routing_keyspace().is_none() {
+                let session_keyspace = self.keyspace_name.load_full();
+                query.set_routing_keyspace(session_keyspace.map(|keyspace| keyspace.to_string()));
+            }
             RowIterator::new_for_query(
                 query,
                 execution_profile,
+                consistency,
+                serial_consistency,
                 self.cluster.get_data(),
                 self.metrics.clone(),
+                self.slow_query_log.clone(),
+                self.request_span_level,
+                self.background_tasks.clone(),
+                self.request_interceptors.clone(),
             )
             .await
         } else {
             // Making RowIterator::new_for_query work with values is too hard (if even possible)
             // so instead of sending one prepare to a specific connection on each iterator query,
             // we fully prepare a statement beforehand.
-            let prepared = self.prepare(query).await?;
-            let values = prepared.serialize_values(&values)?;
+            let prepared = self.prepare(query.clone()).await.and_then(|prepared| {
+                let values = prepared.serialize_values(&values)?;
+                Ok((prepared, values))
+            });
+            let (prepared, values) = match prepared {
+                Ok(prepared) => prepared,
+                Err(error) => {
+                    return Err(self.intercept_iterator_error(
+                        InterceptedStatement::Query(&query),
+                        values_count,
+                        error,
+                    ))
+                }
+            };
             RowIterator::new_for_prepared_statement(PreparedIteratorConfig {
+                consistency: self.resolve_consistency(&prepared.config, &execution_profile),
+                serial_consistency: self.resolve_serial_consistency(
+                    &prepared.config,
+                    &execution_profile,
+                    prepared.is_conditional(),
+                ),
                 prepared,
                 values,
                 execution_profile,
                 cluster_data: self.cluster.get_data(),
                 metrics: self.metrics.clone(),
+                slow_query_log: self.slow_query_log.clone(),
+                request_span_level: self.request_span_level,
+                background_tasks: self.background_tasks.clone(),
+                request_interceptors: self.request_interceptors.clone(),
             })
             .await
-        }
+        }?;
+        Ok(iterator.with_limits(&config))
     }
 
     /// Prepares a statement on the server side and returns a prepared statement,
@@ -839,6 +1727,10 @@ impl Session {
     /// > must be sent as bound values
     /// > (see [performance section](https://rust-driver.docs.scylladb.com/stable/queries/prepared.html#performance))
     ///
+    /// Unless disabled with [`SessionBuilder::prepare_on_all_hosts`](crate::SessionBuilder::prepare_on_all_hosts),
+    /// the statement is prepared on all nodes concurrently. The preparation succeeds
+    /// if it succeeds on any node.
+    ///
     /// See [the book](https://rust-driver.docs.scylladb.com/stable/queries/prepared.html) for more information
     ///
     /// # Arguments
@@ -858,7 +1750,7 @@ impl Session {
     ///
     /// // Run the prepared query with some values, just like a simple query
     /// let to_insert: i32 = 12345;
-    /// session.execute(&prepared, (to_insert,)).await?;
+    /// session.execute_unpaged(&prepared, (to_insert,)).await?;
     /// # Ok(())
     /// # }
     /// ```
@@ -867,10 +1759,16 @@ impl Session {
         let query_ref = &query;
 
         let cluster_data = self.get_cluster_data();
-        let connections_iter = cluster_data.iter_working_connections()?;
+        let connections: Vec<Arc<Connection>> = if self.prepare_on_all_hosts {
+            cluster_data.iter_working_connections()?.collect()
+        } else {
+            cluster_data.any_working_node_connections()?
+        };
 
         // Prepare statements on all connections concurrently
-        let handles = connections_iter.map(|c| async move { c.prepare(query_ref).await });
+        let handles = connections
+            .iter()
+            .map(|c| async move { c.prepare(query_ref).await });
         let mut results = join_all(handles).await.into_iter();
 
         // If at least one prepare was successful, `prepare()` returns Ok.
@@ -901,10 +1799,46 @@ impl Session {
                 .and_then(PartitionerName::from_str)
                 .unwrap_or_default(),
         );
+        self.cluster.register_prepared_statement(&prepared);
 
         Ok(prepared)
     }
 
+    /// Returns a receiver of schema changes which may affect prepared statements.
+    pub(crate) fn subscribe_schema_changes(&self) -> broadcast::Receiver<SchemaChange> {
+        self.cluster.subscribe_schema_changes()
+    }
+
+    /// Re-prepares the statement if the table it operates on was dropped since it was
+    /// prepared, so that it isn't executed with metadata of the dropped table.
+    /// It's enough to do it on one node, others re-prepare it when they respond with `Unprepared`.
+    async fn reprepare_if_stale(&self, prepared: &PreparedStatement) -> Result<(), QueryError> {
+        if !prepared.take_stale() {
+            return Ok(());
+        }
+        debug!(
+            "Re-preparing statement {:?} after its table was dropped",
+            prepared.get_statement()
+        );
+        let result = match self.get_cluster_data().any_working_node_connections() {
+            // There is at least one connection, see `ClusterData::any_working_node_connections()`
+            Ok(connections) => {
+                connections[0]
+                    .reprepare(prepared.get_statement(), prepared)
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+        if let Err(err) = &result {
+            // Unless the statement can't be executed anymore anyway, it's re-prepared
+            // next time again, e.g. if its table wasn't created again yet
+            if !matches!(err, QueryError::BadQuery(BadQuery::PreparedMetadataChanged)) {
+                prepared.mark_stale();
+            }
+        }
+        result
+    }
+
     fn extract_partitioner_name<'a>(
         &self,
         prepared: &PreparedStatement,
@@ -919,9 +1853,14 @@ impl Session {
             .as_deref()
     }
 
-    /// Execute a prepared query. Requires a [PreparedStatement]
-    /// generated using [`Session::prepare`](Session::prepare)\
-    /// Returns only a single page of results, to receive multiple pages use [execute_iter](Session::execute_iter)
+    /// Execute a prepared query and receive all rows of the result. Requires a [PreparedStatement]
+    /// generated using [`Session::prepare`](Session::prepare)
+    ///
+    /// If the statement has a [page size](PreparedStatement::set_page_size) set, the pages are
+    /// fetched one after another and their rows are returned together, otherwise the database
+    /// returns all rows in a single response. Either way the whole result is kept in memory,
+    /// so use [`execute_iter`](Session::execute_iter) to go through large results, or
+    /// [`execute_single_page`](Session::execute_single_page) to fetch them page by page.
     ///
     /// Prepared queries are much faster than simple queries:
     /// * Database doesn't need to parse the query
@@ -952,52 +1891,321 @@ impl Session {
     ///
     /// // Run the prepared query with some values, just like a simple query
     /// let to_insert: i32 = 12345;
-    /// session.execute(&prepared, (to_insert,)).await?;
+    /// session.execute_unpaged(&prepared, (to_insert,)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_unpaged(
+        &self,
+        prepared: &PreparedStatement,
+        values: impl SerializeRow,
+    ) -> Result<QueryResult, QueryError> {
+        self.with_value_redaction(self.execute_page(prepared, values, Paging::AllPages))
+            .await
+            .map_err(QueryError::from)
+    }
+
+    /// Executes a prepared statement and receives all rows of the result.
+    /// Same as [`execute_unpaged`](Session::execute_unpaged).
+    #[deprecated(
+        since = "0.12.0",
+        note = "use `execute_unpaged`, `execute_single_page` or `execute_iter`, which tell how the result is paged"
+    )]
+    pub async fn execute(
+        &self,
+        prepared: &PreparedStatement,
+        values: impl SerializeRow,
+    ) -> Result<QueryResult, QueryError> {
+        self.execute_unpaged(prepared, values).await
+    }
+
+    /// Executes a prepared statement for a single page of the result,
+    /// starting at the given paging state.
+    ///
+    /// The page has at most [page size](PreparedStatement::set_page_size) rows, or 5000 rows
+    /// if the statement has no page size set. The [`paging_state`](QueryResult::paging_state)
+    /// of the result is `None` if it's the last page, otherwise it's passed to the next call
+    /// to fetch the following page. See [`query_single_page`](Session::query_single_page)
+    /// for an example.
+    ///
+    /// # Arguments
+    ///
+    /// * `prepared` - a statement prepared with [prepare](crate::transport::session::Session::prepare)
+    /// * `values` - values bound to the query
+    /// * `paging_state` - paging state from the previous query or None
+    pub async fn execute_single_page(
+        &self,
+        prepared: &PreparedStatement,
+        values: impl SerializeRow,
+        paging_state: Option<Bytes>,
+    ) -> Result<QueryResult, QueryError> {
+        if prepared.get_page_size().is_some() {
+            return self
+                .with_value_redaction(self.execute_page(
+                    prepared,
+                    values,
+                    Paging::Page(paging_state),
+                ))
+                .await
+                .map_err(QueryError::from);
+        }
+        let mut prepared = prepared.clone();
+        prepared.set_page_size(DEFAULT_SINGLE_PAGE_SIZE);
+        self.with_value_redaction(self.execute_page(&prepared, values, Paging::Page(paging_state)))
+            .await
+            .map_err(QueryError::from)
+    }
+
+    /// Executes a previously prepared statement with previously received paging state.
+    ///
+    /// Unlike [`execute_single_page`](Session::execute_single_page), fetches all rows at once
+    /// if the statement has no page size set.
+    #[deprecated(
+        since = "0.12.0",
+        note = "use `execute_single_page`, which always fetches a single page"
+    )]
+    pub async fn execute_paged(
+        &self,
+        prepared: &PreparedStatement,
+        values: impl SerializeRow,
+        paging_state: Option<Bytes>,
+    ) -> Result<QueryResult, QueryError> {
+        self.with_value_redaction(self.execute_page(prepared, values, Paging::Page(paging_state)))
+            .await
+            .map_err(QueryError::from)
+    }
+
+    // Fetches the part of the result given by `paging`, see `query_page`.
+    async fn execute_page(
+        &self,
+        prepared: &PreparedStatement,
+        values: impl SerializeRow,
+        paging: Paging,
+    ) -> Result<QueryResult, ExecutionError> {
+        let serialized_values = prepared
+            .serialize_values_in(take_buffer(self.buffer_pool.as_deref()), &values)
+            .map_err(QueryError::from)?;
+        let result = self
+            .execute_serialized(prepared, &serialized_values, None, paging)
+            .await;
+        give_back_buffer(self.buffer_pool.as_deref(), serialized_values.into_buffer());
+        result
+    }
+
+    /// Executes a prepared statement with values bound to it in advance.
+    ///
+    /// Unlike [`execute_unpaged`](Session::execute_unpaged), neither serializes the values nor computes
+    /// the token, as both were done by [`PreparedStatement::bind`]. Requests are the same
+    /// as if the statement was executed with the values the usual way.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use scylla::Session;
+    /// # use std::error::Error;
+    /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+    /// let prepared = session
+    ///     .prepare("INSERT INTO ks.heartbeats (id, alive) VALUES (?, true)")
+    ///     .await?;
+    /// let bound = prepared.bind(&(42,))?;
+    ///
+    /// // The values are serialized only once
+    /// for _ in 0..3 {
+    ///     session.execute_bound(&bound).await?;
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn execute(
+    pub async fn execute_bound(&self, bound: &BoundStatement) -> Result<QueryResult, QueryError> {
+        self.execute_bound_paged(bound, None).await
+    }
+
+    /// Executes a prepared statement with values bound to it in advance,
+    /// with previously received paging state.
+    /// See [`execute_bound`](Session::execute_bound).
+    pub async fn execute_bound_paged(
+        &self,
+        bound: &BoundStatement,
+        paging_state: Option<Bytes>,
+    ) -> Result<QueryResult, QueryError> {
+        self.with_value_redaction(self.execute_serialized(
+            &bound.prepared,
+            &bound.values,
+            bound.get_token(),
+            Paging::Page(paging_state),
+        ))
+        .await
+        .map_err(QueryError::from)
+    }
+
+    // Executes the statement with serialized values. `bound_token` is the token
+    // computed when the values were bound, if they were.
+    async fn execute_serialized(
         &self,
         prepared: &PreparedStatement,
-        values: impl SerializeRow,
+        serialized_values: &SerializedValues,
+        bound_token: Option<Token>,
+        paging: Paging,
+    ) -> Result<QueryResult, ExecutionError> {
+        let started_at = Instant::now();
+        let stats = RequestStats::for_statement(&prepared.config);
+        let mut context = (!self.request_interceptors.is_empty()).then(|| {
+            self.new_request_context(
+                InterceptedStatement::Prepared(prepared),
+                Some(serialized_values.element_count() as usize),
+            )
+        });
+
+        let result = match &mut context {
+            None => {
+                self.do_execute_paged(
+                    prepared,
+                    serialized_values,
+                    bound_token,
+                    paging,
+                    None,
+                    &stats,
+                )
+                .await
+            }
+            Some(context) => match self.intercept_request(context).await {
+                Ok(Some(config)) => {
+                    let mut prepared = prepared.clone();
+                    prepared.config = config;
+                    self.do_execute_paged(
+                        &prepared,
+                        serialized_values,
+                        bound_token,
+                        paging,
+                        Some(context),
+                        &stats,
+                    )
+                    .await
+                }
+                Ok(None) => {
+                    self.do_execute_paged(
+                        prepared,
+                        serialized_values,
+                        bound_token,
+                        paging,
+                        Some(context),
+                        &stats,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            },
+        };
+
+        let result = result
+            .map(|result| stats.query_result(result))
+            .map_err(|error| {
+                self.attach_statement_info(
+                    stats.execution_error(error, prepared.config.is_idempotent),
+                    |_| prepared.statement_info(),
+                )
+            });
+        if let Some(context) = &context {
+            self.intercept_response(context, &result);
+        }
+        self.report_slow_request(
+            started_at,
+            || Cow::Borrowed(prepared.get_statement()),
+            &stats,
+            &result,
+        );
+        result
+    }
+
+    async fn do_execute_paged(
+        &self,
+        prepared: &PreparedStatement,
+        serialized_values: &SerializedValues,
+        bound_token: Option<Token>,
+        paging: Paging,
+        request_context: Option<&RequestContext<'_>>,
+        request_stats: &RequestStats,
     ) -> Result<QueryResult, QueryError> {
-        self.execute_paged(prepared, values, None).await
+        self.check_statement_guards(|guard| {
+            guard.check_statement(
+                prepared.get_statement(),
+                statement_guard::StatementKind::Prepared,
+            )
+        })?;
+        self.reprepare_if_stale(prepared).await?;
+        if prepared.has_outdated_metadata() {
+            return Err(QueryError::BadQuery(BadQuery::PreparedMetadataChanged));
+        }
+
+        match paging {
+            Paging::Page(paging_state) => {
+                self.do_execute_page(
+                    prepared,
+                    serialized_values,
+                    bound_token,
+                    paging_state,
+                    request_context,
+                    request_stats,
+                )
+                .await
+            }
+            Paging::AllPages => {
+                let first_page = self
+                    .do_execute_page(
+                        prepared,
+                        serialized_values,
+                        bound_token,
+                        None,
+                        request_context,
+                        request_stats,
+                    )
+                    .await?;
+                fetch_remaining_pages(first_page, |paging_state| {
+                    self.do_execute_page(
+                        prepared,
+                        serialized_values,
+                        bound_token,
+                        Some(paging_state),
+                        request_context,
+                        request_stats,
+                    )
+                })
+                .await
+            }
+        }
     }
 
-    /// Executes a previously prepared statement with previously received paging state
-    /// # Arguments
-    ///
-    /// * `prepared` - a statement prepared with [prepare](crate::transport::session::Session::prepare)
-    /// * `values` - values bound to the query
-    /// * `paging_state` - paging state from the previous query or None
-    pub async fn execute_paged(
+    async fn do_execute_page(
         &self,
         prepared: &PreparedStatement,
-        values: impl SerializeRow,
+        serialized_values: &SerializedValues,
+        bound_token: Option<Token>,
         paging_state: Option<Bytes>,
+        request_context: Option<&RequestContext<'_>>,
+        request_stats: &RequestStats,
     ) -> Result<QueryResult, QueryError> {
-        let serialized_values = prepared.serialize_values(&values)?;
-        let values_ref = &serialized_values;
+        let values_ref = serialized_values;
         let paging_state_ref = &paging_state;
 
-        let (partition_key, token) = prepared
-            .extract_partition_key_and_calculate_token(prepared.get_partitioner_name(), values_ref)?
-            .unzip();
+        let (partition_key, token) = match bound_token {
+            // The partition key is only needed for tracing, extracting it is cheap
+            Some(token) => (prepared.extract_partition_key(values_ref).ok(), Some(token)),
+            None => prepared.partition_key_and_routing_token(values_ref)?,
+        };
 
         let execution_profile = prepared
             .get_execution_profile_handle()
             .unwrap_or_else(|| self.get_default_execution_profile_handle())
             .access();
 
+        let serial_consistency = self.resolve_serial_consistency(
+            &prepared.config,
+            &execution_profile,
+            prepared.is_conditional(),
+        );
+        let priority = execution_profile.priority;
         let statement_info = RoutingInfo {
-            consistency: prepared
-                .config
-                .consistency
-                .unwrap_or(execution_profile.consistency),
-            serial_consistency: prepared
-                .config
-                .serial_consistency
-                .unwrap_or(execution_profile.serial_consistency),
+            consistency: self.resolve_consistency(&prepared.config, &execution_profile),
+            serial_consistency,
             token,
             keyspace: prepared.get_keyspace_name(),
             is_confirmed_lwt: prepared.is_confirmed_lwt(),
@@ -1007,7 +2215,9 @@ impl Session {
             partition_key.as_ref().map(|pk| pk.iter()),
             token,
             serialized_values.buffer_size(),
+            self.request_span_level,
         );
+        span.record_table(prepared.get_keyspace_name(), prepared.get_table_name());
 
         if !span.span().is_disabled() {
             if let (Some(keyspace), Some(token)) = (statement_info.keyspace.as_ref(), token) {
@@ -1030,27 +2240,22 @@ impl Session {
                         None => node.random_connection().await,
                     }
                 },
-                |connection: Arc<Connection>,
-                 consistency: Consistency,
-                 execution_profile: &ExecutionProfileInner| {
-                    let serial_consistency = prepared
-                        .config
-                        .serial_consistency
-                        .unwrap_or(execution_profile.serial_consistency);
-                    async move {
-                        connection
-                            .execute_with_consistency(
-                                prepared,
-                                values_ref,
-                                consistency,
-                                serial_consistency,
-                                paging_state_ref.clone(),
-                            )
-                            .await
-                            .and_then(QueryResponse::into_non_error_query_response)
-                    }
+                |connection: Arc<Connection>, consistency: Consistency| async move {
+                    connection
+                        .execute_with_consistency(
+                            prepared,
+                            values_ref,
+                            consistency,
+                            serial_consistency,
+                            priority,
+                            paging_state_ref.clone(),
+                        )
+                        .await
+                        .and_then(QueryResponse::into_non_error_query_response)
                 },
                 &span,
+                request_context,
+                request_stats,
             )
             .instrument(span.span().clone())
             .await?;
@@ -1065,6 +2270,7 @@ impl Session {
         };
 
         self.handle_set_keyspace_response(&response).await?;
+        self.handle_schema_change_response(&response);
         self.handle_auto_await_schema_agreement(&response).await?;
 
         let result = response.into_query_result()?;
@@ -1118,8 +2324,66 @@ impl Session {
         prepared: impl Into<PreparedStatement>,
         values: impl SerializeRow,
     ) -> Result<RowIterator, QueryError> {
-        let prepared = prepared.into();
+        self.execute_iter_with_config(prepared, values, IterConfig::default())
+            .await
+    }
+
+    /// Does the same thing as [`Session::execute_iter`], with options of the statement
+    /// overridden by the given [`IterConfig`], and the rows read limited by it.
+    pub async fn execute_iter_with_config(
+        &self,
+        prepared: impl Into<PreparedStatement>,
+        values: impl SerializeRow,
+        config: IterConfig,
+    ) -> Result<RowIterator, QueryError> {
+        self.with_value_redaction(self.do_execute_iter(prepared.into(), values, config))
+            .await
+    }
+
+    async fn do_execute_iter(
+        &self,
+        mut prepared: PreparedStatement,
+        values: impl SerializeRow,
+        config: IterConfig,
+    ) -> Result<RowIterator, QueryError> {
+        if let Some(page_size) = config.get_page_size() {
+            prepared.set_page_size(page_size);
+        }
+        self.reprepare_if_stale(&prepared).await?;
+        if prepared.has_outdated_metadata() {
+            return Err(QueryError::BadQuery(BadQuery::PreparedMetadataChanged));
+        }
         let serialized_values = prepared.serialize_values(&values)?;
+        let values_count = Some(serialized_values.element_count() as usize);
+
+        if !self.request_interceptors.is_empty() {
+            let mut context =
+                self.new_request_context(InterceptedStatement::Prepared(&prepared), values_count);
+            match self.intercept_request(&mut context).await {
+                Ok(Some(config)) => prepared.config = config,
+                Ok(None) => {}
+                Err(error) => {
+                    return Err(self.intercept_iterator_error(
+                        InterceptedStatement::Prepared(&prepared),
+                        values_count,
+                        error,
+                    ))
+                }
+            }
+        }
+
+        if let Err(error) = self.check_statement_guards(|guard| {
+            guard.check_statement(
+                prepared.get_statement(),
+                statement_guard::StatementKind::Prepared,
+            )
+        }) {
+            return Err(self.intercept_iterator_error(
+                InterceptedStatement::Prepared(&prepared),
+                values_count,
+                error,
+            ));
+        }
 
         let execution_profile = prepared
             .get_execution_profile_handle()
@@ -1127,13 +2391,63 @@ impl Session {
             .access();
 
         RowIterator::new_for_prepared_statement(PreparedIteratorConfig {
+            consistency: self.resolve_consistency(&prepared.config, &execution_profile),
+            serial_consistency: self.resolve_serial_consistency(
+                &prepared.config,
+                &execution_profile,
+                prepared.is_conditional(),
+            ),
             prepared,
             values: serialized_values,
             execution_profile,
             cluster_data: self.cluster.get_data(),
             metrics: self.metrics.clone(),
+            slow_query_log: self.slow_query_log.clone(),
+            request_span_level: self.request_span_level,
+            background_tasks: self.background_tasks.clone(),
+            request_interceptors: self.request_interceptors.clone(),
         })
         .await
+        .map(|iterator| iterator.with_limits(&config))
+    }
+
+    /// Reads all rows of a table, scanning ranges of the token ring in parallel.
+    ///
+    /// Every token range is read with a separate paged query sent to a replica owning
+    /// the range. The rows are returned in no particular order, each of them exactly once.
+    /// Failed pages are retried without restarting the scan, see [`ScanConfig`].
+    ///
+    /// The partition key of the table is taken from the schema metadata, so the table
+    /// must be known to the session (see [`Session::refresh_metadata`]).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scylla::Session;
+    /// # use std::error::Error;
+    /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+    /// use futures::stream::StreamExt;
+    /// use scylla::transport::table_scan::ScanConfig;
+    ///
+    /// let config = ScanConfig::new().parallelism(8).columns("a, b");
+    /// let mut scan = session.scan_table("ks", "t", config).await?;
+    /// let progress = scan.progress();
+    ///
+    /// while let Some(row) = scan.next().await {
+    ///     let (a, b): (i32, i32) = row?.into_typed()?;
+    ///     println!("a, b: {}, {}", a, b);
+    /// }
+    /// assert!(progress.is_finished());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn scan_table(
+        &self,
+        keyspace: &str,
+        table: &str,
+        config: ScanConfig,
+    ) -> Result<TableScan<'_>, QueryError> {
+        TableScan::new(self, keyspace, table, config).await
     }
 
     /// Perform a batch query\
@@ -1185,6 +2499,72 @@ impl Session {
         &self,
         batch: &Batch,
         values: impl BatchValues,
+    ) -> Result<QueryResult, QueryError> {
+        self.with_value_redaction(self.batch_request(batch, values))
+            .await
+            .map_err(QueryError::from)
+    }
+
+    // Sends the batch as a single request, see `query_page`.
+    async fn batch_request(
+        &self,
+        batch: &Batch,
+        values: impl BatchValues,
+    ) -> Result<QueryResult, ExecutionError> {
+        let started_at = Instant::now();
+        let stats = RequestStats::for_statement(&batch.config);
+        let mut context = (!self.request_interceptors.is_empty())
+            .then(|| self.new_request_context(InterceptedStatement::Batch(batch), None));
+
+        let result = match &mut context {
+            None => self.do_batch(batch, values, None, &stats).await,
+            Some(context) => match self.intercept_request(context).await {
+                Ok(Some(config)) => {
+                    let mut batch = batch.clone();
+                    batch.config = config;
+                    self.do_batch(&batch, values, Some(context), &stats).await
+                }
+                Ok(None) => self.do_batch(batch, values, Some(context), &stats).await,
+                Err(e) => Err(e),
+            },
+        };
+
+        let result = result
+            .map(|result| stats.query_result(result))
+            .map_err(|error| {
+                self.attach_statement_info(
+                    stats.execution_error(error, batch.config.is_idempotent),
+                    |max_length| batch.statement_info(max_length),
+                )
+            });
+        if let Some(context) = &context {
+            self.intercept_response(context, &result);
+        }
+        self.report_slow_request(
+            started_at,
+            || {
+                let statements: Vec<&str> = batch
+                    .statements
+                    .iter()
+                    .map(|statement| match statement {
+                        BatchStatement::Query(query) => query.contents.as_str(),
+                        BatchStatement::PreparedStatement(prepared) => prepared.get_statement(),
+                    })
+                    .collect();
+                Cow::Owned(statements.join("; "))
+            },
+            &stats,
+            &result,
+        );
+        result
+    }
+
+    async fn do_batch(
+        &self,
+        batch: &Batch,
+        values: impl BatchValues,
+        request_context: Option<&RequestContext<'_>>,
+        request_stats: &RequestStats,
     ) -> Result<QueryResult, QueryError> {
         // Shard-awareness behavior for batch will be to pick shard based on first batch statement's shard
         // If users batch statements by shard, they will be rewarded with full shard awareness
@@ -1196,21 +2576,37 @@ impl Session {
                 BadQuery::TooManyQueriesInBatchStatement(batch_statements_length),
             ));
         }
+        self.check_statement_guards(|guard| guard.check_batch(batch))?;
+        for statement in &batch.statements {
+            if let BatchStatement::PreparedStatement(prepared) = statement {
+                self.reprepare_if_stale(prepared).await?;
+            }
+        }
 
         let execution_profile = batch
             .get_execution_profile_handle()
             .unwrap_or_else(|| self.get_default_execution_profile_handle())
             .access();
 
-        let consistency = batch
-            .config
-            .consistency
-            .unwrap_or(execution_profile.consistency);
+        let consistency = self.resolve_consistency(&batch.config, &execution_profile);
 
-        let serial_consistency = batch
-            .config
-            .serial_consistency
-            .unwrap_or(execution_profile.serial_consistency);
+        let serial_consistency = self.resolve_serial_consistency(
+            &batch.config,
+            &execution_profile,
+            batch.is_conditional(),
+        );
+        let priority = execution_profile.priority;
+
+        if self.check_unprepared_bind_markers {
+            // Statements with values are prepared before the batch is sent
+            let mut values_iter = values.batch_values_iter();
+            for statement in &batch.statements {
+                let has_values = values_iter.is_empty_next() == Some(false);
+                if let (BatchStatement::Query(query), false) = (statement, has_values) {
+                    self.check_bind_markers(query)?;
+                }
+            }
+        }
 
         let keyspace_name = match batch.statements.first() {
             Some(BatchStatement::PreparedStatement(ps)) => ps.get_keyspace_name(),
@@ -1229,7 +2625,10 @@ impl Session {
             is_confirmed_lwt: false,
         };
 
-        let span = RequestSpan::new_batch();
+        let span = RequestSpan::new_batch(self.request_span_level);
+        if let Some(BatchStatement::PreparedStatement(ps)) = batch.statements.first() {
+            span.record_table(ps.get_keyspace_name(), ps.get_table_name());
+        }
 
         let run_query_result = self
             .run_query(
@@ -1244,25 +2643,20 @@ impl Session {
                         None => node.random_connection().await,
                     }
                 },
-                |connection: Arc<Connection>,
-                 consistency: Consistency,
-                 execution_profile: &ExecutionProfileInner| {
-                    let serial_consistency = batch
-                        .config
-                        .serial_consistency
-                        .unwrap_or(execution_profile.serial_consistency);
-                    async move {
-                        connection
-                            .batch_with_consistency(
-                                batch,
-                                values_ref,
-                                consistency,
-                                serial_consistency,
-                            )
-                            .await
-                    }
+                |connection: Arc<Connection>, consistency: Consistency| async move {
+                    connection
+                        .batch_with_consistency(
+                            batch,
+                            values_ref,
+                            consistency,
+                            serial_consistency,
+                            priority,
+                        )
+                        .await
                 },
                 &span,
+                request_context,
+                request_stats,
             )
             .instrument(span.span().clone())
             .await?;
@@ -1328,9 +2722,11 @@ impl Session {
     /// Note that even failed `use_keyspace` can change currently used keyspace - the request is sent on all connections and
     /// can overwrite previously used keyspace.
     ///
-    /// Call only one `use_keyspace` at a time.\
-    /// Trying to do two `use_keyspace` requests simultaneously with different names
-    /// can end with some connections using one keyspace and the rest using the other.
+    /// Concurrent calls are performed one after another, so that all connections end up using
+    /// the keyspace of the call which was performed last.
+    /// Requests sent while the keyspace is being switched may use either of the keyspaces.
+    ///
+    /// Returns the keyspace used before the call, same as [`get_keyspace`](Session::get_keyspace).
     ///
     /// See [the book](https://rust-driver.docs.scylladb.com/stable/queries/usekeyspace.html) for more information
     ///
@@ -1338,7 +2734,8 @@ impl Session {
     ///
     /// * `keyspace_name` - keyspace name to use,
     /// keyspace names can have up to 48 alphanumeric characters and contain underscores
-    /// * `case_sensitive` - if set to true the generated query will put keyspace name in quotes
+    /// * `case_sensitive` - if set to true the generated query will put keyspace name in quotes,
+    ///   otherwise the name refers to the keyspace with the lowercase name
     /// # Example
     /// ```rust
     /// # use scylla::{Session, SessionBuilder};
@@ -1346,14 +2743,14 @@ impl Session {
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
     /// # let session = SessionBuilder::new().known_node("127.0.0.1:9042").build().await?;
     /// session
-    ///     .query("INSERT INTO my_keyspace.tab (a) VALUES ('test1')", &[])
+    ///     .query_unpaged("INSERT INTO my_keyspace.tab (a) VALUES ('test1')", &[])
     ///     .await?;
     ///
     /// session.use_keyspace("my_keyspace", false).await?;
     ///
     /// // Now we can omit keyspace name in the query
     /// session
-    ///     .query("INSERT INTO tab (a) VALUES ('test2')", &[])
+    ///     .query_unpaged("INSERT INTO tab (a) VALUES ('test2')", &[])
     ///     .await?;
     /// # Ok(())
     /// # }
@@ -1362,19 +2759,47 @@ impl Session {
         &self,
         keyspace_name: impl Into<String>,
         case_sensitive: bool,
-    ) -> Result<(), QueryError> {
+    ) -> Result<Option<Arc<String>>, QueryError> {
         let keyspace_name = keyspace_name.into();
-        self.keyspace_name
-            .store(Some(Arc::new(keyspace_name.clone())));
 
         // Trying to pass keyspace as bound value in "USE ?" doesn't work
         // So we have to create a string for query: "USE " + new_keyspace
         // To avoid any possible CQL injections it's good to verify that the name is valid
         let verified_ks_name = VerifiedKeyspaceName::new(keyspace_name, case_sensitive)?;
+        let used_name = if case_sensitive {
+            verified_ks_name.as_str().to_owned()
+        } else {
+            verified_ks_name.as_str().to_lowercase()
+        };
 
+        let _switch = self.keyspace_switch.lock().await;
+        // Connections opened from now on use the new keyspace, even if the switch fails
+        let previous = self.keyspace_name.swap(Some(Arc::new(used_name)));
         self.cluster.use_keyspace(verified_ks_name).await?;
 
-        Ok(())
+        Ok(previous)
+    }
+
+    /// Same as [`use_keyspace`](Session::use_keyspace), but takes the keyspace as
+    /// a [`CqlIdentifier`], which knows by itself whether the name is case sensitive.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use scylla::Session;
+    /// # async fn example(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    /// use scylla::statement::identifier::CqlIdentifier;
+    ///
+    /// // Same as session.use_keyspace("MyKeyspace", true)
+    /// let keyspace: CqlIdentifier = "\"MyKeyspace\"".parse()?;
+    /// session.use_keyspace_identifier(&keyspace).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn use_keyspace_identifier(
+        &self,
+        keyspace: &CqlIdentifier,
+    ) -> Result<Option<Arc<String>>, QueryError> {
+        self.use_keyspace(keyspace.name(), true).await
     }
 
     /// Manually trigger a metadata refresh\
@@ -1393,6 +2818,32 @@ impl Session {
         self.metrics.clone()
     }
 
+    /// Returns a snapshot of the tasks that the driver runs in the background for this session,
+    /// e.g. connection pool refillers and connection routers, with the nodes they work with
+    /// and when they were last active.
+    ///
+    /// See [`background_tasks`](crate::transport::background_tasks) for more information.
+    pub fn background_tasks_status(&self) -> Vec<BackgroundTaskStatus> {
+        self.background_tasks.status()
+    }
+
+    /// Returns a handle to the background tasks of this session.
+    /// Unlike the session, it can be kept to check that the tasks stopped
+    /// after the session was dropped.
+    pub fn background_tasks(&self) -> BackgroundTasks {
+        self.background_tasks.clone()
+    }
+
+    fn runtime(&self) -> &dyn Runtime {
+        self.background_tasks.runtime().as_ref()
+    }
+
+    /// Returns the node the control connection is currently established to,
+    /// and how many times it was moved to another node.
+    pub fn get_control_connection_status(&self) -> ControlConnectionStatus {
+        self.cluster.get_control_connection_status()
+    }
+
     /// Access cluster data collected by the driver\
     /// Driver collects various information about network topology or schema.
     /// They can be read using this method
@@ -1413,7 +2864,7 @@ impl Session {
 
             match current_try {
                 Some(tracing_info) => return Ok(tracing_info),
-                None => tokio::time::sleep(self.tracing_info_fetch_interval).await,
+                None => self.runtime().sleep(self.tracing_info_fetch_interval).await,
             };
         }
 
@@ -1432,14 +2883,39 @@ impl Session {
     /// in the session configuration, but calling `use_keyspace` will update
     /// it.
     ///
-    /// Note: the return value might be wrong if `use_keyspace` was called
-    /// concurrently or it previously failed. It is also unspecified
-    /// if `get_keyspace` is called concurrently with `use_keyspace`.
+    /// Names passed to `use_keyspace` as case insensitive are returned lowercase,
+    /// as they are stored in the schema.
+    ///
+    /// Note: while `use_keyspace` is in progress, some connections may still use
+    /// the previous keyspace. Once it finishes, even if it failed, the returned keyspace
+    /// is the one used by new connections.
     #[inline]
     pub fn get_keyspace(&self) -> Option<Arc<String>> {
         self.keyspace_name.load_full()
     }
 
+    /// Returns the metadata of the table, as currently known to the driver.\
+    /// When `keyspace` is `None`, the table is looked up in the keyspace used by the session
+    /// (see [`get_keyspace`](Session::get_keyspace)).
+    ///
+    /// Returns `None` if the table, or the keyspace, is unknown.
+    pub fn get_table_metadata(&self, keyspace: Option<&str>, table: &str) -> Option<Table> {
+        let used_keyspace;
+        let keyspace = match keyspace {
+            Some(keyspace) => keyspace,
+            None => {
+                used_keyspace = self.get_keyspace()?;
+                used_keyspace.as_str()
+            }
+        };
+        self.get_cluster_data()
+            .get_keyspace_info()
+            .get(keyspace)?
+            .tables
+            .get(table)
+            .cloned()
+    }
+
     // Tries getting the tracing info
     // If the queries return 0 rows then returns None - the information didn't reach this node yet
     // If there is some other error returns this error
@@ -1459,8 +2935,8 @@ impl Session {
         traces_events_query.set_page_size(1024);
 
         let (traces_session_res, traces_events_res) = tokio::try_join!(
-            self.query(traces_session_query, (tracing_id,)),
-            self.query(traces_events_query, (tracing_id,))
+            self.query_unpaged(traces_session_query, (tracing_id,)),
+            self.query_unpaged(traces_events_query, (tracing_id,))
         )?;
 
         // Get tracing info
@@ -1518,14 +2994,17 @@ impl Session {
     // On success this query's result is returned
     // I tried to make this closures take a reference instead of an Arc but failed
     // maybe once async closures get stabilized this can be fixed
+    #[allow(clippy::too_many_arguments)]
     async fn run_query<'a, ConnFut, QueryFut, ResT>(
         &'a self,
         statement_info: RoutingInfo<'a>,
         statement_config: &'a StatementConfig,
         execution_profile: Arc<ExecutionProfileInner>,
         choose_connection: impl Fn(Arc<Node>) -> ConnFut,
-        do_query: impl Fn(Arc<Connection>, Consistency, &ExecutionProfileInner) -> QueryFut,
+        do_query: impl Fn(Arc<Connection>, Consistency) -> QueryFut,
         request_span: &'a RequestSpan,
+        request_context: Option<&'a RequestContext<'a>>,
+        request_stats: &'a RequestStats,
     ) -> Result<RunQueryResult<ResT>, QueryError>
     where
         ConnFut: Future<Output = Result<Arc<Connection>, QueryError>>,
@@ -1539,7 +3018,14 @@ impl Session {
                 .map(|hl| (&**hl, hl.log_query_start()));
 
         let load_balancer = &execution_profile.load_balancing_policy;
+        request_span.record_consistency(statement_info.consistency);
+        request_span.record_label(statement_config.label.as_deref());
+        let label_metrics: Option<Arc<LabelMetrics>> = statement_config
+            .label
+            .as_deref()
+            .map(|label| self.metrics.label_metrics(label));
 
+        let started_at = Instant::now();
         let runner = async {
             let cluster_data = self.cluster.get_data();
             let query_plan =
@@ -1598,6 +3084,15 @@ impl Session {
 
                         if is_speculative {
                             request_span.inc_speculative_executions();
+                            request_stats.inc_speculative_executions();
+                            self.metrics.inc_speculative_executions_num();
+                            if let Some(label_metrics) = &label_metrics {
+                                label_metrics.inc_speculative_executions();
+                            }
+                            #[cfg(feature = "metrics-exporter")]
+                            self.metrics.notify(|observer| {
+                                observer.on_speculative_execution(request_span.statement_kind())
+                            });
                         }
 
                         self.execute_query(
@@ -1607,11 +3102,15 @@ impl Session {
                             &execution_profile,
                             ExecuteQueryContext {
                                 is_idempotent: statement_config.is_idempotent,
-                                consistency_set_on_statement: statement_config.consistency,
                                 retry_session: retry_policy.new_session(),
                                 history_data,
                                 query_info: &statement_info,
                                 request_span,
+                                request_context,
+                                request_stats,
+                                label_metrics: label_metrics.as_deref(),
+                                started_at,
+                                is_speculative,
                             },
                         )
                     };
@@ -1622,6 +3121,7 @@ impl Session {
 
                     speculative_execution::execute(
                         speculative.as_ref(),
+                        self.runtime(),
                         &context,
                         execute_query_generator,
                     )
@@ -1643,11 +3143,15 @@ impl Session {
                         &execution_profile,
                         ExecuteQueryContext {
                             is_idempotent: statement_config.is_idempotent,
-                            consistency_set_on_statement: statement_config.consistency,
                             retry_session: retry_policy.new_session(),
                             history_data,
                             query_info: &statement_info,
                             request_span,
+                            request_context,
+                            request_stats,
+                            label_metrics: label_metrics.as_deref(),
+                            started_at,
+                            is_speculative: false,
                         },
                     )
                     .await
@@ -1662,7 +3166,7 @@ impl Session {
             .request_timeout
             .or(execution_profile.request_timeout);
         let result = match effective_timeout {
-            Some(timeout) => tokio::time::timeout(timeout, runner)
+            Some(timeout) => runtime::timeout(self.runtime(), timeout, runner)
                 .await
                 .unwrap_or_else(|e| {
                     Err(QueryError::RequestTimeout(format!(
@@ -1680,6 +3184,7 @@ impl Session {
                 Err(e) => history_listener.log_query_error(query_id, e),
             }
         }
+        request_span.record_outcome(result.is_ok());
 
         result
     }
@@ -1688,7 +3193,7 @@ impl Session {
         &'a self,
         query_plan: impl Iterator<Item = NodeRef<'a>>,
         choose_connection: impl Fn(Arc<Node>) -> ConnFut,
-        do_query: impl Fn(Arc<Connection>, Consistency, &ExecutionProfileInner) -> QueryFut,
+        do_query: impl Fn(Arc<Connection>, Consistency) -> QueryFut,
         execution_profile: &ExecutionProfileInner,
         mut context: ExecuteQueryContext<'a>,
     ) -> Option<Result<RunQueryResult<ResT>, QueryError>>
@@ -1698,13 +3203,17 @@ impl Session {
         ResT: AllowedRunQueryResTType,
     {
         let mut last_error: Option<QueryError> = None;
-        let mut current_consistency: Consistency = context
-            .consistency_set_on_statement
-            .unwrap_or(execution_profile.consistency);
+        let mut current_consistency: Consistency = context.query_info.consistency;
+        let mut attempt_number: usize = 0;
 
         'nodes_in_plan: for node in query_plan {
-            let span = trace_span!("Executing query", node = %node.address);
             'same_node_retries: loop {
+                let span = new_attempt_span(
+                    context.request_span.level(),
+                    context.request_span.span(),
+                    node,
+                    context.is_speculative,
+                );
                 trace!(parent: &span, "Execution started");
                 let connection: Arc<Connection> = match choose_connection(node.clone())
                     .instrument(span.clone())
@@ -1723,8 +3232,20 @@ impl Session {
                     }
                 };
                 context.request_span.record_shard_id(&connection);
+                span.record("attempt", context.request_span.inc_attempts());
+                context.request_stats.inc_attempts();
+                attempt_number += 1;
+                context
+                    .request_stats
+                    .record_consistency(current_consistency, context.query_info.serial_consistency);
+                context
+                    .request_span
+                    .record_coordinator(node.address.into_inner());
 
                 self.metrics.inc_total_nonpaged_queries();
+                if let Some(label_metrics) = context.label_metrics {
+                    label_metrics.inc_requests();
+                }
                 let query_start = std::time::Instant::now();
 
                 trace!(
@@ -1734,17 +3255,48 @@ impl Session {
                 );
                 let attempt_id: Option<history::AttemptId> =
                     context.log_attempt_start(connection.get_connect_address());
+                let recorded_attempt = context
+                    .request_stats
+                    .record_attempt_start(node.address.into_inner(), context.is_speculative);
+                if let Some(request_context) = context.request_context {
+                    for interceptor in self.request_interceptors.iter() {
+                        interceptor.on_attempt(request_context, node);
+                    }
+                }
+                let shard_info = connection.get_shard_info().clone();
                 let query_result: Result<ResT, QueryError> =
-                    do_query(connection, current_consistency, execution_profile)
+                    do_query(connection, current_consistency)
                         .instrument(span.clone())
                         .await;
 
                 let elapsed = query_start.elapsed();
+                context
+                    .request_stats
+                    .record_coordinator(node.address.into_inner());
+                span.record("outcome", outcome_str(query_result.is_ok()));
                 last_error = match query_result {
                     Ok(response) => {
                         trace!(parent: &span, "Query succeeded");
                         let _ = self.metrics.log_query_latency(elapsed.as_millis() as u64);
+                        self.metrics
+                            .log_node_latency(node, shard_info.as_ref(), elapsed);
+                        if let Some(label_metrics) = context.label_metrics {
+                            label_metrics.record_latency(elapsed);
+                        }
+                        #[cfg(feature = "metrics-exporter")]
+                        self.metrics.notify(|observer| {
+                            observer.on_request(&RequestEvent {
+                                kind: context.request_span.statement_kind(),
+                                node: NodeLabels::new(node, shard_info.as_ref()),
+                                outcome: RequestOutcome::Success,
+                                latency: elapsed,
+                                paged: false,
+                            })
+                        });
                         context.log_attempt_success(&attempt_id);
+                        context
+                            .request_stats
+                            .record_attempt_success(recorded_attempt, elapsed);
                         execution_profile.load_balancing_policy.on_query_success(
                             context.query_info,
                             elapsed,
@@ -1759,6 +3311,19 @@ impl Session {
                             "Query failed"
                         );
                         self.metrics.inc_failed_nonpaged_queries();
+                        if let Some(label_metrics) = context.label_metrics {
+                            label_metrics.inc_errors();
+                        }
+                        #[cfg(feature = "metrics-exporter")]
+                        self.metrics.notify(|observer| {
+                            observer.on_request(&RequestEvent {
+                                kind: context.request_span.statement_kind(),
+                                node: NodeLabels::new(node, shard_info.as_ref()),
+                                outcome: RequestOutcome::Error,
+                                latency: elapsed,
+                                paged: false,
+                            })
+                        });
                         execution_profile.load_balancing_policy.on_query_failure(
                             context.query_info,
                             elapsed,
@@ -1771,13 +3336,15 @@ impl Session {
 
                 let the_error: &QueryError = last_error.as_ref().unwrap();
                 // Use retry policy to decide what to do next
-                let query_info = QueryInfo {
-                    error: the_error,
-                    is_idempotent: context.is_idempotent,
-                    consistency: context
-                        .consistency_set_on_statement
-                        .unwrap_or(execution_profile.consistency),
-                };
+                let mut query_info = QueryInfo::new(
+                    the_error,
+                    context.is_idempotent,
+                    context.query_info.consistency,
+                );
+                query_info.attempt_consistency = current_consistency;
+                query_info.attempt_number = attempt_number;
+                query_info.elapsed_since_first_attempt = context.started_at.elapsed();
+                query_info.node = Some(node);
 
                 let retry_decision = context.retry_session.decide_should_retry(query_info);
                 trace!(
@@ -1785,15 +3352,51 @@ impl Session {
                     retry_decision = format!("{:?}", retry_decision).as_str()
                 );
                 context.log_attempt_error(&attempt_id, the_error, &retry_decision);
+                context.request_stats.record_attempt_error(
+                    recorded_attempt,
+                    elapsed,
+                    the_error,
+                    &retry_decision,
+                );
                 match retry_decision {
                     RetryDecision::RetrySameNode(new_cl) => {
                         self.metrics.inc_retries_num();
+                        context.request_stats.inc_retries();
+                        if let Some(label_metrics) = context.label_metrics {
+                            label_metrics.inc_retries();
+                        }
+                        #[cfg(feature = "metrics-exporter")]
+                        self.metrics.notify(|observer| {
+                            observer.on_retry(&NodeLabels::new(node, shard_info.as_ref()))
+                        });
                         current_consistency = new_cl.unwrap_or(current_consistency);
                         continue 'same_node_retries;
                     }
                     RetryDecision::RetryNextNode(new_cl) => {
                         self.metrics.inc_retries_num();
+                        context.request_stats.inc_retries();
+                        if let Some(label_metrics) = context.label_metrics {
+                            label_metrics.inc_retries();
+                        }
+                        #[cfg(feature = "metrics-exporter")]
+                        self.metrics.notify(|observer| {
+                            observer.on_retry(&NodeLabels::new(node, shard_info.as_ref()))
+                        });
+                        current_consistency = new_cl.unwrap_or(current_consistency);
+                        continue 'nodes_in_plan;
+                    }
+                    RetryDecision::RetryNextNodeAfter(new_cl, delay) => {
+                        self.metrics.inc_retries_num();
+                        context.request_stats.inc_retries();
+                        if let Some(label_metrics) = context.label_metrics {
+                            label_metrics.inc_retries();
+                        }
+                        #[cfg(feature = "metrics-exporter")]
+                        self.metrics.notify(|observer| {
+                            observer.on_retry(&NodeLabels::new(node, shard_info.as_ref()))
+                        });
                         current_consistency = new_cl.unwrap_or(current_consistency);
+                        self.runtime().sleep(delay).await;
                         continue 'nodes_in_plan;
                     }
                     RetryDecision::DontRetry => break 'nodes_in_plan,
@@ -1808,9 +3411,124 @@ impl Session {
         last_error.map(Result::Err)
     }
 
+    /// Executes the statements issued by `f` with a shared budget of time and attempts.
+    ///
+    /// `f` is called once, with a [`SessionHandle`] through which it executes its statements.
+    /// All requests sent through the handle draw from the `budget`, see
+    /// [the module documentation](crate::transport::execution_budget) for details.
+    /// The returned [`BudgetedExecution`] holds the result of `f` and, also when `f` failed,
+    /// the ordered log of the statements which completed successfully.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use scylla::Session;
+    /// # async fn example(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    /// use scylla::query::Query;
+    /// use scylla::transport::execution_budget::ExecutionBudget;
+    /// use std::time::Duration;
+    ///
+    /// let budget = ExecutionBudget {
+    ///     max_total_time: Some(Duration::from_secs(5)),
+    ///     max_attempts: Some(10),
+    /// };
+    /// let execution = session
+    ///     .execute_with_budget(budget, |handle| async move {
+    ///         let mut reserve = Query::new("UPDATE ks.stock SET owner = ? WHERE id = 1 IF owner = null");
+    ///         reserve.set_label("reserve");
+    ///         handle.query(reserve, ("order-1",)).await?;
+    ///
+    ///         let mut charge = Query::new("INSERT INTO ks.payments (id, amount) VALUES (1, 10) IF NOT EXISTS");
+    ///         charge.set_label("charge");
+    ///         handle.query(charge, &[]).await?;
+    ///         Ok::<_, Box<dyn std::error::Error>>(())
+    ///     })
+    ///     .await;
+    ///
+    /// if execution.result.is_err() && execution.completed == ["reserve"] {
+    ///     // Release the reservation
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_with_budget<'a, F, Fut, T, E>(
+        &'a self,
+        budget: ExecutionBudget,
+        f: F,
+    ) -> BudgetedExecution<T, E>
+    where
+        F: FnOnce(SessionHandle<'a>) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let handle = SessionHandle::new(self, budget);
+        let result = f(handle.clone()).await;
+        handle.finish(result)
+    }
+
+    /// Executes a statement changing the schema, e.g. `CREATE TABLE`, and waits until
+    /// the whole cluster agrees on the new schema, at most for the
+    /// [schema agreement timeout](SessionConfig::schema_agreement_timeout).
+    ///
+    /// Returns the change reported by the server, or `None` if nothing was changed,
+    /// e.g. because of `IF NOT EXISTS`. With
+    /// [`SessionConfig::ddl_ignore_already_exists`] enabled, errors saying that the created
+    /// object already exists are treated as success and return `None` as well.
+    /// Schema agreement is awaited in that case too, as the object may have been
+    /// created by another client a moment ago.
+    ///
+    /// See [the module documentation](crate::transport::ddl) for more information.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use scylla::Session;
+    /// # async fn example(session: &Session) -> Result<(), Box<dyn std::error::Error>> {
+    /// use scylla::transport::ddl::{SchemaChangeTarget, SchemaChangeType};
+    ///
+    /// let change = session
+    ///     .ddl("CREATE TABLE IF NOT EXISTS ks.t (a int PRIMARY KEY)")
+    ///     .await?;
+    /// if let Some(change) = change {
+    ///     assert_eq!(change.change_type, SchemaChangeType::Created);
+    ///     assert_eq!(change.target, SchemaChangeTarget::Table);
+    ///     assert_eq!(change.name.as_deref(), Some("t"));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn ddl(
+        &self,
+        statement: impl Into<Query>,
+    ) -> Result<Option<ddl::SchemaChange>, QueryError> {
+        let schema_change = match self.query_unpaged(statement, ()).await {
+            Ok(result) => result.schema_change().map(ddl::SchemaChange::from_event),
+            Err(err) if self.ddl_ignore_already_exists && ddl::is_already_exists(&err) => {
+                debug!("Ignoring error of a DDL statement: {}", err);
+                None
+            }
+            Err(err) => return Err(err),
+        };
+
+        match &schema_change {
+            Some(change) => debug!(
+                change_type = ?change.change_type,
+                target = %change.target,
+                keyspace = %change.keyspace,
+                name = ?change.name,
+                "DDL statement changed the schema"
+            ),
+            None => debug!("DDL statement didn't change the schema"),
+        }
+
+        // Automatic waiting may have already done it
+        if self.check_schema_agreement().await?.is_none() {
+            self.await_schema_agreement().await?;
+        }
+
+        Ok(schema_change)
+    }
+
     async fn await_schema_agreement_indefinitely(&self) -> Result<Uuid, QueryError> {
         loop {
-            tokio::time::sleep(self.schema_agreement_interval).await;
+            self.runtime().sleep(self.schema_agreement_interval).await;
             if let Some(agreed_version) = self.check_schema_agreement().await? {
                 return Ok(agreed_version);
             }
@@ -1818,7 +3536,8 @@ impl Session {
     }
 
     pub async fn await_schema_agreement(&self) -> Result<Uuid, QueryError> {
-        timeout(
+        runtime::timeout(
+            self.runtime(),
             self.schema_agreement_timeout,
             self.await_schema_agreement_indefinitely(),
         )
@@ -1864,11 +3583,15 @@ impl AllowedRunQueryResTType for NonErrorQueryResponse {}
 
 struct ExecuteQueryContext<'a> {
     is_idempotent: bool,
-    consistency_set_on_statement: Option<Consistency>,
     retry_session: Box<dyn RetrySession>,
     history_data: Option<HistoryData<'a>>,
     query_info: &'a load_balancing::RoutingInfo<'a>,
     request_span: &'a RequestSpan,
+    request_context: Option<&'a RequestContext<'a>>,
+    request_stats: &'a RequestStats,
+    label_metrics: Option<&'a LabelMetrics>,
+    started_at: Instant,
+    is_speculative: bool,
 }
 
 struct HistoryData<'a> {
@@ -1921,31 +3644,84 @@ impl<'a> ExecuteQueryContext<'a> {
     }
 }
 
+// `tracing` requires the level of a span to be known at compile time,
+// so a span with a runtime-configured level is created with the macro for that level.
+macro_rules! span_with_level {
+    ($level:expr, $($args:tt)+) => {{
+        let level: Level = $level;
+        if level == Level::ERROR {
+            tracing::error_span!($($args)+)
+        } else if level == Level::WARN {
+            tracing::warn_span!($($args)+)
+        } else if level == Level::INFO {
+            tracing::info_span!($($args)+)
+        } else if level == Level::DEBUG {
+            tracing::debug_span!($($args)+)
+        } else {
+            tracing::trace_span!($($args)+)
+        }
+    }};
+}
+
+/// Creates a span for a single attempt to send a request to `node`.
+pub(crate) fn new_attempt_span(
+    level: Level,
+    parent: &tracing::Span,
+    node: NodeRef<'_>,
+    speculative: bool,
+) -> tracing::Span {
+    use tracing::field::Empty;
+
+    span_with_level!(
+        level,
+        parent: parent,
+        "scylla.attempt",
+        node = %node.address,
+        speculative = speculative,
+        attempt = Empty,
+        outcome = Empty,
+    )
+}
+
 pub(crate) struct RequestSpan {
     span: tracing::Span,
+    level: Level,
     speculative_executions: AtomicUsize,
+    attempts: AtomicUsize,
+    #[cfg(feature = "metrics-exporter")]
+    statement_kind: StatementKind,
 }
 
 impl RequestSpan {
-    pub(crate) fn new_query(contents: &str) -> Self {
+    pub(crate) fn new_query(contents: &str, level: Level) -> Self {
         use tracing::field::Empty;
 
-        let span = trace_span!(
-            "Request",
+        let span = span_with_level!(
+            level,
+            "scylla.request",
             kind = "unprepared",
             contents = contents,
             //
+            label = Empty,
+            consistency = Empty,
             request_size = Empty,
             result_size = Empty,
             result_rows = Empty,
             replicas = Empty,
             shard = Empty,
+            coordinator = Empty,
+            attempts = Empty,
             speculative_executions = Empty,
+            outcome = Empty,
         );
 
         Self {
             span,
+            level,
             speculative_executions: 0.into(),
+            attempts: 0.into(),
+            #[cfg(feature = "metrics-exporter")]
+            statement_kind: StatementKind::Unprepared,
         }
     }
 
@@ -1953,21 +3729,30 @@ impl RequestSpan {
         partition_key: Option<impl Iterator<Item = (&'ps [u8], &'ps ColumnSpec)> + Clone>,
         token: Option<Token>,
         request_size: usize,
+        level: Level,
     ) -> Self {
         use tracing::field::Empty;
 
-        let span = trace_span!(
-            "Request",
+        let span = span_with_level!(
+            level,
+            "scylla.request",
             kind = "prepared",
+            keyspace = Empty,
+            table = Empty,
             partition_key = Empty,
             token = Empty,
             //
+            label = Empty,
+            consistency = Empty,
             request_size = request_size,
             result_size = Empty,
             result_rows = Empty,
             replicas = Empty,
             shard = Empty,
+            coordinator = Empty,
+            attempts = Empty,
             speculative_executions = Empty,
+            outcome = Empty,
         );
 
         if let Some(partition_key) = partition_key {
@@ -1984,28 +3769,44 @@ impl RequestSpan {
 
         Self {
             span,
+            level,
             speculative_executions: 0.into(),
+            attempts: 0.into(),
+            #[cfg(feature = "metrics-exporter")]
+            statement_kind: StatementKind::Prepared,
         }
     }
 
-    pub(crate) fn new_batch() -> Self {
+    pub(crate) fn new_batch(level: Level) -> Self {
         use tracing::field::Empty;
 
-        let span = trace_span!(
-            "Request",
+        let span = span_with_level!(
+            level,
+            "scylla.request",
             kind = "batch",
+            keyspace = Empty,
+            table = Empty,
             //
+            label = Empty,
+            consistency = Empty,
             request_size = Empty,
             result_size = Empty,
             result_rows = Empty,
             replicas = Empty,
             shard = Empty,
+            coordinator = Empty,
+            attempts = Empty,
             speculative_executions = Empty,
+            outcome = Empty,
         );
 
         Self {
             span,
+            level,
             speculative_executions: 0.into(),
+            attempts: 0.into(),
+            #[cfg(feature = "metrics-exporter")]
+            statement_kind: StatementKind::Batch,
         }
     }
 
@@ -2015,6 +3816,40 @@ impl RequestSpan {
         }
     }
 
+    pub(crate) fn record_table(&self, keyspace: Option<&str>, table: Option<&str>) {
+        if let Some(keyspace) = keyspace {
+            self.span.record("keyspace", keyspace);
+        }
+        if let Some(table) = table {
+            self.span.record("table", table);
+        }
+    }
+
+    pub(crate) fn record_label(&self, label: Option<&str>) {
+        if let Some(label) = label {
+            self.span.record("label", label);
+        }
+    }
+
+    pub(crate) fn record_consistency(&self, consistency: Consistency) {
+        self.span
+            .record("consistency", tracing::field::display(consistency));
+    }
+
+    pub(crate) fn record_coordinator(&self, coordinator: SocketAddr) {
+        self.span
+            .record("coordinator", tracing::field::display(coordinator));
+    }
+
+    pub(crate) fn record_outcome(&self, success: bool) {
+        self.span.record("outcome", outcome_str(success));
+    }
+
+    /// Returns the number of the attempt, starting from 1.
+    pub(crate) fn inc_attempts(&self) -> usize {
+        self.attempts.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
     pub(crate) fn record_result_fields(&self, result: &QueryResult) {
         self.span.record("result_size", result.serialized_size);
         if let Some(rows) = result.rows.as_ref() {
@@ -2060,6 +3895,15 @@ impl RequestSpan {
     pub(crate) fn span(&self) -> &tracing::Span {
         &self.span
     }
+
+    pub(crate) fn level(&self) -> Level {
+        self.level
+    }
+
+    #[cfg(feature = "metrics-exporter")]
+    pub(crate) fn statement_kind(&self) -> StatementKind {
+        self.statement_kind
+    }
 }
 
 impl Drop for RequestSpan {
@@ -2068,6 +3912,37 @@ impl Drop for RequestSpan {
             "speculative_executions",
             self.speculative_executions.load(Ordering::Relaxed),
         );
+        self.span
+            .record("attempts", self.attempts.load(Ordering::Relaxed));
+    }
+}
+
+// Fetches the pages following the first one, appending their rows to its rows.
+async fn fetch_remaining_pages<Fut>(
+    first_page: QueryResult,
+    fetch_page: impl Fn(Bytes) -> Fut,
+) -> Result<QueryResult, QueryError>
+where
+    Fut: Future<Output = Result<QueryResult, QueryError>>,
+{
+    let mut result = first_page;
+    while let Some(paging_state) = result.paging_state.take() {
+        let page = fetch_page(paging_state).await?;
+        if let Some(rows) = page.rows {
+            result.rows.get_or_insert_with(Vec::new).extend(rows);
+        }
+        result.warnings.extend(page.warnings);
+        result.serialized_size += page.serialized_size;
+        result.paging_state = page.paging_state;
+    }
+    Ok(result)
+}
+
+pub(crate) fn outcome_str(success: bool) -> &'static str {
+    if success {
+        "success"
+    } else {
+        "error"
     }
 }
 
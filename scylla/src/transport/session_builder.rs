@@ -3,20 +3,33 @@
 use super::errors::NewSessionError;
 use super::execution_profile::ExecutionProfileHandle;
 use super::session::{AddressTranslator, Session, SessionConfig};
-use super::Compression;
+use super::{AddressFamilyPreference, Compression, HostnameResolver};
 
 #[cfg(feature = "cloud")]
 use crate::cloud::{CloudConfig, CloudConfigError};
 #[cfg(feature = "cloud")]
 use crate::ExecutionProfile;
 
-use crate::statement::Consistency;
-use crate::transport::connection_pool::PoolSize;
+use crate::frame::response::result::{ColumnDecodePolicy, UnknownTypePolicy};
+use crate::serialize::redaction::ValueRedaction;
+use crate::statement::{Consistency, SerialConsistency};
+#[cfg(feature = "config-file")]
+use crate::transport::config_file::{ConfigFileError, SessionConfigFile};
+use crate::transport::connection_pool::{PoolSize, StreamExhaustionPolicy};
+use crate::transport::control_connection_policy::ControlConnectionPolicy;
 use crate::transport::host_filter::HostFilter;
+use crate::transport::interceptor::RequestInterceptor;
+#[cfg(feature = "metrics-exporter")]
+use crate::transport::metrics_exporter::MetricsObserver;
+use crate::transport::priority::PriorityScheduling;
+use crate::transport::runtime::Runtime;
+use crate::transport::slow_query_log::SlowQueryListener;
+use crate::transport::statement_guard::StatementGuard;
 use std::borrow::Borrow;
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::net::SocketAddr;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroUsize};
 #[cfg(feature = "cloud")]
 use std::path::Path;
 use std::sync::Arc;
@@ -25,7 +38,7 @@ use std::time::Duration;
 use crate::authentication::{AuthenticatorProvider, PlainTextAuthenticator};
 #[cfg(feature = "ssl")]
 use openssl::ssl::SslContext;
-use tracing::warn;
+use tracing::{warn, Level};
 
 mod sealed {
     pub trait Sealed {}
@@ -303,6 +316,111 @@ impl GenericSessionBuilder<DefaultMode> {
         self
     }
 
+    /// Sets the resolver of hostnames of the known nodes.
+    /// The hostnames are resolved on startup, and again when the control connection
+    /// can't be re-established to any of the nodes known from the cluster metadata,
+    /// e.g. after the addresses of all nodes changed.
+    /// By default, the resolver of the operating system is used.
+    /// To use SRV records, see [`SrvResolver`](crate::transport::SrvResolver).
+    ///
+    /// # Example
+    /// ```
+    /// # use std::io;
+    /// # use std::net::SocketAddr;
+    /// # use std::sync::Arc;
+    /// # use async_trait::async_trait;
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use scylla::transport::HostnameResolver;
+    /// struct StaticResolver(SocketAddr);
+    ///
+    /// #[async_trait]
+    /// impl HostnameResolver for StaticResolver {
+    ///     async fn resolve_hostname(&self, _hostname: &str) -> Result<Vec<SocketAddr>, io::Error> {
+    ///         Ok(vec![self.0])
+    ///     }
+    /// }
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("scylla.local:9042")
+    ///     .hostname_resolver(Arc::new(StaticResolver("127.0.0.1:9042".parse()?)))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn hostname_resolver(mut self, resolver: Arc<dyn HostnameResolver>) -> Self {
+        self.config.hostname_resolver = Some(resolver);
+        self
+    }
+
+    /// Sets which address is used when a hostname of a known node resolves to both
+    /// IPv4 and IPv6 addresses. IPv4 addresses are preferred by default.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use scylla::transport::AddressFamilyPreference;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("scylla.local:9042")
+    ///     .address_family_preference(AddressFamilyPreference::PreferIpv6)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn address_family_preference(mut self, preference: AddressFamilyPreference) -> Self {
+        self.config.address_family_preference = preference;
+        self
+    }
+
+    /// Sets for how long the addresses which the hostnames of the known nodes resolved to
+    /// are reused, before the hostnames are resolved again. The default is 1 second.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use std::time::Duration;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("scylla.local:9042")
+    ///     .hostname_resolution_interval(Duration::from_secs(30))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn hostname_resolution_interval(mut self, interval: Duration) -> Self {
+        self.config.hostname_resolution_interval = interval;
+        self
+    }
+
+    /// Sets the delay between connection attempts to successive addresses of a known node,
+    /// whose hostname resolved to multiple addresses, e.g. both IPv4 and IPv6 ones.
+    /// Connections are attempted in the order set with
+    /// [`address_family_preference`](Self::address_family_preference), the next one as soon
+    /// as the previous one fails or after this delay, and the first one established is used,
+    /// while the remaining attempts are cancelled. The default is 250 milliseconds.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use std::time::Duration;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("scylla.local:9042")
+    ///     .connection_attempt_delay(Duration::from_millis(100))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn connection_attempt_delay(mut self, delay: Duration) -> Self {
+        self.config.connection_attempt_delay = delay;
+        self
+    }
+
     /// ssl feature
     /// Provide SessionBuilder with SslContext from openssl crate that will be
     /// used to create an ssl connection to the database.
@@ -334,6 +452,33 @@ impl GenericSessionBuilder<DefaultMode> {
         self.config.ssl_context = ssl_context;
         self
     }
+
+    /// Applies the options set in a [`SessionConfigFile`], e.g. one loaded with
+    /// [`SessionConfigFile::from_file`]. Options which aren't set in it are left unchanged.
+    /// Requires the `config-file` feature.
+    ///
+    /// Returns an error if the configuration is invalid or refers to an environment
+    /// variable which isn't set.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use scylla::transport::config_file::SessionConfigFile;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let config = SessionConfigFile::from_yaml_str(
+    ///     "known_nodes: [\"127.0.0.1:9042\"]\nconnection_timeout_ms: 3000",
+    /// )?;
+    /// let session: Session = SessionBuilder::new()
+    ///     .apply_config(&config)?
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "config-file")]
+    pub fn apply_config(self, config: &SessionConfigFile) -> Result<Self, ConfigFileError> {
+        config.apply(self)
+    }
 }
 
 // NOTE: this `impl` block contains configuration options specific for **Cloud** [`Session`].
@@ -436,6 +581,58 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
         self
     }
 
+    /// Set the consistency of requests whose statement and execution profile don't set it.
+    /// The default is `LocalQuorum`.
+    ///
+    /// The consistency set on a statement takes precedence over the one set in its
+    /// execution profile, which takes precedence over this one.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{statement::Consistency, Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .default_consistency(Consistency::One)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn default_consistency(mut self, consistency: Consistency) -> Self {
+        self.config.default_consistency = consistency;
+        self
+    }
+
+    /// Set the serial consistency of conditional requests (lightweight transactions)
+    /// whose statement and execution profile don't set it. `None` leaves the choice
+    /// to the server, which uses `Serial`.
+    /// The default is `Some(LocalSerial)`.
+    ///
+    /// This default is sent only with conditional statements, as serial consistency doesn't
+    /// apply to other ones. The one set on a statement takes precedence over the one set
+    /// in its execution profile, which takes precedence over this one.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{statement::SerialConsistency, Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .default_serial_consistency(Some(SerialConsistency::Serial))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn default_serial_consistency(
+        mut self,
+        serial_consistency: Option<SerialConsistency>,
+    ) -> Self {
+        self.config.default_serial_consistency = serial_consistency;
+        self
+    }
+
     /// Set the nodelay TCP flag.
     /// The default is true.
     ///
@@ -575,6 +772,88 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
         self
     }
 
+    /// Sets the maximal number of requests in flight on a single connection.
+    /// Values larger than 32768, the number of stream ids in the protocol, are treated as 32768,
+    /// which is the default.
+    ///
+    /// When all stream ids of a connection are in use, a request is sent through another
+    /// connection to the node, if there is one with free stream ids, or otherwise handled
+    /// according to the [`StreamExhaustionPolicy`].
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::num::NonZeroUsize;
+    ///
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .max_requests_per_connection(NonZeroUsize::new(2048).unwrap())
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn max_requests_per_connection(mut self, max: NonZeroUsize) -> Self {
+        self.config.max_requests_per_connection = max;
+        self
+    }
+
+    /// Sets what happens to a request when all stream ids of the connection chosen for it,
+    /// and of all other connections to the node, are in use.
+    /// The default is [`StreamExhaustionPolicy::FailFast`].
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::time::Duration;
+    /// use scylla::transport::session::StreamExhaustionPolicy;
+    ///
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .stream_exhaustion_policy(StreamExhaustionPolicy::Queue {
+    ///         max_queued: 1024,
+    ///         timeout: Duration::from_millis(500),
+    ///     })
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stream_exhaustion_policy(mut self, policy: StreamExhaustionPolicy) -> Self {
+        self.config.stream_exhaustion_policy = policy;
+        self
+    }
+
+    /// Makes connections dispatch requests waiting for a stream id by the
+    /// [priority](crate::transport::execution_profile::ExecutionProfileBuilder::priority)
+    /// of their execution profiles, instead of in a single queue.
+    /// Requests wait only with [`StreamExhaustionPolicy::Queue`](crate::transport::session::StreamExhaustionPolicy::Queue),
+    /// but in-flight caps of the classes apply with any policy.
+    /// By default, priorities are ignored.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use scylla::transport::priority::{PriorityScheduling, RequestPriority};
+    ///
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .priority_scheduling(
+    ///         PriorityScheduling::strict().max_in_flight(RequestPriority::Low, 128),
+    ///     )
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn priority_scheduling(mut self, scheduling: PriorityScheduling) -> Self {
+        self.config.priority_scheduling = Some(scheduling);
+        self
+    }
+
     /// If true, prevents the driver from connecting to the shard-aware port, even if the node supports it.
     ///
     /// _This is a Scylla-specific option_. It has no effect on Cassandra clusters.
@@ -789,6 +1068,35 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
         self
     }
 
+    /// Sets the control connection policy, which decides which node the control
+    /// connection is established to, and when it is moved to another node.
+    /// By default, nodes are picked at random and the control connection
+    /// is moved only when fetching metadata on it fails.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use scylla::transport::control_connection_policy::ControlConnectionPolicy;
+    /// # use std::time::Duration;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .control_connection_policy(
+    ///         ControlConnectionPolicy::new()
+    ///             .preferred_datacenter("my-local-dc")
+    ///             .unhealthy_threshold(Duration::from_secs(5))
+    ///             .heartbeat_interval(Duration::from_secs(1)),
+    ///     )
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn control_connection_policy(mut self, policy: ControlConnectionPolicy) -> Self {
+        self.config.control_connection_policy = policy;
+        self
+    }
+
     /// Set the refresh metadata on schema agreement flag.
     /// The default is true.
     ///
@@ -809,6 +1117,34 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
         self
     }
 
+    /// Set whether statements are prepared on all nodes.
+    /// The default is true.
+    ///
+    /// If true, [`Session::prepare`](crate::Session::prepare) sends the statement
+    /// to all nodes concurrently, and statements in use are prepared again on nodes
+    /// which come back up, so that their first executions on any node don't have to
+    /// prepare them first. Failures on some of the nodes don't fail the preparation.
+    ///
+    /// If false, statements are prepared on a single node, which reduces the load
+    /// put on the cluster by preparing many statements at once.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .prepare_on_all_hosts(false)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prepare_on_all_hosts(mut self, prepare_on_all_hosts: bool) -> Self {
+        self.config.prepare_on_all_hosts = prepare_on_all_hosts;
+        self
+    }
+
     /// Set the number of attempts to fetch [TracingInfo](crate::tracing::TracingInfo)
     /// in [`Session::get_tracing_info`].
     /// The default is 5 attempts.
@@ -911,6 +1247,31 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
         self
     }
 
+    /// Sets the size of the buffer in which requests are collected before being
+    /// written to the socket. The buffer is written out when the next request
+    /// doesn't fit in it, or when no more requests are waiting to be sent,
+    /// so a larger buffer allows more requests to be sent in a single syscall.
+    /// Requests larger than the buffer are written directly.
+    ///
+    /// The default is 8 KiB.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .write_coalescing_buffer_size(64 * 1024)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_coalescing_buffer_size(mut self, size: usize) -> Self {
+        self.config.write_coalescing_buffer_size = size;
+        self
+    }
+
     /// Set the interval at which the driver refreshes the cluster metadata which contains information
     /// about the cluster topology as well as the cluster schema.
     ///
@@ -934,6 +1295,637 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
         self.config.cluster_metadata_refresh_interval = interval;
         self
     }
+
+    /// Appends an interceptor to the chain of request interceptors.
+    /// Interceptors are called in the order in which they were appended.
+    /// See [`RequestInterceptor`] for details.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use std::sync::Arc;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use async_trait::async_trait;
+    /// use scylla::transport::errors::QueryError;
+    /// use scylla::transport::interceptor::{RequestContext, RequestInterceptor};
+    ///
+    /// #[derive(Debug)]
+    /// struct TenantInterceptor;
+    ///
+    /// #[async_trait]
+    /// impl RequestInterceptor for TenantInterceptor {
+    ///     async fn on_request(&self, ctx: &mut RequestContext<'_>) -> Result<(), QueryError> {
+    ///         ctx.custom_payload_mut()
+    ///             .insert("tenant".to_owned(), b"acme".to_vec());
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .request_interceptor(Arc::new(TenantInterceptor))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn request_interceptor(mut self, interceptor: Arc<dyn RequestInterceptor>) -> Self {
+        self.config.request_interceptors.push(interceptor);
+        self
+    }
+
+    /// Appends a guard consulted before every `query`, `execute` and `batch` request.
+    /// A request denied by any of the guards is not sent and fails with
+    /// [`QueryError::DeniedByGuard`](crate::transport::errors::QueryError::DeniedByGuard).
+    /// See [`StatementGuard`] and the built-in guards in its module.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use std::sync::Arc;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use scylla::transport::statement_guard::{DenyUnprepared, MaxBatchStatements, RequireWhere};
+    ///
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .statement_guard(Arc::new(DenyUnprepared))
+    ///     .statement_guard(Arc::new(RequireWhere::new(["ks.users"])))
+    ///     .statement_guard(Arc::new(MaxBatchStatements(100)))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn statement_guard(mut self, guard: Arc<dyn StatementGuard>) -> Self {
+        self.config.statement_guards.push(guard);
+        self
+    }
+
+    /// Requests which take longer than `threshold` are reported.
+    /// The measured time covers the whole call, including all retries, speculative executions
+    /// and all pages fetched by `query_unpaged` and `execute_unpaged`;
+    /// paging iterators report every page separately.
+    ///
+    /// By default slow requests are logged as `tracing` events at the `WARN` level,
+    /// with the (truncated) statement, the coordinator, the number of retries
+    /// and whether a speculative execution fired as structured fields.
+    /// Use [`slow_query_listener`](Self::slow_query_listener) to handle them yourself.
+    /// Default is None - slow requests are not reported.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use std::time::Duration;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .slow_query_threshold(Duration::from_millis(500))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn slow_query_threshold(mut self, threshold: Duration) -> Self {
+        self.config.slow_query_threshold = Some(threshold);
+        self
+    }
+
+    /// Installs a listener which receives slow requests instead of them being logged.
+    /// Has no effect unless [`slow_query_threshold`](Self::slow_query_threshold) is set.
+    /// Default is None.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use std::sync::Arc;
+    /// # use std::time::Duration;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use scylla::transport::slow_query_log::{SlowQueryEvent, SlowQueryListener};
+    ///
+    /// #[derive(Debug)]
+    /// struct PrintingListener;
+    ///
+    /// impl SlowQueryListener for PrintingListener {
+    ///     fn on_slow_query(&self, event: &SlowQueryEvent<'_>) {
+    ///         println!("{} took {:?}", event.statement, event.elapsed);
+    ///     }
+    /// }
+    ///
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .slow_query_threshold(Duration::from_millis(500))
+    ///     .slow_query_listener(Arc::new(PrintingListener))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn slow_query_listener(mut self, listener: Arc<dyn SlowQueryListener>) -> Self {
+        self.config.slow_query_listener = Some(listener);
+        self
+    }
+
+    /// Sets the level of the spans created for every request.
+    ///
+    /// Each request issued by the session is executed in a `scylla.request` span, with
+    /// the statement kind, keyspace and table (when known from prepared metadata),
+    /// consistency, number of attempts, coordinator address and outcome as fields.
+    /// Each attempt, including retries and speculative executions, gets its own
+    /// `scylla.attempt` child span. The spans nest under the span current at the time
+    /// of the call, so they integrate with e.g. `tracing-opentelemetry` out of the box.
+    ///
+    /// Spans filtered out by the subscriber cost next to nothing.
+    /// Default is `TRACE`.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .request_span_level(tracing::Level::INFO)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn request_span_level(mut self, level: Level) -> Self {
+        self.config.request_span_level = level;
+        self
+    }
+
+    /// Makes errors of `query`, `execute` and `batch` passed to
+    /// [request interceptors](Self::request_interceptor) carry information about
+    /// the statement which caused them, available with
+    /// [`ExecutionError::statement_info`](crate::transport::errors::ExecutionError::statement_info)
+    /// and included in the error message: the (truncated) text of unprepared statements,
+    /// and the id, keyspace and table of prepared statements.
+    ///
+    /// The information is gathered only when an error occurs, so successful requests
+    /// don't pay for it. Values bound to the statement, such as partition keys,
+    /// are never attached.
+    /// Default is `false`.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .attach_statement_to_errors(true)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn attach_statement_to_errors(mut self, attach: bool) -> Self {
+        self.config.attach_statement_to_errors = attach;
+        self
+    }
+
+    /// Sets the length (in bytes) to which texts of unprepared statements
+    /// attached to errors are truncated.
+    /// Has no effect unless [`attach_statement_to_errors`](Self::attach_statement_to_errors) is enabled.
+    /// Default is 256.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .attach_statement_to_errors(true)
+    ///     .error_statement_max_length(64)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn error_statement_max_length(mut self, max_length: usize) -> Self {
+        self.config.error_statement_max_length = max_length;
+        self
+    }
+
+    /// Sets how bound values are rendered while the session executes requests,
+    /// e.g. in the `Debug` output of serialized values logged by its request interceptors,
+    /// listeners and policies.
+    /// By default only types and lengths of values are rendered, e.g. `[text(12), int, null]`.
+    /// [`ValueRedaction::Full`] renders full values, and is meant for development only.
+    ///
+    /// The setting applies only to this session, overriding the
+    /// [global](ValueRedaction::global) one while its requests are executed.
+    /// Values rendered elsewhere, e.g. of errors formatted by the application,
+    /// use the global redaction.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// use scylla::serialize::redaction::ValueRedaction;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .value_redaction(ValueRedaction::Full)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn value_redaction(mut self, redaction: ValueRedaction) -> Self {
+        self.config.value_redaction = Some(redaction);
+        self
+    }
+
+    /// Sets how column types unknown to the driver, e.g. introduced by a newer version
+    /// of the database, are handled in the metadata of results and prepared statements.
+    /// By default requests whose metadata contains such types fail.
+    /// With [`UnknownTypePolicy::Accept`], values of such columns are available as raw bytes
+    /// in [`CqlValue::Unknown`](crate::frame::response::result::CqlValue::Unknown),
+    /// and only serializing values as such types fails.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// use scylla::frame::response::result::UnknownTypePolicy;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .unknown_type_policy(UnknownTypePolicy::Accept)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn unknown_type_policy(mut self, policy: UnknownTypePolicy) -> Self {
+        self.config.unknown_type_policy = policy;
+        self
+    }
+
+    /// Sets how values of columns which fail to decode are handled when rows are parsed.
+    /// By default a single such value fails the whole result, or page of results.
+    /// With [`ColumnDecodePolicy::Lenient`], the other columns of the row are still decoded,
+    /// and the errors are available through
+    /// [`QueryResult::decode_errors`](crate::QueryResult::decode_errors).
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// use scylla::frame::response::result::ColumnDecodePolicy;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .column_decode_policy(ColumnDecodePolicy::Lenient)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn column_decode_policy(mut self, policy: ColumnDecodePolicy) -> Self {
+        self.config.column_decode_policy = policy;
+        self
+    }
+
+    /// Enables reusing buffers for serialized values and request frames across requests.
+    /// At most `max_retained_bytes` of buffer capacity is kept for reuse; bigger buffers,
+    /// e.g. of requests with large values, are freed after use.
+    /// By default buffers are not reused.
+    ///
+    /// Hits and misses of the pool are counted in [`Metrics`](crate::Metrics).
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .buffer_pool_max_retained_bytes(16 * 1024 * 1024)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn buffer_pool_max_retained_bytes(mut self, max_retained_bytes: usize) -> Self {
+        self.config.buffer_pool_max_retained_bytes = Some(max_retained_bytes);
+        self
+    }
+
+    /// If enabled, blobs in query results aren't copied out of response frames:
+    /// they are returned as [`CqlValue::SharedBlob`](crate::frame::response::result::CqlValue::SharedBlob),
+    /// which shares the buffer of the frame. It speeds up reading large blobs,
+    /// but keeps the whole frame in memory as long as any blob from it is alive.
+    /// Disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .zero_copy_blobs(true)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn zero_copy_blobs(mut self, enabled: bool) -> Self {
+        self.config.zero_copy_blobs = enabled;
+        self
+    }
+
+    /// If enabled, errors of parsing malformed response frames contain up to 64 bytes
+    /// of the frame around the place where parsing failed, as a hex dump.
+    /// The errors always describe the header of the frame and the offset of the failure,
+    /// but the bytes may contain the data of the requests, so they're disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .include_frame_bytes_in_errors(true)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn include_frame_bytes_in_errors(mut self, enabled: bool) -> Self {
+        self.config.include_frame_bytes_in_errors = enabled;
+        self
+    }
+
+    /// Sets options added to the STARTUP request of every connection, e.g. for auditing
+    /// on the server side. They override the options set by the driver, like `DRIVER_NAME`
+    /// (`scylla-rust-driver` by default) and `DRIVER_VERSION` (the version of the crate).
+    ///
+    /// Options which select what is negotiated with the server, like `COMPRESSION`
+    /// or `CQL_VERSION`, aren't overridden, unless forced with
+    /// [`force_reserved_startup_options`](Self::force_reserved_startup_options).
+    /// The options sent to a node are available in [`NegotiatedSettings`](crate::transport::NegotiatedSettings).
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let options = HashMap::from([
+    ///     ("DRIVER_NAME".to_string(), "my-platform".to_string()),
+    ///     ("APPLICATION_NAME".to_string(), "billing".to_string()),
+    /// ]);
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .custom_startup_options(options)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn custom_startup_options(mut self, options: HashMap<String, String>) -> Self {
+        self.config.custom_startup_options = options;
+        self
+    }
+
+    /// If enabled, the [custom STARTUP options](Self::custom_startup_options) override
+    /// the options which select what is negotiated with the server, like `COMPRESSION`.
+    /// The driver keeps working according to what it negotiated itself, so overriding them
+    /// may break the connections. Disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let options = HashMap::from([("CQL_VERSION".to_string(), "3.4.5".to_string())]);
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .custom_startup_options(options)
+    ///     .force_reserved_startup_options(true)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn force_reserved_startup_options(mut self, force: bool) -> Self {
+        self.config.force_reserved_startup_options = force;
+        self
+    }
+
+    /// If enabled, the server is asked not to send metadata of the rows returned
+    /// by prepared statements, which saves resending the names and types of all
+    /// columns with every page. The rows are decoded with the result metadata
+    /// received when the statement was prepared.
+    ///
+    /// After a schema change of the table a statement operates on, announced by the cluster
+    /// or made by the session, the next execution of the statement receives the rows together
+    /// with their metadata. If the names or types of the columns differ from the cached ones,
+    /// e.g. because a field was added to a user defined type of a column, the statement
+    /// is re-prepared. If the server returns a different number of columns before the change
+    /// is noticed, the statement is re-prepared and executed again, this time with the metadata.
+    /// Disabling it may help debugging problems with decoding of results.
+    /// Enabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .skip_result_metadata(false)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn skip_result_metadata(mut self, enabled: bool) -> Self {
+        self.config.skip_result_metadata = enabled;
+        self
+    }
+
+    /// Makes the session count bind markers (`?` outside literals and comments)
+    /// in unprepared statements sent without values, and fail them with
+    /// [`ValueCountMismatchError`](scylla_cql::types::serialize::row::ValueCountMismatchError)
+    /// before they are sent if they have any, instead of waiting for the server to reject them.
+    ///
+    /// The check is a best effort, as the statement isn't parsed. Values of prepared statements,
+    /// and of unprepared ones sent with values, which are prepared first, are always checked
+    /// against the prepared metadata.
+    /// Disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .check_unprepared_bind_markers(true)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn check_unprepared_bind_markers(mut self, enabled: bool) -> Self {
+        self.config.check_unprepared_bind_markers = enabled;
+        self
+    }
+
+    /// Makes [`Session::ddl`](crate::Session::ddl) treat errors saying that the created object
+    /// already exists as success. It lets several clients apply the same schema changes
+    /// concurrently, as the statements of all but one of them fail with such errors,
+    /// sometimes even with `IF NOT EXISTS`.
+    /// Disabled by default.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .ddl_ignore_already_exists(true)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ddl_ignore_already_exists(mut self, enabled: bool) -> Self {
+        self.config.ddl_ignore_already_exists = enabled;
+        self
+    }
+
+    /// Limits the size of serialized batches to `max_size` bytes of statements and values.
+    /// Batches are serialized row by row, and a batch exceeding the limit fails
+    /// before it is sent, with [`BadQuery::BatchTooLarge`](crate::transport::errors::BadQuery::BatchTooLarge)
+    /// naming the statement at which the limit was crossed.
+    /// By default the size of batches is not limited.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .max_batch_serialized_size(1024 * 1024)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn max_batch_serialized_size(mut self, max_size: usize) -> Self {
+        self.config.max_batch_serialized_size = Some(max_size);
+        self
+    }
+
+    /// Limits the size of the bodies of request frames to `max_size` bytes, after compression.
+    /// A request exceeding the limit fails before it is sent, with
+    /// [`BadQuery::RequestTooLarge`](crate::transport::errors::BadQuery::RequestTooLarge).
+    /// By default the size of requests is not limited.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .max_request_frame_size(16 * 1024 * 1024)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn max_request_frame_size(mut self, max_size: usize) -> Self {
+        self.config.max_request_frame_size = Some(max_size);
+        self
+    }
+
+    /// Limits the size of the bodies of response frames to `max_size` bytes, protecting
+    /// the application from running out of memory because of a huge response.
+    ///
+    /// The limit is checked against the size of the body declared in the frame header,
+    /// before the body is read. The body of a response exceeding it is read from the
+    /// connection and discarded without being buffered, so the connection remains usable,
+    /// and the request fails with
+    /// [`QueryError::ResponseTooLarge`](crate::transport::errors::QueryError::ResponseTooLarge).
+    /// The limit applies to compressed bodies after decompression too, and is checked
+    /// before they are decompressed.
+    ///
+    /// Note that a page of results is sent in a single frame, so the page size
+    /// of statements should be chosen so that their pages fit in the limit.
+    /// By default the size of responses is not limited.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .max_response_frame_size(64 * 1024 * 1024)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn max_response_frame_size(mut self, max_size: usize) -> Self {
+        self.config.max_response_frame_size = Some(max_size);
+        self
+    }
+
+    /// Sets the [`Runtime`] which the session uses for timers and for spawning
+    /// its background tasks, see [`runtime`](crate::transport::runtime).
+    /// Default is [`TokioRuntime`](crate::transport::runtime::TokioRuntime).
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use std::sync::Arc;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use scylla::transport::runtime::TokioRuntime;
+    ///
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .runtime(Arc::new(TokioRuntime))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.config.runtime = runtime;
+        self
+    }
+
+    /// metrics-exporter feature
+    /// Installs an observer that receives driver metrics as they are recorded.
+    /// See [`metrics_exporter`](crate::transport::metrics_exporter) for details
+    /// and a ready-made Prometheus adapter.
+    /// Default is None.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use std::sync::Arc;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use scylla::transport::metrics_exporter::{MetricsObserver, RequestEvent};
+    ///
+    /// #[derive(Debug)]
+    /// struct PrintingObserver;
+    ///
+    /// impl MetricsObserver for PrintingObserver {
+    ///     fn on_request(&self, event: &RequestEvent<'_>) {
+    ///         println!("{} request to {} took {:?}", event.kind.as_str(), event.node.address, event.latency);
+    ///     }
+    /// }
+    ///
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .metrics_observer(Arc::new(PrintingObserver))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "metrics-exporter")]
+    pub fn metrics_observer(mut self, observer: Arc<dyn MetricsObserver>) -> Self {
+        self.config.metrics_observer = Some(observer);
+        self
+    }
 }
 
 /// Creates a [`SessionBuilder`] with default configuration, same as [`SessionBuilder::new`]
@@ -1096,12 +2088,15 @@ mod tests {
             .config
             .default_execution_profile_handle
             .access();
+        // Consistencies not set in the profile fall back to the session defaults
+        assert_eq!(default_execution_profile.consistency, None);
+        assert_eq!(default_execution_profile.serial_consistency, None);
         assert_eq!(
-            default_execution_profile.consistency,
+            default_builder.config.default_consistency,
             defaults::consistency()
         );
         assert_eq!(
-            default_execution_profile.serial_consistency,
+            default_builder.config.default_serial_consistency,
             defaults::serial_consistency()
         );
         assert_eq!(
@@ -0,0 +1,134 @@
+//! Abstraction over the [`Session`](crate::Session) operations used by application code.
+//!
+//! Code which is generic over [`SessionOps`] instead of taking a `&Session` can be unit tested
+//! without a cluster, using the `MockSession` from the `testing` module,
+//! available with the `testing` feature.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::batch::Batch;
+use crate::prepared_statement::PreparedStatement;
+use crate::query::Query;
+use crate::serialize::batch::BatchValues;
+use crate::serialize::row::SerializeRow;
+use crate::transport::errors::QueryError;
+use crate::transport::iterator::RowIterator;
+use crate::{QueryResult, Session};
+
+/// Operations of a session used to execute requests.
+///
+/// The methods behave the same as the [`Session`] methods with the same names,
+/// which are called by the implementation for [`Session`].
+///
+/// # Example
+/// ```rust
+/// use scylla::transport::errors::QueryError;
+/// use scylla::SessionOps;
+///
+/// // Works with a `Session`, and with a `MockSession` in unit tests
+/// async fn add_user(
+///     session: &impl SessionOps,
+///     id: i32,
+///     name: &str,
+/// ) -> Result<(), QueryError> {
+///     session
+///         .query("INSERT INTO ks.users (id, name) VALUES (?, ?)", (id, name))
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+#[async_trait]
+pub trait SessionOps: Send + Sync {
+    /// Executes an unprepared statement, see [`Session::query_unpaged`].
+    async fn query(
+        &self,
+        query: impl Into<Query> + Send,
+        values: impl SerializeRow + Send + Sync,
+    ) -> Result<QueryResult, QueryError>;
+
+    /// Executes a prepared statement, see [`Session::execute_unpaged`].
+    async fn execute(
+        &self,
+        prepared: &PreparedStatement,
+        values: impl SerializeRow + Send + Sync,
+    ) -> Result<QueryResult, QueryError>;
+
+    /// Executes a batch, see [`Session::batch`].
+    async fn batch(
+        &self,
+        batch: &Batch,
+        values: impl BatchValues + Send + Sync,
+    ) -> Result<QueryResult, QueryError>;
+
+    /// Prepares a statement, see [`Session::prepare`].
+    async fn prepare(
+        &self,
+        query: impl Into<Query> + Send,
+    ) -> Result<PreparedStatement, QueryError>;
+
+    /// Executes an unprepared statement with paging, see [`Session::query_iter`].
+    async fn query_iter(
+        &self,
+        query: impl Into<Query> + Send,
+        values: impl SerializeRow + Send + Sync,
+    ) -> Result<RowIterator, QueryError>;
+
+    /// Sets the keyspace used by the session, see [`Session::use_keyspace`].
+    async fn use_keyspace(
+        &self,
+        keyspace_name: impl Into<String> + Send,
+        case_sensitive: bool,
+    ) -> Result<Option<Arc<String>>, QueryError>;
+}
+
+#[async_trait]
+impl SessionOps for Session {
+    async fn query(
+        &self,
+        query: impl Into<Query> + Send,
+        values: impl SerializeRow + Send + Sync,
+    ) -> Result<QueryResult, QueryError> {
+        Session::query_unpaged(self, query, values).await
+    }
+
+    async fn execute(
+        &self,
+        prepared: &PreparedStatement,
+        values: impl SerializeRow + Send + Sync,
+    ) -> Result<QueryResult, QueryError> {
+        Session::execute_unpaged(self, prepared, values).await
+    }
+
+    async fn batch(
+        &self,
+        batch: &Batch,
+        values: impl BatchValues + Send + Sync,
+    ) -> Result<QueryResult, QueryError> {
+        Session::batch(self, batch, values).await
+    }
+
+    async fn prepare(
+        &self,
+        query: impl Into<Query> + Send,
+    ) -> Result<PreparedStatement, QueryError> {
+        Session::prepare(self, query).await
+    }
+
+    async fn query_iter(
+        &self,
+        query: impl Into<Query> + Send,
+        values: impl SerializeRow + Send + Sync,
+    ) -> Result<RowIterator, QueryError> {
+        Session::query_iter(self, query, values).await
+    }
+
+    async fn use_keyspace(
+        &self,
+        keyspace_name: impl Into<String> + Send,
+        case_sensitive: bool,
+    ) -> Result<Option<Arc<String>>, QueryError> {
+        Session::use_keyspace(self, keyspace_name, case_sensitive).await
+    }
+}
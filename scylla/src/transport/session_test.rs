@@ -1,20 +1,23 @@
 use crate as scylla;
 use crate::batch::{Batch, BatchStatement};
-use crate::frame::response::result::Row;
-use crate::prepared_statement::PreparedStatement;
+use crate::frame::response::result::{CqlValue, Row};
+use crate::prepared_statement::{PartitionKeyError, PreparedStatement};
 use crate::query::Query;
 use crate::retry_policy::{QueryInfo, RetryDecision, RetryPolicy, RetrySession};
 use crate::routing::Token;
 use crate::statement::Consistency;
 use crate::tracing::TracingInfo;
 use crate::transport::cluster::Datacenter;
-use crate::transport::errors::{BadKeyspaceName, BadQuery, DbError, QueryError};
+use crate::transport::errors::{
+    BadKeyspaceName, BadQuery, DbError, ExecutionError, QueryError, QueryErrorKind, StatementInfo,
+};
 use crate::transport::partitioner::{
     calculate_token_for_partition_key, Murmur3Partitioner, Partitioner, PartitionerName,
 };
+use crate::transport::table_scan::ScanConfig;
 use crate::transport::topology::Strategy::NetworkTopologyStrategy;
 use crate::transport::topology::{
-    CollectionType, ColumnKind, CqlType, NativeType, UserDefinedType,
+    CollectionType, ColumnKind, CqlType, IndexKind, NativeType, UserDefinedType,
 };
 use crate::utils::test_utils::{
     create_new_session_builder, supports_feature, unique_keyspace_name,
@@ -28,6 +31,7 @@ use bytes::Bytes;
 use futures::{FutureExt, StreamExt, TryStreamExt};
 use itertools::Itertools;
 use scylla_cql::frame::response::result::ColumnType;
+use scylla_cql::types::serialize::batch::BatchValuesFromIterator;
 use scylla_cql::types::serialize::row::{SerializeRow, SerializedValues};
 use scylla_cql::types::serialize::value::SerializeCql;
 use std::collections::BTreeSet;
@@ -66,9 +70,9 @@ async fn test_unprepared_statement() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session
-        .query(
+        .query_unpaged(
             format!(
                 "CREATE TABLE IF NOT EXISTS {}.t (a int, b int, c text, primary key (a, b))",
                 ks
@@ -79,21 +83,21 @@ async fn test_unprepared_statement() {
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             format!("INSERT INTO {}.t (a, b, c) VALUES (1, 2, 'abc')", ks),
             &[],
         )
         .await
         .unwrap();
     session
-        .query(
+        .query_unpaged(
             format!("INSERT INTO {}.t (a, b, c) VALUES (7, 11, '')", ks),
             &[],
         )
         .await
         .unwrap();
     session
-        .query(
+        .query_unpaged(
             format!("INSERT INTO {}.t (a, b, c) VALUES (1, 4, 'hello')", ks),
             &[],
         )
@@ -101,7 +105,7 @@ async fn test_unprepared_statement() {
         .unwrap();
 
     let query_result = session
-        .query(format!("SELECT a, b, c FROM {}.t", ks), &[])
+        .query_unpaged(format!("SELECT a, b, c FROM {}.t", ks), &[])
         .await
         .unwrap();
 
@@ -146,7 +150,7 @@ async fn test_unprepared_statement() {
     let mut watchdog = 0;
     loop {
         let rs_manual = session
-            .query_paged(query.clone(), &[], paging_state)
+            .query_single_page(query.clone(), &[], paging_state)
             .await
             .unwrap();
         results_from_manual_paging.append(&mut rs_manual.rows.unwrap());
@@ -164,9 +168,9 @@ async fn test_prepared_statement() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session
-        .query(
+        .query_unpaged(
             format!(
                 "CREATE TABLE IF NOT EXISTS {}.t2 (a int, b int, c text, primary key (a, b))",
                 ks
@@ -176,7 +180,7 @@ async fn test_prepared_statement() {
         .await
         .unwrap();
     session
-        .query(format!("CREATE TABLE IF NOT EXISTS {}.complex_pk (a int, b int, c text, d int, e int, primary key ((a,b,c),d))", ks), &[])
+        .query_unpaged(format!("CREATE TABLE IF NOT EXISTS {}.complex_pk (a int, b int, c text, d int, e int, primary key ((a,b,c),d))", ks), &[])
         .await
         .unwrap();
 
@@ -213,16 +217,19 @@ async fn test_prepared_statement() {
         .serialize_values(&values)
         .unwrap();
 
-    session.execute(&prepared_statement, &values).await.unwrap();
     session
-        .execute(&prepared_complex_pk_statement, &values)
+        .execute_unpaged(&prepared_statement, &values)
+        .await
+        .unwrap();
+    session
+        .execute_unpaged(&prepared_complex_pk_statement, &values)
         .await
         .unwrap();
 
     // Verify that token calculation is compatible with Scylla
     {
         let rs = session
-            .query(format!("SELECT token(a) FROM {}.t2", ks), &[])
+            .query_unpaged(format!("SELECT token(a) FROM {}.t2", ks), &[])
             .await
             .unwrap()
             .rows
@@ -247,7 +254,7 @@ async fn test_prepared_statement() {
     }
     {
         let rs = session
-            .query(format!("SELECT token(a,b,c) FROM {}.complex_pk", ks), &[])
+            .query_unpaged(format!("SELECT token(a,b,c) FROM {}.complex_pk", ks), &[])
             .await
             .unwrap()
             .rows
@@ -275,7 +282,7 @@ async fn test_prepared_statement() {
     // Verify that correct data was inserted
     {
         let rs = session
-            .query(format!("SELECT a,b,c FROM {}.t2", ks), &[])
+            .query_unpaged(format!("SELECT a,b,c FROM {}.t2", ks), &[])
             .await
             .unwrap()
             .rows
@@ -293,7 +300,7 @@ async fn test_prepared_statement() {
         let mut watchdog = 0;
         loop {
             let rs_manual = session
-                .execute_paged(&prepared_paged, &[], paging_state)
+                .execute_single_page(&prepared_paged, &[], paging_state)
                 .await
                 .unwrap();
             results_from_manual_paging.append(&mut rs_manual.rows.unwrap());
@@ -307,7 +314,7 @@ async fn test_prepared_statement() {
     }
     {
         let rs = session
-            .query(format!("SELECT a,b,c,d,e FROM {}.complex_pk", ks), &[])
+            .query_unpaged(format!("SELECT a,b,c,d,e FROM {}.complex_pk", ks), &[])
             .await
             .unwrap()
             .rows
@@ -340,7 +347,7 @@ async fn test_prepared_statement() {
             e: 5,
         };
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "INSERT INTO {}.complex_pk (a,b,c,d,e) VALUES (?,?,?,?,?)",
                     ks
@@ -350,7 +357,7 @@ async fn test_prepared_statement() {
             .await
             .unwrap();
         let mut rs = session
-            .query(
+            .query_unpaged(
                 format!(
                     "SELECT * FROM {}.complex_pk WHERE a = 9 and b = 8 and c = 'seven'",
                     ks
@@ -372,9 +379,9 @@ async fn test_batch() {
     let session = Arc::new(create_new_session_builder().build().await.unwrap());
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session
-        .query(
+        .query_unpaged(
             format!(
                 "CREATE TABLE IF NOT EXISTS {}.t_batch (a int, b int, c text, primary key (a, b))",
                 ks
@@ -420,7 +427,7 @@ async fn test_batch() {
     .unwrap();
 
     let rs = session
-        .query(format!("SELECT a, b, c FROM {}.t_batch", ks), &[])
+        .query_unpaged(format!("SELECT a, b, c FROM {}.t_batch", ks), &[])
         .await
         .unwrap()
         .rows
@@ -452,7 +459,7 @@ async fn test_batch() {
 
     // This statement flushes the prepared statement cache
     session
-        .query(
+        .query_unpaged(
             format!("ALTER TABLE {}.t_batch WITH gc_grace_seconds = 42", ks),
             &[],
         )
@@ -461,7 +468,7 @@ async fn test_batch() {
     session.batch(&batch, values).await.unwrap();
 
     let rs = session
-        .query(
+        .query_unpaged(
             format!("SELECT a, b, c FROM {}.t_batch WHERE a = 4", ks),
             &[],
         )
@@ -482,14 +489,493 @@ async fn test_batch() {
     assert_eq!(results, vec![(4, 20, &String::from("foobar"))]);
 }
 
+#[tokio::test]
+async fn test_batch_warnings() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t_batch_warnings (a int, b text, primary key (a))",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let prepared_statement = session
+        .prepare(format!(
+            "INSERT INTO {}.t_batch_warnings (a, b) VALUES (?, ?)",
+            ks
+        ))
+        .await
+        .unwrap();
+
+    // An unlogged batch spanning many partitions and exceeding the batch size
+    // warning threshold, but still below the failure threshold.
+    let mut batch = Batch::new(crate::batch::BatchType::Unlogged);
+    let big_text = "x".repeat(8 * 1024);
+    let mut values = Vec::new();
+    for i in 0..50_i32 {
+        batch.append_statement(prepared_statement.clone());
+        values.push((i, big_text.as_str()));
+    }
+
+    let result = session.batch(&batch, values).await.unwrap();
+    assert!(!result.warnings().is_empty());
+}
+
+#[tokio::test]
+async fn test_request_interceptors() {
+    use crate::transport::interceptor::{RequestContext, RequestInterceptor};
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    #[derive(Debug)]
+    struct AuditingInterceptor {
+        log: Log,
+    }
+
+    #[async_trait]
+    impl RequestInterceptor for AuditingInterceptor {
+        async fn on_request(&self, ctx: &mut RequestContext<'_>) -> Result<(), QueryError> {
+            self.log.lock().unwrap().push(format!(
+                "audit request {} timestamp {:?}",
+                ctx.statement_text().unwrap_or("<batch>"),
+                ctx.timestamp()
+            ));
+            Ok(())
+        }
+
+        fn on_response(
+            &self,
+            _ctx: &RequestContext<'_>,
+            result: &Result<QueryResult, ExecutionError>,
+        ) {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("audit response ok={}", result.is_ok()));
+        }
+
+        fn on_attempt(&self, _ctx: &RequestContext<'_>, _node: crate::transport::NodeRef<'_>) {
+            self.log.lock().unwrap().push("audit attempt".to_owned());
+        }
+    }
+
+    #[derive(Debug)]
+    struct DenyingInterceptor {
+        log: Log,
+    }
+
+    #[async_trait]
+    impl RequestInterceptor for DenyingInterceptor {
+        async fn on_request(&self, ctx: &mut RequestContext<'_>) -> Result<(), QueryError> {
+            if ctx.statement_text().unwrap_or_default().starts_with("DROP") {
+                self.log
+                    .lock()
+                    .unwrap()
+                    .push("deny request denied".to_owned());
+                return Err(QueryError::BadQuery(BadQuery::Other(
+                    "DROP statements are not allowed".to_owned(),
+                )));
+            }
+            self.log
+                .lock()
+                .unwrap()
+                .push("deny request allowed".to_owned());
+            Ok(())
+        }
+
+        fn on_response(
+            &self,
+            _ctx: &RequestContext<'_>,
+            result: &Result<QueryResult, ExecutionError>,
+        ) {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("deny response ok={}", result.is_ok()));
+        }
+    }
+
+    #[derive(Debug)]
+    struct TimestampInterceptor;
+
+    #[async_trait]
+    impl RequestInterceptor for TimestampInterceptor {
+        async fn on_request(&self, ctx: &mut RequestContext<'_>) -> Result<(), QueryError> {
+            ctx.set_timestamp(Some(42));
+            ctx.custom_payload_mut()
+                .insert("tenant".to_owned(), b"acme".to_vec());
+            Ok(())
+        }
+    }
+
+    let ks = unique_keyspace_name();
+    let setup_session = create_new_session_builder().build().await.unwrap();
+    setup_session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    setup_session
+        .query_unpaged(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t_interceptors (a int primary key, b int)",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let log: Log = Default::default();
+    let session = create_new_session_builder()
+        .request_interceptor(Arc::new(AuditingInterceptor { log: log.clone() }))
+        .request_interceptor(Arc::new(DenyingInterceptor { log: log.clone() }))
+        .request_interceptor(Arc::new(TimestampInterceptor))
+        .build()
+        .await
+        .unwrap();
+    session.use_keyspace(&ks, false).await.unwrap();
+    log.lock().unwrap().clear();
+
+    // A denied request is not sent, but all interceptors learn about its result
+    let drop_result = session
+        .query_unpaged("DROP TABLE t_interceptors", &[])
+        .await;
+    assert_matches!(drop_result, Err(QueryError::BadQuery(BadQuery::Other(_))));
+    assert_eq!(
+        std::mem::take(&mut *log.lock().unwrap()),
+        vec![
+            "audit request DROP TABLE t_interceptors timestamp None",
+            "deny request denied",
+            "audit response ok=false",
+            "deny response ok=false",
+        ]
+    );
+
+    // Modifications made by interceptors are applied to the sent request
+    let insert = session
+        .prepare("INSERT INTO t_interceptors (a, b) VALUES (?, ?)")
+        .await
+        .unwrap();
+    log.lock().unwrap().clear();
+    session
+        .execute_unpaged(&insert, (1_i32, 2_i32))
+        .await
+        .unwrap();
+    assert_eq!(
+        std::mem::take(&mut *log.lock().unwrap()),
+        vec![
+            "audit request INSERT INTO t_interceptors (a, b) VALUES (?, ?) timestamp None",
+            "deny request allowed",
+            "audit attempt",
+            "audit response ok=true",
+            "deny response ok=true",
+        ]
+    );
+    assert_eq!(insert.get_timestamp(), None);
+    assert_eq!(insert.get_custom_payload(), None);
+
+    let (writetime,) = setup_session
+        .query_unpaged(
+            format!("SELECT WRITETIME(b) FROM {}.t_interceptors WHERE a = 1", ks),
+            &[],
+        )
+        .await
+        .unwrap()
+        .single_row_typed::<(i64,)>()
+        .unwrap();
+    assert_eq!(writetime, 42);
+
+    // A request fetching many pages is intercepted once, each page is a separate attempt
+    for a in 2..4_i32 {
+        session.execute_unpaged(&insert, (a, a)).await.unwrap();
+    }
+    log.lock().unwrap().clear();
+    let select = Query::new("SELECT a FROM t_interceptors").with_page_size(1);
+    let result = session.query_unpaged(select, &[]).await.unwrap();
+    assert_eq!(result.rows_num().unwrap(), 3);
+    let (attempts, entries): (Vec<String>, Vec<String>) = std::mem::take(&mut *log.lock().unwrap())
+        .into_iter()
+        .partition(|entry| entry == "audit attempt");
+    assert!(attempts.len() >= 3);
+    assert_eq!(
+        entries,
+        vec![
+            "audit request SELECT a FROM t_interceptors timestamp None",
+            "deny request allowed",
+            "audit response ok=true",
+            "deny response ok=true",
+        ]
+    );
+
+    // Paging iterators call the interceptors with every attempt and once with the outcome
+    let select = Query::new("SELECT a FROM t_interceptors").with_page_size(1);
+    let rows = session
+        .query_iter(select, &[])
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 3);
+    let (attempts, entries): (Vec<String>, Vec<String>) = std::mem::take(&mut *log.lock().unwrap())
+        .into_iter()
+        .partition(|entry| entry == "audit attempt");
+    assert!(attempts.len() >= 3);
+    assert_eq!(
+        entries,
+        vec![
+            "audit request SELECT a FROM t_interceptors timestamp None",
+            "deny request allowed",
+            "audit response ok=true",
+            "deny response ok=true",
+        ]
+    );
+
+    let drop_table = session.prepare("DROP TABLE t_interceptors").await.unwrap();
+    log.lock().unwrap().clear();
+    assert!(session.execute_iter(drop_table, &[]).await.is_err());
+    assert_eq!(
+        std::mem::take(&mut *log.lock().unwrap()),
+        vec![
+            "audit request DROP TABLE t_interceptors timestamp None",
+            "deny request denied",
+            "audit response ok=false",
+            "deny response ok=false",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_statement_guards() {
+    use crate::transport::statement_guard::{DenyUnprepared, MaxBatchStatements, RequireWhere};
+
+    let ks = unique_keyspace_name();
+    let setup_session = create_new_session_builder().build().await.unwrap();
+    setup_session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    setup_session
+        .query_unpaged(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t_guards (a int primary key, b int)",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+    let insert = setup_session
+        .prepare(format!("INSERT INTO {}.t_guards (a, b) VALUES (?, ?)", ks))
+        .await
+        .unwrap();
+    let select_all = setup_session
+        .prepare(format!("SELECT * FROM {}.t_guards", ks))
+        .await
+        .unwrap();
+
+    let session = create_new_session_builder()
+        .statement_guard(Arc::new(DenyUnprepared))
+        .statement_guard(Arc::new(RequireWhere::new([format!("{}.t_guards", ks)])))
+        .statement_guard(Arc::new(MaxBatchStatements(2)))
+        .build()
+        .await
+        .unwrap();
+    let denied_before = session.get_metrics().get_denied_statements();
+
+    let error = session
+        .query_unpaged(format!("SELECT * FROM {}.t_guards WHERE a = 1", ks), &[])
+        .await
+        .unwrap_err();
+    assert_matches!(error, QueryError::DeniedByGuard { .. });
+    assert!(matches!(
+        session.execute_iter(select_all.clone(), &[]).await,
+        Err(QueryError::DeniedByGuard { .. })
+    ));
+
+    session
+        .execute_unpaged(&insert, (1_i32, 2_i32))
+        .await
+        .unwrap();
+
+    let mut batch = Batch::default();
+    for _ in 0..3 {
+        batch.append_statement(insert.clone());
+    }
+    let error = session
+        .batch(&batch, ((1_i32, 2_i32), (2_i32, 3_i32), (3_i32, 4_i32)))
+        .await
+        .unwrap_err();
+    assert_matches!(error, QueryError::DeniedByGuard { .. });
+
+    assert_eq!(
+        session.get_metrics().get_denied_statements() - denied_before,
+        3
+    );
+}
+
+#[tokio::test]
+async fn test_request_spans() {
+    use std::sync::Mutex;
+    use tracing::instrument::WithSubscriber;
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Instrument, Subscriber};
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    #[derive(Debug, Default)]
+    struct RecordedSpan {
+        name: &'static str,
+        parent: Option<&'static str>,
+        fields: HashMap<&'static str, String>,
+    }
+
+    struct FieldVisitor<'a>(&'a mut HashMap<&'static str, String>);
+
+    impl tracing::field::Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name(), format!("{:?}", value));
+        }
+
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0.insert(field.name(), value.to_owned());
+        }
+    }
+
+    // Records all spans together with the name of their parent and their fields.
+    #[derive(Clone, Default)]
+    struct SpanRecorder {
+        spans: Arc<Mutex<Vec<RecordedSpan>>>,
+    }
+
+    struct SpanIndex(usize);
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for SpanRecorder {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let span = ctx.span(id).unwrap();
+            let mut recorded = RecordedSpan {
+                name: span.name(),
+                parent: span.parent().map(|parent| parent.name()),
+                ..Default::default()
+            };
+            attrs.record(&mut FieldVisitor(&mut recorded.fields));
+
+            let mut spans = self.spans.lock().unwrap();
+            span.extensions_mut().insert(SpanIndex(spans.len()));
+            spans.push(recorded);
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+            let span = ctx.span(id).unwrap();
+            let extensions = span.extensions();
+            let index = extensions.get::<SpanIndex>().unwrap().0;
+            values.record(&mut FieldVisitor(
+                &mut self.spans.lock().unwrap()[index].fields,
+            ));
+        }
+    }
+
+    let recorder = SpanRecorder::default();
+    let subscriber = tracing_subscriber::registry().with(
+        recorder
+            .clone()
+            .with_filter(tracing_subscriber::filter::LevelFilter::INFO),
+    );
+
+    let ks = unique_keyspace_name();
+    async {
+        let session = create_new_session_builder()
+            .request_span_level(tracing::Level::INFO)
+            .build()
+            .await
+            .unwrap();
+        session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+        session
+            .query_unpaged(
+                format!("CREATE TABLE IF NOT EXISTS {}.t_spans (a int primary key)", ks),
+                &[],
+            )
+            .await
+            .unwrap();
+        let mut insert = session
+            .prepare(format!("INSERT INTO {}.t_spans (a) VALUES (?)", ks))
+            .await
+            .unwrap();
+        insert.set_consistency(Consistency::One);
+
+        let app_span = tracing::info_span!("app");
+        recorder.spans.lock().unwrap().clear();
+        session
+            .execute_unpaged(&insert, (1_i32,))
+            .instrument(app_span.clone())
+            .await
+            .unwrap();
+        session
+            .query_iter(format!("SELECT a FROM {}.t_spans", ks), &[])
+            .instrument(app_span)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        // With the default level, request spans are filtered out by the subscriber
+        let default_level_session = create_new_session_builder().build().await.unwrap();
+        default_level_session
+            .execute_unpaged(&insert, (2_i32,))
+            .await
+            .unwrap();
+    }
+    .with_subscriber(subscriber)
+    .await;
+
+    let spans = recorder.spans.lock().unwrap();
+    let requests: Vec<&RecordedSpan> = spans
+        .iter()
+        .filter(|span| span.name == "scylla.request")
+        .collect();
+    assert_eq!(requests.len(), 2, "{:?}", spans);
+
+    let execute = requests[0];
+    assert_eq!(execute.parent, Some("app"));
+    assert_eq!(execute.fields["kind"], "prepared");
+    assert_eq!(execute.fields["keyspace"], ks);
+    assert_eq!(execute.fields["table"], "t_spans");
+    assert_eq!(execute.fields["consistency"], "One");
+    assert_eq!(execute.fields["attempts"], "1");
+    assert_eq!(execute.fields["outcome"], "success");
+    assert!(execute.fields.contains_key("coordinator"));
+
+    let attempts: Vec<&RecordedSpan> = spans
+        .iter()
+        .filter(|span| span.name == "scylla.attempt")
+        .collect();
+    assert_eq!(attempts.len(), 2, "{:?}", spans);
+    assert_eq!(attempts[0].parent, Some("scylla.request"));
+    assert_eq!(attempts[0].fields["attempt"], "1");
+    assert_eq!(attempts[0].fields["speculative"], "false");
+    assert_eq!(attempts[0].fields["outcome"], "success");
+
+    // Pages of an iterator are requests nested under the attempt which fetched them
+    assert_eq!(attempts[1].parent, Some("app"));
+    let page = requests[1];
+    assert_eq!(page.parent, Some("scylla.attempt"));
+    assert_eq!(page.fields["kind"], "unprepared");
+    assert_eq!(page.fields["outcome"], "success");
+}
+
 #[tokio::test]
 async fn test_token_calculation() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session
-        .query(
+        .query_unpaged(
             format!("CREATE TABLE IF NOT EXISTS {}.t3 (a text primary key)", ks),
             &[],
         )
@@ -514,10 +1000,13 @@ async fn test_token_calculation() {
         }
         let values = (&s,);
         let serialized_values = prepared_statement.serialize_values(&values).unwrap();
-        session.execute(&prepared_statement, &values).await.unwrap();
+        session
+            .execute_unpaged(&prepared_statement, &values)
+            .await
+            .unwrap();
 
         let rs = session
-            .query(
+            .query_unpaged(
                 format!("SELECT token(a) FROM {}.t3 WHERE a = ?", ks),
                 &values,
             )
@@ -548,9 +1037,9 @@ async fn test_token_awareness() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session
-        .query(
+        .query_unpaged(
             format!("CREATE TABLE IF NOT EXISTS {}.t (a text primary key)", ks),
             &[],
         )
@@ -569,7 +1058,10 @@ async fn test_token_awareness() {
         let values = (&key,);
 
         // Execute a query and observe tracing info
-        let res = session.execute(&prepared_statement, values).await.unwrap();
+        let res = session
+            .execute_unpaged(&prepared_statement, values)
+            .await
+            .unwrap();
         let tracing_info = session
             .get_tracing_info(res.tracing_id.as_ref().unwrap())
             .await
@@ -596,10 +1088,10 @@ async fn test_use_keyspace() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
 
     session
-        .query(
+        .query_unpaged(
             format!("CREATE TABLE IF NOT EXISTS {}.tab (a text primary key)", ks),
             &[],
         )
@@ -607,19 +1099,19 @@ async fn test_use_keyspace() {
         .unwrap();
 
     session
-        .query(format!("INSERT INTO {}.tab (a) VALUES ('test1')", ks), &[])
+        .query_unpaged(format!("INSERT INTO {}.tab (a) VALUES ('test1')", ks), &[])
         .await
         .unwrap();
 
     session.use_keyspace(ks.clone(), false).await.unwrap();
 
     session
-        .query("INSERT INTO tab (a) VALUES ('test2')", &[])
+        .query_unpaged("INSERT INTO tab (a) VALUES ('test2')", &[])
         .await
         .unwrap();
 
     let mut rows: Vec<String> = session
-        .query("SELECT * FROM tab", &[])
+        .query_unpaged("SELECT * FROM tab", &[])
         .await
         .unwrap()
         .rows
@@ -669,7 +1161,7 @@ async fn test_use_keyspace() {
         .unwrap();
 
     let mut rows2: Vec<String> = session2
-        .query("SELECT * FROM tab", &[])
+        .query_unpaged("SELECT * FROM tab", &[])
         .await
         .unwrap()
         .rows
@@ -689,11 +1181,11 @@ async fn test_use_keyspace_case_sensitivity() {
     let ks_lower = unique_keyspace_name().to_lowercase();
     let ks_upper = ks_lower.to_uppercase();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS \"{}\" WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks_lower), &[]).await.unwrap();
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS \"{}\" WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks_upper), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS \"{}\" WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks_lower), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS \"{}\" WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks_upper), &[]).await.unwrap();
 
     session
-        .query(
+        .query_unpaged(
             format!("CREATE TABLE {}.tab (a text primary key)", ks_lower),
             &[],
         )
@@ -701,7 +1193,7 @@ async fn test_use_keyspace_case_sensitivity() {
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             format!("CREATE TABLE \"{}\".tab (a text primary key)", ks_upper),
             &[],
         )
@@ -709,7 +1201,7 @@ async fn test_use_keyspace_case_sensitivity() {
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             format!("INSERT INTO {}.tab (a) VALUES ('lowercase')", ks_lower),
             &[],
         )
@@ -717,7 +1209,7 @@ async fn test_use_keyspace_case_sensitivity() {
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             format!("INSERT INTO \"{}\".tab (a) VALUES ('uppercase')", ks_upper),
             &[],
         )
@@ -729,7 +1221,7 @@ async fn test_use_keyspace_case_sensitivity() {
     session.use_keyspace(ks_upper.clone(), false).await.unwrap();
 
     let rows: Vec<String> = session
-        .query("SELECT * from tab", &[])
+        .query_unpaged("SELECT * from tab", &[])
         .await
         .unwrap()
         .rows
@@ -745,7 +1237,7 @@ async fn test_use_keyspace_case_sensitivity() {
     session.use_keyspace(ks_upper, true).await.unwrap();
 
     let rows: Vec<String> = session
-        .query("SELECT * from tab", &[])
+        .query_unpaged("SELECT * from tab", &[])
         .await
         .unwrap()
         .rows
@@ -762,10 +1254,10 @@ async fn test_raw_use_keyspace() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
 
     session
-        .query(
+        .query_unpaged(
             format!("CREATE TABLE IF NOT EXISTS {}.tab (a text primary key)", ks),
             &[],
         )
@@ -773,7 +1265,7 @@ async fn test_raw_use_keyspace() {
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             format!("INSERT INTO {}.tab (a) VALUES ('raw_test')", ks),
             &[],
         )
@@ -781,12 +1273,12 @@ async fn test_raw_use_keyspace() {
         .unwrap();
 
     session
-        .query(format!("use    \"{}\"    ;", ks), &[])
+        .query_unpaged(format!("use    \"{}\"    ;", ks), &[])
         .await
         .unwrap();
 
     let rows: Vec<String> = session
-        .query("SELECT * FROM tab", &[])
+        .query_unpaged("SELECT * FROM tab", &[])
         .await
         .unwrap()
         .rows
@@ -799,12 +1291,12 @@ async fn test_raw_use_keyspace() {
 
     // Check if case sensitivity is correctly detected
     assert!(session
-        .query(format!("use    \"{}\"    ;", ks.to_uppercase()), &[])
+        .query_unpaged(format!("use    \"{}\"    ;", ks.to_uppercase()), &[])
         .await
         .is_err());
 
     assert!(session
-        .query(format!("use    {}    ;", ks.to_uppercase()), &[])
+        .query_unpaged(format!("use    {}    ;", ks.to_uppercase()), &[])
         .await
         .is_ok());
 }
@@ -818,7 +1310,10 @@ async fn test_fetch_system_keyspace() {
         .await
         .unwrap();
 
-    session.execute(&prepared_statement, &[]).await.unwrap();
+    session
+        .execute_unpaged(&prepared_statement, &[])
+        .await
+        .unwrap();
 }
 
 // Test that some Database Errors are parsed correctly
@@ -828,15 +1323,15 @@ async fn test_db_errors() {
     let ks = unique_keyspace_name();
 
     // SyntaxError on bad query
-    assert!(matches!(
-        session.query("gibberish", &[]).await,
-        Err(QueryError::DbError(DbError::SyntaxError, _))
-    ));
+    let syntax_error = session.query_unpaged("gibberish", &[]).await.unwrap_err();
+    assert_matches!(syntax_error, QueryError::DbError(DbError::SyntaxError, _));
+    assert_eq!(syntax_error.kind(), QueryErrorKind::Syntax);
+    assert!(!syntax_error.is_safe_to_retry(false));
 
     // AlreadyExists when creating a keyspace for the second time
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
 
-    let create_keyspace_res = session.query(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await;
+    let create_keyspace_res = session.query_unpaged(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await;
     let keyspace_exists_error: DbError = match create_keyspace_res {
         Err(QueryError::DbError(e, _)) => e,
         _ => panic!("Second CREATE KEYSPACE didn't return an error!"),
@@ -852,7 +1347,7 @@ async fn test_db_errors() {
 
     // AlreadyExists when creating a table for the second time
     session
-        .query(
+        .query_unpaged(
             format!("CREATE TABLE IF NOT EXISTS {}.tab (a text primary key)", ks),
             &[],
         )
@@ -860,7 +1355,7 @@ async fn test_db_errors() {
         .unwrap();
 
     let create_table_res = session
-        .query(format!("CREATE TABLE {}.tab (a text primary key)", ks), &[])
+        .query_unpaged(format!("CREATE TABLE {}.tab (a text primary key)", ks), &[])
         .await;
     let create_tab_error: DbError = match create_table_res {
         Err(QueryError::DbError(e, _)) => e,
@@ -877,44 +1372,214 @@ async fn test_db_errors() {
 }
 
 #[tokio::test]
-async fn test_tracing() {
-    let session = create_new_session_builder().build().await.unwrap();
-    let ks = unique_keyspace_name();
+async fn test_session_value_redaction() {
+    use crate::serialize::redaction::ValueRedaction;
+    use crate::transport::interceptor::{RequestContext, RequestInterceptor};
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    // Records how values would be rendered by the interceptor
+    #[derive(Debug, Default)]
+    struct RedactionRecorder(Mutex<Vec<ValueRedaction>>);
+
+    #[async_trait]
+    impl RequestInterceptor for RedactionRecorder {
+        async fn on_request(&self, _ctx: &mut RequestContext<'_>) -> Result<(), QueryError> {
+            self.0.lock().unwrap().push(ValueRedaction::current());
+            Ok(())
+        }
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+        fn on_response(
+            &self,
+            _ctx: &RequestContext<'_>,
+            _result: &Result<QueryResult, ExecutionError>,
+        ) {
+            self.0.lock().unwrap().push(ValueRedaction::current());
+        }
+    }
 
-    session
-        .query(
-            format!("CREATE TABLE IF NOT EXISTS {}.tab (a text primary key)", ks),
-            &[],
-        )
+    let full_recorder = Arc::new(RedactionRecorder::default());
+    let full_session = create_new_session_builder()
+        .request_interceptor(full_recorder.clone())
+        .value_redaction(ValueRedaction::Full)
+        .build()
+        .await
+        .unwrap();
+    let recorder = Arc::new(RedactionRecorder::default());
+    let session = create_new_session_builder()
+        .request_interceptor(recorder.clone())
+        .build()
         .await
         .unwrap();
 
-    test_tracing_query(&session, ks.clone()).await;
-    test_tracing_execute(&session, ks.clone()).await;
-    test_tracing_prepare(&session, ks.clone()).await;
-    test_get_tracing_info(&session, ks.clone()).await;
-    test_tracing_query_iter(&session, ks.clone()).await;
-    test_tracing_execute_iter(&session, ks.clone()).await;
-    test_tracing_batch(&session, ks.clone()).await;
+    for session in [&full_session, &session] {
+        session
+            .query_unpaged("SELECT host_id FROM system.local", &[])
+            .await
+            .unwrap();
+        let prepared = session
+            .prepare("SELECT host_id FROM system.local")
+            .await
+            .unwrap();
+        session.execute_unpaged(&prepared, &[]).await.unwrap();
+        // `on_response` of iterators is called by their worker task
+        let mut rows = session.execute_iter(prepared, &[]).await.unwrap();
+        while rows.next().await.is_some() {}
+    }
+
+    assert_eq!(*full_recorder.0.lock().unwrap(), [ValueRedaction::Full; 6]);
+    // Other sessions keep rendering values redacted
+    assert_eq!(*recorder.0.lock().unwrap(), [ValueRedaction::Redacted; 6]);
+    assert_eq!(ValueRedaction::current(), ValueRedaction::Redacted);
 }
 
-async fn test_tracing_query(session: &Session, ks: String) {
-    // A query without tracing enabled has no tracing uuid in result
-    let untraced_query: Query = Query::new(format!("SELECT * FROM {}.tab", ks));
-    let untraced_query_result: QueryResult = session.query(untraced_query, &[]).await.unwrap();
+#[tokio::test]
+async fn test_statement_info_in_errors() {
+    use crate::transport::interceptor::{RequestContext, RequestInterceptor};
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    // Keeps the last error passed to `on_response`
+    #[derive(Debug, Default)]
+    struct ErrorCollector(Mutex<Option<ExecutionError>>);
+
+    #[async_trait]
+    impl RequestInterceptor for ErrorCollector {
+        async fn on_request(&self, _ctx: &mut RequestContext<'_>) -> Result<(), QueryError> {
+            Ok(())
+        }
 
-    assert!(untraced_query_result.tracing_id.is_none());
+        fn on_response(
+            &self,
+            _ctx: &RequestContext<'_>,
+            result: &Result<QueryResult, ExecutionError>,
+        ) {
+            if let Err(error) = result {
+                *self.0.lock().unwrap() = Some(error.clone());
+            }
+        }
+    }
 
-    // A query with tracing enabled has a tracing uuid in result
-    let mut traced_query: Query = Query::new(format!("SELECT * FROM {}.tab", ks));
-    traced_query.config.tracing = true;
+    let collector = Arc::new(ErrorCollector::default());
+    let last_error = || collector.0.lock().unwrap().take().unwrap();
+    let session = create_new_session_builder()
+        .request_interceptor(collector.clone())
+        .build()
+        .await
+        .unwrap();
+    let ks = unique_keyspace_name();
 
-    let traced_query_result: QueryResult = session.query(traced_query, &[]).await.unwrap();
-    assert!(traced_query_result.tracing_id.is_some());
+    // Not attached by default
+    session.query_unpaged("gibberish", &[]).await.unwrap_err();
+    assert_eq!(last_error().statement_info(), None);
 
-    // Querying this uuid from tracing table gives some results
+    let session = create_new_session_builder()
+        .request_interceptor(collector.clone())
+        .attach_statement_to_errors(true)
+        .error_statement_max_length(16)
+        .build()
+        .await
+        .unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(
+            format!("CREATE TABLE {}.t (pk text primary key, v int)", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let statement = format!("SELECT * FROM {}.missing", ks);
+    session
+        .query_unpaged(statement.as_str(), &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        last_error().statement_info(),
+        Some(&StatementInfo::Unprepared {
+            text: statement[..16].to_string()
+        })
+    );
+
+    let prepared = session
+        .prepare(format!("SELECT v FROM {}.t WHERE pk = ?", ks))
+        .await
+        .unwrap();
+    let mut batch = Batch::default();
+    batch.append_statement(format!("INSERT INTO {}.t (pk, v) VALUES (?, 1)", ks).as_str());
+    session
+        .query_unpaged(format!("DROP TABLE {}.t", ks), &[])
+        .await
+        .unwrap();
+
+    // Values bound to the statement must never leak into errors
+    let secret = "secret-partition-key";
+    session
+        .execute_unpaged(&prepared, (secret,))
+        .await
+        .unwrap_err();
+    let error = last_error();
+    assert_eq!(
+        error.statement_info(),
+        Some(&StatementInfo::Prepared {
+            id: prepared.get_id().clone(),
+            keyspace: Some(ks.clone()),
+            table: Some("t".to_string()),
+        })
+    );
+    assert!(!error.to_string().contains(secret));
+    assert!(!format!("{:?}", error).contains(secret));
+
+    session.batch(&batch, ((secret,),)).await.unwrap_err();
+    let error = last_error();
+    assert_matches!(
+        error.statement_info(),
+        Some(StatementInfo::Batch { statements }) if statements.len() == 1
+    );
+    assert!(!error.to_string().contains(secret));
+    assert!(!format!("{:?}", error).contains(secret));
+}
+
+#[tokio::test]
+async fn test_tracing() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+
+    session
+        .query_unpaged(
+            format!("CREATE TABLE IF NOT EXISTS {}.tab (a text primary key)", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    test_tracing_query(&session, ks.clone()).await;
+    test_tracing_execute(&session, ks.clone()).await;
+    test_tracing_prepare(&session, ks.clone()).await;
+    test_get_tracing_info(&session, ks.clone()).await;
+    test_tracing_query_iter(&session, ks.clone()).await;
+    test_tracing_execute_iter(&session, ks.clone()).await;
+    test_tracing_batch(&session, ks.clone()).await;
+}
+
+async fn test_tracing_query(session: &Session, ks: String) {
+    // A query without tracing enabled has no tracing uuid in result
+    let untraced_query: Query = Query::new(format!("SELECT * FROM {}.tab", ks));
+    let untraced_query_result: QueryResult =
+        session.query_unpaged(untraced_query, &[]).await.unwrap();
+
+    assert!(untraced_query_result.tracing_id.is_none());
+
+    // A query with tracing enabled has a tracing uuid in result
+    let mut traced_query: Query = Query::new(format!("SELECT * FROM {}.tab", ks));
+    traced_query.config.tracing = true;
+
+    let traced_query_result: QueryResult = session.query_unpaged(traced_query, &[]).await.unwrap();
+    assert!(traced_query_result.tracing_id.is_some());
+
+    // Querying this uuid from tracing table gives some results
     assert_in_tracing_table(session, traced_query_result.tracing_id.unwrap()).await;
 }
 
@@ -925,8 +1590,10 @@ async fn test_tracing_execute(session: &Session, ks: String) {
         .await
         .unwrap();
 
-    let untraced_prepared_result: QueryResult =
-        session.execute(&untraced_prepared, &[]).await.unwrap();
+    let untraced_prepared_result: QueryResult = session
+        .execute_unpaged(&untraced_prepared, &[])
+        .await
+        .unwrap();
 
     assert!(untraced_prepared_result.tracing_id.is_none());
 
@@ -938,7 +1605,10 @@ async fn test_tracing_execute(session: &Session, ks: String) {
 
     traced_prepared.config.tracing = true;
 
-    let traced_prepared_result: QueryResult = session.execute(&traced_prepared, &[]).await.unwrap();
+    let traced_prepared_result: QueryResult = session
+        .execute_unpaged(&traced_prepared, &[])
+        .await
+        .unwrap();
     assert!(traced_prepared_result.tracing_id.is_some());
 
     // Querying this uuid from tracing table gives some results
@@ -972,13 +1642,30 @@ async fn test_get_tracing_info(session: &Session, ks: String) {
     let mut traced_query: Query = Query::new(format!("SELECT * FROM {}.tab", ks));
     traced_query.config.tracing = true;
 
-    let traced_query_result: QueryResult = session.query(traced_query, &[]).await.unwrap();
-    let tracing_id: Uuid = traced_query_result.tracing_id.unwrap();
+    let traced_query_result: QueryResult = session.query_unpaged(traced_query, &[]).await.unwrap();
+    let tracing_id: Uuid = traced_query_result.tracing_id().unwrap();
 
     // Getting tracing info from session using this uuid works
     let tracing_info: TracingInfo = session.get_tracing_info(&tracing_id).await.unwrap();
+    assert!(tracing_info.coordinator.is_some());
+    assert!(tracing_info.started_at.is_some());
     assert!(!tracing_info.events.is_empty());
     assert!(!tracing_info.nodes().is_empty());
+
+    // The same works for tracing ids of the pages fetched by an iterator
+    let mut traced_query: Query = Query::new(format!("SELECT * FROM {}.tab", ks));
+    traced_query.config.tracing = true;
+
+    let mut traced_row_iter = session.query_iter(traced_query, &[]).await.unwrap();
+    while let Some(_row) = traced_row_iter.next().await {
+        // Receive rows
+    }
+
+    for tracing_id in traced_row_iter.get_tracing_ids() {
+        let tracing_info: TracingInfo = session.get_tracing_info(tracing_id).await.unwrap();
+        assert!(tracing_info.coordinator.is_some());
+        assert!(!tracing_info.events.is_empty());
+    }
 }
 
 async fn test_tracing_query_iter(session: &Session, ks: String) {
@@ -1085,7 +1772,7 @@ async fn assert_in_tracing_table(session: &Session, tracing_uuid: Uuid) {
 
     for _ in 0..8 {
         let row_opt = session
-            .query(traces_query.clone(), (tracing_uuid,))
+            .query_unpaged(traces_query.clone(), (tracing_uuid,))
             .await
             .unwrap()
             .rows
@@ -1122,9 +1809,9 @@ async fn test_timestamp() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session
-        .query(
+        .query_unpaged(
             format!(
                 "CREATE TABLE IF NOT EXISTS {}.t_timestamp (a text, b text, primary key (a))",
                 ks
@@ -1144,13 +1831,13 @@ async fn test_timestamp() {
 
     regular_query.set_timestamp(Some(420));
     session
-        .query(regular_query.clone(), ("regular query", "higher timestamp"))
+        .query_unpaged(regular_query.clone(), ("regular query", "higher timestamp"))
         .await
         .unwrap();
 
     regular_query.set_timestamp(Some(42));
     session
-        .query(regular_query.clone(), ("regular query", "lower timestamp"))
+        .query_unpaged(regular_query.clone(), ("regular query", "lower timestamp"))
         .await
         .unwrap();
 
@@ -1160,13 +1847,13 @@ async fn test_timestamp() {
 
     prepared_statement.set_timestamp(Some(420));
     session
-        .execute(&prepared_statement, ("prepared query", "higher timestamp"))
+        .execute_unpaged(&prepared_statement, ("prepared query", "higher timestamp"))
         .await
         .unwrap();
 
     prepared_statement.set_timestamp(Some(42));
     session
-        .execute(&prepared_statement, ("prepared query", "lower timestamp"))
+        .execute_unpaged(&prepared_statement, ("prepared query", "lower timestamp"))
         .await
         .unwrap();
 
@@ -1201,7 +1888,7 @@ async fn test_timestamp() {
         .unwrap();
 
     let mut results = session
-        .query(
+        .query_unpaged(
             format!("SELECT a, b, WRITETIME(b) FROM {}.t_timestamp", ks),
             &[],
         )
@@ -1242,7 +1929,7 @@ async fn test_request_timeout() {
 
         let mut query: Query = Query::new("SELECT * FROM system_schema.tables");
         query.set_request_timeout(Some(Duration::from_millis(1)));
-        match session.query(query, &[]).await {
+        match session.query_unpaged(query, &[]).await {
             Ok(_) => panic!("the query should have failed due to a client-side timeout"),
             Err(e) => assert_matches!(e, QueryError::RequestTimeout(_)),
         }
@@ -1253,7 +1940,7 @@ async fn test_request_timeout() {
             .unwrap();
 
         prepared.set_request_timeout(Some(Duration::from_millis(1)));
-        match session.execute(&prepared, &[]).await {
+        match session.execute_unpaged(&prepared, &[]).await {
             Ok(_) => panic!("the prepared query should have failed due to a client-side timeout"),
             Err(e) => assert_matches!(e, QueryError::RequestTimeout(_)),
         };
@@ -1267,14 +1954,14 @@ async fn test_request_timeout() {
 
         let mut query = Query::new("SELECT * FROM system_schema.tables");
 
-        match timeouting_session.query(query.clone(), &[]).await {
+        match timeouting_session.query_unpaged(query.clone(), &[]).await {
             Ok(_) => panic!("the query should have failed due to a client-side timeout"),
             Err(e) => assert_matches!(e, QueryError::RequestTimeout(_)),
         };
 
         query.set_request_timeout(Some(Duration::from_secs(10000)));
 
-        timeouting_session.query(query, &[]).await.expect(
+        timeouting_session.query_unpaged(query, &[]).await.expect(
             "the query should have not failed, because no client-side timeout was specified",
         );
 
@@ -1283,14 +1970,14 @@ async fn test_request_timeout() {
             .await
             .unwrap();
 
-        match timeouting_session.execute(&prepared, &[]).await {
+        match timeouting_session.execute_unpaged(&prepared, &[]).await {
             Ok(_) => panic!("the prepared query should have failed due to a client-side timeout"),
             Err(e) => assert_matches!(e, QueryError::RequestTimeout(_)),
         };
 
         prepared.set_request_timeout(Some(Duration::from_secs(10000)));
 
-        timeouting_session.execute(&prepared, &[]).await.expect("the prepared query should have not failed, because no client-side timeout was specified");
+        timeouting_session.execute_unpaged(&prepared, &[]).await.expect("the prepared query should have not failed, because no client-side timeout was specified");
     }
 }
 
@@ -1386,14 +2073,17 @@ async fn test_schema_types_in_metadata() {
     let ks = unique_keyspace_name();
 
     session
-        .query(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+        .query_unpaged(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
         .await
         .unwrap();
 
-    session.query(format!("USE {}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(format!("USE {}", ks), &[])
+        .await
+        .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TYPE IF NOT EXISTS type_a (
                     a map<frozen<list<int>>, text>,
                     b frozen<map<frozen<list<int>>, frozen<set<text>>>>
@@ -1404,12 +2094,12 @@ async fn test_schema_types_in_metadata() {
         .unwrap();
 
     session
-        .query("CREATE TYPE IF NOT EXISTS type_b (a int, b text)", &[])
+        .query_unpaged("CREATE TYPE IF NOT EXISTS type_b (a int, b text)", &[])
         .await
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TYPE IF NOT EXISTS type_c (a map<frozen<set<text>>, frozen<type_b>>)",
             &[],
         )
@@ -1417,7 +2107,7 @@ async fn test_schema_types_in_metadata() {
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS table_a (
                     a frozen<type_a> PRIMARY KEY,
                     b type_b,
@@ -1431,7 +2121,7 @@ async fn test_schema_types_in_metadata() {
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS table_b (
                         a text PRIMARY KEY,
                         b frozen<map<int, int>>
@@ -1541,14 +2231,17 @@ async fn test_user_defined_types_in_metadata() {
     let ks = unique_keyspace_name();
 
     session
-        .query(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+        .query_unpaged(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
         .await
         .unwrap();
 
-    session.query(format!("USE {}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(format!("USE {}", ks), &[])
+        .await
+        .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TYPE IF NOT EXISTS type_a (
                     a map<frozen<list<int>>, text>,
                     b frozen<map<frozen<list<int>>, frozen<set<text>>>>
@@ -1559,12 +2252,12 @@ async fn test_user_defined_types_in_metadata() {
         .unwrap();
 
     session
-        .query("CREATE TYPE IF NOT EXISTS type_b (a int, b text)", &[])
+        .query_unpaged("CREATE TYPE IF NOT EXISTS type_b (a int, b text)", &[])
         .await
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TYPE IF NOT EXISTS type_c (a map<frozen<set<text>>, frozen<type_b>>)",
             &[],
         )
@@ -1601,14 +2294,17 @@ async fn test_column_kinds_in_metadata() {
     let ks = unique_keyspace_name();
 
     session
-        .query(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+        .query_unpaged(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
         .await
         .unwrap();
 
-    session.query(format!("USE {}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(format!("USE {}", ks), &[])
+        .await
+        .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS t (
                     a int,
                     b int,
@@ -1643,14 +2339,17 @@ async fn test_primary_key_ordering_in_metadata() {
     let ks = unique_keyspace_name();
 
     session
-        .query(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+        .query_unpaged(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
         .await
         .unwrap();
 
-    session.query(format!("USE {}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(format!("USE {}", ks), &[])
+        .await
+        .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS t (
                     a int,
                     b int,
@@ -1688,14 +2387,17 @@ async fn test_table_partitioner_in_metadata() {
     let ks = unique_keyspace_name();
 
     session
-        .query(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+        .query_unpaged(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
         .await
         .unwrap();
 
-    session.query(format!("USE {}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(format!("USE {}", ks), &[])
+        .await
+        .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE t (pk int, ck int, v int, PRIMARY KEY (pk, ck, v))WITH cdc = {'enabled':true}",
             &[],
         )
@@ -1727,14 +2429,17 @@ async fn test_turning_off_schema_fetching() {
     let ks = unique_keyspace_name();
 
     session
-        .query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+        .query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
         .await
         .unwrap();
 
-    session.query(format!("USE {}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(format!("USE {}", ks), &[])
+        .await
+        .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TYPE IF NOT EXISTS type_a (
                     a map<frozen<list<int>>, text>,
                     b frozen<map<frozen<list<int>>, frozen<set<text>>>>
@@ -1745,12 +2450,12 @@ async fn test_turning_off_schema_fetching() {
         .unwrap();
 
     session
-        .query("CREATE TYPE IF NOT EXISTS type_b (a int, b text)", &[])
+        .query_unpaged("CREATE TYPE IF NOT EXISTS type_b (a int, b text)", &[])
         .await
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TYPE IF NOT EXISTS type_c (a map<frozen<set<text>>, frozen<type_b>>)",
             &[],
         )
@@ -1758,7 +2463,7 @@ async fn test_turning_off_schema_fetching() {
         .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS table_a (
                     a frozen<type_a> PRIMARY KEY,
                     b type_b,
@@ -1797,14 +2502,17 @@ async fn test_named_bind_markers() {
     let ks = unique_keyspace_name();
 
     session
-        .query(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+        .query_unpaged(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
         .await
         .unwrap();
 
-    session.query(format!("USE {}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(format!("USE {}", ks), &[])
+        .await
+        .unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE t (pk int, ck int, v int, PRIMARY KEY (pk, ck, v))",
             &[],
         )
@@ -1818,13 +2526,13 @@ async fn test_named_bind_markers() {
         .await
         .unwrap();
     let hashmap: HashMap<&str, i32> = HashMap::from([("pk", 7), ("v", 42), ("ck", 13)]);
-    session.execute(&prepared, &hashmap).await.unwrap();
+    session.execute_unpaged(&prepared, &hashmap).await.unwrap();
 
     let btreemap: BTreeMap<&str, i32> = BTreeMap::from([("ck", 113), ("v", 142), ("pk", 17)]);
-    session.execute(&prepared, &btreemap).await.unwrap();
+    session.execute_unpaged(&prepared, &btreemap).await.unwrap();
 
     let rows: Vec<(i32, i32, i32)> = session
-        .query("SELECT pk, ck, v FROM t", &[])
+        .query_unpaged("SELECT pk, ck, v FROM t", &[])
         .await
         .unwrap()
         .rows
@@ -1842,7 +2550,7 @@ async fn test_named_bind_markers() {
         HashMap::from([("ck", 9)]),
     ];
     for wrongmap in wrongmaps {
-        assert!(session.execute(&prepared, &wrongmap).await.is_err());
+        assert!(session.execute_unpaged(&prepared, &wrongmap).await.is_err());
     }
 }
 
@@ -1851,11 +2559,11 @@ async fn test_prepared_partitioner() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session.use_keyspace(ks, false).await.unwrap();
 
     session
-        .query("CREATE TABLE IF NOT EXISTS t1 (a int primary key)", &[])
+        .query_unpaged("CREATE TABLE IF NOT EXISTS t1 (a int primary key)", &[])
         .await
         .unwrap();
 
@@ -1877,7 +2585,7 @@ async fn test_prepared_partitioner() {
     }
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS t2 (a int primary key) WITH cdc = {'enabled':true}",
             &[],
         )
@@ -1900,14 +2608,14 @@ async fn test_prepared_partitioner() {
 
 async fn rename(session: &Session, rename_str: &str) {
     session
-        .query(format!("ALTER TABLE tab RENAME {}", rename_str), ())
+        .query_unpaged(format!("ALTER TABLE tab RENAME {}", rename_str), ())
         .await
         .unwrap();
 }
 
 async fn rename_caching(session: &CachingSession, rename_str: &str) {
     session
-        .execute(format!("ALTER TABLE tab RENAME {}", rename_str), &())
+        .execute_unpaged(format!("ALTER TABLE tab RENAME {}", rename_str), &())
         .await
         .unwrap();
 }
@@ -1926,11 +2634,11 @@ async fn test_unprepared_reprepare_in_execute() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session.use_keyspace(ks, false).await.unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS tab (a int, b int, c int, primary key (a, b, c))",
             &[],
         )
@@ -1942,22 +2650,34 @@ async fn test_unprepared_reprepare_in_execute() {
         .await
         .unwrap();
 
-    session.execute(&insert_a_b_c, (1, 2, 3)).await.unwrap();
+    session
+        .execute_unpaged(&insert_a_b_c, (1, 2, 3))
+        .await
+        .unwrap();
 
     // Swap names of columns b and c
     rename(&session, "b TO tmp_name").await;
 
     // During rename the query should fail
-    assert!(session.execute(&insert_a_b_c, (1, 2, 3)).await.is_err());
+    assert!(session
+        .execute_unpaged(&insert_a_b_c, (1, 2, 3))
+        .await
+        .is_err());
     rename(&session, "c TO b").await;
-    assert!(session.execute(&insert_a_b_c, (1, 2, 3)).await.is_err());
+    assert!(session
+        .execute_unpaged(&insert_a_b_c, (1, 2, 3))
+        .await
+        .is_err());
     rename(&session, "tmp_name TO c").await;
 
     // Insert values again (b and c are swapped so those are different inserts)
-    session.execute(&insert_a_b_c, (1, 2, 3)).await.unwrap();
+    session
+        .execute_unpaged(&insert_a_b_c, (1, 2, 3))
+        .await
+        .unwrap();
 
     let mut all_rows: Vec<(i32, i32, i32)> = session
-        .query("SELECT a, b, c FROM tab", ())
+        .query_unpaged("SELECT a, b, c FROM tab", ())
         .await
         .unwrap()
         .rows_typed::<(i32, i32, i32)>()
@@ -1968,6 +2688,229 @@ async fn test_unprepared_reprepare_in_execute() {
     assert_eq!(all_rows, vec![(1, 2, 3), (1, 3, 2)]);
 }
 
+// Checks that prepared statements keep working after the table they operate on is altered,
+// and that their results reflect the new schema.
+#[tokio::test]
+async fn test_prepared_statement_after_schema_change() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks, false).await.unwrap();
+    session
+        .query_unpaged("CREATE TABLE tab (a int primary key, b int)", &[])
+        .await
+        .unwrap();
+    session
+        .query_unpaged("INSERT INTO tab (a, b) VALUES (1, 2)", &[])
+        .await
+        .unwrap();
+
+    let select = session
+        .prepare("SELECT * FROM tab WHERE a = ?")
+        .await
+        .unwrap();
+    let result = session.execute_unpaged(&select, (1,)).await.unwrap();
+    assert_eq!(result.col_specs.len(), 2);
+
+    session
+        .query_unpaged("ALTER TABLE tab ADD c int", &[])
+        .await
+        .unwrap();
+
+    let result = session.execute_unpaged(&select, (1,)).await.unwrap();
+    let columns: Vec<&str> = result
+        .col_specs
+        .iter()
+        .map(|spec| spec.name.as_str())
+        .collect();
+    assert_eq!(columns, vec!["a", "b", "c"]);
+    assert_eq!(
+        result
+            .single_row_typed::<(i32, i32, Option<i32>)>()
+            .unwrap(),
+        (1, 2, None)
+    );
+}
+
+#[tokio::test]
+async fn test_prepared_statement_after_table_recreation() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks, false).await.unwrap();
+    session
+        .query_unpaged("CREATE TABLE tab (a int primary key, b int)", &[])
+        .await
+        .unwrap();
+
+    let insert = session
+        .prepare("INSERT INTO tab (a, b) VALUES (?, ?)")
+        .await
+        .unwrap();
+    let select = session.prepare("SELECT * FROM tab").await.unwrap();
+    session.execute_unpaged(&insert, (1, 2)).await.unwrap();
+    let result = session.execute_unpaged(&select, &[]).await.unwrap();
+    assert_eq!(result.col_specs.len(), 2);
+
+    // The table is dropped and created again with another column
+    session.query_unpaged("DROP TABLE tab", &[]).await.unwrap();
+    session
+        .query_unpaged("CREATE TABLE tab (a int primary key, b int, c text)", &[])
+        .await
+        .unwrap();
+    session.await_schema_agreement().await.unwrap();
+
+    // The drop is announced asynchronously
+    for _ in 0..50 {
+        if insert.is_stale() && select.is_stale() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(insert.is_stale() && select.is_stale());
+
+    // The statements are re-prepared before being executed
+    session.execute_unpaged(&insert, (3, 4)).await.unwrap();
+    assert!(!insert.is_stale());
+    let result = session.execute_unpaged(&select, &[]).await.unwrap();
+    assert!(!select.is_stale());
+    assert_eq!(result.col_specs.len(), 3);
+    assert_eq!(
+        result
+            .single_row_typed::<(i32, i32, Option<String>)>()
+            .unwrap(),
+        (3, 4, None)
+    );
+    assert_eq!(select.get_current_result_metadata().col_specs.len(), 3);
+}
+
+#[tokio::test]
+async fn test_bound_statement() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks, false).await.unwrap();
+    session
+        .query_unpaged("CREATE TABLE tab (a int primary key, b int)", &[])
+        .await
+        .unwrap();
+
+    let insert = session
+        .prepare("INSERT INTO tab (a, b) VALUES (?, ?)")
+        .await
+        .unwrap();
+    assert_matches!(
+        insert.bind(&(1, "wrong type")),
+        Err(PartitionKeyError::Serialization(_))
+    );
+    let bound = insert.bind(&(1, 2)).unwrap();
+    assert_eq!(
+        bound.get_routing_key(),
+        Some(&insert.compute_partition_key(&(1, 2)).unwrap())
+    );
+    assert_eq!(bound.get_token(), insert.calculate_token(&(1, 2)).unwrap());
+    for _ in 0..3 {
+        session.execute_bound(&bound).await.unwrap();
+    }
+    let rows = session
+        .query_unpaged("SELECT a, b FROM tab", &[])
+        .await
+        .unwrap()
+        .rows_typed::<(i32, i32)>()
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![(1, 2)]);
+
+    // Once the types of the bind markers change, the bound values can't be sent anymore
+    session.query_unpaged("DROP TABLE tab", &[]).await.unwrap();
+    session
+        .query_unpaged("CREATE TABLE tab (a int primary key, b text)", &[])
+        .await
+        .unwrap();
+    let error = session.execute_bound(&bound).await.unwrap_err();
+    assert_matches!(
+        error,
+        QueryError::BadQuery(BadQuery::PreparedMetadataChanged)
+    );
+    assert!(bound.is_outdated());
+    assert!(insert.has_outdated_metadata());
+    let error = session.execute_unpaged(&insert, (1, 2)).await.unwrap_err();
+    assert_matches!(
+        error,
+        QueryError::BadQuery(BadQuery::PreparedMetadataChanged)
+    );
+
+    // Prepared again, the statement can be bound and executed
+    let insert = session
+        .prepare("INSERT INTO tab (a, b) VALUES (?, ?)")
+        .await
+        .unwrap();
+    let bound = insert.bind(&(1, "text")).unwrap();
+    session.execute_bound(&bound).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_prepared_statement_metadata_accessors() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(&ks, false).await.unwrap();
+    session
+        .query_unpaged(
+            "CREATE TABLE tab (a int, b int, c text, primary key ((b, a)))",
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let select = session
+        .prepare("SELECT c, a FROM tab WHERE a = ? AND b = ?")
+        .await
+        .unwrap();
+    assert_eq!(select.get_keyspace_name(), Some(ks.as_str()));
+    assert_eq!(select.get_table_name(), Some("tab"));
+
+    let variables: Vec<(&str, ColumnType)> = select
+        .get_variable_col_specs()
+        .iter()
+        .map(|spec| (spec.name.as_str(), spec.typ.clone()))
+        .collect();
+    assert_eq!(
+        variables,
+        vec![("a", ColumnType::Int), ("b", ColumnType::Int)]
+    );
+
+    let result_columns: Vec<(&str, ColumnType)> = select
+        .get_result_col_specs()
+        .iter()
+        .map(|spec| (spec.name.as_str(), spec.typ.clone()))
+        .collect();
+    assert_eq!(
+        result_columns,
+        vec![("c", ColumnType::Text), ("a", ColumnType::Int)]
+    );
+
+    let pk_indexes: Vec<(u16, u16)> = select
+        .get_partition_key_indexes()
+        .iter()
+        .map(|pk_index| (pk_index.index, pk_index.sequence))
+        .collect();
+    assert_eq!(pk_indexes, vec![(0, 1), (1, 0)]);
+
+    select.check_values(&(1, 2)).unwrap();
+    select.check_values(&(1, "2")).unwrap_err();
+
+    // Without bind markers the table is only known from the result metadata.
+    let select_all = session.prepare("SELECT * FROM tab").await.unwrap();
+    assert!(select_all.get_variable_col_specs().is_empty());
+    assert_eq!(select_all.get_table_name(), Some("tab"));
+}
+
 #[tokio::test]
 async fn test_unusual_valuelists() {
     let _ = tracing_subscriber::fmt::try_init();
@@ -1975,11 +2918,11 @@ async fn test_unusual_valuelists() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session.use_keyspace(ks, false).await.unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS tab (a int, b int, c varchar, primary key (a, b, c))",
             &[],
         )
@@ -1996,7 +2939,10 @@ async fn test_unusual_valuelists() {
         &2 as &dyn SerializeCql,
         &"&dyn" as &dyn SerializeCql,
     ];
-    session.execute(&insert_a_b_c, values_dyn).await.unwrap();
+    session
+        .execute_unpaged(&insert_a_b_c, values_dyn)
+        .await
+        .unwrap();
 
     let values_box_dyn: Vec<Box<dyn SerializeCql>> = vec![
         Box::new(1) as Box<dyn SerializeCql>,
@@ -2004,12 +2950,12 @@ async fn test_unusual_valuelists() {
         Box::new("Box dyn") as Box<dyn SerializeCql>,
     ];
     session
-        .execute(&insert_a_b_c, values_box_dyn)
+        .execute_unpaged(&insert_a_b_c, values_box_dyn)
         .await
         .unwrap();
 
     let mut all_rows: Vec<(i32, i32, String)> = session
-        .query("SELECT a, b, c FROM tab", ())
+        .query_unpaged("SELECT a, b, c FROM tab", ())
         .await
         .unwrap()
         .rows_typed::<(i32, i32, String)>()
@@ -2033,6 +2979,71 @@ async fn test_unusual_valuelists() {
 // RUST_LOG=debug cargo test test_unprepared_reprepare_in_batch -- --nocapture
 // And look for this line in the logs:
 // Connection::batch: got DbError::Unprepared - repreparing statement with id ...
+#[tokio::test]
+async fn test_prepared_statements_share_udt_definitions() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(&ks, false).await.unwrap();
+    session
+        .query_unpaged("CREATE TYPE IF NOT EXISTS shared_udt (a int, b text)", &[])
+        .await
+        .unwrap();
+    session
+        .query_unpaged(
+            "CREATE TABLE tab (pk int primary key, u frozen<shared_udt>, l list<frozen<shared_udt>>)",
+            &[],
+        )
+        .await
+        .unwrap();
+
+    fn udt_fields(typ: &ColumnType) -> &Arc<[(String, ColumnType)]> {
+        match typ {
+            ColumnType::UserDefinedType { field_types, .. } => field_types,
+            ColumnType::List(elem) => udt_fields(elem),
+            _ => panic!("Not a user defined type: {:?}", typ),
+        }
+    }
+
+    let insert = session
+        .prepare("INSERT INTO tab (pk, u, l) VALUES (?, ?, ?)")
+        .await
+        .unwrap();
+    let select = session
+        .prepare("SELECT u, l FROM tab WHERE pk = ?")
+        .await
+        .unwrap();
+    let insert_specs = insert.get_variable_col_specs();
+    let select_specs = select.get_result_col_specs();
+    let fields = udt_fields(&insert_specs[1].typ);
+    assert!(Arc::ptr_eq(fields, udt_fields(&insert_specs[2].typ)));
+    assert!(Arc::ptr_eq(fields, udt_fields(&select_specs[0].typ)));
+    assert!(Arc::ptr_eq(fields, udt_fields(&select_specs[1].typ)));
+
+    // The cluster metadata shares the definition too
+    session.refresh_metadata().await.unwrap();
+    let cluster_data = session.get_cluster_data();
+    let metadata_type = cluster_data.get_keyspace_info()[&ks].user_defined_types["shared_udt"]
+        .to_column_type()
+        .unwrap();
+    assert!(Arc::ptr_eq(fields, udt_fields(&metadata_type)));
+    assert!(session.get_metrics().get_type_metadata_memory_usage() > 0);
+
+    // An altered definition isn't shared with the old one
+    session
+        .query_unpaged("ALTER TYPE shared_udt ADD c bigint", &[])
+        .await
+        .unwrap();
+    let altered = session
+        .prepare("INSERT INTO tab (pk, u) VALUES (?, ?)")
+        .await
+        .unwrap();
+    let altered_fields = udt_fields(&altered.get_variable_col_specs()[1].typ);
+    assert!(!Arc::ptr_eq(fields, altered_fields));
+    assert_eq!(altered_fields.len(), 3);
+}
+
 #[tokio::test]
 async fn test_unprepared_reprepare_in_batch() {
     let _ = tracing_subscriber::fmt::try_init();
@@ -2040,11 +3051,11 @@ async fn test_unprepared_reprepare_in_batch() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session.use_keyspace(ks, false).await.unwrap();
 
     session
-        .query(
+        .query_unpaged(
             "CREATE TABLE IF NOT EXISTS tab (a int, b int, c int, primary key (a, b, c))",
             &[],
         )
@@ -2080,7 +3091,7 @@ async fn test_unprepared_reprepare_in_batch() {
     session.batch(&batch, ((1, 2, 3), (4, 5))).await.unwrap();
 
     let mut all_rows: Vec<(i32, i32, i32)> = session
-        .query("SELECT a, b, c FROM tab", ())
+        .query_unpaged("SELECT a, b, c FROM tab", ())
         .await
         .unwrap()
         .rows_typed::<(i32, i32, i32)>()
@@ -2105,13 +3116,13 @@ async fn test_unprepared_reprepare_in_caching_session_execute() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session.use_keyspace(ks, false).await.unwrap();
 
     let caching_session: CachingSession = CachingSession::from(session, 64);
 
     caching_session
-        .execute(
+        .execute_unpaged(
             "CREATE TABLE IF NOT EXISTS tab (a int, b int, c int, primary key (a, b, c))",
             &[],
         )
@@ -2121,7 +3132,7 @@ async fn test_unprepared_reprepare_in_caching_session_execute() {
     let insert_a_b_c = "INSERT INTO tab (a, b, c) VALUES (?, ?, ?)";
 
     caching_session
-        .execute(insert_a_b_c, &(1, 2, 3))
+        .execute_unpaged(insert_a_b_c, &(1, 2, 3))
         .await
         .unwrap();
 
@@ -2130,24 +3141,24 @@ async fn test_unprepared_reprepare_in_caching_session_execute() {
 
     // During rename the query should fail
     assert!(caching_session
-        .execute(insert_a_b_c, &(1, 2, 3))
+        .execute_unpaged(insert_a_b_c, &(1, 2, 3))
         .await
         .is_err());
     rename_caching(&caching_session, "c TO b").await;
     assert!(caching_session
-        .execute(insert_a_b_c, &(1, 2, 3))
+        .execute_unpaged(insert_a_b_c, &(1, 2, 3))
         .await
         .is_err());
     rename_caching(&caching_session, "tmp_name TO c").await;
 
     // Insert values again (b and c are swapped so those are different inserts)
     caching_session
-        .execute(insert_a_b_c, &(1, 2, 3))
+        .execute_unpaged(insert_a_b_c, &(1, 2, 3))
         .await
         .unwrap();
 
     let mut all_rows: Vec<(i32, i32, i32)> = caching_session
-        .execute("SELECT a, b, c FROM tab", &())
+        .execute_unpaged("SELECT a, b, c FROM tab", &())
         .await
         .unwrap()
         .rows_typed::<(i32, i32, i32)>()
@@ -2159,714 +3170,2353 @@ async fn test_unprepared_reprepare_in_caching_session_execute() {
 }
 
 #[tokio::test]
-async fn test_views_in_schema_info() {
-    let _ = tracing_subscriber::fmt::try_init();
-
+async fn test_token_ring() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
-    session.use_keyspace(ks.clone(), false).await.unwrap();
-
-    session
-        .query("CREATE TABLE t(id int PRIMARY KEY, v int)", &[])
-        .await
-        .unwrap();
-
-    session.query("CREATE MATERIALIZED VIEW mv1 AS SELECT * FROM t WHERE v IS NOT NULL PRIMARY KEY (v, id)", &[]).await.unwrap();
-    session.query("CREATE MATERIALIZED VIEW mv2 AS SELECT id, v FROM t WHERE v IS NOT NULL PRIMARY KEY (v, id)", &[]).await.unwrap();
-
-    session.await_schema_agreement().await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session.refresh_metadata().await.unwrap();
 
-    let keyspace_meta = session
-        .get_cluster_data()
-        .get_keyspace_info()
-        .get(&ks)
-        .unwrap()
-        .clone();
-
-    let tables = keyspace_meta
-        .tables
-        .keys()
-        .collect::<std::collections::HashSet<&String>>();
-
-    let views = keyspace_meta
-        .views
-        .keys()
-        .collect::<std::collections::HashSet<&String>>();
-    let views_base_table = keyspace_meta
-        .views
-        .values()
-        .map(|view_meta| &view_meta.base_table_name)
-        .collect::<std::collections::HashSet<&String>>();
-
-    assert_eq!(tables, std::collections::HashSet::from([&"t".to_string()]));
-    assert_eq!(
-        views,
-        std::collections::HashSet::from([&"mv1".to_string(), &"mv2".to_string()])
-    );
-    assert_eq!(
-        views_base_table,
-        std::collections::HashSet::from([&"t".to_string()])
-    )
-}
+    let cluster_data = session.get_cluster_data();
+    let token_ring = cluster_data.token_ring(&ks);
+    assert!(!token_ring.is_empty());
+
+    // The ranges cover the whole ring, starting with the wrapping one.
+    assert!(token_ring[0].0.is_wrap_around());
+    assert_eq!(token_ring[0].0.start(), token_ring.last().unwrap().0.end());
+    for pair in token_ring.windows(2) {
+        assert_eq!(pair[0].0.end(), pair[1].0.start());
+    }
 
-async fn assert_test_batch_table_rows_contain(sess: &Session, expected_rows: &[(i32, i32)]) {
-    let selected_rows: BTreeSet<(i32, i32)> = sess
-        .query("SELECT a, b FROM test_batch_table", ())
-        .await
-        .unwrap()
-        .rows_typed::<(i32, i32)>()
-        .unwrap()
-        .map(|r| r.unwrap())
-        .collect();
-    for expected_row in expected_rows.iter() {
-        if !selected_rows.contains(expected_row) {
-            panic!(
-                "Expected {:?} to contain row: {:?}, but they didn't",
-                selected_rows, expected_row
-            );
-        }
+    for (range, replicas) in &token_ring {
+        assert_eq!(replicas.len(), 1);
+        assert_eq!(
+            *replicas,
+            cluster_data.get_token_endpoints(&ks, range.end())
+        );
     }
 }
 
 #[tokio::test]
-async fn test_prepare_batch() {
-    let session = create_new_session_builder().build().await.unwrap();
-
+async fn test_buffer_pool() {
+    let session = create_new_session_builder()
+        .buffer_pool_max_retained_bytes(1024 * 1024)
+        .build()
+        .await
+        .unwrap();
     let ks = unique_keyspace_name();
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
-    session.use_keyspace(ks.clone(), false).await.unwrap();
 
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session
-        .query(
-            "CREATE TABLE test_batch_table (a int, b int, primary key (a, b))",
-            (),
+        .query_unpaged(
+            format!("CREATE TABLE {}.t (a int PRIMARY KEY, b text)", ks),
+            &[],
         )
         .await
         .unwrap();
 
-    let unprepared_insert_a_b: &str = "insert into test_batch_table (a, b) values (?, ?)";
-    let unprepared_insert_a_7: &str = "insert into test_batch_table (a, b) values (?, 7)";
-    let unprepared_insert_8_b: &str = "insert into test_batch_table (a, b) values (8, ?)";
-    let prepared_insert_a_b: PreparedStatement =
-        session.prepare(unprepared_insert_a_b).await.unwrap();
-    let prepared_insert_a_7: PreparedStatement =
-        session.prepare(unprepared_insert_a_7).await.unwrap();
-    let prepared_insert_8_b: PreparedStatement =
-        session.prepare(unprepared_insert_8_b).await.unwrap();
-
-    let assert_batch_prepared = |b: &Batch| {
-        for stmt in &b.statements {
-            match stmt {
-                BatchStatement::PreparedStatement(_) => {}
-                _ => panic!("Unprepared statement in prepared batch!"),
-            }
-        }
-    };
-
-    {
-        let mut unprepared_batch: Batch = Default::default();
-        unprepared_batch.append_statement(unprepared_insert_a_b);
-        unprepared_batch.append_statement(unprepared_insert_a_7);
-        unprepared_batch.append_statement(unprepared_insert_8_b);
-
-        let prepared_batch: Batch = session.prepare_batch(&unprepared_batch).await.unwrap();
-        assert_batch_prepared(&prepared_batch);
-
+    let insert = session
+        .prepare(format!("INSERT INTO {}.t (a, b) VALUES (?, ?)", ks))
+        .await
+        .unwrap();
+    for i in 0..100 {
         session
-            .batch(&prepared_batch, ((10, 20), (10,), (20,)))
+            .execute_unpaged(&insert, (i, "value"))
             .await
             .unwrap();
-        assert_test_batch_table_rows_contain(&session, &[(10, 20), (10, 7), (8, 20)]).await;
     }
 
-    {
-        let mut partially_prepared_batch: Batch = Default::default();
-        partially_prepared_batch.append_statement(unprepared_insert_a_b);
-        partially_prepared_batch.append_statement(prepared_insert_a_7.clone());
-        partially_prepared_batch.append_statement(unprepared_insert_8_b);
+    let rows = session
+        .query_unpaged(format!("SELECT a, b FROM {}.t", ks), &[])
+        .await
+        .unwrap()
+        .rows_typed::<(i32, String)>()
+        .unwrap()
+        .count();
+    assert_eq!(rows, 100);
 
-        let prepared_batch: Batch = session
-            .prepare_batch(&partially_prepared_batch)
-            .await
-            .unwrap();
-        assert_batch_prepared(&prepared_batch);
+    let metrics = session.get_metrics();
+    assert!(metrics.get_buffer_pool_hits() > metrics.get_buffer_pool_misses());
+}
 
-        session
-            .batch(&prepared_batch, ((30, 40), (30,), (40,)))
-            .await
-            .unwrap();
-        assert_test_batch_table_rows_contain(&session, &[(30, 40), (30, 7), (8, 40)]).await;
+#[tokio::test]
+async fn test_statement_label_metrics() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(format!("CREATE TABLE {}.t (a int PRIMARY KEY)", ks), &[])
+        .await
+        .unwrap();
+
+    let mut insert = Query::new(format!("INSERT INTO {}.t (a) VALUES (?)", ks));
+    insert.set_label("insert_row");
+    // Prepared statements inherit the label of the query
+    let insert = session.prepare(insert).await.unwrap();
+    assert_eq!(insert.get_label(), Some("insert_row"));
+    for i in 0..10 {
+        session.execute_unpaged(&insert, (i,)).await.unwrap();
     }
 
-    {
-        let mut fully_prepared_batch: Batch = Default::default();
-        fully_prepared_batch.append_statement(prepared_insert_a_b);
-        fully_prepared_batch.append_statement(prepared_insert_a_7);
-        fully_prepared_batch.append_statement(prepared_insert_8_b);
+    let mut select = Query::new(format!("SELECT a FROM {}.t", ks));
+    select.set_label("select_all");
+    select.set_page_size(3);
+    let rows = session
+        .query_iter(select, &[])
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 10);
 
-        let prepared_batch: Batch = session.prepare_batch(&fully_prepared_batch).await.unwrap();
-        assert_batch_prepared(&prepared_batch);
+    let mut missing = Query::new(format!("SELECT a FROM {}.missing", ks));
+    missing.set_label("select_missing");
+    session.query_unpaged(missing, &[]).await.unwrap_err();
 
-        session
-            .batch(&prepared_batch, ((50, 60), (50,), (60,)))
-            .await
-            .unwrap();
+    let metrics = session.get_metrics();
+    let inserts = metrics.statement_label_snapshot("insert_row").unwrap();
+    assert_eq!(
+        (inserts.requests, inserts.errors, inserts.pages),
+        (10, 0, 0)
+    );
+    assert_eq!(inserts.latency.unwrap().count, 10);
 
-        assert_test_batch_table_rows_contain(&session, &[(50, 60), (50, 7), (8, 60)]).await;
-    }
+    let selects = metrics.statement_label_snapshot("select_all").unwrap();
+    assert!(selects.pages >= 4);
+    assert_eq!((selects.requests, selects.errors), (selects.pages, 0));
 
-    {
-        let mut bad_batch: Batch = Default::default();
-        bad_batch.append_statement(unprepared_insert_a_b);
-        bad_batch.append_statement("This isnt even CQL");
-        bad_batch.append_statement(unprepared_insert_8_b);
+    let missing = metrics.statement_label_snapshot("select_missing").unwrap();
+    assert_eq!((missing.requests, missing.errors), (1, 1));
+    assert_eq!(missing.latency, None);
 
-        assert!(session.prepare_batch(&bad_batch).await.is_err());
-    }
+    // Unlabelled statements are not broken down
+    assert_eq!(metrics.statement_labels_snapshot().len(), 3);
 }
 
 #[tokio::test]
-async fn test_refresh_metadata_after_schema_agreement() {
-    let session = create_new_session_builder().build().await.unwrap();
-
+async fn test_zero_copy_blobs() {
+    let session = create_new_session_builder()
+        .zero_copy_blobs(true)
+        .build()
+        .await
+        .unwrap();
     let ks = unique_keyspace_name();
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
-    session.use_keyspace(ks.clone(), false).await.unwrap();
 
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session
-        .query(
-            "CREATE TYPE udt (field1 int, field2 uuid, field3 text)",
+        .query_unpaged(
+            format!("CREATE TABLE {}.t (a int PRIMARY KEY, b blob)", ks),
             &[],
         )
         .await
         .unwrap();
 
-    let cluster_data = session.get_cluster_data();
-    let metadata = cluster_data.get_keyspace_info();
-    let keyspace_metadata = metadata.get(ks.as_str());
-    assert_ne!(keyspace_metadata, None);
+    let blob = vec![0xAB_u8; 64 * 1024];
+    session
+        .query_unpaged(
+            format!("INSERT INTO {}.t (a, b) VALUES (?, ?)", ks),
+            (1, &blob),
+        )
+        .await
+        .unwrap();
 
-    let udt = keyspace_metadata.unwrap().user_defined_types.get("udt");
-    assert_ne!(udt, None);
+    let mut rows = session
+        .query_unpaged(format!("SELECT b FROM {}.t WHERE a = 1", ks), &[])
+        .await
+        .unwrap()
+        .rows
+        .unwrap();
+    match rows.pop().unwrap().columns.pop().unwrap() {
+        Some(CqlValue::SharedBlob(shared)) => assert_eq!(shared, blob),
+        other => panic!("Expected a shared blob, got {:?}", other),
+    }
 
-    assert_eq!(
-        udt.unwrap(),
-        &Arc::new(UserDefinedType {
-            keyspace: ks,
-            name: "udt".to_string(),
-            field_types: Vec::from([
-                ("field1".to_string(), CqlType::Native(NativeType::Int)),
-                ("field2".to_string(), CqlType::Native(NativeType::Uuid)),
-                ("field3".to_string(), CqlType::Native(NativeType::Text))
-            ])
-        })
-    );
+    let (read,) = session
+        .query_unpaged(format!("SELECT b FROM {}.t WHERE a = 1", ks), &[])
+        .await
+        .unwrap()
+        .single_row_typed::<(Bytes,)>()
+        .unwrap();
+    assert_eq!(read, blob);
 }
 
 #[tokio::test]
-async fn test_rate_limit_exceeded_exception() {
+async fn test_skipped_result_metadata_of_wide_table() {
     let session = create_new_session_builder().build().await.unwrap();
-
-    // Typed errors in RPC were introduced along with per-partition rate limiting.
-    // There is no dedicated feature for per-partition rate limiting, so we are
-    // looking at the other one.
-    if !supports_feature(&session, "TYPED_ERRORS_IN_READ_RPC").await {
-        println!("Skipping because the cluster doesn't support per partition rate limiting");
-        return;
-    }
-
     let ks = unique_keyspace_name();
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
-    session.use_keyspace(ks.clone(), false).await.unwrap();
-    session.query("CREATE TABLE tbl (pk int PRIMARY KEY, v int) WITH per_partition_rate_limit = {'max_writes_per_second': 1}", ()).await.unwrap();
 
-    let stmt = session
-        .prepare("INSERT INTO tbl (pk, v) VALUES (?, ?)")
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    let columns = (0..50).map(|i| format!("c{}", i)).join(", ");
+    let column_defs = (0..50).map(|i| format!("c{} int", i)).join(", ");
+    session
+        .query_unpaged(
+            format!(
+                "CREATE TABLE {}.wide (a int PRIMARY KEY, {})",
+                ks, column_defs
+            ),
+            &[],
+        )
         .await
         .unwrap();
-
-    // The rate limit is 1 write/s, so repeat the same query
-    // until an error occurs, it should happen quickly
-
-    let mut maybe_err = None;
-
-    for _ in 0..1000 {
-        match session.execute(&stmt, (123, 456)).await {
-            Ok(_) => {} // Try again
-            Err(err) => {
-                maybe_err = Some(err);
-                break;
-            }
-        }
+    let insert = session
+        .prepare(format!(
+            "INSERT INTO {}.wide (a, {}) VALUES (?{})",
+            ks,
+            columns,
+            ", ?".repeat(50)
+        ))
+        .await
+        .unwrap();
+    for a in 0..20 {
+        let values: Vec<i32> = (0..51).map(|i| a * 100 + i).collect();
+        session.execute_unpaged(&insert, values).await.unwrap();
     }
 
-    use scylla_cql::errors::OperationType;
-
-    match maybe_err.expect("Rate limit error didn't occur") {
-        QueryError::DbError(DbError::RateLimitReached { op_type, .. }, _) => {
-            assert_eq!(op_type, OperationType::Write);
+    let read_all = |session: Session| {
+        let ks = ks.clone();
+        async move {
+            let mut select = session
+                .prepare(format!("SELECT * FROM {}.wide", ks))
+                .await
+                .unwrap();
+            select.set_page_size(7);
+            let mut rows: Vec<Row> = session
+                .execute_iter(select, &[])
+                .await
+                .unwrap()
+                .try_collect()
+                .await
+                .unwrap();
+            rows.sort_by_key(|row| row.columns[0].as_ref().unwrap().as_int());
+            rows
         }
-        err => panic!("Unexpected error type received: {:?}", err),
-    }
+    };
+    let skipped = read_all(session).await;
+    let sent = read_all(
+        create_new_session_builder()
+            .skip_result_metadata(false)
+            .build()
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(skipped.len(), 20);
+    assert!(skipped.iter().all(|row| row.columns.len() == 51));
+    assert_eq!(skipped, sent);
 }
 
-// Batches containing LWT queries (IF col = som) return rows with information whether the queries were applied.
 #[tokio::test]
-async fn test_batch_lwts() {
+async fn test_skipped_result_metadata_after_schema_change() {
     let session = create_new_session_builder().build().await.unwrap();
-
     let ks = unique_keyspace_name();
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
-    session.use_keyspace(ks.clone(), false).await.unwrap();
 
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session
-        .query(
-            "CREATE TABLE tab (p1 int, c1 int, r1 int, r2 int, primary key (p1, c1))",
-            (),
-        )
-        .await
+        .query_unpaged(
+            format!(
+                "CREATE TABLE {}.t (a int, b int, c int, PRIMARY KEY (a, b))",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+    let insert = session
+        .prepare(format!("INSERT INTO {}.t (a, b, c) VALUES (0, ?, ?)", ks))
+        .await
+        .unwrap();
+    for b in 0..30 {
+        session.execute_unpaged(&insert, (b, b)).await.unwrap();
+    }
+
+    let mut select = session
+        .prepare(format!("SELECT * FROM {}.t WHERE a = 0", ks))
+        .await
+        .unwrap();
+    select.set_page_size(5);
+
+    // A column is added while the rows are being fetched
+    let mut rows = session.execute_iter(select.clone(), &[]).await.unwrap();
+    let mut rows_count = 0;
+    while rows_count < 5 {
+        let row = rows.next().await.unwrap().unwrap();
+        assert_eq!(row.columns.len(), 3);
+        rows_count += 1;
+    }
+    session
+        .query_unpaged(format!("ALTER TABLE {}.t ADD d int", ks), &[])
+        .await
+        .unwrap();
+    session.await_schema_agreement().await.unwrap();
+    while let Some(row) = rows.next().await {
+        let columns = row.unwrap().columns;
+        assert!(columns.len() == 3 || columns.len() == 4, "{:?}", columns);
+        rows_count += 1;
+    }
+    assert_eq!(rows_count, 30);
+
+    let rows = session
+        .execute_unpaged(&select, &[])
+        .await
+        .unwrap()
+        .rows
+        .unwrap();
+    assert_eq!(rows[0].columns.len(), 4);
+    assert_eq!(select.get_current_result_metadata().col_specs.len(), 4);
+
+    // Cached metadata with a different number of columns is detected,
+    // and replaced after re-preparation
+    let narrow = session
+        .prepare(format!("SELECT a FROM {}.t", ks))
+        .await
+        .unwrap();
+    select.update_result_metadata(narrow.get_result_metadata().clone());
+    let rows = session
+        .execute_unpaged(&select, &[])
+        .await
+        .unwrap()
+        .rows
+        .unwrap();
+    assert_eq!(rows.len(), 5);
+    assert!(rows.iter().all(|row| row.columns.len() == 4));
+    assert_eq!(select.get_current_result_metadata().col_specs.len(), 4);
+}
+
+#[tokio::test]
+async fn test_skipped_result_metadata_after_same_count_schema_change() {
+    // Changes of types which keep the number of columns are detected too
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(format!("CREATE TYPE {}.ut (x int)", ks), &[])
+        .await
+        .unwrap();
+    session
+        .query_unpaged(
+            format!(
+                "CREATE TABLE {}.t (a int PRIMARY KEY, u frozen<ut>, c int)",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+    session
+        .query_unpaged(
+            format!("INSERT INTO {}.t (a, u, c) VALUES (0, {{x: 1}}, 2)", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let select = session
+        .prepare(format!("SELECT u, c FROM {}.t WHERE a = 0", ks))
+        .await
+        .unwrap();
+    let row = session
+        .execute_unpaged(&select, &[])
+        .await
+        .unwrap()
+        .single_row()
+        .unwrap();
+    assert_eq!(row.columns[1], Some(CqlValue::Int(2)));
+
+    // A field added to the type of a column
+    session
+        .query_unpaged(format!("ALTER TYPE {}.ut ADD y text", ks), &[])
+        .await
+        .unwrap();
+    session
+        .query_unpaged(
+            format!("UPDATE {}.t SET u = {{x: 1, y: 'z'}} WHERE a = 0", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+    session.await_schema_agreement().await.unwrap();
+    let row = session
+        .execute_unpaged(&select, &[])
+        .await
+        .unwrap()
+        .single_row()
+        .unwrap();
+    match &row.columns[0] {
+        Some(CqlValue::UserDefinedType { fields, .. }) => assert_eq!(
+            fields,
+            &vec![
+                ("x".to_string(), Some(CqlValue::Int(1))),
+                ("y".to_string(), Some(CqlValue::Text("z".to_string()))),
+            ]
+        ),
+        other => panic!("Unexpected value: {:?}", other),
+    }
+
+    // A table recreated with a column of another type
+    session
+        .query_unpaged(format!("DROP TABLE {}.t", ks), &[])
+        .await
+        .unwrap();
+    session
+        .query_unpaged(
+            format!(
+                "CREATE TABLE {}.t (a int PRIMARY KEY, u frozen<ut>, c text)",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+    session
+        .query_unpaged(
+            format!("INSERT INTO {}.t (a, u, c) VALUES (0, {{x: 1}}, 'c')", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+    session.await_schema_agreement().await.unwrap();
+    let row = session
+        .execute_unpaged(&select, &[])
+        .await
+        .unwrap()
+        .single_row()
+        .unwrap();
+    assert_eq!(row.columns[1], Some(CqlValue::Text("c".to_string())));
+}
+
+#[tokio::test]
+async fn test_skipped_result_metadata_after_announced_schema_change() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(format!("CREATE TYPE {}.ut (x int)", ks), &[])
+        .await
+        .unwrap();
+    session
+        .query_unpaged(
+            format!("CREATE TABLE {}.t (a int PRIMARY KEY, u frozen<ut>)", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+    session
+        .query_unpaged(
+            format!("INSERT INTO {}.t (a, u) VALUES (0, {{x: 1}})", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let select = session
+        .prepare(format!("SELECT u FROM {}.t WHERE a = 0", ks))
+        .await
+        .unwrap();
+    session.execute_unpaged(&select, &[]).await.unwrap();
+    assert_eq!(select.unconfirmed_result_metadata_version(), None);
+
+    // The type is changed by another client, so the session learns about it
+    // only when the change is announced by the cluster
+    let other_session = create_new_session_builder().build().await.unwrap();
+    other_session
+        .query_unpaged(format!("ALTER TYPE {}.ut ADD y text", ks), &[])
+        .await
+        .unwrap();
+    other_session
+        .query_unpaged(
+            format!("UPDATE {}.t SET u = {{x: 1, y: 'z'}} WHERE a = 0", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+    session.await_schema_agreement().await.unwrap();
+    tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        while select.unconfirmed_result_metadata_version().is_none() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("Schema change wasn't announced");
+
+    let row = session
+        .execute_unpaged(&select, &[])
+        .await
+        .unwrap()
+        .single_row()
+        .unwrap();
+    match &row.columns[0] {
+        Some(CqlValue::UserDefinedType { fields, .. }) => assert_eq!(
+            fields,
+            &vec![
+                ("x".to_string(), Some(CqlValue::Int(1))),
+                ("y".to_string(), Some(CqlValue::Text("z".to_string()))),
+            ]
+        ),
+        other => panic!("Unexpected value: {:?}", other),
+    }
+    // The cached metadata was updated, so the rows are sent without it again
+    assert_eq!(select.unconfirmed_result_metadata_version(), None);
+    assert_matches!(
+        &select.get_current_result_metadata().col_specs[0].typ,
+        ColumnType::UserDefinedType { field_types, .. } if field_types.len() == 2
+    );
+}
+
+#[tokio::test]
+async fn test_unset_in_unprepared_statements() {
+    use crate::frame::value::{MaybeUnset, Unset};
+
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(
+            format!("CREATE TABLE {}.t (a int PRIMARY KEY, b int, c int)", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+    let insert = format!("INSERT INTO {}.t (a, b, c) VALUES (?, ?, ?)", ks);
+    let select = format!("SELECT b, c FROM {}.t WHERE a = 1", ks);
+
+    session
+        .query_unpaged(insert.as_str(), (1, 5, 5))
+        .await
+        .unwrap();
+
+    // Unset values leave the columns as they are, without writing tombstones for them.
+    session
+        .query_unpaged(insert.as_str(), (1, Unset, MaybeUnset::Set(7)))
+        .await
+        .unwrap();
+    let row = session
+        .query_unpaged(select.as_str(), &[])
+        .await
+        .unwrap()
+        .single_row_typed::<(Option<i32>, Option<i32>)>()
+        .unwrap();
+    assert_eq!(row, (Some(5), Some(7)));
+
+    let mut batch = Batch::default();
+    batch.append_statement(insert.as_str());
+    session
+        .batch(&batch, ((1, MaybeUnset::<i32>::Unset, 8),))
+        .await
+        .unwrap();
+    let row = session
+        .query_unpaged(select.as_str(), &[])
+        .await
+        .unwrap()
+        .single_row_typed::<(Option<i32>, Option<i32>)>()
+        .unwrap();
+    assert_eq!(row, (Some(5), Some(8)));
+
+    // Nulls, on the other hand, delete the values.
+    session
+        .query_unpaged(insert.as_str(), (1, None::<i32>, 9))
+        .await
+        .unwrap();
+    let row = session
+        .query_unpaged(select.as_str(), &[])
+        .await
+        .unwrap()
+        .single_row_typed::<(Option<i32>, Option<i32>)>()
+        .unwrap();
+    assert_eq!(row, (None, Some(9)));
+}
+
+#[tokio::test]
+async fn test_batch_values_from_generator() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(
+            format!("CREATE TABLE {}.t (a int, b int, PRIMARY KEY (a, b))", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+    let prepared = session
+        .prepare(format!("INSERT INTO {}.t (a, b) VALUES (?, ?)", ks))
+        .await
+        .unwrap();
+
+    let mut batch = Batch::new(crate::batch::BatchType::Unlogged);
+    for _ in 0..10_000 {
+        batch.append_statement(prepared.clone());
+    }
+
+    let values = BatchValuesFromIterator::from((0..10_000).map(|b| (1_i32, b)));
+    session.batch(&batch, values).await.unwrap();
+
+    let rows = session
+        .query_unpaged(format!("SELECT b FROM {}.t WHERE a = 1", ks), &[])
+        .await
+        .unwrap()
+        .rows_num()
+        .unwrap();
+    assert_eq!(rows, 10_000);
+
+    let values = BatchValuesFromIterator::from((0..9_999).map(|b| (2_i32, b)));
+    let err = session.batch(&batch, values).await.unwrap_err();
+    assert_matches!(
+        err,
+        QueryError::BadQuery(BadQuery::BatchValuesCountMismatch {
+            values_count: 9_999,
+            statements_count: 10_000
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_max_batch_serialized_size() {
+    let session = create_new_session_builder()
+        .max_batch_serialized_size(64 * 1024)
+        .build()
+        .await
+        .unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(
+            format!(
+                "CREATE TABLE {}.t (a int, b int, c blob, PRIMARY KEY (a, b))",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+    let prepared = session
+        .prepare(format!("INSERT INTO {}.t (a, b, c) VALUES (?, ?, ?)", ks))
+        .await
+        .unwrap();
+
+    let mut batch = Batch::new(crate::batch::BatchType::Unlogged);
+    for _ in 0..10 {
+        batch.append_statement(prepared.clone());
+    }
+
+    let small_blob = vec![0_u8; 1024];
+    let values: Vec<_> = (0..10).map(|b| (1_i32, b, &small_blob)).collect();
+    session.batch(&batch, &values).await.unwrap();
+
+    // The 8th row crosses the limit.
+    let big_blob = vec![0_u8; 8 * 1024];
+    let values: Vec<_> = (0..10).map(|b| (2_i32, b, &big_blob)).collect();
+    let err = session.batch(&batch, &values).await.unwrap_err();
+    match &err {
+        QueryError::BadQuery(BadQuery::BatchTooLarge {
+            statement_index,
+            max_size,
+        }) => {
+            assert_eq!(*statement_index, 7);
+            assert_eq!(*max_size, 64 * 1024);
+        }
+        other => panic!("Expected BatchTooLarge, got {:?}", other),
+    }
+
+    let rows = session
+        .query_unpaged(format!("SELECT b FROM {}.t WHERE a = 2", ks), &[])
+        .await
+        .unwrap()
+        .rows_num()
+        .unwrap();
+    assert_eq!(rows, 0);
+}
+
+#[tokio::test]
+async fn test_scan_table() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(
+            format!(
+                "CREATE TABLE {}.t (a int, b int, c int, PRIMARY KEY (a, b))",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    const ROWS: i32 = 10_000;
+    let insert = session
+        .prepare(format!("INSERT INTO {}.t (a, b, c) VALUES (?, ?, ?)", ks))
+        .await
+        .unwrap();
+    futures::stream::iter(0..ROWS)
+        .map(|i| session.execute_unpaged(&insert, (i / 4, i % 4, i)))
+        .buffer_unordered(64)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    session.await_schema_agreement().await.unwrap();
+    session.refresh_metadata().await.unwrap();
+
+    let config = ScanConfig::new().parallelism(4).page_size(100).columns("c");
+    let mut scan = session.scan_table(&ks, "t", config).await.unwrap();
+    let progress = scan.progress();
+    assert!(progress.ranges_total() > 0);
+
+    let mut seen = vec![0; ROWS as usize];
+    while let Some(row) = scan.next().await {
+        let (c,): (i32,) = row.unwrap().into_typed().unwrap();
+        seen[c as usize] += 1;
+    }
+    assert!(seen.iter().all(|count| *count == 1));
+    assert!(progress.is_finished());
+    assert_eq!(progress.ranges_done(), progress.ranges_total());
+
+    // The predicate narrows down the scanned rows.
+    let config = ScanConfig::new().predicate("c < 100 ALLOW FILTERING");
+    let scanned = session
+        .scan_table(&ks, "t", config)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(scanned.len(), 100);
+
+    assert!(session
+        .scan_table(&ks, "unknown", ScanConfig::new())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_views_in_schema_info() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks.clone(), false).await.unwrap();
+
+    session
+        .query_unpaged("CREATE TABLE t(id int PRIMARY KEY, v int)", &[])
+        .await
+        .unwrap();
+
+    session.query_unpaged("CREATE MATERIALIZED VIEW mv1 AS SELECT * FROM t WHERE v IS NOT NULL PRIMARY KEY (v, id)", &[]).await.unwrap();
+    session.query_unpaged("CREATE MATERIALIZED VIEW mv2 AS SELECT id, v FROM t WHERE v IS NOT NULL PRIMARY KEY (v, id)", &[]).await.unwrap();
+
+    session.await_schema_agreement().await.unwrap();
+    session.refresh_metadata().await.unwrap();
+
+    let keyspace_meta = session
+        .get_cluster_data()
+        .get_keyspace_info()
+        .get(&ks)
+        .unwrap()
+        .clone();
+
+    let tables = keyspace_meta
+        .tables
+        .keys()
+        .collect::<std::collections::HashSet<&String>>();
+
+    let views = keyspace_meta
+        .views
+        .keys()
+        .collect::<std::collections::HashSet<&String>>();
+    let views_base_table = keyspace_meta
+        .views
+        .values()
+        .map(|view_meta| &view_meta.base_table_name)
+        .collect::<std::collections::HashSet<&String>>();
+
+    assert_eq!(tables, std::collections::HashSet::from([&"t".to_string()]));
+    assert_eq!(
+        views,
+        std::collections::HashSet::from([&"mv1".to_string(), &"mv2".to_string()])
+    );
+    assert_eq!(
+        views_base_table,
+        std::collections::HashSet::from([&"t".to_string()])
+    )
+}
+
+#[tokio::test]
+async fn test_table_options_indexes_and_views_in_schema_info() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks.clone(), false).await.unwrap();
+
+    session
+        .query_unpaged(
+            "CREATE TABLE t(id int PRIMARY KEY, v int, w int) WITH default_time_to_live = 3600 \
+            AND gc_grace_seconds = 7200 AND comment = 'test table' \
+            AND compaction = {'class': 'LeveledCompactionStrategy'}",
+            &[],
+        )
+        .await
+        .unwrap();
+    session
+        .query_unpaged("CREATE INDEX w_idx ON t (w)", &[])
+        .await
+        .unwrap();
+    session.query_unpaged("CREATE MATERIALIZED VIEW mv AS SELECT id, v FROM t WHERE v IS NOT NULL AND id IS NOT NULL PRIMARY KEY (v, id)", &[]).await.unwrap();
+
+    session.await_schema_agreement().await.unwrap();
+    session.refresh_metadata().await.unwrap();
+
+    let cluster_data = session.get_cluster_data();
+    let keyspace_meta = cluster_data.get_keyspace_info().get(&ks).unwrap();
+
+    let table = &keyspace_meta.tables["t"];
+    assert_eq!(table.options.default_time_to_live, 3600);
+    assert_eq!(table.options.gc_grace_seconds, 7200);
+    assert_eq!(table.options.comment, "test table");
+    assert!(table.options.compaction["class"].ends_with("LeveledCompactionStrategy"));
+
+    let index = &table.indexes["w_idx"];
+    assert_eq!(index.kind, IndexKind::Composites);
+    assert_eq!(index.target(), Some("w"));
+
+    let view = &keyspace_meta.views["mv"];
+    assert_eq!(view.base_table_name, "t");
+    assert!(!view.include_all_columns);
+    assert!(view.where_clause.contains("v IS NOT NULL"));
+    let mut view_columns: Vec<&str> = view
+        .view_metadata
+        .columns
+        .keys()
+        .map(String::as_str)
+        .collect();
+    view_columns.sort_unstable();
+    assert_eq!(view_columns, vec!["id", "v"]);
+}
+
+async fn assert_test_batch_table_rows_contain(sess: &Session, expected_rows: &[(i32, i32)]) {
+    let selected_rows: BTreeSet<(i32, i32)> = sess
+        .query_unpaged("SELECT a, b FROM test_batch_table", ())
+        .await
+        .unwrap()
+        .rows_typed::<(i32, i32)>()
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+    for expected_row in expected_rows.iter() {
+        if !selected_rows.contains(expected_row) {
+            panic!(
+                "Expected {:?} to contain row: {:?}, but they didn't",
+                selected_rows, expected_row
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_prepare_batch() {
+    let session = create_new_session_builder().build().await.unwrap();
+
+    let ks = unique_keyspace_name();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks.clone(), false).await.unwrap();
+
+    session
+        .query_unpaged(
+            "CREATE TABLE test_batch_table (a int, b int, primary key (a, b))",
+            (),
+        )
+        .await
+        .unwrap();
+
+    let unprepared_insert_a_b: &str = "insert into test_batch_table (a, b) values (?, ?)";
+    let unprepared_insert_a_7: &str = "insert into test_batch_table (a, b) values (?, 7)";
+    let unprepared_insert_8_b: &str = "insert into test_batch_table (a, b) values (8, ?)";
+    let prepared_insert_a_b: PreparedStatement =
+        session.prepare(unprepared_insert_a_b).await.unwrap();
+    let prepared_insert_a_7: PreparedStatement =
+        session.prepare(unprepared_insert_a_7).await.unwrap();
+    let prepared_insert_8_b: PreparedStatement =
+        session.prepare(unprepared_insert_8_b).await.unwrap();
+
+    let assert_batch_prepared = |b: &Batch| {
+        for stmt in &b.statements {
+            match stmt {
+                BatchStatement::PreparedStatement(_) => {}
+                _ => panic!("Unprepared statement in prepared batch!"),
+            }
+        }
+    };
+
+    {
+        let mut unprepared_batch: Batch = Default::default();
+        unprepared_batch.append_statement(unprepared_insert_a_b);
+        unprepared_batch.append_statement(unprepared_insert_a_7);
+        unprepared_batch.append_statement(unprepared_insert_8_b);
+
+        let prepared_batch: Batch = session.prepare_batch(&unprepared_batch).await.unwrap();
+        assert_batch_prepared(&prepared_batch);
+
+        session
+            .batch(&prepared_batch, ((10, 20), (10,), (20,)))
+            .await
+            .unwrap();
+        assert_test_batch_table_rows_contain(&session, &[(10, 20), (10, 7), (8, 20)]).await;
+    }
+
+    {
+        let mut partially_prepared_batch: Batch = Default::default();
+        partially_prepared_batch.append_statement(unprepared_insert_a_b);
+        partially_prepared_batch.append_statement(prepared_insert_a_7.clone());
+        partially_prepared_batch.append_statement(unprepared_insert_8_b);
+
+        let prepared_batch: Batch = session
+            .prepare_batch(&partially_prepared_batch)
+            .await
+            .unwrap();
+        assert_batch_prepared(&prepared_batch);
+
+        session
+            .batch(&prepared_batch, ((30, 40), (30,), (40,)))
+            .await
+            .unwrap();
+        assert_test_batch_table_rows_contain(&session, &[(30, 40), (30, 7), (8, 40)]).await;
+    }
+
+    {
+        let mut fully_prepared_batch: Batch = Default::default();
+        fully_prepared_batch.append_statement(prepared_insert_a_b);
+        fully_prepared_batch.append_statement(prepared_insert_a_7);
+        fully_prepared_batch.append_statement(prepared_insert_8_b);
+
+        let prepared_batch: Batch = session.prepare_batch(&fully_prepared_batch).await.unwrap();
+        assert_batch_prepared(&prepared_batch);
+
+        session
+            .batch(&prepared_batch, ((50, 60), (50,), (60,)))
+            .await
+            .unwrap();
+
+        assert_test_batch_table_rows_contain(&session, &[(50, 60), (50, 7), (8, 60)]).await;
+    }
+
+    {
+        let mut bad_batch: Batch = Default::default();
+        bad_batch.append_statement(unprepared_insert_a_b);
+        bad_batch.append_statement("This isnt even CQL");
+        bad_batch.append_statement(unprepared_insert_8_b);
+
+        assert!(session.prepare_batch(&bad_batch).await.is_err());
+    }
+}
+
+#[tokio::test]
+async fn test_refresh_metadata_after_schema_agreement() {
+    let session = create_new_session_builder().build().await.unwrap();
+
+    let ks = unique_keyspace_name();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks.clone(), false).await.unwrap();
+
+    session
+        .query_unpaged(
+            "CREATE TYPE udt (field1 int, field2 uuid, field3 text)",
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let cluster_data = session.get_cluster_data();
+    let metadata = cluster_data.get_keyspace_info();
+    let keyspace_metadata = metadata.get(ks.as_str());
+    assert_ne!(keyspace_metadata, None);
+
+    let udt = keyspace_metadata.unwrap().user_defined_types.get("udt");
+    assert_ne!(udt, None);
+
+    assert_eq!(
+        udt.unwrap(),
+        &Arc::new(UserDefinedType {
+            keyspace: ks,
+            name: "udt".to_string(),
+            field_types: Vec::from([
+                ("field1".to_string(), CqlType::Native(NativeType::Int)),
+                ("field2".to_string(), CqlType::Native(NativeType::Uuid)),
+                ("field3".to_string(), CqlType::Native(NativeType::Text))
+            ])
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_rate_limit_exceeded_exception() {
+    let session = create_new_session_builder().build().await.unwrap();
+
+    // Typed errors in RPC were introduced along with per-partition rate limiting.
+    // There is no dedicated feature for per-partition rate limiting, so we are
+    // looking at the other one.
+    if !supports_feature(&session, "TYPED_ERRORS_IN_READ_RPC").await {
+        println!("Skipping because the cluster doesn't support per partition rate limiting");
+        return;
+    }
+
+    let ks = unique_keyspace_name();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks.clone(), false).await.unwrap();
+    session.query_unpaged("CREATE TABLE tbl (pk int PRIMARY KEY, v int) WITH per_partition_rate_limit = {'max_writes_per_second': 1}", ()).await.unwrap();
+
+    let stmt = session
+        .prepare("INSERT INTO tbl (pk, v) VALUES (?, ?)")
+        .await
+        .unwrap();
+
+    // The rate limit is 1 write/s, so repeat the same query
+    // until an error occurs, it should happen quickly
+
+    let mut maybe_err = None;
+
+    for _ in 0..1000 {
+        match session.execute_unpaged(&stmt, (123, 456)).await {
+            Ok(_) => {} // Try again
+            Err(err) => {
+                maybe_err = Some(err);
+                break;
+            }
+        }
+    }
+
+    use scylla_cql::errors::OperationType;
+
+    match maybe_err.expect("Rate limit error didn't occur") {
+        QueryError::DbError(DbError::RateLimitReached { op_type, .. }, _) => {
+            assert_eq!(op_type, OperationType::Write);
+        }
+        err => panic!("Unexpected error type received: {:?}", err),
+    }
+}
+
+// Batches containing LWT queries (IF col = som) return rows with information whether the queries were applied.
+#[tokio::test]
+async fn test_batch_lwts() {
+    let session = create_new_session_builder().build().await.unwrap();
+
+    let ks = unique_keyspace_name();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks.clone(), false).await.unwrap();
+
+    session
+        .query_unpaged(
+            "CREATE TABLE tab (p1 int, c1 int, r1 int, r2 int, primary key (p1, c1))",
+            (),
+        )
+        .await
+        .unwrap();
+
+    session
+        .query_unpaged("INSERT INTO tab (p1, c1, r1, r2) VALUES (0, 0, 0, 0)", ())
+        .await
+        .unwrap();
+
+    let mut batch: Batch = Batch::default();
+    batch.append_statement("UPDATE tab SET r2 = 1 WHERE p1 = 0 AND c1 = 0 IF r1 = 0");
+    batch.append_statement("INSERT INTO tab (p1, c1, r1, r2) VALUES (0, 123, 321, 312)");
+    batch.append_statement("UPDATE tab SET r1 = 1 WHERE p1 = 0 AND c1 = 0 IF r2 = 0");
+
+    let batch_res: QueryResult = session.batch(&batch, ((), (), ())).await.unwrap();
+
+    // Scylla returns 5 columns, but Cassandra returns only 1
+    let is_scylla: bool = batch_res.col_specs.len() == 5;
+
+    if is_scylla {
+        test_batch_lwts_for_scylla(&session, &batch, batch_res).await;
+    } else {
+        test_batch_lwts_for_cassandra(&session, &batch, batch_res).await;
+    }
+}
+
+async fn test_batch_lwts_for_scylla(session: &Session, batch: &Batch, batch_res: QueryResult) {
+    // Alias required by clippy
+    type IntOrNull = Option<i32>;
+
+    // Returned columns are:
+    // [applied], p1, c1, r1, r2
+    let batch_res_rows: Vec<(bool, IntOrNull, IntOrNull, IntOrNull, IntOrNull)> = batch_res
+        .rows_typed()
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+
+    let expected_batch_res_rows = vec![
+        (true, Some(0), Some(0), Some(0), Some(0)),
+        (true, None, None, None, None),
+        (true, Some(0), Some(0), Some(0), Some(0)),
+    ];
+
+    assert_eq!(batch_res_rows, expected_batch_res_rows);
+
+    let prepared_batch: Batch = session.prepare_batch(batch).await.unwrap();
+    let prepared_batch_res: QueryResult =
+        session.batch(&prepared_batch, ((), (), ())).await.unwrap();
+
+    let prepared_batch_res_rows: Vec<(bool, IntOrNull, IntOrNull, IntOrNull, IntOrNull)> =
+        prepared_batch_res
+            .rows_typed()
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+
+    let expected_prepared_batch_res_rows = vec![
+        (false, Some(0), Some(0), Some(1), Some(1)),
+        (false, None, None, None, None),
+        (false, Some(0), Some(0), Some(1), Some(1)),
+    ];
+
+    assert_eq!(prepared_batch_res_rows, expected_prepared_batch_res_rows);
+}
+
+async fn test_batch_lwts_for_cassandra(session: &Session, batch: &Batch, batch_res: QueryResult) {
+    // Alias required by clippy
+    type IntOrNull = Option<i32>;
+
+    // Returned columns are:
+    // [applied]
+    let batch_res_rows: Vec<(bool,)> = batch_res
+        .rows_typed()
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+
+    let expected_batch_res_rows = vec![(true,)];
+
+    assert_eq!(batch_res_rows, expected_batch_res_rows);
+
+    let prepared_batch: Batch = session.prepare_batch(batch).await.unwrap();
+    let prepared_batch_res: QueryResult =
+        session.batch(&prepared_batch, ((), (), ())).await.unwrap();
+
+    // Returned columns are:
+    // [applied], p1, c1, r1, r2
+    let prepared_batch_res_rows: Vec<(bool, IntOrNull, IntOrNull, IntOrNull, IntOrNull)> =
+        prepared_batch_res
+            .rows_typed()
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+
+    let expected_prepared_batch_res_rows = vec![(false, Some(0), Some(0), Some(1), Some(1))];
+
+    assert_eq!(prepared_batch_res_rows, expected_prepared_batch_res_rows);
+}
+
+#[tokio::test]
+async fn test_keyspaces_to_fetch() {
+    let ks1 = unique_keyspace_name();
+    let ks2 = unique_keyspace_name();
+
+    let session_default = create_new_session_builder().build().await.unwrap();
+    for ks in [&ks1, &ks2] {
+        session_default
+            .query_unpaged(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+            .await
+            .unwrap();
+    }
+    session_default.await_schema_agreement().await.unwrap();
+    assert!(session_default
+        .get_cluster_data()
+        .keyspaces
+        .contains_key(&ks1));
+    assert!(session_default
+        .get_cluster_data()
+        .keyspaces
+        .contains_key(&ks2));
+
+    let session1 = create_new_session_builder()
+        .keyspaces_to_fetch([&ks1])
+        .build()
+        .await
+        .unwrap();
+    assert!(session1.get_cluster_data().keyspaces.contains_key(&ks1));
+    assert!(!session1.get_cluster_data().keyspaces.contains_key(&ks2));
+
+    let session_all = create_new_session_builder()
+        .keyspaces_to_fetch([] as [String; 0])
+        .build()
+        .await
+        .unwrap();
+    assert!(session_all.get_cluster_data().keyspaces.contains_key(&ks1));
+    assert!(session_all.get_cluster_data().keyspaces.contains_key(&ks2));
+}
+
+// Reproduces the problem with execute_iter mentioned in #608.
+#[tokio::test]
+async fn test_iter_works_when_retry_policy_returns_ignore_write_error() {
+    // It's difficult to reproduce the issue with a real downgrading consistency policy,
+    // as it would require triggering a WriteTimeout. We just need the policy
+    // to return IgnoreWriteError, so we will trigger a different error
+    // and use a custom retry policy which returns IgnoreWriteError.
+    let retried_flag = Arc::new(AtomicBool::new(false));
+
+    #[derive(Debug)]
+    struct MyRetryPolicy(Arc<AtomicBool>);
+    impl RetryPolicy for MyRetryPolicy {
+        fn new_session(&self) -> Box<dyn RetrySession> {
+            Box::new(MyRetrySession(self.0.clone()))
+        }
+        fn clone_boxed(&self) -> Box<dyn RetryPolicy> {
+            Box::new(MyRetryPolicy(self.0.clone()))
+        }
+    }
+
+    struct MyRetrySession(Arc<AtomicBool>);
+    impl RetrySession for MyRetrySession {
+        fn decide_should_retry(&mut self, _: QueryInfo) -> RetryDecision {
+            self.0.store(true, Ordering::Relaxed);
+            RetryDecision::IgnoreWriteError
+        }
+        fn reset(&mut self) {}
+    }
+
+    let handle = ExecutionProfile::builder()
+        .consistency(Consistency::All)
+        .retry_policy(Box::new(MyRetryPolicy(retried_flag.clone())))
+        .build()
+        .into_handle();
+
+    let session = create_new_session_builder()
+        .default_execution_profile_handle(handle)
+        .build()
+        .await
+        .unwrap();
+
+    // Create a keyspace with replication factor that is larger than the cluster size
+    let cluster_size = session.get_cluster_data().get_nodes_info().len();
+    let ks = unique_keyspace_name();
+    session.query_unpaged(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class': 'NetworkTopologyStrategy', 'replication_factor': {}}}", ks, cluster_size + 1), ()).await.unwrap();
+    session.use_keyspace(ks, true).await.unwrap();
+    session
+        .query_unpaged("CREATE TABLE t (pk int PRIMARY KEY, v int)", ())
+        .await
+        .unwrap();
+
+    assert!(!retried_flag.load(Ordering::Relaxed));
+    // Try to write something to the new table - it should fail and the policy
+    // will tell us to ignore the error
+    let mut iter = session
+        .query_iter("INSERT INTO t (pk v) VALUES (1, 2)", ())
+        .await
+        .unwrap();
+
+    assert!(retried_flag.load(Ordering::Relaxed));
+    while iter.try_next().await.unwrap().is_some() {}
+
+    retried_flag.store(false, Ordering::Relaxed);
+    // Try the same with execute_iter()
+    let p = session
+        .prepare("INSERT INTO t (pk, v) VALUES (?, ?)")
+        .await
+        .unwrap();
+    let mut iter = session.execute_iter(p, (1, 2)).await.unwrap();
+
+    assert!(retried_flag.load(Ordering::Relaxed));
+    while iter.try_next().await.unwrap().is_some() {}
+}
+
+#[tokio::test]
+async fn test_iter_methods_with_modification_statements() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t (a int, b int, c text, primary key (a, b))",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let mut query = Query::from(format!(
+        "INSERT INTO {}.t (a, b, c) VALUES (1, 2, 'abc')",
+        ks
+    ));
+    query.set_tracing(true);
+    let mut row_iterator = session.query_iter(query, &[]).await.unwrap();
+    row_iterator.next().await.ok_or(()).unwrap_err(); // assert empty
+    assert!(!row_iterator.get_tracing_ids().is_empty());
+
+    let prepared_statement = session
+        .prepare(format!("INSERT INTO {}.t (a, b, c) VALUES (?, ?, ?)", ks))
+        .await
+        .unwrap();
+    let mut row_iterator = session
+        .execute_iter(prepared_statement, (2, 3, "cba"))
+        .await
+        .unwrap();
+    row_iterator.next().await.ok_or(()).unwrap_err(); // assert empty
+}
+
+#[tokio::test]
+async fn test_get_keyspace_name() {
+    let ks = unique_keyspace_name();
+
+    // Create the keyspace
+    // No keyspace is set in config, so get_keyspace() should return None.
+    let session = create_new_session_builder().build().await.unwrap();
+    assert_eq!(session.get_keyspace(), None);
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    assert_eq!(session.get_keyspace(), None);
+
+    // Call use_keyspace(), get_keyspace now should return the new keyspace name
+    assert_eq!(session.use_keyspace(&ks, true).await.unwrap(), None);
+    assert_eq!(*session.get_keyspace().unwrap(), ks);
+
+    // Case insensitive names refer to the lowercase keyspace
+    let previous = session
+        .use_keyspace(ks.to_uppercase(), false)
+        .await
         .unwrap();
+    assert_eq!(*previous.unwrap(), ks);
+    assert_eq!(*session.get_keyspace().unwrap(), ks);
 
+    // Invalid names don't change the keyspace
+    session.use_keyspace("abcd;dfdsf", false).await.unwrap_err();
+    assert_eq!(*session.get_keyspace().unwrap(), ks);
+
+    // Unqualified table names are looked up in the used keyspace
     session
-        .query("INSERT INTO tab (p1, c1, r1, r2) VALUES (0, 0, 0, 0)", ())
+        .query_unpaged(format!("CREATE TABLE {}.t (a int PRIMARY KEY)", ks), &[])
         .await
         .unwrap();
+    session.await_schema_agreement().await.unwrap();
+    session.refresh_metadata().await.unwrap();
+    let table = session.get_table_metadata(None, "t").unwrap();
+    assert_eq!(table.partition_key, vec!["a".to_string()]);
+    assert_eq!(session.get_table_metadata(Some(&ks), "t"), Some(table));
+    assert_eq!(session.get_table_metadata(Some("system"), "t"), None);
 
-    let mut batch: Batch = Batch::default();
-    batch.append_statement("UPDATE tab SET r2 = 1 WHERE p1 = 0 AND c1 = 0 IF r1 = 0");
-    batch.append_statement("INSERT INTO tab (p1, c1, r1, r2) VALUES (0, 123, 321, 312)");
-    batch.append_statement("UPDATE tab SET r1 = 1 WHERE p1 = 0 AND c1 = 0 IF r2 = 0");
+    // Creating a new session with the keyspace set in config should cause
+    // get_keyspace to return that name
+    let session = create_new_session_builder()
+        .use_keyspace(&ks, true)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(*session.get_keyspace().unwrap(), ks);
+}
 
-    let batch_res: QueryResult = session.batch(&batch, ((), (), ())).await.unwrap();
+/// It's recommended to use NetworkTopologyStrategy everywhere, so most tests use only NetworkTopologyStrategy.
+/// We still support SimpleStrategy, so to make sure that SimpleStrategy works correctly this test runs
+/// a few queries in a SimpleStrategy keyspace.
+#[tokio::test]
+async fn simple_strategy_test() {
+    let ks = unique_keyspace_name();
+    let session = create_new_session_builder().build().await.unwrap();
 
-    // Scylla returns 5 columns, but Cassandra returns only 1
-    let is_scylla: bool = batch_res.col_specs.len() == 5;
+    session
+        .query_unpaged(
+            format!(
+                "CREATE KEYSPACE {} WITH REPLICATION = \
+                {{'class': 'SimpleStrategy', 'replication_factor': 1}}",
+                ks
+            ),
+            (),
+        )
+        .await
+        .unwrap();
 
-    if is_scylla {
-        test_batch_lwts_for_scylla(&session, &batch, batch_res).await;
-    } else {
-        test_batch_lwts_for_cassandra(&session, &batch, batch_res).await;
+    session
+        .query_unpaged(
+            format!(
+                "CREATE TABLE {}.tab (p int, c int, r int, PRIMARY KEY (p, c, r))",
+                ks
+            ),
+            (),
+        )
+        .await
+        .unwrap();
+
+    session
+        .query_unpaged(
+            format!("INSERT INTO {}.tab (p, c, r) VALUES (1, 2, 3)", ks),
+            (),
+        )
+        .await
+        .unwrap();
+
+    session
+        .query_unpaged(
+            format!("INSERT INTO {}.tab (p, c, r) VALUES (?, ?, ?)", ks),
+            (4, 5, 6),
+        )
+        .await
+        .unwrap();
+
+    let prepared = session
+        .prepare(format!("INSERT INTO {}.tab (p, c, r) VALUES (?, ?, ?)", ks))
+        .await
+        .unwrap();
+
+    session.execute_unpaged(&prepared, (7, 8, 9)).await.unwrap();
+
+    let mut rows: Vec<(i32, i32, i32)> = session
+        .query_unpaged(format!("SELECT p, c, r FROM {}.tab", ks), ())
+        .await
+        .unwrap()
+        .rows_typed::<(i32, i32, i32)>()
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect::<Vec<(i32, i32, i32)>>();
+    rows.sort();
+
+    assert_eq!(rows, vec![(1, 2, 3), (4, 5, 6), (7, 8, 9)]);
+}
+
+#[tokio::test]
+async fn test_manual_primary_key_computation() {
+    // Setup session
+    let ks = unique_keyspace_name();
+    let session = create_new_session_builder().build().await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(&ks, true).await.unwrap();
+
+    async fn assert_tokens_equal(
+        session: &Session,
+        prepared: &PreparedStatement,
+        serialized_pk_values_in_pk_order: &SerializedValues,
+        all_values_in_query_order: impl SerializeRow,
+    ) {
+        let token_by_prepared = prepared
+            .calculate_token(&all_values_in_query_order)
+            .unwrap()
+            .unwrap();
+
+        session
+            .execute_unpaged(prepared, all_values_in_query_order)
+            .await
+            .unwrap();
+
+        let token_by_hand = calculate_token_for_partition_key(
+            serialized_pk_values_in_pk_order,
+            &Murmur3Partitioner,
+        )
+        .unwrap();
+        println!(
+            "by_prepared: {}, by_hand: {}",
+            token_by_prepared.value, token_by_hand.value
+        );
+        assert_eq!(token_by_prepared, token_by_hand);
+    }
+
+    // Single-column partition key
+    {
+        session
+            .query_unpaged(
+                "CREATE TABLE IF NOT EXISTS t2 (a int, b int, c text, primary key (a, b))",
+                &[],
+            )
+            .await
+            .unwrap();
+
+        // Values are given non partition key order,
+        let prepared_simple_pk = session
+            .prepare("INSERT INTO t2 (a, b, c) VALUES (?, ?, ?)")
+            .await
+            .unwrap();
+
+        let mut pk_values_in_pk_order = SerializedValues::new();
+        pk_values_in_pk_order
+            .add_value(&17_i32, &ColumnType::Int)
+            .unwrap();
+        let all_values_in_query_order = (17_i32, 16_i32, "I'm prepared!!!");
+
+        assert_tokens_equal(
+            &session,
+            &prepared_simple_pk,
+            &pk_values_in_pk_order,
+            all_values_in_query_order,
+        )
+        .await;
+    }
+
+    // Composite partition key
+    {
+        session
+            .query_unpaged("CREATE TABLE IF NOT EXISTS complex_pk (a int, b int, c text, d int, e int, primary key ((a,b,c),d))", &[])
+            .await
+            .unwrap();
+
+        // Values are given in non partition key order, to check that such permutation
+        // still yields consistent hashes.
+        let prepared_complex_pk = session
+            .prepare("INSERT INTO complex_pk (a, d, c, b) VALUES (?, 7, ?, ?)")
+            .await
+            .unwrap();
+
+        let mut pk_values_in_pk_order = SerializedValues::new();
+        pk_values_in_pk_order
+            .add_value(&17_i32, &ColumnType::Int)
+            .unwrap();
+        pk_values_in_pk_order
+            .add_value(&16_i32, &ColumnType::Int)
+            .unwrap();
+        pk_values_in_pk_order
+            .add_value(&"I'm prepared!!!", &ColumnType::Ascii)
+            .unwrap();
+        let all_values_in_query_order = (17_i32, "I'm prepared!!!", 16_i32);
+
+        assert_tokens_equal(
+            &session,
+            &prepared_complex_pk,
+            &pk_values_in_pk_order,
+            all_values_in_query_order,
+        )
+        .await;
     }
 }
 
-async fn test_batch_lwts_for_scylla(session: &Session, batch: &Batch, batch_res: QueryResult) {
-    // Alias required by clippy
-    type IntOrNull = Option<i32>;
+#[tokio::test]
+async fn test_statement_builder() {
+    use crate::statement::builder::{Delete, Insert, Select, Update};
+
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(
+            format!(
+                "CREATE TABLE {}.\"Builder\" (\"key\" int PRIMARY KEY, \"select\" text, \"MixedCase\" int)",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    for key in 0..3 {
+        let (statement, values) = Insert::into(&ks, "Builder")
+            .value("key", key)
+            .value("select", "'); DROP TABLE Builder; --")
+            .value("MixedCase", key * 10)
+            .build()
+            .unwrap();
+        session.query_unpaged(statement, values).await.unwrap();
+    }
+
+    let (statement, values) = Update::table(&ks, "Builder")
+        .set("MixedCase", 100)
+        .where_eq("key", 1)
+        .build()
+        .unwrap();
+    session.query_unpaged(statement, values).await.unwrap();
+
+    let (statement, values) = Delete::from(&ks, "Builder")
+        .where_eq("key", 2)
+        .build()
+        .unwrap();
+    session.query_unpaged(statement, values).await.unwrap();
 
-    // Returned columns are:
-    // [applied], p1, c1, r1, r2
-    let batch_res_rows: Vec<(bool, IntOrNull, IntOrNull, IntOrNull, IntOrNull)> = batch_res
-        .rows_typed()
+    let (statement, values) = Select::from(&ks, "Builder")
+        .column("select")
+        .column("MixedCase")
+        .where_in("key", vec![0, 1, 2])
+        .build()
+        .unwrap();
+    let mut rows: Vec<(String, i32)> = session
+        .query_unpaged(statement, values)
+        .await
         .unwrap()
-        .map(|r| r.unwrap())
+        .rows_typed::<(String, i32)>()
+        .unwrap()
+        .map(Result::unwrap)
         .collect();
+    rows.sort_by_key(|(_, mixed_case)| *mixed_case);
+    assert_eq!(
+        rows,
+        vec![
+            ("'); DROP TABLE Builder; --".to_owned(), 0),
+            ("'); DROP TABLE Builder; --".to_owned(), 100),
+        ]
+    );
+}
 
-    let expected_batch_res_rows = vec![
-        (true, Some(0), Some(0), Some(0), Some(0)),
-        (true, None, None, None, None),
-        (true, Some(0), Some(0), Some(0), Some(0)),
-    ];
-
-    assert_eq!(batch_res_rows, expected_batch_res_rows);
+#[tokio::test]
+async fn test_concurrent_use_keyspace() {
+    let session = Arc::new(create_new_session_builder().build().await.unwrap());
+    let keyspaces: Vec<String> = (0..4).map(|_| unique_keyspace_name()).collect();
 
-    let prepared_batch: Batch = session.prepare_batch(batch).await.unwrap();
-    let prepared_batch_res: QueryResult =
-        session.batch(&prepared_batch, ((), (), ())).await.unwrap();
+    for ks in &keyspaces {
+        session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+        session
+            .query_unpaged(
+                format!("CREATE TABLE {}.tab (a int PRIMARY KEY, ks text)", ks),
+                &[],
+            )
+            .await
+            .unwrap();
+        session
+            .query_unpaged(
+                format!("INSERT INTO {}.tab (a, ks) VALUES (0, ?)", ks),
+                (ks,),
+            )
+            .await
+            .unwrap();
+    }
+    session.await_schema_agreement().await.unwrap();
 
-    let prepared_batch_res_rows: Vec<(bool, IntOrNull, IntOrNull, IntOrNull, IntOrNull)> =
-        prepared_batch_res
-            .rows_typed()
+    let select_used_keyspace = |session: Arc<Session>| async move {
+        session
+            .query_unpaged("SELECT ks FROM tab WHERE a = 0", &[])
+            .await
             .unwrap()
-            .map(|r| r.unwrap())
-            .collect();
+            .single_row_typed::<(String,)>()
+            .unwrap()
+            .0
+    };
 
-    let expected_prepared_batch_res_rows = vec![
-        (false, Some(0), Some(0), Some(1), Some(1)),
-        (false, None, None, None, None),
-        (false, Some(0), Some(0), Some(1), Some(1)),
-    ];
+    // Keyspace switches race with each other and with queries using unqualified names
+    session.use_keyspace(&keyspaces[0], false).await.unwrap();
+    let switches = (0..20).map(|i| {
+        let session = session.clone();
+        let ks = keyspaces[i % keyspaces.len()].clone();
+        tokio::spawn(async move { session.use_keyspace(ks, false).await.unwrap() })
+    });
+    let queries = (0..20).map(|_| tokio::spawn(select_used_keyspace(session.clone())));
+    let switches: Vec<_> = switches.collect();
+    for query in queries.collect::<Vec<_>>() {
+        assert!(keyspaces.contains(&query.await.unwrap()));
+    }
+    for switch in switches {
+        let previous = switch.await.unwrap().unwrap();
+        assert!(keyspaces.contains(&previous));
+    }
 
-    assert_eq!(prepared_batch_res_rows, expected_prepared_batch_res_rows);
+    // Once the switches are done, the accessor and all connections agree on the keyspace
+    let used = session.get_keyspace().unwrap();
+    for _ in 0..50 {
+        assert_eq!(select_used_keyspace(session.clone()).await, *used);
+    }
 }
 
-async fn test_batch_lwts_for_cassandra(session: &Session, batch: &Batch, batch_res: QueryResult) {
-    // Alias required by clippy
-    type IntOrNull = Option<i32>;
-
-    // Returned columns are:
-    // [applied]
-    let batch_res_rows: Vec<(bool,)> = batch_res
-        .rows_typed()
-        .unwrap()
-        .map(|r| r.unwrap())
-        .collect();
-
-    let expected_batch_res_rows = vec![(true,)];
-
-    assert_eq!(batch_res_rows, expected_batch_res_rows);
+#[tokio::test]
+async fn test_bind_marker_count_mismatch() {
+    let session = create_new_session_builder()
+        .check_unprepared_bind_markers(true)
+        .build()
+        .await
+        .unwrap();
+    let ks = unique_keyspace_name();
 
-    let prepared_batch: Batch = session.prepare_batch(batch).await.unwrap();
-    let prepared_batch_res: QueryResult =
-        session.batch(&prepared_batch, ((), (), ())).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query_unpaged(
+            format!("CREATE TABLE {}.t (a int PRIMARY KEY, b text)", ks),
+            &[],
+        )
+        .await
+        .unwrap();
 
-    // Returned columns are:
-    // [applied], p1, c1, r1, r2
-    let prepared_batch_res_rows: Vec<(bool, IntOrNull, IntOrNull, IntOrNull, IntOrNull)> =
-        prepared_batch_res
-            .rows_typed()
-            .unwrap()
-            .map(|r| r.unwrap())
-            .collect();
+    let assert_count_mismatch = |err: QueryError, message: &str| {
+        assert_matches!(
+            &err,
+            QueryError::BadQuery(BadQuery::SerializationError(e)) if e.to_string().contains(message),
+            "{}",
+            err
+        );
+    };
 
-    let expected_prepared_batch_res_rows = vec![(false, Some(0), Some(0), Some(1), Some(1))];
+    let insert = session
+        .prepare(format!("INSERT INTO {}.t (a, b) VALUES (?, ?)", ks))
+        .await
+        .unwrap();
+    let err = session
+        .execute_unpaged(&insert, (1, "x", 2))
+        .await
+        .unwrap_err();
+    assert_count_mismatch(
+        err,
+        "it has 2 bind markers (a, b), but 3 values were provided",
+    );
+    let err = session.execute_unpaged(&insert, (1,)).await.unwrap_err();
+    assert_count_mismatch(
+        err,
+        "it has 2 bind markers (a, b), but 1 values were provided",
+    );
+    session.execute_unpaged(&insert, (1, "x")).await.unwrap();
 
-    assert_eq!(prepared_batch_res_rows, expected_prepared_batch_res_rows);
+    // Unprepared statements sent without values are checked with the bind markers counted in the text
+    let err = session
+        .query_unpaged(format!("SELECT a FROM {}.t WHERE a = ?", ks), ())
+        .await
+        .unwrap_err();
+    assert_count_mismatch(err, "it has 1 bind markers, but 0 values were provided");
+    let Err(err) = session
+        .query_iter(format!("SELECT a FROM {}.t WHERE a = ?", ks), ())
+        .await
+    else {
+        panic!("query_iter should fail")
+    };
+    assert_count_mismatch(err, "it has 1 bind markers, but 0 values were provided");
+    let mut batch = Batch::default();
+    batch.append_statement(format!("INSERT INTO {}.t (a, b) VALUES (2, 'y')", ks).as_str());
+    batch.append_statement(format!("INSERT INTO {}.t (a, b) VALUES (?, ?)", ks).as_str());
+    let err = session.batch(&batch, ((), ())).await.unwrap_err();
+    assert_count_mismatch(err, "it has 2 bind markers, but 0 values were provided");
+    session.batch(&batch, ((), (3, "z"))).await.unwrap();
+
+    // Question marks in literals are not bind markers
+    session
+        .query_unpaged(
+            format!("INSERT INTO {}.t (a, b) VALUES (4, '?') -- a = ?", ks),
+            (),
+        )
+        .await
+        .unwrap();
+    let result = session
+        .query_unpaged(format!("SELECT b FROM {}.t WHERE a = ?", ks), (4,))
+        .await
+        .unwrap();
+    assert_eq!(result.single_row_typed::<(String,)>().unwrap().0, "?");
 }
 
 #[tokio::test]
-async fn test_keyspaces_to_fetch() {
-    let ks1 = unique_keyspace_name();
-    let ks2 = unique_keyspace_name();
-
-    let session_default = create_new_session_builder().build().await.unwrap();
-    for ks in [&ks1, &ks2] {
-        session_default
-            .query(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
-            .await
-            .unwrap();
-    }
-    session_default.await_schema_agreement().await.unwrap();
-    assert!(session_default
-        .get_cluster_data()
-        .keyspaces
-        .contains_key(&ks1));
-    assert!(session_default
-        .get_cluster_data()
-        .keyspaces
-        .contains_key(&ks2));
+async fn test_ddl() {
+    use crate::transport::ddl::{SchemaChange, SchemaChangeTarget, SchemaChangeType};
 
     let session1 = create_new_session_builder()
-        .keyspaces_to_fetch([&ks1])
+        .ddl_ignore_already_exists(true)
         .build()
         .await
         .unwrap();
-    assert!(session1.get_cluster_data().keyspaces.contains_key(&ks1));
-    assert!(!session1.get_cluster_data().keyspaces.contains_key(&ks2));
-
-    let session_all = create_new_session_builder()
-        .keyspaces_to_fetch([] as [String; 0])
+    let session2 = create_new_session_builder()
+        .ddl_ignore_already_exists(true)
         .build()
         .await
         .unwrap();
-    assert!(session_all.get_cluster_data().keyspaces.contains_key(&ks1));
-    assert!(session_all.get_cluster_data().keyspaces.contains_key(&ks2));
+    let ks = unique_keyspace_name();
+
+    let change = session1
+        .ddl(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks))
+        .await
+        .unwrap();
+    assert_eq!(
+        change,
+        Some(SchemaChange {
+            change_type: SchemaChangeType::Created,
+            target: SchemaChangeTarget::Keyspace,
+            keyspace: ks.clone(),
+            name: None,
+        })
+    );
+
+    // Both sessions create the same table at once, one of them loses the race
+    let create_table = format!("CREATE TABLE {}.t (a int PRIMARY KEY, b text)", ks);
+    let (change1, change2) = tokio::join!(
+        session1.ddl(create_table.as_str()),
+        session2.ddl(create_table.as_str())
+    );
+    let changes = [change1.unwrap(), change2.unwrap()];
+    assert!(changes.iter().flatten().count() >= 1, "{:?}", changes);
+    for change in changes.into_iter().flatten() {
+        assert_eq!(change.change_type, SchemaChangeType::Created);
+        assert_eq!(change.target, SchemaChangeTarget::Table);
+        assert_eq!(change.name.as_deref(), Some("t"));
+    }
+    // The table is visible right after the schema change
+    session2
+        .query_unpaged(format!("INSERT INTO {}.t (a, b) VALUES (1, 'x')", ks), ())
+        .await
+        .unwrap();
+
+    // Nothing is changed with IF NOT EXISTS
+    let change = session1
+        .ddl(format!(
+            "CREATE TABLE IF NOT EXISTS {}.t (a int PRIMARY KEY, b text)",
+            ks
+        ))
+        .await
+        .unwrap();
+    assert_eq!(change, None);
+
+    let change = session1
+        .ddl(format!("ALTER TABLE {}.t ADD c int", ks))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(change.change_type, SchemaChangeType::Updated);
+
+    // Errors other than AlreadyExists are not ignored
+    let err = session1
+        .ddl(format!("CREATE TABLE {}.t2 (a int)", ks))
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), QueryErrorKind::Invalid);
+
+    // Without the option, AlreadyExists is returned
+    let session3 = create_new_session_builder().build().await.unwrap();
+    let err = session3.ddl(create_table).await.unwrap_err();
+    assert_eq!(err.kind(), QueryErrorKind::AlreadyExists);
 }
 
-// Reproduces the problem with execute_iter mentioned in #608.
 #[tokio::test]
-async fn test_iter_works_when_retry_policy_returns_ignore_write_error() {
-    // It's difficult to reproduce the issue with a real downgrading consistency policy,
-    // as it would require triggering a WriteTimeout. We just need the policy
-    // to return IgnoreWriteError, so we will trigger a different error
-    // and use a custom retry policy which returns IgnoreWriteError.
-    let retried_flag = Arc::new(AtomicBool::new(false));
+async fn test_schema_change_result() {
+    use crate::frame::response::event::SchemaChangeType;
+    use crate::frame::response::result::SchemaChangeResult;
 
-    #[derive(Debug)]
-    struct MyRetryPolicy(Arc<AtomicBool>);
-    impl RetryPolicy for MyRetryPolicy {
-        fn new_session(&self) -> Box<dyn RetrySession> {
-            Box::new(MyRetrySession(self.0.clone()))
-        }
-        fn clone_boxed(&self) -> Box<dyn RetryPolicy> {
-            Box::new(MyRetryPolicy(self.0.clone()))
-        }
-    }
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
 
-    struct MyRetrySession(Arc<AtomicBool>);
-    impl RetrySession for MyRetrySession {
-        fn decide_should_retry(&mut self, _: QueryInfo) -> RetryDecision {
-            self.0.store(true, Ordering::Relaxed);
-            RetryDecision::IgnoreWriteError
-        }
-        fn reset(&mut self) {}
-    }
+    let result = session
+        .query_unpaged(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        result.schema_change(),
+        Some(&SchemaChangeResult::KeyspaceChange {
+            change_type: SchemaChangeType::Created,
+            keyspace_name: ks.clone(),
+        })
+    );
 
-    let handle = ExecutionProfile::builder()
-        .consistency(Consistency::All)
-        .retry_policy(Box::new(MyRetryPolicy(retried_flag.clone())))
-        .build()
-        .into_handle();
+    let result = session
+        .query_unpaged(format!("CREATE TABLE {}.t (a int PRIMARY KEY)", ks), &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        result.schema_change(),
+        Some(&SchemaChangeResult::TableChange {
+            change_type: SchemaChangeType::Created,
+            keyspace_name: ks.clone(),
+            object_name: "t".to_owned(),
+        })
+    );
 
-    let session = create_new_session_builder()
-        .default_execution_profile_handle(handle)
-        .build()
+    let result = session
+        .query_unpaged(format!("CREATE TYPE {}.udt (x int)", ks), &[])
         .await
         .unwrap();
+    let change = result.schema_change().unwrap();
+    assert_eq!(change.change_type(), SchemaChangeType::Created);
+    assert_eq!(change.object_name(), Some("udt"));
 
-    // Create a keyspace with replication factor that is larger than the cluster size
-    let cluster_size = session.get_cluster_data().get_nodes_info().len();
+    let result = session
+        .query_unpaged(format!("DROP TABLE {}.t", ks), &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        result.schema_change().map(|change| change.change_type()),
+        Some(SchemaChangeType::Dropped)
+    );
+
+    // Statements which don't change the schema have no schema change
+    let result = session
+        .query_unpaged(
+            format!("CREATE TABLE IF NOT EXISTS {}.t2 (a int PRIMARY KEY)", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+    assert!(result.schema_change().is_some());
+    let result = session
+        .query_unpaged(
+            format!("CREATE TABLE IF NOT EXISTS {}.t2 (a int PRIMARY KEY)", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+    assert!(result.schema_change().is_none());
+    let result = session
+        .query_unpaged(format!("SELECT * FROM {}.t2", ks), &[])
+        .await
+        .unwrap();
+    assert!(result.schema_change().is_none());
+}
+
+#[tokio::test]
+async fn test_page_observer_checkpoint() {
+    use std::ops::ControlFlow;
+    use std::sync::Mutex;
+
+    let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
-    session.query(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class': 'NetworkTopologyStrategy', 'replication_factor': {}}}", ks, cluster_size + 1), ()).await.unwrap();
-    session.use_keyspace(ks, true).await.unwrap();
     session
-        .query("CREATE TABLE t (pk int PRIMARY KEY, v int)", ())
+        .query_unpaged(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+        .await
+        .unwrap();
+    session
+        .query_unpaged(
+            format!("CREATE TABLE {}.t (a int, b int, PRIMARY KEY (a, b))", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+    let insert = session
+        .prepare(format!("INSERT INTO {}.t (a, b) VALUES (0, ?)", ks))
         .await
         .unwrap();
+    for b in 0..20 {
+        session.execute_unpaged(&insert, (b,)).await.unwrap();
+    }
+
+    let mut select = Query::new(format!("SELECT b FROM {}.t WHERE a = 0", ks));
+    select.set_page_size(4);
+
+    let checkpoint = Arc::new(Mutex::new(None));
+    let checkpoint_ref = checkpoint.clone();
+    let pages = Arc::new(Mutex::new(Vec::new()));
+    let pages_ref = pages.clone();
+    let read: Vec<i32> = session
+        .query_iter(select.clone(), &[])
+        .await
+        .unwrap()
+        .with_page_observer(move |page| {
+            assert!(page.coordinator.is_some());
+            pages_ref.lock().unwrap().push((page.page, page.rows));
+            if page.page == 2 {
+                *checkpoint_ref.lock().unwrap() = page.paging_state;
+                return ControlFlow::Break(());
+            }
+            ControlFlow::Continue(())
+        })
+        .into_typed::<(i32,)>()
+        .map(|row| row.unwrap().0)
+        .collect()
+        .await;
+    assert_eq!(read, (0..12).collect::<Vec<_>>());
+    assert_eq!(*pages.lock().unwrap(), [(0, 4), (1, 4), (2, 4)]);
+
+    // Resume from the saved paging state
+    let mut paging_state = checkpoint.lock().unwrap().take();
+    assert!(paging_state.is_some());
+    let mut resumed = Vec::new();
+    while paging_state.is_some() {
+        let result = session
+            .query_single_page(select.clone(), &[], paging_state)
+            .await
+            .unwrap();
+        paging_state = result.paging_state.clone();
+        resumed.extend(
+            result
+                .rows_typed::<(i32,)>()
+                .unwrap()
+                .map(|row| row.unwrap().0),
+        );
+    }
+    assert_eq!(resumed, (12..20).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn test_cmp_cql_matches_clustering_order() {
+    use scylla_cql::frame::response::cql_ordering::{ClusteringOrder, SortOrder};
+    use std::net::IpAddr;
+    use std::str::FromStr;
 
-    assert!(!retried_flag.load(Ordering::Relaxed));
-    // Try to write something to the new table - it should fail and the policy
-    // will tell us to ignore the error
-    let mut iter = session
-        .query_iter("INSERT INTO t (pk v) VALUES (1, 2)", ())
-        .await
-        .unwrap();
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
 
-    assert!(retried_flag.load(Ordering::Relaxed));
-    while iter.try_next().await.unwrap().is_some() {}
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(&ks, false).await.unwrap();
+
+    let timeuuid = |msb: u64, lsb: u64| CqlValue::Timeuuid(Uuid::from_u64_pair(msb, lsb));
+    let cases: Vec<(&str, ColumnType, Vec<CqlValue>)> = vec![
+        (
+            "text",
+            ColumnType::Text,
+            ["", "apple", "Zebra", "a", "é", "中", "Z"]
+                .map(|s| CqlValue::Text(s.to_string()))
+                .to_vec(),
+        ),
+        (
+            "blob",
+            ColumnType::Blob,
+            [
+                &[0x80][..],
+                &[0x7f],
+                &[],
+                &[0xff, 0x00],
+                &[0x00, 0x01],
+                &[0xff],
+            ]
+            .map(|b| CqlValue::Blob(b.to_vec()))
+            .to_vec(),
+        ),
+        (
+            "timeuuid",
+            ColumnType::Timeuuid,
+            vec![
+                // time_low of all ones is before a greater time_mid
+                timeuuid(0xFFFF_FFFF_0000_1000, 0x8000_0000_0000_0000),
+                timeuuid(0x0000_0000_0001_1000, 0x8000_0000_0000_0000),
+                timeuuid(0x0000_0001_0000_1000, 0x8000_0000_0000_0000),
+                timeuuid(0x0000_0001_0000_1000, 0x0000_0000_0000_0000),
+                timeuuid(0x0000_0001_0000_1000, 0x7f00_0000_0000_0000),
+            ],
+        ),
+        (
+            "uuid",
+            ColumnType::Uuid,
+            [
+                0x00000000_0000_4000_8000_000000000000,
+                0x80000000_0000_4000_8000_000000000000,
+                0xffffffff_0000_1000_8000_000000000000,
+                0x00000000_0001_1000_8000_000000000000,
+                0x00000000_0000_5000_8000_000000000000,
+            ]
+            .map(|uuid| CqlValue::Uuid(Uuid::from_u128(uuid)))
+            .to_vec(),
+        ),
+        (
+            "inet",
+            ColumnType::Inet,
+            ["255.255.255.255", "::1", "10.0.0.1", "ff02::1", "0.0.0.0"]
+                .map(|addr| CqlValue::Inet(IpAddr::from_str(addr).unwrap()))
+                .to_vec(),
+        ),
+        (
+            "double",
+            ColumnType::Double,
+            [1.5, -0.0, f64::NEG_INFINITY, 0.0, -1.5, f64::INFINITY]
+                .map(CqlValue::Double)
+                .to_vec(),
+        ),
+        (
+            "varint",
+            ColumnType::Varint,
+            [-300, 1, 0, -1, 300]
+                .map(|v| CqlValue::Varint(v.into()))
+                .to_vec(),
+        ),
+        (
+            "frozen<list<int>>",
+            ColumnType::List(Box::new(ColumnType::Int)),
+            [&[1, 0][..], &[], &[-1], &[-1, 0], &[0]]
+                .map(|l| CqlValue::List(l.iter().copied().map(CqlValue::Int).collect()))
+                .to_vec(),
+        ),
+        (
+            "frozen<tuple<int, text>>",
+            ColumnType::Tuple(vec![ColumnType::Int, ColumnType::Text]),
+            [(2, "a"), (1, "b"), (1, "B"), (-1, "")]
+                .map(|(i, s)| {
+                    CqlValue::Tuple(vec![
+                        Some(CqlValue::Int(i)),
+                        Some(CqlValue::Text(s.to_string())),
+                    ])
+                })
+                .to_vec(),
+        ),
+    ];
 
-    retried_flag.store(false, Ordering::Relaxed);
-    // Try the same with execute_iter()
-    let p = session
-        .prepare("INSERT INTO t (pk, v) VALUES (?, ?)")
-        .await
-        .unwrap();
-    let mut iter = session.execute_iter(p, (1, 2)).await.unwrap();
+    for (idx, (cql_type, typ, values)) in cases.into_iter().enumerate() {
+        for (order, sort_order) in [
+            ("ASC", SortOrder::Ascending),
+            ("DESC", SortOrder::Descending),
+        ] {
+            let table = format!("ord_{}_{}", idx, order);
+            session
+                .query_unpaged(
+                    format!(
+                        "CREATE TABLE {} (pk int, ck {}, PRIMARY KEY (pk, ck)) WITH CLUSTERING ORDER BY (ck {})",
+                        table, cql_type, order
+                    ),
+                    &[],
+                )
+                .await
+                .unwrap();
+            let insert = session
+                .prepare(format!("INSERT INTO {} (pk, ck) VALUES (0, ?)", table))
+                .await
+                .unwrap();
+            for value in &values {
+                session.execute_unpaged(&insert, (value,)).await.unwrap();
+            }
 
-    assert!(retried_flag.load(Ordering::Relaxed));
-    while iter.try_next().await.unwrap().is_some() {}
+            let rows = session
+                .query_unpaged(format!("SELECT ck FROM {} WHERE pk = 0", table), &[])
+                .await
+                .unwrap()
+                .rows()
+                .unwrap();
+            assert_eq!(rows.len(), values.len(), "{}", cql_type);
+            let clustering_order = ClusteringOrder::new(vec![(typ.clone(), sort_order)]);
+            for pair in rows.windows(2) {
+                assert_eq!(
+                    clustering_order.compare(&pair[0].columns, &pair[1].columns),
+                    Ok(std::cmp::Ordering::Less),
+                    "{} {}: {:?}",
+                    cql_type,
+                    order,
+                    pair
+                );
+            }
+        }
+    }
 }
 
 #[tokio::test]
-async fn test_iter_methods_with_modification_statements() {
+async fn test_unpaged_and_single_page_methods() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
-
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session
-        .query(
-            format!(
-                "CREATE TABLE IF NOT EXISTS {}.t (a int, b int, c text, primary key (a, b))",
-                ks
-            ),
+        .query_unpaged(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+        .await
+        .unwrap();
+    session
+        .query_unpaged(
+            format!("CREATE TABLE {}.t (a int, b int, PRIMARY KEY (a, b))", ks),
             &[],
         )
         .await
         .unwrap();
+    let insert = session
+        .prepare(format!("INSERT INTO {}.t (a, b) VALUES (0, ?)", ks))
+        .await
+        .unwrap();
+    for b in 0..100 {
+        session.execute_unpaged(&insert, (b,)).await.unwrap();
+    }
 
-    let mut query = Query::from(format!(
-        "INSERT INTO {}.t (a, b, c) VALUES (1, 2, 'abc')",
-        ks
-    ));
-    query.set_tracing(true);
-    let mut row_iterator = session.query_iter(query, &[]).await.unwrap();
-    row_iterator.next().await.ok_or(()).unwrap_err(); // assert empty
-    assert!(!row_iterator.get_tracing_ids().is_empty());
+    let all_rows: Vec<i32> = (0..100).collect();
+    let read = |result: QueryResult| -> Vec<i32> {
+        result
+            .rows_typed::<(i32,)>()
+            .unwrap()
+            .map(|row| row.unwrap().0)
+            .collect()
+    };
 
-    let prepared_statement = session
-        .prepare(format!("INSERT INTO {}.t (a, b, c) VALUES (?, ?, ?)", ks))
+    let select = Query::new(format!("SELECT b FROM {}.t WHERE a = 0", ks)).with_page_size(7);
+    let prepared = session.prepare(select.clone()).await.unwrap();
+
+    // Unpaged methods fetch all pages
+    let result = session.query_unpaged(select.clone(), &[]).await.unwrap();
+    assert!(result.paging_state.is_none());
+    assert_eq!(read(result), all_rows);
+    let result = session.execute_unpaged(&prepared, &[]).await.unwrap();
+    assert!(result.paging_state.is_none());
+    assert_eq!(read(result), all_rows);
+
+    // Single page methods fetch one page of the given size
+    let result = session
+        .query_single_page(select.clone(), &[], None)
         .await
         .unwrap();
-    let mut row_iterator = session
-        .execute_iter(prepared_statement, (2, 3, "cba"))
+    assert!(result.paging_state.is_some());
+    assert_eq!(read(result), (0..7).collect::<Vec<_>>());
+    let first_page = session
+        .execute_single_page(&prepared, &[], None)
         .await
         .unwrap();
-    row_iterator.next().await.ok_or(()).unwrap_err(); // assert empty
-}
-
-#[tokio::test]
-async fn test_get_keyspace_name() {
-    let ks = unique_keyspace_name();
-
-    // Create the keyspace
-    // No keyspace is set in config, so get_keyspace() should return None.
-    let session = create_new_session_builder().build().await.unwrap();
-    assert_eq!(session.get_keyspace(), None);
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
-    assert_eq!(session.get_keyspace(), None);
+    let result = session
+        .execute_single_page(&prepared, &[], first_page.paging_state)
+        .await
+        .unwrap();
+    assert!(result.paging_state.is_some());
+    assert_eq!(read(result), (7..14).collect::<Vec<_>>());
 
-    // Call use_keyspace(), get_keyspace now should return the new keyspace name
-    session.use_keyspace(&ks, true).await.unwrap();
-    assert_eq!(*session.get_keyspace().unwrap(), ks);
+    // Iterators go through all pages
+    let rows: Vec<i32> = session
+        .query_iter(select.clone(), &[])
+        .await
+        .unwrap()
+        .into_typed::<(i32,)>()
+        .map(|row| row.unwrap().0)
+        .collect()
+        .await;
+    assert_eq!(rows, all_rows);
+    let rows: Vec<i32> = session
+        .execute_iter(prepared.clone(), &[])
+        .await
+        .unwrap()
+        .into_typed::<(i32,)>()
+        .map(|row| row.unwrap().0)
+        .collect()
+        .await;
+    assert_eq!(rows, all_rows);
 
-    // Creating a new session with the keyspace set in config should cause
-    // get_keyspace to return that name
-    let session = create_new_session_builder()
-        .use_keyspace(&ks, true)
-        .build()
+    // Without a page size, single page methods use the default one
+    let mut unsized_select = select.clone();
+    unsized_select.disable_paging();
+    let result = session
+        .query_single_page(unsized_select, &[], None)
         .await
         .unwrap();
-    assert_eq!(*session.get_keyspace().unwrap(), ks);
+    assert!(result.paging_state.is_none());
+    assert_eq!(read(result), all_rows);
 }
 
-/// It's recommended to use NetworkTopologyStrategy everywhere, so most tests use only NetworkTopologyStrategy.
-/// We still support SimpleStrategy, so to make sure that SimpleStrategy works correctly this test runs
-/// a few queries in a SimpleStrategy keyspace.
 #[tokio::test]
-async fn simple_strategy_test() {
-    let ks = unique_keyspace_name();
-    let session = create_new_session_builder().build().await.unwrap();
+async fn test_check_statement_types() {
+    use crate::transport::schema_check::{
+        check_statement, check_statement_values, ColumnMismatch, StatementCheckError,
+    };
 
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
     session
-        .query(
-            format!(
-                "CREATE KEYSPACE {} WITH REPLICATION = \
-                {{'class': 'SimpleStrategy', 'replication_factor': 1}}",
-                ks
-            ),
-            (),
-        )
+        .query_unpaged(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
         .await
         .unwrap();
-
     session
-        .query(
+        .query_unpaged(
             format!(
-                "CREATE TABLE {}.tab (p int, c int, r int, PRIMARY KEY (p, c, r))",
+                "CREATE TABLE {}.users (id int PRIMARY KEY, name text, age bigint)",
                 ks
             ),
-            (),
+            &[],
         )
         .await
         .unwrap();
 
-    session
-        .query(
-            format!("INSERT INTO {}.tab (p, c, r) VALUES (1, 2, 3)", ks),
-            (),
-        )
-        .await
-        .unwrap();
+    let select = format!("SELECT name, age FROM {}.users WHERE id = ?", ks);
+    let insert = format!("INSERT INTO {}.users (id, name, age) VALUES (?, ?, ?)", ks);
 
-    session
-        .query(
-            format!("INSERT INTO {}.tab (p, c, r) VALUES (?, ?, ?)", ks),
-            (4, 5, 6),
-        )
+    check_statement::<(String, i64), (i32,)>(&session, select.as_str())
         .await
         .unwrap();
-
-    let prepared = session
-        .prepare(format!("INSERT INTO {}.tab (p, c, r) VALUES (?, ?, ?)", ks))
+    check_statement_values::<(i32, String, i64)>(&session, insert.as_str())
         .await
         .unwrap();
+    crate::assert_statement_types!(&session, select.as_str(), (Option<String>, i64), (i32,));
+    crate::assert_statement_types!(&session, insert.as_str(), (i32, String, i64));
 
-    session.execute(&prepared, (7, 8, 9)).await.unwrap();
-
-    let mut rows: Vec<(i32, i32, i32)> = session
-        .query(format!("SELECT p, c, r FROM {}.tab", ks), ())
+    // Both the bind marker and the column are reported
+    let error = check_statement::<(String, i32), (i64,)>(&session, select.as_str())
         .await
-        .unwrap()
-        .rows_typed::<(i32, i32, i32)>()
-        .unwrap()
-        .map(|r| r.unwrap())
-        .collect::<Vec<(i32, i32, i32)>>();
-    rows.sort();
+        .unwrap_err();
+    let mismatch = match error {
+        StatementCheckError::Mismatch(mismatch) => mismatch,
+        error => panic!("Unexpected error: {}", error),
+    };
+    assert_eq!(mismatch.statement, select);
+    assert_matches!(
+        &mismatch.mismatches[..],
+        [
+            ColumnMismatch::BindMarker {
+                cql_type: ColumnType::Int,
+                accepted_type: Some(ColumnType::BigInt),
+                ..
+            },
+            ColumnMismatch::ResultColumn {
+                index: 1,
+                cql_type: ColumnType::BigInt,
+                accepted_type: Some(ColumnType::Int),
+                ..
+            },
+        ]
+    );
 
-    assert_eq!(rows, vec![(1, 2, 3), (4, 5, 6), (7, 8, 9)]);
+    let error = check_statement_values::<(i32, String)>(
+        &session,
+        format!("INSERT INTO {}.users (id, missing) VALUES (?, ?)", ks),
+    )
+    .await
+    .unwrap_err();
+    assert_matches!(error, StatementCheckError::Prepare(_));
 }
 
 #[tokio::test]
-async fn test_manual_primary_key_computation() {
-    // Setup session
-    let ks = unique_keyspace_name();
+async fn test_background_tasks_status() {
+    use crate::transport::background_tasks::BackgroundTaskKind;
+
     let session = create_new_session_builder().build().await.unwrap();
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
-    session.use_keyspace(&ks, true).await.unwrap();
+    let tasks = session.background_tasks();
 
-    async fn assert_tokens_equal(
-        session: &Session,
-        prepared: &PreparedStatement,
-        serialized_pk_values_in_pk_order: &SerializedValues,
-        all_values_in_query_order: impl SerializeRow,
-    ) {
-        let token_by_prepared = prepared
-            .calculate_token(&all_values_in_query_order)
-            .unwrap()
-            .unwrap();
+    let status = session.background_tasks_status();
+    let has_kind = |kind| status.iter().any(|task| task.kind == kind);
+    assert!(has_kind(BackgroundTaskKind::ClusterWorker));
+    assert!(has_kind(BackgroundTaskKind::ConnectionRouter));
+    assert!(status
+        .iter()
+        .any(|task| task.kind == BackgroundTaskKind::PoolRefiller && task.node.is_some()));
+    assert!(status.iter().all(|task| task.last_activity >= task.started));
+
+    // The tasks stop together with the session
+    drop(session);
+    tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        while !tasks.status().is_empty() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+}
 
-        session
-            .execute(prepared, all_values_in_query_order)
-            .await
-            .unwrap();
+#[tokio::test]
+async fn test_retry_delays_are_measured_by_session_runtime() {
+    use crate::transport::runtime::TestRuntime;
+    use std::time::Duration;
 
-        let token_by_hand = calculate_token_for_partition_key(
-            serialized_pk_values_in_pk_order,
-            &Murmur3Partitioner,
-        )
-        .unwrap();
-        println!(
-            "by_prepared: {}, by_hand: {}",
-            token_by_prepared.value, token_by_hand.value
-        );
-        assert_eq!(token_by_prepared, token_by_hand);
+    // Retries the first failure after a delay
+    #[derive(Debug)]
+    struct DelayedRetryPolicy(Arc<AtomicBool>);
+    impl RetryPolicy for DelayedRetryPolicy {
+        fn new_session(&self) -> Box<dyn RetrySession> {
+            Box::new(DelayedRetrySession(self.0.clone()))
+        }
+        fn clone_boxed(&self) -> Box<dyn RetryPolicy> {
+            Box::new(DelayedRetryPolicy(self.0.clone()))
+        }
     }
 
-    // Single-column partition key
-    {
-        session
-            .query(
-                "CREATE TABLE IF NOT EXISTS t2 (a int, b int, c text, primary key (a, b))",
-                &[],
-            )
-            .await
-            .unwrap();
-
-        // Values are given non partition key order,
-        let prepared_simple_pk = session
-            .prepare("INSERT INTO t2 (a, b, c) VALUES (?, ?, ?)")
-            .await
-            .unwrap();
-
-        let mut pk_values_in_pk_order = SerializedValues::new();
-        pk_values_in_pk_order
-            .add_value(&17_i32, &ColumnType::Int)
-            .unwrap();
-        let all_values_in_query_order = (17_i32, 16_i32, "I'm prepared!!!");
-
-        assert_tokens_equal(
-            &session,
-            &prepared_simple_pk,
-            &pk_values_in_pk_order,
-            all_values_in_query_order,
-        )
-        .await;
+    struct DelayedRetrySession(Arc<AtomicBool>);
+    impl RetrySession for DelayedRetrySession {
+        fn decide_should_retry(&mut self, _: QueryInfo) -> RetryDecision {
+            if self.0.swap(true, Ordering::Relaxed) {
+                RetryDecision::DontRetry
+            } else {
+                RetryDecision::RetryNextNodeAfter(None, Duration::from_secs(10))
+            }
+        }
+        fn reset(&mut self) {}
     }
 
-    // Composite partition key
-    {
-        session
-            .query("CREATE TABLE IF NOT EXISTS complex_pk (a int, b int, c text, d int, e int, primary key ((a,b,c),d))", &[])
-            .await
-            .unwrap();
+    let retried_flag = Arc::new(AtomicBool::new(false));
+    let handle = ExecutionProfile::builder()
+        .retry_policy(Box::new(DelayedRetryPolicy(retried_flag.clone())))
+        .build()
+        .into_handle();
 
-        // Values are given in non partition key order, to check that such permutation
-        // still yields consistent hashes.
-        let prepared_complex_pk = session
-            .prepare("INSERT INTO complex_pk (a, d, c, b) VALUES (?, 7, ?, ?)")
+    let runtime = TestRuntime::new();
+    let session = Arc::new(
+        create_new_session_builder()
+            .default_execution_profile_handle(handle)
+            .runtime(Arc::new(runtime.clone()))
+            .build()
             .await
-            .unwrap();
-
-        let mut pk_values_in_pk_order = SerializedValues::new();
-        pk_values_in_pk_order
-            .add_value(&17_i32, &ColumnType::Int)
-            .unwrap();
-        pk_values_in_pk_order
-            .add_value(&16_i32, &ColumnType::Int)
-            .unwrap();
-        pk_values_in_pk_order
-            .add_value(&"I'm prepared!!!", &ColumnType::Ascii)
-            .unwrap();
-        let all_values_in_query_order = (17_i32, "I'm prepared!!!", 16_i32);
+            .unwrap(),
+    );
 
-        assert_tokens_equal(
-            &session,
-            &prepared_complex_pk,
-            &pk_values_in_pk_order,
-            all_values_in_query_order,
-        )
-        .await;
+    let ks = unique_keyspace_name();
+    let query = tokio::spawn({
+        let session = session.clone();
+        async move {
+            session
+                .query_unpaged(format!("SELECT * FROM {}.missing", ks), ())
+                .await
+        }
+    });
+    while !retried_flag.load(Ordering::Relaxed) && !query.is_finished() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
     }
+
+    // The retry waits for the runtime's clock, not for the wall clock
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!query.is_finished());
+    runtime.advance(Duration::from_secs(10)).await;
+    let err = query.await.unwrap().unwrap_err();
+    assert_matches!(err, QueryError::DbError(DbError::Invalid, _));
 }
@@ -0,0 +1,407 @@
+//! Grouping rows written with prepared statements into batches by their target replica and shard.
+//!
+//! An unlogged batch spanning many partitions is split by its coordinator, which sends
+//! the parts to their replicas, so it's not faster than executing the statements one by one.
+//! A batch whose statements all go to the same replica (and shard), on the other hand, saves
+//! round trips. [`ShardAwareBatcher`] computes the token of every added row, and groups the rows
+//! by the node owning the token in the ring and by the shard of the token on that node.
+//! The resulting [`TargetedBatch`]es are capped by the number of statements and the size
+//! of the values, and are meant to be executed concurrently by the caller.
+
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::batch::Batch;
+use crate::prepared_statement::PreparedStatement;
+use crate::routing::{Shard, Token};
+use crate::serialize::row::SerializeRow;
+use crate::serialize::SerializationError;
+use crate::transport::ClusterData;
+
+/// Where the statements of a [`TargetedBatch`] go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BatchTarget {
+    /// The node which owns the tokens of the rows in the ring, and the shard of the tokens
+    /// on that node, if the node is sharded and its sharding is known.
+    Replica { host_id: Uuid, shard: Option<Shard> },
+    /// Rows whose token couldn't be computed, e.g. because their statements don't have
+    /// the whole partition key bound, or whose token isn't owned by any known node.
+    Unknown,
+}
+
+/// A batch of statements going to the same [`BatchTarget`], together with the values
+/// of the statements.
+#[derive(Clone)]
+pub struct TargetedBatch<V> {
+    pub target: BatchTarget,
+    pub batch: Batch,
+    pub values: Vec<V>,
+    size: usize,
+}
+
+impl<V> TargetedBatch<V> {
+    fn new(target: BatchTarget, template: &Batch) -> Self {
+        let mut batch = template.clone();
+        batch.statements.clear();
+        Self {
+            target,
+            batch,
+            values: Vec::new(),
+            size: 0,
+        }
+    }
+
+    /// The total size of the serialized values of the statements, in bytes.
+    pub fn values_size(&self) -> usize {
+        self.size
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// Groups rows written with prepared statements into batches by their target replica
+/// and shard, see the [module documentation](self).
+///
+/// # Example
+/// ```rust
+/// # use scylla::Session;
+/// # use std::error::Error;
+/// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+/// use futures::future::try_join_all;
+/// use scylla::batch::{Batch, BatchType};
+/// use scylla::transport::shard_aware_batcher::ShardAwareBatcher;
+///
+/// let insert = session
+///     .prepare("INSERT INTO ks.tab (a, b) VALUES (?, ?)")
+///     .await?;
+///
+/// let mut batcher = ShardAwareBatcher::new(
+///     session.get_cluster_data(),
+///     Batch::new(BatchType::Unlogged),
+/// );
+/// for a in 0..1000 {
+///     batcher.add(&insert, (a, a * 2))?;
+/// }
+///
+/// let batches = batcher.finish();
+/// try_join_all(
+///     batches
+///         .iter()
+///         .map(|targeted| session.batch(&targeted.batch, &targeted.values)),
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ShardAwareBatcher<V> {
+    cluster_data: Arc<ClusterData>,
+    template: Batch,
+    max_statements: usize,
+    max_values_size: usize,
+    open: HashMap<BatchTarget, TargetedBatch<V>>,
+    full: Vec<TargetedBatch<V>>,
+}
+
+impl<V: SerializeRow> ShardAwareBatcher<V> {
+    /// Creates a batcher routing rows according to `cluster_data`. The created batches
+    /// are copies of `template`, without its statements, so they have its type and options.
+    ///
+    /// By default, batches have at most 100 statements, and values of at most 5 KiB,
+    /// the default size at which the database warns about large batches.
+    pub fn new(cluster_data: Arc<ClusterData>, template: Batch) -> Self {
+        Self {
+            cluster_data,
+            template,
+            max_statements: 100,
+            max_values_size: 5 * 1024,
+            open: HashMap::new(),
+            full: Vec::new(),
+        }
+    }
+
+    /// Sets the maximal number of statements in a batch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_statements` is 0.
+    pub fn max_statements(mut self, max_statements: usize) -> Self {
+        assert!(
+            max_statements > 0,
+            "batches must allow at least one statement"
+        );
+        self.max_statements = max_statements;
+        self
+    }
+
+    /// Sets the maximal total size of serialized values in a batch, in bytes.
+    /// A row larger than that gets a batch of its own.
+    pub fn max_values_size(mut self, max_values_size: usize) -> Self {
+        self.max_values_size = max_values_size;
+        self
+    }
+
+    /// Adds a row written with `prepared` to the batch of its target, which is full once
+    /// it reaches the caps. Fails if the values can't be serialized for the statement.
+    pub fn add(
+        &mut self,
+        prepared: &PreparedStatement,
+        values: V,
+    ) -> Result<(), SerializationError> {
+        let serialized = prepared.serialize_values(&values)?;
+        let token = prepared
+            .partition_key_and_routing_token(&serialized)
+            .ok()
+            .and_then(|(_, token)| token);
+        let target = match token {
+            Some(token) => self.target_of(token),
+            None => BatchTarget::Unknown,
+        };
+        let size = serialized.buffer_size();
+
+        let template = &self.template;
+        let batch = self
+            .open
+            .entry(target)
+            .or_insert_with(|| TargetedBatch::new(target, template));
+        if !batch.is_empty() && batch.size + size > self.max_values_size {
+            let full = mem::replace(batch, TargetedBatch::new(target, template));
+            self.full.push(full);
+        }
+        batch.batch.append_statement(prepared.clone());
+        batch.values.push(values);
+        batch.size += size;
+        if batch.len() >= self.max_statements || batch.size >= self.max_values_size {
+            let full = mem::replace(batch, TargetedBatch::new(target, template));
+            self.full.push(full);
+        }
+        Ok(())
+    }
+
+    /// Takes the batches which reached the caps, so that they can be executed
+    /// while more rows are being added.
+    pub fn take_full(&mut self) -> Vec<TargetedBatch<V>> {
+        mem::take(&mut self.full)
+    }
+
+    /// Returns all batches which weren't taken yet.
+    pub fn finish(self) -> Vec<TargetedBatch<V>> {
+        let mut batches = self.full;
+        batches.extend(self.open.into_values().filter(|batch| !batch.is_empty()));
+        batches
+    }
+
+    fn target_of(&self, token: Token) -> BatchTarget {
+        match self
+            .cluster_data
+            .replica_locator()
+            .ring()
+            .get_elem_for_token(token)
+        {
+            Some(node) => BatchTarget::Replica {
+                host_id: node.host_id,
+                shard: node.sharder().map(|sharder| sharder.shard_of(token)),
+            },
+            None => BatchTarget::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use scylla_cql::frame::response::result::{
+        ColumnSpec, ColumnType, PartitionKeyIndex, PreparedMetadata, TableSpec,
+    };
+    use uuid::Uuid;
+
+    use super::{BatchTarget, ShardAwareBatcher, TargetedBatch};
+    use crate::batch::{Batch, BatchType};
+    use crate::prepared_statement::PreparedStatement;
+    use crate::routing::Token;
+    use crate::transport::locator::test::id_to_invalid_addr;
+    use crate::transport::topology::{Metadata, Peer};
+    use crate::transport::ClusterData;
+
+    // Three nodes owning tokens spread over the whole ring
+    async fn fixture_cluster_data() -> (Arc<ClusterData>, [Uuid; 3]) {
+        let host_ids = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+        let tokens = [
+            vec![-6_000_000_000_000_000_000, 6_000_000_000_000_000_000],
+            vec![-2_000_000_000_000_000_000],
+            vec![2_000_000_000_000_000_000],
+        ];
+        let peers = host_ids
+            .iter()
+            .zip(tokens)
+            .enumerate()
+            .map(|(id, (host_id, tokens))| Peer {
+                datacenter: Some("dc".to_owned()),
+                rack: None,
+                address: id_to_invalid_addr(id as u16 + 1),
+                tokens: tokens.into_iter().map(|value| Token { value }).collect(),
+                host_id: *host_id,
+            })
+            .collect();
+        let metadata = Metadata {
+            peers,
+            keyspaces: HashMap::new(),
+        };
+        let cluster_data =
+            ClusterData::new(metadata, &Default::default(), &HashMap::new(), &None, None).await;
+        (Arc::new(cluster_data), host_ids)
+    }
+
+    fn insert(pk_indexes: Vec<PartitionKeyIndex>) -> PreparedStatement {
+        let table_spec = TableSpec {
+            ks_name: "ks".to_owned(),
+            table_name: "t".to_owned(),
+        };
+        let col_specs = [("a", ColumnType::Int), ("b", ColumnType::Text)]
+            .into_iter()
+            .map(|(name, typ)| ColumnSpec {
+                table_spec: table_spec.clone(),
+                name: name.to_owned(),
+                typ,
+            })
+            .collect();
+        let metadata = PreparedMetadata {
+            flags: 0,
+            col_count: 2,
+            pk_indexes,
+            col_specs,
+        };
+        PreparedStatement::new(
+            Bytes::from_static(b"id"),
+            false,
+            metadata,
+            Default::default(),
+            "INSERT INTO ks.t (a, b) VALUES (?, ?)".to_owned(),
+            None,
+            Default::default(),
+        )
+    }
+
+    fn token_aware_insert() -> PreparedStatement {
+        insert(vec![PartitionKeyIndex {
+            index: 0,
+            sequence: 0,
+        }])
+    }
+
+    fn rows_count(batches: &[TargetedBatch<(i32, String)>]) -> usize {
+        batches.iter().map(|batch| batch.values.len()).sum()
+    }
+
+    #[tokio::test]
+    async fn rows_are_grouped_by_token_owner() {
+        let (cluster_data, host_ids) = fixture_cluster_data().await;
+        let insert = token_aware_insert();
+        let mut batcher =
+            ShardAwareBatcher::new(cluster_data.clone(), Batch::new(BatchType::Unlogged))
+                .max_statements(1000);
+        for a in 0..300 {
+            batcher.add(&insert, (a, a.to_string())).unwrap();
+        }
+        let batches = batcher.finish();
+        assert_eq!(rows_count(&batches), 300);
+
+        // All nodes get some rows, and each row goes to the owner of its token
+        assert_eq!(batches.len(), 3);
+        for batch in &batches {
+            let host_id = match batch.target {
+                BatchTarget::Replica { host_id, shard } => {
+                    assert_eq!(shard, None);
+                    host_id
+                }
+                BatchTarget::Unknown => panic!("Unexpected fallback batch"),
+            };
+            assert!(host_ids.contains(&host_id));
+            assert!(matches!(batch.batch.get_type(), BatchType::Unlogged));
+            assert_eq!(batch.batch.statements.len(), batch.values.len());
+            for values in &batch.values {
+                let token = insert.calculate_token(values).unwrap().unwrap();
+                let owner = cluster_data
+                    .replica_locator()
+                    .ring()
+                    .get_elem_for_token(token)
+                    .unwrap();
+                assert_eq!(owner.host_id, host_id);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn rows_without_token_go_to_fallback_batch() {
+        let (cluster_data, _) = fixture_cluster_data().await;
+        let mut batcher =
+            ShardAwareBatcher::new(cluster_data.clone(), Batch::new(BatchType::Unlogged));
+        let token_unaware = insert(Vec::new());
+        for a in 0..5 {
+            batcher.add(&token_unaware, (a, a.to_string())).unwrap();
+        }
+        // Values not matching the statement are rejected
+        let mut mismatched = ShardAwareBatcher::new(cluster_data, Batch::new(BatchType::Unlogged));
+        assert!(mismatched.add(&token_unaware, (1, 2)).is_err());
+        assert!(mismatched.finish().is_empty());
+
+        let batches = batcher.finish();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].target, BatchTarget::Unknown);
+        assert_eq!(batches[0].values.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn batches_respect_caps() {
+        let (cluster_data, _) = fixture_cluster_data().await;
+        let insert = token_aware_insert();
+        let row_size = insert
+            .serialize_values(&(0, "x".repeat(10)))
+            .unwrap()
+            .buffer_size();
+
+        // Batches are capped by the number of statements
+        let mut batcher =
+            ShardAwareBatcher::new(cluster_data.clone(), Batch::new(BatchType::Unlogged))
+                .max_statements(7)
+                .max_values_size(usize::MAX);
+        for a in 0..200 {
+            batcher.add(&insert, (a, "x".repeat(10))).unwrap();
+        }
+        let full = batcher.take_full();
+        assert!(!full.is_empty());
+        assert!(full.iter().all(|batch| batch.values.len() == 7));
+        assert!(batcher.take_full().is_empty());
+        let rest = batcher.finish();
+        assert!(rest.iter().all(|batch| batch.values.len() < 7));
+        assert_eq!(rows_count(&full) + rows_count(&rest), 200);
+
+        // Batches are capped by the size of values, and a larger row gets a batch of its own
+        let mut batcher = ShardAwareBatcher::new(cluster_data, Batch::new(BatchType::Unlogged))
+            .max_values_size(3 * row_size + 1);
+        for a in 0..200 {
+            batcher.add(&insert, (a, "x".repeat(10))).unwrap();
+        }
+        batcher.add(&insert, (200, "x".repeat(100))).unwrap();
+        let batches = batcher.finish();
+        assert_eq!(rows_count(&batches), 201);
+        for batch in &batches {
+            assert!(batch.values.len() <= 3);
+            assert!(batch.values_size() <= 3 * row_size + 1 || batch.values.len() == 1);
+        }
+        assert!(batches
+            .iter()
+            .any(|batch| batch.values.len() == 1 && batch.values[0].0 == 200));
+    }
+}
@@ -0,0 +1,328 @@
+//! Reporting requests that take longer than a configured threshold.
+//!
+//! Enabled with [`SessionBuilder::slow_query_threshold`](crate::transport::session_builder::GenericSessionBuilder::slow_query_threshold).
+//! By default slow requests are logged as `tracing` events at the `WARN` level.
+//! An application can receive them instead by installing a [`SlowQueryListener`] with
+//! [`SessionBuilder::slow_query_listener`](crate::transport::session_builder::GenericSessionBuilder::slow_query_listener).
+
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tracing::warn;
+
+use crate::history::{AttemptOutcome, ExecutionHistory};
+use crate::retry_policy::RetryDecision;
+use crate::statement::{truncate_statement, Consistency, SerialConsistency, StatementConfig};
+use crate::transport::errors::{ExecutionError, QueryError};
+use crate::QueryResult;
+
+/// Statements longer than this (in bytes) are truncated in reports of slow requests.
+const MAX_STATEMENT_LENGTH: usize = 256;
+
+/// A request which took longer than the configured threshold.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct SlowQueryEvent<'a> {
+    /// CQL text of the statement, truncated if it is long.
+    /// For batches, texts of all statements separated with `; `.
+    pub statement: &'a str,
+    /// [Label](crate::query::Query::set_label) of the statement, if it has one.
+    pub label: Option<&'a str>,
+    /// Time the whole request took, including all retries and speculative executions.
+    /// For paging iterators, time it took to fetch a single page.
+    pub elapsed: Duration,
+    /// Node which handled the last attempt of the request, if any attempt was made.
+    pub coordinator: Option<SocketAddr>,
+    /// Number of retries decided by the retry policy.
+    pub retries: usize,
+    /// Whether at least one speculative execution was started.
+    pub speculative_execution_fired: bool,
+    /// Whether the request fetched a page for a paging iterator
+    /// ([`Session::query_iter`](crate::Session::query_iter) and
+    /// [`Session::execute_iter`](crate::Session::execute_iter)).
+    pub paged: bool,
+    /// Error that the request failed with, `None` if it succeeded.
+    pub error: Option<&'a QueryError>,
+}
+
+/// Receives requests which took longer than the configured slow query threshold.
+///
+/// When a listener is installed, slow requests are no longer logged by the driver.
+/// The listener is called on the hot path of request execution,
+/// so it should be cheap and must never block.
+pub trait SlowQueryListener: Debug + Send + Sync {
+    /// Called after a slow request completes.
+    fn on_slow_query(&self, event: &SlowQueryEvent<'_>);
+}
+
+/// Per-request data gathered while executing it,
+/// used in reports of slow requests and in [`ExecutionError`]s.
+#[derive(Debug, Default)]
+pub(crate) struct RequestStats {
+    label: Option<Arc<str>>,
+    attempts: AtomicUsize,
+    retries: AtomicUsize,
+    speculative_executions: AtomicUsize,
+    coordinator: Mutex<Option<SocketAddr>>,
+    consistency: Mutex<Option<(Consistency, Option<SerialConsistency>)>>,
+    // `None` unless collecting the history is enabled on the statement
+    history: Option<Mutex<ExecutionHistory>>,
+}
+
+impl RequestStats {
+    /// Creates stats of a request of a statement with the given label.
+    pub(crate) fn with_label(label: Option<Arc<str>>) -> Self {
+        Self {
+            label,
+            ..Default::default()
+        }
+    }
+
+    /// Creates stats of a request of the statement, collecting the history
+    /// of its attempts if the statement asks for it.
+    pub(crate) fn for_statement(config: &StatementConfig) -> Self {
+        Self {
+            label: config.label.clone(),
+            history: config
+                .collect_history
+                .then(|| Mutex::new(ExecutionHistory::new())),
+            ..Default::default()
+        }
+    }
+
+    /// Records the start of an attempt in the history, if it is collected.
+    /// Returns the id of the attempt in the history.
+    pub(crate) fn record_attempt_start(
+        &self,
+        node: SocketAddr,
+        speculative: bool,
+    ) -> Option<usize> {
+        self.history
+            .as_ref()?
+            .lock()
+            .unwrap()
+            .start_attempt(node, speculative)
+    }
+
+    pub(crate) fn record_attempt_success(&self, attempt: Option<usize>, latency: Duration) {
+        if let (Some(history), Some(attempt)) = (&self.history, attempt) {
+            history
+                .lock()
+                .unwrap()
+                .finish_attempt(attempt, latency, AttemptOutcome::Success);
+        }
+    }
+
+    pub(crate) fn record_attempt_error(
+        &self,
+        attempt: Option<usize>,
+        latency: Duration,
+        error: &QueryError,
+        decision: &RetryDecision,
+    ) {
+        if let (Some(history), Some(attempt)) = (&self.history, attempt) {
+            history.lock().unwrap().finish_attempt(
+                attempt,
+                latency,
+                AttemptOutcome::Error {
+                    error: error.clone(),
+                    decision: decision.clone(),
+                },
+            );
+        }
+    }
+
+    fn take_history(&self) -> Option<ExecutionHistory> {
+        self.history
+            .as_ref()
+            .map(|history| std::mem::take(&mut *history.lock().unwrap()))
+    }
+
+    pub(crate) fn inc_attempts(&self) {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn inc_retries(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn inc_speculative_executions(&self) {
+        self.speculative_executions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_coordinator(&self, coordinator: SocketAddr) {
+        *self.coordinator.lock().unwrap() = Some(coordinator);
+    }
+
+    /// Records the consistencies with which the last attempt was sent.
+    pub(crate) fn record_consistency(
+        &self,
+        consistency: Consistency,
+        serial_consistency: Option<SerialConsistency>,
+    ) {
+        *self.consistency.lock().unwrap() = Some((consistency, serial_consistency));
+    }
+
+    pub(crate) fn execution_error(&self, error: QueryError, is_idempotent: bool) -> ExecutionError {
+        let error = ExecutionError::new(
+            error,
+            *self.coordinator.lock().unwrap(),
+            self.attempts.load(Ordering::Relaxed),
+            is_idempotent,
+        );
+        let error = match *self.consistency.lock().unwrap() {
+            Some((consistency, serial_consistency)) => {
+                error.with_consistency(consistency, serial_consistency)
+            }
+            None => error,
+        };
+        match self.take_history() {
+            Some(history) => error.with_execution_history(history),
+            None => error,
+        }
+    }
+
+    /// Fills in the consistencies with which the request was sent,
+    /// and the history of its attempts.
+    pub(crate) fn query_result(&self, mut result: QueryResult) -> QueryResult {
+        if let Some((consistency, serial_consistency)) = *self.consistency.lock().unwrap() {
+            result.consistency = Some(consistency);
+            result.serial_consistency = serial_consistency;
+        }
+        result.execution_history = self.take_history().map(Box::new);
+        result
+    }
+}
+
+/// Reports requests which took longer than the threshold,
+/// either to the listener or as `tracing` events.
+#[derive(Debug, Clone)]
+pub(crate) struct SlowQueryLog {
+    threshold: Duration,
+    listener: Option<Arc<dyn SlowQueryListener>>,
+}
+
+impl SlowQueryLog {
+    /// Returns `None` if no threshold is set, i.e. slow requests are not reported.
+    pub(crate) fn new(
+        threshold: Option<Duration>,
+        listener: Option<Arc<dyn SlowQueryListener>>,
+    ) -> Option<Self> {
+        threshold.map(|threshold| Self {
+            threshold,
+            listener,
+        })
+    }
+
+    /// Reports the request if `elapsed` exceeds the threshold.
+    /// `statement` is only called for slow requests.
+    pub(crate) fn report<'s>(
+        &self,
+        elapsed: Duration,
+        statement: impl FnOnce() -> Cow<'s, str>,
+        stats: &RequestStats,
+        paged: bool,
+        error: Option<&QueryError>,
+    ) {
+        if elapsed <= self.threshold {
+            return;
+        }
+
+        let statement = statement();
+        let event = SlowQueryEvent {
+            statement: truncate_statement(&statement, MAX_STATEMENT_LENGTH),
+            label: stats.label.as_deref(),
+            elapsed,
+            coordinator: *stats.coordinator.lock().unwrap(),
+            retries: stats.retries.load(Ordering::Relaxed),
+            speculative_execution_fired: stats.speculative_executions.load(Ordering::Relaxed) > 0,
+            paged,
+            error,
+        };
+
+        match &self.listener {
+            Some(listener) => listener.on_slow_query(&event),
+            None => warn!(
+                statement = event.statement,
+                label = event.label,
+                elapsed_ms = event.elapsed.as_millis() as u64,
+                threshold_ms = self.threshold.as_millis() as u64,
+                coordinator = ?event.coordinator,
+                retries = event.retries,
+                speculative_execution_fired = event.speculative_execution_fired,
+                paged = event.paged,
+                error = ?event.error,
+                "Slow query"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RequestStats, SlowQueryEvent, SlowQueryListener, SlowQueryLog};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[derive(Debug, Default)]
+    struct CollectingListener {
+        events: Mutex<Vec<(String, Duration, usize, bool)>>,
+    }
+
+    impl SlowQueryListener for CollectingListener {
+        fn on_slow_query(&self, event: &SlowQueryEvent<'_>) {
+            self.events.lock().unwrap().push((
+                event.statement.to_owned(),
+                event.elapsed,
+                event.retries,
+                event.speculative_execution_fired,
+            ));
+        }
+    }
+
+    #[test]
+    fn reports_only_requests_above_threshold() {
+        let listener = Arc::new(CollectingListener::default());
+        let log =
+            SlowQueryLog::new(Some(Duration::from_millis(100)), Some(listener.clone())).unwrap();
+
+        let stats = RequestStats::default();
+        stats.inc_retries();
+        stats.inc_speculative_executions();
+        stats.record_coordinator("127.0.0.1:9042".parse().unwrap());
+
+        log.report(
+            Duration::from_millis(100),
+            || "SELECT fast".into(),
+            &stats,
+            false,
+            None,
+        );
+        log.report(
+            Duration::from_millis(150),
+            || "SELECT slow".into(),
+            &stats,
+            false,
+            None,
+        );
+
+        assert_eq!(
+            *listener.events.lock().unwrap(),
+            vec![(
+                "SELECT slow".to_owned(),
+                Duration::from_millis(150),
+                1,
+                true
+            )]
+        );
+    }
+
+    #[test]
+    fn no_threshold_disables_reporting() {
+        assert!(SlowQueryLog::new(None, Some(Arc::new(CollectingListener::default()))).is_none());
+    }
+}
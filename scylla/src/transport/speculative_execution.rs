@@ -5,7 +5,7 @@ use futures::{
 use std::{future::Future, sync::Arc, time::Duration};
 use tracing::{trace_span, warn, Instrument};
 
-use super::{errors::QueryError, metrics::Metrics};
+use super::{errors::QueryError, metrics::Metrics, runtime::Runtime};
 
 /// Context is passed as an argument to `SpeculativeExecutionPolicy` methods
 pub struct Context {
@@ -93,6 +93,7 @@ const EMPTY_PLAN_ERROR: QueryError = QueryError::ProtocolError("Empty query plan
 
 pub(crate) async fn execute<QueryFut, ResT>(
     policy: &dyn SpeculativeExecutionPolicy,
+    runtime: &dyn Runtime,
     context: &Context,
     query_runner_generator: impl Fn(bool) -> QueryFut,
 ) -> Result<ResT, QueryError>
@@ -108,7 +109,7 @@ where
             .instrument(trace_span!("Speculative execution: original query")),
     );
 
-    let sleep = tokio::time::sleep(retry_interval).fuse();
+    let sleep = runtime.sleep(retry_interval).fuse();
     tokio::pin!(sleep);
 
     let mut last_error = None;
@@ -120,7 +121,7 @@ where
                     retries_remaining -= 1;
 
                     // reset the timeout
-                    sleep.set(tokio::time::sleep(retry_interval).fuse());
+                    sleep.set(runtime.sleep(retry_interval).fuse());
                 }
             }
             res = async_tasks.select_next_some() => {
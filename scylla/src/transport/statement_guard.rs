@@ -0,0 +1,381 @@
+//! Guardrails denying statements before they are sent.
+//!
+//! A [`StatementGuard`] is consulted for every `query`, `execute` and `batch` request
+//! (including their paged and iterator variants) and may deny it with a reason.
+//! A denied request is not sent - it fails with [`QueryError::DeniedByGuard`] and is
+//! counted in [`Metrics::get_denied_statements`](crate::transport::metrics::Metrics::get_denied_statements).
+//! Guards are installed with
+//! [`SessionBuilder::statement_guard`](crate::transport::session_builder::GenericSessionBuilder::statement_guard).
+//!
+//! The built-in guards are:
+//! - `DenyPatterns` - denies statements matching any of the given regular expressions,
+//!   available with the `regex` feature,
+//! - [`DenyUnprepared`] - denies unprepared statements,
+//! - [`RequireWhere`] - denies statements without a `WHERE` clause on the given tables,
+//! - [`MaxBatchStatements`] - denies batches with too many statements.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+#[cfg(feature = "regex")]
+use regex::RegexSet;
+
+use crate::batch::{Batch, BatchStatement};
+#[cfg(feature = "regex")]
+use crate::statement::normalize::mask_literals;
+use crate::statement::normalize::{parse_table_name, statement_target};
+use crate::transport::errors::QueryError;
+
+/// How a statement is sent to the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StatementKind {
+    /// An unprepared statement, sent with `query*` methods or in a batch.
+    Unprepared,
+    /// A prepared statement, sent with `execute*` methods or in a batch.
+    Prepared,
+}
+
+/// Verdict of a [`StatementGuard`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardDecision {
+    /// The statement may be sent.
+    Allow,
+    /// The statement must not be sent, the request fails with
+    /// [`QueryError::DeniedByGuard`] carrying the reason.
+    Deny { reason: String },
+}
+
+impl GuardDecision {
+    /// Denies the statement for the given reason.
+    pub fn deny(reason: impl Into<String>) -> Self {
+        GuardDecision::Deny {
+            reason: reason.into(),
+        }
+    }
+
+    pub(crate) fn into_result(self) -> Result<(), QueryError> {
+        match self {
+            GuardDecision::Allow => Ok(()),
+            GuardDecision::Deny { reason } => Err(QueryError::DeniedByGuard { reason }),
+        }
+    }
+}
+
+/// Decides whether a statement may be sent, before the request is issued.
+///
+/// Guards are called synchronously on the path of every request, so they should be cheap.
+/// A request is denied if any of the installed guards denies it.
+///
+/// # Example
+/// ```
+/// use scylla::transport::statement_guard::{GuardDecision, StatementGuard, StatementKind};
+///
+/// // Only allows prepared statements to be sent to the `ks` keyspace
+/// #[derive(Debug)]
+/// struct KeyspaceGuard;
+///
+/// impl StatementGuard for KeyspaceGuard {
+///     fn check_statement(&self, statement: &str, kind: StatementKind) -> GuardDecision {
+///         if kind == StatementKind::Unprepared && statement.contains("ks.") {
+///             return GuardDecision::deny("statements on ks must be prepared");
+///         }
+///         GuardDecision::Allow
+///     }
+/// }
+/// ```
+pub trait StatementGuard: Debug + Send + Sync {
+    /// Decides whether the statement with the given text may be sent.
+    fn check_statement(&self, statement: &str, kind: StatementKind) -> GuardDecision;
+
+    /// Decides whether the batch may be sent.
+    ///
+    /// By default checks each statement of the batch with [`check_statement`](Self::check_statement),
+    /// denying the batch if any of them is denied.
+    fn check_batch(&self, batch: &Batch) -> GuardDecision {
+        for statement in &batch.statements {
+            let decision = match statement {
+                BatchStatement::Query(query) => {
+                    self.check_statement(&query.contents, StatementKind::Unprepared)
+                }
+                BatchStatement::PreparedStatement(prepared) => {
+                    self.check_statement(prepared.get_statement(), StatementKind::Prepared)
+                }
+            };
+            if decision != GuardDecision::Allow {
+                return decision;
+            }
+        }
+        GuardDecision::Allow
+    }
+}
+
+/// Denies statements matching any of the given regular expressions.
+///
+/// The expressions are matched against the statement with string literals replaced
+/// by `''` and comments and runs of whitespace replaced by a single space,
+/// so that e.g. `(?i)^DROP\b` doesn't deny an `INSERT` of the text `'DROP'`.
+/// The case of the statement is kept, use `(?i)` for case insensitive matching.
+#[cfg(feature = "regex")]
+#[derive(Debug)]
+pub struct DenyPatterns {
+    patterns: RegexSet,
+}
+
+#[cfg(feature = "regex")]
+impl DenyPatterns {
+    /// Creates a guard denying statements matching any of `patterns`.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::transport::statement_guard::DenyPatterns;
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let guard = DenyPatterns::new([r"(?i)^\s*(DROP|TRUNCATE)\b", r"(?i)\bALLOW FILTERING\b"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new<I, S>(patterns: I) -> Result<Self, regex::Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Ok(Self {
+            patterns: RegexSet::new(patterns)?,
+        })
+    }
+}
+
+#[cfg(feature = "regex")]
+impl StatementGuard for DenyPatterns {
+    fn check_statement(&self, statement: &str, _kind: StatementKind) -> GuardDecision {
+        let masked = mask_literals(statement);
+        match self.patterns.matches(&masked).iter().next() {
+            Some(index) => GuardDecision::deny(format!(
+                "statement matches the denied pattern `{}`",
+                self.patterns.patterns()[index]
+            )),
+            None => GuardDecision::Allow,
+        }
+    }
+}
+
+/// Denies unprepared statements, including unprepared statements in batches.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DenyUnprepared;
+
+impl StatementGuard for DenyUnprepared {
+    fn check_statement(&self, _statement: &str, kind: StatementKind) -> GuardDecision {
+        match kind {
+            StatementKind::Unprepared => {
+                GuardDecision::deny("unprepared statements are not allowed")
+            }
+            StatementKind::Prepared => GuardDecision::Allow,
+        }
+    }
+}
+
+/// Denies `SELECT`, `UPDATE` and `DELETE` statements without a `WHERE` clause,
+/// and `TRUNCATE` statements, on the given tables.
+///
+/// Statements are parsed on a best-effort basis: statements which can't be understood,
+/// e.g. ones with a syntax error, are allowed and left for the server to handle.
+/// A statement which doesn't name the keyspace of the table is matched against the
+/// tables of any keyspace with the same name, since the keyspace of the session isn't known.
+#[derive(Debug)]
+pub struct RequireWhere {
+    // Keyspace is `None` for tables configured without one, which match in every keyspace
+    tables: HashSet<(Option<String>, String)>,
+}
+
+impl RequireWhere {
+    /// Creates a guard for the given tables, written the way they would be in a statement,
+    /// i.e. `keyspace.table` or `table`, with unquoted names being case insensitive.
+    /// Names which are not valid table names are ignored.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::transport::statement_guard::RequireWhere;
+    /// let guard = RequireWhere::new(["ks.users", "\"AuditLog\""]);
+    /// ```
+    pub fn new(tables: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        Self {
+            tables: tables
+                .into_iter()
+                .filter_map(|table| parse_table_name(table.as_ref()))
+                .collect(),
+        }
+    }
+
+    fn is_guarded(&self, keyspace: Option<&String>, table: &str) -> bool {
+        let unqualified = (None, table.to_owned());
+        match keyspace {
+            Some(keyspace) => {
+                self.tables.contains(&unqualified)
+                    || self
+                        .tables
+                        .contains(&(Some(keyspace.clone()), table.to_owned()))
+            }
+            None => self.tables.iter().any(|(_, guarded)| guarded == table),
+        }
+    }
+}
+
+impl StatementGuard for RequireWhere {
+    fn check_statement(&self, statement: &str, _kind: StatementKind) -> GuardDecision {
+        match statement_target(statement) {
+            Some(target)
+                if !target.has_where
+                    && self.is_guarded(target.keyspace.as_ref(), &target.table) =>
+            {
+                GuardDecision::deny(format!(
+                    "statements on table {} require a WHERE clause",
+                    target.table
+                ))
+            }
+            _ => GuardDecision::Allow,
+        }
+    }
+}
+
+/// Denies batches with more than the given number of statements.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxBatchStatements(pub usize);
+
+impl StatementGuard for MaxBatchStatements {
+    fn check_statement(&self, _statement: &str, _kind: StatementKind) -> GuardDecision {
+        GuardDecision::Allow
+    }
+
+    fn check_batch(&self, batch: &Batch) -> GuardDecision {
+        match batch.statements.len() {
+            len if len > self.0 => GuardDecision::deny(format!(
+                "batch has {} statements, more than the allowed {}",
+                len, self.0
+            )),
+            _ => GuardDecision::Allow,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "regex")]
+    use super::DenyPatterns;
+    use super::{
+        DenyUnprepared, GuardDecision, MaxBatchStatements, RequireWhere, StatementGuard,
+        StatementKind,
+    };
+    use crate::batch::Batch;
+    use crate::query::Query;
+    use crate::transport::errors::QueryError;
+
+    fn is_denied(guard: &dyn StatementGuard, statement: &str) -> bool {
+        matches!(
+            guard.check_statement(statement, StatementKind::Unprepared),
+            GuardDecision::Deny { .. }
+        )
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn deny_patterns_ignore_literals_and_comments() {
+        let guard = DenyPatterns::new([r"(?i)^DROP\b", r"(?i)\bALLOW FILTERING$"]).unwrap();
+
+        for statement in [
+            "DROP TABLE ks.t",
+            "  drop keyspace ks",
+            "/* cleanup */ DROP TABLE t",
+            "SELECT * FROM t WHERE b = 1 ALLOW  FILTERING",
+            "SELECT * FROM t WHERE b = 1 ALLOW\nFILTERING",
+        ] {
+            assert!(is_denied(&guard, statement), "{}", statement);
+        }
+
+        for statement in [
+            "INSERT INTO t (a) VALUES ('DROP TABLE t')",
+            "SELECT * FROM t WHERE a = 'ALLOW FILTERING'",
+            "SELECT * FROM t WHERE a = $$ x ALLOW FILTERING $$",
+            "SELECT * FROM dropped",
+            "SELECT * FROM t -- ALLOW FILTERING",
+        ] {
+            assert!(!is_denied(&guard, statement), "{}", statement);
+        }
+
+        assert_eq!(
+            guard.check_statement("DROP TABLE t", StatementKind::Prepared),
+            GuardDecision::deny(r"statement matches the denied pattern `(?i)^DROP\b`")
+        );
+        assert!(DenyPatterns::new(["("]).is_err());
+    }
+
+    #[test]
+    fn deny_unprepared_checks_kind() {
+        let guard = DenyUnprepared;
+        assert!(is_denied(&guard, "SELECT * FROM t"));
+        assert_eq!(
+            guard.check_statement("SELECT * FROM t", StatementKind::Prepared),
+            GuardDecision::Allow
+        );
+
+        let mut batch = Batch::default();
+        batch.append_statement("INSERT INTO t (a) VALUES (1)");
+        assert!(matches!(
+            guard.check_batch(&batch),
+            GuardDecision::Deny { .. }
+        ));
+    }
+
+    #[test]
+    fn require_where_applies_to_configured_tables() {
+        let guard = RequireWhere::new(["ks.users", "\"Events\"", "not a table"]);
+
+        for statement in [
+            "SELECT * FROM ks.users",
+            "select * from KS.Users",
+            "SELECT * FROM users",
+            "DELETE FROM ks.users -- WHERE id = 1",
+            "UPDATE \"Events\" SET a = 1",
+            "UPDATE other.\"Events\" SET a = 1",
+            "TRUNCATE ks.users",
+            "TRUNCATE TABLE \"Events\"",
+        ] {
+            assert!(is_denied(&guard, statement), "{}", statement);
+        }
+
+        for statement in [
+            "SELECT * FROM ks.users WHERE id = ?",
+            "UPDATE ks.users SET name = 'no WHERE' WHERE id = 1",
+            "SELECT * FROM other.users",
+            "SELECT * FROM events",
+            "SELECT * FROM ks.users_by_name",
+            "INSERT INTO ks.users (id) VALUES (1)",
+            "SELECT 'FROM ks.users' FROM ks.t",
+            "UPDATE \"ks.users\" SET a = 1",
+        ] {
+            assert!(!is_denied(&guard, statement), "{}", statement);
+        }
+
+        assert_eq!(
+            guard.check_statement("SELECT * FROM ks.users", StatementKind::Prepared),
+            GuardDecision::deny("statements on table users require a WHERE clause")
+        );
+    }
+
+    #[test]
+    fn max_batch_statements_counts_statements() {
+        let guard = MaxBatchStatements(2);
+        let mut batch = Batch::default();
+        batch.append_statement("INSERT INTO t (a) VALUES (1)");
+        batch.append_statement(Query::new("INSERT INTO t (a) VALUES (2)"));
+        assert_eq!(guard.check_batch(&batch), GuardDecision::Allow);
+
+        batch.append_statement("INSERT INTO t (a) VALUES (3)");
+        let denied = guard.check_batch(&batch).into_result().unwrap_err();
+        assert!(matches!(
+            denied,
+            QueryError::DeniedByGuard { reason }
+                if reason == "batch has 3 statements, more than the allowed 2"
+        ));
+        assert!(!is_denied(&guard, "INSERT INTO t (a) VALUES (1)"));
+    }
+}
@@ -0,0 +1,450 @@
+//! Scanning whole tables in parallel, range by range of the token ring.
+//!
+//! [`Session::scan_table`](crate::Session::scan_table) splits the token ring into ranges owned
+//! by the same replicas and reads every range with a separate paged query of the form
+//! `SELECT ... WHERE token(pk) > ? AND token(pk) <= ?`, sent directly to a replica of the range.
+//! Up to [`ScanConfig::parallelism`] ranges are read concurrently and their rows are merged
+//! into a single [`TableScan`] stream, in no particular order.
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, StreamExt};
+use futures::{ready, FutureExt, Stream};
+use scylla_cql::errors::{BadQuery, QueryError};
+use scylla_cql::frame::response::result::Row;
+use scylla_cql::frame::types::Consistency;
+use tracing::debug;
+
+use crate::prepared_statement::PreparedStatement;
+use crate::statement::identifier::quote_identifier;
+use crate::transport::execution_profile::ExecutionProfileHandle;
+use crate::transport::load_balancing::{FallbackPlan, LoadBalancingPolicy, RoutingInfo};
+use crate::transport::locator::TokenRange;
+use crate::transport::{ClusterData, Node, NodeRef};
+use crate::{QueryResult, Session};
+
+/// Configuration of a table scan started with [`Session::scan_table`](crate::Session::scan_table).
+#[derive(Debug, Clone)]
+pub struct ScanConfig {
+    parallelism: usize,
+    page_size: i32,
+    consistency: Option<Consistency>,
+    columns: Option<String>,
+    predicate: Option<String>,
+    max_retries: usize,
+    execution_profile_handle: Option<ExecutionProfileHandle>,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            parallelism: 16,
+            page_size: 5000,
+            consistency: None,
+            columns: None,
+            predicate: None,
+            max_retries: 3,
+            execution_profile_handle: None,
+        }
+    }
+}
+
+impl ScanConfig {
+    /// Creates a default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximal number of token ranges read concurrently. Defaults to 16.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parallelism` is 0.
+    pub fn parallelism(mut self, parallelism: usize) -> Self {
+        assert!(parallelism > 0, "scan parallelism must be positive");
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// Sets the page size of the queries reading token ranges. Defaults to 5000.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `page_size` is not positive.
+    pub fn page_size(mut self, page_size: i32) -> Self {
+        assert!(page_size > 0, "page size must be larger than 0");
+        self.page_size = page_size;
+        self
+    }
+
+    /// Sets the consistency of the queries reading token ranges.
+    /// By default, the consistency of the execution profile is used.
+    pub fn consistency(mut self, consistency: Consistency) -> Self {
+        self.consistency = Some(consistency);
+        self
+    }
+
+    /// Sets the selected columns, e.g. `"a, b, writetime(b)"`. Selects all columns (`*`) by default.
+    ///
+    /// The projection is inserted into the query verbatim.
+    pub fn columns(mut self, columns: impl Into<String>) -> Self {
+        self.columns = Some(columns.into());
+        self
+    }
+
+    /// Sets an additional restriction added to the token restrictions with `AND`,
+    /// e.g. `"v > 10 ALLOW FILTERING"`.
+    ///
+    /// The predicate is inserted into the query verbatim.
+    pub fn predicate(mut self, predicate: impl Into<String>) -> Self {
+        self.predicate = Some(predicate.into());
+        self
+    }
+
+    /// Sets how many times in a row fetching a page of a token range may fail
+    /// before the scan fails. Defaults to 3.
+    ///
+    /// The failed page is fetched again, so the rows read so far are neither lost
+    /// nor returned twice. These retries happen on top of the ones decided by the retry policy.
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the execution profile of the queries reading token ranges.
+    /// The session's default profile is used by default.
+    ///
+    /// Queries are routed to replicas of their token ranges; the load balancing policy
+    /// of the profile is only used when all replicas of a range are unavailable.
+    pub fn execution_profile_handle(mut self, handle: ExecutionProfileHandle) -> Self {
+        self.execution_profile_handle = Some(handle);
+        self
+    }
+}
+
+/// Progress of a table scan, in token ranges. Can be cloned and observed
+/// from another task while the scan is running.
+#[derive(Debug, Clone)]
+pub struct ScanProgress {
+    ranges_done: Arc<AtomicUsize>,
+    ranges_total: usize,
+}
+
+impl ScanProgress {
+    /// Number of token ranges which have been read completely.
+    pub fn ranges_done(&self) -> usize {
+        self.ranges_done.load(Ordering::Relaxed)
+    }
+
+    /// Number of token ranges the scan reads.
+    pub fn ranges_total(&self) -> usize {
+        self.ranges_total
+    }
+
+    /// Returns true if all token ranges have been read.
+    pub fn is_finished(&self) -> bool {
+        self.ranges_done() == self.ranges_total
+    }
+}
+
+/// A token range being read, together with the query reading it.
+struct RangeScan {
+    range: TokenRange,
+    statement: PreparedStatement,
+    paging_state: Option<Bytes>,
+    failures: usize,
+}
+
+type PageFuture<'a> = BoxFuture<'a, (RangeScan, Result<QueryResult, QueryError>)>;
+
+/// Stream of rows of a table scan, started with [`Session::scan_table`](crate::Session::scan_table).
+///
+/// If reading a token range fails more than [`ScanConfig::max_retries`] times in a row,
+/// the error is returned and the stream ends.
+pub struct TableScan<'a> {
+    session: &'a Session,
+    parallelism: usize,
+    max_retries: usize,
+    pending: VecDeque<RangeScan>,
+    in_flight: FuturesUnordered<PageFuture<'a>>,
+    rows: VecDeque<Row>,
+    progress: ScanProgress,
+    failed: bool,
+}
+
+impl<'a> TableScan<'a> {
+    pub(crate) async fn new(
+        session: &'a Session,
+        keyspace: &str,
+        table: &str,
+        config: ScanConfig,
+    ) -> Result<TableScan<'a>, QueryError> {
+        let cluster_data = session.get_cluster_data();
+        let partition_key = cluster_data
+            .get_keyspace_info()
+            .get(keyspace)
+            .and_then(|ks| ks.tables.get(table))
+            .map(|table| table.partition_key.clone())
+            .ok_or_else(|| {
+                QueryError::BadQuery(BadQuery::Other(format!(
+                    "Cannot scan table {}.{}: table not found in the schema metadata",
+                    keyspace, table
+                )))
+            })?;
+
+        let mut prepared = session
+            .prepare(scan_statement(keyspace, table, &partition_key, &config))
+            .await?;
+        prepared.set_is_idempotent(true);
+        prepared.set_page_size(config.page_size);
+        if let Some(consistency) = config.consistency {
+            prepared.set_consistency(consistency);
+        }
+        let base_profile = config
+            .execution_profile_handle
+            .as_ref()
+            .unwrap_or_else(|| session.get_default_execution_profile_handle());
+        let fallback_policy = base_profile.access().load_balancing_policy.clone();
+
+        let mut ring = cluster_data.token_ring(keyspace);
+        if ring.is_empty() {
+            // Token ownership is unknown, read the whole ring through any node.
+            ring.push((
+                TokenRange::new(TokenRange::MIN_TOKEN, TokenRange::MIN_TOKEN),
+                Vec::new(),
+            ));
+        }
+
+        let mut pending = VecDeque::new();
+        for (range, replicas) in ring {
+            let policy = Arc::new(RangeReplicasPolicy {
+                replicas,
+                fallback: fallback_policy.clone(),
+            });
+            let handle = base_profile
+                .pointee_to_builder()
+                .load_balancing_policy(policy)
+                .build()
+                .into_handle();
+            for range in range.unwrap_ranges() {
+                let mut statement = prepared.clone();
+                statement.set_execution_profile_handle(Some(handle.clone()));
+                pending.push_back(RangeScan {
+                    range,
+                    statement,
+                    paging_state: None,
+                    failures: 0,
+                });
+            }
+        }
+
+        let progress = ScanProgress {
+            ranges_done: Arc::new(AtomicUsize::new(0)),
+            ranges_total: pending.len(),
+        };
+        Ok(TableScan {
+            session,
+            parallelism: config.parallelism,
+            max_retries: config.max_retries,
+            pending,
+            in_flight: FuturesUnordered::new(),
+            rows: VecDeque::new(),
+            progress,
+            failed: false,
+        })
+    }
+
+    /// Returns a handle to the progress of the scan.
+    pub fn progress(&self) -> ScanProgress {
+        self.progress.clone()
+    }
+
+    fn fetch_page(&self, range: RangeScan) -> PageFuture<'a> {
+        let session = self.session;
+        async move {
+            let bounds = (range.range.start().value, range.range.end().value);
+            let result = session
+                .execute_single_page(&range.statement, bounds, range.paging_state.clone())
+                .await;
+            (range, result)
+        }
+        .boxed()
+    }
+}
+
+impl Stream for TableScan<'_> {
+    type Item = Result<Row, QueryError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(row) = self.rows.pop_front() {
+                return Poll::Ready(Some(Ok(row)));
+            }
+            if self.failed {
+                return Poll::Ready(None);
+            }
+
+            while self.in_flight.len() < self.parallelism {
+                match self.pending.pop_front() {
+                    Some(range) => {
+                        let page = self.fetch_page(range);
+                        self.in_flight.push(page);
+                    }
+                    None => break,
+                }
+            }
+
+            let (mut range, result) = match ready!(self.in_flight.poll_next_unpin(cx)) {
+                Some(page) => page,
+                None => return Poll::Ready(None),
+            };
+            match result {
+                Ok(result) => {
+                    self.rows.extend(result.rows.unwrap_or_default());
+                    range.failures = 0;
+                    match result.paging_state {
+                        // The next page of the range is fetched before starting new ranges.
+                        Some(paging_state) => {
+                            range.paging_state = Some(paging_state);
+                            self.pending.push_front(range);
+                        }
+                        None => {
+                            self.progress.ranges_done.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+                Err(error) => {
+                    range.failures += 1;
+                    if range.failures > self.max_retries {
+                        self.failed = true;
+                        self.pending.clear();
+                        self.in_flight = FuturesUnordered::new();
+                        self.rows.clear();
+                        return Poll::Ready(Some(Err(error)));
+                    }
+                    debug!(
+                        "Fetching a page of token range {:?} failed, retrying: {}",
+                        range.range, error
+                    );
+                    self.pending.push_front(range);
+                }
+            }
+        }
+    }
+}
+
+fn scan_statement(
+    keyspace: &str,
+    table: &str,
+    partition_key: &[String],
+    config: &ScanConfig,
+) -> String {
+    let token = format!(
+        "token({})",
+        partition_key
+            .iter()
+            .map(|column| quote_identifier(column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let mut statement = format!(
+        "SELECT {} FROM {}.{} WHERE {} > ? AND {} <= ?",
+        config.columns.as_deref().unwrap_or("*"),
+        quote_identifier(keyspace),
+        quote_identifier(table),
+        token,
+        token
+    );
+    if let Some(predicate) = &config.predicate {
+        statement.push_str(" AND ");
+        statement.push_str(predicate);
+    }
+    statement
+}
+
+/// Routes queries to replicas of a token range first,
+/// then to the nodes chosen by the wrapped policy.
+#[derive(Debug)]
+struct RangeReplicasPolicy {
+    replicas: Vec<Arc<Node>>,
+    fallback: Arc<dyn LoadBalancingPolicy>,
+}
+
+impl RangeReplicasPolicy {
+    fn available_replicas(&self) -> impl Iterator<Item = NodeRef<'_>> {
+        self.replicas
+            .iter()
+            .filter(|node| node.is_enabled() && !node.is_down())
+    }
+}
+
+impl LoadBalancingPolicy for RangeReplicasPolicy {
+    fn pick<'a>(&'a self, query: &'a RoutingInfo, cluster: &'a ClusterData) -> Option<NodeRef<'a>> {
+        self.available_replicas()
+            .next()
+            .or_else(|| self.fallback.pick(query, cluster))
+    }
+
+    fn fallback<'a>(
+        &'a self,
+        query: &'a RoutingInfo,
+        cluster: &'a ClusterData,
+    ) -> FallbackPlan<'a> {
+        let others = self.fallback.fallback(query, cluster).filter(move |node| {
+            !self
+                .replicas
+                .iter()
+                .any(|replica| Arc::ptr_eq(replica, node))
+        });
+        Box::new(self.available_replicas().chain(others))
+    }
+
+    fn on_query_success(&self, query: &RoutingInfo, latency: Duration, node: NodeRef<'_>) {
+        self.fallback.on_query_success(query, latency, node)
+    }
+
+    fn on_query_failure(
+        &self,
+        query: &RoutingInfo,
+        latency: Duration,
+        node: NodeRef<'_>,
+        error: &QueryError,
+    ) {
+        self.fallback.on_query_failure(query, latency, node, error)
+    }
+
+    fn name(&self) -> String {
+        "RangeReplicasPolicy".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{scan_statement, ScanConfig};
+
+    #[test]
+    fn test_scan_statement() {
+        let partition_key = vec!["a".to_owned(), "B\"b".to_owned()];
+        assert_eq!(
+            scan_statement("ks", "t", &partition_key, &ScanConfig::new()),
+            "SELECT * FROM \"ks\".\"t\" WHERE token(\"a\", \"B\"\"b\") > ? \
+             AND token(\"a\", \"B\"\"b\") <= ?"
+        );
+
+        let config = ScanConfig::new()
+            .columns("a, c")
+            .predicate("c > 10 ALLOW FILTERING");
+        assert_eq!(
+            scan_statement("ks", "t", &partition_key[..1], &config),
+            "SELECT a, c FROM \"ks\".\"t\" WHERE token(\"a\") > ? AND token(\"a\") <= ? \
+             AND c > 10 ALLOW FILTERING"
+        );
+    }
+}
@@ -1,37 +1,47 @@
 use crate::frame::response::event::Event;
 use crate::routing::Token;
+use crate::statement::identifier::CqlIdentifier;
 use crate::statement::query::Query;
 use crate::transport::connection::{Connection, ConnectionConfig};
 use crate::transport::connection_pool::{NodeConnectionPool, PoolConfig, PoolSize};
+use crate::transport::control_connection_policy::{
+    ControlConnectionPolicy, ControlConnectionStatus,
+};
 use crate::transport::errors::{DbError, QueryError};
 use crate::transport::host_filter::HostFilter;
-use crate::transport::node::resolve_contact_points;
+use crate::transport::metrics::Metrics;
+use crate::transport::node::ContactPoints;
+use crate::transport::runtime;
 use crate::utils::parse::{ParseErrorCause, ParseResult, ParserState};
 
+use arc_swap::ArcSwap;
 use futures::future::{self, FutureExt};
 use futures::stream::{self, StreamExt, TryStreamExt};
 use futures::Stream;
 use rand::seq::SliceRandom;
 use rand::{thread_rng, Rng};
 use scylla_cql::errors::NewSessionError;
-use scylla_cql::frame::response::result::Row;
+use scylla_cql::frame::response::result::{ColumnType, Row};
+use scylla_cql::frame::response::type_interner::TypeInterner;
 use scylla_macros::FromRow;
 use std::borrow::BorrowMut;
 use std::cell::Cell;
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Formatter;
+use std::future::Future;
 use std::net::{IpAddr, SocketAddr};
 use std::num::NonZeroUsize;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use strum_macros::EnumString;
+use thiserror::Error;
 use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, error, trace, warn};
 use uuid::Uuid;
 
-use super::node::{KnownNode, NodeAddr, ResolvedContactPoint};
+use super::node::{NodeAddr, ResolvedContactPoint};
 
 /// Allows to read current metadata from the cluster
 pub(crate) struct MetadataReader {
@@ -49,11 +59,19 @@ pub(crate) struct MetadataReader {
 
     // When no known peer is reachable, initial known nodes are resolved once again as a fallback
     // and establishing control connection to them is attempted.
-    initial_known_nodes: Vec<KnownNode>,
+    contact_points: ContactPoints,
 
     // When a control connection breaks, the PoolRefiller of its pool uses the requester
     // to signal ClusterWorker that an immediate metadata refresh is advisable.
     control_connection_repair_requester: broadcast::Sender<()>,
+
+    policy: ControlConnectionPolicy,
+    // Set when a heartbeat fails, so that the next metadata read
+    // moves the control connection to another node right away
+    control_connection_failure: Option<QueryError>,
+    failovers: u64,
+    status: Arc<ArcSwap<ControlConnectionStatus>>,
+    metrics: Option<Arc<Metrics>>,
 }
 
 /// Describes all metadata retrieved from the cluster
@@ -93,7 +111,16 @@ impl UntranslatedEndpoint {
     }
     pub(crate) fn set_port(&mut self, port: u16) {
         let inner_addr = match self {
-            UntranslatedEndpoint::ContactPoint(ResolvedContactPoint { address, .. }) => address,
+            UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                address,
+                fallback_addresses,
+                ..
+            }) => {
+                for fallback_address in fallback_addresses {
+                    fallback_address.set_port(port);
+                }
+                address
+            }
             UntranslatedEndpoint::Peer(PeerEndpoint { address, .. }) => address.inner_mut(),
         };
         inner_addr.set_port(port);
@@ -155,6 +182,27 @@ pub struct Keyspace {
     pub views: HashMap<String, MaterializedView>,
     /// Empty HashMap may as well mean that the client disabled schema fetching in SessionConfig
     pub user_defined_types: HashMap<String, Arc<UserDefinedType>>,
+    /// Empty HashMap may as well mean that the client disabled schema fetching in SessionConfig
+    pub functions: HashMap<FunctionSignature, UserDefinedFunction>,
+    /// Empty HashMap may as well mean that the client disabled schema fetching in SessionConfig
+    pub aggregates: HashMap<FunctionSignature, UserDefinedAggregate>,
+}
+
+impl Keyspace {
+    /// Looks up a table by its identifier.
+    pub fn get_table(&self, table: &CqlIdentifier) -> Option<&Table> {
+        self.tables.get(table.name())
+    }
+
+    /// Looks up a materialized view by its identifier.
+    pub fn get_view(&self, view: &CqlIdentifier) -> Option<&MaterializedView> {
+        self.views.get(view.name())
+    }
+
+    /// Looks up a user defined type by its identifier.
+    pub fn get_user_defined_type(&self, udt: &CqlIdentifier) -> Option<&Arc<UserDefinedType>> {
+        self.user_defined_types.get(udt.name())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -163,12 +211,88 @@ pub struct Table {
     pub partition_key: Vec<String>,
     pub clustering_key: Vec<String>,
     pub partitioner: Option<String>,
+    /// Left at defaults if the server does not expose the options in its schema tables.
+    pub options: TableOptions,
+    /// Secondary indexes on the table, by index name.
+    /// Always empty for materialized views.
+    pub indexes: HashMap<String, Index>,
+}
+
+/// Options of a table or a materialized view, as stored in `system_schema.tables`
+/// and `system_schema.views`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TableOptions {
+    pub comment: String,
+    /// Compaction strategy class and its options
+    pub compaction: HashMap<String, String>,
+    /// Compression algorithm and its options, empty if compression is disabled
+    pub compression: HashMap<String, String>,
+    pub caching: HashMap<String, String>,
+    /// Default time to live of the data in seconds, 0 means no TTL
+    pub default_time_to_live: i32,
+    pub gc_grace_seconds: i32,
+    pub speculative_retry: String,
+}
+
+impl Table {
+    /// Looks up a column by its identifier.
+    pub fn get_column(&self, column: &CqlIdentifier) -> Option<&Column> {
+        self.columns.get(column.name())
+    }
+
+    /// Looks up a secondary index by its identifier.
+    pub fn get_index(&self, index: &CqlIdentifier) -> Option<&Index> {
+        self.indexes.get(index.name())
+    }
+
+    fn empty() -> Self {
+        Self {
+            columns: HashMap::new(),
+            partition_key: vec![],
+            clustering_key: vec![],
+            partitioner: None,
+            options: TableOptions::default(),
+            indexes: HashMap::new(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MaterializedView {
     pub view_metadata: Table,
     pub base_table_name: String,
+    /// Restrictions from the `WHERE` clause of the view, as in `CREATE MATERIALIZED VIEW`
+    pub where_clause: String,
+    /// Whether the view was created with `SELECT *`,
+    /// i.e. it includes all columns of the base table.
+    /// Columns included in the view are listed in `view_metadata.columns`.
+    pub include_all_columns: bool,
+}
+
+/// Secondary index, as stored in `system_schema.indexes`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Index {
+    pub kind: IndexKind,
+    /// Options of the index. The indexed column (or expression)
+    /// is stored under the `target` key, see [`Index::target`].
+    pub options: HashMap<String, String>,
+}
+
+impl Index {
+    /// Returns the column (or expression, e.g. `keys(m)`) that the index is built on.
+    pub fn target(&self) -> Option<&str> {
+        self.options.get("target").map(String::as_str)
+    }
+}
+
+/// Kind of a secondary index, as stored in the `kind` column of `system_schema.indexes`.
+#[derive(Clone, Debug, PartialEq, Eq, EnumString)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum IndexKind {
+    Keys,
+    Custom,
+    Composites,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -248,8 +372,94 @@ pub struct UserDefinedType {
     pub field_types: Vec<(String, CqlType)>,
 }
 
-/// Represents a user defined type whose definition is missing from the metadata.
+impl UserDefinedType {
+    /// Returns the type in the form in which types of values are checked,
+    /// e.g. to build its values with [`UdtValueBuilder`](crate::frame::response::udt_value::UdtValueBuilder).\
+    /// Fails if the definition of a nested user defined type is missing.
+    ///
+    /// The fields of the returned type are [interned](scylla_cql::frame::response::type_interner),
+    /// so they are shared with the types of columns in the metadata of prepared statements.
+    pub fn to_column_type(&self) -> Result<ColumnType, MissingUserDefinedType> {
+        let field_types = self
+            .field_types
+            .iter()
+            .map(|(name, typ)| Ok((name.clone(), typ.to_column_type()?)))
+            .collect::<Result<_, _>>()?;
+        Ok(ColumnType::UserDefinedType {
+            type_name: self.name.clone(),
+            keyspace: self.keyspace.clone(),
+            field_types: TypeInterner::global().intern_udt(&self.keyspace, &self.name, field_types),
+        })
+    }
+}
+
+impl CqlType {
+    /// Returns the type in the form in which types of values are checked.\
+    /// Fails if the definition of a user defined type is missing.
+    pub fn to_column_type(&self) -> Result<ColumnType, MissingUserDefinedType> {
+        let to_boxed = |typ: &CqlType| typ.to_column_type().map(Box::new);
+        Ok(match self {
+            CqlType::Native(native) => native.to_column_type(),
+            CqlType::Collection { type_, .. } => match type_ {
+                CollectionType::List(element) => ColumnType::List(to_boxed(element)?),
+                CollectionType::Map(key, value) => {
+                    ColumnType::Map(to_boxed(key)?, to_boxed(value)?)
+                }
+                CollectionType::Set(element) => ColumnType::Set(to_boxed(element)?),
+            },
+            CqlType::Tuple(elements) => ColumnType::Tuple(
+                elements
+                    .iter()
+                    .map(CqlType::to_column_type)
+                    .collect::<Result<_, _>>()?,
+            ),
+            CqlType::UserDefinedType { definition, .. } => match definition {
+                Ok(definition) => definition.to_column_type()?,
+                Err(missing) => return Err(missing.clone()),
+            },
+        })
+    }
+}
+
+/// Identifies a user-defined function or aggregate within a keyspace.
+/// Functions and aggregates may be overloaded, so the name alone is not enough.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FunctionSignature {
+    pub name: String,
+    /// Types of the arguments, as stored in `system_schema`, e.g. `frozen<list<int>>`
+    pub argument_types: Vec<String>,
+}
+
+/// Definition of a user-defined function, as stored in `system_schema.functions`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserDefinedFunction {
+    pub argument_names: Vec<String>,
+    pub argument_types: Vec<CqlType>,
+    pub return_type: CqlType,
+    pub language: String,
+    pub body: String,
+    /// Whether the function is called when any of its arguments is null
+    /// (`CALLED ON NULL INPUT`), or returns null right away (`RETURNS NULL ON NULL INPUT`).
+    pub called_on_null_input: bool,
+}
+
+/// Definition of a user-defined aggregate, as stored in `system_schema.aggregates`.
 #[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserDefinedAggregate {
+    pub argument_types: Vec<CqlType>,
+    pub return_type: CqlType,
+    /// Name of the state function, defined in the same keyspace
+    pub state_func: String,
+    pub state_type: CqlType,
+    /// Name of the final function, defined in the same keyspace
+    pub final_func: Option<String>,
+    /// Initial state, as a CQL literal
+    pub initcond: Option<String>,
+}
+
+/// Represents a user defined type whose definition is missing from the metadata.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("Definition of user defined type {keyspace}.{name} is missing")]
 pub struct MissingUserDefinedType {
     pub name: String,
     pub keyspace: String,
@@ -280,6 +490,33 @@ pub enum NativeType {
     Varint,
 }
 
+impl NativeType {
+    fn to_column_type(&self) -> ColumnType {
+        match self {
+            NativeType::Ascii => ColumnType::Ascii,
+            NativeType::Boolean => ColumnType::Boolean,
+            NativeType::Blob => ColumnType::Blob,
+            NativeType::Counter => ColumnType::Counter,
+            NativeType::Date => ColumnType::Date,
+            NativeType::Decimal => ColumnType::Decimal,
+            NativeType::Double => ColumnType::Double,
+            NativeType::Duration => ColumnType::Duration,
+            NativeType::Float => ColumnType::Float,
+            NativeType::Int => ColumnType::Int,
+            NativeType::BigInt => ColumnType::BigInt,
+            NativeType::Text => ColumnType::Text,
+            NativeType::Timestamp => ColumnType::Timestamp,
+            NativeType::Inet => ColumnType::Inet,
+            NativeType::SmallInt => ColumnType::SmallInt,
+            NativeType::TinyInt => ColumnType::TinyInt,
+            NativeType::Time => ColumnType::Time,
+            NativeType::Timeuuid => ColumnType::Timeuuid,
+            NativeType::Uuid => ColumnType::Uuid,
+            NativeType::Varint => ColumnType::Varint,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum PreCollectionType {
     List(Box<PreCqlType>),
@@ -401,7 +638,7 @@ impl MetadataReader {
     /// Creates new MetadataReader, which connects to initially_known_peers in the background
     #[allow(clippy::too_many_arguments)]
     pub(crate) async fn new(
-        initial_known_nodes: Vec<KnownNode>,
+        mut contact_points: ContactPoints,
         control_connection_repair_requester: broadcast::Sender<()>,
         mut connection_config: ConnectionConfig,
         keepalive_interval: Option<Duration>,
@@ -409,9 +646,10 @@ impl MetadataReader {
         keyspaces_to_fetch: Vec<String>,
         fetch_schema: bool,
         host_filter: &Option<Arc<dyn HostFilter>>,
+        policy: ControlConnectionPolicy,
+        metrics: Option<Arc<Metrics>>,
     ) -> Result<Self, NewSessionError> {
-        let (initial_peers, resolved_hostnames) =
-            resolve_contact_points(&initial_known_nodes).await;
+        let (initial_peers, resolved_hostnames) = contact_points.resolve().await;
         // Ensure there is at least one resolved node
         if initial_peers.is_empty() {
             return Err(NewSessionError::FailedToResolveAnyHostname(
@@ -419,12 +657,26 @@ impl MetadataReader {
             ));
         }
 
-        let control_connection_endpoint = UntranslatedEndpoint::ContactPoint(
-            initial_peers
-                .choose(&mut thread_rng())
-                .expect("Tried to initialize MetadataReader with empty initial_known_nodes list!")
-                .clone(),
-        );
+        let mut initial_candidates = initial_peers.clone();
+        initial_candidates.shuffle(&mut thread_rng());
+        let control_connection_endpoint = policy
+            .candidates(
+                initial_candidates
+                    .into_iter()
+                    .map(UntranslatedEndpoint::ContactPoint),
+            )
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| {
+                // Exclusions can't be honoured yet; the control connection is moved
+                // to an accepted node after the first metadata read.
+                UntranslatedEndpoint::ContactPoint(
+                    initial_peers
+                        .choose(&mut thread_rng())
+                        .expect("Tried to initialize MetadataReader with empty initial_known_nodes list!")
+                        .clone(),
+                )
+            });
 
         // setting event_sender field in connection config will cause control connection to
         // - send REGISTER message to receive server events
@@ -438,6 +690,11 @@ impl MetadataReader {
             control_connection_repair_requester.clone(),
         );
 
+        let status = Arc::new(ArcSwap::from_pointee(ControlConnectionStatus::new(
+            &control_connection_endpoint,
+            0,
+        )));
+
         Ok(MetadataReader {
             control_connection_endpoint,
             control_connection,
@@ -450,20 +707,78 @@ impl MetadataReader {
             keyspaces_to_fetch,
             fetch_schema,
             host_filter: host_filter.clone(),
-            initial_known_nodes,
+            contact_points,
             control_connection_repair_requester,
+            policy,
+            control_connection_failure: None,
+            failovers: 0,
+            status,
+            metrics,
         })
     }
 
+    /// Returns the state of the control connection, kept up to date by this reader.
+    pub(crate) fn status(&self) -> Arc<ArcSwap<ControlConnectionStatus>> {
+        self.status.clone()
+    }
+
+    pub(crate) fn heartbeat_interval(&self) -> Option<Duration> {
+        self.policy.get_heartbeat_interval()
+    }
+
+    /// Checks that the control node still responds. If it doesn't, the next
+    /// metadata read moves the control connection to another node.
+    pub(crate) async fn check_control_connection(&mut self) -> Result<(), QueryError> {
+        let control_connection = &self.control_connection;
+        let heartbeat = async {
+            control_connection
+                .random_connection()?
+                .get_options()
+                .await
+                .map(|_| ())
+        };
+        let result = self.within_unhealthy_threshold(heartbeat).await;
+        if let Err(err) = &result {
+            self.control_connection_failure = Some(err.clone());
+        }
+        result
+    }
+
+    async fn within_unhealthy_threshold<T>(
+        &self,
+        request: impl Future<Output = Result<T, QueryError>>,
+    ) -> Result<T, QueryError> {
+        match self.policy.get_unhealthy_threshold() {
+            Some(threshold) => runtime::timeout(
+                self.connection_config.runtime().as_ref(),
+                threshold,
+                request,
+            )
+            .await
+            .unwrap_or_else(|_| {
+                Err(QueryError::RequestTimeout(format!(
+                    "Control node {} did not respond within {:?}",
+                    self.control_connection_endpoint.address(),
+                    threshold
+                )))
+            }),
+            None => request.await,
+        }
+    }
+
     /// Fetches current metadata from the cluster
     pub(crate) async fn read_metadata(&mut self, initial: bool) -> Result<Metadata, QueryError> {
-        let mut result = self.fetch_metadata(initial).await;
+        let mut result = match self.control_connection_failure.take() {
+            Some(err) => Err(err),
+            None => self.fetch_metadata(initial).await,
+        };
         let prev_err = match result {
             Ok(metadata) => {
                 debug!("Fetched new metadata");
                 self.update_known_peers(&metadata);
                 if initial {
                     self.handle_unaccepted_host_in_control_connection(&metadata);
+                    self.publish_status();
                 }
                 return Ok(metadata);
             }
@@ -485,16 +800,17 @@ impl MetadataReader {
         );
 
         let address_of_failed_control_connection = self.control_connection_endpoint.address();
-        let filtered_known_peers = self
-            .known_peers
-            .clone()
-            .into_iter()
-            .filter(|peer| peer.address() != address_of_failed_control_connection);
+        let filtered_known_peers = self.policy.candidates(
+            self.known_peers
+                .clone()
+                .into_iter()
+                .filter(|peer| peer.address() != address_of_failed_control_connection),
+        );
 
         // if fetching metadata on current control connection failed,
         // try to fetch metadata from other known peer
         result = self
-            .retry_fetch_metadata_on_nodes(initial, filtered_known_peers, prev_err)
+            .retry_fetch_metadata_on_nodes(initial, filtered_known_peers.into_iter(), prev_err)
             .await;
 
         if let Err(prev_err) = result {
@@ -502,16 +818,14 @@ impl MetadataReader {
                 // If no known peer is reachable, try falling back to initial contact points, in hope that
                 // there are some hostnames there which will resolve to reachable new addresses.
                 warn!("Failed to establish control connection and fetch metadata on all known peers. Falling back to initial contact points.");
-                let (initial_peers, _hostnames) =
-                    resolve_contact_points(&self.initial_known_nodes).await;
+                let (initial_peers, _hostnames) = self.contact_points.resolve().await;
+                let initial_peers = self.policy.candidates(
+                    initial_peers
+                        .into_iter()
+                        .map(UntranslatedEndpoint::ContactPoint),
+                );
                 result = self
-                    .retry_fetch_metadata_on_nodes(
-                        initial,
-                        initial_peers
-                            .into_iter()
-                            .map(UntranslatedEndpoint::ContactPoint),
-                        prev_err,
-                    )
+                    .retry_fetch_metadata_on_nodes(initial, initial_peers.into_iter(), prev_err)
                     .await;
             } else {
                 // No point in falling back as this is an initial connection attempt.
@@ -521,8 +835,17 @@ impl MetadataReader {
 
         match &result {
             Ok(metadata) => {
+                if self.control_connection_endpoint.address()
+                    != address_of_failed_control_connection
+                {
+                    self.failovers += 1;
+                    if let Some(metrics) = &self.metrics {
+                        metrics.inc_control_connection_failovers();
+                    }
+                }
                 self.update_known_peers(metadata);
                 self.handle_unaccepted_host_in_control_connection(metadata);
+                self.publish_status();
                 debug!("Fetched new metadata");
             }
             Err(error) => error!(
@@ -575,17 +898,19 @@ impl MetadataReader {
     }
 
     async fn fetch_metadata(&self, initial: bool) -> Result<Metadata, QueryError> {
-        // TODO: Timeouts?
-        self.control_connection.wait_until_initialized().await;
-        let conn = &self.control_connection.random_connection()?;
-
-        let res = query_metadata(
-            conn,
-            self.control_connection_endpoint.address().port(),
-            &self.keyspaces_to_fetch,
-            self.fetch_schema,
-        )
-        .await;
+        let fetch = async {
+            self.control_connection.wait_until_initialized().await;
+            let conn = &self.control_connection.random_connection()?;
+
+            query_metadata(
+                conn,
+                self.control_connection_endpoint.address().port(),
+                &self.keyspaces_to_fetch,
+                self.fetch_schema,
+            )
+            .await
+        };
+        let res = self.within_unhealthy_threshold(fetch).await;
 
         if initial {
             if let Err(err) = res {
@@ -633,8 +958,10 @@ impl MetadataReader {
             .peers
             .iter()
             .find(|peer| matches!(self.control_connection_endpoint, UntranslatedEndpoint::Peer(PeerEndpoint{address, ..}) if address == peer.address));
+        let mut rejected_by_host_filter = false;
         if let Some(peer) = control_connection_peer {
             if !self.host_filter.as_ref().map_or(true, |f| f.accept(peer)) {
+                rejected_by_host_filter = true;
                 warn!(
                     filtered_node_ips = ?metadata
                         .peers
@@ -649,26 +976,53 @@ impl MetadataReader {
                     host filter. The driver will try to re-establish the \
                     control connection to a different node."
                 );
+            }
+        }
 
-                // Assuming here that known_peers are up-to-date
-                if !self.known_peers.is_empty() {
-                    self.control_connection_endpoint = self
-                        .known_peers
-                        .choose(&mut thread_rng())
-                        .expect("known_peers is empty - should be impossible")
-                        .clone();
-
-                    self.control_connection = Self::make_control_connection_pool(
-                        self.control_connection_endpoint.clone(),
-                        self.connection_config.clone(),
-                        self.keepalive_interval,
-                        self.control_connection_repair_requester.clone(),
-                    );
+        // Assuming here that known_peers are up-to-date
+        let mut known_peers = self.known_peers.clone();
+        known_peers.shuffle(&mut thread_rng());
+        let candidates = self.policy.candidates(known_peers);
+
+        let rejected_by_policy = !self.policy.accepts(&self.control_connection_endpoint)
+            || (!self.policy.prefers(&self.control_connection_endpoint)
+                && matches!(candidates.first(), Some(candidate) if self.policy.prefers(candidate)));
+        if rejected_by_policy {
+            debug!(
+                control_connection_address = ?self.control_connection_endpoint.address(),
+                "The control connection is established to a node excluded by \
+                the control connection policy or outside of its preferred datacenter, \
+                re-establishing it to a different node."
+            );
+        }
+
+        if rejected_by_host_filter || rejected_by_policy {
+            let new_endpoint = candidates.into_iter().next().or_else(|| {
+                if rejected_by_host_filter {
+                    self.known_peers.choose(&mut thread_rng()).cloned()
+                } else {
+                    None
                 }
+            });
+            if let Some(endpoint) = new_endpoint {
+                self.control_connection_endpoint = endpoint;
+                self.control_connection = Self::make_control_connection_pool(
+                    self.control_connection_endpoint.clone(),
+                    self.connection_config.clone(),
+                    self.keepalive_interval,
+                    self.control_connection_repair_requester.clone(),
+                );
             }
         }
     }
 
+    fn publish_status(&self) {
+        self.status.store(Arc::new(ControlConnectionStatus::new(
+            &self.control_connection_endpoint,
+            self.failovers,
+        )));
+    }
+
     fn make_control_connection_pool(
         endpoint: UntranslatedEndpoint,
         connection_config: ConnectionConfig,
@@ -685,6 +1039,12 @@ impl MetadataReader {
             // The shard-aware port won't be used with PerHost pool size anyway,
             // so explicitly disable it here
             can_use_shard_aware_port: false,
+
+            // The control connection is not used for requests,
+            // so its pool is not reported in metrics
+            metrics: None,
+            #[cfg(feature = "metrics-exporter")]
+            metrics_observer: None,
         };
 
         NodeConnectionPool::new(endpoint, pool_config, None, refresh_requester)
@@ -892,15 +1252,29 @@ async fn query_keyspaces(
         keyspaces_to_fetch,
     );
 
-    let (mut all_tables, mut all_views, mut all_user_defined_types) = if fetch_schema {
+    let (
+        mut all_tables,
+        mut all_views,
+        mut all_functions,
+        mut all_aggregates,
+        mut all_user_defined_types,
+    ) = if fetch_schema {
         let udts = query_user_defined_types(conn, keyspaces_to_fetch).await?;
         (
             query_tables(conn, keyspaces_to_fetch, &udts).await?,
             query_views(conn, keyspaces_to_fetch, &udts).await?,
+            query_functions(conn, keyspaces_to_fetch, &udts).await?,
+            query_aggregates(conn, keyspaces_to_fetch, &udts).await?,
             udts,
         )
     } else {
-        (HashMap::new(), HashMap::new(), HashMap::new())
+        (
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+        )
     };
 
     rows.map(|row_result| {
@@ -912,6 +1286,8 @@ async fn query_keyspaces(
         let strategy: Strategy = strategy_from_string_map(strategy_map)?;
         let tables = all_tables.remove(&keyspace_name).unwrap_or_default();
         let views = all_views.remove(&keyspace_name).unwrap_or_default();
+        let functions = all_functions.remove(&keyspace_name).unwrap_or_default();
+        let aggregates = all_aggregates.remove(&keyspace_name).unwrap_or_default();
         let user_defined_types = all_user_defined_types
             .remove(&keyspace_name)
             .unwrap_or_default();
@@ -921,6 +1297,8 @@ async fn query_keyspaces(
             tables,
             views,
             user_defined_types,
+            functions,
+            aggregates,
         };
 
         Ok((keyspace_name, keyspace))
@@ -1300,6 +1678,9 @@ async fn query_tables(
     );
     let mut result = HashMap::new();
     let mut tables = query_tables_schema(conn, keyspaces_to_fetch, udts).await?;
+    let mut all_options =
+        query_table_options(conn, keyspaces_to_fetch, "tables", "table_name").await?;
+    let mut all_indexes = query_indexes(conn, keyspaces_to_fetch).await?;
 
     rows.map(|row_result| {
         let row = row_result?;
@@ -1309,12 +1690,15 @@ async fn query_tables(
 
         let keyspace_and_table_name = (keyspace_name, table_name);
 
-        let table = tables.remove(&keyspace_and_table_name).unwrap_or(Table {
-            columns: HashMap::new(),
-            partition_key: vec![],
-            clustering_key: vec![],
-            partitioner: None,
-        });
+        let mut table = tables
+            .remove(&keyspace_and_table_name)
+            .unwrap_or_else(Table::empty);
+        table.options = all_options
+            .remove(&keyspace_and_table_name)
+            .unwrap_or_default();
+        table.indexes = all_indexes
+            .remove(&keyspace_and_table_name)
+            .unwrap_or_default();
 
         result
             .entry(keyspace_and_table_name.0)
@@ -1336,43 +1720,343 @@ async fn query_views(
 ) -> Result<HashMap<String, HashMap<String, MaterializedView>>, QueryError> {
     let rows = query_filter_keyspace_name(
         conn,
-        "SELECT keyspace_name, view_name, base_table_name FROM system_schema.views",
+        "SELECT keyspace_name, view_name, base_table_name, where_clause, include_all_columns FROM system_schema.views",
         keyspaces_to_fetch,
     );
 
     let mut result = HashMap::new();
     let mut tables = query_tables_schema(conn, keyspaces_to_fetch, udts).await?;
+    let mut all_options =
+        query_table_options(conn, keyspaces_to_fetch, "views", "view_name").await?;
 
-    rows.map(|row_result| {
-        let row = row_result?;
-        let (keyspace_name, view_name, base_table_name) = row.into_typed().map_err(|_| {
-            QueryError::ProtocolError("system_schema.views has invalid column type")
-        })?;
+    let views = rows
+        .map(|row_result| {
+            let row = row_result?;
+            let (keyspace_name, view_name, base_table_name, where_clause, include_all_columns): (
+                String,
+                String,
+                String,
+                Option<String>,
+                Option<bool>,
+            ) = row.into_typed().map_err(|_| {
+                QueryError::ProtocolError("system_schema.views has invalid column type")
+            })?;
 
-        let keyspace_and_view_name = (keyspace_name, view_name);
+            let keyspace_and_view_name = (keyspace_name, view_name);
+
+            let mut table = tables
+                .remove(&keyspace_and_view_name)
+                .unwrap_or_else(Table::empty);
+            table.options = all_options
+                .remove(&keyspace_and_view_name)
+                .unwrap_or_default();
+            let materialized_view = MaterializedView {
+                view_metadata: table,
+                base_table_name,
+                where_clause: where_clause.unwrap_or_default(),
+                include_all_columns: include_all_columns.unwrap_or_default(),
+            };
 
-        let table = tables.remove(&keyspace_and_view_name).unwrap_or(Table {
-            columns: HashMap::new(),
-            partition_key: vec![],
-            clustering_key: vec![],
-            partitioner: None,
-        });
-        let materialized_view = MaterializedView {
-            view_metadata: table,
-            base_table_name,
+            result
+                .entry(keyspace_and_view_name.0)
+                .or_insert_with(HashMap::new)
+                .insert(keyspace_and_view_name.1, materialized_view);
+
+            Ok::<_, QueryError>(())
+        })
+        .try_for_each(|_| future::ok(()))
+        .await;
+
+    match views {
+        // Servers without materialized views support may lack system_schema.views.
+        Err(QueryError::DbError(DbError::Invalid, _)) => Ok(HashMap::new()),
+        Err(err) => Err(err),
+        Ok(()) => Ok(result),
+    }
+}
+
+async fn query_table_options(
+    conn: &Arc<Connection>,
+    keyspaces_to_fetch: &[String],
+    schema_table: &str,
+    name_column: &str,
+) -> Result<HashMap<(String, String), TableOptions>, QueryError> {
+    let query_str = format!(
+        "SELECT keyspace_name, {}, comment, compaction, compression, caching, \
+        default_time_to_live, gc_grace_seconds, speculative_retry FROM system_schema.{}",
+        name_column, schema_table
+    );
+    let rows = query_filter_keyspace_name(conn, &query_str, keyspaces_to_fetch);
+
+    type OptionsRow = (
+        String,
+        String,
+        Option<String>,
+        Option<HashMap<String, String>>,
+        Option<HashMap<String, String>>,
+        Option<HashMap<String, String>>,
+        Option<i32>,
+        Option<i32>,
+        Option<String>,
+    );
+
+    let result = rows
+        .map(|row_result| {
+            let (
+                keyspace_name,
+                table_name,
+                comment,
+                compaction,
+                compression,
+                caching,
+                default_time_to_live,
+                gc_grace_seconds,
+                speculative_retry,
+            ): OptionsRow = row_result?.into_typed().map_err(|_| {
+                QueryError::InvalidMessage(format!(
+                    "system_schema.{} has invalid column type",
+                    schema_table
+                ))
+            })?;
+
+            let options = TableOptions {
+                comment: comment.unwrap_or_default(),
+                compaction: compaction.unwrap_or_default(),
+                compression: compression.unwrap_or_default(),
+                caching: caching.unwrap_or_default(),
+                default_time_to_live: default_time_to_live.unwrap_or_default(),
+                gc_grace_seconds: gc_grace_seconds.unwrap_or_default(),
+                speculative_retry: speculative_retry.unwrap_or_default(),
+            };
+            Ok::<_, QueryError>(((keyspace_name, table_name), options))
+        })
+        .try_collect::<HashMap<_, _>>()
+        .await;
+
+    match result {
+        // Servers which do not have some of the option columns respond with Invalid,
+        // in which case the tables are reported with default options.
+        Err(QueryError::DbError(DbError::Invalid, _)) => Ok(HashMap::new()),
+        result => result,
+    }
+}
+
+async fn query_indexes(
+    conn: &Arc<Connection>,
+    keyspaces_to_fetch: &[String],
+) -> Result<HashMap<(String, String), HashMap<String, Index>>, QueryError> {
+    let rows = query_filter_keyspace_name(
+        conn,
+        "SELECT keyspace_name, table_name, index_name, kind, options FROM system_schema.indexes",
+        keyspaces_to_fetch,
+    );
+
+    let mut result: HashMap<(String, String), HashMap<String, Index>> = HashMap::new();
+
+    let indexes = rows
+        .map(|row_result| {
+            let (keyspace_name, table_name, index_name, kind, options): (
+                String,
+                String,
+                String,
+                String,
+                Option<HashMap<String, String>>,
+            ) = row_result?.into_typed().map_err(|_| {
+                QueryError::ProtocolError("system_schema.indexes has invalid column type")
+            })?;
+
+            let kind = IndexKind::from_str(&kind)
+                .map_err(|_| QueryError::InvalidMessage(format!("invalid index kind {}", kind)))?;
+
+            result
+                .entry((keyspace_name, table_name))
+                .or_default()
+                .insert(
+                    index_name,
+                    Index {
+                        kind,
+                        options: options.unwrap_or_default(),
+                    },
+                );
+
+            Ok::<_, QueryError>(())
+        })
+        .try_for_each(|_| future::ok(()))
+        .await;
+
+    match indexes {
+        // Servers without secondary indexes support may lack system_schema.indexes.
+        Err(QueryError::DbError(DbError::Invalid, _)) => Ok(HashMap::new()),
+        Err(err) => Err(err),
+        Ok(()) => Ok(result),
+    }
+}
+
+#[derive(FromRow, Debug)]
+#[scylla_crate = "crate"]
+struct FunctionRow {
+    keyspace_name: String,
+    function_name: String,
+    argument_names: Option<Vec<String>>,
+    argument_types: Option<Vec<String>>,
+    return_type: String,
+    language: String,
+    body: String,
+    called_on_null_input: bool,
+}
+
+impl FunctionRow {
+    fn into_function(
+        self,
+        udts: &HashMap<String, HashMap<String, Arc<UserDefinedType>>>,
+    ) -> Result<(String, FunctionSignature, UserDefinedFunction), InvalidCqlType> {
+        // Empty collections are stored as nulls, so functions without arguments have null lists.
+        let argument_types = self.argument_types.unwrap_or_default();
+        let function = UserDefinedFunction {
+            argument_names: self.argument_names.unwrap_or_default(),
+            argument_types: parse_cql_types(&argument_types, &self.keyspace_name, udts)?,
+            return_type: map_string_to_cql_type(&self.return_type)?
+                .into_cql_type(&self.keyspace_name, udts),
+            language: self.language,
+            body: self.body,
+            called_on_null_input: self.called_on_null_input,
         };
+        let signature = FunctionSignature {
+            name: self.function_name,
+            argument_types,
+        };
+        Ok((self.keyspace_name, signature, function))
+    }
+}
 
-        result
-            .entry(keyspace_and_view_name.0)
-            .or_insert_with(HashMap::new)
-            .insert(keyspace_and_view_name.1, materialized_view);
+#[derive(FromRow, Debug)]
+#[scylla_crate = "crate"]
+struct AggregateRow {
+    keyspace_name: String,
+    aggregate_name: String,
+    argument_types: Option<Vec<String>>,
+    return_type: String,
+    state_func: String,
+    state_type: String,
+    final_func: Option<String>,
+    initcond: Option<String>,
+}
 
-        Ok::<_, QueryError>(())
-    })
-    .try_for_each(|_| future::ok(()))
-    .await?;
+impl AggregateRow {
+    fn into_aggregate(
+        self,
+        udts: &HashMap<String, HashMap<String, Arc<UserDefinedType>>>,
+    ) -> Result<(String, FunctionSignature, UserDefinedAggregate), InvalidCqlType> {
+        let argument_types = self.argument_types.unwrap_or_default();
+        let aggregate = UserDefinedAggregate {
+            argument_types: parse_cql_types(&argument_types, &self.keyspace_name, udts)?,
+            return_type: map_string_to_cql_type(&self.return_type)?
+                .into_cql_type(&self.keyspace_name, udts),
+            state_func: self.state_func,
+            state_type: map_string_to_cql_type(&self.state_type)?
+                .into_cql_type(&self.keyspace_name, udts),
+            final_func: self.final_func,
+            initcond: self.initcond,
+        };
+        let signature = FunctionSignature {
+            name: self.aggregate_name,
+            argument_types,
+        };
+        Ok((self.keyspace_name, signature, aggregate))
+    }
+}
 
-    Ok(result)
+fn parse_cql_types(
+    types: &[String],
+    keyspace_name: &String,
+    udts: &HashMap<String, HashMap<String, Arc<UserDefinedType>>>,
+) -> Result<Vec<CqlType>, InvalidCqlType> {
+    types
+        .iter()
+        .map(|type_| Ok(map_string_to_cql_type(type_)?.into_cql_type(keyspace_name, udts)))
+        .collect()
+}
+
+async fn query_functions(
+    conn: &Arc<Connection>,
+    keyspaces_to_fetch: &[String],
+    udts: &HashMap<String, HashMap<String, Arc<UserDefinedType>>>,
+) -> Result<HashMap<String, HashMap<FunctionSignature, UserDefinedFunction>>, QueryError> {
+    let rows = query_filter_keyspace_name(
+        conn,
+        "SELECT keyspace_name, function_name, argument_names, argument_types, return_type, language, body, called_on_null_input FROM system_schema.functions",
+        keyspaces_to_fetch,
+    );
+
+    let mut result: HashMap<String, HashMap<FunctionSignature, UserDefinedFunction>> =
+        HashMap::new();
+
+    let functions = rows
+        .map(|row_result| {
+            let (keyspace_name, signature, function) = row_result?
+                .into_typed::<FunctionRow>()
+                .map_err(|_| {
+                    QueryError::ProtocolError("system_schema.functions has invalid column type")
+                })?
+                .into_function(udts)?;
+
+            result
+                .entry(keyspace_name)
+                .or_default()
+                .insert(signature, function);
+
+            Ok::<_, QueryError>(())
+        })
+        .try_for_each(|_| future::ok(()))
+        .await;
+
+    match functions {
+        // Servers without user-defined functions support may lack system_schema.functions.
+        Err(QueryError::DbError(DbError::Invalid, _)) => Ok(HashMap::new()),
+        Err(err) => Err(err),
+        Ok(()) => Ok(result),
+    }
+}
+
+async fn query_aggregates(
+    conn: &Arc<Connection>,
+    keyspaces_to_fetch: &[String],
+    udts: &HashMap<String, HashMap<String, Arc<UserDefinedType>>>,
+) -> Result<HashMap<String, HashMap<FunctionSignature, UserDefinedAggregate>>, QueryError> {
+    let rows = query_filter_keyspace_name(
+        conn,
+        "SELECT keyspace_name, aggregate_name, argument_types, return_type, state_func, state_type, final_func, initcond FROM system_schema.aggregates",
+        keyspaces_to_fetch,
+    );
+
+    let mut result: HashMap<String, HashMap<FunctionSignature, UserDefinedAggregate>> =
+        HashMap::new();
+
+    let aggregates = rows
+        .map(|row_result| {
+            let (keyspace_name, signature, aggregate) = row_result?
+                .into_typed::<AggregateRow>()
+                .map_err(|_| {
+                    QueryError::ProtocolError("system_schema.aggregates has invalid column type")
+                })?
+                .into_aggregate(udts)?;
+
+            result
+                .entry(keyspace_name)
+                .or_default()
+                .insert(signature, aggregate);
+
+            Ok::<_, QueryError>(())
+        })
+        .try_for_each(|_| future::ok(()))
+        .await;
+
+    match aggregates {
+        // Servers without user-defined aggregates support may lack system_schema.aggregates.
+        Err(QueryError::DbError(DbError::Invalid, _)) => Ok(HashMap::new()),
+        Err(err) => Err(err),
+        Ok(()) => Ok(result),
+    }
 }
 
 async fn query_tables_schema(
@@ -1472,6 +2156,8 @@ async fn query_tables_schema(
                 partition_key,
                 clustering_key,
                 partitioner,
+                options: TableOptions::default(),
+                indexes: HashMap::new(),
             },
         );
     }
@@ -1686,6 +2372,56 @@ fn strategy_from_string_map(
 mod tests {
     use super::*;
 
+    #[test]
+    fn udt_to_column_type() {
+        let inner = Arc::new(UserDefinedType {
+            name: "inner".to_string(),
+            keyspace: "ks".to_string(),
+            field_types: vec![("a".to_string(), CqlType::Native(NativeType::Int))],
+        });
+        let outer = UserDefinedType {
+            name: "outer".to_string(),
+            keyspace: "ks".to_string(),
+            field_types: vec![(
+                "list".to_string(),
+                CqlType::Collection {
+                    frozen: false,
+                    type_: CollectionType::List(Box::new(CqlType::UserDefinedType {
+                        frozen: true,
+                        definition: Ok(inner),
+                    })),
+                },
+            )],
+        };
+
+        assert_eq!(
+            outer.to_column_type(),
+            Ok(ColumnType::UserDefinedType {
+                type_name: "outer".to_string(),
+                keyspace: "ks".to_string(),
+                field_types: vec![(
+                    "list".to_string(),
+                    ColumnType::List(Box::new(ColumnType::UserDefinedType {
+                        type_name: "inner".to_string(),
+                        keyspace: "ks".to_string(),
+                        field_types: vec![("a".to_string(), ColumnType::Int)].into(),
+                    }))
+                )]
+                .into(),
+            })
+        );
+
+        let missing = MissingUserDefinedType {
+            name: "gone".to_string(),
+            keyspace: "ks".to_string(),
+        };
+        let broken = CqlType::Tuple(vec![CqlType::UserDefinedType {
+            frozen: true,
+            definition: Err(missing.clone()),
+        }]);
+        assert_eq!(broken.to_column_type(), Err(missing));
+    }
+
     #[test]
     fn test_cql_type_parsing() {
         let test_cases = [
@@ -1849,4 +2585,155 @@ mod tests {
             assert_eq!(parsed, expected);
         }
     }
+
+    fn point_udt() -> HashMap<String, HashMap<String, Arc<UserDefinedType>>> {
+        let point = Arc::new(UserDefinedType {
+            name: "point".to_string(),
+            keyspace: "ks".to_string(),
+            field_types: vec![
+                ("x".to_string(), CqlType::Native(NativeType::Int)),
+                ("y".to_string(), CqlType::Native(NativeType::Int)),
+            ],
+        });
+        HashMap::from([(
+            "ks".to_string(),
+            HashMap::from([("point".to_string(), point)]),
+        )])
+    }
+
+    #[test]
+    fn test_function_from_row() {
+        let udts = point_udt();
+        let row = FunctionRow {
+            keyspace_name: "ks".to_string(),
+            function_name: "sum_all".to_string(),
+            argument_names: Some(vec!["values".to_string(), "origin".to_string()]),
+            argument_types: Some(vec![
+                "frozen<map<text, list<int>>>".to_string(),
+                "frozen<point>".to_string(),
+            ]),
+            return_type: "bigint".to_string(),
+            language: "lua".to_string(),
+            body: "return 0".to_string(),
+            called_on_null_input: false,
+        };
+
+        let (keyspace_name, signature, function) = row.into_function(&udts).unwrap();
+        assert_eq!(keyspace_name, "ks");
+        assert_eq!(
+            signature,
+            FunctionSignature {
+                name: "sum_all".to_string(),
+                argument_types: vec![
+                    "frozen<map<text, list<int>>>".to_string(),
+                    "frozen<point>".to_string(),
+                ],
+            }
+        );
+        assert_eq!(
+            function,
+            UserDefinedFunction {
+                argument_names: vec!["values".to_string(), "origin".to_string()],
+                argument_types: vec![
+                    CqlType::Collection {
+                        frozen: true,
+                        type_: CollectionType::Map(
+                            Box::new(CqlType::Native(NativeType::Text)),
+                            Box::new(CqlType::Collection {
+                                frozen: false,
+                                type_: CollectionType::List(Box::new(CqlType::Native(
+                                    NativeType::Int
+                                ))),
+                            }),
+                        ),
+                    },
+                    CqlType::UserDefinedType {
+                        frozen: true,
+                        definition: Ok(udts["ks"]["point"].clone()),
+                    },
+                ],
+                return_type: CqlType::Native(NativeType::BigInt),
+                language: "lua".to_string(),
+                body: "return 0".to_string(),
+                called_on_null_input: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_function_without_arguments_from_row() {
+        // Empty lists are stored as nulls.
+        let row = FunctionRow {
+            keyspace_name: "ks".to_string(),
+            function_name: "zero".to_string(),
+            argument_names: None,
+            argument_types: None,
+            return_type: "int".to_string(),
+            language: "lua".to_string(),
+            body: "return 0".to_string(),
+            called_on_null_input: true,
+        };
+
+        let (_, signature, function) = row.into_function(&HashMap::new()).unwrap();
+        assert!(signature.argument_types.is_empty());
+        assert!(function.argument_names.is_empty());
+        assert!(function.argument_types.is_empty());
+        assert!(function.called_on_null_input);
+    }
+
+    #[test]
+    fn test_aggregate_from_row() {
+        let udts = point_udt();
+        let row = AggregateRow {
+            keyspace_name: "ks".to_string(),
+            aggregate_name: "centroid".to_string(),
+            argument_types: Some(vec!["frozen<point>".to_string()]),
+            return_type: "frozen<point>".to_string(),
+            state_func: "accumulate".to_string(),
+            state_type: "frozen<tuple<bigint, bigint, int>>".to_string(),
+            final_func: Some("average".to_string()),
+            initcond: Some("(0, 0, 0)".to_string()),
+        };
+
+        let (keyspace_name, signature, aggregate) = row.into_aggregate(&udts).unwrap();
+        assert_eq!(keyspace_name, "ks");
+        assert_eq!(signature.name, "centroid");
+        assert_eq!(signature.argument_types, vec!["frozen<point>".to_string()]);
+
+        let point = CqlType::UserDefinedType {
+            frozen: true,
+            definition: Ok(udts["ks"]["point"].clone()),
+        };
+        assert_eq!(
+            aggregate,
+            UserDefinedAggregate {
+                argument_types: vec![point.clone()],
+                return_type: point,
+                state_func: "accumulate".to_string(),
+                state_type: CqlType::Tuple(vec![
+                    CqlType::Native(NativeType::BigInt),
+                    CqlType::Native(NativeType::BigInt),
+                    CqlType::Native(NativeType::Int),
+                ]),
+                final_func: Some("average".to_string()),
+                initcond: Some("(0, 0, 0)".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_function_with_invalid_type_from_row() {
+        let row = AggregateRow {
+            keyspace_name: "ks".to_string(),
+            aggregate_name: "broken".to_string(),
+            argument_types: Some(vec!["map<int>".to_string()]),
+            return_type: "int".to_string(),
+            state_func: "f".to_string(),
+            state_type: "int".to_string(),
+            final_func: None,
+            initcond: None,
+        };
+
+        row.into_aggregate(&HashMap::new()).unwrap_err();
+    }
 }
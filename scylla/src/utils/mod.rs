@@ -1,4 +1,4 @@
-pub(crate) mod parse;
+pub(crate) use scylla_cql::utils::parse;
 
 pub(crate) mod pretty;
 pub mod test_utils;
@@ -41,6 +41,9 @@ where
             CqlValue::Ascii(a) => write!(f, "{}", CqlStringLiteralDisplayer(a))?,
             CqlValue::Text(t) => write!(f, "{}", CqlStringLiteralDisplayer(t))?,
             CqlValue::Blob(b) => write!(f, "0x{:x}", HexBytes(b))?,
+            CqlValue::SharedBlob(b) => write!(f, "0x{:x}", HexBytes(b))?,
+            // Rendered as a blob, as there is no way to know its actual syntax
+            CqlValue::Unknown { raw, .. } => write!(f, "0x{:x}", HexBytes(raw))?,
             CqlValue::Empty => write!(f, "0x")?,
             CqlValue::Decimal(d) => write!(f, "{}", d)?,
             CqlValue::Float(fl) => write!(f, "{}", fl)?,
@@ -122,6 +125,10 @@ where
                 keyspace: _,
                 type_name: _,
                 fields,
+            }
+            | CqlValue::Custom {
+                class_name: _,
+                fields,
             } => {
                 f.write_str("{")?;
                 CommaSeparatedDisplayer(fields.iter().map(|(k, v)| {
@@ -44,7 +44,7 @@ pub(crate) async fn supports_feature(session: &Session, feature: &str) -> bool {
     }
 
     let (features,): (Option<String>,) = session
-        .query("SELECT supported_features FROM system.local", ())
+        .query_unpaged("SELECT supported_features FROM system.local", ())
         .await
         .unwrap()
         .single_row_typed()
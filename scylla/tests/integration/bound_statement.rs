@@ -0,0 +1,60 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::transport::session::Session;
+use scylla::SessionBuilder;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn bound_statement_is_sent_the_same_as_executed_statement() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+
+            let (execute_tx, mut execute_rx) = mpsc::unbounded_channel();
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Execute),
+                    RequestReaction::noop().with_feedback_when_performed(execute_tx.clone()),
+                )]));
+            }
+
+            let mut prepared = session
+                .prepare("SELECT host_id FROM system.peers WHERE peer = ? AND host_id = ? ALLOW FILTERING")
+                .await
+                .unwrap();
+            prepared.set_page_size(7);
+            let values = ("127.0.0.1".parse::<IpAddr>().unwrap(), uuid::Uuid::nil());
+            let bound = prepared.bind(&values).unwrap();
+
+            session.execute_unpaged(&prepared, values).await.unwrap();
+            let (executed, _shard) = execute_rx.recv().await.unwrap();
+            session.execute_bound(&bound).await.unwrap();
+            let (executed_bound, _shard) = execute_rx.recv().await.unwrap();
+
+            assert_eq!(executed_bound.opcode, executed.opcode);
+            assert_eq!(executed_bound.body, executed.body);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -0,0 +1,86 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::transport::session::Session;
+use scylla::{CachingSession, SessionBuilder};
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn concurrent_executions_of_uncached_statement_prepare_it_once() {
+    const TASKS: usize = 64;
+    const STATEMENT: &str = "SELECT key AS single_flight_mark FROM system.local WHERE key = ?";
+
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let (prepare_tx, mut prepare_rx) = mpsc::unbounded_channel();
+            let prepare_rule = RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Prepare).and(
+                    Condition::BodyContainsCaseSensitive(Box::new(*b"single_flight")),
+                ),
+                RequestReaction::noop().with_feedback_when_performed(prepare_tx),
+            );
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![prepare_rule.clone()]));
+            }
+
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+
+            let session: Arc<CachingSession> = Arc::new(CachingSession::from(session, 10));
+            let handles = (0..TASKS).map(|_| {
+                let session = session.clone();
+                tokio::spawn(async move { session.execute_unpaged(STATEMENT, ("local",)).await })
+            });
+            for handle in handles.collect::<Vec<_>>() {
+                handle.await.unwrap().unwrap();
+            }
+            let mut frames = 0;
+            while prepare_rx.try_recv().is_ok() {
+                frames += 1;
+            }
+
+            // A single prepare is sent to every open connection. Measure it afterwards,
+            // because connection pools may still be filling up when the session is built.
+            session
+                .get_session()
+                .prepare("SELECT key AS single_flight_baseline FROM system.local")
+                .await
+                .unwrap();
+            let mut frames_per_prepare = 0;
+            while prepare_rx.try_recv().is_ok() {
+                frames_per_prepare += 1;
+            }
+            assert!(frames > 0);
+            assert!(
+                frames <= frames_per_prepare,
+                "{} PREPARE frames sent, while a single prepare takes {}",
+                frames,
+                frames_per_prepare
+            );
+
+            let stats = session.cache_stats();
+            assert_eq!(stats.misses, 1);
+            assert_eq!(stats.hits, TASKS as u64 - 1);
+            assert_eq!(stats.size, 1);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -0,0 +1,117 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::query::Query;
+use scylla::transport::session::Session;
+use scylla::SessionBuilder;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Responses to queries containing the mark are delayed by the proxy for this long.
+const DELAY: Duration = Duration::from_millis(500);
+
+fn delay_marked_queries() -> Option<Vec<RequestRule>> {
+    Some(vec![RequestRule(
+        Condition::RequestOpcode(RequestOpcode::Query).and(Condition::BodyContainsCaseSensitive(
+            Box::new(*b"cancellation_mark"),
+        )),
+        RequestReaction::delay(DELAY),
+    )])
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn dropped_requests_release_stream_permits() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(delay_marked_queries());
+            }
+
+            let query = Query::new("SELECT host_id AS cancellation_mark FROM system.local");
+            let dropped =
+                tokio::time::timeout(Duration::from_millis(50), session.query_unpaged(query, &[]));
+            assert!(dropped.await.is_err());
+
+            // The request was already sent, so its stream id is kept until the late response
+            tokio::time::sleep(DELAY * 2).await;
+            let metrics = session.get_metrics();
+            assert_eq!(metrics.get_orphaned_requests(), 1);
+            assert_eq!(metrics.get_late_responses(), 1);
+            assert_eq!(metrics.get_in_flight_requests(), 0);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn dropped_iterators_cancel_their_workers() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(delay_marked_queries());
+            }
+            let metrics = session.get_metrics();
+
+            // Two peers make two pages, the second one is fetched in the background
+            let mut query = Query::new("SELECT peer AS cancellation_mark FROM system.peers");
+            query.set_page_size(1);
+
+            // Dropped while waiting for the first page
+            let creation = tokio::time::timeout(
+                Duration::from_millis(50),
+                session.query_iter(query.clone(), &[]),
+            );
+            assert!(creation.await.is_err());
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            assert_eq!(metrics.get_running_iterator_workers(), 0);
+
+            // Dropped between pages, while the second page is being fetched
+            let iterator = session.query_iter(query, &[]).await.unwrap();
+            assert_eq!(metrics.get_running_iterator_workers(), 1);
+            drop(iterator);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            assert_eq!(metrics.get_running_iterator_workers(), 0);
+
+            // Late responses to the abandoned requests free their stream ids
+            tokio::time::sleep(DELAY * 2).await;
+            assert_eq!(metrics.get_in_flight_requests(), 0);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -0,0 +1,150 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::transport::metrics::Metrics;
+use scylla::transport::session::{ConnectionCloseReason, Session};
+use scylla::SessionBuilder;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn closed_by(metrics: &Metrics, reason: ConnectionCloseReason) -> u64 {
+    metrics
+        .node_connections_snapshot()
+        .iter()
+        .map(|node| node.closed_by(reason))
+        .sum()
+}
+
+// Waits until a connection is closed for the reason, for at most 10 seconds.
+async fn wait_for_close(metrics: &Metrics, reason: ConnectionCloseReason) {
+    for _ in 0..100 {
+        if closed_by(metrics, reason) > 0 {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("No connection was closed with reason {}", reason);
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn connections_broken_by_node_are_closed_with_error() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+            let metrics = session.get_metrics();
+            assert!(metrics
+                .node_connections_snapshot()
+                .iter()
+                .any(|node| node.opened > 0));
+            assert_eq!(closed_by(&metrics, ConnectionCloseReason::Error), 0);
+
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Query).and(
+                        Condition::BodyContainsCaseSensitive(Box::new(*b"lifecycle_mark")),
+                    ),
+                    RequestReaction::drop_connection(),
+                )]));
+            }
+            let _ = session
+                .query_unpaged("SELECT host_id AS lifecycle_mark FROM system.local", &[])
+                .await;
+
+            wait_for_close(&metrics, ConnectionCloseReason::Error).await;
+            assert_eq!(
+                closed_by(&metrics, ConnectionCloseReason::KeepaliveTimeout),
+                0
+            );
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn unanswered_keepalives_close_connections_with_keepalive_timeout() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .keepalive_interval(Duration::from_millis(200))
+                .keepalive_timeout(Duration::from_millis(500))
+                .build()
+                .await
+                .unwrap();
+            let metrics = session.get_metrics();
+
+            // Keepalive requests are OPTIONS requests
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Options),
+                    RequestReaction::drop_frame(),
+                )]));
+            }
+
+            wait_for_close(&metrics, ConnectionCloseReason::KeepaliveTimeout).await;
+            assert_eq!(closed_by(&metrics, ConnectionCloseReason::PoolShutdown), 0);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn dropped_sessions_close_connections_with_pool_shutdown() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+            let metrics = session.get_metrics();
+            assert_eq!(closed_by(&metrics, ConnectionCloseReason::PoolShutdown), 0);
+
+            drop(session);
+            wait_for_close(&metrics, ConnectionCloseReason::PoolShutdown).await;
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -56,12 +56,14 @@ fn pairs_of_all_consistencies() -> impl Iterator<Item = (Consistency, Option<Ser
 
 const CREATE_TABLE_STR: &str = "CREATE TABLE consistency_tests (a int, b int, PRIMARY KEY (a, b))";
 const QUERY_STR: &str = "INSERT INTO consistency_tests (a, b) VALUES (?, 1)";
+const CONDITIONAL_QUERY_STR: &str =
+    "INSERT INTO consistency_tests (a, b) VALUES (?, 1) IF NOT EXISTS";
 
 async fn create_schema(session: &Session, ks: &str) {
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+    session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
     session.use_keyspace(ks, false).await.unwrap();
 
-    session.query(CREATE_TABLE_STR, &[]).await.unwrap();
+    session.query_unpaged(CREATE_TABLE_STR, &[]).await.unwrap();
 }
 
 // The following functions perform a request with consistencies set directly on a statement.
@@ -74,7 +76,7 @@ async fn query_consistency_set_directly(
     let mut query = query.clone();
     query.set_consistency(c);
     query.set_serial_consistency(sc);
-    session.query(query.clone(), (1,)).await.unwrap();
+    session.query_unpaged(query.clone(), (1,)).await.unwrap();
     session.query_iter(query, (1,)).await.unwrap();
 }
 
@@ -87,7 +89,7 @@ async fn execute_consistency_set_directly(
     let mut prepared = prepared.clone();
     prepared.set_consistency(c);
     prepared.set_serial_consistency(sc);
-    session.execute(&prepared, (1,)).await.unwrap();
+    session.execute_unpaged(&prepared, (1,)).await.unwrap();
     session.execute_iter(prepared, (1,)).await.unwrap();
 }
 
@@ -111,7 +113,7 @@ async fn query_consistency_set_on_exec_profile(
 ) {
     let mut query = query.clone();
     query.set_execution_profile_handle(Some(profile));
-    session.query(query.clone(), (1,)).await.unwrap();
+    session.query_unpaged(query.clone(), (1,)).await.unwrap();
     session.query_iter(query, (1,)).await.unwrap();
 }
 
@@ -122,7 +124,7 @@ async fn execute_consistency_set_on_exec_profile(
 ) {
     let mut prepared = prepared.clone();
     prepared.set_execution_profile_handle(Some(profile));
-    session.execute(&prepared, (1,)).await.unwrap();
+    session.execute_unpaged(&prepared, (1,)).await.unwrap();
     session.execute_iter(prepared, (1,)).await.unwrap();
 }
 
@@ -220,7 +222,7 @@ async fn check_for_all_consistencies_and_setting_options<
         rx = after_session_init(rx).await;
 
         session_with_consistencies
-            .query(QUERY_STR, (1,))
+            .query_unpaged(QUERY_STR, (1,))
             .await
             .unwrap();
         rx = check_consistencies(consistency, serial_consistency, rx).await;
@@ -231,7 +233,7 @@ async fn check_for_all_consistencies_and_setting_options<
         rx = check_consistencies(consistency, serial_consistency, rx).await;
 
         session_with_consistencies
-            .execute(&prepared, (1,))
+            .execute_unpaged(&prepared, (1,))
             .await
             .unwrap();
         rx = check_consistencies(consistency, serial_consistency, rx).await;
@@ -333,6 +335,116 @@ async fn consistency_is_correctly_set_in_cql_requests() {
     }
 }
 
+// Checks that the session defaults are set in the CQL request frame when neither the statement
+// nor its execution profile sets the consistencies, and that the default serial consistency
+// is sent only with conditional statements. The consistencies used are reported in the result.
+#[tokio::test]
+#[ntest::timeout(60000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn session_default_consistencies_are_set_in_cql_requests() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let (request_tx, mut request_rx) = mpsc::unbounded_channel();
+            for running_node in running_proxy.running_nodes.iter_mut() {
+                running_node.change_request_rules(Some(vec![RequestRule(
+                    Condition::not(Condition::ConnectionRegisteredAnyEvent).and(
+                        Condition::RequestOpcode(RequestOpcode::Execute)
+                            .or(Condition::RequestOpcode(RequestOpcode::Batch))
+                            .or(Condition::and(
+                                Condition::RequestOpcode(RequestOpcode::Query),
+                                Condition::BodyContainsCaseSensitive(Box::new(
+                                    *b"INTO consistency_tests",
+                                )),
+                            )),
+                    ),
+                    RequestReaction::noop().with_feedback_when_performed(request_tx.clone()),
+                )]));
+            }
+
+            async fn check_request(
+                request_rx: &mut UnboundedReceiver<(RequestFrame, Option<TargetShard>)>,
+                consistency: Consistency,
+                serial_consistency: Option<SerialConsistency>,
+            ) {
+                let (request_frame, _shard) = request_rx.recv().await.unwrap();
+                let request = request_frame.deserialize().unwrap();
+                assert_eq!(request.get_consistency().unwrap(), consistency);
+                assert_eq!(
+                    request.get_serial_consistency().unwrap(),
+                    serial_consistency
+                );
+            }
+
+            let ks = unique_keyspace_name();
+            let session_builder = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map));
+            let session = session_builder.clone().build().await.unwrap();
+            create_schema(&session, &ks).await;
+
+            // Consistencies supported by conditional statements
+            let defaults = [
+                (Consistency::One, Some(SerialConsistency::Serial)),
+                (Consistency::Quorum, Some(SerialConsistency::LocalSerial)),
+                (Consistency::LocalQuorum, None),
+            ];
+            for (consistency, serial_consistency) in defaults {
+                let session = session_builder
+                    .clone()
+                    .default_consistency(consistency)
+                    .default_serial_consistency(serial_consistency)
+                    .build()
+                    .await
+                    .unwrap();
+                session.use_keyspace(&ks, true).await.unwrap();
+                let conditional = session.prepare(CONDITIONAL_QUERY_STR).await.unwrap();
+                let non_conditional = session.prepare(QUERY_STR).await.unwrap();
+                let batch = Batch::new_with_statements(
+                    BatchType::Logged,
+                    vec![BatchStatement::Query(Query::from(CONDITIONAL_QUERY_STR))],
+                );
+                while request_rx.try_recv().is_ok() {}
+
+                // Conditional statements are sent with the default serial consistency
+                let result = session
+                    .query_unpaged(CONDITIONAL_QUERY_STR, (1,))
+                    .await
+                    .unwrap();
+                assert_eq!(result.consistency, Some(consistency));
+                assert_eq!(result.serial_consistency, serial_consistency);
+                check_request(&mut request_rx, consistency, serial_consistency).await;
+                let result = session.execute_unpaged(&conditional, (1,)).await.unwrap();
+                assert_eq!(result.serial_consistency, serial_consistency);
+                check_request(&mut request_rx, consistency, serial_consistency).await;
+                session.batch(&batch, ((1,),)).await.unwrap();
+                check_request(&mut request_rx, consistency, serial_consistency).await;
+
+                // Other ones are sent without it
+                let result = session.query_unpaged(QUERY_STR, (1,)).await.unwrap();
+                assert_eq!(result.consistency, Some(consistency));
+                assert_eq!(result.serial_consistency, None);
+                check_request(&mut request_rx, consistency, None).await;
+                let result = session
+                    .execute_unpaged(&non_conditional, (1,))
+                    .await
+                    .unwrap();
+                assert_eq!(result.serial_consistency, None);
+                check_request(&mut request_rx, consistency, None).await;
+            }
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct OwnedRoutingInfo {
     pub consistency: Consistency,
@@ -474,5 +586,5 @@ async fn consistency_allows_for_paxos_selects() {
 
     let mut query = Query::from("SELECT host_id FROM system.peers WHERE peer = '127.0.0.1'");
     query.set_consistency(Consistency::Serial);
-    session.query(query, ()).await.unwrap();
+    session.query_unpaged(query, ()).await.unwrap();
 }
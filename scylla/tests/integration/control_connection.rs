@@ -0,0 +1,87 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::transport::control_connection_policy::ControlConnectionPolicy;
+use scylla::transport::session::Session;
+use scylla::transport::NodeAddr;
+use scylla::SessionBuilder;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn control_connection_fails_over_from_stalled_node() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let first: SocketAddr = proxy_uris[0].parse().unwrap();
+
+            // The session knows only the first node, so the control connection is established to it
+            let session: Session = SessionBuilder::new()
+                .known_node(first.to_string())
+                .address_translator(Arc::new(translation_map.clone()))
+                .control_connection_policy(
+                    ControlConnectionPolicy::new()
+                        .unhealthy_threshold(Duration::from_millis(500))
+                        .heartbeat_interval(Duration::from_millis(100)),
+                )
+                .build()
+                .await
+                .unwrap();
+            let status = session.get_control_connection_status();
+            assert_eq!(status.address, NodeAddr::Untranslatable(first));
+            assert_eq!(status.failovers, 0);
+
+            // The control node stops answering, while the others report registrations for events
+            running_proxy.running_nodes[0].change_request_rules(Some(vec![RequestRule(
+                Condition::ConnectionRegisteredAnyEvent,
+                RequestReaction::drop_frame(),
+            )]));
+            let (register_tx, mut register_rx) = mpsc::unbounded_channel();
+            for node in &mut running_proxy.running_nodes[1..] {
+                node.change_request_rules(Some(vec![RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Register),
+                    RequestReaction::noop().with_feedback_when_performed(register_tx.clone()),
+                )]));
+            }
+
+            // The new control connection registers for events on another node...
+            tokio::time::timeout(Duration::from_secs(10), register_rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+
+            // ...and is reported once the metadata is fetched on it
+            let status = loop {
+                let status = session.get_control_connection_status();
+                if status.failovers > 0 {
+                    break status;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            };
+            assert_eq!(status.failovers, 1);
+            assert_eq!(session.get_metrics().get_control_connection_failovers(), 1);
+            let address = SocketAddr::new(status.address.ip(), status.address.port());
+            let translated = translation_map.get(&address).copied().unwrap_or(address);
+            assert_ne!(translated, first);
+
+            // Metadata keeps being fetched on the new control connection
+            session.refresh_metadata().await.unwrap();
+            assert_eq!(session.get_control_connection_status().failovers, 1);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -0,0 +1,163 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::query::Query;
+use scylla::transport::errors::{DbError, QueryError};
+use scylla::transport::execution_budget::ExecutionBudget;
+use scylla::transport::session::Session;
+use scylla::SessionBuilder;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+// A statement selecting from the local node, marked so that proxy rules can match it
+fn step(label: &str, mark: &str) -> Query {
+    let mut query = Query::new(format!("SELECT host_id AS {} FROM system.local", mark));
+    query.set_label(label);
+    query.set_is_idempotent(true);
+    query
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn retries_draw_from_shared_attempts() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Query).and(
+                        Condition::BodyContainsCaseSensitive(Box::new(*b"budget_fail")),
+                    ),
+                    RequestReaction::forge().overloaded(),
+                )]));
+            }
+
+            // All statements complete within the budget
+            let budget = ExecutionBudget {
+                max_attempts: Some(2),
+                ..Default::default()
+            };
+            let execution = session
+                .execute_with_budget(budget, |handle| async move {
+                    handle.query(step("first", "budget_ok"), &[]).await?;
+                    handle.query(step("second", "budget_ok"), &[]).await?;
+                    Ok::<_, QueryError>(handle.remaining_attempts())
+                })
+                .await;
+            assert_eq!(execution.result.unwrap(), Some(0));
+            assert_eq!(execution.completed, ["first", "second"]);
+            assert_eq!(execution.attempts, 2);
+
+            // The failing statement is retried once, then the budget is exhausted
+            let budget = ExecutionBudget {
+                max_attempts: Some(3),
+                ..Default::default()
+            };
+            let execution = session
+                .execute_with_budget(budget, |handle| async move {
+                    handle.query(step("reserve", "budget_ok"), &[]).await?;
+
+                    let error = handle
+                        .query(step("charge", "budget_fail"), &[])
+                        .await
+                        .unwrap_err();
+                    assert!(matches!(error, QueryError::DbError(DbError::Overloaded, _)));
+
+                    // Further statements aren't sent
+                    let exhausted = handle
+                        .query(step("ship", "budget_ok"), &[])
+                        .await
+                        .unwrap_err();
+                    assert!(matches!(exhausted, QueryError::BudgetExhausted { .. }));
+                    Err::<(), _>(error)
+                })
+                .await;
+            assert!(execution.result.is_err());
+            assert_eq!(execution.completed, ["reserve"]);
+            assert_eq!(execution.attempts, 3);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn requests_are_limited_by_shared_time() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Query).and(
+                        Condition::BodyContainsCaseSensitive(Box::new(*b"budget_hang")),
+                    ),
+                    RequestReaction::drop_frame(),
+                )]));
+            }
+
+            let budget = ExecutionBudget {
+                max_total_time: Some(Duration::from_millis(500)),
+                ..Default::default()
+            };
+            let execution = session
+                .execute_with_budget(budget, |handle| async move {
+                    handle.query(step("reserve", "budget_ok"), &[]).await?;
+
+                    // The request times out when the budget runs out
+                    let error = handle
+                        .query(step("charge", "budget_hang"), &[])
+                        .await
+                        .unwrap_err();
+                    assert!(matches!(error, QueryError::RequestTimeout(_)));
+                    assert_eq!(handle.remaining_time(), Some(Duration::ZERO));
+
+                    let exhausted = handle
+                        .query(step("ship", "budget_ok"), &[])
+                        .await
+                        .unwrap_err();
+                    assert!(matches!(exhausted, QueryError::BudgetExhausted { .. }));
+                    Err::<(), _>(error)
+                })
+                .await;
+            assert!(execution.result.is_err());
+            assert_eq!(execution.completed, ["reserve"]);
+            assert!(execution.elapsed >= Duration::from_millis(500));
+            assert!(execution.elapsed < Duration::from_secs(5));
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -0,0 +1,157 @@
+use crate::utils::test_with_3_node_cluster;
+use assert_matches::assert_matches;
+use scylla::history::AttemptOutcome;
+use scylla::query::Query;
+use scylla::retry_policy::RetryDecision;
+use scylla::transport::errors::{DbError, ExecutionError, QueryError};
+use scylla::transport::interceptor::{RequestContext, RequestInterceptor};
+use scylla::transport::session::Session;
+use scylla::{QueryResult, SessionBuilder};
+use scylla_cql::Consistency;
+use scylla_proxy::{
+    Condition, ProxyError, RequestOpcode, RequestReaction, RequestRule, ShardAwareness, WorkerError,
+};
+use std::sync::{Arc, Mutex};
+
+const MARKED_QUERY: &str = "SELECT host_id AS execution_history_mark FROM system.local";
+
+fn marked_query() -> Condition {
+    Condition::RequestOpcode(RequestOpcode::Query).and(Condition::BodyContainsCaseSensitive(
+        Box::new(*b"execution_history_mark"),
+    ))
+}
+
+/// Keeps the last error passed to `on_response`.
+#[derive(Debug, Default)]
+struct ErrorCollector(Mutex<Option<ExecutionError>>);
+
+#[async_trait::async_trait]
+impl RequestInterceptor for ErrorCollector {
+    async fn on_request(&self, _ctx: &mut RequestContext<'_>) -> Result<(), QueryError> {
+        Ok(())
+    }
+
+    fn on_response(&self, _ctx: &RequestContext<'_>, result: &Result<QueryResult, ExecutionError>) {
+        if let Err(err) = result {
+            *self.0.lock().unwrap() = Some(err.clone());
+        }
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn transient_failure_is_recorded_in_history() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+
+            // The first marked query on each node times out in a way
+            // which the default retry policy retries on the same node
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![RequestRule(
+                    marked_query().and(Condition::TrueForLimitedTimes(1)),
+                    RequestReaction::forge_with_error(DbError::ReadTimeout {
+                        consistency: Consistency::One,
+                        received: 1,
+                        required: 1,
+                        data_present: false,
+                    }),
+                )]));
+            }
+
+            let mut query = Query::new(MARKED_QUERY);
+            query.set_history_collection(true);
+            let result = session.query_unpaged(query, &[]).await.unwrap();
+
+            let history = result.execution_history().unwrap();
+            assert_eq!(history.dropped_attempts(), 0);
+            let attempts = history.attempts();
+            assert_eq!(attempts.len(), 2);
+            assert_matches!(
+                &attempts[0].outcome,
+                AttemptOutcome::Error {
+                    error: QueryError::DbError(DbError::ReadTimeout { .. }, _),
+                    decision: RetryDecision::RetrySameNode(None),
+                }
+            );
+            assert_matches!(attempts[1].outcome, AttemptOutcome::Success);
+            assert_eq!(attempts[0].node, attempts[1].node);
+            assert!(attempts
+                .iter()
+                .all(|attempt| !attempt.speculative && attempt.latency.is_some()));
+
+            // Without collection enabled, no history is attached
+            let result = session.query_unpaged(MARKED_QUERY, &[]).await.unwrap();
+            assert!(result.execution_history().is_none());
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn failed_request_carries_history() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let collector = Arc::new(ErrorCollector::default());
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .request_interceptor(collector.clone())
+                .build()
+                .await
+                .unwrap();
+
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![RequestRule(
+                    marked_query(),
+                    RequestReaction::forge().invalid(),
+                )]));
+            }
+
+            let mut query = Query::new(MARKED_QUERY);
+            query.set_history_collection(true);
+            let err = session.query_unpaged(query, &[]).await.unwrap_err();
+            assert_matches!(err, QueryError::DbError(DbError::Invalid, _));
+
+            let err = collector.0.lock().unwrap().take().unwrap();
+            let history = err.execution_history().unwrap();
+            let attempts = history.attempts();
+            assert_eq!(attempts.len(), 1);
+            assert_eq!(Some(attempts[0].node), err.coordinator());
+            assert_matches!(
+                &attempts[0].outcome,
+                AttemptOutcome::Error {
+                    error: QueryError::DbError(DbError::Invalid, _),
+                    decision: RetryDecision::DontRetry,
+                }
+            );
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
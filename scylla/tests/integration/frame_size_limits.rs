@@ -0,0 +1,145 @@
+use crate::utils::test_with_3_node_cluster;
+use assert_matches::assert_matches;
+use bytes::Bytes;
+use scylla::transport::errors::{BadQuery, QueryError};
+use scylla::transport::session::Session;
+use scylla::SessionBuilder;
+use scylla_cql::frame::types;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestFrame, RequestOpcode, RequestReaction, RequestRule,
+    ResponseFrame, ResponseOpcode, ShardAwareness, WorkerError,
+};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+const MAX_FRAME_SIZE: usize = 4 * 1024 * 1024;
+
+// Forges a single row with a blob larger than the limit.
+fn forge_huge_row(request: RequestFrame) -> ResponseFrame {
+    let mut body = Vec::new();
+    types::write_int(0x0002, &mut body); // Rows
+    types::write_int(0x0001, &mut body); // Global table spec
+    types::write_int(1, &mut body);
+    types::write_string("ks", &mut body).unwrap();
+    types::write_string("t", &mut body).unwrap();
+    types::write_string("v", &mut body).unwrap();
+    types::write_short(0x0003, &mut body); // Blob
+    types::write_int(1, &mut body);
+    types::write_bytes(&vec![0u8; MAX_FRAME_SIZE + 1], &mut body).unwrap();
+
+    ResponseFrame {
+        params: request.params.for_response(),
+        opcode: ResponseOpcode::Result,
+        body: Bytes::from(body),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn oversized_responses_fail_requests_but_not_connections() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .max_response_frame_size(MAX_FRAME_SIZE)
+                .build()
+                .await
+                .unwrap();
+
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Query).and(
+                        Condition::BodyContainsCaseSensitive(Box::new(*b"huge_response_mark")),
+                    ),
+                    RequestReaction::forge_response(Arc::new(forge_huge_row)),
+                )]));
+            }
+
+            for _ in 0..3 {
+                let err = session
+                    .query_unpaged("SELECT v FROM ks.huge_response_mark", &[])
+                    .await
+                    .unwrap_err();
+                assert_matches!(
+                    err,
+                    QueryError::ResponseTooLarge { size, limit: MAX_FRAME_SIZE }
+                        if size > MAX_FRAME_SIZE
+                );
+
+                // The connections remain usable
+                session
+                    .query_unpaged("SELECT host_id FROM system.local", &[])
+                    .await
+                    .unwrap();
+            }
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn oversized_requests_are_not_sent() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .max_request_frame_size(1024)
+                .build()
+                .await
+                .unwrap();
+
+            let prepared = session
+                .prepare("SELECT host_id FROM system.local WHERE key = ?")
+                .await
+                .unwrap();
+
+            let (execute_tx, mut execute_rx) = mpsc::unbounded_channel();
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Execute),
+                    RequestReaction::noop().with_feedback_when_performed(execute_tx.clone()),
+                )]));
+            }
+
+            let err = session
+                .execute_unpaged(&prepared, ("x".repeat(2048),))
+                .await
+                .unwrap_err();
+            assert_matches!(
+                err,
+                QueryError::BadQuery(BadQuery::RequestTooLarge { size, limit: 1024 }) if size > 1024
+            );
+            assert!(execute_rx.try_recv().is_err());
+
+            session
+                .execute_unpaged(&prepared, ("local",))
+                .await
+                .unwrap();
+            assert!(execute_rx.try_recv().is_ok());
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
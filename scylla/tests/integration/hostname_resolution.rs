@@ -0,0 +1,126 @@
+use crate::utils::test_with_3_node_cluster;
+use async_trait::async_trait;
+use scylla::transport::session::Session;
+use scylla::transport::{AddressFamilyPreference, HostnameResolver};
+use scylla::SessionBuilder;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestReaction, RequestRule, ShardAwareness, WorkerError,
+};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Resolves all hostnames to the given addresses.
+struct StaticResolver(Vec<SocketAddr>);
+
+#[async_trait]
+impl HostnameResolver for StaticResolver {
+    async fn resolve_hostname(&self, _hostname: &str) -> Result<Vec<SocketAddr>, io::Error> {
+        Ok(self.0.clone())
+    }
+}
+
+// Resolves all hostnames to the address it currently holds.
+struct SwitchingResolver(Mutex<SocketAddr>);
+
+#[async_trait]
+impl HostnameResolver for SwitchingResolver {
+    async fn resolve_hostname(&self, _hostname: &str) -> Result<Vec<SocketAddr>, io::Error> {
+        Ok(vec![*self.0.lock().unwrap()])
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn control_connection_is_reestablished_to_reresolved_hostname() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let first: SocketAddr = proxy_uris[0].parse().unwrap();
+            let second: SocketAddr = proxy_uris[1].parse().unwrap();
+
+            // Only the first node is reachable at the address broadcast by the cluster,
+            // as if the other ones moved to addresses which only DNS knows about.
+            let unreachable: SocketAddr = "127.0.0.1:1".parse().unwrap();
+            let translation_map = translation_map
+                .into_iter()
+                .map(|(real, proxy)| match proxy == first {
+                    true => (real, proxy),
+                    false => (real, unreachable),
+                })
+                .collect::<std::collections::HashMap<_, _>>();
+
+            let resolver = Arc::new(SwitchingResolver(Mutex::new(first)));
+            let session: Session = SessionBuilder::new()
+                .known_node("scylla.test:9042")
+                .hostname_resolver(resolver.clone())
+                .hostname_resolution_interval(Duration::ZERO)
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+
+            // The first node becomes unreachable too, and the hostname now points at the second one.
+            running_proxy.running_nodes[0].change_request_rules(Some(vec![RequestRule(
+                Condition::True,
+                RequestReaction::drop_connection(),
+            )]));
+            *resolver.0.lock().unwrap() = second;
+
+            session.refresh_metadata().await.unwrap();
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn connection_to_live_address_is_not_delayed_by_dead_one() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, running_proxy| async move {
+            let live: SocketAddr = proxy_uris[0].parse().unwrap();
+            // Discard prefix, nothing answers there
+            let dead: SocketAddr = "[100::1]:9042".parse().unwrap();
+
+            let resolver = Arc::new(StaticResolver(vec![dead, live]));
+            let started = Instant::now();
+            let session: Session = SessionBuilder::new()
+                .known_node("scylla.test:9042")
+                .hostname_resolver(resolver)
+                .address_family_preference(AddressFamilyPreference::PreferIpv6)
+                .connection_attempt_delay(Duration::from_millis(250))
+                .connection_timeout(Duration::from_secs(10))
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+            assert!(started.elapsed() < Duration::from_secs(5));
+
+            session
+                .query_unpaged("SELECT host_id FROM system.local", &[])
+                .await
+                .unwrap();
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -0,0 +1,65 @@
+use crate::utils::test_with_3_node_cluster;
+use assert_matches::assert_matches;
+use scylla::query::Query;
+use scylla::transport::errors::QueryError;
+use scylla::transport::session::Session;
+use scylla::SessionBuilder;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn late_responses_free_orphaned_streams() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Query).and(
+                        Condition::BodyContainsCaseSensitive(Box::new(*b"late_response_mark")),
+                    ),
+                    RequestReaction::delay(Duration::from_secs(1)),
+                )]));
+            }
+
+            let mut query = Query::new("SELECT host_id AS late_response_mark FROM system.local");
+            query.set_request_timeout(Some(Duration::from_millis(100)));
+            let err = session.query_unpaged(query, &[]).await.unwrap_err();
+            assert_matches!(err, QueryError::RequestTimeout(_));
+
+            // Give the response time to arrive after the request was abandoned
+            tokio::time::sleep(Duration::from_secs(2)).await;
+
+            let metrics = session.get_metrics();
+            assert_eq!(metrics.get_orphaned_requests(), 1);
+            assert_eq!(metrics.get_late_responses(), 1);
+
+            // The connection, with the stream id freed, is still usable
+            session
+                .query_unpaged("SELECT host_id FROM system.local", &[])
+                .await
+                .unwrap();
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -1,5 +1,7 @@
 use crate::utils::test_with_3_node_cluster;
 use scylla::frame::types;
+use scylla::load_balancing::{DefaultPolicy, LatencyAwarenessBuilder};
+use scylla::prepared_statement::PreparedStatement;
 use scylla::retry_policy::FallthroughRetryPolicy;
 use scylla::transport::session::Session;
 use scylla::{frame::protocol_features::ProtocolFeatures, test_utils::unique_keyspace_name};
@@ -65,11 +67,11 @@ async fn if_lwt_optimisation_mark_offered_then_negotiatied_and_lwt_routed_optima
 
         // Create schema
         let ks = unique_keyspace_name();
-        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
         session.use_keyspace(ks, false).await.unwrap();
 
         session
-            .query("CREATE TABLE t (a int primary key, b int)", &[])
+            .query_unpaged("CREATE TABLE t (a int primary key, b int)", &[])
             .await
             .unwrap();
 
@@ -125,14 +127,14 @@ async fn if_lwt_optimisation_mark_offered_then_negotiatied_and_lwt_routed_optima
 
         // We execute non-LWT statements and ensure that all nodes were queried (due to RoundRobin).
         for _ in 0..15 {
-            session.execute(&prepared_non_lwt, (MAGIC_MARK,)).await.unwrap();
+            session.execute_unpaged(&prepared_non_lwt, (MAGIC_MARK,)).await.unwrap();
         }
 
         assert_all_replicas_queried(&mut prepared_rxs).await;
 
         // We execute LWT statements, and...
         for _ in 0..15 {
-            session.execute(&prepared_lwt, (MAGIC_MARK,)).await.unwrap();
+            session.execute_unpaged(&prepared_lwt, (MAGIC_MARK,)).await.unwrap();
         }
 
         if supports_optimisation_mark {
@@ -152,3 +154,97 @@ async fn if_lwt_optimisation_mark_offered_then_negotiatied_and_lwt_routed_optima
         Err(err) => panic!("{}", err),
     }
 }
+
+#[tokio::test]
+#[ntest::timeout(20000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn lwt_of_concurrent_clients_is_routed_to_the_same_replica() {
+    // Only prepared statements containing this mark are captured by the proxy.
+    const MAGIC_MARK: i32 = 321;
+
+    let res = test_with_3_node_cluster(ShardAwareness::QueryNode, |proxy_uris, translation_map, mut running_proxy| async move {
+        let (supported_tx, mut supported_rx) = mpsc::unbounded_channel();
+        running_proxy.running_nodes[0].change_response_rules(Some(vec![ResponseRule(
+            Condition::ResponseOpcode(ResponseOpcode::Supported),
+            ResponseReaction::noop().with_feedback_when_performed(supported_tx)
+        )]));
+
+        let mut executed_rxs = [0, 1, 2].map(|i| {
+            let (executed_tx, executed_rx) = mpsc::unbounded_channel();
+            running_proxy.running_nodes[i].change_request_rules(Some(vec![RequestRule(
+                Condition::and(Condition::RequestOpcode(RequestOpcode::Execute), Condition::BodyContainsCaseSensitive(Box::new(MAGIC_MARK.to_be_bytes()))),
+                RequestReaction::noop().with_feedback_when_performed(executed_tx)
+            )]));
+            executed_rx
+        });
+
+        // The clients know different nodes, and the second one measures latencies,
+        // but neither should change the replica which LWT statements are routed to.
+        let first_client: Session = SessionBuilder::new()
+            .known_node(proxy_uris[0].as_str())
+            .address_translator(Arc::new(translation_map.clone()))
+            .build()
+            .await
+            .unwrap();
+        let latency_aware_profile = ExecutionProfile::builder()
+            .load_balancing_policy(
+                DefaultPolicy::builder()
+                    .latency_awareness(LatencyAwarenessBuilder::default())
+                    .build(),
+            )
+            .build()
+            .into_handle();
+        let second_client: Session = SessionBuilder::new()
+            .known_node(proxy_uris[1].as_str())
+            .address_translator(Arc::new(translation_map))
+            .default_execution_profile_handle(latency_aware_profile)
+            .build()
+            .await
+            .unwrap();
+
+        let (supported_frame, _shard) = supported_rx.recv().await.unwrap();
+        let supported_options = types::read_string_multimap(&mut &*supported_frame.body).unwrap();
+        let supported_features = ProtocolFeatures::parse_from_supported(&supported_options);
+
+        let ks = unique_keyspace_name();
+        first_client.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        first_client.query_unpaged(format!("CREATE TABLE {}.t (a int primary key, b int)", ks), &[]).await.unwrap();
+        second_client.await_schema_agreement().await.unwrap();
+
+        let statement = format!("UPDATE {}.t SET b=3 WHERE a=? IF b=2", ks);
+        let first_lwt = first_client.prepare(statement.as_str()).await.unwrap();
+        let second_lwt = second_client.prepare(statement.as_str()).await.unwrap();
+
+        async fn run(session: &Session, prepared: &PreparedStatement) {
+            for _ in 0..10 {
+                session.execute_unpaged(prepared, (MAGIC_MARK,)).await.unwrap();
+            }
+        }
+        tokio::join!(run(&first_client, &first_lwt), run(&second_client, &second_lwt));
+
+        let queried_nodes = executed_rxs
+            .iter_mut()
+            .map(|rx| {
+                let mut executions = 0;
+                while rx.try_recv().is_ok() {
+                    executions += 1;
+                }
+                executions
+            })
+            .filter(|executions| *executions > 0)
+            .count();
+        if supported_features.lwt_optimization_meta_bit_mask.is_some() {
+            assert_eq!(queried_nodes, 1);
+        } else {
+            assert!(queried_nodes > 1);
+        }
+
+        running_proxy
+    }).await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
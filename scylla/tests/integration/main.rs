@@ -1,9 +1,28 @@
+mod bound_statement;
+mod caching_session;
+mod cancellation;
+mod connection_lifecycle;
 mod consistency;
+mod control_connection;
+mod execution_budget;
+mod execution_history;
 mod execution_profiles;
+mod frame_size_limits;
+mod hostname_resolution;
 mod hygiene;
+mod late_responses;
 mod lwt_optimisation;
 mod new_session;
+mod node_capabilities;
+mod paging;
+mod prepare_on_all_hosts;
+mod repreparation;
+mod request_priority;
 mod retries;
+mod routing_key;
 mod shards;
 mod silent_prepare_query;
+mod skip_metadata;
+mod slow_query_log;
+mod stream_exhaustion;
 pub(crate) mod utils;
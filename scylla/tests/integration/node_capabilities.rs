@@ -0,0 +1,115 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::transport::session::Session;
+use scylla::transport::Compression;
+use scylla::SessionBuilder;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestFrame, RequestOpcode, RequestReaction, RequestRule,
+    ResponseFrame, ShardAwareness, WorkerError,
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+// The compression algorithms advertised by each of the nodes
+const COMPRESSION: [&[&str]; 3] = [&["lz4"], &["snappy"], &[]];
+
+fn forged_supported(node_idx: usize) -> HashMap<String, Vec<String>> {
+    [
+        ("CQL_VERSION", vec!["3.3.1".to_string()]),
+        (
+            "PROTOCOL_VERSIONS",
+            vec!["3/v3".to_string(), "4/v4".to_string()],
+        ),
+        (
+            "COMPRESSION",
+            COMPRESSION[node_idx]
+                .iter()
+                .map(|c| c.to_string())
+                .collect(),
+        ),
+        ("TEST_NODE_IDX", vec![node_idx.to_string()]),
+    ]
+    .into_iter()
+    .map(|(key, values)| (key.to_string(), values))
+    .collect()
+}
+
+fn custom_startup_options() -> HashMap<String, String> {
+    [
+        ("DRIVER_NAME", "test-platform"),
+        ("AUDIT_TAG", "node-capabilities"),
+        ("COMPRESSION", "snappy"),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect()
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn node_capabilities_reflect_supported_responses() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            for (idx, node) in running_proxy.running_nodes.iter_mut().enumerate() {
+                let supported = forged_supported(idx);
+                node.change_request_rules(Some(vec![RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Options),
+                    RequestReaction::forge_response(Arc::new(move |frame: RequestFrame| {
+                        ResponseFrame::forged_supported(frame.params, &supported).unwrap()
+                    })),
+                )]));
+            }
+
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map.clone()))
+                .compression(Some(Compression::Lz4))
+                .custom_startup_options(custom_startup_options())
+                .build()
+                .await
+                .unwrap();
+
+            let cluster_data = session.get_cluster_data();
+            for node in cluster_data.get_nodes_info() {
+                let address = SocketAddr::new(node.address.ip(), node.address.port());
+                let proxy_addr = translation_map[&address];
+                let idx = proxy_uris
+                    .iter()
+                    .position(|uri| uri.parse::<SocketAddr>().unwrap() == proxy_addr)
+                    .unwrap();
+
+                let capabilities = node.capabilities().unwrap();
+                assert_eq!(capabilities.options, forged_supported(idx));
+                assert_eq!(capabilities.compression, COMPRESSION[idx]);
+                assert_eq!(capabilities.protocol_versions, ["3/v3", "4/v4"]);
+                assert_eq!(capabilities.sharder, None);
+
+                let settings = node.negotiated_settings().unwrap();
+                assert_eq!(settings.protocol_version, 4);
+                let expected_compression = (idx == 0).then_some(Compression::Lz4);
+                assert_eq!(settings.compression, expected_compression);
+
+                // Custom options override the driver's ones, except the reserved COMPRESSION
+                let options = &settings.startup_options;
+                assert_eq!(options["DRIVER_NAME"], "test-platform");
+                assert_eq!(options["AUDIT_TAG"], "node-capabilities");
+                assert!(options.contains_key("DRIVER_VERSION"));
+                assert_eq!(
+                    options.get("COMPRESSION").map(String::as_str),
+                    (idx == 0).then_some("lz4")
+                );
+            }
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
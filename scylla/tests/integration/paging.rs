@@ -0,0 +1,192 @@
+use crate::utils::test_with_3_node_cluster;
+use bytes::Bytes;
+use futures::TryStreamExt;
+use scylla::query::Query;
+use scylla::transport::iterator::IterConfig;
+use scylla::transport::session::Session;
+use scylla::SessionBuilder;
+use scylla_cql::frame::request::Request;
+use scylla_cql::frame::types;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestFrame, RequestOpcode, RequestReaction, RequestRule,
+    ResponseFrame, ResponseOpcode, ShardAwareness, WorkerError,
+};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+// Pages returned by the forged server, all of them smaller than the requested page size.
+const PAGES: &[&[i32]] = &[&[0, 1, 2], &[], &[3, 4, 5], &[6]];
+
+// Forges a page of int rows, chosen by the paging state of the request,
+// which is the index of the page.
+fn forge_page(request: RequestFrame) -> ResponseFrame {
+    let paging_state = match request.deserialize().unwrap() {
+        Request::Query(query) => query.parameters.paging_state,
+        _ => panic!("Expected QUERY"),
+    };
+    let page = paging_state.map_or(0, |paging_state| paging_state[0] as usize);
+
+    let mut body = Vec::new();
+    types::write_int(0x0002, &mut body); // Rows
+    let has_more_pages = page + 1 < PAGES.len();
+    match has_more_pages {
+        true => types::write_int(0x0001 | 0x0002, &mut body),
+        false => types::write_int(0x0001, &mut body),
+    }
+    types::write_int(1, &mut body);
+    if has_more_pages {
+        types::write_bytes(&[page as u8 + 1], &mut body).unwrap();
+    }
+    types::write_string("ks", &mut body).unwrap();
+    types::write_string("t", &mut body).unwrap();
+    types::write_string("v", &mut body).unwrap();
+    types::write_short(0x0009, &mut body); // Int
+    types::write_int(PAGES[page].len() as i32, &mut body);
+    for v in PAGES[page] {
+        types::write_bytes(&v.to_be_bytes(), &mut body).unwrap();
+    }
+
+    ResponseFrame {
+        params: request.params.for_response(),
+        opcode: ResponseOpcode::Result,
+        body: Bytes::from(body),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn iterator_fetches_pages_smaller_than_requested() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+
+            let (query_tx, mut query_rx) = mpsc::unbounded_channel();
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Query).and(
+                        Condition::BodyContainsCaseSensitive(Box::new(*b"page_shrinking_mark")),
+                    ),
+                    RequestReaction::forge_response(Arc::new(forge_page))
+                        .with_feedback_when_performed(query_tx.clone()),
+                )]));
+            }
+
+            let query = Query::new("SELECT v FROM ks.page_shrinking_mark").with_page_size(1000);
+            let rows: Vec<(i32,)> = session
+                .query_iter_with_config(query.clone(), &[], IterConfig::new().page_size(10))
+                .await
+                .unwrap()
+                .into_typed::<(i32,)>()
+                .try_collect()
+                .await
+                .unwrap();
+            let expected: Vec<(i32,)> = PAGES
+                .iter()
+                .flat_map(|page| page.iter())
+                .map(|v| (*v,))
+                .collect();
+            assert_eq!(rows, expected);
+
+            // All pages were requested with the overridden page size
+            let mut requests = 0;
+            while let Ok((request, _shard)) = query_rx.try_recv() {
+                match request.deserialize().unwrap() {
+                    Request::Query(query) => assert_eq!(query.parameters.page_size, Some(10)),
+                    _ => panic!("Expected QUERY"),
+                }
+                requests += 1;
+            }
+            assert_eq!(requests, PAGES.len());
+            assert_eq!(query.get_page_size(), Some(1000));
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn unpaged_and_single_page_queries() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+
+            let (query_tx, mut query_rx) = mpsc::unbounded_channel();
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Query).and(
+                        Condition::BodyContainsCaseSensitive(Box::new(*b"page_shrinking_mark")),
+                    ),
+                    RequestReaction::forge_response(Arc::new(forge_page))
+                        .with_feedback_when_performed(query_tx.clone()),
+                )]));
+            }
+            let mut requested_page_sizes = || {
+                let mut page_sizes = Vec::new();
+                while let Ok((request, _shard)) = query_rx.try_recv() {
+                    match request.deserialize().unwrap() {
+                        Request::Query(query) => page_sizes.push(query.parameters.page_size),
+                        _ => panic!("Expected QUERY"),
+                    }
+                }
+                page_sizes
+            };
+
+            // All pages are fetched and returned together
+            let query = Query::new("SELECT v FROM ks.page_shrinking_mark").with_page_size(1000);
+            let result = session.query_unpaged(query.clone(), &[]).await.unwrap();
+            assert!(result.paging_state.is_none());
+            let rows: Vec<i32> = result
+                .rows_typed::<(i32,)>()
+                .unwrap()
+                .map(|row| row.unwrap().0)
+                .collect();
+            assert_eq!(rows, [0, 1, 2, 3, 4, 5, 6]);
+            assert_eq!(requested_page_sizes(), [Some(1000); 4]);
+
+            // A single page is fetched, with the default page size if the query has none
+            let mut query = Query::new("SELECT v FROM ks.page_shrinking_mark");
+            let result = session
+                .query_single_page(query.clone(), &[], None)
+                .await
+                .unwrap();
+            assert!(result.paging_state.is_some());
+            assert_eq!(result.rows.unwrap().len(), 3);
+            assert_eq!(requested_page_sizes(), [Some(5000)]);
+
+            query.set_page_size(10);
+            session.query_single_page(query, &[], None).await.unwrap();
+            assert_eq!(requested_page_sizes(), [Some(10)]);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
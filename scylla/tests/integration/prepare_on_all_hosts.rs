@@ -0,0 +1,123 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::transport::session::Session;
+use scylla::SessionBuilder;
+use scylla_cql::errors::DbError;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestFrame, RequestOpcode, RequestReaction, RequestRule,
+    RunningProxy, ShardAwareness, TargetShard, WorkerError,
+};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+// Makes the nodes report prepares of statements containing the mark,
+// and the last node fail them.
+fn count_prepares(
+    running_proxy: &mut RunningProxy,
+) -> Vec<mpsc::UnboundedReceiver<(RequestFrame, Option<TargetShard>)>> {
+    let nodes_count = running_proxy.running_nodes.len();
+    running_proxy
+        .running_nodes
+        .iter_mut()
+        .enumerate()
+        .map(|(i, node)| {
+            let (prepare_tx, prepare_rx) = mpsc::unbounded_channel();
+            let reaction = match i == nodes_count - 1 {
+                true => RequestReaction::forge_with_error(DbError::Overloaded),
+                false => RequestReaction::noop(),
+            };
+            node.change_request_rules(Some(vec![RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Prepare).and(
+                    Condition::BodyContainsCaseSensitive(Box::new(*b"prepare_everywhere_mark")),
+                ),
+                reaction.with_feedback_when_performed(prepare_tx),
+            )]));
+            prepare_rx
+        })
+        .collect()
+}
+
+fn prepared_nodes(
+    prepare_rxs: &mut [mpsc::UnboundedReceiver<(RequestFrame, Option<TargetShard>)>],
+) -> usize {
+    prepare_rxs
+        .iter_mut()
+        .map(|rx| {
+            let mut prepares = 0;
+            while rx.try_recv().is_ok() {
+                prepares += 1;
+            }
+            prepares
+        })
+        .filter(|prepares| *prepares > 0)
+        .count()
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn statements_are_prepared_on_all_nodes() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+
+            let mut prepare_rxs = count_prepares(&mut running_proxy);
+
+            // The failure on the last node doesn't fail the preparation
+            session
+                .prepare("SELECT key AS prepare_everywhere_mark FROM system.local")
+                .await
+                .unwrap();
+            assert_eq!(prepared_nodes(&mut prepare_rxs), 3);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn statements_are_prepared_on_single_node_if_disabled() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .prepare_on_all_hosts(false)
+                .build()
+                .await
+                .unwrap();
+
+            let mut prepare_rxs = count_prepares(&mut running_proxy);
+
+            // The statement may be sent to the failing node, so the result doesn't matter
+            let _ = session
+                .prepare("SELECT key AS prepare_everywhere_mark FROM system.local")
+                .await;
+            assert_eq!(prepared_nodes(&mut prepare_rxs), 1);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
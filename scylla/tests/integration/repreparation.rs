@@ -0,0 +1,93 @@
+use crate::utils::{test_with_3_node_cluster, FixedOrderLoadBalancer};
+use scylla::transport::session::Session;
+use scylla::{ExecutionProfile, SessionBuilder};
+use scylla_cql::errors::DbError;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn concurrent_unprepared_executions_reprepare_once() {
+    const TASKS: usize = 100;
+
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            // All requests go to a single node.
+            let profile = ExecutionProfile::builder()
+                .load_balancing_policy(Arc::new(FixedOrderLoadBalancer))
+                .build();
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .default_execution_profile_handle(profile.into_handle())
+                .build()
+                .await
+                .unwrap();
+
+            let prepared = session
+                .prepare("SELECT key AS reprepare_mark FROM system.local WHERE key = ?")
+                .await
+                .unwrap();
+            let statement_id = prepared.get_id().clone();
+
+            // The node reports the statement as unprepared to all the executions,
+            // which are answered late enough for all of them to be sent by then.
+            // Executions retried after re-preparing the statement pass.
+            let (prepare_tx, mut prepare_rx) = mpsc::unbounded_channel();
+            let rules = vec![
+                RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Execute)
+                        .and(Condition::TrueForLimitedTimes(TASKS)),
+                    RequestReaction::forge_with_error_lazy_delay(
+                        Box::new(move || DbError::Unprepared {
+                            statement_id: statement_id.clone(),
+                        }),
+                        Some(Duration::from_millis(500)),
+                    ),
+                ),
+                RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Prepare).and(
+                        Condition::BodyContainsCaseSensitive(Box::new(*b"reprepare_mark")),
+                    ),
+                    RequestReaction::noop().with_feedback_when_performed(prepare_tx),
+                ),
+            ];
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(rules.clone()));
+            }
+
+            let session = Arc::new(session);
+            let handles = (0..TASKS).map(|_| {
+                let session = session.clone();
+                let prepared = prepared.clone();
+                tokio::spawn(async move { session.execute_unpaged(&prepared, ("local",)).await })
+            });
+            for handle in handles.collect::<Vec<_>>() {
+                handle.await.unwrap().unwrap();
+            }
+
+            let mut prepares = 0;
+            while prepare_rx.try_recv().is_ok() {
+                prepares += 1;
+            }
+            assert_eq!(prepares, 1);
+            assert_eq!(session.get_metrics().get_reprepares(), 1);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
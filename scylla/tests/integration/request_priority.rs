@@ -0,0 +1,126 @@
+use crate::utils::{test_with_3_node_cluster, FixedOrderLoadBalancer};
+use scylla::retry_policy::FallthroughRetryPolicy;
+use scylla::transport::priority::{PriorityScheduling, RequestPriority};
+use scylla::transport::session::{PoolSize, Session, StreamExhaustionPolicy};
+use scylla::{ExecutionProfile, SessionBuilder};
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const SLOW_QUERY: &str = "SELECT host_id AS request_priority_mark FROM system.local";
+
+fn spawn_slow_query(
+    session: &Arc<Session>,
+    priority: RequestPriority,
+    finished: &Arc<Mutex<Vec<RequestPriority>>>,
+) -> tokio::task::JoinHandle<()> {
+    let session = session.clone();
+    let finished = finished.clone();
+    tokio::spawn(async move {
+        let profile = ExecutionProfile::builder()
+            .load_balancing_policy(Arc::new(FixedOrderLoadBalancer))
+            .retry_policy(Box::new(FallthroughRetryPolicy))
+            .priority(priority)
+            .build();
+        let mut query = scylla::query::Query::new(SLOW_QUERY);
+        query.set_execution_profile_handle(Some(profile.into_handle()));
+        session.query_unpaged(query, &[]).await.unwrap();
+        finished.lock().unwrap().push(priority);
+    })
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn low_priority_requests_yield_on_saturated_connection() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            // A single connection to each node with a single request in flight,
+            // all requests are sent to the same node
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .pool_size(PoolSize::PerHost(NonZeroUsize::new(1).unwrap()))
+                .max_requests_per_connection(NonZeroUsize::new(1).unwrap())
+                .stream_exhaustion_policy(StreamExhaustionPolicy::Queue {
+                    max_queued: 16,
+                    timeout: Duration::from_secs(10),
+                })
+                .priority_scheduling(PriorityScheduling::strict())
+                .build()
+                .await
+                .unwrap();
+            let session = Arc::new(session);
+
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Query).and(
+                        Condition::BodyContainsCaseSensitive(Box::new(*b"request_priority_mark")),
+                    ),
+                    RequestReaction::delay(Duration::from_millis(300)),
+                )]));
+            }
+
+            let finished = Arc::new(Mutex::new(Vec::new()));
+            let in_flight = spawn_slow_query(&session, RequestPriority::Normal, &finished);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+
+            // Low priority requests are queued first, but wait for the high priority ones
+            let mut queued = Vec::new();
+            for priority in [
+                RequestPriority::Low,
+                RequestPriority::Low,
+                RequestPriority::High,
+                RequestPriority::High,
+            ] {
+                queued.push(spawn_slow_query(&session, priority, &finished));
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            let metrics = session.get_metrics();
+            assert_eq!(
+                metrics.priority_class_snapshot(RequestPriority::Low).queued,
+                2
+            );
+            assert_eq!(
+                metrics
+                    .priority_class_snapshot(RequestPriority::High)
+                    .queued,
+                2
+            );
+
+            in_flight.await.unwrap();
+            for request in queued {
+                request.await.unwrap();
+            }
+            assert_eq!(
+                *finished.lock().unwrap(),
+                [
+                    RequestPriority::Normal,
+                    RequestPriority::High,
+                    RequestPriority::High,
+                    RequestPriority::Low,
+                    RequestPriority::Low,
+                ]
+            );
+
+            let low = metrics.priority_class_snapshot(RequestPriority::Low);
+            let high = metrics.priority_class_snapshot(RequestPriority::High);
+            assert_eq!(low.queued, 0);
+            assert!(low.latency.unwrap().max > high.latency.unwrap().max);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
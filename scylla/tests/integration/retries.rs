@@ -35,10 +35,10 @@ async fn speculative_execution_is_fired() {
             .unwrap();
 
         let ks = unique_keyspace_name();
-        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
         session.use_keyspace(ks, false).await.unwrap();
         session
-            .query("CREATE TABLE t (a int primary key)", &[])
+            .query_unpaged("CREATE TABLE t (a int primary key)", &[])
             .await
             .unwrap();
 
@@ -55,7 +55,7 @@ async fn speculative_execution_is_fired() {
 
         info!("--------------------- first query - no rules  ----------------");
         // first run before any rules
-        session.query(q.clone(), (3,)).await.unwrap();
+        session.query_unpaged(q.clone(), (3,)).await.unwrap();
 
         info!("--------------------- second query - 0 and 2 nodes not responding  ----------------");
         running_proxy.running_nodes[0]
@@ -63,7 +63,7 @@ async fn speculative_execution_is_fired() {
         running_proxy.running_nodes[2]
             .change_request_rules(Some(vec![drop_frame_rule.clone()]));
 
-        session.query(q.clone(), (2,)).await.unwrap();
+        session.query_unpaged(q.clone(), (2,)).await.unwrap();
 
         info!("--------------------- third query - 0 and 1 nodes not responding  ----------------");
         running_proxy.running_nodes[2]
@@ -71,7 +71,7 @@ async fn speculative_execution_is_fired() {
         running_proxy.running_nodes[1]
             .change_request_rules(Some(vec![drop_frame_rule.clone()]));
 
-        session.query(q.clone(), (1,)).await.unwrap();
+        session.query_unpaged(q.clone(), (1,)).await.unwrap();
 
 
         info!("--------------------- fourth query - all nodes not responding  ----------------");
@@ -79,7 +79,7 @@ async fn speculative_execution_is_fired() {
         .change_request_rules(Some(vec![drop_frame_rule]));
 
         tokio::select! {
-            res = session.query(q, (0,)) => panic!("Rules did not work: received response {:?}", res),
+            res = session.query_unpaged(q, (0,)) => panic!("Rules did not work: received response {:?}", res),
             _ = tokio::time::sleep(TIMEOUT_PER_REQUEST) => (),
         };
 
@@ -110,10 +110,10 @@ async fn retries_occur() {
             .unwrap();
 
         let ks = unique_keyspace_name();
-        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
         session.use_keyspace(ks, false).await.unwrap();
         session
-            .query("CREATE TABLE t (a int primary key)", &[])
+            .query_unpaged("CREATE TABLE t (a int primary key)", &[])
             .await
             .unwrap();
 
@@ -129,7 +129,7 @@ async fn retries_occur() {
         info!("--------------------- BEGINNING main test part ----------------");
 
         info!("--------------------- first query - no rules  ----------------");
-        session.query(q.clone(), (3,)).await.unwrap();
+        session.query_unpaged(q.clone(), (3,)).await.unwrap();
 
         info!("--------------------- second query - 0 and 2 nodes not responding  ----------------");
         running_proxy.running_nodes[0]
@@ -137,19 +137,19 @@ async fn retries_occur() {
         running_proxy.running_nodes[2]
             .change_request_rules(Some(vec![forge_error_rule.clone()]));
 
-        session.query(q.clone(), (2,)).await.unwrap();
+        session.query_unpaged(q.clone(), (2,)).await.unwrap();
 
         info!("--------------------- third query - all nodes not responding  ----------------");
         running_proxy.running_nodes[1]
             .change_request_rules(Some(vec![forge_error_rule]));
 
-        session.query(q.clone(), (1,)).await.unwrap_err();
+        session.query_unpaged(q.clone(), (1,)).await.unwrap_err();
 
         info!("--------------------- fourth query - 0 and 1 nodes not responding  ----------------");
         running_proxy.running_nodes[2]
         .change_request_rules(None);
 
-        session.query(q, (1,)).await.unwrap();
+        session.query_unpaged(q, (1,)).await.unwrap();
 
         info!("--------------------- FINISHING main test part ----------------");
 
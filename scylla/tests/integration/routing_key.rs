@@ -0,0 +1,117 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::query::Query;
+use scylla::test_utils::unique_keyspace_name;
+use scylla::transport::session::Session;
+use scylla::SessionBuilder;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestFrame, RequestOpcode, RequestReaction, RequestRule,
+    ShardAwareness, TargetShard, WorkerError,
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+type RequestRx = mpsc::UnboundedReceiver<(RequestFrame, Option<TargetShard>)>;
+
+fn queried_nodes(rxs: &mut [RequestRx]) -> Vec<usize> {
+    rxs.iter_mut()
+        .enumerate()
+        .filter_map(|(i, rx)| {
+            let mut queried = false;
+            while rx.try_recv().is_ok() {
+                queried = true;
+            }
+            queried.then_some(i)
+        })
+        .collect()
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn requests_are_routed_by_overridden_routing_key() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .build()
+                .await
+                .unwrap();
+
+            let ks = unique_keyspace_name();
+            session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+            session.use_keyspace(&ks, false).await.unwrap();
+            session
+                .query_unpaged("CREATE TABLE t (a int primary key)", &[])
+                .await
+                .unwrap();
+            session.refresh_metadata().await.unwrap();
+
+            let mut request_rxs: Vec<RequestRx> = running_proxy
+                .running_nodes
+                .iter_mut()
+                .map(|node| {
+                    let (request_tx, request_rx) = mpsc::unbounded_channel();
+                    node.change_request_rules(Some(vec![RequestRule(
+                        Condition::RequestOpcode(RequestOpcode::Execute).or(Condition::and(
+                            Condition::RequestOpcode(RequestOpcode::Query),
+                            Condition::BodyContainsCaseSensitive(Box::new(*b"routing_key_mark")),
+                        )),
+                        RequestReaction::noop().with_feedback_when_performed(request_tx),
+                    )]));
+                    request_rx
+                })
+                .collect();
+
+            let mut prepared = session
+                .prepare("SELECT a FROM t WHERE a IN ?")
+                .await
+                .unwrap();
+            let cluster_data = session.get_cluster_data();
+
+            for key in 0..10i32 {
+                prepared.set_routing_key(&(key,)).unwrap();
+                let token = prepared.get_routing_token().unwrap();
+                let replicas = cluster_data.get_token_endpoints(&ks, token);
+                assert_eq!(replicas.len(), 1);
+                let replica_address = SocketAddr::new(replicas[0].address.ip(), replicas[0].address.port());
+                let replica = proxy_uris
+                    .iter()
+                    .position(|uri| uri.parse::<SocketAddr>().unwrap() == replica_address)
+                    .unwrap();
+
+                for _ in 0..3 {
+                    session
+                        .execute_unpaged(&prepared, (vec![key, key + 100, key + 200],))
+                        .await
+                        .unwrap();
+                }
+                assert_eq!(queried_nodes(&mut request_rxs), [replica]);
+
+                // A raw key of an unprepared query leads to the same replica
+                let mut query = Query::new(format!(
+                    "SELECT a AS routing_key_mark FROM t WHERE a IN ({}, {})",
+                    key,
+                    key + 100
+                ));
+                query.set_raw_routing_key(&key.to_be_bytes());
+                assert_eq!(query.get_routing_token(), Some(token));
+                for _ in 0..3 {
+                    session.query_unpaged(query.clone(), ()).await.unwrap();
+                }
+                assert_eq!(queried_nodes(&mut request_rxs), [replica]);
+            }
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -36,9 +36,9 @@ async fn test_consistent_shard_awareness() {
         let ks = unique_keyspace_name();
 
         /* Prepare schema */
-        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
         session
-            .query(
+            .query_unpaged(
                 format!(
                     "CREATE TABLE IF NOT EXISTS {}.t (a int, b int, c text, primary key (a, b))",
                     ks
@@ -65,7 +65,7 @@ async fn test_consistent_shard_awareness() {
 
         for values in value_lists {
             for _ in 0..10 {
-                session.execute(&prepared, values).await.unwrap();
+                session.execute_unpaged(&prepared, values).await.unwrap();
             }
             for rx in feedback_rxs.iter_mut() {
                 assert_one_shard_queried(rx);
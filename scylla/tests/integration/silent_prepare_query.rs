@@ -26,10 +26,10 @@ async fn test_prepare_query_with_values() {
             .unwrap();
 
         let ks = unique_keyspace_name();
-        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
         session.use_keyspace(ks, false).await.unwrap();
         session
-            .query("CREATE TABLE t (a int primary key)", &[])
+            .query_unpaged("CREATE TABLE t (a int primary key)", &[])
             .await
             .unwrap();
 
@@ -45,7 +45,7 @@ async fn test_prepare_query_with_values() {
         .change_request_rules(Some(vec![drop_unprepared_frame_rule]));
 
         tokio::select! {
-            _res = session.query(q, (0,)) => (),
+            _res = session.query_unpaged(q, (0,)) => (),
             _ = tokio::time::sleep(TIMEOUT_PER_REQUEST) => panic!("Rules did not work: no received response"),
         };
 
@@ -76,10 +76,10 @@ async fn test_query_with_no_values() {
             .unwrap();
 
         let ks = unique_keyspace_name();
-        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.query_unpaged(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
         session.use_keyspace(ks, false).await.unwrap();
         session
-            .query("CREATE TABLE t (a int primary key)", &[])
+            .query_unpaged("CREATE TABLE t (a int primary key)", &[])
             .await
             .unwrap();
 
@@ -95,7 +95,7 @@ async fn test_query_with_no_values() {
         .change_request_rules(Some(vec![drop_prepared_frame_rule]));
 
         tokio::select! {
-            _res = session.query(q, ()) => (),
+            _res = session.query_unpaged(q, ()) => (),
             _ = tokio::time::sleep(TIMEOUT_PER_REQUEST) => panic!("Rules did not work: no received response"),
         };
 
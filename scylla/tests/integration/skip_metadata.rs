@@ -0,0 +1,76 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::transport::session::Session;
+use scylla::SessionBuilder;
+use scylla_cql::frame::request::Request;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, RunningProxy,
+    ShardAwareness, WorkerError,
+};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+// Executes a prepared statement returning rows, and tells whether the server
+// was asked to skip their metadata.
+async fn execute_skips_metadata(session: &Session, running_proxy: &mut RunningProxy) -> bool {
+    let (execute_tx, mut execute_rx) = mpsc::unbounded_channel();
+    for node in running_proxy.running_nodes.iter_mut() {
+        node.change_request_rules(Some(vec![RequestRule(
+            Condition::RequestOpcode(RequestOpcode::Execute),
+            RequestReaction::noop().with_feedback_when_performed(execute_tx.clone()),
+        )]));
+    }
+
+    let prepared = session
+        .prepare("SELECT host_id FROM system.local")
+        .await
+        .unwrap();
+    let (host_id,) = session
+        .execute_unpaged(&prepared, &[])
+        .await
+        .unwrap()
+        .single_row_typed::<(uuid::Uuid,)>()
+        .unwrap();
+    assert!(!host_id.is_nil());
+
+    let (executed, _shard) = execute_rx.recv().await.unwrap();
+    match executed.deserialize().unwrap() {
+        Request::Execute(execute) => execute.parameters.skip_metadata,
+        _ => panic!("Expected EXECUTE"),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn result_metadata_is_skipped_for_prepared_statements() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map.clone()))
+                .build()
+                .await
+                .unwrap();
+            assert!(execute_skips_metadata(&session, &mut running_proxy).await);
+
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .skip_result_metadata(false)
+                .build()
+                .await
+                .unwrap();
+            assert!(!execute_skips_metadata(&session, &mut running_proxy).await);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
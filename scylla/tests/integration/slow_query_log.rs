@@ -0,0 +1,91 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::transport::session::Session;
+use scylla::transport::slow_query_log::{SlowQueryEvent, SlowQueryListener};
+use scylla::SessionBuilder;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Statement, coordinator, number of retries and whether the request failed.
+type SlowQueryEvents = Mutex<Vec<(String, Option<SocketAddr>, usize, bool)>>;
+
+#[derive(Debug, Default)]
+struct CollectingListener {
+    events: SlowQueryEvents,
+}
+
+impl SlowQueryListener for CollectingListener {
+    fn on_slow_query(&self, event: &SlowQueryEvent<'_>) {
+        self.events.lock().unwrap().push((
+            event.statement.to_owned(),
+            event.coordinator,
+            event.retries,
+            event.error.is_some(),
+        ));
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn delayed_response_is_reported_as_slow_query() {
+    const THRESHOLD: Duration = Duration::from_millis(1000);
+    const DELAY: Duration = Duration::from_millis(1500);
+    const STATEMENT: &str = "SELECT now() FROM system.local";
+
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let listener = Arc::new(CollectingListener::default());
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .slow_query_threshold(THRESHOLD)
+                .slow_query_listener(listener.clone())
+                .build()
+                .await
+                .unwrap();
+
+            // Without any delay the request is not slow
+            session.query_unpaged(STATEMENT, &[]).await.unwrap();
+            assert!(listener.events.lock().unwrap().is_empty());
+
+            let delay_rule = RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Query)
+                    .and(Condition::BodyContainsCaseSensitive(Box::new(*b"now()"))),
+                RequestReaction::delay(DELAY),
+            );
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![delay_rule.clone()]));
+            }
+
+            session.query_unpaged(STATEMENT, &[]).await.unwrap();
+
+            let events = listener.events.lock().unwrap().clone();
+            assert_eq!(
+                events.len(),
+                1,
+                "expected exactly one slow query, got {:?}",
+                events
+            );
+            let (statement, coordinator, retries, failed) = &events[0];
+            assert_eq!(statement, STATEMENT);
+            assert!(coordinator.is_some());
+            assert_eq!(*retries, 0);
+            assert!(!failed);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -0,0 +1,187 @@
+use crate::utils::{test_with_3_node_cluster, FixedOrderLoadBalancer};
+use assert_matches::assert_matches;
+use scylla::retry_policy::FallthroughRetryPolicy;
+use scylla::transport::errors::QueryError;
+use scylla::transport::session::{PoolSize, Session, StreamExhaustionPolicy};
+use scylla::{ExecutionProfile, SessionBuilder};
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, RunningProxy,
+    ShardAwareness, WorkerError,
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+
+const SLOW_QUERY: &str = "SELECT host_id AS stream_exhaustion_mark FROM system.local";
+
+// Creates a session with a single connection to each node, allowing two requests
+// in flight on each of them, and sending all requests to the same node without retries.
+// Marked queries are answered after a second.
+async fn setup(
+    proxy_uris: &[String; 3],
+    translation_map: HashMap<SocketAddr, SocketAddr>,
+    running_proxy: &mut RunningProxy,
+    policy: StreamExhaustionPolicy,
+) -> Arc<Session> {
+    let profile = ExecutionProfile::builder()
+        .load_balancing_policy(Arc::new(FixedOrderLoadBalancer))
+        .retry_policy(Box::new(FallthroughRetryPolicy))
+        .build();
+    let session: Session = SessionBuilder::new()
+        .known_node(proxy_uris[0].as_str())
+        .address_translator(Arc::new(translation_map))
+        .default_execution_profile_handle(profile.into_handle())
+        .pool_size(PoolSize::PerHost(NonZeroUsize::new(1).unwrap()))
+        .max_requests_per_connection(NonZeroUsize::new(2).unwrap())
+        .stream_exhaustion_policy(policy)
+        .build()
+        .await
+        .unwrap();
+
+    for node in running_proxy.running_nodes.iter_mut() {
+        node.change_request_rules(Some(vec![RequestRule(
+            Condition::RequestOpcode(RequestOpcode::Query).and(
+                Condition::BodyContainsCaseSensitive(Box::new(*b"stream_exhaustion_mark")),
+            ),
+            RequestReaction::delay(Duration::from_secs(1)),
+        )]));
+    }
+
+    Arc::new(session)
+}
+
+fn spawn_slow_query(session: &Arc<Session>) -> tokio::task::JoinHandle<Result<(), QueryError>> {
+    let session = session.clone();
+    tokio::spawn(async move { session.query_unpaged(SLOW_QUERY, &[]).await.map(|_| ()) })
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn saturated_connection_fails_fast() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session = setup(
+                &proxy_uris,
+                translation_map,
+                &mut running_proxy,
+                StreamExhaustionPolicy::FailFast,
+            )
+            .await;
+
+            let in_flight = [spawn_slow_query(&session), spawn_slow_query(&session)];
+            tokio::time::sleep(Duration::from_millis(200)).await;
+
+            let err = session.query_unpaged(SLOW_QUERY, &[]).await.unwrap_err();
+            assert_matches!(err, QueryError::ConnectionBusy);
+
+            for request in in_flight {
+                request.await.unwrap().unwrap();
+            }
+
+            // Stream ids were freed
+            session.query_unpaged(SLOW_QUERY, &[]).await.unwrap();
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn saturated_connection_queues_requests() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session = setup(
+                &proxy_uris,
+                translation_map,
+                &mut running_proxy,
+                StreamExhaustionPolicy::Queue {
+                    max_queued: 1,
+                    timeout: Duration::from_secs(5),
+                },
+            )
+            .await;
+
+            let in_flight = [spawn_slow_query(&session), spawn_slow_query(&session)];
+            tokio::time::sleep(Duration::from_millis(200)).await;
+
+            // Waits for one of the requests in flight to finish
+            let queued = spawn_slow_query(&session);
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            assert_eq!(session.get_metrics().get_queued_requests(), 1);
+
+            // The queue is full
+            let err = session.query_unpaged(SLOW_QUERY, &[]).await.unwrap_err();
+            assert_matches!(err, QueryError::ConnectionBusy);
+
+            for request in in_flight {
+                request.await.unwrap().unwrap();
+            }
+            queued.await.unwrap().unwrap();
+            assert_eq!(session.get_metrics().get_queued_requests(), 0);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn queued_requests_time_out() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session = setup(
+                &proxy_uris,
+                translation_map,
+                &mut running_proxy,
+                StreamExhaustionPolicy::Queue {
+                    max_queued: 10,
+                    timeout: Duration::from_millis(100),
+                },
+            )
+            .await;
+
+            let in_flight = [spawn_slow_query(&session), spawn_slow_query(&session)];
+            tokio::time::sleep(Duration::from_millis(200)).await;
+
+            let err = session.query_unpaged(SLOW_QUERY, &[]).await.unwrap_err();
+            assert_matches!(err, QueryError::ConnectionBusy);
+            assert_eq!(session.get_metrics().get_queued_requests(), 0);
+
+            for request in in_flight {
+                request.await.unwrap().unwrap();
+            }
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}